@@ -5,6 +5,8 @@ use securewatch_agent::{
     Agent, Config, BufferConfig, TransportConfig, ParsedEvent,
     InputValidator, ValidationConfig,
 };
+use securewatch_agent::collectors::RawLogEvent;
+use securewatch_agent::parsers::{Parser, PassthroughParser};
 use std::collections::HashMap;
 use std::time::Duration;
 use tokio::runtime::Runtime;
@@ -22,7 +24,7 @@ fn create_test_event(id: usize) -> ParsedEvent {
             fields.insert("category".to_string(), serde_json::Value::String("application".to_string()));
             fields
         },
-        raw_data: format!("raw benchmark data for event {}", id),
+        raw_data: format!("raw benchmark data for event {}", id).into(),
     }
 }
 
@@ -308,6 +310,36 @@ fn benchmark_memory_usage(c: &mut Criterion) {
     group.finish();
 }
 
+fn benchmark_event_path(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let parser = PassthroughParser::new("syslog".to_string());
+    
+    let mut group = c.benchmark_group("event_path");
+    
+    // Raw payloads from a few hundred bytes up to a large multi-line record
+    for size in [256usize, 4096, 65536].iter() {
+        let raw_event = RawLogEvent {
+            timestamp: chrono::Utc::now(),
+            source: "syslog".to_string(),
+            raw_data: "x".repeat(*size).into(),
+            metadata: HashMap::new(),
+        };
+        group.throughput(criterion::Throughput::Bytes(*size as u64));
+        
+        group.bench_with_input(BenchmarkId::new("parse", size), &raw_event, |b, raw_event| {
+            b.to_async(&rt).iter(|| async { black_box(parser.parse(black_box(raw_event)).await.unwrap()) });
+        });
+        
+        // Routing hands a copy of each event to every matching destination
+        let event = rt.block_on(parser.parse(&raw_event)).unwrap();
+        group.bench_with_input(BenchmarkId::new("fan_out_3_destinations", size), &event, |b, event| {
+            b.iter(|| black_box([event.clone(), event.clone(), event.clone()]));
+        });
+    }
+    
+    group.finish();
+}
+
 fn benchmark_regex_performance(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
     
@@ -392,6 +424,7 @@ criterion_group!(
     benchmark_serialization_performance,
     benchmark_concurrent_processing,
     benchmark_memory_usage,
+    benchmark_event_path,
     benchmark_regex_performance,
     benchmark_transport_compression
 );
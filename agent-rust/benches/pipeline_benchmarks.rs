@@ -0,0 +1,159 @@
+// Parsing and buffering benchmarks for before/after comparisons of performance changes
+// Run with `cargo bench --bench pipeline_benchmarks -- --save-baseline before`, then compare a change with
+// `-- --baseline before`; criterion reports regressions against the saved numbers
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use securewatch_agent::buffer::EventBuffer;
+use securewatch_agent::collectors::RawLogEvent;
+use securewatch_agent::config::{AgentConfig, BufferConfig, ParserDefinition, ParserType};
+use securewatch_agent::parsers::{ParsedEvent, Parser, ParsingEngine, RegexParser};
+use std::collections::HashMap;
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+
+const SYSLOG_LINE: &str = "<38>Oct 16 09:14:07 web-01.example.com sshd: Failed password for alice from 203.0.113.7 port 52114 ssh2";
+
+fn raw_event(source: &str, raw_data: &str) -> RawLogEvent {
+    RawLogEvent {
+        timestamp: chrono::Utc::now(),
+        source: source.to_string(),
+        raw_data: raw_data.into(),
+        metadata: HashMap::new(),
+    }
+}
+
+fn parsed_event(id: usize) -> ParsedEvent {
+    ParsedEvent {
+        timestamp: chrono::Utc::now(),
+        source: "syslog".to_string(),
+        level: Some("info".to_string()),
+        message: format!("Failed password for alice from 203.0.113.7 port {} ssh2", id),
+        fields: HashMap::from([
+            ("user.name".to_string(), serde_json::json!("alice")),
+            ("source.port".to_string(), serde_json::json!(id)),
+        ]),
+        raw_data: SYSLOG_LINE.into(),
+        parser_name: "syslog_rfc3164".to_string(),
+    }
+}
+
+/// The default configuration's parsers, the ones a fresh install runs
+fn default_parsers() -> Vec<ParserDefinition> {
+    AgentConfig::default().parsers.parsers
+}
+
+/// Memory-only buffer, or a SQLite-backed one under `dir` whose memory lanes hold `memory_events`
+fn buffer_config(dir: Option<&TempDir>, memory_events: usize) -> BufferConfig {
+    let mut config = AgentConfig::default().buffer;
+    config.max_events = memory_events;
+    config.burst_capacity = 0;
+    match dir {
+        Some(dir) => config.persistence_path = dir.path().to_string_lossy().to_string(),
+        None => config.persistent = false,
+    }
+    config
+}
+
+fn benchmark_regex_parser(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let definition = default_parsers().into_iter()
+        .find(|p| p.parser_type == ParserType::Regex)
+        .expect("default configuration has a regex parser");
+    let parser = RegexParser::new(&definition).unwrap();
+    let matching = raw_event(&definition.source_type, SYSLOG_LINE);
+    let unmatched = raw_event(&definition.source_type, "not a syslog line at all");
+
+    let mut group = c.benchmark_group("regex_parser");
+    group.throughput(Throughput::Bytes(SYSLOG_LINE.len() as u64));
+    group.bench_function("match", |b| {
+        b.iter(|| black_box(rt.block_on(parser.parse(black_box(&matching))).unwrap()));
+    });
+    group.bench_function("no_match", |b| {
+        b.iter(|| black_box(rt.block_on(parser.parse(black_box(&unmatched))).is_err()));
+    });
+    group.finish();
+}
+
+fn benchmark_parsing_engine(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let engine = ParsingEngine::new(&AgentConfig::default().parsers).unwrap();
+    let cases = [
+        ("configured_parser", raw_event("syslog", SYSLOG_LINE)),
+        ("passthrough_fallback", raw_event("file_monitor", "2026-10-16 09:14:07 ERROR worker crashed")),
+        ("unknown_source", raw_event("custom_app", "{\"level\":\"warn\",\"msg\":\"disk almost full\"}")),
+    ];
+
+    let mut group = c.benchmark_group("parsing_engine");
+    group.throughput(Throughput::Elements(1));
+    for (name, event) in &cases {
+        group.bench_with_input(BenchmarkId::new("dispatch", name), event, |b, event| {
+            b.iter(|| black_box(rt.block_on(engine.parse_events(black_box(event))).ok()));
+        });
+    }
+    group.finish();
+}
+
+fn benchmark_event_buffer(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    let mut group = c.benchmark_group("event_buffer");
+    for batch in [100usize, 1000] {
+        group.throughput(Throughput::Elements(batch as u64));
+        let buffer = rt.block_on(EventBuffer::new(buffer_config(None, batch * 2))).unwrap();
+        let events: Vec<ParsedEvent> = (0..batch).map(parsed_event).collect();
+        group.bench_with_input(BenchmarkId::new("send_receive_memory", batch), &events, |b, events| {
+            b.iter(|| rt.block_on(async {
+                for event in events {
+                    buffer.send(event.clone()).await.unwrap();
+                }
+                black_box(buffer.receive_batch(events.len()).await)
+            }));
+        });
+    }
+    group.finish();
+}
+
+fn benchmark_sqlite_spill(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    let mut group = c.benchmark_group("sqlite_spill");
+    group.sample_size(20);
+    for batch in [100usize, 1000] {
+        group.throughput(Throughput::Elements(batch as u64));
+        let events: Vec<ParsedEvent> = (0..batch).map(parsed_event).collect();
+
+        // Direct batch writes, as on shutdown or when the transport hands back a batch
+        let dir = TempDir::new().unwrap();
+        let buffer = rt.block_on(EventBuffer::new(buffer_config(Some(&dir), batch))).unwrap();
+        group.bench_with_input(BenchmarkId::new("persist_and_read_back", batch), &events, |b, events| {
+            b.iter(|| rt.block_on(async {
+                buffer.persist_events(events.clone()).await.unwrap();
+                black_box(buffer.receive_batch(events.len()).await)
+            }));
+        });
+
+        // Sends past a full memory lane spill through the batched disk writer
+        let dir = TempDir::new().unwrap();
+        let buffer = rt.block_on(EventBuffer::new(buffer_config(Some(&dir), 10))).unwrap();
+        group.bench_with_input(BenchmarkId::new("send_overflow", batch), &events, |b, events| {
+            b.iter(|| rt.block_on(async {
+                for event in events {
+                    buffer.send(event.clone()).await.unwrap();
+                }
+                buffer.flush_pending_writes().await.unwrap();
+                black_box(buffer.receive_batch(events.len()).await)
+            }));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_regex_parser,
+    benchmark_parsing_engine,
+    benchmark_event_buffer,
+    benchmark_sqlite_spill
+);
+
+criterion_main!(benches);
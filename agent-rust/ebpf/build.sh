@@ -0,0 +1,21 @@
+#!/bin/bash
+# Build the eBPF probe object loaded by the eBPF collector (requires clang and libbpf headers)
+
+set -euo pipefail
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+OUTPUT=${1:-"${SCRIPT_DIR}/securewatch_probes.bpf.o"}
+
+case "$(uname -m)" in
+    x86_64) ARCH_INCLUDE="/usr/include/x86_64-linux-gnu" ;;
+    aarch64) ARCH_INCLUDE="/usr/include/aarch64-linux-gnu" ;;
+    *) ARCH_INCLUDE="/usr/include/$(uname -m)-linux-gnu" ;;
+esac
+
+echo "🐝 Building eBPF probes -> ${OUTPUT}"
+clang -O2 -g -target bpf \
+    -I"${ARCH_INCLUDE}" \
+    -c "${SCRIPT_DIR}/securewatch_probes.bpf.c" \
+    -o "${OUTPUT}"
+
+echo "✅ Install to /usr/lib/securewatch/securewatch_probes.bpf.o (or set collectors.ebpf.probe_path)"
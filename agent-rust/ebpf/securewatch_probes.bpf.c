@@ -0,0 +1,164 @@
+// SecureWatch endpoint telemetry probes
+// Tracepoint programs for process exec, outbound connect and file open, streamed to user space
+// through the EVENTS ring buffer. The record layout must match collectors/ebpf.rs.
+
+#include <linux/bpf.h>
+#include <linux/types.h>
+#include <bpf/bpf_helpers.h>
+
+#define KIND_EXEC 1
+#define KIND_CONNECT 2
+#define KIND_OPEN 3
+
+#define AF_INET 2
+#define AF_INET6 10
+
+#define O_ACCMODE 00000003
+#define O_CREAT 00000100
+#define O_TRUNC 00001000
+
+#define CONFIG_IGNORE_TGID 0
+#define CONFIG_OPEN_WRITES_ONLY 1
+
+#define PATH_LEN 256
+
+struct endpoint_event {
+    __u64 ktime_ns;
+    __u32 kind;
+    __u32 pid;
+    __u32 tid;
+    __u32 uid;
+    __u32 gid;
+    __s32 flags;
+    __u16 family;
+    __u16 port;
+    __u8 addr[16];
+    char comm[16];
+    char path[PATH_LEN];
+};
+
+/* sched/sched_process_exec */
+struct exec_ctx {
+    __u64 common;
+    __u32 filename_loc;
+    __s32 pid;
+    __s32 old_pid;
+};
+
+/* syscalls/sys_enter_* */
+struct sys_enter_ctx {
+    __u64 common;
+    __s64 syscall_nr;
+    __u64 args[6];
+};
+
+struct {
+    __uint(type, BPF_MAP_TYPE_RINGBUF);
+    __uint(max_entries, 1 << 22);
+} EVENTS SEC(".maps");
+
+/* Set by the agent before attaching: its own tgid, and whether read-only opens are skipped */
+struct {
+    __uint(type, BPF_MAP_TYPE_ARRAY);
+    __uint(max_entries, 2);
+    __type(key, __u32);
+    __type(value, __u32);
+} CONFIG SEC(".maps");
+
+/* Events lost because the ring buffer was full */
+struct {
+    __uint(type, BPF_MAP_TYPE_ARRAY);
+    __uint(max_entries, 1);
+    __type(key, __u32);
+    __type(value, __u64);
+} DROPPED SEC(".maps");
+
+static __always_inline __u32 config_value(__u32 key)
+{
+    __u32 *value = bpf_map_lookup_elem(&CONFIG, &key);
+    return value ? *value : 0;
+}
+
+static __always_inline struct endpoint_event *reserve_event(__u32 kind)
+{
+    __u64 pid_tgid = bpf_get_current_pid_tgid();
+    __u32 tgid = pid_tgid >> 32;
+    if (tgid == config_value(CONFIG_IGNORE_TGID))
+        return 0;
+
+    struct endpoint_event *event = bpf_ringbuf_reserve(&EVENTS, sizeof(*event), 0);
+    if (!event) {
+        __u32 key = 0;
+        __u64 *dropped = bpf_map_lookup_elem(&DROPPED, &key);
+        if (dropped)
+            __sync_fetch_and_add(dropped, 1);
+        return 0;
+    }
+
+    __u64 uid_gid = bpf_get_current_uid_gid();
+    __builtin_memset(event, 0, sizeof(*event));
+    event->ktime_ns = bpf_ktime_get_ns();
+    event->kind = kind;
+    event->pid = tgid;
+    event->tid = (__u32)pid_tgid;
+    event->uid = (__u32)uid_gid;
+    event->gid = uid_gid >> 32;
+    bpf_get_current_comm(event->comm, sizeof(event->comm));
+    return event;
+}
+
+SEC("tracepoint/sched/sched_process_exec")
+int securewatch_exec(struct exec_ctx *ctx)
+{
+    struct endpoint_event *event = reserve_event(KIND_EXEC);
+    if (!event)
+        return 0;
+
+    bpf_probe_read_kernel_str(event->path, sizeof(event->path), (void *)ctx + (ctx->filename_loc & 0xFFFF));
+    bpf_ringbuf_submit(event, 0);
+    return 0;
+}
+
+SEC("tracepoint/syscalls/sys_enter_connect")
+int securewatch_connect(struct sys_enter_ctx *ctx)
+{
+    const void *addr = (const void *)ctx->args[1];
+    __u16 family = 0;
+    if (bpf_probe_read_user(&family, sizeof(family), addr) || (family != AF_INET && family != AF_INET6))
+        return 0;
+
+    struct endpoint_event *event = reserve_event(KIND_CONNECT);
+    if (!event)
+        return 0;
+
+    __u16 port = 0;
+    bpf_probe_read_user(&port, sizeof(port), addr + 2);
+    event->family = family;
+    event->port = __builtin_bswap16(port);
+    if (family == AF_INET)
+        bpf_probe_read_user(event->addr, 4, addr + 4);
+    else
+        bpf_probe_read_user(event->addr, 16, addr + 8);
+    bpf_ringbuf_submit(event, 0);
+    return 0;
+}
+
+SEC("tracepoint/syscalls/sys_enter_openat")
+int securewatch_openat(struct sys_enter_ctx *ctx)
+{
+    __s32 flags = (__s32)ctx->args[2];
+    int writes = (flags & O_ACCMODE) != 0 || (flags & (O_CREAT | O_TRUNC)) != 0;
+    if (!writes && config_value(CONFIG_OPEN_WRITES_ONLY))
+        return 0;
+
+    struct endpoint_event *event = reserve_event(KIND_OPEN);
+    if (!event)
+        return 0;
+
+    event->flags = flags;
+    bpf_probe_read_user_str(event->path, sizeof(event->path), (const char *)ctx->args[1]);
+    bpf_ringbuf_submit(event, 0);
+    return 0;
+}
+
+char LICENSE[] SEC("license") = "GPL";
@@ -0,0 +1,364 @@
+// Append-only audit log of administrative actions on the agent
+// Configuration changes, state-changing management API calls, parser reloads, ingest pauses and agent starts and
+// stops are appended to a JSON lines file. Every entry carries the SHA-256 of the one before it, so editing or
+// removing an entry breaks the chain from that point on; together with the start/stop records this is the evidence
+// auditors ask for that nobody quietly turned collection off. The agent, the management API and the CLI append to
+// the same file under an exclusive lock.
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Result, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// `previous_hash` of the first entry
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Longest `details` kept, so the last entry can always be found in the tail of the file
+const MAX_DETAILS_LEN: usize = 4096;
+
+/// Bytes read from the end of the file to find the last entry
+const TAIL_CHUNK: u64 = 16 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdminAuditConfig {
+    pub enabled: bool,
+    /// JSON lines file entries are appended to
+    pub path: String,
+}
+
+impl Default for AdminAuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: "./audit/admin-audit.jsonl".to_string(),
+        }
+    }
+}
+
+impl AdminAuditConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.path.trim().is_empty() {
+            errors.push("path must not be empty".to_string());
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    AgentStarted,
+    AgentStopped,
+    ConfigChanged,
+    ConfigRejected,
+    ConfigRolledBack,
+    ConfigReloadRequested,
+    ParsersReloaded,
+    IngestPaused,
+    IngestResumed,
+    DeadLettersRedriven,
+    LogLevelChanged,
+    BandwidthLimitChanged,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::AgentStarted => "agent_started",
+            AuditAction::AgentStopped => "agent_stopped",
+            AuditAction::ConfigChanged => "config_changed",
+            AuditAction::ConfigRejected => "config_rejected",
+            AuditAction::ConfigRolledBack => "config_rolled_back",
+            AuditAction::ConfigReloadRequested => "config_reload_requested",
+            AuditAction::ParsersReloaded => "parsers_reloaded",
+            AuditAction::IngestPaused => "ingest_paused",
+            AuditAction::IngestResumed => "ingest_resumed",
+            AuditAction::DeadLettersRedriven => "dead_letters_redriven",
+            AuditAction::LogLevelChanged => "log_level_changed",
+            AuditAction::BandwidthLimitChanged => "bandwidth_limit_changed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    pub action: AuditAction,
+    /// Who made the change: a config file path, "management_api 10.0.0.5:51234", "cli alice", "agent"
+    pub actor: String,
+    /// What changed, e.g. the config paths or the paused source
+    pub details: String,
+    pub success: bool,
+    /// `hash` of the entry before; zeros for the first
+    pub previous_hash: String,
+    /// SHA-256 over this entry serialized with an empty `hash`
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        let unhashed = AuditEntry { hash: String::new(), ..self.clone() };
+        let bytes = serde_json::to_vec(&unhashed).unwrap_or_default();
+        digest::digest(&digest::SHA256, &bytes).as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Outcome of walking the hash chain from the first entry
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChainVerification {
+    pub entries: u64,
+    pub valid: bool,
+    /// Line number of the first entry that doesn't follow from the one before
+    pub broken_at_line: Option<u64>,
+    pub reason: Option<String>,
+    /// Hash of the last entry, for anchoring the chain elsewhere
+    pub head_hash: Option<String>,
+}
+
+/// The audit log file and the lock serializing appends from this process
+pub struct AdminAuditLog {
+    path: PathBuf,
+    append_lock: Mutex<()>,
+}
+
+impl AdminAuditLog {
+    /// Open the log, creating its directory; the file itself is created by the first entry
+    pub fn open(config: &AdminAuditConfig) -> Result<Self> {
+        let path = PathBuf::from(&config.path);
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| context(e, format!("Failed to create audit log directory {}", parent.display())))?;
+        }
+        Ok(Self { path, append_lock: Mutex::new(()) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry chained to the last one in the file
+    pub fn record(&self, action: AuditAction, actor: &str, details: &str, success: bool) -> Result<AuditEntry> {
+        let _guard = self.append_lock.lock();
+        let io_error = |e| context(e, format!("Failed to append to audit log {}", self.path.display()));
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(&self.path).map_err(io_error)?;
+        // Held until the file is closed, so the CLI and the agent never chain to the same entry
+        file.lock().map_err(io_error)?;
+        let (previous, torn) = last_entry(&mut file).map_err(io_error)?;
+
+        let mut details = details.to_string();
+        if details.len() > MAX_DETAILS_LEN {
+            let cut = (0..=MAX_DETAILS_LEN).rev().find(|i| details.is_char_boundary(*i)).unwrap_or(0);
+            details.truncate(cut);
+            details.push('…');
+        }
+        let mut entry = AuditEntry {
+            sequence: previous.as_ref().map_or(1, |previous| previous.sequence + 1),
+            timestamp: Utc::now(),
+            action,
+            actor: actor.to_string(),
+            details,
+            success,
+            previous_hash: previous.map_or_else(|| GENESIS_HASH.to_string(), |previous| previous.hash),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        let mut line = if torn { "\n".to_string() } else { String::new() };
+        line.push_str(&serde_json::to_string(&entry)?);
+        line.push('\n');
+        file.write_all(line.as_bytes()).map_err(io_error)?;
+        file.sync_data().map_err(io_error)?;
+        Ok(entry)
+    }
+
+    /// Record an action, logging instead of failing when the log can't be written
+    pub fn record_or_warn(&self, action: AuditAction, actor: &str, details: &str, success: bool) {
+        if let Err(e) = self.record(action, actor, details, success) {
+            warn!("⚠️ Failed to record {} in the audit log: {}", action.as_str(), e);
+        }
+    }
+
+    /// Entries in the order they were written, newest `limit` of those matching
+    pub fn query(&self, since: Option<DateTime<Utc>>, action: Option<AuditAction>, limit: usize) -> Result<Vec<AuditEntry>> {
+        let mut entries: Vec<AuditEntry> = self.read_entries()?
+            .into_iter()
+            .filter_map(|(_, entry)| entry.ok())
+            .filter(|entry| since.is_none_or(|since| entry.timestamp >= since))
+            .filter(|entry| action.is_none_or(|action| entry.action == action))
+            .collect();
+        let skip = entries.len().saturating_sub(limit);
+        entries.drain(..skip);
+        Ok(entries)
+    }
+
+    /// Walk the chain from the first entry and report where, if anywhere, it breaks
+    pub fn verify(&self) -> Result<ChainVerification> {
+        let mut verification = ChainVerification { valid: true, ..Default::default() };
+        let mut previous: Option<AuditEntry> = None;
+        for (line_number, entry) in self.read_entries()? {
+            let problem = match entry {
+                Err(e) => Some(format!("unreadable entry: {}", e)),
+                Ok(entry) => {
+                    let expected_previous = previous.as_ref().map_or(GENESIS_HASH, |previous| previous.hash.as_str());
+                    let expected_sequence = previous.as_ref().map_or(1, |previous| previous.sequence + 1);
+                    let problem = if entry.hash != entry.compute_hash() {
+                        Some(format!("entry {} does not match its hash", entry.sequence))
+                    } else if entry.previous_hash != expected_previous {
+                        Some(format!("entry {} does not chain to the entry before it", entry.sequence))
+                    } else if entry.sequence != expected_sequence {
+                        Some(format!("expected entry {}, found {}", expected_sequence, entry.sequence))
+                    } else {
+                        None
+                    };
+                    verification.entries += 1;
+                    verification.head_hash = Some(entry.hash.clone());
+                    previous = Some(entry);
+                    problem
+                }
+            };
+            if let (Some(reason), true) = (problem, verification.valid) {
+                verification.valid = false;
+                verification.broken_at_line = Some(line_number);
+                verification.reason = Some(reason);
+            }
+        }
+        Ok(verification)
+    }
+
+    /// Verify the chain on startup and note a previous run that ended without recording its stop
+    pub fn check_on_startup(&self) -> Result<Option<AuditEntry>> {
+        let verification = self.verify()?;
+        match &verification.reason {
+            Some(reason) => warn!("🚨 Audit log {} has been altered at line {}: {}",
+                                  self.path.display(), verification.broken_at_line.unwrap_or(0), reason),
+            None => info!("📜 Audit log {} verified ({} entries)", self.path.display(), verification.entries),
+        }
+        let last_lifecycle = self.read_entries()?
+            .into_iter()
+            .filter_map(|(_, entry)| entry.ok())
+            .rfind(|entry| matches!(entry.action, AuditAction::AgentStarted | AuditAction::AgentStopped));
+        Ok(last_lifecycle.filter(|entry| entry.action == AuditAction::AgentStarted))
+    }
+
+    fn read_entries(&self) -> Result<Vec<(u64, std::result::Result<AuditEntry, String>)>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(context(e, format!("Failed to read audit log {}", self.path.display()))),
+        };
+        let mut entries = Vec::new();
+        // Split on bytes so a line torn mid-character is reported as unreadable rather than failing the read
+        for (index, line) in BufReader::new(file).split(b'\n').enumerate() {
+            let line = line.map_err(|e| context(e, format!("Failed to read audit log {}", self.path.display())))?;
+            if !line.trim_ascii().is_empty() {
+                entries.push((index as u64 + 1, serde_json::from_slice(&line).map_err(|e| e.to_string())));
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Who is running a CLI command, for the actor of entries it records
+pub fn cli_actor() -> String {
+    let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| "unknown".to_string());
+    format!("cli {}", user)
+}
+
+fn context(error: std::io::Error, message: String) -> std::io::Error {
+    std::io::Error::new(error.kind(), format!("{}: {}", message, error))
+}
+
+/// The last readable entry in the tail of the file, and whether the file ends mid-line. A line torn by a crash
+/// is skipped, so appends chain past it and `verify` reports it
+fn last_entry(file: &mut File) -> Result<(Option<AuditEntry>, bool)> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let start = len.saturating_sub(TAIL_CHUNK);
+    file.seek(SeekFrom::Start(start))?;
+    let mut tail = Vec::new();
+    file.take(len - start).read_to_end(&mut tail)?;
+    let torn = tail.last().is_some_and(|byte| *byte != b'\n');
+    let entry = String::from_utf8_lossy(&tail).lines().rev()
+        .find_map(|line| serde_json::from_str::<AuditEntry>(line).ok());
+    Ok((entry, torn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn log(dir: &TempDir) -> AdminAuditLog {
+        let config = AdminAuditConfig { enabled: true, path: dir.path().join("audit").join("admin.jsonl").to_string_lossy().to_string() };
+        AdminAuditLog::open(&config).unwrap()
+    }
+
+    #[test]
+    fn test_entries_chain_and_tampering_is_detected() {
+        let dir = TempDir::new().unwrap();
+        let audit = log(&dir);
+        let started = audit.record(AuditAction::AgentStarted, "agent", "version 1.0", true).unwrap();
+        audit.record(AuditAction::IngestPaused, "cli alice", "all sources", true).unwrap();
+        let resumed = audit.record(AuditAction::IngestResumed, "cli alice", "all sources", true).unwrap();
+        assert_eq!(started.previous_hash, GENESIS_HASH);
+        assert_eq!(resumed.sequence, 3);
+
+        let verification = audit.verify().unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.entries, 3);
+        assert_eq!(verification.head_hash.as_deref(), Some(resumed.hash.as_str()));
+
+        // Hiding the pause breaks the chain at the entry that followed it
+        let content = std::fs::read_to_string(audit.path()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        std::fs::write(audit.path(), format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        let verification = audit.verify().unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.broken_at_line, Some(2));
+
+        // So does rewriting an entry in place
+        std::fs::write(audit.path(), format!("{}\n{}\n{}\n", lines[0], lines[1].replace("all sources", "syslog"), lines[2])).unwrap();
+        assert_eq!(audit.verify().unwrap().reason.as_deref(), Some("entry 2 does not match its hash"));
+    }
+
+    #[test]
+    fn test_query_and_unclean_stop() {
+        let dir = TempDir::new().unwrap();
+        let audit = log(&dir);
+        assert!(audit.check_on_startup().unwrap().is_none());
+        audit.record(AuditAction::AgentStarted, "agent", "", true).unwrap();
+        audit.record(AuditAction::ConfigChanged, "/etc/securewatch/agent.toml", "collectors.syslog.enabled", true).unwrap();
+        audit.record(AuditAction::ConfigRejected, "management_api 10.0.0.5:51234", "transport.server_url", false).unwrap();
+
+        // The agent never recorded a stop
+        assert_eq!(audit.check_on_startup().unwrap().map(|entry| entry.sequence), Some(1));
+        audit.record(AuditAction::AgentStopped, "agent", "service manager", true).unwrap();
+        assert!(audit.check_on_startup().unwrap().is_none());
+
+        let config_changes = audit.query(None, Some(AuditAction::ConfigChanged), 10).unwrap();
+        assert_eq!(config_changes.len(), 1);
+        assert_eq!(config_changes[0].details, "collectors.syslog.enabled");
+        let newest = audit.query(None, None, 2).unwrap();
+        assert_eq!(newest.iter().map(|entry| entry.sequence).collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(AuditAction::parse("ingest_paused"), Some(AuditAction::IngestPaused));
+
+        // A line torn by a crash is flagged, and appends continue after it
+        let mut file = OpenOptions::new().append(true).open(audit.path()).unwrap();
+        file.write_all(b"{\"sequence\":5,\"time").unwrap();
+        let after_crash = audit.record(AuditAction::AgentStarted, "agent", "", true).unwrap();
+        assert_eq!(after_crash.sequence, 5);
+        let verification = audit.verify().unwrap();
+        assert_eq!(verification.broken_at_line, Some(5));
+        assert_eq!(verification.entries, 5);
+    }
+}
@@ -0,0 +1,207 @@
+// Running agent status as reported over the management API
+// Collector, buffer and transport health gathered in one place for the `status` command; the management client
+// fills it from the gRPC responses so the CLI does not depend on generated protobuf types
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CollectorHealth {
+    pub name: String,
+    pub source_type: String,
+    pub running: bool,
+    pub last_error: Option<String>,
+    pub last_activity: Option<DateTime<Utc>>,
+    pub dropped_events: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BufferHealth {
+    pub memory_events: u64,
+    pub disk_events: u64,
+    pub total_bytes: u64,
+    pub events_processed: u64,
+    pub events_dropped: u64,
+    pub memory_usage_percent: f64,
+    pub disk_usage_percent: f64,
+    pub backpressure_active: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TransportHealth {
+    pub server_url: String,
+    pub tls_enabled: bool,
+    pub requests_sent: u64,
+    pub requests_failed: u64,
+    pub bytes_sent: u64,
+    pub average_latency_ms: f64,
+    pub last_error: Option<String>,
+    pub last_success: Option<DateTime<Utc>>,
+    pub certificates: Vec<CertificateHealth>,
+    /// Why the upload schedule is holding events in the buffer
+    pub uploads_held: Option<String>,
+    pub next_upload_window: Option<DateTime<Utc>>,
+    /// None while uploads are unlimited
+    pub bandwidth_limit_kb_per_second: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CertificateHealth {
+    /// "client" or "ca"
+    pub role: String,
+    pub path: String,
+    pub subject: String,
+    pub not_after: Option<DateTime<Utc>>,
+    /// Negative once expired
+    pub days_until_expiry: i64,
+    /// "valid", "expiring" or "expired"
+    pub state: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentStatus {
+    pub agent_id: String,
+    pub version: String,
+    /// "healthy", or "paused" while ingest pauses are active
+    pub status: String,
+    pub uptime_seconds: u64,
+    /// Sources with an active ingest pause ("all" for a global pause)
+    pub paused_sources: Vec<String>,
+    pub collectors: Vec<CollectorHealth>,
+    pub buffer: BufferHealth,
+    pub transport: TransportHealth,
+}
+
+impl AgentStatus {
+    /// Stopped collectors, backpressure and failing transport; empty when the agent looks healthy
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self.collectors.iter()
+            .filter(|c| !c.running)
+            .map(|c| match &c.last_error {
+                Some(error) => format!("collector {} is stopped: {}", c.name, error),
+                None => format!("collector {} is stopped", c.name),
+            })
+            .collect();
+        problems.extend(self.collectors.iter()
+            .filter(|c| c.dropped_events > 0)
+            .map(|c| format!("{} events dropped by collector {}", c.dropped_events, c.name)));
+        if self.buffer.backpressure_active {
+            problems.push("buffer backpressure is active".to_string());
+        }
+        if self.buffer.events_dropped > 0 {
+            problems.push(format!("{} events dropped by the buffer", self.buffer.events_dropped));
+        }
+        if let Some(error) = &self.transport.last_error {
+            problems.push(format!("transport: {}", error));
+        }
+        problems.extend(self.transport.certificates.iter()
+            .filter(|c| c.state != "valid")
+            .map(|c| match c.state.as_str() {
+                "expired" => format!("{} certificate {} ({}) has expired", c.role, c.subject, c.path),
+                _ => format!("{} certificate {} ({}) expires in {} days", c.role, c.subject, c.path, c.days_until_expiry),
+            }));
+        problems
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Agent {} v{}: {} (up {}s)", self.agent_id, self.version, self.status, self.uptime_seconds);
+        if !self.paused_sources.is_empty() {
+            let _ = writeln!(out, "Ingest paused: {}", self.paused_sources.join(", "));
+        }
+
+        let running = self.collectors.iter().filter(|c| c.running).count();
+        let _ = writeln!(out, "\nCollectors ({}/{} running)", running, self.collectors.len());
+        for collector in &self.collectors {
+            let activity = collector.last_activity.map(|t| t.to_rfc3339()).unwrap_or_else(|| "never".to_string());
+            let _ = writeln!(out, "  {} {:<24} {:<16} last activity {}", if collector.running { "●" } else { "○" },
+                             collector.name, collector.source_type, activity);
+            if collector.dropped_events > 0 {
+                let _ = writeln!(out, "      dropped: {}", collector.dropped_events);
+            }
+            if let Some(error) = &collector.last_error {
+                let _ = writeln!(out, "      last error: {}", error);
+            }
+        }
+
+        let buffer = &self.buffer;
+        let _ = writeln!(out, "\nBuffer");
+        let _ = writeln!(out, "  events:       {} in memory ({:.1}%), {} on disk ({:.1}%), {} bytes",
+                         buffer.memory_events, buffer.memory_usage_percent, buffer.disk_events, buffer.disk_usage_percent, buffer.total_bytes);
+        let _ = writeln!(out, "  processed:    {} ({} dropped)", buffer.events_processed, buffer.events_dropped);
+        let _ = writeln!(out, "  backpressure: {}", if buffer.backpressure_active { "active" } else { "no" });
+
+        let transport = &self.transport;
+        let _ = writeln!(out, "\nTransport");
+        let _ = writeln!(out, "  server:       {}{}", transport.server_url, if transport.tls_enabled { " (TLS)" } else { "" });
+        let _ = writeln!(out, "  requests:     {} sent, {} failed, {} bytes, {:.1}ms average",
+                         transport.requests_sent, transport.requests_failed, transport.bytes_sent, transport.average_latency_ms);
+        let last_success = transport.last_success.map(|t| t.to_rfc3339()).unwrap_or_else(|| "never".to_string());
+        let _ = writeln!(out, "  last success: {}", last_success);
+        if let Some(reason) = &transport.uploads_held {
+            let next = transport.next_upload_window.map(|t| format!(" until {}", t.to_rfc3339())).unwrap_or_default();
+            let _ = writeln!(out, "  uploads:      held{} ({})", next, reason);
+        }
+        if let Some(limit) = transport.bandwidth_limit_kb_per_second {
+            let _ = writeln!(out, "  upload cap:   {} KB/s", limit);
+        }
+        for certificate in &transport.certificates {
+            let not_after = certificate.not_after.map(|t| t.to_rfc3339()).unwrap_or_else(|| "unknown".to_string());
+            let _ = writeln!(out, "  {} cert:  {} expires {} ({} days, {})",
+                             certificate.role, certificate.subject, not_after, certificate.days_until_expiry, certificate.state);
+        }
+
+        let problems = self.problems();
+        if !problems.is_empty() {
+            let _ = writeln!(out, "\nProblems");
+            for problem in problems {
+                let _ = writeln!(out, "  - {}", problem);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problems_flag_stopped_collectors_and_transport_errors() {
+        let mut status = AgentStatus {
+            agent_id: "agent-1".to_string(),
+            version: "1.0.0".to_string(),
+            status: "healthy".to_string(),
+            uptime_seconds: 90,
+            paused_sources: Vec::new(),
+            collectors: vec![
+                CollectorHealth { name: "syslog".to_string(), source_type: "syslog".to_string(), running: true, last_error: None, last_activity: Some(Utc::now()), dropped_events: 0 },
+                CollectorHealth { name: "fim".to_string(), source_type: "fim".to_string(), running: false, last_error: Some("permission denied".to_string()), last_activity: None, dropped_events: 0 },
+            ],
+            buffer: BufferHealth::default(),
+            transport: TransportHealth { server_url: "https://siem:8443".to_string(), ..Default::default() },
+        };
+        assert_eq!(status.problems(), vec!["collector fim is stopped: permission denied".to_string()]);
+
+        status.transport.last_error = Some("connection refused".to_string());
+        let text = status.to_text();
+        assert!(text.contains("Collectors (1/2 running)"));
+        assert!(text.contains("transport: connection refused"));
+        assert!(!text.contains("upload cap"));
+        status.transport.bandwidth_limit_kb_per_second = Some(256);
+        assert!(status.to_text().contains("upload cap:   256 KB/s"));
+        status.transport.uploads_held = Some("outside the upload windows".to_string());
+        assert!(status.to_text().contains("uploads:      held (outside the upload windows)"));
+
+        status.transport.certificates.push(CertificateHealth {
+            role: "client".to_string(),
+            path: "/etc/securewatch/client.pem".to_string(),
+            subject: "CN=agent-1".to_string(),
+            not_after: Some(Utc::now()),
+            days_until_expiry: 6,
+            state: "expiring".to_string(),
+        });
+        assert!(status.problems().contains(&"client certificate CN=agent-1 (/etc/securewatch/client.pem) expires in 6 days".to_string()));
+    }
+}
@@ -0,0 +1,604 @@
+// Local aggregation mode
+// An aggregator agent accepts batches from other agents over the native ingest protocol (the HTTP(S) POST the
+// transport sends to the server), queues their events in its own buffer next to local events and ships them
+// upstream with its own transport, so a branch office can run one concentrator that rides out WAN outages
+
+use crate::buffer::EventBuffer;
+use crate::compression::CompressionAlgorithm;
+use crate::payload_format::PayloadFormat;
+use crate::errors::ConfigError;
+use crate::parsers::ParsedEvent;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, watch, Semaphore};
+use tracing::{debug, info, warn};
+
+/// Field listing the aggregators an event passed through, nearest to the origin first
+pub const AGGREGATOR_PATH_FIELD: &str = "securewatch.aggregators";
+
+/// Largest request head (request line and headers) accepted
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// Time a connecting agent has to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Seconds a sender is asked to wait while the buffer applies backpressure
+const BACKPRESSURE_RETRY_AFTER_SECONDS: u32 = 5;
+
+/// Aggregator configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AggregatorConfig {
+    /// Accept batches from the agents listed in `clients` and forward them with this agent's transport
+    pub enabled: bool,
+    pub listen_address: String,
+    /// Identity recorded on aggregated events (defaults to the agent name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregator_id: Option<String>,
+    /// Without TLS, API keys and events cross the branch network in clear text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<AggregatorTlsConfig>,
+    pub clients: Vec<AggregatorClientConfig>,
+    /// Largest request body as sent, before decompression
+    pub max_body_bytes: usize,
+    /// Largest request body after decompression
+    pub max_decoded_bytes: usize,
+    pub max_events_per_request: usize,
+    pub max_connections: usize,
+    /// Idle time allowed between requests on a kept-alive connection
+    pub idle_timeout_seconds: u64,
+}
+
+impl Default for AggregatorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_address: "0.0.0.0:8443".to_string(),
+            aggregator_id: None,
+            tls: None,
+            clients: Vec::new(),
+            max_body_bytes: 16 * 1024 * 1024,
+            max_decoded_bytes: 64 * 1024 * 1024,
+            max_events_per_request: 10_000,
+            max_connections: 256,
+            idle_timeout_seconds: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregatorTlsConfig {
+    /// PEM certificate chain presented to agents
+    pub cert_path: String,
+    /// PEM PKCS#8 private key for the certificate
+    pub key_path: String,
+}
+
+/// Agents allowed to send through this aggregator; they use it as `transport.server_url` with this `api_key`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregatorClientConfig {
+    pub name: String,
+    pub api_key: String,
+}
+
+impl AggregatorConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+        if self.listen_address.parse::<std::net::SocketAddr>().is_err() {
+            errors.push(format!("Invalid aggregator listen address: {}", self.listen_address));
+        }
+        if self.clients.is_empty() {
+            errors.push("Aggregator is enabled but no clients are configured".to_string());
+        }
+        let mut names = std::collections::HashSet::new();
+        let mut keys = std::collections::HashSet::new();
+        for client in &self.clients {
+            if client.name.trim().is_empty() {
+                errors.push("Aggregator client names cannot be empty".to_string());
+            } else if !names.insert(client.name.as_str()) {
+                errors.push(format!("Duplicate aggregator client '{}'", client.name));
+            }
+            if client.api_key.len() < 16 {
+                errors.push(format!("Aggregator client '{}' api_key must be at least 16 characters", client.name));
+            } else if !keys.insert(client.api_key.as_str()) {
+                errors.push(format!("Aggregator client '{}' reuses another client's api_key", client.name));
+            }
+        }
+        if let Some(tls) = &self.tls {
+            if tls.cert_path.is_empty() || tls.key_path.is_empty() {
+                errors.push("Aggregator TLS needs cert_path and key_path".to_string());
+            }
+            if cfg!(not(feature = "native-tls-backend")) {
+                errors.push("Aggregator TLS requires the native-tls-backend feature".to_string());
+            }
+        }
+        if self.max_body_bytes == 0 || self.max_decoded_bytes < self.max_body_bytes {
+            errors.push("max_body_bytes must be greater than 0 and no larger than max_decoded_bytes".to_string());
+        }
+        if self.max_events_per_request == 0 || self.max_connections == 0 || self.idle_timeout_seconds == 0 {
+            errors.push("max_events_per_request, max_connections and idle_timeout_seconds must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// Batch body sent by `SecureTransport` in native mode
+#[derive(Debug, Deserialize)]
+struct IngestBatch {
+    events: Vec<Value>,
+    #[serde(default)]
+    agent_id: Option<String>,
+}
+
+/// Per-agent aggregation counters
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AggregatorSenderStats {
+    pub agent_id: String,
+    /// Client entry whose key the agent used
+    pub client: String,
+    pub batches_accepted: u64,
+    pub events_accepted: u64,
+    /// Events that were not valid agent events or had already passed through this aggregator
+    pub events_rejected: u64,
+    pub last_seen: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AggregatorStats {
+    pub aggregator_id: String,
+    pub listen_address: String,
+    pub active_connections: usize,
+    /// Requests refused for a missing or unknown API key
+    pub unauthorized_requests: u64,
+    /// Batches refused with 503 while the buffer applied backpressure
+    pub backpressure_rejections: u64,
+    pub senders: Vec<AggregatorSenderStats>,
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    /// Lowercased header names
+    headers: HashMap<String, String>,
+    /// Index of the client whose API key the request carried
+    client: usize,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Value,
+    /// Close the connection after responding
+    close: bool,
+}
+
+impl Response {
+    fn new(status: u16, body: Value) -> Self {
+        Self { status, headers: Vec::new(), body, close: false }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::new(status, json!({ "error": message.into() }))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            405 => "Method Not Allowed",
+            411 => "Length Required",
+            413 => "Payload Too Large",
+            415 => "Unsupported Media Type",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Error",
+        }
+    }
+
+    async fn write<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> std::io::Result<()> {
+        let body = self.body.to_string();
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+            self.status, self.reason(), body.len()
+        );
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if self.close {
+            head.push_str("Connection: close\r\n");
+        }
+        head.push_str("\r\n");
+        writer.write_all(head.as_bytes()).await?;
+        writer.write_all(body.as_bytes()).await?;
+        writer.flush().await
+    }
+}
+
+/// Read the next request on a connection; `Ok(None)` on a clean close between requests.
+/// `authenticate` sees the headers before any of the body is read and names the sending client.
+async fn read_request<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    pending: &mut Vec<u8>,
+    max_body_bytes: usize,
+    authenticate: impl FnOnce(&HashMap<String, String>) -> Result<usize, Response>,
+) -> std::io::Result<Option<Result<Request, Response>>> {
+    let head_len = loop {
+        if let Some(end) = pending.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if pending.len() > MAX_HEAD_BYTES {
+            return Ok(Some(Err(Response { close: true, ..Response::error(431, "request head too large") })));
+        }
+        let mut chunk = [0u8; 8192];
+        let read = reader.read(&mut chunk).await?;
+        if read == 0 {
+            return if pending.is_empty() {
+                Ok(None)
+            } else {
+                Err(std::io::ErrorKind::UnexpectedEof.into())
+            };
+        }
+        pending.extend_from_slice(&chunk[..read]);
+    };
+
+    let mut header_slots = [httparse::EMPTY_HEADER; 64];
+    let mut parsed = httparse::Request::new(&mut header_slots);
+    if !matches!(parsed.parse(&pending[..head_len]), Ok(httparse::Status::Complete(_))) {
+        return Ok(Some(Err(Response { close: true, ..Response::error(400, "malformed request") })));
+    }
+    let method = parsed.method.unwrap_or_default().to_string();
+    let path = parsed.path.unwrap_or_default().to_string();
+    let headers: HashMap<String, String> = parsed.headers.iter()
+        .map(|h| (h.name.to_ascii_lowercase(), String::from_utf8_lossy(h.value).trim().to_string()))
+        .collect();
+    pending.drain(..head_len);
+
+    // An unauthenticated sender never gets to make the aggregator read or buffer a body
+    let client = match authenticate(&headers) {
+        Ok(client) => client,
+        Err(response) => return Ok(Some(Err(Response { close: true, ..response }))),
+    };
+    if headers.contains_key("transfer-encoding") {
+        return Ok(Some(Err(Response { close: true, ..Response::error(411, "chunked bodies are not accepted") })));
+    }
+    let Ok(content_length) = headers.get("content-length").map_or(Ok(0), |value| value.parse::<usize>()) else {
+        return Ok(Some(Err(Response { close: true, ..Response::error(400, "invalid Content-Length") })));
+    };
+    if content_length > max_body_bytes {
+        return Ok(Some(Err(Response { close: true, ..Response::error(413, format!("body exceeds {} bytes", max_body_bytes)) })));
+    }
+    let buffered = pending.len().min(content_length);
+    let mut body: Vec<u8> = pending.drain(..buffered).collect();
+    body.resize(content_length, 0);
+    reader.read_exact(&mut body[buffered..]).await?;
+
+    Ok(Some(Ok(Request { method, path, headers, client, body })))
+}
+
+/// Constant-time comparison of an offered API key against a configured one
+fn key_matches(offered: &str, expected: &str) -> bool {
+    let offered = digest::digest(&digest::SHA256, offered.as_bytes());
+    let expected = digest::digest(&digest::SHA256, expected.as_bytes());
+    offered.as_ref().iter().zip(expected.as_ref()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Turn a received event into a local one, recording this aggregator on its path; `None` for invalid or looping events
+fn admit_event(value: Value, origin_agent: Option<&str>, aggregator_id: &str) -> Option<ParsedEvent> {
+    let mut event: ParsedEvent = serde_json::from_value(value).ok()?;
+    let mut path: Vec<Value> = match event.fields.remove(AGGREGATOR_PATH_FIELD) {
+        Some(Value::Array(path)) => path,
+        _ => Vec::new(),
+    };
+    if path.iter().any(|hop| hop.as_str() == Some(aggregator_id)) {
+        return None;
+    }
+    path.push(json!(aggregator_id));
+    event.fields.insert(AGGREGATOR_PATH_FIELD.to_string(), Value::Array(path));
+    if let Some(agent_id) = origin_agent {
+        event.fields.entry("agent.id".to_string()).or_insert_with(|| json!(agent_id));
+    }
+    Some(event)
+}
+
+/// Listener accepting other agents' batches into this agent's buffer
+pub struct AggregatorServer {
+    config: AggregatorConfig,
+    aggregator_id: String,
+    buffer: EventBuffer,
+    backpressure: watch::Receiver<bool>,
+    #[cfg(feature = "native-tls-backend")]
+    tls_acceptor: Option<tokio_native_tls::TlsAcceptor>,
+    connections: Arc<Semaphore>,
+    senders: parking_lot::Mutex<HashMap<String, AggregatorSenderStats>>,
+    unauthorized_requests: AtomicU64,
+    backpressure_rejections: AtomicU64,
+}
+
+impl AggregatorServer {
+    pub fn new(config: AggregatorConfig, default_aggregator_id: &str, buffer: EventBuffer) -> Result<Self, ConfigError> {
+        #[cfg(feature = "native-tls-backend")]
+        let tls_acceptor = match &config.tls {
+            Some(tls) => {
+                let read_pem = |path: &str| std::fs::read(path)
+                    .map_err(|e| ConfigError::Validation(format!("Failed to read aggregator TLS file {}: {}", path, e)));
+                let identity = native_tls::Identity::from_pkcs8(&read_pem(&tls.cert_path)?, &read_pem(&tls.key_path)?)
+                    .map_err(|e| ConfigError::Validation(format!("Invalid aggregator TLS certificate or key: {}", e)))?;
+                let acceptor = native_tls::TlsAcceptor::new(identity)
+                    .map_err(|e| ConfigError::Validation(format!("Failed to create aggregator TLS acceptor: {}", e)))?;
+                Some(tokio_native_tls::TlsAcceptor::from(acceptor))
+            }
+            None => None,
+        };
+        #[cfg(not(feature = "native-tls-backend"))]
+        if config.tls.is_some() {
+            return Err(ConfigError::Validation("Aggregator TLS requires the native-tls-backend feature".to_string()));
+        }
+
+        Ok(Self {
+            aggregator_id: config.aggregator_id.clone().unwrap_or_else(|| default_aggregator_id.to_string()),
+            backpressure: buffer.get_backpressure_receiver(),
+            buffer,
+            #[cfg(feature = "native-tls-backend")]
+            tls_acceptor,
+            connections: Arc::new(Semaphore::new(config.max_connections)),
+            senders: parking_lot::Mutex::new(HashMap::new()),
+            unauthorized_requests: AtomicU64::new(0),
+            backpressure_rejections: AtomicU64::new(0),
+            config,
+        })
+    }
+
+    pub fn aggregator_id(&self) -> &str {
+        &self.aggregator_id
+    }
+
+    /// Accept agent connections until shutdown
+    pub async fn run(self: Arc<Self>, mut shutdown_receiver: broadcast::Receiver<()>) -> std::io::Result<()> {
+        let listener = TcpListener::bind(&self.config.listen_address).await?;
+        let scheme = if self.config.tls.is_some() { "https" } else { "http" };
+        info!("🏢 Aggregator '{}' listening on {}://{} for {} clients",
+              self.aggregator_id, scheme, self.config.listen_address, self.config.clients.len());
+        if self.config.tls.is_none() {
+            warn!("⚠️ Aggregator listener has no TLS; API keys and events are sent in clear text");
+        }
+
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, addr) = match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            warn!("⚠️ Aggregator accept failed: {}", e);
+                            continue;
+                        }
+                    };
+                    let Ok(permit) = self.connections.clone().try_acquire_owned() else {
+                        warn!("⚠️ Aggregator connection limit ({}) reached, dropping {}", self.config.max_connections, addr);
+                        continue;
+                    };
+                    let server = self.clone();
+                    crate::component_usage::spawn_inherited(async move {
+                        #[cfg(feature = "native-tls-backend")]
+                        let result = match &server.tls_acceptor {
+                            Some(acceptor) => match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                                Ok(Ok(stream)) => server.serve_connection(stream).await,
+                                Ok(Err(e)) => Err(std::io::Error::other(format!("TLS handshake failed: {}", e))),
+                                Err(_) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "TLS handshake timed out")),
+                            },
+                            None => server.serve_connection(stream).await,
+                        };
+                        #[cfg(not(feature = "native-tls-backend"))]
+                        let result = server.serve_connection(stream).await;
+                        if let Err(e) = result {
+                            debug!("Aggregator connection from {} closed: {}", addr, e);
+                        }
+                        drop(permit);
+                    });
+                }
+                _ = shutdown_receiver.recv() => {
+                    info!("🛑 Aggregator listener shutting down");
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn serve_connection<S: AsyncRead + AsyncWrite + Unpin>(&self, mut stream: S) -> std::io::Result<()> {
+        let idle_timeout = Duration::from_secs(self.config.idle_timeout_seconds);
+        let mut pending = Vec::new();
+        loop {
+            let authenticate = |headers: &HashMap<String, String>| self.authenticate(headers);
+            let next = tokio::time::timeout(idle_timeout, read_request(&mut stream, &mut pending, self.config.max_body_bytes, authenticate)).await;
+            let Ok(next) = next else { return Ok(()) };
+            let Some(request) = next? else { return Ok(()) };
+            let response = match request {
+                Ok(request) => {
+                    let close = request.header("connection").is_some_and(|value| value.eq_ignore_ascii_case("close"));
+                    let response = self.handle(request).await;
+                    Response { close: response.close || close, ..response }
+                }
+                Err(response) => response,
+            };
+            response.write(&mut stream).await?;
+            if response.close {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Client whose API key the request headers carry
+    fn authenticate(&self, headers: &HashMap<String, String>) -> Result<usize, Response> {
+        let offered = headers.get("authorization").and_then(|value| value.strip_prefix("Bearer ")).unwrap_or_default();
+        self.config.clients.iter().position(|client| key_matches(offered, &client.api_key)).ok_or_else(|| {
+            self.unauthorized_requests.fetch_add(1, Ordering::Relaxed);
+            Response { headers: vec![("WWW-Authenticate", "Bearer".to_string())], ..Response::error(401, "invalid or missing API key") }
+        })
+    }
+
+    async fn handle(&self, request: Request) -> Response {
+        let client = &self.config.clients[request.client];
+        if request.method != "POST" {
+            return Response { headers: vec![("Allow", "POST".to_string())], ..Response::error(405, "only POST is supported") };
+        }
+        // Connection tests post to <server_url>/health
+        if request.path.trim_end_matches('/').ends_with("/health") {
+            return Response::new(200, json!({ "status": "ok", "aggregator": self.aggregator_id }));
+        }
+        if *self.backpressure.borrow() {
+            self.backpressure_rejections.fetch_add(1, Ordering::Relaxed);
+            return Response {
+                headers: vec![("Retry-After", BACKPRESSURE_RETRY_AFTER_SECONDS.to_string())],
+                ..Response::error(503, "aggregator buffer is full")
+            };
+        }
+
+        // Only JSON batches are accepted; the 415 Accept header moves senders using another format back to JSON
+        if request.header("content-type").is_some_and(|value| PayloadFormat::from_content_type(value) != Some(PayloadFormat::Json)) {
+            return Response { headers: vec![("Accept", PayloadFormat::Json.content_type().to_string())], ..Response::error(415, "unsupported Content-Type") };
+        }
+
+        // A missing Content-Encoding means the body is not compressed
+        let encoding = CompressionAlgorithm::from_content_encoding(request.header("content-encoding").unwrap_or("identity"));
+        let Some(encoding) = encoding else {
+            return Response { headers: vec![("Accept-Encoding", "zstd, br, gzip".to_string())], ..Response::error(415, "unsupported Content-Encoding") };
+        };
+        let batch = match encoding.decompress(&request.body, self.config.max_decoded_bytes) {
+            Ok(body) => serde_json::from_slice::<IngestBatch>(&body).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let batch = match batch {
+            Ok(batch) => batch,
+            Err(e) => return Response::error(400, format!("invalid batch: {}", e)),
+        };
+        if batch.events.len() > self.config.max_events_per_request {
+            return Response::error(413, format!("batch exceeds {} events", self.config.max_events_per_request));
+        }
+        self.accept_batch(client, batch).await
+    }
+
+    async fn accept_batch(&self, client: &AggregatorClientConfig, batch: IngestBatch) -> Response {
+        let agent_id = batch.agent_id.filter(|id| !id.is_empty());
+        let received = batch.events.len();
+        let mut accepted = 0u64;
+        for value in batch.events {
+            let Some(event) = admit_event(value, agent_id.as_deref(), &self.aggregator_id) else { continue };
+            if let Err(e) = self.buffer.send_forwarded(event).await {
+                // The sender retries the whole batch; events already queued are caught by deduplication when enabled
+                warn!("⚠️ Aggregator could not queue events from '{}': {}", client.name, e);
+                return Response::error(503, "aggregator buffer unavailable");
+            }
+            accepted += 1;
+        }
+        let rejected = received as u64 - accepted;
+
+        let sender = agent_id.unwrap_or_else(|| client.name.clone());
+        let mut senders = self.senders.lock();
+        let stats = senders.entry(sender.clone()).or_insert_with(|| AggregatorSenderStats {
+            agent_id: sender.clone(),
+            client: client.name.clone(),
+            ..Default::default()
+        });
+        stats.batches_accepted += 1;
+        stats.events_accepted += accepted;
+        stats.events_rejected += rejected;
+        stats.last_seen = Some(chrono::Utc::now());
+        if rejected > 0 {
+            debug!("🏢 Aggregator rejected {} of {} events from '{}'", rejected, received, sender);
+        }
+        Response::new(200, json!({ "accepted": accepted, "rejected": rejected }))
+    }
+
+    pub fn get_stats(&self) -> AggregatorStats {
+        let mut senders: Vec<AggregatorSenderStats> = self.senders.lock().values().cloned().collect();
+        senders.sort_by(|a, b| a.agent_id.cmp(&b.agent_id));
+        AggregatorStats {
+            aggregator_id: self.aggregator_id.clone(),
+            listen_address: self.config.listen_address.clone(),
+            active_connections: self.config.max_connections - self.connections.available_permits(),
+            unauthorized_requests: self.unauthorized_requests.load(Ordering::Relaxed),
+            backpressure_rejections: self.backpressure_rejections.load(Ordering::Relaxed),
+            senders,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requests_are_read_across_keep_alive() {
+        let body = br#"{"events":[]}"#;
+        let mut wire = format!("POST /ingest HTTP/1.1\r\nHost: agg\r\nAuthorization: Bearer k\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+        wire.extend_from_slice(body);
+        wire.extend_from_slice(b"POST /ingest HTTP/1.1\r\nContent-Length: 999999\r\n\r\n");
+
+        let mut reader = &wire[..];
+        let mut pending = Vec::new();
+        let first = read_request(&mut reader, &mut pending, 1024, |_| Ok(0)).await.unwrap().unwrap().unwrap();
+        assert_eq!((first.method.as_str(), first.path.as_str()), ("POST", "/ingest"));
+        assert_eq!(first.header("authorization"), Some("Bearer k"));
+        assert_eq!(first.body, body);
+
+        let second = read_request(&mut reader, &mut pending, 1024, |_| Ok(0)).await.unwrap().unwrap();
+        assert_eq!(second.unwrap_err().status, 413);
+        assert!(read_request(&mut reader, &mut pending, 1024, |_| Ok(0)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unauthenticated_request_is_refused_before_its_body() {
+        // The body is never sent; the request must be answered from its headers alone
+        let wire = b"POST /ingest HTTP/1.1\r\nAuthorization: Bearer wrong\r\nContent-Length: 1000\r\n\r\n".to_vec();
+        let mut reader = &wire[..];
+        let mut pending = Vec::new();
+        let refused = read_request(&mut reader, &mut pending, 4096, |headers| {
+            assert_eq!(headers.get("authorization").map(String::as_str), Some("Bearer wrong"));
+            Err(Response::error(401, "invalid or missing API key"))
+        }).await.unwrap().unwrap().unwrap_err();
+        assert_eq!(refused.status, 401);
+        assert!(refused.close);
+    }
+
+    #[test]
+    fn test_admitted_events_record_origin_and_path() {
+        let event = json!({
+            "timestamp": "2026-10-16T12:00:00Z",
+            "source": "syslog",
+            "level": "info",
+            "message": "sshd: accepted",
+            "fields": { "securewatch.aggregators": ["branch-a"] },
+            "raw_data": "<34>sshd: accepted",
+            "parser_name": "syslog",
+        });
+
+        let admitted = admit_event(event.clone(), Some("ws-17"), "region-1").unwrap();
+        assert_eq!(admitted.fields["agent.id"], json!("ws-17"));
+        assert_eq!(admitted.fields[AGGREGATOR_PATH_FIELD], json!(["branch-a", "region-1"]));
+
+        // An event that already passed through this aggregator is a forwarding loop
+        assert!(admit_event(event, None, "branch-a").is_none());
+        assert!(admit_event(json!({ "message": "not an event" }), None, "region-1").is_none());
+        assert!(key_matches("0123456789abcdef", "0123456789abcdef"));
+        assert!(!key_matches("0123456789abcdef", "0123456789abcdeF"));
+    }
+}
@@ -0,0 +1,545 @@
+// Declarative alert-on-parse rules for critical local events
+// Parsed events are matched against configured conditions; matches are tagged so the buffer ships them on
+// its priority lane, and a rule can trigger a local webhook or an allowlisted script
+
+use crate::parsers::ParsedEvent;
+use crate::proxy::ProxyConfig;
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+/// Field holding the names of the rules an event matched
+pub const ALERT_RULES_FIELD: &str = "alert.rules";
+/// Field holding the highest severity among matched rules
+pub const ALERT_SEVERITY_FIELD: &str = "alert.severity";
+
+const SEVERITIES: [&str; 4] = ["low", "medium", "high", "critical"];
+
+/// Alert rule configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertRulesConfig {
+    pub enabled: bool,
+    pub rules: Vec<AlertRule>,
+    /// Absolute paths of scripts that rule actions may run
+    pub script_allowlist: Vec<String>,
+    /// Upper bound on a single webhook call or script run
+    pub action_timeout_seconds: u64,
+    /// Local actions allowed per minute across all rules; further matches are still tagged and shipped
+    pub max_actions_per_minute: u32,
+}
+
+impl Default for AlertRulesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: Vec::new(),
+            script_allowlist: Vec::new(),
+            action_timeout_seconds: 10,
+            max_actions_per_minute: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// low, medium, high or critical
+    #[serde(default = "default_severity")]
+    pub severity: String,
+    /// Event sources the rule applies to; empty means any source
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Parser names the rule applies to; empty means any parser
+    #[serde(default)]
+    pub parsers: Vec<String>,
+    /// All conditions must match
+    pub conditions: Vec<AlertCondition>,
+    #[serde(default)]
+    pub action: Option<AlertAction>,
+    /// Minimum time between two actions of this rule
+    #[serde(default = "default_cooldown_seconds")]
+    pub cooldown_seconds: u64,
+}
+
+fn default_severity() -> String {
+    "high".to_string()
+}
+
+fn default_cooldown_seconds() -> u64 {
+    60
+}
+
+/// A single test against one event attribute; exactly one matcher must be set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertCondition {
+    /// `message`, `raw_data`, `source`, `level`, `parser_name` or a parsed field name
+    pub field: String,
+    #[serde(default)]
+    pub equals: Option<String>,
+    #[serde(default)]
+    pub contains: Option<String>,
+    #[serde(default)]
+    pub regex: Option<String>,
+    #[serde(default)]
+    pub one_of: Vec<String>,
+}
+
+/// Local action run when a rule matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertAction {
+    /// POST the alert and event as JSON
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// Run an allowlisted script with the event JSON on stdin and alert details in the environment
+    Script {
+        path: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+impl AlertRulesConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut names = HashSet::new();
+        for path in &self.script_allowlist {
+            if !std::path::Path::new(path).is_absolute() {
+                errors.push(format!("script_allowlist entry '{}' must be an absolute path", path));
+            }
+        }
+        if self.action_timeout_seconds == 0 {
+            errors.push("action_timeout_seconds must be greater than 0".to_string());
+        }
+
+        for rule in &self.rules {
+            if rule.name.trim().is_empty() {
+                errors.push("rule name cannot be empty".to_string());
+            } else if !names.insert(rule.name.as_str()) {
+                errors.push(format!("duplicate rule name '{}'", rule.name));
+            }
+            if !SEVERITIES.contains(&rule.severity.to_ascii_lowercase().as_str()) {
+                errors.push(format!("rule '{}': severity must be one of {}", rule.name, SEVERITIES.join(", ")));
+            }
+            if rule.conditions.is_empty() {
+                errors.push(format!("rule '{}': at least one condition is required", rule.name));
+            }
+            for condition in &rule.conditions {
+                let matchers = [condition.equals.is_some(), condition.contains.is_some(), condition.regex.is_some(), !condition.one_of.is_empty()];
+                if condition.field.is_empty() || matchers.iter().filter(|set| **set).count() != 1 {
+                    errors.push(format!("rule '{}': each condition needs a field and exactly one of equals, contains, regex or one_of", rule.name));
+                }
+                if let Some(Err(e)) = condition.regex.as_deref().map(Regex::new) {
+                    errors.push(format!("rule '{}': invalid regex for '{}': {}", rule.name, condition.field, e));
+                }
+            }
+            match &rule.action {
+                Some(AlertAction::Webhook { url, .. }) => match url::Url::parse(url) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                    _ => errors.push(format!("rule '{}': webhook url must be an HTTP or HTTPS URL", rule.name)),
+                },
+                Some(AlertAction::Script { path, .. }) if !self.script_allowlist.contains(path) => {
+                    errors.push(format!("rule '{}': script '{}' is not in script_allowlist", rule.name, path));
+                }
+                _ => {}
+            }
+        }
+        errors
+    }
+}
+
+enum Matcher {
+    Equals(String),
+    Contains(String),
+    Regex(Regex),
+    OneOf(Vec<String>),
+}
+
+struct CompiledCondition {
+    field: String,
+    matcher: Matcher,
+}
+
+impl CompiledCondition {
+    fn matches(&self, event: &ParsedEvent) -> bool {
+        let value = match self.field.as_str() {
+            "message" => Some(event.message.clone()),
+            "raw_data" => Some(event.raw_data.to_string()),
+            "source" => Some(event.source.clone()),
+            "level" => event.level.clone(),
+            "parser_name" => Some(event.parser_name.clone()),
+            field => event.fields.get(field).map(|value| match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            }),
+        };
+        let Some(value) = value else {
+            return false;
+        };
+        match &self.matcher {
+            Matcher::Equals(expected) => value == *expected,
+            Matcher::Contains(needle) => value.contains(needle.as_str()),
+            Matcher::Regex(regex) => regex.is_match(&value),
+            Matcher::OneOf(options) => options.contains(&value),
+        }
+    }
+}
+
+struct CompiledRule {
+    rule: AlertRule,
+    severity_rank: usize,
+    conditions: Vec<CompiledCondition>,
+    matches: AtomicU64,
+    last_action: Mutex<Option<Instant>>,
+}
+
+impl CompiledRule {
+    fn applies_to(&self, event: &ParsedEvent) -> bool {
+        (self.rule.sources.is_empty() || self.rule.sources.contains(&event.source))
+            && (self.rule.parsers.is_empty() || self.rule.parsers.contains(&event.parser_name))
+            && self.conditions.iter().all(|condition| condition.matches(event))
+    }
+}
+
+/// Alert rule metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertStats {
+    pub rules: usize,
+    pub events_matched: u64,
+    pub actions_triggered: u64,
+    pub actions_failed: u64,
+    pub actions_suppressed: u64,
+    pub matches_by_rule: HashMap<String, u64>,
+}
+
+/// Evaluates alert rules against parsed events and dispatches their actions
+pub struct AlertEngine {
+    rules: Vec<CompiledRule>,
+    script_allowlist: Vec<String>,
+    action_timeout: Duration,
+    max_actions_per_minute: u32,
+    action_window: Mutex<(Instant, u32)>,
+    /// One client per webhook URL, each through the proxy `transport.proxy` resolves for it
+    webhook_clients: HashMap<String, reqwest::Client>,
+    events_matched: AtomicU64,
+    actions_triggered: Arc<AtomicU64>,
+    actions_failed: Arc<AtomicU64>,
+    actions_suppressed: AtomicU64,
+}
+
+impl AlertEngine {
+    pub fn new(config: &AlertRulesConfig, proxy: &ProxyConfig) -> Result<Self, String> {
+        if let Some(error) = config.validate().into_iter().next() {
+            return Err(error);
+        }
+        let rules = config.rules.iter().map(|rule| {
+            let conditions = rule.conditions.iter().map(|condition| {
+                let matcher = if let Some(expected) = &condition.equals {
+                    Matcher::Equals(expected.clone())
+                } else if let Some(needle) = &condition.contains {
+                    Matcher::Contains(needle.clone())
+                } else if let Some(pattern) = &condition.regex {
+                    // Already checked by validate
+                    Matcher::Regex(Regex::new(pattern).map_err(|e| e.to_string())?)
+                } else {
+                    Matcher::OneOf(condition.one_of.clone())
+                };
+                Ok(CompiledCondition { field: condition.field.clone(), matcher })
+            }).collect::<Result<Vec<_>, String>>()?;
+
+            Ok(CompiledRule {
+                severity_rank: SEVERITIES.iter().position(|s| s.eq_ignore_ascii_case(&rule.severity)).unwrap_or(2),
+                rule: rule.clone(),
+                conditions,
+                matches: AtomicU64::new(0),
+                last_action: Mutex::new(None),
+            })
+        }).collect::<Result<Vec<_>, String>>()?;
+
+        let action_timeout = Duration::from_secs(config.action_timeout_seconds);
+        let mut webhook_clients = HashMap::new();
+        for rule in &config.rules {
+            let Some(AlertAction::Webhook { url, .. }) = &rule.action else {
+                continue;
+            };
+            if webhook_clients.contains_key(url) {
+                continue;
+            }
+            let client = proxy.apply(reqwest::Client::builder().timeout(action_timeout), url)
+                .and_then(|builder| builder.build().map_err(|e| e.to_string()))
+                .map_err(|e| format!("failed to create webhook client for rule '{}': {}", rule.name, e))?;
+            webhook_clients.insert(url.clone(), client);
+        }
+
+        Ok(Self {
+            rules,
+            script_allowlist: config.script_allowlist.clone(),
+            action_timeout,
+            max_actions_per_minute: config.max_actions_per_minute,
+            action_window: Mutex::new((Instant::now(), 0)),
+            webhook_clients,
+            events_matched: AtomicU64::new(0),
+            actions_triggered: Arc::new(AtomicU64::new(0)),
+            actions_failed: Arc::new(AtomicU64::new(0)),
+            actions_suppressed: AtomicU64::new(0),
+        })
+    }
+
+    /// Tag the event with every rule it matches and fire their actions; returns the number of matches
+    pub fn evaluate(&self, event: &mut ParsedEvent) -> usize {
+        let matched: Vec<&CompiledRule> = self.rules.iter().filter(|rule| rule.applies_to(event)).collect();
+        if matched.is_empty() {
+            return 0;
+        }
+
+        let severity = matched.iter().map(|rule| rule.severity_rank).max().unwrap_or_default();
+        let names: Vec<&str> = matched.iter().map(|rule| rule.rule.name.as_str()).collect();
+        event.fields.insert(ALERT_RULES_FIELD.to_string(), json!(names));
+        event.fields.insert(ALERT_SEVERITY_FIELD.to_string(), json!(SEVERITIES[severity]));
+        self.events_matched.fetch_add(1, Ordering::Relaxed);
+        warn!("🚨 Alert rule(s) {} matched {} event from {}", names.join(", "), SEVERITIES[severity], event.source);
+
+        for rule in &matched {
+            rule.matches.fetch_add(1, Ordering::Relaxed);
+            if let Some(action) = &rule.rule.action {
+                if self.claim_action(rule) {
+                    self.dispatch(&rule.rule, action, event);
+                } else {
+                    self.actions_suppressed.fetch_add(1, Ordering::Relaxed);
+                    debug!("Alert action for '{}' suppressed by cooldown or rate limit", rule.rule.name);
+                }
+            }
+        }
+        matched.len()
+    }
+
+    /// Check the rule cooldown and the global per-minute budget, reserving a slot when both allow it
+    fn claim_action(&self, rule: &CompiledRule) -> bool {
+        let now = Instant::now();
+        let mut last_action = rule.last_action.lock();
+        if last_action.is_some_and(|last| now.duration_since(last) < Duration::from_secs(rule.rule.cooldown_seconds)) {
+            return false;
+        }
+        let mut window = self.action_window.lock();
+        if now.duration_since(window.0) >= Duration::from_secs(60) {
+            *window = (now, 0);
+        }
+        if window.1 >= self.max_actions_per_minute {
+            return false;
+        }
+        window.1 += 1;
+        *last_action = Some(now);
+        true
+    }
+
+    fn dispatch(&self, rule: &AlertRule, action: &AlertAction, event: &ParsedEvent) {
+        let payload = json!({
+            "rule": rule.name,
+            "description": rule.description,
+            "severity": rule.severity,
+            "triggered_at": chrono::Utc::now().to_rfc3339(),
+            "event": event,
+        });
+        let triggered = self.actions_triggered.clone();
+        let failed = self.actions_failed.clone();
+        let rule_name = rule.name.clone();
+
+        match action.clone() {
+            AlertAction::Webhook { url, headers } => {
+                let Some(client) = self.webhook_clients.get(&url) else {
+                    failed.fetch_add(1, Ordering::Relaxed);
+                    warn!("⚠️ Alert webhook for '{}' has no client for {}", rule_name, url);
+                    return;
+                };
+                let mut request = client.post(&url).json(&payload);
+                for (name, value) in &headers {
+                    request = request.header(name.as_str(), value);
+                }
+                tokio::spawn(async move {
+                    triggered.fetch_add(1, Ordering::Relaxed);
+                    match request.send().await.and_then(|response| response.error_for_status()) {
+                        Ok(_) => info!("📣 Alert webhook for '{}' delivered to {}", rule_name, url),
+                        Err(e) => {
+                            failed.fetch_add(1, Ordering::Relaxed);
+                            warn!("⚠️ Alert webhook for '{}' failed: {}", rule_name, e);
+                        }
+                    }
+                });
+            }
+            AlertAction::Script { path, args } => {
+                // Re-checked here so a rule can never run a script that was dropped from the allowlist
+                if !self.script_allowlist.contains(&path) {
+                    failed.fetch_add(1, Ordering::Relaxed);
+                    warn!("⚠️ Alert script '{}' for '{}' is not allowlisted; not running it", path, rule_name);
+                    return;
+                }
+                let timeout = self.action_timeout;
+                let mut command = tokio::process::Command::new(&path);
+                command
+                    .args(&args)
+                    .env("SECUREWATCH_ALERT_RULE", &rule.name)
+                    .env("SECUREWATCH_ALERT_SEVERITY", &rule.severity)
+                    .env("SECUREWATCH_EVENT_SOURCE", &event.source)
+                    .env("SECUREWATCH_EVENT_MESSAGE", &event.message)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true);
+                tokio::spawn(async move {
+                    triggered.fetch_add(1, Ordering::Relaxed);
+                    let run = async {
+                        let mut child = command.spawn()?;
+                        if let Some(mut stdin) = child.stdin.take() {
+                            stdin.write_all(payload.to_string().as_bytes()).await?;
+                        }
+                        child.wait().await
+                    };
+                    match tokio::time::timeout(timeout, run).await {
+                        Ok(Ok(status)) if status.success() => info!("📣 Alert script for '{}' completed", rule_name),
+                        Ok(Ok(status)) => {
+                            failed.fetch_add(1, Ordering::Relaxed);
+                            warn!("⚠️ Alert script for '{}' exited with {}", rule_name, status);
+                        }
+                        Ok(Err(e)) => {
+                            failed.fetch_add(1, Ordering::Relaxed);
+                            warn!("⚠️ Alert script for '{}' failed to run: {}", rule_name, e);
+                        }
+                        Err(_) => {
+                            failed.fetch_add(1, Ordering::Relaxed);
+                            warn!("⚠️ Alert script for '{}' timed out after {:?} and was killed", rule_name, timeout);
+                        }
+                    }
+                });
+            }
+        }
+    }
+
+    pub fn get_stats(&self) -> AlertStats {
+        AlertStats {
+            rules: self.rules.len(),
+            events_matched: self.events_matched.load(Ordering::Relaxed),
+            actions_triggered: self.actions_triggered.load(Ordering::Relaxed),
+            actions_failed: self.actions_failed.load(Ordering::Relaxed),
+            actions_suppressed: self.actions_suppressed.load(Ordering::Relaxed),
+            matches_by_rule: self.rules.iter()
+                .map(|rule| (rule.rule.name.clone(), rule.matches.load(Ordering::Relaxed)))
+                .collect(),
+        }
+    }
+}
+
+/// Whether an event was tagged by an alert rule and belongs on the priority path
+pub fn is_alert(event: &ParsedEvent) -> bool {
+    event.fields.contains_key(ALERT_RULES_FIELD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(message: &str) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "syslog".to_string(),
+            level: Some("info".to_string()),
+            message: message.to_string(),
+            fields: HashMap::from([("process.name".to_string(), json!("usermod"))]),
+            raw_data: message.into(),
+            parser_name: "syslog".to_string(),
+        }
+    }
+
+    fn sudo_rule() -> AlertRule {
+        AlertRule {
+            name: "user_added_to_sudo".to_string(),
+            description: None,
+            severity: "critical".to_string(),
+            sources: vec!["syslog".to_string()],
+            parsers: Vec::new(),
+            conditions: vec![
+                AlertCondition { field: "process.name".to_string(), one_of: vec!["usermod".to_string(), "gpasswd".to_string()], ..Default::default() },
+                AlertCondition { field: "message".to_string(), regex: Some(r"(?i)to group '?sudo".to_string()), ..Default::default() },
+            ],
+            action: None,
+            cooldown_seconds: 60,
+        }
+    }
+
+    #[test]
+    fn test_matching_event_is_tagged() {
+        let config = AlertRulesConfig { enabled: true, rules: vec![sudo_rule()], ..Default::default() };
+        let engine = AlertEngine::new(&config, &ProxyConfig::default()).unwrap();
+
+        let mut matching = event("add 'mallory' to group 'sudo'");
+        assert_eq!(engine.evaluate(&mut matching), 1);
+        assert!(is_alert(&matching));
+        assert_eq!(matching.fields[ALERT_SEVERITY_FIELD], "critical");
+
+        let mut other = event("add 'mallory' to group 'video'");
+        assert_eq!(engine.evaluate(&mut other), 0);
+        assert!(!is_alert(&other));
+        assert_eq!(engine.get_stats().matches_by_rule["user_added_to_sudo"], 1);
+    }
+
+    #[test]
+    fn test_validation_enforces_script_allowlist_and_matchers() {
+        let mut rule = sudo_rule();
+        rule.action = Some(AlertAction::Script { path: "/usr/local/bin/isolate.sh".to_string(), args: Vec::new() });
+        rule.conditions.push(AlertCondition { field: "level".to_string(), ..Default::default() });
+        let mut config = AlertRulesConfig { enabled: true, rules: vec![rule], ..Default::default() };
+
+        let errors = config.validate();
+        assert!(errors.iter().any(|e| e.contains("not in script_allowlist")));
+        assert!(errors.iter().any(|e| e.contains("exactly one of")));
+
+        config.rules[0].conditions.pop();
+        config.script_allowlist.push("/usr/local/bin/isolate.sh".to_string());
+        assert!(config.validate().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_go_through_the_transport_proxy() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let proxy = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream.write_all(b"HTTP/1.1 204 No Content\r\nconnection: close\r\n\r\n").await.unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        let mut rule = sudo_rule();
+        rule.action = Some(AlertAction::Webhook { url: "http://soar.example.invalid/hooks/alert".to_string(), headers: HashMap::new() });
+        let config = AlertRulesConfig { enabled: true, rules: vec![rule], ..Default::default() };
+        let proxy_config = ProxyConfig {
+            mode: crate::proxy::ProxyMode::Manual,
+            url: Some(format!("http://{}", address)),
+            ..Default::default()
+        };
+        let engine = AlertEngine::new(&config, &proxy_config).unwrap();
+        assert_eq!(engine.evaluate(&mut event("add 'mallory' to group 'sudo'")), 1);
+
+        let request = tokio::time::timeout(Duration::from_secs(5), proxy).await.unwrap().unwrap();
+        assert!(request.starts_with("POST http://soar.example.invalid/hooks/alert HTTP/1.1"));
+    }
+}
@@ -0,0 +1,204 @@
+// Egress bandwidth caps for the transport
+// A token bucket in bytes shared by the primary endpoint, every destination and relayed envelopes, so draining a
+// backlog after an outage cannot saturate a thin branch-office link. The limit can be changed at runtime through
+// the management API; a config reload puts the configured limit back.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+const KB: f64 = 1024.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandwidthConfig {
+    pub enabled: bool,
+    /// Sustained upload rate across all endpoints, in KB/s
+    pub max_kb_per_second: u64,
+    /// Bytes that may go out at once after the link was idle, in KB
+    pub burst_kb: u64,
+}
+
+impl Default for BandwidthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_kb_per_second: 1024,
+            burst_kb: 256,
+        }
+    }
+}
+
+impl BandwidthConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.max_kb_per_second == 0 {
+            errors.push("max_kb_per_second must be greater than 0".to_string());
+        }
+        if self.burst_kb == 0 {
+            errors.push("burst_kb must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BandwidthStats {
+    /// None while uploads are unlimited
+    pub max_kb_per_second: Option<u64>,
+    pub burst_kb: u64,
+    pub bytes_sent: u64,
+    /// Requests that had to wait for the bucket
+    pub throttled_requests: u64,
+    pub throttled_wait_ms: u64,
+    /// Burst currently available, in KB; negative while requests are waiting
+    pub available_kb: f64,
+}
+
+struct Bucket {
+    /// Bytes per second; None when unlimited
+    rate: Option<f64>,
+    capacity: f64,
+    /// Goes negative when a payload larger than what is available is let through; later requests wait it off
+    tokens: f64,
+    refilled: Instant,
+    stats: BandwidthStats,
+}
+
+/// Token bucket the transport passes every payload through before sending it
+pub struct BandwidthLimiter {
+    bucket: Mutex<Bucket>,
+}
+
+impl BandwidthLimiter {
+    /// Limiter at the configured rate, or unlimited when the cap is disabled
+    pub fn new(config: &BandwidthConfig) -> Self {
+        let limiter = Self {
+            bucket: Mutex::new(Bucket {
+                rate: None,
+                capacity: 0.0,
+                tokens: 0.0,
+                refilled: Instant::now(),
+                stats: BandwidthStats::default(),
+            }),
+        };
+        limiter.configure(config);
+        limiter
+    }
+
+    pub fn configure(&self, config: &BandwidthConfig) {
+        match config.enabled {
+            true => self.set_limit(Some(config.max_kb_per_second), config.burst_kb),
+            false => self.set_limit(None, config.burst_kb),
+        }
+    }
+
+    /// Change the cap; `None` lifts it. Requests already waiting keep their delay
+    pub fn set_limit(&self, max_kb_per_second: Option<u64>, burst_kb: u64) {
+        let mut bucket = self.bucket.lock();
+        let now = Instant::now();
+        Self::refill(&mut bucket, now);
+        let was_unlimited = bucket.rate.is_none();
+        bucket.rate = max_kb_per_second.map(|kb| kb as f64 * KB);
+        bucket.capacity = burst_kb as f64 * KB;
+        // A new cap starts with its full burst
+        bucket.tokens = match (bucket.rate, was_unlimited) {
+            (None, _) => 0.0,
+            (Some(_), true) => bucket.capacity,
+            (Some(_), false) => bucket.tokens.min(bucket.capacity),
+        };
+        bucket.stats.max_kb_per_second = max_kb_per_second;
+        bucket.stats.burst_kb = burst_kb;
+        match max_kb_per_second {
+            Some(kb) => info!("🚦 Upload bandwidth capped at {} KB/s (burst {} KB)", kb, burst_kb),
+            None => info!("🚦 Upload bandwidth unlimited"),
+        }
+    }
+
+    /// Wait until `bytes` may be sent
+    pub async fn acquire(&self, bytes: usize) {
+        let wait = self.reserve_at(bytes, Instant::now());
+        if !wait.is_zero() {
+            debug!("🚦 Holding {} bytes for {:?} to stay under the upload cap", bytes, wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take `bytes` from the bucket and return how long the caller has to wait before sending them
+    fn reserve_at(&self, bytes: usize, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock();
+        bucket.stats.bytes_sent += bytes as u64;
+        let Some(rate) = bucket.rate else {
+            return Duration::ZERO;
+        };
+        Self::refill(&mut bucket, now);
+        bucket.tokens -= bytes as f64;
+        if bucket.tokens >= 0.0 {
+            return Duration::ZERO;
+        }
+        let wait = Duration::from_secs_f64(-bucket.tokens / rate);
+        bucket.stats.throttled_requests += 1;
+        bucket.stats.throttled_wait_ms += wait.as_millis() as u64;
+        wait
+    }
+
+    fn refill(bucket: &mut Bucket, now: Instant) {
+        if let Some(rate) = bucket.rate {
+            let elapsed = now.saturating_duration_since(bucket.refilled).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * rate).min(bucket.capacity);
+        }
+        bucket.refilled = now;
+    }
+
+    pub fn stats(&self) -> BandwidthStats {
+        let mut bucket = self.bucket.lock();
+        Self::refill(&mut bucket, Instant::now());
+        BandwidthStats { available_kb: bucket.tokens / KB, ..bucket.stats.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capped(kb_per_second: u64, burst_kb: u64) -> BandwidthLimiter {
+        BandwidthLimiter::new(&BandwidthConfig { enabled: true, max_kb_per_second: kb_per_second, burst_kb })
+    }
+
+    #[test]
+    fn test_burst_then_sustained_rate() {
+        let limiter = capped(100, 50);
+        let start = Instant::now();
+        // The burst goes out immediately, the rest at 100 KB/s
+        assert_eq!(limiter.reserve_at(50 * 1024, start), Duration::ZERO);
+        assert_eq!(limiter.reserve_at(100 * 1024, start), Duration::from_secs(1));
+        assert_eq!(limiter.reserve_at(50 * 1024, start), Duration::from_millis(1500));
+
+        // Idle time refills the bucket, but never beyond the burst
+        let later = start + Duration::from_secs(60);
+        assert_eq!(limiter.reserve_at(50 * 1024, later), Duration::ZERO);
+        assert!(limiter.reserve_at(1024, later) > Duration::ZERO);
+
+        let stats = limiter.stats();
+        assert_eq!(stats.bytes_sent, 251 * 1024);
+        assert_eq!((stats.throttled_requests, stats.throttled_wait_ms), (3, 2510));
+    }
+
+    #[test]
+    fn test_limit_changes_at_runtime() {
+        let limiter = BandwidthLimiter::new(&BandwidthConfig::default());
+        let start = Instant::now();
+        assert_eq!(limiter.reserve_at(10 * 1024 * 1024, start), Duration::ZERO);
+        assert_eq!(limiter.stats().max_kb_per_second, None);
+
+        limiter.set_limit(Some(10), 1);
+        let stats = limiter.stats();
+        assert_eq!((stats.max_kb_per_second, stats.burst_kb), (Some(10), 1));
+        assert!(limiter.reserve_at(11 * 1024, Instant::now()) >= Duration::from_millis(999));
+
+        limiter.set_limit(None, 1);
+        assert_eq!(limiter.reserve_at(11 * 1024, Instant::now()), Duration::ZERO);
+        assert_eq!(BandwidthConfig { max_kb_per_second: 0, burst_kb: 0, ..Default::default() }.validate().len(), 2);
+    }
+}
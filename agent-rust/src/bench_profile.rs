@@ -0,0 +1,313 @@
+// Synthetic load against a running agent
+// `--bench-profile` replays generated syslog traffic into an agent's syslog listener at a fixed rate and reports
+// the rate it sustained, so ingest throughput can be compared before and after a performance change
+
+use crate::config::SyslogCollectorConfig;
+use crate::errors::ConfigError;
+use crate::simulator::{EventGenerator, EventMix, Platform};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::{interval, Duration, Instant, MissedTickBehavior};
+use tracing::info;
+
+/// Load generator parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BenchProfile {
+    /// Syslog listener to load as host:port; defaults to the configured syslog collector
+    pub target: Option<String>,
+    /// udp or tcp (newline framed); defaults to the collector's protocol, preferring udp for "both"
+    pub protocol: Option<String>,
+    /// Total events per second across all connections
+    pub events_per_second: u64,
+    pub duration_seconds: u64,
+    /// Concurrent senders, each with its own socket
+    pub connections: usize,
+    /// Messages shorter than this are padded, to measure large payloads
+    pub message_size: usize,
+    /// Seed for the generated messages
+    pub seed: u64,
+}
+
+impl Default for BenchProfile {
+    fn default() -> Self {
+        Self {
+            target: None,
+            protocol: None,
+            events_per_second: 10_000,
+            duration_seconds: 30,
+            connections: 1,
+            message_size: 0,
+            seed: 1,
+        }
+    }
+}
+
+impl BenchProfile {
+    pub async fn load_from_file(path: &str) -> Result<Self, ConfigError> {
+        let content = tokio::fs::read_to_string(path).await
+            .map_err(|e| ConfigError::Io(e.to_string()))?;
+
+        let profile: BenchProfile = toml::from_str(&content)
+            .map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+        let errors = profile.validate();
+        if !errors.is_empty() {
+            return Err(ConfigError::Validation(errors.join("; ")));
+        }
+        Ok(profile)
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.events_per_second == 0 || self.duration_seconds == 0 {
+            errors.push("Bench profile events_per_second and duration_seconds must be greater than 0".to_string());
+        }
+        if self.connections == 0 {
+            errors.push("Bench profile connections must be greater than 0".to_string());
+        }
+        if let Some(protocol) = &self.protocol {
+            if protocol != "udp" && protocol != "tcp" {
+                errors.push(format!("Bench profile protocol must be udp or tcp, got '{}'", protocol));
+            }
+        }
+        errors
+    }
+
+    /// Target address and protocol, filling gaps from the agent's syslog collector
+    pub fn resolve_target(&self, syslog: Option<&SyslogCollectorConfig>) -> Result<(String, String), String> {
+        let collector = syslog.filter(|s| s.enabled);
+        let target = match (&self.target, collector) {
+            (Some(target), _) => target.clone(),
+            (None, Some(collector)) => {
+                // A wildcard bind is reached over loopback
+                let host = match collector.bind_address.as_str() {
+                    "0.0.0.0" | "" => "127.0.0.1",
+                    "::" => "::1",
+                    host => host,
+                };
+                if host.contains(':') { format!("[{}]:{}", host, collector.port) } else { format!("{}:{}", host, collector.port) }
+            }
+            (None, None) => return Err("no bench target set and the syslog collector is disabled".to_string()),
+        };
+        let protocol = match (&self.protocol, collector.map(|c| c.protocol.as_str())) {
+            (Some(protocol), _) => protocol.clone(),
+            (None, Some("tcp")) => "tcp".to_string(),
+            (None, Some("tls")) => return Err("the syslog collector only accepts TLS; set a udp or tcp bench target".to_string()),
+            _ => "udp".to_string(),
+        };
+        Ok((target, protocol))
+    }
+}
+
+/// Outcome of a load run, as seen by the sender
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub target: String,
+    pub protocol: String,
+    pub elapsed_seconds: f64,
+    pub events_sent: u64,
+    pub bytes_sent: u64,
+    pub send_errors: u64,
+    pub target_events_per_second: u64,
+    pub achieved_events_per_second: f64,
+    /// Events still due when the run ended because the senders could not keep up
+    pub events_behind: u64,
+}
+
+impl BenchReport {
+    pub fn to_text(&self) -> String {
+        format!(
+            "Target:        {} ({})\nDuration:      {:.1}s\nSent:          {} events, {} bytes\nSend errors:   {}\nRate:          {:.0} events/s of {} requested\nBehind:        {} events\n",
+            self.target, self.protocol, self.elapsed_seconds, self.events_sent, self.bytes_sent, self.send_errors,
+            self.achieved_events_per_second, self.target_events_per_second, self.events_behind,
+        )
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    sent: AtomicU64,
+    bytes: AtomicU64,
+    errors: AtomicU64,
+    behind: AtomicU64,
+}
+
+enum Sender {
+    Udp(UdpSocket),
+    Tcp(BufWriter<TcpStream>),
+}
+
+impl Sender {
+    async fn connect(target: &str, protocol: &str) -> std::io::Result<Self> {
+        Ok(match protocol {
+            "tcp" => Sender::Tcp(BufWriter::new(TcpStream::connect(target).await?)),
+            _ => {
+                let address = tokio::net::lookup_host(target).await?.next()
+                    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("cannot resolve {}", target)))?;
+                let socket = UdpSocket::bind(if address.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" }).await?;
+                socket.connect(address).await?;
+                Sender::Udp(socket)
+            }
+        })
+    }
+
+    async fn send(&mut self, message: &str) -> std::io::Result<()> {
+        match self {
+            Sender::Udp(socket) => socket.send(message.as_bytes()).await.map(|_| ()),
+            Sender::Tcp(stream) => {
+                stream.write_all(message.as_bytes()).await?;
+                stream.write_all(b"\n").await
+            }
+        }
+    }
+
+    async fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Sender::Udp(_) => Ok(()),
+            Sender::Tcp(stream) => stream.flush().await,
+        }
+    }
+}
+
+/// Send generated syslog messages to `target` at the profile's rate for its duration
+pub async fn run(profile: &BenchProfile, target: &str, protocol: &str) -> std::io::Result<BenchReport> {
+    info!(
+        target_address = target,
+        protocol = protocol,
+        events_per_second = profile.events_per_second,
+        duration_seconds = profile.duration_seconds,
+        "🏋️ Starting synthetic load"
+    );
+    let counters = Arc::new(Counters::default());
+    let duration = Duration::from_secs(profile.duration_seconds);
+    let mut senders = Vec::new();
+    for connection in 0..profile.connections {
+        // Spread the rate evenly, giving the remainder to the first senders
+        let rate = profile.events_per_second / profile.connections as u64
+            + u64::from((connection as u64) < profile.events_per_second % profile.connections as u64);
+        let sender = Sender::connect(target, protocol).await?;
+        let generator = EventGenerator::new(
+            &format!("bench-{}", connection),
+            Platform::Linux,
+            EventMix::from_str("syslog_auth=1,syslog_system=1").expect("valid event mix"),
+            profile.seed.wrapping_add(connection as u64),
+        );
+        senders.push(tokio::spawn(send_at_rate(sender, generator, rate, duration, profile.message_size, counters.clone())));
+    }
+
+    let started = Instant::now();
+    for sender in senders {
+        sender.await.map_err(std::io::Error::other)?;
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+    let events_sent = counters.sent.load(Ordering::Relaxed);
+    Ok(BenchReport {
+        target: target.to_string(),
+        protocol: protocol.to_string(),
+        elapsed_seconds: elapsed,
+        events_sent,
+        bytes_sent: counters.bytes.load(Ordering::Relaxed),
+        send_errors: counters.errors.load(Ordering::Relaxed),
+        target_events_per_second: profile.events_per_second,
+        achieved_events_per_second: events_sent as f64 / elapsed.max(f64::EPSILON),
+        events_behind: counters.behind.load(Ordering::Relaxed),
+    })
+}
+
+/// Keep the number of sent events on the line `rate * elapsed`, topping up every tick
+async fn send_at_rate(
+    mut sender: Sender,
+    mut generator: EventGenerator,
+    rate: u64,
+    duration: Duration,
+    message_size: usize,
+    counters: Arc<Counters>,
+) {
+    // Senders that can't keep up stop this long after the deadline and report what they still owed
+    const GRACE: Duration = Duration::from_secs(1);
+    let started = Instant::now();
+    let total = (rate as f64 * duration.as_secs_f64()) as u64;
+    let mut ticker = interval(Duration::from_millis(10));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut sent = 0u64;
+    loop {
+        ticker.tick().await;
+        let due = ((rate as f64 * started.elapsed().as_secs_f64()) as u64).min(total);
+        while sent < due {
+            let mut message = generator.generate().raw_data.to_string();
+            if message.len() < message_size {
+                message.push(' ');
+                message.extend(std::iter::repeat_n('x', message_size - message.len()));
+            }
+            match sender.send(&message).await {
+                Ok(()) => {
+                    counters.sent.fetch_add(1, Ordering::Relaxed);
+                    counters.bytes.fetch_add(message.len() as u64, Ordering::Relaxed);
+                }
+                Err(_) => {
+                    counters.errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            sent += 1;
+            if sent % 1000 == 0 && started.elapsed() >= duration + GRACE {
+                break;
+            }
+        }
+        // Stream senders write each tick's messages together
+        if sender.flush().await.is_err() {
+            counters.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        if sent >= total || started.elapsed() >= duration + GRACE {
+            break;
+        }
+    }
+    counters.behind.fetch_add(total - sent, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collector(bind_address: &str, protocol: &str) -> SyslogCollectorConfig {
+        let mut syslog = crate::config::AgentConfig::default().collectors.syslog.expect("default syslog collector");
+        syslog.enabled = true;
+        syslog.bind_address = bind_address.to_string();
+        syslog.port = 5514;
+        syslog.protocol = protocol.to_string();
+        syslog
+    }
+
+    #[test]
+    fn test_target_defaults_to_syslog_collector() {
+        let profile = BenchProfile::default();
+        assert_eq!(profile.resolve_target(Some(&collector("0.0.0.0", "both"))).unwrap(), ("127.0.0.1:5514".to_string(), "udp".to_string()));
+        assert_eq!(profile.resolve_target(Some(&collector("::", "tcp"))).unwrap(), ("[::1]:5514".to_string(), "tcp".to_string()));
+        assert!(profile.resolve_target(Some(&collector("0.0.0.0", "tls"))).is_err());
+        assert!(profile.resolve_target(None).is_err());
+
+        let profile = BenchProfile { target: Some("10.0.0.5:514".to_string()), protocol: Some("tcp".to_string()), ..Default::default() };
+        assert_eq!(profile.resolve_target(None).unwrap(), ("10.0.0.5:514".to_string(), "tcp".to_string()));
+        assert_eq!(BenchProfile { connections: 0, protocol: Some("tls".to_string()), ..Default::default() }.validate().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sends_padded_messages_at_rate() {
+        let listener = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        let profile = BenchProfile { events_per_second: 200, duration_seconds: 1, connections: 2, message_size: 300, ..Default::default() };
+
+        let report = run(&profile, &target, "udp").await.unwrap();
+        assert_eq!(report.events_sent + report.events_behind, 200);
+        assert_eq!(report.send_errors, 0);
+
+        let mut datagram = [0u8; 1024];
+        let len = listener.recv(&mut datagram).await.unwrap();
+        assert_eq!(len, 300);
+        assert!(datagram.starts_with(b"<"));
+    }
+}
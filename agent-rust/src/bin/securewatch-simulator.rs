@@ -0,0 +1,164 @@
+// SecureWatch agent simulator - load-tests the backend with a fleet of virtual agents
+// Transport settings come from a regular agent.toml so the simulator talks to the backend exactly like the agent
+
+use clap::Parser;
+use std::path::PathBuf;
+use tokio::sync::broadcast;
+use tracing::{error, info, Level};
+use tracing_subscriber::EnvFilter;
+
+use securewatch_agent::simulator::{EventMix, Simulator, SimulatorConfig};
+use securewatch_agent::AgentConfig;
+
+#[derive(Parser)]
+#[command(author, version, about = "Simulate many SecureWatch agents for backend load testing", long_about = None)]
+struct Cli {
+    /// Agent configuration file providing transport settings
+    #[arg(short, long, default_value = "agent.toml")]
+    config: PathBuf,
+
+    /// Override the backend URL from the configuration
+    #[arg(long)]
+    server_url: Option<String>,
+
+    /// Override the API key from the configuration
+    #[arg(long)]
+    api_key: Option<String>,
+
+    /// Number of virtual agents
+    #[arg(short, long, default_value_t = 10)]
+    agents: usize,
+
+    /// Events per second generated by each agent
+    #[arg(long, default_value_t = 5.0)]
+    eps: f64,
+
+    /// Events per batch sent to the backend
+    #[arg(long, default_value_t = 100)]
+    batch_size: usize,
+
+    /// Stop after this many seconds (runs until Ctrl+C when omitted)
+    #[arg(short, long)]
+    duration: Option<u64>,
+
+    /// Spread agent start-up over this many seconds
+    #[arg(long, default_value_t = 10)]
+    ramp_up: u64,
+
+    /// Seconds between heartbeats of each agent
+    #[arg(long, default_value_t = 30)]
+    heartbeat_interval: u64,
+
+    /// Simulated network outages per agent per hour
+    #[arg(long, default_value_t = 2.0)]
+    disconnect_rate: f64,
+
+    /// Longest simulated outage in seconds
+    #[arg(long, default_value_t = 60)]
+    max_offline: u64,
+
+    /// Share of agents that behave like Windows hosts (0-1)
+    #[arg(long, default_value_t = 0.3)]
+    windows_ratio: f64,
+
+    /// Event mix as kind=weight pairs, e.g. "syslog_auth=30,web_access=10"
+    #[arg(long)]
+    mix: Option<String>,
+
+    /// Seed for reproducible runs
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+
+    /// Prefix of the generated agent IDs
+    #[arg(long, default_value = "sim-agent")]
+    agent_prefix: String,
+
+    /// Print the final statistics as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Log level
+    #[arg(short, long, default_value = "info")]
+    log_level: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    let log_level = match cli.log_level.to_lowercase().as_str() {
+        "trace" => Level::TRACE,
+        "debug" => Level::DEBUG,
+        "warn" => Level::WARN,
+        "error" => Level::ERROR,
+        _ => Level::INFO,
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::builder().with_default_directive(log_level.into()).from_env_lossy())
+        .init();
+
+    let mut transport_config = if cli.config.exists() {
+        info!("📖 Loading transport settings from {}", cli.config.display());
+        AgentConfig::load_from_file(cli.config.to_str().unwrap()).await?.transport
+    } else {
+        info!("📝 Using default transport settings");
+        AgentConfig::default().transport
+    };
+    if let Some(server_url) = cli.server_url {
+        transport_config.server_url = server_url;
+    }
+    if let Some(api_key) = cli.api_key {
+        transport_config.api_key = api_key;
+    }
+
+    let config = SimulatorConfig {
+        agents: cli.agents,
+        events_per_second: cli.eps,
+        batch_size: cli.batch_size,
+        heartbeat_interval_secs: cli.heartbeat_interval,
+        duration_secs: cli.duration,
+        ramp_up_secs: cli.ramp_up,
+        disconnects_per_hour: cli.disconnect_rate,
+        max_offline_secs: cli.max_offline,
+        windows_ratio: cli.windows_ratio,
+        mix: match cli.mix {
+            Some(mix) => mix.parse::<EventMix>()?,
+            None => EventMix::default(),
+        },
+        seed: cli.seed,
+        agent_prefix: cli.agent_prefix,
+        ..Default::default()
+    };
+    let errors = config.validate();
+    if !errors.is_empty() {
+        for e in &errors {
+            error!("❌ Invalid simulator setting: {}", e);
+        }
+        std::process::exit(1);
+    }
+
+    let (shutdown_tx, _) = broadcast::channel(1);
+    let signal_tx = shutdown_tx.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            info!("🛑 Interrupted, stopping virtual agents");
+            let _ = signal_tx.send(());
+        }
+    });
+
+    let snapshot = Simulator::new(config, transport_config).run(shutdown_tx).await?;
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&snapshot)?);
+    } else {
+        println!("Elapsed:           {:.1}s", snapshot.elapsed_secs);
+        println!("Events generated:  {}", snapshot.events_generated);
+        println!("Events sent:       {} ({:.1}/s)", snapshot.events_sent, snapshot.events_per_second);
+        println!("Events dropped:    {}", snapshot.events_dropped);
+        println!("Batches sent:      {} ({:.1}ms avg)", snapshot.batches_sent, snapshot.average_batch_latency_ms);
+        println!("Send failures:     {}", snapshot.send_failures);
+        println!("Heartbeats sent:   {}", snapshot.heartbeats_sent);
+        println!("Disconnects:       {}", snapshot.disconnects);
+        println!("Reconnects:        {}", snapshot.reconnects);
+    }
+    Ok(())
+}
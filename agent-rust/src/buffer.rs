@@ -1,35 +1,106 @@
 // Advanced persistent buffering with SQLite WAL mode, checkpointing, and vacuum operations
 
-use crate::config::{BufferConfig, SqliteSynchronousMode, SqliteAutoVacuum, SqliteTempStore, CleanupStrategy};
+use crate::alert_rules;
+use crate::buffer_compression;
+use crate::buffer_encryption::{self, BufferCipher};
+use crate::burst_overflow::{BurstOverflow, BurstStats};
+use crate::component_usage;
+use crate::config::{BufferConfig, BufferLimits, SqliteSynchronousMode, SqliteAutoVacuum, SqliteTempStore, CleanupStrategy, NewerEventPolicy};
+use crate::dedup::DuplicateFilter;
+use crate::disk_quota::{DiskPressure, DiskQuota, DiskQuotaStats};
+use crate::ingest_pause::IngestPauses;
+use crate::load_shedding::LoadShedder;
+use crate::delivery;
+use crate::integrity;
 use crate::errors::BufferError;
+use crate::event_index::EventIndex;
 
 #[cfg(test)]
 mod tests;
 use crate::parsers::ParsedEvent;
+use crate::resource_monitor::DiskMetrics;
 #[cfg(feature = "persistent-storage")]
 use rusqlite::{Connection, OpenFlags, Result as SqliteResult};
+use std::collections::VecDeque;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::{mpsc, watch, Mutex};
 use tokio::time::{interval, Duration, Instant};
+#[cfg(feature = "persistent-storage")]
+use tokio_util::sync::{CancellationToken, DropGuard};
 use tracing::{info, warn, error, debug};
 
 const HIGH_WATER_MARK: f32 = 0.8; // 80% capacity triggers disk buffering
 const LOW_WATER_MARK: f32 = 0.3;  // 30% capacity clears backpressure
+const PRIORITY_LANE_CAPACITY: usize = 10_000; // alert-tagged events held ahead of the memory channel
+const EVENT_VERSION: i64 = 3; // format of stored event rows; bump when the meaning of a column changes (2: sealed columns are BLOBs, 3: fields and raw_data may be zstd BLOBs)
+const SCHEMA_VERSION: i64 = 4; // layout of the database, kept in PRAGMA user_version; add a migration to change it
+const INSERT_ROWS_PER_STATEMENT: usize = 100; // 9 parameters per row keeps statements under SQLite's oldest 999 limit
+const MAX_QUEUED_WRITE_BATCHES: usize = 4; // batches held in memory while disk writes keep failing before spills are refused
+
+/// One step of the buffer database layout; applied in order to databases below its version
+struct SchemaMigration {
+    version: i64,
+    description: &'static str,
+    apply: fn(&Connection) -> rusqlite::Result<()>,
+}
+
+const SCHEMA_MIGRATIONS: &[SchemaMigration] = &[
+    SchemaMigration { version: 1, description: "events and buffer_metadata tables", apply: EventBuffer::schema_v1_events },
+    SchemaMigration { version: 2, description: "per-row event_version", apply: EventBuffer::schema_v2_event_version },
+    SchemaMigration { version: 3, description: "events_quarantine table", apply: EventBuffer::schema_v3_quarantine },
+    SchemaMigration { version: 4, description: "dead_letters table", apply: EventBuffer::schema_v4_dead_letters },
+];
 
 #[derive(Clone)]
 pub struct EventBuffer {
     config: BufferConfig,
+    // Size and cleanup limits, replaceable by a configuration reload while the buffer is open
+    limits: Arc<parking_lot::RwLock<BufferLimits>>,
     
     // In-memory channel
     memory_sender: mpsc::Sender<ParsedEvent>,
     memory_receiver: Arc<Mutex<mpsc::Receiver<ParsedEvent>>>,
     
+    // Overflow spill list for short bursts, drained before disk
+    overflow: Arc<parking_lot::Mutex<BurstOverflow<ParsedEvent>>>,
+    
+    // Events tagged by alert rules, delivered ahead of everything else
+    priority: Arc<parking_lot::Mutex<VecDeque<ParsedEvent>>>,
+    
+    // Optional short-horizon duplicate filter applied before buffering
+    duplicate_filter: Option<DuplicateFilter>,
+    ingest_pauses: Option<Arc<IngestPauses>>,
+    load_shedder: Option<Arc<LoadShedder>>,
+    // Stamp event.hash on accepted events before they are stored
+    event_hashing: bool,
+    // Stamp event.id on accepted events, so every send of an event carries the same ID
+    event_ids: bool,
+    
+    // Optional local full-text index fed with every accepted event
+    event_index: Option<Arc<EventIndex>>,
+    
+    // Free-space policy for the volume holding the database
+    disk_quota: Option<Arc<DiskQuota>>,
+    
     // Persistent storage (conditional)
     #[cfg(feature = "persistent-storage")]
     db_connection: Arc<Mutex<Connection>>,
     
+    // Seals event payload columns at rest when buffer encryption is enabled
+    #[cfg(feature = "persistent-storage")]
+    cipher: Option<Arc<BufferCipher>>,
+    
+    // Spilled events waiting to be written to disk as one batch
+    #[cfg(feature = "persistent-storage")]
+    pending_writes: Arc<parking_lot::Mutex<VecDeque<ParsedEvent>>>,
+    // Stops the write coalescing task after a last write, on shutdown or once the last clone is dropped
+    #[cfg(feature = "persistent-storage")]
+    write_shutdown: CancellationToken,
+    #[cfg(feature = "persistent-storage")]
+    _write_shutdown_on_drop: Arc<DropGuard>,
+    
     // WAL mode management
     #[cfg(feature = "persistent-storage")]
     last_checkpoint: Arc<Mutex<Instant>>,
@@ -66,6 +137,23 @@ pub struct BufferStats {
     pub database_size_kb: i64,
     pub page_count: i64,
     pub auto_vacuum_enabled: bool,
+    
+    // Dead-letter queue: batches the transport gave up on
+    pub dead_letter_batches: u64,
+    pub dead_letter_events: u64,
+}
+
+/// A batch the transport could not deliver, held in the dead-letter table
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DeadLetterBatch {
+    pub id: i64,
+    pub failed_at: chrono::DateTime<chrono::Utc>,
+    pub attempts: u32,
+    pub error: String,
+    pub event_count: usize,
+    /// Left empty by `list_dead_letters`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<ParsedEvent>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -130,6 +218,14 @@ impl EventBuffer {
         // Setup persistent storage (conditional)
         #[cfg(feature = "persistent-storage")]
         let db_connection = Self::setup_database(&config).await?;
+        #[cfg(feature = "persistent-storage")]
+        let cipher = match config.encryption.enabled && config.persistent {
+            true => Some(Arc::new(BufferCipher::open(&config.encryption, &db_connection)?)),
+            false => None,
+        };
+        
+        #[cfg(feature = "persistent-storage")]
+        let (dead_letter_batches, dead_letter_events) = Self::count_dead_letters(&db_connection)?;
         
         // Setup backpressure signaling
         let (backpressure_sender, backpressure_receiver) = watch::channel(false);
@@ -151,18 +247,41 @@ impl EventBuffer {
             database_size_kb: 0,
             page_count: 0,
             auto_vacuum_enabled: matches!(config.auto_vacuum, SqliteAutoVacuum::Full | SqliteAutoVacuum::Incremental),
+            
+            dead_letter_batches,
+            dead_letter_events,
         }));
         
-        info!("📦 Event buffer initialized with memory capacity: {}, persistent: {}", 
-              config.max_events, config.persistent);
+        info!("📦 Event buffer initialized with memory capacity: {}, burst overflow: {}, persistent: {}", 
+              config.max_events, config.burst_capacity, config.persistent);
         
+        #[cfg(feature = "persistent-storage")]
+        let write_shutdown = CancellationToken::new();
         let buffer = Self {
             config: config.clone(),
+            limits: Arc::new(parking_lot::RwLock::new(config.limits())),
             memory_sender,
             memory_receiver: Arc::new(Mutex::new(memory_receiver)),
+            overflow: Arc::new(parking_lot::Mutex::new(BurstOverflow::new(config.burst_capacity))),
+            priority: Arc::new(parking_lot::Mutex::new(VecDeque::new())),
+            duplicate_filter: None,
+            ingest_pauses: None,
+            load_shedder: None,
+            event_hashing: false,
+            event_ids: false,
+            event_index: None,
+            disk_quota: None,
             #[cfg(feature = "persistent-storage")]
             db_connection: Arc::new(Mutex::new(db_connection)),
             #[cfg(feature = "persistent-storage")]
+            cipher,
+            #[cfg(feature = "persistent-storage")]
+            pending_writes: Arc::new(parking_lot::Mutex::new(VecDeque::new())),
+            #[cfg(feature = "persistent-storage")]
+            _write_shutdown_on_drop: Arc::new(write_shutdown.clone().drop_guard()),
+            #[cfg(feature = "persistent-storage")]
+            write_shutdown,
+            #[cfg(feature = "persistent-storage")]
             last_checkpoint: Arc::new(Mutex::new(Instant::now())),
             #[cfg(feature = "persistent-storage")]
             last_vacuum: Arc::new(Mutex::new(SystemTime::now())),
@@ -181,11 +300,17 @@ impl EventBuffer {
             buffer.start_wal_management_task().await;
         }
         
+        // A size limit can be configured later by a reload, so the cleanup task runs for every persistent buffer
         #[cfg(feature = "persistent-storage")]
-        if config.max_database_size_mb.is_some() {
+        if config.persistent {
             buffer.start_cleanup_management_task().await;
         }
         
+        #[cfg(feature = "persistent-storage")]
+        if config.persistent && config.write_batch_size > 1 {
+            buffer.start_write_coalescing_task().await;
+        }
+        
         Ok(buffer)
     }
     
@@ -252,6 +377,7 @@ impl EventBuffer {
         
         // Create schema
         Self::create_schema(&conn)?;
+        Self::apply_newer_event_policy(&conn, config.newer_events)?;
         
         info!("💾 Advanced SQLite buffer initialized at: {} (WAL: {}, Sync: {:?})", 
               db_path.display(), config.wal_mode, config.synchronous_mode);
@@ -372,9 +498,47 @@ impl EventBuffer {
         Ok(())
     }
     
+    /// Bring the database up to SCHEMA_VERSION, one migration per step and each in its own transaction, so an
+    /// upgrade interrupted halfway resumes from the last finished step with every buffered event still in place
     fn create_schema(conn: &Connection) -> Result<(), BufferError> {
-        // Create main events table with optimized schema
-        conn.execute(
+        let migration_error = |operation: String, e: rusqlite::Error| BufferError::PersistenceError {
+            operation,
+            database_path: "unknown".to_string(),
+            recoverable: false,
+            source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
+        };
+        let current: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| migration_error("read_schema_version".to_string(), e))?;
+        
+        if current > SCHEMA_VERSION {
+            // Columns and tables added by a newer agent are ignored; rows it wrote are handled by the newer_events policy
+            warn!("🗄️ Buffer schema v{} was written by a newer agent (this one knows v{}); using it as-is",
+                  current, SCHEMA_VERSION);
+            return Ok(());
+        }
+        
+        for migration in SCHEMA_MIGRATIONS.iter().filter(|m| m.version > current) {
+            let operation = format!("migrate_schema_v{}", migration.version);
+            let tx = conn.unchecked_transaction().map_err(|e| migration_error(operation.clone(), e))?;
+            (migration.apply)(&tx)
+                .and_then(|_| tx.pragma_update(None, "user_version", migration.version))
+                .and_then(|_| tx.commit())
+                .map_err(|e| migration_error(operation.clone(), e))?;
+            if current > 0 {
+                info!("🗄️ Buffer schema migrated to v{}: {}", migration.version, migration.description);
+            }
+        }
+        
+        debug!("✅ Database schema at v{}", SCHEMA_VERSION);
+        Ok(())
+    }
+    
+    // Buffers from before schema versioning report user_version 0 but may already have any of these tables, so
+    // every step is safe to run against a database that has it applied
+
+    fn schema_v1_events(conn: &Connection) -> rusqlite::Result<()> {
+        conn.execute_batch(
             "CREATE TABLE IF NOT EXISTS events (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 timestamp TEXT NOT NULL,
@@ -386,79 +550,265 @@ impl EventBuffer {
                 parser_name TEXT NOT NULL,
                 created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
                 size_bytes INTEGER NOT NULL DEFAULT 0
-            )",
-            [],
-        ).map_err(|e| BufferError::PersistenceError {
-            operation: "create_events_table".to_string(),
-            database_path: "unknown".to_string(),
-            recoverable: false,
-            source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-        })?;
-        
-        // Create indexes for efficient queries
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_events_created_at ON events(created_at)",
-            [],
-        ).map_err(|e| BufferError::PersistenceError {
-            operation: "create_created_at_index".to_string(),
-            database_path: "unknown".to_string(),
-            recoverable: false,
-            source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-        })?;
-        
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_events_source ON events(source)",
-            [],
-        ).map_err(|e| BufferError::PersistenceError {
-            operation: "create_source_index".to_string(),
-            database_path: "unknown".to_string(),
-            recoverable: false,
-            source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-        })?;
-        
-        // Create buffer metadata table for tracking statistics
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS buffer_metadata (
+            );
+            CREATE INDEX IF NOT EXISTS idx_events_created_at ON events(created_at);
+            CREATE INDEX IF NOT EXISTS idx_events_source ON events(source);
+            CREATE TABLE IF NOT EXISTS buffer_metadata (
                 key TEXT PRIMARY KEY,
                 value TEXT NOT NULL,
                 updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
-            )",
-            [],
-        ).map_err(|e| BufferError::PersistenceError {
-            operation: "create_metadata_table".to_string(),
-            database_path: "unknown".to_string(),
-            recoverable: false,
-            source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
+            );",
+        )
+    }
+    
+    fn schema_v2_event_version(conn: &Connection) -> rusqlite::Result<()> {
+        // Rows written before event versioning are version 1
+        let has_event_version = conn
+            .prepare("SELECT 1 FROM pragma_table_info('events') WHERE name = 'event_version'")?
+            .exists([])?;
+        if !has_event_version {
+            conn.execute("ALTER TABLE events ADD COLUMN event_version INTEGER NOT NULL DEFAULT 1", [])?;
+        }
+        Ok(())
+    }
+    
+    fn schema_v3_quarantine(conn: &Connection) -> rusqlite::Result<()> {
+        // Rows this agent cannot ship: the full row as JSON, including columns unknown to this version
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS events_quarantine (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                original_id INTEGER NOT NULL,
+                event_version INTEGER,
+                reason TEXT NOT NULL,
+                row_json TEXT NOT NULL,
+                quarantined_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+            );",
+        )
+    }
+    
+    fn schema_v4_dead_letters(conn: &Connection) -> rusqlite::Result<()> {
+        // Transport batches that failed for good, kept whole (events as one JSON array) until re-driven or exported
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS dead_letters (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                error TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                event_count INTEGER NOT NULL,
+                events_json TEXT NOT NULL,
+                failed_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+            );",
+        )
+    }
+    
+    /// Deal with rows written by a newer agent before anything is read from the buffer
+    fn apply_newer_event_policy(conn: &Connection, policy: NewerEventPolicy) -> Result<(), BufferError> {
+        let newer: Vec<i64> = conn
+            .prepare("SELECT id FROM events WHERE event_version > ?1 ORDER BY id")?
+            .query_map([EVENT_VERSION], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        if newer.is_empty() {
+            return Ok(());
+        }
+        
+        match policy {
+            NewerEventPolicy::Skip => {
+                warn!("⏭️ {} buffered events were written by a newer agent (format > v{}); leaving them on disk",
+                      newer.len(), EVENT_VERSION);
+            }
+            NewerEventPolicy::Quarantine => {
+                for id in &newer {
+                    Self::quarantine_row(conn, *id, "written by a newer agent version")?;
+                }
+                warn!("🧪 Quarantined {} buffered events written by a newer agent (format > v{})", newer.len(), EVENT_VERSION);
+            }
+            NewerEventPolicy::Fail => {
+                return Err(BufferError::CorruptionError {
+                    location: "events".to_string(),
+                    corruption_type: format!("events newer than format v{}", EVENT_VERSION),
+                    affected_records: Some(newer.len()),
+                    recovery_possible: true,
+                });
+            }
+        }
+        Ok(())
+    }
+    
+    /// Decode a stored row into (id, event); a row that cannot be decoded yields the reason instead
+    fn decode_row(row: &rusqlite::Row, cipher: Option<&BufferCipher>) -> rusqlite::Result<(i64, std::result::Result<ParsedEvent, String>)> {
+        let id: i64 = row.get(0)?;
+        Ok((id, Self::decode_event(row, cipher)))
+    }
+    
+    /// Decode an events row selected as (id, timestamp, source, level, message, fields, raw_data, parser_name, event_version)
+    pub(crate) fn decode_event(row: &rusqlite::Row, cipher: Option<&BufferCipher>) -> std::result::Result<ParsedEvent, String> {
+        let text = |index: usize| -> std::result::Result<String, String> {
+            row.get::<_, Option<String>>(index)
+                .map(Option::unwrap_or_default)
+                .map_err(|e| format!("unreadable column {}: {}", index, e))
+        };
+        let stored = |index: usize| row.get::<_, rusqlite::types::Value>(index).map_err(|e| format!("unreadable column {}: {}", index, e));
+        let event_version: i64 = row.get(8).map_err(|e| format!("unreadable event_version: {}", e))?;
+        let sealed = |index: usize, column: &str| stored(index).and_then(|value| buffer_encryption::open_column(cipher, column, value, event_version));
+        // fields and raw_data may be compressed BLOBs as well as plain or sealed values
+        let payload = |index: usize, column: &str| stored(index).and_then(|value| buffer_compression::load_column(cipher, column, value, event_version));
+        
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&text(1)?)
+            .map_err(|e| format!("invalid timestamp: {}", e))?
+            .with_timezone(&chrono::Utc);
+        let fields = match serde_json::from_str::<serde_json::Value>(&payload(5, "fields")?) {
+            Ok(serde_json::Value::Object(fields)) => fields.into_iter().collect(),
+            Ok(_) => return Err("fields is not a JSON object".to_string()),
+            Err(e) => return Err(format!("invalid fields JSON: {}", e)),
+        };
+        
+        Ok(ParsedEvent {
+            timestamp,
+            source: text(2)?,
+            level: Some(text(3)?).filter(|l| !l.is_empty()),
+            message: sealed(4, "message")?,
+            fields,
+            raw_data: payload(6, "raw_data")?.into(),
+            parser_name: text(7)?,
+        })
+    }
+    
+    /// Move a row to events_quarantine, keeping every column (including ones this version does not know)
+    fn quarantine_row(conn: &Connection, id: i64, reason: &str) -> Result<(), BufferError> {
+        use rusqlite::types::ValueRef;
+        
+        let (event_version, row_json) = conn.query_row("SELECT * FROM events WHERE id = ?1", [id], |row| {
+            let mut columns = serde_json::Map::new();
+            for (index, name) in row.as_ref().column_names().into_iter().enumerate() {
+                let value = match row.get_ref(index)? {
+                    ValueRef::Null => serde_json::Value::Null,
+                    ValueRef::Integer(n) => serde_json::json!(n),
+                    ValueRef::Real(f) => serde_json::json!(f),
+                    ValueRef::Text(t) => serde_json::json!(String::from_utf8_lossy(t)),
+                    ValueRef::Blob(b) => serde_json::json!(b.iter().map(|byte| format!("{:02x}", byte)).collect::<String>()),
+                };
+                columns.insert(name.to_string(), value);
+            }
+            let event_version = columns.get("event_version").and_then(serde_json::Value::as_i64);
+            Ok((event_version, serde_json::Value::Object(columns).to_string()))
         })?;
         
-        debug!("✅ Database schema created successfully");
+        conn.execute(
+            "INSERT INTO events_quarantine (original_id, event_version, reason, row_json) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![id, event_version, reason, row_json],
+        )?;
+        conn.execute("DELETE FROM events WHERE id = ?1", [id])?;
         Ok(())
     }
     
     pub async fn send(&self, event: ParsedEvent) -> Result<(), BufferError> {
+        self.accept(event, false).await
+    }
+    
+    /// Queue an event another agent sent through the aggregator, keeping the `event.hash` taken where it was collected
+    pub async fn send_forwarded(&self, event: ParsedEvent) -> Result<(), BufferError> {
+        self.accept(event, true).await
+    }
+    
+    async fn accept(&self, mut event: ParsedEvent, forwarded: bool) -> Result<(), BufferError> {
+        // Operator pauses discard events before they are counted or indexed
+        if let Some(pauses) = &self.ingest_pauses {
+            if pauses.is_paused(&event.source) {
+                pauses.record_discarded(&event.source);
+                return Ok(());
+            }
+        }
+        
+        // Under resource pressure only a sample of non-alert events is kept
+        if self.load_shedder.as_ref().is_some_and(|shedder| shedder.should_sample_out(&event)) {
+            return Ok(());
+        }
+        
+        // Drop events already shipped inside the duplicate window (e.g. re-read after a restart)
+        if let Some(filter) = &self.duplicate_filter {
+            if filter.is_duplicate(&event.source, &event.raw_data).await {
+                return Ok(());
+            }
+        }
+        
+        if self.event_hashing {
+            if forwarded {
+                integrity::stamp_forwarded_event_hash(&mut event);
+            } else {
+                integrity::stamp_event_hash(&mut event);
+            }
+        }
+        if self.event_ids {
+            delivery::stamp_event_id(&mut event);
+        }
+        
+        if let Some(index) = &self.event_index {
+            index.record(&event);
+        }
+        
+        // Alert-tagged events skip the queue; a flood beyond the lane's capacity takes the normal path
+        let event = if alert_rules::is_alert(&event) {
+            // The lane's lock must be released before awaiting, so the event comes back out of this block when full
+            let rejected = {
+                let mut priority = self.priority.lock();
+                if priority.len() < PRIORITY_LANE_CAPACITY {
+                    priority.push_back(event);
+                    None
+                } else {
+                    Some(event)
+                }
+            };
+            match rejected {
+                None => {
+                    debug!("🚨 Alert event queued on the priority lane");
+                    self.update_stats(|stats| stats.events_processed += 1).await;
+                    return Ok(());
+                }
+                Some(event) => {
+                    warn!("🚨 Priority lane full ({} events), queueing alert event normally", PRIORITY_LANE_CAPACITY);
+                    event
+                }
+            }
+        } else {
+            event
+        };
+        
+        // While a burst is being absorbed, queue behind it to preserve ordering
+        let event = {
+            let mut overflow = self.overflow.lock();
+            if overflow.is_active() {
+                match overflow.try_push(event) {
+                    Ok(()) => None,
+                    Err(event) => Some(event),
+                }
+            } else {
+                Some(event)
+            }
+        };
+        let Some(event) = event else {
+            self.update_stats(|stats| stats.events_processed += 1).await;
+            return Ok(());
+        };
+        if self.overflow.lock().is_active() {
+            return self.spill(event).await;
+        }
+        
         // Try to send to memory buffer first
-        match self.memory_sender.try_send(event.clone()) {
+        match self.memory_sender.try_send(event) {
             Ok(_) => {
                 debug!("📥 Event sent to memory buffer");
                 self.update_stats(|stats| stats.events_processed += 1).await;
                 Ok(())
             }
-            Err(mpsc::error::TrySendError::Full(_)) => {
-                // Memory buffer is full, try persistent storage
-                if self.config.persistent {
-                    debug!("💾 Memory buffer full, storing to disk");
-                    self.store_to_disk(event).await?;
-                    self.check_backpressure().await;
-                    Ok(())
-                } else {
-                    warn!("📦 Buffer full and persistence disabled, dropping event");
-                    self.update_stats(|stats| stats.events_dropped += 1).await;
-                    Err(BufferError::CapacityExceeded {
-                        current: self.config.max_events,
-                        max: self.config.max_events,
-                        buffer_type: "memory".to_string(),
-                        oldest_item_age: None,
-                    })
+            Err(mpsc::error::TrySendError::Full(event)) => {
+                // Memory channel is full, absorb the burst in the overflow list
+                let pushed = self.overflow.lock().try_push(event);
+                match pushed {
+                    Ok(()) => {
+                        debug!("🌊 Memory buffer full, event absorbed by burst overflow");
+                        self.update_stats(|stats| stats.events_processed += 1).await;
+                        Ok(())
+                    }
+                    Err(event) => self.spill(event).await,
                 }
             }
             Err(mpsc::error::TrySendError::Closed(_)) => {
@@ -473,66 +823,249 @@ impl EventBuffer {
         }
     }
     
+    /// Memory and burst overflow are both full: persist the event or drop it
+    async fn spill(&self, event: ParsedEvent) -> Result<(), BufferError> {
+        if let Some(quota) = self.disk_quota.as_ref().filter(|quota| quota.pressure() == DiskPressure::Stop) {
+            quota.record_dropped();
+            self.update_stats(|stats| stats.events_dropped += 1).await;
+            return Err(BufferError::CapacityExceeded {
+                current: self.config.max_events + self.config.burst_capacity,
+                max: self.config.max_events + self.config.burst_capacity,
+                buffer_type: "disk".to_string(),
+                oldest_item_age: None,
+            });
+        }
+        if self.config.persistent {
+            debug!("💾 Memory buffer full, storing to disk");
+            self.queue_disk_write(event).await?;
+            self.check_backpressure().await;
+            Ok(())
+        } else {
+            warn!("📦 Buffer full and persistence disabled, dropping event");
+            self.update_stats(|stats| stats.events_dropped += 1).await;
+            Err(BufferError::CapacityExceeded {
+                current: self.config.max_events + self.config.burst_capacity,
+                max: self.config.max_events + self.config.burst_capacity,
+                buffer_type: "memory".to_string(),
+                oldest_item_age: None,
+            })
+        }
+    }
+    
     async fn store_to_disk(&self, event: ParsedEvent) -> Result<(), BufferError> {
-        let db = self.db_connection.clone();
-        let event_clone = event.clone();
+        Self::write_events(self.db_connection.clone(), self.cipher.clone(), self.config.compression, &self.stats, vec![event]).await?;
+        Ok(())
+    }
+    
+    /// Queue an event for the next batched disk write, writing the batch straight away once it is full
+    async fn queue_disk_write(&self, event: ParsedEvent) -> Result<(), BufferError> {
+        self.update_stats(|stats| stats.events_processed += 1).await;
+        if self.config.write_batch_size <= 1 || self.write_shutdown.is_cancelled() {
+            return self.store_to_disk(event).await;
+        }
         
-        // Use blocking task for database operations
-        tokio::task::spawn_blocking(move || {
-            let conn = db.blocking_lock();
-            
-            let fields_json = serde_json::to_string(&event_clone.fields)
-                .map_err(|e| BufferError::SerializationError {
-                    data_type: "event_fields".to_string(),
-                    operation: "serialize".to_string(),
-                    size_bytes: None,
-                    source: Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
-                })?;
-            
-            // Calculate event size for statistics
-            let event_size = event_clone.raw_data.len() + fields_json.len() + 
-                           event_clone.message.len() + event_clone.source.len() +
-                           event_clone.parser_name.len();
-            
-            conn.execute(
-                "INSERT INTO events (timestamp, source, level, message, fields, raw_data, parser_name, size_bytes)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-                [
-                    &event_clone.timestamp.to_rfc3339() as &dyn rusqlite::ToSql,
-                    &event_clone.source,
-                    &event_clone.level.unwrap_or_default(),
-                    &event_clone.message,
-                    &fields_json,
-                    &event_clone.raw_data,
-                    &event_clone.parser_name,
-                    &(event_size as i64),
-                ],
-            ).map_err(|e| BufferError::PersistenceError {
+        let limit = self.config.write_batch_size * MAX_QUEUED_WRITE_BATCHES;
+        let full = {
+            let mut pending = self.pending_writes.lock();
+            if pending.len() >= limit {
+                None
+            } else {
+                pending.push_back(event);
+                Some(pending.len() >= self.config.write_batch_size)
+            }
+        };
+        match full {
+            // Earlier batches are still waiting for the disk, so this event cannot be kept
+            None => {
+                self.update_stats(|stats| stats.events_dropped += 1).await;
+                Err(BufferError::CapacityExceeded {
+                    current: limit,
+                    max: limit,
+                    buffer_type: "disk_write_queue".to_string(),
+                    oldest_item_age: None,
+                })
+            }
+            Some(true) => {
+                // A failed batch stays queued for the coalescing task to retry, so the event is still held
+                if let Err(e) = self.flush_pending_writes().await {
+                    debug!("💾 Spilled event queued until the disk write succeeds: {}", e);
+                }
+                Ok(())
+            }
+            Some(false) => Ok(()),
+        }
+    }
+    
+    /// Write all queued events in one transaction, returning how many were written
+    pub async fn flush_pending_writes(&self) -> Result<usize, BufferError> {
+        Self::flush_writes(self.db_connection.clone(), self.cipher.clone(), self.config.compression, &self.pending_writes, &self.stats).await
+    }
+    
+    async fn flush_writes(
+        db: Arc<Mutex<Connection>>,
+        cipher: Option<Arc<BufferCipher>>,
+        compress: bool,
+        pending: &parking_lot::Mutex<VecDeque<ParsedEvent>>,
+        stats: &Mutex<BufferStats>,
+    ) -> Result<usize, BufferError> {
+        let events: Vec<ParsedEvent> = pending.lock().drain(..).collect();
+        if events.is_empty() {
+            return Ok(0);
+        }
+        
+        match Self::write_batch(db, cipher, compress, stats, events).await {
+            Ok(written) => {
+                debug!("💾 Wrote batch of {} events to disk", written);
+                Ok(written)
+            }
+            Err((e, events)) => {
+                error!("💾 Batched write of {} events failed, keeping them queued for retry: {}", events.len(), e);
+                Self::requeue_writes(pending, events);
+                Err(e)
+            }
+        }
+    }
+    
+    /// Put events whose write failed back at the front of the queue, ahead of anything queued since
+    fn requeue_writes(pending: &parking_lot::Mutex<VecDeque<ParsedEvent>>, events: Vec<ParsedEvent>) {
+        let mut pending = pending.lock();
+        for event in events.into_iter().rev() {
+            pending.push_front(event);
+        }
+    }
+    
+    async fn write_events(
+        db: Arc<Mutex<Connection>>,
+        cipher: Option<Arc<BufferCipher>>,
+        compress: bool,
+        stats: &Mutex<BufferStats>,
+        events: Vec<ParsedEvent>,
+    ) -> Result<usize, BufferError> {
+        Self::write_batch(db, cipher, compress, stats, events).await.map_err(|(e, _)| e)
+    }
+    
+    /// Write events in one transaction, handing them back alongside the error when the write fails
+    async fn write_batch(
+        db: Arc<Mutex<Connection>>,
+        cipher: Option<Arc<BufferCipher>>,
+        compress: bool,
+        stats: &Mutex<BufferStats>,
+        events: Vec<ParsedEvent>,
+    ) -> Result<usize, (BufferError, Vec<ParsedEvent>)> {
+        if crate::chaos::disk_full() {
+            return Err((BufferError::PersistenceError {
                 operation: "insert_event".to_string(),
                 database_path: "unknown".to_string(),
                 recoverable: true,
-                source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-            })?;
-            
-            Ok::<(), BufferError>(())
+                source: Box::new(std::io::Error::new(std::io::ErrorKind::StorageFull, "chaos: simulated disk full")),
+            }, events));
+        }
+        
+        // Use blocking task for database operations
+        let events = Arc::new(events);
+        let task_events = events.clone();
+        let result = component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
+            let mut conn = db.blocking_lock();
+            Self::insert_events(&mut conn, cipher.as_deref(), compress, &task_events)?;
+            Ok::<usize, BufferError>(task_events.len())
         }).await
         .map_err(|e| BufferError::PersistenceError {
             operation: "database_task".to_string(),
             database_path: "unknown".to_string(),
             recoverable: true,
             source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-        })??;
-        
-        self.update_stats(|stats| {
-            stats.disk_events += 1;
-            stats.events_processed += 1;
-        }).await;
+        })
+        .and_then(|written| written);
         
+        match result {
+            Ok(written) => {
+                stats.lock().await.disk_events += written as i64;
+                Ok(written)
+            }
+            // The blocking task has finished with its copy by now, so this only clones if it panicked mid-flight
+            Err(e) => Err((e, Arc::try_unwrap(events).unwrap_or_else(|shared| (*shared).clone()))),
+        }
+    }
+    
+    /// Insert events in a single transaction, as multi-row INSERTs of up to INSERT_ROWS_PER_STATEMENT rows
+    fn insert_events(conn: &mut Connection, cipher: Option<&BufferCipher>, compress: bool, events: &[ParsedEvent]) -> Result<(), BufferError> {
+        let tx = conn.transaction().map_err(Self::insert_error)?;
+        Self::insert_event_rows(&tx, cipher, compress, events)?;
+        tx.commit().map_err(Self::insert_error)
+    }
+    
+    fn insert_error(e: rusqlite::Error) -> BufferError {
+        BufferError::PersistenceError {
+            operation: "insert_event".to_string(),
+            database_path: "unknown".to_string(),
+            recoverable: true,
+            source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
+        }
+    }
+    
+    /// Insert events inside the caller's transaction; with `compress`, large fields and raw_data are stored as zstd BLOBs
+    fn insert_event_rows(tx: &Connection, cipher: Option<&BufferCipher>, compress: bool, events: &[ParsedEvent]) -> Result<(), BufferError> {
+        for chunk in events.chunks(INSERT_ROWS_PER_STATEMENT) {
+            // Columns computed per event: timestamp, level, message, fields, raw_data, size
+            let mut rows = Vec::with_capacity(chunk.len());
+            for event in chunk {
+                let fields_json = serde_json::to_string(&event.fields)
+                    .map_err(|e| BufferError::SerializationError {
+                        data_type: "event_fields".to_string(),
+                        operation: "serialize".to_string(),
+                        size_bytes: None,
+                        source: Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
+                    })?;
+                
+                // Calculate event size for statistics
+                let event_size = event.raw_data.len() + fields_json.len() +
+                               event.message.len() + event.source.len() +
+                               event.parser_name.len();
+                
+                rows.push((
+                    event.timestamp.to_rfc3339(),
+                    event.level.as_deref().unwrap_or_default(),
+                    buffer_encryption::seal_column(cipher, "message", &event.message)?,
+                    buffer_compression::store_column(cipher, compress, "fields", &fields_json)?,
+                    buffer_compression::store_column(cipher, compress, "raw_data", &event.raw_data)?,
+                    event_size as i64,
+                ));
+            }
+            
+            let mut params: Vec<&dyn rusqlite::ToSql> = Vec::with_capacity(chunk.len() * 9);
+            for (event, (timestamp, level, message, fields, raw_data, size)) in chunk.iter().zip(&rows) {
+                params.extend_from_slice(&[
+                    timestamp as &dyn rusqlite::ToSql,
+                    &event.source,
+                    level,
+                    message,
+                    fields,
+                    raw_data,
+                    &event.parser_name,
+                    size,
+                    &EVENT_VERSION,
+                ]);
+            }
+            
+            let sql = format!(
+                "INSERT INTO events (timestamp, source, level, message, fields, raw_data, parser_name, size_bytes, event_version) VALUES {}",
+                vec!["(?, ?, ?, ?, ?, ?, ?, ?, ?)"; chunk.len()].join(", ")
+            );
+            tx.prepare_cached(&sql)
+                .and_then(|mut stmt| stmt.execute(rusqlite::params_from_iter(params)))
+                .map_err(Self::insert_error)?;
+        }
         Ok(())
     }
     
     pub async fn receive(&self) -> Option<ParsedEvent> {
-        // First try to get from memory buffer
+        // Alert events go out first
+        if let Some(event) = self.priority.lock().pop_front() {
+            debug!("📤 Event retrieved from priority lane");
+            return Some(event);
+        }
+        
+        // Then try to get from memory buffer
         if let Ok(mut receiver) = self.memory_receiver.try_lock() {
             if let Ok(event) = receiver.try_recv() {
                 debug!("📤 Event retrieved from memory buffer");
@@ -540,88 +1073,141 @@ impl EventBuffer {
             }
         }
         
-        // If memory buffer is empty, try to load from disk
+        // Then drain any absorbed burst
+        if let Some(event) = self.overflow.lock().pop() {
+            debug!("📤 Event retrieved from burst overflow");
+            return Some(event);
+        }
+        
+        // If memory buffer is empty, try to load from disk, then from writes not yet batched to it
         if self.config.persistent {
-            self.load_from_disk().await.unwrap_or(None)
+            match self.load_from_disk().await.unwrap_or(None) {
+                Some(event) => Some(event),
+                None => self.pending_writes.lock().pop_front(),
+            }
         } else {
             None
         }
     }
     
-    async fn load_from_disk(&self) -> Result<Option<ParsedEvent>, BufferError> {
-        let db = self.db_connection.clone();
+    /// Take up to `max_events` events in the order `receive` would return them, reading disk in one query
+    pub async fn receive_batch(&self, max_events: usize) -> Vec<ParsedEvent> {
+        let mut events: Vec<ParsedEvent> = {
+            let mut priority = self.priority.lock();
+            let take = priority.len().min(max_events);
+            priority.drain(..take).collect()
+        };
         
-        tokio::task::spawn_blocking(move || {
-            let conn = db.blocking_lock();
-            
-            let mut stmt = conn.prepare(
-                "SELECT id, timestamp, source, level, message, fields, raw_data, parser_name 
-                 FROM events ORDER BY created_at LIMIT 1"
-            ).map_err(|e| BufferError::PersistenceError {
-                operation: "prepare_statement".to_string(),
-                database_path: "unknown".to_string(),
-                recoverable: true,
-                source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-            })?;
-            
-            let mut rows = stmt.query_map([], |row| {
-                let id: i64 = row.get(0)?;
-                let timestamp_str: String = row.get(1)?;
-                let fields_json: String = row.get(5)?;
-                
-                let timestamp = chrono::DateTime::parse_from_rfc3339(&timestamp_str)
-                    .map_err(|e| rusqlite::Error::InvalidColumnType(
-                        1, "timestamp".to_string(), rusqlite::types::Type::Text
-                    ))?
-                    .with_timezone(&chrono::Utc);
-                
-                let fields: std::collections::HashMap<String, serde_json::Value> = 
-                    serde_json::from_str(&fields_json)
-                        .map_err(|e| rusqlite::Error::InvalidColumnType(
-                            5, "fields".to_string(), rusqlite::types::Type::Text
-                        ))?;
-                
-                Ok((id, ParsedEvent {
-                    timestamp,
-                    source: row.get(2)?,
-                    level: {
-                        let level: String = row.get(3)?;
-                        if level.is_empty() { None } else { Some(level) }
-                    },
-                    message: row.get(4)?,
-                    fields,
-                    raw_data: row.get(6)?,
-                    parser_name: row.get(7)?,
-                }))
-            }).map_err(|e| BufferError::PersistenceError {
-                operation: "query_events".to_string(),
-                database_path: "unknown".to_string(),
-                recoverable: true,
-                source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-            })?;
-            
-            if let Some(result) = rows.next() {
-                let (id, event) = result.map_err(|e| BufferError::PersistenceError {
-                    operation: "parse_row".to_string(),
+        if events.len() < max_events {
+            if let Ok(mut receiver) = self.memory_receiver.try_lock() {
+                while events.len() < max_events {
+                    match receiver.try_recv() {
+                        Ok(event) => events.push(event),
+                        Err(_) => break,
+                    }
+                }
+            }
+        }
+        
+        {
+            let mut overflow = self.overflow.lock();
+            while events.len() < max_events {
+                match overflow.pop() {
+                    Some(event) => events.push(event),
+                    None => break,
+                }
+            }
+        }
+        
+        if self.config.persistent && events.len() < max_events {
+            match self.load_batch_from_disk(max_events - events.len()).await {
+                Ok(persisted) => events.extend(persisted),
+                Err(e) => warn!("💾 Failed to load buffered events from disk: {}", e),
+            }
+            let mut pending = self.pending_writes.lock();
+            let take = pending.len().min(max_events - events.len());
+            events.extend(pending.drain(..take));
+        }
+        
+        debug!("📤 Retrieved batch of {} events", events.len());
+        events
+    }
+    
+    async fn load_from_disk(&self) -> Result<Option<ParsedEvent>, BufferError> {
+        Ok(self.load_batch_from_disk(1).await?.pop())
+    }
+    
+    /// Remove and return up to `limit` of the oldest persisted events with one query and one transaction
+    async fn load_batch_from_disk(&self, limit: usize) -> Result<Vec<ParsedEvent>, BufferError> {
+        let db = self.db_connection.clone();
+        let cipher = self.cipher.clone();
+        let policy = self.config.newer_events;
+        
+        component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
+            let mut conn = db.blocking_lock();
+            let tx = conn.transaction()?;
+            let mut events = Vec::new();
+            
+            {
+                let mut stmt = tx.prepare(
+                    "SELECT id, timestamp, source, level, message, fields, raw_data, parser_name, event_version
+                     FROM events WHERE event_version <= ?1 ORDER BY created_at, id LIMIT ?2"
+                ).map_err(|e| BufferError::PersistenceError {
+                    operation: "prepare_statement".to_string(),
                     database_path: "unknown".to_string(),
-                    recoverable: false,
-                    source: Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
+                    recoverable: true,
+                    source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
                 })?;
+                let mut delete = tx.prepare("DELETE FROM events WHERE id = ?1")?;
                 
-                // Delete the event from the database
-                conn.execute("DELETE FROM events WHERE id = ?1", [id])
-                    .map_err(|e| BufferError::PersistenceError {
-                        operation: "delete_event".to_string(),
-                        database_path: "unknown".to_string(),
-                        recoverable: true,
-                        source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-                    })?;
-                
-                debug!("💾 Event loaded from disk and removed");
-                Ok(Some(event))
-            } else {
-                Ok(None)
+                // Rows that cannot be decoded are set aside rather than blocking the rows behind them
+                while events.is_empty() {
+                    let rows = stmt.query_map(rusqlite::params![EVENT_VERSION, limit as i64], |row| Self::decode_row(row, cipher.as_deref()))
+                        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                        .map_err(|e| BufferError::PersistenceError {
+                            operation: "parse_row".to_string(),
+                            database_path: "unknown".to_string(),
+                            recoverable: false,
+                            source: Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
+                        })?;
+                    if rows.is_empty() {
+                        break;
+                    }
+                    
+                    for (id, event) in rows {
+                        match event {
+                            Ok(event) => {
+                                delete.execute([id]).map_err(|e| BufferError::PersistenceError {
+                                    operation: "delete_event".to_string(),
+                                    database_path: "unknown".to_string(),
+                                    recoverable: true,
+                                    source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
+                                })?;
+                                events.push(event);
+                            }
+                            Err(reason) if policy == NewerEventPolicy::Fail => {
+                                return Err(BufferError::CorruptionError {
+                                    location: format!("events row {}", id),
+                                    corruption_type: reason,
+                                    affected_records: Some(1),
+                                    recovery_possible: true,
+                                });
+                            }
+                            Err(reason) => {
+                                // An undecodable row cannot be left in place, so skip also quarantines it
+                                warn!("🧪 Quarantining undecodable buffered event {}: {}", id, reason);
+                                Self::quarantine_row(&tx, id, &reason)?;
+                            }
+                        }
+                    }
+                }
             }
+            
+            tx.commit()?;
+            if !events.is_empty() {
+                debug!("💾 {} events loaded from disk and removed", events.len());
+            }
+            Ok(events)
         }).await
         .map_err(|e| BufferError::PersistenceError {
             operation: "database_task".to_string(),
@@ -650,7 +1236,7 @@ impl EventBuffer {
         
         debug!("🔄 Performing WAL checkpoint...");
         
-        let result = tokio::task::spawn_blocking(move || {
+        let result = component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             // Perform PRAGMA wal_checkpoint(TRUNCATE) for maximum WAL cleanup
@@ -723,7 +1309,7 @@ impl EventBuffer {
         
         debug!("🧹 Performing incremental vacuum...");
         
-        tokio::task::spawn_blocking(move || {
+        component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             // Get current page count before vacuum
@@ -773,7 +1359,7 @@ impl EventBuffer {
     async fn update_wal_stats(&self) -> Result<(), BufferError> {
         let db = self.db_connection.clone();
         
-        let wal_stats = tokio::task::spawn_blocking(move || {
+        let wal_stats = component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             // Get WAL status
@@ -843,24 +1429,31 @@ impl EventBuffer {
     }
     
     async fn check_backpressure(&self) {
-        let stats = self.stats.lock().await;
+        let mut stats = self.stats.lock().await;
         let memory_usage = stats.memory_events as f32 / self.config.max_events as f32;
         let disk_events = stats.disk_events;
+        let max_size_mb = self.limits.read().max_size_mb;
+        let disk_pressure = self.disk_quota.as_ref().map_or(DiskPressure::Normal, |quota| quota.pressure());
         
         let should_activate_backpressure = memory_usage > HIGH_WATER_MARK || 
-                                          disk_events > self.config.max_size_mb as i64 * 1000;
+                                          disk_events > max_size_mb as i64 * 1000 ||
+                                          disk_pressure != DiskPressure::Normal;
         
         let should_clear_backpressure = memory_usage < LOW_WATER_MARK && 
-                                       disk_events < (self.config.max_size_mb as i64 * 1000) / 2;
+                                       disk_events < (max_size_mb as i64 * 1000) / 2 &&
+                                       disk_pressure == DiskPressure::Normal;
         
-        if should_activate_backpressure && !stats.backpressure_active {
-            warn!("🚨 Activating backpressure - memory: {:.1}%, disk events: {}", 
-                  memory_usage * 100.0, disk_events);
+        let active = *self.backpressure_receiver.borrow();
+        if should_activate_backpressure && !active {
+            warn!("🚨 Activating backpressure - memory: {:.1}%, disk events: {}, disk space: {:?}", 
+                  memory_usage * 100.0, disk_events, disk_pressure);
             let _ = self.backpressure_sender.send(true);
-        } else if should_clear_backpressure && stats.backpressure_active {
+            stats.backpressure_active = true;
+        } else if should_clear_backpressure && active {
             info!("✅ Clearing backpressure - memory: {:.1}%, disk events: {}", 
                   memory_usage * 100.0, disk_events);
             let _ = self.backpressure_sender.send(false);
+            stats.backpressure_active = false;
         }
     }
     
@@ -868,7 +1461,7 @@ impl EventBuffer {
         let stats = self.stats.clone();
         let flush_interval = self.config.flush_interval;
         
-        tokio::spawn(async move {
+        component_usage::spawn(component_usage::BUFFER_WRITER, async move {
             let mut flush_timer = interval(Duration::from_secs(flush_interval));
             
             loop {
@@ -889,12 +1482,53 @@ impl EventBuffer {
         });
     }
     
+    /// Write spilled events on a short interval so a partial batch never waits long in memory
+    #[cfg(feature = "persistent-storage")]
+    async fn start_write_coalescing_task(&self) {
+        let db = self.db_connection.clone();
+        let cipher = self.cipher.clone();
+        let compress = self.config.compression;
+        let pending = self.pending_writes.clone();
+        let stats = self.stats.clone();
+        let shutdown = self.write_shutdown.clone();
+        let batch_interval = Duration::from_millis(self.config.write_batch_interval_ms.max(1));
+        
+        component_usage::spawn(component_usage::BUFFER_WRITER, async move {
+            let mut write_timer = interval(batch_interval);
+            
+            loop {
+                tokio::select! {
+                    _ = write_timer.tick() => {
+                        // Failed batches are logged and stay queued for the next tick
+                        let _ = Self::flush_writes(db.clone(), cipher.clone(), compress, &pending, &stats).await;
+                    }
+                    _ = shutdown.cancelled() => {
+                        if let Err(e) = Self::flush_writes(db.clone(), cipher.clone(), compress, &pending, &stats).await {
+                            error!("❌ {} spilled events could not be written before the buffer closed: {}", pending.lock().len(), e);
+                        }
+                        debug!("💾 Write coalescing stopped");
+                        break;
+                    }
+                }
+            }
+        });
+    }
+    
+    /// Write the events still queued for a batched disk write and stop the coalescing task; spills after this are
+    /// written straight away
+    #[cfg(feature = "persistent-storage")]
+    pub async fn shutdown(&self) -> Result<(), BufferError> {
+        self.write_shutdown.cancel();
+        self.flush_pending_writes().await.map(|_| ())
+    }
+
+    
     async fn start_monitoring_task(&self) {
         let memory_receiver = self.memory_receiver.clone();
         let stats = self.stats.clone();
         let max_events = self.config.max_events;
         
-        tokio::spawn(async move {
+        component_usage::spawn(component_usage::BUFFER_WRITER, async move {
             let mut monitor_timer = interval(Duration::from_secs(1));
             
             loop {
@@ -921,7 +1555,7 @@ impl EventBuffer {
         let stats = self.stats.clone();
         let config = self.config.clone();
         
-        tokio::spawn(async move {
+        component_usage::spawn(component_usage::BUFFER_WRITER, async move {
             let mut wal_timer = interval(Duration::from_secs(30)); // Check every 30 seconds
             
             loop {
@@ -974,7 +1608,7 @@ impl EventBuffer {
     async fn perform_checkpoint(db_connection: &Arc<Mutex<Connection>>) -> Result<(), BufferError> {
         let db = db_connection.clone();
         
-        tokio::task::spawn_blocking(move || {
+        component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             // Perform PRAGMA wal_checkpoint(TRUNCATE) for maximum WAL cleanup
@@ -1019,7 +1653,7 @@ impl EventBuffer {
     async fn perform_incremental_vacuum(db_connection: &Arc<Mutex<Connection>>) -> Result<(), BufferError> {
         let db = db_connection.clone();
         
-        tokio::task::spawn_blocking(move || {
+        component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             // Get current page count before vacuum
@@ -1064,6 +1698,69 @@ impl EventBuffer {
         update_fn(&mut stats);
     }
     
+    /// Burst absorption metrics for the in-memory overflow list
+    pub fn get_burst_stats(&self) -> BurstStats {
+        self.overflow.lock().stats()
+    }
+    
+    /// Drop events whose content was already buffered inside the filter's window
+    pub fn set_duplicate_filter(&mut self, filter: DuplicateFilter) {
+        self.duplicate_filter = Some(filter);
+    }
+    
+    /// Discard events from sources an operator has paused
+    pub fn set_ingest_pauses(&mut self, pauses: Arc<IngestPauses>) {
+        self.ingest_pauses = Some(pauses);
+    }
+    
+    /// Sample events while load shedding asks for it
+    pub fn set_load_shedder(&mut self, shedder: Arc<LoadShedder>) {
+        self.load_shedder = Some(shedder);
+    }
+    
+    /// Record each event's raw-data digest before it is stored, for chain of custody
+    pub fn set_event_hashing(&mut self, enabled: bool) {
+        self.event_hashing = enabled;
+    }
+    
+    /// Give each event a ULID before it is stored, for acknowledgement tracking and server-side deduplication
+    pub fn set_event_ids(&mut self, enabled: bool) {
+        self.event_ids = enabled;
+    }
+    
+    /// Throttle and stop disk writes by the free space left on the database's volume
+    pub fn set_disk_quota(&mut self, quota: Arc<DiskQuota>) {
+        self.disk_quota = Some(quota);
+    }
+    
+    /// Apply a resource monitor disk sample to the disk quota, raising or clearing backpressure on a change
+    pub async fn update_disk_pressure(&self, disks: &[DiskMetrics]) {
+        if self.disk_quota.as_ref().and_then(|quota| quota.update(disks)).is_some() {
+            self.check_backpressure().await;
+        }
+    }
+    
+    pub fn get_disk_quota_stats(&self) -> Option<DiskQuotaStats> {
+        self.disk_quota.as_ref().map(|quota| quota.stats())
+    }
+    
+    /// Queue every accepted event for the local search index
+    pub fn set_event_index(&mut self, index: Arc<EventIndex>) {
+        self.event_index = Some(index);
+    }
+    
+    /// Size and cleanup limits currently enforced
+    pub fn limits(&self) -> BufferLimits {
+        self.limits.read().clone()
+    }
+    
+    /// Replace the size and cleanup limits; background cleanup and backpressure pick them up on their next check
+    pub fn set_limits(&self, limits: BufferLimits) {
+        info!("📦 Buffer limits updated: max_size_mb={}, max_database_size_mb={:?}, min_retention_hours={}",
+              limits.max_size_mb, limits.max_database_size_mb, limits.min_retention_hours);
+        *self.limits.write() = limits;
+    }
+    
     pub fn get_backpressure_receiver(&self) -> watch::Receiver<bool> {
         self.backpressure_receiver.clone()
     }
@@ -1119,7 +1816,7 @@ impl EventBuffer {
     pub async fn get_database_size_info(&self) -> Result<DatabaseSizeInfo, BufferError> {
         let db = self.db_connection.clone();
         
-        tokio::task::spawn_blocking(move || {
+        component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             let page_count: i64 = conn.pragma_query_value(None, "page_count", |row| row.get(0))?;
@@ -1171,10 +1868,11 @@ impl EventBuffer {
     async fn start_cleanup_management_task(&self) {
         let db_connection = self.db_connection.clone();
         let last_cleanup = self.last_cleanup.clone();
+        let limits = self.limits.clone();
         let config = self.config.clone();
         let cleanup_interval_sec = config.cleanup_interval_sec;
         
-        tokio::spawn(async move {
+        component_usage::spawn(component_usage::BUFFER_WRITER, async move {
             let mut cleanup_timer = interval(Duration::from_secs(config.cleanup_interval_sec));
             
             loop {
@@ -1186,7 +1884,9 @@ impl EventBuffer {
                     last_cleanup_time.elapsed().unwrap_or_default() >= Duration::from_secs(config.cleanup_interval_sec)
                 };
                 
-                if should_cleanup {
+                // Limits as of this run; nothing to enforce while no size limit is configured
+                let config = config.with_limits(&limits.read());
+                if should_cleanup && config.max_database_size_mb.is_some() {
                     if let Err(e) = Self::perform_automatic_cleanup(&db_connection, &config).await {
                         warn!("⚠️  Automatic cleanup failed: {}", e);
                     } else {
@@ -1206,7 +1906,7 @@ impl EventBuffer {
         let db = db_connection.clone();
         let config_clone = config.clone();
         
-        let cleanup_result = tokio::task::spawn_blocking(move || {
+        let cleanup_result = component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             // Get current database size
@@ -1510,7 +2210,8 @@ impl EventBuffer {
     /// Force cleanup operation (useful for testing or manual maintenance)
     #[cfg(feature = "persistent-storage")]
     pub async fn force_cleanup(&self) -> Result<usize, BufferError> {
-        if self.config.max_database_size_mb.is_none() {
+        let config = self.config.with_limits(&self.limits.read());
+        if config.max_database_size_mb.is_none() {
             return Err(BufferError::PersistenceError {
                 operation: "force_cleanup".to_string(),
                 database_path: "unknown".to_string(),
@@ -1524,7 +2225,7 @@ impl EventBuffer {
         
         info!("🧹 Forcing database cleanup...");
         
-        let result = Self::perform_automatic_cleanup(&self.db_connection, &self.config).await?;
+        let result = Self::perform_automatic_cleanup(&self.db_connection, &config).await?;
         
         // Update cleanup time
         {
@@ -1540,9 +2241,9 @@ impl EventBuffer {
     #[cfg(feature = "persistent-storage")]
     pub async fn apply_retention_policies(&self) -> Result<usize, BufferError> {
         let db = self.db_connection.clone();
-        let config = self.config.clone();
+        let config = self.config.with_limits(&self.limits.read());
         
-        tokio::task::spawn_blocking(move || {
+        component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             let min_retention_seconds = config.min_retention_hours * 3600;
@@ -1606,7 +2307,7 @@ impl EventBuffer {
     pub async fn perform_full_vacuum_if_needed(&self) -> Result<bool, BufferError> {
         let db = self.db_connection.clone();
         
-        let vacuum_performed = tokio::task::spawn_blocking(move || {
+        let vacuum_performed = component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             // Get database fragmentation information
@@ -1690,7 +2391,7 @@ impl EventBuffer {
     pub async fn analyze_database_optimization(&self) -> Result<DatabaseOptimizationReport, BufferError> {
         let db = self.db_connection.clone();
         
-        tokio::task::spawn_blocking(move || {
+        component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             // Run ANALYZE to update statistics
@@ -1789,7 +2490,7 @@ impl EventBuffer {
     pub async fn get_cleanup_stats(&self) -> Result<CleanupStats, BufferError> {
         let db = self.db_connection.clone();
         
-        let stats = tokio::task::spawn_blocking(move || {
+        let stats = component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
             let conn = db.blocking_lock();
             
             // Get total event count
@@ -1831,9 +2532,315 @@ impl EventBuffer {
         Ok(stats)
     }
     
+    /// Take every event held in memory (priority lane, channel and burst overflow), leaving disk untouched
+    pub async fn drain_pending(&self) -> Vec<ParsedEvent> {
+        let mut events: Vec<ParsedEvent> = self.priority.lock().drain(..).collect();
+        {
+            let mut receiver = self.memory_receiver.lock().await;
+            while let Ok(event) = receiver.try_recv() {
+                events.push(event);
+            }
+        }
+        let mut overflow = self.overflow.lock();
+        while let Some(event) = overflow.pop() {
+            events.push(event);
+        }
+        events
+    }
+    
+    /// Remove and return up to `limit` persisted events whose level or source matches, oldest first
+    pub async fn take_persisted_matching(&self, levels: &[String], sources: &[String], limit: usize) -> Result<Vec<ParsedEvent>, BufferError> {
+        if !self.config.persistent || limit == 0 || (levels.is_empty() && sources.is_empty()) {
+            return Ok(Vec::new());
+        }
+        self.flush_pending_writes().await?;
+        
+        let db = self.db_connection.clone();
+        let cipher = self.cipher.clone();
+        let levels: Vec<String> = levels.iter().map(|l| l.to_lowercase()).collect();
+        let sources = sources.to_vec();
+        
+        component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || {
+            let conn = db.blocking_lock();
+            let placeholders = |count: usize, offset: usize| {
+                (1..=count).map(|i| format!("?{}", i + offset)).collect::<Vec<_>>().join(", ")
+            };
+            let query = format!(
+                "SELECT id, timestamp, source, level, message, fields, raw_data, parser_name, event_version FROM events
+                 WHERE (lower(level) IN ({}) OR source IN ({})) AND event_version <= {} ORDER BY id LIMIT {}",
+                placeholders(levels.len(), 0), placeholders(sources.len(), levels.len()), EVENT_VERSION, limit
+            );
+            
+            let mut stmt = conn.prepare(&query)?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(levels.iter().chain(sources.iter())), |row| {
+                Self::decode_row(row, cipher.as_deref())
+            })?.collect::<Result<Vec<_>, _>>()?;
+            
+            // Undecodable rows stay behind for the regular path to quarantine
+            let mut delete = conn.prepare("DELETE FROM events WHERE id = ?1")?;
+            let mut events = Vec::with_capacity(rows.len());
+            for (id, event) in rows {
+                if let Ok(event) = event {
+                    delete.execute([id])?;
+                    events.push(event);
+                }
+            }
+            Ok::<_, BufferError>(events)
+        }).await
+        .map_err(|e| BufferError::PersistenceError {
+            operation: "database_task".to_string(),
+            database_path: self.config.persistence_path.clone(),
+            recoverable: true,
+            source: Box::new(e),
+        })?
+    }
+    
+    /// Write events to disk regardless of memory headroom, returning how many were persisted
+    pub async fn persist_events(&self, events: Vec<ParsedEvent>) -> Result<usize, BufferError> {
+        if !self.config.persistent {
+            let dropped = events.len();
+            if dropped > 0 {
+                warn!("📦 Persistence disabled, dropping {} unsent events", dropped);
+                self.update_stats(|stats| stats.events_dropped += dropped as u64).await;
+            }
+            return Ok(0);
+        }
+        
+        // Queued batch first so the rows keep their order, then everything in one transaction
+        let mut batch: Vec<ParsedEvent> = self.pending_writes.lock().drain(..).collect();
+        let queued = batch.len();
+        batch.extend(events);
+        match Self::write_batch(self.db_connection.clone(), self.cipher.clone(), self.config.compression, &self.stats, batch).await {
+            Ok(written) => Ok(written - queued),
+            Err((e, mut batch)) => {
+                // The caller's own events fail with the error; only those taken from the queue go back on it
+                batch.truncate(queued);
+                Self::requeue_writes(&self.pending_writes, batch);
+                Err(e)
+            }
+        }
+    }
+    
+    /// Set aside a batch the transport gave up on, returning how many events were kept
+    pub async fn dead_letter_batch(&self, events: Vec<ParsedEvent>, error: &str, attempts: u32) -> Result<usize, BufferError> {
+        if events.is_empty() {
+            return Ok(0);
+        }
+        let count = events.len();
+        let events_json = serde_json::to_string(&events)
+            .map_err(|e| BufferError::SerializationError {
+                data_type: "dead_letter_events".to_string(),
+                operation: "serialize".to_string(),
+                size_bytes: None,
+                source: Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
+            })?;
+        let events_json = buffer_encryption::seal_column(self.cipher.as_deref(), "dead_letter_events", &events_json)?;
+        let error = error.to_string();
+        
+        let counts = self.with_connection(move |conn| {
+            conn.execute(
+                "INSERT INTO dead_letters (error, attempts, event_count, events_json) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![error, attempts, count as i64, events_json],
+            )?;
+            Self::count_dead_letters(conn)
+        }).await?;
+        self.set_dead_letter_stats(counts).await;
+        
+        warn!("☠️ Dead-lettered a batch of {} events after {} attempts", count, attempts);
+        Ok(count)
+    }
+    
+    /// Dead-lettered batches, oldest first, without their events
+    pub async fn list_dead_letters(&self, limit: usize) -> Result<Vec<DeadLetterBatch>, BufferError> {
+        self.with_connection(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, failed_at, attempts, error, event_count FROM dead_letters ORDER BY id LIMIT ?1",
+            )?;
+            let batches = stmt.query_map([limit as i64], Self::dead_letter_summary)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(batches)
+        }).await
+    }
+    
+    /// One dead-lettered batch with its events
+    pub async fn inspect_dead_letter(&self, id: i64) -> Result<Option<DeadLetterBatch>, BufferError> {
+        let cipher = self.cipher.clone();
+        let batch = self.with_connection(move |conn| {
+            Ok(Self::read_dead_letters(conn, cipher.as_deref(), &[id])?.pop())
+        }).await?;
+        
+        match batch {
+            Some((_, Some(reason))) => Err(BufferError::CorruptionError {
+                location: "dead_letters".to_string(),
+                corruption_type: reason,
+                affected_records: Some(1),
+                recovery_possible: false,
+            }),
+            Some((batch, None)) => Ok(Some(batch)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Move dead-lettered batches (all of them when `ids` is empty) back into the buffer, queued behind
+    /// the events already on disk; returns how many events will be sent again
+    pub async fn redrive_dead_letters(&self, ids: &[i64]) -> Result<usize, BufferError> {
+        self.flush_pending_writes().await?;
+        
+        let cipher = self.cipher.clone();
+        let compress = self.config.compression;
+        let ids = ids.to_vec();
+        let (redriven, counts) = self.with_connection(move |conn| {
+            let tx = conn.transaction()?;
+            let mut redriven = 0;
+            for (batch, failure) in Self::read_dead_letters(&tx, cipher.as_deref(), &ids)? {
+                if let Some(reason) = failure {
+                    warn!("⚠️ Leaving dead-letter batch {} in place: {}", batch.id, reason);
+                    continue;
+                }
+                Self::insert_event_rows(&tx, cipher.as_deref(), compress, &batch.events)?;
+                tx.execute("DELETE FROM dead_letters WHERE id = ?1", [batch.id])?;
+                redriven += batch.events.len();
+            }
+            tx.commit()?;
+            Ok((redriven, Self::count_dead_letters(conn)?))
+        }).await?;
+        
+        self.update_stats(|stats| stats.disk_events += redriven as i64).await;
+        self.set_dead_letter_stats(counts).await;
+        if redriven > 0 {
+            info!("🔁 Re-drove {} dead-lettered events into the buffer", redriven);
+        }
+        Ok(redriven)
+    }
+    
+    /// Write dead-lettered batches (all of them when `ids` is empty) as NDJSON, one batch per line;
+    /// returns how many batches were written
+    pub async fn export_dead_letters<W: std::io::Write>(&self, ids: &[i64], mut writer: W) -> Result<usize, BufferError> {
+        let cipher = self.cipher.clone();
+        let ids = ids.to_vec();
+        let batches = self.with_connection(move |conn| Self::read_dead_letters(conn, cipher.as_deref(), &ids)).await?;
+        
+        let write_error = |e: std::io::Error| BufferError::PersistenceError {
+            operation: "export_dead_letters".to_string(),
+            database_path: self.config.persistence_path.clone(),
+            recoverable: true,
+            source: Box::new(e),
+        };
+        let mut exported = 0;
+        for (batch, failure) in batches {
+            if let Some(reason) = failure {
+                warn!("⚠️ Skipping dead-letter batch {} in export: {}", batch.id, reason);
+                continue;
+            }
+            serde_json::to_writer(&mut writer, &batch).map_err(|e| write_error(e.into()))?;
+            writer.write_all(b"\n").map_err(write_error)?;
+            exported += 1;
+        }
+        writer.flush().map_err(write_error)?;
+        Ok(exported)
+    }
+    
+    /// Delete dead-lettered batches (all of them when `ids` is empty); returns how many were removed
+    pub async fn discard_dead_letters(&self, ids: &[i64]) -> Result<usize, BufferError> {
+        let ids = ids.to_vec();
+        let (discarded, counts) = self.with_connection(move |conn| {
+            let discarded = if ids.is_empty() {
+                conn.execute("DELETE FROM dead_letters", [])?
+            } else {
+                conn.execute(
+                    &format!("DELETE FROM dead_letters WHERE id IN ({})", vec!["?"; ids.len()].join(", ")),
+                    rusqlite::params_from_iter(&ids),
+                )?
+            };
+            Ok((discarded, Self::count_dead_letters(conn)?))
+        }).await?;
+        self.set_dead_letter_stats(counts).await;
+        Ok(discarded)
+    }
+    
+    fn count_dead_letters(conn: &Connection) -> Result<(u64, u64), BufferError> {
+        Ok(conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(event_count), 0) FROM dead_letters",
+            [],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+        )?)
+    }
+    
+    async fn set_dead_letter_stats(&self, (batches, events): (u64, u64)) {
+        self.update_stats(|stats| {
+            stats.dead_letter_batches = batches;
+            stats.dead_letter_events = events;
+        }).await;
+    }
+    
+    pub(crate) fn dead_letter_summary(row: &rusqlite::Row) -> rusqlite::Result<DeadLetterBatch> {
+        Ok(DeadLetterBatch {
+            id: row.get(0)?,
+            failed_at: chrono::DateTime::from_timestamp(row.get(1)?, 0).unwrap_or_default(),
+            attempts: row.get(2)?,
+            error: row.get(3)?,
+            event_count: row.get::<_, i64>(4)? as usize,
+            events: Vec::new(),
+        })
+    }
+    
+    /// Whole dead-letter batches (all of them when `ids` is empty); a batch that cannot be decoded
+    /// comes back without events, alongside the reason
+    pub(crate) fn read_dead_letters(conn: &Connection, cipher: Option<&BufferCipher>, ids: &[i64]) -> Result<Vec<(DeadLetterBatch, Option<String>)>, BufferError> {
+        let filter = match ids.is_empty() {
+            true => String::new(),
+            false => format!("WHERE id IN ({})", vec!["?"; ids.len()].join(", ")),
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, failed_at, attempts, error, event_count, events_json FROM dead_letters {} ORDER BY id",
+            filter
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(ids), |row| {
+            Ok((Self::dead_letter_summary(row)?, row.get::<_, rusqlite::types::Value>(5)?))
+        })?.collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(rows.into_iter().map(|(mut batch, stored)| {
+            // Batches are JSON arrays, so TEXT with the sealed prefix can only have been sealed by an older agent
+            let events = buffer_encryption::open_column(cipher, "dead_letter_events", stored, buffer_encryption::SEALED_BLOB_EVENT_VERSION - 1)
+                .and_then(|json| serde_json::from_str::<Vec<ParsedEvent>>(&json).map_err(|e| format!("invalid events JSON: {}", e)));
+            match events {
+                Ok(events) => {
+                    batch.events = events;
+                    (batch, None)
+                }
+                Err(reason) => (batch, Some(reason)),
+            }
+        }).collect())
+    }
+    
+    /// Run `f` against the database on the blocking pool
+    async fn with_connection<T, F>(&self, f: F) -> Result<T, BufferError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, BufferError> + Send + 'static,
+    {
+        let db = self.db_connection.clone();
+        component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || f(&mut db.blocking_lock()))
+            .await
+            .map_err(|e| BufferError::PersistenceError {
+                operation: "database_task".to_string(),
+                database_path: self.config.persistence_path.clone(),
+                recoverable: true,
+                source: Box::new(e),
+            })?
+    }
+    
+    pub fn is_persistent(&self) -> bool {
+        self.config.persistent
+    }
+    
     pub async fn flush(&self) -> Result<(), BufferError> {
         info!("🔄 Flushing buffer...");
         
+        // Spills still queued for a batched write reach the database before anything is drained
+        #[cfg(feature = "persistent-storage")]
+        self.flush_pending_writes().await?;
+        
         // Drain memory buffer
         let mut drained_count = 0;
         while let Some(_) = self.receive().await {
@@ -1981,6 +2988,12 @@ mod tests {
             cleanup_interval_sec: 300,
             min_retention_hours: 1,
             max_events_per_cleanup: 1000,
+            burst_capacity: 100,
+            newer_events: crate::config::NewerEventPolicy::Quarantine,
+            encryption: Default::default(),
+            write_batch_size: 500,
+            write_batch_interval_ms: 100,
+            disk_quota: Default::default(),
         };
         
         let buffer = EventBuffer::new(config).await;
@@ -2017,6 +3030,12 @@ mod tests {
             cleanup_interval_sec: 300,
             min_retention_hours: 1,
             max_events_per_cleanup: 1000,
+            burst_capacity: 100,
+            newer_events: crate::config::NewerEventPolicy::Quarantine,
+            encryption: Default::default(),
+            write_batch_size: 500,
+            write_batch_interval_ms: 100,
+            disk_quota: Default::default(),
         };
         
         let buffer = EventBuffer::new(config).await.unwrap();
@@ -2027,7 +3046,7 @@ mod tests {
             level: Some("INFO".to_string()),
             message: "Test message".to_string(),
             fields: HashMap::new(),
-            raw_data: "raw test data".to_string(),
+            raw_data: "raw test data".into(),
             parser_name: "test_parser".to_string(),
         };
         
@@ -2039,5 +3058,359 @@ mod tests {
         let received = buffer.receive().await;
         assert!(received.is_some());
         assert_eq!(received.unwrap().message, "Test message");
+    }    
+    #[tokio::test]
+    async fn test_newer_and_undecodable_rows_are_quarantined() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let conn = Connection::open(temp_dir.path().join("events.db")).unwrap();
+            EventBuffer::create_schema(&conn).unwrap();
+            // A column added by a future agent must not break this one
+            conn.execute("ALTER TABLE events ADD COLUMN tenant TEXT", []).unwrap();
+            let insert = "INSERT INTO events (timestamp, source, level, message, fields, raw_data, parser_name, event_version, tenant)
+                          VALUES (?1, 'test', 'info', ?2, ?3, '', 'test_parser', ?4, 'acme')";
+            let now = chrono::Utc::now().to_rfc3339();
+            conn.execute(insert, rusqlite::params![now, "from the future", "{}", 99]).unwrap();
+            conn.execute(insert, rusqlite::params![now, "corrupt", "[1, 2]", EVENT_VERSION]).unwrap();
+            conn.execute(insert, rusqlite::params![now, "current", "{\"user\": \"alice\"}", EVENT_VERSION]).unwrap();
+        }
+        
+        let mut config = crate::config::AgentConfig::default().buffer;
+        config.persistence_path = temp_dir.path().to_string_lossy().to_string();
+        config.newer_events = crate::config::NewerEventPolicy::Quarantine;
+        let buffer = EventBuffer::new(config.clone()).await.unwrap();
+        
+        let received = buffer.receive().await.unwrap();
+        assert_eq!(received.message, "current");
+        assert_eq!(received.fields["user"], "alice");
+        assert!(buffer.receive().await.is_none());
+        
+        let conn = buffer.db_connection.lock().await;
+        let quarantined: Vec<String> = conn
+            .prepare("SELECT row_json FROM events_quarantine ORDER BY id").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(quarantined.len(), 2);
+        assert!(quarantined[0].contains("\"tenant\":\"acme\""));
+        drop(conn);
+        drop(buffer);
+        
+        // Under the fail policy a newer row stops startup
+        {
+            let conn = Connection::open(temp_dir.path().join("events.db")).unwrap();
+            conn.execute(
+                "INSERT INTO events (timestamp, source, message, fields, raw_data, parser_name, event_version)
+                 VALUES ('2024-01-01T00:00:00Z', 'test', 'newer', '{}', '', 'test_parser', ?1)",
+                [EVENT_VERSION + 1],
+            ).unwrap();
+        }
+        config.newer_events = crate::config::NewerEventPolicy::Fail;
+        assert!(matches!(EventBuffer::new(config).await, Err(BufferError::CorruptionError { .. })));
+    }
+    
+    #[tokio::test]
+    async fn test_legacy_buffer_is_migrated_with_its_events() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            // Layout written by agents from before event_version and schema versioning
+            let conn = Connection::open(temp_dir.path().join("events.db")).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT, timestamp TEXT NOT NULL, source TEXT NOT NULL, level TEXT,
+                    message TEXT NOT NULL, fields TEXT NOT NULL, raw_data TEXT NOT NULL, parser_name TEXT NOT NULL,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')), size_bytes INTEGER NOT NULL DEFAULT 0
+                );
+                INSERT INTO events (timestamp, source, message, fields, raw_data, parser_name)
+                VALUES ('2024-01-01T00:00:00Z', 'test', 'collected before the upgrade', '{}', '', 'test_parser');",
+            ).unwrap();
+        }
+        
+        let mut config = crate::config::AgentConfig::default().buffer;
+        config.persistence_path = temp_dir.path().to_string_lossy().to_string();
+        let buffer = EventBuffer::new(config.clone()).await.unwrap();
+        {
+            let conn = buffer.db_connection.lock().await;
+            let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+            assert_eq!(version, SCHEMA_VERSION);
+            // Migrating again is a no-op
+            EventBuffer::create_schema(&conn).unwrap();
+        }
+        assert_eq!(buffer.receive().await.unwrap().message, "collected before the upgrade");
+        drop(buffer);
+        
+        // A newer agent's layout is used as-is rather than failing startup
+        {
+            let conn = Connection::open(temp_dir.path().join("events.db")).unwrap();
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1).unwrap();
+        }
+        let buffer = EventBuffer::new(config).await.unwrap();
+        let conn = buffer.db_connection.lock().await;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION + 1);
+    }
+    
+    #[tokio::test]
+    async fn test_encrypted_buffer_seals_payload_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            // Written before encryption was turned on
+            let conn = Connection::open(temp_dir.path().join("events.db")).unwrap();
+            EventBuffer::create_schema(&conn).unwrap();
+            conn.execute(
+                "INSERT INTO events (timestamp, source, message, fields, raw_data, parser_name, event_version)
+                 VALUES ('2024-01-01T00:00:00Z', 'test', 'legacy', '{}', '', 'test_parser', ?1)",
+                [EVENT_VERSION],
+            ).unwrap();
+        }
+        
+        let mut config = crate::config::AgentConfig::default().buffer;
+        config.persistence_path = temp_dir.path().to_string_lossy().to_string();
+        config.encryption = crate::buffer_encryption::BufferEncryptionConfig {
+            enabled: true,
+            key_source: crate::buffer_encryption::BufferKeySource::File {
+                path: temp_dir.path().join("buffer.key").to_string_lossy().to_string(),
+            },
+        };
+        let buffer = EventBuffer::new(config).await.unwrap();
+        buffer.store_to_disk(ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "sshd".to_string(),
+            level: Some("warning".to_string()),
+            message: "Failed password for root".to_string(),
+            fields: HashMap::from([("user".to_string(), serde_json::json!("root"))]),
+            raw_data: "sshd[42]: Failed password for root".into(),
+            parser_name: "syslog".to_string(),
+        }).await.unwrap();
+        
+        let stored: Vec<rusqlite::types::Value> = buffer.db_connection.lock().await
+            .query_row("SELECT message, fields, raw_data FROM events WHERE source = 'sshd'", [], |row| {
+                (0..3).map(|index| row.get(index)).collect()
+            }).unwrap();
+        for column in &stored {
+            let rusqlite::types::Value::Blob(blob) = column else { panic!("expected a sealed BLOB, got {:?}", column) };
+            assert!(!String::from_utf8_lossy(blob).contains("root"));
+        }
+        
+        assert_eq!(buffer.load_from_disk().await.unwrap().unwrap().message, "legacy");
+        let event = buffer.load_from_disk().await.unwrap().unwrap();
+        assert_eq!(event.message, "Failed password for root");
+        assert_eq!(event.fields["user"], "root");
+    }
+    
+    #[tokio::test]
+    async fn test_spilled_events_are_written_in_batches() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = crate::config::AgentConfig::default().buffer;
+        config.persistence_path = temp_dir.path().to_string_lossy().to_string();
+        config.write_batch_size = 3;
+        config.write_batch_interval_ms = 60_000;
+        let buffer = EventBuffer::new(config).await.unwrap();
+        
+        let event = |n: usize| ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "test".to_string(),
+            level: None,
+            message: format!("event {}", n),
+            fields: HashMap::new(),
+            raw_data: "".into(),
+            parser_name: "test_parser".to_string(),
+        };
+        async fn stored(buffer: &EventBuffer) -> i64 {
+            let conn = buffer.db_connection.lock().await;
+            conn.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0)).unwrap()
+        }
+        
+        buffer.queue_disk_write(event(0)).await.unwrap();
+        buffer.queue_disk_write(event(1)).await.unwrap();
+        assert_eq!(stored(&buffer).await, 0);
+        buffer.queue_disk_write(event(2)).await.unwrap();
+        assert_eq!(stored(&buffer).await, 3);
+        
+        // Partial batches are still delivered, after the rows already on disk
+        buffer.queue_disk_write(event(3)).await.unwrap();
+        assert_eq!(buffer.receive().await.unwrap().message, "event 0");
+        
+        // Larger writes span several multi-row statements in one transaction
+        assert_eq!(buffer.persist_events((4..254).map(event).collect()).await.unwrap(), 250);
+        assert_eq!(stored(&buffer).await, 253);
+        assert_eq!(buffer.get_stats().await.disk_events, 254);
+        
+        let mut messages = Vec::new();
+        while let Some(event) = buffer.receive().await {
+            messages.push(event.message);
+        }
+        assert_eq!(messages.len(), 253);
+        assert_eq!(messages[..3], ["event 1", "event 2", "event 3"]);
+    }
+    
+    #[tokio::test]
+    async fn test_queued_writes_are_written_on_shutdown_and_drop() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = crate::config::AgentConfig::default().buffer;
+        config.persistence_path = temp_dir.path().to_string_lossy().to_string();
+        config.write_batch_size = 10;
+        config.write_batch_interval_ms = 60_000;
+        let event = |n: usize| ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "test".to_string(),
+            level: None,
+            message: format!("event {}", n),
+            fields: HashMap::new(),
+            raw_data: "".into(),
+            parser_name: "test_parser".to_string(),
+        };
+        let stored = || -> i64 {
+            let conn = Connection::open(temp_dir.path().join("events.db")).unwrap();
+            conn.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0)).unwrap()
+        };
+        
+        let buffer = EventBuffer::new(config.clone()).await.unwrap();
+        buffer.queue_disk_write(event(0)).await.unwrap();
+        assert_eq!(stored(), 0);
+        buffer.shutdown().await.unwrap();
+        assert_eq!(stored(), 1);
+        // Once shut down, spills no longer wait for a batch
+        buffer.queue_disk_write(event(1)).await.unwrap();
+        assert_eq!(stored(), 2);
+        drop(buffer);
+        
+        // Dropping the last clone makes the coalescing task write what is queued
+        let buffer = EventBuffer::new(config).await.unwrap();
+        let clone = buffer.clone();
+        buffer.queue_disk_write(event(2)).await.unwrap();
+        drop(buffer);
+        clone.queue_disk_write(event(3)).await.unwrap();
+        assert_eq!(stored(), 2);
+        drop(clone);
+        for _ in 0..100 {
+            if stored() == 4 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(stored(), 4);
+    }
+    
+    #[tokio::test]
+    async fn test_failed_batch_write_is_kept_for_retry() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = crate::config::AgentConfig::default().buffer;
+        config.persistence_path = temp_dir.path().to_string_lossy().to_string();
+        config.write_batch_size = 2;
+        config.write_batch_interval_ms = 60_000;
+        let buffer = EventBuffer::new(config).await.unwrap();
+        
+        let event = |n: usize| ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "test".to_string(),
+            level: None,
+            message: format!("event {}", n),
+            fields: HashMap::new(),
+            raw_data: "".into(),
+            parser_name: "test_parser".to_string(),
+        };
+        let rename = |from: &'static str, to: &'static str| {
+            let db = buffer.db_connection.clone();
+            async move {
+                db.lock().await.execute_batch(&format!("ALTER TABLE {} RENAME TO {}", from, to)).unwrap();
+            }
+        };
+        
+        // Every write fails while the table is missing, but the events stay queued in order
+        rename("events", "events_offline").await;
+        for n in 0..8 {
+            buffer.queue_disk_write(event(n)).await.unwrap();
+        }
+        assert!(buffer.flush_pending_writes().await.is_err());
+        assert_eq!(buffer.pending_writes.lock().len(), 8);
+        
+        // Once the queue holds MAX_QUEUED_WRITE_BATCHES batches, further spills are refused rather than lost silently
+        assert!(matches!(buffer.queue_disk_write(event(8)).await, Err(BufferError::CapacityExceeded { .. })));
+        assert!(buffer.persist_events(vec![event(9)]).await.is_err());
+        assert_eq!(buffer.pending_writes.lock().len(), 8);
+        assert_eq!(buffer.get_stats().await.events_dropped, 1);
+        
+        rename("events_offline", "events").await;
+        assert_eq!(buffer.flush_pending_writes().await.unwrap(), 8);
+        let messages: Vec<String> = buffer.receive_batch(10).await.into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, (0..8).map(|n| format!("event {}", n)).collect::<Vec<_>>());
+    }
+    
+    #[tokio::test]
+    async fn test_receive_batch_takes_memory_then_disk_in_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = crate::config::AgentConfig::default().buffer;
+        config.persistence_path = temp_dir.path().to_string_lossy().to_string();
+        let buffer = EventBuffer::new(config).await.unwrap();
+        
+        let event = |n: usize| ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "test".to_string(),
+            level: None,
+            message: format!("event {}", n),
+            fields: HashMap::new(),
+            raw_data: "".into(),
+            parser_name: "test_parser".to_string(),
+        };
+        buffer.persist_events((0..5).map(event).collect()).await.unwrap();
+        buffer.send(event(5)).await.unwrap();
+        buffer.send(event(6)).await.unwrap();
+        
+        let messages = |events: Vec<ParsedEvent>| events.into_iter().map(|e| e.message).collect::<Vec<_>>();
+        assert_eq!(messages(buffer.receive_batch(4).await), ["event 5", "event 6", "event 0", "event 1"]);
+        assert_eq!(messages(buffer.receive_batch(10).await), ["event 2", "event 3", "event 4"]);
+        assert!(buffer.receive_batch(10).await.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_dead_letters_can_be_exported_and_redriven() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = crate::config::AgentConfig::default().buffer;
+        config.persistence_path = temp_dir.path().to_string_lossy().to_string();
+        let buffer = EventBuffer::new(config.clone()).await.unwrap();
+        
+        let event = |n: usize| ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "test".to_string(),
+            level: Some("error".to_string()),
+            message: format!("event {}", n),
+            fields: HashMap::new(),
+            raw_data: "".into(),
+            parser_name: "test_parser".to_string(),
+        };
+        assert_eq!(buffer.dead_letter_batch((0..3).map(event).collect(), "400 Bad Request", 3).await.unwrap(), 3);
+        assert_eq!(buffer.dead_letter_batch(vec![event(3)], "413 Payload Too Large", 3).await.unwrap(), 1);
+        let stats = buffer.get_stats().await;
+        assert_eq!((stats.dead_letter_batches, stats.dead_letter_events), (2, 4));
+        
+        let listed = buffer.list_dead_letters(10).await.unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].error, "400 Bad Request");
+        assert_eq!(listed[0].event_count, 3);
+        assert!(listed[0].events.is_empty());
+        let inspected = buffer.inspect_dead_letter(listed[1].id).await.unwrap().unwrap();
+        assert_eq!(inspected.events[0].message, "event 3");
+        
+        let mut exported = Vec::new();
+        assert_eq!(buffer.export_dead_letters(&[], &mut exported).await.unwrap(), 2);
+        let lines: Vec<serde_json::Value> = String::from_utf8(exported).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["events"].as_array().unwrap().len(), 3);
+        assert_eq!(lines[1]["attempts"], 3);
+        
+        // The dead-letter table survives a restart and re-driven batches are delivered again
+        drop(buffer);
+        let buffer = EventBuffer::new(config).await.unwrap();
+        assert_eq!(buffer.get_stats().await.dead_letter_events, 4);
+        assert_eq!(buffer.redrive_dead_letters(&[listed[0].id]).await.unwrap(), 3);
+        let messages: Vec<String> = buffer.receive_batch(10).await.into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, ["event 0", "event 1", "event 2"]);
+        
+        let stats = buffer.get_stats().await;
+        assert_eq!((stats.dead_letter_batches, stats.dead_letter_events), (1, 1));
+        assert_eq!(buffer.discard_dead_letters(&[]).await.unwrap(), 1);
+        assert!(buffer.list_dead_letters(10).await.unwrap().is_empty());
     }
 }
\ No newline at end of file
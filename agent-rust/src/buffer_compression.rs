@@ -0,0 +1,101 @@
+// Compression of buffered event payloads
+// The fields and raw data of events spilled to disk are stored as zstd BLOBs whenever that makes them smaller. With
+// buffer encryption on, the compressed bytes are sealed (ciphertext itself does not compress). Values stored
+// uncompressed, plain or sealed, are still read as before, so enabling or disabling compression never strands
+// buffered events.
+
+use crate::buffer_encryption::{self, BufferCipher};
+use crate::errors::BufferError;
+use rusqlite::types::Value;
+
+/// Values shorter than this stay TEXT; the zstd frame overhead eats most of the gain
+pub const MIN_COMPRESS_BYTES: usize = 256;
+const ZSTD_LEVEL: i32 = 3;
+
+/// First byte of a stored BLOB: how the rest is encoded; uncompressed sealed values use buffer_encryption::FORMAT_SEALED
+const FORMAT_ZSTD: u8 = 1;
+const FORMAT_ZSTD_SEALED: u8 = 2;
+
+/// First event_version whose fields and raw_data may be zstd BLOBs; agents reading an older format quarantine
+/// these rows instead of handing compressed bytes on as text
+pub const ZSTD_BLOB_EVENT_VERSION: i64 = 3;
+
+/// Encode a column value for storage: compressed BLOB when `compress` is set and it pays off, otherwise as
+/// `buffer_encryption::seal_column` stores it
+pub fn store_column(cipher: Option<&BufferCipher>, compress: bool, column: &str, value: &str) -> Result<Value, BufferError> {
+    if compress && value.len() >= MIN_COMPRESS_BYTES {
+        let compressed = zstd::bulk::compress(value.as_bytes(), ZSTD_LEVEL).map_err(|e| BufferError::SerializationError {
+            data_type: column.to_string(),
+            operation: "compress".to_string(),
+            size_bytes: Some(value.len()),
+            source: Box::new(e),
+        })?;
+        if compressed.len() < value.len() {
+            let mut stored = Vec::with_capacity(compressed.len() + 1);
+            match cipher {
+                Some(cipher) => {
+                    stored.push(FORMAT_ZSTD_SEALED);
+                    stored.extend_from_slice(&cipher.seal_bytes(column, &compressed)?);
+                }
+                None => {
+                    stored.push(FORMAT_ZSTD);
+                    stored.extend_from_slice(&compressed);
+                }
+            }
+            return Ok(Value::Blob(stored));
+        }
+    }
+    buffer_encryption::seal_column(cipher, column, value)
+}
+
+/// Read a stored column value back, whichever way it was written; `event_version` is the row's
+pub fn load_column(cipher: Option<&BufferCipher>, column: &str, stored: Value, event_version: i64) -> Result<String, String> {
+    let compressed = match stored {
+        Value::Blob(blob) if event_version < ZSTD_BLOB_EVENT_VERSION && matches!(blob.first(), Some(&(FORMAT_ZSTD | FORMAT_ZSTD_SEALED))) => {
+            return Err(format!("{} is compressed in a format v{} row", column, event_version));
+        }
+        Value::Blob(blob) if blob.first() == Some(&FORMAT_ZSTD) => blob[1..].to_vec(),
+        Value::Blob(blob) if blob.first() == Some(&FORMAT_ZSTD_SEALED) => match cipher {
+            Some(cipher) => cipher.open_bytes(column, &blob[1..])?,
+            None => return Err(format!("{} is encrypted but buffer encryption is not configured", column)),
+        },
+        other => return buffer_encryption::open_column(cipher, column, other, event_version),
+    };
+    let bytes = zstd::stream::decode_all(&compressed[..]).map_err(|e| format!("cannot decompress {}: {}", column, e))?;
+    String::from_utf8(bytes).map_err(|_| format!("decompressed {} is not UTF-8", column))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_in_every_storage_format() {
+        let version = ZSTD_BLOB_EVENT_VERSION;
+        let verbose = "Oct 16 10:00:00 host sshd[1234]: Failed password for invalid user admin from 203.0.113.9 port 22 ssh2\n".repeat(20);
+        let cipher = BufferCipher::from_key(&[7u8; 32]).unwrap();
+
+        let compressed = store_column(None, true, "raw_data", &verbose).unwrap();
+        match &compressed {
+            Value::Blob(blob) => assert!(blob.len() < verbose.len() / 5),
+            other => panic!("expected a BLOB, got {:?}", other),
+        }
+        assert_eq!(load_column(None, "raw_data", compressed.clone(), version).unwrap(), verbose);
+        // Rows written before compression existed cannot hold compressed columns
+        assert!(load_column(None, "raw_data", compressed, version - 1).is_err());
+
+        let sealed = store_column(Some(&cipher), true, "raw_data", &verbose).unwrap();
+        assert!(load_column(None, "raw_data", sealed.clone(), version).is_err());
+        // The column name is bound to the sealed bytes
+        assert!(load_column(Some(&cipher), "fields", sealed.clone(), version).is_err());
+        assert_eq!(load_column(Some(&cipher), "raw_data", sealed, version).unwrap(), verbose);
+
+        // Short values and disabled compression stay uncompressed
+        assert_eq!(store_column(None, true, "fields", "{}").unwrap(), Value::Text("{}".to_string()));
+        let short = store_column(Some(&cipher), true, "fields", "{}").unwrap();
+        assert!(matches!(&short, Value::Blob(blob) if blob[0] == buffer_encryption::FORMAT_SEALED));
+        assert_eq!(load_column(Some(&cipher), "fields", short, version).unwrap(), "{}");
+        assert_eq!(store_column(None, false, "raw_data", &verbose).unwrap(), Value::Text(verbose.clone()));
+        assert_eq!(load_column(None, "raw_data", Value::Text(verbose.clone()), version).unwrap(), verbose);
+    }
+}
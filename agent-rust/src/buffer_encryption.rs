@@ -0,0 +1,344 @@
+// Encryption at rest for the disk buffer
+// The message, fields and raw data of each stored event are sealed with AES-256-GCM before they reach SQLite.
+// Timestamp, source and level stay in clear so ordering, cleanup and priority queries keep working. Sealed values
+// are stored as BLOBs behind a format byte and TEXT is always plaintext, so a logged message that happens to look
+// like ciphertext is never mistaken for it. Rows written before encryption was enabled are still read as plaintext.
+
+use crate::errors::BufferError;
+use base64::{Engine as _, engine::general_purpose};
+use ring::{aead, rand::{SecureRandom, SystemRandom}};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "persistent-storage")]
+use ring::pbkdf2;
+#[cfg(feature = "persistent-storage")]
+use rusqlite::{types::Value, Connection, OptionalExtension};
+#[cfg(feature = "persistent-storage")]
+use tracing::info;
+
+/// Marks a sealed TEXT value: prefix, then base64 of nonce || ciphertext || tag. Used for buffer_metadata, and for
+/// event columns in rows older than SEALED_BLOB_EVENT_VERSION
+const SEALED_PREFIX: &str = "enc1:";
+/// First byte of a sealed column BLOB, followed by nonce || ciphertext || tag; buffer_compression's formats share
+/// this byte
+pub const FORMAT_SEALED: u8 = 3;
+/// First event_version whose sealed columns are BLOBs; older rows told sealed values apart only by SEALED_PREFIX
+pub const SEALED_BLOB_EVENT_VERSION: i64 = 2;
+/// buffer_metadata key holding a sealed known value, used to detect a wrong key at startup
+#[cfg(feature = "persistent-storage")]
+const KEY_CHECK_KEY: &str = "encryption_key_check";
+/// buffer_metadata key holding the PBKDF2 salt for passphrase keys
+#[cfg(feature = "persistent-storage")]
+const SALT_KEY: &str = "encryption_salt";
+#[cfg(feature = "persistent-storage")]
+const KEY_CHECK_VALUE: &str = "securewatch-buffer";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BufferEncryptionConfig {
+    pub enabled: bool,
+    pub key_source: BufferKeySource,
+}
+
+impl Default for BufferEncryptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_source: BufferKeySource::Env { variable: "SECUREWATCH_BUFFER_KEY".to_string() },
+        }
+    }
+}
+
+/// Where the 256-bit buffer key comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BufferKeySource {
+    /// Base64-encoded key in an environment variable
+    Env { variable: String },
+    /// File holding the base64-encoded key; a random key is generated on first start
+    File { path: String },
+    /// Passphrase in an environment variable, stretched with PBKDF2 (same scheme as the credential store)
+    Passphrase {
+        variable: String,
+        #[serde(default = "default_pbkdf2_iterations")]
+        iterations: u32,
+    },
+}
+
+fn default_pbkdf2_iterations() -> u32 {
+    100_000
+}
+
+impl BufferEncryptionConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        match &self.key_source {
+            BufferKeySource::Env { variable } | BufferKeySource::Passphrase { variable, .. } if variable.is_empty() => {
+                errors.push("key_source.variable must not be empty".to_string());
+            }
+            BufferKeySource::File { path } if path.is_empty() => {
+                errors.push("key_source.path must not be empty".to_string());
+            }
+            BufferKeySource::Passphrase { iterations, .. } if *iterations < 10_000 => {
+                errors.push(format!("key_source.iterations must be at least 10000, got {}", iterations));
+            }
+            _ => {}
+        }
+        errors
+    }
+}
+
+/// AES-256-GCM sealing of buffered column values
+pub struct BufferCipher {
+    key: aead::LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl std::fmt::Debug for BufferCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BufferCipher")
+    }
+}
+
+impl BufferCipher {
+    pub fn from_key(key_bytes: &[u8]) -> Result<Self, BufferError> {
+        let key = aead::UnboundKey::new(&aead::AES_256_GCM, key_bytes)
+            .map_err(|_| encryption_error("load_key", format!("key must be 32 bytes, got {}", key_bytes.len())))?;
+        Ok(Self { key: aead::LessSafeKey::new(key), rng: SystemRandom::new() })
+    }
+
+    /// Load the key from its configured source and check it against the one the database was written with
+    #[cfg(feature = "persistent-storage")]
+    pub fn open(config: &BufferEncryptionConfig, conn: &Connection) -> Result<Self, BufferError> {
+        let cipher = match &config.key_source {
+            BufferKeySource::Env { variable } => {
+                let encoded = std::env::var(variable)
+                    .map_err(|_| encryption_error("load_key", format!("environment variable {} is not set", variable)))?;
+                Self::from_key(&decode_key(&encoded)?)?
+            }
+            BufferKeySource::File { path } => Self::from_key(&load_or_create_key_file(path)?)?,
+            BufferKeySource::Passphrase { variable, iterations } => {
+                let passphrase = std::env::var(variable)
+                    .map_err(|_| encryption_error("load_key", format!("environment variable {} is not set", variable)))?;
+                let salt = match metadata(conn, SALT_KEY)? {
+                    Some(salt) => general_purpose::STANDARD.decode(salt)
+                        .map_err(|e| encryption_error("load_salt", e.to_string()))?,
+                    None => {
+                        let mut salt = vec![0u8; 32];
+                        SystemRandom::new().fill(&mut salt)
+                            .map_err(|_| encryption_error("generate_salt", "system random source failed".to_string()))?;
+                        set_metadata(conn, SALT_KEY, &general_purpose::STANDARD.encode(&salt))?;
+                        salt
+                    }
+                };
+                let mut key = [0u8; 32];
+                let iterations = std::num::NonZeroU32::new(*iterations)
+                    .ok_or_else(|| encryption_error("derive_key", "iterations must be positive".to_string()))?;
+                pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations, &salt, passphrase.as_bytes(), &mut key);
+                Self::from_key(&key)?
+            }
+        };
+
+        match metadata(conn, KEY_CHECK_KEY)? {
+            Some(check) => {
+                if cipher.open_value(KEY_CHECK_KEY, &check).ok().as_deref() != Some(KEY_CHECK_VALUE) {
+                    return Err(encryption_error("verify_key", "key does not match the one the buffer was encrypted with".to_string()));
+                }
+            }
+            None => {
+                set_metadata(conn, KEY_CHECK_KEY, &cipher.seal(KEY_CHECK_KEY, KEY_CHECK_VALUE)?)?;
+                info!("🔒 Buffer encryption enabled; new events are sealed with AES-256-GCM");
+            }
+        }
+        Ok(cipher)
+    }
+
+    /// Seal a column value; the column name is bound as associated data so values cannot be swapped between columns
+    pub fn seal(&self, column: &str, plaintext: &str) -> Result<String, BufferError> {
+        let sealed = self.seal_bytes(column, plaintext.as_bytes())?;
+        Ok(format!("{}{}", SEALED_PREFIX, general_purpose::STANDARD.encode(sealed)))
+    }
+
+    /// Open a sealed column value
+    pub fn open_value(&self, column: &str, stored: &str) -> Result<String, String> {
+        let encoded = stored.strip_prefix(SEALED_PREFIX).ok_or_else(|| format!("{} is not encrypted", column))?;
+        let sealed = general_purpose::STANDARD.decode(encoded)
+            .map_err(|e| format!("invalid encrypted {}: {}", column, e))?;
+        let plaintext = self.open_bytes(column, &sealed)?;
+        String::from_utf8(plaintext).map_err(|_| format!("decrypted {} is not UTF-8", column))
+    }
+
+    /// Seal raw bytes into nonce || ciphertext || tag
+    pub fn seal_bytes(&self, column: &str, plaintext: &[u8]) -> Result<Vec<u8>, BufferError> {
+        let mut nonce = [0u8; aead::NONCE_LEN];
+        self.rng.fill(&mut nonce)
+            .map_err(|_| encryption_error("seal", "system random source failed".to_string()))?;
+
+        let mut in_out = plaintext.to_vec();
+        self.key.seal_in_place_append_tag(aead::Nonce::assume_unique_for_key(nonce), aead::Aad::from(column.as_bytes()), &mut in_out)
+            .map_err(|_| encryption_error("seal", format!("could not encrypt {}", column)))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&in_out);
+        Ok(sealed)
+    }
+
+    /// Open bytes sealed with `seal_bytes`
+    pub fn open_bytes(&self, column: &str, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        if sealed.len() < aead::NONCE_LEN {
+            return Err(format!("encrypted {} is truncated", column));
+        }
+        let mut sealed = sealed.to_vec();
+        let nonce = aead::Nonce::try_assume_unique_for_key(&sealed[..aead::NONCE_LEN])
+            .map_err(|_| format!("invalid nonce in {}", column))?;
+        let plaintext = self.key.open_in_place(nonce, aead::Aad::from(column.as_bytes()), &mut sealed[aead::NONCE_LEN..])
+            .map_err(|_| format!("{} failed authentication (wrong key or tampered row)", column))?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+/// Encode a column value for storage: a sealed BLOB when encryption is enabled, plaintext TEXT otherwise
+#[cfg(feature = "persistent-storage")]
+pub fn seal_column(cipher: Option<&BufferCipher>, column: &str, value: &str) -> Result<Value, BufferError> {
+    let Some(cipher) = cipher else {
+        return Ok(Value::Text(value.to_string()));
+    };
+    let mut stored = vec![FORMAT_SEALED];
+    stored.extend_from_slice(&cipher.seal_bytes(column, value.as_bytes())?);
+    Ok(Value::Blob(stored))
+}
+
+/// Read a column value stored by `seal_column`; `event_version` is the row's, and only rows older than
+/// SEALED_BLOB_EVENT_VERSION may carry sealed TEXT
+#[cfg(feature = "persistent-storage")]
+pub fn open_column(cipher: Option<&BufferCipher>, column: &str, stored: Value, event_version: i64) -> Result<String, String> {
+    let sealed = match stored {
+        Value::Null => return Ok(String::new()),
+        Value::Text(text) if event_version < SEALED_BLOB_EVENT_VERSION && text.starts_with(SEALED_PREFIX) => {
+            return match cipher {
+                Some(cipher) => cipher.open_value(column, &text),
+                None => Err(format!("{} is encrypted but buffer encryption is not configured", column)),
+            };
+        }
+        Value::Text(text) => return Ok(text),
+        Value::Blob(blob) => match blob.split_first() {
+            Some((&FORMAT_SEALED, sealed)) => sealed.to_vec(),
+            Some((format, _)) => return Err(format!("{} has unknown storage format {}", column, format)),
+            None => return Ok(String::new()),
+        },
+        other => return Err(format!("{} has unexpected type {:?}", column, other.data_type())),
+    };
+    let Some(cipher) = cipher else {
+        return Err(format!("{} is encrypted but buffer encryption is not configured", column));
+    };
+    String::from_utf8(cipher.open_bytes(column, &sealed)?).map_err(|_| format!("decrypted {} is not UTF-8", column))
+}
+
+fn encryption_error(operation: &str, reason: String) -> BufferError {
+    BufferError::EncryptionError { operation: operation.to_string(), reason }
+}
+
+#[cfg(feature = "persistent-storage")]
+fn decode_key(encoded: &str) -> Result<Vec<u8>, BufferError> {
+    general_purpose::STANDARD.decode(encoded.trim())
+        .map_err(|e| encryption_error("load_key", format!("key is not valid base64: {}", e)))
+}
+
+#[cfg(feature = "persistent-storage")]
+fn load_or_create_key_file(path: &str) -> Result<Vec<u8>, BufferError> {
+    match std::fs::read_to_string(path) {
+        Ok(encoded) => decode_key(&encoded),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let mut key = vec![0u8; 32];
+            SystemRandom::new().fill(&mut key)
+                .map_err(|_| encryption_error("generate_key", "system random source failed".to_string()))?;
+            if let Some(parent) = std::path::Path::new(path).parent() {
+                std::fs::create_dir_all(parent).map_err(|e| encryption_error("create_key_file", e.to_string()))?;
+            }
+
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            let mut file = options.open(path).map_err(|e| encryption_error("create_key_file", e.to_string()))?;
+            std::io::Write::write_all(&mut file, general_purpose::STANDARD.encode(&key).as_bytes())
+                .map_err(|e| encryption_error("create_key_file", e.to_string()))?;
+
+            info!("🔑 Generated buffer encryption key at {}", path);
+            Ok(key)
+        }
+        Err(e) => Err(encryption_error("load_key", format!("cannot read {}: {}", path, e))),
+    }
+}
+
+#[cfg(feature = "persistent-storage")]
+fn metadata(conn: &Connection, key: &str) -> Result<Option<String>, BufferError> {
+    Ok(conn.query_row("SELECT value FROM buffer_metadata WHERE key = ?1", [key], |row| row.get(0)).optional()?)
+}
+
+#[cfg(feature = "persistent-storage")]
+fn set_metadata(conn: &Connection, key: &str, value: &str) -> Result<(), BufferError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO buffer_metadata (key, value, updated_at) VALUES (?1, ?2, strftime('%s', 'now'))",
+        [key, value],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "persistent-storage")]
+    #[test]
+    fn test_sealed_values_round_trip_and_bind_the_column() {
+        let version = SEALED_BLOB_EVENT_VERSION;
+        let cipher = BufferCipher::from_key(&[7u8; 32]).unwrap();
+        let sealed = seal_column(Some(&cipher), "message", "Failed password for root").unwrap();
+        match &sealed {
+            Value::Blob(blob) => assert!(blob[0] == FORMAT_SEALED && !String::from_utf8_lossy(blob).contains("root")),
+            other => panic!("expected a BLOB, got {:?}", other),
+        }
+        assert_ne!(sealed, seal_column(Some(&cipher), "message", "Failed password for root").unwrap());
+
+        assert_eq!(open_column(Some(&cipher), "message", sealed.clone(), version).unwrap(), "Failed password for root");
+        assert!(open_column(Some(&cipher), "raw_data", sealed.clone(), version).is_err());
+        assert!(open_column(None, "message", sealed.clone(), version).is_err());
+        assert!(open_column(Some(&BufferCipher::from_key(&[8u8; 32]).unwrap()), "message", sealed, version).is_err());
+
+        // Rows written before encryption was turned on stay readable, and plaintext that looks sealed stays plaintext
+        assert_eq!(open_column(Some(&cipher), "message", Value::Text("plain".to_string()), version).unwrap(), "plain");
+        let lookalike = format!("{}{}", SEALED_PREFIX, general_purpose::STANDARD.encode([0u8; 40]));
+        assert_eq!(open_column(Some(&cipher), "message", Value::Text(lookalike.clone()), version).unwrap(), lookalike);
+        assert_eq!(open_column(None, "message", Value::Text(lookalike.clone()), version).unwrap(), lookalike);
+
+        // Older rows marked sealed values with the prefix
+        let legacy = cipher.seal("message", "Failed password for root").unwrap();
+        assert_eq!(open_column(Some(&cipher), "message", Value::Text(legacy), version - 1).unwrap(), "Failed password for root");
+        assert!(BufferCipher::from_key(&[0u8; 16]).is_err());
+    }
+
+    #[cfg(feature = "persistent-storage")]
+    #[test]
+    fn test_key_sources_and_wrong_key_detection() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let conn = Connection::open(dir.path().join("events.db")).unwrap();
+        conn.execute("CREATE TABLE buffer_metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL, updated_at INTEGER NOT NULL DEFAULT 0)", []).unwrap();
+
+        let key_path = dir.path().join("keys").join("buffer.key").display().to_string();
+        let file_config = BufferEncryptionConfig { enabled: true, key_source: BufferKeySource::File { path: key_path.clone() } };
+        let first = BufferCipher::open(&file_config, &conn).unwrap();
+        let sealed = first.seal("fields", "{}").unwrap();
+        let reopened = BufferCipher::open(&file_config, &conn).unwrap();
+        assert_eq!(reopened.open_value("fields", &sealed).unwrap(), "{}");
+
+        std::env::set_var("SECUREWATCH_TEST_BUFFER_PASSPHRASE", "correct horse");
+        let passphrase_config = BufferEncryptionConfig {
+            enabled: true,
+            key_source: BufferKeySource::Passphrase { variable: "SECUREWATCH_TEST_BUFFER_PASSPHRASE".to_string(), iterations: 10_000 },
+        };
+        assert!(matches!(BufferCipher::open(&passphrase_config, &conn), Err(BufferError::EncryptionError { .. })));
+        assert!(passphrase_config.validate().is_empty());
+        assert_eq!(BufferEncryptionConfig { enabled: true, key_source: BufferKeySource::File { path: String::new() } }.validate().len(), 1);
+    }
+}
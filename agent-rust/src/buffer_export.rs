@@ -0,0 +1,356 @@
+// Offline export and inspection of the persistent event buffer
+// Reads events.db without taking events out of it, so it is safe to run next to a live agent. NDJSON lines are
+// ParsedEvent objects that can be reingested as-is; CSV is for spreadsheets and ad-hoc analysis. Disk stats
+// summarize what is waiting to ship per source, plus quarantined rows and dead-lettered batches
+
+use crate::buffer::DeadLetterBatch;
+use crate::config::BufferConfig;
+use crate::errors::BufferError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::str::FromStr;
+
+#[cfg(feature = "persistent-storage")]
+use crate::buffer::EventBuffer;
+#[cfg(feature = "persistent-storage")]
+use crate::buffer_encryption::BufferCipher;
+#[cfg(feature = "persistent-storage")]
+use rusqlite::{Connection, OpenFlags};
+#[cfg(feature = "persistent-storage")]
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Ndjson,
+    Csv,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!("unknown export format '{}' (expected ndjson or csv)", other)),
+        }
+    }
+}
+
+/// Which buffered events to export
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub source: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportSummary {
+    pub exported: usize,
+    /// Rows that could not be decoded (corrupt, or sealed with a key that is not configured)
+    pub skipped: usize,
+}
+
+/// Buffered events and bytes for one source
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SourceBacklog {
+    pub events: u64,
+    pub bytes: u64,
+}
+
+/// What the buffer on disk holds, read without opening it for writing
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiskBufferStats {
+    pub database_path: String,
+    /// Database plus write-ahead log
+    pub file_bytes: u64,
+    pub events: u64,
+    pub event_bytes: u64,
+    pub by_source: BTreeMap<String, SourceBacklog>,
+    /// When the oldest and newest waiting events were written to the buffer
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+    pub quarantined: u64,
+    pub dead_letter_batches: u64,
+    pub dead_letter_events: u64,
+}
+
+#[cfg(feature = "persistent-storage")]
+const CSV_HEADER: &str = "id,timestamp,source,level,parser_name,message,fields,raw_data";
+
+/// Write the events buffered on disk to `writer`, oldest first
+#[cfg(feature = "persistent-storage")]
+pub fn export_buffer<W: Write>(config: &BufferConfig, options: &ExportOptions, mut writer: W) -> Result<ExportSummary, BufferError> {
+    let (database_path, conn) = open_read_only(config, "open_buffer_for_export")?;
+    let cipher = match config.encryption.enabled {
+        true => Some(BufferCipher::open(&config.encryption, &conn)?),
+        false => None,
+    };
+
+    let write_error = |e: std::io::Error| BufferError::PersistenceError {
+        operation: "write_export".to_string(),
+        database_path: database_path.to_string_lossy().to_string(),
+        recoverable: true,
+        source: Box::new(e),
+    };
+    if options.format == ExportFormat::Csv {
+        writeln!(writer, "{}", CSV_HEADER).map_err(write_error)?;
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, source, level, message, fields, raw_data, parser_name, event_version FROM events
+         WHERE ?1 IS NULL OR source = ?1 ORDER BY id",
+    )?;
+    let mut rows = stmt.query([options.source.as_deref()])?;
+    let mut summary = ExportSummary::default();
+    while let Some(row) = rows.next()? {
+        if options.limit.is_some_and(|limit| summary.exported >= limit) {
+            break;
+        }
+        let id: i64 = row.get(0)?;
+        let event = match EventBuffer::decode_event(row, cipher.as_ref()) {
+            Ok(event) => event,
+            Err(_) => {
+                summary.skipped += 1;
+                continue;
+            }
+        };
+        if options.since.is_some_and(|since| event.timestamp < since)
+            || options.until.is_some_and(|until| event.timestamp > until)
+        {
+            continue;
+        }
+
+        match options.format {
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut writer, &event).map_err(|e| write_error(e.into()))?;
+                writer.write_all(b"\n").map_err(write_error)?;
+            }
+            ExportFormat::Csv => {
+                let fields = serde_json::to_string(&event.fields).unwrap_or_default();
+                let columns = [
+                    id.to_string(),
+                    event.timestamp.to_rfc3339(),
+                    event.source,
+                    event.level.unwrap_or_default(),
+                    event.parser_name,
+                    event.message,
+                    fields,
+                    event.raw_data.to_string(),
+                ];
+                let line = columns.iter().map(|column| csv_field(column)).collect::<Vec<_>>().join(",");
+                writeln!(writer, "{}", line).map_err(write_error)?;
+            }
+        }
+        summary.exported += 1;
+    }
+    writer.flush().map_err(write_error)?;
+    Ok(summary)
+}
+
+/// Count what the buffer on disk holds, per source, without reading event bodies
+#[cfg(feature = "persistent-storage")]
+pub fn buffer_disk_stats(config: &BufferConfig) -> Result<DiskBufferStats, BufferError> {
+    let (database_path, conn) = open_read_only(config, "open_buffer_for_stats")?;
+    let file_size = |path: &Path| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut stats = DiskBufferStats {
+        database_path: database_path.to_string_lossy().to_string(),
+        file_bytes: file_size(&database_path) + file_size(&database_path.with_extension("db-wal")),
+        ..Default::default()
+    };
+
+    let mut stmt = conn.prepare("SELECT source, COUNT(*), COALESCE(SUM(size_bytes), 0) FROM events GROUP BY source")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let backlog = SourceBacklog { events: row.get::<_, i64>(1)? as u64, bytes: row.get::<_, i64>(2)? as u64 };
+        stats.events += backlog.events;
+        stats.event_bytes += backlog.bytes;
+        stats.by_source.insert(row.get(0)?, backlog);
+    }
+    let (oldest, newest): (Option<i64>, Option<i64>) =
+        conn.query_row("SELECT MIN(created_at), MAX(created_at) FROM events", [], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    stats.oldest = oldest.and_then(|secs| DateTime::from_timestamp(secs, 0));
+    stats.newest = newest.and_then(|secs| DateTime::from_timestamp(secs, 0));
+
+    // Buffers written by older agents may predate the quarantine and dead letter tables
+    if has_table(&conn, "events_quarantine")? {
+        stats.quarantined = conn.query_row("SELECT COUNT(*) FROM events_quarantine", [], |row| row.get::<_, i64>(0))? as u64;
+    }
+    if has_table(&conn, "dead_letters")? {
+        let (batches, events): (i64, i64) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(event_count), 0) FROM dead_letters", [], |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        stats.dead_letter_batches = batches as u64;
+        stats.dead_letter_events = events as u64;
+    }
+    Ok(stats)
+}
+
+/// Dead-lettered batches on disk, oldest first, without their events
+#[cfg(feature = "persistent-storage")]
+pub fn list_dead_letters(config: &BufferConfig, limit: usize) -> Result<Vec<DeadLetterBatch>, BufferError> {
+    let (_, conn) = open_read_only(config, "open_buffer_for_dead_letters")?;
+    if !has_table(&conn, "dead_letters")? {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare("SELECT id, failed_at, attempts, error, event_count FROM dead_letters ORDER BY id LIMIT ?1")?;
+    let batches = stmt.query_map([limit as i64], EventBuffer::dead_letter_summary)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(batches)
+}
+
+/// Dead-lettered batches on disk with their events (all of them when `ids` is empty); a batch that cannot be
+/// decoded comes back without events, alongside the reason
+#[cfg(feature = "persistent-storage")]
+pub fn read_dead_letters(config: &BufferConfig, ids: &[i64]) -> Result<Vec<(DeadLetterBatch, Option<String>)>, BufferError> {
+    let (_, conn) = open_read_only(config, "open_buffer_for_dead_letters")?;
+    if !has_table(&conn, "dead_letters")? {
+        return Ok(Vec::new());
+    }
+    let cipher = match config.encryption.enabled {
+        true => Some(BufferCipher::open(&config.encryption, &conn)?),
+        false => None,
+    };
+    EventBuffer::read_dead_letters(&conn, cipher.as_ref(), ids)
+}
+
+#[cfg(feature = "persistent-storage")]
+fn has_table(conn: &Connection, table: &str) -> rusqlite::Result<bool> {
+    conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")?.exists([table])
+}
+
+#[cfg(feature = "persistent-storage")]
+pub(crate) fn open_read_only(config: &BufferConfig, operation: &str) -> Result<(std::path::PathBuf, Connection), BufferError> {
+    let database_path = Path::new(&config.persistence_path).join("events.db");
+    if !config.persistent || !database_path.exists() {
+        return Err(BufferError::PersistenceError {
+            operation: operation.to_string(),
+            database_path: database_path.to_string_lossy().to_string(),
+            recoverable: false,
+            source: Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "no persistent buffer at this path")),
+        });
+    }
+    let conn = Connection::open_with_flags(&database_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    Ok((database_path, conn))
+}
+
+/// Memory-only builds have no buffer on disk to export
+#[cfg(not(feature = "persistent-storage"))]
+pub fn export_buffer<W: Write>(config: &BufferConfig, _options: &ExportOptions, _writer: W) -> Result<ExportSummary, BufferError> {
+    Err(BufferError::PersistenceError {
+        operation: "open_buffer_for_export".to_string(),
+        database_path: config.persistence_path.clone(),
+        recoverable: false,
+        source: Box::new(std::io::Error::new(std::io::ErrorKind::Unsupported, "built without persistent-storage")),
+    })
+}
+
+#[cfg(not(feature = "persistent-storage"))]
+pub fn buffer_disk_stats(config: &BufferConfig) -> Result<DiskBufferStats, BufferError> {
+    Err(BufferError::PersistenceError {
+        operation: "open_buffer_for_stats".to_string(),
+        database_path: config.persistence_path.clone(),
+        recoverable: false,
+        source: Box::new(std::io::Error::new(std::io::ErrorKind::Unsupported, "built without persistent-storage")),
+    })
+}
+
+#[cfg(not(feature = "persistent-storage"))]
+pub fn list_dead_letters(config: &BufferConfig, _limit: usize) -> Result<Vec<DeadLetterBatch>, BufferError> {
+    Err(BufferError::PersistenceError {
+        operation: "open_buffer_for_dead_letters".to_string(),
+        database_path: config.persistence_path.clone(),
+        recoverable: false,
+        source: Box::new(std::io::Error::new(std::io::ErrorKind::Unsupported, "built without persistent-storage")),
+    })
+}
+
+#[cfg(not(feature = "persistent-storage"))]
+pub fn read_dead_letters(config: &BufferConfig, _ids: &[i64]) -> Result<Vec<(DeadLetterBatch, Option<String>)>, BufferError> {
+    Err(BufferError::PersistenceError {
+        operation: "open_buffer_for_dead_letters".to_string(),
+        database_path: config.persistence_path.clone(),
+        recoverable: false,
+        source: Box::new(std::io::Error::new(std::io::ErrorKind::Unsupported, "built without persistent-storage")),
+    })
+}
+
+/// RFC 4180 quoting: fields with commas, quotes or line breaks are quoted, quotes doubled
+#[cfg(feature = "persistent-storage")]
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(all(test, feature = "persistent-storage"))]
+mod tests {
+    use super::*;
+    use crate::parsers::ParsedEvent;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_export_reads_buffer_without_draining_it() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = crate::config::AgentConfig::default().buffer;
+        config.persistence_path = temp_dir.path().to_string_lossy().to_string();
+        let buffer = EventBuffer::new(config.clone()).await.unwrap();
+
+        let event = |source: &str, message: &str| ParsedEvent {
+            timestamp: Utc::now(),
+            source: source.to_string(),
+            level: Some("warning".to_string()),
+            message: message.to_string(),
+            fields: HashMap::from([("user".to_string(), serde_json::json!("root"))]),
+            raw_data: "raw".into(),
+            parser_name: "test_parser".to_string(),
+        };
+        buffer.persist_events(vec![
+            event("syslog", "first"),
+            event("auth", "login, \"root\"\nfailed"),
+            event("syslog", "third"),
+        ]).await.unwrap();
+
+        let mut options = ExportOptions { format: ExportFormat::Ndjson, source: Some("syslog".to_string()), since: None, until: None, limit: None };
+        let mut output = Vec::new();
+        let summary = export_buffer(&config, &options, &mut output).unwrap();
+        assert_eq!((summary.exported, summary.skipped), (2, 0));
+        let exported: Vec<ParsedEvent> = String::from_utf8(output).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(exported[1].message, "third");
+        assert_eq!(exported[0].fields["user"], "root");
+
+        options.format = ExportFormat::Csv;
+        options.source = Some("auth".to_string());
+        let mut output = Vec::new();
+        export_buffer(&config, &options, &mut output).unwrap();
+        let csv = String::from_utf8(output).unwrap();
+        assert!(csv.starts_with(CSV_HEADER));
+        assert!(csv.contains(",test_parser,\"login, \"\"root\"\"\nfailed\",\"{\"\"user\"\":\"\"root\"\"}\",raw\n"));
+
+        let stats = buffer_disk_stats(&config).unwrap();
+        assert_eq!(stats.events, 3);
+        assert_eq!(stats.by_source["syslog"].events, 2);
+        assert!(stats.oldest.is_some() && stats.file_bytes > 0);
+
+        buffer.dead_letter_batch(vec![event("auth", "refused")], "Server error 400: invalid batch", 3).await.unwrap();
+        let listed = list_dead_letters(&config, 10).unwrap();
+        assert_eq!((listed.len(), listed[0].event_count, listed[0].events.len()), (1, 1, 0));
+        let (batch, failure) = read_dead_letters(&config, &[listed[0].id]).unwrap().pop().unwrap();
+        assert!(failure.is_none());
+        assert_eq!(batch.events[0].message, "refused");
+
+        // Nothing was taken out of the buffer
+        assert_eq!(buffer.receive_batch(10).await.len(), 3);
+    }
+}
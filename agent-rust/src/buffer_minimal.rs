@@ -1,9 +1,19 @@
 // Minimal memory-only buffer implementation for cross-compilation builds
 // This avoids SQLite C compilation dependencies
 
-use crate::config::BufferConfig;
+use crate::alert_rules;
+use crate::burst_overflow::{BurstOverflow, BurstStats};
+use crate::config::{BufferConfig, BufferLimits};
+use crate::dedup::DuplicateFilter;
+use crate::disk_quota::{DiskQuota, DiskQuotaStats};
+use crate::ingest_pause::IngestPauses;
+use crate::load_shedding::LoadShedder;
+use crate::delivery;
+use crate::integrity;
 use crate::errors::BufferError;
 use crate::parsers::ParsedEvent;
+use crate::resource_monitor::DiskMetrics;
+use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::{mpsc, watch, Mutex};
 use tokio::time::{interval, Duration};
@@ -11,11 +21,22 @@ use tracing::{info, warn, error, debug};
 
 const HIGH_WATER_MARK: f32 = 0.8;
 const LOW_WATER_MARK: f32 = 0.3;
+const PRIORITY_LANE_CAPACITY: usize = 10_000;
 
+#[derive(Clone)]
 pub struct EventBuffer {
     config: BufferConfig,
     memory_sender: mpsc::Sender<ParsedEvent>,
     memory_receiver: Arc<Mutex<mpsc::Receiver<ParsedEvent>>>,
+    overflow: Arc<parking_lot::Mutex<BurstOverflow<ParsedEvent>>>,
+    priority: Arc<parking_lot::Mutex<VecDeque<ParsedEvent>>>,
+    duplicate_filter: Option<DuplicateFilter>,
+    ingest_pauses: Option<Arc<IngestPauses>>,
+    load_shedder: Option<Arc<LoadShedder>>,
+    // Stamp event.hash on accepted events before they are stored
+    event_hashing: bool,
+    // Stamp event.id on accepted events, so every send of an event carries the same ID
+    event_ids: bool,
     backpressure_sender: watch::Sender<bool>,
     backpressure_receiver: watch::Receiver<bool>,
     stats: Arc<Mutex<BufferStats>>,
@@ -29,6 +50,20 @@ pub struct BufferStats {
     pub backpressure_active: bool,
     pub events_processed: u64,
     pub events_dropped: u64,
+    pub dead_letter_batches: u64,
+    pub dead_letter_events: u64,
+}
+
+/// A batch the transport could not deliver; never held by memory-only builds
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DeadLetterBatch {
+    pub id: i64,
+    pub failed_at: chrono::DateTime<chrono::Utc>,
+    pub attempts: u32,
+    pub error: String,
+    pub event_count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<ParsedEvent>,
 }
 
 impl EventBuffer {
@@ -43,11 +78,21 @@ impl EventBuffer {
             backpressure_active: false,
             events_processed: 0,
             events_dropped: 0,
+            dead_letter_batches: 0,
+            dead_letter_events: 0,
         }));
         
-        info!("📦 Minimal event buffer initialized with memory capacity: {}", config.max_events);
+        info!("📦 Minimal event buffer initialized with memory capacity: {}, burst overflow: {}",
+              config.max_events, config.burst_capacity);
         
         let buffer = Self {
+            overflow: Arc::new(parking_lot::Mutex::new(BurstOverflow::new(config.burst_capacity))),
+            priority: Arc::new(parking_lot::Mutex::new(VecDeque::new())),
+            duplicate_filter: None,
+            ingest_pauses: None,
+            load_shedder: None,
+            event_hashing: false,
+            event_ids: false,
             config,
             memory_sender,
             memory_receiver: Arc::new(Mutex::new(memory_receiver)),
@@ -60,7 +105,90 @@ impl EventBuffer {
     }
     
     pub async fn send(&self, event: ParsedEvent) -> Result<(), BufferError> {
-        match self.memory_sender.try_send(event) {
+        self.accept(event, false).await
+    }
+    
+    /// Queue an event another agent sent through the aggregator, keeping the `event.hash` taken where it was collected
+    pub async fn send_forwarded(&self, event: ParsedEvent) -> Result<(), BufferError> {
+        self.accept(event, true).await
+    }
+    
+    async fn accept(&self, mut event: ParsedEvent, forwarded: bool) -> Result<(), BufferError> {
+        // Operator pauses discard events before they are counted or indexed
+        if let Some(pauses) = &self.ingest_pauses {
+            if pauses.is_paused(&event.source) {
+                pauses.record_discarded(&event.source);
+                return Ok(());
+            }
+        }
+        
+        // Under resource pressure only a sample of non-alert events is kept
+        if self.load_shedder.as_ref().is_some_and(|shedder| shedder.should_sample_out(&event)) {
+            return Ok(());
+        }
+        
+        // Drop events already shipped inside the duplicate window (e.g. re-read after a restart)
+        if let Some(filter) = &self.duplicate_filter {
+            if filter.is_duplicate(&event.source, &event.raw_data).await {
+                return Ok(());
+            }
+        }
+        
+        if self.event_hashing {
+            if forwarded {
+                integrity::stamp_forwarded_event_hash(&mut event);
+            } else {
+                integrity::stamp_event_hash(&mut event);
+            }
+        }
+        if self.event_ids {
+            delivery::stamp_event_id(&mut event);
+        }
+        
+        // Alert-tagged events skip the queue; a flood beyond the lane's capacity takes the normal path
+        let event = if alert_rules::is_alert(&event) {
+            // The lane's lock must be released before awaiting, so the event comes back out of this block when full
+            let rejected = {
+                let mut priority = self.priority.lock();
+                if priority.len() < PRIORITY_LANE_CAPACITY {
+                    priority.push_back(event);
+                    None
+                } else {
+                    Some(event)
+                }
+            };
+            match rejected {
+                None => {
+                    let mut stats = self.stats.lock().await;
+                    stats.memory_events += 1;
+                    stats.events_processed += 1;
+                    return Ok(());
+                }
+                Some(event) => {
+                    warn!("🚨 Priority lane full ({} events), queueing alert event normally", PRIORITY_LANE_CAPACITY);
+                    event
+                }
+            }
+        } else {
+            event
+        };
+        
+        // Queue behind an active burst to preserve ordering
+        let send_result = {
+            let mut overflow = self.overflow.lock();
+            if overflow.is_active() {
+                overflow.try_push(event).map_err(mpsc::error::TrySendError::Full)
+            } else {
+                self.memory_sender.try_send(event).or_else(|e| match e {
+                    mpsc::error::TrySendError::Full(event) => {
+                        overflow.try_push(event).map_err(mpsc::error::TrySendError::Full)
+                    }
+                    closed => Err(closed),
+                })
+            }
+        };
+        
+        match send_result {
             Ok(_) => {
                 let mut stats = self.stats.lock().await;
                 stats.memory_events += 1;
@@ -89,6 +217,12 @@ impl EventBuffer {
     }
     
     pub async fn receive(&self) -> Result<Option<ParsedEvent>, BufferError> {
+        let priority_event = self.priority.lock().pop_front();
+        if let Some(event) = priority_event {
+            let mut stats = self.stats.lock().await;
+            stats.memory_events = stats.memory_events.saturating_sub(1);
+            return Ok(Some(event));
+        }
         let mut receiver = self.memory_receiver.lock().await;
         match receiver.try_recv() {
             Ok(event) => {
@@ -96,7 +230,14 @@ impl EventBuffer {
                 stats.memory_events = stats.memory_events.saturating_sub(1);
                 Ok(Some(event))
             }
-            Err(mpsc::error::TryRecvError::Empty) => Ok(None),
+            Err(mpsc::error::TryRecvError::Empty) => {
+                let event = self.overflow.lock().pop();
+                if event.is_some() {
+                    let mut stats = self.stats.lock().await;
+                    stats.memory_events = stats.memory_events.saturating_sub(1);
+                }
+                Ok(event)
+            }
             Err(mpsc::error::TryRecvError::Disconnected) => Err(BufferError::ChannelError {
                 operation: "receive".to_string(),
                 channel_name: "memory_buffer".to_string(),
@@ -106,20 +247,183 @@ impl EventBuffer {
         }
     }
     
+    /// Take up to `max_events` events in the order `receive` would return them
+    pub async fn receive_batch(&self, max_events: usize) -> Result<Vec<ParsedEvent>, BufferError> {
+        let mut events: Vec<ParsedEvent> = {
+            let mut priority = self.priority.lock();
+            let take = priority.len().min(max_events);
+            priority.drain(..take).collect()
+        };
+        {
+            let mut receiver = self.memory_receiver.lock().await;
+            while events.len() < max_events {
+                match receiver.try_recv() {
+                    Ok(event) => events.push(event),
+                    Err(mpsc::error::TryRecvError::Empty) => break,
+                    Err(mpsc::error::TryRecvError::Disconnected) if events.is_empty() => {
+                        return Err(BufferError::ChannelError {
+                            operation: "receive_batch".to_string(),
+                            channel_name: "memory_buffer".to_string(),
+                            buffer_size: Some(self.config.max_events),
+                            is_closed: true,
+                        });
+                    }
+                    Err(mpsc::error::TryRecvError::Disconnected) => break,
+                }
+            }
+        }
+        {
+            let mut overflow = self.overflow.lock();
+            while events.len() < max_events {
+                match overflow.pop() {
+                    Some(event) => events.push(event),
+                    None => break,
+                }
+            }
+        }
+        
+        let mut stats = self.stats.lock().await;
+        stats.memory_events = stats.memory_events.saturating_sub(events.len());
+        Ok(events)
+    }
+    
     pub async fn stats(&self) -> BufferStats {
         self.stats.lock().await.clone()
     }
     
+    pub async fn get_stats(&self) -> BufferStats {
+        self.stats().await
+    }
+    
+    /// Burst absorption metrics for the in-memory overflow list
+    pub fn get_burst_stats(&self) -> BurstStats {
+        self.overflow.lock().stats()
+    }
+    
+    /// Drop events whose content was already buffered inside the filter's window
+    pub fn set_duplicate_filter(&mut self, filter: DuplicateFilter) {
+        self.duplicate_filter = Some(filter);
+    }
+    
+    /// Discard events from sources an operator has paused
+    pub fn set_ingest_pauses(&mut self, pauses: Arc<IngestPauses>) {
+        self.ingest_pauses = Some(pauses);
+    }
+    
+    /// Sample events while load shedding asks for it
+    pub fn set_load_shedder(&mut self, shedder: Arc<LoadShedder>) {
+        self.load_shedder = Some(shedder);
+    }
+    
+    /// Record each event's raw-data digest before it is stored, for chain of custody
+    pub fn set_event_hashing(&mut self, enabled: bool) {
+        self.event_hashing = enabled;
+    }
+    
+    /// Give each event a ULID before it is stored, for acknowledgement tracking and server-side deduplication
+    pub fn set_event_ids(&mut self, enabled: bool) {
+        self.event_ids = enabled;
+    }
+    
     pub fn backpressure_receiver(&self) -> watch::Receiver<bool> {
         self.backpressure_receiver.clone()
     }
     
+    /// Size and cleanup limits as configured; memory-only builds have no disk to enforce them on
+    pub fn limits(&self) -> BufferLimits {
+        self.config.limits()
+    }
+    
+    pub fn set_limits(&self, _limits: BufferLimits) {
+        debug!("📦 Memory-only buffer has no disk limits to update");
+    }
+    
+    pub fn set_disk_quota(&mut self, _quota: Arc<DiskQuota>) {
+        debug!("📦 Memory-only buffer has no disk to apply a quota to");
+    }
+    
+    pub async fn update_disk_pressure(&self, _disks: &[DiskMetrics]) {}
+    
+    pub fn get_disk_quota_stats(&self) -> Option<DiskQuotaStats> {
+        None
+    }
+    
     pub fn get_backpressure_receiver(&self) -> watch::Receiver<bool> {
         self.backpressure_receiver.clone()
     }
     
+    /// Take every event held in memory (priority lane, channel and burst overflow)
+    pub async fn drain_pending(&self) -> Vec<ParsedEvent> {
+        let mut events: Vec<ParsedEvent> = self.priority.lock().drain(..).collect();
+        {
+            let mut receiver = self.memory_receiver.lock().await;
+            while let Ok(event) = receiver.try_recv() {
+                events.push(event);
+            }
+        }
+        let mut overflow = self.overflow.lock();
+        while let Some(event) = overflow.pop() {
+            events.push(event);
+        }
+        drop(overflow);
+        self.stats.lock().await.memory_events = 0;
+        events
+    }
+    
+    /// Nothing is persisted in memory-only builds
+    pub async fn take_persisted_matching(&self, _levels: &[String], _sources: &[String], _limit: usize) -> Result<Vec<ParsedEvent>, BufferError> {
+        Ok(Vec::new())
+    }
+    
+    /// Memory-only builds cannot persist; the events are counted as dropped
+    pub async fn persist_events(&self, events: Vec<ParsedEvent>) -> Result<usize, BufferError> {
+        if !events.is_empty() {
+            warn!("📦 Memory-only buffer, dropping {} unsent events", events.len());
+            self.stats.lock().await.events_dropped += events.len() as u64;
+        }
+        Ok(0)
+    }
+    
+    /// Memory-only builds have no dead-letter table; the events are counted as dropped
+    pub async fn dead_letter_batch(&self, events: Vec<ParsedEvent>, error: &str, _attempts: u32) -> Result<usize, BufferError> {
+        if !events.is_empty() {
+            warn!("📦 Memory-only buffer, dropping {} undeliverable events: {}", events.len(), error);
+            self.stats.lock().await.events_dropped += events.len() as u64;
+        }
+        Ok(0)
+    }
+    
+    pub async fn list_dead_letters(&self, _limit: usize) -> Result<Vec<DeadLetterBatch>, BufferError> {
+        Ok(Vec::new())
+    }
+    
+    pub async fn inspect_dead_letter(&self, _id: i64) -> Result<Option<DeadLetterBatch>, BufferError> {
+        Ok(None)
+    }
+    
+    pub async fn redrive_dead_letters(&self, _ids: &[i64]) -> Result<usize, BufferError> {
+        Ok(0)
+    }
+    
+    pub async fn export_dead_letters<W: std::io::Write>(&self, _ids: &[i64], _writer: W) -> Result<usize, BufferError> {
+        Ok(0)
+    }
+    
+    pub async fn discard_dead_letters(&self, _ids: &[i64]) -> Result<usize, BufferError> {
+        Ok(0)
+    }
+    
+    pub fn is_persistent(&self) -> bool {
+        false
+    }
+    
     pub async fn flush(&self) -> Result<(), BufferError> {
         // Minimal implementation - just return Ok since we're memory-only
         Ok(())
     }
+    
+    /// Memory-only builds queue nothing for disk
+    pub async fn shutdown(&self) -> Result<(), BufferError> {
+        Ok(())
+    }
 }
\ No newline at end of file
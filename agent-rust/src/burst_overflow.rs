@@ -0,0 +1,157 @@
+// In-memory overflow spill list for absorbing short event bursts
+// Sits between the bounded memory channel and disk persistence so momentary spikes
+// (e.g. log rotation replay) don't trigger disk writes or backpressure flapping
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::Instant;
+use tracing::{debug, info};
+
+/// Burst absorption metrics
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BurstStats {
+    pub overflow_capacity: usize,
+    pub overflow_events: usize,
+    pub peak_overflow_events: usize,
+    /// Bursts fully absorbed in memory
+    pub bursts_absorbed: u64,
+    /// Bursts that outgrew the overflow list and spilled to the next tier
+    pub bursts_spilled: u64,
+    pub events_absorbed: u64,
+    pub events_spilled: u64,
+    pub longest_burst_ms: u64,
+    pub last_burst_peak: usize,
+}
+
+/// FIFO overflow list. While a burst is active new items must queue here
+/// (not in the channel) so ordering is preserved.
+#[derive(Debug)]
+pub struct BurstOverflow<T> {
+    capacity: usize,
+    queue: VecDeque<T>,
+    burst_started: Option<Instant>,
+    burst_peak: usize,
+    burst_spilled: bool,
+    stats: BurstStats,
+}
+
+impl<T> BurstOverflow<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            queue: VecDeque::new(),
+            burst_started: None,
+            burst_peak: 0,
+            burst_spilled: false,
+            stats: BurstStats {
+                overflow_capacity: capacity,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Whether a burst is currently being absorbed
+    pub fn is_active(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Queue an item, handing it back when the overflow list is full
+    pub fn try_push(&mut self, item: T) -> Result<(), T> {
+        if self.capacity == 0 {
+            return Err(item);
+        }
+        if self.queue.len() >= self.capacity {
+            if self.burst_started.is_some() && !self.burst_spilled {
+                debug!("🌊 Burst exceeded overflow capacity ({}), spilling", self.capacity);
+                self.burst_spilled = true;
+            }
+            self.stats.events_spilled += 1;
+            return Err(item);
+        }
+
+        if self.burst_started.is_none() {
+            debug!("🌊 Burst started, absorbing into overflow list");
+            self.burst_started = Some(Instant::now());
+            self.burst_peak = 0;
+            self.burst_spilled = false;
+        }
+
+        self.queue.push_back(item);
+        self.burst_peak = self.burst_peak.max(self.queue.len());
+        self.stats.events_absorbed += 1;
+        self.stats.overflow_events = self.queue.len();
+        self.stats.peak_overflow_events = self.stats.peak_overflow_events.max(self.queue.len());
+        Ok(())
+    }
+
+    /// Take the oldest queued item, closing the burst once the list drains
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.queue.pop_front()?;
+        self.stats.overflow_events = self.queue.len();
+
+        if self.queue.is_empty() {
+            if let Some(started) = self.burst_started.take() {
+                let duration_ms = started.elapsed().as_millis() as u64;
+                self.stats.longest_burst_ms = self.stats.longest_burst_ms.max(duration_ms);
+                self.stats.last_burst_peak = self.burst_peak;
+                if self.burst_spilled {
+                    self.stats.bursts_spilled += 1;
+                } else {
+                    self.stats.bursts_absorbed += 1;
+                }
+                info!("🌊 Burst drained after {}ms (peak {} events, spilled: {})",
+                      duration_ms, self.burst_peak, self.burst_spilled);
+            }
+        }
+
+        Some(item)
+    }
+
+    pub fn stats(&self) -> BurstStats {
+        self.stats.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_absorbed_in_order() {
+        let mut overflow = BurstOverflow::new(3);
+        for i in 0..3 {
+            assert!(overflow.try_push(i).is_ok());
+        }
+        assert_eq!(overflow.try_push(3), Err(3));
+        assert_eq!(overflow.stats().events_spilled, 1);
+
+        assert_eq!(overflow.pop(), Some(0));
+        assert_eq!(overflow.pop(), Some(1));
+        assert_eq!(overflow.pop(), Some(2));
+        assert_eq!(overflow.pop(), None);
+
+        let stats = overflow.stats();
+        assert_eq!(stats.bursts_spilled, 1);
+        assert_eq!(stats.bursts_absorbed, 0);
+        assert_eq!(stats.peak_overflow_events, 3);
+
+        overflow.try_push(10).unwrap();
+        overflow.pop();
+        assert_eq!(overflow.stats().bursts_absorbed, 1);
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_overflow() {
+        let mut overflow = BurstOverflow::new(0);
+        assert_eq!(overflow.try_push("event"), Err("event"));
+        assert!(!overflow.is_active());
+    }
+}
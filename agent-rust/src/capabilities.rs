@@ -0,0 +1,406 @@
+// Feature capability report
+// Combines the cargo features this binary was built with, platform facilities detected at runtime
+// and the configuration to show which config sections are active, degraded or silently ignored
+
+use crate::collectors::session::SessionSourceKind;
+use crate::config::{AgentConfig, TransportProtocol};
+use serde::Serialize;
+use std::path::Path;
+
+/// Default systemd-logind session directory, used when the session collector isn't configured
+const LOGIND_SESSIONS_DIR: &str = "/run/systemd/sessions";
+const WTMP_PATH: &str = "/var/log/wtmp";
+
+/// A cargo feature and whether this binary was compiled with it
+#[derive(Debug, Clone, Serialize)]
+pub struct CompiledFeature {
+    pub name: &'static str,
+    pub enabled: bool,
+    pub description: &'static str,
+}
+
+/// A platform facility probed at runtime
+#[derive(Debug, Clone, Serialize)]
+pub struct PlatformCapability {
+    pub name: &'static str,
+    pub available: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionStatus {
+    /// Enabled and fully supported by this build and host
+    Active,
+    /// Enabled but running with reduced functionality
+    Degraded,
+    /// Enabled but has no effect in this build or on this platform
+    Ignored,
+    /// Disabled in the configuration
+    Disabled,
+}
+
+impl SectionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SectionStatus::Active => "active",
+            SectionStatus::Degraded => "degraded",
+            SectionStatus::Ignored => "ignored",
+            SectionStatus::Disabled => "disabled",
+        }
+    }
+}
+
+/// Effective state of one configuration section
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigSectionStatus {
+    pub section: &'static str,
+    pub status: SectionStatus,
+    /// Why the section is degraded or ignored
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CapabilityReport {
+    pub version: &'static str,
+    pub target_os: &'static str,
+    pub target_arch: &'static str,
+    pub compiled_features: Vec<CompiledFeature>,
+    pub platform: Vec<PlatformCapability>,
+    pub config_sections: Vec<ConfigSectionStatus>,
+}
+
+impl CapabilityReport {
+    /// Probe the build and host, and evaluate `config` against what was found
+    pub fn detect(config: &AgentConfig) -> Self {
+        let compiled_features = compiled_features();
+        let platform = detect_platform(config);
+        let config_sections = evaluate_sections(config, &platform);
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            target_os: std::env::consts::OS,
+            target_arch: std::env::consts::ARCH,
+            compiled_features,
+            platform,
+            config_sections,
+        }
+    }
+
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.compiled_features.iter().any(|f| f.name == name && f.enabled)
+    }
+
+    pub fn platform_available(&self, name: &str) -> bool {
+        self.platform.iter().any(|p| p.name == name && p.available)
+    }
+
+    /// Enabled sections that won't behave as configured
+    pub fn ignored_sections(&self) -> impl Iterator<Item = &ConfigSectionStatus> {
+        self.config_sections.iter().filter(|s| s.status == SectionStatus::Ignored)
+    }
+
+    /// Plain-text rendering for the `capabilities` command
+    pub fn to_text(&self) -> String {
+        let mut out = format!("SecureWatch Agent {} ({}/{})\n\nCompiled features:\n", self.version, self.target_os, self.target_arch);
+        for feature in &self.compiled_features {
+            out.push_str(&format!("  [{}] {:<20} {}\n", if feature.enabled { "x" } else { " " }, feature.name, feature.description));
+        }
+        out.push_str("\nPlatform:\n");
+        for capability in &self.platform {
+            out.push_str(&format!("  [{}] {:<20} {}\n", if capability.available { "x" } else { " " }, capability.name, capability.detail));
+        }
+        out.push_str("\nConfiguration sections:\n");
+        for section in &self.config_sections {
+            out.push_str(&format!("  {:<26} {:<9}", section.section, section.status.as_str()));
+            if let Some(reason) = &section.reason {
+                out.push_str(&format!(" {}", reason));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn compiled_features() -> Vec<CompiledFeature> {
+    vec![
+        CompiledFeature {
+            name: "persistent-storage",
+            enabled: cfg!(feature = "persistent-storage"),
+            description: "SQLite buffer persistence, durable dedup and the local event index",
+        },
+        CompiledFeature {
+            name: "grpc-management",
+            enabled: crate::management::GRPC_ENABLED,
+            description: "gRPC management API",
+        },
+        CompiledFeature {
+            name: "ebpf",
+            enabled: cfg!(feature = "ebpf"),
+            description: "eBPF process, network and file telemetry collector",
+        },
+        CompiledFeature {
+            name: "geoip",
+            enabled: cfg!(feature = "geoip"),
+            description: "GeoIP enrichment from MaxMind databases",
+        },
+        CompiledFeature {
+            name: "pcap",
+            enabled: cfg!(feature = "pcap"),
+            description: "Packet metadata collector (libpcap, or Npcap on Windows)",
+        },
+        CompiledFeature {
+            name: "native-tls-backend",
+            enabled: cfg!(feature = "native-tls-backend"),
+            description: "Platform TLS libraries for the transport",
+        },
+        CompiledFeature {
+            name: "rustls-backend",
+            enabled: cfg!(feature = "rustls-backend"),
+            description: "Pure Rust TLS for the transport",
+        },
+        CompiledFeature {
+            name: "opentelemetry",
+            enabled: cfg!(feature = "opentelemetry"),
+            description: "OpenTelemetry tracing export",
+        },
+    ]
+}
+
+fn detect_platform(config: &AgentConfig) -> Vec<PlatformCapability> {
+    let session = config.collectors.session.as_ref();
+    let logind_dir = session.map(|s| s.logind_sessions_dir.as_str()).unwrap_or(LOGIND_SESSIONS_DIR);
+    let wtmp_path = session.map(|s| s.wtmp_path.as_str()).unwrap_or(WTMP_PATH);
+    let elevated = is_elevated();
+
+    vec![
+        PlatformCapability {
+            name: "windows_event_log",
+            available: cfg!(windows),
+            detail: if cfg!(windows) { "Windows Event Log API".to_string() } else { "not a Windows host".to_string() },
+        },
+        PlatformCapability {
+            name: "logind",
+            available: Path::new(logind_dir).is_dir(),
+            detail: logind_dir.to_string(),
+        },
+        PlatformCapability {
+            name: "wtmp",
+            available: Path::new(wtmp_path).is_file(),
+            detail: wtmp_path.to_string(),
+        },
+        PlatformCapability {
+            name: "procfs",
+            available: Path::new("/proc/self/stat").is_file(),
+            detail: "/proc".to_string(),
+        },
+        PlatformCapability {
+            name: "bpf",
+            available: cfg!(target_os = "linux") && Path::new("/proc/sys/kernel/unprivileged_bpf_disabled").is_file(),
+            detail: "kernel bpf() syscall".to_string(),
+        },
+        PlatformCapability {
+            name: "service_manager_notify",
+            available: std::env::var_os("NOTIFY_SOCKET").is_some(),
+            detail: "systemd NOTIFY_SOCKET".to_string(),
+        },
+        PlatformCapability {
+            name: "elevated",
+            available: elevated,
+            detail: if elevated { "running with administrative privileges".to_string() } else { "running unprivileged".to_string() },
+        },
+    ]
+}
+
+#[cfg(unix)]
+fn is_elevated() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_elevated() -> bool {
+    false
+}
+
+fn section(section: &'static str, enabled: bool, problem: Option<(SectionStatus, String)>) -> ConfigSectionStatus {
+    match (enabled, problem) {
+        (false, _) => ConfigSectionStatus { section, status: SectionStatus::Disabled, reason: None },
+        (true, Some((status, reason))) => ConfigSectionStatus { section, status, reason: Some(reason) },
+        (true, None) => ConfigSectionStatus { section, status: SectionStatus::Active, reason: None },
+    }
+}
+
+fn evaluate_sections(config: &AgentConfig, platform: &[PlatformCapability]) -> Vec<ConfigSectionStatus> {
+    let persistent = cfg!(feature = "persistent-storage");
+    let grpc = crate::management::GRPC_ENABLED;
+    let available = |name: &str| platform.iter().any(|p| p.name == name && p.available);
+    let no_storage = |what: &str| format!("built without persistent-storage; {}", what);
+    let mut sections = Vec::new();
+
+    sections.push(section("transport.otlp", config.transport.protocol.is_otlp(), None));
+    sections.push(section("transport.splunk_hec", config.transport.protocol == TransportProtocol::SplunkHec, None));
+    sections.push(section("transport.elasticsearch", config.transport.protocol == TransportProtocol::Elasticsearch, None));
+    sections.push(section("transport.destinations", !config.transport.destinations.is_empty(), None));
+    sections.push(section("buffer.persistent", config.buffer.persistent,
+        (!persistent).then(|| (SectionStatus::Ignored, no_storage("events are buffered in memory only")))));
+    sections.push(section("dedup", config.dedup.enabled,
+        (!persistent).then(|| (SectionStatus::Degraded, no_storage("the dedup window does not survive restarts")))));
+    sections.push(section("event_index", config.event_index.enabled,
+        (!persistent).then(|| (SectionStatus::Ignored, no_storage("local search is unavailable")))));
+    sections.push(section("shutdown_drain", config.shutdown_drain.enabled,
+        (!persistent).then(|| (SectionStatus::Degraded, no_storage("events not shipped before the deadline are lost")))));
+
+    let management_problem = (!grpc).then(|| (SectionStatus::Ignored, "built without grpc-management".to_string()));
+    sections.push(section("management.tls", config.management.enabled && config.management.tls.enabled, management_problem.clone()));
+    sections.push(section("management", config.management.enabled, management_problem));
+    sections.push(section("live_tail", config.live_tail.enabled,
+        (!cfg!(unix)).then(|| (SectionStatus::Ignored, "the live tail socket needs a Unix platform".to_string()))));
+    sections.push(section("control_socket", config.control_socket.enabled,
+        (!cfg!(unix)).then(|| (SectionStatus::Ignored, "the control socket needs a Unix platform".to_string()))));
+
+    if let Some(syslog) = &config.collectors.syslog {
+        let privileged_port = cfg!(unix) && syslog.port < 1024 && !available("elevated");
+        sections.push(section("collectors.syslog", syslog.enabled, privileged_port.then(|| {
+            (SectionStatus::Degraded, format!("port {} needs elevated privileges or CAP_NET_BIND_SERVICE", syslog.port))
+        })));
+    }
+    if let Some(windows_event) = &config.collectors.windows_event {
+        sections.push(section("collectors.windows_event", windows_event.enabled,
+            (!available("windows_event_log")).then(|| (SectionStatus::Ignored, "Windows Event Log is only available on Windows".to_string()))));
+    }
+    if let Some(file_monitor) = &config.collectors.file_monitor {
+        sections.push(section("collectors.file_monitor", file_monitor.enabled, None));
+    }
+    if let Some(database) = &config.collectors.database {
+        sections.push(section("collectors.database", database.enabled, None));
+    }
+    if let Some(session) = &config.collectors.session {
+        let sources = if session.sources.is_empty() {
+            SessionSourceKind::platform_defaults(&session.logind_sessions_dir)
+        } else {
+            session.sources.clone()
+        };
+        let missing: Vec<&str> = sources.iter()
+            .filter(|source| match source {
+                SessionSourceKind::Wtmp => !available("wtmp"),
+                SessionSourceKind::Logind => !available("logind"),
+                SessionSourceKind::WindowsSecurity => !available("windows_event_log"),
+                SessionSourceKind::Who => cfg!(windows),
+            })
+            .map(|source| source.as_str())
+            .collect();
+        let problem = match missing.len() {
+            0 => None,
+            n if n == sources.len() => Some((SectionStatus::Ignored, format!("no session source available ({})", missing.join(", ")))),
+            _ => Some((SectionStatus::Degraded, format!("unavailable sources: {}", missing.join(", ")))),
+        };
+        sections.push(section("collectors.session", session.enabled, problem));
+    }
+
+    if let Some(ebpf) = &config.collectors.ebpf {
+        let problem = if !cfg!(feature = "ebpf") {
+            Some((SectionStatus::Ignored, "built without the ebpf feature".to_string()))
+        } else if !available("bpf") {
+            Some((SectionStatus::Ignored, "kernel BPF support not detected".to_string()))
+        } else if !available("elevated") {
+            Some((SectionStatus::Degraded, "loading probes needs root or CAP_BPF and CAP_PERFMON".to_string()))
+        } else {
+            None
+        };
+        sections.push(section("collectors.ebpf", ebpf.enabled, problem));
+    }
+    if let Some(etw) = &config.collectors.etw {
+        let problem = if !available("windows_event_log") {
+            Some((SectionStatus::Ignored, "ETW is only available on Windows".to_string()))
+        } else if !available("elevated") {
+            Some((SectionStatus::Degraded, "real-time trace sessions need administrative privileges".to_string()))
+        } else {
+            None
+        };
+        sections.push(section("collectors.etw", etw.enabled, problem));
+    }
+    if let Some(packet_metadata) = &config.collectors.packet_metadata {
+        let problem = if !cfg!(feature = "pcap") {
+            Some((SectionStatus::Ignored, "built without the pcap feature".to_string()))
+        } else if !available("elevated") {
+            Some((SectionStatus::Degraded, "capturing packets needs root or CAP_NET_RAW (administrator on Windows)".to_string()))
+        } else {
+            None
+        };
+        sections.push(section("collectors.packet_metadata", packet_metadata.enabled, problem));
+    }
+    if let Some(container) = &config.collectors.container {
+        let found = container.log_paths.iter()
+            .filter_map(|pattern| ::glob::glob(pattern).ok())
+            .any(|mut paths| paths.next().is_some());
+        sections.push(section("collectors.container", container.enabled,
+            (!found).then(|| (SectionStatus::Degraded, "no container log files match log_paths yet".to_string()))));
+    }
+
+    if let Some(fim) = &config.collectors.fim {
+        let found = fim.paths.iter()
+            .filter_map(|pattern| ::glob::glob(pattern).ok())
+            .any(|mut paths| paths.next().is_some());
+        sections.push(section("collectors.fim", fim.enabled,
+            (!found).then(|| (SectionStatus::Degraded, "none of the FIM paths exist".to_string()))));
+    }
+
+    sections.push(section("process_lineage", config.process_lineage.enabled, None));
+    sections.push(section("field_filter", config.field_filter.enabled, None));
+    sections.push(section("alert_rules", config.alert_rules.enabled, None));
+    let geoip_configured = config.enrichment.enrichers.iter()
+        .any(|enricher| matches!(enricher.kind, crate::enrichment::EnricherKind::Geoip { .. }));
+    let problem = (geoip_configured && !cfg!(feature = "geoip"))
+        .then(|| (SectionStatus::Degraded, "geoip enrichers need a build with the geoip feature".to_string()));
+    sections.push(section("enrichment", config.enrichment.enabled, problem));
+    sections.push(section("sigma", config.sigma.enabled, None));
+    sections.push(section("relay", config.relay.enabled, None));
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_follow_compiled_features() {
+        let mut config = AgentConfig::default();
+        config.buffer.persistent = true;
+        config.event_index.enabled = true;
+        config.management.enabled = true;
+        let report = CapabilityReport::detect(&config);
+
+        let status = |name: &str| report.config_sections.iter().find(|s| s.section == name).unwrap().status;
+        let expected = if cfg!(feature = "persistent-storage") { SectionStatus::Active } else { SectionStatus::Ignored };
+        assert_eq!(status("buffer.persistent"), expected);
+        assert_eq!(status("event_index"), expected);
+        assert_eq!(status("relay"), SectionStatus::Disabled);
+        assert_eq!(report.has_feature("persistent-storage"), cfg!(feature = "persistent-storage"));
+        assert_eq!(report.has_feature("grpc-management"), status("management") == SectionStatus::Active);
+
+        config.collectors.packet_metadata = Some(crate::config::PacketMetadataCollectorConfig { enabled: true, ..Default::default() });
+        let report = CapabilityReport::detect(&config);
+        let packet_metadata = report.config_sections.iter().find(|s| s.section == "collectors.packet_metadata").unwrap();
+        assert_eq!(packet_metadata.status == SectionStatus::Ignored, !cfg!(feature = "pcap"));
+        assert_eq!(report.has_feature("pcap"), cfg!(feature = "pcap"));
+    }
+
+    #[test]
+    fn test_windows_event_section_ignored_off_windows() {
+        let mut config = AgentConfig::default();
+        if let Some(windows_event) = config.collectors.windows_event.as_mut() {
+            windows_event.enabled = true;
+        }
+        let report = CapabilityReport::detect(&config);
+
+        let windows_event = report.config_sections.iter().find(|s| s.section == "collectors.windows_event").unwrap();
+        if cfg!(windows) {
+            assert_eq!(windows_event.status, SectionStatus::Active);
+        } else {
+            assert_eq!(windows_event.status, SectionStatus::Ignored);
+            assert!(report.ignored_sections().any(|s| s.section == "collectors.windows_event"));
+        }
+        assert!(report.to_text().contains("collectors.windows_event"));
+    }
+}
@@ -0,0 +1,244 @@
+// Transport certificate expiry
+// The client certificate the transport presents and the CA certificates it trusts are read from disk and compared
+// with transport.cert_expiry_warning_days. The agent checks them hourly, reports them in the transport stats and
+// sends an alert event for each certificate inside the warning window once a day until it is replaced
+
+use crate::alert_rules::{ALERT_RULES_FIELD, ALERT_SEVERITY_FIELD};
+use crate::errors::TransportError;
+use crate::parsers::ParsedEvent;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use x509_parser::pem::Pem;
+
+pub const CERTIFICATE_EXPIRY_PARSER: &str = "certificate_expiry";
+/// Name the alert events carry in `alert.rules`
+pub const CERTIFICATE_EXPIRY_RULE: &str = "agent_certificate_expiry";
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateRole {
+    /// Presented to the server for mTLS, with any chain in the same file
+    Client,
+    /// Trusted for the server's certificate
+    Ca,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryState {
+    Valid,
+    /// Inside the warning window
+    Expiring,
+    Expired,
+}
+
+impl CertificateRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CertificateRole::Client => "client",
+            CertificateRole::Ca => "ca",
+        }
+    }
+}
+
+impl ExpiryState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExpiryState::Valid => "valid",
+            ExpiryState::Expiring => "expiring",
+            ExpiryState::Expired => "expired",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CertificateStatus {
+    pub role: CertificateRole,
+    pub path: String,
+    pub subject: String,
+    pub issuer: String,
+    pub not_after: DateTime<Utc>,
+    /// Whole days left, negative once expired
+    pub days_until_expiry: i64,
+    pub state: ExpiryState,
+}
+
+/// Every certificate in the PEM file at `path`
+pub fn read_certificates(
+    path: &str,
+    role: CertificateRole,
+    warning_days: u32,
+    now: DateTime<Utc>,
+) -> Result<Vec<CertificateStatus>, TransportError> {
+    let tls_error = |reason: String| TransportError::TlsError {
+        operation: "read_certificate_expiry".to_string(),
+        reason: reason.clone(),
+        certificate_issue: true,
+        source: reason.into(),
+    };
+    let pem = std::fs::read(path).map_err(|e| tls_error(format!("Failed to read certificate file {}: {}", path, e)))?;
+
+    let mut certificates = Vec::new();
+    for block in Pem::iter_from_buffer(&pem) {
+        let block = block.map_err(|e| tls_error(format!("Invalid PEM in {}: {}", path, e)))?;
+        if block.label != "CERTIFICATE" {
+            continue;
+        }
+        let certificate = block.parse_x509().map_err(|e| tls_error(format!("Invalid certificate in {}: {}", path, e)))?;
+        let not_after = Utc.timestamp_opt(certificate.validity().not_after.timestamp(), 0).single()
+            .ok_or_else(|| tls_error(format!("Invalid expiry in {}", path)))?;
+        let days_until_expiry = (not_after - now).num_seconds().div_euclid(86_400);
+        let state = if not_after <= now {
+            ExpiryState::Expired
+        } else if days_until_expiry < warning_days as i64 {
+            ExpiryState::Expiring
+        } else {
+            ExpiryState::Valid
+        };
+        certificates.push(CertificateStatus {
+            role,
+            path: path.to_string(),
+            subject: certificate.subject().to_string(),
+            issuer: certificate.issuer().to_string(),
+            not_after,
+            days_until_expiry,
+            state,
+        });
+    }
+    if certificates.is_empty() {
+        return Err(tls_error(format!("No certificate found in {}", path)));
+    }
+    Ok(certificates)
+}
+
+/// Remembers which certificates were alerted on so each gets one alert per remaining day
+#[derive(Debug, Default)]
+pub struct ExpiryAlerts {
+    alerted: HashMap<(String, String, DateTime<Utc>), i64>,
+}
+
+impl ExpiryAlerts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Certificates inside the warning window that have not been alerted on with their current days remaining
+    pub fn due<'a>(&mut self, certificates: &'a [CertificateStatus]) -> Vec<&'a CertificateStatus> {
+        certificates.iter()
+            .filter(|certificate| certificate.state != ExpiryState::Valid)
+            .filter(|certificate| {
+                let key = (certificate.path.clone(), certificate.subject.clone(), certificate.not_after);
+                self.alerted.insert(key, certificate.days_until_expiry) != Some(certificate.days_until_expiry)
+            })
+            .collect()
+    }
+}
+
+/// Alert event for a certificate inside the warning window
+pub fn alert_event(certificate: &CertificateStatus, agent_id: &str, source: &str) -> ParsedEvent {
+    let severity = match certificate.days_until_expiry {
+        _ if certificate.state == ExpiryState::Expired => "critical",
+        0..=7 => "high",
+        _ => "medium",
+    };
+    let role = match certificate.role {
+        CertificateRole::Client => "client",
+        CertificateRole::Ca => "CA",
+    };
+    let message = match certificate.state {
+        ExpiryState::Expired => format!("Transport {} certificate {} in {} expired on {}",
+                                        role, certificate.subject, certificate.path, certificate.not_after.to_rfc3339()),
+        _ => format!("Transport {} certificate {} in {} expires in {} days on {}",
+                     role, certificate.subject, certificate.path, certificate.days_until_expiry, certificate.not_after.to_rfc3339()),
+    };
+    let fields: HashMap<String, Value> = HashMap::from([
+        ("event.kind".to_string(), json!("alert")),
+        ("event.dataset".to_string(), json!("securewatch.agent.certificate")),
+        ("agent.id".to_string(), json!(agent_id)),
+        ("tls.certificate.role".to_string(), json!(certificate.role)),
+        ("tls.certificate.path".to_string(), json!(certificate.path)),
+        ("x509.subject.distinguished_name".to_string(), json!(certificate.subject)),
+        ("x509.issuer.distinguished_name".to_string(), json!(certificate.issuer)),
+        ("x509.not_after".to_string(), json!(certificate.not_after.to_rfc3339())),
+        ("tls.certificate.days_until_expiry".to_string(), json!(certificate.days_until_expiry)),
+        (ALERT_RULES_FIELD.to_string(), json!([CERTIFICATE_EXPIRY_RULE])),
+        (ALERT_SEVERITY_FIELD.to_string(), json!(severity)),
+    ]);
+    let raw_data = serde_json::to_string(&fields).unwrap_or_default();
+
+    ParsedEvent {
+        timestamp: Utc::now(),
+        source: source.to_string(),
+        level: Some(if certificate.state == ExpiryState::Expired { "error" } else { "warning" }.to_string()),
+        message,
+        fields,
+        raw_data: raw_data.into(),
+        parser_name: CERTIFICATE_EXPIRY_PARSER.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{CertificateParams, KeyPair};
+    use tempfile::TempDir;
+
+    fn certificate_pem(common_name: &str, not_after: DateTime<Utc>) -> String {
+        let mut params = CertificateParams::new(vec![common_name.to_string()]).unwrap();
+        params.distinguished_name.push(rcgen::DnType::CommonName, common_name);
+        params.not_before = rcgen::date_time_ymd(2020, 1, 1);
+        params.not_after = rcgen::date_time_ymd(1970, 1, 1) + Duration::from_secs(not_after.timestamp() as u64);
+        params.self_signed(&KeyPair::generate().unwrap()).unwrap().pem()
+    }
+
+    #[test]
+    fn test_bundle_certificates_are_classified_by_days_left() {
+        let dir = TempDir::new().unwrap();
+        // Certificates carry whole seconds
+        let now = Utc.timestamp_opt(Utc::now().timestamp(), 0).unwrap();
+        let path = dir.path().join("ca.pem");
+        let bundle = [
+            certificate_pem("current-ca", now + chrono::Duration::days(400)),
+            certificate_pem("old-ca", now - chrono::Duration::days(2)),
+            certificate_pem("rolling-ca", now + chrono::Duration::days(10) + chrono::Duration::hours(1)),
+        ].concat();
+        std::fs::write(&path, bundle).unwrap();
+
+        let certificates = read_certificates(path.to_str().unwrap(), CertificateRole::Ca, 30, now).unwrap();
+        let states: Vec<(ExpiryState, i64)> = certificates.iter().map(|c| (c.state, c.days_until_expiry)).collect();
+        assert_eq!(states, vec![(ExpiryState::Valid, 400), (ExpiryState::Expired, -2), (ExpiryState::Expiring, 10)]);
+        assert!(certificates[2].subject.contains("CN=rolling-ca"));
+
+        std::fs::write(&path, "not a certificate").unwrap();
+        assert!(read_certificates(path.to_str().unwrap(), CertificateRole::Ca, 30, now).is_err());
+    }
+
+    #[test]
+    fn test_alerts_once_per_remaining_day() {
+        let status = |days: i64, state: ExpiryState| CertificateStatus {
+            role: CertificateRole::Client,
+            path: "/etc/securewatch/client.pem".to_string(),
+            subject: "CN=agent-1".to_string(),
+            issuer: "CN=SecureWatch CA".to_string(),
+            not_after: DateTime::parse_from_rfc3339("2026-11-01T00:00:00Z").unwrap().with_timezone(&Utc),
+            days_until_expiry: days,
+            state,
+        };
+        let mut alerts = ExpiryAlerts::new();
+        assert!(alerts.due(&[status(40, ExpiryState::Valid)]).is_empty());
+        assert_eq!(alerts.due(&[status(5, ExpiryState::Expiring)]).len(), 1);
+        assert!(alerts.due(&[status(5, ExpiryState::Expiring)]).is_empty());
+
+        let expired = [status(-1, ExpiryState::Expired)];
+        let due = alerts.due(&expired);
+        assert_eq!(due.len(), 1);
+        let event = alert_event(due[0], "agent-1", "securewatch_agent");
+        assert!(crate::alert_rules::is_alert(&event));
+        assert_eq!(event.fields[ALERT_SEVERITY_FIELD], "critical");
+        assert!(event.message.contains("client certificate CN=agent-1"));
+    }
+}
@@ -0,0 +1,324 @@
+// Fault injection for long-running soak tests
+// Dev builds only: the hidden `--chaos` flag installs a process-wide injector that the transport,
+// buffer, config writer and wall-clock consumers consult, so resilience paths get exercised
+
+use crate::errors::ConfigError;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+static INJECTOR: OnceLock<FaultInjector> = OnceLock::new();
+
+/// Fault probabilities and magnitudes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChaosConfig {
+    /// Seed for reproducible runs (random when unset)
+    pub seed: Option<u64>,
+    /// Probability that a transport request fails with a 5xx response
+    pub transport_error_rate: f64,
+    /// Probability that a transport request hangs and then times out
+    pub transport_timeout_rate: f64,
+    /// How long an injected timeout hangs before failing
+    pub transport_timeout_ms: u64,
+    /// Probability that a buffer disk write fails as if the volume were full
+    pub disk_full_rate: f64,
+    /// Probability that a configuration write is truncated mid-file
+    pub config_corruption_rate: f64,
+    /// Minimum time between wall-clock jumps (0 disables clock jumps)
+    pub clock_jump_interval_seconds: u64,
+    /// Largest jump applied in either direction
+    pub max_clock_jump_seconds: u64,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            transport_error_rate: 0.05,
+            transport_timeout_rate: 0.02,
+            transport_timeout_ms: 5000,
+            disk_full_rate: 0.01,
+            config_corruption_rate: 0.1,
+            clock_jump_interval_seconds: 600,
+            max_clock_jump_seconds: 3600,
+        }
+    }
+}
+
+impl ChaosConfig {
+    pub async fn load_from_file(path: &str) -> Result<Self, ConfigError> {
+        let content = tokio::fs::read_to_string(path).await
+            .map_err(|e| ConfigError::Io(e.to_string()))?;
+
+        let config: ChaosConfig = toml::from_str(&content)
+            .map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+        let errors = config.validate();
+        if !errors.is_empty() {
+            return Err(ConfigError::Validation(errors.join("; ")));
+        }
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let rates = [
+            ("transport_error_rate", self.transport_error_rate),
+            ("transport_timeout_rate", self.transport_timeout_rate),
+            ("disk_full_rate", self.disk_full_rate),
+            ("config_corruption_rate", self.config_corruption_rate),
+        ];
+        rates
+            .iter()
+            .filter(|(_, rate)| !(0.0..=1.0).contains(rate))
+            .map(|(name, rate)| format!("Chaos {} must be between 0.0 and 1.0, got {}", name, rate))
+            .collect()
+    }
+}
+
+/// Fault injected into a transport request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportFault {
+    ServerError { status: u16 },
+    Timeout { after: Duration },
+}
+
+/// Counts of injected faults, logged at shutdown to correlate with soak-test results
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChaosStats {
+    pub seed: u64,
+    pub transport_errors: u64,
+    pub transport_timeouts: u64,
+    pub disk_full_errors: u64,
+    pub corrupted_config_writes: u64,
+    pub clock_jumps: u64,
+    pub clock_offset_seconds: i64,
+}
+
+/// Seeded fault source shared by every injection point
+pub struct FaultInjector {
+    config: ChaosConfig,
+    seed: u64,
+    rng_state: AtomicU64,
+    clock_offset_seconds: AtomicI64,
+    last_clock_jump: parking_lot::Mutex<Instant>,
+    transport_errors: AtomicU64,
+    transport_timeouts: AtomicU64,
+    disk_full_errors: AtomicU64,
+    corrupted_config_writes: AtomicU64,
+    clock_jumps: AtomicU64,
+}
+
+impl FaultInjector {
+    pub fn new(config: ChaosConfig) -> Self {
+        let seed = config.seed.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64
+        });
+
+        Self {
+            config,
+            seed,
+            rng_state: AtomicU64::new(seed),
+            clock_offset_seconds: AtomicI64::new(0),
+            last_clock_jump: parking_lot::Mutex::new(Instant::now()),
+            transport_errors: AtomicU64::new(0),
+            transport_timeouts: AtomicU64::new(0),
+            disk_full_errors: AtomicU64::new(0),
+            corrupted_config_writes: AtomicU64::new(0),
+            clock_jumps: AtomicU64::new(0),
+        }
+    }
+
+    /// SplitMix64; lock-free so injection points never contend
+    fn next_u64(&self) -> u64 {
+        let mut z = self.rng_state.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn roll(&self, rate: f64) -> bool {
+        let sample = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        rate > 0.0 && sample < rate
+    }
+
+    pub fn transport_fault(&self) -> Option<TransportFault> {
+        if self.roll(self.config.transport_error_rate) {
+            const STATUSES: [u16; 4] = [500, 502, 503, 504];
+            let status = STATUSES[(self.next_u64() % STATUSES.len() as u64) as usize];
+            self.transport_errors.fetch_add(1, Ordering::Relaxed);
+            warn!("🐒 Chaos: injecting HTTP {} into transport request", status);
+            return Some(TransportFault::ServerError { status });
+        }
+        if self.roll(self.config.transport_timeout_rate) {
+            self.transport_timeouts.fetch_add(1, Ordering::Relaxed);
+            warn!("🐒 Chaos: injecting {}ms transport timeout", self.config.transport_timeout_ms);
+            return Some(TransportFault::Timeout {
+                after: Duration::from_millis(self.config.transport_timeout_ms),
+            });
+        }
+        None
+    }
+
+    pub fn disk_full(&self) -> bool {
+        let inject = self.roll(self.config.disk_full_rate);
+        if inject {
+            self.disk_full_errors.fetch_add(1, Ordering::Relaxed);
+            warn!("🐒 Chaos: simulating full disk on buffer write");
+        }
+        inject
+    }
+
+    /// Truncate the serialized config at a random point, as a crash mid-write would
+    pub fn corrupt_config_write(&self, content: String) -> String {
+        if content.is_empty() || !self.roll(self.config.config_corruption_rate) {
+            return content;
+        }
+        let mut cut = (self.next_u64() % content.len() as u64) as usize;
+        while !content.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        self.corrupted_config_writes.fetch_add(1, Ordering::Relaxed);
+        warn!("🐒 Chaos: truncating configuration write at byte {} of {}", cut, content.len());
+        content[..cut].to_string()
+    }
+
+    /// Wall-clock time with the current injected offset, jumping once per interval
+    pub fn now(&self) -> DateTime<Utc> {
+        if self.config.clock_jump_interval_seconds > 0 && self.config.max_clock_jump_seconds > 0 {
+            let mut last_jump = self.last_clock_jump.lock();
+            if last_jump.elapsed() >= Duration::from_secs(self.config.clock_jump_interval_seconds) {
+                *last_jump = Instant::now();
+                let span = self.config.max_clock_jump_seconds * 2 + 1;
+                let offset = (self.next_u64() % span) as i64 - self.config.max_clock_jump_seconds as i64;
+                self.clock_offset_seconds.store(offset, Ordering::Relaxed);
+                self.clock_jumps.fetch_add(1, Ordering::Relaxed);
+                warn!("🐒 Chaos: wall clock jumped to {:+}s from real time", offset);
+            }
+        }
+        Utc::now() + ChronoDuration::seconds(self.clock_offset_seconds.load(Ordering::Relaxed))
+    }
+
+    pub fn stats(&self) -> ChaosStats {
+        ChaosStats {
+            seed: self.seed,
+            transport_errors: self.transport_errors.load(Ordering::Relaxed),
+            transport_timeouts: self.transport_timeouts.load(Ordering::Relaxed),
+            disk_full_errors: self.disk_full_errors.load(Ordering::Relaxed),
+            corrupted_config_writes: self.corrupted_config_writes.load(Ordering::Relaxed),
+            clock_jumps: self.clock_jumps.load(Ordering::Relaxed),
+            clock_offset_seconds: self.clock_offset_seconds.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Install the process-wide injector; refused in release builds
+pub fn install(config: ChaosConfig) -> Result<(), ConfigError> {
+    if !cfg!(debug_assertions) {
+        return Err(ConfigError::Validation("chaos mode is only available in dev builds".to_string()));
+    }
+    let injector = FaultInjector::new(config);
+    let seed = injector.seed;
+    INJECTOR.set(injector)
+        .map_err(|_| ConfigError::Validation("chaos mode is already installed".to_string()))?;
+
+    warn!("🐒 Chaos mode enabled (seed {}): faults will be injected into transport, buffer, config and clock", seed);
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    INJECTOR.get().is_some()
+}
+
+pub fn transport_fault() -> Option<TransportFault> {
+    INJECTOR.get().and_then(FaultInjector::transport_fault)
+}
+
+pub fn disk_full() -> bool {
+    INJECTOR.get().is_some_and(FaultInjector::disk_full)
+}
+
+pub fn corrupt_config_write(content: String) -> String {
+    match INJECTOR.get() {
+        Some(injector) => injector.corrupt_config_write(content),
+        None => content,
+    }
+}
+
+/// Current wall-clock time; callers whose behavior depends on it use this so clock jumps reach them
+pub fn now() -> DateTime<Utc> {
+    match INJECTOR.get() {
+        Some(injector) => injector.now(),
+        None => Utc::now(),
+    }
+}
+
+pub fn stats() -> Option<ChaosStats> {
+    INJECTOR.get().map(FaultInjector::stats)
+}
+
+/// Log the injected fault totals (called on shutdown)
+pub fn log_summary() {
+    if let Some(stats) = stats() {
+        info!("🐒 Chaos summary (seed {}): {} transport errors, {} timeouts, {} disk-full, {} corrupted config writes, {} clock jumps",
+              stats.seed, stats.transport_errors, stats.transport_timeouts, stats.disk_full_errors,
+              stats.corrupted_config_writes, stats.clock_jumps);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet() -> ChaosConfig {
+        ChaosConfig {
+            seed: Some(42),
+            transport_error_rate: 0.0,
+            transport_timeout_rate: 0.0,
+            disk_full_rate: 0.0,
+            config_corruption_rate: 0.0,
+            clock_jump_interval_seconds: 0,
+            ..ChaosConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_seeded_faults_are_reproducible() {
+        let config = ChaosConfig { transport_error_rate: 0.3, transport_timeout_rate: 0.3, ..quiet() };
+        let a = FaultInjector::new(config.clone());
+        let b = FaultInjector::new(config);
+        let run = |injector: &FaultInjector| (0..200).map(|_| injector.transport_fault()).collect::<Vec<_>>();
+
+        let faults = run(&a);
+        assert_eq!(faults, run(&b));
+        assert!(faults.iter().any(Option::is_some));
+        assert!(faults.iter().any(Option::is_none));
+        assert_eq!(a.stats().transport_errors + a.stats().transport_timeouts,
+                   faults.iter().filter(|f| f.is_some()).count() as u64);
+    }
+
+    #[test]
+    fn test_zero_rates_inject_nothing() {
+        let injector = FaultInjector::new(quiet());
+        assert!((0..1000).all(|_| injector.transport_fault().is_none() && !injector.disk_full()));
+        assert_eq!(injector.corrupt_config_write("a = 1".to_string()), "a = 1");
+        assert!((injector.now() - Utc::now()).num_seconds().abs() <= 1);
+    }
+
+    #[test]
+    fn test_config_corruption_truncates() {
+        let injector = FaultInjector::new(ChaosConfig { config_corruption_rate: 1.0, ..quiet() });
+        let content = "[agent]\nname = \"ünïcode\"\n".to_string();
+        let corrupted = injector.corrupt_config_write(content.clone());
+        assert!(corrupted.len() < content.len());
+        assert!(content.starts_with(&corrupted));
+        assert!(ChaosConfig { disk_full_rate: 1.5, ..quiet() }.validate().len() == 1);
+    }
+}
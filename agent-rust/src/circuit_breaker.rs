@@ -75,6 +75,8 @@ pub struct CircuitBreakerStats {
     pub failure_rate: f64,
     pub uptime_percentage: f64,
     pub state_changes: u32,
+    /// Recent endpoint health from 0.0 (failing) to 1.0 (answering promptly)
+    pub health_score: f64,
 }
 
 /// Request outcome for circuit breaker tracking
@@ -145,6 +147,46 @@ impl SlidingWindow {
     }
 }
 
+/// Weight of the newest outcome in the health score
+const HEALTH_SMOOTHING: f64 = 0.2;
+/// Successes slower than this only count half towards health
+const SLOW_RESPONSE: Duration = Duration::from_secs(5);
+
+/// Exponentially weighted endpoint health
+/// Unlike the sliding window it recovers gradually, so an endpoint that flaps between up and down keeps a low
+/// score and callers can back off from it harder than from one that failed once
+#[derive(Debug, Clone, Copy)]
+pub struct EndpointHealth {
+    score: f64,
+}
+
+impl EndpointHealth {
+    pub fn new() -> Self {
+        Self { score: 1.0 }
+    }
+
+    pub fn record(&mut self, outcome: RequestOutcome, latency: Duration) {
+        let sample = match outcome {
+            RequestOutcome::Success if latency > SLOW_RESPONSE => 0.5,
+            RequestOutcome::Success => 1.0,
+            // Cancellations say nothing about the endpoint
+            RequestOutcome::Cancelled => return,
+            RequestOutcome::Failure | RequestOutcome::Timeout => 0.0,
+        };
+        self.score += HEALTH_SMOOTHING * (sample - self.score);
+    }
+
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+}
+
+impl Default for EndpointHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Internal circuit breaker state tracking
 #[derive(Debug)]
 struct CircuitBreakerInner {
@@ -162,6 +204,7 @@ struct CircuitBreakerInner {
     total_requests: u64,
     total_successes: u64,
     total_failures: u64,
+    health: EndpointHealth,
 }
 
 impl CircuitBreakerInner {
@@ -183,6 +226,7 @@ impl CircuitBreakerInner {
             total_requests: 0,
             total_successes: 0,
             total_failures: 0,
+            health: EndpointHealth::new(),
         }
     }
     
@@ -397,6 +441,7 @@ impl CircuitBreakerInner {
         self.total_successes = 0;
         self.total_failures = 0;
         self.state_changes = 0;
+        self.health = EndpointHealth::new();
         
         debug!("Circuit breaker '{}' statistics reset", self.name);
     }
@@ -420,6 +465,7 @@ impl CircuitBreakerInner {
             failure_rate: self.sliding_window.failure_rate(),
             uptime_percentage,
             state_changes: self.state_changes,
+            health_score: self.health.score(),
         }
     }
 }
@@ -472,6 +518,7 @@ impl CircuitBreaker {
             match &result {
                 Ok(_) => {
                     inner.record_success();
+                    inner.health.record(RequestOutcome::Success, duration);
                     debug!("Circuit breaker '{}' call succeeded in {:?}", inner.name, duration);
                 },
                 Err(error) => {
@@ -490,6 +537,7 @@ impl CircuitBreaker {
                     };
                     
                     inner.record_failure(outcome);
+                    inner.health.record(outcome, duration);
                     debug!("Circuit breaker '{}' call failed in {:?}: {}", inner.name, duration, error);
                 },
             }
@@ -516,6 +564,11 @@ impl CircuitBreaker {
         inner.get_stats()
     }
     
+    /// Current endpoint health from 0.0 to 1.0
+    pub async fn health_score(&self) -> f64 {
+        self.inner.read().await.health.score()
+    }
+    
     /// Manually force the circuit breaker to open state
     pub async fn force_open(&self) {
         let mut inner = self.inner.write().await;
@@ -579,6 +632,12 @@ impl CircuitBreakerRegistry {
             .clone()
     }
     
+    /// Add a breaker created elsewhere, replacing any with the same name
+    pub async fn register(&self, breaker: CircuitBreaker) {
+        let name = breaker.name().await;
+        self.breakers.write().await.insert(name, breaker);
+    }
+    
     /// Get an existing circuit breaker by name
     pub async fn get(&self, name: &str) -> Option<CircuitBreaker> {
         let breakers = self.breakers.read().await;
@@ -609,6 +668,17 @@ impl CircuitBreakerRegistry {
         stats
     }
     
+    /// Health score of every endpoint, worst first
+    pub async fn health_scores(&self) -> Vec<(String, f64)> {
+        let breakers = self.breakers.read().await;
+        let mut scores = Vec::with_capacity(breakers.len());
+        for (name, breaker) in breakers.iter() {
+            scores.push((name.clone(), breaker.health_score().await));
+        }
+        scores.sort_by(|a, b| a.1.total_cmp(&b.1));
+        scores
+    }
+    
     /// Force all circuit breakers to a specific state
     pub async fn force_all_state(&self, target_state: CircuitBreakerState) {
         let breakers = self.breakers.read().await;
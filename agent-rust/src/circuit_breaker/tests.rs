@@ -555,4 +555,32 @@ async fn test_request_outcome_classification() {
     
     assert!(!RequestOutcome::Cancelled.is_success());
     assert!(RequestOutcome::Cancelled.is_failure());
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_health_score_tracks_flapping_endpoint() {
+    let registry = CircuitBreakerRegistry::new();
+    let steady = registry.get_or_create("steady".to_string(), CircuitBreakerConfig::default()).await;
+    let flapping = CircuitBreaker::with_defaults("flapping".to_string());
+    registry.register(flapping.clone()).await;
+    
+    for attempt in 0..10 {
+        let _ = steady.call(|| async { Ok::<_, TransportError>(()) }).await;
+        let _ = flapping.call(|| async move {
+            match attempt % 2 {
+                0 => Err(TransportError::connection_failed("connection reset")),
+                _ => Ok(()),
+            }
+        }).await;
+    }
+    
+    assert_eq!(steady.health_score().await, 1.0);
+    let flapping_score = flapping.health_score().await;
+    assert!(flapping_score > 0.2 && flapping_score < 0.8);
+    
+    let scores = registry.health_scores().await;
+    assert_eq!(scores[0].0, "flapping");
+    assert_eq!(registry.get_all_stats().await.len(), 2);
+    
+    flapping.reset().await;
+    assert_eq!(flapping.stats().await.health_score, 1.0);
+}
@@ -0,0 +1,469 @@
+// Transport client identity: mTLS certificate reload and SPIFFE Workload API X.509-SVIDs
+// Client certificate files are watched and the HTTP clients rebuilt when they change. With SPIFFE configured the
+// agent keeps a FetchX509SVID stream open and rebuilds on every rotation, so short-lived certificates need no restart
+
+use crate::config::TransportConfig;
+use crate::errors::TransportError;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// Environment variable the SPIFFE specification uses for the Workload API address
+pub const SPIFFE_ENDPOINT_ENV: &str = "SPIFFE_ENDPOINT_SOCKET";
+const DEFAULT_SPIFFE_SOCKET: &str = "unix:///tmp/spire-agent/public/api.sock";
+
+/// Quiet period after a file change before the clients are rebuilt
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientIdentityConfig {
+    /// Rebuild the HTTP clients when client_cert_path, client_key_path or ca_cert_path change on disk
+    pub watch_files: bool,
+    /// Take the client certificate from a SPIFFE Workload API instead of client_cert_path/client_key_path
+    pub spiffe: Option<SpiffeConfig>,
+}
+
+impl Default for ClientIdentityConfig {
+    fn default() -> Self {
+        Self {
+            watch_files: true,
+            spiffe: None,
+        }
+    }
+}
+
+impl ClientIdentityConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(spiffe) = &self.spiffe {
+            if let Err(e) = spiffe.socket_path() {
+                errors.push(e);
+            }
+            if spiffe.spiffe_id.as_deref().is_some_and(|id| !id.starts_with("spiffe://")) {
+                errors.push("spiffe_id must start with spiffe://".to_string());
+            }
+            if spiffe.reconnect_delay_seconds == 0 {
+                errors.push("SPIFFE reconnect_delay_seconds must be greater than 0".to_string());
+            }
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpiffeConfig {
+    /// Workload API address (`unix:///path`); defaults to $SPIFFE_ENDPOINT_SOCKET, then the SPIRE agent's socket
+    pub endpoint_socket: Option<String>,
+    /// SVID to present when the workload is issued several; defaults to the first
+    pub spiffe_id: Option<String>,
+    /// Trust the SVID's bundle for the server certificate in addition to the configured roots
+    pub trust_bundle: bool,
+    /// Delay before reconnecting after the Workload API stream ends
+    pub reconnect_delay_seconds: u64,
+}
+
+impl Default for SpiffeConfig {
+    fn default() -> Self {
+        Self {
+            endpoint_socket: None,
+            spiffe_id: None,
+            trust_bundle: true,
+            reconnect_delay_seconds: 5,
+        }
+    }
+}
+
+impl SpiffeConfig {
+    pub fn socket_path(&self) -> Result<PathBuf, String> {
+        let address = self.endpoint_socket.clone()
+            .or_else(|| std::env::var(SPIFFE_ENDPOINT_ENV).ok())
+            .unwrap_or_else(|| DEFAULT_SPIFFE_SOCKET.to_string());
+        address.strip_prefix("unix://")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .ok_or_else(|| format!("SPIFFE endpoint '{}' must be a unix:// socket with an absolute path", address))
+    }
+}
+
+/// Client certificate chain and key, plus extra trust roots, all PEM-encoded
+#[derive(Clone)]
+pub struct IdentityMaterial {
+    pub cert_pem: Vec<u8>,
+    pub key_pem: Vec<u8>,
+    /// Individual CA certificates to trust for the server
+    pub ca_pem: Vec<Vec<u8>>,
+    pub spiffe_id: Option<String>,
+}
+
+// The private key stays out of logs
+impl std::fmt::Debug for IdentityMaterial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdentityMaterial")
+            .field("spiffe_id", &self.spiffe_id)
+            .field("ca_certificates", &self.ca_pem.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Certificate files of the primary transport and its destinations
+pub fn identity_files(config: &TransportConfig) -> Vec<PathBuf> {
+    let destinations = config.destinations.iter()
+        .flat_map(|d| [&d.client_cert_path, &d.client_key_path, &d.ca_cert_path]);
+    let mut seen = HashSet::new();
+    [&config.client_cert_path, &config.client_key_path, &config.ca_cert_path].into_iter()
+        .chain(destinations)
+        .flatten()
+        .map(PathBuf::from)
+        .filter(|path| seen.insert(path.clone()))
+        .collect()
+}
+
+/// Watches certificate files for replacement, including Kubernetes-style `..data` symlink swaps
+pub struct IdentityFileWatcher {
+    _watcher: notify::RecommendedWatcher,
+    changes: mpsc::Receiver<PathBuf>,
+}
+
+impl IdentityFileWatcher {
+    pub fn new(files: &[PathBuf]) -> Result<Self, notify::Error> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let (sender, changes) = mpsc::channel(64);
+        let watched: HashSet<PathBuf> = files.iter().cloned().collect();
+        let directories: HashSet<PathBuf> = files.iter()
+            .filter_map(|file| file.parent().map(Path::to_path_buf))
+            .collect();
+        let mut watcher = notify::recommended_watcher(move |result: Result<notify::Event, notify::Error>| {
+            let Ok(event) = result else { return };
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            let relevant = event.paths.iter().find(|path| {
+                watched.contains(*path)
+                    || path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with(".."))
+            });
+            if let Some(path) = relevant {
+                let _ = sender.try_send(path.clone());
+            }
+        })?;
+        for directory in &directories {
+            let directory = if directory.as_os_str().is_empty() { Path::new(".") } else { directory };
+            watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        }
+        Ok(Self { _watcher: watcher, changes })
+    }
+
+    /// Wait for the next change, coalescing the burst of events one rotation produces
+    pub async fn changed(&mut self) -> Option<PathBuf> {
+        let first = self.changes.recv().await?;
+        while let Ok(Some(_)) = tokio::time::timeout(RELOAD_DEBOUNCE, self.changes.recv()).await {}
+        Some(first)
+    }
+}
+
+fn spiffe_error(reason: String) -> TransportError {
+    TransportError::TlsError {
+        operation: "spiffe_fetch_x509_svid".to_string(),
+        source: Box::new(std::io::Error::other(reason.clone())),
+        reason,
+        certificate_issue: true,
+    }
+}
+
+/// Fetch the current X.509-SVID once
+pub async fn fetch_x509_svid(config: &SpiffeConfig) -> Result<IdentityMaterial, TransportError> {
+    let mut first = None;
+    watch_x509_svids(config, |identity| {
+        first = Some(identity);
+        false
+    }).await?;
+    first.ok_or_else(|| spiffe_error("Workload API closed the stream without an SVID".to_string()))
+}
+
+/// Stream X.509-SVID updates from the Workload API until the stream ends or `on_update` returns false
+pub async fn watch_x509_svids<F>(config: &SpiffeConfig, on_update: F) -> Result<(), TransportError>
+where
+    F: FnMut(IdentityMaterial) -> bool,
+{
+    let socket = config.socket_path().map_err(spiffe_error)?;
+    stream_x509_svids(&socket, config, on_update).await.map_err(spiffe_error)
+}
+
+#[cfg(unix)]
+async fn stream_x509_svids<F>(socket: &Path, config: &SpiffeConfig, mut on_update: F) -> Result<(), String>
+where
+    F: FnMut(IdentityMaterial) -> bool,
+{
+    let stream = tokio::net::UnixStream::connect(socket).await
+        .map_err(|e| format!("failed to connect to {}: {}", socket.display(), e))?;
+    let (client, connection) = h2::client::handshake(stream).await
+        .map_err(|e| format!("HTTP/2 handshake failed: {}", e))?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            debug!("SPIFFE Workload API connection closed: {}", e);
+        }
+    });
+
+    let mut client = client.ready().await.map_err(|e| e.to_string())?;
+    let request = http::Request::post("http://localhost/SpiffeWorkloadAPI/FetchX509SVID")
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        // Required by the Workload API to reject requests from browsers and proxies
+        .header("workload.spiffe.io", "true")
+        .body(())
+        .map_err(|e| e.to_string())?;
+    let (response, mut send) = client.send_request(request, false).map_err(|e| e.to_string())?;
+    send.send_data(crate::otlp::grpc_frame(&[]).into(), true).map_err(|e| e.to_string())?;
+
+    let response = response.await.map_err(|e| format!("FetchX509SVID failed: {}", e))?;
+    check_grpc_status(response.headers())?;
+    let mut body = response.into_body();
+    let mut buffer = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| format!("FetchX509SVID stream failed: {}", e))?;
+        let _ = body.flow_control().release_capacity(chunk.len());
+        buffer.extend_from_slice(&chunk);
+        while let Some(message) = take_grpc_message(&mut buffer)? {
+            match decode_x509_svid_response(&message, config) {
+                Ok(identity) => {
+                    if !on_update(identity) {
+                        return Ok(());
+                    }
+                }
+                Err(e) => warn!("⚠️ Ignoring unusable X.509-SVID update: {}", e),
+            }
+        }
+    }
+    if let Ok(Some(trailers)) = body.trailers().await {
+        check_grpc_status(&trailers)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn stream_x509_svids<F>(_socket: &Path, _config: &SpiffeConfig, _on_update: F) -> Result<(), String>
+where
+    F: FnMut(IdentityMaterial) -> bool,
+{
+    Err("the SPIFFE Workload API is only supported over Unix domain sockets".to_string())
+}
+
+#[cfg(unix)]
+fn check_grpc_status(headers: &http::HeaderMap) -> Result<(), String> {
+    match headers.get("grpc-status").and_then(|s| s.to_str().ok()) {
+        None | Some("0") => Ok(()),
+        Some(code) => {
+            let message = headers.get("grpc-message").and_then(|m| m.to_str().ok()).unwrap_or_default();
+            Err(format!("Workload API returned gRPC status {}: {}", code, message))
+        }
+    }
+}
+
+/// Remove one complete length-prefixed message from the front of `buffer`
+fn take_grpc_message(buffer: &mut Vec<u8>) -> Result<Option<Vec<u8>>, String> {
+    if buffer.len() < 5 {
+        return Ok(None);
+    }
+    if buffer[0] != 0 {
+        return Err("compressed gRPC messages are not supported".to_string());
+    }
+    let length = u32::from_be_bytes([buffer[1], buffer[2], buffer[3], buffer[4]]) as usize;
+    if buffer.len() < 5 + length {
+        return Ok(None);
+    }
+    let message = buffer[5..5 + length].to_vec();
+    buffer.drain(..5 + length);
+    Ok(Some(message))
+}
+
+/// Length-delimited fields of a protobuf message; other wire types are skipped
+fn protobuf_fields(mut message: &[u8]) -> Result<Vec<(u64, &[u8])>, String> {
+    fn varint(input: &mut &[u8]) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = input.split_first().ok_or("truncated varint")?;
+            *input = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("varint too long".to_string())
+    }
+
+    let mut fields = Vec::new();
+    while !message.is_empty() {
+        let key = varint(&mut message)?;
+        let skip = match key & 7 {
+            0 => {
+                varint(&mut message)?;
+                0
+            }
+            1 => 8,
+            2 => {
+                let length = varint(&mut message)? as usize;
+                let value = message.get(..length).ok_or("truncated field")?;
+                fields.push((key >> 3, value));
+                length
+            }
+            5 => 4,
+            wire_type => return Err(format!("unsupported wire type {}", wire_type)),
+        };
+        message = message.get(skip..).ok_or("truncated field")?;
+    }
+    Ok(fields)
+}
+
+/// X509SVIDResponse: `repeated X509SVID svids = 1`, where X509SVID is
+/// `spiffe_id = 1, x509_svid = 2 (DER chain), x509_svid_key = 3 (PKCS#8 DER), bundle = 4 (DER certificates)`
+fn decode_x509_svid_response(message: &[u8], config: &SpiffeConfig) -> Result<IdentityMaterial, String> {
+    let mut candidates = Vec::new();
+    for (_, svid) in protobuf_fields(message)?.into_iter().filter(|(field, _)| *field == 1) {
+        let (mut spiffe_id, mut chain, mut key, mut bundle) = (String::new(), &[][..], &[][..], &[][..]);
+        for (field, value) in protobuf_fields(svid)? {
+            match field {
+                1 => spiffe_id = String::from_utf8_lossy(value).into_owned(),
+                2 => chain = value,
+                3 => key = value,
+                4 => bundle = value,
+                _ => {}
+            }
+        }
+        candidates.push((spiffe_id, chain, key, bundle));
+    }
+
+    let (spiffe_id, chain, key, bundle) = match &config.spiffe_id {
+        Some(wanted) => candidates.into_iter().find(|(id, ..)| id == wanted)
+            .ok_or_else(|| format!("no SVID issued for {}", wanted))?,
+        None => candidates.into_iter().next().ok_or("response contains no SVIDs")?,
+    };
+    if key.is_empty() {
+        return Err(format!("SVID {} has no private key", spiffe_id));
+    }
+
+    let cert_pem = split_der_certificates(chain)?.into_iter()
+        .flat_map(|der| pem("CERTIFICATE", der).into_bytes())
+        .collect::<Vec<u8>>();
+    if cert_pem.is_empty() {
+        return Err(format!("SVID {} has no certificate", spiffe_id));
+    }
+    let ca_pem = if config.trust_bundle {
+        split_der_certificates(bundle)?.into_iter().map(|der| pem("CERTIFICATE", der).into_bytes()).collect()
+    } else {
+        Vec::new()
+    };
+    Ok(IdentityMaterial {
+        cert_pem,
+        key_pem: pem("PRIVATE KEY", key).into_bytes(),
+        ca_pem,
+        spiffe_id: Some(spiffe_id),
+    })
+}
+
+/// Split concatenated DER certificates on their outer SEQUENCE headers
+fn split_der_certificates(mut der: &[u8]) -> Result<Vec<&[u8]>, String> {
+    let mut certificates = Vec::new();
+    while !der.is_empty() {
+        if der[0] != 0x30 || der.len() < 2 {
+            return Err("malformed DER certificate".to_string());
+        }
+        let (header, length) = match der[1] {
+            short @ 0..=0x7f => (2, short as usize),
+            long => {
+                let octets = (long & 0x7f) as usize;
+                if !(1..=4).contains(&octets) || der.len() < 2 + octets {
+                    return Err("malformed DER certificate length".to_string());
+                }
+                (2 + octets, der[2..2 + octets].iter().fold(0usize, |n, &b| n << 8 | b as usize))
+            }
+        };
+        let end = header + length;
+        certificates.push(der.get(..end).ok_or("truncated DER certificate")?);
+        der = &der[end..];
+    }
+    Ok(certificates)
+}
+
+fn pem(label: &str, der: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn length_delimited(field: u8, value: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![field << 3 | 2, value.len() as u8];
+        encoded.extend_from_slice(value);
+        encoded
+    }
+
+    #[test]
+    fn test_decodes_selected_svid_from_workload_api_response() {
+        let cert = [0x30, 0x03, 0x02, 0x01, 0x01];
+        let intermediate = [0x30, 0x03, 0x02, 0x01, 0x02];
+        let svid = |id: &str, key: &[u8]| {
+            let mut svid = length_delimited(1, id.as_bytes());
+            svid.extend(length_delimited(2, &[cert, intermediate].concat()));
+            svid.extend(length_delimited(3, key));
+            svid.extend(length_delimited(4, &cert));
+            svid
+        };
+        let mut response = length_delimited(1, &svid("spiffe://example.org/other", b"k1"));
+        response.extend(length_delimited(1, &svid("spiffe://example.org/agent", b"k2")));
+        response.extend([2 << 3, 7]); // unrelated varint field
+
+        let mut buffer = crate::otlp::grpc_frame(&response);
+        buffer.extend_from_slice(&[0, 0, 0]);
+        let message = take_grpc_message(&mut buffer).unwrap().unwrap();
+        assert_eq!(buffer, vec![0, 0, 0]);
+        assert!(take_grpc_message(&mut buffer).unwrap().is_none());
+
+        let config = SpiffeConfig { spiffe_id: Some("spiffe://example.org/agent".to_string()), ..Default::default() };
+        let identity = decode_x509_svid_response(&message, &config).unwrap();
+        assert_eq!(identity.spiffe_id.as_deref(), Some("spiffe://example.org/agent"));
+        assert_eq!(String::from_utf8(identity.cert_pem).unwrap().matches("BEGIN CERTIFICATE").count(), 2);
+        assert_eq!(identity.key_pem, pem("PRIVATE KEY", b"k2").into_bytes());
+        assert_eq!(identity.ca_pem.len(), 1);
+
+        let first = decode_x509_svid_response(&message, &SpiffeConfig { trust_bundle: false, ..Default::default() }).unwrap();
+        assert_eq!((first.spiffe_id.as_deref(), first.ca_pem.len()), (Some("spiffe://example.org/other"), 0));
+        let missing = SpiffeConfig { spiffe_id: Some("spiffe://example.org/none".to_string()), ..Default::default() };
+        assert!(decode_x509_svid_response(&message, &missing).is_err());
+    }
+
+    #[test]
+    fn test_identity_files_and_spiffe_validation() {
+        let mut config = crate::config::AgentConfig::default().transport;
+        config.client_cert_path = Some("/etc/securewatch/client.crt".to_string());
+        config.client_key_path = Some("/etc/securewatch/client.key".to_string());
+        config.destinations.push(crate::destinations::DestinationConfig {
+            client_cert_path: Some("/etc/securewatch/client.crt".to_string()),
+            ca_cert_path: Some("/etc/securewatch/siem-ca.pem".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(identity_files(&config).len(), 3);
+
+        let spiffe = |endpoint: &str| ClientIdentityConfig {
+            watch_files: true,
+            spiffe: Some(SpiffeConfig { endpoint_socket: Some(endpoint.to_string()), ..Default::default() }),
+        };
+        assert!(spiffe("unix:///run/spire/sockets/agent.sock").validate().is_empty());
+        assert_eq!(spiffe("tcp://127.0.0.1:8081").validate().len(), 1);
+        assert_eq!(split_der_certificates(&[0x30, 0x82, 0x00, 0x01, 0x05]).unwrap().len(), 1);
+        assert!(split_der_certificates(&[0x30, 0x05, 0x01]).is_err());
+    }
+}
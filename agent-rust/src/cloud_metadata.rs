@@ -0,0 +1,266 @@
+// Cloud instance identity from the EC2, GCE and Azure instance metadata services
+// Every provider is probed at once on the link-local metadata address; the first that answers (in the configured
+// order of preference) describes the host. Off-cloud hosts simply get no answer within the timeout
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::debug;
+
+const METADATA_ADDRESS: &str = "http://169.254.169.254";
+/// Instance tags read from IMDS; larger tag sets are cut here
+const MAX_AWS_TAGS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloudProvider {
+    Aws,
+    Gcp,
+    Azure,
+}
+
+impl CloudProvider {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Aws, Self::Gcp, Self::Azure]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Aws => "aws",
+            Self::Gcp => "gcp",
+            Self::Azure => "azure",
+        }
+    }
+}
+
+/// What the metadata service says about this instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloudMetadata {
+    pub provider: CloudProvider,
+    /// AWS account, GCP project or Azure subscription
+    pub account_id: Option<String>,
+    pub instance_id: Option<String>,
+    pub instance_name: Option<String>,
+    pub region: Option<String>,
+    pub availability_zone: Option<String>,
+    pub machine_type: Option<String>,
+    /// Instance tags (AWS only when tags are allowed in instance metadata); GCE exposes no labels here
+    pub tags: BTreeMap<String, String>,
+}
+
+impl CloudMetadata {
+    /// ECS `cloud.*` fields
+    pub fn fields(&self) -> Vec<(String, Value)> {
+        let mut fields = vec![("cloud.provider".to_string(), json!(self.provider.as_str()))];
+        let optional = [
+            ("cloud.account.id", &self.account_id),
+            ("cloud.instance.id", &self.instance_id),
+            ("cloud.instance.name", &self.instance_name),
+            ("cloud.region", &self.region),
+            ("cloud.availability_zone", &self.availability_zone),
+            ("cloud.machine.type", &self.machine_type),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                fields.push((name.to_string(), json!(value)));
+            }
+        }
+        if self.provider == CloudProvider::Gcp {
+            if let Some(project) = &self.account_id {
+                fields.push(("cloud.project.id".to_string(), json!(project)));
+            }
+        }
+        for (key, value) in &self.tags {
+            fields.push((format!("cloud.tags.{}", key), json!(value)));
+        }
+        fields
+    }
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// EC2 instance identity document (`/latest/dynamic/instance-identity/document`)
+pub fn parse_aws(identity: &Value, tags: BTreeMap<String, String>) -> Option<CloudMetadata> {
+    Some(CloudMetadata {
+        provider: CloudProvider::Aws,
+        account_id: text(&identity["accountId"]),
+        instance_id: Some(text(&identity["instanceId"])?),
+        instance_name: tags.get("Name").cloned(),
+        region: text(&identity["region"]),
+        availability_zone: text(&identity["availabilityZone"]),
+        machine_type: text(&identity["instanceType"]),
+        tags,
+    })
+}
+
+/// GCE instance metadata (`/computeMetadata/v1/instance/?recursive=true`); zone and machine type are resource paths
+pub fn parse_gcp(instance: &Value, project_id: Option<String>) -> Option<CloudMetadata> {
+    let last_segment = |value: &Value| value.as_str().and_then(|path| path.rsplit('/').next()).map(str::to_string);
+    let zone = last_segment(&instance["zone"]);
+    Some(CloudMetadata {
+        provider: CloudProvider::Gcp,
+        account_id: project_id,
+        instance_id: Some(text(&instance["id"])?),
+        instance_name: text(&instance["name"]),
+        // us-central1-a is in us-central1
+        region: zone.as_deref().and_then(|zone| zone.rsplit_once('-')).map(|(region, _)| region.to_string()),
+        availability_zone: zone,
+        machine_type: last_segment(&instance["machineType"]),
+        tags: BTreeMap::new(),
+    })
+}
+
+/// Azure instance metadata (`/metadata/instance`)
+pub fn parse_azure(instance: &Value) -> Option<CloudMetadata> {
+    let compute = &instance["compute"];
+    let tags = compute["tagsList"]
+        .as_array()
+        .map(|tags| tags.iter().filter_map(|tag| Some((text(&tag["name"])?, text(&tag["value"]).unwrap_or_default()))).collect())
+        .unwrap_or_default();
+    Some(CloudMetadata {
+        provider: CloudProvider::Azure,
+        account_id: text(&compute["subscriptionId"]),
+        instance_id: Some(text(&compute["vmId"])?),
+        instance_name: text(&compute["name"]),
+        region: text(&compute["location"]),
+        availability_zone: text(&compute["zone"]),
+        machine_type: text(&compute["vmSize"]),
+        tags,
+    })
+}
+
+/// Query the metadata services, returning the first provider in `providers` order that answered
+pub async fn detect(providers: &[CloudProvider], timeout: Duration) -> Option<CloudMetadata> {
+    // Metadata services are link-local and must never go through a configured proxy
+    let client = match reqwest::Client::builder().timeout(timeout).no_proxy().build() {
+        Ok(client) => client,
+        Err(e) => {
+            debug!("Cannot create metadata client: {}", e);
+            return None;
+        }
+    };
+    let probes = providers.iter().map(|provider| {
+        let client = &client;
+        async move {
+            let result = match provider {
+                CloudProvider::Aws => query_aws(client).await,
+                CloudProvider::Gcp => query_gcp(client).await,
+                CloudProvider::Azure => query_azure(client).await,
+            };
+            if let Err(e) = &result {
+                debug!("No {} instance metadata: {}", provider.as_str(), e);
+            }
+            result.ok().flatten()
+        }
+    });
+    futures::future::join_all(probes).await.into_iter().flatten().next()
+}
+
+async fn query_aws(client: &reqwest::Client) -> Result<Option<CloudMetadata>, reqwest::Error> {
+    // IMDSv2 session token; instances that only allow IMDSv1 answer without one
+    let response = client
+        .put(format!("{}/latest/api/token", METADATA_ADDRESS))
+        .header("X-aws-ec2-metadata-token-ttl-seconds", "300")
+        .send()
+        .await?;
+    let token = match response.status().is_success() {
+        true => response.text().await.ok(),
+        false => None,
+    };
+    let get = |path: String| {
+        let request = client.get(format!("{}{}", METADATA_ADDRESS, path));
+        match &token {
+            Some(token) => request.header("X-aws-ec2-metadata-token", token),
+            None => request,
+        }
+    };
+
+    let identity: Value = get("/latest/dynamic/instance-identity/document".to_string())
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    // Tags are only readable when the instance allows tags in metadata; a 404 leaves them out
+    let mut tags = BTreeMap::new();
+    if let Ok(response) = get("/latest/meta-data/tags/instance".to_string()).send().await {
+        if response.status().is_success() {
+            let keys = response.text().await.unwrap_or_default();
+            for key in keys.lines().filter(|key| !key.is_empty()).take(MAX_AWS_TAGS) {
+                let value = get(format!("/latest/meta-data/tags/instance/{}", key)).send().await?;
+                if value.status().is_success() {
+                    tags.insert(key.to_string(), value.text().await.unwrap_or_default());
+                }
+            }
+        }
+    }
+    Ok(parse_aws(&identity, tags))
+}
+
+async fn query_gcp(client: &reqwest::Client) -> Result<Option<CloudMetadata>, reqwest::Error> {
+    let get = |path: &str| client.get(format!("{}/computeMetadata/v1/{}", METADATA_ADDRESS, path)).header("Metadata-Flavor", "Google");
+    let instance: Value = get("instance/?recursive=true").send().await?.error_for_status()?.json().await?;
+    let project_id = match get("project/project-id").send().await?.error_for_status() {
+        Ok(response) => response.text().await.ok(),
+        Err(_) => None,
+    };
+    Ok(parse_gcp(&instance, project_id))
+}
+
+async fn query_azure(client: &reqwest::Client) -> Result<Option<CloudMetadata>, reqwest::Error> {
+    let instance: Value = client
+        .get(format!("{}/metadata/instance?api-version=2021-02-01", METADATA_ADDRESS))
+        .header("Metadata", "true")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(parse_azure(&instance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_documents() {
+        let identity = json!({
+            "accountId": "123456789012", "instanceId": "i-0abc", "region": "eu-west-1",
+            "availabilityZone": "eu-west-1b", "instanceType": "m5.large"
+        });
+        let tags = BTreeMap::from([("Name".to_string(), "web-1".to_string()), ("team".to_string(), "payments".to_string())]);
+        let aws = parse_aws(&identity, tags).unwrap();
+        assert_eq!(aws.instance_name.as_deref(), Some("web-1"));
+        let fields: BTreeMap<String, Value> = aws.fields().into_iter().collect();
+        assert_eq!(fields["cloud.provider"], "aws");
+        assert_eq!(fields["cloud.account.id"], "123456789012");
+        assert_eq!(fields["cloud.availability_zone"], "eu-west-1b");
+        assert_eq!(fields["cloud.tags.team"], "payments");
+        assert!(parse_aws(&json!({}), BTreeMap::new()).is_none());
+
+        let gcp = parse_gcp(&json!({
+            "id": 4520031799277581759u64, "name": "worker-3",
+            "zone": "projects/998877/zones/us-central1-a", "machineType": "projects/998877/machineTypes/e2-medium"
+        }), Some("acme-prod".to_string())).unwrap();
+        assert_eq!(gcp.instance_id.as_deref(), Some("4520031799277581759"));
+        assert_eq!((gcp.region.as_deref(), gcp.machine_type.as_deref()), (Some("us-central1"), Some("e2-medium")));
+        assert!(gcp.fields().contains(&("cloud.project.id".to_string(), json!("acme-prod"))));
+
+        let azure = parse_azure(&json!({ "compute": {
+            "subscriptionId": "8d10da13-8125-4ba9-a717-bf7490507b3d", "vmId": "02aab8a4-74ef-476e-8182-f6d2ba4166a6",
+            "name": "vm-eus-01", "location": "eastus", "zone": "1", "vmSize": "Standard_D2s_v3",
+            "tagsList": [{ "name": "env", "value": "prod" }]
+        }})).unwrap();
+        assert_eq!(azure.region.as_deref(), Some("eastus"));
+        assert_eq!(azure.tags["env"], "prod");
+    }
+}
@@ -0,0 +1,330 @@
+// Scheduled command collector
+// Runs allow-listed programs and scripts (`netstat -an`, `who`, inventory scripts) on their own intervals and sends
+// their output into the pipeline under a configurable source, so the parsing engine handles it like any other log.
+// Runs are bounded by a timeout, an output cap and a concurrency limit, and are skipped while the resource manager
+// refuses background work.
+
+use crate::collectors::{Collector, RawLogEvent};
+use crate::config::{CommandCollectorConfig, ScheduledCommandConfig};
+use crate::errors::CollectorError;
+use crate::resource_management::ResourceManager;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::{mpsc, watch, Semaphore};
+use tracing::{debug, info, warn};
+
+/// Default source of command output
+pub const COMMAND_SOURCE: &str = "command";
+
+/// Resource manager rate-limit category charged for each run
+const RESOURCE_CATEGORY: &str = "background_tasks";
+
+/// Most of stderr kept for the log when a command fails
+const MAX_STDERR_BYTES: usize = 4096;
+
+/// How a command's output becomes events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandOutputMode {
+    /// One event per non-empty output line
+    #[default]
+    Lines,
+    /// The whole output as a single event
+    Whole,
+}
+
+/// Output of one finished run
+#[derive(Debug)]
+struct CommandRun {
+    stdout: String,
+    stderr: String,
+    exit_code: Option<i32>,
+    truncated: bool,
+    duration: Duration,
+}
+
+/// Read up to `limit` bytes and discard the rest, so the child never blocks on a full pipe
+async fn read_capped<R: AsyncRead + Unpin>(mut reader: R, limit: usize) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut kept = Vec::new();
+    (&mut reader).take(limit as u64).read_to_end(&mut kept).await?;
+    let discarded = tokio::io::copy(&mut reader, &mut tokio::io::sink()).await?;
+    Ok((kept, discarded > 0))
+}
+
+async fn run_command(command: &ScheduledCommandConfig, max_output_bytes: usize) -> Result<CommandRun, String> {
+    let started = std::time::Instant::now();
+    let mut process = tokio::process::Command::new(&command.program);
+    process.args(&command.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Dropping the run on timeout or shutdown must not leave the process behind
+        .kill_on_drop(true);
+    if let Some(directory) = &command.working_directory {
+        process.current_dir(directory);
+    }
+    let mut child = process.spawn().map_err(|e| format!("cannot start {}: {}", command.program, e))?;
+    let stdout = child.stdout.take().ok_or("stdout not captured")?;
+    let stderr = child.stderr.take().ok_or("stderr not captured")?;
+
+    let (stdout, stderr, status) = tokio::try_join!(
+        read_capped(stdout, max_output_bytes),
+        read_capped(stderr, MAX_STDERR_BYTES),
+        child.wait(),
+    ).map_err(|e| format!("reading output of {} failed: {}", command.program, e))?;
+
+    Ok(CommandRun {
+        stdout: String::from_utf8_lossy(&stdout.0).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr.0).trim().to_string(),
+        exit_code: status.code(),
+        truncated: stdout.1,
+        duration: started.elapsed(),
+    })
+}
+
+/// Events for one run's output, tagged with the command and how it ended
+fn output_events(command: &ScheduledCommandConfig, run: &CommandRun) -> Vec<RawLogEvent> {
+    let timestamp = chrono::Utc::now();
+    let mut metadata = HashMap::from([
+        ("command".to_string(), command.name.clone()),
+        ("program".to_string(), command.program.clone()),
+        ("exit_code".to_string(), run.exit_code.map(|code| code.to_string()).unwrap_or_else(|| "signal".to_string())),
+        ("duration_ms".to_string(), run.duration.as_millis().to_string()),
+    ]);
+    if run.truncated {
+        metadata.insert("truncated".to_string(), "true".to_string());
+    }
+    let event = |raw_data: &str, metadata: HashMap<String, String>| RawLogEvent {
+        timestamp,
+        source: command.source.clone(),
+        raw_data: raw_data.into(),
+        metadata,
+    };
+
+    match command.output {
+        CommandOutputMode::Whole => {
+            let output = run.stdout.trim_end();
+            if output.is_empty() { Vec::new() } else { vec![event(output, metadata)] }
+        }
+        CommandOutputMode::Lines => run.stdout.lines()
+            .enumerate()
+            .skip(command.skip_lines)
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                let mut metadata = metadata.clone();
+                metadata.insert("line_number".to_string(), (index + 1).to_string());
+                event(line.trim_end(), metadata)
+            })
+            .collect(),
+    }
+}
+
+pub struct CommandCollector {
+    config: CommandCollectorConfig,
+    event_sender: mpsc::Sender<RawLogEvent>,
+    resource_manager: Option<Arc<ResourceManager>>,
+    shutdown_sender: Option<watch::Sender<bool>>,
+    running: bool,
+}
+
+impl CommandCollector {
+    pub fn new(config: CommandCollectorConfig, event_sender: mpsc::Sender<RawLogEvent>) -> Self {
+        Self {
+            config,
+            event_sender,
+            resource_manager: None,
+            shutdown_sender: None,
+            running: false,
+        }
+    }
+
+    /// Skip runs whenever the resource manager denies a permit (rate limits, memory pressure, CPU throttling)
+    pub fn set_resource_manager(&mut self, resource_manager: Arc<ResourceManager>) {
+        self.resource_manager = Some(resource_manager);
+    }
+
+    pub fn command_names(&self) -> Vec<&str> {
+        self.config.commands.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    /// Run `command` on its interval until shutdown
+    async fn schedule(
+        command: ScheduledCommandConfig,
+        max_output_bytes: usize,
+        slots: Arc<Semaphore>,
+        resource_manager: Option<Arc<ResourceManager>>,
+        event_sender: mpsc::Sender<RawLogEvent>,
+    ) {
+        let mut ticker = tokio::time::interval(Duration::from_secs(command.interval_seconds));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let timeout = Duration::from_secs(command.timeout_seconds);
+
+        loop {
+            ticker.tick().await;
+            let _permit = match &resource_manager {
+                Some(manager) => match manager.acquire_resource_permit(RESOURCE_CATEGORY, 1).await {
+                    Ok(Some(permit)) => Some(permit),
+                    Ok(None) => {
+                        debug!("⏭️ Skipping command '{}': resource manager denied a permit", command.name);
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("⚠️ Skipping command '{}': {}", command.name, e);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let Ok(_slot) = slots.acquire().await else { return };
+
+            let run = match tokio::time::timeout(timeout, run_command(&command, max_output_bytes)).await {
+                Ok(Ok(run)) => run,
+                Ok(Err(e)) => {
+                    warn!("⚠️ Command '{}' failed: {}", command.name, e);
+                    continue;
+                }
+                Err(_) => {
+                    warn!("⚠️ Command '{}' killed after {}s timeout", command.name, command.timeout_seconds);
+                    continue;
+                }
+            };
+            if run.exit_code != Some(0) {
+                warn!("⚠️ Command '{}' exited with {:?}: {}", command.name, run.exit_code, run.stderr);
+            }
+            if run.truncated {
+                warn!("⚠️ Output of command '{}' truncated to {} bytes", command.name, max_output_bytes);
+            }
+            let events = output_events(&command, &run);
+            debug!("⚙️ Command '{}' produced {} events in {:?}", command.name, events.len(), run.duration);
+            for event in events {
+                if event_sender.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for CommandCollector {
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        if !self.config.enabled {
+            info!("Command collector is disabled");
+            return Ok(());
+        }
+        // Validation already enforces this; checked again because the commands run with the agent's privileges
+        if let Some(command) = self.config.commands.iter().find(|c| !self.config.allows(&c.program)) {
+            return Err(CollectorError::InvalidConfig(format!(
+                "Command '{}' runs {}, which is not in allowed_programs", command.name, command.program
+            )));
+        }
+        info!("🚀 Starting command collector ({})", self.command_names().join(", "));
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        self.shutdown_sender = Some(shutdown_tx);
+        let slots = Arc::new(Semaphore::new(self.config.max_concurrent));
+
+        for command in self.config.commands.clone() {
+            let mut shutdown_rx = shutdown_rx.clone();
+            let schedule = Self::schedule(
+                command,
+                self.config.max_output_bytes,
+                slots.clone(),
+                self.resource_manager.clone(),
+                self.event_sender.clone(),
+            );
+            crate::component_usage::spawn_inherited(async move {
+                // Dropping a run in progress kills its process
+                tokio::select! {
+                    _ = schedule => {}
+                    _ = shutdown_rx.changed() => {}
+                }
+            });
+        }
+
+        self.running = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        info!("🛑 Stopping command collector");
+        if let Some(sender) = self.shutdown_sender.take() {
+            let _ = sender.send(true);
+        }
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        // Output is sent by the scheduled tasks
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "command"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(output: CommandOutputMode, skip_lines: usize) -> ScheduledCommandConfig {
+        ScheduledCommandConfig {
+            name: "netstat".to_string(),
+            program: "/bin/netstat".to_string(),
+            args: vec!["-an".to_string()],
+            interval_seconds: 300,
+            timeout_seconds: 30,
+            source: "netstat".to_string(),
+            output,
+            working_directory: None,
+            skip_lines,
+        }
+    }
+
+    #[test]
+    fn test_output_becomes_line_or_whole_events() {
+        let run = CommandRun {
+            stdout: "Proto Local Foreign State\ntcp 0.0.0.0:22 0.0.0.0:* LISTEN\n\ntcp 10.0.0.5:22 10.0.0.9:5050 ESTABLISHED\n".to_string(),
+            stderr: String::new(),
+            exit_code: Some(0),
+            truncated: false,
+            duration: Duration::from_millis(12),
+        };
+
+        let lines = output_events(&command(CommandOutputMode::Lines, 1), &run);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].source, "netstat");
+        assert_eq!(&*lines[0].raw_data, "tcp 0.0.0.0:22 0.0.0.0:* LISTEN");
+        assert_eq!(lines[1].metadata["line_number"], "4");
+        assert_eq!(lines[1].metadata["exit_code"], "0");
+
+        let whole = output_events(&command(CommandOutputMode::Whole, 0), &run);
+        assert_eq!(whole.len(), 1);
+        assert!(whole[0].raw_data.ends_with("ESTABLISHED"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_output_is_capped_without_blocking_the_command() {
+        let mut command = command(CommandOutputMode::Whole, 0);
+        command.program = "/bin/sh".to_string();
+        command.args = vec!["-c".to_string(), "head -c 200000 /dev/zero | tr '\\0' x; echo oops >&2; exit 3".to_string()];
+
+        let run = run_command(&command, 1024).await.unwrap();
+        assert_eq!(run.stdout.len(), 1024);
+        assert!(run.truncated);
+        assert_eq!(run.stderr, "oops");
+        assert_eq!(run.exit_code, Some(3));
+    }
+}
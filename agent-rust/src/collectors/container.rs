@@ -0,0 +1,668 @@
+// Container log collector for CRI (containerd, CRI-O) and Docker json-file logs
+// Tails the runtime's log files, joins lines the runtime split, and attaches pod, namespace and container
+// metadata from the kubelet's /pods endpoint so a DaemonSet deployment sees workload logs in context
+
+use crate::collectors::{Collector, RawLogEvent};
+use crate::config::{ContainerCollectorConfig, KubeletConfig};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+pub const CONTAINER_SOURCE: &str = "container";
+
+/// Longest a container missing from the pod index waits before the kubelet is asked again
+const MISS_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// Bytes read from one file per poll, so a large backlog doesn't starve other containers
+const MAX_READ_PER_POLL: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerLogFormat {
+    /// Docker json-file for lines starting with '{', CRI otherwise
+    #[default]
+    Auto,
+    Cri,
+    Docker,
+}
+
+/// One line as written by the container runtime
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub time: Option<DateTime<Utc>>,
+    pub stream: String,
+    /// The runtime split a long line and more of it follows
+    pub partial: bool,
+    pub message: String,
+}
+
+/// CRI format: `<RFC 3339 time> <stream> <P|F> <message>`
+pub fn parse_cri_line(line: &str) -> Option<LogLine> {
+    let mut parts = line.splitn(4, ' ');
+    let time = DateTime::parse_from_rfc3339(parts.next()?).ok()?.with_timezone(&Utc);
+    let stream = parts.next()?;
+    let tag = parts.next()?;
+    Some(LogLine {
+        time: Some(time),
+        stream: stream.to_string(),
+        // Tags are ':'-separated; only the first (P partial, F full) is defined
+        partial: tag.split(':').next() == Some("P"),
+        message: parts.next().unwrap_or_default().to_string(),
+    })
+}
+
+/// Docker json-file format: `{"log":"...\n","stream":"stdout","time":"..."}`; lines over 16KB are split
+/// and every piece but the last lacks the trailing newline
+pub fn parse_docker_line(line: &str) -> Option<LogLine> {
+    #[derive(Deserialize)]
+    struct DockerLine {
+        log: String,
+        #[serde(default)]
+        stream: String,
+        time: Option<DateTime<Utc>>,
+    }
+
+    let entry: DockerLine = serde_json::from_str(line).ok()?;
+    let partial = !entry.log.ends_with('\n');
+    Some(LogLine {
+        time: entry.time,
+        stream: entry.stream,
+        partial,
+        message: entry.log.trim_end_matches(['\r', '\n']).to_string(),
+    })
+}
+
+pub fn parse_line(format: ContainerLogFormat, line: &str) -> Option<LogLine> {
+    match format {
+        ContainerLogFormat::Cri => parse_cri_line(line),
+        ContainerLogFormat::Docker => parse_docker_line(line),
+        ContainerLogFormat::Auto if line.starts_with('{') => parse_docker_line(line),
+        ContainerLogFormat::Auto => parse_cri_line(line),
+    }
+}
+
+/// What a log file's path says about its container
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContainerRef {
+    pub container_id: Option<String>,
+    pub container_name: Option<String>,
+    pub pod_name: Option<String>,
+    pub namespace: Option<String>,
+    pub pod_uid: Option<String>,
+}
+
+impl ContainerRef {
+    /// Recognizes the layouts the kubelet and Docker write:
+    /// /var/log/containers/<pod>_<namespace>_<container>-<id>.log,
+    /// /var/log/pods/<namespace>_<pod>_<uid>/<container>/<restart>.log and
+    /// /var/lib/docker/containers/<id>/<id>-json.log
+    pub fn from_path(path: &Path) -> Self {
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if let Some(id) = file_name.strip_suffix("-json.log") {
+            return Self { container_id: Some(id.to_string()), ..Self::default() };
+        }
+
+        let container_dir = path.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str());
+        let pod_dir = path.parent().and_then(|p| p.parent()).and_then(|p| p.file_name()).and_then(|n| n.to_str());
+        let restart_count = file_name.strip_suffix(".log").is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        if let (true, Some(container), Some(pod_dir)) = (restart_count, container_dir, pod_dir) {
+            if let [namespace, pod, uid] = pod_dir.splitn(3, '_').collect::<Vec<_>>()[..] {
+                return Self {
+                    container_id: None,
+                    container_name: Some(container.to_string()),
+                    pod_name: Some(pod.to_string()),
+                    namespace: Some(namespace.to_string()),
+                    pod_uid: Some(uid.to_string()),
+                };
+            }
+        }
+
+        let stem = file_name.strip_suffix(".log").unwrap_or(file_name);
+        if let [pod, namespace, container_and_id] = stem.splitn(3, '_').collect::<Vec<_>>()[..] {
+            if let Some((container, id)) = container_and_id.rsplit_once('-') {
+                return Self {
+                    container_id: Some(id.to_string()),
+                    container_name: Some(container.to_string()),
+                    pod_name: Some(pod.to_string()),
+                    namespace: Some(namespace.to_string()),
+                    pod_uid: None,
+                };
+            }
+        }
+        Self::default()
+    }
+}
+
+/// Pod and container metadata reported by the kubelet
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PodMetadata {
+    pub pod_name: String,
+    pub namespace: String,
+    pub pod_uid: String,
+    pub node_name: Option<String>,
+    pub container_name: String,
+    pub image: Option<String>,
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Containers on the node, from the kubelet's pod list
+#[derive(Debug, Default)]
+pub struct PodIndex {
+    by_container_id: HashMap<String, PodMetadata>,
+    by_name: HashMap<(String, String, String), PodMetadata>,
+}
+
+impl PodIndex {
+    /// Build the index from a `/pods` response (a v1 PodList)
+    pub fn from_pod_list(pod_list: &Value) -> Self {
+        let mut index = Self::default();
+        let pods = pod_list["items"].as_array().map(Vec::as_slice).unwrap_or_default();
+        for pod in pods {
+            let metadata = &pod["metadata"];
+            let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+            let labels: BTreeMap<String, String> = metadata["labels"]
+                .as_object()
+                .map(|labels| labels.iter().map(|(k, v)| (k.clone(), text(v))).collect())
+                .unwrap_or_default();
+            let statuses = ["initContainerStatuses", "containerStatuses", "ephemeralContainerStatuses"]
+                .iter()
+                .filter_map(|key| pod["status"][*key].as_array())
+                .flatten();
+            for status in statuses {
+                let entry = PodMetadata {
+                    pod_name: text(&metadata["name"]),
+                    namespace: text(&metadata["namespace"]),
+                    pod_uid: text(&metadata["uid"]),
+                    node_name: pod["spec"]["nodeName"].as_str().map(str::to_string),
+                    container_name: text(&status["name"]),
+                    image: status["image"].as_str().map(str::to_string),
+                    labels: labels.clone(),
+                };
+                // containerID is "<runtime>://<id>"
+                if let Some(id) = status["containerID"].as_str().and_then(|id| id.split("://").last()).filter(|id| !id.is_empty()) {
+                    index.by_container_id.insert(id.to_string(), entry.clone());
+                }
+                index.by_name.insert((entry.namespace.clone(), entry.pod_name.clone(), entry.container_name.clone()), entry);
+            }
+        }
+        index
+    }
+
+    pub fn lookup(&self, container: &ContainerRef) -> Option<&PodMetadata> {
+        let by_id = container.container_id.as_ref().and_then(|id| self.by_container_id.get(id));
+        by_id.or_else(|| match (&container.namespace, &container.pod_name, &container.container_name) {
+            (Some(namespace), Some(pod), Some(name)) => self.by_name.get(&(namespace.clone(), pod.clone(), name.clone())),
+            _ => None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+}
+
+struct KubeletClient {
+    client: reqwest::Client,
+    config: KubeletConfig,
+}
+
+impl KubeletClient {
+    fn new(config: &KubeletConfig) -> Result<Self, CollectorError> {
+        let init_error = |reason: String| CollectorError::InitializationFailed {
+            name: "container".to_string(),
+            collector_type: "kubelet".to_string(),
+            reason,
+            configuration: config.url.clone(),
+        };
+        #[cfg_attr(not(any(feature = "native-tls-backend", feature = "rustls-backend")), allow(unused_mut))]
+        let mut client_builder = reqwest::Client::builder().timeout(Duration::from_secs(config.timeout_seconds));
+        #[cfg(any(feature = "native-tls-backend", feature = "rustls-backend"))]
+        {
+            client_builder = client_builder.danger_accept_invalid_certs(!config.tls_verify);
+            if let Some(ca_path) = &config.ca_cert_path {
+                let pem = std::fs::read(ca_path).map_err(|e| init_error(format!("cannot read {}: {}", ca_path, e)))?;
+                let certificate = reqwest::Certificate::from_pem(&pem).map_err(|e| init_error(e.to_string()))?;
+                client_builder = client_builder.add_root_certificate(certificate);
+            }
+        }
+        let client = client_builder.build().map_err(|e| init_error(e.to_string()))?;
+        Ok(Self { client, config: config.clone() })
+    }
+
+    async fn pods(&self) -> Result<PodIndex, CollectorError> {
+        let endpoint = format!("{}/pods", self.config.url.trim_end_matches('/'));
+        let network_error = |e: Box<dyn std::error::Error + Send + Sync>| CollectorError::NetworkError {
+            protocol: "kubelet".to_string(),
+            endpoint: endpoint.clone(),
+            source: e,
+        };
+        let mut request = self.client.get(&endpoint);
+        // Projected service account tokens rotate, so the token is read for every request
+        if let Ok(token) = tokio::fs::read_to_string(&self.config.token_path).await {
+            request = request.bearer_auth(token.trim());
+        }
+        let response = request.send().await.map_err(|e| network_error(e.into()))?;
+        let response = response.error_for_status().map_err(|e| network_error(e.into()))?;
+        let pod_list: Value = response.json().await.map_err(|e| network_error(e.into()))?;
+        Ok(PodIndex::from_pod_list(&pod_list))
+    }
+}
+
+/// A container log record with its workload metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContainerLogEvent {
+    pub time: DateTime<Utc>,
+    pub stream: String,
+    pub message: String,
+    pub log_path: String,
+    pub container_id: Option<String>,
+    pub container_name: Option<String>,
+    pub image: Option<String>,
+    pub pod_name: Option<String>,
+    pub namespace: Option<String>,
+    pub pod_uid: Option<String>,
+    pub node_name: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl ContainerLogEvent {
+    pub fn new(line: LogLine, log_path: &Path, container: &ContainerRef, metadata: Option<&PodMetadata>) -> Self {
+        Self {
+            time: line.time.unwrap_or_else(Utc::now),
+            stream: line.stream,
+            message: line.message,
+            log_path: log_path.display().to_string(),
+            container_id: container.container_id.clone(),
+            container_name: metadata.map(|m| m.container_name.clone()).or_else(|| container.container_name.clone()),
+            image: metadata.and_then(|m| m.image.clone()),
+            pod_name: metadata.map(|m| m.pod_name.clone()).or_else(|| container.pod_name.clone()),
+            namespace: metadata.map(|m| m.namespace.clone()).or_else(|| container.namespace.clone()),
+            pod_uid: metadata.map(|m| m.pod_uid.clone()).or_else(|| container.pod_uid.clone()),
+            node_name: metadata.and_then(|m| m.node_name.clone()),
+            labels: metadata.map(|m| m.labels.clone()).unwrap_or_default(),
+        }
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn to_fields(&self) -> HashMap<String, Value> {
+        let mut fields = HashMap::from([
+            ("stream".to_string(), json!(self.stream)),
+            ("log.file.path".to_string(), json!(self.log_path)),
+        ]);
+        let optional = [
+            ("container.id", &self.container_id),
+            ("container.name", &self.container_name),
+            ("container.image.name", &self.image),
+            ("kubernetes.pod.name", &self.pod_name),
+            ("kubernetes.namespace", &self.namespace),
+            ("kubernetes.pod.uid", &self.pod_uid),
+            ("kubernetes.node.name", &self.node_name),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                fields.insert(name.to_string(), json!(value));
+            }
+        }
+        for (label, value) in &self.labels {
+            fields.insert(format!("kubernetes.labels.{}", label), json!(value));
+        }
+        fields
+    }
+}
+
+/// Read position and partial-line state for one log file
+#[derive(Debug)]
+pub struct TailState {
+    position: u64,
+    file_id: u64,
+    container: ContainerRef,
+    pending: Option<LogLine>,
+}
+
+impl TailState {
+    fn new(path: &Path, position: u64, file_id: u64) -> Self {
+        Self { position, file_id, container: ContainerRef::from_path(path), pending: None }
+    }
+
+    /// Add a runtime line, returning the record it completes once no more of it follows
+    pub fn assemble(&mut self, line: LogLine, max_bytes: usize) -> Option<LogLine> {
+        let mut record = match self.pending.take() {
+            Some(mut pending) => {
+                pending.message.push_str(&line.message);
+                pending.partial = line.partial;
+                pending
+            }
+            None => line,
+        };
+        if record.message.len() > max_bytes {
+            let mut end = max_bytes;
+            while !record.message.is_char_boundary(end) {
+                end -= 1;
+            }
+            record.message.truncate(end);
+            // The rest of an oversized record is dropped until its final piece
+            if record.partial {
+                self.pending = Some(record.clone());
+                return None;
+            }
+        }
+        if record.partial {
+            self.pending = Some(record);
+            None
+        } else {
+            Some(record)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> u64 {
+    0
+}
+
+pub struct ContainerLogCollector {
+    config: ContainerCollectorConfig,
+    event_sender: mpsc::Sender<RawLogEvent>,
+    shutdown_sender: Option<tokio::sync::oneshot::Sender<()>>,
+    running: bool,
+}
+
+impl ContainerLogCollector {
+    pub fn new(config: ContainerCollectorConfig, event_sender: mpsc::Sender<RawLogEvent>) -> Self {
+        Self {
+            config,
+            event_sender,
+            shutdown_sender: None,
+            running: false,
+        }
+    }
+
+    pub fn log_paths(&self) -> &[String] {
+        &self.config.log_paths
+    }
+
+    fn discover(config: &ContainerCollectorConfig) -> HashSet<PathBuf> {
+        config.log_paths.iter()
+            .filter_map(|pattern| ::glob::glob(pattern).ok())
+            .flat_map(|paths| paths.flatten())
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// Read new complete lines from one file
+    async fn read_new_lines(path: &Path, state: &mut TailState) -> Result<Vec<String>, CollectorError> {
+        let fs_error = |operation: &str, e: std::io::Error| CollectorError::FileSystemError {
+            operation: operation.to_string(),
+            path: path.display().to_string(),
+            permissions_issue: e.kind() == std::io::ErrorKind::PermissionDenied,
+            source: e,
+        };
+        let mut file = tokio::fs::File::open(path).await.map_err(|e| fs_error("open_container_log", e))?;
+        let metadata = file.metadata().await.map_err(|e| fs_error("stat_container_log", e))?;
+
+        // Rotated (a new file behind the same name) or truncated
+        let id = file_id(&metadata);
+        if id != state.file_id || metadata.len() < state.position {
+            state.position = 0;
+            state.file_id = id;
+            state.pending = None;
+        }
+        if metadata.len() == state.position {
+            return Ok(Vec::new());
+        }
+
+        file.seek(SeekFrom::Start(state.position)).await.map_err(|e| fs_error("seek_container_log", e))?;
+        let mut data = Vec::new();
+        file.take(MAX_READ_PER_POLL).read_to_end(&mut data).await.map_err(|e| fs_error("read_container_log", e))?;
+
+        // A line still being written is read again on the next poll
+        let complete = match data.iter().rposition(|b| *b == b'\n') {
+            Some(last_newline) => last_newline + 1,
+            None => return Ok(Vec::new()),
+        };
+        state.position += complete as u64;
+        Ok(String::from_utf8_lossy(&data[..complete])
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Collector for ContainerLogCollector {
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        if !self.config.enabled {
+            info!("Container log collector is disabled");
+            return Ok(());
+        }
+
+        let kubelet = match self.config.kubelet.enabled {
+            true => Some(KubeletClient::new(&self.config.kubelet)?),
+            false => None,
+        };
+        info!("🚀 Starting container log collector (paths: {})", self.config.log_paths.join(", "));
+
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel();
+        self.shutdown_sender = Some(shutdown_tx);
+
+        let config = self.config.clone();
+        let event_sender = self.event_sender.clone();
+        let mut interval = tokio::time::interval(Duration::from_millis(config.poll_interval_ms.max(100)));
+
+        crate::component_usage::spawn_inherited(async move {
+            let excluded: HashSet<&str> = config.exclude_namespaces.iter().map(String::as_str).collect();
+            let mut states: HashMap<PathBuf, TailState> = HashMap::new();
+            let mut pods = PodIndex::default();
+            let mut pods_refreshed: Option<Instant> = None;
+
+            // Files present at startup are read from their end unless history was asked for
+            for path in Self::discover(&config) {
+                let (position, id) = match std::fs::metadata(&path) {
+                    Ok(metadata) if !config.read_from_start => (metadata.len(), file_id(&metadata)),
+                    Ok(metadata) => (0, file_id(&metadata)),
+                    Err(_) => (0, 0),
+                };
+                states.insert(path.clone(), TailState::new(&path, position, id));
+            }
+            debug!("🐳 Container log collector tailing {} files", states.len());
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = &mut shutdown_rx => {
+                        debug!("Container log collector shutting down");
+                        break;
+                    }
+                }
+
+                let discovered = Self::discover(&config);
+                states.retain(|path, _| discovered.contains(path));
+                for path in discovered {
+                    states.entry(path.clone()).or_insert_with(|| {
+                        let id = std::fs::metadata(&path).map(|m| file_id(&m)).unwrap_or(0);
+                        TailState::new(&path, 0, id)
+                    });
+                }
+
+                if let Some(kubelet) = &kubelet {
+                    let unknown = states.values().any(|state| pods.lookup(&state.container).is_none());
+                    let due = match pods_refreshed {
+                        None => true,
+                        Some(at) => at.elapsed() >= Duration::from_secs(config.kubelet.refresh_interval_secs)
+                            || (unknown && at.elapsed() >= MISS_REFRESH_INTERVAL),
+                    };
+                    if due {
+                        pods_refreshed = Some(Instant::now());
+                        match kubelet.pods().await {
+                            Ok(index) => pods = index,
+                            Err(e) => warn!("⚠️ Failed to refresh pod metadata from the kubelet: {}", e),
+                        }
+                    }
+                }
+
+                for (path, state) in states.iter_mut() {
+                    let lines = match Self::read_new_lines(path, state).await {
+                        Ok(lines) => lines,
+                        Err(e) => {
+                            debug!("Failed to read container log {}: {}", path.display(), e);
+                            continue;
+                        }
+                    };
+                    let metadata = pods.lookup(&state.container);
+                    let namespace = metadata.map(|m| m.namespace.as_str()).or(state.container.namespace.as_deref());
+                    if namespace.is_some_and(|namespace| excluded.contains(namespace)) {
+                        continue;
+                    }
+
+                    for line in lines {
+                        let Some(line) = parse_line(config.format, &line) else {
+                            debug!("Skipping unrecognized line in {}", path.display());
+                            continue;
+                        };
+                        let Some(record) = state.assemble(line, config.max_line_bytes) else {
+                            continue;
+                        };
+                        let event = ContainerLogEvent::new(record, path, &state.container, metadata);
+                        let raw_event = RawLogEvent {
+                            timestamp: event.timestamp(),
+                            source: CONTAINER_SOURCE.to_string(),
+                            raw_data: serde_json::to_string(&event).unwrap_or_default().into(),
+                            metadata: HashMap::from([
+                                ("file_path".to_string(), event.log_path.clone()),
+                                ("stream".to_string(), event.stream.clone()),
+                            ]),
+                        };
+                        if let Err(e) = event_sender.send(raw_event).await {
+                            error!("Failed to send container log event: {}", e);
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        self.running = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        info!("🛑 Stopping container log collector");
+
+        if let Some(sender) = self.shutdown_sender.take() {
+            let _ = sender.send(());
+        }
+
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        // Collection happens in the background polling task
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "container"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINER_ID: &str = "4f1c9b2e8d7a6c5b4a3f2e1d0c9b8a7f6e5d4c3b2a1f0e9d8c7b6a5f4e3d2c1b";
+
+    #[test]
+    fn test_runtime_lines_and_partial_records() {
+        let full = parse_line(ContainerLogFormat::Auto, "2024-05-01T10:00:00.123456789Z stderr F connection refused").unwrap();
+        assert_eq!(full.stream, "stderr");
+        assert!(!full.partial);
+        assert_eq!(full.message, "connection refused");
+        assert_eq!(parse_cri_line("2024-05-01T10:00:00Z stdout F ").unwrap().message, "");
+        assert!(parse_cri_line("not a cri line").is_none());
+
+        let mut state = TailState::new(Path::new("/var/log/containers/x.log"), 0, 0);
+        let first = parse_line(ContainerLogFormat::Auto, r#"{"log":"GET /health ","stream":"stdout","time":"2024-05-01T10:00:01Z"}"#).unwrap();
+        assert!(first.partial);
+        assert!(state.assemble(first, 1024).is_none());
+        let last = parse_docker_line(r#"{"log":"200 OK\n","stream":"stdout","time":"2024-05-01T10:00:01Z"}"#).unwrap();
+        let record = state.assemble(last, 1024).unwrap();
+        assert_eq!(record.message, "GET /health 200 OK");
+        assert_eq!(record.time.unwrap().to_rfc3339(), "2024-05-01T10:00:01+00:00");
+
+        // Oversized records are truncated and the rest of them dropped
+        let piece = |text: &str, partial: bool| LogLine { time: None, stream: "stdout".to_string(), partial, message: text.to_string() };
+        assert!(state.assemble(piece("aaaa", true), 6).is_none());
+        assert!(state.assemble(piece("bbbb", true), 6).is_none());
+        assert_eq!(state.assemble(piece("cccc", false), 6).unwrap().message, "aaaabb");
+    }
+
+    #[test]
+    fn test_paths_and_kubelet_metadata() {
+        let path = format!("/var/log/containers/web-7d4b9_shop_nginx-{}.log", CONTAINER_ID);
+        let container = ContainerRef::from_path(Path::new(&path));
+        assert_eq!(container.pod_name.as_deref(), Some("web-7d4b9"));
+        assert_eq!(container.namespace.as_deref(), Some("shop"));
+        assert_eq!(container.container_name.as_deref(), Some("nginx"));
+        assert_eq!(container.container_id.as_deref(), Some(CONTAINER_ID));
+
+        let pods_path = ContainerRef::from_path(Path::new("/var/log/pods/shop_web-7d4b9_0b6f/nginx/2.log"));
+        assert_eq!(pods_path.pod_uid.as_deref(), Some("0b6f"));
+        assert_eq!(pods_path.container_name.as_deref(), Some("nginx"));
+        let docker = ContainerRef::from_path(Path::new(&format!("/var/lib/docker/containers/{0}/{0}-json.log", CONTAINER_ID)));
+        assert_eq!(docker.container_id.as_deref(), Some(CONTAINER_ID));
+
+        let pod_list = json!({
+            "items": [{
+                "metadata": { "name": "web-7d4b9", "namespace": "shop", "uid": "0b6f", "labels": { "app": "web" } },
+                "spec": { "nodeName": "node-1" },
+                "status": { "containerStatuses": [
+                    { "name": "nginx", "image": "nginx:1.27", "containerID": format!("containerd://{}", CONTAINER_ID) }
+                ] }
+            }]
+        });
+        let index = PodIndex::from_pod_list(&pod_list);
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.lookup(&docker).unwrap().pod_name, "web-7d4b9");
+        let metadata = index.lookup(&pods_path).unwrap();
+
+        let line = parse_cri_line("2024-05-01T10:00:00Z stdout F ready").unwrap();
+        let event = ContainerLogEvent::new(line, Path::new(&path), &container, Some(metadata));
+        let fields = event.to_fields();
+        assert_eq!(fields["kubernetes.namespace"], "shop");
+        assert_eq!(fields["kubernetes.labels.app"], "web");
+        assert_eq!(fields["kubernetes.node.name"], "node-1");
+        assert_eq!(fields["container.image.name"], "nginx:1.27");
+        assert_eq!(fields["container.id"], CONTAINER_ID);
+    }
+}
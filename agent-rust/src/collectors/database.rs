@@ -0,0 +1,296 @@
+// Database audit log collector for PostgreSQL and MySQL/MariaDB log files
+// Tails the configured log files and joins multi-line statements into single records
+
+use crate::collectors::{Collector, RawLogEvent};
+use crate::config::DatabaseCollectorConfig;
+use crate::errors::CollectorError;
+use crate::parsers::database::{DatabaseLogFormat, DATABASE_SOURCE};
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader, SeekFrom};
+use tokio::sync::mpsc;
+use tracing::{info, error, debug, warn};
+
+/// Per-file tail state
+#[derive(Debug, Default)]
+struct TailState {
+    position: u64,
+    /// Identity of the file being read, to notice a new file rotated in behind the same name
+    file_id: Option<u64>,
+    pending: Vec<String>,
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> u64 {
+    0
+}
+
+pub struct DatabaseAuditCollector {
+    config: DatabaseCollectorConfig,
+    event_sender: mpsc::Sender<RawLogEvent>,
+    format: Arc<DatabaseLogFormat>,
+    shutdown_sender: Option<tokio::sync::oneshot::Sender<()>>,
+    running: bool,
+}
+
+impl DatabaseAuditCollector {
+    pub fn new(
+        config: DatabaseCollectorConfig,
+        event_sender: mpsc::Sender<RawLogEvent>,
+    ) -> Result<Self, CollectorError> {
+        let format = DatabaseLogFormat::new(config.preset, config.log_line_prefix.as_deref())
+            .map_err(|e| CollectorError::InitializationFailed {
+                name: "database".to_string(),
+                collector_type: config.preset.as_str().to_string(),
+                reason: e.to_string(),
+                configuration: config.log_line_prefix.clone().unwrap_or_default(),
+            })?;
+
+        Ok(Self {
+            config,
+            event_sender,
+            format: Arc::new(format),
+            shutdown_sender: None,
+            running: false,
+        })
+    }
+
+    fn discover_files(paths: &[String]) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for pattern in paths {
+            if let Ok(expanded) = ::glob::glob(pattern) {
+                files.extend(expanded.flatten().filter(|path| path.is_file()));
+            }
+        }
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Read newly appended lines and return every record completed by them
+    async fn poll_file(
+        path: &Path,
+        state: &mut TailState,
+        format: &DatabaseLogFormat,
+        max_record_lines: usize,
+    ) -> Result<Vec<String>, CollectorError> {
+        let fs_error = |operation: &str, e: std::io::Error| CollectorError::FileSystemError {
+            operation: operation.to_string(),
+            path: path.to_string_lossy().to_string(),
+            permissions_issue: e.kind() == std::io::ErrorKind::PermissionDenied,
+            source: e,
+        };
+
+        let mut file = File::open(path).await.map_err(|e| fs_error("open_file", e))?;
+        let metadata = file.metadata().await.map_err(|e| fs_error("get_metadata", e))?;
+        let mut records = Vec::new();
+
+        // Rotated (a new file behind the same name, however large it already is) or truncated: start over from the
+        // beginning, finishing the record the old file ended with
+        let id = file_id(&metadata);
+        if state.file_id.is_some_and(|known| known != id) || state.position > metadata.len() {
+            debug!("🔄 Database log truncated or rotated: {}", path.display());
+            if !state.pending.is_empty() {
+                records.push(state.pending.join("\n"));
+                state.pending.clear();
+            }
+            state.position = 0;
+        }
+        state.file_id = Some(id);
+        file.seek(SeekFrom::Start(state.position)).await.map_err(|e| fs_error("seek_file", e))?;
+
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        let mut read_any = false;
+
+        loop {
+            line.clear();
+            let n = reader.read_line(&mut line).await.map_err(|e| fs_error("read_line", e))?;
+            // Leave partially written lines for the next poll
+            if n == 0 || !line.ends_with('\n') {
+                break;
+            }
+            state.position += n as u64;
+            read_any = true;
+
+            let text = line.trim_end_matches(['\r', '\n']);
+            if text.is_empty() {
+                continue;
+            }
+
+            let starts_record = format.is_record_start(text);
+            if (starts_record || state.pending.len() >= max_record_lines) && !state.pending.is_empty() {
+                records.push(state.pending.join("\n"));
+                state.pending.clear();
+            }
+            if starts_record || !state.pending.is_empty() {
+                state.pending.push(text.to_string());
+            } else {
+                debug!("Skipping database log line outside of a record: {}", text);
+            }
+        }
+
+        // A quiet poll means the last record is complete
+        if !read_any && !state.pending.is_empty() {
+            records.push(state.pending.join("\n"));
+            state.pending.clear();
+        }
+
+        Ok(records)
+    }
+}
+
+#[async_trait]
+impl Collector for DatabaseAuditCollector {
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        if !self.config.enabled {
+            info!("Database audit collector is disabled");
+            return Ok(());
+        }
+
+        info!("🚀 Starting database audit collector ({})", self.config.preset.as_str());
+
+        let mut states: HashMap<PathBuf, TailState> = HashMap::new();
+        for path in Self::discover_files(&self.config.paths) {
+            let metadata = tokio::fs::metadata(&path).await.ok();
+            let position = match &metadata {
+                Some(metadata) if !self.config.read_from_start => metadata.len(),
+                _ => 0,
+            };
+            debug!("🗄️  Monitoring database log: {}", path.display());
+            states.insert(path, TailState { position, file_id: metadata.as_ref().map(file_id), pending: Vec::new() });
+        }
+        info!("🗄️  Monitoring {} database log files", states.len());
+
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel();
+        self.shutdown_sender = Some(shutdown_tx);
+
+        let paths = self.config.paths.clone();
+        let preset = self.config.preset.as_str().to_string();
+        let max_record_lines = self.config.max_record_lines.max(1);
+        let format = self.format.clone();
+        let event_sender = self.event_sender.clone();
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(self.config.poll_interval_ms.max(100)));
+
+        crate::component_usage::spawn_inherited(async move {
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        let discovered: HashSet<PathBuf> = Self::discover_files(&paths).into_iter().collect();
+                        let mut batches: Vec<(PathBuf, Vec<String>)> = Vec::new();
+
+                        // Deleted or rotated-away files are forgotten; the record they ended with is complete
+                        let gone: Vec<PathBuf> = states.keys().filter(|path| !discovered.contains(*path)).cloned().collect();
+                        for path in gone {
+                            debug!("🗄️  Database log no longer present: {}", path.display());
+                            if let Some(state) = states.remove(&path).filter(|state| !state.pending.is_empty()) {
+                                batches.push((path, vec![state.pending.join("\n")]));
+                            }
+                        }
+
+                        // Pick up log files created after startup (e.g. after rotation)
+                        for path in discovered {
+                            states.entry(path).or_default();
+                        }
+
+                        for (path, state) in states.iter_mut() {
+                            match Self::poll_file(path, state, &format, max_record_lines).await {
+                                Ok(records) if !records.is_empty() => batches.push((path.clone(), records)),
+                                Ok(_) => {}
+                                Err(e) => warn!("Failed to read database log {}: {}", path.display(), e),
+                            }
+                        }
+
+                        for (path, records) in batches {
+                            for record in records {
+                                let event = RawLogEvent {
+                                    timestamp: chrono::Utc::now(),
+                                    source: DATABASE_SOURCE.to_string(),
+                                    raw_data: record.into(),
+                                    metadata: HashMap::from([
+                                        ("file_path".to_string(), path.display().to_string()),
+                                        ("db_preset".to_string(), preset.clone()),
+                                    ]),
+                                };
+
+                                if let Err(e) = event_sender.send(event).await {
+                                    error!("Failed to send database audit event: {}", e);
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                    _ = &mut shutdown_rx => {
+                        debug!("Database audit collector shutting down");
+                        break;
+                    }
+                }
+            }
+        });
+
+        self.running = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        info!("🛑 Stopping database audit collector");
+
+        if let Some(sender) = self.shutdown_sender.take() {
+            let _ = sender.send(());
+        }
+
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        // Collection happens in the background tail task
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::database::DatabaseLogPreset;
+
+    const FIRST: &str = "2024-01-15 10:00:00.123 UTC [4242] alice@sales LOG:  statement: SELECT *\n\tFROM orders\n";
+    const SECOND: &str = "2024-01-15 10:00:01.000 UTC [4242] alice@sales LOG:  statement: SELECT 1\n";
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_rotated_file_is_read_from_the_start_even_when_larger() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("postgresql.log");
+        let format = DatabaseLogFormat::new(DatabaseLogPreset::Postgresql, None).unwrap();
+        std::fs::write(&path, FIRST).unwrap();
+
+        let mut state = TailState::default();
+        assert!(DatabaseAuditCollector::poll_file(&path, &mut state, &format, 100).await.unwrap().is_empty());
+        assert_eq!(state.pending.len(), 2);
+
+        // The new file already holds more than the old offset
+        std::fs::rename(&path, dir.path().join("postgresql.log.1")).unwrap();
+        std::fs::write(&path, format!("{}{}{}", SECOND, SECOND, SECOND)).unwrap();
+        let records = DatabaseAuditCollector::poll_file(&path, &mut state, &format, 100).await.unwrap();
+        assert_eq!(records, vec![FIRST.trim_end().to_string(), SECOND.trim_end().to_string(), SECOND.trim_end().to_string()]);
+        assert_eq!(state.position, (SECOND.len() * 3) as u64);
+    }
+}
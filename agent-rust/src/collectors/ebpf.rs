@@ -0,0 +1,549 @@
+// eBPF endpoint telemetry collector (Linux)
+// Loads the tracepoint probes from ebpf/securewatch_probes.bpf.c and turns their ring buffer
+// records into normalized process exec, network connect and file open events
+
+use crate::collectors::{Collector, RawLogEvent};
+use crate::config::EbpfCollectorConfig;
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tokio::sync::mpsc;
+#[cfg(all(target_os = "linux", feature = "ebpf"))]
+use tracing::{info, warn};
+
+pub const EBPF_SOURCE: &str = "ebpf";
+
+/// Size of `struct endpoint_event` in the probe program
+pub const PROBE_RECORD_SIZE: usize = 324;
+const PATH_OFFSET: usize = 68;
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointEventKind {
+    ProcessExec,
+    NetworkConnect,
+    FileOpen,
+}
+
+impl EndpointEventKind {
+    fn from_probe(kind: u32) -> Option<Self> {
+        match kind {
+            1 => Some(EndpointEventKind::ProcessExec),
+            2 => Some(EndpointEventKind::NetworkConnect),
+            3 => Some(EndpointEventKind::FileOpen),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndpointEventKind::ProcessExec => "process_exec",
+            EndpointEventKind::NetworkConnect => "network_connect",
+            EndpointEventKind::FileOpen => "file_open",
+        }
+    }
+}
+
+/// A decoded ring buffer record
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeRecord {
+    pub kind: EndpointEventKind,
+    pub pid: u32,
+    pub tid: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub flags: i32,
+    pub address: Option<IpAddr>,
+    pub port: u16,
+    pub comm: String,
+    pub path: String,
+}
+
+impl ProbeRecord {
+    /// Decode a `struct endpoint_event`, returning None for short or unknown records
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < PROBE_RECORD_SIZE {
+            return None;
+        }
+        let u32_at = |offset: usize| u32::from_ne_bytes(data[offset..offset + 4].try_into().unwrap_or_default());
+        let u16_at = |offset: usize| u16::from_ne_bytes(data[offset..offset + 2].try_into().unwrap_or_default());
+        let c_string = |bytes: &[u8]| {
+            let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).to_string()
+        };
+
+        let family = u16_at(32);
+        let address = match family {
+            AF_INET => Some(IpAddr::V4(Ipv4Addr::new(data[36], data[37], data[38], data[39]))),
+            AF_INET6 => <[u8; 16]>::try_from(&data[36..52]).ok().map(|octets| IpAddr::V6(Ipv6Addr::from(octets))),
+            _ => None,
+        };
+
+        Some(Self {
+            kind: EndpointEventKind::from_probe(u32_at(8))?,
+            pid: u32_at(12),
+            tid: u32_at(16),
+            uid: u32_at(20),
+            gid: u32_at(24),
+            flags: u32_at(28) as i32,
+            address,
+            port: u16_at(34),
+            comm: c_string(&data[52..PATH_OFFSET]),
+            path: c_string(&data[PATH_OFFSET..PROBE_RECORD_SIZE]),
+        })
+    }
+}
+
+/// Normalized endpoint event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndpointEvent {
+    pub kind: EndpointEventKind,
+    pub time: DateTime<Utc>,
+    pub pid: u32,
+    pub tid: u32,
+    pub ppid: Option<u32>,
+    pub uid: u32,
+    pub gid: u32,
+    pub process_name: String,
+    pub executable: Option<String>,
+    pub command_line: Option<String>,
+    pub destination_address: Option<String>,
+    pub destination_port: Option<u16>,
+    pub file_path: Option<String>,
+    pub file_flags: Option<i32>,
+}
+
+impl EndpointEvent {
+    pub fn from_record(record: ProbeRecord, time: DateTime<Utc>) -> Self {
+        let mut event = Self {
+            kind: record.kind,
+            time,
+            pid: record.pid,
+            tid: record.tid,
+            ppid: None,
+            uid: record.uid,
+            gid: record.gid,
+            process_name: record.comm,
+            executable: None,
+            command_line: None,
+            destination_address: None,
+            destination_port: None,
+            file_path: None,
+            file_flags: None,
+        };
+        match record.kind {
+            EndpointEventKind::ProcessExec => event.executable = Some(record.path),
+            EndpointEventKind::NetworkConnect => {
+                event.destination_address = record.address.map(|a| a.to_string());
+                event.destination_port = Some(record.port);
+            }
+            EndpointEventKind::FileOpen => {
+                event.file_path = Some(record.path);
+                event.file_flags = Some(record.flags);
+            }
+        }
+        event
+    }
+
+    /// Time the event happened
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    pub fn message(&self) -> String {
+        let process = format!("{} (pid {})", self.process_name, self.pid);
+        match self.kind {
+            EndpointEventKind::ProcessExec => format!(
+                "Process executed: {} by {}",
+                self.command_line.as_deref().or(self.executable.as_deref()).unwrap_or("?"),
+                process
+            ),
+            EndpointEventKind::NetworkConnect => {
+                let destination = match (self.destination_address.as_deref(), self.destination_port) {
+                    (Some(address), Some(port)) if address.contains(':') => format!("[{}]:{}", address, port),
+                    (Some(address), Some(port)) => format!("{}:{}", address, port),
+                    _ => "?".to_string(),
+                };
+                format!("Network connection to {} from {}", destination, process)
+            }
+            EndpointEventKind::FileOpen => format!(
+                "File opened: {} by {}",
+                self.file_path.as_deref().unwrap_or("?"),
+                process
+            ),
+        }
+    }
+
+    /// Flattened fields for the parsed event (ECS names, so process lineage can enrich them)
+    pub fn to_fields(&self) -> HashMap<String, Value> {
+        let category = match self.kind {
+            EndpointEventKind::ProcessExec => "process",
+            EndpointEventKind::NetworkConnect => "network",
+            EndpointEventKind::FileOpen => "file",
+        };
+        let mut fields = HashMap::from([
+            ("event.category".to_string(), json!(category)),
+            ("event.action".to_string(), json!(self.kind.as_str())),
+            ("process.pid".to_string(), json!(self.pid)),
+            ("process.thread.id".to_string(), json!(self.tid)),
+            ("process.name".to_string(), json!(self.process_name)),
+            ("user.id".to_string(), json!(self.uid.to_string())),
+            ("group.id".to_string(), json!(self.gid.to_string())),
+        ]);
+        let optional = [
+            ("process.parent.pid", self.ppid.map(|v| json!(v))),
+            ("process.executable", self.executable.as_ref().map(|v| json!(v))),
+            ("process.command_line", self.command_line.as_ref().map(|v| json!(v))),
+            ("destination.ip", self.destination_address.as_ref().map(|v| json!(v))),
+            ("destination.port", self.destination_port.map(|v| json!(v))),
+            ("file.path", self.file_path.as_ref().map(|v| json!(v))),
+            ("file.flags", self.file_flags.map(|v| json!(v))),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                fields.insert(key.to_string(), value);
+            }
+        }
+        fields
+    }
+}
+
+/// User-space filtering applied after decoding
+#[derive(Debug, Clone)]
+pub struct EndpointFilter {
+    exclude_processes: Vec<String>,
+    file_path_prefixes: Vec<String>,
+}
+
+impl EndpointFilter {
+    pub fn new(config: &EbpfCollectorConfig) -> Self {
+        Self {
+            exclude_processes: config.exclude_processes.clone(),
+            file_path_prefixes: config.file_path_prefixes.clone(),
+        }
+    }
+
+    pub fn allows(&self, record: &ProbeRecord) -> bool {
+        if self.exclude_processes.contains(&record.comm) {
+            return false;
+        }
+        record.kind != EndpointEventKind::FileOpen
+            || self.file_path_prefixes.is_empty()
+            || self.file_path_prefixes.iter().any(|prefix| record.path.starts_with(prefix.as_str()))
+    }
+}
+
+/// Parent PID and command line from procfs; the process may already have exited
+pub fn process_context(pid: u32) -> (Option<u32>, Option<String>) {
+    let ppid = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok().and_then(|stat| {
+        // The command name may contain spaces or parentheses, so parse after the last ')'
+        let (_, rest) = stat.rsplit_once(')')?;
+        rest.split_whitespace().nth(1)?.parse().ok()
+    });
+    let command_line = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()
+        .map(|raw| raw.split(|b| *b == 0).filter(|arg| !arg.is_empty()).map(String::from_utf8_lossy).collect::<Vec<_>>().join(" "))
+        .filter(|cmdline| !cmdline.is_empty());
+    (ppid, command_line)
+}
+
+// Without probe support the collector only reports why it can't start
+#[cfg_attr(not(all(target_os = "linux", feature = "ebpf")), allow(dead_code))]
+pub struct EbpfCollector {
+    config: EbpfCollectorConfig,
+    event_sender: mpsc::Sender<RawLogEvent>,
+    shutdown_sender: Option<tokio::sync::oneshot::Sender<()>>,
+    running: bool,
+}
+
+impl EbpfCollector {
+    pub fn new(config: EbpfCollectorConfig, event_sender: mpsc::Sender<RawLogEvent>) -> Self {
+        Self {
+            config,
+            event_sender,
+            shutdown_sender: None,
+            running: false,
+        }
+    }
+
+    /// Probe kinds selected in the configuration
+    pub fn probes(&self) -> Vec<EndpointEventKind> {
+        let mut probes = Vec::new();
+        if self.config.process_exec {
+            probes.push(EndpointEventKind::ProcessExec);
+        }
+        if self.config.network_connect {
+            probes.push(EndpointEventKind::NetworkConnect);
+        }
+        if self.config.file_open {
+            probes.push(EndpointEventKind::FileOpen);
+        }
+        probes
+    }
+
+    fn init_error(&self, reason: String) -> CollectorError {
+        CollectorError::InitializationFailed {
+            name: "ebpf".to_string(),
+            collector_type: "ebpf".to_string(),
+            reason,
+            configuration: self.config.probe_path.clone(),
+        }
+    }
+
+    #[cfg(all(target_os = "linux", feature = "ebpf"))]
+    fn load_probes(&self) -> Result<(aya::Ebpf, aya::maps::RingBuf<aya::maps::MapData>), CollectorError> {
+        use aya::maps::{Array, RingBuf};
+        use aya::programs::TracePoint;
+
+        // Kernels before 5.11 charge BPF maps against RLIMIT_MEMLOCK
+        let memlock = libc::rlimit { rlim_cur: libc::RLIM_INFINITY, rlim_max: libc::RLIM_INFINITY };
+        // SAFETY: setrlimit only reads the struct passed by reference
+        if unsafe { libc::setrlimit(libc::RLIMIT_MEMLOCK, &memlock) } != 0 {
+            warn!("⚠️ Failed to raise RLIMIT_MEMLOCK; loading eBPF probes may fail on older kernels");
+        }
+
+        let mut bpf = aya::EbpfLoader::new()
+            .set_max_entries("EVENTS", self.config.ring_buffer_kb * 1024)
+            .load_file(&self.config.probe_path)
+            .map_err(|e| self.init_error(format!("failed to load {}: {}", self.config.probe_path, e)))?;
+
+        let mut settings: Array<_, u32> = Array::try_from(bpf.map_mut("CONFIG").ok_or_else(|| self.init_error("probe object has no CONFIG map".to_string()))?)
+            .map_err(|e| self.init_error(e.to_string()))?;
+        settings.set(0, std::process::id(), 0).map_err(|e| self.init_error(e.to_string()))?;
+        settings.set(1, self.config.file_open_writes_only as u32, 0).map_err(|e| self.init_error(e.to_string()))?;
+
+        for kind in self.probes() {
+            let (program_name, category, tracepoint) = match kind {
+                EndpointEventKind::ProcessExec => ("securewatch_exec", "sched", "sched_process_exec"),
+                EndpointEventKind::NetworkConnect => ("securewatch_connect", "syscalls", "sys_enter_connect"),
+                EndpointEventKind::FileOpen => ("securewatch_openat", "syscalls", "sys_enter_openat"),
+            };
+            let program: &mut TracePoint = bpf.program_mut(program_name)
+                .ok_or_else(|| self.init_error(format!("probe object has no {} program", program_name)))?
+                .try_into()
+                .map_err(|e: aya::programs::ProgramError| self.init_error(e.to_string()))?;
+            program.load().map_err(|e| self.init_error(format!("failed to load {}: {}", program_name, e)))?;
+            program.attach(category, tracepoint)
+                .map_err(|e| self.init_error(format!("failed to attach {}/{}: {}", category, tracepoint, e)))?;
+        }
+
+        let events = RingBuf::try_from(bpf.take_map("EVENTS").ok_or_else(|| self.init_error("probe object has no EVENTS map".to_string()))?)
+            .map_err(|e| self.init_error(e.to_string()))?;
+        Ok((bpf, events))
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "ebpf"))]
+#[async_trait]
+impl Collector for EbpfCollector {
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        use tokio::io::unix::AsyncFd;
+
+        if !self.config.enabled {
+            info!("eBPF collector is disabled");
+            return Ok(());
+        }
+
+        let (mut bpf, events) = self.load_probes()?;
+        let mut events = AsyncFd::new(events).map_err(|e| self.init_error(e.to_string()))?;
+        let names: Vec<&str> = self.probes().iter().map(|p| p.as_str()).collect();
+        info!("🚀 Starting eBPF collector (probes: {})", names.join(", "));
+
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel();
+        self.shutdown_sender = Some(shutdown_tx);
+
+        let filter = EndpointFilter::new(&self.config);
+        let max_per_second = self.config.max_events_per_second.max(1) as usize;
+        let event_sender = self.event_sender.clone();
+
+        crate::component_usage::spawn_inherited(async move {
+            let mut window_start = tokio::time::Instant::now();
+            let mut window_count = 0usize;
+            let mut rate_limited = 0u64;
+            let mut report_interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+
+            loop {
+                let mut pending = Vec::new();
+                tokio::select! {
+                    ready = events.readable_mut() => {
+                        let mut guard = match ready {
+                            Ok(guard) => guard,
+                            Err(e) => {
+                                warn!("eBPF ring buffer polling failed: {}", e);
+                                break;
+                            }
+                        };
+                        let ring = guard.get_inner_mut();
+                        while let Some(item) = ring.next() {
+                            if window_start.elapsed() >= tokio::time::Duration::from_secs(1) {
+                                window_start = tokio::time::Instant::now();
+                                window_count = 0;
+                            }
+                            let Some(record) = ProbeRecord::decode(&item) else { continue };
+                            if !filter.allows(&record) {
+                                continue;
+                            }
+                            if window_count >= max_per_second {
+                                rate_limited += 1;
+                                continue;
+                            }
+                            window_count += 1;
+                            pending.push(record);
+                        }
+                        guard.clear_ready();
+                    }
+                    _ = report_interval.tick() => {
+                        let dropped = bpf.map_mut("DROPPED")
+                            .and_then(|map| aya::maps::Array::<_, u64>::try_from(map).ok())
+                            .and_then(|map| map.get(&0, 0).ok())
+                            .unwrap_or(0);
+                        if dropped > 0 || rate_limited > 0 {
+                            warn!("⚠️ eBPF collector lost {} events to a full ring buffer and {} to rate limiting", dropped, rate_limited);
+                        }
+                    }
+                    _ = &mut shutdown_rx => {
+                        info!("eBPF collector shutting down");
+                        break;
+                    }
+                }
+
+                let now = Utc::now();
+                for record in pending {
+                    let mut event = EndpointEvent::from_record(record, now);
+                    (event.ppid, event.command_line) = process_context(event.pid);
+                    let raw_event = RawLogEvent {
+                        timestamp: event.timestamp(),
+                        source: EBPF_SOURCE.to_string(),
+                        raw_data: serde_json::to_string(&event).unwrap_or_default().into(),
+                        metadata: HashMap::from([("ebpf_probe".to_string(), event.kind.as_str().to_string())]),
+                    };
+                    if event_sender.send(raw_event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            // Dropping the loader detaches the probes
+            drop(bpf);
+        });
+
+        self.running = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        info!("🛑 Stopping eBPF collector");
+
+        if let Some(sender) = self.shutdown_sender.take() {
+            let _ = sender.send(());
+        }
+
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        // Collection happens in the background ring buffer task
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "ebpf"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "ebpf")))]
+#[async_trait]
+impl Collector for EbpfCollector {
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        Err(self.init_error("eBPF collection requires Linux and a build with the ebpf feature".to_string()))
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "ebpf"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(kind: u32, comm: &str, path: &str, family: u16, addr: &[u8], port: u16) -> Vec<u8> {
+        let mut data = vec![0u8; PROBE_RECORD_SIZE + 4];
+        data[8..12].copy_from_slice(&kind.to_ne_bytes());
+        data[12..16].copy_from_slice(&4242u32.to_ne_bytes());
+        data[16..20].copy_from_slice(&4243u32.to_ne_bytes());
+        data[20..24].copy_from_slice(&1000u32.to_ne_bytes());
+        data[28..32].copy_from_slice(&0o101i32.to_ne_bytes());
+        data[32..34].copy_from_slice(&family.to_ne_bytes());
+        data[34..36].copy_from_slice(&port.to_ne_bytes());
+        data[36..36 + addr.len()].copy_from_slice(addr);
+        data[52..52 + comm.len()].copy_from_slice(comm.as_bytes());
+        data[PATH_OFFSET..PATH_OFFSET + path.len()].copy_from_slice(path.as_bytes());
+        data
+    }
+
+    #[test]
+    fn test_decode_exec_and_connect_records() {
+        let exec = ProbeRecord::decode(&record(1, "bash", "/usr/bin/curl", 0, &[], 0)).unwrap();
+        assert_eq!(exec.kind, EndpointEventKind::ProcessExec);
+        assert_eq!(exec.pid, 4242);
+        let event = EndpointEvent::from_record(exec, Utc::now());
+        assert_eq!(event.to_fields()["process.executable"], "/usr/bin/curl");
+        assert_eq!(event.to_fields()["user.id"], "1000");
+
+        let connect = ProbeRecord::decode(&record(2, "curl", "", AF_INET, &[203, 0, 113, 7], 443)).unwrap();
+        let event = EndpointEvent::from_record(connect, Utc::now());
+        assert_eq!(event.destination_address.as_deref(), Some("203.0.113.7"));
+        assert_eq!(event.message(), "Network connection to 203.0.113.7:443 from curl (pid 4242)");
+
+        let mut v6 = [0u8; 16];
+        v6[15] = 1;
+        let connect = ProbeRecord::decode(&record(2, "ssh", "", AF_INET6, &v6, 22)).unwrap();
+        assert_eq!(connect.address, Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+
+        assert!(ProbeRecord::decode(&record(9, "x", "", 0, &[], 0)).is_none());
+        assert!(ProbeRecord::decode(&[0u8; 16]).is_none());
+    }
+
+    #[test]
+    fn test_filter_excludes_processes_and_paths() {
+        let config = EbpfCollectorConfig {
+            exclude_processes: vec!["systemd-journal".to_string()],
+            file_path_prefixes: vec!["/etc/".to_string()],
+            ..Default::default()
+        };
+        let filter = EndpointFilter::new(&config);
+
+        let open = |comm: &str, path: &str| ProbeRecord::decode(&record(3, comm, path, 0, &[], 0)).unwrap();
+        assert!(filter.allows(&open("vim", "/etc/shadow")));
+        assert!(!filter.allows(&open("vim", "/tmp/scratch")));
+        assert!(!filter.allows(&open("systemd-journal", "/etc/machine-id")));
+        assert!(filter.allows(&ProbeRecord::decode(&record(1, "bash", "/usr/bin/id", 0, &[], 0)).unwrap()));
+        assert_eq!(EndpointEvent::from_record(open("vim", "/etc/shadow"), Utc::now()).file_flags, Some(0o101));
+    }
+}
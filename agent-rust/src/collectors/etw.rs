@@ -0,0 +1,746 @@
+// ETW (Event Tracing for Windows) collector
+// Runs a real-time trace session with the configured providers (Sysmon, DNS-Client, ...) enabled at their
+// level and keyword filters, and decodes each event's properties through TDH into a normalized event
+
+use crate::collectors::{Collector, RawLogEvent};
+use crate::config::{EtwCollectorConfig, EtwProviderConfig};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tokio::sync::mpsc;
+#[cfg(windows)]
+use tracing::{info, warn};
+
+pub const ETW_SOURCE: &str = "etw";
+
+/// Providers that can be configured by name alone
+const WELL_KNOWN_PROVIDERS: &[(&str, u128)] = &[
+    ("Microsoft-Windows-Sysmon", 0x5770385F_C22A_43E0_BF4C_06F5698FFBD9),
+    ("Microsoft-Windows-DNS-Client", 0x1C95126E_7EEA_49A9_A3FE_A378B03DDB4D),
+    ("Microsoft-Windows-PowerShell", 0xA0C1853B_5C40_4B15_8766_3CF1C58F985A),
+    ("Microsoft-Windows-Kernel-Process", 0x22FB2CD6_0E7B_422B_A0C7_2FAD1FD0E716),
+    ("Microsoft-Windows-Kernel-Network", 0x7DD42A49_5329_4832_8DFD_43D979153A88),
+    ("Microsoft-Windows-Kernel-File", 0xEDD08927_9CC4_4E65_B970_C2560FB5C289),
+    ("Microsoft-Windows-WMI-Activity", 0x1418EF04_B0B4_4623_BF7E_D74AB47BBDAA),
+    ("Microsoft-Windows-TaskScheduler", 0xDE7B24EA_73C8_4A09_985D_5BDADCFA9017),
+    ("Microsoft-Antimalware-Scan-Interface", 0x2A576B87_09A7_520E_C21A_4942F0271D67),
+];
+
+/// Properties summarized in the event message, in order of preference
+const SUMMARY_PROPERTIES: &[&str] = &["CommandLine", "QueryName", "Image", "TargetFilename", "DestinationIp", "ScriptBlockText"];
+const SUMMARY_MAX_CHARS: usize = 200;
+
+/// Parse a GUID written as 8-4-4-4-12 hex digits, with or without braces
+pub fn parse_guid(value: &str) -> Option<u128> {
+    let value = value.trim();
+    let value = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')).unwrap_or(value);
+    let groups: Vec<&str> = value.split('-').collect();
+    let lengths: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+    if lengths != [8, 4, 4, 4, 12] || !groups.iter().all(|group| group.chars().all(|c| c.is_ascii_hexdigit())) {
+        return None;
+    }
+    u128::from_str_radix(&groups.concat(), 16).ok()
+}
+
+pub fn format_guid(guid: u128) -> String {
+    format!(
+        "{:08X}-{:04X}-{:04X}-{:04X}-{:012X}",
+        guid >> 96,
+        (guid >> 80) & 0xFFFF,
+        (guid >> 64) & 0xFFFF,
+        (guid >> 48) & 0xFFFF,
+        guid & 0xFFFF_FFFF_FFFF
+    )
+}
+
+/// GUID from the configuration or the well-known table; other providers are looked up among
+/// those registered on the host when the session starts
+pub fn known_provider_guid(provider: &EtwProviderConfig) -> Option<u128> {
+    match &provider.guid {
+        Some(guid) => parse_guid(guid),
+        None => WELL_KNOWN_PROVIDERS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&provider.name))
+            .map(|(_, guid)| *guid),
+    }
+}
+
+/// TDH input types (`_TDH_IN_TYPE` in tdh.h) understood by `render_property`
+pub mod in_type {
+    pub const UNICODE_STRING: u16 = 1;
+    pub const ANSI_STRING: u16 = 2;
+    pub const INT8: u16 = 3;
+    pub const UINT8: u16 = 4;
+    pub const INT16: u16 = 5;
+    pub const UINT16: u16 = 6;
+    pub const INT32: u16 = 7;
+    pub const UINT32: u16 = 8;
+    pub const INT64: u16 = 9;
+    pub const UINT64: u16 = 10;
+    pub const FLOAT: u16 = 11;
+    pub const DOUBLE: u16 = 12;
+    pub const BOOLEAN: u16 = 13;
+    pub const BINARY: u16 = 14;
+    pub const GUID: u16 = 15;
+    pub const POINTER: u16 = 16;
+    pub const FILETIME: u16 = 17;
+    pub const SYSTEMTIME: u16 = 18;
+    pub const SID: u16 = 19;
+    pub const HEXINT32: u16 = 20;
+    pub const HEXINT64: u16 = 21;
+}
+
+/// Render a property value as TDH returns it (little-endian, in the layout of its input type)
+pub fn render_property(kind: u16, data: &[u8]) -> String {
+    let bytes = |n: usize| data.get(..n);
+    let hex = || data.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    let rendered = match kind {
+        in_type::UNICODE_STRING => {
+            let units: Vec<u16> = data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+            let end = units.iter().position(|u| *u == 0).unwrap_or(units.len());
+            Some(String::from_utf16_lossy(&units[..end]))
+        }
+        in_type::ANSI_STRING => {
+            let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+            Some(String::from_utf8_lossy(&data[..end]).to_string())
+        }
+        in_type::INT8 => bytes(1).map(|b| (b[0] as i8).to_string()),
+        in_type::UINT8 => bytes(1).map(|b| b[0].to_string()),
+        in_type::INT16 => bytes(2).map(|b| i16::from_le_bytes([b[0], b[1]]).to_string()),
+        in_type::UINT16 => bytes(2).map(|b| u16::from_le_bytes([b[0], b[1]]).to_string()),
+        in_type::INT32 => bytes(4).map(|b| i32::from_le_bytes(b.try_into().unwrap_or_default()).to_string()),
+        in_type::UINT32 => bytes(4).map(|b| u32::from_le_bytes(b.try_into().unwrap_or_default()).to_string()),
+        in_type::INT64 => bytes(8).map(|b| i64::from_le_bytes(b.try_into().unwrap_or_default()).to_string()),
+        in_type::UINT64 => bytes(8).map(|b| u64::from_le_bytes(b.try_into().unwrap_or_default()).to_string()),
+        in_type::FLOAT => bytes(4).map(|b| f32::from_le_bytes(b.try_into().unwrap_or_default()).to_string()),
+        in_type::DOUBLE => bytes(8).map(|b| f64::from_le_bytes(b.try_into().unwrap_or_default()).to_string()),
+        in_type::BOOLEAN => bytes(4).map(|b| (u32::from_le_bytes(b.try_into().unwrap_or_default()) != 0).to_string()),
+        in_type::HEXINT32 => bytes(4).map(|b| format!("0x{:x}", u32::from_le_bytes(b.try_into().unwrap_or_default()))),
+        in_type::HEXINT64 => bytes(8).map(|b| format!("0x{:x}", u64::from_le_bytes(b.try_into().unwrap_or_default()))),
+        in_type::POINTER => match data.len() {
+            4 => Some(format!("0x{:x}", u32::from_le_bytes(data.try_into().unwrap_or_default()))),
+            8 => Some(format!("0x{:x}", u64::from_le_bytes(data.try_into().unwrap_or_default()))),
+            _ => None,
+        },
+        in_type::GUID => bytes(16).map(|b| {
+            // Data1..Data3 are little-endian, Data4 is a byte array
+            let data1 = u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as u128;
+            let data2 = u16::from_le_bytes([b[4], b[5]]) as u128;
+            let data3 = u16::from_le_bytes([b[6], b[7]]) as u128;
+            let data4 = u64::from_be_bytes(b[8..16].try_into().unwrap_or_default()) as u128;
+            format_guid(data1 << 96 | data2 << 80 | data3 << 64 | data4)
+        }),
+        in_type::FILETIME => bytes(8)
+            .and_then(|b| filetime_to_utc(i64::from_le_bytes(b.try_into().unwrap_or_default())))
+            .map(|time| time.to_rfc3339()),
+        in_type::SYSTEMTIME => bytes(16).map(|b| {
+            let field = |i: usize| u16::from_le_bytes([b[i * 2], b[i * 2 + 1]]);
+            // wYear, wMonth, wDayOfWeek, wDay, wHour, wMinute, wSecond, wMilliseconds
+            format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}", field(0), field(1), field(3), field(4), field(5), field(6), field(7))
+        }),
+        in_type::SID => render_sid(data),
+        _ => None,
+    };
+    rendered.unwrap_or_else(hex)
+}
+
+/// S-R-I-S-S... form of a binary SID
+fn render_sid(data: &[u8]) -> Option<String> {
+    let revision = *data.first()?;
+    let count = *data.get(1)? as usize;
+    let authority = data.get(2..8)?.iter().fold(0u64, |acc, b| acc << 8 | *b as u64);
+    let mut sid = format!("S-{}-{}", revision, authority);
+    for i in 0..count {
+        let sub = data.get(8 + i * 4..12 + i * 4)?;
+        sid.push_str(&format!("-{}", u32::from_le_bytes(sub.try_into().ok()?)));
+    }
+    Some(sid)
+}
+
+/// FILETIME ticks (100ns since 1601-01-01) to UTC
+pub fn filetime_to_utc(ticks: i64) -> Option<DateTime<Utc>> {
+    const UNIX_EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+    let since_epoch = ticks.checked_sub(UNIX_EPOCH_TICKS)?;
+    DateTime::from_timestamp(since_epoch.div_euclid(10_000_000), (since_epoch.rem_euclid(10_000_000) * 100) as u32)
+}
+
+/// Normalized ETW event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EtwEvent {
+    pub provider_name: String,
+    pub provider_guid: String,
+    pub event_id: u16,
+    pub version: u8,
+    pub level: u8,
+    pub opcode: u8,
+    pub task: u16,
+    pub keywords: u64,
+    pub process_id: u32,
+    pub thread_id: u32,
+    pub time: DateTime<Utc>,
+    /// Top-level event properties decoded through TDH
+    pub properties: BTreeMap<String, String>,
+}
+
+impl EtwEvent {
+    /// Time the event was logged
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    pub fn level_name(&self) -> &'static str {
+        match self.level {
+            1 => "critical",
+            2 => "error",
+            3 => "warning",
+            5 => "debug",
+            // 0 (log always) and 4
+            _ => "info",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        let summary = SUMMARY_PROPERTIES
+            .iter()
+            .find_map(|name| self.properties.get(*name).filter(|value| !value.is_empty()));
+        match summary {
+            Some(value) if value.chars().count() > SUMMARY_MAX_CHARS => format!(
+                "{} event {}: {}...",
+                self.provider_name,
+                self.event_id,
+                value.chars().take(SUMMARY_MAX_CHARS).collect::<String>()
+            ),
+            Some(value) => format!("{} event {}: {}", self.provider_name, self.event_id, value),
+            None => format!("{} event {}", self.provider_name, self.event_id),
+        }
+    }
+
+    /// Flattened fields for the parsed event; properties keep their manifest names under etw.data
+    pub fn to_fields(&self) -> HashMap<String, Value> {
+        let mut fields = HashMap::from([
+            ("event.code".to_string(), json!(self.event_id.to_string())),
+            ("event.provider".to_string(), json!(self.provider_name)),
+            ("etw.provider_guid".to_string(), json!(self.provider_guid)),
+            ("etw.version".to_string(), json!(self.version)),
+            ("etw.level".to_string(), json!(self.level)),
+            ("etw.opcode".to_string(), json!(self.opcode)),
+            ("etw.task".to_string(), json!(self.task)),
+            ("etw.keywords".to_string(), json!(format!("0x{:x}", self.keywords))),
+            ("process.pid".to_string(), json!(self.process_id)),
+            ("process.thread.id".to_string(), json!(self.thread_id)),
+        ]);
+        for (name, value) in &self.properties {
+            fields.insert(format!("etw.data.{}", name), json!(value));
+        }
+        fields
+    }
+}
+
+/// Event ID filters per provider, applied after decoding
+#[derive(Debug, Clone, Default)]
+pub struct EtwEventFilter {
+    event_ids: HashMap<u128, HashSet<u16>>,
+}
+
+impl EtwEventFilter {
+    pub fn new(providers: &[(u128, &EtwProviderConfig)]) -> Self {
+        let event_ids = providers
+            .iter()
+            .filter(|(_, provider)| !provider.event_ids.is_empty())
+            .map(|(guid, provider)| (*guid, provider.event_ids.iter().copied().collect()))
+            .collect();
+        Self { event_ids }
+    }
+
+    pub fn allows(&self, provider: u128, event_id: u16) -> bool {
+        self.event_ids.get(&provider).map_or(true, |ids| ids.contains(&event_id))
+    }
+}
+
+// Without ETW the collector only reports why it can't start
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct EtwCollector {
+    config: EtwCollectorConfig,
+    event_sender: mpsc::Sender<RawLogEvent>,
+    session: Option<std::thread::JoinHandle<()>>,
+    running: bool,
+}
+
+impl EtwCollector {
+    pub fn new(config: EtwCollectorConfig, event_sender: mpsc::Sender<RawLogEvent>) -> Self {
+        Self {
+            config,
+            event_sender,
+            session: None,
+            running: false,
+        }
+    }
+
+    /// Provider names enabled in the session
+    pub fn providers(&self) -> Vec<&str> {
+        self.config.providers.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    fn init_error(&self, reason: String) -> CollectorError {
+        CollectorError::InitializationFailed {
+            name: "etw".to_string(),
+            collector_type: "etw".to_string(),
+            reason,
+            configuration: self.config.session_name.clone(),
+        }
+    }
+}
+
+#[cfg(windows)]
+#[async_trait]
+impl Collector for EtwCollector {
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        if !self.config.enabled {
+            info!("ETW collector is disabled");
+            return Ok(());
+        }
+
+        let registered = session::registered_providers();
+        let mut providers = Vec::new();
+        for provider in &self.config.providers {
+            let guid = known_provider_guid(provider)
+                .or_else(|| registered.get(&provider.name.to_ascii_lowercase()).copied())
+                .ok_or_else(|| self.init_error(format!("provider '{}' is not registered on this host; set its guid", provider.name)))?;
+            providers.push((guid, provider.clone()));
+        }
+
+        let trace = session::TraceSession::start(&self.config, &providers).map_err(|e| self.init_error(e))?;
+        info!("🚀 Starting ETW collector (session {}, providers: {})", self.config.session_name, self.providers().join(", "));
+
+        let context = session::CallbackContext::new(&self.config, &providers, self.event_sender.clone());
+        let handle = std::thread::Builder::new()
+            .name("etw-session".to_string())
+            .spawn(move || trace.process(context))
+            .map_err(|e| self.init_error(format!("failed to start the trace thread: {}", e)))?;
+        self.session = Some(handle);
+        self.running = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        info!("🛑 Stopping ETW collector");
+
+        // Stopping the session ends ProcessTrace on the trace thread
+        if let Err(e) = session::stop_session(&self.config.session_name) {
+            warn!("⚠️ Failed to stop ETW session {}: {}", self.config.session_name, e);
+        }
+        if let Some(handle) = self.session.take() {
+            let _ = tokio::task::spawn_blocking(move || handle.join()).await;
+        }
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        // Events are delivered by the trace session thread
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "etw"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(not(windows))]
+#[async_trait]
+impl Collector for EtwCollector {
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        Err(self.init_error("ETW collection is only available on Windows".to_string()))
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "etw"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(windows)]
+mod session {
+    use super::*;
+    use std::ffi::c_void;
+    use std::time::Instant;
+    use windows::core::{GUID, HSTRING, PWSTR};
+    use windows::Win32::Foundation::{ERROR_ALREADY_EXISTS, ERROR_INSUFFICIENT_BUFFER, ERROR_SUCCESS};
+    use windows::Win32::System::Diagnostics::Etw::*;
+
+    const INVALID_PROCESSTRACE_HANDLE: u64 = u64::MAX;
+    /// PropertyStruct in EVENT_PROPERTY_INFO.Flags
+    const PROPERTY_STRUCT: i32 = 0x1;
+
+    /// EVENT_TRACE_PROPERTIES followed by the session name, 8-byte aligned as StartTrace requires
+    struct SessionProperties {
+        buffer: Vec<u64>,
+    }
+
+    impl SessionProperties {
+        fn new(name: &str, buffer_size_kb: u32) -> Self {
+            let header = std::mem::size_of::<EVENT_TRACE_PROPERTIES>();
+            let total = header + (name.encode_utf16().count() + 1) * 2;
+            let mut properties = Self { buffer: vec![0u64; total.div_ceil(8)] };
+            let header_ptr = properties.as_mut_ptr();
+            // SAFETY: the buffer is zeroed, aligned and large enough for the header
+            unsafe {
+                (*header_ptr).Wnode.BufferSize = total as u32;
+                (*header_ptr).Wnode.Flags = WNODE_FLAG_TRACED_GUID;
+                // Query performance counter clock
+                (*header_ptr).Wnode.ClientContext = 1;
+                (*header_ptr).BufferSize = buffer_size_kb;
+                (*header_ptr).LogFileMode = EVENT_TRACE_REAL_TIME_MODE;
+                (*header_ptr).LoggerNameOffset = header as u32;
+            }
+            properties
+        }
+
+        fn as_mut_ptr(&mut self) -> *mut EVENT_TRACE_PROPERTIES {
+            self.buffer.as_mut_ptr() as *mut EVENT_TRACE_PROPERTIES
+        }
+    }
+
+    /// Stop a session by name, e.g. one left behind by a crashed agent
+    pub fn stop_session(name: &str) -> Result<(), String> {
+        let mut properties = SessionProperties::new(name, 0);
+        // SAFETY: properties points at a buffer sized for the header and the session name
+        let status = unsafe {
+            ControlTraceW(CONTROLTRACE_HANDLE::default(), &HSTRING::from(name), properties.as_mut_ptr(), EVENT_TRACE_CONTROL_STOP)
+        };
+        if status == ERROR_SUCCESS {
+            Ok(())
+        } else {
+            Err(format!("ControlTrace failed with error {}", status.0))
+        }
+    }
+
+    /// Providers registered on the host, by lowercase name
+    pub fn registered_providers() -> HashMap<String, u128> {
+        let mut size = 0u32;
+        // SAFETY: a null buffer only queries the required size
+        let status = unsafe { TdhEnumerateProviders(None, &mut size) };
+        if status != ERROR_INSUFFICIENT_BUFFER.0 || size == 0 {
+            return HashMap::new();
+        }
+        let mut buffer = vec![0u64; (size as usize).div_ceil(8)];
+        let info = buffer.as_mut_ptr() as *mut PROVIDER_ENUMERATION_INFO;
+        // SAFETY: the buffer holds `size` bytes and is aligned for PROVIDER_ENUMERATION_INFO
+        if unsafe { TdhEnumerateProviders(Some(info), &mut size) } != ERROR_SUCCESS.0 {
+            return HashMap::new();
+        }
+
+        let base = buffer.as_ptr() as *const u8;
+        let mut providers = HashMap::new();
+        // SAFETY: TDH wrote NumberOfProviders entries and their names inside the buffer
+        unsafe {
+            let entries = std::slice::from_raw_parts((*info).TraceProviderInfoArray.as_ptr(), (*info).NumberOfProviders as usize);
+            for entry in entries {
+                let name = wide_string_at(base, entry.ProviderNameOffset);
+                providers.insert(name.to_ascii_lowercase(), entry.ProviderGuid.to_u128());
+            }
+        }
+        providers
+    }
+
+    /// Null-terminated UTF-16 string at `offset` bytes into a TDH buffer
+    unsafe fn wide_string_at(base: *const u8, offset: u32) -> String {
+        let start = base.add(offset as usize) as *const u16;
+        let mut len = 0;
+        while *start.add(len) != 0 {
+            len += 1;
+        }
+        String::from_utf16_lossy(std::slice::from_raw_parts(start, len))
+    }
+
+    pub struct TraceSession {
+        name: String,
+        handle: CONTROLTRACE_HANDLE,
+    }
+
+    // The control handle is a plain session identifier
+    unsafe impl Send for TraceSession {}
+
+    impl TraceSession {
+        /// Start the real-time session and enable every provider in it
+        pub fn start(config: &EtwCollectorConfig, providers: &[(u128, EtwProviderConfig)]) -> Result<Self, String> {
+            let name = HSTRING::from(config.session_name.as_str());
+            let mut handle = CONTROLTRACE_HANDLE::default();
+            let mut properties = SessionProperties::new(&config.session_name, config.buffer_size_kb);
+            // SAFETY: properties is sized for the header and the session name
+            let mut status = unsafe { StartTraceW(&mut handle, &name, properties.as_mut_ptr()) };
+            if status == ERROR_ALREADY_EXISTS {
+                warn!("⚠️ Replacing existing ETW session {}", config.session_name);
+                stop_session(&config.session_name)?;
+                let mut properties = SessionProperties::new(&config.session_name, config.buffer_size_kb);
+                // SAFETY: as above
+                status = unsafe { StartTraceW(&mut handle, &name, properties.as_mut_ptr()) };
+            }
+            if status != ERROR_SUCCESS {
+                return Err(format!("StartTrace failed with error {} (administrative privileges are required)", status.0));
+            }
+            let session = Self { name: config.session_name.clone(), handle };
+
+            for (guid, provider) in providers {
+                let provider_id = GUID::from_u128(*guid);
+                // SAFETY: handle is the session just started; provider_id outlives the call
+                let status = unsafe {
+                    EnableTraceEx2(
+                        session.handle,
+                        &provider_id,
+                        EVENT_CONTROL_CODE_ENABLE_PROVIDER,
+                        provider.level,
+                        provider.match_any_keyword,
+                        provider.match_all_keyword,
+                        0,
+                        None,
+                    )
+                };
+                if status != ERROR_SUCCESS {
+                    let _ = stop_session(&session.name);
+                    return Err(format!("failed to enable provider '{}' (error {})", provider.name, status.0));
+                }
+            }
+            Ok(session)
+        }
+
+        /// Deliver events until the session is stopped; runs on the dedicated trace thread
+        pub fn process(self, mut context: CallbackContext) {
+            let mut logger_name: Vec<u16> = self.name.encode_utf16().chain(std::iter::once(0)).collect();
+            let mut logfile = EVENT_TRACE_LOGFILEW::default();
+            logfile.LoggerName = PWSTR(logger_name.as_mut_ptr());
+            logfile.Anonymous1.ProcessTraceMode = PROCESS_TRACE_MODE_REAL_TIME | PROCESS_TRACE_MODE_EVENT_RECORD;
+            logfile.Anonymous2.EventRecordCallback = Some(on_event);
+            logfile.Context = &mut context as *mut CallbackContext as *mut c_void;
+
+            // SAFETY: logfile, logger_name and context stay alive until ProcessTrace returns
+            unsafe {
+                let trace = OpenTraceW(&mut logfile);
+                if trace.Value == INVALID_PROCESSTRACE_HANDLE {
+                    warn!("⚠️ Failed to open ETW session {} for processing", self.name);
+                    let _ = stop_session(&self.name);
+                    return;
+                }
+                let status = ProcessTrace(&[trace], None, None);
+                if status != ERROR_SUCCESS {
+                    warn!("⚠️ ETW session {} stopped processing (error {})", self.name, status.0);
+                }
+                let _ = CloseTrace(trace);
+            }
+            if context.dropped > 0 {
+                warn!("⚠️ ETW collector dropped {} events to rate limiting or a full pipeline", context.dropped);
+            }
+            info!("ETW session {} closed", self.name);
+        }
+    }
+
+    /// State for `on_event`, owned by the trace thread and reached through EVENT_TRACE_LOGFILEW.Context
+    pub struct CallbackContext {
+        sender: mpsc::Sender<RawLogEvent>,
+        names: HashMap<u128, String>,
+        filter: EtwEventFilter,
+        max_per_second: usize,
+        window_start: Instant,
+        window_count: usize,
+        dropped: u64,
+    }
+
+    impl CallbackContext {
+        pub fn new(config: &EtwCollectorConfig, providers: &[(u128, EtwProviderConfig)], sender: mpsc::Sender<RawLogEvent>) -> Self {
+            let filter_input: Vec<(u128, &EtwProviderConfig)> = providers.iter().map(|(guid, p)| (*guid, p)).collect();
+            Self {
+                sender,
+                names: providers.iter().map(|(guid, p)| (*guid, p.name.clone())).collect(),
+                filter: EtwEventFilter::new(&filter_input),
+                max_per_second: config.max_events_per_second.max(1) as usize,
+                window_start: Instant::now(),
+                window_count: 0,
+                dropped: 0,
+            }
+        }
+    }
+
+    unsafe extern "system" fn on_event(record: *mut EVENT_RECORD) {
+        let Some(record) = record.as_ref() else { return };
+        let Some(context) = (record.UserContext as *mut CallbackContext).as_mut() else { return };
+        let header = &record.EventHeader;
+        let provider = header.ProviderId.to_u128();
+        let descriptor = &header.EventDescriptor;
+        if !context.filter.allows(provider, descriptor.Id) {
+            return;
+        }
+        if context.window_start.elapsed() >= std::time::Duration::from_secs(1) {
+            context.window_start = Instant::now();
+            context.window_count = 0;
+        }
+        if context.window_count >= context.max_per_second {
+            context.dropped += 1;
+            return;
+        }
+        context.window_count += 1;
+
+        let event = EtwEvent {
+            provider_name: context.names.get(&provider).cloned().unwrap_or_else(|| format_guid(provider)),
+            provider_guid: format_guid(provider),
+            event_id: descriptor.Id,
+            version: descriptor.Version,
+            level: descriptor.Level,
+            opcode: descriptor.Opcode,
+            task: descriptor.Task,
+            keywords: descriptor.Keyword,
+            process_id: header.ProcessId,
+            thread_id: header.ThreadId,
+            time: filetime_to_utc(header.TimeStamp).unwrap_or_else(Utc::now),
+            properties: decode_properties(record),
+        };
+        let raw_event = RawLogEvent {
+            timestamp: event.timestamp(),
+            source: ETW_SOURCE.to_string(),
+            raw_data: serde_json::to_string(&event).unwrap_or_default().into(),
+            metadata: HashMap::from([
+                ("etw_provider".to_string(), event.provider_name.clone()),
+                ("etw_event_id".to_string(), event.event_id.to_string()),
+            ]),
+        };
+        // The trace thread must not block, so a full pipeline drops the event
+        if context.sender.try_send(raw_event).is_err() {
+            context.dropped += 1;
+        }
+    }
+
+    /// Top-level scalar properties by name; structs and undecodable properties are left out
+    unsafe fn decode_properties(record: &EVENT_RECORD) -> BTreeMap<String, String> {
+        let mut properties = BTreeMap::new();
+        let mut size = 0u32;
+        if TdhGetEventInformation(record, None, None, &mut size) != ERROR_INSUFFICIENT_BUFFER.0 {
+            return properties;
+        }
+        let mut buffer = vec![0u64; (size as usize).div_ceil(8)];
+        let info = buffer.as_mut_ptr() as *mut TRACE_EVENT_INFO;
+        if TdhGetEventInformation(record, None, Some(info), &mut size) != ERROR_SUCCESS.0 {
+            return properties;
+        }
+
+        let base = buffer.as_ptr() as *const u8;
+        let count = (*info).TopLevelPropertyCount as usize;
+        let entries = std::slice::from_raw_parts((*info).EventPropertyInfoArray.as_ptr(), count);
+        for entry in entries {
+            if entry.Flags.0 & PROPERTY_STRUCT != 0 {
+                continue;
+            }
+            let descriptor = PROPERTY_DATA_DESCRIPTOR {
+                PropertyName: base.add(entry.NameOffset as usize) as u64,
+                ArrayIndex: u32::MAX,
+                Reserved: 0,
+            };
+            let mut property_size = 0u32;
+            if TdhGetPropertySize(record, None, &[descriptor], &mut property_size) != ERROR_SUCCESS.0 {
+                continue;
+            }
+            let mut data = vec![0u8; property_size as usize];
+            if TdhGetProperty(record, None, &[descriptor], &mut data) != ERROR_SUCCESS.0 {
+                continue;
+            }
+            let name = wide_string_at(base, entry.NameOffset);
+            properties.insert(name, render_property(entry.Anonymous1.nonStructType.InType, &data));
+        }
+        properties
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(name: &str, guid: Option<&str>, event_ids: Vec<u16>) -> EtwProviderConfig {
+        EtwProviderConfig {
+            name: name.to_string(),
+            guid: guid.map(|g| g.to_string()),
+            level: 4,
+            match_any_keyword: 0,
+            match_all_keyword: 0,
+            event_ids,
+        }
+    }
+
+    #[test]
+    fn test_provider_guids_and_event_filter() {
+        let sysmon = provider("microsoft-windows-sysmon", None, vec![1, 3]);
+        let sysmon_guid = known_provider_guid(&sysmon).unwrap();
+        assert_eq!(format_guid(sysmon_guid), "5770385F-C22A-43E0-BF4C-06F5698FFBD9");
+
+        let custom = provider("Contoso-App", Some("{1c95126e-7eea-49a9-a3fe-a378b03ddb4d}"), vec![]);
+        assert_eq!(known_provider_guid(&custom), Some(0x1C95126E_7EEA_49A9_A3FE_A378B03DDB4D));
+        assert_eq!(known_provider_guid(&provider("Contoso-Unknown", None, vec![])), None);
+        assert_eq!(parse_guid("1C95126E-7EEA-49A9-A3FE"), None);
+        assert_eq!(parse_guid("1C95126E-7EEA-49A9-A3FE-A378B03DDBZZ"), None);
+
+        let filter = EtwEventFilter::new(&[(sysmon_guid, &sysmon), (0x1C95126E_7EEA_49A9_A3FE_A378B03DDB4D, &custom)]);
+        assert!(filter.allows(sysmon_guid, 3));
+        assert!(!filter.allows(sysmon_guid, 22));
+        assert!(filter.allows(0x1C95126E_7EEA_49A9_A3FE_A378B03DDB4D, 3008));
+    }
+
+    #[test]
+    fn test_render_properties_and_fields() {
+        let utf16: Vec<u8> = "example.com\0".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        assert_eq!(render_property(in_type::UNICODE_STRING, &utf16), "example.com");
+        assert_eq!(render_property(in_type::UINT32, &4242u32.to_le_bytes()), "4242");
+        assert_eq!(render_property(in_type::INT16, &(-2i16).to_le_bytes()), "-2");
+        assert_eq!(render_property(in_type::HEXINT64, &0x1fu64.to_le_bytes()), "0x1f");
+        assert_eq!(render_property(in_type::BOOLEAN, &1u32.to_le_bytes()), "true");
+        // S-1-5-21-1-2-3-1001
+        let mut sid = vec![1u8, 5, 0, 0, 0, 0, 0, 5];
+        for sub in [21u32, 1, 2, 3, 1001] {
+            sid.extend_from_slice(&sub.to_le_bytes());
+        }
+        assert_eq!(render_property(in_type::SID, &sid), "S-1-5-21-1-2-3-1001");
+        let guid = [0x6e, 0x12, 0x95, 0x1c, 0xea, 0x7e, 0xa9, 0x49, 0xa3, 0xfe, 0xa3, 0x78, 0xb0, 0x3d, 0xdb, 0x4d];
+        assert_eq!(render_property(in_type::GUID, &guid), "1C95126E-7EEA-49A9-A3FE-A378B03DDB4D");
+        assert_eq!(render_property(in_type::FILETIME, &116_444_736_000_000_000i64.to_le_bytes()), "1970-01-01T00:00:00+00:00");
+        assert_eq!(render_property(in_type::UINT32, &[1, 2]), "0102");
+
+        let event = EtwEvent {
+            provider_name: "Microsoft-Windows-DNS-Client".to_string(),
+            provider_guid: "1C95126E-7EEA-49A9-A3FE-A378B03DDB4D".to_string(),
+            event_id: 3008,
+            version: 0,
+            level: 4,
+            opcode: 0,
+            task: 0,
+            keywords: 0x8000000000000000,
+            process_id: 1234,
+            thread_id: 5678,
+            time: Utc::now(),
+            properties: BTreeMap::from([("QueryName".to_string(), "example.com".to_string())]),
+        };
+        assert_eq!(event.message(), "Microsoft-Windows-DNS-Client event 3008: example.com");
+        assert_eq!(event.level_name(), "info");
+        let fields = event.to_fields();
+        assert_eq!(fields["event.code"], "3008");
+        assert_eq!(fields["etw.data.QueryName"], "example.com");
+        assert_eq!(fields["etw.keywords"], "0x8000000000000000");
+    }
+}
@@ -0,0 +1,508 @@
+// Backfill of rotated, compressed log files for the file monitor
+// Archives such as app.log.1.gz are decompressed as they are read by the decoder registered for their format, so
+// nothing is unpacked to disk. Checkpoints are keyed by a fingerprint of the archive's content, so logrotate
+// renaming app.log.1.gz to app.log.2.gz doesn't read it again, and record how far into the decompressed content
+// each archive was read, so a restart resumes a long backfill instead of starting over.
+
+use crate::collectors::file_monitor::MultilineAggregator;
+use crate::collectors::RawLogEvent;
+use crate::config::{ArchiveBackfillConfig, MultilineConfig};
+use chrono::{DateTime, Utc};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Bytes from the start of an archive hashed into its fingerprint
+const FINGERPRINT_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    Gz,
+    Zip,
+    Bz2,
+}
+
+impl ArchiveFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ArchiveFormat::Gz => "gz",
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::Bz2 => "bz2",
+        }
+    }
+}
+
+/// Called with each decompressed stream of an archive in order and the stream's name inside the archive, if it
+/// has one; returns false to stop reading
+pub type StreamVisitor<'a> = dyn FnMut(Option<&str>, &mut dyn Read) -> io::Result<bool> + 'a;
+
+/// Decompresses one archive format
+pub trait ArchiveDecoder: Send + Sync {
+    fn format(&self) -> ArchiveFormat;
+    /// File name suffixes, compared case-insensitively
+    fn extensions(&self) -> &'static [&'static str];
+    /// Leading bytes every archive of the format starts with
+    fn magic(&self) -> &'static [u8];
+    fn read_streams(&self, file: File, visit: &mut StreamVisitor<'_>) -> io::Result<()>;
+}
+
+struct GzipDecoder;
+
+impl ArchiveDecoder for GzipDecoder {
+    fn format(&self) -> ArchiveFormat {
+        ArchiveFormat::Gz
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".gz", ".gzip"]
+    }
+
+    fn magic(&self) -> &'static [u8] {
+        &[0x1f, 0x8b]
+    }
+
+    fn read_streams(&self, file: File, visit: &mut StreamVisitor<'_>) -> io::Result<()> {
+        // Concatenated members (cat a.gz b.gz) are one stream to gzip, so they are here too
+        visit(None, &mut flate2::read::MultiGzDecoder::new(BufReader::new(file))).map(drop)
+    }
+}
+
+struct Bzip2Decoder;
+
+impl ArchiveDecoder for Bzip2Decoder {
+    fn format(&self) -> ArchiveFormat {
+        ArchiveFormat::Bz2
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".bz2", ".bzip2"]
+    }
+
+    fn magic(&self) -> &'static [u8] {
+        b"BZh"
+    }
+
+    fn read_streams(&self, file: File, visit: &mut StreamVisitor<'_>) -> io::Result<()> {
+        visit(None, &mut bzip2::read::MultiBzDecoder::new(BufReader::new(file))).map(drop)
+    }
+}
+
+struct ZipDecoder;
+
+impl ArchiveDecoder for ZipDecoder {
+    fn format(&self) -> ArchiveFormat {
+        ArchiveFormat::Zip
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".zip"]
+    }
+
+    fn magic(&self) -> &'static [u8] {
+        b"PK\x03\x04"
+    }
+
+    fn read_streams(&self, file: File, visit: &mut StreamVisitor<'_>) -> io::Result<()> {
+        let mut archive = zip::ZipArchive::new(BufReader::new(file)).map_err(io::Error::other)?;
+        for index in 0..archive.len() {
+            let mut member = match archive.by_index(index) {
+                Ok(member) if member.is_dir() => continue,
+                Ok(member) => member,
+                // Encrypted members and unsupported compression methods; the rest of the archive is still read
+                Err(e) => {
+                    warn!("⚠️ Skipping zip member {}: {}", index, e);
+                    continue;
+                }
+            };
+            let name = member.name().to_string();
+            if !visit(Some(&name), &mut member)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+static DECODERS: [&dyn ArchiveDecoder; 3] = [&GzipDecoder, &ZipDecoder, &Bzip2Decoder];
+
+/// The decoder for an archive by its file name
+pub fn decoder_for(path: &Path) -> Option<&'static dyn ArchiveDecoder> {
+    let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+    DECODERS.iter().copied().find(|decoder| decoder.extensions().iter().any(|extension| name.ends_with(extension)))
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    decoder_for(path).is_some()
+}
+
+/// The file name without its compression suffix ("app.log.1.gz" becomes "app.log.1")
+pub fn uncompressed_name(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let decoder = decoder_for(path)?;
+    let lowercase = name.to_ascii_lowercase();
+    let extension = decoder.extensions().iter().find(|extension| lowercase.ends_with(*extension))?;
+    Some(name[..name.len() - extension.len()].to_string())
+}
+
+/// SHA-256 of the archive's first bytes and its size; stays the same across renames
+pub fn fingerprint(path: &Path) -> io::Result<String> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut head = Vec::new();
+    file.take(FINGERPRINT_BYTES).read_to_end(&mut head)?;
+    let mut context = digest::Context::new(&digest::SHA256);
+    context.update(&head);
+    context.update(&size.to_le_bytes());
+    Ok(context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ArchiveCheckpoint {
+    /// Where the archive was last seen
+    path: PathBuf,
+    /// Decompressed bytes read, always at the end of a line
+    offset: u64,
+    complete: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckpointFile {
+    /// When backfill first ran. Archives written after it were rotated out of files the agent was already
+    /// tailing, so their lines were collected live and they are marked complete unread.
+    tailing_since: Option<DateTime<Utc>>,
+    archives: HashMap<String, ArchiveCheckpoint>,
+}
+
+/// Archives read by one backfill run
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BackfillSummary {
+    pub archives_read: usize,
+    /// Already read by an earlier run or collected live before they were rotated
+    pub archives_skipped: usize,
+    pub archives_failed: usize,
+    pub records: u64,
+}
+
+/// Reads archives into the file monitor's event channel; blocking, so it runs on the blocking pool
+pub struct ArchiveBackfill {
+    config: ArchiveBackfillConfig,
+    multiline: Option<MultilineConfig>,
+    sender: mpsc::Sender<RawLogEvent>,
+    stop: Arc<AtomicBool>,
+    checkpoints: CheckpointFile,
+}
+
+impl ArchiveBackfill {
+    pub fn new(
+        config: ArchiveBackfillConfig,
+        multiline: Option<MultilineConfig>,
+        sender: mpsc::Sender<RawLogEvent>,
+        stop: Arc<AtomicBool>,
+    ) -> Self {
+        let checkpoints = Self::load_checkpoints(&config.checkpoint_path);
+        Self { config, multiline, sender, stop, checkpoints }
+    }
+
+    fn load_checkpoints(path: &str) -> CheckpointFile {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return CheckpointFile::default(),
+            Err(e) => {
+                warn!("⚠️ Cannot read archive checkpoints {}: {}", path, e);
+                return CheckpointFile::default();
+            }
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("⚠️ Ignoring unreadable archive checkpoints {}: {}", path, e);
+            CheckpointFile::default()
+        })
+    }
+
+    fn save_checkpoints(&self) -> io::Result<()> {
+        let path = Path::new(&self.config.checkpoint_path);
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, serde_json::to_vec(&self.checkpoints)?)?;
+        std::fs::rename(&temporary, path)
+    }
+
+    fn save_checkpoints_or_warn(&self) {
+        if let Err(e) = self.save_checkpoints() {
+            warn!("⚠️ Cannot save archive checkpoints {}: {}", self.config.checkpoint_path, e);
+        }
+    }
+
+    /// The configured backfill globs, expanded
+    pub fn configured_archives(&self) -> Vec<PathBuf> {
+        self.config.paths.iter()
+            .filter_map(|pattern| ::glob::glob(pattern).ok())
+            .flat_map(|paths| paths.flatten())
+            .filter(|path| path.is_file() && is_archive(path))
+            .collect()
+    }
+
+    /// Read every archive not read yet, oldest first, until done or stopped
+    pub fn run(mut self, mut archives: Vec<PathBuf>) -> BackfillSummary {
+        archives.extend(self.configured_archives());
+        archives.sort();
+        archives.dedup();
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).map(DateTime::<Utc>::from).ok();
+        archives.sort_by_key(|path| modified(path));
+
+        let tailing_since = *self.checkpoints.tailing_since.get_or_insert_with(Utc::now);
+        let mut summary = BackfillSummary::default();
+        for path in archives {
+            if self.stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let Some(decoder) = decoder_for(&path).filter(|decoder| self.config.formats.contains(&decoder.format())) else {
+                debug!("Skipping archive in a format not enabled for backfill: {}", path.display());
+                continue;
+            };
+            let rotated_while_tailing = modified(&path).is_some_and(|time| time > tailing_since);
+            match self.read_archive(&path, decoder, rotated_while_tailing) {
+                Ok(Some(records)) => {
+                    summary.archives_read += 1;
+                    summary.records += records;
+                }
+                Ok(None) => summary.archives_skipped += 1,
+                Err(e) => {
+                    warn!("⚠️ Failed to backfill archive {}: {}", path.display(), e);
+                    summary.archives_failed += 1;
+                }
+            }
+        }
+        self.save_checkpoints_or_warn();
+        info!(
+            "📦 Archive backfill: {} read ({} records), {} already collected, {} failed",
+            summary.archives_read, summary.records, summary.archives_skipped, summary.archives_failed
+        );
+        summary
+    }
+
+    /// Records sent from one archive, or None when it needed no reading
+    fn read_archive(&mut self, path: &Path, decoder: &dyn ArchiveDecoder, rotated_while_tailing: bool) -> io::Result<Option<u64>> {
+        let key = fingerprint(path)?;
+        let checkpoint = self.checkpoints.archives.entry(key.clone()).or_default();
+        checkpoint.path = path.to_path_buf();
+        if checkpoint.complete || (rotated_while_tailing && checkpoint.offset == 0) {
+            checkpoint.complete = true;
+            return Ok(None);
+        }
+        let resume_at = checkpoint.offset;
+
+        let mut magic = vec![0; decoder.magic().len()];
+        let mut file = File::open(path)?;
+        file.read_exact(&mut magic)?;
+        if magic != decoder.magic() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("not a {} archive", decoder.format().as_str())));
+        }
+        let file = File::open(path)?;
+        if resume_at > 0 {
+            info!("📦 Resuming backfill of {} after {} bytes", path.display(), resume_at);
+        } else {
+            info!("📦 Backfilling {}", path.display());
+        }
+
+        let format = decoder.format();
+        let mut offset = 0u64;
+        let mut records = 0u64;
+        let mut unsaved = 0usize;
+        let mut stopped = false;
+        decoder.read_streams(file, &mut |member, stream| {
+            let label = match member {
+                Some(member) => format!("{}!{}", path.display(), member),
+                None => path.display().to_string(),
+            };
+            let mut aggregator = self.multiline.as_ref()
+                .map(MultilineAggregator::new)
+                .transpose()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("multiline pattern: {}", e)))?;
+            let mut reader = BufReader::new(stream);
+            let mut line = Vec::new();
+            loop {
+                if self.stop.load(Ordering::Relaxed) {
+                    stopped = true;
+                    return Ok(false);
+                }
+                line.clear();
+                let read = reader.read_until(b'\n', &mut line)?;
+                if read == 0 {
+                    break;
+                }
+                offset += read as u64;
+                // Read by an earlier run; a stream can only be skipped by decompressing it
+                if offset <= resume_at {
+                    continue;
+                }
+                let text = String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string();
+                let record = match &mut aggregator {
+                    Some(aggregator) if !text.trim().is_empty() => aggregator.push(text, Instant::now()),
+                    None if !text.trim().is_empty() => Some(text.trim().to_string()),
+                    _ => None,
+                };
+                if let Some(record) = record {
+                    if self.sender.blocking_send(archive_event(&label, format, record)).is_err() {
+                        stopped = true;
+                        return Ok(false);
+                    }
+                    records += 1;
+                    unsaved += 1;
+                }
+                // A record still being joined is lost if the agent stops before the next checkpoint
+                if unsaved >= self.config.checkpoint_interval {
+                    unsaved = 0;
+                    self.checkpoints.archives.entry(key.clone()).or_default().offset = offset;
+                    self.save_checkpoints_or_warn();
+                }
+            }
+            // A record can't continue into the next member
+            if let Some(record) = aggregator.as_mut().and_then(MultilineAggregator::flush) {
+                if self.sender.blocking_send(archive_event(&label, format, record)).is_err() {
+                    stopped = true;
+                    return Ok(false);
+                }
+                records += 1;
+            }
+            Ok(true)
+        })?;
+
+        let checkpoint = self.checkpoints.archives.entry(key).or_default();
+        checkpoint.offset = checkpoint.offset.max(offset);
+        checkpoint.complete = !stopped;
+        self.save_checkpoints_or_warn();
+        Ok(Some(records))
+    }
+}
+
+fn archive_event(label: &str, format: ArchiveFormat, record: String) -> RawLogEvent {
+    RawLogEvent {
+        timestamp: Utc::now(),
+        source: "file_monitor".to_string(),
+        raw_data: record.into(),
+        metadata: HashMap::from([
+            ("file_path".to_string(), label.to_string()),
+            ("archive_format".to_string(), format.as_str().to_string()),
+        ]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn config(dir: &Path, interval: usize) -> ArchiveBackfillConfig {
+        ArchiveBackfillConfig {
+            enabled: true,
+            checkpoint_path: dir.join("checkpoints.json").display().to_string(),
+            checkpoint_interval: interval,
+            ..ArchiveBackfillConfig::default()
+        }
+    }
+
+    fn backfill(config: &ArchiveBackfillConfig, multiline: Option<MultilineConfig>, archives: &[PathBuf]) -> (BackfillSummary, Vec<RawLogEvent>) {
+        let (sender, mut receiver) = mpsc::channel(1000);
+        let summary = ArchiveBackfill::new(config.clone(), multiline, sender, Arc::new(AtomicBool::new(false))).run(archives.to_vec());
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        (summary, events)
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_gz_bz2_and_zip_archives_are_read_once() {
+        let dir = tempfile::tempdir().unwrap();
+        // Two concatenated gzip members, as `cat` or some rotation tools produce
+        let gz = dir.path().join("app.log.2.gz");
+        std::fs::write(&gz, [gzip(b"gz one\ngz two\n"), gzip(b"gz three\n")].concat()).unwrap();
+        let bz2 = dir.path().join("app.log.3.bz2");
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(b"bz one\r\n\nbz two").unwrap();
+        std::fs::write(&bz2, encoder.finish().unwrap()).unwrap();
+        let zip_path = dir.path().join("logs.ZIP");
+        let mut writer = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        for (name, content) in [("a.log", "zip a\n"), ("b.log", "zip b\n")] {
+            writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+        let plain = dir.path().join("app.log");
+        std::fs::write(&plain, "not an archive\n").unwrap();
+
+        assert!(is_archive(&zip_path) && !is_archive(&plain));
+        assert_eq!(uncompressed_name(&gz).as_deref(), Some("app.log.2"));
+        let config = config(dir.path(), 100);
+        let archives = vec![gz.clone(), bz2.clone(), zip_path.clone(), plain];
+        let (summary, events) = backfill(&config, None, &archives);
+        assert_eq!(summary, BackfillSummary { archives_read: 3, archives_skipped: 0, archives_failed: 0, records: 7 });
+        let mut records: Vec<&str> = events.iter().map(|event| event.raw_data.as_ref()).collect();
+        records.sort();
+        assert_eq!(records, ["bz one", "bz two", "gz one", "gz three", "gz two", "zip a", "zip b"]);
+        let zip_event = events.iter().find(|event| &*event.raw_data == "zip b").unwrap();
+        assert_eq!(zip_event.metadata["file_path"], format!("{}!b.log", zip_path.display()));
+        assert_eq!(zip_event.metadata["archive_format"], "zip");
+
+        // Renamed by the next rotation: same content, so nothing is read again
+        let renamed = dir.path().join("app.log.3.gz");
+        std::fs::rename(&gz, &renamed).unwrap();
+        let (summary, events) = backfill(&config, None, &[renamed, bz2, zip_path]);
+        assert_eq!((summary.archives_skipped, events.len()), (3, 0));
+
+        // An archive whose extension lies is refused instead of read as text
+        let fake = dir.path().join("fake.log.gz");
+        std::fs::write(&fake, "plain text\n").unwrap();
+        let fresh = ArchiveBackfillConfig { checkpoint_path: dir.path().join("fresh.json").display().to_string(), ..config };
+        assert_eq!(backfill(&fresh, None, &[fake]).0.archives_failed, 1);
+    }
+
+    #[test]
+    fn test_interrupted_backfill_resumes_after_the_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("app.log.1.gz");
+        let lines = "2024-05-01 first\n2024-05-01 second\n\tat Frame.one\n\tat Frame.two\n2024-05-01 third\n";
+        std::fs::write(&archive, gzip(lines.as_bytes())).unwrap();
+
+        // Checkpoint as left by a run stopped after the first line
+        let config = config(dir.path(), 1);
+        let key = fingerprint(&archive).unwrap();
+        let checkpoints = CheckpointFile {
+            tailing_since: Some(Utc::now()),
+            archives: HashMap::from([(key.clone(), ArchiveCheckpoint { path: archive.clone(), offset: 17, complete: false })]),
+        };
+        std::fs::write(&config.checkpoint_path, serde_json::to_vec(&checkpoints).unwrap()).unwrap();
+
+        let multiline = MultilineConfig {
+            start_pattern: Some(r"^\d{4}-".to_string()),
+            continuation_pattern: None,
+            max_lines: 500,
+            timeout_ms: 1000,
+        };
+        let (summary, events) = backfill(&config, Some(multiline), std::slice::from_ref(&archive));
+        assert_eq!(summary.records, 2);
+        let records: Vec<&str> = events.iter().map(|event| event.raw_data.as_ref()).collect();
+        assert_eq!(records, ["2024-05-01 second\n\tat Frame.one\n\tat Frame.two", "2024-05-01 third"]);
+
+        let saved: CheckpointFile = serde_json::from_str(&std::fs::read_to_string(&config.checkpoint_path).unwrap()).unwrap();
+        let checkpoint = &saved.archives[&key];
+        assert_eq!((checkpoint.offset, checkpoint.complete), (lines.len() as u64, true));
+    }
+}
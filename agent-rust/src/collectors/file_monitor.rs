@@ -1,23 +1,99 @@
 // File monitoring collector with pattern matching and recursive directory support
+// Optional multi-line aggregation joins stack traces and wrapped records into single events
+// Compressed archives among the matched files are never tailed; with backfill enabled they are read once instead
 
+use crate::collectors::file_archive::{self, ArchiveBackfill};
 use crate::collectors::{Collector, RawLogEvent};
-use crate::config::FileMonitorConfig;
+use crate::config::{FileMonitorConfig, MultilineConfig};
 use crate::errors::CollectorError;
 use async_trait::async_trait;
 use notify::{Watcher, RecommendedWatcher, RecursiveMode, Event, EventKind};
+use regex::Regex;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader, SeekFrom};
 use tokio::sync::mpsc;
 use tracing::{info, error, debug, warn};
 
+/// Groups lines into multi-line records according to a `MultilineConfig`
+pub struct MultilineAggregator {
+    start: Option<Regex>,
+    continuation: Option<Regex>,
+    max_lines: usize,
+    timeout: Duration,
+    pending: Vec<String>,
+    last_line_at: Option<Instant>,
+}
+
+impl MultilineAggregator {
+    pub fn new(config: &MultilineConfig) -> Result<Self, regex::Error> {
+        Ok(Self {
+            start: config.start_pattern.as_deref().map(Regex::new).transpose()?,
+            continuation: config.continuation_pattern.as_deref().map(Regex::new).transpose()?,
+            max_lines: config.max_lines.max(1),
+            timeout: Duration::from_millis(config.timeout_ms),
+            pending: Vec::new(),
+            last_line_at: None,
+        })
+    }
+    
+    fn continues_record(&self, line: &str) -> bool {
+        let starts = self.start.as_ref().is_some_and(|start| start.is_match(line));
+        match &self.continuation {
+            Some(continuation) => !starts && continuation.is_match(line),
+            None => self.start.is_some() && !starts,
+        }
+    }
+    
+    /// Add a line, returning the record it completed, if any
+    pub fn push(&mut self, line: String, now: Instant) -> Option<String> {
+        let completed = if self.is_expired(now) || !self.continues_record(&line) || self.pending.len() >= self.max_lines {
+            self.flush()
+        } else {
+            None
+        };
+        self.pending.push(line);
+        self.last_line_at = Some(now);
+        completed
+    }
+    
+    /// The pending record, once no line has been added to it for the timeout
+    pub fn flush_expired(&mut self, now: Instant) -> Option<String> {
+        if self.is_expired(now) {
+            self.flush()
+        } else {
+            None
+        }
+    }
+    
+    /// The pending record regardless of age
+    pub fn flush(&mut self) -> Option<String> {
+        self.last_line_at = None;
+        if self.pending.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.pending).join("\n"))
+    }
+    
+    fn is_expired(&self, now: Instant) -> bool {
+        self.last_line_at.is_some_and(|last| now.duration_since(last) >= self.timeout)
+    }
+}
+
 pub struct FileMonitorCollector {
     config: FileMonitorConfig,
     event_sender: mpsc::Sender<RawLogEvent>,
     watcher: Option<RecommendedWatcher>,
     file_positions: HashMap<PathBuf, u64>,
     monitored_files: HashSet<PathBuf>,
+    // Per-file multi-line state when aggregation is configured
+    aggregators: HashMap<PathBuf, MultilineAggregator>,
+    // Tells a running archive backfill to stop at the next line
+    backfill_stop: Arc<AtomicBool>,
     running: bool,
 }
 
@@ -32,10 +108,47 @@ impl FileMonitorCollector {
             watcher: None,
             file_positions: HashMap::new(),
             monitored_files: HashSet::new(),
+            aggregators: HashMap::new(),
+            backfill_stop: Arc::new(AtomicBool::new(false)),
             running: false,
         }
     }
     
+    /// Turn lines read from a file into records, joining multi-line records when configured
+    fn assemble_records(&mut self, file_path: &Path, lines: Vec<String>) -> Result<Vec<String>, CollectorError> {
+        let Some(multiline) = &self.config.multiline else {
+            return Ok(lines.iter()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect());
+        };
+        
+        let aggregator = match self.aggregators.entry(file_path.to_path_buf()) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => entry.insert(
+                MultilineAggregator::new(multiline).map_err(|e| CollectorError::InvalidConfig(format!("multiline pattern: {}", e)))?,
+            ),
+        };
+        
+        let now = Instant::now();
+        Ok(lines.into_iter()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| aggregator.push(line, now))
+            .collect())
+    }
+    
+    fn record_event(file_path: &Path, record: String) -> RawLogEvent {
+        RawLogEvent {
+            timestamp: chrono::Utc::now(),
+            source: "file_monitor".to_string(),
+            raw_data: record.into(),
+            metadata: HashMap::from([
+                ("file_path".to_string(), file_path.display().to_string()),
+            ]),
+        }
+    }
+    
     async fn discover_files(&mut self) -> Result<Vec<PathBuf>, CollectorError> {
         let mut discovered_files = Vec::new();
         
@@ -95,10 +208,12 @@ impl FileMonitorCollector {
         let file_name = path.file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("");
+        // Rotated archives match the patterns of the file they were rotated from ("*.log" matches "app.log.gz")
+        let uncompressed_name = file_archive::uncompressed_name(path);
             
         for pattern in &self.config.patterns {
             if let Ok(pattern_matcher) = ::glob::Pattern::new(pattern) {
-                if pattern_matcher.matches(file_name) {
+                if pattern_matcher.matches(file_name) || uncompressed_name.as_deref().is_some_and(|name| pattern_matcher.matches(name)) {
                     return true;
                 }
             }
@@ -155,9 +270,8 @@ impl FileMonitorCollector {
                 Ok(0) => break, // EOF
                 Ok(n) => {
                     bytes_read += n as u64;
-                    if !line.trim().is_empty() {
-                        lines.push(line.trim().to_string());
-                    }
+                    // Leading whitespace marks continuation lines, so only the line ending is removed here
+                    lines.push(line.trim_end_matches(['\r', '\n']).to_string());
                 }
                 Err(e) => {
                     return Err(CollectorError::FileSystemError {
@@ -214,7 +328,7 @@ impl FileMonitorCollector {
         
         // Spawn task to handle file system events
         let mut monitored_files = self.monitored_files.clone();
-        tokio::spawn(async move {
+        crate::component_usage::spawn_inherited(async move {
             while let Ok(event) = rx.recv() {
                 if let EventKind::Modify(_) = event.kind {
                     for path in event.paths {
@@ -244,7 +358,21 @@ impl Collector for FileMonitorCollector {
         
         // Discover initial files
         let discovered_files = self.discover_files().await?;
-        self.monitored_files = discovered_files.into_iter().collect();
+        let (archives, files): (Vec<PathBuf>, Vec<PathBuf>) = discovered_files.into_iter().partition(|path| file_archive::is_archive(path));
+        self.monitored_files = files.into_iter().collect();
+        
+        if self.config.backfill.enabled {
+            self.backfill_stop.store(false, Ordering::Relaxed);
+            let backfill = ArchiveBackfill::new(
+                self.config.backfill.clone(),
+                self.config.multiline.clone(),
+                self.event_sender.clone(),
+                self.backfill_stop.clone(),
+            );
+            crate::component_usage::spawn_blocking("file_monitor", move || backfill.run(archives));
+        } else if !archives.is_empty() {
+            info!("📦 Skipping {} compressed archives; enable collectors.file_monitor.backfill to read them", archives.len());
+        }
         
         info!("📁 Monitoring {} files", self.monitored_files.len());
         for file in &self.monitored_files {
@@ -256,25 +384,17 @@ impl Collector for FileMonitorCollector {
         
         // Read initial content from all files
         for file_path in self.monitored_files.clone() {
-            match self.read_file_tail(&file_path).await {
-                Ok(lines) => {
-                    for line in lines {
-                        let event = RawLogEvent {
-                            timestamp: chrono::Utc::now(),
-                            source: "file_monitor".to_string(),
-                            raw_data: line,
-                            metadata: HashMap::from([
-                                ("file_path".to_string(), file_path.display().to_string()),
-                            ]),
-                        };
-                        
-                        if let Err(e) = self.event_sender.send(event).await {
-                            error!("Failed to send file monitor event: {}", e);
-                        }
-                    }
-                }
+            let records = match self.read_file_tail(&file_path).await {
+                Ok(lines) => self.assemble_records(&file_path, lines)?,
                 Err(e) => {
                     warn!("Failed to read file {}: {}", file_path.display(), e);
+                    continue;
+                }
+            };
+            
+            for record in records {
+                if let Err(e) = self.event_sender.send(Self::record_event(&file_path, record)).await {
+                    error!("Failed to send file monitor event: {}", e);
                 }
             }
         }
@@ -285,15 +405,29 @@ impl Collector for FileMonitorCollector {
     
     async fn stop(&mut self) -> Result<(), CollectorError> {
         info!("🛑 Stopping file monitor collector");
+        self.backfill_stop.store(true, Ordering::Relaxed);
+        
+        // Records still being assembled are complete once nothing more will be read
+        for (path, aggregator) in self.aggregators.iter_mut() {
+            if let Some(record) = aggregator.flush() {
+                if let Err(e) = self.event_sender.send(Self::record_event(path, record)).await {
+                    error!("Failed to send file monitor event: {}", e);
+                }
+            }
+        }
+
         self.watcher = None;
         self.running = false;
         Ok(())
     }
     
     async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
-        // For file monitor, collection happens via file system events
-        // This method could be used for periodic polling if needed
-        Ok(Vec::new())
+        // For file monitor, collection happens via file system events; polling
+        // completes multi-line records that have gone quiet for the timeout
+        let now = Instant::now();
+        Ok(self.aggregators.iter_mut()
+            .filter_map(|(path, aggregator)| aggregator.flush_expired(now).map(|record| Self::record_event(path, record)))
+            .collect())
     }
     
     fn name(&self) -> &str {
@@ -303,4 +437,65 @@ impl Collector for FileMonitorCollector {
     fn is_running(&self) -> bool {
         self.running
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregator(start: Option<&str>, continuation: Option<&str>, max_lines: usize) -> MultilineAggregator {
+        MultilineAggregator::new(&MultilineConfig {
+            start_pattern: start.map(str::to_string),
+            continuation_pattern: continuation.map(str::to_string),
+            max_lines,
+            timeout_ms: 1000,
+        }).unwrap()
+    }
+
+    fn feed(aggregator: &mut MultilineAggregator, lines: &[&str], now: Instant) -> Vec<String> {
+        lines.iter().filter_map(|line| aggregator.push(line.to_string(), now)).collect()
+    }
+
+    #[test]
+    fn test_stack_traces_are_joined_into_one_record() {
+        let now = Instant::now();
+        let lines = [
+            "2024-05-01 10:00:00 ERROR Request failed",
+            "java.lang.IllegalStateException: boom",
+            "\tat com.example.Service.handle(Service.java:42)",
+            "\tat com.example.Server.run(Server.java:7)",
+            "2024-05-01 10:00:01 INFO Recovered",
+        ];
+
+        let mut by_start = aggregator(Some(r"^\d{4}-\d{2}-\d{2} "), None, 500);
+        let records = feed(&mut by_start, &lines, now);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].lines().count(), 4);
+        assert!(records[0].ends_with("(Server.java:7)"));
+        assert_eq!(by_start.flush().as_deref(), Some("2024-05-01 10:00:01 INFO Recovered"));
+
+        // Continuation-only: indented "at" lines and "Caused by" join the line before them
+        let mut by_continuation = aggregator(None, Some(r"^(\s+at |Caused by:)"), 500);
+        let records = feed(&mut by_continuation, &lines, now);
+        assert_eq!(records, vec![lines[0].to_string(), lines[1..4].join("\n")]);
+    }
+
+    #[test]
+    fn test_records_are_cut_at_max_lines_and_timeout() {
+        let now = Instant::now();
+        let mut capped = aggregator(Some("^START"), None, 2);
+        let records = feed(&mut capped, &["START a", "b", "c", "d"], now);
+        assert_eq!(records, vec!["START a\nb".to_string()]);
+        assert_eq!(capped.flush().as_deref(), Some("c\nd"));
+
+        let mut timed = aggregator(Some("^START"), None, 500);
+        assert!(timed.push("START a".to_string(), now).is_none());
+        assert!(timed.flush_expired(now + Duration::from_millis(500)).is_none());
+        assert_eq!(timed.flush_expired(now + Duration::from_secs(1)).as_deref(), Some("START a"));
+
+        // A late continuation line cannot reopen a record that already timed out
+        timed.push("START b".to_string(), now);
+        assert_eq!(timed.push("late".to_string(), now + Duration::from_secs(2)).as_deref(), Some("START b"));
+        assert!(aggregator(None, None, 1).flush().is_none());
+    }
+}
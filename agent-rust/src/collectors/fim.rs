@@ -0,0 +1,679 @@
+// File integrity monitoring collector
+// Watched files are hashed into a baseline that is kept across restarts. File system notifications and periodic
+// rescans compare files against it and report creations, content changes, deletions and permission or ownership
+// changes as structured events, including changes made while the agent was not running.
+
+use crate::collectors::{Collector, RawLogEvent};
+use crate::config::FimCollectorConfig;
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+/// Source name of file integrity events
+pub const FIM_SOURCE: &str = "fim";
+
+/// Notifications are gathered for this long before the changed paths are examined
+const NOTIFY_SETTLE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FimHashAlgorithm {
+    #[default]
+    Sha256,
+    Sha512,
+    /// For matching legacy indicator lists only
+    Sha1,
+}
+
+impl FimHashAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FimHashAlgorithm::Sha256 => "sha256",
+            FimHashAlgorithm::Sha512 => "sha512",
+            FimHashAlgorithm::Sha1 => "sha1",
+        }
+    }
+
+    fn algorithm(self) -> &'static ring::digest::Algorithm {
+        match self {
+            FimHashAlgorithm::Sha256 => &ring::digest::SHA256,
+            FimHashAlgorithm::Sha512 => &ring::digest::SHA512,
+            FimHashAlgorithm::Sha1 => &ring::digest::SHA1_FOR_LEGACY_USE_ONLY,
+        }
+    }
+
+    fn hash_file(self, path: &Path) -> std::io::Result<String> {
+        let mut file = std::fs::File::open(path)?;
+        let mut context = ring::digest::Context::new(self.algorithm());
+        let mut chunk = vec![0u8; 64 * 1024];
+        loop {
+            let read = file.read(&mut chunk)?;
+            if read == 0 {
+                break;
+            }
+            context.update(&chunk[..read]);
+        }
+        Ok(context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect())
+    }
+}
+
+/// What the baseline remembers about a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileState {
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    /// Unix permission bits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gid: Option<u32>,
+    pub readonly: bool,
+    /// None when the file is larger than the hashing limit or could not be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+impl FileState {
+    fn read(path: &Path, metadata: &std::fs::Metadata, algorithm: FimHashAlgorithm, max_hash_bytes: u64) -> Self {
+        let hash = (metadata.len() <= max_hash_bytes)
+            .then(|| algorithm.hash_file(path))
+            .and_then(|hash| hash.inspect_err(|e| debug!("Cannot hash {}: {}", path.display(), e)).ok());
+        #[cfg(unix)]
+        let (mode, uid, gid) = {
+            use std::os::unix::fs::MetadataExt;
+            (Some(metadata.mode() & 0o7777), Some(metadata.uid()), Some(metadata.gid()))
+        };
+        #[cfg(not(unix))]
+        let (mode, uid, gid) = (None, None, None);
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+            mode,
+            uid,
+            gid,
+            readonly: metadata.permissions().readonly(),
+            hash,
+        }
+    }
+
+    /// Aspects that differ from an earlier state: content, permissions and owner
+    fn changes_since(&self, previous: &FileState) -> Vec<String> {
+        let mut changes = Vec::new();
+        let content_changed = match (&self.hash, &previous.hash) {
+            (Some(hash), Some(previous_hash)) => hash != previous_hash,
+            // Without hashes, fall back to size and modification time
+            _ => self.size != previous.size || self.modified != previous.modified,
+        };
+        if content_changed {
+            changes.push("content".to_string());
+        }
+        if self.mode != previous.mode || self.readonly != previous.readonly {
+            changes.push("permissions".to_string());
+        }
+        if self.uid != previous.uid || self.gid != previous.gid {
+            changes.push("owner".to_string());
+        }
+        changes
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FimAction {
+    Created,
+    Modified,
+    Deleted,
+    /// Permissions or ownership changed, content did not
+    PermissionsChanged,
+}
+
+impl FimAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FimAction::Created => "created",
+            FimAction::Modified => "modified",
+            FimAction::Deleted => "deleted",
+            FimAction::PermissionsChanged => "permissions_changed",
+        }
+    }
+}
+
+/// One detected change, carried as the raw event's JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FimEvent {
+    pub timestamp: DateTime<Utc>,
+    pub action: FimAction,
+    pub path: String,
+    pub hash_algorithm: FimHashAlgorithm,
+    #[serde(default)]
+    pub changes: Vec<String>,
+    #[serde(default)]
+    pub previous: Option<FileState>,
+    #[serde(default)]
+    pub current: Option<FileState>,
+}
+
+impl FimEvent {
+    pub fn message(&self) -> String {
+        match self.changes.is_empty() {
+            true => format!("File {}: {}", self.action.as_str().replace('_', " "), self.path),
+            false => format!("File {}: {} ({})", self.action.as_str().replace('_', " "), self.path, self.changes.join(", ")),
+        }
+    }
+
+    /// Anything other than a new file is worth a look
+    pub fn level(&self) -> &'static str {
+        match self.action {
+            FimAction::Created => "info",
+            _ => "warning",
+        }
+    }
+
+    /// ECS file fields for the current state, with the previous state under `fim.previous.*`
+    pub fn to_fields(&self) -> HashMap<String, Value> {
+        let path = Path::new(&self.path);
+        let event_type = match self.action {
+            FimAction::Created => "creation",
+            FimAction::Deleted => "deletion",
+            FimAction::Modified | FimAction::PermissionsChanged => "change",
+        };
+        let mut fields = HashMap::from([
+            ("event.category".to_string(), json!("file")),
+            ("event.type".to_string(), json!(event_type)),
+            ("event.action".to_string(), json!(self.action.as_str())),
+            ("file.path".to_string(), json!(self.path)),
+            ("fim.changes".to_string(), json!(self.changes)),
+        ]);
+        if let Some(name) = path.file_name() {
+            fields.insert("file.name".to_string(), json!(name.to_string_lossy()));
+        }
+        if let Some(directory) = path.parent() {
+            fields.insert("file.directory".to_string(), json!(directory.display().to_string()));
+        }
+        let hash_field = format!("hash.{}", self.hash_algorithm.as_str());
+        for (prefix, state) in [("file", &self.current), ("fim.previous", &self.previous)] {
+            let Some(state) = state else {
+                continue;
+            };
+            fields.insert(format!("{}.size", prefix), json!(state.size));
+            if let Some(modified) = state.modified {
+                fields.insert(format!("{}.mtime", prefix), json!(modified.to_rfc3339()));
+            }
+            if let Some(mode) = state.mode {
+                fields.insert(format!("{}.mode", prefix), json!(format!("{:04o}", mode)));
+            }
+            if let Some(uid) = state.uid {
+                fields.insert(format!("{}.uid", prefix), json!(uid.to_string()));
+            }
+            if let Some(gid) = state.gid {
+                fields.insert(format!("{}.gid", prefix), json!(gid.to_string()));
+            }
+            if let Some(hash) = &state.hash {
+                fields.insert(format!("{}.{}", prefix, hash_field), json!(hash));
+            }
+        }
+        fields
+    }
+}
+
+/// The baseline and the rules for which paths are watched
+pub struct FimScanner {
+    config: FimCollectorConfig,
+    excludes: Vec<::glob::Pattern>,
+    roots: Vec<PathBuf>,
+    baseline: HashMap<PathBuf, FileState>,
+    dirty: bool,
+    limit_warned: bool,
+}
+
+impl FimScanner {
+    pub fn new(config: FimCollectorConfig) -> Self {
+        let excludes = config.exclude.iter().filter_map(|pattern| ::glob::Pattern::new(pattern).ok()).collect();
+        let mut scanner = Self {
+            config,
+            excludes,
+            roots: Vec::new(),
+            baseline: HashMap::new(),
+            dirty: false,
+            limit_warned: false,
+        };
+        scanner.refresh_roots();
+        scanner
+    }
+
+    /// Watched files or directories, glob patterns expanded
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    pub fn tracked_files(&self) -> usize {
+        self.baseline.len()
+    }
+
+    fn refresh_roots(&mut self) {
+        self.roots = self.config.paths.iter()
+            .filter_map(|pattern| ::glob::glob(pattern).ok())
+            .flat_map(|paths| paths.flatten())
+            .collect();
+    }
+
+    /// Load the baseline saved by a previous run; false when there is none
+    pub fn load_baseline(&mut self) -> bool {
+        let content = match std::fs::read_to_string(&self.config.baseline_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return false,
+            Err(e) => {
+                warn!("⚠️ Cannot read FIM baseline {}: {}", self.config.baseline_path, e);
+                return false;
+            }
+        };
+        match serde_json::from_str::<HashMap<PathBuf, FileState>>(&content) {
+            Ok(baseline) => {
+                self.baseline = baseline;
+                true
+            }
+            Err(e) => {
+                warn!("⚠️ Ignoring unreadable FIM baseline {}: {}", self.config.baseline_path, e);
+                false
+            }
+        }
+    }
+
+    /// Write the baseline if it changed since it was last saved
+    pub fn save_baseline(&mut self) -> std::io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let path = Path::new(&self.config.baseline_path);
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, serde_json::to_vec(&self.baseline)?)?;
+        std::fs::rename(&temporary, path)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn is_excluded(&self, path: &Path) -> bool {
+        let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        self.excludes.iter().any(|pattern| pattern.matches(&name) || pattern.matches_path(path))
+    }
+
+    fn is_watched(&self, path: &Path) -> bool {
+        !self.is_excluded(path) && self.roots.iter().any(|root| {
+            path == root || path.parent() == Some(root.as_path()) || (self.config.recursive && path.starts_with(root))
+        })
+    }
+
+    /// Walk every root and compare each file with the baseline; files gone since are reported deleted.
+    /// With `report` off the baseline is only (re)built, e.g. on a first run.
+    pub fn scan(&mut self, report: bool) -> Vec<FimEvent> {
+        self.refresh_roots();
+        let mut files = Vec::new();
+        for root in self.roots.clone() {
+            self.collect_files(&root, true, &mut files);
+        }
+
+        let seen: HashSet<&PathBuf> = files.iter().collect();
+        let gone: Vec<PathBuf> = self.baseline.keys().filter(|path| !seen.contains(path)).cloned().collect();
+        let mut events = Vec::new();
+        for path in &files {
+            events.extend(self.compare(path));
+        }
+        for path in gone {
+            events.extend(self.forget(&path));
+        }
+        if report { events } else { Vec::new() }
+    }
+
+    /// Examine a path a notification named: a file, a directory (every file under it) or something now gone
+    pub fn check(&mut self, path: &Path) -> Vec<FimEvent> {
+        if !self.is_watched(path) {
+            return Vec::new();
+        }
+        match std::fs::metadata(path) {
+            Ok(metadata) if metadata.is_dir() => {
+                let mut files = Vec::new();
+                self.collect_files(path, self.config.recursive, &mut files);
+                files.iter().flat_map(|file| self.compare(file)).collect()
+            }
+            Ok(_) => self.compare(path).into_iter().collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                // A removed directory takes every file under it along
+                let gone: Vec<PathBuf> = self.baseline.keys().filter(|tracked| tracked.starts_with(path)).cloned().collect();
+                gone.iter().flat_map(|tracked| self.forget(tracked)).collect()
+            }
+            Err(e) => {
+                debug!("Cannot examine {}: {}", path.display(), e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn collect_files(&self, path: &Path, descend: bool, files: &mut Vec<PathBuf>) {
+        if self.is_excluded(path) {
+            return;
+        }
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return;
+        };
+        if metadata.is_file() {
+            files.push(path.to_path_buf());
+            return;
+        }
+        if !metadata.is_dir() || !descend {
+            return;
+        }
+        let Ok(entries) = std::fs::read_dir(path) else {
+            debug!("Cannot list {}", path.display());
+            return;
+        };
+        for entry in entries.flatten() {
+            self.collect_files(&entry.path(), self.config.recursive, files);
+        }
+    }
+
+    fn compare(&mut self, path: &Path) -> Option<FimEvent> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let current = FileState::read(path, &metadata, self.config.hash_algorithm, self.config.max_file_size_bytes);
+        let previous = self.baseline.get(path).cloned();
+        let (action, changes) = match &previous {
+            None => {
+                if self.baseline.len() >= self.config.max_files {
+                    if !self.limit_warned {
+                        warn!("⚠️ FIM baseline is full ({} files); new files are not tracked", self.config.max_files);
+                        self.limit_warned = true;
+                    }
+                    return None;
+                }
+                (FimAction::Created, Vec::new())
+            }
+            Some(previous) => {
+                let changes = current.changes_since(previous);
+                if changes.is_empty() {
+                    // Keep the newest timestamps so time-only comparisons stay accurate
+                    if *previous != current {
+                        self.baseline.insert(path.to_path_buf(), current);
+                        self.dirty = true;
+                    }
+                    return None;
+                }
+                let action = if changes.iter().any(|c| c == "content") { FimAction::Modified } else { FimAction::PermissionsChanged };
+                (action, changes)
+            }
+        };
+        self.baseline.insert(path.to_path_buf(), current.clone());
+        self.dirty = true;
+        Some(self.event(path, action, changes, previous, Some(current)))
+    }
+
+    fn forget(&mut self, path: &Path) -> Option<FimEvent> {
+        let previous = self.baseline.remove(path)?;
+        self.dirty = true;
+        Some(self.event(path, FimAction::Deleted, Vec::new(), Some(previous), None))
+    }
+
+    fn event(&self, path: &Path, action: FimAction, changes: Vec<String>, previous: Option<FileState>, current: Option<FileState>) -> FimEvent {
+        FimEvent {
+            timestamp: Utc::now(),
+            action,
+            path: path.display().to_string(),
+            hash_algorithm: self.config.hash_algorithm,
+            changes,
+            previous,
+            current,
+        }
+    }
+}
+
+pub struct FimCollector {
+    config: FimCollectorConfig,
+    event_sender: mpsc::Sender<RawLogEvent>,
+    shutdown_sender: Option<tokio::sync::oneshot::Sender<()>>,
+    running: bool,
+}
+
+impl FimCollector {
+    pub fn new(config: FimCollectorConfig, event_sender: mpsc::Sender<RawLogEvent>) -> Self {
+        Self {
+            config,
+            event_sender,
+            shutdown_sender: None,
+            running: false,
+        }
+    }
+
+    pub fn paths(&self) -> &[String] {
+        &self.config.paths
+    }
+
+    fn raw_event(event: &FimEvent) -> RawLogEvent {
+        RawLogEvent {
+            timestamp: event.timestamp,
+            source: FIM_SOURCE.to_string(),
+            raw_data: serde_json::to_string(event).unwrap_or_default().into(),
+            metadata: HashMap::from([("file_path".to_string(), event.path.clone())]),
+        }
+    }
+
+    /// Run scanner work (hashing, directory walks) off the async workers
+    async fn blocking<T: Send + 'static>(
+        scanner: FimScanner,
+        work: impl FnOnce(&mut FimScanner) -> T + Send + 'static,
+    ) -> Option<(FimScanner, T)> {
+        tokio::task::spawn_blocking(move || {
+            let mut scanner = scanner;
+            let result = work(&mut scanner);
+            if let Err(e) = scanner.save_baseline() {
+                warn!("⚠️ Cannot save FIM baseline: {}", e);
+            }
+            (scanner, result)
+        })
+        .await
+        .inspect_err(|e| error!("❌ FIM scan task failed: {}", e))
+        .ok()
+    }
+}
+
+#[async_trait]
+impl Collector for FimCollector {
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        if !self.config.enabled {
+            info!("FIM collector is disabled");
+            return Ok(());
+        }
+        info!("🚀 Starting FIM collector (paths: {})", self.config.paths.join(", "));
+
+        // Notifications only say where to look; the scanner decides what changed
+        let (notify_tx, mut notify_rx) = mpsc::unbounded_channel::<Vec<PathBuf>>();
+        let watcher = if self.config.realtime {
+            let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+                if let Ok(event) = result {
+                    let _ = notify_tx.send(event.paths);
+                }
+            })
+            .map_err(|e| CollectorError::InitializationFailed {
+                name: "fim".to_string(),
+                collector_type: "fim".to_string(),
+                reason: e.to_string(),
+                configuration: "notify::RecommendedWatcher".to_string(),
+            })?;
+            let mode = if self.config.recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+            let scanner = FimScanner::new(self.config.clone());
+            for root in scanner.roots() {
+                // Editors replace files by renaming over them, so single files are watched through their directory
+                let (target, mode) = match root.is_dir() {
+                    true => (root.as_path(), mode),
+                    false => (root.parent().unwrap_or(root.as_path()), RecursiveMode::NonRecursive),
+                };
+                if let Err(e) = watcher.watch(target, mode) {
+                    warn!("⚠️ FIM cannot watch {}: {}", target.display(), e);
+                }
+            }
+            Some(watcher)
+        } else {
+            None
+        };
+
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel();
+        self.shutdown_sender = Some(shutdown_tx);
+        let config = self.config.clone();
+        let event_sender = self.event_sender.clone();
+
+        crate::component_usage::spawn_inherited(async move {
+            let _watcher = watcher;
+            let mut scanner = FimScanner::new(config.clone());
+            let has_baseline = scanner.load_baseline();
+
+            // Without a saved baseline the first scan only records what is there
+            let Some((restored, events)) = Self::blocking(scanner, move |s: &mut FimScanner| s.scan(has_baseline)).await else {
+                return;
+            };
+            scanner = restored;
+            info!("🔏 FIM baseline holds {} files ({} changes since the last run)", scanner.tracked_files(), events.len());
+            for event in &events {
+                if event_sender.send(Self::raw_event(event)).await.is_err() {
+                    return;
+                }
+            }
+
+            let scan_period = Duration::from_secs(config.scan_interval_seconds.max(60));
+            let mut rescan = tokio::time::interval_at(tokio::time::Instant::now() + scan_period, scan_period);
+            let mut settle = tokio::time::interval(NOTIFY_SETTLE);
+            let mut pending: HashSet<PathBuf> = HashSet::new();
+
+            loop {
+                let work: Box<dyn FnOnce(&mut FimScanner) -> Vec<FimEvent> + Send> = tokio::select! {
+                    Some(paths) = notify_rx.recv() => {
+                        pending.extend(paths);
+                        continue;
+                    }
+                    _ = settle.tick() => {
+                        if pending.is_empty() {
+                            continue;
+                        }
+                        let paths = std::mem::take(&mut pending);
+                        Box::new(move |s: &mut FimScanner| paths.iter().flat_map(|path| s.check(path)).collect())
+                    }
+                    _ = rescan.tick() => Box::new(|s: &mut FimScanner| s.scan(true)),
+                    _ = &mut shutdown_rx => {
+                        debug!("FIM collector shutting down");
+                        break;
+                    }
+                };
+
+                let Some((restored, events)) = Self::blocking(scanner, work).await else {
+                    return;
+                };
+                scanner = restored;
+                for event in &events {
+                    debug!("🔏 {}", event.message());
+                    if let Err(e) = event_sender.send(Self::raw_event(event)).await {
+                        error!("Failed to send FIM event: {}", e);
+                        return;
+                    }
+                }
+            }
+        });
+
+        self.running = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        info!("🛑 Stopping FIM collector");
+        if let Some(sender) = self.shutdown_sender.take() {
+            let _ = sender.send(());
+        }
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        // Changes are reported by the background task
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "fim"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_against_the_saved_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let watched = dir.path().join("etc");
+        std::fs::create_dir(&watched).unwrap();
+        std::fs::write(watched.join("hosts"), "127.0.0.1 localhost\n").unwrap();
+        std::fs::write(watched.join("passwd"), "root:x:0:0::/root:/bin/sh\n").unwrap();
+        std::fs::write(watched.join("passwd.swp"), "editor state").unwrap();
+        let config = FimCollectorConfig {
+            enabled: true,
+            paths: vec![watched.display().to_string()],
+            baseline_path: dir.path().join("baseline.json").display().to_string(),
+            ..Default::default()
+        };
+
+        // First run: baseline only, no events
+        let mut scanner = FimScanner::new(config.clone());
+        assert!(!scanner.load_baseline());
+        assert!(scanner.scan(false).is_empty());
+        assert_eq!(scanner.tracked_files(), 2);
+        scanner.save_baseline().unwrap();
+
+        // Changes made while the agent was down show up on the next start
+        std::fs::write(watched.join("passwd"), "root:x:0:0::/root:/bin/sh\nevil:x:0:0::/:/bin/sh\n").unwrap();
+        std::fs::remove_file(watched.join("hosts")).unwrap();
+        std::fs::write(watched.join("sudoers"), "evil ALL=(ALL) NOPASSWD: ALL\n").unwrap();
+        let mut restarted = FimScanner::new(config);
+        assert!(restarted.load_baseline());
+        let mut events = restarted.scan(true);
+        events.sort_by(|a, b| a.path.cmp(&b.path));
+        let actions: Vec<(FimAction, &str)> = events.iter()
+            .map(|e| (e.action, Path::new(&e.path).file_name().unwrap().to_str().unwrap()))
+            .collect();
+        assert_eq!(actions, vec![
+            (FimAction::Deleted, "hosts"),
+            (FimAction::Modified, "passwd"),
+            (FimAction::Created, "sudoers"),
+        ]);
+
+        let modified = events[1].to_fields();
+        assert_eq!(modified["event.action"], "modified");
+        assert_eq!(modified["fim.changes"], json!(["content"]));
+        assert_ne!(modified["file.hash.sha256"], modified["fim.previous.hash.sha256"]);
+
+        // Notifications for unchanged or excluded files report nothing
+        assert!(restarted.check(&watched.join("passwd")).is_empty());
+        assert!(restarted.check(&watched.join("passwd.swp")).is_empty());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(watched.join("sudoers"), std::fs::Permissions::from_mode(0o666)).unwrap();
+            let events = restarted.check(&watched.join("sudoers"));
+            assert_eq!(events[0].action, FimAction::PermissionsChanged);
+            assert_eq!(events[0].to_fields()["file.mode"], "0666");
+        }
+    }
+}
@@ -0,0 +1,843 @@
+// Honeytoken (canary) collector
+// Decoy files, and registry keys on Windows, are put where an intruder looks for credentials. Nothing legitimate
+// has a reason to touch them, so every access is reported as a critical alert. On Linux inotify reports opens as
+// they happen and the process holding the decoy open is looked up in procfs; elsewhere decoys are polled, which
+// catches reads through access times where the file system records them, but can't tell who read.
+
+use crate::alert_rules::{ALERT_RULES_FIELD, ALERT_SEVERITY_FIELD};
+use crate::collectors::{Collector, RawLogEvent};
+use crate::config::{HoneyRegistryKeyConfig, HoneytokenCollectorConfig};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Source name of honeytoken alerts
+pub const HONEYTOKEN_SOURCE: &str = "honeytoken";
+/// Rule name carried in `alert.rules`
+pub const HONEYTOKEN_RULE: &str = "honeytoken_access";
+
+/// The same process doing the same thing to the same decoy within this window is one alert
+const REPEAT_SUPPRESSION: Duration = Duration::from_secs(2);
+/// How often the monitor thread looks for notifications and checks whether it should stop
+const TICK: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoneytokenAction {
+    /// Opened or read
+    Accessed,
+    Modified,
+    /// Permissions, ownership or times changed
+    AttributesChanged,
+    /// Deleted or moved away
+    Deleted,
+}
+
+impl HoneytokenAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HoneytokenAction::Accessed => "accessed",
+            HoneytokenAction::Modified => "modified",
+            HoneytokenAction::AttributesChanged => "attributes_changed",
+            HoneytokenAction::Deleted => "deleted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecoyKind {
+    File,
+    RegistryKey,
+}
+
+/// A process that had the decoy open when the access was noticed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    #[serde(default)]
+    pub ppid: Option<u32>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub executable: Option<String>,
+    #[serde(default)]
+    pub command_line: Option<String>,
+    #[serde(default)]
+    pub uid: Option<u32>,
+}
+
+/// One access to a decoy, carried as the raw event's JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoneytokenEvent {
+    pub timestamp: DateTime<Utc>,
+    pub action: HoneytokenAction,
+    pub kind: DecoyKind,
+    /// File path, or registry key and value name
+    pub path: String,
+    /// Processes holding the decoy open, empty when they couldn't be determined
+    #[serde(default)]
+    pub processes: Vec<ProcessInfo>,
+}
+
+impl HoneytokenEvent {
+    pub fn message(&self) -> String {
+        let decoy = match self.kind {
+            DecoyKind::File => "file",
+            DecoyKind::RegistryKey => "registry key",
+        };
+        let action = self.action.as_str().replace('_', " ");
+        match self.processes.first() {
+            Some(process) => format!("Honeytoken {} {}: {} by {} (pid {})",
+                                     decoy, action, self.path, process.name.as_deref().unwrap_or("unknown"), process.pid),
+            None => format!("Honeytoken {} {}: {}", decoy, action, self.path),
+        }
+    }
+
+    pub fn severity(&self) -> &'static str {
+        match self.action {
+            // touch and backup tools restoring times change attributes without looking inside
+            HoneytokenAction::AttributesChanged => "high",
+            _ => "critical",
+        }
+    }
+
+    /// ECS alert fields; the first process holding the decoy open fills `process.*`
+    pub fn to_fields(&self) -> HashMap<String, Value> {
+        let (category, prefix) = match self.kind {
+            DecoyKind::File => ("file", "file.path"),
+            DecoyKind::RegistryKey => ("registry", "registry.path"),
+        };
+        let event_type = match self.action {
+            HoneytokenAction::Accessed => "access",
+            HoneytokenAction::Modified | HoneytokenAction::AttributesChanged => "change",
+            HoneytokenAction::Deleted => "deletion",
+        };
+        let mut fields = HashMap::from([
+            ("event.kind".to_string(), json!("alert")),
+            ("event.category".to_string(), json!(category)),
+            ("event.type".to_string(), json!(event_type)),
+            ("event.action".to_string(), json!(format!("honeytoken_{}", self.action.as_str()))),
+            (prefix.to_string(), json!(self.path)),
+            (ALERT_RULES_FIELD.to_string(), json!([HONEYTOKEN_RULE])),
+            (ALERT_SEVERITY_FIELD.to_string(), json!(self.severity())),
+        ]);
+        if let Some(process) = self.processes.first() {
+            fields.insert("process.pid".to_string(), json!(process.pid));
+            let optional = [
+                ("process.parent.pid", process.ppid.map(|ppid| json!(ppid))),
+                ("process.name", process.name.as_ref().map(|name| json!(name))),
+                ("process.executable", process.executable.as_ref().map(|executable| json!(executable))),
+                ("process.command_line", process.command_line.as_ref().map(|command_line| json!(command_line))),
+                ("user.id", process.uid.map(|uid| json!(uid.to_string()))),
+            ];
+            fields.extend(optional.into_iter().filter_map(|(field, value)| Some((field.to_string(), value?))));
+        }
+        if self.processes.len() > 1 {
+            fields.insert("honeytoken.pids".to_string(), json!(self.processes.iter().map(|p| p.pid).collect::<Vec<_>>()));
+        }
+        fields
+    }
+}
+
+/// Registry root and subkey of 'HKLM\...' or 'HKCU\...' (long names accepted); true for the local machine hive
+pub fn split_registry_key(key: &str) -> Option<(bool, &str)> {
+    let (root, subkey) = key.split_once('\\')?;
+    let local_machine = match root.to_ascii_uppercase().as_str() {
+        "HKLM" | "HKEY_LOCAL_MACHINE" => true,
+        "HKCU" | "HKEY_CURRENT_USER" => false,
+        _ => return None,
+    };
+    let subkey = subkey.trim_matches('\\');
+    (!subkey.is_empty()).then_some((local_machine, subkey))
+}
+
+fn random_text(length: usize, alphabet: &[u8]) -> String {
+    let mut bytes = vec![0u8; length];
+    if SystemRandom::new().fill(&mut bytes).is_err() {
+        for chunk in bytes.chunks_mut(16) {
+            chunk.copy_from_slice(&uuid::Uuid::new_v4().as_bytes()[..chunk.len()]);
+        }
+    }
+    bytes.iter().map(|b| alphabet[*b as usize % alphabet.len()] as char).collect()
+}
+
+/// Fake cloud credentials; the keys are random, so a decoy's keys showing up elsewhere identify it
+pub fn decoy_content() -> String {
+    const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    const SECRET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    format!("[default]\naws_access_key_id = AKIA{}\naws_secret_access_key = {}\nregion = us-east-1\n",
+            random_text(16, UPPER), random_text(40, SECRET))
+}
+
+/// Create a decoy that doesn't exist yet; true when it was created, existing files are left as they are
+pub fn deploy_file(path: &Path, content: &str) -> std::io::Result<bool> {
+    use std::io::Write;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => {
+            file.write_all(content.as_bytes())?;
+            Ok(true)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// What polling compares between checks
+#[derive(Debug, Clone, PartialEq)]
+struct Observation {
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+    size: u64,
+    readonly: bool,
+    mode: Option<u32>,
+    owner: Option<(u32, u32)>,
+}
+
+impl Observation {
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = std::fs::symlink_metadata(path).ok()?;
+        #[cfg(unix)]
+        let (mode, owner) = {
+            use std::os::unix::fs::MetadataExt;
+            (Some(metadata.mode() & 0o7777), Some((metadata.uid(), metadata.gid())))
+        };
+        #[cfg(not(unix))]
+        let (mode, owner) = (None, None);
+        Some(Self {
+            accessed: metadata.accessed().ok(),
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+            readonly: metadata.permissions().readonly(),
+            mode,
+            owner,
+        })
+    }
+
+    /// Changes since `previous`, most serious first
+    fn actions_since(&self, previous: &Observation) -> Vec<HoneytokenAction> {
+        let mut actions = Vec::new();
+        if self.modified != previous.modified || self.size != previous.size {
+            actions.push(HoneytokenAction::Modified);
+        }
+        if self.mode != previous.mode || self.owner != previous.owner || self.readonly != previous.readonly {
+            actions.push(HoneytokenAction::AttributesChanged);
+        }
+        if self.accessed > previous.accessed {
+            actions.push(HoneytokenAction::Accessed);
+        }
+        actions
+    }
+}
+
+/// Set the access time before the modification time; file systems mounted relatime (the Linux default) only
+/// record a read when the access time isn't newer, so this arms the decoy for the next read
+fn arm_access_time(path: &Path) {
+    let result = std::fs::OpenOptions::new().write(true).open(path).and_then(|file| {
+        let modified = file.metadata()?.modified()?;
+        let accessed = modified.checked_sub(Duration::from_secs(3600)).unwrap_or(SystemTime::UNIX_EPOCH);
+        file.set_times(std::fs::FileTimes::new().set_accessed(accessed))
+    });
+    if let Err(e) = result {
+        debug!("Cannot reset the access time of {}: {}", path.display(), e);
+    }
+}
+
+struct FileDecoy {
+    path: PathBuf,
+    content: String,
+    /// None while the decoy is missing
+    observed: Option<Observation>,
+}
+
+struct RegistryDecoy {
+    config: HoneyRegistryKeyConfig,
+    data: String,
+    /// Last value read; None while the key or value is missing
+    observed: Option<String>,
+}
+
+/// The deployed decoys and what was last seen of them
+pub struct HoneytokenMonitor {
+    config: HoneytokenCollectorConfig,
+    files: Vec<FileDecoy>,
+    registry: Vec<RegistryDecoy>,
+    /// Opens and changes of files are notified, so polling only notices missing files
+    notified: bool,
+    last_reported: HashMap<(String, HoneytokenAction, Option<u32>), Instant>,
+}
+
+impl HoneytokenMonitor {
+    pub fn new(config: HoneytokenCollectorConfig) -> Self {
+        let files = config.files.iter()
+            .map(|file| FileDecoy {
+                path: PathBuf::from(&file.path),
+                content: file.content.clone().unwrap_or_else(decoy_content),
+                observed: None,
+            })
+            .collect();
+        // Registry decoys only exist on Windows; the collector warns about them elsewhere
+        let registry_keys = if cfg!(windows) { config.registry_keys.as_slice() } else { &[] };
+        let registry = registry_keys.iter()
+            .map(|key| RegistryDecoy {
+                config: key.clone(),
+                data: key.value_data.clone().unwrap_or_else(|| random_text(20, b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789!#%")),
+                observed: None,
+            })
+            .collect();
+        Self { config, files, registry, notified: false, last_reported: HashMap::new() }
+    }
+
+    pub fn file_paths(&self) -> Vec<PathBuf> {
+        self.files.iter().map(|decoy| decoy.path.clone()).collect()
+    }
+
+    /// Create missing decoys and remember what they look like; returns how many were created
+    pub fn deploy(&mut self) -> usize {
+        let mut created = 0;
+        for decoy in &mut self.files {
+            match deploy_file(&decoy.path, &decoy.content) {
+                Ok(true) => {
+                    info!("🍯 Deployed decoy {}", decoy.path.display());
+                    created += 1;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("⚠️ Cannot deploy decoy {}: {}", decoy.path.display(), e),
+            }
+            if !self.notified {
+                arm_access_time(&decoy.path);
+            }
+            decoy.observed = Observation::read(&decoy.path);
+        }
+        for decoy in &mut self.registry {
+            match registry::deploy_key(&decoy.config, &decoy.data) {
+                Ok(true) => {
+                    info!("🍯 Deployed decoy registry key {}", decoy.config.key);
+                    created += 1;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("⚠️ Cannot deploy decoy registry key {}: {}", decoy.config.key, e),
+            }
+            decoy.observed = registry::read_value(&decoy.config).ok().flatten();
+        }
+        created
+    }
+
+    /// Compare every decoy with what was last seen; missing decoys are redeployed when configured
+    pub fn poll(&mut self) -> Vec<HoneytokenEvent> {
+        let mut events = Vec::new();
+        for index in 0..self.files.len() {
+            let decoy = &mut self.files[index];
+            let current = Observation::read(&decoy.path);
+            let actions = match (&decoy.observed, &current) {
+                (Some(_), None) => vec![HoneytokenAction::Deleted],
+                (Some(previous), Some(current)) if !self.notified => current.actions_since(previous),
+                _ => Vec::new(),
+            };
+            decoy.observed = current;
+            let path = decoy.path.display().to_string();
+            let accessed = actions.contains(&HoneytokenAction::Accessed);
+            events.extend(actions.into_iter().filter_map(|action| self.report(&path, DecoyKind::File, action, Vec::new())));
+            if accessed {
+                let decoy = &mut self.files[index];
+                arm_access_time(&decoy.path);
+                decoy.observed = Observation::read(&decoy.path);
+            }
+            if self.files[index].observed.is_none() && self.config.redeploy {
+                self.deploy_missing(index);
+            }
+        }
+        for index in 0..self.registry.len() {
+            let decoy = &mut self.registry[index];
+            let current = match registry::read_value(&decoy.config) {
+                Ok(current) => current,
+                Err(e) => {
+                    debug!("Cannot read decoy registry key {}: {}", decoy.config.key, e);
+                    continue;
+                }
+            };
+            let action = match (&decoy.observed, &current) {
+                (Some(_), None) => Some(HoneytokenAction::Deleted),
+                (Some(previous), Some(current)) if previous != current => Some(HoneytokenAction::Modified),
+                _ => None,
+            };
+            decoy.observed = current;
+            let path = format!("{}\\{}", decoy.config.key, decoy.config.value_name);
+            events.extend(action.and_then(|action| self.report(&path, DecoyKind::RegistryKey, action, Vec::new())));
+            let decoy = &mut self.registry[index];
+            if decoy.observed.is_none() && self.config.redeploy && registry::deploy_key(&decoy.config, &decoy.data).is_ok() {
+                decoy.observed = registry::read_value(&decoy.config).ok().flatten();
+            }
+        }
+        events
+    }
+
+    fn deploy_missing(&mut self, index: usize) {
+        let notified = self.notified;
+        let decoy = &mut self.files[index];
+        match deploy_file(&decoy.path, &decoy.content) {
+            Ok(_) => {
+                info!("🍯 Redeployed decoy {}", decoy.path.display());
+                if !notified {
+                    arm_access_time(&decoy.path);
+                }
+                decoy.observed = Observation::read(&decoy.path);
+            }
+            Err(e) => debug!("Cannot redeploy decoy {}: {}", decoy.path.display(), e),
+        }
+    }
+
+    /// A notified access to a file decoy
+    fn file_notification(&mut self, path: &Path, action: HoneytokenAction, processes: Vec<ProcessInfo>) -> Option<HoneytokenEvent> {
+        let decoy = self.files.iter_mut().find(|decoy| decoy.path == path)?;
+        if action == HoneytokenAction::Deleted {
+            // Reported once; polling redeploys it
+            decoy.observed.take()?;
+        }
+        let path = decoy.path.display().to_string();
+        // One open can come with several notifications, and tools open files more than once
+        let now = Instant::now();
+        self.last_reported.retain(|_, at| now.duration_since(*at) < REPEAT_SUPPRESSION);
+        let key = (path.clone(), action, processes.first().map(|process| process.pid));
+        if self.last_reported.contains_key(&key) {
+            return None;
+        }
+        let event = self.report(&path, DecoyKind::File, action, processes)?;
+        self.last_reported.insert(key, now);
+        Some(event)
+    }
+
+    fn report(&self, path: &str, kind: DecoyKind, action: HoneytokenAction, processes: Vec<ProcessInfo>) -> Option<HoneytokenEvent> {
+        let ignored = |process: &ProcessInfo| process.name.as_ref().is_some_and(|name| self.config.ignore_processes.contains(name));
+        if !processes.is_empty() && processes.iter().all(ignored) {
+            debug!("Ignoring {} of decoy {} by {:?}", action.as_str(), path, processes[0].name);
+            return None;
+        }
+        Some(HoneytokenEvent { timestamp: Utc::now(), action, kind, path: path.to_string(), processes })
+    }
+}
+
+/// Processes other than the agent with `path` open
+#[cfg(target_os = "linux")]
+pub fn processes_holding(path: &Path) -> Vec<ProcessInfo> {
+    let own_pid = std::process::id();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries.flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| *pid != own_pid)
+        .filter(|pid| {
+            std::fs::read_dir(format!("/proc/{}/fd", pid))
+                .map(|fds| fds.flatten().any(|fd| std::fs::read_link(fd.path()).is_ok_and(|target| target == path)))
+                .unwrap_or(false)
+        })
+        .map(|pid| {
+            let (ppid, command_line) = crate::collectors::ebpf::process_context(pid);
+            let uid = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok().and_then(|status| {
+                status.lines().find_map(|line| line.strip_prefix("Uid:")?.split_whitespace().next()?.parse().ok())
+            });
+            ProcessInfo {
+                pid,
+                ppid,
+                name: std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok().map(|comm| comm.trim_end().to_string()),
+                executable: std::fs::read_link(format!("/proc/{}/exe", pid)).ok().map(|exe| exe.display().to_string()),
+                command_line,
+                uid,
+            }
+        })
+        .collect()
+}
+
+/// inotify watches on the file decoys; every open is reported with the processes that hold the file
+#[cfg(target_os = "linux")]
+struct OpenWatcher {
+    inotify: inotify::Inotify,
+    watches: HashMap<inotify::WatchDescriptor, PathBuf>,
+    buffer: Vec<u8>,
+}
+
+#[cfg(target_os = "linux")]
+impl OpenWatcher {
+    fn new() -> std::io::Result<Self> {
+        Ok(Self { inotify: inotify::Inotify::init()?, watches: HashMap::new(), buffer: vec![0; 16 * 1024] })
+    }
+
+    /// Watch decoys that exist and aren't watched yet, e.g. after they were redeployed
+    fn refresh(&mut self, paths: &[PathBuf]) {
+        use inotify::WatchMask;
+        let mask = WatchMask::OPEN | WatchMask::MODIFY | WatchMask::ATTRIB | WatchMask::DELETE_SELF | WatchMask::MOVE_SELF;
+        for path in paths {
+            if self.watches.values().any(|watched| watched == path) || !path.exists() {
+                continue;
+            }
+            match self.inotify.add_watch(path, mask) {
+                Ok(watch) => {
+                    self.watches.insert(watch, path.clone());
+                }
+                Err(e) => warn!("⚠️ Cannot watch decoy {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    fn read(&mut self, monitor: &mut HoneytokenMonitor) -> Vec<HoneytokenEvent> {
+        use inotify::EventMask;
+        let notifications: Vec<(inotify::WatchDescriptor, EventMask)> = match self.inotify.read_events(&mut self.buffer) {
+            Ok(events) => events.map(|event| (event.wd.clone(), event.mask)).collect(),
+            Err(e) => {
+                warn!("⚠️ Cannot read decoy notifications: {}", e);
+                return Vec::new();
+            }
+        };
+        let mut events = Vec::new();
+        for (watch, mask) in notifications {
+            let Some(path) = self.watches.get(&watch).cloned() else {
+                continue;
+            };
+            if mask.contains(EventMask::IGNORED) {
+                self.watches.remove(&watch);
+                continue;
+            }
+            // Looked up first: a short-lived reader may be gone a moment later
+            let processes = if mask.intersects(EventMask::OPEN | EventMask::MODIFY) { processes_holding(&path) } else { Vec::new() };
+            let actions = [
+                (EventMask::OPEN, HoneytokenAction::Accessed),
+                (EventMask::MODIFY, HoneytokenAction::Modified),
+                (EventMask::ATTRIB, HoneytokenAction::AttributesChanged),
+                (EventMask::DELETE_SELF | EventMask::MOVE_SELF, HoneytokenAction::Deleted),
+            ];
+            for (flags, action) in actions {
+                // Unlinking changes the link count, which is notified as an attribute change ahead of the deletion
+                if action == HoneytokenAction::AttributesChanged && !path.exists() {
+                    continue;
+                }
+                if mask.intersects(flags) {
+                    events.extend(monitor.file_notification(&path, action, processes.clone()));
+                }
+            }
+            if mask.contains(EventMask::MOVE_SELF) {
+                // The watch follows the moved file; the decoy's path gets a new one once redeployed
+                let _ = self.inotify.rm_watch(watch.clone());
+                self.watches.remove(&watch);
+            }
+        }
+        events
+    }
+}
+
+#[cfg(windows)]
+mod registry {
+    use crate::config::HoneyRegistryKeyConfig;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS};
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegCreateKeyExW, RegGetValueW, RegSetValueExW, HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE,
+        KEY_SET_VALUE, REG_CREATE_KEY_DISPOSITION, REG_CREATED_NEW_KEY, REG_OPTION_NON_VOLATILE, REG_SZ, RRF_RT_REG_SZ,
+    };
+
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    fn root_and_subkey(config: &HoneyRegistryKeyConfig) -> std::io::Result<(HKEY, Vec<u16>)> {
+        let (local_machine, subkey) = super::split_registry_key(&config.key)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a HKLM or HKCU key"))?;
+        Ok((if local_machine { HKEY_LOCAL_MACHINE } else { HKEY_CURRENT_USER }, wide(subkey)))
+    }
+
+    /// Create the key and value unless the value already exists; true when it was created
+    pub fn deploy_key(config: &HoneyRegistryKeyConfig, data: &str) -> std::io::Result<bool> {
+        if read_value(config)?.is_some() {
+            return Ok(false);
+        }
+        let (root, subkey) = root_and_subkey(config)?;
+        let mut key = HKEY::default();
+        let mut disposition = REG_CREATE_KEY_DISPOSITION::default();
+        let status = unsafe {
+            RegCreateKeyExW(root, PCWSTR(subkey.as_ptr()), 0, PCWSTR::null(), REG_OPTION_NON_VOLATILE, KEY_SET_VALUE,
+                            None, &mut key, Some(&mut disposition))
+        };
+        if status != ERROR_SUCCESS {
+            return Err(std::io::Error::from_raw_os_error(status.0 as i32));
+        }
+        let name = wide(&config.value_name);
+        let bytes: Vec<u8> = wide(data).iter().flat_map(|unit| unit.to_le_bytes()).collect();
+        let status = unsafe { RegSetValueExW(key, PCWSTR(name.as_ptr()), 0, REG_SZ, Some(&bytes)) };
+        let _ = unsafe { RegCloseKey(key) };
+        if status != ERROR_SUCCESS {
+            return Err(std::io::Error::from_raw_os_error(status.0 as i32));
+        }
+        if disposition == REG_CREATED_NEW_KEY {
+            tracing::debug!("Created registry key {}", config.key);
+        }
+        Ok(true)
+    }
+
+    /// The value's string data; None when the key or value doesn't exist
+    pub fn read_value(config: &HoneyRegistryKeyConfig) -> std::io::Result<Option<String>> {
+        let (root, subkey) = root_and_subkey(config)?;
+        let name = wide(&config.value_name);
+        let mut buffer = vec![0u16; 1024];
+        let mut size = (buffer.len() * 2) as u32;
+        let status = unsafe {
+            RegGetValueW(root, PCWSTR(subkey.as_ptr()), PCWSTR(name.as_ptr()), RRF_RT_REG_SZ, None,
+                         Some(buffer.as_mut_ptr().cast()), Some(&mut size))
+        };
+        if status == ERROR_FILE_NOT_FOUND {
+            return Ok(None);
+        }
+        if status != ERROR_SUCCESS {
+            return Err(std::io::Error::from_raw_os_error(status.0 as i32));
+        }
+        let units = (size as usize / 2).min(buffer.len());
+        Ok(Some(String::from_utf16_lossy(&buffer[..units]).trim_end_matches('\0').to_string()))
+    }
+}
+
+#[cfg(not(windows))]
+mod registry {
+    use crate::config::HoneyRegistryKeyConfig;
+
+    fn unsupported() -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::Unsupported, "registry decoys need Windows")
+    }
+
+    pub fn deploy_key(_config: &HoneyRegistryKeyConfig, _data: &str) -> std::io::Result<bool> {
+        Err(unsupported())
+    }
+
+    pub fn read_value(_config: &HoneyRegistryKeyConfig) -> std::io::Result<Option<String>> {
+        Err(unsupported())
+    }
+}
+
+pub struct HoneytokenCollector {
+    config: HoneytokenCollectorConfig,
+    event_sender: mpsc::Sender<RawLogEvent>,
+    stop: Arc<AtomicBool>,
+    running: bool,
+}
+
+impl HoneytokenCollector {
+    pub fn new(config: HoneytokenCollectorConfig, event_sender: mpsc::Sender<RawLogEvent>) -> Self {
+        Self {
+            config,
+            event_sender,
+            stop: Arc::new(AtomicBool::new(false)),
+            running: false,
+        }
+    }
+
+    pub fn decoy_count(&self) -> usize {
+        self.config.files.len() + self.config.registry_keys.len()
+    }
+
+    fn raw_event(event: &HoneytokenEvent) -> RawLogEvent {
+        RawLogEvent {
+            timestamp: event.timestamp,
+            source: HONEYTOKEN_SOURCE.to_string(),
+            raw_data: serde_json::to_string(event).unwrap_or_default().into(),
+            metadata: HashMap::from([("decoy".to_string(), event.path.clone())]),
+        }
+    }
+
+    /// Deploy the decoys, then watch and poll them until stopped; runs on the blocking pool
+    fn run(mut monitor: HoneytokenMonitor, poll_interval: Duration, event_sender: mpsc::Sender<RawLogEvent>, stop: Arc<AtomicBool>) {
+        // Whether opens are notified decides whether deploying arms access times, so the watcher comes first
+        #[cfg(target_os = "linux")]
+        let mut opens = OpenWatcher::new()
+            .inspect_err(|e| warn!("⚠️ Decoy opens can't be watched ({}); polling access times instead", e))
+            .ok();
+        #[cfg(target_os = "linux")]
+        {
+            monitor.notified = opens.is_some();
+        }
+        let created = monitor.deploy();
+        info!("🍯 Honeytoken collector watching {} decoys ({} deployed now)", monitor.files.len() + monitor.registry.len(), created);
+        #[cfg(target_os = "linux")]
+        if let Some(opens) = &mut opens {
+            opens.refresh(&monitor.file_paths());
+        }
+
+        let mut next_poll = Instant::now() + poll_interval;
+        while !stop.load(Ordering::Relaxed) {
+            let mut events = Vec::new();
+            #[cfg(target_os = "linux")]
+            if let Some(opens) = &mut opens {
+                events.extend(opens.read(&mut monitor));
+            }
+            if Instant::now() >= next_poll {
+                events.extend(monitor.poll());
+                #[cfg(target_os = "linux")]
+                if let Some(opens) = &mut opens {
+                    opens.refresh(&monitor.file_paths());
+                }
+                next_poll = Instant::now() + poll_interval;
+            }
+            for event in &events {
+                warn!("🍯 {}", event.message());
+                if event_sender.blocking_send(Self::raw_event(event)).is_err() {
+                    return;
+                }
+            }
+            std::thread::sleep(TICK);
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for HoneytokenCollector {
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        if !self.config.enabled {
+            info!("Honeytoken collector is disabled");
+            return Ok(());
+        }
+        if cfg!(not(windows)) && !self.config.registry_keys.is_empty() {
+            warn!("⚠️ Ignoring {} decoy registry keys; they need Windows", self.config.registry_keys.len());
+        }
+
+        self.stop.store(false, Ordering::Relaxed);
+        let monitor = HoneytokenMonitor::new(self.config.clone());
+        let poll_interval = Duration::from_secs(self.config.poll_interval_seconds.max(1));
+        let (event_sender, stop) = (self.event_sender.clone(), self.stop.clone());
+        crate::component_usage::spawn_blocking("honeytoken", move || Self::run(monitor, poll_interval, event_sender, stop));
+
+        self.running = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        info!("🛑 Stopping honeytoken collector");
+        self.stop.store(true, Ordering::Relaxed);
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        // Accesses are reported by the background thread
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "honeytoken"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HoneyfileConfig;
+
+    fn config(paths: &[&Path]) -> HoneytokenCollectorConfig {
+        HoneytokenCollectorConfig {
+            enabled: true,
+            files: paths.iter().map(|path| HoneyfileConfig { path: path.display().to_string(), content: None }).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_polled_decoys_report_access_changes_and_deletion() {
+        let dir = tempfile::tempdir().unwrap();
+        let decoy = dir.path().join(".aws").join("credentials");
+        let existing = dir.path().join("passwords.txt");
+        std::fs::write(&existing, "placed by hand\n").unwrap();
+
+        let mut monitor = HoneytokenMonitor::new(config(&[&decoy, &existing]));
+        assert_eq!(monitor.deploy(), 1);
+        let content = std::fs::read_to_string(&decoy).unwrap();
+        assert!(content.starts_with("[default]\naws_access_key_id = AKIA") && content.len() > 90);
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "placed by hand\n");
+        // Where the file system records access times the two reads above already count
+        monitor.poll();
+        assert!(monitor.poll().is_empty());
+
+        // A read moves the access time forward
+        let file = std::fs::File::open(&existing).unwrap();
+        file.set_times(std::fs::FileTimes::new().set_accessed(SystemTime::now())).unwrap();
+        drop(file);
+        let events = monitor.poll();
+        assert_eq!(events.iter().map(|e| e.action).collect::<Vec<_>>(), vec![HoneytokenAction::Accessed]);
+        let fields = events[0].to_fields();
+        assert_eq!(fields["event.kind"], "alert");
+        assert_eq!(fields[ALERT_RULES_FIELD], json!([HONEYTOKEN_RULE]));
+        assert_eq!(fields[ALERT_SEVERITY_FIELD], "critical");
+        assert_eq!(fields["file.path"], existing.display().to_string());
+        assert!(monitor.poll().is_empty());
+
+        std::fs::write(&existing, "changed\n").unwrap();
+        assert_eq!(monitor.poll()[0].action, HoneytokenAction::Modified);
+
+        // Deletion is reported once and the decoy comes back
+        std::fs::remove_file(&decoy).unwrap();
+        let events = monitor.poll();
+        assert_eq!((events.len(), events[0].action, events[0].message().contains("file deleted")), (1, HoneytokenAction::Deleted, true));
+        assert!(decoy.exists());
+        assert!(monitor.poll().is_empty());
+    }
+
+    #[test]
+    fn test_notified_opens_are_attributed_and_filtered() {
+        let dir = tempfile::tempdir().unwrap();
+        let decoy = dir.path().join("id_rsa");
+        let mut monitor = HoneytokenMonitor::new(HoneytokenCollectorConfig {
+            ignore_processes: vec!["backupd".to_string()],
+            ..config(&[&decoy])
+        });
+        monitor.notified = true;
+        monitor.deploy();
+
+        let reader = |pid: u32, name: &str| ProcessInfo {
+            pid,
+            ppid: Some(1),
+            name: Some(name.to_string()),
+            executable: Some(format!("/usr/bin/{}", name)),
+            command_line: Some(format!("{} {}", name, decoy.display())),
+            uid: Some(1000),
+        };
+        let event = monitor.file_notification(&decoy, HoneytokenAction::Accessed, vec![reader(4242, "cat")]).unwrap();
+        assert_eq!(event.message(), format!("Honeytoken file accessed: {} by cat (pid 4242)", decoy.display()));
+        let fields = event.to_fields();
+        assert_eq!((fields["process.pid"].clone(), fields["process.parent.pid"].clone()), (json!(4242), json!(1)));
+        assert_eq!((fields["process.name"].clone(), fields["user.id"].clone()), (json!("cat"), json!("1000")));
+
+        // Repeats within the suppression window and ignored processes are not reported; other readers are
+        assert!(monitor.file_notification(&decoy, HoneytokenAction::Accessed, vec![reader(4242, "cat")]).is_none());
+        assert!(monitor.file_notification(&decoy, HoneytokenAction::Accessed, vec![reader(77, "backupd")]).is_none());
+        assert!(monitor.file_notification(&decoy, HoneytokenAction::Accessed, vec![reader(78, "less")]).is_some());
+        assert!(monitor.file_notification(&dir.path().join("other"), HoneytokenAction::Accessed, Vec::new()).is_none());
+
+        // Notified deletions are reported once; polling puts the decoy back without a second report
+        assert!(monitor.file_notification(&decoy, HoneytokenAction::Deleted, Vec::new()).is_some());
+        std::fs::remove_file(&decoy).unwrap();
+        assert!(monitor.file_notification(&decoy, HoneytokenAction::Deleted, Vec::new()).is_none());
+        assert!(monitor.poll().is_empty() && decoy.exists());
+
+        #[cfg(target_os = "linux")]
+        {
+            // The agent's own handles never count as the reader
+            let _open = std::fs::File::open(&decoy).unwrap();
+            assert!(processes_holding(&decoy).iter().all(|process| process.pid != std::process::id()));
+        }
+        assert_eq!(split_registry_key(r"HKEY_LOCAL_MACHINE\SOFTWARE\Acme\VPN\"), Some((true, r"SOFTWARE\Acme\VPN")));
+        assert_eq!(split_registry_key(r"hkcu\Software\Acme"), Some((false, r"Software\Acme")));
+        assert!(split_registry_key(r"HKCR\Acme").is_none() && split_registry_key("HKLM").is_none());
+    }
+}
@@ -1,6 +1,9 @@
 // Collector management and base traits
 
+use crate::component_usage;
+use crate::error_events::ErrorEvents;
 use crate::errors::CollectorError;
+use crate::load_shedding::LoadShedder;
 use crate::parsers::ParsedEvent;
 
 #[cfg(test)]
@@ -8,19 +11,33 @@ mod tests;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
 pub mod syslog;
 pub mod file_monitor;
+pub mod file_archive;
+pub mod database;
+pub mod session;
+pub mod ebpf;
+pub mod etw;
+pub mod container;
+pub mod fim;
+pub mod honeytoken;
+pub mod command;
+pub mod http_pull;
+pub mod packet_metadata;
 
-#[cfg(all(windows, feature = "persistent-storage"))]
+#[cfg(windows)]
 pub mod windows_event;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RawLogEvent {
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub source: String,
-    pub raw_data: String,
+    /// Shared with the parsed event, so the payload is allocated once between collector and transport
+    pub raw_data: Arc<str>,
     pub metadata: HashMap<String, String>,
 }
 
@@ -31,6 +48,14 @@ pub trait Collector: Send + Sync {
     async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError>;
     fn name(&self) -> &str;
     fn is_running(&self) -> bool;
+    
+    /// Called before `start` with the buffer's backpressure signal, for collectors that shed load themselves
+    fn set_backpressure(&mut self, _receiver: tokio::sync::watch::Receiver<bool>) {}
+    
+    /// Events this collector has dropped since it was created, for collectors that count them
+    fn drop_counter(&self) -> Option<Arc<AtomicU64>> {
+        None
+    }
 }
 
 pub struct CollectorManager {
@@ -38,6 +63,11 @@ pub struct CollectorManager {
     event_sender: mpsc::Sender<RawLogEvent>,
     backpressure_receiver: tokio::sync::watch::Receiver<bool>,
     shutdown_sender: tokio::sync::broadcast::Sender<()>,
+    // Status as of the last start or stop, readable without access to the manager
+    status: std::sync::Arc<parking_lot::RwLock<Vec<CollectorStatus>>>,
+    // Low-priority collectors pause while the agent sheds load
+    load_shedder: Option<Arc<LoadShedder>>,
+    error_events: Option<Arc<ErrorEvents>>,
 }
 
 impl CollectorManager {
@@ -52,9 +82,21 @@ impl CollectorManager {
             event_sender,
             backpressure_receiver,
             shutdown_sender,
+            status: Default::default(),
+            load_shedder: None,
+            error_events: None,
         }
     }
     
+    pub fn set_load_shedder(&mut self, shedder: Arc<LoadShedder>) {
+        self.load_shedder = Some(shedder);
+    }
+    
+    /// Collectors that fail to start or stop are reported to the server
+    pub fn set_error_events(&mut self, error_events: Arc<ErrorEvents>) {
+        self.error_events = Some(error_events);
+    }
+    
     pub fn add_collector(&mut self, collector: Box<dyn Collector>) {
         self.collectors.push(collector);
     }
@@ -63,10 +105,16 @@ impl CollectorManager {
         tracing::info!("Starting {} collectors", self.collectors.len());
         
         for collector in &mut self.collectors {
-            match collector.start().await {
+            collector.set_backpressure(self.backpressure_receiver.clone());
+            // Tasks the collector spawns while starting are charged to it
+            let component = component_usage::collector(collector.name());
+            match component_usage::instrument(&component, collector.start()).await {
                 Ok(_) => tracing::info!("✅ Started collector: {}", collector.name()),
                 Err(e) => {
                     tracing::error!("❌ Failed to start collector {}: {}", collector.name(), e);
+                    if let Some(error_events) = &self.error_events {
+                        error_events.record(&format!("collector:{}", collector.name()), e.report());
+                    }
                     return Err(e);
                 }
             }
@@ -74,28 +122,47 @@ impl CollectorManager {
         
         // Spawn collection tasks for each collector
         self.spawn_collection_tasks().await;
+        *self.status.write() = self.get_status();
         
         Ok(())
     }
     
     async fn spawn_collection_tasks(&self) {
-        for (index, _) in self.collectors.iter().enumerate() {
+        for (index, collector) in self.collectors.iter().enumerate() {
+            let component = component_usage::collector(collector.name());
             let event_sender = self.event_sender.clone();
             let mut backpressure_receiver = self.backpressure_receiver.clone();
             let mut shutdown_receiver = self.shutdown_sender.subscribe();
+            let drop_counter = collector.drop_counter();
+            let status = self.status.clone();
+            let load_shedder = self.load_shedder.clone();
+            let name = collector.name().to_string();
             
-            tokio::spawn(async move {
+            component_usage::spawn(&component, async move {
                 let mut collection_interval = tokio::time::interval(tokio::time::Duration::from_millis(100));
                 
                 loop {
                     tokio::select! {
                         _ = collection_interval.tick() => {
+                            // Keep the shared status current with the collector's own drop count
+                            if let Some(counter) = &drop_counter {
+                                let dropped = counter.load(Ordering::Relaxed);
+                                if let Some(entry) = status.write().get_mut(index) {
+                                    entry.dropped_events = dropped;
+                                }
+                            }
+                            
                             // Check for backpressure
                             if *backpressure_receiver.borrow() {
                                 tracing::debug!("Collector {} paused due to backpressure", index);
                                 continue;
                             }
                             
+                            if load_shedder.as_ref().is_some_and(|shedder| shedder.is_collector_disabled(&name)) {
+                                tracing::debug!("Collector {} paused while shedding load", name);
+                                continue;
+                            }
+                            
                             // Collection logic would go here
                             // This is simplified for the current implementation
                         }
@@ -119,25 +186,45 @@ impl CollectorManager {
         for collector in &mut self.collectors {
             if let Err(e) = collector.stop().await {
                 tracing::error!("Error stopping collector {}: {}", collector.name(), e);
+                if let Some(error_events) = &self.error_events {
+                    error_events.record(&format!("collector:{}", collector.name()), e.report());
+                }
             }
         }
         
+        *self.status.write() = self.get_status();
+        
         Ok(())
     }
     
+    /// Stop the running collectors and start `collectors` in their place (configuration reload)
+    pub async fn replace_collectors(&mut self, collectors: Vec<Box<dyn Collector>>) -> Result<(), CollectorError> {
+        self.stop_all().await?;
+        self.collectors = collectors;
+        self.start_all().await
+    }
+    
+    /// Shared view of collector status, refreshed whenever collectors are started or stopped
+    pub fn status_handle(&self) -> std::sync::Arc<parking_lot::RwLock<Vec<CollectorStatus>>> {
+        self.status.clone()
+    }
+    
     pub fn get_status(&self) -> Vec<CollectorStatus> {
         self.collectors
             .iter()
             .map(|collector| CollectorStatus {
                 name: collector.name().to_string(),
                 running: collector.is_running(),
+                dropped_events: collector.drop_counter().map_or(0, |counter| counter.load(Ordering::Relaxed)),
             })
             .collect()
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollectorStatus {
     pub name: String,
     pub running: bool,
+    /// Events the collector shed itself (e.g. syslog UDP under backpressure)
+    pub dropped_events: u64,
 }
\ No newline at end of file
@@ -0,0 +1,899 @@
+// Packet capture metadata collector (pcap-lite)
+// Captures packets with libpcap (AF_PACKET on Linux) and keeps only connection metadata: DNS questions and answers,
+// the TLS ClientHello server name and JA3 fingerprint, HTTP request hosts and, optionally, new TCP connections.
+// Payloads are never stored. Capturing needs a build with the `pcap` feature and capture privileges; the decoders
+// below are always built.
+
+use crate::collectors::{Collector, RawLogEvent};
+use crate::config::PacketMetadataCollectorConfig;
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::info;
+
+pub const PACKET_METADATA_SOURCE: &str = "packet_metadata";
+
+/// Answers kept from one DNS response
+const MAX_DNS_ANSWERS: usize = 16;
+
+/// Bytes of an HTTP request examined for the request line and headers
+const MAX_HTTP_HEAD: usize = 4096;
+
+const HTTP_METHODS: &[&str] = &["GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS", "PATCH", "CONNECT", "TRACE"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkEventKind {
+    Dns,
+    Tls,
+    Http,
+    /// TCP SYN from a client
+    Connection,
+}
+
+impl NetworkEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetworkEventKind::Dns => "dns",
+            NetworkEventKind::Tls => "tls",
+            NetworkEventKind::Http => "http",
+            NetworkEventKind::Connection => "connection",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DnsMetadata {
+    pub id: u16,
+    pub response: bool,
+    pub question: String,
+    pub query_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_code: Option<String>,
+    /// Addresses and CNAME targets from the answer section
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub answers: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsMetadata {
+    /// Highest version the client offers
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alpn: Vec<String>,
+    /// JA3 string and its MD5; only when the whole ClientHello was in the packet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ja3: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ja3_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpMetadata {
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+/// Metadata of one packet, as sent to the pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkMetadataEvent {
+    pub kind: NetworkEventKind,
+    pub time: DateTime<Utc>,
+    /// "tcp" or "udp"
+    pub transport: String,
+    pub source_ip: String,
+    pub source_port: u16,
+    pub destination_ip: String,
+    pub destination_port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<DnsMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpMetadata>,
+}
+
+impl NetworkMetadataEvent {
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    pub fn message(&self) -> String {
+        let peers = format!("{}:{} -> {}:{}", self.source_ip, self.source_port, self.destination_ip, self.destination_port);
+        match (&self.dns, &self.tls, &self.http) {
+            (Some(dns), _, _) if dns.response => format!(
+                "DNS response {} {} {}: {} ({})",
+                dns.query_type, dns.question, dns.response_code.as_deref().unwrap_or("?"), dns.answers.join(", "), peers
+            ),
+            (Some(dns), _, _) => format!("DNS query {} {} ({})", dns.query_type, dns.question, peers),
+            (_, Some(tls), _) => format!("TLS ClientHello for {} ({})", tls.server_name.as_deref().unwrap_or("?"), peers),
+            (_, _, Some(http)) => format!("HTTP {} to {} ({})", http.method, http.host.as_deref().unwrap_or("?"), peers),
+            _ => format!("TCP connection {}", peers),
+        }
+    }
+
+    /// Flattened ECS fields for the parsed event
+    pub fn to_fields(&self) -> HashMap<String, Value> {
+        let mut fields = HashMap::from([
+            ("event.category".to_string(), json!("network")),
+            ("event.action".to_string(), json!(self.kind.as_str())),
+            ("network.transport".to_string(), json!(self.transport)),
+            ("source.ip".to_string(), json!(self.source_ip)),
+            ("source.port".to_string(), json!(self.source_port)),
+            ("destination.ip".to_string(), json!(self.destination_ip)),
+            ("destination.port".to_string(), json!(self.destination_port)),
+        ]);
+        if let Some(dns) = &self.dns {
+            fields.insert("network.protocol".to_string(), json!("dns"));
+            fields.insert("dns.id".to_string(), json!(dns.id.to_string()));
+            fields.insert("dns.type".to_string(), json!(if dns.response { "answer" } else { "query" }));
+            fields.insert("dns.question.name".to_string(), json!(dns.question));
+            fields.insert("dns.question.type".to_string(), json!(dns.query_type));
+            if let Some(code) = &dns.response_code {
+                fields.insert("dns.response_code".to_string(), json!(code));
+            }
+            if !dns.answers.is_empty() {
+                fields.insert("dns.answers.data".to_string(), json!(dns.answers));
+            }
+        }
+        if let Some(tls) = &self.tls {
+            fields.insert("network.protocol".to_string(), json!("tls"));
+            fields.insert("tls.version".to_string(), json!(tls.version));
+            let optional = [
+                ("tls.client.server_name", tls.server_name.as_ref().map(|v| json!(v))),
+                ("tls.client.ja3", tls.ja3_hash.as_ref().map(|v| json!(v))),
+                ("tls.client.ja3_string", tls.ja3.as_ref().map(|v| json!(v))),
+                ("tls.client.supported_protocols", (!tls.alpn.is_empty()).then(|| json!(tls.alpn))),
+            ];
+            fields.extend(optional.into_iter().filter_map(|(key, value)| Some((key.to_string(), value?))));
+        }
+        if let Some(http) = &self.http {
+            fields.insert("network.protocol".to_string(), json!("http"));
+            fields.insert("http.request.method".to_string(), json!(http.method));
+            if let Some(host) = &http.host {
+                fields.insert("url.domain".to_string(), json!(host));
+            }
+            if let Some(user_agent) = &http.user_agent {
+                fields.insert("user_agent.original".to_string(), json!(user_agent));
+            }
+        }
+        fields
+    }
+}
+
+/// Link-layer header types understood by the decoder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkType {
+    Ethernet,
+    /// Linux "cooked" capture, used for the "any" device
+    LinuxSll,
+    /// Bare IPv4/IPv6
+    Raw,
+}
+
+impl LinkType {
+    /// From a pcap DLT value
+    pub fn from_dlt(dlt: i32) -> Option<Self> {
+        match dlt {
+            1 => Some(LinkType::Ethernet),
+            113 => Some(LinkType::LinuxSll),
+            12 | 101 => Some(LinkType::Raw),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    Tcp { flags: u8 },
+    Udp,
+}
+
+/// Addresses, ports and payload of a TCP or UDP packet
+#[derive(Debug)]
+struct Segment<'a> {
+    transport: Transport,
+    source: IpAddr,
+    destination: IpAddr,
+    source_port: u16,
+    destination_port: u16,
+    payload: &'a [u8],
+}
+
+const TCP_SYN: u8 = 0x02;
+const TCP_ACK: u8 = 0x10;
+
+fn be16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(offset)?, *data.get(offset + 1)?]))
+}
+
+fn decode_segment(link: LinkType, frame: &[u8]) -> Option<Segment<'_>> {
+    let (ethertype, packet) = match link {
+        LinkType::Ethernet => {
+            let mut offset = 12;
+            let mut ethertype = be16(frame, offset)?;
+            // 802.1Q and 802.1ad VLAN tags
+            while ethertype == 0x8100 || ethertype == 0x88a8 {
+                offset += 4;
+                ethertype = be16(frame, offset)?;
+            }
+            (ethertype, frame.get(offset + 2..)?)
+        }
+        LinkType::LinuxSll => (be16(frame, 14)?, frame.get(16..)?),
+        LinkType::Raw => match frame.first()? >> 4 {
+            4 => (0x0800, frame),
+            6 => (0x86dd, frame),
+            _ => return None,
+        },
+    };
+
+    let (source, destination, protocol, payload) = match ethertype {
+        0x0800 => {
+            let header_len = usize::from(packet.first()? & 0x0f) * 4;
+            let total_len = usize::from(be16(packet, 2)?).min(packet.len());
+            // Only first fragments carry the transport header
+            if be16(packet, 6)? & 0x1fff != 0 || header_len < 20 {
+                return None;
+            }
+            let source = Ipv4Addr::from(<[u8; 4]>::try_from(packet.get(12..16)?).ok()?);
+            let destination = Ipv4Addr::from(<[u8; 4]>::try_from(packet.get(16..20)?).ok()?);
+            (IpAddr::V4(source), IpAddr::V4(destination), *packet.get(9)?, packet.get(header_len..total_len)?)
+        }
+        0x86dd => {
+            let source = Ipv6Addr::from(<[u8; 16]>::try_from(packet.get(8..24)?).ok()?);
+            let destination = Ipv6Addr::from(<[u8; 16]>::try_from(packet.get(24..40)?).ok()?);
+            let end = (40 + usize::from(be16(packet, 4)?)).min(packet.len());
+            let mut next_header = *packet.get(6)?;
+            let mut offset = 40;
+            // Skip hop-by-hop, routing, fragment and destination option headers
+            loop {
+                match next_header {
+                    0 | 43 | 60 => {
+                        next_header = *packet.get(offset)?;
+                        offset += (usize::from(*packet.get(offset + 1)?) + 1) * 8;
+                    }
+                    44 => {
+                        if be16(packet, offset + 2)? & 0xfff8 != 0 {
+                            return None;
+                        }
+                        next_header = *packet.get(offset)?;
+                        offset += 8;
+                    }
+                    _ => break,
+                }
+            }
+            (IpAddr::V6(source), IpAddr::V6(destination), next_header, packet.get(offset..end)?)
+        }
+        _ => return None,
+    };
+
+    let (transport, header_len) = match protocol {
+        6 => (Transport::Tcp { flags: *payload.get(13)? }, usize::from(payload.get(12)? >> 4) * 4),
+        17 => (Transport::Udp, 8),
+        _ => return None,
+    };
+    Some(Segment {
+        transport,
+        source,
+        destination,
+        source_port: be16(payload, 0)?,
+        destination_port: be16(payload, 2)?,
+        payload: payload.get(header_len..)?,
+    })
+}
+
+/// A possibly compressed domain name at `offset`, and the offset just past it
+fn read_dns_name(message: &[u8], offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut position = offset;
+    let mut end = None;
+    let mut jumps = 0;
+    loop {
+        let length = usize::from(*message.get(position)?);
+        match length & 0xc0 {
+            0x00 if length == 0 => {
+                position += 1;
+                break;
+            }
+            0x00 => {
+                labels.push(String::from_utf8_lossy(message.get(position + 1..position + 1 + length)?).into_owned());
+                position += 1 + length;
+            }
+            0xc0 => {
+                end.get_or_insert(position + 2);
+                jumps += 1;
+                if jumps > 16 {
+                    return None;
+                }
+                position = ((length & 0x3f) << 8) | usize::from(*message.get(position + 1)?);
+            }
+            _ => return None,
+        }
+    }
+    Some((labels.join("."), end.unwrap_or(position)))
+}
+
+fn dns_type_name(record_type: u16) -> String {
+    match record_type {
+        1 => "A".to_string(),
+        2 => "NS".to_string(),
+        5 => "CNAME".to_string(),
+        6 => "SOA".to_string(),
+        12 => "PTR".to_string(),
+        15 => "MX".to_string(),
+        16 => "TXT".to_string(),
+        28 => "AAAA".to_string(),
+        33 => "SRV".to_string(),
+        65 => "HTTPS".to_string(),
+        255 => "ANY".to_string(),
+        other => format!("TYPE{}", other),
+    }
+}
+
+fn dns_rcode_name(rcode: u16) -> String {
+    match rcode {
+        0 => "NOERROR".to_string(),
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        3 => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        other => format!("RCODE{}", other),
+    }
+}
+
+fn parse_dns(message: &[u8]) -> Option<DnsMetadata> {
+    let id = be16(message, 0)?;
+    let flags = be16(message, 2)?;
+    if be16(message, 4)? == 0 {
+        return None;
+    }
+    let (question, offset) = read_dns_name(message, 12)?;
+    let query_type = be16(message, offset)?;
+    let response = flags & 0x8000 != 0;
+
+    let mut answers = Vec::new();
+    if response {
+        let mut offset = offset + 4;
+        for _ in 0..be16(message, 6)?.min(MAX_DNS_ANSWERS as u16) {
+            let Some((_, record)) = read_dns_name(message, offset) else { break };
+            let (Some(record_type), Some(length)) = (be16(message, record), be16(message, record + 8)) else { break };
+            let data_start = record + 10;
+            let Some(data) = message.get(data_start..data_start + usize::from(length)) else { break };
+            match (record_type, data.len()) {
+                (1, 4) => answers.push(Ipv4Addr::new(data[0], data[1], data[2], data[3]).to_string()),
+                (28, 16) => answers.push(Ipv6Addr::from(<[u8; 16]>::try_from(data).ok()?).to_string()),
+                (5, _) => answers.extend(read_dns_name(message, data_start).map(|(name, _)| name)),
+                _ => {}
+            }
+            offset = data_start + data.len();
+        }
+    }
+
+    Some(DnsMetadata {
+        id,
+        response,
+        question,
+        query_type: dns_type_name(query_type),
+        response_code: response.then(|| dns_rcode_name(flags & 0x000f)),
+        answers,
+    })
+}
+
+/// GREASE values (RFC 8701) are random per connection and left out of JA3
+fn is_grease(value: u16) -> bool {
+    value & 0x0f0f == 0x0a0a && value >> 8 == value & 0xff
+}
+
+fn tls_version_name(version: u16) -> String {
+    match version {
+        0x0300 => "SSL 3.0".to_string(),
+        0x0301 => "TLS 1.0".to_string(),
+        0x0302 => "TLS 1.1".to_string(),
+        0x0303 => "TLS 1.2".to_string(),
+        0x0304 => "TLS 1.3".to_string(),
+        other => format!("0x{:04x}", other),
+    }
+}
+
+/// Sequential reader over a possibly truncated buffer
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Option<u8> {
+        let value = *self.data.get(self.offset)?;
+        self.offset += 1;
+        Some(value)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let value = be16(self.data, self.offset)?;
+        self.offset += 2;
+        Some(value)
+    }
+
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        let value = self.data.get(self.offset..self.offset + length)?;
+        self.offset += length;
+        Some(value)
+    }
+
+    fn u16_list(&mut self, length: usize) -> Option<Vec<u16>> {
+        Some(self.take(length)?.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect())
+    }
+}
+
+/// Server name, ALPN and JA3 from a ClientHello at the start of a TCP payload
+fn parse_client_hello(payload: &[u8]) -> Option<TlsMetadata> {
+    // Handshake record carrying a ClientHello
+    if payload.len() < 9 || payload[0] != 0x16 || payload[1] != 0x03 || payload[5] != 0x01 {
+        return None;
+    }
+    let mut hello = Reader { data: &payload[9..], offset: 0 };
+    let legacy_version = hello.u16()?;
+    let mut metadata = TlsMetadata {
+        version: tls_version_name(legacy_version),
+        server_name: None,
+        alpn: Vec::new(),
+        ja3: None,
+        ja3_hash: None,
+    };
+
+    // Anything missing past the fixed fields means the hello continues in the next segment
+    let mut complete = || -> Option<String> {
+        hello.take(32)?;
+        let session_id = usize::from(hello.u8()?);
+        hello.take(session_id)?;
+        let cipher_bytes = usize::from(hello.u16()?);
+        let ciphers = hello.u16_list(cipher_bytes)?;
+        let compression = usize::from(hello.u8()?);
+        hello.take(compression)?;
+
+        let (mut extensions, mut groups, mut point_formats) = (Vec::new(), Vec::new(), Vec::new());
+        let mut highest_version = legacy_version;
+        if let Some(extension_bytes) = hello.u16() {
+            let mut extension_data = Reader { data: hello.take(usize::from(extension_bytes))?, offset: 0 };
+            while let Some(extension_type) = extension_data.u16() {
+                let length = usize::from(extension_data.u16()?);
+                let mut body = Reader { data: extension_data.take(length)?, offset: 0 };
+                extensions.push(extension_type);
+                match extension_type {
+                    0 => {
+                        body.u16()?;
+                        if body.u8()? == 0 {
+                            let name_length = usize::from(body.u16()?);
+                            metadata.server_name = Some(String::from_utf8_lossy(body.take(name_length)?).into_owned());
+                        }
+                    }
+                    10 => {
+                        let length = usize::from(body.u16()?);
+                        groups = body.u16_list(length)?;
+                    }
+                    11 => {
+                        let length = usize::from(body.u8()?);
+                        point_formats = body.take(length)?.to_vec();
+                    }
+                    16 => {
+                        body.u16()?;
+                        while let Some(length) = body.u8() {
+                            metadata.alpn.push(String::from_utf8_lossy(body.take(usize::from(length))?).into_owned());
+                        }
+                    }
+                    43 => {
+                        let length = usize::from(body.u8()?);
+                        highest_version = body.u16_list(length)?.into_iter().filter(|v| !is_grease(*v)).fold(highest_version, u16::max);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        metadata.version = tls_version_name(highest_version);
+
+        let join = |values: &[u16]| values.iter().filter(|v| !is_grease(**v)).map(u16::to_string).collect::<Vec<_>>().join("-");
+        Some(format!(
+            "{},{},{},{},{}",
+            legacy_version,
+            join(&ciphers),
+            join(&extensions),
+            join(&groups),
+            point_formats.iter().map(u8::to_string).collect::<Vec<_>>().join("-"),
+        ))
+    };
+    let ja3 = complete();
+    metadata.ja3_hash = ja3.as_deref().map(|ja3| md5_hex(ja3.as_bytes()));
+    metadata.ja3 = ja3;
+    Some(metadata)
+}
+
+/// MD5 digest as hex; JA3 fingerprints are defined as MD5, nothing else here relies on it
+fn md5_hex(input: &[u8]) -> String {
+    const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+    let constants: Vec<u32> = (0..64).map(|i| ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32).collect();
+
+    let mut message = input.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((input.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+    for block in message.chunks_exact(64) {
+        let words: Vec<u32> = block.chunks_exact(4).map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]])).collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a.wrapping_add(f).wrapping_add(constants[i]).wrapping_add(words[g])
+                .rotate_left(SHIFTS[(i / 16) * 4 + i % 4]);
+            (a, d, c) = (d, c, b);
+            b = b.wrapping_add(rotated);
+        }
+        for (word, value) in state.iter_mut().zip([a, b, c, d]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+    state.iter().flat_map(|word| word.to_le_bytes()).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Method, Host and User-Agent of an HTTP/1.x request
+fn parse_http_request(payload: &[u8]) -> Option<HttpMetadata> {
+    let head = String::from_utf8_lossy(&payload[..payload.len().min(MAX_HTTP_HEAD)]);
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?;
+    if !HTTP_METHODS.contains(&method) || request_line.nth(1).is_none_or(|version| !version.starts_with("HTTP/1.")) {
+        return None;
+    }
+    let mut metadata = HttpMetadata { method: method.to_string(), host: None, user_agent: None };
+    for line in lines.take_while(|line| !line.is_empty()) {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        if name.eq_ignore_ascii_case("host") {
+            metadata.host = Some(value.trim().to_string());
+        } else if name.eq_ignore_ascii_case("user-agent") {
+            metadata.user_agent = Some(value.trim().to_string());
+        }
+    }
+    Some(metadata)
+}
+
+/// Turns captured frames into metadata events according to the enabled protocols
+pub struct MetadataExtractor {
+    dns: bool,
+    tls: bool,
+    http: bool,
+    connections: bool,
+}
+
+impl MetadataExtractor {
+    pub fn new(config: &PacketMetadataCollectorConfig) -> Self {
+        Self { dns: config.dns, tls: config.tls, http: config.http, connections: config.connections }
+    }
+
+    pub fn extract(&self, link: LinkType, frame: &[u8], time: DateTime<Utc>) -> Option<NetworkMetadataEvent> {
+        let segment = decode_segment(link, frame)?;
+        let is_dns_port = segment.source_port == 53 || segment.destination_port == 53;
+        let mut event = NetworkMetadataEvent {
+            kind: NetworkEventKind::Connection,
+            time,
+            transport: match segment.transport { Transport::Tcp { .. } => "tcp", Transport::Udp => "udp" }.to_string(),
+            source_ip: segment.source.to_string(),
+            source_port: segment.source_port,
+            destination_ip: segment.destination.to_string(),
+            destination_port: segment.destination_port,
+            dns: None,
+            tls: None,
+            http: None,
+        };
+
+        match segment.transport {
+            Transport::Udp if self.dns && is_dns_port => {
+                event.kind = NetworkEventKind::Dns;
+                event.dns = Some(parse_dns(segment.payload)?);
+            }
+            Transport::Udp => return None,
+            // DNS over TCP prefixes each message with its length
+            Transport::Tcp { .. } if self.dns && is_dns_port && segment.payload.len() > 2 => {
+                event.kind = NetworkEventKind::Dns;
+                event.dns = Some(parse_dns(&segment.payload[2..])?);
+            }
+            Transport::Tcp { flags } if segment.payload.is_empty() => {
+                if !(self.connections && flags & TCP_SYN != 0 && flags & TCP_ACK == 0) {
+                    return None;
+                }
+            }
+            Transport::Tcp { .. } => {
+                if let Some(tls) = self.tls.then(|| parse_client_hello(segment.payload)).flatten() {
+                    event.kind = NetworkEventKind::Tls;
+                    event.tls = Some(tls);
+                } else if let Some(http) = self.http.then(|| parse_http_request(segment.payload)).flatten() {
+                    event.kind = NetworkEventKind::Http;
+                    event.http = Some(http);
+                } else {
+                    return None;
+                }
+            }
+        }
+        Some(event)
+    }
+}
+
+pub struct PacketMetadataCollector {
+    config: PacketMetadataCollectorConfig,
+    #[cfg_attr(not(feature = "pcap"), allow(dead_code))]
+    event_sender: mpsc::Sender<RawLogEvent>,
+    stop: Arc<AtomicBool>,
+    /// Events over max_events_per_second
+    dropped_events: Arc<AtomicU64>,
+    running: bool,
+}
+
+impl PacketMetadataCollector {
+    pub fn new(config: PacketMetadataCollectorConfig, event_sender: mpsc::Sender<RawLogEvent>) -> Self {
+        Self {
+            config,
+            event_sender,
+            stop: Arc::new(AtomicBool::new(false)),
+            dropped_events: Arc::new(AtomicU64::new(0)),
+            running: false,
+        }
+    }
+
+    pub fn protocols(&self) -> Vec<&'static str> {
+        [
+            (self.config.dns, "dns"),
+            (self.config.tls, "tls"),
+            (self.config.http, "http"),
+            (self.config.connections, "connections"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect()
+    }
+
+    pub fn raw_event(event: &NetworkMetadataEvent) -> RawLogEvent {
+        RawLogEvent {
+            timestamp: event.time,
+            source: PACKET_METADATA_SOURCE.to_string(),
+            raw_data: serde_json::to_string(event).unwrap_or_default().into(),
+            metadata: HashMap::from([("kind".to_string(), event.kind.as_str().to_string())]),
+        }
+    }
+
+    fn init_error(&self, reason: String) -> CollectorError {
+        CollectorError::InitializationFailed {
+            name: "packet_metadata".to_string(),
+            collector_type: "pcap".to_string(),
+            reason,
+            configuration: self.config.interface.clone(),
+        }
+    }
+
+    /// Read packets until `stop` is set; runs on a blocking thread
+    #[cfg(feature = "pcap")]
+    fn capture(
+        mut capture: pcap::Capture<pcap::Active>,
+        link: LinkType,
+        extractor: MetadataExtractor,
+        max_per_second: u32,
+        event_sender: mpsc::Sender<RawLogEvent>,
+        stop: Arc<AtomicBool>,
+        dropped_events: Arc<AtomicU64>,
+    ) {
+        use std::sync::atomic::Ordering;
+        use tracing::warn;
+
+        let mut window_start = std::time::Instant::now();
+        let mut window_count = 0u32;
+        while !stop.load(Ordering::Relaxed) {
+            let packet = match capture.next_packet() {
+                Ok(packet) => packet,
+                // The read timeout only exists so `stop` is noticed
+                Err(pcap::Error::TimeoutExpired) => continue,
+                Err(e) => {
+                    warn!("⚠️ Packet capture stopped: {}", e);
+                    break;
+                }
+            };
+            let time = DateTime::from_timestamp(packet.header.ts.tv_sec as i64, packet.header.ts.tv_usec as u32 * 1000)
+                .unwrap_or_else(Utc::now);
+            let Some(event) = extractor.extract(link, packet.data, time) else { continue };
+
+            if window_start.elapsed() >= std::time::Duration::from_secs(1) {
+                window_start = std::time::Instant::now();
+                window_count = 0;
+            }
+            window_count += 1;
+            if window_count > max_per_second {
+                dropped_events.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            if event_sender.blocking_send(Self::raw_event(&event)).is_err() {
+                break;
+            }
+        }
+        if let Ok(stats) = capture.stats() {
+            info!("📦 Packet capture finished: {} received, {} dropped by the kernel", stats.received, stats.dropped);
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for PacketMetadataCollector {
+    #[cfg(feature = "pcap")]
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        use std::sync::atomic::Ordering;
+
+        if !self.config.enabled {
+            info!("Packet metadata collector is disabled");
+            return Ok(());
+        }
+        let mut capture = pcap::Capture::from_device(self.config.interface.as_str())
+            .and_then(|capture| capture
+                .promisc(self.config.promiscuous)
+                .snaplen(self.config.snaplen as i32)
+                .timeout(500)
+                .immediate_mode(true)
+                .open())
+            .map_err(|e| self.init_error(format!("cannot capture on {}: {}", self.config.interface, e)))?;
+        if !self.config.bpf_filter.is_empty() {
+            capture.filter(&self.config.bpf_filter, true)
+                .map_err(|e| self.init_error(format!("invalid bpf_filter: {}", e)))?;
+        }
+        let datalink = capture.get_datalink();
+        let link = LinkType::from_dlt(datalink.0)
+            .ok_or_else(|| self.init_error(format!("unsupported link type {:?}", datalink)))?;
+        info!("🚀 Starting packet metadata collector on {} ({})", self.config.interface, self.protocols().join(", "));
+
+        self.stop.store(false, Ordering::Relaxed);
+        let extractor = MetadataExtractor::new(&self.config);
+        let (max_per_second, event_sender) = (self.config.max_events_per_second, self.event_sender.clone());
+        let (stop, dropped_events) = (self.stop.clone(), self.dropped_events.clone());
+        crate::component_usage::spawn_blocking(&crate::component_usage::collector("packet_metadata"), move || {
+            Self::capture(capture, link, extractor, max_per_second, event_sender, stop, dropped_events)
+        });
+
+        self.running = true;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "pcap"))]
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        Err(self.init_error("packet capture requires a build with the pcap feature".to_string()))
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        info!("🛑 Stopping packet metadata collector");
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        // Packets are read by the capture thread
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "packet_metadata"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn drop_counter(&self) -> Option<Arc<AtomicU64>> {
+        Some(self.dropped_events.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ethernet + IPv4 frame around a transport header and payload
+    fn ipv4_frame(protocol: u8, transport: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+        let total = (20 + transport.len()) as u16;
+        frame.extend_from_slice(&[0x45, 0, (total >> 8) as u8, total as u8, 0, 0, 0x40, 0, 64, protocol, 0, 0]);
+        frame.extend_from_slice(&[10, 0, 0, 5, 93, 184, 216, 34]);
+        frame.extend_from_slice(transport);
+        frame
+    }
+
+    fn extractor() -> MetadataExtractor {
+        MetadataExtractor { dns: true, tls: true, http: true, connections: true }
+    }
+
+    #[test]
+    fn test_md5_matches_reference_digests() {
+        assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5_hex(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(md5_hex(&[b'a'; 100]), "36a92cc94a9e0fa21f625f8bfb007adf");
+    }
+
+    #[test]
+    fn test_dns_queries_and_answers_are_decoded() {
+        // Response for example.com A with a compressed answer name
+        let mut dns = vec![0x12, 0x34, 0x81, 0x80, 0, 1, 0, 1, 0, 0, 0, 0];
+        dns.extend_from_slice(b"\x07example\x03com\x00\x00\x01\x00\x01");
+        dns.extend_from_slice(&[0xc0, 0x0c, 0, 1, 0, 1, 0, 0, 0x0e, 0x10, 0, 4, 93, 184, 216, 34]);
+        let mut udp = vec![0, 53, 0xc3, 0x50, 0, 0, 0, 0];
+        udp.extend_from_slice(&dns);
+
+        let event = extractor().extract(LinkType::Ethernet, &ipv4_frame(17, &udp), Utc::now()).unwrap();
+        assert_eq!(event.kind, NetworkEventKind::Dns);
+        let dns = event.dns.as_ref().unwrap();
+        assert_eq!((dns.question.as_str(), dns.query_type.as_str()), ("example.com", "A"));
+        assert_eq!(dns.response_code.as_deref(), Some("NOERROR"));
+        assert_eq!(dns.answers, vec!["93.184.216.34".to_string()]);
+        assert_eq!(event.to_fields()["dns.question.name"], json!("example.com"));
+    }
+
+    #[test]
+    fn test_client_hello_yields_sni_and_ja3() {
+        let sni = b"\x00\x00\x00\x10\x00\x0e\x00\x00\x0bexample.com";
+        let groups = b"\x00\x0a\x00\x06\x00\x04\x0a\x0a\x00\x1d";
+        let points = b"\x00\x0b\x00\x02\x01\x00";
+        let extensions: Vec<u8> = [&sni[..], &groups[..], &points[..]].concat();
+        let mut hello = vec![0x03, 0x03];
+        hello.extend_from_slice(&[0u8; 32]);
+        hello.push(0);
+        hello.extend_from_slice(&[0, 6, 0x1a, 0x1a, 0x13, 0x01, 0xc0, 0x2f]);
+        hello.extend_from_slice(&[1, 0]);
+        hello.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        hello.extend_from_slice(&extensions);
+        let mut record = vec![0x16, 0x03, 0x01];
+        record.extend_from_slice(&((hello.len() + 4) as u16).to_be_bytes());
+        record.extend_from_slice(&[0x01, 0, (hello.len() >> 8) as u8, hello.len() as u8]);
+        record.extend_from_slice(&hello);
+
+        let tls = parse_client_hello(&record).unwrap();
+        assert_eq!(tls.server_name.as_deref(), Some("example.com"));
+        assert_eq!(tls.ja3.as_deref(), Some("771,4865-49199,0-10-11,29,0"));
+        assert_eq!(tls.ja3_hash, Some(md5_hex(b"771,4865-49199,0-10-11,29,0")));
+
+        // Cut inside the extensions: the server name may be known, the fingerprint is not
+        let truncated = parse_client_hello(&record[..record.len() - 4]).unwrap();
+        assert_eq!(truncated.ja3, None);
+    }
+
+    #[test]
+    fn test_http_hosts_and_connections() {
+        let mut tcp = vec![0xc3, 0x50, 0, 80, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x18, 0, 0, 0, 0, 0, 0];
+        tcp.extend_from_slice(b"GET /index.html HTTP/1.1\r\nHost: example.com\r\nUser-Agent: curl/8.0\r\n\r\n");
+        let event = extractor().extract(LinkType::Ethernet, &ipv4_frame(6, &tcp), Utc::now()).unwrap();
+        assert_eq!(event.kind, NetworkEventKind::Http);
+        assert_eq!(event.http.as_ref().unwrap().host.as_deref(), Some("example.com"));
+        assert_eq!(event.destination_ip, "93.184.216.34");
+
+        let syn = [0xc3, 0x50, 1, 187, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, TCP_SYN, 0, 0, 0, 0, 0, 0];
+        let event = extractor().extract(LinkType::Ethernet, &ipv4_frame(6, &syn), Utc::now()).unwrap();
+        assert_eq!((event.kind, event.destination_port), (NetworkEventKind::Connection, 443));
+        let quiet = MetadataExtractor { connections: false, ..extractor() };
+        assert!(quiet.extract(LinkType::Ethernet, &ipv4_frame(6, &syn), Utc::now()).is_none());
+    }
+}
@@ -0,0 +1,776 @@
+// Host user session tracking collector
+// Correlates login/logout records from wtmp, systemd-logind, the Windows Security log
+// (4624/4634/4647) and who(1) into normalized session start/stop events with durations
+
+use crate::collectors::{Collector, RawLogEvent};
+use crate::config::SessionCollectorConfig;
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use tokio::sync::mpsc;
+use tracing::{info, error, debug, warn};
+
+pub const SESSION_SOURCE: &str = "session";
+
+/// Linux `struct utmp` size (glibc, 64-bit)
+const UTMP_RECORD_SIZE: usize = 384;
+const UT_RUN_LVL: i32 = 1;
+const UT_BOOT_TIME: i32 = 2;
+const UT_USER_PROCESS: i32 = 7;
+const UT_DEAD_PROCESS: i32 = 8;
+
+/// Windows logon types that don't represent a user session (system, service)
+const WINDOWS_NON_USER_LOGON_TYPES: &[&str] = &["0", "5"];
+
+/// Where session records come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionSourceKind {
+    /// Binary wtmp login/logout log
+    Wtmp,
+    /// systemd-logind runtime session files
+    Logind,
+    /// Windows Security log logon (4624) / logoff (4634, 4647) events
+    WindowsSecurity,
+    /// Active sessions reported by who(1), used on macOS
+    Who,
+}
+
+impl SessionSourceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionSourceKind::Wtmp => "wtmp",
+            SessionSourceKind::Logind => "logind",
+            SessionSourceKind::WindowsSecurity => "windows_security",
+            SessionSourceKind::Who => "who",
+        }
+    }
+
+    /// Sources used when none are configured
+    pub fn platform_defaults(logind_sessions_dir: &str) -> Vec<Self> {
+        if cfg!(windows) {
+            vec![SessionSourceKind::WindowsSecurity]
+        } else if cfg!(target_os = "macos") {
+            vec![SessionSourceKind::Who]
+        } else if Path::new(logind_sessions_dir).is_dir() {
+            vec![SessionSourceKind::Logind]
+        } else {
+            vec![SessionSourceKind::Wtmp]
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionAction {
+    Start,
+    Stop,
+}
+
+/// A session as reported by one of the sources
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionRecord {
+    /// Correlation key within the source (tty, logind session, logon ID)
+    pub key: String,
+    pub session_id: String,
+    pub user: String,
+    pub tty: Option<String>,
+    pub remote_host: Option<String>,
+    pub login_type: Option<String>,
+    pub time: DateTime<Utc>,
+}
+
+/// Normalized session start/stop event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionEvent {
+    pub action: SessionAction,
+    pub source: SessionSourceKind,
+    pub session_id: String,
+    pub user: String,
+    pub tty: Option<String>,
+    pub remote_host: Option<String>,
+    pub login_type: Option<String>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub duration_seconds: Option<i64>,
+    /// Why the session ended: logout, superseded, reboot or shutdown
+    pub end_reason: Option<String>,
+}
+
+impl SessionEvent {
+    fn started(source: SessionSourceKind, record: &SessionRecord) -> Self {
+        Self {
+            action: SessionAction::Start,
+            source,
+            session_id: record.session_id.clone(),
+            user: record.user.clone(),
+            tty: record.tty.clone(),
+            remote_host: record.remote_host.clone(),
+            login_type: record.login_type.clone(),
+            start_time: record.time,
+            end_time: None,
+            duration_seconds: None,
+            end_reason: None,
+        }
+    }
+
+    fn stopped(source: SessionSourceKind, record: &SessionRecord, end_time: DateTime<Utc>, reason: &str) -> Self {
+        Self {
+            action: SessionAction::Stop,
+            end_time: Some(end_time),
+            duration_seconds: Some((end_time - record.time).num_seconds().max(0)),
+            end_reason: Some(reason.to_string()),
+            ..Self::started(source, record)
+        }
+    }
+
+    /// Time the event happened
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.end_time.unwrap_or(self.start_time)
+    }
+
+    pub fn message(&self) -> String {
+        let location = match (&self.tty, &self.remote_host) {
+            (Some(tty), Some(host)) => format!(" on {} from {}", tty, host),
+            (Some(tty), None) => format!(" on {}", tty),
+            (None, Some(host)) => format!(" from {}", host),
+            (None, None) => String::new(),
+        };
+        match self.action {
+            SessionAction::Start => format!("Session started for {}{}", self.user, location),
+            SessionAction::Stop => format!(
+                "Session ended for {}{} after {}s ({})",
+                self.user,
+                location,
+                self.duration_seconds.unwrap_or(0),
+                self.end_reason.as_deref().unwrap_or("logout")
+            ),
+        }
+    }
+
+    /// Flattened fields for the parsed event
+    pub fn to_fields(&self) -> HashMap<String, Value> {
+        let mut fields = HashMap::from([
+            ("event.category".to_string(), json!("session")),
+            ("event.action".to_string(), json!(match self.action {
+                SessionAction::Start => "session_start",
+                SessionAction::Stop => "session_end",
+            })),
+            ("session.id".to_string(), json!(self.session_id)),
+            ("session.source".to_string(), json!(self.source.as_str())),
+            ("session.start".to_string(), json!(self.start_time.to_rfc3339())),
+            ("user.name".to_string(), json!(self.user)),
+        ]);
+        let optional = [
+            ("session.tty", self.tty.as_ref().map(|v| json!(v))),
+            ("session.type", self.login_type.as_ref().map(|v| json!(v))),
+            ("source.address", self.remote_host.as_ref().map(|v| json!(v))),
+            ("session.end", self.end_time.map(|v| json!(v.to_rfc3339()))),
+            ("session.duration_seconds", self.duration_seconds.map(|v| json!(v))),
+            ("session.end_reason", self.end_reason.as_ref().map(|v| json!(v))),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                fields.insert(key.to_string(), value);
+            }
+        }
+        fields
+    }
+}
+
+/// Correlates start and stop records into sessions with durations
+#[derive(Debug, Default)]
+pub struct SessionTracker {
+    open: HashMap<(SessionSourceKind, String), SessionRecord>,
+}
+
+impl SessionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn open_sessions(&self) -> usize {
+        self.open.len()
+    }
+
+    pub fn start(&mut self, source: SessionSourceKind, record: SessionRecord) -> Vec<SessionEvent> {
+        let mut events = Vec::new();
+        // A new login on the same tty means the previous logout was never recorded
+        if let Some(previous) = self.open.remove(&(source, record.key.clone())) {
+            events.push(SessionEvent::stopped(source, &previous, record.time, "superseded"));
+        }
+        events.push(SessionEvent::started(source, &record));
+        self.open.insert((source, record.key.clone()), record);
+        events
+    }
+
+    pub fn stop(&mut self, source: SessionSourceKind, key: &str, time: DateTime<Utc>, reason: &str) -> Option<SessionEvent> {
+        let record = self.open.remove(&(source, key.to_string()))?;
+        Some(SessionEvent::stopped(source, &record, time, reason))
+    }
+
+    /// Close every open session from a source (e.g. on reboot)
+    pub fn stop_all(&mut self, source: SessionSourceKind, time: DateTime<Utc>, reason: &str) -> Vec<SessionEvent> {
+        let keys: Vec<String> = self.open.keys().filter(|(s, _)| *s == source).map(|(_, k)| k.clone()).collect();
+        keys.iter().filter_map(|key| self.stop(source, key, time, reason)).collect()
+    }
+
+    /// Diff a snapshot of active sessions against the open set
+    pub fn reconcile(&mut self, source: SessionSourceKind, snapshot: Vec<SessionRecord>, now: DateTime<Utc>) -> Vec<SessionEvent> {
+        let active: std::collections::HashSet<String> = snapshot.iter().map(|r| r.key.clone()).collect();
+        let ended: Vec<String> = self
+            .open
+            .keys()
+            .filter(|(s, key)| *s == source && !active.contains(key))
+            .map(|(_, key)| key.clone())
+            .collect();
+
+        let mut events: Vec<SessionEvent> = ended
+            .iter()
+            .filter_map(|key| self.stop(source, key, now, "logout"))
+            .collect();
+        for record in snapshot {
+            if !self.open.contains_key(&(source, record.key.clone())) {
+                events.extend(self.start(source, record));
+            }
+        }
+        events
+    }
+}
+
+/// A decoded wtmp/utmp entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct UtmpRecord {
+    pub ut_type: i32,
+    pub pid: i32,
+    pub line: String,
+    pub user: String,
+    pub host: String,
+    pub time: DateTime<Utc>,
+}
+
+/// Decode every complete record in a wtmp buffer
+pub fn parse_utmp_records(data: &[u8]) -> Vec<UtmpRecord> {
+    let c_string = |bytes: &[u8]| {
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+    };
+    let i32_at = |record: &[u8], offset: usize| i32::from_ne_bytes(record[offset..offset + 4].try_into().unwrap_or_default());
+
+    data.chunks_exact(UTMP_RECORD_SIZE)
+        .map(|record| UtmpRecord {
+            ut_type: i32_at(record, 0),
+            pid: i32_at(record, 4),
+            line: c_string(&record[8..40]),
+            user: c_string(&record[44..76]),
+            host: c_string(&record[76..332]),
+            time: Utc.timestamp_opt(i32_at(record, 340) as i64, 0).single().unwrap_or_else(Utc::now),
+        })
+        .collect()
+}
+
+/// Feed one wtmp entry to the tracker
+pub fn apply_wtmp_record(tracker: &mut SessionTracker, record: &UtmpRecord) -> Vec<SessionEvent> {
+    let source = SessionSourceKind::Wtmp;
+    match record.ut_type {
+        UT_USER_PROCESS if !record.user.is_empty() => {
+            let remote = !record.host.is_empty() && !record.host.starts_with(':');
+            tracker.start(source, SessionRecord {
+                key: record.line.clone(),
+                session_id: format!("{}-{}-{}", record.line, record.pid, record.time.timestamp()),
+                user: record.user.clone(),
+                tty: Some(record.line.clone()),
+                remote_host: remote.then(|| record.host.clone()),
+                login_type: Some(if remote { "remote" } else { "local" }.to_string()),
+                time: record.time,
+            })
+        }
+        UT_DEAD_PROCESS => tracker.stop(source, &record.line, record.time, "logout").into_iter().collect(),
+        UT_BOOT_TIME => tracker.stop_all(source, record.time, "reboot"),
+        UT_RUN_LVL if record.user == "shutdown" => tracker.stop_all(source, record.time, "shutdown"),
+        _ => Vec::new(),
+    }
+}
+
+/// Parse a systemd-logind session file (`/run/systemd/sessions/<id>`)
+pub fn parse_logind_session(session_id: &str, contents: &str) -> Option<SessionRecord> {
+    let values: HashMap<&str, &str> = contents
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .collect();
+
+    // Skip greeter/lock-screen and background sessions
+    if values.get("CLASS").is_some_and(|class| *class != "user") {
+        return None;
+    }
+    let user = values.get("USER")?.to_string();
+    let time = values
+        .get("REALTIME")
+        .and_then(|usec| usec.parse::<i64>().ok())
+        .and_then(|usec| Utc.timestamp_micros(usec).single())
+        .unwrap_or_else(Utc::now);
+    let non_empty = |key: &str| values.get(key).filter(|v| !v.is_empty()).map(|v| v.to_string());
+
+    Some(SessionRecord {
+        key: session_id.to_string(),
+        session_id: session_id.to_string(),
+        user,
+        tty: non_empty("TTY").or_else(|| non_empty("DISPLAY")),
+        remote_host: non_empty("REMOTE_HOST"),
+        login_type: non_empty("SERVICE").or_else(|| non_empty("TYPE")),
+        time,
+    })
+}
+
+fn read_logind_sessions(dir: &str) -> Vec<SessionRecord> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().is_file() && entry.path().extension().is_none())
+        .filter_map(|entry| {
+            let session_id = entry.file_name().to_string_lossy().to_string();
+            let contents = std::fs::read_to_string(entry.path()).ok()?;
+            parse_logind_session(&session_id, &contents)
+        })
+        .collect()
+}
+
+/// Parse `who` output into the active session set
+pub fn parse_who_output(output: &str, now: DateTime<Utc>) -> Vec<SessionRecord> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let user = parts.next()?.to_string();
+            let tty = parts.next()?.to_string();
+            let remote_host = line
+                .rfind('(')
+                .and_then(|start| line[start + 1..].strip_suffix(')'))
+                .filter(|host| !host.is_empty() && !host.starts_with(':'))
+                .map(|host| host.to_string());
+            Some(SessionRecord {
+                key: format!("{}@{}", user, tty),
+                session_id: format!("{}-{}-{}", user, tty, now.timestamp()),
+                login_type: Some(if remote_host.is_some() { "remote" } else { "local" }.to_string()),
+                user,
+                tty: Some(tty),
+                remote_host,
+                time: now,
+            })
+        })
+        .collect()
+}
+
+/// A Windows Security log logon/logoff event
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowsLogonEvent {
+    pub event_id: u32,
+    pub record_id: u64,
+    pub time: DateTime<Utc>,
+    pub data: HashMap<String, String>,
+}
+
+fn windows_event_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)<Event[ >].*?</Event>").unwrap())
+}
+
+fn windows_data_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"<Data Name=['"](\w+)['"]>([^<]*)</Data>"#).unwrap())
+}
+
+fn windows_system_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"<EventID[^>]*>(\d+)</EventID>|<EventRecordID>(\d+)</EventRecordID>|<TimeCreated SystemTime=['"]([^'"]+)['"]"#).unwrap()
+    })
+}
+
+/// Parse `wevtutil qe /f:xml` output
+pub fn parse_windows_events(xml: &str) -> Vec<WindowsLogonEvent> {
+    windows_event_regex()
+        .find_iter(xml)
+        .filter_map(|event| {
+            let event = event.as_str();
+            let (mut event_id, mut record_id, mut time) = (None, None, None);
+            for captures in windows_system_regex().captures_iter(event) {
+                if let Some(id) = captures.get(1) {
+                    event_id = id.as_str().parse().ok();
+                } else if let Some(id) = captures.get(2) {
+                    record_id = id.as_str().parse().ok();
+                } else if let Some(created) = captures.get(3) {
+                    time = DateTime::parse_from_rfc3339(created.as_str()).ok().map(|t| t.with_timezone(&Utc));
+                }
+            }
+            let data = windows_data_regex()
+                .captures_iter(event)
+                .map(|c| (c[1].to_string(), c[2].trim().to_string()))
+                .collect();
+            Some(WindowsLogonEvent {
+                event_id: event_id?,
+                record_id: record_id?,
+                time: time.unwrap_or_else(Utc::now),
+                data,
+            })
+        })
+        .collect()
+}
+
+/// Feed one Security log event to the tracker, correlating on TargetLogonId
+pub fn apply_windows_event(tracker: &mut SessionTracker, event: &WindowsLogonEvent) -> Vec<SessionEvent> {
+    let source = SessionSourceKind::WindowsSecurity;
+    let field = |name: &str| event.data.get(name).filter(|v| !v.is_empty() && v.as_str() != "-").cloned();
+    let Some(logon_id) = field("TargetLogonId") else {
+        return Vec::new();
+    };
+
+    match event.event_id {
+        4624 => {
+            let user = field("TargetUserName").unwrap_or_default();
+            let logon_type = field("LogonType").unwrap_or_default();
+            // Computer accounts and service logons are not user sessions
+            if user.is_empty() || user.ends_with('$') || WINDOWS_NON_USER_LOGON_TYPES.contains(&logon_type.as_str()) {
+                return Vec::new();
+            }
+            let user = match field("TargetDomainName") {
+                Some(domain) => format!("{}\\{}", domain, user),
+                None => user,
+            };
+            tracker.start(source, SessionRecord {
+                key: logon_id.clone(),
+                session_id: logon_id,
+                user,
+                tty: field("WorkstationName"),
+                remote_host: field("IpAddress").filter(|ip| ip != "127.0.0.1" && ip != "::1"),
+                login_type: Some(windows_logon_type_name(&logon_type).to_string()),
+                time: event.time,
+            })
+        }
+        4634 | 4647 => tracker.stop(source, &logon_id, event.time, "logout").into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn windows_logon_type_name(logon_type: &str) -> &str {
+    match logon_type {
+        "2" => "interactive",
+        "3" => "network",
+        "4" => "batch",
+        "7" => "unlock",
+        "8" => "network_cleartext",
+        "9" => "new_credentials",
+        "10" => "remote_interactive",
+        "11" => "cached_interactive",
+        other => other,
+    }
+}
+
+/// Per-source polling state
+#[derive(Debug, Default)]
+struct SourceState {
+    wtmp_position: u64,
+    windows_record_id: u64,
+}
+
+pub struct SessionCollector {
+    config: SessionCollectorConfig,
+    sources: Vec<SessionSourceKind>,
+    event_sender: mpsc::Sender<RawLogEvent>,
+    shutdown_sender: Option<tokio::sync::oneshot::Sender<()>>,
+    running: bool,
+}
+
+impl SessionCollector {
+    pub fn new(config: SessionCollectorConfig, event_sender: mpsc::Sender<RawLogEvent>) -> Self {
+        let sources = if config.sources.is_empty() {
+            SessionSourceKind::platform_defaults(&config.logind_sessions_dir)
+        } else {
+            config.sources.clone()
+        };
+
+        Self {
+            config,
+            sources,
+            event_sender,
+            shutdown_sender: None,
+            running: false,
+        }
+    }
+
+    pub fn sources(&self) -> &[SessionSourceKind] {
+        &self.sources
+    }
+
+    /// Poll one source, returning the session events it produced
+    async fn poll_source(
+        source: SessionSourceKind,
+        config: &SessionCollectorConfig,
+        state: &mut SourceState,
+        tracker: &mut SessionTracker,
+    ) -> Result<Vec<SessionEvent>, CollectorError> {
+        match source {
+            SessionSourceKind::Wtmp => {
+                let data = tokio::fs::read(&config.wtmp_path).await.map_err(|e| CollectorError::FileSystemError {
+                    operation: "read_wtmp".to_string(),
+                    path: config.wtmp_path.clone(),
+                    permissions_issue: e.kind() == std::io::ErrorKind::PermissionDenied,
+                    source: e,
+                })?;
+                // wtmp rotated or truncated
+                if state.wtmp_position > data.len() as u64 {
+                    state.wtmp_position = 0;
+                }
+                let start = state.wtmp_position as usize;
+                let complete = (data.len() - start) / UTMP_RECORD_SIZE * UTMP_RECORD_SIZE;
+                state.wtmp_position += complete as u64;
+                Ok(parse_utmp_records(&data[start..start + complete])
+                    .iter()
+                    .flat_map(|record| apply_wtmp_record(tracker, record))
+                    .collect())
+            }
+            SessionSourceKind::Logind => {
+                let snapshot = read_logind_sessions(&config.logind_sessions_dir);
+                Ok(tracker.reconcile(source, snapshot, Utc::now()))
+            }
+            SessionSourceKind::Who => {
+                let output = run_command("who", &[]).await?;
+                Ok(tracker.reconcile(source, parse_who_output(&output, Utc::now()), Utc::now()))
+            }
+            SessionSourceKind::WindowsSecurity => {
+                let query = format!(
+                    "/q:*[System[(EventID=4624 or EventID=4634 or EventID=4647) and EventRecordID>{}]]",
+                    state.windows_record_id
+                );
+                let output = run_command("wevtutil", &["qe", "Security", &query, "/f:xml", "/c:1000"]).await?;
+                let mut events = Vec::new();
+                for event in parse_windows_events(&output) {
+                    state.windows_record_id = state.windows_record_id.max(event.record_id);
+                    events.extend(apply_windows_event(tracker, &event));
+                }
+                Ok(events)
+            }
+        }
+    }
+
+    /// Skip (or replay silently) history that predates the collector so only new
+    /// sessions are reported while logouts of already-open sessions still correlate
+    async fn prime_source(
+        source: SessionSourceKind,
+        config: &SessionCollectorConfig,
+        state: &mut SourceState,
+        tracker: &mut SessionTracker,
+    ) -> Vec<SessionEvent> {
+        let events = match source {
+            SessionSourceKind::WindowsSecurity if !config.read_from_start => {
+                // Start after the newest Security log record
+                if let Ok(output) = run_command("wevtutil", &["qe", "Security", "/c:1", "/rd:true", "/f:xml"]).await {
+                    state.windows_record_id = parse_windows_events(&output).first().map(|e| e.record_id).unwrap_or(0);
+                }
+                Ok(Vec::new())
+            }
+            _ => Self::poll_source(source, config, state, tracker).await,
+        };
+
+        match events {
+            Ok(events) if config.read_from_start => events,
+            Ok(_) => Vec::new(),
+            Err(e) => {
+                warn!("Failed to read initial {} session state: {}", source.as_str(), e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+async fn run_command(program: &str, args: &[&str]) -> Result<String, CollectorError> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| CollectorError::FileSystemError {
+            operation: "run_command".to_string(),
+            path: program.to_string(),
+            permissions_issue: e.kind() == std::io::ErrorKind::PermissionDenied,
+            source: e,
+        })?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[async_trait]
+impl Collector for SessionCollector {
+    async fn start(&mut self) -> Result<(), CollectorError> {
+        if !self.config.enabled {
+            info!("Session collector is disabled");
+            return Ok(());
+        }
+
+        let names: Vec<&str> = self.sources.iter().map(|s| s.as_str()).collect();
+        info!("🚀 Starting session collector (sources: {})", names.join(", "));
+
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel();
+        self.shutdown_sender = Some(shutdown_tx);
+
+        let config = self.config.clone();
+        let sources = self.sources.clone();
+        let event_sender = self.event_sender.clone();
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(config.poll_interval_ms.max(500)));
+
+        crate::component_usage::spawn_inherited(async move {
+            let mut tracker = SessionTracker::new();
+            let mut states: HashMap<SessionSourceKind, SourceState> = HashMap::new();
+            let mut pending = Vec::new();
+            for source in &sources {
+                let state = states.entry(*source).or_default();
+                pending.extend(Self::prime_source(*source, &config, state, &mut tracker).await);
+            }
+            debug!("👤 Session collector primed with {} open sessions", tracker.open_sessions());
+
+            loop {
+                for event in pending.drain(..) {
+                    let raw_event = RawLogEvent {
+                        timestamp: event.timestamp(),
+                        source: SESSION_SOURCE.to_string(),
+                        raw_data: serde_json::to_string(&event).unwrap_or_default().into(),
+                        metadata: HashMap::from([
+                            ("session_source".to_string(), event.source.as_str().to_string()),
+                            ("session_action".to_string(), format!("{:?}", event.action).to_lowercase()),
+                        ]),
+                    };
+                    if let Err(e) = event_sender.send(raw_event).await {
+                        error!("Failed to send session event: {}", e);
+                        return;
+                    }
+                }
+
+                tokio::select! {
+                    _ = interval.tick() => {
+                        for source in &sources {
+                            let state = states.entry(*source).or_default();
+                            match Self::poll_source(*source, &config, state, &mut tracker).await {
+                                Ok(events) => pending.extend(events),
+                                Err(e) => warn!("Failed to poll {} sessions: {}", source.as_str(), e),
+                            }
+                        }
+                    }
+                    _ = &mut shutdown_rx => {
+                        debug!("Session collector shutting down");
+                        break;
+                    }
+                }
+            }
+        });
+
+        self.running = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), CollectorError> {
+        info!("🛑 Stopping session collector");
+
+        if let Some(sender) = self.shutdown_sender.take() {
+            let _ = sender.send(());
+        }
+
+        self.running = false;
+        Ok(())
+    }
+
+    async fn collect(&mut self) -> Result<Vec<RawLogEvent>, CollectorError> {
+        // Collection happens in the background polling task
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        "session"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utmp(ut_type: i32, pid: i32, line: &str, user: &str, host: &str, time: i32) -> Vec<u8> {
+        let mut record = vec![0u8; UTMP_RECORD_SIZE];
+        record[0..4].copy_from_slice(&ut_type.to_ne_bytes());
+        record[4..8].copy_from_slice(&pid.to_ne_bytes());
+        record[8..8 + line.len()].copy_from_slice(line.as_bytes());
+        record[44..44 + user.len()].copy_from_slice(user.as_bytes());
+        record[76..76 + host.len()].copy_from_slice(host.as_bytes());
+        record[340..344].copy_from_slice(&time.to_ne_bytes());
+        record
+    }
+
+    #[test]
+    fn test_wtmp_login_logout_and_reboot() {
+        let mut data = utmp(UT_USER_PROCESS, 100, "pts/0", "alice", "10.0.0.5", 1_700_000_000);
+        data.extend(utmp(UT_USER_PROCESS, 101, "tty1", "bob", "", 1_700_000_100));
+        data.extend(utmp(UT_DEAD_PROCESS, 100, "pts/0", "", "", 1_700_003_600));
+        data.extend(utmp(UT_BOOT_TIME, 0, "~", "reboot", "", 1_700_010_000));
+
+        let mut tracker = SessionTracker::new();
+        let events: Vec<SessionEvent> = parse_utmp_records(&data)
+            .iter()
+            .flat_map(|record| apply_wtmp_record(&mut tracker, record))
+            .collect();
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].action, SessionAction::Start);
+        assert_eq!(events[0].remote_host.as_deref(), Some("10.0.0.5"));
+        assert_eq!(events[2].user, "alice");
+        assert_eq!(events[2].duration_seconds, Some(3600));
+        assert_eq!(events[2].session_id, events[0].session_id);
+        assert_eq!(events[3].user, "bob");
+        assert_eq!(events[3].end_reason.as_deref(), Some("reboot"));
+        assert_eq!(tracker.open_sessions(), 0);
+    }
+
+    #[test]
+    fn test_logind_snapshot_reconciliation() {
+        let session = "USER=alice\nACTIVE=1\nREMOTE=1\nREMOTE_HOST=192.0.2.10\nTTY=pts/1\nSERVICE=sshd\nCLASS=user\nREALTIME=1700000000000000\n";
+        let record = parse_logind_session("42", session).unwrap();
+        assert_eq!(record.login_type.as_deref(), Some("sshd"));
+        assert!(parse_logind_session("c1", "USER=gdm\nCLASS=greeter\n").is_none());
+
+        let mut tracker = SessionTracker::new();
+        let started = tracker.reconcile(SessionSourceKind::Logind, vec![record.clone()], Utc::now());
+        assert_eq!(started.len(), 1);
+        assert!(tracker.reconcile(SessionSourceKind::Logind, vec![record], Utc::now()).is_empty());
+
+        let end = Utc.timestamp_opt(1_700_000_600, 0).unwrap();
+        let stopped = tracker.reconcile(SessionSourceKind::Logind, Vec::new(), end);
+        assert_eq!(stopped[0].duration_seconds, Some(600));
+        assert_eq!(stopped[0].to_fields()["event.action"], "session_end");
+    }
+
+    #[test]
+    fn test_windows_logon_logoff_correlation() {
+        let xml = r#"<Events><Event xmlns='http://schemas.microsoft.com/win/2004/08/events/event'><System><EventID>4624</EventID><TimeCreated SystemTime='2024-01-15T10:00:00.0000000Z'/><EventRecordID>500</EventRecordID></System><EventData><Data Name='TargetUserName'>alice</Data><Data Name='TargetDomainName'>CORP</Data><Data Name='TargetLogonId'>0x3e7aa</Data><Data Name='LogonType'>10</Data><Data Name='IpAddress'>10.1.1.1</Data></EventData></Event>
+<Event xmlns='http://schemas.microsoft.com/win/2004/08/events/event'><System><EventID>4624</EventID><TimeCreated SystemTime='2024-01-15T10:00:01Z'/><EventRecordID>501</EventRecordID></System><EventData><Data Name='TargetUserName'>WS01$</Data><Data Name='TargetLogonId'>0x1</Data><Data Name='LogonType'>3</Data></EventData></Event>
+<Event xmlns='http://schemas.microsoft.com/win/2004/08/events/event'><System><EventID>4634</EventID><TimeCreated SystemTime='2024-01-15T10:30:00Z'/><EventRecordID>502</EventRecordID></System><EventData><Data Name='TargetUserName'>alice</Data><Data Name='TargetLogonId'>0x3e7aa</Data></EventData></Event></Events>"#;
+
+        let events = parse_windows_events(xml);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2].record_id, 502);
+
+        let mut tracker = SessionTracker::new();
+        let sessions: Vec<SessionEvent> = events.iter().flat_map(|e| apply_windows_event(&mut tracker, e)).collect();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].user, "CORP\\alice");
+        assert_eq!(sessions[0].login_type.as_deref(), Some("remote_interactive"));
+        assert_eq!(sessions[1].action, SessionAction::Stop);
+        assert_eq!(sessions[1].duration_seconds, Some(1800));
+    }
+}
@@ -1,21 +1,225 @@
-// Syslog collector with UDP/TCP support and RFC 3164/5424 parsing
+// Syslog collector with UDP, TCP and TLS (RFC 5425) listeners
+// TCP streams are split into messages by octet counting or non-transparent framing (RFC 6587); the messages
+// themselves are parsed by the built-in syslog parser. TCP senders are slowed by flow control under backpressure;
+// UDP has none, so the UDP listener sheds load itself and reports what it dropped.
 
 use crate::collectors::{Collector, RawLogEvent};
-use crate::config::SyslogCollectorConfig;
+use crate::config::{SyslogCollectorConfig, SyslogFraming};
 use crate::errors::CollectorError;
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::io;
 use std::net::SocketAddr;
-use tokio::net::{UdpSocket, TcpListener, TcpStream};
-use tokio::sync::mpsc;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::net::{UdpSocket, TcpListener};
+use tokio::sync::{mpsc, watch};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
 use tracing::{info, error, debug, warn};
 
+/// Longest MSG-LEN prefix accepted for an octet-counted frame
+const MAX_OCTET_COUNT_DIGITS: usize = 10;
+
+/// Read the next syslog message from a stream; None once the peer closes the connection
+pub async fn read_frame<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    framing: SyslogFraming,
+    max_message_size: usize,
+) -> io::Result<Option<Vec<u8>>> {
+    // Skip trailers left by the previous frame and keep-alive newlines
+    let first = loop {
+        let available = reader.fill_buf().await?;
+        let Some(&byte) = available.first() else { return Ok(None) };
+        if matches!(byte, b'\n' | b'\r' | b'\0') {
+            reader.consume(1);
+            continue;
+        }
+        break byte;
+    };
+
+    let octet_counted = match framing {
+        SyslogFraming::OctetCounting if !first.is_ascii_digit() => {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "expected an octet-counted syslog frame"));
+        }
+        SyslogFraming::OctetCounting => true,
+        SyslogFraming::Auto => first.is_ascii_digit(),
+        SyslogFraming::NonTransparent => false,
+    };
+
+    if octet_counted {
+        let mut digits = Vec::with_capacity(MAX_OCTET_COUNT_DIGITS);
+        loop {
+            let byte = reader.read_u8().await?;
+            match byte {
+                b' ' if !digits.is_empty() => break,
+                b'0'..=b'9' if digits.len() < MAX_OCTET_COUNT_DIGITS => digits.push(byte),
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed syslog octet count")),
+            }
+        }
+        let length: usize = std::str::from_utf8(&digits).ok().and_then(|d| d.parse().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed syslog octet count"))?;
+        if length > max_message_size {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("syslog frame of {} bytes exceeds max_message_size {}", length, max_message_size)));
+        }
+        let mut message = vec![0u8; length];
+        reader.read_exact(&mut message).await?;
+        return Ok(Some(message));
+    }
+
+    // Non-transparent: up to LF or NUL; the excess of oversized messages is dropped
+    let mut message = Vec::new();
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(Some(message));
+        }
+        let end = available.iter().position(|&b| b == b'\n' || b == b'\0');
+        let chunk = &available[..end.unwrap_or(available.len())];
+        let room = max_message_size.saturating_sub(message.len());
+        message.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        let consumed = chunk.len() + usize::from(end.is_some());
+        reader.consume(consumed);
+        if end.is_some() {
+            return Ok(Some(message));
+        }
+    }
+}
+
+/// Counts UDP messages lost to backpressure and turns them into periodic summary events
+struct DropAccounting {
+    total: Arc<AtomicU64>,
+    /// Dropped since the last summary that reached the pipeline
+    pending: u64,
+    /// Kernel receive-queue drop counter at the last poll
+    kernel_drops: Option<u64>,
+}
+
+impl DropAccounting {
+    fn new(total: Arc<AtomicU64>) -> Self {
+        Self { total, pending: 0, kernel_drops: None }
+    }
+
+    fn record(&mut self, dropped: u64) {
+        if dropped > 0 {
+            self.total.fetch_add(dropped, Ordering::Relaxed);
+            self.pending += dropped;
+        }
+    }
+
+    /// Count datagrams the kernel discarded since the last poll; the first reading only sets the baseline
+    fn record_kernel_drops(&mut self, current: Option<u64>) {
+        let Some(current) = current else { return };
+        if let Some(previous) = self.kernel_drops.replace(current) {
+            self.record(current.saturating_sub(previous));
+        }
+    }
+
+    /// Summary of the drops not yet reported, or None when nothing was dropped
+    fn marker(&self, local_addr: &str) -> Option<RawLogEvent> {
+        (self.pending > 0).then(|| RawLogEvent {
+            timestamp: chrono::Utc::now(),
+            source: "syslog".to_string(),
+            raw_data: format!("{} syslog events dropped on {} due to backpressure", self.pending, local_addr).into(),
+            metadata: HashMap::from([
+                ("protocol".to_string(), "udp".to_string()),
+                ("marker".to_string(), "dropped_events".to_string()),
+                ("dropped_events".to_string(), self.pending.to_string()),
+                ("dropped_events_total".to_string(), self.total.load(Ordering::Relaxed).to_string()),
+            ]),
+        })
+    }
+
+    /// Queue the summary without waiting; drops stay pending until one gets through
+    fn flush(&mut self, event_sender: &mpsc::Sender<RawLogEvent>, local_addr: &str) {
+        if let Some(marker) = self.marker(local_addr) {
+            if event_sender.try_send(marker).is_ok() {
+                warn!("⚠️ Syslog UDP listener {} dropped {} events under backpressure", local_addr, self.pending);
+                self.pending = 0;
+            }
+        }
+    }
+}
+
+/// Wait for the next backpressure transition; never resolves without a signal
+async fn backpressure_changed(receiver: &mut Option<watch::Receiver<bool>>) -> bool {
+    if let Some(rx) = receiver {
+        if rx.changed().await.is_ok() {
+            return *rx.borrow_and_update();
+        }
+        *receiver = None;
+    }
+    std::future::pending().await
+}
+
+#[cfg(unix)]
+fn socket_receive_buffer(socket: &UdpSocket) -> io::Result<usize> {
+    use std::os::fd::AsRawFd;
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(socket.as_raw_fd(), libc::SOL_SOCKET, libc::SO_RCVBUF,
+                         &mut value as *mut libc::c_int as *mut libc::c_void, &mut len)
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Linux reports twice the requested size to account for bookkeeping overhead
+    Ok(if cfg!(target_os = "linux") { value as usize / 2 } else { value as usize })
+}
+
+#[cfg(unix)]
+fn set_socket_receive_buffer(socket: &UdpSocket, bytes: usize) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    let value = bytes.min(libc::c_int::MAX as usize) as libc::c_int;
+    let result = unsafe {
+        libc::setsockopt(socket.as_raw_fd(), libc::SOL_SOCKET, libc::SO_RCVBUF,
+                         &value as *const libc::c_int as *const libc::c_void,
+                         std::mem::size_of::<libc::c_int>() as libc::socklen_t)
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn socket_receive_buffer(_socket: &UdpSocket) -> io::Result<usize> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "socket receive buffer size is not adjustable on this platform"))
+}
+
+#[cfg(not(unix))]
+fn set_socket_receive_buffer(_socket: &UdpSocket, _bytes: usize) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "socket receive buffer size is not adjustable on this platform"))
+}
+
+/// Receive-queue overflows for this socket from /proc/net/udp{,6}
+#[cfg(target_os = "linux")]
+fn kernel_drops(socket: &UdpSocket) -> Option<u64> {
+    use std::os::fd::AsRawFd;
+    let link = std::fs::read_link(format!("/proc/self/fd/{}", socket.as_raw_fd())).ok()?;
+    let inode = link.to_str()?.strip_prefix("socket:[")?.strip_suffix(']')?.to_string();
+    ["/proc/net/udp", "/proc/net/udp6"].iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .find_map(|table| table.lines().skip(1).find_map(|line| {
+            // sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode ref pointer drops
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            (columns.get(9) == Some(&inode.as_str())).then(|| columns.last()?.parse().ok()).flatten()
+        }))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn kernel_drops(_socket: &UdpSocket) -> Option<u64> {
+    None
+}
+
 pub struct SyslogCollector {
     config: SyslogCollectorConfig,
     event_sender: mpsc::Sender<RawLogEvent>,
     shutdown_sender: Option<tokio::sync::oneshot::Sender<()>>,
     running: bool,
+    backpressure_receiver: Option<watch::Receiver<bool>>,
+    dropped_events: Arc<AtomicU64>,
 }
 
 impl SyslogCollector {
@@ -28,6 +232,8 @@ impl SyslogCollector {
             event_sender,
             shutdown_sender: None,
             running: false,
+            backpressure_receiver: None,
+            dropped_events: Arc::new(AtomicU64::new(0)),
         }
     }
     
@@ -43,34 +249,76 @@ impl SyslogCollector {
         info!("🌐 Syslog UDP server listening on {}", bind_addr);
         
         let event_sender = self.event_sender.clone();
+        let max_message_size = self.config.max_message_size;
+        let backpressure_buffer = self.config.backpressure_receive_buffer_bytes;
+        let mut backpressure_receiver = self.backpressure_receiver.clone();
+        let mut backpressure = backpressure_receiver.as_ref().is_some_and(|rx| *rx.borrow());
+        let mut drops = DropAccounting::new(self.dropped_events.clone());
+        let mut marker_timer = tokio::time::interval(std::time::Duration::from_secs(self.config.drop_marker_interval_seconds));
+        let normal_buffer = socket_receive_buffer(&socket).ok();
         
-        tokio::spawn(async move {
-            let mut buffer = [0u8; 8192];
+        crate::component_usage::spawn_inherited(async move {
+            let mut buffer = vec![0u8; max_message_size];
+            drops.record_kernel_drops(kernel_drops(&socket));
             
             loop {
-                match socket.recv_from(&mut buffer).await {
-                    Ok((size, peer_addr)) => {
-                        let raw_data = String::from_utf8_lossy(&buffer[..size]).into_owned();
-                        if !raw_data.trim().is_empty() {
-                            let event = RawLogEvent {
-                                timestamp: chrono::Utc::now(),
-                                source: "syslog".to_string(),
-                                raw_data: raw_data.trim().to_string(),
-                                metadata: HashMap::from([
-                                    ("protocol".to_string(), "udp".to_string()),
-                                    ("peer_address".to_string(), peer_addr.to_string()),
-                                ]),
-                            };
-                            
-                            if let Err(e) = event_sender.send(event).await {
-                                error!("Failed to send syslog event: {}", e);
-                                break;
+                tokio::select! {
+                    received = socket.recv_from(&mut buffer) => match received {
+                        Ok((size, peer_addr)) => {
+                            let raw_data = String::from_utf8_lossy(&buffer[..size]).into_owned();
+                            if !raw_data.trim().is_empty() {
+                                let event = RawLogEvent {
+                                    timestamp: chrono::Utc::now(),
+                                    source: "syslog".to_string(),
+                                    raw_data: raw_data.trim().into(),
+                                    metadata: HashMap::from([
+                                        ("protocol".to_string(), "udp".to_string()),
+                                        ("peer_address".to_string(), peer_addr.to_string()),
+                                    ]),
+                                };
+                                
+                                // Waiting on a full pipeline only moves the loss into the kernel, where nobody sees it
+                                let sent = if backpressure {
+                                    match event_sender.try_send(event) {
+                                        Err(mpsc::error::TrySendError::Full(_)) => {
+                                            drops.record(1);
+                                            Ok(())
+                                        }
+                                        result => result.map_err(|_| ()),
+                                    }
+                                } else {
+                                    event_sender.send(event).await.map_err(|_| ())
+                                };
+                                if sent.is_err() {
+                                    error!("Failed to send syslog event: event channel closed");
+                                    break;
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            error!("UDP receive error: {}", e);
+                            break;
+                        }
+                    },
+                    active = backpressure_changed(&mut backpressure_receiver) => {
+                        backpressure = active;
+                        let size = if active { Some(backpressure_buffer) } else { normal_buffer };
+                        if let Some(size) = size.filter(|size| *size > 0) {
+                            if let Err(e) = set_socket_receive_buffer(&socket, size) {
+                                debug!("Could not set syslog UDP receive buffer to {} bytes: {}", size, e);
                             }
                         }
+                        if active {
+                            warn!("⚠️ Backpressure active; syslog UDP listener {} is shedding load", bind_addr);
+                        } else {
+                            info!("✅ Backpressure cleared; syslog UDP listener {} resumed normal delivery", bind_addr);
+                            drops.record_kernel_drops(kernel_drops(&socket));
+                            drops.flush(&event_sender, &bind_addr);
+                        }
                     }
-                    Err(e) => {
-                        error!("UDP receive error: {}", e);
-                        break;
+                    _ = marker_timer.tick() => {
+                        drops.record_kernel_drops(kernel_drops(&socket));
+                        drops.flush(&event_sender, &bind_addr);
                     }
                 }
             }
@@ -79,26 +327,32 @@ impl SyslogCollector {
         Ok(())
     }
     
-    async fn start_tcp_server(&self) -> Result<(), CollectorError> {
+    async fn bind_tcp(&self, protocol: &str) -> Result<TcpListener, CollectorError> {
         let bind_addr = format!("{}:{}", self.config.bind_address, self.config.port);
         let listener = TcpListener::bind(&bind_addr).await
             .map_err(|e| CollectorError::NetworkError {
-                protocol: "TCP".to_string(),
+                protocol: protocol.to_string(),
                 endpoint: bind_addr.to_string(),
                 source: Box::new(std::io::Error::new(std::io::ErrorKind::AddrInUse, e.to_string())),
             })?;
             
-        info!("🌐 Syslog TCP server listening on {}", bind_addr);
-        
+        info!("🌐 Syslog {} server listening on {} ({:?} framing)", protocol, bind_addr, self.config.framing);
+        Ok(listener)
+    }
+    
+    async fn start_tcp_server(&self) -> Result<(), CollectorError> {
+        let listener = self.bind_tcp("TCP").await?;
         let event_sender = self.event_sender.clone();
+        let framing = self.config.framing;
+        let max_message_size = self.config.max_message_size;
         
-        tokio::spawn(async move {
+        crate::component_usage::spawn_inherited(async move {
             loop {
                 match listener.accept().await {
                     Ok((stream, peer_addr)) => {
                         let event_sender = event_sender.clone();
-                        tokio::spawn(async move {
-                            if let Err(e) = Self::handle_tcp_connection(stream, peer_addr, event_sender).await {
+                        crate::component_usage::spawn_inherited(async move {
+                            if let Err(e) = Self::handle_stream(stream, peer_addr, "tcp", framing, max_message_size, event_sender).await {
                                 warn!("TCP connection error from {}: {}", peer_addr, e);
                             }
                         });
@@ -114,49 +368,103 @@ impl SyslogCollector {
         Ok(())
     }
     
-    async fn handle_tcp_connection(
-        stream: TcpStream,
+    /// RFC 5425 listener; TLS syslog is always octet-counted, but senders using newlines are tolerated in auto mode
+    #[cfg(feature = "native-tls-backend")]
+    async fn start_tls_server(&self) -> Result<(), CollectorError> {
+        let tls = self.config.tls.as_ref().ok_or_else(|| CollectorError::InvalidConfig(
+            "Syslog protocol 'tls' requires [collectors.syslog.tls]".to_string()
+        ))?;
+        let read_pem = |path: &str| std::fs::read(path).map_err(|e| CollectorError::InvalidConfig(
+            format!("Failed to read syslog TLS file {}: {}", path, e)
+        ));
+        let identity = native_tls::Identity::from_pkcs8(&read_pem(&tls.cert_path)?, &read_pem(&tls.key_path)?)
+            .map_err(|e| CollectorError::InvalidConfig(format!("Invalid syslog TLS certificate or key: {}", e)))?;
+        let acceptor = native_tls::TlsAcceptor::new(identity)
+            .map_err(|e| CollectorError::InvalidConfig(format!("Failed to create syslog TLS acceptor: {}", e)))?;
+        let acceptor = std::sync::Arc::new(tokio_native_tls::TlsAcceptor::from(acceptor));
+        
+        let listener = self.bind_tcp("TLS").await?;
+        let event_sender = self.event_sender.clone();
+        let framing = self.config.framing;
+        let max_message_size = self.config.max_message_size;
+        
+        crate::component_usage::spawn_inherited(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, peer_addr)) => {
+                        let event_sender = event_sender.clone();
+                        let acceptor = acceptor.clone();
+                        crate::component_usage::spawn_inherited(async move {
+                            let stream = match acceptor.accept(stream).await {
+                                Ok(stream) => stream,
+                                Err(e) => {
+                                    warn!("TLS handshake with {} failed: {}", peer_addr, e);
+                                    return;
+                                }
+                            };
+                            if let Err(e) = Self::handle_stream(stream, peer_addr, "tls", framing, max_message_size, event_sender).await {
+                                warn!("TLS connection error from {}: {}", peer_addr, e);
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        error!("TLS accept error: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        
+        Ok(())
+    }
+    
+    #[cfg(not(feature = "native-tls-backend"))]
+    async fn start_tls_server(&self) -> Result<(), CollectorError> {
+        Err(CollectorError::InvalidConfig(
+            "Syslog over TLS requires the native-tls-backend feature".to_string()
+        ))
+    }
+    
+    async fn handle_stream<S: AsyncRead + Unpin>(
+        stream: S,
         peer_addr: SocketAddr,
+        protocol: &'static str,
+        framing: SyslogFraming,
+        max_message_size: usize,
         event_sender: mpsc::Sender<RawLogEvent>,
     ) -> Result<(), CollectorError> {
         let mut reader = BufReader::new(stream);
-        let mut line_buffer = String::new();
         
-        debug!("📡 New TCP connection from {}", peer_addr);
+        debug!("📡 New {} connection from {}", protocol, peer_addr);
         
         loop {
-            line_buffer.clear();
+            let frame = read_frame(&mut reader, framing, max_message_size).await
+                .map_err(|e| CollectorError::NetworkError {
+                    protocol: protocol.to_uppercase(),
+                    endpoint: peer_addr.to_string(),
+                    source: Box::new(e),
+                })?;
+            let Some(frame) = frame else {
+                debug!("📡 {} connection closed by {}", protocol, peer_addr);
+                break;
+            };
             
-            match reader.read_line(&mut line_buffer).await {
-                Ok(0) => {
-                    debug!("📡 TCP connection closed by {}", peer_addr);
-                    break; // Connection closed
-                }
-                Ok(_) => {
-                    let raw_data = line_buffer.trim();
-                    if !raw_data.is_empty() {
-                        let event = RawLogEvent {
-                            timestamp: chrono::Utc::now(),
-                            source: "syslog".to_string(),
-                            raw_data: raw_data.to_string(),
-                            metadata: HashMap::from([
-                                ("protocol".to_string(), "tcp".to_string()),
-                                ("peer_address".to_string(), peer_addr.to_string()),
-                            ]),
-                        };
-                        
-                        if let Err(e) = event_sender.send(event).await {
-                            error!("Failed to send TCP syslog event: {}", e);
-                            break;
-                        }
-                    }
-                }
-                Err(e) => {
-                    return Err(CollectorError::NetworkError {
-                        protocol: "TCP".to_string(),
-                        endpoint: "unknown".to_string(),
-                        source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-                    });
+            let raw_data = String::from_utf8_lossy(&frame);
+            let raw_data = raw_data.trim_end_matches(['\r', '\n']);
+            if !raw_data.trim().is_empty() {
+                let event = RawLogEvent {
+                    timestamp: chrono::Utc::now(),
+                    source: "syslog".to_string(),
+                    raw_data: raw_data.into(),
+                    metadata: HashMap::from([
+                        ("protocol".to_string(), protocol.to_string()),
+                        ("peer_address".to_string(), peer_addr.to_string()),
+                    ]),
+                };
+                
+                if let Err(e) = event_sender.send(event).await {
+                    error!("Failed to send {} syslog event: {}", protocol, e);
+                    break;
                 }
             }
         }
@@ -182,6 +490,7 @@ impl Collector for SyslogCollector {
                 self.start_udp_server().await?;
                 self.start_tcp_server().await?;
             }
+            "tls" => self.start_tls_server().await?,
             _ => {
                 return Err(CollectorError::InvalidConfig(
                     format!("Unsupported syslog protocol: {}", self.config.protocol)
@@ -217,4 +526,71 @@ impl Collector for SyslogCollector {
     fn is_running(&self) -> bool {
         self.running
     }
-}
\ No newline at end of file
+    
+    fn set_backpressure(&mut self, receiver: watch::Receiver<bool>) {
+        self.backpressure_receiver = Some(receiver);
+    }
+    
+    fn drop_counter(&self) -> Option<Arc<AtomicU64>> {
+        Some(self.dropped_events.clone())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn frames(input: &[u8], framing: SyslogFraming, max_message_size: usize) -> io::Result<Vec<String>> {
+        let mut reader = BufReader::new(input);
+        let mut frames = Vec::new();
+        while let Some(frame) = read_frame(&mut reader, framing, max_message_size).await? {
+            frames.push(String::from_utf8(frame).unwrap());
+        }
+        Ok(frames)
+    }
+
+    #[tokio::test]
+    async fn test_octet_counted_and_non_transparent_framing() {
+        let octet = b"11 <13>1 - a b22 <13>1 - - - - - line\nx";
+        assert_eq!(frames(octet, SyslogFraming::OctetCounting, 1024).await.unwrap(),
+                   vec!["<13>1 - a b", "<13>1 - - - - - line\nx"]);
+        assert_eq!(frames(octet, SyslogFraming::Auto, 1024).await.unwrap().len(), 2);
+
+        let lines = b"<13>first\n\n<14>second\0<15>third";
+        assert_eq!(frames(lines, SyslogFraming::Auto, 1024).await.unwrap(),
+                   vec!["<13>first", "<14>second", "<15>third"]);
+        assert_eq!(frames(b"<13>truncated\n<14>next\n", SyslogFraming::NonTransparent, 6).await.unwrap(),
+                   vec!["<13>tr", "<14>ne"]);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_octet_counts_are_rejected() {
+        assert!(frames(b"<13>no count\n", SyslogFraming::OctetCounting, 1024).await.is_err());
+        assert!(frames(b"12x<13>bad", SyslogFraming::Auto, 1024).await.is_err());
+        assert!(frames(b"99999 <13>too long", SyslogFraming::Auto, 1024).await.is_err());
+        // A stream that ends mid-frame is an error rather than a short message
+        assert!(frames(b"50 <13>short", SyslogFraming::Auto, 1024).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_drops_are_summarized_until_a_marker_is_queued() {
+        let total = Arc::new(AtomicU64::new(0));
+        let mut drops = DropAccounting::new(total.clone());
+        drops.record_kernel_drops(Some(40));
+        drops.record(3);
+        drops.record_kernel_drops(Some(42));
+        assert_eq!(total.load(Ordering::Relaxed), 5);
+
+        // A full pipeline keeps the count pending
+        let (sender, mut receiver) = mpsc::channel(1);
+        sender.try_send(drops.marker("0.0.0.0:514").unwrap()).unwrap();
+        drops.flush(&sender, "0.0.0.0:514");
+        assert_eq!(drops.pending, 5);
+
+        receiver.recv().await.unwrap();
+        drops.flush(&sender, "0.0.0.0:514");
+        let marker = receiver.recv().await.unwrap();
+        assert_eq!(marker.metadata["dropped_events"], "5");
+        assert!(marker.raw_data.starts_with("5 syslog events dropped"));
+        assert!(drops.marker("0.0.0.0:514").is_none());
+    }
+}
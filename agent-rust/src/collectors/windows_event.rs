@@ -32,6 +32,7 @@ use serde::{Deserialize, Serialize};
 use windows::{
     core::*,
     Win32::Foundation::*,
+    Win32::Globalization::LocaleNameToLCID,
     Win32::System::EventLog::*,
 };
 
@@ -60,6 +61,10 @@ pub struct EventBookmark {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WindowsEventData {
     pub event_id: u32,
+    /// EventID Qualifiers attribute (classic event sources)
+    pub qualifiers: Option<u32>,
+    /// Event definition version; together with the provider it selects the message template
+    pub version: u32,
     pub event_record_id: u64,
     pub level: u32,
     pub level_name: String,
@@ -90,6 +95,10 @@ pub struct WindowsEventCollector {
     shutdown_sender: Option<tokio::sync::oneshot::Sender<()>>,
     bookmark_persistence_path: String,
     mock_mode: bool, // For testing on non-Windows platforms
+    /// Publisher metadata handles by provider; None records a provider without a message catalog
+    publishers: HashMap<String, Option<isize>>,
+    /// LCID messages are rendered in (0 = system locale)
+    locale_id: u32,
 }
 
 #[cfg(windows)]
@@ -100,6 +109,17 @@ impl WindowsEventCollector {
     ) -> Self {
         let bookmark_path = format!("./{}_bookmarks.json", "windows_event_collector");
         
+        let locale_id = match &config.message_locale {
+            Some(locale) => {
+                let lcid = unsafe { LocaleNameToLCID(&HSTRING::from(locale.as_str()), 0) };
+                if lcid == 0 {
+                    warn!("⚠️  Unknown message locale '{}', rendering in the system locale", locale);
+                }
+                lcid
+            }
+            None => 0,
+        };
+        
         Self {
             config,
             event_sender,
@@ -110,6 +130,8 @@ impl WindowsEventCollector {
             shutdown_sender: None,
             bookmark_persistence_path: bookmark_path,
             mock_mode: false,
+            publishers: HashMap::new(),
+            locale_id,
         }
     }
     
@@ -316,20 +338,39 @@ impl WindowsEventCollector {
                 match self.render_event_as_xml(event_handle).await {
                     Ok(xml_data) => {
                         match self.parse_windows_event_xml(&xml_data, channel).await {
-                            Ok(parsed_event) => {
+                            Ok(mut parsed_event) => {
+                                let (message, message_source) = self.render_message(event_handle, &parsed_event);
+                                parsed_event.message = Some(message.clone());
+                                
+                                let mut metadata = HashMap::from([
+                                    ("channel".to_string(), channel.to_string()),
+                                    ("event_id".to_string(), parsed_event.event_id.to_string()),
+                                    ("level".to_string(), parsed_event.level_name.clone()),
+                                    ("provider".to_string(), parsed_event.provider_name.clone()),
+                                    ("computer".to_string(), parsed_event.computer.clone()),
+                                    ("record_id".to_string(), parsed_event.event_record_id.to_string()),
+                                    ("format".to_string(), "xml".to_string()),
+                                    // What the backend needs to re-render the message against its own catalogs
+                                    ("message".to_string(), message),
+                                    ("message_source".to_string(), message_source.to_string()),
+                                    ("message_locale".to_string(), self.config.message_locale.clone().unwrap_or_else(|| "system".to_string())),
+                                    ("event_version".to_string(), parsed_event.version.to_string()),
+                                    ("task".to_string(), parsed_event.task.to_string()),
+                                    ("opcode".to_string(), parsed_event.opcode.to_string()),
+                                    ("keywords".to_string(), format!("0x{:x}", parsed_event.keywords)),
+                                ]);
+                                if let Some(guid) = &parsed_event.provider_guid {
+                                    metadata.insert("provider_guid".to_string(), guid.clone());
+                                }
+                                if let Some(qualifiers) = parsed_event.qualifiers {
+                                    metadata.insert("qualifiers".to_string(), qualifiers.to_string());
+                                }
+                                
                                 let raw_event = RawLogEvent {
                                     timestamp: parsed_event.time_created,
                                     source: "windows_event".to_string(),
-                                    raw_data: xml_data,
-                                    metadata: HashMap::from([
-                                        ("channel".to_string(), channel.to_string()),
-                                        ("event_id".to_string(), parsed_event.event_id.to_string()),
-                                        ("level".to_string(), parsed_event.level_name.clone()),
-                                        ("provider".to_string(), parsed_event.provider_name.clone()),
-                                        ("computer".to_string(), parsed_event.computer.clone()),
-                                        ("record_id".to_string(), parsed_event.event_record_id.to_string()),
-                                        ("format".to_string(), "xml".to_string()),
-                                    ]),
+                                    raw_data: xml_data.into(),
+                                    metadata,
                                 };
                                 
                                 events.push(raw_event);
@@ -395,6 +436,80 @@ impl WindowsEventCollector {
         }
     }
     
+    /// Message for an event and where it came from: "catalog" when the provider's message catalog rendered it,
+    /// "event_data" when it was built from the raw EventData (rendering disabled or catalog missing)
+    fn render_message(&mut self, event_handle: isize, event: &WindowsEventData) -> (String, &'static str) {
+        if self.config.render_messages {
+            if let Some(message) = self.format_from_catalog(event_handle, &event.provider_name) {
+                return (message, "catalog");
+            }
+        }
+        (event_data_message(event), "event_data")
+    }
+    
+    /// Render the event message with the provider's message catalog in the configured locale
+    fn format_from_catalog(&mut self, event_handle: isize, provider: &str) -> Option<String> {
+        let publisher = match self.publishers.get(provider) {
+            Some(publisher) => (*publisher)?,
+            None => {
+                let opened = unsafe {
+                    EvtOpenPublisherMetadata(None, &HSTRING::from(provider), PCWSTR::null(), self.locale_id, 0)
+                };
+                let publisher = match opened {
+                    Ok(handle) => Some(handle.0),
+                    Err(e) => {
+                        debug!("📖 No message catalog for provider '{}', using EventData: {}", provider, e);
+                        None
+                    }
+                };
+                self.publishers.insert(provider.to_string(), publisher);
+                publisher?
+            }
+        };
+        
+        unsafe {
+            let mut buffer_used = 0u32;
+            // First call sizes the buffer
+            let _ = EvtFormatMessage(
+                Some(EVT_HANDLE(publisher)),
+                Some(EVT_HANDLE(event_handle)),
+                0,
+                None,
+                EvtFormatMessageEvent.0,
+                None,
+                &mut buffer_used,
+            );
+            if buffer_used == 0 {
+                return None;
+            }
+            
+            let mut buffer: Vec<u16> = vec![0; buffer_used as usize];
+            EvtFormatMessage(
+                Some(EVT_HANDLE(publisher)),
+                Some(EVT_HANDLE(event_handle)),
+                0,
+                None,
+                EvtFormatMessageEvent.0,
+                Some(&mut buffer),
+                &mut buffer_used,
+            ).ok()?;
+            
+            let message = String::from_utf16_lossy(&buffer[..(buffer_used as usize).saturating_sub(1)]);
+            Some(message.trim_end().to_string()).filter(|m| !m.is_empty())
+        }
+    }
+    
+    /// Close cached publisher metadata handles
+    fn close_publishers(&mut self) {
+        for (provider, publisher) in self.publishers.drain() {
+            if let Some(handle) = publisher {
+                if let Err(e) = unsafe { EvtClose(handle) } {
+                    debug!("⚠️  Failed to close publisher metadata for '{}': {}", provider, e);
+                }
+            }
+        }
+    }
+    
     /// Parse Windows Event XML into structured data
     async fn parse_windows_event_xml(&self, xml_data: &str, channel: &str) -> Result<WindowsEventData, CollectorError> {
         let mut reader = Reader::from_str(xml_data);
@@ -402,6 +517,8 @@ impl WindowsEventCollector {
         
         let mut event_data = WindowsEventData {
             event_id: 0,
+            qualifiers: None,
+            version: 0,
             event_record_id: 0,
             level: 0,
             level_name: "Unknown".to_string(),
@@ -423,6 +540,7 @@ impl WindowsEventCollector {
         let mut buf = Vec::new();
         let mut current_path = Vec::new();
         let mut current_text = String::new();
+        let mut current_data_name: Option<String> = None;
         
         loop {
             match reader.read_event_into(&mut buf) {
@@ -455,6 +573,12 @@ impl WindowsEventCollector {
                                     event_data.time_created = parsed_time.with_timezone(&chrono::Utc);
                                 }
                             }
+                            "Event/System/EventID" if attr_name == "Qualifiers" => {
+                                event_data.qualifiers = attr_value.parse().ok();
+                            }
+                            "Event/EventData/Data" if attr_name == "Name" => {
+                                current_data_name = Some(attr_value.to_string());
+                            }
                             _ => {}
                         }
                     }
@@ -470,6 +594,9 @@ impl WindowsEventCollector {
                         "Event/System/EventID" => {
                             event_data.event_id = current_text.parse().unwrap_or(0);
                         }
+                        "Event/System/Version" => {
+                            event_data.version = current_text.parse().unwrap_or(0);
+                        }
                         "Event/System/EventRecordID" => {
                             event_data.event_record_id = current_text.parse().unwrap_or(0);
                         }
@@ -500,8 +627,9 @@ impl WindowsEventCollector {
                         _ => {
                             // Extract event data fields
                             if path.starts_with("Event/EventData/Data") {
-                                // Use path as key for now, could be improved with Name attribute parsing
-                                let key = path.split('/').last().unwrap_or("unknown").to_string();
+                                // Named data items keep their name; unnamed ones (classic sources) are numbered
+                                let key = current_data_name.take()
+                                    .unwrap_or_else(|| format!("Data{}", event_data.event_data.len() + 1));
                                 event_data.event_data.insert(key, current_text.clone());
                             }
                         }
@@ -624,7 +752,7 @@ impl WindowsEventCollector {
                     </Event>"#,
                     chrono::Utc::now().to_rfc3339(),
                     channel
-                ),
+                ).into(),
                 metadata: HashMap::from([
                     ("channel".to_string(), channel.to_string()),
                     ("event_id".to_string(), "4624".to_string()),
@@ -755,7 +883,7 @@ impl WindowsEventCollector {
         
         let mut collector = self.clone(); // We'll need to make this cloneable
         
-        tokio::spawn(async move {
+        crate::component_usage::spawn_inherited(async move {
             let mut collection_interval = interval(Duration::from_secs(1));
             
             loop {
@@ -791,12 +919,28 @@ impl WindowsEventCollector {
                     }
                 }
             }
+            
+            collector.close_publishers();
         });
         
         debug!("🚀 Windows Event collection task started for {} channels", channels.len());
     }
 }
 
+/// Message built from the raw EventData, used when no message catalog is available
+#[cfg(windows)]
+fn event_data_message(event: &WindowsEventData) -> String {
+    let mut items: Vec<_> = event.event_data.iter().collect();
+    items.sort();
+    let data = items.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join(", ");
+    
+    match (data.is_empty(), &event.user_data) {
+        (false, _) => format!("{} event {}: {}", event.provider_name, event.event_id, data),
+        (true, Some(user_data)) => format!("{} event {}: {}", event.provider_name, event.event_id, user_data),
+        (true, None) => format!("{} event {}", event.provider_name, event.event_id),
+    }
+}
+
 // Implement Clone for WindowsEventCollector to enable task spawning
 #[cfg(windows)]
 impl Clone for WindowsEventCollector {
@@ -811,6 +955,8 @@ impl Clone for WindowsEventCollector {
             shutdown_sender: None, // Don't clone shutdown sender
             bookmark_persistence_path: self.bookmark_persistence_path.clone(),
             mock_mode: self.mock_mode,
+            publishers: HashMap::new(), // Handles are owned by the instance that opened them
+            locale_id: self.locale_id,
         }
     }
 }
@@ -890,6 +1036,7 @@ impl Collector for WindowsEventCollector {
             }
         }
         self.query_handles.clear();
+        self.close_publishers();
         
         self.running = false;
         info!("✅ Windows Event collector stopped successfully");
@@ -0,0 +1,328 @@
+// Per-component CPU and allocation attribution for the agent pipeline
+// Tasks and blocking closures run inside a named component scope; thread CPU time spent polling
+// them and heap allocations made while the scope is active are charged to that component
+
+use serde::Serialize;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::task::JoinHandle;
+
+/// Parsing engine work
+pub const PARSER: &str = "parser";
+/// Buffer database writes and maintenance
+pub const BUFFER_WRITER: &str = "buffer_writer";
+/// Outbound transport requests and websocket tasks
+pub const TRANSPORT: &str = "transport";
+
+/// Collector components are named `collector:<name>`
+pub fn collector(name: &str) -> String {
+    format!("collector:{}", name)
+}
+
+/// Fixed slot table so the allocator hook never locks or allocates
+const MAX_COMPONENTS: usize = 64;
+
+#[derive(Default)]
+struct Slot {
+    cpu_nanos: AtomicU64,
+    polls: AtomicU64,
+    allocations: AtomicU64,
+    allocated_bytes: AtomicU64,
+}
+
+struct Registry {
+    slots: [Slot; MAX_COMPONENTS],
+    names: parking_lot::RwLock<Vec<String>>,
+}
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(|| Registry {
+        slots: std::array::from_fn(|_| Slot::default()),
+        names: parking_lot::RwLock::new(Vec::new()),
+    })
+}
+
+thread_local! {
+    /// Active component slot + 1 (0 when no component is active)
+    static CURRENT: Cell<usize> = const { Cell::new(0) };
+    /// CPU time already charged to nested scopes inside the active one
+    static NESTED_NANOS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Handle to a registered component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentId(usize);
+
+impl ComponentId {
+    /// Register (or look up) a component; past the slot limit everything shares the last slot
+    pub fn named(name: &str) -> Self {
+        let registry = registry();
+        if let Some(index) = registry.names.read().iter().position(|n| n == name) {
+            return Self(index);
+        }
+        let mut names = registry.names.write();
+        if let Some(index) = names.iter().position(|n| n == name) {
+            return Self(index);
+        }
+        if names.len() == MAX_COMPONENTS - 1 {
+            names.push("other".to_string());
+        }
+        if names.len() >= MAX_COMPONENTS {
+            return Self(MAX_COMPONENTS - 1);
+        }
+        names.push(name.to_string());
+        Self(names.len() - 1)
+    }
+
+    /// Component whose scope is active on this thread
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|c| c.get().checked_sub(1).map(Self)).ok().flatten()
+    }
+
+    /// Run `f` with this component active, charging its CPU time and allocations here
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        let previous = CURRENT.with(|c| c.replace(self.0 + 1));
+        let outer_nested = NESTED_NANOS.with(|n| n.replace(0));
+        let start = thread_cpu_nanos();
+
+        let result = f();
+
+        let elapsed = thread_cpu_nanos().saturating_sub(start);
+        let nested = NESTED_NANOS.with(|n| n.replace(outer_nested + elapsed));
+        CURRENT.with(|c| c.set(previous));
+
+        let slot = &registry().slots[self.0];
+        slot.cpu_nanos.fetch_add(elapsed.saturating_sub(nested), Ordering::Relaxed);
+        slot.polls.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+}
+
+/// Future wrapper that enters its component scope on every poll
+pub struct Instrumented<F> {
+    component: ComponentId,
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let component = self.component;
+        component.scope(|| self.inner.as_mut().poll(cx))
+    }
+}
+
+pub fn instrument<F: Future>(component: &str, future: F) -> Instrumented<F> {
+    Instrumented {
+        component: ComponentId::named(component),
+        inner: Box::pin(future),
+    }
+}
+
+/// Spawn a task charged to `component`
+pub fn spawn<F>(component: &str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(instrument(component, future))
+}
+
+/// Spawn a task charged to whichever component is spawning it (unattributed otherwise)
+pub fn spawn_inherited<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match ComponentId::current() {
+        Some(component) => tokio::spawn(Instrumented { component, inner: Box::pin(future) }),
+        None => tokio::spawn(future),
+    }
+}
+
+/// Run blocking work on the blocking pool, charged to `component`
+pub fn spawn_blocking<F, R>(component: &str, f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let component = ComponentId::named(component);
+    tokio::task::spawn_blocking(move || component.scope(f))
+}
+
+/// Cumulative usage of one component
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentUsage {
+    pub component: String,
+    pub cpu_seconds: f64,
+    /// Share of one core used since the previous sample (0 until two samples exist)
+    pub cpu_percent: f64,
+    pub polls: u64,
+    pub allocations: u64,
+    pub allocated_bytes: u64,
+    /// Allocation rate since the previous sample
+    pub allocated_bytes_per_second: f64,
+}
+
+/// Cumulative counters for every registered component
+pub fn snapshot() -> Vec<ComponentUsage> {
+    let registry = registry();
+    let names = registry.names.read();
+    names
+        .iter()
+        .zip(registry.slots.iter())
+        .map(|(name, slot)| ComponentUsage {
+            component: name.clone(),
+            cpu_seconds: slot.cpu_nanos.load(Ordering::Relaxed) as f64 / 1e9,
+            cpu_percent: 0.0,
+            polls: slot.polls.load(Ordering::Relaxed),
+            allocations: slot.allocations.load(Ordering::Relaxed),
+            allocated_bytes: slot.allocated_bytes.load(Ordering::Relaxed),
+            allocated_bytes_per_second: 0.0,
+        })
+        .collect()
+}
+
+/// Turns cumulative snapshots into per-interval rates
+#[derive(Debug, Default)]
+pub struct UsageSampler {
+    previous: Option<(Instant, Vec<ComponentUsage>)>,
+}
+
+impl UsageSampler {
+    pub fn sample(&mut self) -> Vec<ComponentUsage> {
+        let now = Instant::now();
+        let mut usage = snapshot();
+
+        if let Some((at, previous)) = &self.previous {
+            let elapsed = now.duration_since(*at).as_secs_f64();
+            if elapsed > 0.0 {
+                for current in &mut usage {
+                    let (cpu, bytes) = previous
+                        .iter()
+                        .find(|p| p.component == current.component)
+                        .map(|p| (p.cpu_seconds, p.allocated_bytes))
+                        .unwrap_or((0.0, 0));
+                    current.cpu_percent = (current.cpu_seconds - cpu) / elapsed * 100.0;
+                    current.allocated_bytes_per_second = current.allocated_bytes.saturating_sub(bytes) as f64 / elapsed;
+                }
+            }
+        }
+
+        self.previous = Some((now, usage.clone()));
+        usage.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent).then(b.cpu_seconds.total_cmp(&a.cpu_seconds)));
+        usage
+    }
+}
+
+/// System allocator that charges allocations to the active component; installed by the binary
+pub struct TrackingAllocator;
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_allocation(layout.size());
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_allocation(new_size.saturating_sub(layout.size()));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[inline]
+fn record_allocation(bytes: usize) {
+    // `try_with` because the allocator also runs while thread-locals are being torn down
+    let Ok(Some(index)) = CURRENT.try_with(|c| c.get().checked_sub(1)) else {
+        return;
+    };
+    // The registry exists whenever a component is active; never initialize it from here
+    if let Some(registry) = REGISTRY.get() {
+        let slot = &registry.slots[index];
+        slot.allocations.fetch_add(1, Ordering::Relaxed);
+        slot.allocated_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(unix)]
+fn thread_cpu_nanos() -> u64 {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `ts` is a valid timespec for the duration of the call
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) } == 0 {
+        ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+    } else {
+        monotonic_nanos()
+    }
+}
+
+/// Without a per-thread CPU clock, time spent inside the scope is used instead
+#[cfg(not(unix))]
+fn thread_cpu_nanos() -> u64 {
+    monotonic_nanos()
+}
+
+fn monotonic_nanos() -> u64 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(component: &str) -> ComponentUsage {
+        snapshot().into_iter().find(|u| u.component == component).unwrap()
+    }
+
+    #[test]
+    fn test_nested_scopes_charge_self_time() {
+        let outer = ComponentId::named("test:outer");
+        let inner = ComponentId::named("test:inner");
+        let spin = || {
+            let start = Instant::now();
+            while start.elapsed().as_millis() < 20 {
+                std::hint::black_box(0u64);
+            }
+        };
+
+        outer.scope(|| {
+            spin();
+            inner.scope(spin);
+            assert_eq!(ComponentId::current(), Some(outer));
+        });
+        assert_eq!(ComponentId::current(), None);
+
+        let outer_cpu = usage("test:outer").cpu_seconds;
+        let inner_cpu = usage("test:inner").cpu_seconds;
+        assert!(inner_cpu >= 0.005, "inner charged {}", inner_cpu);
+        assert!(outer_cpu < 0.035, "outer charged {}", outer_cpu);
+    }
+
+    #[tokio::test]
+    async fn test_spawned_tasks_inherit_component() {
+        let handle = spawn("test:spawner", async {
+            spawn_inherited(async { ComponentId::current() }).await.unwrap()
+        });
+        assert_eq!(handle.await.unwrap(), Some(ComponentId::named("test:spawner")));
+        assert!(usage("test:spawner").polls >= 1);
+        assert_eq!(ComponentId::named("test:spawner"), ComponentId::named("test:spawner"));
+    }
+}
@@ -0,0 +1,207 @@
+// Transport payload compression: algorithm selection, levels and Content-Encoding negotiation
+// The configured algorithm is used until the server answers 415 with an Accept-Encoding list (RFC 7694);
+// the transport then switches to the best encoding the server accepts
+
+use crate::errors::TransportError;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::io::{Read, Write};
+
+/// Content coding applied to transport payloads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionAlgorithm {
+    None,
+    Gzip,
+    /// Cheapest per byte at our event volumes; accepted by SecureWatch ingest nodes
+    #[default]
+    Zstd,
+    Brotli,
+}
+
+/// Preference order when the server lists several acceptable encodings
+const NEGOTIATION_ORDER: [CompressionAlgorithm; 3] =
+    [CompressionAlgorithm::Zstd, CompressionAlgorithm::Brotli, CompressionAlgorithm::Gzip];
+
+impl CompressionAlgorithm {
+    pub fn is_enabled(self) -> bool {
+        self != Self::None
+    }
+
+    /// Content-Encoding header value; None sends no header
+    pub fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gzip"),
+            Self::Zstd => Some("zstd"),
+            Self::Brotli => Some("br"),
+        }
+    }
+
+    pub fn default_level(self) -> i32 {
+        match self {
+            Self::None => 0,
+            Self::Gzip => 6,
+            Self::Zstd => 3,
+            Self::Brotli => 5,
+        }
+    }
+
+    /// Accepted `compression_level` values
+    pub fn level_range(self) -> std::ops::RangeInclusive<i32> {
+        match self {
+            Self::None => 0..=0,
+            Self::Gzip => 0..=9,
+            Self::Zstd => 1..=22,
+            Self::Brotli => 0..=11,
+        }
+    }
+
+    /// Algorithm named by a received Content-Encoding header
+    pub fn from_content_encoding(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "identity" => Some(Self::None),
+            "gzip" | "x-gzip" => Some(Self::Gzip),
+            "zstd" => Some(Self::Zstd),
+            "br" => Some(Self::Brotli),
+            _ => None,
+        }
+    }
+
+    /// Pick an encoding from a server's Accept-Encoding value, keeping `preferred` when it is acceptable
+    pub fn negotiate(preferred: Self, accept_encoding: &str) -> Self {
+        let accepted: Vec<Self> = accept_encoding
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let coding = parts.next()?.trim().to_ascii_lowercase();
+                // "gzip;q=0" explicitly refuses the coding
+                let refused = parts.any(|p| p.trim().strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0));
+                if refused {
+                    return None;
+                }
+                match coding.as_str() {
+                    "gzip" | "x-gzip" => Some(Self::Gzip),
+                    "zstd" => Some(Self::Zstd),
+                    "br" => Some(Self::Brotli),
+                    _ => None,
+                }
+            })
+            .collect();
+        if accepted.contains(&preferred) {
+            return preferred;
+        }
+        NEGOTIATION_ORDER.into_iter().find(|a| accepted.contains(a)).unwrap_or(Self::None)
+    }
+
+    pub fn compress(self, data: &[u8], level: i32) -> Result<Vec<u8>, TransportError> {
+        let failed = |e: std::io::Error| TransportError::compression_error(&format!("{:?} compression failed: {}", self, e));
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(level.clamp(0, 9) as u32));
+                encoder.write_all(data).map_err(failed)?;
+                encoder.finish().map_err(failed)
+            }
+            Self::Zstd => zstd::stream::encode_all(data, level).map_err(failed),
+            Self::Brotli => {
+                let mut output = Vec::new();
+                {
+                    let mut encoder = brotli::CompressorWriter::new(&mut output, 4096, level.clamp(0, 11) as u32, 22);
+                    encoder.write_all(data).map_err(failed)?;
+                    encoder.flush().map_err(failed)?;
+                }
+                Ok(output)
+            }
+        }
+    }
+
+    /// Decode a received payload, refusing output larger than `max_bytes`
+    pub fn decompress(self, data: &[u8], max_bytes: usize) -> Result<Vec<u8>, TransportError> {
+        let failed = |e: std::io::Error| TransportError::compression_error(&format!("{:?} decompression failed: {}", self, e));
+        let reader: Box<dyn Read + '_> = match self {
+            Self::None => Box::new(data),
+            Self::Gzip => Box::new(flate2::read::GzDecoder::new(data)),
+            Self::Zstd => Box::new(zstd::stream::read::Decoder::new(data).map_err(failed)?),
+            Self::Brotli => Box::new(brotli::Decompressor::new(data, 4096)),
+        };
+        let mut decoded = Vec::new();
+        reader.take(max_bytes as u64 + 1).read_to_end(&mut decoded).map_err(failed)?;
+        if decoded.len() > max_bytes {
+            return Err(TransportError::compression_error(&format!("decoded payload exceeds {} bytes", max_bytes)));
+        }
+        Ok(decoded)
+    }
+}
+
+/// Accepts the algorithm name, or the legacy `compression = true/false` (true meaning zstd)
+impl<'de> Deserialize<'de> for CompressionAlgorithm {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Setting {
+            Enabled(bool),
+            Name(String),
+        }
+        match Setting::deserialize(deserializer)? {
+            Setting::Enabled(true) => Ok(Self::Zstd),
+            Setting::Enabled(false) => Ok(Self::None),
+            Setting::Name(name) => match name.to_ascii_lowercase().as_str() {
+                "none" => Ok(Self::None),
+                "gzip" => Ok(Self::Gzip),
+                "zstd" => Ok(Self::Zstd),
+                "brotli" | "br" => Ok(Self::Brotli),
+                other => Err(serde::de::Error::custom(format!(
+                    "unknown compression '{}', expected none, gzip, zstd or brotli", other
+                ))),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_algorithms_round_trip() {
+        let payload = serde_json::to_vec(&serde_json::json!({ "events": vec!["event"; 200] })).unwrap();
+        for algorithm in [CompressionAlgorithm::None, CompressionAlgorithm::Gzip, CompressionAlgorithm::Zstd, CompressionAlgorithm::Brotli] {
+            let compressed = algorithm.compress(&payload, algorithm.default_level()).unwrap();
+            assert_eq!(algorithm.decompress(&compressed, payload.len()).unwrap(), payload);
+            assert!(algorithm.decompress(&compressed, payload.len() - 1).is_err());
+
+            let mut decoded = Vec::new();
+            match algorithm {
+                CompressionAlgorithm::None => decoded = compressed,
+                CompressionAlgorithm::Gzip => { flate2::read::GzDecoder::new(&compressed[..]).read_to_end(&mut decoded).unwrap(); }
+                CompressionAlgorithm::Zstd => decoded = zstd::stream::decode_all(&compressed[..]).unwrap(),
+                CompressionAlgorithm::Brotli => { brotli::Decompressor::new(&compressed[..], 4096).read_to_end(&mut decoded).unwrap(); }
+            }
+            assert_eq!(decoded, payload, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_negotiation_and_legacy_settings() {
+        use CompressionAlgorithm::*;
+        assert_eq!(CompressionAlgorithm::negotiate(Gzip, "gzip, zstd"), Gzip);
+        assert_eq!(CompressionAlgorithm::negotiate(Brotli, "gzip, zstd;q=0.5"), Zstd);
+        assert_eq!(CompressionAlgorithm::negotiate(Zstd, "zstd;q=0, gzip"), Gzip);
+        assert_eq!(CompressionAlgorithm::negotiate(Zstd, "identity"), None);
+
+        // Bodies without a Content-Encoding are taken as they are, whatever their first bytes look like
+        assert_eq!(CompressionAlgorithm::from_content_encoding("identity"), Some(None));
+        assert_eq!(CompressionAlgorithm::from_content_encoding("BR"), Some(Brotli));
+        assert!(CompressionAlgorithm::from_content_encoding("lz4").is_none());
+
+        #[derive(Deserialize)]
+        struct Settings {
+            compression: CompressionAlgorithm,
+        }
+        let parse = |toml: &str| toml::from_str::<Settings>(toml).map(|s| s.compression);
+        assert_eq!(parse("compression = true").unwrap(), Zstd);
+        assert_eq!(parse("compression = false").unwrap(), None);
+        assert_eq!(parse("compression = \"brotli\"").unwrap(), Brotli);
+        assert!(parse("compression = \"lz4\"").is_err());
+    }
+}
@@ -22,6 +22,42 @@ pub struct AgentConfig {
     pub throttle: crate::throttle::ThrottleConfig,
     pub emergency_shutdown: crate::emergency_shutdown::EmergencyShutdownConfig,
     pub security: crate::security::SecurityConfig,
+    #[serde(default)]
+    pub process_lineage: crate::process_lineage::ProcessLineageConfig,
+    #[serde(default)]
+    pub field_filter: crate::field_filter::FieldFilterConfig,
+    #[serde(default)]
+    pub dedup: crate::dedup::DedupConfig,
+    #[serde(default)]
+    pub relay: crate::relay::RelayConfig,
+    #[serde(default)]
+    pub aggregator: crate::aggregator::AggregatorConfig,
+    #[serde(default)]
+    pub integrity: crate::integrity::IntegrityConfig,
+    #[serde(default)]
+    pub event_index: crate::event_index::EventIndexConfig,
+    #[serde(default)]
+    pub shutdown_drain: crate::shutdown_drain::ShutdownDrainConfig,
+    #[serde(default)]
+    pub alert_rules: crate::alert_rules::AlertRulesConfig,
+    #[serde(default)]
+    pub ecs: crate::ecs::EcsConfig,
+    #[serde(default)]
+    pub enrichment: crate::enrichment::EnrichmentConfig,
+    #[serde(default)]
+    pub sigma: crate::sigma::SigmaConfig,
+    #[serde(default)]
+    pub self_telemetry: crate::self_telemetry::SelfTelemetryConfig,
+    #[serde(default)]
+    pub load_shedding: crate::load_shedding::LoadSheddingConfig,
+    #[serde(default)]
+    pub error_events: crate::error_events::ErrorEventsConfig,
+    #[serde(default)]
+    pub admin_audit: crate::admin_audit::AdminAuditConfig,
+    #[serde(default)]
+    pub live_tail: crate::live_tail::LiveTailConfig,
+    #[serde(default)]
+    pub control_socket: crate::control_socket::ControlSocketConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,22 +72,44 @@ pub struct AgentSettings {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransportConfig {
     pub server_url: String,
+    /// The key itself, or keyring:<service>/<account> to read it from the OS keyring
     pub api_key: String,
     pub tls_verify: bool,
-    pub compression: bool,
+    /// Payload encoding: none, gzip, zstd or brotli (`true`/`false` are read as zstd/none)
+    pub compression: crate::compression::CompressionAlgorithm,
     pub compression_threshold: Option<usize>,
+    /// Algorithm-specific level; defaults to 6 for gzip, 3 for zstd and 5 for brotli
     pub compression_level: Option<i32>,
     pub batch_size: usize,
     pub batch_timeout: u64,
     pub retry_attempts: usize,
     pub retry_delay: u64,
+    // Jitter, maximum delay and retry budget for batch retries
+    #[serde(default)]
+    pub retry_policy: crate::retry::RetryPolicyConfig,
+    // Upload cap shared by every endpoint, so backlog drains can't saturate thin links
+    #[serde(default)]
+    pub bandwidth: crate::bandwidth::BandwidthConfig,
+    // Store-and-forward: upload only inside time windows or on allowed networks, buffering otherwise
+    #[serde(default)]
+    pub schedule: crate::upload_schedule::UploadScheduleConfig,
+    // Outbound HTTP(S)/SOCKS proxy for every endpoint and enrollment
+    #[serde(default)]
+    pub proxy: crate::proxy::ProxyConfig,
     
     // mTLS client certificate configuration
     pub client_cert_path: Option<String>,
     pub client_key_path: Option<String>,
+    /// The password itself, or keyring:<service>/<account> to read it from the OS keyring
     pub client_key_password: Option<String>,
     pub ca_cert_path: Option<String>,
     pub cert_expiry_warning_days: u32,
+    // Certificate reload on file change and SPIFFE-issued identities
+    #[serde(default)]
+    pub identity: crate::client_identity::ClientIdentityConfig,
+    // Token-based enrollment that issues the API key and client certificate on first start
+    #[serde(default)]
+    pub enrollment: crate::enrollment::EnrollmentConfig,
     
     // Circuit breaker configuration for external service resilience
     pub circuit_breaker_failure_threshold: Option<u32>,
@@ -71,6 +129,69 @@ pub struct TransportConfig {
     pub http2_keep_alive_interval: Option<std::time::Duration>,
     pub http2_keep_alive_timeout: Option<std::time::Duration>,
     pub http2_keep_alive_while_idle: Option<bool>,
+
+    // Wire protocol for this destination: the native SecureWatch endpoint, an OpenTelemetry collector or a third-party SIEM
+    #[serde(default)]
+    pub protocol: TransportProtocol,
+    #[serde(default)]
+    pub otlp: crate::otlp::OtlpConfig,
+    /// Message settings for syslog destinations, which can override them individually
+    #[serde(default)]
+    pub syslog: crate::syslog_output::SyslogOutputConfig,
+    /// HEC metadata templates for the `splunk_hec` protocol
+    #[serde(default)]
+    pub splunk_hec: crate::splunk_hec::SplunkHecConfig,
+    /// Index template and bulk settings for the `elasticsearch` protocol
+    #[serde(default)]
+    pub elasticsearch: crate::elasticsearch::ElasticsearchConfig,
+    /// Batch serialization for the native protocol: json, ndjson, protobuf or msgpack
+    #[serde(default)]
+    pub format: crate::payload_format::PayloadFormat,
+    /// Event IDs and acknowledged ranges, so retries and buffer replays don't store events twice
+    #[serde(default)]
+    pub delivery: crate::delivery::DeliveryConfig,
+
+    // Additional named destinations, each with its own routes, retries and circuit breaker
+    #[serde(default)]
+    pub destinations: Vec<crate::destinations::DestinationConfig>,
+    #[serde(default)]
+    pub primary_route: crate::destinations::PrimaryRoute,
+}
+
+impl TransportConfig {
+    /// Replace keyring references in the API key and client key password with the secrets they name
+    pub async fn resolve_secrets(&mut self) -> Result<(), crate::errors::AgentError> {
+        self.api_key = crate::security::resolve_secret(&self.api_key).await?;
+        if let Some(password) = &self.client_key_password {
+            self.client_key_password = Some(crate::security::resolve_secret(password).await?);
+        }
+        Ok(())
+    }
+}
+
+/// How batches are shipped to a destination
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransportProtocol {
+    /// SecureWatch JSON batches posted to `server_url`
+    #[default]
+    Native,
+    /// OTLP/HTTP LogRecords (protobuf or JSON)
+    OtlpHttp,
+    /// OTLP/gRPC LogsService export
+    OtlpGrpc,
+    /// RFC 5424 syslog over TLS or TCP to a `tls://` or `tcp://` server_url; destinations only
+    Syslog,
+    /// Splunk HTTP Event Collector
+    SplunkHec,
+    /// Elasticsearch or OpenSearch `_bulk` API
+    Elasticsearch,
+}
+
+impl TransportProtocol {
+    pub fn is_otlp(&self) -> bool {
+        matches!(self, TransportProtocol::OtlpHttp | TransportProtocol::OtlpGrpc)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,29 +199,994 @@ pub struct CollectorsConfig {
     pub syslog: Option<SyslogCollectorConfig>,
     pub windows_event: Option<WindowsEventCollectorConfig>,
     pub file_monitor: Option<FileMonitorConfig>,
+    #[serde(default)]
+    pub database: Option<DatabaseCollectorConfig>,
+    #[serde(default)]
+    pub session: Option<SessionCollectorConfig>,
+    #[serde(default)]
+    pub ebpf: Option<EbpfCollectorConfig>,
+    #[serde(default)]
+    pub etw: Option<EtwCollectorConfig>,
+    #[serde(default)]
+    pub container: Option<ContainerCollectorConfig>,
+    #[serde(default)]
+    pub fim: Option<FimCollectorConfig>,
+    #[serde(default)]
+    pub honeytoken: Option<HoneytokenCollectorConfig>,
+    #[serde(default)]
+    pub command: Option<CommandCollectorConfig>,
+    #[serde(default)]
+    pub http_pull: Option<HttpPullCollectorConfig>,
+    #[serde(default)]
+    pub packet_metadata: Option<PacketMetadataCollectorConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyslogCollectorConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub port: u16,
+    /// udp, tcp, both, or tls (RFC 5425)
+    pub protocol: String,
+    /// How messages are delimited on TCP and TLS streams
+    #[serde(default)]
+    pub framing: SyslogFraming,
+    /// Longest accepted message in bytes; also sizes the UDP receive buffer
+    #[serde(default = "default_syslog_max_message_size")]
+    pub max_message_size: usize,
+    /// Server certificate for protocol = "tls"
+    #[serde(default)]
+    pub tls: Option<SyslogTlsConfig>,
+    /// UDP socket receive buffer while buffer backpressure is active, so the kernel sheds load instead of queueing
+    /// it; 0 keeps the system default
+    #[serde(default = "default_syslog_backpressure_receive_buffer")]
+    pub backpressure_receive_buffer_bytes: usize,
+    /// How often a summary of dropped UDP messages is emitted while drops are being counted
+    #[serde(default = "default_syslog_drop_marker_interval")]
+    pub drop_marker_interval_seconds: u64,
+}
+
+fn default_syslog_max_message_size() -> usize {
+    65536
+}
+
+fn default_syslog_backpressure_receive_buffer() -> usize {
+    131072
+}
+
+fn default_syslog_drop_marker_interval() -> u64 {
+    10
+}
+
+/// Stream framing for syslog over TCP (RFC 6587)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyslogFraming {
+    /// Octet counting when a frame starts with a digit, otherwise non-transparent
+    #[default]
+    Auto,
+    /// `MSG-LEN SP SYSLOG-MSG`, as required by RFC 5425
+    OctetCounting,
+    /// Messages terminated by LF (or NUL)
+    NonTransparent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyslogTlsConfig {
+    /// PEM certificate chain presented to senders
+    pub cert_path: String,
+    /// PEM PKCS#8 private key for the certificate
+    pub key_path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowsEventCollectorConfig {
+    pub enabled: bool,
+    pub channels: Vec<String>,
+    pub batch_size: usize,
+    /// Bookmark file used to resume channels after a restart; kept as JSON so it works without persistent-storage
+    #[serde(default)]
+    pub bookmark_path: Option<String>,
+    /// Render event messages from the providers' message catalogs; events whose catalog is missing
+    /// fall back to a message built from the raw EventData
+    #[serde(default = "default_windows_render_messages")]
+    pub render_messages: bool,
+    /// Locale for rendered messages (e.g. "en-US"); the system locale when unset
+    #[serde(default)]
+    pub message_locale: Option<String>,
+}
+
+fn default_windows_render_messages() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMonitorConfig {
+    pub enabled: bool,
+    pub paths: Vec<String>,
+    pub patterns: Vec<String>,
+    pub recursive: bool,
+    /// Join stack traces and other multi-line records into single events
+    #[serde(default)]
+    pub multiline: Option<MultilineConfig>,
+    /// Read rotated, compressed files (app.log.1.gz, ...) instead of skipping them
+    #[serde(default)]
+    pub backfill: ArchiveBackfillConfig,
+}
+
+/// How lines of a file are grouped into records.
+/// With `start_pattern`, lines not matching it continue the current record; with `continuation_pattern`,
+/// lines matching it do. When both are set a line continues the record only if it matches the continuation
+/// pattern and not the start pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultilineConfig {
+    #[serde(default)]
+    pub start_pattern: Option<String>,
+    #[serde(default)]
+    pub continuation_pattern: Option<String>,
+    /// Upper bound on lines joined into one record
+    #[serde(default = "default_multiline_max_lines")]
+    pub max_lines: usize,
+    /// A record with no new lines for this long is complete
+    #[serde(default = "default_multiline_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_multiline_max_lines() -> usize {
+    500
+}
+
+fn default_multiline_timeout_ms() -> u64 {
+    1000
+}
+
+impl MultilineConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.start_pattern.is_none() && self.continuation_pattern.is_none() {
+            errors.push("one of start_pattern or continuation_pattern is required".to_string());
+        }
+        for (name, pattern) in [("start_pattern", &self.start_pattern), ("continuation_pattern", &self.continuation_pattern)] {
+            if let Some(Err(e)) = pattern.as_deref().map(Regex::new) {
+                errors.push(format!("invalid {}: {}", name, e));
+            }
+        }
+        if self.max_lines == 0 {
+            errors.push("max_lines must be at least 1".to_string());
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseCollectorConfig {
+    pub enabled: bool,
+    /// Log format preset: postgresql, mysql_general or mysql_audit
+    pub preset: crate::parsers::database::DatabaseLogPreset,
+    /// Log file paths (glob patterns supported)
+    pub paths: Vec<String>,
+    /// PostgreSQL log_line_prefix; defaults to '%m [%p] %q%u@%d '
+    #[serde(default)]
+    pub log_line_prefix: Option<String>,
+    #[serde(default = "default_database_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Upper bound on lines joined into one multi-line statement
+    #[serde(default = "default_database_max_record_lines")]
+    pub max_record_lines: usize,
+    /// Read existing file contents on startup instead of only new lines
+    #[serde(default)]
+    pub read_from_start: bool,
+}
+
+fn default_database_poll_interval_ms() -> u64 {
+    1000
+}
+
+fn default_database_max_record_lines() -> usize {
+    500
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCollectorConfig {
+    pub enabled: bool,
+    /// Session sources: wtmp, logind, windows_security or who; empty selects the platform default
+    #[serde(default)]
+    pub sources: Vec<crate::collectors::session::SessionSourceKind>,
+    #[serde(default = "default_session_wtmp_path")]
+    pub wtmp_path: String,
+    #[serde(default = "default_session_logind_sessions_dir")]
+    pub logind_sessions_dir: String,
+    #[serde(default = "default_session_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Report sessions found in existing history on startup instead of only new ones
+    #[serde(default)]
+    pub read_from_start: bool,
+}
+
+fn default_session_wtmp_path() -> String {
+    "/var/log/wtmp".to_string()
+}
+
+fn default_session_logind_sessions_dir() -> String {
+    "/run/systemd/sessions".to_string()
+}
+
+fn default_session_poll_interval_ms() -> u64 {
+    5000
+}
+
+/// eBPF endpoint telemetry collector (Linux, `ebpf` feature)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EbpfCollectorConfig {
+    pub enabled: bool,
+    /// Compiled probe object built by ebpf/build.sh
+    pub probe_path: String,
+    pub process_exec: bool,
+    pub network_connect: bool,
+    /// File opens are high volume, so they are off by default
+    pub file_open: bool,
+    /// Only report opens for writing, creating or truncating (filtered in the kernel)
+    pub file_open_writes_only: bool,
+    /// Only report file opens under these path prefixes; empty reports all
+    pub file_path_prefixes: Vec<String>,
+    /// Process names (comm) whose events are dropped
+    pub exclude_processes: Vec<String>,
+    /// Kernel ring buffer size; a power of two, at least 4
+    pub ring_buffer_kb: u32,
+    pub max_events_per_second: u32,
+}
+
+impl Default for EbpfCollectorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            probe_path: "/usr/lib/securewatch/securewatch_probes.bpf.o".to_string(),
+            process_exec: true,
+            network_connect: true,
+            file_open: false,
+            file_open_writes_only: true,
+            file_path_prefixes: Vec::new(),
+            exclude_processes: Vec::new(),
+            ring_buffer_kb: 4096,
+            max_events_per_second: 5000,
+        }
+    }
+}
+
+impl EbpfCollectorConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+        if !self.process_exec && !self.network_connect && !self.file_open {
+            errors.push("At least one of process_exec, network_connect or file_open must be enabled".to_string());
+        }
+        if self.probe_path.is_empty() {
+            errors.push("probe_path is required".to_string());
+        }
+        if self.ring_buffer_kb < 4 || !self.ring_buffer_kb.is_power_of_two() || self.ring_buffer_kb > 1024 * 1024 {
+            errors.push("ring_buffer_kb must be a power of two between 4 and 1048576".to_string());
+        }
+        if self.max_events_per_second == 0 {
+            errors.push("max_events_per_second must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// ETW real-time trace collector (Windows)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EtwCollectorConfig {
+    pub enabled: bool,
+    /// Trace session name; a session left behind by a previous run under this name is replaced
+    pub session_name: String,
+    pub providers: Vec<EtwProviderConfig>,
+    /// Size of each trace session buffer
+    pub buffer_size_kb: u32,
+    pub max_events_per_second: u32,
+}
+
+impl Default for EtwCollectorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            session_name: "SecureWatch-ETW".to_string(),
+            providers: Vec::new(),
+            buffer_size_kb: 64,
+            max_events_per_second: 5000,
+        }
+    }
+}
+
+/// One ETW provider enabled in the collector's session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EtwProviderConfig {
+    /// Provider name, e.g. Microsoft-Windows-Sysmon
+    pub name: String,
+    /// Provider GUID; only needed for providers that are neither well known nor registered on the host
+    #[serde(default)]
+    pub guid: Option<String>,
+    /// Most verbose level delivered: 1=Critical, 2=Error, 3=Warning, 4=Information, 5=Verbose
+    #[serde(default = "default_etw_level")]
+    pub level: u8,
+    /// Events must carry at least one of these keyword bits; 0 delivers all keywords
+    #[serde(default)]
+    pub match_any_keyword: u64,
+    /// Events must carry all of these keyword bits
+    #[serde(default)]
+    pub match_all_keyword: u64,
+    /// Only these event IDs are kept; empty keeps all
+    #[serde(default)]
+    pub event_ids: Vec<u16>,
+}
+
+fn default_etw_level() -> u8 {
+    4
+}
+
+impl EtwCollectorConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+        if self.session_name.is_empty() || self.session_name.len() > 1024 {
+            errors.push("session_name must be between 1 and 1024 characters".to_string());
+        }
+        if self.providers.is_empty() {
+            errors.push("At least one provider is required".to_string());
+        }
+        for provider in &self.providers {
+            if provider.name.is_empty() {
+                errors.push("Provider name is required".to_string());
+            }
+            if let Some(guid) = &provider.guid {
+                if crate::collectors::etw::parse_guid(guid).is_none() {
+                    errors.push(format!("Provider '{}' has an invalid GUID '{}'", provider.name, guid));
+                }
+            }
+            if !(1..=5).contains(&provider.level) {
+                errors.push(format!("Provider '{}' level must be between 1 and 5", provider.name));
+            }
+        }
+        if !(4..=16384).contains(&self.buffer_size_kb) {
+            errors.push("buffer_size_kb must be between 4 and 16384".to_string());
+        }
+        if self.max_events_per_second == 0 {
+            errors.push("max_events_per_second must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// Container log collector (CRI and Docker json-file logs, e.g. when running as a DaemonSet)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContainerCollectorConfig {
+    pub enabled: bool,
+    /// Glob patterns for container log files; /var/log/containers names them after pod, namespace and container
+    pub log_paths: Vec<String>,
+    /// Log line format: auto, cri or docker
+    pub format: crate::collectors::container::ContainerLogFormat,
+    pub poll_interval_ms: u64,
+    /// Read logs that existed before the agent started instead of only new lines
+    pub read_from_start: bool,
+    /// Containers in these namespaces are not collected (the agent's own namespace is a common entry)
+    pub exclude_namespaces: Vec<String>,
+    /// Longest record kept after joining partial lines; longer records are truncated
+    pub max_line_bytes: usize,
+    pub kubelet: KubeletConfig,
+}
+
+impl Default for ContainerCollectorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            log_paths: vec!["/var/log/containers/*.log".to_string()],
+            format: crate::collectors::container::ContainerLogFormat::Auto,
+            poll_interval_ms: 1000,
+            read_from_start: false,
+            exclude_namespaces: Vec::new(),
+            max_line_bytes: 256 * 1024,
+            kubelet: KubeletConfig::default(),
+        }
+    }
+}
+
+/// Pod metadata lookups against the node's kubelet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KubeletConfig {
+    pub enabled: bool,
+    pub url: String,
+    /// Service account token sent as a bearer token
+    pub token_path: String,
+    pub ca_cert_path: Option<String>,
+    /// Kubelet serving certificates are often self-signed and lack the node IP
+    pub tls_verify: bool,
+    pub refresh_interval_secs: u64,
+    pub timeout_seconds: u64,
+}
+
+impl Default for KubeletConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "https://127.0.0.1:10250".to_string(),
+            token_path: "/var/run/secrets/kubernetes.io/serviceaccount/token".to_string(),
+            ca_cert_path: None,
+            tls_verify: true,
+            refresh_interval_secs: 60,
+            timeout_seconds: 10,
+        }
+    }
+}
+
+impl ContainerCollectorConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+        if self.log_paths.is_empty() {
+            errors.push("At least one log path is required".to_string());
+        }
+        for path in &self.log_paths {
+            if ::glob::Pattern::new(path).is_err() {
+                errors.push(format!("Invalid log path pattern '{}'", path));
+            }
+        }
+        if self.poll_interval_ms < 100 {
+            errors.push("poll_interval_ms must be at least 100".to_string());
+        }
+        if self.max_line_bytes < 1024 {
+            errors.push("max_line_bytes must be at least 1024".to_string());
+        }
+        if self.kubelet.enabled {
+            if !self.kubelet.url.starts_with("https://") && !self.kubelet.url.starts_with("http://") {
+                errors.push("kubelet.url must be an http:// or https:// URL".to_string());
+            }
+            if self.kubelet.refresh_interval_secs == 0 {
+                errors.push("kubelet.refresh_interval_secs must be greater than 0".to_string());
+            }
+            if self.kubelet.timeout_seconds == 0 {
+                errors.push("kubelet.timeout_seconds must be greater than 0".to_string());
+            }
+        }
+        errors
+    }
+}
+
+/// Backfill of compressed archives found by the file monitor. Every archive is read once from start to end;
+/// the checkpoint file remembers how far, keyed by content rather than name, so renames by logrotate don't
+/// cause a second read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveBackfillConfig {
+    pub enabled: bool,
+    /// Archive globs read besides the archives under the file monitor's own paths, e.g. "/var/log/app/app.log.*.gz"
+    pub paths: Vec<String>,
+    /// Formats decompressed; archives in other formats are skipped
+    pub formats: Vec<crate::collectors::file_archive::ArchiveFormat>,
+    pub checkpoint_path: String,
+    /// Records sent between checkpoint writes
+    pub checkpoint_interval: usize,
+}
+
+impl Default for ArchiveBackfillConfig {
+    fn default() -> Self {
+        use crate::collectors::file_archive::ArchiveFormat;
+        Self {
+            enabled: false,
+            paths: Vec::new(),
+            formats: vec![ArchiveFormat::Gz, ArchiveFormat::Zip, ArchiveFormat::Bz2],
+            checkpoint_path: "./file_monitor_backfill.json".to_string(),
+            checkpoint_interval: 10_000,
+        }
+    }
+}
+
+impl ArchiveBackfillConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+        for pattern in &self.paths {
+            if ::glob::Pattern::new(pattern).is_err() {
+                errors.push(format!("Invalid path pattern '{}'", pattern));
+            }
+        }
+        if self.formats.is_empty() {
+            errors.push("At least one format is required".to_string());
+        }
+        if self.checkpoint_path.trim().is_empty() {
+            errors.push("checkpoint_path must not be empty".to_string());
+        }
+        if self.checkpoint_interval == 0 {
+            errors.push("checkpoint_interval must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// File integrity monitoring: watched paths are hashed into a baseline and every change is reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FimCollectorConfig {
+    pub enabled: bool,
+    /// Files or directories to watch (glob patterns allowed)
+    pub paths: Vec<String>,
+    /// Descend into subdirectories of watched directories
+    pub recursive: bool,
+    /// File name or path glob patterns that are never reported, e.g. "*.swp"
+    pub exclude: Vec<String>,
+    pub hash_algorithm: crate::collectors::fim::FimHashAlgorithm,
+    /// Larger files are tracked by size, time and permissions only
+    pub max_file_size_bytes: u64,
+    /// Stop adding files to the baseline past this many
+    pub max_files: usize,
+    /// React to file system notifications instead of waiting for the next scan
+    pub realtime: bool,
+    /// Full rescan interval; catches changes notifications missed
+    pub scan_interval_seconds: u64,
+    /// Baseline kept across restarts so changes made while the agent was down are reported
+    pub baseline_path: String,
+}
+
+impl Default for FimCollectorConfig {
+    fn default() -> Self {
+        let paths = if cfg!(windows) {
+            vec![r"C:\Windows\System32\drivers\etc".to_string()]
+        } else if cfg!(target_os = "macos") {
+            vec!["/etc".to_string(), "/usr/local/bin".to_string()]
+        } else {
+            vec!["/etc".to_string(), "/usr/bin".to_string(), "/usr/sbin".to_string()]
+        };
+        Self {
+            enabled: false,
+            paths,
+            recursive: true,
+            exclude: vec!["*.swp".to_string(), "*~".to_string()],
+            hash_algorithm: crate::collectors::fim::FimHashAlgorithm::Sha256,
+            max_file_size_bytes: 64 * 1024 * 1024,
+            max_files: 100_000,
+            realtime: true,
+            scan_interval_seconds: 3600,
+            baseline_path: "./fim_baseline.json".to_string(),
+        }
+    }
+}
+
+impl FimCollectorConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+        if self.paths.is_empty() {
+            errors.push("At least one path is required".to_string());
+        }
+        for pattern in self.paths.iter().chain(&self.exclude) {
+            if ::glob::Pattern::new(pattern).is_err() {
+                errors.push(format!("Invalid path pattern '{}'", pattern));
+            }
+        }
+        if self.max_files == 0 {
+            errors.push("max_files must be greater than 0".to_string());
+        }
+        if self.scan_interval_seconds < 60 {
+            errors.push("scan_interval_seconds must be at least 60".to_string());
+        }
+        if self.baseline_path.trim().is_empty() {
+            errors.push("baseline_path must not be empty".to_string());
+        }
+        errors
+    }
+}
+
+/// Decoy files and registry keys nothing legitimate touches, so any access to them is a critical alert
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HoneytokenCollectorConfig {
+    pub enabled: bool,
+    pub files: Vec<HoneyfileConfig>,
+    /// Windows only
+    pub registry_keys: Vec<HoneyRegistryKeyConfig>,
+    /// Recreate decoys that were deleted or moved away, after reporting it
+    pub redeploy: bool,
+    /// Process names whose opens are not reported, e.g. backup or antivirus agents that read every file
+    pub ignore_processes: Vec<String>,
+    /// Decoys are checked this often where opens aren't notified, registry keys always
+    pub poll_interval_seconds: u64,
+}
+
+impl Default for HoneytokenCollectorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            files: Vec::new(),
+            registry_keys: Vec::new(),
+            redeploy: true,
+            ignore_processes: Vec::new(),
+            poll_interval_seconds: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoneyfileConfig {
+    pub path: String,
+    /// Written when the agent creates the file; fake cloud credentials when unset. Existing files are never
+    /// overwritten, so a decoy can also be put in place by hand.
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoneyRegistryKeyConfig {
+    /// Key under HKLM or HKCU, e.g. 'HKLM\SOFTWARE\Acme\VPN'
+    pub key: String,
+    #[serde(default = "default_honey_registry_value_name")]
+    pub value_name: String,
+    /// String data of the value; a random password when unset
+    #[serde(default)]
+    pub value_data: Option<String>,
+}
+
+fn default_honey_registry_value_name() -> String {
+    "Password".to_string()
+}
+
+impl HoneytokenCollectorConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+        if self.files.is_empty() && self.registry_keys.is_empty() {
+            errors.push("At least one decoy file or registry key is required".to_string());
+        }
+        let mut paths = std::collections::HashSet::new();
+        for file in &self.files {
+            if !std::path::Path::new(&file.path).is_absolute() {
+                errors.push(format!("Decoy path '{}' must be absolute", file.path));
+            } else if !paths.insert(file.path.as_str()) {
+                errors.push(format!("Decoy path '{}' is listed twice", file.path));
+            }
+        }
+        for key in &self.registry_keys {
+            if crate::collectors::honeytoken::split_registry_key(&key.key).is_none() {
+                errors.push(format!("Registry key '{}' must be a subkey of HKLM or HKCU", key.key));
+            }
+            if key.value_name.trim().is_empty() {
+                errors.push(format!("Registry key '{}' needs a value_name", key.key));
+            }
+        }
+        if self.poll_interval_seconds == 0 {
+            errors.push("poll_interval_seconds must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// Allow-listed commands and scripts run on a schedule; their output is parsed under each command's source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandCollectorConfig {
+    pub enabled: bool,
+    /// Absolute paths of the programs commands may run; anything else is refused
+    pub allowed_programs: Vec<String>,
+    pub commands: Vec<ScheduledCommandConfig>,
+    /// Commands running at the same time
+    pub max_concurrent: usize,
+    /// Stdout past this many bytes is discarded and the run marked truncated
+    pub max_output_bytes: usize,
+}
+
+impl Default for CommandCollectorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_programs: Vec::new(),
+            commands: Vec::new(),
+            max_concurrent: 2,
+            max_output_bytes: 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledCommandConfig {
+    pub name: String,
+    /// Absolute path, listed in allowed_programs
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub interval_seconds: u64,
+    /// The process is killed when a run takes longer
+    #[serde(default = "default_command_timeout")]
+    pub timeout_seconds: u64,
+    /// Source of the output events, which selects the parsers applied to them
+    #[serde(default = "default_command_source")]
+    pub source: String,
+    #[serde(default)]
+    pub output: crate::collectors::command::CommandOutputMode,
+    #[serde(default)]
+    pub working_directory: Option<String>,
+    /// Leading output lines dropped in lines mode, e.g. column headers
+    #[serde(default)]
+    pub skip_lines: usize,
+}
+
+fn default_command_timeout() -> u64 {
+    30
+}
+
+fn default_command_source() -> String {
+    crate::collectors::command::COMMAND_SOURCE.to_string()
+}
+
+impl CommandCollectorConfig {
+    /// `program` is on the allow-list
+    pub fn allows(&self, program: &str) -> bool {
+        self.allowed_programs.iter().any(|allowed| allowed == program)
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+        if self.commands.is_empty() {
+            errors.push("At least one command is required".to_string());
+        }
+        for program in &self.allowed_programs {
+            if !std::path::Path::new(program).is_absolute() {
+                errors.push(format!("Allowed program '{}' must be an absolute path", program));
+            }
+        }
+        let mut names = std::collections::HashSet::new();
+        for command in &self.commands {
+            if command.name.trim().is_empty() {
+                errors.push("Command name must not be empty".to_string());
+            } else if !names.insert(command.name.as_str()) {
+                errors.push(format!("Duplicate command name '{}'", command.name));
+            }
+            if !self.allows(&command.program) {
+                errors.push(format!("Command '{}' runs {}, which is not in allowed_programs", command.name, command.program));
+            }
+            if command.interval_seconds < 10 {
+                errors.push(format!("Command '{}' interval_seconds must be at least 10", command.name));
+            }
+            if command.timeout_seconds == 0 || command.timeout_seconds > command.interval_seconds {
+                errors.push(format!("Command '{}' timeout_seconds must be between 1 and interval_seconds", command.name));
+            }
+            if command.source.trim().is_empty() {
+                errors.push(format!("Command '{}' source must not be empty", command.name));
+            }
+        }
+        if self.max_concurrent == 0 {
+            errors.push("max_concurrent must be greater than 0".to_string());
+        }
+        if self.max_output_bytes < 1024 {
+            errors.push("max_output_bytes must be at least 1024".to_string());
+        }
+        errors
+    }
+}
+
+/// REST APIs polled for JSON records (SaaS audit logs), with cursors kept across restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpPullCollectorConfig {
+    pub enabled: bool,
+    pub endpoints: Vec<HttpPullEndpointConfig>,
+    /// Cursor of every endpoint, so polling resumes after a restart
+    pub state_path: String,
+    pub timeout_seconds: u64,
+    pub tls_verify: bool,
+}
+
+impl Default for HttpPullCollectorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoints: Vec::new(),
+            state_path: "./http_pull_state.json".to_string(),
+            timeout_seconds: 30,
+            tls_verify: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpPullEndpointConfig {
+    pub name: String,
+    pub url: String,
+    /// Query parameters; `{cursor}` in a value is replaced by the endpoint's cursor (left out until there is one)
+    #[serde(default)]
+    pub query: HashMap<String, String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default = "default_http_pull_interval")]
+    pub interval_seconds: u64,
+    /// Source of the record events, which selects the parsers applied to them
+    #[serde(default = "default_http_pull_source")]
+    pub source: String,
+    /// Dotted path of the record array in the response (e.g. "value"); the whole response when unset
+    #[serde(default)]
+    pub records_path: Option<String>,
+    #[serde(default)]
+    pub auth: crate::collectors::http_pull::HttpPullAuth,
+    #[serde(default)]
+    pub pagination: crate::collectors::http_pull::HttpPagination,
+    #[serde(default)]
+    pub cursor: Option<HttpPullCursorConfig>,
+    /// Pages fetched per poll at most; the rest waits for the next poll
+    #[serde(default = "default_http_pull_max_pages")]
+    pub max_pages: usize,
+    /// Record field holding the event time, for the default JSON parser
+    #[serde(default)]
+    pub timestamp_field: Option<String>,
+}
+
+/// Resume point taken from the records; they are expected oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpPullCursorConfig {
+    /// Dotted path of the cursor value in each record, e.g. "published"
+    pub field: String,
+    /// Cursor used before any record was pulled
+    #[serde(default)]
+    pub initial: Option<String>,
+}
+
+fn default_http_pull_interval() -> u64 {
+    300
+}
+
+fn default_http_pull_source() -> String {
+    crate::collectors::http_pull::HTTP_PULL_SOURCE.to_string()
+}
+
+fn default_http_pull_max_pages() -> usize {
+    50
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SyslogCollectorConfig {
-    pub enabled: bool,
-    pub bind_address: String,
-    pub port: u16,
-    pub protocol: String,
+impl HttpPullCollectorConfig {
+    pub fn validate(&self) -> Vec<String> {
+        use crate::collectors::http_pull::{HttpPagination, HttpPullAuth, CURSOR_PLACEHOLDER};
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+        if self.endpoints.is_empty() {
+            errors.push("At least one endpoint is required".to_string());
+        }
+        if self.state_path.trim().is_empty() {
+            errors.push("state_path must not be empty".to_string());
+        }
+        if self.timeout_seconds == 0 {
+            errors.push("timeout_seconds must be greater than 0".to_string());
+        }
+        let mut names = std::collections::HashSet::new();
+        for endpoint in &self.endpoints {
+            let name = &endpoint.name;
+            if name.trim().is_empty() {
+                errors.push("Endpoint name must not be empty".to_string());
+            } else if !names.insert(name.as_str()) {
+                errors.push(format!("Duplicate endpoint name '{}'", name));
+            }
+            match url::Url::parse(&endpoint.url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => errors.push(format!("Endpoint '{}' url must be an http(s) URL", name)),
+            }
+            if endpoint.interval_seconds < 10 {
+                errors.push(format!("Endpoint '{}' interval_seconds must be at least 10", name));
+            }
+            if endpoint.max_pages == 0 {
+                errors.push(format!("Endpoint '{}' max_pages must be greater than 0", name));
+            }
+            if endpoint.source.trim().is_empty() {
+                errors.push(format!("Endpoint '{}' source must not be empty", name));
+            }
+            let secret_env = match &endpoint.auth {
+                HttpPullAuth::None => None,
+                HttpPullAuth::Bearer { token_env } => Some(token_env),
+                HttpPullAuth::Header { value_env, .. } => Some(value_env),
+                HttpPullAuth::Basic { password_env, .. } => Some(password_env),
+                HttpPullAuth::OAuth2 { token_url, client_secret_env, .. } => {
+                    if url::Url::parse(token_url).is_err() {
+                        errors.push(format!("Endpoint '{}' OAuth2 token_url is not a valid URL", name));
+                    }
+                    Some(client_secret_env)
+                }
+            };
+            if secret_env.is_some_and(|variable| variable.trim().is_empty()) {
+                errors.push(format!("Endpoint '{}' auth needs the name of the environment variable holding its secret", name));
+            }
+            match &endpoint.pagination {
+                HttpPagination::NextUrl { path } if path.is_empty() => {
+                    errors.push(format!("Endpoint '{}' next_url pagination needs a path", name));
+                }
+                HttpPagination::Cursor { path, param } if path.is_empty() || param.is_empty() => {
+                    errors.push(format!("Endpoint '{}' cursor pagination needs a path and a param", name));
+                }
+                _ => {}
+            }
+            if let Some(cursor) = &endpoint.cursor {
+                if cursor.field.is_empty() {
+                    errors.push(format!("Endpoint '{}' cursor field must not be empty", name));
+                }
+                if !endpoint.query.values().any(|value| value.contains(CURSOR_PLACEHOLDER)) {
+                    errors.push(format!("Endpoint '{}' cursor is never sent; use {} in a query value", name, CURSOR_PLACEHOLDER));
+                }
+            }
+        }
+        errors
+    }
 }
 
+/// Packet capture metadata collector (`pcap` feature): DNS, TLS SNI/JA3 and HTTP hosts, never payloads
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WindowsEventCollectorConfig {
+#[serde(default)]
+pub struct PacketMetadataCollectorConfig {
     pub enabled: bool,
-    pub channels: Vec<String>,
-    pub batch_size: usize,
+    /// Capture device; "any" captures on every interface (Linux)
+    pub interface: String,
+    pub promiscuous: bool,
+    /// Kernel-side BPF filter applied before packets reach the agent; empty captures everything
+    pub bpf_filter: String,
+    /// Bytes captured per packet; enough for headers and a ClientHello
+    pub snaplen: u32,
+    pub dns: bool,
+    pub tls: bool,
+    pub http: bool,
+    /// New TCP connections (client SYN), off by default because of the volume
+    pub connections: bool,
+    pub max_events_per_second: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileMonitorConfig {
-    pub enabled: bool,
-    pub paths: Vec<String>,
-    pub patterns: Vec<String>,
-    pub recursive: bool,
+impl Default for PacketMetadataCollectorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interface: "any".to_string(),
+            promiscuous: false,
+            bpf_filter: "udp port 53 or tcp port 53 or tcp port 443 or tcp port 80".to_string(),
+            snaplen: 4096,
+            dns: true,
+            tls: true,
+            http: true,
+            connections: false,
+            max_events_per_second: 1000,
+        }
+    }
+}
+
+impl PacketMetadataCollectorConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+        if self.interface.trim().is_empty() {
+            errors.push("interface is required".to_string());
+        }
+        if !self.dns && !self.tls && !self.http && !self.connections {
+            errors.push("At least one of dns, tls, http or connections must be enabled".to_string());
+        }
+        if !(256..=65535).contains(&self.snaplen) {
+            errors.push("snaplen must be between 256 and 65535".to_string());
+        }
+        if self.max_events_per_second == 0 {
+            errors.push("max_events_per_second must be greater than 0".to_string());
+        }
+        errors
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -133,6 +1219,78 @@ pub struct BufferConfig {
     pub cleanup_interval_sec: u64,
     pub min_retention_hours: u64,
     pub max_events_per_cleanup: usize,
+    
+    // In-memory overflow list for absorbing short bursts before spilling to disk (0 disables)
+    #[serde(default = "default_burst_capacity")]
+    pub burst_capacity: usize,
+    
+    // Events on disk written by a newer agent version, e.g. after a downgrade
+    #[serde(default)]
+    pub newer_events: NewerEventPolicy,
+    
+    // Encryption at rest for buffered event payloads
+    #[serde(default)]
+    pub encryption: crate::buffer_encryption::BufferEncryptionConfig,
+    
+    // Events spilled to disk are written in batches of up to this many rows (1 writes each event on its own)
+    #[serde(default = "default_write_batch_size")]
+    pub write_batch_size: usize,
+    // Longest a partial batch waits in memory before it is written
+    #[serde(default = "default_write_batch_interval_ms")]
+    pub write_batch_interval_ms: u64,
+    
+    // Free-space thresholds for the volume holding persistence_path, checked with resource monitor samples
+    #[serde(default)]
+    pub disk_quota: crate::disk_quota::DiskQuotaConfig,
+}
+
+/// Buffer settings that can change while the buffer is open; the rest shape the database and channels created at startup
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferLimits {
+    pub max_size_mb: usize,
+    pub max_database_size_mb: Option<usize>,
+    pub cleanup_trigger_percent: f64,
+    pub cleanup_target_percent: f64,
+    pub min_retention_hours: u64,
+    pub max_events_per_cleanup: usize,
+}
+
+impl BufferConfig {
+    pub fn limits(&self) -> BufferLimits {
+        BufferLimits {
+            max_size_mb: self.max_size_mb,
+            max_database_size_mb: self.max_database_size_mb,
+            cleanup_trigger_percent: self.cleanup_trigger_percent,
+            cleanup_target_percent: self.cleanup_target_percent,
+            min_retention_hours: self.min_retention_hours,
+            max_events_per_cleanup: self.max_events_per_cleanup,
+        }
+    }
+
+    /// This configuration with `limits` in place of its own
+    pub fn with_limits(&self, limits: &BufferLimits) -> BufferConfig {
+        BufferConfig {
+            max_size_mb: limits.max_size_mb,
+            max_database_size_mb: limits.max_database_size_mb,
+            cleanup_trigger_percent: limits.cleanup_trigger_percent,
+            cleanup_target_percent: limits.cleanup_target_percent,
+            min_retention_hours: limits.min_retention_hours,
+            max_events_per_cleanup: limits.max_events_per_cleanup,
+            ..self.clone()
+        }
+    }
+}
+
+fn default_burst_capacity() -> usize {
+    5000
+}
+
+fn default_write_batch_size() -> usize {
+    500
+}
+
+fn default_write_batch_interval_ms() -> u64 {
+    100
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -165,6 +1323,20 @@ pub enum CleanupStrategy {
     Intelligent,   // Combine multiple strategies for optimal cleanup
 }
 
+/// Handling of buffered events whose format version is newer than this agent understands.
+/// Rows that cannot be decoded at all are quarantined unless the policy is `Fail`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NewerEventPolicy {
+    /// Leave them on disk for a later upgrade and ship everything else
+    Skip,
+    /// Move them to the `events_quarantine` table
+    #[default]
+    Quarantine,
+    /// Refuse to open the buffer
+    Fail,
+}
+
 /// Structured validation error for detailed error reporting
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigValidationError {
@@ -174,17 +1346,83 @@ pub struct ConfigValidationError {
     pub suggestion: Option<String>,
 }
 
+/// Risky-but-legal setting: the configuration is applied and the finding is reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigValidationWarning {
+    pub path: String,
+    pub warning_type: String,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+/// Validation outcome split by severity; only errors block applying a configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigValidationReport {
+    pub errors: Vec<ConfigValidationError>,
+    pub warnings: Vec<ConfigValidationWarning>,
+}
+
+impl ConfigValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParsersConfig {
     pub parsers: Vec<ParserDefinition>,
+    /// Named processor chains that parsers and sources reference by name
+    #[serde(default)]
+    pub chains: HashMap<String, Vec<crate::parsers::processors::ProcessorStep>>,
+    /// Processor chains applied to every event from a source type
+    #[serde(default)]
+    pub source_chains: HashMap<String, Vec<String>>,
+    /// Capture samples of raw events no parser matched, for parser development
+    #[serde(default)]
+    pub sample_capture: crate::parsers::samples::SampleCaptureConfig,
+    /// Event time extraction from parsed fields, with per-source formats and timezones
+    #[serde(default)]
+    pub timestamps: crate::parsers::timestamps::TimestampConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParserDefinition {
     pub name: String,
     pub source_type: String,
+    #[serde(default, rename = "type")]
+    pub parser_type: ParserType,
+    /// Required for regex parsers; unused by JSON parsers
+    #[serde(default)]
     pub regex_pattern: String,
+    #[serde(default)]
     pub field_mappings: HashMap<String, String>,
+    /// Processor chains applied to events from this parser, in order
+    #[serde(default)]
+    pub processors: Vec<String>,
+    #[serde(default)]
+    pub json: crate::parsers::json::JsonParserOptions,
+    /// Expected field types, coerced and checked after the processor chains run
+    #[serde(default)]
+    pub schema: crate::parsers::schema::FieldSchema,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParserType {
+    /// Named capture groups mapped through `field_mappings`
+    #[default]
+    Regex,
+    /// JSON / NDJSON lines decoded directly into fields
+    Json,
+}
+
+impl ParserType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParserType::Regex => "regex",
+            ParserType::Json => "json",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -193,6 +1431,8 @@ pub struct ManagementConfig {
     pub bind_address: String,
     pub port: u16,
     pub auth_token: Option<String>,
+    #[serde(default)]
+    pub tls: crate::management_tls::ManagementTlsConfig,
 }
 
 impl Default for AgentConfig {
@@ -209,13 +1449,17 @@ impl Default for AgentConfig {
                 server_url: "https://api.securewatch.local".to_string(),
                 api_key: "your-api-key".to_string(),
                 tls_verify: true,
-                compression: true,
+                compression: crate::compression::CompressionAlgorithm::Zstd,
                 compression_threshold: Some(1024), // Compress data larger than 1KB
                 compression_level: Some(3), // Balanced compression level for zstd
                 batch_size: 100,
                 batch_timeout: 5,
                 retry_attempts: 3,
                 retry_delay: 2,
+                retry_policy: Default::default(),
+                bandwidth: Default::default(),
+                schedule: Default::default(),
+                proxy: Default::default(),
                 
                 // mTLS client certificate configuration (all optional)
                 client_cert_path: None,
@@ -223,6 +1467,8 @@ impl Default for AgentConfig {
                 client_key_password: None,
                 ca_cert_path: None,
                 cert_expiry_warning_days: 30,
+                identity: Default::default(),
+                enrollment: Default::default(),
                 
                 // Circuit breaker configuration with reasonable defaults
                 circuit_breaker_failure_threshold: Some(5),
@@ -242,6 +1488,15 @@ impl Default for AgentConfig {
                 http2_keep_alive_interval: Some(std::time::Duration::from_secs(30)), // HTTP/2 ping interval
                 http2_keep_alive_timeout: Some(std::time::Duration::from_secs(10)), // HTTP/2 ping timeout
                 http2_keep_alive_while_idle: Some(true), // HTTP/2 keep-alive while idle
+                protocol: TransportProtocol::Native,
+                otlp: crate::otlp::OtlpConfig::default(),
+                syslog: crate::syslog_output::SyslogOutputConfig::default(),
+                splunk_hec: crate::splunk_hec::SplunkHecConfig::default(),
+                elasticsearch: crate::elasticsearch::ElasticsearchConfig::default(),
+                format: crate::payload_format::PayloadFormat::Json,
+                delivery: crate::delivery::DeliveryConfig::default(),
+                destinations: Vec::new(),
+                primary_route: crate::destinations::PrimaryRoute::Unmatched,
             },
             collectors: CollectorsConfig {
                 syslog: Some(SyslogCollectorConfig {
@@ -249,18 +1504,38 @@ impl Default for AgentConfig {
                     bind_address: "0.0.0.0".to_string(),
                     port: 514,
                     protocol: "udp".to_string(),
+                    framing: SyslogFraming::Auto,
+                    max_message_size: default_syslog_max_message_size(),
+                    tls: None,
+                    backpressure_receive_buffer_bytes: default_syslog_backpressure_receive_buffer(),
+                    drop_marker_interval_seconds: default_syslog_drop_marker_interval(),
                 }),
                 windows_event: Some(WindowsEventCollectorConfig {
                     enabled: false,
                     channels: vec!["System".to_string(), "Security".to_string()],
                     batch_size: 50,
+                    bookmark_path: None,
+                    render_messages: true,
+                    message_locale: None,
                 }),
                 file_monitor: Some(FileMonitorConfig {
                     enabled: false,
                     paths: vec!["/var/log/*.log".to_string()],
                     patterns: vec!["*.log".to_string()],
                     recursive: true,
+                    multiline: None,
+                    backfill: ArchiveBackfillConfig::default(),
                 }),
+                database: None,
+                session: None,
+                ebpf: None,
+                etw: None,
+                container: None,
+                fim: None,
+                honeytoken: None,
+                command: None,
+                http_pull: None,
+                packet_metadata: None,
             },
             buffer: BufferConfig {
                 max_events: 10000,
@@ -291,12 +1566,19 @@ impl Default for AgentConfig {
                 cleanup_interval_sec: 300,         // Check every 5 minutes
                 min_retention_hours: 24,           // Keep events for at least 24 hours
                 max_events_per_cleanup: 10000,     // Limit cleanup batch size
+                burst_capacity: 5000,              // Absorb short bursts in memory
+                newer_events: NewerEventPolicy::Quarantine,
+                encryption: crate::buffer_encryption::BufferEncryptionConfig::default(),
+                write_batch_size: 500,             // Coalesce disk spills into multi-row inserts
+                write_batch_interval_ms: 100,
+                disk_quota: crate::disk_quota::DiskQuotaConfig::default(),
             },
             parsers: ParsersConfig {
                 parsers: vec![
                     ParserDefinition {
                         name: "syslog_rfc3164".to_string(),
                         source_type: "syslog".to_string(),
+                        parser_type: ParserType::Regex,
                         regex_pattern: r"^<(?P<priority>\d+)>(?P<timestamp>\w+\s+\d+\s+\d+:\d+:\d+)\s+(?P<hostname>\S+)\s+(?P<tag>\w+):\s*(?P<message>.*)$".to_string(),
                         field_mappings: HashMap::from([
                             ("priority".to_string(), "syslog.priority".to_string()),
@@ -305,19 +1587,45 @@ impl Default for AgentConfig {
                             ("tag".to_string(), "process.name".to_string()),
                             ("message".to_string(), "message".to_string()),
                         ]),
+                        processors: Vec::new(),
+                        json: Default::default(),
+                        schema: Default::default(),
                     }
                 ],
+                chains: HashMap::new(),
+                source_chains: HashMap::new(),
+                sample_capture: crate::parsers::samples::SampleCaptureConfig::default(),
+                timestamps: crate::parsers::timestamps::TimestampConfig::default(),
             },
             management: ManagementConfig {
                 enabled: true,
                 bind_address: "127.0.0.1".to_string(),
                 port: 9090,
                 auth_token: Some("securewatch-token".to_string()),
+                tls: crate::management_tls::ManagementTlsConfig::default(),
             },
             resource_monitor: crate::resource_monitor::ResourceMonitorConfig::default(),
             throttle: crate::throttle::ThrottleConfig::default(),
             emergency_shutdown: crate::emergency_shutdown::EmergencyShutdownConfig::default(),
             security: crate::security::SecurityConfig::default(),
+            process_lineage: crate::process_lineage::ProcessLineageConfig::default(),
+            field_filter: crate::field_filter::FieldFilterConfig::default(),
+            dedup: crate::dedup::DedupConfig::default(),
+            relay: crate::relay::RelayConfig::default(),
+            aggregator: crate::aggregator::AggregatorConfig::default(),
+            integrity: crate::integrity::IntegrityConfig::default(),
+            event_index: crate::event_index::EventIndexConfig::default(),
+            shutdown_drain: crate::shutdown_drain::ShutdownDrainConfig::default(),
+            alert_rules: crate::alert_rules::AlertRulesConfig::default(),
+            ecs: crate::ecs::EcsConfig::default(),
+            enrichment: crate::enrichment::EnrichmentConfig::default(),
+            sigma: crate::sigma::SigmaConfig::default(),
+            self_telemetry: crate::self_telemetry::SelfTelemetryConfig::default(),
+            load_shedding: crate::load_shedding::LoadSheddingConfig::default(),
+            error_events: crate::error_events::ErrorEventsConfig::default(),
+            admin_audit: crate::admin_audit::AdminAuditConfig::default(),
+            live_tail: crate::live_tail::LiveTailConfig::default(),
+            control_socket: crate::control_socket::ControlSocketConfig::default(),
         }
     }
 }
@@ -336,6 +1644,7 @@ impl AgentConfig {
     pub async fn save_to_file(&self, path: &str) -> Result<(), ConfigError> {
         let content = toml::to_string_pretty(self)
             .map_err(|e| ConfigError::Serialize(e.to_string()))?;
+        let content = crate::chaos::corrupt_config_write(content);
         
         tokio::fs::write(path, content).await
             .map_err(|e| ConfigError::Io(e.to_string()))?;
@@ -403,18 +1712,27 @@ impl AgentConfig {
                         },
                         "api_key": {
                             "type": "string",
-                            "minLength": 16,
-                            "maxLength": 256,
+                            "anyOf": [
+                                { "minLength": 16, "maxLength": 256 },
+                                { "pattern": "^keyring:[A-Za-z0-9_@-][A-Za-z0-9._@-]*/[A-Za-z0-9_@-][A-Za-z0-9._@-]*$" }
+                            ],
                             "not": { "enum": ["your-api-key", "test-key", ""] },
-                            "description": "API key for authentication (16-256 chars, not default value)"
+                            "description": "API key for authentication (16-256 chars, not default value), or keyring:<service>/<account> to read it from the OS keyring"
                         },
                         "tls_verify": {
                             "type": "boolean",
                             "description": "Enable TLS certificate verification"
                         },
                         "compression": {
-                            "type": "boolean",
-                            "description": "Enable HTTP compression"
+                            "type": ["string", "boolean"],
+                            "enum": ["none", "gzip", "zstd", "brotli", true, false],
+                            "description": "Payload Content-Encoding (true = zstd, false = none)"
+                        },
+                        "compression_level": {
+                            "type": ["integer", "null"],
+                            "minimum": 0,
+                            "maximum": 22,
+                            "description": "Compression level (gzip 0-9, zstd 1-22, brotli 0-11)"
                         },
                         "batch_size": {
                             "type": "integer",
@@ -440,6 +1758,58 @@ impl AgentConfig {
                             "maximum": 60,
                             "description": "Retry delay in seconds (1-60)"
                         },
+                        "retry_policy": {
+                            "type": "object",
+                            "properties": {
+                                "jitter": { "type": "string", "enum": ["none", "proportional", "decorrelated"] },
+                                "max_delay_secs": { "type": "integer", "minimum": 1 },
+                                "budget_ratio": { "type": "number", "minimum": 0, "maximum": 10 },
+                                "budget_min_per_second": { "type": "integer", "minimum": 0 }
+                            },
+                            "description": "Retry jitter, delay cap and the share of requests that may be retries"
+                        },
+                        "bandwidth": {
+                            "type": "object",
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "max_kb_per_second": { "type": "integer", "minimum": 1 },
+                                "burst_kb": { "type": "integer", "minimum": 1 }
+                            },
+                            "description": "Upload bandwidth cap across all endpoints, in KB/s with a burst in KB"
+                        },
+                        "schedule": {
+                            "type": "object",
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "utc": { "type": "boolean" },
+                                "windows": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "properties": {
+                                            "days": { "type": "array", "items": { "type": "string" } },
+                                            "start": { "type": "string", "pattern": "^[0-2][0-9]:[0-5][0-9]$" },
+                                            "end": { "type": "string", "pattern": "^[0-2][0-9]:[0-5][0-9]$" }
+                                        },
+                                        "required": ["start", "end"]
+                                    }
+                                },
+                                "required_interfaces": { "type": "array", "items": { "type": "string" } },
+                                "metered_interfaces": { "type": "array", "items": { "type": "string" } }
+                            },
+                            "description": "Upload windows and network conditions; events are buffered outside them"
+                        },
+                        "proxy": {
+                            "type": "object",
+                            "properties": {
+                                "mode": { "type": "string", "enum": ["auto", "manual", "direct"] },
+                                "url": { "type": ["string", "null"] },
+                                "username": { "type": ["string", "null"] },
+                                "password": { "type": ["string", "null"] },
+                                "no_proxy": { "type": "array", "items": { "type": "string" } }
+                            },
+                            "description": "HTTP, HTTPS or SOCKS5 proxy, configured or discovered from the environment and OS settings, with optional Basic credentials and bypassed hosts"
+                        },
                         "client_cert_path": {
                             "type": ["string", "null"],
                             "description": "Path to client certificate for mTLS"
@@ -450,7 +1820,7 @@ impl AgentConfig {
                         },
                         "client_key_password": {
                             "type": ["string", "null"],
-                            "description": "Password for encrypted client key"
+                            "description": "Password for encrypted client key, or keyring:<service>/<account> to read it from the OS keyring"
                         },
                         "ca_cert_path": {
                             "type": ["string", "null"],
@@ -461,6 +1831,152 @@ impl AgentConfig {
                             "minimum": 1,
                             "maximum": 365,
                             "description": "Days before certificate expiry to warn (1-365)"
+                        },
+                        "identity": {
+                            "type": "object",
+                            "properties": {
+                                "watch_files": { "type": "boolean" },
+                                "spiffe": {
+                                    "type": ["object", "null"],
+                                    "properties": {
+                                        "endpoint_socket": { "type": ["string", "null"], "pattern": "^unix:///" },
+                                        "spiffe_id": { "type": ["string", "null"], "pattern": "^spiffe://" },
+                                        "trust_bundle": { "type": "boolean" },
+                                        "reconnect_delay_seconds": { "type": "integer", "minimum": 1 }
+                                    }
+                                }
+                            },
+                            "description": "Client certificate reload and SPIFFE Workload API identity"
+                        },
+                        "enrollment": {
+                            "type": "object",
+                            "properties": {
+                                "token": { "type": ["string", "null"] },
+                                "token_env": { "type": "string" },
+                                "endpoint": { "type": ["string", "null"], "pattern": "^https?://" },
+                                "state_dir": { "type": "string", "minLength": 1 },
+                                "request_certificate": { "type": "boolean" },
+                                "timeout_seconds": { "type": "integer", "minimum": 1 }
+                            },
+                            "description": "Enrollment token exchange that issues the agent ID, API key and client certificate"
+                        },
+                        "protocol": {
+                            "type": "string",
+                            "enum": ["native", "otlp_http", "otlp_grpc", "splunk_hec", "elasticsearch"],
+                            "description": "Native SecureWatch endpoint, an OpenTelemetry collector, Splunk HEC or Elasticsearch/OpenSearch"
+                        },
+                        "otlp": {
+                            "type": "object",
+                            "properties": {
+                                "endpoint": { "type": ["string", "null"], "pattern": "^https?://" },
+                                "encoding": { "type": "string", "enum": ["protobuf", "json"] },
+                                "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                                "send_api_key": { "type": "boolean" },
+                                "service_name": { "type": "string", "minLength": 1 },
+                                "resource_attributes": { "type": "object", "additionalProperties": { "type": "string" } },
+                                "include_raw_data": { "type": "boolean" }
+                            }
+                        },
+                        "syslog": {
+                            "type": "object",
+                            "properties": {
+                                "app_name": { "type": "string", "minLength": 1, "maxLength": 48 },
+                                "facility": {
+                                    "type": "string",
+                                    "enum": ["kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron", "authpriv", "ftp",
+                                             "ntp", "security", "console", "solaris-cron", "local0", "local1", "local2", "local3", "local4",
+                                             "local5", "local6", "local7"]
+                                },
+                                "hostname": { "type": ["string", "null"] },
+                                "structured_data_id": { "type": "string", "maxLength": 32 },
+                                "max_message_bytes": { "type": "integer", "minimum": 480 },
+                                "octet_counting": { "type": "boolean" },
+                                "timeout_seconds": { "type": "integer", "minimum": 1 }
+                            },
+                            "description": "RFC 5424 message settings for destinations with protocol = \"syslog\""
+                        },
+                        "splunk_hec": {
+                            "type": "object",
+                            "properties": {
+                                "endpoint": { "type": ["string", "null"], "pattern": "^https?://" },
+                                "index": { "type": "string" },
+                                "source": { "type": "string" },
+                                "sourcetype": { "type": "string", "minLength": 1 },
+                                "host": { "type": ["string", "null"] },
+                                "indexed_fields": { "type": "array", "items": { "type": "string" } },
+                                "include_raw_data": { "type": "boolean" }
+                            },
+                            "description": "HEC metadata templates; placeholders are {source}, {parser}, {level}, {<field>|<default>} and {date:<strftime>}"
+                        },
+                        "elasticsearch": {
+                            "type": "object",
+                            "properties": {
+                                "endpoint": { "type": ["string", "null"], "pattern": "^https?://" },
+                                "index": { "type": "string", "minLength": 1 },
+                                "operation": { "type": "string", "enum": ["create", "index"] },
+                                "pipeline": { "type": ["string", "null"], "minLength": 1 },
+                                "content_ids": { "type": "boolean" },
+                                "username": { "type": ["string", "null"] },
+                                "include_raw_data": { "type": "boolean" }
+                            },
+                            "description": "Bulk index template and settings for Elasticsearch or OpenSearch"
+                        },
+                        "format": {
+                            "type": "string",
+                            "enum": ["json", "ndjson", "protobuf", "msgpack"],
+                            "description": "Batch serialization for the native protocol"
+                        },
+                        "delivery": {
+                            "type": "object",
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "max_tracked_ranges": { "type": "integer", "minimum": 1 }
+                            },
+                            "description": "ULIDs in event.id and server-acknowledged ID ranges, so retries don't duplicate stored events"
+                        },
+                        "primary_route": {
+                            "type": "string",
+                            "enum": ["unmatched", "all", "disabled"],
+                            "description": "Which events server_url keeps receiving when destinations are configured"
+                        },
+                        "destinations": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["name", "server_url"],
+                                "properties": {
+                                    "name": { "type": "string", "minLength": 1 },
+                                    "server_url": { "type": "string", "pattern": "^(https?|tls|tcp)://" },
+                                    "api_key": { "type": ["string", "null"] },
+                                    "protocol": { "type": ["string", "null"], "enum": ["native", "otlp_http", "otlp_grpc", "syslog", "splunk_hec", "elasticsearch", null] },
+                                    "otlp": { "type": ["object", "null"] },
+                                    "syslog": { "type": ["object", "null"] },
+                                    "splunk_hec": { "type": ["object", "null"] },
+                                    "elasticsearch": { "type": ["object", "null"] },
+                                    "format": { "type": ["string", "null"], "enum": ["json", "ndjson", "protobuf", "msgpack", null] },
+                                    "tls_verify": { "type": ["boolean", "null"] },
+                                    "ca_cert_path": { "type": ["string", "null"] },
+                                    "client_cert_path": { "type": ["string", "null"] },
+                                    "client_key_path": { "type": ["string", "null"] },
+                                    "batch_size": { "type": ["integer", "null"], "minimum": 1 },
+                                    "retry_attempts": { "type": ["integer", "null"], "minimum": 1 },
+                                    "retry_delay": { "type": ["integer", "null"], "minimum": 0 },
+                                    "compression": { "type": ["string", "boolean", "null"], "enum": ["none", "gzip", "zstd", "brotli", true, false, null] },
+                                    "compression_level": { "type": ["integer", "null"], "minimum": 0, "maximum": 22 },
+                                    "routes": {
+                                        "type": "array",
+                                        "items": {
+                                            "type": "object",
+                                            "properties": {
+                                                "sources": { "type": "array", "items": { "type": "string" } },
+                                                "parsers": { "type": "array", "items": { "type": "string" } },
+                                                "field": { "type": ["string", "null"] },
+                                                "equals": { "type": ["string", "null"] }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
                         }
                     }
                 },
@@ -486,7 +2002,32 @@ impl AgentConfig {
                                 },
                                 "protocol": {
                                     "type": "string",
-                                    "enum": ["udp", "tcp"]
+                                    "enum": ["udp", "tcp", "both", "tls"]
+                                },
+                                "framing": {
+                                    "type": "string",
+                                    "enum": ["auto", "octet_counting", "non_transparent"]
+                                },
+                                "max_message_size": {
+                                    "type": "integer",
+                                    "minimum": 480,
+                                    "maximum": 16777216
+                                },
+                                "backpressure_receive_buffer_bytes": {
+                                    "type": "integer",
+                                    "minimum": 0
+                                },
+                                "drop_marker_interval_seconds": {
+                                    "type": "integer",
+                                    "minimum": 1
+                                },
+                                "tls": {
+                                    "type": ["object", "null"],
+                                    "properties": {
+                                        "cert_path": { "type": "string", "minLength": 1 },
+                                        "key_path": { "type": "string", "minLength": 1 }
+                                    },
+                                    "required": ["cert_path", "key_path"]
                                 }
                             }
                         },
@@ -503,6 +2044,12 @@ impl AgentConfig {
                                     "type": "integer",
                                     "minimum": 1,
                                     "maximum": 1000
+                                },
+                                "bookmark_path": { "type": ["string", "null"], "minLength": 1 },
+                                "render_messages": { "type": "boolean" },
+                                "message_locale": {
+                                    "type": ["string", "null"],
+                                    "pattern": "^[A-Za-z]{2,3}(-[A-Za-z0-9]{2,8})*$"
                                 }
                             }
                         },
@@ -520,7 +2067,251 @@ impl AgentConfig {
                                     "items": { "type": "string", "minLength": 1 },
                                     "maxItems": 50
                                 },
-                                "recursive": { "type": "boolean" }
+                                "recursive": { "type": "boolean" },
+                                "multiline": {
+                                    "type": ["object", "null"],
+                                    "properties": {
+                                        "start_pattern": { "type": ["string", "null"], "minLength": 1 },
+                                        "continuation_pattern": { "type": ["string", "null"], "minLength": 1 },
+                                        "max_lines": { "type": "integer", "minimum": 1, "maximum": 10000 },
+                                        "timeout_ms": { "type": "integer", "minimum": 1 }
+                                    }
+                                },
+                                "backfill": {
+                                    "type": "object",
+                                    "properties": {
+                                        "enabled": { "type": "boolean" },
+                                        "paths": {
+                                            "type": "array",
+                                            "items": { "type": "string", "minLength": 1 },
+                                            "maxItems": 100
+                                        },
+                                        "formats": {
+                                            "type": "array",
+                                            "items": { "type": "string", "enum": ["gz", "zip", "bz2"] },
+                                            "uniqueItems": true
+                                        },
+                                        "checkpoint_path": { "type": "string", "minLength": 1 },
+                                        "checkpoint_interval": { "type": "integer", "minimum": 1 }
+                                    }
+                                }
+                            }
+                        },
+                        "ebpf": {
+                            "type": ["object", "null"],
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "probe_path": { "type": "string", "minLength": 1 },
+                                "process_exec": { "type": "boolean" },
+                                "network_connect": { "type": "boolean" },
+                                "file_open": { "type": "boolean" },
+                                "file_open_writes_only": { "type": "boolean" },
+                                "file_path_prefixes": {
+                                    "type": "array",
+                                    "items": { "type": "string", "minLength": 1 },
+                                    "maxItems": 100
+                                },
+                                "exclude_processes": {
+                                    "type": "array",
+                                    "items": { "type": "string", "minLength": 1, "maxLength": 15 },
+                                    "maxItems": 100
+                                },
+                                "ring_buffer_kb": { "type": "integer", "minimum": 4, "maximum": 1048576 },
+                                "max_events_per_second": { "type": "integer", "minimum": 1 }
+                            }
+                        },
+                        "etw": {
+                            "type": ["object", "null"],
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "session_name": { "type": "string", "minLength": 1, "maxLength": 1024 },
+                                "providers": {
+                                    "type": "array",
+                                    "maxItems": 64,
+                                    "items": {
+                                        "type": "object",
+                                        "required": ["name"],
+                                        "properties": {
+                                            "name": { "type": "string", "minLength": 1 },
+                                            "guid": { "type": ["string", "null"] },
+                                            "level": { "type": "integer", "minimum": 1, "maximum": 5 },
+                                            "match_any_keyword": { "type": "integer", "minimum": 0 },
+                                            "match_all_keyword": { "type": "integer", "minimum": 0 },
+                                            "event_ids": {
+                                                "type": "array",
+                                                "items": { "type": "integer", "minimum": 0, "maximum": 65535 }
+                                            }
+                                        }
+                                    }
+                                },
+                                "buffer_size_kb": { "type": "integer", "minimum": 4, "maximum": 16384 },
+                                "max_events_per_second": { "type": "integer", "minimum": 1 }
+                            }
+                        },
+                        "fim": {
+                            "type": ["object", "null"],
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "paths": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                                "recursive": { "type": "boolean" },
+                                "exclude": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                                "hash_algorithm": { "type": "string", "enum": ["sha256", "sha512", "sha1"] },
+                                "max_file_size_bytes": { "type": "integer", "minimum": 0 },
+                                "max_files": { "type": "integer", "minimum": 1 },
+                                "realtime": { "type": "boolean" },
+                                "scan_interval_seconds": { "type": "integer", "minimum": 60 },
+                                "baseline_path": { "type": "string", "minLength": 1 }
+                            }
+                        },
+                        "honeytoken": {
+                            "type": ["object", "null"],
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "files": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "required": ["path"],
+                                        "properties": {
+                                            "path": { "type": "string", "minLength": 1 },
+                                            "content": { "type": ["string", "null"] }
+                                        }
+                                    }
+                                },
+                                "registry_keys": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "required": ["key"],
+                                        "properties": {
+                                            "key": { "type": "string", "minLength": 1 },
+                                            "value_name": { "type": "string", "minLength": 1 },
+                                            "value_data": { "type": ["string", "null"] }
+                                        }
+                                    }
+                                },
+                                "redeploy": { "type": "boolean" },
+                                "ignore_processes": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                                "poll_interval_seconds": { "type": "integer", "minimum": 1 }
+                            }
+                        },
+                        "command": {
+                            "type": ["object", "null"],
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "allowed_programs": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                                "commands": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "required": ["name", "program", "interval_seconds"],
+                                        "properties": {
+                                            "name": { "type": "string", "minLength": 1 },
+                                            "program": { "type": "string", "minLength": 1 },
+                                            "args": { "type": "array", "items": { "type": "string" } },
+                                            "interval_seconds": { "type": "integer", "minimum": 10 },
+                                            "timeout_seconds": { "type": "integer", "minimum": 1 },
+                                            "source": { "type": "string", "minLength": 1 },
+                                            "output": { "type": "string", "enum": ["lines", "whole"] },
+                                            "working_directory": { "type": ["string", "null"] },
+                                            "skip_lines": { "type": "integer", "minimum": 0 }
+                                        }
+                                    }
+                                },
+                                "max_concurrent": { "type": "integer", "minimum": 1 },
+                                "max_output_bytes": { "type": "integer", "minimum": 1024 }
+                            }
+                        },
+                        "http_pull": {
+                            "type": ["object", "null"],
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "state_path": { "type": "string", "minLength": 1 },
+                                "timeout_seconds": { "type": "integer", "minimum": 1 },
+                                "tls_verify": { "type": "boolean" },
+                                "endpoints": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "required": ["name", "url"],
+                                        "properties": {
+                                            "name": { "type": "string", "minLength": 1 },
+                                            "url": { "type": "string", "format": "uri" },
+                                            "query": { "type": "object", "additionalProperties": { "type": "string" } },
+                                            "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                                            "interval_seconds": { "type": "integer", "minimum": 10 },
+                                            "source": { "type": "string", "minLength": 1 },
+                                            "records_path": { "type": ["string", "null"] },
+                                            "auth": {
+                                                "type": "object",
+                                                "properties": {
+                                                    "type": { "type": "string", "enum": ["none", "bearer", "header", "basic", "oauth2"] }
+                                                }
+                                            },
+                                            "pagination": {
+                                                "type": "object",
+                                                "properties": {
+                                                    "type": { "type": "string", "enum": ["none", "link_header", "next_url", "cursor"] }
+                                                }
+                                            },
+                                            "cursor": {
+                                                "type": ["object", "null"],
+                                                "properties": {
+                                                    "field": { "type": "string", "minLength": 1 },
+                                                    "initial": { "type": ["string", "null"] }
+                                                }
+                                            },
+                                            "max_pages": { "type": "integer", "minimum": 1 },
+                                            "timestamp_field": { "type": ["string", "null"] }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "packet_metadata": {
+                            "type": ["object", "null"],
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "interface": { "type": "string", "minLength": 1 },
+                                "promiscuous": { "type": "boolean" },
+                                "bpf_filter": { "type": "string" },
+                                "snaplen": { "type": "integer", "minimum": 256, "maximum": 65535 },
+                                "dns": { "type": "boolean" },
+                                "tls": { "type": "boolean" },
+                                "http": { "type": "boolean" },
+                                "connections": { "type": "boolean" },
+                                "max_events_per_second": { "type": "integer", "minimum": 1 }
+                            }
+                        },
+                        "container": {
+                            "type": ["object", "null"],
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "log_paths": {
+                                    "type": "array",
+                                    "items": { "type": "string", "minLength": 1 },
+                                    "maxItems": 100
+                                },
+                                "format": { "type": "string", "enum": ["auto", "cri", "docker"] },
+                                "poll_interval_ms": { "type": "integer", "minimum": 100 },
+                                "read_from_start": { "type": "boolean" },
+                                "exclude_namespaces": {
+                                    "type": "array",
+                                    "items": { "type": "string", "minLength": 1 }
+                                },
+                                "max_line_bytes": { "type": "integer", "minimum": 1024 },
+                                "kubelet": {
+                                    "type": "object",
+                                    "properties": {
+                                        "enabled": { "type": "boolean" },
+                                        "url": { "type": "string", "pattern": "^https?://" },
+                                        "token_path": { "type": "string" },
+                                        "ca_cert_path": { "type": ["string", "null"] },
+                                        "tls_verify": { "type": "boolean" },
+                                        "refresh_interval_secs": { "type": "integer", "minimum": 1 },
+                                        "timeout_seconds": { "type": "integer", "minimum": 1 }
+                                    }
+                                }
                             }
                         }
                     }
@@ -553,6 +2344,51 @@ impl AgentConfig {
                             "type": "string",
                             "minLength": 1,
                             "description": "Path for persistent buffer storage"
+                        },
+                        "newer_events": {
+                            "type": "string",
+                            "enum": ["skip", "quarantine", "fail"],
+                            "description": "Handling of buffered events written by a newer agent version"
+                        },
+                        "write_batch_size": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": 10000,
+                            "description": "Events written to disk per batched INSERT (1 disables batching)"
+                        },
+                        "write_batch_interval_ms": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": 10000,
+                            "description": "Longest a partial write batch waits before it is written, in milliseconds"
+                        },
+                        "encryption": {
+                            "type": "object",
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "key_source": {
+                                    "type": "object",
+                                    "required": ["type"],
+                                    "properties": {
+                                        "type": { "type": "string", "enum": ["env", "file", "passphrase"] },
+                                        "variable": { "type": "string", "minLength": 1 },
+                                        "path": { "type": "string", "minLength": 1 },
+                                        "iterations": { "type": "integer", "minimum": 10000 }
+                                    }
+                                }
+                            },
+                            "description": "AES-256-GCM encryption of buffered event payloads at rest"
+                        },
+                        "disk_quota": {
+                            "type": "object",
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "throttle_free_mb": { "type": "integer", "minimum": 0 },
+                                "throttle_free_percent": { "type": "number", "minimum": 0, "maximum": 100 },
+                                "stop_free_mb": { "type": "integer", "minimum": 0 },
+                                "stop_free_percent": { "type": "number", "minimum": 0, "maximum": 100 }
+                            },
+                            "description": "Throttle, then stop, disk buffering when the buffer's volume runs low on free space"
                         }
                     }
                 },
@@ -564,7 +2400,7 @@ impl AgentConfig {
                             "type": "array",
                             "items": {
                                 "type": "object",
-                                "required": ["name", "source_type", "regex_pattern", "field_mappings"],
+                                "required": ["name", "source_type"],
                                 "properties": {
                                     "name": {
                                         "type": "string",
@@ -577,18 +2413,121 @@ impl AgentConfig {
                                         "minLength": 1,
                                         "maxLength": 32
                                     },
+                                    "type": {
+                                        "type": "string",
+                                        "enum": ["regex", "json"]
+                                    },
                                     "regex_pattern": {
                                         "type": "string",
                                         "minLength": 1,
                                         "maxLength": 2048,
                                         "description": "Valid regex pattern for parsing"
                                     },
+                                    "json": {
+                                        "type": "object",
+                                        "properties": {
+                                            "flatten": { "type": "boolean" },
+                                            "separator": { "type": "string", "minLength": 1 },
+                                            "max_depth": { "type": "integer", "minimum": 1, "maximum": 64 },
+                                            "keep_unmapped": { "type": "boolean" },
+                                            "timestamp_field": { "type": "string", "minLength": 1 },
+                                            "timestamp_format": { "type": "string", "minLength": 1 },
+                                            "level_field": { "type": "string", "minLength": 1 },
+                                            "message_field": { "type": "string", "minLength": 1 }
+                                        },
+                                        "description": "Options for JSON / NDJSON parsers"
+                                    },
                                     "field_mappings": {
                                         "type": "object",
                                         "additionalProperties": { "type": "string" }
+                                    },
+                                    "processors": {
+                                        "type": "array",
+                                        "items": { "type": "string", "minLength": 1 }
+                                    },
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": {
+                                            "fields": {
+                                                "type": "object",
+                                                "additionalProperties": {
+                                                    "oneOf": [
+                                                        { "type": "string", "enum": ["string", "integer", "float", "boolean", "ip", "timestamp", "enum"] },
+                                                        {
+                                                            "type": "object",
+                                                            "required": ["type"],
+                                                            "properties": {
+                                                                "type": { "type": "string", "enum": ["string", "integer", "float", "boolean", "ip", "timestamp", "enum"] },
+                                                                "values": { "type": "array", "items": { "type": "string" } },
+                                                                "required": { "type": "boolean" }
+                                                            }
+                                                        }
+                                                    ]
+                                                }
+                                            },
+                                            "on_violation": { "type": "string", "enum": ["keep", "remove"] }
+                                        },
+                                        "description": "Expected field types; values that can't be coerced are listed in schema.violations"
+                                    }
+                                }
+                            }
+                        },
+                        "chains": {
+                            "type": "object",
+                            "additionalProperties": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "required": ["type"],
+                                    "properties": {
+                                        "type": {
+                                            "type": "string",
+                                            "enum": ["rename", "copy", "set", "remove", "lowercase", "redact", "hash", "mask", "convert", "extract", "route", "if", "chain"]
+                                        }
                                     }
                                 }
                             }
+                        },
+                        "source_chains": {
+                            "type": "object",
+                            "additionalProperties": {
+                                "type": "array",
+                                "items": { "type": "string" }
+                            }
+                        },
+                        "sample_capture": {
+                            "type": "object",
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "directory": { "type": "string", "minLength": 1 },
+                                "max_samples_per_source": { "type": "integer", "minimum": 1, "maximum": 10000 },
+                                "max_bytes_per_source": { "type": "integer", "minimum": 1 },
+                                "max_event_bytes": { "type": "integer", "minimum": 1 },
+                                "flush_interval_seconds": { "type": "integer", "minimum": 1 }
+                            }
+                        },
+                        "timestamps": {
+                            "type": "object",
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "default_timezone": { "type": "string", "minLength": 1 },
+                                "record_receive_time": { "type": "boolean" },
+                                "rules": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "properties": {
+                                            "sources": { "type": "array", "items": { "type": "string" } },
+                                            "parsers": { "type": "array", "items": { "type": "string" } },
+                                            "fields": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                                            "formats": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                                            "timezone": { "type": ["string", "null"] },
+                                            "locale": { "type": ["string", "null"] }
+                                        }
+                                    }
+                                }
+                            },
+                            "description": "Event time extraction; timezones are IANA names, UTC, local or fixed offsets"
                         }
                     }
                 },
@@ -616,6 +2555,375 @@ impl AgentConfig {
                             "minLength": 16,
                             "maxLength": 128,
                             "description": "Authentication token for management API"
+                        },
+                        "tls": {
+                            "type": "object",
+                            "properties": {
+                                "enabled": { "type": "boolean" },
+                                "cert_dir": { "type": "string", "minLength": 1 },
+                                "subject_alt_names": {
+                                    "type": "array",
+                                    "items": { "type": "string", "minLength": 1 }
+                                },
+                                "ca_cert_path": { "type": ["string", "null"] },
+                                "ca_key_path": { "type": ["string", "null"] },
+                                "validity_days": {
+                                    "type": "integer",
+                                    "minimum": 1,
+                                    "maximum": 825,
+                                    "description": "Lifetime of generated management certificates"
+                                },
+                                "renew_before_days": { "type": "integer", "minimum": 0 },
+                                "check_interval_seconds": { "type": "integer", "minimum": 1 }
+                            }
+                        }
+                    }
+                },
+                "dedup": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "database_path": { "type": "string", "minLength": 1 },
+                        "window_seconds": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": 604800,
+                            "description": "Duplicate window in seconds (max 7 days)"
+                        },
+                        "max_entries": { "type": "integer", "minimum": 1 },
+                        "purge_interval_seconds": { "type": "integer", "minimum": 1 }
+                    }
+                },
+                "process_lineage": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "max_entries": { "type": "integer", "minimum": 1 },
+                        "ttl_seconds": { "type": "integer", "minimum": 0 },
+                        "purge_interval_seconds": { "type": "integer", "minimum": 1 },
+                        "refresh_interval_seconds": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Process table refresh interval in seconds (0 = learn from events only)"
+                        },
+                        "max_depth": { "type": "integer", "minimum": 1, "maximum": 64 },
+                        "sources": {
+                            "type": "array",
+                            "items": { "type": "string", "minLength": 1 }
+                        }
+                    }
+                },
+                "relay": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "listen_address": { "type": "string", "minLength": 1 },
+                        "relay_id": { "type": "string", "minLength": 1 },
+                        "max_hops": { "type": "integer", "minimum": 1, "maximum": 16 },
+                        "max_frame_bytes": { "type": "integer", "minimum": 1024 },
+                        "max_connections": { "type": "integer", "minimum": 1 },
+                        "max_clock_skew_seconds": { "type": "integer", "minimum": 1 },
+                        "handshake_timeout_seconds": { "type": "integer", "minimum": 1 },
+                        "read_timeout_seconds": { "type": "integer", "minimum": 1 },
+                        "idle_timeout_seconds": { "type": "integer", "minimum": 1 },
+                        "peers": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["id", "key"],
+                                "properties": {
+                                    "id": { "type": "string", "minLength": 1, "maxLength": 256 },
+                                    "key": { "type": "string", "minLength": 1 },
+                                    "max_events_per_minute": { "type": "integer", "minimum": 1 },
+                                    "max_bytes_per_minute": { "type": "integer", "minimum": 1 }
+                                }
+                            },
+                            "description": "Peer agents allowed to relay through this agent"
+                        },
+                        "upstream": {
+                            "type": "object",
+                            "required": ["address", "peer_id", "key"],
+                            "properties": {
+                                "address": { "type": "string", "minLength": 1 },
+                                "peer_id": { "type": "string", "minLength": 1 },
+                                "key": { "type": "string", "minLength": 1 },
+                                "timeout_seconds": { "type": "integer", "minimum": 1 }
+                            },
+                            "description": "Relay agent to ship batches through instead of the server"
+                        }
+                    }
+                },
+                "aggregator": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "listen_address": { "type": "string", "minLength": 1 },
+                        "aggregator_id": { "type": "string", "minLength": 1 },
+                        "tls": {
+                            "type": "object",
+                            "required": ["cert_path", "key_path"],
+                            "properties": {
+                                "cert_path": { "type": "string", "minLength": 1 },
+                                "key_path": { "type": "string", "minLength": 1 }
+                            }
+                        },
+                        "clients": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["name", "api_key"],
+                                "properties": {
+                                    "name": { "type": "string", "minLength": 1 },
+                                    "api_key": { "type": "string", "minLength": 16 }
+                                }
+                            },
+                            "description": "Agents allowed to send their batches to this aggregator"
+                        },
+                        "max_body_bytes": { "type": "integer", "minimum": 1 },
+                        "max_decoded_bytes": { "type": "integer", "minimum": 1 },
+                        "max_events_per_request": { "type": "integer", "minimum": 1 },
+                        "max_connections": { "type": "integer", "minimum": 1 },
+                        "idle_timeout_seconds": { "type": "integer", "minimum": 1 }
+                    }
+                },
+                "integrity": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "event_hashes": { "type": "boolean", "description": "Stamp event.hash before events are buffered" },
+                        "sign": { "type": "boolean", "description": "Sign batch digests with the Ed25519 key from the credential store" },
+                        "signing_key_id": { "type": "string", "minLength": 1 }
+                    }
+                },
+                "event_index": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "database_path": { "type": "string", "minLength": 1 },
+                        "archive_paths": {
+                            "type": "array",
+                            "items": { "type": "string", "minLength": 1 },
+                            "description": "Glob patterns of NDJSON event archives to index"
+                        },
+                        "max_events_per_second": { "type": "integer", "minimum": 1 },
+                        "max_pending_events": { "type": "integer", "minimum": 1 },
+                        "max_text_bytes": { "type": "integer", "minimum": 64 },
+                        "max_indexed_events": { "type": "integer", "minimum": 1 },
+                        "max_index_size_mb": { "type": "integer", "minimum": 1 },
+                        "retention_hours": { "type": "integer", "minimum": 0 },
+                        "max_archive_events_per_pass": { "type": "integer", "minimum": 0 },
+                        "commit_interval_ms": { "type": "integer", "minimum": 1 },
+                        "max_results": { "type": "integer", "minimum": 1 }
+                    }
+                },
+                "shutdown_drain": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "deadline_seconds": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": 600,
+                            "description": "Time allowed for shipping priority events on stop"
+                        },
+                        "priority_levels": { "type": "array", "items": { "type": "string" } },
+                        "priority_sources": { "type": "array", "items": { "type": "string" } },
+                        "include_persisted": { "type": "boolean" },
+                        "max_persisted_events": { "type": "integer", "minimum": 0 }
+                    }
+                },
+                "alert_rules": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "script_allowlist": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                        "action_timeout_seconds": { "type": "integer", "minimum": 1, "maximum": 300 },
+                        "max_actions_per_minute": { "type": "integer", "minimum": 0 },
+                        "rules": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["name", "conditions"],
+                                "properties": {
+                                    "name": { "type": "string", "minLength": 1 },
+                                    "description": { "type": ["string", "null"] },
+                                    "severity": { "type": "string", "enum": ["low", "medium", "high", "critical"] },
+                                    "sources": { "type": "array", "items": { "type": "string" } },
+                                    "parsers": { "type": "array", "items": { "type": "string" } },
+                                    "conditions": {
+                                        "type": "array",
+                                        "minItems": 1,
+                                        "items": {
+                                            "type": "object",
+                                            "required": ["field"],
+                                            "properties": {
+                                                "field": { "type": "string", "minLength": 1 },
+                                                "equals": { "type": ["string", "null"] },
+                                                "contains": { "type": ["string", "null"] },
+                                                "regex": { "type": ["string", "null"] },
+                                                "one_of": { "type": "array", "items": { "type": "string" } }
+                                            }
+                                        }
+                                    },
+                                    "action": {
+                                        "type": ["object", "null"],
+                                        "required": ["type"],
+                                        "properties": {
+                                            "type": { "type": "string", "enum": ["webhook", "script"] },
+                                            "url": { "type": "string", "pattern": "^https?://" },
+                                            "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                                            "path": { "type": "string", "minLength": 1 },
+                                            "args": { "type": "array", "items": { "type": "string" } }
+                                        }
+                                    },
+                                    "cooldown_seconds": { "type": "integer", "minimum": 0 }
+                                }
+                            }
+                        }
+                    }
+                },
+                "enrichment": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "enrichers": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["type"],
+                                "properties": {
+                                    "type": { "type": "string", "enum": ["geoip", "reverse_dns", "host", "labels", "threat_intel"] },
+                                    "name": { "type": ["string", "null"] },
+                                    "sources": { "type": "array", "items": { "type": "string" } },
+                                    "database_path": { "type": "string", "minLength": 1 },
+                                    "fields": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                                    "cache_size": { "type": "integer", "minimum": 1 },
+                                    "cache_ttl_seconds": { "type": "integer", "minimum": 0 },
+                                    "timeout_ms": { "type": "integer", "minimum": 1, "maximum": 10000 },
+                                    "overwrite": { "type": "boolean" },
+                                    "labels": { "type": "object", "additionalProperties": { "type": "string" } },
+                                    "feeds": {
+                                        "type": "array",
+                                        "items": {
+                                            "type": "object",
+                                            "required": ["name", "url", "format"],
+                                            "properties": {
+                                                "name": { "type": "string", "pattern": "^[A-Za-z0-9_-]+$" },
+                                                "url": { "type": "string", "minLength": 1 },
+                                                "format": { "type": "string", "enum": ["stix", "taxii", "csv"] },
+                                                "auth": { "type": "object" },
+                                                "column": { "type": ["string", "null"] },
+                                                "description_column": { "type": ["string", "null"] }
+                                            }
+                                        }
+                                    },
+                                    "refresh_interval_seconds": { "type": "integer", "minimum": 60 },
+                                    "timeout_seconds": { "type": "integer", "minimum": 1 },
+                                    "max_feed_bytes": { "type": "integer", "minimum": 1 },
+                                    "cache_directory": { "type": "string", "minLength": 1 },
+                                    "ip_fields": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                                    "domain_fields": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                                    "hash_fields": { "type": "array", "items": { "type": "string", "minLength": 1 } }
+                                }
+                            }
+                        }
+                    }
+                },
+                "ecs": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "mapping_file": { "type": ["string", "null"], "minLength": 1 },
+                        "builtin_aliases": { "type": "boolean" },
+                        "keep_original": { "type": "boolean" }
+                    }
+                },
+                "sigma": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "rule_paths": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                        "output": { "type": "string", "enum": ["tag", "alert_event", "both"] },
+                        "min_level": { "type": "string", "enum": ["informational", "low", "medium", "high", "critical"] },
+                        "logsources": { "type": "object", "additionalProperties": { "type": "array", "items": { "type": "string" } } },
+                        "field_mapping": { "type": "object", "additionalProperties": { "type": "string" } }
+                    }
+                },
+                "self_telemetry": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "interval_seconds": { "type": "integer", "minimum": 10, "maximum": 86400 },
+                        "source": { "type": "string", "minLength": 1 },
+                        "include_resources": { "type": "boolean" },
+                        "include_pipeline_latency": { "type": "boolean" }
+                    }
+                },
+                "load_shedding": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "escalate_after_samples": { "type": "integer", "minimum": 1 },
+                        "recover_after_samples": { "type": "integer", "minimum": 1 },
+                        "recovery_margin_percent": { "type": "number", "minimum": 0, "exclusiveMaximum": 100 },
+                        "batch_size_factor": { "type": "number", "exclusiveMinimum": 0, "maximum": 1 },
+                        "sample_every": { "type": "integer", "minimum": 1 },
+                        "sampled_sources": { "type": "array", "items": { "type": "string" } },
+                        "low_priority_collectors": { "type": "array", "items": { "type": "string" } }
+                    }
+                },
+                "error_events": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "source": { "type": "string", "minLength": 1 },
+                        "window_seconds": { "type": "integer", "minimum": 1, "maximum": 86400 },
+                        "max_events_per_code": { "type": "integer", "minimum": 1 },
+                        "max_pending": { "type": "integer", "minimum": 1 }
+                    }
+                },
+                "admin_audit": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "path": { "type": "string", "minLength": 1 }
+                    }
+                },
+                "live_tail": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "socket_path": { "type": "string", "minLength": 1 },
+                        "max_watchers": { "type": "integer", "minimum": 1 }
+                    }
+                },
+                "control_socket": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "socket_path": { "type": "string", "minLength": 1 }
+                    }
+                },
+                "field_filter": {
+                    "type": "object",
+                    "required": ["enabled", "rules"],
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "rules": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["name"],
+                                "properties": {
+                                    "name": { "type": "string", "minLength": 1 },
+                                    "destinations": { "type": "array", "items": { "type": "string" } },
+                                    "parsers": { "type": "array", "items": { "type": "string" } },
+                                    "allow": { "type": "array", "items": { "type": "string" } },
+                                    "deny": { "type": "array", "items": { "type": "string" } },
+                                    "drop_raw_data": { "type": "boolean" }
+                                }
+                            },
+                            "description": "Per-destination field allow/deny rules (supports * wildcards)"
                         }
                     }
                 },
@@ -687,11 +2995,15 @@ impl AgentConfig {
     /// Validate configuration using JSON schema with detailed errors
     pub fn validate_with_schema(&self) -> Result<(), ConfigError> {
         // Convert config to JSON for schema validation
-        let config_value = serde_json::to_value(self)
+        let mut config_value = serde_json::to_value(self)
             .map_err(|e| ConfigError::SerializationError {
                 operation: "config_to_json".to_string(),
                 source: Box::new(e),
             })?;
+        // An agent that enrolls has no API key in its config file yet
+        if self.transport.enrollment.provides_api_key(&self.transport.api_key) {
+            config_value["transport"]["api_key"] = serde_json::json!(crate::enrollment::PENDING_API_KEY);
+        }
         
         // Get and compile the schema
         let schema_value = Self::get_json_schema();
@@ -752,35 +3064,221 @@ impl AgentConfig {
         } else {
             format!("Validation error at '{}' ({}): {}", instance_path, schema_path, error_msg)
         }
-    }
-    
-    /// Additional custom validation rules beyond schema
-    fn validate_custom_rules(&self) -> Result<(), ConfigError> {
-        let mut errors = Vec::new();
+    }
+    
+    /// Additional custom validation rules beyond schema
+    fn validate_custom_rules(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        
+        // Validate transport configuration
+        if let Err(e) = self.validate_transport_config() {
+            errors.push(format!("Transport validation: {}", e));
+        }
+        
+        // Validate parser regex patterns
+        if let Err(e) = self.validate_parser_patterns() {
+            errors.push(format!("Parser validation: {}", e));
+        }
+        
+        // Validate buffer configuration
+        if let Err(e) = self.validate_buffer_config() {
+            errors.push(format!("Buffer validation: {}", e));
+        }
+        
+        // Validate collector configuration
+        if let Err(e) = self.validate_collector_config() {
+            errors.push(format!("Collector validation: {}", e));
+        }
+        
+        // Validate management configuration
+        if let Err(e) = self.validate_management_config() {
+            errors.push(format!("Management validation: {}", e));
+        }
+        
+        // Validate named processor chains and their references
+        for e in crate::parsers::processors::validate_chains(&self.parsers) {
+            errors.push(format!("Processor chain validation: {}", e));
+        }
+        
+        // Validate unmatched sample capture bounds
+        if self.parsers.sample_capture.enabled {
+            for e in self.parsers.sample_capture.validate() {
+                errors.push(format!("Parser sample capture validation: {}", e));
+            }
+        }
+        
+        // Audit field filter rules used for data minimization
+        for e in self.validate_field_filter_config() {
+            errors.push(format!("Field filter validation: {}", e));
+        }
+        
+        // Validate relay listener, peers and upstream
+        for e in self.relay.validate() {
+            errors.push(format!("Relay validation: {}", e));
+        }
+        
+        // Validate aggregator listener and client keys
+        for e in self.aggregator.validate() {
+            errors.push(format!("Aggregator validation: {}", e));
+        }
+        
+        // Validate batch digest and signing settings
+        for e in self.integrity.validate() {
+            errors.push(format!("Integrity validation: {}", e));
+        }
+        
+        // Validate local event index budgets
+        if self.event_index.enabled {
+            for e in self.event_index.validate() {
+                errors.push(format!("Event index validation: {}", e));
+            }
+        }
+        
+        // Validate shutdown drain deadline and priorities
+        if self.shutdown_drain.enabled {
+            for e in self.shutdown_drain.validate() {
+                errors.push(format!("Shutdown drain validation: {}", e));
+            }
+        }
+        
+        // Validate alert-on-parse rules and their local actions
+        if self.alert_rules.enabled {
+            for e in self.alert_rules.validate() {
+                errors.push(format!("Alert rule validation: {}", e));
+            }
+        }
+        
+        // Validate process lineage cache bounds and attributed sources
+        if self.process_lineage.enabled {
+            for e in self.process_lineage.validate() {
+                errors.push(format!("Process lineage validation: {}", e));
+            }
+        }
+        
+        // Validate enrichers
+        if self.enrichment.enabled {
+            for e in self.enrichment.validate() {
+                errors.push(format!("Enrichment validation: {}", e));
+            }
+        }
+        
+        // Validate the ECS mapping file location
+        if self.ecs.enabled {
+            for e in self.ecs.validate() {
+                errors.push(format!("ECS normalization validation: {}", e));
+            }
+        }
+        
+        // Validate Sigma rule paths and logsource mapping
+        if self.sigma.enabled {
+            for e in self.sigma.validate() {
+                errors.push(format!("Sigma validation: {}", e));
+            }
+        }
+        
+        // Validate agent health event settings
+        if self.self_telemetry.enabled {
+            for e in self.self_telemetry.validate() {
+                errors.push(format!("Self-telemetry validation: {}", e));
+            }
+        }
+        
+        // Validate load shedding thresholds and stages
+        if self.load_shedding.enabled {
+            for e in self.load_shedding.validate() {
+                errors.push(format!("Load shedding validation: {}", e));
+            }
+        }
+        
+        // Validate error event rate limits
+        if self.error_events.enabled {
+            for e in self.error_events.validate() {
+                errors.push(format!("Error events validation: {}", e));
+            }
+        }
+        
+        // Validate the administrative audit log location
+        if self.admin_audit.enabled {
+            for e in self.admin_audit.validate() {
+                errors.push(format!("Admin audit validation: {}", e));
+            }
+        }
         
-        // Validate transport configuration
-        if let Err(e) = self.validate_transport_config() {
-            errors.push(format!("Transport validation: {}", e));
+        // Validate the live tail socket
+        if self.live_tail.enabled {
+            for e in self.live_tail.validate() {
+                errors.push(format!("Live tail validation: {}", e));
+            }
         }
         
-        // Validate parser regex patterns
-        if let Err(e) = self.validate_parser_patterns() {
-            errors.push(format!("Parser validation: {}", e));
+        // Validate the control socket
+        if self.control_socket.enabled {
+            for e in self.control_socket.validate() {
+                errors.push(format!("Control socket validation: {}", e));
+            }
         }
         
-        // Validate buffer configuration
-        if let Err(e) = self.validate_buffer_config() {
-            errors.push(format!("Buffer validation: {}", e));
+        // Validate eBPF collector probes and limits
+        if let Some(ebpf) = &self.collectors.ebpf {
+            for e in ebpf.validate() {
+                errors.push(format!("eBPF collector validation: {}", e));
+            }
         }
         
-        // Validate collector configuration
-        if let Err(e) = self.validate_collector_config() {
-            errors.push(format!("Collector validation: {}", e));
+        // Validate ETW providers and session settings
+        if let Some(etw) = &self.collectors.etw {
+            for e in etw.validate() {
+                errors.push(format!("ETW collector validation: {}", e));
+            }
         }
         
-        // Validate management configuration
-        if let Err(e) = self.validate_management_config() {
-            errors.push(format!("Management validation: {}", e));
+        // Validate file integrity monitoring paths and scan settings
+        if let Some(fim) = &self.collectors.fim {
+            for e in fim.validate() {
+                errors.push(format!("FIM collector validation: {}", e));
+            }
+        }
+        
+        // Validate decoy paths and registry keys
+        if let Some(honeytoken) = &self.collectors.honeytoken {
+            for e in honeytoken.validate() {
+                errors.push(format!("Honeytoken collector validation: {}", e));
+            }
+        }
+        
+        // Validate the command allow-list and schedules
+        if let Some(command) = &self.collectors.command {
+            for e in command.validate() {
+                errors.push(format!("Command collector validation: {}", e));
+            }
+        }
+        
+        // Validate polled endpoints, their auth and pagination
+        if let Some(http_pull) = &self.collectors.http_pull {
+            for e in http_pull.validate() {
+                errors.push(format!("HTTP pull collector validation: {}", e));
+            }
+        }
+        
+        // Validate capture settings and enabled protocols
+        if let Some(packet_metadata) = &self.collectors.packet_metadata {
+            for e in packet_metadata.validate() {
+                errors.push(format!("Packet metadata collector validation: {}", e));
+            }
+        }
+        
+        // Validate container log paths and kubelet settings
+        if let Some(container) = &self.collectors.container {
+            for e in container.validate() {
+                errors.push(format!("Container collector validation: {}", e));
+            }
+        }
+        
+        // Validate management listener certificate settings
+        if self.management.enabled {
+            for e in self.management.tls.validate() {
+                errors.push(format!("Management TLS validation: {}", e));
+            }
         }
         
         if errors.is_empty() {
@@ -827,12 +3325,118 @@ impl AgentConfig {
             }
         }
         
+        // Validate the SPIFFE identity source
+        if let Some(e) = self.transport.identity.validate().into_iter().next() {
+            return Err(e);
+        }
+        
+        // Validate enrollment settings
+        if let Some(e) = self.transport.enrollment.validate().into_iter().next() {
+            return Err(e);
+        }
+        
+        // Validate the retry policy
+        if let Some(e) = self.transport.retry_policy.validate().into_iter().next() {
+            return Err(format!("transport.retry_policy: {}", e));
+        }
+        
+        // Validate the upload bandwidth cap
+        if self.transport.bandwidth.enabled {
+            if let Some(e) = self.transport.bandwidth.validate().into_iter().next() {
+                return Err(format!("transport.bandwidth: {}", e));
+            }
+        }
+        
+        // Validate the upload windows
+        if self.transport.schedule.enabled {
+            if let Some(e) = self.transport.schedule.validate().into_iter().next() {
+                return Err(format!("transport.schedule: {}", e));
+            }
+        }
+        
+        // Validate acknowledgement tracking
+        if self.transport.delivery.enabled {
+            if let Some(e) = self.transport.delivery.validate().into_iter().next() {
+                return Err(format!("transport.delivery: {}", e));
+            }
+        }
+        
+        // Validate the proxy
+        if let Some(e) = self.transport.proxy.validate().into_iter().next() {
+            return Err(format!("transport.proxy: {}", e));
+        }
+        
+        // Validate keyring references; the secrets themselves are read when the transport starts
+        let secrets = [("api_key", Some(&self.transport.api_key)), ("client_key_password", self.transport.client_key_password.as_ref())];
+        for (field, value) in secrets {
+            if let Some(Err(e)) = value.and_then(|value| crate::security::KeyringReference::parse(value)) {
+                return Err(format!("transport.{}: {}", field, e));
+            }
+        }
+        
+        // Validate the compression level against the selected algorithm
+        if let Some(level) = self.transport.compression_level {
+            let range = self.transport.compression.level_range();
+            if self.transport.compression.is_enabled() && !range.contains(&level) {
+                return Err(format!("compression_level {} is outside {:?}'s range {}-{}",
+                                   level, self.transport.compression, range.start(), range.end()));
+            }
+        }
+        
+        // Syslog receivers can only be additional outputs
+        if self.transport.protocol == TransportProtocol::Syslog {
+            return Err("protocol = \"syslog\" is only available for transport.destinations".to_string());
+        }
+        
+        // Validate the settings of the selected exporter
+        let export_errors = match self.transport.protocol {
+            TransportProtocol::OtlpHttp | TransportProtocol::OtlpGrpc => self.transport.otlp.validate().into_iter().map(|e| format!("OTLP {}", e)).collect(),
+            TransportProtocol::SplunkHec => self.transport.splunk_hec.validate().into_iter().map(|e| format!("Splunk HEC {}", e)).collect(),
+            TransportProtocol::Elasticsearch => self.transport.elasticsearch.validate().into_iter().map(|e| format!("Elasticsearch {}", e)).collect(),
+            TransportProtocol::Native | TransportProtocol::Syslog => Vec::new(),
+        };
+        if let Some(e) = export_errors.into_iter().next() {
+            return Err(e);
+        }
+        if self.transport.protocol != TransportProtocol::Native && self.relay.upstream.is_some() {
+            return Err("Only the native protocol can be sent through a relay upstream".to_string());
+        }
+        if self.transport.format != crate::payload_format::PayloadFormat::Json && self.relay.upstream.is_some() {
+            return Err("Relay upstreams only accept JSON batches; set transport.format = \"json\"".to_string());
+        }
+
+        // Validate additional destinations
+        if let Some(e) = crate::destinations::validate_destinations(&self.transport.destinations).into_iter().next() {
+            return Err(e);
+        }
+        if !self.transport.destinations.is_empty() && self.relay.upstream.is_some() {
+            return Err("Transport destinations cannot be combined with a relay upstream".to_string());
+        }
+        if self.integrity.enabled && self.relay.upstream.is_some() {
+            return Err("Relay frames don't carry batch digests or signatures; disable integrity or the relay upstream".to_string());
+        }
+        
         Ok(())
     }
     
     /// Validate parser regex patterns
     fn validate_parser_patterns(&self) -> Result<(), String> {
+        if let Some(e) = self.parsers.timestamps.validate().into_iter().next() {
+            return Err(format!("parsers.timestamps: {}", e));
+        }
         for parser in &self.parsers.parsers {
+            if let Some(e) = parser.schema.validate().into_iter().next() {
+                return Err(format!("Parser '{}' schema: {}", parser.name, e));
+            }
+            if parser.parser_type == ParserType::Json {
+                if parser.json.separator.is_empty() || parser.json.max_depth == 0 {
+                    return Err(format!("JSON parser '{}' needs a non-empty separator and max_depth > 0", parser.name));
+                }
+                continue;
+            }
+            if parser.regex_pattern.is_empty() {
+                return Err(format!("Regex parser '{}' has no regex_pattern", parser.name));
+            }
             if let Err(e) = Regex::new(&parser.regex_pattern) {
                 return Err(format!("Invalid regex in parser '{}': {}", parser.name, e));
             }
@@ -847,6 +3451,17 @@ impl AgentConfig {
     
     /// Validate buffer configuration
     fn validate_buffer_config(&self) -> Result<(), String> {
+        if self.buffer.disk_quota.enabled {
+            if let Some(error) = self.buffer.disk_quota.validate().into_iter().next() {
+                return Err(format!("Buffer disk quota: {}", error));
+            }
+        }
+        if self.buffer.encryption.enabled {
+            if let Some(error) = self.buffer.encryption.validate().into_iter().next() {
+                return Err(format!("Buffer encryption: {}", error));
+            }
+        }
+        
         // Check persistence path
         if self.buffer.persistent {
             let path = std::path::Path::new(&self.buffer.persistence_path);
@@ -888,6 +3503,26 @@ impl AgentConfig {
                 if syslog.port < 1024 && syslog.port != 514 {
                     return Err("Syslog port should be 514 or >= 1024 to avoid privilege requirements".to_string());
                 }
+
+                // RFC 5424 requires receivers to accept at least 480 octets
+                if syslog.max_message_size < 480 {
+                    return Err("Syslog max_message_size must be at least 480 bytes".to_string());
+                }
+
+                if syslog.backpressure_receive_buffer_bytes != 0 && syslog.backpressure_receive_buffer_bytes < syslog.max_message_size {
+                    return Err("Syslog backpressure_receive_buffer_bytes must hold at least one max_message_size datagram (or be 0)".to_string());
+                }
+
+                if syslog.drop_marker_interval_seconds == 0 {
+                    return Err("Syslog drop_marker_interval_seconds must be greater than 0".to_string());
+                }
+
+                if syslog.protocol.eq_ignore_ascii_case("tls") {
+                    match &syslog.tls {
+                        Some(tls) if !tls.cert_path.is_empty() && !tls.key_path.is_empty() => {}
+                        _ => return Err("Syslog protocol 'tls' requires [collectors.syslog.tls] cert_path and key_path".to_string()),
+                    }
+                }
             }
         }
         
@@ -899,6 +3534,14 @@ impl AgentConfig {
                 if windows_event.channels.is_empty() {
                     return Err("Windows Event collector must have at least one channel configured".to_string());
                 }
+                
+                if let Some(locale) = &windows_event.message_locale {
+                    let mut parts = locale.split('-');
+                    let language_ok = parts.next().is_some_and(|l| (2..=3).contains(&l.len()) && l.chars().all(|c| c.is_ascii_alphabetic()));
+                    if !language_ok || !parts.all(|p| (2..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric())) {
+                        return Err(format!("Windows Event message_locale '{}' is not a locale name like 'en-US'", locale));
+                    }
+                }
             }
         }
         
@@ -917,6 +3560,45 @@ impl AgentConfig {
                         return Err("File monitor paths cannot be empty".to_string());
                     }
                 }
+                
+                if let Some(multiline) = &file_monitor.multiline {
+                    if let Some(error) = multiline.validate().into_iter().next() {
+                        return Err(format!("File monitor multiline: {}", error));
+                    }
+                }
+                
+                if let Some(error) = file_monitor.backfill.validate().into_iter().next() {
+                    return Err(format!("File monitor backfill: {}", error));
+                }
+            }
+        }
+        
+        // Check database audit collector
+        if let Some(database) = &self.collectors.database {
+            if database.enabled {
+                enabled_count += 1;
+                
+                if database.paths.is_empty() || database.paths.iter().any(|p| p.trim().is_empty()) {
+                    return Err("Database collector must have at least one non-empty path configured".to_string());
+                }
+                
+                if let Err(e) = crate::parsers::database::DatabaseLogFormat::new(database.preset, database.log_line_prefix.as_deref()) {
+                    return Err(format!("Invalid database collector log format: {}", e));
+                }
+            }
+        }
+        
+        // Check session collector
+        if let Some(session) = &self.collectors.session {
+            if session.enabled {
+                enabled_count += 1;
+                
+                if session.sources.contains(&crate::collectors::session::SessionSourceKind::Wtmp) && session.wtmp_path.trim().is_empty() {
+                    return Err("Session collector wtmp_path cannot be empty when the wtmp source is enabled".to_string());
+                }
+                if session.poll_interval_ms < 500 {
+                    return Err("Session collector poll_interval_ms must be at least 500".to_string());
+                }
             }
         }
         
@@ -948,6 +3630,12 @@ impl AgentConfig {
         Ok(())
     }
     
+    /// Validate field filter allow/deny rules against the configured destination and parsers
+    fn validate_field_filter_config(&self) -> Vec<String> {
+        let parser_names: Vec<String> = self.parsers.parsers.iter().map(|p| p.name.clone()).collect();
+        self.field_filter.validate(&self.transport.server_url, &parser_names)
+    }
+    
     /// Enhanced validation with input security checks
     pub async fn validate_with_security(&self) -> Result<(), ConfigError> {
         // First run the schema validation
@@ -1144,6 +3832,117 @@ impl AgentConfig {
         errors
     }
     
+    /// Errors and warnings in one pass; errors block applying the configuration, warnings are only reported
+    pub fn validate_report(&self) -> ConfigValidationReport {
+        ConfigValidationReport {
+            errors: self.get_validation_errors(),
+            warnings: self.get_validation_warnings(),
+        }
+    }
+    
+    /// Validate a configuration file, also warning about settings in the file that the agent ignores
+    pub async fn validate_file_report(config_path: &str) -> Result<ConfigValidationReport, ConfigError> {
+        let content = tokio::fs::read_to_string(config_path).await
+            .map_err(|e| ConfigError::Io(e.to_string()))?;
+        let config: AgentConfig = toml::from_str(&content)
+            .map_err(|e| ConfigError::Parse(e.to_string()))?;
+        
+        let mut report = config.validate_report();
+        report.warnings.extend(config.get_unknown_field_warnings(&content));
+        Ok(report)
+    }
+    
+    /// Settings that are legal but weaken security or reliability
+    pub fn get_validation_warnings(&self) -> Vec<ConfigValidationWarning> {
+        let mut warnings = Vec::new();
+        let mut warn = |path: &str, warning_type: &str, message: String, suggestion: &str| {
+            warnings.push(ConfigValidationWarning {
+                path: path.to_string(),
+                warning_type: warning_type.to_string(),
+                message,
+                suggestion: Some(suggestion.to_string()),
+            });
+        };
+        
+        if !self.transport.tls_verify {
+            warn("/transport/tls_verify", "insecure_tls",
+                 "TLS certificate verification is disabled".to_string(),
+                 "Set tls_verify = true or configure ca_cert_path for private CAs");
+        }
+        if is_plaintext_remote(&self.transport.server_url) {
+            warn("/transport/server_url", "plaintext_transport",
+                 format!("Events are sent unencrypted to {}", self.transport.server_url),
+                 "Use an https:// server URL");
+        }
+        for (i, destination) in self.transport.destinations.iter().enumerate() {
+            if destination.tls_verify == Some(false) {
+                warn(&format!("/transport/destinations/{}/tls_verify", i), "insecure_tls",
+                     format!("TLS certificate verification is disabled for destination '{}'", destination.name),
+                     "Remove tls_verify = false or configure ca_cert_path for private CAs");
+            }
+            if is_plaintext_remote(&destination.server_url) {
+                warn(&format!("/transport/destinations/{}/server_url", i), "plaintext_transport",
+                     format!("Destination '{}' receives events unencrypted", destination.name),
+                     "Use an https:// server URL, or tls:// for syslog");
+            }
+        }
+        if self.transport.retry_attempts == 1 {
+            warn("/transport/retry_attempts", "no_retries",
+                 "Failed batches are not retried".to_string(),
+                 "Allow at least 2 attempts so transient network errors do not fail batches");
+        }
+        
+        if self.management.enabled {
+            if !self.management.tls.enabled {
+                warn("/management/tls/enabled", "plaintext_management",
+                     "The management API is served without TLS".to_string(),
+                     "Enable management.tls so auth tokens and commands are encrypted");
+            }
+            if self.management.auth_token.as_deref().unwrap_or_default().is_empty() {
+                warn("/management/auth_token", "unauthenticated_management",
+                     "The management API accepts requests without an auth token".to_string(),
+                     "Set management.auth_token");
+            }
+        }
+        
+        if self.alert_rules.enabled {
+            if self.alert_rules.max_actions_per_minute == 0 {
+                warn("/alert_rules/max_actions_per_minute", "unbounded_actions",
+                     "Alert actions are not rate limited".to_string(),
+                     "Set max_actions_per_minute so an event storm cannot flood webhooks or spawn unbounded scripts");
+            }
+            let scripts = self.alert_rules.rules.iter()
+                .filter(|rule| matches!(rule.action, Some(crate::alert_rules::AlertAction::Script { .. })))
+                .count();
+            if scripts > 0 {
+                warn("/alert_rules/rules", "local_script_actions",
+                     format!("{} alert rules run local scripts with the agent's privileges", scripts),
+                     "Keep allowlisted scripts owned by root/Administrators and not writable by other users");
+            }
+        }
+        
+        warnings
+    }
+    
+    /// Keys present in the TOML source that the agent does not recognize (removed, renamed or misspelled
+    /// settings), found by comparing the source with the configuration as the agent understood it
+    pub fn get_unknown_field_warnings(&self, source: &str) -> Vec<ConfigValidationWarning> {
+        let (Ok(raw), Ok(toml::Value::Table(known))) = (source.parse::<toml::Table>(), toml::Value::try_from(self)) else {
+            return Vec::new();
+        };
+        let mut unknown = Vec::new();
+        collect_unknown_keys(&raw, &known, "", &mut unknown);
+        unknown
+            .into_iter()
+            .map(|path| ConfigValidationWarning {
+                message: format!("'{}' is not a recognized setting and is ignored", path.trim_start_matches('/').replace('/', ".")),
+                path,
+                warning_type: "unknown_field".to_string(),
+                suggestion: Some("Remove the setting or check it against the current configuration reference".to_string()),
+            })
+            .collect()
+    }
+    
     /// Get suggestions for fixing validation errors
     fn get_validation_suggestion(&self, error: &JsonSchemaError) -> Option<String> {
         let error_msg = error.to_string();
@@ -1194,6 +3993,32 @@ impl AgentConfig {
     }
 }
 
+/// True for http:// URLs that leave the host
+fn is_plaintext_remote(url: &str) -> bool {
+    match url::Url::parse(url) {
+        Ok(url) => matches!(url.scheme(), "http" | "tcp") && !matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]")),
+        Err(_) => false,
+    }
+}
+
+fn collect_unknown_keys(raw: &toml::Table, known: &toml::Table, prefix: &str, unknown: &mut Vec<String>) {
+    for (key, value) in raw {
+        let path = format!("{}/{}", prefix, key);
+        match (value, known.get(key)) {
+            (_, None) => unknown.push(path),
+            (toml::Value::Table(raw), Some(toml::Value::Table(known))) => collect_unknown_keys(raw, known, &path, unknown),
+            (toml::Value::Array(raw), Some(toml::Value::Array(known))) => {
+                for (i, (raw, known)) in raw.iter().zip(known).enumerate() {
+                    if let (toml::Value::Table(raw), toml::Value::Table(known)) = (raw, known) {
+                        collect_unknown_keys(raw, known, &format!("{}/{}", path, i), unknown);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 /// Configuration manager with hot-reloading, validation, and rollback capabilities
 pub struct ConfigManager {
     config_path: String,
@@ -1205,6 +4030,8 @@ pub struct ConfigManager {
     auto_rollback: bool,
     debounce_duration: tokio::time::Duration,
     watcher_handle: Option<tokio::task::JoinHandle<()>>,
+    identity_watcher_handle: Option<tokio::task::JoinHandle<()>>,
+    audit_handle: Option<tokio::task::JoinHandle<()>>,
 }
 
 /// Configuration update event with detailed context
@@ -1214,8 +4041,12 @@ pub struct ConfigUpdateEvent {
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub config: Option<AgentConfig>,
     pub validation_errors: Vec<ConfigValidationError>,
+    /// Findings that did not block the configuration from being applied
+    pub validation_warnings: Vec<ConfigValidationWarning>,
     pub source: String,
     pub success: bool,
+    /// What changed relative to the previously active configuration (secrets redacted)
+    pub changes: Vec<crate::config_diff::ConfigChange>,
 }
 
 #[derive(Debug, Clone)]
@@ -1226,6 +4057,8 @@ pub enum ConfigEventType {
     RolledBack,
     FileChanged,
     WatcherError,
+    /// A transport client certificate, key or CA file was replaced on disk; `source` is its path
+    CertificateChanged,
 }
 
 impl ConfigManager {
@@ -1236,6 +4069,10 @@ impl ConfigManager {
         
         // Validate initial configuration
         initial_config.validate_with_schema()?;
+        let initial_warnings = initial_config.get_validation_warnings();
+        for warning in &initial_warnings {
+            tracing::warn!("⚠️ Configuration warning at {}: {}", warning.path, warning.message);
+        }
         
         // Create channels for config updates
         let (config_tx, config_rx) = tokio::sync::broadcast::channel(100);
@@ -1250,6 +4087,8 @@ impl ConfigManager {
             auto_rollback: true,
             debounce_duration: tokio::time::Duration::from_millis(500),
             watcher_handle: None,
+            identity_watcher_handle: None,
+            audit_handle: None,
         };
         
         // Send initial load event
@@ -1258,8 +4097,10 @@ impl ConfigManager {
             timestamp: chrono::Utc::now(),
             config: Some(initial_config),
             validation_errors: vec![],
+            validation_warnings: initial_warnings,
             source: config_path,
             success: true,
+            changes: vec![],
         });
         
         tracing::info!("🔧 Configuration manager initialized with hot-reloading enabled");
@@ -1311,8 +4152,10 @@ impl ConfigManager {
                             message: format!("Failed to create file watcher: {}", e),
                             suggestion: Some("Check file permissions and system limits".to_string()),
                         }],
+                        validation_warnings: vec![],
                         source: config_path.clone(),
                         success: false,
+                        changes: vec![],
                     });
                     return;
                 }
@@ -1332,8 +4175,10 @@ impl ConfigManager {
                             message: format!("Failed to watch config directory: {}", e),
                             suggestion: Some("Ensure directory exists and is readable".to_string()),
                         }],
+                        validation_warnings: vec![],
                         source: config_path.clone(),
                         success: false,
+                        changes: vec![],
                     });
                     return;
                 }
@@ -1366,8 +4211,10 @@ impl ConfigManager {
                     timestamp: chrono::Utc::now(),
                     config: None,
                     validation_errors: vec![],
+                    validation_warnings: vec![],
                     source: config_path.clone(),
                     success: true,
+                    changes: vec![],
                 });
                 
                 // Attempt to reload configuration
@@ -1379,6 +4226,7 @@ impl ConfigManager {
                         } else {
                             vec![]
                         };
+                        let validation_warnings = new_config.get_validation_warnings();
                         
                         if validation_enabled && !validation_errors.is_empty() {
                             tracing::warn!("🚫 Configuration validation failed with {} errors", validation_errors.len());
@@ -1389,8 +4237,10 @@ impl ConfigManager {
                                 timestamp: chrono::Utc::now(),
                                 config: Some(new_config.clone()),
                                 validation_errors: validation_errors.clone(),
+                                validation_warnings: validation_warnings.clone(),
                                 source: config_path.clone(),
                                 success: false,
+                                changes: vec![],
                             });
                             
                             // Auto-rollback if enabled
@@ -1399,29 +4249,39 @@ impl ConfigManager {
                                     if let Err(e) = backup.save_to_file(&config_path).await {
                                         tracing::error!("Failed to rollback configuration: {}", e);
                                     } else {
-                                        tracing::info!("🔄 Auto-rollback completed");
+                                        // Changes reverted in the file: rejected config -> backup
+                                        let changes = crate::config_diff::diff_configs(&new_config, backup);
+                                        tracing::info!("🔄 Auto-rollback completed, reverted {} changes: {}",
+                                                       changes.len(), crate::config_diff::summarize_changes(&changes));
                                         
                                         let _ = config_tx.send(ConfigUpdateEvent {
                                             event_type: ConfigEventType::RolledBack,
                                             timestamp: chrono::Utc::now(),
                                             config: Some(backup.clone()),
                                             validation_errors: vec![],
+                                            validation_warnings: vec![],
                                             source: config_path.clone(),
                                             success: true,
+                                            changes,
                                         });
                                     }
                                 }
                             }
                         } else {
                             // Configuration is valid, update current config and backup
-                            {
+                            let changes = {
                                 let current = current_config.read().await;
                                 *backup_config.write().await = Some(current.clone());
-                            }
+                                crate::config_diff::diff_configs(&current, &new_config)
+                            };
                             
                             *current_config.write().await = new_config.clone();
                             
-                            tracing::info!("✅ Configuration reloaded successfully");
+                            tracing::info!("✅ Configuration reloaded successfully ({} changes): {}",
+                                           changes.len(), crate::config_diff::summarize_changes(&changes));
+                            for warning in &validation_warnings {
+                                tracing::warn!("⚠️ Configuration warning at {}: {}", warning.path, warning.message);
+                            }
                             
                             // Send successful update event
                             let _ = config_tx.send(ConfigUpdateEvent {
@@ -1429,8 +4289,10 @@ impl ConfigManager {
                                 timestamp: chrono::Utc::now(),
                                 config: Some(new_config),
                                 validation_errors: validation_errors,
+                                validation_warnings,
                                 source: config_path.clone(),
                                 success: true,
+                                changes,
                             });
                         }
                     }
@@ -1447,8 +4309,10 @@ impl ConfigManager {
                                 message: format!("Failed to load configuration: {}", e),
                                 suggestion: Some("Check file syntax and permissions".to_string()),
                             }],
+                            validation_warnings: vec![],
                             source: config_path.clone(),
                             success: false,
+                            changes: vec![],
                         });
                     }
                 }
@@ -1456,27 +4320,78 @@ impl ConfigManager {
         });
         
         self.watcher_handle = Some(watcher_handle);
+        self.start_identity_watching().await;
         tracing::info!("🔄 Hot-reloading watcher started");
         Ok(())
     }
     
+    /// Report replaced transport certificate files as CertificateChanged events.
+    /// Files are taken from the configuration active when watching starts.
+    async fn start_identity_watching(&mut self) {
+        let transport = self.current_config.read().await.transport.clone();
+        let files = crate::client_identity::identity_files(&transport);
+        if !transport.identity.watch_files || files.is_empty() {
+            return;
+        }
+        let mut watcher = match crate::client_identity::IdentityFileWatcher::new(&files) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                tracing::warn!("⚠️ Failed to watch transport certificate files: {}", e);
+                return;
+            }
+        };
+        let config_tx = self.config_tx.clone();
+        self.identity_watcher_handle = Some(tokio::spawn(async move {
+            while let Some(path) = watcher.changed().await {
+                tracing::info!("🔏 Transport certificate file changed: {}", path.display());
+                let _ = config_tx.send(ConfigUpdateEvent {
+                    event_type: ConfigEventType::CertificateChanged,
+                    timestamp: chrono::Utc::now(),
+                    config: None,
+                    validation_errors: vec![],
+                    validation_warnings: vec![],
+                    source: path.display().to_string(),
+                    success: true,
+                    changes: vec![],
+                });
+            }
+        }));
+    }
+    
     /// Get current configuration (thread-safe)
     pub async fn get_config(&self) -> AgentConfig {
         self.current_config.read().await.clone()
     }
     
+    /// Validate a candidate configuration and diff it against the active one without applying it
+    pub async fn preview_config(&self, candidate: &AgentConfig) -> ConfigPreview {
+        ConfigPreview {
+            errors: if self.validation_enabled { candidate.get_validation_errors() } else { Vec::new() },
+            warnings: candidate.get_validation_warnings(),
+            changes: crate::config_diff::diff_configs(&*self.current_config.read().await, candidate),
+        }
+    }
+    
     /// Update configuration programmatically with validation
     pub async fn update_config(&self, new_config: AgentConfig) -> Result<(), ConfigError> {
+        self.update_config_from(new_config, "programmatic").await.map(|_| ())
+    }
+    
+    /// Validate, apply and persist a configuration on behalf of `source` (e.g. the management API);
+    /// subscribers see it as an update from that source. Returns what changed
+    pub async fn update_config_from(&self, new_config: AgentConfig, source: &str) -> Result<Vec<crate::config_diff::ConfigChange>, ConfigError> {
         // Validate new configuration if enabled
         if self.validation_enabled {
             new_config.validate_with_schema()?;
         }
+        let validation_warnings = new_config.get_validation_warnings();
         
         // Backup current configuration
-        {
+        let changes = {
             let current = self.current_config.read().await;
             *self.backup_config.write().await = Some(current.clone());
-        }
+            crate::config_diff::diff_configs(&current, &new_config)
+        };
         
         // Update current configuration
         *self.current_config.write().await = new_config.clone();
@@ -1484,18 +4399,78 @@ impl ConfigManager {
         // Save to file
         new_config.save_to_file(&self.config_path).await?;
         
+        tracing::info!("📝 Configuration changes ({}): {}", changes.len(), crate::config_diff::summarize_changes(&changes));
+        
         // Send update event
         let _ = self.config_tx.send(ConfigUpdateEvent {
             event_type: ConfigEventType::Updated,
             timestamp: chrono::Utc::now(),
             config: Some(new_config),
             validation_errors: vec![],
-            source: "programmatic".to_string(),
+            validation_warnings,
+            source: source.to_string(),
             success: true,
+            changes: changes.clone(),
         });
         
-        tracing::info!("✅ Configuration updated programmatically");
-        Ok(())
+        tracing::info!("✅ Configuration updated ({})", source);
+        Ok(changes)
+    }
+    
+    /// Re-read the configuration file now on behalf of `source` (e.g. SIGHUP) instead of waiting for the watcher.
+    /// A file that fails to load or validate is reported to subscribers and leaves the active configuration in place
+    pub async fn reload_from_file(&self, source: &str) -> Result<Vec<crate::config_diff::ConfigChange>, ConfigError> {
+        let rejected = |validation_errors: Vec<ConfigValidationError>| ConfigUpdateEvent {
+            event_type: ConfigEventType::ValidationFailed,
+            timestamp: chrono::Utc::now(),
+            config: None,
+            validation_errors,
+            validation_warnings: vec![],
+            source: source.to_string(),
+            success: false,
+            changes: vec![],
+        };
+        let new_config = match AgentConfig::load_from_file(&self.config_path).await {
+            Ok(config) => config,
+            Err(e) => {
+                let _ = self.config_tx.send(rejected(vec![ConfigValidationError {
+                    path: self.config_path.clone(),
+                    error_type: "load_failed".to_string(),
+                    message: format!("Failed to load configuration: {}", e),
+                    suggestion: Some("Check file syntax and permissions".to_string()),
+                }]));
+                return Err(e);
+            }
+        };
+        let validation_errors = if self.validation_enabled { new_config.get_validation_errors() } else { vec![] };
+        if !validation_errors.is_empty() {
+            let summary = validation_errors.iter().map(|e| format!("{}: {}", e.path, e.message)).collect::<Vec<_>>().join("; ");
+            let _ = self.config_tx.send(rejected(validation_errors));
+            return Err(ConfigError::Validation(summary));
+        }
+        let validation_warnings = new_config.get_validation_warnings();
+        
+        let changes = {
+            let mut current = self.current_config.write().await;
+            *self.backup_config.write().await = Some(current.clone());
+            let changes = crate::config_diff::diff_configs(&current, &new_config);
+            *current = new_config.clone();
+            changes
+        };
+        tracing::info!("✅ Configuration reloaded ({}, {} changes): {}",
+                       source, changes.len(), crate::config_diff::summarize_changes(&changes));
+        
+        let _ = self.config_tx.send(ConfigUpdateEvent {
+            event_type: ConfigEventType::Updated,
+            timestamp: chrono::Utc::now(),
+            config: Some(new_config),
+            validation_errors: vec![],
+            validation_warnings,
+            source: source.to_string(),
+            success: true,
+            changes: changes.clone(),
+        });
+        Ok(changes)
     }
     
     /// Rollback to previous configuration
@@ -1504,7 +4479,12 @@ impl ConfigManager {
             let backup_config = backup.clone();
             
             // Update current configuration
-            *self.current_config.write().await = backup_config.clone();
+            let changes = {
+                let mut current = self.current_config.write().await;
+                let changes = crate::config_diff::diff_configs(&current, &backup_config);
+                *current = backup_config.clone();
+                changes
+            };
             
             // Save to file
             backup_config.save_to_file(&self.config_path).await?;
@@ -1515,17 +4495,51 @@ impl ConfigManager {
                 timestamp: chrono::Utc::now(),
                 config: Some(backup_config),
                 validation_errors: vec![],
+                validation_warnings: vec![],
                 source: "manual_rollback".to_string(),
                 success: true,
+                changes: changes.clone(),
             });
             
-            tracing::info!("🔄 Configuration rolled back successfully");
+            tracing::info!("🔄 Configuration rolled back successfully ({} changes): {}",
+                           changes.len(), crate::config_diff::summarize_changes(&changes));
             Ok(())
         } else {
             Err(ConfigError::Validation("No backup configuration available for rollback".to_string()))
         }
     }
     
+    /// Record applied, rejected and rolled back configurations in the administrative audit log
+    pub fn set_audit_log(&mut self, audit_log: std::sync::Arc<crate::admin_audit::AdminAuditLog>) {
+        use crate::admin_audit::AuditAction;
+        let mut config_rx = self.config_tx.subscribe();
+        self.audit_handle = Some(tokio::spawn(async move {
+            loop {
+                let event = match config_rx.recv().await {
+                    Ok(event) => event,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                        audit_log.record_or_warn(AuditAction::ConfigChanged, "agent",
+                                                 &format!("{} configuration events were not recorded", missed), false);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                let (action, details) = match event.event_type {
+                    // Saving an update rewrites the file, which the watcher then reloads without changes
+                    ConfigEventType::Updated if event.changes.is_empty() => continue,
+                    ConfigEventType::Updated => (AuditAction::ConfigChanged, crate::config_diff::summarize_changes(&event.changes)),
+                    ConfigEventType::RolledBack => (AuditAction::ConfigRolledBack, crate::config_diff::summarize_changes(&event.changes)),
+                    ConfigEventType::ValidationFailed => (
+                        AuditAction::ConfigRejected,
+                        event.validation_errors.iter().map(|e| format!("{}: {}", e.path, e.message)).collect::<Vec<_>>().join("; "),
+                    ),
+                    _ => continue,
+                };
+                audit_log.record_or_warn(action, &event.source, &details, event.success);
+            }
+        }));
+    }
+    
     /// Subscribe to configuration change events
     pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<ConfigUpdateEvent> {
         self.config_tx.subscribe()
@@ -1565,9 +4579,23 @@ impl ConfigManager {
             handle.abort();
             tracing::info!("🛑 Configuration watcher stopped");
         }
+        if let Some(handle) = self.identity_watcher_handle.take() {
+            handle.abort();
+        }
+        if let Some(handle) = self.audit_handle.take() {
+            handle.abort();
+        }
     }
 }
 
+/// Outcome of validating a candidate configuration against the active one
+#[derive(Debug, Clone)]
+pub struct ConfigPreview {
+    pub errors: Vec<ConfigValidationError>,
+    pub warnings: Vec<ConfigValidationWarning>,
+    pub changes: Vec<crate::config_diff::ConfigChange>,
+}
+
 /// Hot-reload configuration options
 #[derive(Debug, Clone)]
 pub struct HotReloadOptions {
@@ -1603,6 +4631,12 @@ impl Drop for ConfigManager {
         if let Some(handle) = self.watcher_handle.take() {
             handle.abort();
         }
+        if let Some(handle) = self.identity_watcher_handle.take() {
+            handle.abort();
+        }
+        if let Some(handle) = self.audit_handle.take() {
+            handle.abort();
+        }
     }
 }
 
@@ -1626,7 +4660,7 @@ mod tests {
                 server_url: "https://api.securewatch.test".to_string(),
                 api_key: "secure-test-api-key-123456".to_string(),
                 tls_verify: true,
-                compression: true,
+                compression: crate::compression::CompressionAlgorithm::Zstd,
                 batch_size: 100,
                 batch_timeout: 5,
                 retry_attempts: 3,
@@ -1636,6 +4670,8 @@ mod tests {
                 client_key_password: None,
                 ca_cert_path: None,
                 cert_expiry_warning_days: 30,
+                identity: Default::default(),
+                enrollment: Default::default(),
             },
             collectors: CollectorsConfig {
                 syslog: Some(SyslogCollectorConfig {
@@ -1643,18 +4679,38 @@ mod tests {
                     bind_address: "127.0.0.1".to_string(),
                     port: 5514,
                     protocol: "udp".to_string(),
+                    framing: SyslogFraming::Auto,
+                    max_message_size: default_syslog_max_message_size(),
+                    tls: None,
+                    backpressure_receive_buffer_bytes: default_syslog_backpressure_receive_buffer(),
+                    drop_marker_interval_seconds: default_syslog_drop_marker_interval(),
                 }),
                 windows_event: Some(WindowsEventCollectorConfig {
                     enabled: false,
                     channels: vec!["System".to_string()],
                     batch_size: 50,
+                    bookmark_path: None,
+                    render_messages: true,
+                    message_locale: None,
                 }),
                 file_monitor: Some(FileMonitorConfig {
                     enabled: false,
                     paths: vec!["/tmp/test.log".to_string()],
                     patterns: vec!["*.log".to_string()],
                     recursive: false,
+                    multiline: None,
+                    backfill: ArchiveBackfillConfig::default(),
                 }),
+                database: None,
+                session: None,
+                ebpf: None,
+                etw: None,
+                container: None,
+                fim: None,
+                honeytoken: None,
+                command: None,
+                http_pull: None,
+                packet_metadata: None,
             },
             buffer: BufferConfig {
                 max_events: 1000,
@@ -1681,24 +4737,34 @@ mod tests {
                 cleanup_interval_sec: 300,
                 min_retention_hours: 24,
                 max_events_per_cleanup: 10000,
+                burst_capacity: 1000,
             },
             parsers: ParsersConfig {
                 parsers: vec![
                     ParserDefinition {
                         name: "test_parser".to_string(),
                         source_type: "test".to_string(),
+                        parser_type: ParserType::Regex,
                         regex_pattern: r"^(?P<timestamp>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}).*$".to_string(),
                         field_mappings: HashMap::from([
                             ("timestamp".to_string(), "@timestamp".to_string()),
                         ]),
+                        processors: Vec::new(),
+                        json: Default::default(),
+                        schema: Default::default(),
                     }
                 ],
+                chains: HashMap::new(),
+                source_chains: HashMap::new(),
+                sample_capture: crate::parsers::samples::SampleCaptureConfig::default(),
+                timestamps: crate::parsers::timestamps::TimestampConfig::default(),
             },
             management: ManagementConfig {
                 enabled: true,
                 bind_address: "127.0.0.1".to_string(),
                 port: 9090,
                 auth_token: Some("secure-management-token-12345".to_string()),
+                tls: crate::management_tls::ManagementTlsConfig::default(),
             },
         }
     }
@@ -1708,7 +4774,17 @@ mod tests {
         let config = create_valid_test_config();
         assert!(config.validate_with_schema().is_ok());
     }
-    
+
+    #[test]
+    fn test_default_config_passes_schema_validation() {
+        // Unset optional settings must be left out rather than serialized as null
+        let mut config = AgentConfig::default();
+        config.transport.api_key = "secure-test-api-key-123456".to_string();
+        if let Err(e) = config.validate_with_schema() {
+            panic!("default config failed schema validation: {:?}", e);
+        }
+    }
+
     #[test]
     fn test_invalid_api_key_fails_validation() {
         let mut config = create_valid_test_config();
@@ -1987,6 +5063,37 @@ mod tests {
         assert_eq!(event.source, "programmatic");
     }
     
+    #[tokio::test]
+    async fn test_config_manager_remote_push_preview_and_apply() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+        let initial_config = create_valid_test_config();
+        initial_config.save_to_file(config_path.to_str().unwrap()).await.unwrap();
+        let manager = ConfigManager::new(config_path.to_str().unwrap().to_string()).await.unwrap();
+        let mut event_rx = manager.subscribe();
+        
+        let mut invalid = initial_config.clone();
+        invalid.transport.batch_size = 0;
+        assert!(!manager.preview_config(&invalid).await.errors.is_empty());
+        
+        let mut pushed = initial_config.clone();
+        pushed.agent.name = "pushed-agent".to_string();
+        let preview = manager.preview_config(&pushed).await;
+        assert!(preview.errors.is_empty());
+        assert_eq!(preview.changes.len(), 1);
+        assert_eq!(manager.get_config().await.agent.name, initial_config.agent.name);
+        
+        let changes = manager.update_config_from(pushed, "management").await.unwrap();
+        assert_eq!(changes[0].path, "agent.name");
+        assert_eq!(manager.get_config().await.agent.name, "pushed-agent");
+        let event = event_rx.recv().await.unwrap();
+        assert_eq!(event.source, "management");
+        assert_eq!(event.changes, changes);
+        
+        let persisted = AgentConfig::load_from_file(config_path.to_str().unwrap()).await.unwrap();
+        assert_eq!(persisted.agent.name, "pushed-agent");
+    }
+    
     #[tokio::test]
     async fn test_config_manager_rollback() {
         let temp_dir = TempDir::new().unwrap();
@@ -2182,6 +5289,7 @@ mod tests {
             ConfigEventType::RolledBack,
             ConfigEventType::FileChanged,
             ConfigEventType::WatcherError,
+            ConfigEventType::CertificateChanged,
         ];
         
         for event in events {
@@ -2192,7 +5300,32 @@ mod tests {
                 ConfigEventType::RolledBack => assert!(true),
                 ConfigEventType::FileChanged => assert!(true),
                 ConfigEventType::WatcherError => assert!(true),
+                ConfigEventType::CertificateChanged => assert!(true),
             }
         }
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_risky_settings_are_warnings_not_errors() {
+        let mut config = AgentConfig::default();
+        config.transport.tls_verify = false;
+        config.transport.server_url = "http://siem.example.com/ingest".to_string();
+        
+        let report = config.validate_report();
+        let types: Vec<&str> = report.warnings.iter().map(|w| w.warning_type.as_str()).collect();
+        assert!(types.contains(&"insecure_tls"));
+        assert!(types.contains(&"plaintext_transport"));
+        assert!(report.errors.iter().all(|e| !e.message.contains("tls_verify")));
+    }
+    
+    #[test]
+    fn test_unknown_fields_are_reported() {
+        let config = AgentConfig::default();
+        let mut source = toml::to_string(&config).unwrap();
+        source = source.replacen("[transport]\n", "[transport]\nbatch_timout = 5\n", 1);
+        source.push_str("\n[legacy_output]\nenabled = true\n");
+        
+        let paths: Vec<String> = config.get_unknown_field_warnings(&source).into_iter().map(|w| w.path).collect();
+        assert_eq!(paths, vec!["/legacy_output".to_string(), "/transport/batch_timout".to_string()]);
+    }
+}
@@ -0,0 +1,308 @@
+// Local control socket for administering a running agent
+// Pushing a configuration, reloading parsers and reading collector status need the running agent, not just its
+// files on disk. The CLI commands that talk to a running agent connect to a Unix socket readable only by the
+// agent's user, send one JSON request line and read one JSON response line back. The agent's main loop answers
+// each request, so it sees and changes the same state a configuration file reload does.
+
+use crate::event_index::SearchRequest;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+/// Longest request line a client may send; pushed configurations travel inline
+const MAX_REQUEST_BYTES: u64 = 4 * 1024 * 1024;
+
+/// How long a client has to send its request after connecting
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a request may wait for the main loop; a pushed configuration rebuilds components before it answers
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Clients connected at once; further connections are closed
+const MAX_CLIENTS: usize = 8;
+
+/// Local control socket (`control_socket`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlSocketConfig {
+    pub enabled: bool,
+    /// Unix socket clients connect to; created owner-only
+    pub socket_path: String,
+}
+
+impl Default for ControlSocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            socket_path: "./run/control.sock".to_string(),
+        }
+    }
+}
+
+impl ControlSocketConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.socket_path.trim().is_empty() {
+            errors.push("socket_path must not be empty".to_string());
+        }
+        errors
+    }
+}
+
+/// What a client asks the running agent to do
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// Validate, apply and persist a whole configuration; a dry run only reports what would change
+    PushConfig {
+        config: String,
+        /// "toml" (the default) or "json"
+        #[serde(default)]
+        format: String,
+        #[serde(default)]
+        dry_run: bool,
+    },
+    /// Rebuild the configured parsers from the active configuration; the previous ones stay on failure
+    ReloadParsers,
+    /// Whether each collector is running and what it shed
+    CollectorStatus,
+    /// Search the agent's local full-text index
+    SearchEvents(SearchRequest),
+    /// Capability report for the running binary, host and active configuration
+    Capabilities,
+    /// Discard events from `source` (all sources when None) until `until`, or until resumed
+    PauseIngest {
+        source: Option<String>,
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        reason: Option<String>,
+    },
+    /// Lift the pause on `source` (the all-sources pause when None), or every pause
+    ResumeIngest {
+        source: Option<String>,
+        #[serde(default)]
+        all: bool,
+    },
+    /// Active pauses and the events each source lost to them
+    IngestStatus,
+    /// Parse, queue wait and flush latency histograms since the agent started
+    PipelineLatency,
+    /// KQL over the events in the agent's buffer, e.g. "Events | where source == 'syslog' | take 10"
+    QueryEvents {
+        query: String,
+        /// Rows to return, the query default when None
+        limit: Option<usize>,
+        /// Newest buffered events to query, the query default when None
+        max_scan_events: Option<usize>,
+    },
+    /// Move dead-lettered batches (all of them when `ids` is empty) back into the buffer to be sent again
+    RedriveDeadLetters {
+        #[serde(default)]
+        ids: Vec<i64>,
+    },
+}
+
+impl ControlRequest {
+    /// Name for logs; requests themselves may carry secrets, e.g. a pushed configuration
+    pub fn method(&self) -> &'static str {
+        match self {
+            ControlRequest::PushConfig { .. } => "push_config",
+            ControlRequest::ReloadParsers => "reload_parsers",
+            ControlRequest::CollectorStatus => "collector_status",
+            ControlRequest::SearchEvents(_) => "search_events",
+            ControlRequest::Capabilities => "capabilities",
+            ControlRequest::PauseIngest { .. } => "pause_ingest",
+            ControlRequest::ResumeIngest { .. } => "resume_ingest",
+            ControlRequest::IngestStatus => "ingest_status",
+            ControlRequest::PipelineLatency => "pipeline_latency",
+            ControlRequest::QueryEvents { .. } => "query_events",
+            ControlRequest::RedriveDeadLetters { .. } => "redrive_dead_letters",
+        }
+    }
+}
+
+/// The agent's answer; `data` carries the method's result, e.g. the collector list
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlResponse {
+    pub success: bool,
+    pub message: String,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub data: serde_json::Value,
+}
+
+impl ControlResponse {
+    pub fn ok(message: impl Into<String>, data: serde_json::Value) -> Self {
+        Self { success: true, message: message.into(), data, ..Default::default() }
+    }
+
+    pub fn failed(message: impl Into<String>, errors: Vec<String>) -> Self {
+        Self { success: false, message: message.into(), errors, ..Default::default() }
+    }
+}
+
+/// A request waiting for the agent's main loop, with who sent it for the audit log
+#[derive(Debug)]
+pub struct ControlCall {
+    pub request: ControlRequest,
+    pub actor: String,
+    pub reply: oneshot::Sender<ControlResponse>,
+}
+
+/// Serve the control socket until shutdown, handing each request to `calls`
+#[cfg(unix)]
+pub async fn serve(config: ControlSocketConfig, calls: mpsc::Sender<ControlCall>, mut shutdown_receiver: broadcast::Receiver<()>) -> std::io::Result<()> {
+    let path = Path::new(&config.socket_path);
+    let listener = crate::live_tail::bind_owner_only(path)?;
+    let clients = Arc::new(tokio::sync::Semaphore::new(MAX_CLIENTS));
+    info!("🎛️ Control socket listening on {}", path.display());
+
+    let result = loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("⚠️ Control socket accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let Ok(permit) = clients.clone().try_acquire_owned() else {
+                    warn!("⚠️ Control socket client limit ({}) reached, closing connection", MAX_CLIENTS);
+                    continue;
+                };
+                let calls = calls.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = answer(stream, &calls).await {
+                        debug!("Control socket client closed: {}", e);
+                    }
+                    drop(permit);
+                });
+            }
+            _ = shutdown_receiver.recv() => {
+                info!("🛑 Control socket shutting down");
+                break Ok(());
+            }
+        }
+    };
+    let _ = std::fs::remove_file(path);
+    result
+}
+
+#[cfg(not(unix))]
+pub async fn serve(_config: ControlSocketConfig, _calls: mpsc::Sender<ControlCall>, _shutdown_receiver: broadcast::Receiver<()>) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "the control socket needs a Unix platform"))
+}
+
+/// Read one request, wait for the main loop's answer and write it back
+#[cfg(unix)]
+async fn answer(stream: tokio::net::UnixStream, calls: &mpsc::Sender<ControlCall>) -> std::io::Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+    let actor = match stream.peer_cred() {
+        Ok(credentials) => format!("control socket uid {}", credentials.uid()),
+        Err(_) => "control socket".to_string(),
+    };
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader.take(MAX_REQUEST_BYTES));
+    let mut line = String::new();
+    tokio::time::timeout(REQUEST_TIMEOUT, reader.read_line(&mut line))
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "no request received"))??;
+
+    let response = match serde_json::from_str::<ControlRequest>(&line) {
+        Ok(request) => {
+            debug!("🎛️ Control request {} from {}", request.method(), actor);
+            let (reply, response) = oneshot::channel();
+            match calls.send(ControlCall { request, actor, reply }).await {
+                Ok(()) => match tokio::time::timeout(RESPONSE_TIMEOUT, response).await {
+                    Ok(Ok(response)) => response,
+                    Ok(Err(_)) => ControlResponse::failed("The agent is shutting down", Vec::new()),
+                    Err(_) => ControlResponse::failed("The agent did not answer in time", Vec::new()),
+                },
+                Err(_) => ControlResponse::failed("The agent is shutting down", Vec::new()),
+            }
+        }
+        Err(e) => ControlResponse::failed("Invalid control request", vec![e.to_string()]),
+    };
+    let mut line = serde_json::to_vec(&response).map_err(std::io::Error::other)?;
+    line.push(b'\n');
+    writer.write_all(&line).await
+}
+
+/// Send one request to a running agent's control socket and wait for its answer
+#[cfg(unix)]
+pub async fn request(path: &Path, request: &ControlRequest) -> std::io::Result<ControlResponse> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = tokio::net::UnixStream::connect(path).await?.into_split();
+    let mut line = serde_json::to_vec(request).map_err(std::io::Error::other)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+
+    let mut response = String::new();
+    BufReader::new(reader).read_line(&mut response).await?;
+    serde_json::from_str(&response).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(not(unix))]
+pub async fn request(_path: &Path, _request: &ControlRequest) -> std::io::Result<ControlResponse> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "the control socket needs a Unix platform"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_requests_are_answered_by_the_main_loop() {
+        use std::os::unix::fs::PermissionsExt;
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = ControlSocketConfig {
+            socket_path: dir.path().join("control.sock").display().to_string(),
+            ..Default::default()
+        };
+        let (calls, mut pending) = mpsc::channel::<ControlCall>(4);
+        let (shutdown_sender, shutdown_receiver) = broadcast::channel(1);
+        let server = tokio::spawn(serve(config.clone(), calls, shutdown_receiver));
+        tokio::spawn(async move {
+            while let Some(call) = pending.recv().await {
+                let response = match call.request {
+                    ControlRequest::CollectorStatus => ControlResponse::ok("1 collectors", serde_json::json!([{ "name": "syslog" }])),
+                    other => ControlResponse::failed(format!("unexpected {:?}", other), Vec::new()),
+                };
+                assert!(call.actor.starts_with("control socket"));
+                call.reply.send(response).unwrap();
+            }
+        });
+
+        let path = Path::new(&config.socket_path);
+        while !path.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(std::fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        let response = request(path, &ControlRequest::CollectorStatus).await.unwrap();
+        assert!(response.success);
+        assert_eq!(response.data[0]["name"], "syslog");
+
+        // A request the agent does not understand is answered rather than dropped
+        let (reader, mut writer) = tokio::net::UnixStream::connect(path).await.unwrap().into_split();
+        writer.write_all(b"{\"method\":\"format_disk\"}\n").await.unwrap();
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await.unwrap();
+        let response: ControlResponse = serde_json::from_str(&line).unwrap();
+        assert!(!response.success);
+        assert_eq!(response.message, "Invalid control request");
+
+        shutdown_sender.send(()).unwrap();
+        server.await.unwrap().unwrap();
+        assert!(!path.exists());
+    }
+}
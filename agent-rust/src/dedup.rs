@@ -0,0 +1,434 @@
+// Short-horizon duplicate filter for backfill-safe re-ingestion
+// Remembers a content hash per source for a configurable window so restarts, checkpoint
+// loss and re-read rotated files don't double-ship recent events. Hashes are stored once the
+// transport's batch is acknowledged; until then they are held in memory as in flight, so an
+// event that never reaches the server is not suppressed when it is read again.
+
+use crate::component_usage;
+use crate::errors::BufferError;
+use crate::parsers::ParsedEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+#[cfg(feature = "persistent-storage")]
+use rusqlite::Connection;
+
+/// Duplicate filter configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DedupConfig {
+    /// Enable duplicate filtering
+    pub enabled: bool,
+    /// SQLite database holding the seen hashes
+    pub database_path: String,
+    /// How long an event is remembered; repeats inside this window are dropped
+    pub window_seconds: u64,
+    /// Upper bound on remembered hashes; the oldest are evicted first
+    pub max_entries: usize,
+    /// How often expired hashes are purged
+    pub purge_interval_seconds: u64,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            database_path: "./buffer/dedup.db".to_string(),
+            window_seconds: 900,
+            max_entries: 1_000_000,
+            purge_interval_seconds: 60,
+        }
+    }
+}
+
+/// Duplicate filter metrics
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DedupStats {
+    pub events_checked: u64,
+    pub duplicates_dropped: u64,
+    pub entries: u64,
+    pub entries_purged: u64,
+    pub persistent: bool,
+}
+
+/// Source and content hash identifying an event to the filter
+pub type SeenKey = (String, Vec<u8>);
+
+#[cfg(feature = "persistent-storage")]
+type SeenStore = Connection;
+/// Without SQLite the window only survives as long as the process
+#[cfg(not(feature = "persistent-storage"))]
+type SeenStore = HashMap<SeenKey, i64>;
+
+/// Content-hash duplicate filter shared between the buffer and its maintenance task
+#[derive(Clone)]
+pub struct DuplicateFilter {
+    config: DedupConfig,
+    store: Arc<parking_lot::Mutex<SeenStore>>,
+    /// Accepted events not yet acknowledged, with when they were accepted
+    in_flight: Arc<parking_lot::Mutex<HashMap<SeenKey, i64>>>,
+    events_checked: Arc<AtomicU64>,
+    duplicates_dropped: Arc<AtomicU64>,
+    entries_purged: Arc<AtomicU64>,
+}
+
+impl DuplicateFilter {
+    pub fn open(config: DedupConfig) -> Result<Self, BufferError> {
+        let store = Self::open_store(&config)?;
+        info!("🧬 Duplicate filter initialized (window: {}s, max entries: {})",
+              config.window_seconds, config.max_entries);
+
+        Ok(Self {
+            config,
+            store: Arc::new(parking_lot::Mutex::new(store)),
+            in_flight: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            events_checked: Arc::new(AtomicU64::new(0)),
+            duplicates_dropped: Arc::new(AtomicU64::new(0)),
+            entries_purged: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    #[cfg(feature = "persistent-storage")]
+    fn open_store(config: &DedupConfig) -> Result<SeenStore, BufferError> {
+        let path = std::path::Path::new(&config.database_path);
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| persistence_error("create_directory", config, e))?;
+        }
+
+        let conn = Connection::open(path).map_err(|e| persistence_error("open_database", config, e))?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .and_then(|_| conn.pragma_update(None, "synchronous", "NORMAL"))
+            .and_then(|_| conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS seen_events (
+                    source TEXT NOT NULL,
+                    content_hash BLOB NOT NULL,
+                    first_seen INTEGER NOT NULL,
+                    PRIMARY KEY (source, content_hash)
+                ) WITHOUT ROWID;
+                CREATE INDEX IF NOT EXISTS idx_seen_events_first_seen ON seen_events(first_seen);",
+            ))
+            .map_err(|e| persistence_error("create_schema", config, e))?;
+
+        Ok(conn)
+    }
+
+    #[cfg(not(feature = "persistent-storage"))]
+    fn open_store(_config: &DedupConfig) -> Result<SeenStore, BufferError> {
+        warn!("⚠️ Persistent storage disabled; duplicate window will not survive restarts");
+        Ok(HashMap::new())
+    }
+
+    pub fn config(&self) -> &DedupConfig {
+        &self.config
+    }
+
+    /// Report whether the event was delivered inside the window or is already in flight; an
+    /// event that is neither is held as in flight until its batch is acknowledged
+    pub async fn is_duplicate(&self, source: &str, raw_data: &str) -> bool {
+        let now = crate::chaos::now().timestamp();
+        self.check_at(source, raw_data, now).await
+    }
+
+    async fn check_at(&self, source: &str, raw_data: &str, now: i64) -> bool {
+        self.events_checked.fetch_add(1, Ordering::Relaxed);
+        let key: SeenKey = (source.to_string(), content_hash(raw_data));
+        let cutoff = now - self.config.window_seconds as i64;
+
+        let mut duplicate = self.in_flight.lock().get(&key).is_some_and(|accepted| *accepted > cutoff);
+        if !duplicate {
+            // SQLite lookups stay off the async workers
+            let filter = self.clone();
+            let lookup = key.clone();
+            duplicate = match component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || filter.seen_since(&lookup, cutoff)).await {
+                Ok(Ok(seen)) => seen,
+                Ok(Err(e)) => {
+                    // Never lose events because the filter is unavailable
+                    warn!("⚠️ Duplicate filter lookup failed, passing event through: {}", e);
+                    false
+                }
+                Err(e) => {
+                    warn!("⚠️ Duplicate filter lookup failed, passing event through: {}", e);
+                    false
+                }
+            };
+        }
+        if !duplicate {
+            // A copy accepted while the store was being read wins
+            let mut in_flight = self.in_flight.lock();
+            match in_flight.get(&key) {
+                Some(accepted) if *accepted > cutoff => duplicate = true,
+                _ => {
+                    in_flight.insert(key, now);
+                }
+            }
+        }
+
+        if duplicate {
+            self.duplicates_dropped.fetch_add(1, Ordering::Relaxed);
+            debug!("🧬 Dropping duplicate event from {}", source);
+        }
+        duplicate
+    }
+
+    /// Keys of a batch about to be sent, taken before the transport trims it
+    pub fn keys(&self, events: &[ParsedEvent]) -> Vec<SeenKey> {
+        events.iter().map(|event| (event.source.clone(), content_hash(&event.raw_data))).collect()
+    }
+
+    /// Store the keys of an acknowledged batch in one transaction on the blocking pool
+    pub async fn record_delivered(&self, keys: Vec<SeenKey>) {
+        if keys.is_empty() {
+            return;
+        }
+        let now = crate::chaos::now().timestamp();
+        let filter = self.clone();
+        match component_usage::spawn_blocking(component_usage::BUFFER_WRITER, move || filter.record_at(keys, now)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("⚠️ Failed to record delivered events in the duplicate filter: {}", e),
+            Err(e) => warn!("⚠️ Failed to record delivered events in the duplicate filter: {}", e),
+        }
+    }
+
+    fn record_at(&self, keys: Vec<SeenKey>, now: i64) -> Result<(), StoreError> {
+        let cutoff = now - self.config.window_seconds as i64;
+        self.store_keys(&keys, now, cutoff)?;
+        let mut in_flight = self.in_flight.lock();
+        for key in &keys {
+            in_flight.remove(key);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "persistent-storage")]
+    fn seen_since(&self, key: &SeenKey, cutoff: i64) -> Result<bool, StoreError> {
+        let conn = self.store.lock();
+        let mut statement = conn.prepare_cached(
+            "SELECT 1 FROM seen_events WHERE source = ?1 AND content_hash = ?2 AND first_seen > ?3",
+        )?;
+        statement.exists(rusqlite::params![key.0, key.1, cutoff])
+    }
+
+    #[cfg(not(feature = "persistent-storage"))]
+    fn seen_since(&self, key: &SeenKey, cutoff: i64) -> Result<bool, StoreError> {
+        Ok(self.store.lock().get(key).is_some_and(|first_seen| *first_seen > cutoff))
+    }
+
+    #[cfg(feature = "persistent-storage")]
+    fn store_keys(&self, keys: &[SeenKey], now: i64, cutoff: i64) -> Result<(), StoreError> {
+        let mut conn = self.store.lock();
+        let transaction = conn.transaction()?;
+        {
+            // Inserts new hashes and restarts expired ones
+            let mut statement = transaction.prepare_cached(
+                "INSERT INTO seen_events (source, content_hash, first_seen) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(source, content_hash) DO UPDATE SET first_seen = excluded.first_seen
+                 WHERE seen_events.first_seen <= ?4",
+            )?;
+            for (source, hash) in keys {
+                statement.execute(rusqlite::params![source, hash, now, cutoff])?;
+            }
+        }
+        transaction.commit()
+    }
+
+    #[cfg(not(feature = "persistent-storage"))]
+    fn store_keys(&self, keys: &[SeenKey], now: i64, cutoff: i64) -> Result<(), StoreError> {
+        let mut store = self.store.lock();
+        for key in keys {
+            match store.get_mut(key) {
+                Some(first_seen) if *first_seen > cutoff => {}
+                Some(first_seen) => *first_seen = now,
+                None => {
+                    store.insert(key.clone(), now);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop expired hashes and enforce `max_entries`, returning how many were removed
+    pub fn purge_expired(&self) -> usize {
+        let cutoff = crate::chaos::now().timestamp() - self.config.window_seconds as i64;
+        match self.purge_before(cutoff) {
+            Ok(purged) => {
+                if purged > 0 {
+                    debug!("🧹 Purged {} expired duplicate filter entries", purged);
+                }
+                self.entries_purged.fetch_add(purged as u64, Ordering::Relaxed);
+                purged
+            }
+            Err(e) => {
+                warn!("⚠️ Failed to purge duplicate filter entries: {}", e);
+                0
+            }
+        }
+    }
+
+    #[cfg(feature = "persistent-storage")]
+    fn purge_before(&self, cutoff: i64) -> Result<usize, StoreError> {
+        self.purge_in_flight(cutoff);
+        let conn = self.store.lock();
+        let mut purged = conn.execute("DELETE FROM seen_events WHERE first_seen <= ?1", [cutoff])?;
+        let entries: i64 = conn.query_row("SELECT COUNT(*) FROM seen_events", [], |row| row.get(0))?;
+        let excess = entries - self.config.max_entries as i64;
+        if excess > 0 {
+            purged += conn.execute(
+                "DELETE FROM seen_events WHERE (source, content_hash) IN
+                 (SELECT source, content_hash FROM seen_events ORDER BY first_seen LIMIT ?1)",
+                [excess],
+            )?;
+        }
+        Ok(purged)
+    }
+
+    #[cfg(not(feature = "persistent-storage"))]
+    fn purge_before(&self, cutoff: i64) -> Result<usize, StoreError> {
+        self.purge_in_flight(cutoff);
+        let mut store = self.store.lock();
+        let before = store.len();
+        store.retain(|_, first_seen| *first_seen > cutoff);
+        if store.len() > self.config.max_entries {
+            let mut ages: Vec<i64> = store.values().copied().collect();
+            ages.sort_unstable();
+            let threshold = ages[store.len() - self.config.max_entries - 1];
+            store.retain(|_, first_seen| *first_seen > threshold);
+        }
+        Ok(before - store.len())
+    }
+
+    /// Forget in-flight events that were never acknowledged inside the window
+    fn purge_in_flight(&self, cutoff: i64) {
+        self.in_flight.lock().retain(|_, accepted| *accepted > cutoff);
+    }
+
+    pub fn stats(&self) -> DedupStats {
+        #[cfg(feature = "persistent-storage")]
+        let entries = self.store.lock()
+            .query_row("SELECT COUNT(*) FROM seen_events", [], |row| row.get::<_, i64>(0))
+            .unwrap_or(0) as u64;
+        #[cfg(not(feature = "persistent-storage"))]
+        let entries = self.store.lock().len() as u64;
+
+        DedupStats {
+            events_checked: self.events_checked.load(Ordering::Relaxed),
+            duplicates_dropped: self.duplicates_dropped.load(Ordering::Relaxed),
+            entries,
+            entries_purged: self.entries_purged.load(Ordering::Relaxed),
+            persistent: cfg!(feature = "persistent-storage"),
+        }
+    }
+}
+
+#[cfg(feature = "persistent-storage")]
+type StoreError = rusqlite::Error;
+#[cfg(not(feature = "persistent-storage"))]
+type StoreError = std::convert::Infallible;
+
+fn content_hash(raw_data: &str) -> Vec<u8> {
+    ring::digest::digest(&ring::digest::SHA256, raw_data.as_bytes()).as_ref().to_vec()
+}
+
+#[cfg(feature = "persistent-storage")]
+fn persistence_error(operation: &str, config: &DedupConfig, e: impl std::error::Error + Send + Sync + 'static) -> BufferError {
+    BufferError::PersistenceError {
+        operation: operation.to_string(),
+        database_path: config.database_path.clone(),
+        recoverable: true,
+        source: Box::new(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(dir: &std::path::Path) -> DuplicateFilter {
+        DuplicateFilter::open(DedupConfig {
+            enabled: true,
+            database_path: dir.join("dedup.db").display().to_string(),
+            window_seconds: 60,
+            max_entries: 2,
+            purge_interval_seconds: 1,
+        }).unwrap()
+    }
+
+    fn key(source: &str, raw_data: &str) -> SeenKey {
+        (source.to_string(), content_hash(raw_data))
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_inside_window_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let filter = filter(dir.path());
+
+        assert!(!filter.check_at("syslog", "event one", 1_000).await);
+        // In flight until acknowledged
+        assert!(filter.check_at("syslog", "event one", 1_030).await);
+        // Same content from another source is not a duplicate
+        assert!(!filter.check_at("file_monitor", "event one", 1_030).await);
+
+        filter.record_at(vec![key("syslog", "event one")], 1_000).unwrap();
+        assert!(filter.check_at("syslog", "event one", 1_030).await);
+        // Window restarts once the original delivery expires
+        assert!(!filter.check_at("syslog", "event one", 1_061).await);
+        assert!(filter.check_at("syslog", "event one", 1_062).await);
+
+        let stats = filter.stats();
+        assert_eq!(stats.events_checked, 6);
+        assert_eq!(stats.duplicates_dropped, 3);
+    }
+
+    #[tokio::test]
+    async fn test_only_acknowledged_events_are_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let filter = filter(dir.path());
+
+        assert!(!filter.check_at("syslog", "sent", 1_000).await);
+        assert!(!filter.check_at("syslog", "lost", 1_000).await);
+        filter.record_at(vec![key("syslog", "sent")], 1_001).unwrap();
+        assert_eq!(filter.stats().entries, 1);
+
+        // A copy of an event that was never acknowledged is let through once the window passes
+        filter.purge_before(1_000).unwrap();
+        assert!(!filter.check_at("syslog", "lost", 1_010).await);
+        assert!(filter.check_at("syslog", "sent", 1_010).await);
+    }
+
+    #[tokio::test]
+    async fn test_purge_enforces_ttl_and_capacity() {
+        let dir = tempfile::tempdir().unwrap();
+        let filter = filter(dir.path());
+        for (i, raw) in ["a", "b", "c", "d"].iter().enumerate() {
+            filter.record_at(vec![key("syslog", raw)], 1_000 + i as i64).unwrap();
+        }
+
+        assert_eq!(filter.purge_before(1_000).unwrap(), 2);
+        assert_eq!(filter.stats().entries, 2);
+        assert!(filter.check_at("syslog", "d", 1_010).await);
+        assert!(!filter.check_at("syslog", "a", 1_010).await);
+    }
+
+    #[cfg(feature = "persistent-storage")]
+    #[tokio::test]
+    async fn test_window_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let before = filter(dir.path());
+        assert!(!before.check_at("syslog", "before restart", now).await);
+        let event = ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "syslog".to_string(),
+            level: None,
+            message: "before restart".to_string(),
+            fields: Default::default(),
+            raw_data: "before restart".into(),
+            parser_name: "test".to_string(),
+        };
+        before.record_delivered(before.keys(&[event])).await;
+        drop(before);
+        assert!(filter(dir.path()).check_at("syslog", "before restart", now + 1).await);
+    }
+}
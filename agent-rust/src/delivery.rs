@@ -0,0 +1,352 @@
+// Event IDs and acknowledged delivery ranges
+// Events get a ULID in `event.id` before they are buffered, so every retry and every replay from the buffer carries
+// the same ID and the server can drop copies it already stored. Native batches advertise the acknowledgement
+// protocol; the server answers with the ID ranges it stored and each endpoint's transport remembers them, so a retry
+// after a partial failure, or a buffer replay of a send that half went through, only carries the events that were
+// not acknowledged. A server answering without ranges acknowledges the whole batch, as before.
+
+use crate::parsers::ParsedEvent;
+use parking_lot::Mutex;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// ECS field holding the event's ULID
+pub const EVENT_ID_FIELD: &str = "event.id";
+/// Sent with native batches so the server answers with acknowledged ranges and drops IDs it already stored
+pub const DELIVERY_HEADER: &str = "X-SecureWatch-Delivery";
+pub const DELIVERY_PROTOCOL: &str = "ack-ranges-v1";
+
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const ULID_LENGTH: usize = 26;
+const SEQUENCE_BITS: u32 = 80;
+const SEQUENCE_MASK: u128 = (1 << SEQUENCE_BITS) - 1;
+
+/// Event IDs and acknowledgement tracking (`transport.delivery`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeliveryConfig {
+    pub enabled: bool,
+    /// Acknowledged ID ranges remembered per endpoint; the oldest are forgotten first
+    pub max_tracked_ranges: usize,
+}
+
+impl Default for DeliveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_tracked_ranges: 100_000,
+        }
+    }
+}
+
+impl DeliveryConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.max_tracked_ranges == 0 {
+            errors.push("max_tracked_ranges must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// ULID: 48 bits of Unix milliseconds followed by 80 bits that start random
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EventId(u128);
+
+impl EventId {
+    /// Parse the 26-character Crockford base32 form, case-insensitively
+    pub fn parse(text: &str) -> Option<Self> {
+        if text.len() != ULID_LENGTH {
+            return None;
+        }
+        let mut value: u128 = 0;
+        for (i, c) in text.bytes().enumerate() {
+            let digit = CROCKFORD.iter().position(|d| *d == c.to_ascii_uppercase())? as u128;
+            // The first character carries only the top 3 bits
+            if i == 0 && digit > 7 {
+                return None;
+            }
+            value = (value << 5) | digit;
+        }
+        Some(Self(value))
+    }
+
+    pub fn timestamp_ms(self) -> u64 {
+        (self.0 >> SEQUENCE_BITS) as u64
+    }
+
+    fn sequence(self) -> u128 {
+        self.0 & SEQUENCE_MASK
+    }
+}
+
+impl fmt::Display for EventId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut text = [0u8; ULID_LENGTH];
+        for (i, c) in text.iter_mut().enumerate() {
+            *c = CROCKFORD[((self.0 >> (5 * (ULID_LENGTH - 1 - i))) & 0x1f) as usize];
+        }
+        f.write_str(std::str::from_utf8(&text).unwrap_or_default())
+    }
+}
+
+struct Generator {
+    last_ms: u64,
+    next_sequence: u128,
+}
+
+static GENERATOR: OnceLock<Mutex<Generator>> = OnceLock::new();
+
+/// A new ID. The 80-bit part is drawn once per process and incremented for every event (ULID monotonic mode,
+/// carried across milliseconds), so one agent's IDs are consecutive and acknowledged ranges stay few.
+pub fn next_event_id() -> EventId {
+    let generator = GENERATOR.get_or_init(|| {
+        let mut seed = [0u8; 16];
+        if SystemRandom::new().fill(&mut seed[6..]).is_err() {
+            let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos();
+            seed[6..].copy_from_slice(&nanos.to_be_bytes()[6..]);
+        }
+        // The top bit stays clear so the sequence can't wrap
+        seed[6] &= 0x7f;
+        Mutex::new(Generator { last_ms: 0, next_sequence: u128::from_be_bytes(seed) })
+    });
+    let mut generator = generator.lock();
+    // A clock stepping back doesn't make IDs go back
+    generator.last_ms = generator.last_ms.max(chrono::Utc::now().timestamp_millis().max(0) as u64);
+    let sequence = generator.next_sequence;
+    generator.next_sequence += 1;
+    EventId((u128::from(generator.last_ms) << SEQUENCE_BITS) | (sequence & SEQUENCE_MASK))
+}
+
+/// Record `event.id` unless the event already has one, so relayed events keep the ID given where they were collected
+pub fn stamp_event_id(event: &mut ParsedEvent) {
+    if !event.fields.contains_key(EVENT_ID_FIELD) {
+        event.fields.insert(EVENT_ID_FIELD.to_string(), Value::String(next_event_id().to_string()));
+    }
+}
+
+fn event_id(event: &ParsedEvent) -> Option<EventId> {
+    event.fields.get(EVENT_ID_FIELD)?.as_str().and_then(EventId::parse)
+}
+
+/// What the server stored of a batch
+#[derive(Debug, Clone, PartialEq)]
+pub enum Acknowledgement {
+    /// The whole batch: servers that don't send ranges, and every protocol but the native one
+    All,
+    /// Inclusive ID ranges; events outside them were not stored
+    Ranges(Vec<(EventId, EventId)>),
+}
+
+impl Acknowledgement {
+    /// Read a native response body, `{"acknowledged": [{"first": "<ulid>", "last": "<ulid>"}, ...]}`;
+    /// any other body acknowledges the batch
+    pub fn parse(body: &str) -> Self {
+        #[derive(Deserialize)]
+        struct Response {
+            acknowledged: Vec<Range>,
+        }
+        #[derive(Deserialize)]
+        struct Range {
+            first: String,
+            last: String,
+        }
+        match serde_json::from_str::<Response>(body) {
+            Ok(response) => Acknowledgement::Ranges(
+                response.acknowledged.iter()
+                    .filter_map(|range| Some((EventId::parse(&range.first)?, EventId::parse(&range.last)?)))
+                    .collect(),
+            ),
+            Err(_) => Acknowledgement::All,
+        }
+    }
+
+    fn covers(&self, id: EventId) -> bool {
+        match self {
+            Acknowledgement::All => true,
+            Acknowledgement::Ranges(ranges) => ranges.iter().any(|(first, last)| (*first..=*last).contains(&id)),
+        }
+    }
+}
+
+/// Range of consecutive sequence numbers
+#[derive(Debug, Clone, Copy)]
+struct Delivered {
+    last_sequence: u128,
+    /// Newest event time in the range, for forgetting the oldest ranges first
+    newest_ms: u64,
+}
+
+/// Acknowledged event IDs of one endpoint, kept as ranges of consecutive IDs
+pub struct DeliveryLedger {
+    /// First sequence number of each range
+    ranges: Mutex<BTreeMap<u128, Delivered>>,
+    max_ranges: usize,
+    skipped: AtomicU64,
+}
+
+impl DeliveryLedger {
+    pub fn new(config: &DeliveryConfig) -> Self {
+        Self {
+            ranges: Mutex::new(BTreeMap::new()),
+            max_ranges: config.max_tracked_ranges.max(1),
+            skipped: AtomicU64::new(0),
+        }
+    }
+
+    /// Drop events the server already acknowledged
+    pub fn undelivered(&self, mut events: Vec<ParsedEvent>) -> Vec<ParsedEvent> {
+        let before = events.len();
+        {
+            let ranges = self.ranges.lock();
+            events.retain(|event| !event_id(event).is_some_and(|id| Self::contains(&ranges, id)));
+        }
+        self.skipped.fetch_add((before - events.len()) as u64, Ordering::Relaxed);
+        events
+    }
+
+    /// Remember the events `acknowledgement` covers; returns how many it left out. Events without an ID (buffered
+    /// before IDs were enabled) can't be tracked and are counted as delivered.
+    pub fn record(&self, events: &[ParsedEvent], acknowledgement: &Acknowledgement) -> usize {
+        let mut ranges = self.ranges.lock();
+        let mut missing = 0;
+        for id in events.iter().filter_map(event_id) {
+            if acknowledgement.covers(id) {
+                Self::insert(&mut ranges, id);
+            } else {
+                missing += 1;
+            }
+        }
+        if ranges.len() > self.max_ranges {
+            self.forget_oldest(&mut ranges);
+        }
+        missing
+    }
+
+    /// Events left out of batches because an earlier attempt had already been acknowledged
+    pub fn skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    pub fn tracked_ranges(&self) -> usize {
+        self.ranges.lock().len()
+    }
+
+    fn contains(ranges: &BTreeMap<u128, Delivered>, id: EventId) -> bool {
+        let sequence = id.sequence();
+        ranges.range(..=sequence).next_back().is_some_and(|(_, range)| range.last_sequence >= sequence)
+    }
+
+    fn insert(ranges: &mut BTreeMap<u128, Delivered>, id: EventId) {
+        let sequence = id.sequence();
+        let newest_ms = id.timestamp_ms();
+        // Extend the range ending just before, or start a new one
+        let start = match ranges.range_mut(..=sequence).next_back() {
+            Some((_, range)) if range.last_sequence >= sequence => {
+                range.newest_ms = range.newest_ms.max(newest_ms);
+                return;
+            }
+            Some((start, range)) if range.last_sequence + 1 == sequence => {
+                range.last_sequence = sequence;
+                range.newest_ms = range.newest_ms.max(newest_ms);
+                *start
+            }
+            _ => {
+                ranges.insert(sequence, Delivered { last_sequence: sequence, newest_ms });
+                sequence
+            }
+        };
+        // Merge with the range starting just after
+        if let Some(next) = ranges.remove(&(sequence + 1)) {
+            if let Some(range) = ranges.get_mut(&start) {
+                range.last_sequence = next.last_sequence;
+                range.newest_ms = range.newest_ms.max(next.newest_ms);
+            }
+        }
+    }
+
+    /// Forget the ranges with the oldest events, down to 90% of the limit so this doesn't run on every batch
+    fn forget_oldest(&self, ranges: &mut BTreeMap<u128, Delivered>) {
+        let mut by_age: Vec<(u64, u128)> = ranges.iter().map(|(start, range)| (range.newest_ms, *start)).collect();
+        by_age.sort_unstable();
+        let excess = ranges.len() - self.max_ranges * 9 / 10;
+        for (_, start) in by_age.into_iter().take(excess) {
+            ranges.remove(&start);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn event(id: EventId) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "syslog".to_string(),
+            level: None,
+            message: String::new(),
+            fields: HashMap::from([(EVENT_ID_FIELD.to_string(), Value::String(id.to_string()))]),
+            raw_data: "".into(),
+            parser_name: "syslog".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_event_ids_are_monotonic_ulids() {
+        let ids: Vec<EventId> = (0..1000).map(|_| next_event_id()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1] && pair[0].sequence() < pair[1].sequence()));
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        assert!(now - ids[0].timestamp_ms() < 60_000);
+
+        let text = ids[0].to_string();
+        assert_eq!(text.len(), ULID_LENGTH);
+        assert_eq!(EventId::parse(&text.to_lowercase()), Some(ids[0]));
+        let reference = EventId::parse("01ARZ3NDEKTSV4RRFFQ69G5FAV").unwrap();
+        assert_eq!((reference.timestamp_ms(), reference.to_string().as_str()), (1_469_922_850_259, "01ARZ3NDEKTSV4RRFFQ69G5FAV"));
+        assert_eq!(EventId::parse("81ARZ3NDEKTSV4RRFFQ69G5FAV"), None);
+        assert_eq!(EventId::parse("01ARZ3NDEKTSV4RRFFQ69G5FAU"), None);
+
+        // IDs given upstream are kept
+        let mut relayed = event(ids[0]);
+        stamp_event_id(&mut relayed);
+        assert_eq!(relayed.fields[EVENT_ID_FIELD], text);
+    }
+
+    #[test]
+    fn test_partial_acknowledgement_is_not_sent_again() {
+        let ledger = DeliveryLedger::new(&DeliveryConfig { enabled: true, max_tracked_ranges: 10 });
+        // Consecutive IDs as one agent hands them out
+        let base = next_event_id();
+        let ids: Vec<EventId> = (0..10).map(|i| EventId(base.0 + i)).collect();
+        let batch: Vec<ParsedEvent> = ids.iter().map(|id| event(*id)).collect();
+
+        // The server stored the first four and the last two
+        let body = format!(r#"{{"acknowledged":[{{"first":"{}","last":"{}"}},{{"first":"{}","last":"{}"}}]}}"#, ids[0], ids[3], ids[8], ids[9]);
+        let acknowledgement = Acknowledgement::parse(&body);
+        assert_eq!(ledger.record(&batch, &acknowledgement), 4);
+        assert_eq!(ledger.tracked_ranges(), 2);
+
+        let retry = ledger.undelivered(batch.clone());
+        assert_eq!(retry.iter().map(|e| event_id(e).unwrap()).collect::<Vec<_>>(), ids[4..8]);
+        assert_eq!(ledger.skipped(), 6);
+
+        // A server without ranges acknowledges everything; the gap closes into one range
+        assert_eq!(Acknowledgement::parse(""), Acknowledgement::All);
+        assert_eq!(ledger.record(&retry, &Acknowledgement::parse("{\"status\":\"ok\"}")), 0);
+        assert_eq!(ledger.tracked_ranges(), 1);
+        assert!(ledger.undelivered(batch).is_empty());
+
+        // Separate ranges beyond the limit forget the oldest
+        let scattered: Vec<ParsedEvent> = (0..15).map(|i| event(EventId(base.0 + 20 + 2 * i))).collect();
+        ledger.record(&scattered, &Acknowledgement::All);
+        assert!(ledger.tracked_ranges() <= 10);
+        assert_eq!(ledger.undelivered(scattered).len(), 6);
+    }
+}
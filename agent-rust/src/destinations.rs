@@ -0,0 +1,305 @@
+// Additional transport destinations with per-destination routing
+// Events are fanned out to every named destination whose routes match; each destination gets its own
+// HTTP client, retry policy and circuit breaker, so one unreachable SIEM does not stall the others
+
+use crate::compression::CompressionAlgorithm;
+use crate::config::{TransportConfig, TransportProtocol};
+use crate::otlp::OtlpConfig;
+use crate::payload_format::PayloadFormat;
+use crate::syslog_output::SyslogOutputConfig;
+use crate::splunk_hec::SplunkHecConfig;
+use crate::elasticsearch::ElasticsearchConfig;
+use crate::parsers::ParsedEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Which events the primary `server_url` keeps receiving once named destinations are configured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimaryRoute {
+    /// Only events no named destination routed
+    #[default]
+    Unmatched,
+    /// Every event, in addition to the named destinations
+    All,
+    /// Nothing; events that match no destination are dropped
+    Disabled,
+}
+
+/// Named destination; settings left unset are inherited from the primary transport
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DestinationConfig {
+    /// Name used in logs, stats and validation messages
+    pub name: String,
+    pub server_url: String,
+    pub api_key: Option<String>,
+    pub protocol: Option<TransportProtocol>,
+    pub otlp: Option<OtlpConfig>,
+    pub syslog: Option<SyslogOutputConfig>,
+    pub splunk_hec: Option<SplunkHecConfig>,
+    pub elasticsearch: Option<ElasticsearchConfig>,
+    pub format: Option<PayloadFormat>,
+    pub tls_verify: Option<bool>,
+    pub ca_cert_path: Option<String>,
+    pub client_cert_path: Option<String>,
+    pub client_key_path: Option<String>,
+    pub batch_size: Option<usize>,
+    pub retry_attempts: Option<usize>,
+    pub retry_delay: Option<u64>,
+    pub compression: Option<CompressionAlgorithm>,
+    /// Level for this destination's algorithm; when the algorithm is overridden the primary level is not inherited
+    pub compression_level: Option<i32>,
+    /// An event is sent here when any route matches; no routes means every event
+    pub routes: Vec<RouteRule>,
+}
+
+/// Route criteria; all criteria that are set must match
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RouteRule {
+    /// Event sources, e.g. "windows_event_log" or "syslog"
+    pub sources: Vec<String>,
+    /// Parser names
+    pub parsers: Vec<String>,
+    /// Event field to inspect, e.g. "event_id"
+    pub field: Option<String>,
+    /// Required value of `field`; without it the field only has to be present
+    pub equals: Option<String>,
+}
+
+impl RouteRule {
+    pub fn matches(&self, event: &ParsedEvent) -> bool {
+        if !self.sources.is_empty() && !self.sources.iter().any(|s| s.eq_ignore_ascii_case(&event.source)) {
+            return false;
+        }
+        if !self.parsers.is_empty() && !self.parsers.iter().any(|p| p == &event.parser_name) {
+            return false;
+        }
+        match (&self.field, event_field(event, self.field.as_deref())) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(_), Some(value)) => match &self.equals {
+                Some(expected) => value_text(&value) == *expected,
+                None => true,
+            },
+        }
+    }
+}
+
+impl DestinationConfig {
+    pub fn matches(&self, event: &ParsedEvent) -> bool {
+        self.routes.is_empty() || self.routes.iter().any(|route| route.matches(event))
+    }
+
+    /// Transport settings for this destination on top of the primary transport's
+    pub fn resolve(&self, base: &TransportConfig) -> TransportConfig {
+        let mut config = base.clone();
+        config.server_url = self.server_url.clone();
+        config.destinations = Vec::new();
+        config.primary_route = PrimaryRoute::All;
+        if let Some(api_key) = &self.api_key {
+            config.api_key = api_key.clone();
+        }
+        if let Some(protocol) = self.protocol {
+            config.protocol = protocol;
+        }
+        if let Some(otlp) = &self.otlp {
+            config.otlp = otlp.clone();
+        }
+        if let Some(syslog) = &self.syslog {
+            config.syslog = syslog.clone();
+        }
+        if let Some(splunk_hec) = &self.splunk_hec {
+            config.splunk_hec = splunk_hec.clone();
+        }
+        if let Some(elasticsearch) = &self.elasticsearch {
+            config.elasticsearch = elasticsearch.clone();
+        }
+        if let Some(format) = self.format {
+            config.format = format;
+        }
+        if let Some(tls_verify) = self.tls_verify {
+            config.tls_verify = tls_verify;
+        }
+        if self.ca_cert_path.is_some() {
+            config.ca_cert_path = self.ca_cert_path.clone();
+        }
+        if self.client_cert_path.is_some() || self.client_key_path.is_some() {
+            config.client_cert_path = self.client_cert_path.clone();
+            config.client_key_path = self.client_key_path.clone();
+            config.client_key_password = None;
+            config.identity.spiffe = None;
+        }
+        config.batch_size = self.batch_size.unwrap_or(config.batch_size);
+        config.retry_attempts = self.retry_attempts.unwrap_or(config.retry_attempts);
+        config.retry_delay = self.retry_delay.unwrap_or(config.retry_delay);
+        if let Some(compression) = self.compression {
+            config.compression = compression;
+            config.compression_level = None;
+        }
+        if self.compression_level.is_some() {
+            config.compression_level = self.compression_level;
+        }
+        config
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.name.trim().is_empty() {
+            errors.push("name cannot be empty".to_string());
+        }
+        if self.protocol == Some(TransportProtocol::Syslog) {
+            if let Err(e) = crate::syslog_output::parse_endpoint(&self.server_url) {
+                errors.push(e);
+            }
+        } else {
+            match url::Url::parse(&self.server_url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(_) => errors.push("server_url must use HTTP or HTTPS scheme".to_string()),
+                Err(e) => errors.push(format!("invalid server_url: {}", e)),
+            }
+        }
+        if self.client_cert_path.is_some() != self.client_key_path.is_some() {
+            errors.push("client_cert_path and client_key_path must be set together".to_string());
+        }
+        if self.batch_size == Some(0) {
+            errors.push("batch_size must be greater than 0".to_string());
+        }
+        if self.retry_attempts == Some(0) {
+            errors.push("retry_attempts must be greater than 0".to_string());
+        }
+        if let (Some(compression), Some(level)) = (self.compression, self.compression_level) {
+            let range = compression.level_range();
+            if compression.is_enabled() && !range.contains(&level) {
+                errors.push(format!("compression_level {} is outside {:?}'s range {}-{}", level, compression, range.start(), range.end()));
+            }
+        }
+        if let Some(otlp) = &self.otlp {
+            errors.extend(otlp.validate().into_iter().map(|e| format!("OTLP {}", e)));
+        }
+        if let Some(syslog) = &self.syslog {
+            errors.extend(syslog.validate().into_iter().map(|e| format!("syslog {}", e)));
+        }
+        if let Some(splunk_hec) = &self.splunk_hec {
+            errors.extend(splunk_hec.validate().into_iter().map(|e| format!("Splunk HEC {}", e)));
+        }
+        if let Some(elasticsearch) = &self.elasticsearch {
+            errors.extend(elasticsearch.validate().into_iter().map(|e| format!("Elasticsearch {}", e)));
+        }
+        for (i, route) in self.routes.iter().enumerate() {
+            if route.equals.is_some() && route.field.is_none() {
+                errors.push(format!("route {} sets equals without field", i + 1));
+            }
+        }
+        errors
+    }
+}
+
+/// Validate the destination list as a whole (names must be unique)
+pub fn validate_destinations(destinations: &[DestinationConfig]) -> Vec<String> {
+    let mut errors = Vec::new();
+    for (i, destination) in destinations.iter().enumerate() {
+        errors.extend(destination.validate().into_iter().map(|e| format!("destination '{}': {}", destination.name, e)));
+        if destinations[..i].iter().any(|other| other.name == destination.name) {
+            errors.push(format!("duplicate destination name '{}'", destination.name));
+        }
+    }
+    errors
+}
+
+fn event_field(event: &ParsedEvent, field: Option<&str>) -> Option<Value> {
+    match field? {
+        "source" => Some(Value::String(event.source.clone())),
+        "parser_name" => Some(Value::String(event.parser_name.clone())),
+        "level" => event.level.clone().map(Value::String),
+        "message" => Some(Value::String(event.message.clone())),
+        name => event.fields.get(name).cloned(),
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn event(source: &str, parser: &str, fields: &[(&str, Value)]) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: source.to_string(),
+            level: Some("info".to_string()),
+            message: "test".to_string(),
+            fields: fields.iter().map(|(k, v)| (k.to_string(), v.clone())).collect::<HashMap<_, _>>(),
+            raw_data: "".into(),
+            parser_name: parser.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_routes_match_source_parser_and_field() {
+        let destination = DestinationConfig {
+            name: "siem-a".to_string(),
+            server_url: "https://siem-a.example.com/ingest".to_string(),
+            routes: vec![
+                RouteRule { sources: vec!["windows_event_log".to_string()], ..Default::default() },
+                RouteRule { field: Some("event_id".to_string()), equals: Some("4625".to_string()), ..Default::default() },
+            ],
+            ..Default::default()
+        };
+
+        assert!(destination.matches(&event("Windows_Event_Log", "windows", &[])));
+        assert!(destination.matches(&event("syslog", "sshd", &[("event_id", serde_json::json!(4625))])));
+        assert!(!destination.matches(&event("syslog", "sshd", &[("event_id", serde_json::json!(4624))])));
+        assert!(!destination.matches(&event("syslog", "sshd", &[])));
+
+        let catch_all = DestinationConfig { routes: Vec::new(), ..destination };
+        assert!(catch_all.matches(&event("syslog", "sshd", &[])));
+    }
+
+    #[test]
+    fn test_resolve_inherits_unset_settings_and_validates() {
+        let base = crate::config::AgentConfig::default().transport;
+        let destination = DestinationConfig {
+            name: "siem-b".to_string(),
+            server_url: "https://siem-b.example.com/logs".to_string(),
+            retry_attempts: Some(7),
+            ..Default::default()
+        };
+        let resolved = destination.resolve(&base);
+        assert_eq!(resolved.server_url, "https://siem-b.example.com/logs");
+        assert_eq!(resolved.retry_attempts, 7);
+        assert_eq!(resolved.api_key, base.api_key);
+        assert_eq!(resolved.batch_size, base.batch_size);
+        assert!(resolved.destinations.is_empty());
+        assert_eq!(resolved.compression, base.compression);
+
+        let gzip = DestinationConfig { compression: Some(CompressionAlgorithm::Gzip), ..destination.clone() };
+        let resolved = gzip.resolve(&base);
+        assert_eq!((resolved.compression, resolved.compression_level), (CompressionAlgorithm::Gzip, None));
+        assert!(DestinationConfig { compression_level: Some(15), ..gzip }.validate().iter().any(|e| e.contains("compression_level")));
+
+        let duplicate = vec![destination.clone(), destination];
+        assert!(validate_destinations(&duplicate).iter().any(|e| e.contains("duplicate")));
+        let invalid = DestinationConfig { name: String::new(), server_url: "ftp://x".to_string(), ..Default::default() };
+        assert_eq!(invalid.validate().len(), 2);
+
+        let legacy = DestinationConfig {
+            name: "legacy-siem".to_string(),
+            server_url: "tls://siem-legacy.example.com:6514".to_string(),
+            protocol: Some(TransportProtocol::Syslog),
+            syslog: Some(SyslogOutputConfig { facility: "local4".to_string(), ..Default::default() }),
+            ..Default::default()
+        };
+        assert!(legacy.validate().is_empty());
+        assert_eq!(legacy.resolve(&base).syslog.facility, "local4");
+        let https_syslog = DestinationConfig { server_url: "https://siem-legacy.example.com".to_string(), ..legacy };
+        assert!(https_syslog.validate()[0].contains("tls://"));
+    }
+}
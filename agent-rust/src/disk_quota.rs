@@ -0,0 +1,217 @@
+// Free-space policy for the volume hosting the disk buffer
+// max_database_size_mb caps the buffer's own database, but other files on the same volume can still fill it. The
+// resource monitor's disk samples are matched to the volume holding persistence_path: below the throttle threshold
+// the buffer holds backpressure so collectors slow down, and below the stop threshold it stops writing to disk and
+// drops spilled events rather than failing SQLite writes on a full volume.
+
+use crate::resource_monitor::DiskMetrics;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use tracing::{info, warn};
+
+/// Free space must climb this far above a threshold before the policy steps back down
+const RECOVERY_MARGIN: f64 = 1.1;
+
+/// Thresholds apply when free space falls below either the absolute or the percentage value; 0 disables one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiskQuotaConfig {
+    pub enabled: bool,
+    pub throttle_free_mb: u64,
+    pub throttle_free_percent: f64,
+    pub stop_free_mb: u64,
+    pub stop_free_percent: f64,
+}
+
+impl Default for DiskQuotaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            throttle_free_mb: 2048,
+            throttle_free_percent: 10.0,
+            stop_free_mb: 512,
+            stop_free_percent: 5.0,
+        }
+    }
+}
+
+impl DiskQuotaConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (name, percent) in [("throttle_free_percent", self.throttle_free_percent), ("stop_free_percent", self.stop_free_percent)] {
+            if !(0.0..100.0).contains(&percent) {
+                errors.push(format!("{} must be between 0 and 100", name));
+            }
+        }
+        if self.stop_free_mb > self.throttle_free_mb {
+            errors.push("stop_free_mb cannot be larger than throttle_free_mb".to_string());
+        }
+        if self.stop_free_percent > self.throttle_free_percent {
+            errors.push("stop_free_percent cannot be larger than throttle_free_percent".to_string());
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskPressure {
+    Normal,
+    /// Backpressure on collectors; disk writes continue
+    Throttle,
+    /// No further disk writes
+    Stop,
+}
+
+impl DiskPressure {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            2 => Self::Stop,
+            1 => Self::Throttle,
+            _ => Self::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskQuotaStats {
+    pub pressure: DiskPressure,
+    /// Mount point matched to persistence_path, once a sample has been seen
+    pub mount_point: Option<String>,
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+    pub events_dropped: u64,
+}
+
+pub struct DiskQuota {
+    config: DiskQuotaConfig,
+    path: PathBuf,
+    pressure: AtomicU8,
+    mount_point: parking_lot::Mutex<Option<String>>,
+    available_bytes: AtomicU64,
+    total_bytes: AtomicU64,
+    events_dropped: AtomicU64,
+}
+
+impl DiskQuota {
+    pub fn new(config: DiskQuotaConfig, persistence_path: &str) -> Self {
+        // Symlinked buffer directories are matched by where they really live
+        let path = std::fs::canonicalize(persistence_path).unwrap_or_else(|_| PathBuf::from(persistence_path));
+        Self {
+            config,
+            path,
+            pressure: AtomicU8::new(DiskPressure::Normal as u8),
+            mount_point: parking_lot::Mutex::new(None),
+            available_bytes: AtomicU64::new(0),
+            total_bytes: AtomicU64::new(0),
+            events_dropped: AtomicU64::new(0),
+        }
+    }
+
+    pub fn pressure(&self) -> DiskPressure {
+        DiskPressure::from_u8(self.pressure.load(Ordering::Relaxed))
+    }
+
+    /// Re-evaluate from a resource monitor sample; returns the new pressure when it changed
+    pub fn update(&self, disks: &[DiskMetrics]) -> Option<DiskPressure> {
+        let disk = volume_for(&self.path, disks)?;
+        self.available_bytes.store(disk.available_bytes, Ordering::Relaxed);
+        self.total_bytes.store(disk.total_bytes, Ordering::Relaxed);
+        *self.mount_point.lock() = Some(disk.mount_point.clone());
+
+        let current = self.pressure();
+        let mut next = pressure_for(&self.config, disk.available_bytes, disk.total_bytes, 1.0);
+        if next < current {
+            // Step down only once free space has cleared the threshold by the recovery margin
+            next = next.max(pressure_for(&self.config, disk.available_bytes, disk.total_bytes, RECOVERY_MARGIN)).min(current);
+        }
+        if next == current {
+            return None;
+        }
+        self.pressure.store(next as u8, Ordering::Relaxed);
+        let free_mb = disk.available_bytes / (1024 * 1024);
+        match next {
+            DiskPressure::Stop => warn!("💽 Only {} MB free on {}, disk buffering stopped", free_mb, disk.mount_point),
+            DiskPressure::Throttle => warn!("💽 {} MB free on {}, throttling disk buffering", free_mb, disk.mount_point),
+            DiskPressure::Normal => info!("💽 {} MB free on {}, disk buffering resumed", free_mb, disk.mount_point),
+        }
+        Some(next)
+    }
+
+    pub fn record_dropped(&self) {
+        self.events_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> DiskQuotaStats {
+        DiskQuotaStats {
+            pressure: self.pressure(),
+            mount_point: self.mount_point.lock().clone(),
+            available_bytes: self.available_bytes.load(Ordering::Relaxed),
+            total_bytes: self.total_bytes.load(Ordering::Relaxed),
+            events_dropped: self.events_dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The disk with the longest mount point containing `path`
+fn volume_for<'a>(path: &Path, disks: &'a [DiskMetrics]) -> Option<&'a DiskMetrics> {
+    disks
+        .iter()
+        .filter(|disk| path.starts_with(&disk.mount_point))
+        .max_by_key(|disk| disk.mount_point.len())
+}
+
+/// Pressure for a volume with thresholds scaled by `margin`
+fn pressure_for(config: &DiskQuotaConfig, available_bytes: u64, total_bytes: u64, margin: f64) -> DiskPressure {
+    let available_mb = available_bytes as f64 / (1024.0 * 1024.0);
+    let available_percent = if total_bytes > 0 { available_bytes as f64 / total_bytes as f64 * 100.0 } else { 100.0 };
+    let below = |free_mb: u64, free_percent: f64| {
+        (free_mb > 0 && available_mb < free_mb as f64 * margin)
+            || (free_percent > 0.0 && available_percent < free_percent * margin)
+    };
+    if below(config.stop_free_mb, config.stop_free_percent) {
+        DiskPressure::Stop
+    } else if below(config.throttle_free_mb, config.throttle_free_percent) {
+        DiskPressure::Throttle
+    } else {
+        DiskPressure::Normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disk(mount_point: &str, available_mb: u64) -> DiskMetrics {
+        let total_bytes = 100 * 1024 * 1024 * 1024;
+        let available_bytes = available_mb * 1024 * 1024;
+        DiskMetrics {
+            name: "sda1".to_string(),
+            mount_point: mount_point.to_string(),
+            total_bytes,
+            used_bytes: total_bytes - available_bytes,
+            available_bytes,
+            usage_percent: 0.0,
+            file_system: "ext4".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_pressure_follows_the_buffer_volume() {
+        let config = DiskQuotaConfig { throttle_free_percent: 0.0, stop_free_percent: 0.0, ..Default::default() };
+        let quota = DiskQuota::new(config, "/var/lib/securewatch/buffer");
+
+        // The root volume is nearly full, but the buffer lives on /var/lib
+        assert_eq!(quota.update(&[disk("/", 100), disk("/var/lib", 10_000)]), None);
+        assert_eq!(quota.update(&[disk("/", 100), disk("/var/lib", 1_000)]), Some(DiskPressure::Throttle));
+        assert_eq!(quota.update(&[disk("/var/lib", 400)]), Some(DiskPressure::Stop));
+        assert_eq!(quota.stats().mount_point.as_deref(), Some("/var/lib"));
+
+        // Just above the stop threshold is still inside the recovery margin
+        assert_eq!(quota.update(&[disk("/var/lib", 530)]), None);
+        assert_eq!(quota.update(&[disk("/var/lib", 600)]), Some(DiskPressure::Throttle));
+        assert_eq!(quota.update(&[disk("/var/lib", 5_000)]), Some(DiskPressure::Normal));
+        assert_eq!(quota.pressure(), DiskPressure::Normal);
+    }
+}
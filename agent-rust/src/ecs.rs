@@ -0,0 +1,270 @@
+// Elastic Common Schema normalization of parsed events
+// Renames parser output fields to ECS names (source.ip, user.name, event.action, ...) from a mapping file layered
+// over built-in aliases for common vendor field names, so correlation rules and searches work on one schema
+// regardless of the original log format. Runs after processor chains and before enrichment.
+
+use crate::parsers::ParsedEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tracing::info;
+
+/// ECS release the built-in aliases follow
+pub const ECS_VERSION: &str = "8.11.0";
+pub const ECS_VERSION_FIELD: &str = "ecs.version";
+
+/// Common non-ECS field names and the ECS field each one becomes
+const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    ("src_ip", "source.ip"),
+    ("srcip", "source.ip"),
+    ("source_ip", "source.ip"),
+    ("src_port", "source.port"),
+    ("srcport", "source.port"),
+    ("source_port", "source.port"),
+    ("dst_ip", "destination.ip"),
+    ("dstip", "destination.ip"),
+    ("dest_ip", "destination.ip"),
+    ("destination_ip", "destination.ip"),
+    ("dst_port", "destination.port"),
+    ("dstport", "destination.port"),
+    ("dest_port", "destination.port"),
+    ("destination_port", "destination.port"),
+    ("client_ip", "client.ip"),
+    ("user", "user.name"),
+    ("username", "user.name"),
+    ("user_name", "user.name"),
+    ("uid", "user.id"),
+    ("hostname", "host.name"),
+    ("pid", "process.pid"),
+    ("ppid", "process.parent.pid"),
+    ("program", "process.name"),
+    ("process_name", "process.name"),
+    ("cmdline", "process.command_line"),
+    ("command_line", "process.command_line"),
+    ("action", "event.action"),
+    ("outcome", "event.outcome"),
+    ("proto", "network.transport"),
+    ("protocol", "network.transport"),
+    ("method", "http.request.method"),
+    ("status_code", "http.response.status_code"),
+    ("url", "url.original"),
+    ("user_agent", "user_agent.original"),
+];
+
+/// ECS fields typed as integers; string values holding a whole number are converted
+const INTEGER_FIELDS: &[&str] = &[
+    "source.port",
+    "destination.port",
+    "client.port",
+    "process.pid",
+    "process.parent.pid",
+    "http.response.status_code",
+    "source.bytes",
+    "destination.bytes",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EcsConfig {
+    pub enabled: bool,
+    /// TOML mapping file; optional when the built-in aliases are enough
+    pub mapping_file: Option<String>,
+    /// Map common non-ECS names (src_ip, username, dst_port, ...) without listing them in the mapping file
+    pub builtin_aliases: bool,
+    /// Keep the original field next to its ECS name instead of renaming it
+    pub keep_original: bool,
+}
+
+impl Default for EcsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mapping_file: None,
+            builtin_aliases: true,
+            keep_original: false,
+        }
+    }
+}
+
+impl EcsConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        match &self.mapping_file {
+            Some(path) if !Path::new(path).is_file() => errors.push(format!("mapping_file {} does not exist", path)),
+            None if !self.builtin_aliases => {
+                errors.push("nothing to map: set mapping_file or enable builtin_aliases".to_string());
+            }
+            _ => {}
+        }
+        errors
+    }
+}
+
+/// Mappings for one scope: every event, one source type or one parser
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EcsMappingSet {
+    /// Original field name to ECS field name
+    pub fields: HashMap<String, String>,
+    /// ECS fields set to a constant when absent, e.g. `"event.category" = "authentication"`
+    pub set: HashMap<String, Value>,
+}
+
+/// Contents of the mapping file; parser mappings take precedence over source mappings, which take precedence
+/// over the top-level ones
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EcsMappingFile {
+    #[serde(flatten)]
+    pub global: EcsMappingSet,
+    pub sources: HashMap<String, EcsMappingSet>,
+    pub parsers: HashMap<String, EcsMappingSet>,
+}
+
+/// A mapping set in a fixed order, so the same event always normalizes the same way
+#[derive(Debug, Default)]
+struct CompiledSet {
+    fields: Vec<(String, String)>,
+    set: Vec<(String, Value)>,
+}
+
+impl From<EcsMappingSet> for CompiledSet {
+    fn from(mappings: EcsMappingSet) -> Self {
+        let fields: BTreeMap<String, String> = mappings.fields.into_iter().collect();
+        let set: BTreeMap<String, Value> = mappings.set.into_iter().collect();
+        Self { fields: fields.into_iter().collect(), set: set.into_iter().collect() }
+    }
+}
+
+pub struct EcsNormalizer {
+    global: CompiledSet,
+    sources: HashMap<String, CompiledSet>,
+    parsers: HashMap<String, CompiledSet>,
+    keep_original: bool,
+}
+
+impl EcsNormalizer {
+    /// Read the mapping file (if any) and combine it with the built-in aliases
+    pub fn load(config: &EcsConfig) -> Result<Self, String> {
+        if let Some(error) = config.validate().into_iter().next() {
+            return Err(error);
+        }
+        let mapping = match &config.mapping_file {
+            Some(path) => {
+                let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read mapping file {}: {}", path, e))?;
+                toml::from_str(&content).map_err(|e| format!("invalid mapping file {}: {}", path, e))?
+            }
+            None => EcsMappingFile::default(),
+        };
+        let normalizer = Self::from_mapping(config, mapping);
+        info!("🗺️ ECS normalization enabled: {} field mappings, {} source and {} parser overrides",
+              normalizer.global.fields.len(), normalizer.sources.len(), normalizer.parsers.len());
+        Ok(normalizer)
+    }
+
+    pub fn from_mapping(config: &EcsConfig, mapping: EcsMappingFile) -> Self {
+        let mut global = CompiledSet::from(mapping.global);
+        if config.builtin_aliases {
+            for (from, to) in BUILTIN_ALIASES {
+                if !global.fields.iter().any(|(mapped, _)| mapped == from) {
+                    global.fields.push((from.to_string(), to.to_string()));
+                }
+            }
+        }
+        Self {
+            global,
+            sources: mapping.sources.into_iter().map(|(source, set)| (source, set.into())).collect(),
+            parsers: mapping.parsers.into_iter().map(|(parser, set)| (parser, set.into())).collect(),
+            keep_original: config.keep_original,
+        }
+    }
+
+    /// Rename mapped fields, fill constants and coerce integer ECS fields; existing ECS fields are never overwritten
+    pub fn normalize(&self, event: &mut ParsedEvent) {
+        let scopes = [self.parsers.get(&event.parser_name), self.sources.get(&event.source), Some(&self.global)];
+        for scope in scopes.into_iter().flatten() {
+            for (from, to) in &scope.fields {
+                if from == to || event.fields.contains_key(to) {
+                    continue;
+                }
+                let value = if self.keep_original { event.fields.get(from).cloned() } else { event.fields.remove(from) };
+                if let Some(value) = value {
+                    event.fields.insert(to.clone(), value);
+                }
+            }
+            for (field, value) in &scope.set {
+                event.fields.entry(field.clone()).or_insert_with(|| value.clone());
+            }
+        }
+
+        for field in INTEGER_FIELDS {
+            if let Some(value) = event.fields.get_mut(*field) {
+                if let Some(number) = value.as_str().and_then(|s| s.trim().parse::<i64>().ok()) {
+                    *value = Value::from(number);
+                }
+            }
+        }
+        if let Some(level) = &event.level {
+            event.fields.entry("log.level".to_string()).or_insert_with(|| Value::String(level.to_lowercase()));
+        }
+        event.fields.entry(ECS_VERSION_FIELD.to_string()).or_insert_with(|| Value::String(ECS_VERSION.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_mapping_file_overrides_builtins_by_scope() {
+        let mapping: EcsMappingFile = toml::from_str(r#"
+            [fields]
+            client = "source.ip"
+
+            [sources.syslog.fields]
+            user = "user.id"
+
+            [parsers.sshd_auth.fields]
+            user = "user.name"
+
+            [parsers.sshd_auth.set]
+            "event.category" = "authentication"
+        "#).unwrap();
+        let normalizer = EcsNormalizer::from_mapping(&EcsConfig { enabled: true, ..Default::default() }, mapping);
+
+        let event = |parser: &str| ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "syslog".to_string(),
+            level: Some("WARNING".to_string()),
+            message: "Failed password".to_string(),
+            fields: HashMap::from([
+                ("user".to_string(), json!("root")),
+                ("client".to_string(), json!("203.0.113.9")),
+                ("dst_port".to_string(), json!("22")),
+                ("user.name".to_string(), json!("kept")),
+            ]),
+            raw_data: "".into(),
+            parser_name: parser.to_string(),
+        };
+
+        let mut sshd = event("sshd_auth");
+        sshd.fields.remove("user.name");
+        normalizer.normalize(&mut sshd);
+        assert_eq!(sshd.fields["user.name"], "root");
+        assert_eq!(sshd.fields["source.ip"], "203.0.113.9");
+        assert_eq!(sshd.fields["destination.port"], 22);
+        assert_eq!(sshd.fields["event.category"], "authentication");
+        assert_eq!(sshd.fields["log.level"], "warning");
+        assert_eq!(sshd.fields[ECS_VERSION_FIELD], ECS_VERSION);
+        assert!(!sshd.fields.contains_key("user") && !sshd.fields.contains_key("dst_port"));
+
+        // The source scope applies to other parsers, and existing ECS fields are left alone
+        let mut other = event("syslog_rfc3164");
+        normalizer.normalize(&mut other);
+        assert_eq!(other.fields["user.id"], "root");
+        assert_eq!(other.fields["user.name"], "kept");
+        assert!(!other.fields.contains_key("event.category"));
+    }
+}
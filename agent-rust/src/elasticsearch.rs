@@ -0,0 +1,274 @@
+// Elasticsearch and OpenSearch _bulk output
+// Each event becomes an action line naming its index (rendered from a template, so daily or per-source indexes
+// and data streams work) followed by the document. Document IDs derived from the content make retries idempotent:
+// with the default `create` operation, documents already indexed by an earlier attempt come back as conflicts.
+
+use crate::config::TransportConfig;
+use crate::output_template::{self, EventTemplate};
+use crate::parsers::ParsedEvent;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// Appended to `server_url` when it names only the cluster
+pub const BULK_PATH: &str = "/_bulk";
+
+/// Bulk action written for each document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkOperation {
+    /// Fails with a conflict when the ID exists; required for data streams
+    #[default]
+    Create,
+    /// Overwrites a document with the same ID
+    Index,
+}
+
+impl BulkOperation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BulkOperation::Create => "create",
+            BulkOperation::Index => "index",
+        }
+    }
+}
+
+/// Elasticsearch/OpenSearch settings, used when the transport protocol is `elasticsearch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ElasticsearchConfig {
+    /// Bulk endpoint; defaults to `server_url`, with `/_bulk` appended when it has no path
+    pub endpoint: Option<String>,
+    /// Index or data stream template
+    pub index: String,
+    pub operation: BulkOperation,
+    /// Ingest pipeline documents are run through
+    pub pipeline: Option<String>,
+    /// Derive document IDs from the event content; otherwise the cluster assigns them and a retried batch can
+    /// index duplicates
+    pub content_ids: bool,
+    /// Authenticate with HTTP basic auth as this user with `transport.api_key` as the password; otherwise the API
+    /// key is sent as an Elasticsearch `ApiKey` credential
+    pub username: Option<String>,
+    /// Attach raw_data as `event.original`
+    pub include_raw_data: bool,
+}
+
+impl Default for ElasticsearchConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            index: "securewatch-{source}-{date:%Y.%m.%d}".to_string(),
+            operation: BulkOperation::Create,
+            pipeline: None,
+            content_ids: true,
+            username: None,
+            include_raw_data: false,
+        }
+    }
+}
+
+impl ElasticsearchConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(endpoint) = &self.endpoint {
+            match url::Url::parse(endpoint) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(_) => errors.push("endpoint must use HTTP or HTTPS scheme".to_string()),
+                Err(e) => errors.push(format!("invalid endpoint: {}", e)),
+            }
+        }
+        if self.index.trim().is_empty() {
+            errors.push("index cannot be empty".to_string());
+        } else if let Err(e) = EventTemplate::parse(&self.index) {
+            errors.push(format!("index: {}", e));
+        }
+        if self.pipeline.as_deref().is_some_and(|p| p.trim().is_empty()) {
+            errors.push("pipeline cannot be empty".to_string());
+        }
+        errors
+    }
+}
+
+/// Per-document results of a bulk request
+#[derive(Debug, Default, PartialEq)]
+pub struct BulkOutcome {
+    pub items: usize,
+    /// `create` conflicts: the document was indexed by an earlier attempt
+    pub duplicates: usize,
+    /// Throttled (429) or failed on the cluster (5xx); resending the batch can succeed
+    pub retryable: usize,
+    /// Rejected for the document itself, e.g. mapping conflicts; resending won't help
+    pub rejected: usize,
+    pub first_error: Option<String>,
+}
+
+impl BulkOutcome {
+    /// Summarize a bulk response body
+    pub fn parse(body: &str) -> Result<Self, String> {
+        let response: Value = serde_json::from_str(body).map_err(|e| format!("invalid bulk response: {}", e))?;
+        let items = response["items"].as_array().ok_or("bulk response has no items")?;
+        let mut outcome = BulkOutcome { items: items.len(), ..Default::default() };
+        if response["errors"].as_bool() != Some(true) {
+            return Ok(outcome);
+        }
+        for result in items.iter().filter_map(|item| item.as_object()?.values().next()) {
+            let status = result["status"].as_u64().unwrap_or(0);
+            match status {
+                200..=299 => continue,
+                409 => outcome.duplicates += 1,
+                429 | 500..=599 => outcome.retryable += 1,
+                _ => outcome.rejected += 1,
+            }
+            if status != 409 && outcome.first_error.is_none() {
+                let error = &result["error"];
+                outcome.first_error = Some(format!("{} {}: {}", status, error["type"].as_str().unwrap_or("error"),
+                                                   error["reason"].as_str().unwrap_or_default()));
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+/// Builds bulk request bodies with the index template parsed once
+pub struct BulkEncoder {
+    url: String,
+    index: EventTemplate,
+    operation: BulkOperation,
+    content_ids: bool,
+    include_raw_data: bool,
+}
+
+impl BulkEncoder {
+    pub fn new(config: &TransportConfig) -> Result<Self, String> {
+        let es = &config.elasticsearch;
+        let mut url = url::Url::parse(&output_template::endpoint_url(es.endpoint.as_deref().unwrap_or(&config.server_url), BULK_PATH))
+            .map_err(|e| format!("invalid Elasticsearch endpoint: {}", e))?;
+        if let Some(pipeline) = &es.pipeline {
+            url.query_pairs_mut().append_pair("pipeline", pipeline);
+        }
+        Ok(Self {
+            url: url.to_string(),
+            index: EventTemplate::parse(&es.index)?,
+            operation: es.operation,
+            content_ids: es.content_ids,
+            include_raw_data: es.include_raw_data,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// NDJSON body of action and document line pairs
+    pub fn encode<'a>(&self, events: impl IntoIterator<Item = &'a ParsedEvent>, agent_id: &str) -> serde_json::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        for event in events {
+            let document = serde_json::to_vec(&self.document(event, agent_id))?;
+            let mut action = json!({ "_index": index_name(&self.index.render(event)) });
+            if self.content_ids {
+                // The agent ID and timestamp go into the document, so only true duplicates share an ID
+                let hash = digest::digest(&digest::SHA256, &document);
+                action["_id"] = json!(hash.as_ref()[..20].iter().map(|b| format!("{:02x}", b)).collect::<String>());
+            }
+            serde_json::to_writer(&mut body, &json!({ self.operation.as_str(): action }))?;
+            body.push(b'\n');
+            body.extend_from_slice(&document);
+            body.push(b'\n');
+        }
+        Ok(body)
+    }
+
+    /// Event fields with ECS-style metadata on top
+    fn document(&self, event: &ParsedEvent, agent_id: &str) -> Value {
+        let mut document: Map<String, Value> = event.fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        document.insert("@timestamp".to_string(), json!(event.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)));
+        document.insert("message".to_string(), json!(event.message));
+        if let Some(level) = &event.level {
+            document.insert("log.level".to_string(), json!(level));
+        }
+        document.insert("securewatch.source".to_string(), json!(event.source));
+        document.insert("securewatch.parser".to_string(), json!(event.parser_name));
+        document.insert("agent.id".to_string(), json!(agent_id));
+        document.insert("agent.type".to_string(), json!("securewatch"));
+        document.insert("agent.version".to_string(), json!(env!("CARGO_PKG_VERSION")));
+        if self.include_raw_data {
+            document.insert("event.original".to_string(), json!(event.raw_data.as_ref()));
+        }
+        Value::Object(document)
+    }
+}
+
+/// Index names are lowercase and can't contain `\ / * ? " < > | , #`, spaces or a leading `-`, `_` or `+`
+fn index_name(rendered: &str) -> String {
+    let name: String = rendered
+        .to_lowercase()
+        .chars()
+        .map(|c| if matches!(c, '\\' | '/' | '*' | '?' | '"' | '<' | '>' | '|' | ',' | '#' | ':') || c.is_whitespace() { '_' } else { c })
+        .collect();
+    name.trim_start_matches(['-', '_', '+']).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn event(message: &str) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::DateTime::from_timestamp_millis(1_710_025_200_250).unwrap(),
+            source: "Windows Event Log".to_string(),
+            level: Some("warning".to_string()),
+            message: message.to_string(),
+            fields: HashMap::from([("event_id".to_string(), serde_json::json!(4625))]),
+            raw_data: "<Event/>".into(),
+            parser_name: "windows_security".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_bulk_body_with_index_template_and_content_ids() {
+        let mut config = crate::config::AgentConfig::default().transport;
+        config.server_url = "https://es.example.com:9200".to_string();
+        config.elasticsearch.pipeline = Some("securewatch".to_string());
+        config.elasticsearch.include_raw_data = true;
+        let encoder = BulkEncoder::new(&config).unwrap();
+        assert_eq!(encoder.url(), "https://es.example.com:9200/_bulk?pipeline=securewatch");
+
+        let body = encoder.encode([&event("one"), &event("one"), &event("two")], "agent-1").unwrap();
+        let lines: Vec<Value> = body.split(|b| *b == b'\n').filter(|l| !l.is_empty()).map(|l| serde_json::from_slice(l).unwrap()).collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0]["create"]["_index"], "securewatch-windows_event_log-2024.03.09");
+        assert_eq!(lines[0]["create"]["_id"], lines[2]["create"]["_id"]);
+        assert_ne!(lines[0]["create"]["_id"], lines[4]["create"]["_id"]);
+        assert_eq!(lines[1]["@timestamp"], "2024-03-09T23:00:00.250Z");
+        assert_eq!(lines[1]["event_id"], 4625);
+        assert_eq!(lines[1]["log.level"], "warning");
+        assert_eq!(lines[1]["agent.id"], "agent-1");
+        assert_eq!(lines[1]["event.original"], "<Event/>");
+
+        config.elasticsearch = ElasticsearchConfig { operation: BulkOperation::Index, content_ids: false, index: "logs-{parser}".to_string(), ..Default::default() };
+        let body = BulkEncoder::new(&config).unwrap().encode([&event("one")], "agent-1").unwrap();
+        let action: Value = serde_json::from_slice(body.split(|b| *b == b'\n').next().unwrap()).unwrap();
+        assert_eq!(action, json!({ "index": { "_index": "logs-windows_security" } }));
+    }
+
+    #[test]
+    fn test_bulk_outcome_classifies_item_errors() {
+        assert_eq!(BulkOutcome::parse(r#"{"errors":false,"items":[{"create":{"status":201}}]}"#).unwrap().items, 1);
+
+        let body = r#"{"errors":true,"items":[
+            {"create":{"status":201}},
+            {"create":{"status":409,"error":{"type":"version_conflict_engine_exception","reason":"exists"}}},
+            {"create":{"status":429,"error":{"type":"es_rejected_execution_exception","reason":"queue full"}}},
+            {"create":{"status":400,"error":{"type":"mapper_parsing_exception","reason":"failed to parse field [event_id]"}}}
+        ]}"#;
+        let outcome = BulkOutcome::parse(body).unwrap();
+        assert_eq!((outcome.items, outcome.duplicates, outcome.retryable, outcome.rejected), (4, 1, 1, 1));
+        assert_eq!(outcome.first_error.as_deref(), Some("429 es_rejected_execution_exception: queue full"));
+        assert!(BulkOutcome::parse("not json").is_err());
+
+        assert!(ElasticsearchConfig::default().validate().is_empty());
+        assert_eq!(ElasticsearchConfig { index: " ".to_string(), ..Default::default() }.validate(), vec!["index cannot be empty"]);
+    }
+}
@@ -2,6 +2,7 @@
 // Implements graceful shutdown coordination when system resources reach critical levels
 
 use crate::errors::{AgentError, Result};
+use crate::load_shedding::LoadShedder;
 use crate::resource_monitor::{ResourceAlert, AlertLevel, ResourceMetrics};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -181,6 +182,9 @@ pub struct EmergencyShutdownCoordinator {
     // Resource thresholds tracking
     last_metrics: Arc<RwLock<Option<ResourceMetrics>>>,
     
+    // Shutdown waits until load shedding has nothing left to shed
+    load_shedder: Option<Arc<LoadShedder>>,
+    
     start_time: Instant,
 }
 
@@ -208,10 +212,21 @@ impl EmergencyShutdownCoordinator {
             shutdown_sender,
             event_sender,
             last_metrics: Arc::new(RwLock::new(None)),
+            load_shedder: None,
             start_time: Instant::now(),
         })
     }
     
+    /// Hold off shutting down while load shedding can still reduce usage; call before `start_monitoring`
+    pub fn set_load_shedder(&mut self, shedder: Arc<LoadShedder>) {
+        self.load_shedder = Some(shedder);
+    }
+    
+    /// Load shedding, when present, has applied every stage
+    fn shedding_exhausted(shedder: &Option<Arc<LoadShedder>>) -> bool {
+        shedder.as_ref().is_none_or(|shedder| shedder.is_exhausted())
+    }
+    
     /// Start monitoring for emergency conditions
     pub async fn start_monitoring(
         &self,
@@ -250,6 +265,7 @@ impl EmergencyShutdownCoordinator {
         let alert_tracker = self.alert_tracker.clone();
         let event_sender = self.event_sender.clone();
         let shutdown_initiated = self.shutdown_initiated.clone();
+        let load_shedder = self.load_shedder.clone();
         
         tokio::spawn(async move {
             while let Ok(alert) = alert_receiver.recv().await {
@@ -288,7 +304,10 @@ impl EmergencyShutdownCoordinator {
                             _ => false,
                         };
                         
-                        if should_shutdown && alert.current_value >= Self::get_emergency_threshold(&config, &alert.resource_type) {
+                        if should_shutdown && alert.current_value >= Self::get_emergency_threshold(&config, &alert.resource_type)
+                            && !Self::shedding_exhausted(&load_shedder) {
+                            warn!("📉 Critical threshold reached for {}, shedding load before any shutdown", alert.resource_type);
+                        } else if should_shutdown && alert.current_value >= Self::get_emergency_threshold(&config, &alert.resource_type) {
                             error!("🚨 EMERGENCY: Critical threshold reached for {} - initiating shutdown", 
                                    alert.resource_type);
                             
@@ -356,6 +375,7 @@ impl EmergencyShutdownCoordinator {
         let event_sender = self.event_sender.clone();
         let shutdown_initiated = self.shutdown_initiated.clone();
         let last_metrics = self.last_metrics.clone();
+        let load_shedder = self.load_shedder.clone();
         
         tokio::spawn(async move {
             let mut consecutive_emergency_count = 0u32;
@@ -387,6 +407,10 @@ impl EmergencyShutdownCoordinator {
                     consecutive_emergency_count += 1;
                     
                     if consecutive_emergency_count >= config.critical_alert_threshold &&
+                       !shutdown_initiated.load(Ordering::SeqCst) && !Self::shedding_exhausted(&load_shedder) {
+                        warn!("📉 Emergency thresholds exceeded ({}), shedding load before any shutdown",
+                              emergency_conditions.join(", "));
+                    } else if consecutive_emergency_count >= config.critical_alert_threshold &&
                        !shutdown_initiated.load(Ordering::SeqCst) {
                         
                         error!("🚨 EMERGENCY: Direct threshold monitoring detected critical conditions");
@@ -745,6 +769,8 @@ mod tests {
             disk: vec![],
             network: vec![],
             processes: None,
+            agent_process: None,
+            components: vec![],
             system: crate::resource_monitor::SystemMetrics {
                 hostname: "test".to_string(),
                 os_name: "test".to_string(),
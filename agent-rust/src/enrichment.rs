@@ -0,0 +1,776 @@
+// Event enrichment between parsing and buffering
+// Enrichers attach context to parsed event fields: GeoIP data for IP addresses, reverse DNS names, the local
+// hostname and agent tags, cloud instance identity, static labels, and threat intel indicator matches. They run in configured order after the parser's processor chain,
+// so alert rules and outbound field filters see the enriched event; a failing enricher never drops the event
+
+use crate::cloud_metadata::{self, CloudProvider};
+use crate::config::AgentSettings;
+use crate::parsers::ParsedEvent;
+use crate::proxy::ProxyConfig;
+use crate::threat_intel::{ThreatIntel, ThreatIntelConfig};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// Enrichment stage configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnrichmentConfig {
+    pub enabled: bool,
+    /// Enrichers, applied in order
+    pub enrichers: Vec<EnricherConfig>,
+}
+
+/// A configured enricher and the events it applies to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnricherConfig {
+    /// Name used in logs and statistics; defaults to the enricher type
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Event sources the enricher applies to; empty means any source
+    #[serde(default)]
+    pub sources: Vec<String>,
+    #[serde(flatten)]
+    pub kind: EnricherKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EnricherKind {
+    /// Geolocation and ASN data from a MaxMind (GeoLite2/GeoIP2 City, Country or ASN) database
+    Geoip {
+        database_path: String,
+        /// Fields holding IP addresses; `source.ip` is enriched into `source.geo.*` and `source.as.*`
+        #[serde(default = "default_ip_fields")]
+        fields: Vec<String>,
+    },
+    /// Reverse DNS names for IP fields; `source.ip` is enriched into `source.domain`
+    ReverseDns {
+        #[serde(default = "default_ip_fields")]
+        fields: Vec<String>,
+        #[serde(default = "default_dns_cache_size")]
+        cache_size: usize,
+        /// How long answers (including failed lookups) are cached
+        #[serde(default = "default_dns_cache_ttl_seconds")]
+        cache_ttl_seconds: u64,
+        #[serde(default = "default_dns_timeout_ms")]
+        timeout_ms: u64,
+    },
+    /// Local hostname, agent name and agent tags
+    Host {
+        /// Replace a host.hostname the event already carries
+        #[serde(default)]
+        overwrite: bool,
+    },
+    /// Cloud instance identity (`cloud.*`) from the EC2, GCE or Azure metadata service, looked up once at startup
+    Cloud {
+        /// Providers to probe; when several answer the first listed wins
+        #[serde(default = "CloudProvider::all")]
+        providers: Vec<CloudProvider>,
+        #[serde(default = "default_cloud_timeout_ms")]
+        timeout_ms: u64,
+        /// Add instance tags as `cloud.tags.<key>`
+        #[serde(default = "default_cloud_include_tags")]
+        include_tags: bool,
+        /// Replace cloud fields the event already carries
+        #[serde(default)]
+        overwrite: bool,
+    },
+    /// Static labels, added as `labels.<key>`
+    Labels {
+        labels: HashMap<String, String>,
+        /// Replace labels the event already carries
+        #[serde(default)]
+        overwrite: bool,
+    },
+    /// IP, domain and file hash indicators from STIX/TAXII or CSV feeds; hits are added to `threat.enrichments`
+    ThreatIntel(ThreatIntelConfig),
+}
+
+fn default_ip_fields() -> Vec<String> {
+    vec!["source.ip".to_string(), "destination.ip".to_string(), "client.ip".to_string()]
+}
+
+fn default_cloud_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_cloud_include_tags() -> bool {
+    true
+}
+
+fn default_dns_cache_size() -> usize {
+    10000
+}
+
+fn default_dns_cache_ttl_seconds() -> u64 {
+    3600
+}
+
+fn default_dns_timeout_ms() -> u64 {
+    500
+}
+
+impl EnricherConfig {
+    pub fn display_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| match &self.kind {
+            EnricherKind::Geoip { .. } => "geoip",
+            EnricherKind::ReverseDns { .. } => "reverse_dns",
+            EnricherKind::Host { .. } => "host",
+            EnricherKind::Cloud { .. } => "cloud",
+            EnricherKind::Labels { .. } => "labels",
+            EnricherKind::ThreatIntel(_) => "threat_intel",
+        }.to_string())
+    }
+}
+
+impl EnrichmentConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for enricher in &self.enrichers {
+            let name = enricher.display_name();
+            match &enricher.kind {
+                EnricherKind::Geoip { database_path, fields } => {
+                    if database_path.is_empty() {
+                        errors.push(format!("enricher '{}': database_path is required", name));
+                    }
+                    if fields.is_empty() {
+                        errors.push(format!("enricher '{}': at least one IP field is required", name));
+                    }
+                }
+                EnricherKind::ReverseDns { fields, cache_size, timeout_ms, .. } => {
+                    if fields.is_empty() {
+                        errors.push(format!("enricher '{}': at least one IP field is required", name));
+                    }
+                    if *cache_size == 0 {
+                        errors.push(format!("enricher '{}': cache_size must be greater than 0", name));
+                    }
+                    if *timeout_ms == 0 || *timeout_ms > 10_000 {
+                        errors.push(format!("enricher '{}': timeout_ms must be between 1 and 10000", name));
+                    }
+                }
+                EnricherKind::Labels { labels, .. } => {
+                    if labels.keys().any(|key| key.is_empty()) {
+                        errors.push(format!("enricher '{}': label keys must not be empty", name));
+                    }
+                }
+                EnricherKind::Cloud { providers, timeout_ms, .. } => {
+                    if providers.is_empty() {
+                        errors.push(format!("enricher '{}': at least one provider is required", name));
+                    }
+                    if *timeout_ms == 0 || *timeout_ms > 10_000 {
+                        errors.push(format!("enricher '{}': timeout_ms must be between 1 and 10000", name));
+                    }
+                }
+                EnricherKind::ThreatIntel(config) => {
+                    errors.extend(config.validate().into_iter().map(|e| format!("enricher '{}': {}", name, e)));
+                }
+                EnricherKind::Host { .. } => {}
+            }
+        }
+        errors
+    }
+}
+
+/// A stage that adds fields to parsed events
+#[async_trait]
+pub trait Enricher: Send + Sync {
+    /// Add fields to the event; returns whether anything was added
+    async fn enrich(&self, event: &mut ParsedEvent) -> Result<bool, String>;
+    fn name(&self) -> &str;
+    fn applies_to(&self, event: &ParsedEvent) -> bool;
+}
+
+/// Per-enricher counters
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EnricherStats {
+    pub name: String,
+    pub events_enriched: u64,
+    pub errors: u64,
+}
+
+struct Stage {
+    enricher: Box<dyn Enricher>,
+    enriched: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// Ordered enrichers applied to every parsed event
+pub struct EnrichmentPipeline {
+    stages: Vec<Stage>,
+}
+
+impl EnrichmentPipeline {
+    /// `proxy` routes enrichers that download data (threat intel feeds) like the transport
+    pub fn new(config: &EnrichmentConfig, agent: &AgentSettings, proxy: &ProxyConfig) -> Result<Self, String> {
+        if let Some(error) = config.validate().into_iter().next() {
+            return Err(error);
+        }
+        let mut pipeline = Self { stages: Vec::new() };
+        for enricher in &config.enrichers {
+            let name = enricher.display_name();
+            let sources = enricher.sources.clone();
+            let built: Box<dyn Enricher> = match &enricher.kind {
+                EnricherKind::Geoip { database_path, fields } => {
+                    Box::new(GeoIpEnricher::open(name, sources, database_path, fields.clone())?)
+                }
+                EnricherKind::ReverseDns { fields, cache_size, cache_ttl_seconds, timeout_ms } => Box::new(ReverseDnsEnricher {
+                    name,
+                    sources,
+                    fields: fields.clone(),
+                    cache: Mutex::new(HashMap::new()),
+                    cache_size: *cache_size,
+                    cache_ttl: Duration::from_secs(*cache_ttl_seconds),
+                    timeout: Duration::from_millis(*timeout_ms),
+                }),
+                EnricherKind::Host { overwrite } => Box::new(HostEnricher::new(name, sources, agent, *overwrite)),
+                EnricherKind::Cloud { providers, timeout_ms, include_tags, overwrite } => Box::new(CloudEnricher::new(
+                    name,
+                    sources,
+                    providers.clone(),
+                    Duration::from_millis(*timeout_ms),
+                    *include_tags,
+                    *overwrite,
+                )),
+                EnricherKind::Labels { labels, overwrite } => Box::new(LabelsEnricher {
+                    name,
+                    sources,
+                    labels: labels.clone(),
+                    overwrite: *overwrite,
+                }),
+                EnricherKind::ThreatIntel(config) => {
+                    Box::new(ThreatIntelEnricher::new(name, sources, config.clone(), proxy.clone()))
+                }
+            };
+            info!("🧩 Enricher loaded: {}", built.name());
+            pipeline.register(built);
+        }
+        Ok(pipeline)
+    }
+
+    /// Add an enricher after the configured ones
+    pub fn register(&mut self, enricher: Box<dyn Enricher>) {
+        self.stages.push(Stage { enricher, enriched: AtomicU64::new(0), errors: AtomicU64::new(0) });
+    }
+
+    pub async fn enrich(&self, event: &mut ParsedEvent) {
+        for stage in &self.stages {
+            if !stage.enricher.applies_to(event) {
+                continue;
+            }
+            match stage.enricher.enrich(event).await {
+                Ok(true) => {
+                    stage.enriched.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    stage.errors.fetch_add(1, Ordering::Relaxed);
+                    debug!("⚠️ Enricher '{}' failed: {}", stage.enricher.name(), e);
+                }
+            }
+        }
+    }
+
+    pub fn get_stats(&self) -> Vec<EnricherStats> {
+        self.stages.iter().map(|stage| EnricherStats {
+            name: stage.enricher.name().to_string(),
+            events_enriched: stage.enriched.load(Ordering::Relaxed),
+            errors: stage.errors.load(Ordering::Relaxed),
+        }).collect()
+    }
+}
+
+fn applies_to_source(sources: &[String], event: &ParsedEvent) -> bool {
+    sources.is_empty() || sources.contains(&event.source)
+}
+
+/// IP addresses in the configured fields, with the field prefix enrichment results are written under
+fn ip_fields<'a>(event: &ParsedEvent, fields: &'a [String]) -> Vec<(&'a str, IpAddr)> {
+    fields.iter().filter_map(|field| {
+        let ip = event.fields.get(field)?.as_str()?.trim().parse().ok()?;
+        Some((field.strip_suffix(".ip").unwrap_or(field), ip))
+    }).collect()
+}
+
+/// MaxMind database lookups
+pub struct GeoIpEnricher {
+    name: String,
+    sources: Vec<String>,
+    #[cfg(feature = "geoip")]
+    fields: Vec<String>,
+    #[cfg(feature = "geoip")]
+    reader: maxminddb::Reader<Vec<u8>>,
+    #[cfg(feature = "geoip")]
+    asn_database: bool,
+}
+
+impl GeoIpEnricher {
+    #[cfg(feature = "geoip")]
+    pub fn open(name: String, sources: Vec<String>, database_path: &str, fields: Vec<String>) -> Result<Self, String> {
+        let reader = maxminddb::Reader::open_readfile(database_path)
+            .map_err(|e| format!("enricher '{}': cannot open GeoIP database {}: {}", name, database_path, e))?;
+        let asn_database = reader.metadata.database_type.contains("ASN");
+        info!("🌍 GeoIP database {} ({}) loaded", database_path, reader.metadata.database_type);
+        Ok(Self { name, sources, fields, reader, asn_database })
+    }
+
+    #[cfg(not(feature = "geoip"))]
+    pub fn open(name: String, _sources: Vec<String>, _database_path: &str, _fields: Vec<String>) -> Result<Self, String> {
+        Err(format!("enricher '{}': this build does not include the geoip feature", name))
+    }
+
+    #[cfg(feature = "geoip")]
+    fn lookup(&self, ip: IpAddr) -> Result<Vec<(String, Value)>, maxminddb::MaxMindDBError> {
+        use maxminddb::geoip2;
+
+        let mut values = Vec::new();
+        if self.asn_database {
+            let asn: geoip2::Asn = self.reader.lookup(ip)?;
+            if let Some(number) = asn.autonomous_system_number {
+                values.push(("as.number".to_string(), json!(number)));
+            }
+            if let Some(organization) = asn.autonomous_system_organization {
+                values.push(("as.organization.name".to_string(), json!(organization)));
+            }
+            return Ok(values);
+        }
+
+        // City databases are a superset of Country databases; the city and location parts are just absent
+        let city: geoip2::City = self.reader.lookup(ip)?;
+        if let Some(continent) = &city.continent {
+            if let Some(code) = continent.code {
+                values.push(("geo.continent_code".to_string(), json!(code)));
+            }
+        }
+        if let Some(country) = &city.country {
+            if let Some(iso_code) = country.iso_code {
+                values.push(("geo.country_iso_code".to_string(), json!(iso_code)));
+            }
+            if let Some(name) = english(country.names.as_ref()) {
+                values.push(("geo.country_name".to_string(), json!(name)));
+            }
+        }
+        if let Some(region) = city.subdivisions.as_ref().and_then(|s| s.first()) {
+            if let Some(name) = english(region.names.as_ref()) {
+                values.push(("geo.region_name".to_string(), json!(name)));
+            }
+        }
+        if let Some(name) = english(city.city.as_ref().and_then(|c| c.names.as_ref())) {
+            values.push(("geo.city_name".to_string(), json!(name)));
+        }
+        if let Some(location) = &city.location {
+            if let (Some(lat), Some(lon)) = (location.latitude, location.longitude) {
+                values.push(("geo.location".to_string(), json!({ "lat": lat, "lon": lon })));
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// English name from a MaxMind names map
+#[cfg(feature = "geoip")]
+fn english<'a>(names: Option<&std::collections::BTreeMap<&'a str, &'a str>>) -> Option<&'a str> {
+    names.and_then(|names| names.get("en").copied())
+}
+
+#[async_trait]
+impl Enricher for GeoIpEnricher {
+    #[cfg(feature = "geoip")]
+    async fn enrich(&self, event: &mut ParsedEvent) -> Result<bool, String> {
+        let mut enriched = false;
+        for (prefix, ip) in ip_fields(event, &self.fields) {
+            match self.lookup(ip) {
+                Ok(values) => {
+                    for (key, value) in values {
+                        event.fields.insert(format!("{}.{}", prefix, key), value);
+                        enriched = true;
+                    }
+                }
+                // Private and unrouted addresses are simply not in the database
+                Err(maxminddb::MaxMindDBError::AddressNotFoundError(_)) => {}
+                Err(e) => return Err(format!("lookup of {} failed: {}", ip, e)),
+            }
+        }
+        Ok(enriched)
+    }
+
+    #[cfg(not(feature = "geoip"))]
+    async fn enrich(&self, _event: &mut ParsedEvent) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, event: &ParsedEvent) -> bool {
+        applies_to_source(&self.sources, event)
+    }
+}
+
+/// Cached reverse DNS lookups through the system resolver
+pub struct ReverseDnsEnricher {
+    name: String,
+    sources: Vec<String>,
+    fields: Vec<String>,
+    /// Address -> (name, when it was resolved); failed lookups are cached as None
+    cache: Mutex<HashMap<IpAddr, (Option<String>, Instant)>>,
+    cache_size: usize,
+    cache_ttl: Duration,
+    timeout: Duration,
+}
+
+impl ReverseDnsEnricher {
+    fn cached(&self, ip: &IpAddr) -> Option<Option<String>> {
+        let cache = self.cache.lock();
+        cache.get(ip)
+            .filter(|(_, resolved_at)| resolved_at.elapsed() < self.cache_ttl)
+            .map(|(name, _)| name.clone())
+    }
+
+    fn remember(&self, ip: IpAddr, name: Option<String>) {
+        let mut cache = self.cache.lock();
+        if cache.len() >= self.cache_size && !cache.contains_key(&ip) {
+            let ttl = self.cache_ttl;
+            cache.retain(|_, (_, resolved_at)| resolved_at.elapsed() < ttl);
+            if cache.len() >= self.cache_size {
+                if let Some(oldest) = cache.iter().min_by_key(|(_, (_, resolved_at))| *resolved_at).map(|(ip, _)| *ip) {
+                    cache.remove(&oldest);
+                }
+            }
+        }
+        cache.insert(ip, (name, Instant::now()));
+    }
+
+    async fn resolve(&self, ip: IpAddr) -> Option<String> {
+        if let Some(name) = self.cached(&ip) {
+            return name;
+        }
+        let lookup = tokio::task::spawn_blocking(move || dns_lookup::lookup_addr(&ip));
+        let name = match tokio::time::timeout(self.timeout, lookup).await {
+            Ok(Ok(Ok(name))) if name.parse::<IpAddr>().is_err() => Some(name.trim_end_matches('.').to_string()),
+            Ok(_) => None,
+            // Leave slow answers uncached so a later event can pick them up
+            Err(_) => return None,
+        };
+        self.remember(ip, name.clone());
+        name
+    }
+}
+
+#[async_trait]
+impl Enricher for ReverseDnsEnricher {
+    async fn enrich(&self, event: &mut ParsedEvent) -> Result<bool, String> {
+        let mut enriched = false;
+        for (prefix, ip) in ip_fields(event, &self.fields) {
+            let key = format!("{}.domain", prefix);
+            if event.fields.contains_key(&key) {
+                continue;
+            }
+            if let Some(name) = self.resolve(ip).await {
+                event.fields.insert(key, json!(name));
+                enriched = true;
+            }
+        }
+        Ok(enriched)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, event: &ParsedEvent) -> bool {
+        applies_to_source(&self.sources, event)
+    }
+}
+
+/// Identity of the host and agent that collected the event
+pub struct HostEnricher {
+    name: String,
+    sources: Vec<String>,
+    hostname: Option<String>,
+    agent_name: String,
+    agent_tags: Vec<String>,
+    overwrite: bool,
+}
+
+impl HostEnricher {
+    pub fn new(name: String, sources: Vec<String>, agent: &AgentSettings, overwrite: bool) -> Self {
+        Self {
+            name,
+            sources,
+            hostname: hostname::get().ok().map(|h| h.to_string_lossy().into_owned()),
+            agent_name: agent.name.clone(),
+            agent_tags: agent.tags.clone(),
+            overwrite,
+        }
+    }
+}
+
+#[async_trait]
+impl Enricher for HostEnricher {
+    async fn enrich(&self, event: &mut ParsedEvent) -> Result<bool, String> {
+        if let Some(hostname) = &self.hostname {
+            if self.overwrite || !event.fields.contains_key("host.hostname") {
+                event.fields.insert("host.hostname".to_string(), json!(hostname));
+            }
+        }
+        event.fields.insert("agent.name".to_string(), json!(self.agent_name));
+        if !self.agent_tags.is_empty() {
+            event.fields.insert("agent.tags".to_string(), json!(self.agent_tags));
+        }
+        Ok(true)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, event: &ParsedEvent) -> bool {
+        applies_to_source(&self.sources, event)
+    }
+}
+
+/// Fields found by the metadata lookup; None off-cloud
+type CloudFields = Arc<tokio::sync::OnceCell<Option<Vec<(String, Value)>>>>;
+
+/// Identity of the cloud instance the agent runs on
+pub struct CloudEnricher {
+    name: String,
+    sources: Vec<String>,
+    providers: Vec<CloudProvider>,
+    timeout: Duration,
+    include_tags: bool,
+    fields: CloudFields,
+    overwrite: bool,
+}
+
+impl CloudEnricher {
+    /// Starts the metadata lookup right away; events enriched before it finishes wait for it
+    pub fn new(
+        name: String,
+        sources: Vec<String>,
+        providers: Vec<CloudProvider>,
+        timeout: Duration,
+        include_tags: bool,
+        overwrite: bool,
+    ) -> Self {
+        let enricher = Self { name, sources, providers, timeout, include_tags, fields: CloudFields::default(), overwrite };
+        if tokio::runtime::Handle::try_current().is_ok() {
+            let fields = enricher.fields.clone();
+            let (providers, timeout) = (enricher.providers.clone(), enricher.timeout);
+            crate::component_usage::spawn_inherited(async move {
+                fields.get_or_init(|| Self::lookup(providers, timeout, include_tags)).await;
+            });
+        }
+        enricher
+    }
+
+    async fn lookup(providers: Vec<CloudProvider>, timeout: Duration, include_tags: bool) -> Option<Vec<(String, Value)>> {
+        let Some(metadata) = cloud_metadata::detect(&providers, timeout).await else {
+            info!("☁️ No cloud instance metadata found; cloud enrichment is inactive");
+            return None;
+        };
+        info!("☁️ Cloud instance detected: {} {} ({})",
+              metadata.provider.as_str(),
+              metadata.instance_id.as_deref().unwrap_or("unknown"),
+              metadata.region.as_deref().unwrap_or("unknown region"));
+        Some(metadata.fields()
+            .into_iter()
+            .filter(|(key, _)| include_tags || !key.starts_with("cloud.tags."))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Enricher for CloudEnricher {
+    async fn enrich(&self, event: &mut ParsedEvent) -> Result<bool, String> {
+        let fields = self.fields
+            .get_or_init(|| Self::lookup(self.providers.clone(), self.timeout, self.include_tags))
+            .await;
+        let mut enriched = false;
+        for (key, value) in fields.iter().flatten() {
+            if self.overwrite || !event.fields.contains_key(key) {
+                event.fields.insert(key.clone(), value.clone());
+                enriched = true;
+            }
+        }
+        Ok(enriched)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, event: &ParsedEvent) -> bool {
+        applies_to_source(&self.sources, event)
+    }
+}
+
+/// Fixed `labels.*` fields
+pub struct LabelsEnricher {
+    name: String,
+    sources: Vec<String>,
+    labels: HashMap<String, String>,
+    overwrite: bool,
+}
+
+#[async_trait]
+impl Enricher for LabelsEnricher {
+    async fn enrich(&self, event: &mut ParsedEvent) -> Result<bool, String> {
+        let mut enriched = false;
+        for (key, value) in &self.labels {
+            let field = format!("labels.{}", key);
+            if self.overwrite || !event.fields.contains_key(&field) {
+                event.fields.insert(field, Value::String(value.clone()));
+                enriched = true;
+            }
+        }
+        Ok(enriched)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, event: &ParsedEvent) -> bool {
+        applies_to_source(&self.sources, event)
+    }
+}
+
+/// Threat intel indicator hits, appended to `threat.enrichments`
+pub struct ThreatIntelEnricher {
+    name: String,
+    sources: Vec<String>,
+    intel: Arc<ThreatIntel>,
+}
+
+impl ThreatIntelEnricher {
+    /// Loads the cached feeds and starts refreshing them; the refresh stops once the enricher is dropped
+    pub fn new(name: String, sources: Vec<String>, config: ThreatIntelConfig, proxy: ProxyConfig) -> Self {
+        let intel = Arc::new(ThreatIntel::new(config, proxy));
+        info!("🛡️ Threat intel enricher '{}' starting with {} cached indicators", name, intel.store().len());
+        ThreatIntel::start_refresh(&intel);
+        Self { name, sources, intel }
+    }
+}
+
+#[async_trait]
+impl Enricher for ThreatIntelEnricher {
+    async fn enrich(&self, event: &mut ParsedEvent) -> Result<bool, String> {
+        let hits = self.intel.enrichments(event);
+        if hits.is_empty() {
+            return Ok(false);
+        }
+        match event.fields.get_mut("threat.enrichments") {
+            Some(Value::Array(existing)) => existing.extend(hits),
+            _ => {
+                event.fields.insert("threat.enrichments".to_string(), Value::Array(hits));
+            }
+        }
+        Ok(true)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, event: &ParsedEvent) -> bool {
+        applies_to_source(&self.sources, event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(source: &str, fields: &[(&str, &str)]) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: source.to_string(),
+            level: None,
+            message: "test".to_string(),
+            fields: fields.iter().map(|(k, v)| (k.to_string(), json!(v))).collect(),
+            raw_data: "test".into(),
+            parser_name: "test".to_string(),
+        }
+    }
+
+    fn agent_settings() -> AgentSettings {
+        crate::config::AgentConfig::default().agent
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_applies_enrichers_in_order_by_source() {
+        let config: EnrichmentConfig = toml::from_str(r#"
+            enabled = true
+
+            [[enrichers]]
+            type = "labels"
+            sources = ["syslog"]
+            labels = { env = "prod", site = "dc1" }
+
+            [[enrichers]]
+            name = "identity"
+            type = "host"
+        "#).unwrap();
+        let pipeline = EnrichmentPipeline::new(&config, &agent_settings(), &ProxyConfig::default()).unwrap();
+
+        let mut syslog = event("syslog", &[("labels.env", "staging")]);
+        pipeline.enrich(&mut syslog).await;
+        assert_eq!(syslog.fields["labels.env"], json!("staging"));
+        assert_eq!(syslog.fields["labels.site"], json!("dc1"));
+        assert_eq!(syslog.fields["agent.name"], json!(agent_settings().name));
+
+        let mut file = event("file_monitor", &[]);
+        pipeline.enrich(&mut file).await;
+        assert!(!file.fields.contains_key("labels.site"));
+        assert!(file.fields.contains_key("agent.name"));
+
+        let stats = pipeline.get_stats();
+        assert_eq!(stats.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["labels", "identity"]);
+        assert_eq!((stats[0].events_enriched, stats[1].events_enriched), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn test_reverse_dns_caches_answers_and_validation() {
+        let enricher = ReverseDnsEnricher {
+            name: "reverse_dns".to_string(),
+            sources: Vec::new(),
+            fields: default_ip_fields(),
+            cache: Mutex::new(HashMap::new()),
+            cache_size: 1,
+            cache_ttl: Duration::from_secs(60),
+            timeout: Duration::from_millis(500),
+        };
+        enricher.remember("192.0.2.10".parse().unwrap(), Some("gw.example.net".to_string()));
+
+        let mut event = event("syslog", &[("source.ip", "192.0.2.10"), ("destination.ip", "not-an-ip")]);
+        assert!(enricher.enrich(&mut event).await.unwrap());
+        assert_eq!(event.fields["source.domain"], json!("gw.example.net"));
+        assert!(!event.fields.contains_key("destination.domain"));
+
+        // A full cache evicts the oldest entry
+        enricher.remember("192.0.2.11".parse().unwrap(), None);
+        assert!(enricher.cached(&"192.0.2.10".parse().unwrap()).is_none());
+        assert_eq!(enricher.cached(&"192.0.2.11".parse().unwrap()), Some(None));
+
+        let config: EnrichmentConfig = toml::from_str(r#"
+            [[enrichers]]
+            type = "geoip"
+            database_path = ""
+
+            [[enrichers]]
+            type = "reverse_dns"
+            timeout_ms = 0
+        "#).unwrap();
+        assert_eq!(config.validate().len(), 2);
+    }
+}
@@ -0,0 +1,417 @@
+// Agent enrollment: exchange a one-time token for an agent ID, API key and client certificate
+// On first start the agent presents the token together with a CSR for a key it generated, persists what the
+// server issues under `state_dir` and applies it to the transport; later starts reuse the persisted identity
+
+use crate::config::TransportConfig;
+use crate::errors::{AgentError, ConfigError, TransportError};
+use chrono::{DateTime, Utc};
+use rcgen::{CertificateParams, DnType, KeyPair};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+const IDENTITY_FILE: &str = "identity.json";
+const CERT_FILE: &str = "client.crt";
+const KEY_FILE: &str = "client.key";
+const CA_FILE: &str = "ca.crt";
+
+/// Stand-in API key for schema validation while the agent is waiting to enroll
+pub const PENDING_API_KEY: &str = "issued-at-enrollment";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnrollmentConfig {
+    /// One-time enrollment token; leave api_key empty and the agent enrolls on first start
+    pub token: Option<String>,
+    /// Environment variable holding the token when `token` is unset
+    pub token_env: String,
+    /// Enrollment URL; defaults to `<server_url>/api/agents/enroll`
+    pub endpoint: Option<String>,
+    /// Directory the issued identity is persisted to (owner-only permissions)
+    pub state_dir: String,
+    /// Generate a key pair and ask the server to issue a client certificate for it
+    pub request_certificate: bool,
+    pub timeout_seconds: u64,
+}
+
+impl Default for EnrollmentConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            token_env: "SECUREWATCH_ENROLLMENT_TOKEN".to_string(),
+            endpoint: None,
+            state_dir: "./enrollment".to_string(),
+            request_certificate: true,
+            timeout_seconds: 30,
+        }
+    }
+}
+
+impl EnrollmentConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.state_dir.trim().is_empty() {
+            errors.push("Enrollment state_dir cannot be empty".to_string());
+        }
+        if self.timeout_seconds == 0 {
+            errors.push("Enrollment timeout_seconds must be greater than 0".to_string());
+        }
+        if let Some(endpoint) = &self.endpoint {
+            if !endpoint.starts_with("https://") && !endpoint.starts_with("http://") {
+                errors.push(format!("Enrollment endpoint must be an HTTP(S) URL: {}", endpoint));
+            }
+        }
+        errors
+    }
+
+    /// The configured token, falling back to `token_env`
+    pub fn token(&self) -> Option<String> {
+        self.token
+            .clone()
+            .or_else(|| std::env::var(&self.token_env).ok())
+            .filter(|token| !token.trim().is_empty())
+    }
+
+    /// True when the API key will come from enrollment rather than the config file
+    pub fn provides_api_key(&self, api_key: &str) -> bool {
+        api_key.is_empty() && (self.token().is_some() || Path::new(&self.state_dir).join(IDENTITY_FILE).exists())
+    }
+
+    fn endpoint(&self, server_url: &str) -> String {
+        self.endpoint
+            .clone()
+            .unwrap_or_else(|| format!("{}/api/agents/enroll", server_url.trim_end_matches('/')))
+    }
+}
+
+/// What the server issued at enrollment, as persisted in `identity.json`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EnrolledIdentity {
+    pub agent_id: String,
+    pub api_key: String,
+    pub server_url: String,
+    pub enrolled_at: DateTime<Utc>,
+    pub cert_path: Option<String>,
+    pub key_path: Option<String>,
+    pub ca_cert_path: Option<String>,
+}
+
+impl std::fmt::Debug for EnrolledIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnrolledIdentity")
+            .field("agent_id", &self.agent_id)
+            .field("api_key", &"<redacted>")
+            .field("server_url", &self.server_url)
+            .field("enrolled_at", &self.enrolled_at)
+            .field("cert_path", &self.cert_path)
+            .finish()
+    }
+}
+
+impl EnrolledIdentity {
+    pub fn load(state_dir: &str) -> Result<Option<Self>, ConfigError> {
+        let path = Path::new(state_dir).join(IDENTITY_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map(Some)
+                .map_err(|e| ConfigError::Parse(format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(ConfigError::Io(format!("cannot read {}: {}", path.display(), e))),
+        }
+    }
+
+    fn save(&self, state_dir: &str) -> Result<(), ConfigError> {
+        let json = serde_json::to_vec_pretty(self).map_err(|e| ConfigError::Serialize(e.to_string()))?;
+        write_private(&Path::new(state_dir).join(IDENTITY_FILE), &json)
+    }
+
+    /// Use the issued API key and certificate wherever the config doesn't set its own
+    pub fn apply(&self, transport: &mut TransportConfig) {
+        if transport.api_key.is_empty() {
+            transport.api_key = self.api_key.clone();
+        }
+        if transport.client_cert_path.is_none() && transport.client_key_path.is_none() {
+            transport.client_cert_path = self.cert_path.clone();
+            transport.client_key_path = self.key_path.clone();
+        }
+        if transport.ca_cert_path.is_none() {
+            transport.ca_cert_path = self.ca_cert_path.clone();
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EnrollmentRequest<'a> {
+    agent_name: &'a str,
+    hostname: String,
+    os: &'static str,
+    arch: &'static str,
+    agent_version: &'static str,
+    csr: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EnrollmentResponse {
+    agent_id: String,
+    api_key: String,
+    certificate: Option<String>,
+    ca_certificate: Option<String>,
+}
+
+/// The identity to run with: persisted from an earlier enrollment, freshly enrolled when a token is
+/// configured, or None when the API key is provisioned by hand
+pub async fn ensure_enrolled(transport: &TransportConfig, agent_name: &str) -> Result<Option<EnrolledIdentity>, AgentError> {
+    let config = &transport.enrollment;
+    if let Some(identity) = EnrolledIdentity::load(&config.state_dir)? {
+        return Ok(Some(identity));
+    }
+    if !transport.api_key.is_empty() {
+        return Ok(None);
+    }
+    match config.token() {
+        Some(token) => enroll(transport, agent_name, &token).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Present the token, persist the issued identity and return it
+pub async fn enroll(transport: &TransportConfig, agent_name: &str, token: &str) -> Result<EnrolledIdentity, AgentError> {
+    let config = &transport.enrollment;
+    let endpoint = config.endpoint(&transport.server_url);
+    let hostname = hostname::get()
+        .map(|h| h.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| agent_name.to_string());
+
+    let key_pair = if config.request_certificate {
+        Some(KeyPair::generate().map_err(|e| tls_error("generate_key", e))?)
+    } else {
+        None
+    };
+    let csr = match &key_pair {
+        Some(key_pair) => {
+            let mut params = CertificateParams::new(vec![hostname.clone()]).map_err(|e| tls_error("create_csr", e))?;
+            params.distinguished_name.push(DnType::CommonName, agent_name);
+            let request = params.serialize_request(key_pair).map_err(|e| tls_error("create_csr", e))?;
+            Some(request.pem().map_err(|e| tls_error("create_csr", e))?)
+        }
+        None => None,
+    };
+
+    let mut client_builder = reqwest::Client::builder().timeout(Duration::from_secs(config.timeout_seconds));
+    #[cfg(any(feature = "native-tls-backend", feature = "rustls-backend"))]
+    {
+        client_builder = client_builder.danger_accept_invalid_certs(!transport.tls_verify);
+        if let Some(ca_path) = &transport.ca_cert_path {
+            let pem = std::fs::read(ca_path).map_err(|e| ConfigError::Io(format!("cannot read {}: {}", ca_path, e)))?;
+            let certificate = reqwest::Certificate::from_pem(&pem).map_err(|e| tls_error("load_ca_certificate", e))?;
+            client_builder = client_builder.add_root_certificate(certificate);
+        }
+    }
+    client_builder = transport.proxy.apply(client_builder, &endpoint).map_err(ConfigError::Validation)?;
+    let client = client_builder.build().map_err(|e| tls_error("create_client", e))?;
+
+    info!("🎫 Enrolling with {}", endpoint);
+    let request = EnrollmentRequest {
+        agent_name,
+        hostname,
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        agent_version: env!("CARGO_PKG_VERSION"),
+        csr,
+    };
+    let response = client
+        .post(&endpoint)
+        .bearer_auth(token)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| TransportError::connection_failed(&format!("enrollment request to {} failed: {}", endpoint, e)))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::CONFLICT {
+        let reason = response.text().await.unwrap_or_default();
+        return Err(TransportError::AuthenticationFailed {
+            method: "enrollment_token".to_string(),
+            reason: format!("server rejected the enrollment token ({}): {}", status, reason.trim()),
+            retry_allowed: false,
+        }
+        .into());
+    }
+    if !status.is_success() {
+        let body = response.text().await.ok();
+        return Err(TransportError::ServerError {
+            status: status.as_u16(),
+            message: "enrollment failed".to_string(),
+            headers: Vec::new(),
+            body,
+            retryable: status.is_server_error(),
+        }
+        .into());
+    }
+    let issued: EnrollmentResponse = response.json().await.map_err(|e| TransportError::RequestFailed {
+        method: "POST".to_string(),
+        url: endpoint.clone(),
+        status_code: Some(status.as_u16()),
+        source: Box::new(e),
+    })?;
+
+    let state_dir = Path::new(&config.state_dir);
+    std::fs::create_dir_all(state_dir)
+        .map_err(|e| ConfigError::Io(format!("cannot create {}: {}", state_dir.display(), e)))?;
+    let path_string = |name: &str| state_dir.join(name).to_string_lossy().into_owned();
+
+    // Without an issued certificate the generated key is useless and isn't kept
+    let (cert_path, key_path) = match (&issued.certificate, &key_pair) {
+        (Some(certificate), Some(key_pair)) => {
+            write_private(&state_dir.join(KEY_FILE), key_pair.serialize_pem().as_bytes())?;
+            write_private(&state_dir.join(CERT_FILE), certificate.as_bytes())?;
+            (Some(path_string(CERT_FILE)), Some(path_string(KEY_FILE)))
+        }
+        _ => (None, None),
+    };
+    let ca_cert_path = match &issued.ca_certificate {
+        Some(ca_certificate) => {
+            write_private(&state_dir.join(CA_FILE), ca_certificate.as_bytes())?;
+            Some(path_string(CA_FILE))
+        }
+        None => None,
+    };
+
+    let identity = EnrolledIdentity {
+        agent_id: issued.agent_id,
+        api_key: issued.api_key,
+        server_url: transport.server_url.clone(),
+        enrolled_at: Utc::now(),
+        cert_path,
+        key_path,
+        ca_cert_path,
+    };
+    identity.save(&config.state_dir)?;
+    info!("🎫 Enrolled as agent {}; identity stored in {}", identity.agent_id, state_dir.display());
+    Ok(identity)
+}
+
+fn tls_error(operation: &str, reason: impl std::fmt::Display) -> TransportError {
+    TransportError::TlsError {
+        operation: operation.to_string(),
+        reason: reason.to_string(),
+        certificate_issue: true,
+        source: Box::new(std::io::Error::other(reason.to_string())),
+    }
+}
+
+/// Write owner-only, replacing the file atomically. The temporary file is new and uniquely named, so an existing
+/// file or link at a predictable path is never opened and left with the key in it
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), ConfigError> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp_path: PathBuf = path.with_file_name(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4().simple()));
+    let io_error = |e: std::io::Error| ConfigError::Io(format!("cannot write {}: {}", path.display(), e));
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let written = options.open(&temp_path).and_then(|mut file| {
+        std::io::Write::write_all(&mut file, contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(io_error(e));
+    }
+    std::fs::rename(&temp_path, path).map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        io_error(e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn transport(server_url: &str, state_dir: &Path) -> TransportConfig {
+        let mut transport = crate::config::AgentConfig::default().transport;
+        transport.server_url = server_url.to_string();
+        transport.api_key = String::new();
+        transport.enrollment.state_dir = state_dir.to_string_lossy().into_owned();
+        transport.enrollment.token = Some("enroll-me".to_string());
+        transport
+    }
+
+    #[tokio::test]
+    async fn test_enrollment_persists_and_applies_the_issued_identity() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // Read until the JSON body is complete
+            while !request.ends_with(b"}") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let body = r#"{"agent_id":"agt-42","api_key":"issued-key-0123456789","certificate":"-----BEGIN CERTIFICATE-----\nMA==\n-----END CERTIFICATE-----\n"}"#;
+            let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = transport(&format!("http://{}", address), dir.path());
+        let identity = ensure_enrolled(&config, "web-01").await.unwrap().unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /api/agents/enroll "));
+        assert!(request.to_ascii_lowercase().contains("authorization: bearer enroll-me"));
+        assert!(request.contains("BEGIN CERTIFICATE REQUEST"));
+
+        assert_eq!(identity.agent_id, "agt-42");
+        identity.apply(&mut config);
+        assert_eq!(config.api_key, "issued-key-0123456789");
+        assert!(config.client_cert_path.as_deref().unwrap().ends_with(CERT_FILE));
+        assert!(config.ca_cert_path.is_none());
+
+        // The next start reuses the persisted identity without contacting the server
+        config.api_key = String::new();
+        let reloaded = ensure_enrolled(&config, "web-01").await.unwrap().unwrap();
+        assert_eq!(reloaded.api_key, "issued-key-0123456789");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join(KEY_FILE)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hand_provisioned_key_skips_enrollment() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = transport("http://127.0.0.1:9", dir.path());
+        config.api_key = "hand-provisioned-key-123".to_string();
+        assert!(ensure_enrolled(&config, "web-01").await.unwrap().is_none());
+        assert!(!config.enrollment.provides_api_key(&config.api_key));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_files_never_write_through_a_planted_temp_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(KEY_FILE);
+        let target = dir.path().join("elsewhere");
+        std::fs::write(&target, "untouched").unwrap();
+        std::os::unix::fs::symlink(&target, path.with_extension("tmp")).unwrap();
+
+        write_private(&path, b"first").unwrap();
+        write_private(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "untouched");
+        // No temporary files are left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+}
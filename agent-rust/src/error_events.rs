@@ -0,0 +1,273 @@
+// Agent errors as events for the server
+// Errors the agent handles itself (a failed batch, a rejected reload, a collector that won't start) otherwise only
+// reach local logs. Each occurrence becomes a structured event sent through the buffer, rate-limited per error code,
+// so fleet-wide problems such as a bad parser config or an expired certificate show up centrally.
+
+use crate::errors::{AgentError, ErrorReport, ErrorSeverity};
+use crate::parsers::ParsedEvent;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+pub const ERROR_EVENTS_PARSER: &str = "agent_errors";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErrorEventsConfig {
+    pub enabled: bool,
+    /// Source name the error events carry
+    pub source: String,
+    /// Rate-limit window per error code
+    pub window_seconds: u64,
+    /// Events per error code and window; further occurrences are summarised when the window ends
+    pub max_events_per_code: u32,
+    /// Events waiting for the buffer; the oldest are dropped beyond this
+    pub max_pending: usize,
+}
+
+impl Default for ErrorEventsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            source: "securewatch_agent".to_string(),
+            window_seconds: 300,
+            max_events_per_code: 5,
+            max_pending: 1000,
+        }
+    }
+}
+
+impl ErrorEventsConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !(1..=86_400).contains(&self.window_seconds) {
+            errors.push("window_seconds must be between 1 and 86400".to_string());
+        }
+        if self.max_events_per_code == 0 || self.max_pending == 0 {
+            errors.push("max_events_per_code and max_pending must be greater than 0".to_string());
+        }
+        if self.source.trim().is_empty() {
+            errors.push("source must not be empty".to_string());
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ErrorEventStats {
+    pub recorded: u64,
+    pub emitted: u64,
+    /// Occurrences folded into a summary instead of getting their own event
+    pub suppressed: u64,
+    /// Events dropped because the buffer did not take them fast enough
+    pub dropped: u64,
+    pub pending: usize,
+}
+
+/// Occurrences of one error code in the current window
+struct CodeWindow {
+    started: Instant,
+    emitted: u32,
+    suppressed: u64,
+    component: String,
+    last: ErrorReport,
+}
+
+#[derive(Default)]
+struct State {
+    windows: HashMap<&'static str, CodeWindow>,
+    pending: VecDeque<ParsedEvent>,
+    stats: ErrorEventStats,
+}
+
+/// Turns recorded errors into events; shared by the agent, transport and collector manager
+pub struct ErrorEvents {
+    config: ErrorEventsConfig,
+    agent_id: String,
+    state: Mutex<State>,
+}
+
+impl ErrorEvents {
+    pub fn new(config: ErrorEventsConfig, agent_id: &str) -> Self {
+        Self { config, agent_id: agent_id.to_string(), state: Mutex::new(State::default()) }
+    }
+
+    pub fn record_agent_error(&self, component: &str, error: &AgentError) {
+        let mut report = error.report();
+        report.message = error.detailed_message();
+        self.record(component, report);
+    }
+
+    /// Queue an event for this occurrence unless its code already used up the window
+    pub fn record(&self, component: &str, report: ErrorReport) {
+        self.record_at(component, report, Instant::now());
+    }
+
+    fn record_at(&self, component: &str, report: ErrorReport, now: Instant) {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        self.close_windows(state, now);
+        state.stats.recorded += 1;
+
+        let window = state.windows.entry(report.code.name).or_insert_with(|| CodeWindow {
+            started: now,
+            emitted: 0,
+            suppressed: 0,
+            component: component.to_string(),
+            last: report.clone(),
+        });
+        window.component = component.to_string();
+        if window.emitted >= self.config.max_events_per_code {
+            window.suppressed += 1;
+            window.last = report;
+            state.stats.suppressed += 1;
+            return;
+        }
+        window.emitted += 1;
+        let event = self.event(component, &report, None);
+        self.push(state, event);
+    }
+
+    /// Events ready for the buffer, including summaries of windows that ended with suppressed occurrences
+    pub fn take_pending(&self) -> Vec<ParsedEvent> {
+        self.take_pending_at(Instant::now())
+    }
+
+    fn take_pending_at(&self, now: Instant) -> Vec<ParsedEvent> {
+        let mut state = self.state.lock();
+        self.close_windows(&mut state, now);
+        state.pending.drain(..).collect()
+    }
+
+    pub fn stats(&self) -> ErrorEventStats {
+        let state = self.state.lock();
+        ErrorEventStats { pending: state.pending.len(), ..state.stats.clone() }
+    }
+
+    fn close_windows(&self, state: &mut State, now: Instant) {
+        let window_length = Duration::from_secs(self.config.window_seconds);
+        let ended: Vec<&'static str> = state.windows.iter()
+            .filter(|(_, window)| now.duration_since(window.started) >= window_length)
+            .map(|(code, _)| *code)
+            .collect();
+        for code in ended {
+            let Some(window) = state.windows.remove(code) else { continue };
+            if window.suppressed > 0 {
+                let event = self.event(&window.component, &window.last, Some(window.suppressed));
+                self.push(state, event);
+            }
+        }
+    }
+
+    fn push(&self, state: &mut State, event: ParsedEvent) {
+        if state.pending.len() >= self.config.max_pending {
+            state.pending.pop_front();
+            state.stats.dropped += 1;
+        }
+        state.pending.push_back(event);
+        state.stats.emitted += 1;
+    }
+
+    fn event(&self, component: &str, report: &ErrorReport, suppressed: Option<u64>) -> ParsedEvent {
+        let mut fields: HashMap<String, Value> = HashMap::from([
+            ("event.kind".to_string(), json!("event")),
+            ("event.dataset".to_string(), json!("securewatch.agent.error")),
+            ("event.outcome".to_string(), json!("failure")),
+            ("agent.id".to_string(), json!(self.agent_id)),
+            ("agent.version".to_string(), json!(env!("CARGO_PKG_VERSION"))),
+            ("agent.component".to_string(), json!(component)),
+            ("error.code".to_string(), json!(report.code.to_string())),
+            ("error.id".to_string(), json!(report.code.name)),
+            ("error.type".to_string(), json!(format!("{:?}", report.category).to_lowercase())),
+            ("error.severity".to_string(), json!(report.severity.to_string().to_lowercase())),
+            ("error.retryable".to_string(), json!(report.retryable)),
+            ("error.message".to_string(), json!(report.message)),
+        ]);
+        let message = match suppressed {
+            Some(count) => {
+                fields.insert("error.suppressed".to_string(), json!(count));
+                format!("{} {} in {}: {} more occurrences in the last {}s, last: {}",
+                        report.code, report.code.name, component, count, self.config.window_seconds, report.message)
+            }
+            None => format!("{} {} in {}: {}", report.code, report.code.name, component, report.message),
+        };
+        let level = match report.severity {
+            ErrorSeverity::High | ErrorSeverity::Critical => "error",
+            ErrorSeverity::Low | ErrorSeverity::Medium => "warning",
+        };
+        let raw_data = serde_json::to_string(&fields).unwrap_or_default();
+
+        ParsedEvent {
+            timestamp: report.occurred_at,
+            source: self.config.source.clone(),
+            level: Some(level.to_string()),
+            message,
+            fields,
+            raw_data: raw_data.into(),
+            parser_name: ERROR_EVENTS_PARSER.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::TransportError;
+
+    fn tls_error() -> ErrorReport {
+        TransportError::TlsError {
+            operation: "handshake".to_string(),
+            reason: "certificate has expired".to_string(),
+            certificate_issue: true,
+            source: "certificate has expired".into(),
+        }.report()
+    }
+
+    #[test]
+    fn test_occurrences_over_the_limit_are_summarised_per_window() {
+        let config = ErrorEventsConfig { window_seconds: 60, max_events_per_code: 2, ..Default::default() };
+        let events = ErrorEvents::new(config, "agent-1");
+        let start = Instant::now();
+        for _ in 0..5 {
+            events.record_at("transport", tls_error(), start);
+        }
+        let parse_error = AgentError::from(crate::errors::ParserError::invalid_regex("unclosed group"));
+        events.record_agent_error("parsers", &parse_error);
+
+        let first = events.take_pending_at(start);
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].fields["error.id"], "transport.tls_error");
+        assert_eq!(first[0].fields["agent.component"], "transport");
+        assert_eq!(first[0].level.as_deref(), Some("warning"));
+        assert!(first[2].fields["error.message"].as_str().unwrap().contains("unclosed group"));
+
+        // The window ends: one summary for the three suppressed occurrences, then the limit starts over
+        let later = start + Duration::from_secs(61);
+        let summary = events.take_pending_at(later);
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].fields["error.suppressed"], 3);
+        assert!(summary[0].message.contains("3 more occurrences in the last 60s"));
+        events.record_at("transport", tls_error(), later);
+        assert_eq!(events.take_pending_at(later).len(), 1);
+
+        let stats = events.stats();
+        assert_eq!((stats.recorded, stats.emitted, stats.suppressed, stats.pending), (7, 5, 3, 0));
+    }
+
+    #[test]
+    fn test_pending_events_are_capped() {
+        let config = ErrorEventsConfig { max_pending: 2, ..Default::default() };
+        let events = ErrorEvents::new(config, "agent-1");
+        events.record("transport", tls_error());
+        events.record("collector:syslog", crate::errors::CollectorError::InvalidConfig("port 0".to_string()).report());
+        events.record("collector:fim", crate::errors::CollectorError::InvalidConfig("no paths".to_string()).report());
+
+        let pending = events.take_pending();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].fields["agent.component"], "collector:syslog");
+        assert_eq!(events.stats().dropped, 1);
+        assert_eq!(ErrorEventsConfig { window_seconds: 0, source: " ".to_string(), ..Default::default() }.validate().len(), 2);
+    }
+}
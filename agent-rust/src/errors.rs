@@ -1,6 +1,7 @@
 // Enhanced error handling for SecureWatch Agent with comprehensive categorization
 // Uses thiserror with structured error context and error categorization
 
+use serde::Serialize;
 use std::fmt;
 use thiserror::Error;
 
@@ -228,6 +229,18 @@ pub enum TransportError {
         retry_after_seconds: Option<u32>,
     },
     
+    #[error("Transmission deferred: {reason}")]
+    TransmissionDeferred {
+        reason: String,
+        next_window: Option<chrono::DateTime<chrono::Utc>>,
+    },
+    
+    #[error("{events} events kept in the dead-letter table: {reason}")]
+    DeadLettered {
+        events: usize,
+        reason: String,
+    },
+    
     // Legacy compatibility variants for existing code
     #[error("TLS error: {0}")]
     Tls(String),
@@ -364,6 +377,18 @@ pub enum BufferError {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
     
+    #[error("Invalid search query '{query}': {reason}")]
+    InvalidSearchQuery {
+        query: String,
+        reason: String,
+    },
+    
+    #[error("Buffer encryption failed during {operation}: {reason}")]
+    EncryptionError {
+        operation: String,
+        reason: String,
+    },
+    
     #[cfg(feature = "persistent-storage")]
     #[error("SQLite database error: {0}")]
     SqliteError(#[from] rusqlite::Error),
@@ -410,6 +435,11 @@ pub enum ParserError {
         data_sample: String,
     },
     
+    #[error("Invalid processor chain '{chain}': {reason}")]
+    InvalidProcessorChain {
+        chain: String,
+        reason: String,
+    },
 }
 
 /// Management API and control plane errors
@@ -450,6 +480,13 @@ pub enum ManagementError {
         limit_type: String,
         reset_time: std::time::SystemTime,
     },
+    
+    #[error("Management certificate operation '{operation}' failed for '{path}': {reason}")]
+    CertificateError {
+        operation: String,
+        path: String,
+        reason: String,
+    },
 }
 
 /// Resource management and system health errors
@@ -569,7 +606,7 @@ pub enum SecurityError {
 }
 
 /// Error severity levels for prioritization and alerting
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum ErrorSeverity {
     Low,
     Medium,
@@ -589,7 +626,7 @@ impl fmt::Display for ErrorSeverity {
 }
 
 /// Error category for metrics and monitoring
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum ErrorCategory {
     Configuration,
     Network,
@@ -600,6 +637,331 @@ pub enum ErrorCategory {
     Runtime,
 }
 
+/// Stable, machine-matchable identifier for an error variant
+///
+/// Numbers and names are part of the agent's external interface (logs, management API,
+/// heartbeats): never renumber or reuse a code, only add new ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct ErrorCode {
+    pub code: u16,
+    pub name: &'static str,
+}
+
+impl ErrorCode {
+    const fn new(code: u16, name: &'static str) -> Self {
+        Self { code, name }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "E{:04}", self.code)
+    }
+}
+
+/// Registry of every error code, grouped by thousands: 1xxx agent, 2xxx config, 3xxx transport,
+/// 4xxx collector, 5xxx buffer, 6xxx parser, 7xxx management, 8xxx resource, 9xxx security
+pub mod codes {
+    use super::ErrorCode;
+
+    pub const AGENT_CHANNEL: ErrorCode = ErrorCode::new(1001, "agent.channel");
+    pub const AGENT_SHUTDOWN_TIMEOUT: ErrorCode = ErrorCode::new(1002, "agent.shutdown_timeout");
+    pub const AGENT_INITIALIZATION_FAILED: ErrorCode = ErrorCode::new(1003, "agent.initialization_failed");
+    pub const AGENT_CRITICAL: ErrorCode = ErrorCode::new(1004, "agent.critical");
+    pub const AGENT_CONFIGURATION: ErrorCode = ErrorCode::new(1005, "agent.configuration");
+    pub const AGENT_SERIALIZATION: ErrorCode = ErrorCode::new(1006, "agent.serialization");
+    pub const AGENT_UNHEALTHY: ErrorCode = ErrorCode::new(1007, "agent.unhealthy");
+    pub const AGENT_IO: ErrorCode = ErrorCode::new(1008, "agent.io");
+    pub const AGENT_TASK_JOIN: ErrorCode = ErrorCode::new(1009, "agent.task_join");
+    pub const AGENT_JSON: ErrorCode = ErrorCode::new(1010, "agent.json");
+    pub const AGENT_URL_PARSE: ErrorCode = ErrorCode::new(1011, "agent.url_parse");
+
+    pub const CONFIG_FILE_READ: ErrorCode = ErrorCode::new(2001, "config.file_read");
+    pub const CONFIG_PARSE_ERROR: ErrorCode = ErrorCode::new(2002, "config.parse_error");
+    pub const CONFIG_IO: ErrorCode = ErrorCode::new(2003, "config.io");
+    pub const CONFIG_PARSE: ErrorCode = ErrorCode::new(2004, "config.parse");
+    pub const CONFIG_SERIALIZE: ErrorCode = ErrorCode::new(2005, "config.serialize");
+    pub const CONFIG_VALIDATION: ErrorCode = ErrorCode::new(2006, "config.validation");
+    pub const CONFIG_INVALID_FIELD: ErrorCode = ErrorCode::new(2007, "config.invalid_field");
+    pub const CONFIG_MISSING_FIELD: ErrorCode = ErrorCode::new(2008, "config.missing_field");
+    pub const CONFIG_SERIALIZATION: ErrorCode = ErrorCode::new(2009, "config.serialization");
+    pub const CONFIG_HOT_RELOAD_FAILED: ErrorCode = ErrorCode::new(2010, "config.hot_reload_failed");
+    pub const CONFIG_SCHEMA_VALIDATION_FAILED: ErrorCode = ErrorCode::new(2011, "config.schema_validation_failed");
+
+    pub const TRANSPORT_CONNECTION_FAILED: ErrorCode = ErrorCode::new(3001, "transport.connection_failed");
+    pub const TRANSPORT_AUTHENTICATION_FAILED: ErrorCode = ErrorCode::new(3002, "transport.authentication_failed");
+    pub const TRANSPORT_REQUEST_FAILED: ErrorCode = ErrorCode::new(3003, "transport.request_failed");
+    pub const TRANSPORT_SERVER_ERROR: ErrorCode = ErrorCode::new(3004, "transport.server_error");
+    pub const TRANSPORT_TIMEOUT: ErrorCode = ErrorCode::new(3005, "transport.timeout");
+    pub const TRANSPORT_TLS_ERROR: ErrorCode = ErrorCode::new(3006, "transport.tls_error");
+    pub const TRANSPORT_COMPRESSION_ERROR: ErrorCode = ErrorCode::new(3007, "transport.compression_error");
+    pub const TRANSPORT_CIRCUIT_BREAKER_OPEN: ErrorCode = ErrorCode::new(3008, "transport.circuit_breaker_open");
+    pub const TRANSPORT_RATE_LIMIT_EXCEEDED: ErrorCode = ErrorCode::new(3009, "transport.rate_limit_exceeded");
+    pub const TRANSPORT_TLS: ErrorCode = ErrorCode::new(3010, "transport.tls");
+    pub const TRANSPORT_COMPRESSION: ErrorCode = ErrorCode::new(3011, "transport.compression");
+    pub const TRANSPORT_TRANSMISSION_DEFERRED: ErrorCode = ErrorCode::new(3012, "transport.transmission_deferred");
+    pub const TRANSPORT_DEAD_LETTERED: ErrorCode = ErrorCode::new(3013, "transport.dead_lettered");
+
+    pub const COLLECTOR_INITIALIZATION_FAILED: ErrorCode = ErrorCode::new(4001, "collector.initialization_failed");
+    pub const COLLECTOR_COLLECTION_FAILED: ErrorCode = ErrorCode::new(4002, "collector.collection_failed");
+    pub const COLLECTOR_FILE_SYSTEM: ErrorCode = ErrorCode::new(4003, "collector.file_system");
+    pub const COLLECTOR_WINDOWS_EVENT: ErrorCode = ErrorCode::new(4004, "collector.windows_event");
+    pub const COLLECTOR_NETWORK: ErrorCode = ErrorCode::new(4005, "collector.network");
+    pub const COLLECTOR_HEALTH_CHECK_FAILED: ErrorCode = ErrorCode::new(4006, "collector.health_check_failed");
+    pub const COLLECTOR_DATA_VALIDATION_FAILED: ErrorCode = ErrorCode::new(4007, "collector.data_validation_failed");
+    pub const COLLECTOR_INVALID_CONFIG: ErrorCode = ErrorCode::new(4008, "collector.invalid_config");
+
+    pub const BUFFER_CAPACITY_EXCEEDED: ErrorCode = ErrorCode::new(5001, "buffer.capacity_exceeded");
+    pub const BUFFER_PERSISTENCE: ErrorCode = ErrorCode::new(5002, "buffer.persistence");
+    pub const BUFFER_CORRUPTION: ErrorCode = ErrorCode::new(5003, "buffer.corruption");
+    pub const BUFFER_SERIALIZATION: ErrorCode = ErrorCode::new(5004, "buffer.serialization");
+    pub const BUFFER_CHANNEL: ErrorCode = ErrorCode::new(5005, "buffer.channel");
+    pub const BUFFER_RECOVERY_FAILED: ErrorCode = ErrorCode::new(5006, "buffer.recovery_failed");
+    pub const BUFFER_WAL: ErrorCode = ErrorCode::new(5007, "buffer.wal");
+    pub const BUFFER_SQLITE: ErrorCode = ErrorCode::new(5008, "buffer.sqlite");
+    pub const BUFFER_INVALID_SEARCH_QUERY: ErrorCode = ErrorCode::new(5009, "buffer.invalid_search_query");
+    pub const BUFFER_ENCRYPTION: ErrorCode = ErrorCode::new(5010, "buffer.encryption");
+
+    pub const PARSER_INVALID_REGEX: ErrorCode = ErrorCode::new(6001, "parser.invalid_regex");
+    pub const PARSER_PARSE_FAILED: ErrorCode = ErrorCode::new(6002, "parser.parse_failed");
+    pub const PARSER_NO_MATCHING_PARSER: ErrorCode = ErrorCode::new(6003, "parser.no_matching_parser");
+    pub const PARSER_FIELD_EXTRACTION_FAILED: ErrorCode = ErrorCode::new(6004, "parser.field_extraction_failed");
+    pub const PARSER_SCHEMA_VALIDATION_FAILED: ErrorCode = ErrorCode::new(6005, "parser.schema_validation_failed");
+    pub const PARSER_INVALID_PROCESSOR_CHAIN: ErrorCode = ErrorCode::new(6006, "parser.invalid_processor_chain");
+
+    pub const MANAGEMENT_GRPC: ErrorCode = ErrorCode::new(7001, "management.grpc");
+    pub const MANAGEMENT_INVALID_REQUEST: ErrorCode = ErrorCode::new(7002, "management.invalid_request");
+    pub const MANAGEMENT_SERVICE_UNAVAILABLE: ErrorCode = ErrorCode::new(7003, "management.service_unavailable");
+    pub const MANAGEMENT_AUTHORIZATION_FAILED: ErrorCode = ErrorCode::new(7004, "management.authorization_failed");
+    pub const MANAGEMENT_RATE_LIMITED: ErrorCode = ErrorCode::new(7005, "management.rate_limited");
+    pub const MANAGEMENT_CERTIFICATE: ErrorCode = ErrorCode::new(7006, "management.certificate");
+
+    pub const RESOURCE_LIMIT_EXCEEDED: ErrorCode = ErrorCode::new(8001, "resource.limit_exceeded");
+    pub const RESOURCE_MEMORY_PRESSURE: ErrorCode = ErrorCode::new(8002, "resource.memory_pressure");
+    pub const RESOURCE_CPU_THROTTLING: ErrorCode = ErrorCode::new(8003, "resource.cpu_throttling");
+    pub const RESOURCE_DISK_SPACE: ErrorCode = ErrorCode::new(8004, "resource.disk_space");
+    pub const RESOURCE_MONITORING_FAILED: ErrorCode = ErrorCode::new(8005, "resource.monitoring_failed");
+
+    pub const SECURITY_CERTIFICATE: ErrorCode = ErrorCode::new(9001, "security.certificate");
+    pub const SECURITY_INPUT_VALIDATION: ErrorCode = ErrorCode::new(9002, "security.input_validation");
+    pub const SECURITY_CREDENTIAL: ErrorCode = ErrorCode::new(9003, "security.credential");
+    pub const SECURITY_AUDIT_EVENT: ErrorCode = ErrorCode::new(9004, "security.audit_event");
+    pub const SECURITY_MASTER_KEY_NOT_INITIALIZED: ErrorCode = ErrorCode::new(9005, "security.master_key_not_initialized");
+    pub const SECURITY_SALT_GENERATION_FAILED: ErrorCode = ErrorCode::new(9006, "security.salt_generation_failed");
+    pub const SECURITY_NONCE_GENERATION_FAILED: ErrorCode = ErrorCode::new(9007, "security.nonce_generation_failed");
+    pub const SECURITY_SYSTEM_TIME: ErrorCode = ErrorCode::new(9008, "security.system_time");
+    pub const SECURITY_CREDENTIAL_NOT_FOUND: ErrorCode = ErrorCode::new(9009, "security.credential_not_found");
+    pub const SECURITY_CREDENTIAL_EXPIRED: ErrorCode = ErrorCode::new(9010, "security.credential_expired");
+    pub const SECURITY_INVALID_NONCE: ErrorCode = ErrorCode::new(9011, "security.invalid_nonce");
+    pub const SECURITY_ENCRYPTION_FAILED: ErrorCode = ErrorCode::new(9012, "security.encryption_failed");
+    pub const SECURITY_DECRYPTION_FAILED: ErrorCode = ErrorCode::new(9013, "security.decryption_failed");
+    pub const SECURITY_INVALID_UTF8: ErrorCode = ErrorCode::new(9014, "security.invalid_utf8");
+    pub const SECURITY_KEY_CREATION_FAILED: ErrorCode = ErrorCode::new(9015, "security.key_creation_failed");
+    pub const SECURITY_VALIDATION_FAILED: ErrorCode = ErrorCode::new(9016, "security.validation_failed");
+
+    /// Every code, for documentation and uniqueness checks
+    pub const ALL: &[ErrorCode] = &[
+        AGENT_CHANNEL, AGENT_SHUTDOWN_TIMEOUT, AGENT_INITIALIZATION_FAILED, AGENT_CRITICAL, AGENT_CONFIGURATION, AGENT_SERIALIZATION, AGENT_UNHEALTHY, AGENT_IO, AGENT_TASK_JOIN, AGENT_JSON, AGENT_URL_PARSE,
+        CONFIG_FILE_READ, CONFIG_PARSE_ERROR, CONFIG_IO, CONFIG_PARSE, CONFIG_SERIALIZE, CONFIG_VALIDATION, CONFIG_INVALID_FIELD, CONFIG_MISSING_FIELD, CONFIG_SERIALIZATION, CONFIG_HOT_RELOAD_FAILED, CONFIG_SCHEMA_VALIDATION_FAILED,
+        TRANSPORT_CONNECTION_FAILED, TRANSPORT_AUTHENTICATION_FAILED, TRANSPORT_REQUEST_FAILED, TRANSPORT_SERVER_ERROR, TRANSPORT_TIMEOUT, TRANSPORT_TLS_ERROR, TRANSPORT_COMPRESSION_ERROR, TRANSPORT_CIRCUIT_BREAKER_OPEN, TRANSPORT_RATE_LIMIT_EXCEEDED, TRANSPORT_TLS, TRANSPORT_COMPRESSION, TRANSPORT_TRANSMISSION_DEFERRED, TRANSPORT_DEAD_LETTERED,
+        COLLECTOR_INITIALIZATION_FAILED, COLLECTOR_COLLECTION_FAILED, COLLECTOR_FILE_SYSTEM, COLLECTOR_WINDOWS_EVENT, COLLECTOR_NETWORK, COLLECTOR_HEALTH_CHECK_FAILED, COLLECTOR_DATA_VALIDATION_FAILED, COLLECTOR_INVALID_CONFIG,
+        BUFFER_CAPACITY_EXCEEDED, BUFFER_PERSISTENCE, BUFFER_CORRUPTION, BUFFER_SERIALIZATION, BUFFER_CHANNEL, BUFFER_RECOVERY_FAILED, BUFFER_WAL, BUFFER_SQLITE, BUFFER_INVALID_SEARCH_QUERY, BUFFER_ENCRYPTION,
+        PARSER_INVALID_REGEX, PARSER_PARSE_FAILED, PARSER_NO_MATCHING_PARSER, PARSER_FIELD_EXTRACTION_FAILED, PARSER_SCHEMA_VALIDATION_FAILED, PARSER_INVALID_PROCESSOR_CHAIN,
+        MANAGEMENT_GRPC, MANAGEMENT_INVALID_REQUEST, MANAGEMENT_SERVICE_UNAVAILABLE, MANAGEMENT_AUTHORIZATION_FAILED, MANAGEMENT_RATE_LIMITED, MANAGEMENT_CERTIFICATE,
+        RESOURCE_LIMIT_EXCEEDED, RESOURCE_MEMORY_PRESSURE, RESOURCE_CPU_THROTTLING, RESOURCE_DISK_SPACE, RESOURCE_MONITORING_FAILED,
+        SECURITY_CERTIFICATE, SECURITY_INPUT_VALIDATION, SECURITY_CREDENTIAL, SECURITY_AUDIT_EVENT, SECURITY_MASTER_KEY_NOT_INITIALIZED, SECURITY_SALT_GENERATION_FAILED, SECURITY_NONCE_GENERATION_FAILED, SECURITY_SYSTEM_TIME, SECURITY_CREDENTIAL_NOT_FOUND, SECURITY_CREDENTIAL_EXPIRED, SECURITY_INVALID_NONCE, SECURITY_ENCRYPTION_FAILED, SECURITY_DECRYPTION_FAILED, SECURITY_INVALID_UTF8, SECURITY_KEY_CREATION_FAILED, SECURITY_VALIDATION_FAILED,
+    ];
+}
+
+impl AgentError {
+    /// Stable code identifying this error variant
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AgentError::Config(source) => source.code(),
+            AgentError::Transport(source) => source.code(),
+            AgentError::Collector(source) => source.code(),
+            AgentError::Buffer(source) => source.code(),
+            AgentError::Parser(source) => source.code(),
+            AgentError::Management(source) => source.code(),
+            AgentError::Resource(source) => source.code(),
+            AgentError::Security(source) => source.code(),
+            AgentError::ChannelError { .. } => codes::AGENT_CHANNEL,
+            AgentError::ShutdownTimeout { .. } => codes::AGENT_SHUTDOWN_TIMEOUT,
+            AgentError::InitializationFailed { .. } => codes::AGENT_INITIALIZATION_FAILED,
+            AgentError::CriticalError { .. } => codes::AGENT_CRITICAL,
+            AgentError::Configuration(_) => codes::AGENT_CONFIGURATION,
+            AgentError::Serialization(_) => codes::AGENT_SERIALIZATION,
+            AgentError::AgentUnhealthy(_) => codes::AGENT_UNHEALTHY,
+            AgentError::Io(_) => codes::AGENT_IO,
+            AgentError::TaskJoin(_) => codes::AGENT_TASK_JOIN,
+            AgentError::Json(_) => codes::AGENT_JSON,
+            AgentError::UrlParse(_) => codes::AGENT_URL_PARSE,
+        }
+    }
+}
+
+impl ConfigError {
+    /// Stable code identifying this error variant
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ConfigError::FileRead { .. } => codes::CONFIG_FILE_READ,
+            ConfigError::ParseError { .. } => codes::CONFIG_PARSE_ERROR,
+            ConfigError::Io(_) => codes::CONFIG_IO,
+            ConfigError::Parse(_) => codes::CONFIG_PARSE,
+            ConfigError::Serialize(_) => codes::CONFIG_SERIALIZE,
+            ConfigError::Validation(_) => codes::CONFIG_VALIDATION,
+            ConfigError::ValidationError { .. } => codes::CONFIG_INVALID_FIELD,
+            ConfigError::MissingField { .. } => codes::CONFIG_MISSING_FIELD,
+            ConfigError::SerializationError { .. } => codes::CONFIG_SERIALIZATION,
+            ConfigError::HotReloadFailed { .. } => codes::CONFIG_HOT_RELOAD_FAILED,
+            ConfigError::SchemaValidationFailed { .. } => codes::CONFIG_SCHEMA_VALIDATION_FAILED,
+        }
+    }
+}
+
+impl TransportError {
+    /// Stable code identifying this error variant
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            TransportError::ConnectionFailed { .. } => codes::TRANSPORT_CONNECTION_FAILED,
+            TransportError::AuthenticationFailed { .. } => codes::TRANSPORT_AUTHENTICATION_FAILED,
+            TransportError::RequestFailed { .. } => codes::TRANSPORT_REQUEST_FAILED,
+            TransportError::ServerError { .. } => codes::TRANSPORT_SERVER_ERROR,
+            TransportError::Timeout { .. } => codes::TRANSPORT_TIMEOUT,
+            TransportError::TlsError { .. } => codes::TRANSPORT_TLS_ERROR,
+            TransportError::CompressionError { .. } => codes::TRANSPORT_COMPRESSION_ERROR,
+            TransportError::CircuitBreakerOpen { .. } => codes::TRANSPORT_CIRCUIT_BREAKER_OPEN,
+            TransportError::RateLimitExceeded { .. } => codes::TRANSPORT_RATE_LIMIT_EXCEEDED,
+            TransportError::Tls(_) => codes::TRANSPORT_TLS,
+            TransportError::Compression(_) => codes::TRANSPORT_COMPRESSION,
+            TransportError::TransmissionDeferred { .. } => codes::TRANSPORT_TRANSMISSION_DEFERRED,
+            TransportError::DeadLettered { .. } => codes::TRANSPORT_DEAD_LETTERED,
+        }
+    }
+}
+
+impl CollectorError {
+    /// Stable code identifying this error variant
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            CollectorError::InitializationFailed { .. } => codes::COLLECTOR_INITIALIZATION_FAILED,
+            CollectorError::CollectionFailed { .. } => codes::COLLECTOR_COLLECTION_FAILED,
+            CollectorError::FileSystemError { .. } => codes::COLLECTOR_FILE_SYSTEM,
+            CollectorError::WindowsEventError { .. } => codes::COLLECTOR_WINDOWS_EVENT,
+            CollectorError::NetworkError { .. } => codes::COLLECTOR_NETWORK,
+            CollectorError::HealthCheckFailed { .. } => codes::COLLECTOR_HEALTH_CHECK_FAILED,
+            CollectorError::DataValidationFailed { .. } => codes::COLLECTOR_DATA_VALIDATION_FAILED,
+            CollectorError::InvalidConfig(_) => codes::COLLECTOR_INVALID_CONFIG,
+        }
+    }
+}
+
+impl BufferError {
+    /// Stable code identifying this error variant
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            BufferError::CapacityExceeded { .. } => codes::BUFFER_CAPACITY_EXCEEDED,
+            BufferError::PersistenceError { .. } => codes::BUFFER_PERSISTENCE,
+            BufferError::CorruptionError { .. } => codes::BUFFER_CORRUPTION,
+            BufferError::SerializationError { .. } => codes::BUFFER_SERIALIZATION,
+            BufferError::ChannelError { .. } => codes::BUFFER_CHANNEL,
+            BufferError::RecoveryFailed { .. } => codes::BUFFER_RECOVERY_FAILED,
+            BufferError::WalError { .. } => codes::BUFFER_WAL,
+            BufferError::InvalidSearchQuery { .. } => codes::BUFFER_INVALID_SEARCH_QUERY,
+            BufferError::EncryptionError { .. } => codes::BUFFER_ENCRYPTION,
+            #[cfg(feature = "persistent-storage")]
+            BufferError::SqliteError(_) => codes::BUFFER_SQLITE,
+        }
+    }
+}
+
+impl ParserError {
+    /// Stable code identifying this error variant
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ParserError::InvalidRegex { .. } => codes::PARSER_INVALID_REGEX,
+            ParserError::ParseFailed { .. } => codes::PARSER_PARSE_FAILED,
+            ParserError::NoMatchingParser { .. } => codes::PARSER_NO_MATCHING_PARSER,
+            ParserError::FieldExtractionFailed { .. } => codes::PARSER_FIELD_EXTRACTION_FAILED,
+            ParserError::SchemaValidationFailed { .. } => codes::PARSER_SCHEMA_VALIDATION_FAILED,
+            ParserError::InvalidProcessorChain { .. } => codes::PARSER_INVALID_PROCESSOR_CHAIN,
+        }
+    }
+}
+
+impl ManagementError {
+    /// Stable code identifying this error variant
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ManagementError::GrpcError { .. } => codes::MANAGEMENT_GRPC,
+            ManagementError::InvalidRequest { .. } => codes::MANAGEMENT_INVALID_REQUEST,
+            ManagementError::ServiceUnavailable { .. } => codes::MANAGEMENT_SERVICE_UNAVAILABLE,
+            ManagementError::AuthorizationFailed { .. } => codes::MANAGEMENT_AUTHORIZATION_FAILED,
+            ManagementError::RateLimited { .. } => codes::MANAGEMENT_RATE_LIMITED,
+            ManagementError::CertificateError { .. } => codes::MANAGEMENT_CERTIFICATE,
+        }
+    }
+}
+
+impl ResourceError {
+    /// Stable code identifying this error variant
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ResourceError::LimitExceeded { .. } => codes::RESOURCE_LIMIT_EXCEEDED,
+            ResourceError::MemoryPressure { .. } => codes::RESOURCE_MEMORY_PRESSURE,
+            ResourceError::CpuThrottling { .. } => codes::RESOURCE_CPU_THROTTLING,
+            ResourceError::DiskSpaceError { .. } => codes::RESOURCE_DISK_SPACE,
+            ResourceError::MonitoringFailed { .. } => codes::RESOURCE_MONITORING_FAILED,
+        }
+    }
+}
+
+impl SecurityError {
+    /// Stable code identifying this error variant
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            SecurityError::CertificateError { .. } => codes::SECURITY_CERTIFICATE,
+            SecurityError::InputValidation { .. } => codes::SECURITY_INPUT_VALIDATION,
+            SecurityError::CredentialError { .. } => codes::SECURITY_CREDENTIAL,
+            SecurityError::AuditEvent { .. } => codes::SECURITY_AUDIT_EVENT,
+            SecurityError::MasterKeyNotInitialized => codes::SECURITY_MASTER_KEY_NOT_INITIALIZED,
+            SecurityError::SaltGenerationFailed => codes::SECURITY_SALT_GENERATION_FAILED,
+            SecurityError::NonceGenerationFailed => codes::SECURITY_NONCE_GENERATION_FAILED,
+            SecurityError::SystemTimeError => codes::SECURITY_SYSTEM_TIME,
+            SecurityError::CredentialNotFound(_) => codes::SECURITY_CREDENTIAL_NOT_FOUND,
+            SecurityError::CredentialExpired(_) => codes::SECURITY_CREDENTIAL_EXPIRED,
+            SecurityError::InvalidNonce => codes::SECURITY_INVALID_NONCE,
+            SecurityError::EncryptionFailed => codes::SECURITY_ENCRYPTION_FAILED,
+            SecurityError::DecryptionFailed => codes::SECURITY_DECRYPTION_FAILED,
+            SecurityError::InvalidUtf8 => codes::SECURITY_INVALID_UTF8,
+            SecurityError::KeyCreationFailed => codes::SECURITY_KEY_CREATION_FAILED,
+            SecurityError::ValidationFailed(_) => codes::SECURITY_VALIDATION_FAILED,
+        }
+    }
+}
+/// An error's message followed by those of its sources
+fn chained_message(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    message
+}
+
+/// Structured view of an error for management responses and heartbeats
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    pub code: ErrorCode,
+    pub category: ErrorCategory,
+    pub severity: ErrorSeverity,
+    pub retryable: bool,
+    pub message: String,
+    pub occurred_at: chrono::DateTime<chrono::Utc>,
+}
+
 impl AgentError {
     /// Get the error category for metrics collection
     pub fn category(&self) -> ErrorCategory {
@@ -653,6 +1015,23 @@ impl AgentError {
         }
     }
     
+    /// Code, classification and message of this error as reported outside the process
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport {
+            code: self.code(),
+            category: self.category(),
+            severity: self.severity(),
+            retryable: self.is_retryable(),
+            message: self.to_string(),
+            occurred_at: chrono::Utc::now(),
+        }
+    }
+    
+    /// This error's message followed by those of the errors it wraps, e.g. "Transport error: Connection failed ..."
+    pub fn detailed_message(&self) -> String {
+        chained_message(self)
+    }
+    
     /// Create a channel error
     pub fn channel_error(reason: &str, component: &str) -> Self {
         AgentError::ChannelError {
@@ -677,6 +1056,18 @@ impl AgentError {
 }
 
 impl TransportError {
+    /// Report for an error handled where it occurred, classified as it would be inside `AgentError`
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport {
+            code: self.code(),
+            category: ErrorCategory::Network,
+            severity: ErrorSeverity::Medium,
+            retryable: self.is_retryable(),
+            message: chained_message(self),
+            occurred_at: chrono::Utc::now(),
+        }
+    }
+    
     /// Check if transport error is retryable
     pub fn is_retryable(&self) -> bool {
         match self {
@@ -691,11 +1082,25 @@ impl TransportError {
             TransportError::RateLimitExceeded { .. } => true,
             TransportError::Tls(_) => false,
             TransportError::Compression(_) => true,
+            TransportError::TransmissionDeferred { .. } => true,
+            TransportError::DeadLettered { .. } => false,
         }
     }
 }
 
 impl CollectorError {
+    /// Report for an error handled where it occurred, classified as it would be inside `AgentError`
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport {
+            code: self.code(),
+            category: ErrorCategory::Data,
+            severity: ErrorSeverity::Low,
+            retryable: self.is_retryable(),
+            message: chained_message(self),
+            occurred_at: chrono::Utc::now(),
+        }
+    }
+    
     /// Check if collector error is retryable
     pub fn is_retryable(&self) -> bool {
         match self {
@@ -722,6 +1127,8 @@ impl BufferError {
             BufferError::ChannelError { is_closed, .. } => !is_closed,
             BufferError::RecoveryFailed { partial_success, .. } => *partial_success,
             BufferError::WalError { .. } => true,
+            BufferError::InvalidSearchQuery { .. } => false,
+            BufferError::EncryptionError { .. } => false,
             #[cfg(feature = "persistent-storage")]
             BufferError::SqliteError(_) => true,
         }
@@ -839,4 +1246,41 @@ impl ParserError {
             expected_format: None,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes_are_unique_and_grouped() {
+        let mut numbers = std::collections::HashSet::new();
+        let mut names = std::collections::HashSet::new();
+        for code in codes::ALL {
+            assert!(numbers.insert(code.code), "duplicate error code {}", code.code);
+            assert!(names.insert(code.name), "duplicate error name {}", code.name);
+            let group = match code.code / 1000 {
+                1 => "agent", 2 => "config", 3 => "transport", 4 => "collector", 5 => "buffer",
+                6 => "parser", 7 => "management", 8 => "resource", 9 => "security",
+                _ => panic!("code {} outside the registry ranges", code.code),
+            };
+            assert!(code.name.starts_with(group), "{} does not belong to the {} range", code.name, group);
+        }
+    }
+
+    #[test]
+    fn test_wrapped_errors_keep_their_code() {
+        let error: AgentError = TransportError::Timeout {
+            operation: "http_request".to_string(),
+            duration_ms: 30000,
+            retryable: true,
+        }.into();
+
+        assert_eq!(error.code(), codes::TRANSPORT_TIMEOUT);
+        assert_eq!(error.code().to_string(), "E3005");
+        let report = error.report();
+        assert_eq!(report.code.name, "transport.timeout");
+        assert!(report.retryable);
+        assert_eq!(AgentError::agent_unhealthy("stalled").code(), codes::AGENT_UNHEALTHY);
+    }
+}
@@ -0,0 +1,665 @@
+// Local full-text index over buffered and archived events
+// Keeps an SQLite FTS5 index of event messages and fields so operators can search recent
+// activity on the host during incident response, bounded by rate, count, size and age budgets
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg(feature = "persistent-storage")]
+use crate::errors::BufferError;
+#[cfg(feature = "persistent-storage")]
+use crate::parsers::ParsedEvent;
+#[cfg(feature = "persistent-storage")]
+use rusqlite::{Connection, OptionalExtension};
+#[cfg(feature = "persistent-storage")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "persistent-storage")]
+use tracing::{debug, info, warn};
+
+/// Origin recorded for events indexed as they enter the buffer
+pub const ORIGIN_BUFFER: &str = "buffer";
+
+/// Local event index configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventIndexConfig {
+    /// Index events as they are buffered
+    pub enabled: bool,
+    /// SQLite database holding the FTS5 index
+    pub database_path: String,
+    /// NDJSON archives of parsed events (glob patterns) indexed alongside the buffer
+    pub archive_paths: Vec<String>,
+    /// Indexing budget: events accepted per second; the excess is skipped, never blocked on
+    pub max_events_per_second: usize,
+    /// Events waiting for the next commit; the excess is skipped
+    pub max_pending_events: usize,
+    /// Message and field text indexed per event; longer text is truncated
+    pub max_text_bytes: usize,
+    /// Upper bound on indexed events; the oldest are evicted first
+    pub max_indexed_events: usize,
+    /// Upper bound on live index data
+    pub max_index_size_mb: usize,
+    /// Indexed events older than this are evicted
+    pub retention_hours: u64,
+    /// Archive events indexed per maintenance pass
+    pub max_archive_events_per_pass: usize,
+    /// How often pending events are committed and budgets enforced
+    pub commit_interval_ms: u64,
+    /// Upper bound on results returned by a single search
+    pub max_results: usize,
+}
+
+impl Default for EventIndexConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            database_path: "./buffer/index.db".to_string(),
+            archive_paths: Vec::new(),
+            max_events_per_second: 2_000,
+            max_pending_events: 10_000,
+            max_text_bytes: 8 * 1024,
+            max_indexed_events: 1_000_000,
+            max_index_size_mb: 512,
+            retention_hours: 72,
+            max_archive_events_per_pass: 50_000,
+            commit_interval_ms: 1_000,
+            max_results: 1_000,
+        }
+    }
+}
+
+impl EventIndexConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.database_path.trim().is_empty() {
+            errors.push("Event index database_path cannot be empty".to_string());
+        }
+        if self.max_events_per_second == 0 {
+            errors.push("max_events_per_second must be greater than 0".to_string());
+        }
+        if self.max_pending_events == 0 {
+            errors.push("max_pending_events must be greater than 0".to_string());
+        }
+        if self.max_text_bytes < 64 {
+            errors.push("max_text_bytes must be at least 64".to_string());
+        }
+        if self.max_indexed_events == 0 || self.max_index_size_mb == 0 {
+            errors.push("max_indexed_events and max_index_size_mb must be greater than 0".to_string());
+        }
+        if self.commit_interval_ms == 0 {
+            errors.push("commit_interval_ms must be greater than 0".to_string());
+        }
+        if self.max_results == 0 {
+            errors.push("max_results must be greater than 0".to_string());
+        }
+        for pattern in &self.archive_paths {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                errors.push(format!("Invalid archive path pattern '{}': {}", pattern, e));
+            }
+        }
+        errors
+    }
+}
+
+/// A search against the local index
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchRequest {
+    /// FTS5 query, e.g. `"failed password" AND source:sshd`
+    pub query: String,
+    /// Restrict to a single event source
+    pub source: Option<String>,
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+    /// 0 for the configured maximum
+    pub limit: usize,
+}
+
+/// A matching event, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub source: String,
+    pub level: Option<String>,
+    pub message: String,
+    pub fields: HashMap<String, serde_json::Value>,
+    pub parser_name: String,
+    /// `buffer` or `archive:<path>`
+    pub origin: String,
+    /// Message excerpt with matches wrapped in `[` `]`
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    /// More matches exist beyond `limit`
+    pub truncated: bool,
+    pub took_ms: u64,
+}
+
+/// Index metrics
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EventIndexStats {
+    pub events_indexed: u64,
+    /// Events skipped because the rate or pending budget was exhausted
+    pub events_skipped: u64,
+    pub events_evicted: u64,
+    pub events_pending: u64,
+    pub entries: u64,
+    pub size_bytes: u64,
+    pub archives_indexed: u64,
+    pub searches: u64,
+}
+
+/// Event text queued for the next commit
+#[cfg(feature = "persistent-storage")]
+#[derive(Debug, Clone)]
+struct PendingEvent {
+    timestamp_ms: i64,
+    source: String,
+    level: String,
+    message: String,
+    fields: String,
+    parser_name: String,
+    origin: String,
+}
+
+#[cfg(feature = "persistent-storage")]
+impl PendingEvent {
+    fn from_event(event: &ParsedEvent, origin: &str, max_text_bytes: usize) -> Self {
+        let fields = serde_json::to_string(&event.fields).unwrap_or_default();
+        Self {
+            timestamp_ms: event.timestamp.timestamp_millis(),
+            source: event.source.clone(),
+            level: event.level.clone().unwrap_or_default(),
+            message: truncate_utf8(&event.message, max_text_bytes).to_string(),
+            fields: truncate_utf8(&fields, max_text_bytes).to_string(),
+            parser_name: event.parser_name.clone(),
+            origin: origin.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "persistent-storage")]
+#[derive(Default)]
+struct PendingQueue {
+    events: Vec<PendingEvent>,
+    window_second: i64,
+    window_count: usize,
+}
+
+/// FTS5 index shared by the buffer (writer), maintenance task, management API and CLI
+#[cfg(feature = "persistent-storage")]
+pub struct EventIndex {
+    config: EventIndexConfig,
+    store: parking_lot::Mutex<Connection>,
+    pending: parking_lot::Mutex<PendingQueue>,
+    events_indexed: AtomicU64,
+    events_skipped: AtomicU64,
+    events_evicted: AtomicU64,
+    archives_indexed: AtomicU64,
+    searches: AtomicU64,
+}
+
+#[cfg(feature = "persistent-storage")]
+impl EventIndex {
+    pub fn open(config: EventIndexConfig) -> Result<Self, BufferError> {
+        let path = std::path::Path::new(&config.database_path);
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| persistence_error("create_directory", &config, e))?;
+        }
+
+        let conn = Connection::open(path).map_err(|e| persistence_error("open_database", &config, e))?;
+        // auto_vacuum only takes effect before the first table is created
+        conn.pragma_update(None, "auto_vacuum", "INCREMENTAL")
+            .and_then(|_| conn.pragma_update(None, "journal_mode", "WAL"))
+            .and_then(|_| conn.pragma_update(None, "synchronous", "NORMAL"))
+            .and_then(|_| conn.execute_batch(
+                "CREATE VIRTUAL TABLE IF NOT EXISTS event_text USING fts5(
+                    message, fields, source, level,
+                    timestamp UNINDEXED, parser_name UNINDEXED, origin UNINDEXED,
+                    tokenize = 'unicode61'
+                );
+                CREATE TABLE IF NOT EXISTS indexed_archives (
+                    path TEXT PRIMARY KEY,
+                    size_bytes INTEGER NOT NULL,
+                    modified INTEGER NOT NULL,
+                    events INTEGER NOT NULL
+                );",
+            ))
+            .map_err(|e| persistence_error("create_schema", &config, e))?;
+
+        info!("🔎 Event index opened at {} (budget: {} events/s, max {} events / {} MB)",
+              config.database_path, config.max_events_per_second, config.max_indexed_events, config.max_index_size_mb);
+
+        Ok(Self {
+            config,
+            store: parking_lot::Mutex::new(conn),
+            pending: parking_lot::Mutex::new(PendingQueue::default()),
+            events_indexed: AtomicU64::new(0),
+            events_skipped: AtomicU64::new(0),
+            events_evicted: AtomicU64::new(0),
+            archives_indexed: AtomicU64::new(0),
+            searches: AtomicU64::new(0),
+        })
+    }
+
+    pub fn config(&self) -> &EventIndexConfig {
+        &self.config
+    }
+
+    /// Queue a buffered event for indexing; never blocks the buffer on the index
+    pub fn record(&self, event: &ParsedEvent) {
+        let now = chrono::Utc::now().timestamp();
+        let mut pending = self.pending.lock();
+        if pending.window_second != now {
+            pending.window_second = now;
+            pending.window_count = 0;
+        }
+        if pending.window_count >= self.config.max_events_per_second
+            || pending.events.len() >= self.config.max_pending_events
+        {
+            self.events_skipped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        pending.window_count += 1;
+        pending.events.push(PendingEvent::from_event(event, ORIGIN_BUFFER, self.config.max_text_bytes));
+    }
+
+    /// Write queued events in one transaction, returning how many were indexed
+    pub fn commit(&self) -> Result<usize, BufferError> {
+        let events = std::mem::take(&mut self.pending.lock().events);
+        if events.is_empty() {
+            return Ok(0);
+        }
+        let count = events.len();
+        self.insert(&events).map_err(|e| persistence_error("index_events", &self.config, e))?;
+        self.events_indexed.fetch_add(count as u64, Ordering::Relaxed);
+        debug!("🔎 Indexed {} buffered events", count);
+        Ok(count)
+    }
+
+    fn insert(&self, events: &[PendingEvent]) -> Result<(), rusqlite::Error> {
+        let mut conn = self.store.lock();
+        let tx = conn.transaction()?;
+        {
+            let mut statement = tx.prepare_cached(
+                "INSERT INTO event_text (message, fields, source, level, timestamp, parser_name, origin)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for event in events {
+                statement.execute(rusqlite::params![
+                    event.message, event.fields, event.source, event.level,
+                    event.timestamp_ms, event.parser_name, event.origin,
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Index archive files not seen before (or changed since), within the per-pass budget
+    pub fn index_archives(&self) -> Result<usize, BufferError> {
+        let mut budget = self.config.max_archive_events_per_pass;
+        let mut indexed = 0;
+
+        for pattern in &self.config.archive_paths {
+            let Ok(paths) = glob::glob(pattern) else {
+                continue;
+            };
+            for path in paths.flatten() {
+                if budget == 0 {
+                    return Ok(indexed);
+                }
+                let count = self.index_archive(&path, budget)
+                    .map_err(|e| persistence_error("index_archive", &self.config, e))?;
+                budget -= count;
+                indexed += count;
+            }
+        }
+        Ok(indexed)
+    }
+
+    /// Index up to `budget` events from one archive, resuming where a previous pass stopped
+    fn index_archive(&self, path: &std::path::Path, budget: usize) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let metadata = std::fs::metadata(path)?;
+        let size = metadata.len() as i64;
+        let modified = metadata.modified()?.duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64;
+        let key = path.display().to_string();
+
+        let previous: Option<(i64, i64, i64)> = self.store.lock().query_row(
+            "SELECT size_bytes, modified, events FROM indexed_archives WHERE path = ?1",
+            [&key],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).optional()?;
+        // Archives are append-only; a file that shrank was rewritten and is indexed again
+        let skip = match previous {
+            Some((s, m, events)) if s == size && m == modified => events as usize,
+            Some((s, _, events)) if s < size => events as usize,
+            _ => 0,
+        };
+
+        let content = std::fs::read_to_string(path)?;
+        let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
+        if skip >= lines.len() {
+            return Ok(0);
+        }
+        let batch = &lines[skip..(skip + budget).min(lines.len())];
+        let consumed = skip + batch.len();
+
+        let origin = format!("archive:{}", key);
+        let events: Vec<PendingEvent> = batch
+            .iter()
+            .filter_map(|line| match serde_json::from_str::<ParsedEvent>(line) {
+                Ok(event) => Some(PendingEvent::from_event(&event, &origin, self.config.max_text_bytes)),
+                Err(e) => {
+                    debug!("Skipping unreadable archived event in {}: {}", key, e);
+                    None
+                }
+            })
+            .collect();
+
+        self.insert(&events)?;
+        self.store.lock().execute(
+            "INSERT INTO indexed_archives (path, size_bytes, modified, events) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(path) DO UPDATE SET size_bytes = excluded.size_bytes, modified = excluded.modified,
+             events = excluded.events",
+            rusqlite::params![key, size, modified, consumed as i64],
+        )?;
+
+        if consumed == lines.len() {
+            self.archives_indexed.fetch_add(1, Ordering::Relaxed);
+            info!("🔎 Indexed archive {} ({} events)", key, consumed);
+        }
+        self.events_indexed.fetch_add(events.len() as u64, Ordering::Relaxed);
+        Ok(batch.len())
+    }
+
+    /// Evict expired events, then the oldest until the count and size budgets hold
+    pub fn enforce_budgets(&self) -> Result<usize, BufferError> {
+        let cutoff = chrono::Utc::now().timestamp_millis() - self.config.retention_hours as i64 * 3_600_000;
+        self.evict(cutoff).map_err(|e| persistence_error("enforce_budgets", &self.config, e))
+    }
+
+    fn evict(&self, cutoff_ms: i64) -> Result<usize, rusqlite::Error> {
+        let conn = self.store.lock();
+        let mut evicted = conn.execute("DELETE FROM event_text WHERE timestamp < ?1", [cutoff_ms])?;
+
+        let entries: i64 = conn.query_row("SELECT COUNT(*) FROM event_text", [], |row| row.get(0))?;
+        let excess = entries - self.config.max_indexed_events as i64;
+        if excess > 0 {
+            evicted += conn.execute(
+                "DELETE FROM event_text WHERE rowid IN (SELECT rowid FROM event_text ORDER BY rowid LIMIT ?1)",
+                [excess],
+            )?;
+        }
+
+        // Shed a tenth of the remaining events at a time until live data fits
+        let max_bytes = self.config.max_index_size_mb as i64 * 1024 * 1024;
+        while live_bytes(&conn)? > max_bytes {
+            let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM event_text", [], |row| row.get(0))?;
+            if remaining == 0 {
+                break;
+            }
+            evicted += conn.execute(
+                "DELETE FROM event_text WHERE rowid IN (SELECT rowid FROM event_text ORDER BY rowid LIMIT ?1)",
+                [(remaining / 10).max(1)],
+            )?;
+        }
+
+        if evicted > 0 {
+            conn.execute_batch("INSERT INTO event_text(event_text) VALUES('optimize'); PRAGMA incremental_vacuum;")?;
+            self.events_evicted.fetch_add(evicted as u64, Ordering::Relaxed);
+            debug!("🧹 Evicted {} events from the event index", evicted);
+        }
+        Ok(evicted)
+    }
+
+    /// Run an FTS5 query, newest matches first
+    pub fn search(&self, request: &SearchRequest) -> Result<SearchResults, BufferError> {
+        let started = std::time::Instant::now();
+        let query = request.query.trim();
+        if query.is_empty() {
+            return Err(BufferError::InvalidSearchQuery {
+                query: request.query.clone(),
+                reason: "query cannot be empty".to_string(),
+            });
+        }
+        self.searches.fetch_add(1, Ordering::Relaxed);
+
+        let limit = match request.limit {
+            0 => self.config.max_results,
+            limit => limit.min(self.config.max_results),
+        };
+        let since = request.since.map(|t| t.timestamp_millis()).unwrap_or(i64::MIN);
+        let until = request.until.map(|t| t.timestamp_millis()).unwrap_or(i64::MAX);
+
+        let conn = self.store.lock();
+        let rows = conn.prepare_cached(
+            "SELECT timestamp, source, level, message, fields, parser_name, origin,
+                    snippet(event_text, 0, '[', ']', '…', 16)
+             FROM event_text
+             WHERE event_text MATCH ?1 AND timestamp BETWEEN ?2 AND ?3 AND (?4 IS NULL OR source = ?4)
+             ORDER BY timestamp DESC
+             LIMIT ?5",
+        )
+        .and_then(|mut statement| {
+            statement
+                .query_map(
+                    rusqlite::params![query, since, until, request.source, limit as i64 + 1],
+                    |row| {
+                        let fields: String = row.get(4)?;
+                        let level: String = row.get(2)?;
+                        Ok(SearchHit {
+                            timestamp: chrono::DateTime::from_timestamp_millis(row.get(0)?).unwrap_or_default(),
+                            source: row.get(1)?,
+                            level: Some(level).filter(|l| !l.is_empty()),
+                            message: row.get(3)?,
+                            // Truncated field text no longer parses; the message is still searchable
+                            fields: serde_json::from_str(&fields).unwrap_or_default(),
+                            parser_name: row.get(5)?,
+                            origin: row.get(6)?,
+                            snippet: row.get(7)?,
+                        })
+                    },
+                )?
+                .collect::<Result<Vec<_>, _>>()
+        });
+
+        let mut hits = rows.map_err(|e| match e {
+            // FTS5 reports query syntax problems as plain SQLITE_ERROR; the statement itself is fixed
+            rusqlite::Error::SqliteFailure(failure, Some(message)) if failure.extended_code == rusqlite::ffi::SQLITE_ERROR => {
+                BufferError::InvalidSearchQuery { query: request.query.clone(), reason: message }
+            }
+            other => persistence_error("search", &self.config, other),
+        })?;
+
+        let truncated = hits.len() > limit;
+        hits.truncate(limit);
+        Ok(SearchResults {
+            hits,
+            truncated,
+            took_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+
+    pub fn stats(&self) -> EventIndexStats {
+        let conn = self.store.lock();
+        let entries = conn.query_row("SELECT COUNT(*) FROM event_text", [], |row| row.get::<_, i64>(0)).unwrap_or(0);
+        let size_bytes = live_bytes(&conn).unwrap_or(0);
+        drop(conn);
+
+        EventIndexStats {
+            events_indexed: self.events_indexed.load(Ordering::Relaxed),
+            events_skipped: self.events_skipped.load(Ordering::Relaxed),
+            events_evicted: self.events_evicted.load(Ordering::Relaxed),
+            events_pending: self.pending.lock().events.len() as u64,
+            entries: entries as u64,
+            size_bytes: size_bytes as u64,
+            archives_indexed: self.archives_indexed.load(Ordering::Relaxed),
+            searches: self.searches.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Commit queued events, index pending archives and enforce budgets
+    pub fn run_maintenance(&self) {
+        if let Err(e) = self.commit() {
+            warn!("⚠️ Failed to commit indexed events: {}", e);
+        }
+        if !self.config.archive_paths.is_empty() {
+            if let Err(e) = self.index_archives() {
+                warn!("⚠️ Failed to index event archives: {}", e);
+            }
+        }
+        if let Err(e) = self.enforce_budgets() {
+            warn!("⚠️ Failed to enforce event index budgets: {}", e);
+        }
+    }
+}
+
+#[cfg(feature = "persistent-storage")]
+fn live_bytes(conn: &Connection) -> Result<i64, rusqlite::Error> {
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let freelist: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    Ok((page_count - freelist) * page_size)
+}
+
+#[cfg(feature = "persistent-storage")]
+fn persistence_error(operation: &str, config: &EventIndexConfig, e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> BufferError {
+    BufferError::PersistenceError {
+        operation: operation.to_string(),
+        database_path: config.database_path.clone(),
+        recoverable: true,
+        source: e.into(),
+    }
+}
+
+#[cfg(feature = "persistent-storage")]
+fn truncate_utf8(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Parse a search time bound: RFC 3339, or a relative age such as `30m`, `2h` or `7d`
+pub fn parse_time_bound(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&chrono::Utc));
+    }
+    let split = value.len().checked_sub(1)?;
+    let amount: i64 = value[..split].parse().ok()?;
+    let age = match &value[split..] {
+        "s" => chrono::Duration::seconds(amount),
+        "m" => chrono::Duration::minutes(amount),
+        "h" => chrono::Duration::hours(amount),
+        "d" => chrono::Duration::days(amount),
+        _ => return None,
+    };
+    Some(chrono::Utc::now() - age)
+}
+
+#[cfg(all(test, feature = "persistent-storage"))]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn index(dir: &std::path::Path, configure: impl FnOnce(&mut EventIndexConfig)) -> EventIndex {
+        let mut config = EventIndexConfig {
+            enabled: true,
+            database_path: dir.join("index.db").display().to_string(),
+            ..Default::default()
+        };
+        configure(&mut config);
+        EventIndex::open(config).unwrap()
+    }
+
+    fn event(source: &str, message: &str, age_minutes: i64) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc::now() - chrono::Duration::minutes(age_minutes),
+            source: source.to_string(),
+            level: Some("warn".to_string()),
+            message: message.to_string(),
+            fields: HashMap::from([("user".to_string(), serde_json::json!("mallory"))]),
+            raw_data: message.into(),
+            parser_name: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_search_matches_message_and_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = index(dir.path(), |_| {});
+        index.record(&event("sshd", "Failed password for root from 10.0.0.5", 30));
+        index.record(&event("sshd", "Accepted publickey for deploy", 20));
+        index.record(&event("sudo", "session opened for user root", 10));
+        assert_eq!(index.commit().unwrap(), 3);
+
+        let results = index.search(&SearchRequest { query: "root".to_string(), ..Default::default() }).unwrap();
+        assert_eq!(results.hits.len(), 2);
+        assert_eq!(results.hits[0].source, "sudo");
+        assert!(results.hits[1].snippet.contains("[root]"));
+
+        let filtered = index.search(&SearchRequest {
+            query: "mallory".to_string(),
+            source: Some("sshd".to_string()),
+            since: parse_time_bound("25m"),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(filtered.hits.len(), 1);
+        assert_eq!(filtered.hits[0].fields["user"], "mallory");
+
+        let invalid = index.search(&SearchRequest { query: "\"unterminated".to_string(), ..Default::default() });
+        assert!(matches!(invalid, Err(BufferError::InvalidSearchQuery { .. })));
+    }
+
+    #[test]
+    fn test_budgets_skip_and_evict() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = index(dir.path(), |config| {
+            config.max_events_per_second = 3;
+            config.max_indexed_events = 2;
+            config.retention_hours = 1;
+        });
+        index.record(&event("syslog", "expired event", 120));
+        for i in 0..4 {
+            index.record(&event("syslog", &format!("event {}", i), 1));
+        }
+        index.commit().unwrap();
+        assert!(index.stats().events_skipped >= 1);
+
+        assert_eq!(index.enforce_budgets().unwrap(), 1);
+        let stats = index.stats();
+        assert_eq!(stats.entries, 2);
+        assert!(index.search(&SearchRequest { query: "expired".to_string(), ..Default::default() }).unwrap().hits.is_empty());
+    }
+
+    #[test]
+    fn test_archives_are_indexed_incrementally() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("events-1.ndjson");
+        let mut file = std::fs::File::create(&archive).unwrap();
+        for i in 0..3 {
+            serde_json::to_writer(&mut file, &event("archived", &format!("archived login {}", i), 5)).unwrap();
+            file.write_all(b"\n").unwrap();
+        }
+        drop(file);
+
+        let index = index(dir.path(), |config| {
+            config.archive_paths = vec![dir.path().join("*.ndjson").display().to_string()];
+            config.max_archive_events_per_pass = 2;
+        });
+        assert_eq!(index.index_archives().unwrap(), 2);
+        assert_eq!(index.index_archives().unwrap(), 1);
+        assert_eq!(index.index_archives().unwrap(), 0);
+
+        let results = index.search(&SearchRequest { query: "login".to_string(), ..Default::default() }).unwrap();
+        assert_eq!(results.hits.len(), 3);
+        assert!(results.hits[0].origin.starts_with("archive:"));
+        assert_eq!(index.stats().archives_indexed, 1);
+    }
+}
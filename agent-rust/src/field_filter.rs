@@ -0,0 +1,401 @@
+// Field-level data minimization for outbound events
+// Applies per-destination allowlists/denylists to event fields at serialization time so
+// sensitive fields (e.g. command lines containing secrets) never leave the host
+
+use crate::parsers::processors::DEFAULT_REDACTION;
+use crate::parsers::ParsedEvent;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+use tracing::info;
+
+/// Shorter denied values are left in the message; masking them would mangle unrelated words
+const MIN_MASKED_VALUE_LEN: usize = 4;
+
+/// Field filtering configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FieldFilterConfig {
+    /// Enable field filtering
+    pub enabled: bool,
+    /// Filter rules, evaluated together for every outbound event
+    pub rules: Vec<FieldFilterRule>,
+}
+
+/// A single allow/deny rule.
+///
+/// Patterns support `*` wildcards (e.g. `process.*`, `*.command_line`).
+/// Empty `destinations`/`parsers` lists apply the rule everywhere.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldFilterRule {
+    /// Rule name used in validation messages and audit logs
+    pub name: String,
+    /// Destinations the rule applies to (matched against the transport server URL)
+    #[serde(default)]
+    pub destinations: Vec<String>,
+    /// Parsers the rule applies to (matched against the event's parser name)
+    #[serde(default)]
+    pub parsers: Vec<String>,
+    /// Only fields matching one of these patterns are sent
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Fields matching any of these patterns are never sent (deny wins over allow); their values, and
+    /// `key=value` or `key: value` pairs in the message whose key matches, are masked in the message
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Drop the original raw log line, which may contain the denied values
+    #[serde(default)]
+    pub drop_raw_data: bool,
+}
+
+/// Field filter bound to a single destination
+#[derive(Debug, Clone)]
+pub struct FieldFilter {
+    destination: String,
+    rules: Vec<FieldFilterRule>,
+}
+
+impl FieldFilter {
+    /// Build the filter for a destination, keeping only the rules that target it
+    pub fn new(config: &FieldFilterConfig, destination: &str) -> Self {
+        let rules: Vec<FieldFilterRule> = if config.enabled {
+            config
+                .rules
+                .iter()
+                .filter(|rule| matches_any(&rule.destinations, destination, true))
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        for rule in &rules {
+            info!(
+                "🔏 Field filter rule '{}' active for {}: allow={:?}, deny={:?}, drop_raw_data={}",
+                rule.name, destination, rule.allow, rule.deny, rule.drop_raw_data
+            );
+        }
+
+        Self {
+            destination: destination.to_string(),
+            rules,
+        }
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    /// Serialize an event, removing every field the destination must not receive
+    pub fn to_value(&self, event: &ParsedEvent) -> serde_json::Result<Value> {
+        let mut value = serde_json::to_value(event)?;
+        if !self.is_active() {
+            return Ok(value);
+        }
+
+        let rules: Vec<&FieldFilterRule> = self
+            .rules
+            .iter()
+            .filter(|rule| matches_any(&rule.parsers, &event.parser_name, true))
+            .collect();
+        if rules.is_empty() {
+            return Ok(value);
+        }
+
+        let denied_values: Vec<String> = value
+            .get("fields")
+            .and_then(Value::as_object)
+            .map(|fields| {
+                fields
+                    .iter()
+                    .filter(|(key, _)| Self::is_field_denied(&rules, key))
+                    .filter_map(|(_, value)| value.as_str())
+                    .filter(|value| value.len() >= MIN_MASKED_VALUE_LEN)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(fields) = value.get_mut("fields").and_then(Value::as_object_mut) {
+            fields.retain(|key, _| Self::is_field_allowed(&rules, key));
+        }
+        if let Some(message) = value.get_mut("message") {
+            if let Some(masked) = message.as_str().and_then(|text| Self::mask_message(&rules, text, &denied_values)) {
+                *message = Value::String(masked);
+            }
+        }
+        if rules.iter().any(|rule| rule.drop_raw_data) {
+            if let Some(object) = value.as_object_mut() {
+                object.insert("raw_data".to_string(), Value::String(String::new()));
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Filtered copy of an event, borrowing it unchanged when no rule applies
+    pub fn apply<'a>(&self, event: &'a ParsedEvent) -> serde_json::Result<Cow<'a, ParsedEvent>> {
+        if !self.is_active() {
+            return Ok(Cow::Borrowed(event));
+        }
+        serde_json::from_value(self.to_value(event)?).map(Cow::Owned)
+    }
+
+    fn is_field_denied(rules: &[&FieldFilterRule], field: &str) -> bool {
+        rules.iter().any(|rule| matches_any(&rule.deny, field, false))
+    }
+
+    fn is_field_allowed(rules: &[&FieldFilterRule], field: &str) -> bool {
+        if Self::is_field_denied(rules, field) {
+            return false;
+        }
+
+        let mut allowlists = rules.iter().filter(|rule| !rule.allow.is_empty()).peekable();
+        if allowlists.peek().is_none() {
+            return true;
+        }
+        allowlists.any(|rule| matches_any(&rule.allow, field, false))
+    }
+
+    /// Whether a key written into a message, e.g. `password` in "password=hunter2", names a denied field; the
+    /// last segment of a dotted pattern matches too, so `*.command_line` denies "command_line: ..."
+    fn is_message_key_denied(rules: &[&FieldFilterRule], key: &str) -> bool {
+        let key = key.to_ascii_lowercase();
+        rules.iter().flat_map(|rule| &rule.deny).any(|pattern| {
+            let pattern = pattern.to_ascii_lowercase();
+            let last = pattern.rsplit('.').next().unwrap_or(&pattern);
+            wildcard_match(&pattern, &key) || (last != "*" && wildcard_match(last, &key))
+        })
+    }
+
+    /// The message with denied key/value pairs and denied field values masked, None when nothing was masked
+    fn mask_message(rules: &[&FieldFilterRule], message: &str, denied_values: &[String]) -> Option<String> {
+        let mut masked = message.to_string();
+        for denied in denied_values {
+            if masked.contains(denied.as_str()) {
+                masked = masked.replace(denied.as_str(), DEFAULT_REDACTION);
+            }
+        }
+        let masked = key_value_regex()
+            .replace_all(&masked, |caps: &regex::Captures| {
+                if Self::is_message_key_denied(rules, &caps["key"]) {
+                    format!("{}{}{}", &caps["key"], &caps["separator"], DEFAULT_REDACTION)
+                } else {
+                    caps[0].to_string()
+                }
+            })
+            .into_owned();
+        (masked != message).then_some(masked)
+    }
+}
+
+fn key_value_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?P<key>[A-Za-z_][A-Za-z0-9_.\-]*)(?P<separator>\s*[=:]\s*)(?P<value>"[^"]*"|'[^']*'|[^\s,;&]+)"#)
+            .expect("valid key/value regex")
+    })
+}
+
+impl FieldFilterConfig {
+    /// Audit the rules, returning a message for every problem found
+    pub fn validate(&self, destination: &str, parser_names: &[String]) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for rule in &self.rules {
+            if rule.name.trim().is_empty() {
+                errors.push("Field filter rule names cannot be empty".to_string());
+            } else if !seen.insert(rule.name.as_str()) {
+                errors.push(format!("Duplicate field filter rule name '{}'", rule.name));
+            }
+
+            if rule.allow.is_empty() && rule.deny.is_empty() && !rule.drop_raw_data {
+                errors.push(format!("Field filter rule '{}' has no allow, deny or drop_raw_data settings", rule.name));
+            }
+
+            let patterns = rule.allow.iter().chain(&rule.deny).chain(&rule.destinations).chain(&rule.parsers);
+            for pattern in patterns {
+                if pattern.trim().is_empty() {
+                    errors.push(format!("Field filter rule '{}' contains an empty pattern", rule.name));
+                }
+            }
+
+            for pattern in &rule.deny {
+                if rule.allow.contains(pattern) {
+                    errors.push(format!(
+                        "Field filter rule '{}' both allows and denies '{}'; deny takes precedence",
+                        rule.name, pattern
+                    ));
+                }
+            }
+
+            if !rule.destinations.is_empty() && !matches_any(&rule.destinations, destination, false) {
+                errors.push(format!(
+                    "Field filter rule '{}' does not match the configured destination '{}'",
+                    rule.name, destination
+                ));
+            }
+
+            for pattern in &rule.parsers {
+                let known = pattern.starts_with("passthrough")
+                    || parser_names.iter().any(|name| wildcard_match(pattern, name));
+                if !known {
+                    errors.push(format!(
+                        "Field filter rule '{}' references unknown parser '{}'",
+                        rule.name, pattern
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+fn matches_any(patterns: &[String], value: &str, empty_matches: bool) -> bool {
+    if patterns.is_empty() {
+        return empty_matches;
+    }
+    patterns.iter().any(|pattern| wildcard_match(pattern, value))
+}
+
+/// Match `value` against a pattern where `*` matches any sequence of characters
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, v));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == value[v] {
+            p += 1;
+            v += 1;
+        } else if let Some((star_p, star_v)) = star {
+            p = star_p + 1;
+            v = star_v + 1;
+            star = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn event(parser_name: &str) -> ParsedEvent {
+        let mut fields = HashMap::new();
+        fields.insert("process.name".to_string(), serde_json::json!("curl"));
+        fields.insert("process.command_line".to_string(), serde_json::json!("curl -u admin:secret"));
+        fields.insert("host.name".to_string(), serde_json::json!("web-01"));
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "test".to_string(),
+            level: None,
+            message: "curl".to_string(),
+            fields,
+            raw_data: "curl -u admin:secret".into(),
+            parser_name: parser_name.to_string(),
+        }
+    }
+
+    fn rule(name: &str) -> FieldFilterRule {
+        FieldFilterRule {
+            name: name.to_string(),
+            destinations: Vec::new(),
+            parsers: Vec::new(),
+            allow: Vec::new(),
+            deny: Vec::new(),
+            drop_raw_data: false,
+        }
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("process.*", "process.command_line"));
+        assert!(wildcard_match("*.command_line", "process.parent.command_line"));
+        assert!(wildcard_match("https://*.internal/*", "https://siem.internal/ingest"));
+        assert!(!wildcard_match("process.*", "host.name"));
+        assert!(wildcard_match("host.name", "host.name"));
+    }
+
+    #[test]
+    fn test_deny_and_allow_per_destination() {
+        let mut deny = rule("no-command-lines");
+        deny.destinations = vec!["https://external.*".to_string()];
+        deny.deny = vec!["*.command_line".to_string()];
+        deny.drop_raw_data = true;
+        let mut allow = rule("process-only");
+        allow.parsers = vec!["sysmon".to_string()];
+        allow.allow = vec!["process.*".to_string()];
+        let config = FieldFilterConfig { enabled: true, rules: vec![deny, allow] };
+
+        let external = FieldFilter::new(&config, "https://external.example.com/ingest");
+        let value = external.to_value(&event("sysmon")).unwrap();
+        let fields = value["fields"].as_object().unwrap();
+        assert_eq!(fields.len(), 1);
+        assert!(fields.contains_key("process.name"));
+        assert_eq!(value["raw_data"], "");
+
+        // Parser-scoped allowlist does not apply to other parsers
+        let value = external.to_value(&event("syslog")).unwrap();
+        assert_eq!(value["fields"].as_object().unwrap().len(), 2);
+
+        let internal = FieldFilter::new(&config, "https://siem.internal/ingest");
+        let value = internal.to_value(&event("syslog")).unwrap();
+        assert_eq!(value["fields"].as_object().unwrap().len(), 3);
+        assert_eq!(value["raw_data"], "curl -u admin:secret");
+    }
+
+    #[test]
+    fn test_validation_audit() {
+        let mut unknown_parser = rule("unknown-parser");
+        unknown_parser.parsers = vec!["missing".to_string()];
+        unknown_parser.deny = vec!["user.*".to_string()];
+        let config = FieldFilterConfig {
+            enabled: true,
+            rules: vec![rule("noop"), unknown_parser],
+        };
+
+        let errors = config.validate("https://siem.internal", &["sysmon".to_string()]);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("no allow, deny"));
+        assert!(errors[1].contains("unknown parser 'missing'"));
+    }
+
+    #[test]
+    fn test_deny_rules_mask_the_message() {
+        let mut deny = rule("no-secrets");
+        deny.deny = vec!["*.command_line".to_string(), "password".to_string()];
+        let config = FieldFilterConfig { enabled: true, rules: vec![deny] };
+        let filter = FieldFilter::new(&config, "https://siem.internal/ingest");
+
+        let mut sent = event("syslog");
+        sent.message = "ran curl -u admin:secret as web, Password=\"hunter 2\" command_line: curl host=web-01".to_string();
+        let value = filter.to_value(&sent).unwrap();
+        assert_eq!(
+            value["message"],
+            "ran [REDACTED] as web, Password=[REDACTED] command_line: [REDACTED] host=web-01"
+        );
+        assert!(!value["fields"].as_object().unwrap().contains_key("process.command_line"));
+
+        // A message without denied keys or values is sent unchanged
+        let value = filter.to_value(&event("syslog")).unwrap();
+        assert_eq!(value["message"], "curl");
+    }
+}
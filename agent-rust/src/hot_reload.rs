@@ -0,0 +1,109 @@
+// Configuration hot-reload planning
+// Sorts the changes between the running and the reloaded configuration into the components the agent rebuilds in
+// place (collectors, parsing stages, transport, buffer limits) and settings that keep their old value until the
+// next restart, so an operator can see exactly which parts of an edit took effect.
+
+use crate::config_diff::ConfigChange;
+
+/// Settings inside reloadable sections that are read once at startup
+const STARTUP_ONLY_SETTINGS: &[&str] = &["parsers.sample_capture"];
+
+/// Sections the parsing engine is built from; collectors decide which built-in source parsers are registered
+const PARSING_SECTIONS: &[&str] = &["parsers", "ecs", "enrichment", "alert_rules", "sigma", "collectors"];
+
+/// Sections the outbound transport is built from
+const TRANSPORT_SECTIONS: &[&str] = &["transport", "field_filter", "relay.upstream"];
+
+/// Buffer settings read while the buffer is open (see `BufferLimits`)
+const BUFFER_LIMIT_SETTINGS: &[&str] = &[
+    "buffer.max_size_mb",
+    "buffer.max_database_size_mb",
+    "buffer.cleanup_trigger_percent",
+    "buffer.cleanup_target_percent",
+    "buffer.min_retention_hours",
+    "buffer.max_events_per_cleanup",
+];
+
+/// What a configuration reload has to rebuild
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReloadPlan {
+    pub collectors: bool,
+    pub parsing: bool,
+    pub transport: bool,
+    pub buffer_limits: bool,
+    /// Changed settings that only take effect after a restart
+    pub restart_required: Vec<String>,
+}
+
+impl ReloadPlan {
+    pub fn new(changes: &[ConfigChange]) -> Self {
+        let mut plan = Self::default();
+        for change in changes {
+            let path = change.path.as_str();
+            let in_any = |sections: &[&str]| sections.iter().any(|section| in_section(path, section));
+            plan.collectors |= in_section(path, "collectors");
+            if in_any(STARTUP_ONLY_SETTINGS) {
+                plan.restart_required.push(change.path.clone());
+            } else if in_any(PARSING_SECTIONS) {
+                plan.parsing = true;
+            } else if in_any(TRANSPORT_SECTIONS) {
+                plan.transport = true;
+            } else if in_any(BUFFER_LIMIT_SETTINGS) {
+                plan.buffer_limits = true;
+            } else {
+                plan.restart_required.push(change.path.clone());
+            }
+        }
+        plan
+    }
+
+    /// True when nothing can be applied without a restart
+    pub fn is_empty(&self) -> bool {
+        !(self.collectors || self.parsing || self.transport || self.buffer_limits)
+    }
+
+    /// Components rebuilt by this plan, for logging
+    pub fn components(&self) -> Vec<&'static str> {
+        [
+            (self.collectors, "collectors"),
+            (self.parsing, "parsers"),
+            (self.transport, "transport"),
+            (self.buffer_limits, "buffer limits"),
+        ]
+        .into_iter()
+        .filter_map(|(apply, name)| apply.then_some(name))
+        .collect()
+    }
+}
+
+/// `path` is `section` itself or a value inside it
+fn in_section(path: &str, section: &str) -> bool {
+    path.strip_prefix(section)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.') || rest.starts_with('['))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AgentConfig;
+    use crate::config_diff::diff_configs;
+
+    #[test]
+    fn test_plan_sorts_changes_by_component() {
+        let old = AgentConfig::default();
+        let mut new = old.clone();
+        new.transport.server_url = "https://siem-2.example.com:8443".to_string();
+        new.buffer.max_size_mb += 100;
+        new.buffer.max_events += 1;
+        new.ecs.enabled = true;
+        new.management.port += 1;
+
+        let plan = ReloadPlan::new(&diff_configs(&old, &new));
+        assert!(plan.transport && plan.buffer_limits && plan.parsing);
+        assert!(!plan.collectors);
+        assert_eq!(plan.restart_required, vec!["buffer.max_events".to_string(), "management.port".to_string()]);
+        assert_eq!(plan.components(), vec!["parsers", "transport", "buffer limits"]);
+
+        assert!(ReloadPlan::new(&[]).is_empty());
+    }
+}
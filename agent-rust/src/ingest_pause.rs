@@ -0,0 +1,319 @@
+// Operator-controlled ingest pauses, globally or per source
+// Events from a paused source are discarded at the buffer until the pause expires or is lifted. Pauses are kept
+// in the buffer database's buffer_metadata table so they survive restarts and can be set from the CLI; a running
+// agent picks up changes made by the CLI on its next refresh
+
+use crate::config::BufferConfig;
+use crate::errors::BufferError;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+#[cfg(feature = "persistent-storage")]
+use rusqlite::{Connection, OptionalExtension};
+
+/// buffer_metadata key the pauses are stored under
+const METADATA_KEY: &str = "ingest_pauses";
+
+/// A pause on one source, or on all sources when `source` is None
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IngestPause {
+    pub source: Option<String>,
+    pub paused_at: DateTime<Utc>,
+    /// None pauses until explicitly resumed
+    pub until: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+impl IngestPause {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.until.is_none_or(|until| now < until)
+    }
+
+    fn covers(&self, source: &str) -> bool {
+        self.source.as_deref().is_none_or(|s| s == source)
+    }
+
+    /// "all sources" or the source name, for logs and status output
+    pub fn scope(&self) -> &str {
+        self.source.as_deref().unwrap_or("all sources")
+    }
+
+    /// Scope, expiry and reason on one line, for the audit log
+    pub fn describe(&self) -> String {
+        let mut description = self.scope().to_string();
+        if let Some(until) = self.until {
+            description.push_str(&format!(" until {}", until.to_rfc3339()));
+        }
+        if let Some(reason) = &self.reason {
+            description.push_str(&format!(": {}", reason));
+        }
+        description
+    }
+}
+
+/// Active pauses and how many events each source lost to them since startup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestPauseStatus {
+    pub pauses: Vec<IngestPause>,
+    pub discarded_events: HashMap<String, u64>,
+}
+
+impl IngestPauseStatus {
+    pub fn is_paused(&self) -> bool {
+        !self.pauses.is_empty()
+    }
+}
+
+/// Pause registry shared by the buffer, the agent, the control socket and the CLI
+pub struct IngestPauses {
+    pauses: RwLock<Vec<IngestPause>>,
+    discarded: RwLock<HashMap<String, u64>>,
+    /// Buffer database; None when the buffer is not persistent and pauses last until restart
+    database_path: Option<PathBuf>,
+}
+
+impl IngestPauses {
+    /// Open the pause registry stored alongside the buffer described by `config`
+    pub fn open(config: &BufferConfig) -> Result<Self, BufferError> {
+        let database_path = (config.persistent && cfg!(feature = "persistent-storage"))
+            .then(|| Path::new(&config.persistence_path).join("events.db"));
+        let registry = Self {
+            pauses: RwLock::new(Vec::new()),
+            discarded: RwLock::new(HashMap::new()),
+            database_path,
+        };
+        registry.refresh()?;
+
+        for pause in registry.pauses.read().iter() {
+            match pause.until {
+                Some(until) => info!("⏸️ Ingest from {} is paused until {}", pause.scope(), until.to_rfc3339()),
+                None => info!("⏸️ Ingest from {} is paused until resumed", pause.scope()),
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Whether events from `source` are currently discarded
+    pub fn is_paused(&self, source: &str) -> bool {
+        let pauses = self.pauses.read();
+        if pauses.is_empty() {
+            return false;
+        }
+        let now = Utc::now();
+        pauses.iter().any(|pause| pause.covers(source) && pause.is_active(now))
+    }
+
+    /// Count an event discarded because its source is paused
+    pub fn record_discarded(&self, source: &str) {
+        *self.discarded.write().entry(source.to_string()).or_insert(0) += 1;
+    }
+
+    /// Pause `source` (all sources when None), replacing any existing pause with the same scope
+    pub fn pause(&self, source: Option<&str>, until: Option<DateTime<Utc>>, reason: Option<String>) -> Result<IngestPause, BufferError> {
+        let pause = IngestPause {
+            source: source.map(str::to_string),
+            paused_at: Utc::now(),
+            until,
+            reason,
+        };
+        let mut pauses = self.load()?;
+        pauses.retain(|existing| existing.source != pause.source);
+        pauses.push(pause.clone());
+        self.store(&pauses)?;
+        *self.pauses.write() = pauses;
+
+        warn!("⏸️ Ingest from {} paused{}", pause.scope(),
+              pause.until.map(|until| format!(" until {}", until.to_rfc3339())).unwrap_or_default());
+        Ok(pause)
+    }
+
+    /// Lift the pause on `source` (the global pause when None); returns whether one was lifted
+    pub fn resume(&self, source: Option<&str>) -> Result<bool, BufferError> {
+        let mut pauses = self.load()?;
+        let before = pauses.len();
+        pauses.retain(|pause| pause.source.as_deref() != source);
+        let resumed = pauses.len() != before;
+        if resumed {
+            self.store(&pauses)?;
+            info!("▶️ Ingest from {} resumed", source.unwrap_or("all sources"));
+        }
+        *self.pauses.write() = pauses;
+        Ok(resumed)
+    }
+
+    /// Lift every pause; returns how many were lifted
+    pub fn resume_all(&self) -> Result<usize, BufferError> {
+        let lifted = self.load()?.len();
+        self.store(&[])?;
+        self.pauses.write().clear();
+        if lifted > 0 {
+            info!("▶️ Ingest resumed for all sources ({} pauses lifted)", lifted);
+        }
+        Ok(lifted)
+    }
+
+    /// Reload pauses from the buffer database and forget expired ones
+    pub fn refresh(&self) -> Result<(), BufferError> {
+        let stored = self.load()?;
+        let now = Utc::now();
+        let (active, expired): (Vec<_>, Vec<_>) = stored.into_iter().partition(|pause| pause.is_active(now));
+        for pause in &expired {
+            info!("▶️ Ingest pause on {} expired", pause.scope());
+        }
+        if !expired.is_empty() {
+            self.store(&active)?;
+        }
+        *self.pauses.write() = active;
+        Ok(())
+    }
+
+    pub fn status(&self) -> IngestPauseStatus {
+        let now = Utc::now();
+        IngestPauseStatus {
+            pauses: self.pauses.read().iter().filter(|pause| pause.is_active(now)).cloned().collect(),
+            discarded_events: self.discarded.read().clone(),
+        }
+    }
+
+    #[cfg(feature = "persistent-storage")]
+    fn connection(&self) -> Result<Option<Connection>, BufferError> {
+        let Some(path) = &self.database_path else { return Ok(None) };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| BufferError::PersistenceError {
+                operation: "create_directory".to_string(),
+                database_path: parent.display().to_string(),
+                recoverable: true,
+                source: Box::new(e),
+            })?;
+        }
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS buffer_metadata (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+            )",
+            [],
+        )?;
+        Ok(Some(conn))
+    }
+
+    #[cfg(feature = "persistent-storage")]
+    fn load(&self) -> Result<Vec<IngestPause>, BufferError> {
+        let Some(conn) = self.connection()? else { return Ok(self.pauses.read().clone()) };
+        let stored: Option<String> = conn
+            .query_row("SELECT value FROM buffer_metadata WHERE key = ?1", [METADATA_KEY], |row| row.get(0))
+            .optional()?;
+        match stored {
+            Some(json) => serde_json::from_str(&json).map_err(|e| BufferError::SerializationError {
+                data_type: "ingest_pauses".to_string(),
+                operation: "deserialize".to_string(),
+                size_bytes: Some(json.len()),
+                source: Box::new(e),
+            }),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    #[cfg(feature = "persistent-storage")]
+    fn store(&self, pauses: &[IngestPause]) -> Result<(), BufferError> {
+        let Some(conn) = self.connection()? else { return Ok(()) };
+        let json = serde_json::to_string(pauses).map_err(|e| BufferError::SerializationError {
+            data_type: "ingest_pauses".to_string(),
+            operation: "serialize".to_string(),
+            size_bytes: None,
+            source: Box::new(e),
+        })?;
+        conn.execute(
+            "INSERT OR REPLACE INTO buffer_metadata (key, value, updated_at) VALUES (?1, ?2, strftime('%s', 'now'))",
+            [METADATA_KEY, json.as_str()],
+        )?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "persistent-storage"))]
+    fn load(&self) -> Result<Vec<IngestPause>, BufferError> {
+        Ok(self.pauses.read().clone())
+    }
+
+    #[cfg(not(feature = "persistent-storage"))]
+    fn store(&self, _pauses: &[IngestPause]) -> Result<(), BufferError> {
+        Ok(())
+    }
+}
+
+/// Parse a pause deadline: RFC 3339, or a duration from now such as `30m`, `2h` or `1d`
+pub fn parse_pause_until(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let split = value.len().checked_sub(1)?;
+    let amount: i64 = value[..split].parse().ok().filter(|amount| *amount > 0)?;
+    let duration = match &value[split..] {
+        "s" => chrono::Duration::seconds(amount),
+        "m" => chrono::Duration::minutes(amount),
+        "h" => chrono::Duration::hours(amount),
+        "d" => chrono::Duration::days(amount),
+        _ => return None,
+    };
+    Some(Utc::now() + duration)
+}
+
+#[cfg(all(test, feature = "persistent-storage"))]
+mod tests {
+    use super::*;
+
+    fn buffer_config(dir: &Path) -> BufferConfig {
+        let mut config = crate::config::AgentConfig::default().buffer;
+        config.persistent = true;
+        config.persistence_path = dir.display().to_string();
+        config
+    }
+
+    #[test]
+    fn test_pauses_survive_reopen_and_scope_sources() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let pauses = IngestPauses::open(&buffer_config(dir.path())).unwrap();
+        assert!(!pauses.is_paused("syslog"));
+
+        pauses.pause(Some("syslog"), None, Some("noisy upgrade".to_string())).unwrap();
+        assert!(pauses.is_paused("syslog"));
+        assert!(!pauses.is_paused("file_monitor"));
+
+        // A second process (the CLI) sees and changes the same state
+        let reopened = IngestPauses::open(&buffer_config(dir.path())).unwrap();
+        assert!(reopened.is_paused("syslog"));
+        reopened.pause(None, parse_pause_until("1h"), None).unwrap();
+        pauses.refresh().unwrap();
+        assert!(pauses.is_paused("file_monitor"));
+        assert_eq!(pauses.status().pauses.len(), 2);
+
+        assert!(pauses.resume(None).unwrap());
+        assert!(!pauses.resume(Some("windows_event")).unwrap());
+        assert!(!pauses.is_paused("file_monitor"));
+        assert_eq!(pauses.resume_all().unwrap(), 1);
+        assert!(!IngestPauses::open(&buffer_config(dir.path())).unwrap().is_paused("syslog"));
+    }
+
+    #[test]
+    fn test_expired_pauses_are_dropped_on_refresh() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let pauses = IngestPauses::open(&buffer_config(dir.path())).unwrap();
+        pauses.pause(Some("syslog"), Some(Utc::now() - chrono::Duration::seconds(1)), None).unwrap();
+        assert!(!pauses.is_paused("syslog"));
+
+        pauses.refresh().unwrap();
+        assert!(pauses.status().pauses.is_empty());
+        assert!(pauses.load().unwrap().is_empty());
+
+        assert!(parse_pause_until("30m").unwrap() > Utc::now());
+        assert!(parse_pause_until("2024-01-01T00:00:00Z").is_some());
+        assert!(parse_pause_until("0m").is_none());
+        assert!(parse_pause_until("soon").is_none());
+    }
+}
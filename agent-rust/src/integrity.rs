@@ -0,0 +1,182 @@
+// Batch integrity digests and signatures for chain of custody
+// Events are stamped with `event.hash` (SHA-256 of the raw record) before they enter the buffer, and every native
+// batch is posted with a SHA-256 digest of its serialized body and an Ed25519 signature over that digest. The
+// backend can then show a batch arrived as the agent produced it and that a record's raw data still matches the
+// hash taken at collection; with buffer encryption both are sealed at rest, so an edit in the on-disk buffer fails
+// to decrypt instead of passing as a consistent record. The signing key lives in the security module's credential store.
+
+use crate::errors::{ConfigError, Result};
+use crate::parsers::ParsedEvent;
+use crate::security::SecureCredentialManager;
+use base64::{Engine as _, engine::general_purpose};
+use ring::digest;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// `sha-256=<base64>` digest of the uncompressed batch body
+pub const DIGEST_HEADER: &str = "X-SecureWatch-Batch-Digest";
+/// Base64 Ed25519 signature over `SIGNATURE_CONTEXT` followed by the raw digest
+pub const SIGNATURE_HEADER: &str = "X-SecureWatch-Batch-Signature";
+/// Identifies the public key that verifies the signature
+pub const KEY_ID_HEADER: &str = "X-SecureWatch-Signing-Key-Id";
+/// ECS field holding the per-event digest
+pub const EVENT_HASH_FIELD: &str = "event.hash";
+
+/// Domain separation, so a batch signature can't be replayed as a signature over anything else
+const SIGNATURE_CONTEXT: &[u8] = b"securewatch-batch-v1\n";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntegrityConfig {
+    pub enabled: bool,
+    /// Stamp `event.hash` on events before they are buffered
+    pub event_hashes: bool,
+    /// Sign batch digests; without it batches only carry the digest
+    pub sign: bool,
+    /// Credential holding the Ed25519 key; generated on first start and rotated on the credential schedule
+    pub signing_key_id: String,
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            event_hashes: true,
+            sign: true,
+            signing_key_id: "batch-signing-key".to_string(),
+        }
+    }
+}
+
+impl IntegrityConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.enabled && self.sign && self.signing_key_id.trim().is_empty() {
+            errors.push("signing_key_id cannot be empty when signing is enabled".to_string());
+        }
+        errors
+    }
+}
+
+/// Record `event.hash` from the raw record, replacing any value the collected data itself carried
+pub fn stamp_event_hash(event: &mut ParsedEvent) {
+    let hash = hex(digest::digest(&digest::SHA256, event.raw_data.as_bytes()).as_ref());
+    event.fields.insert(EVENT_HASH_FIELD.to_string(), Value::String(hash));
+}
+
+/// Keep the digest an upstream agent took where the event was collected; only for events that reached this agent
+/// over an authenticated aggregator connection
+pub fn stamp_forwarded_event_hash(event: &mut ParsedEvent) {
+    if !event.fields.contains_key(EVENT_HASH_FIELD) {
+        stamp_event_hash(event);
+    }
+}
+
+/// Digest and signature headers for outgoing batches
+pub struct BatchIntegrity {
+    key_pair: Option<Ed25519KeyPair>,
+    key_id: Option<String>,
+}
+
+impl BatchIntegrity {
+    /// Load the signing key from the credential store when signing is enabled
+    pub async fn load(config: &IntegrityConfig, security: Option<&SecureCredentialManager>) -> Result<Self> {
+        if !config.sign {
+            return Ok(Self::new(None));
+        }
+        let security = security
+            .ok_or_else(|| ConfigError::Validation("Batch signing requires the credential store".to_string()))?;
+        Ok(Self::new(Some(security.signing_key_pair(&config.signing_key_id).await?)))
+    }
+
+    pub fn new(key_pair: Option<Ed25519KeyPair>) -> Self {
+        // First 8 bytes of the public key's SHA-256, enough to pick the right key after a rotation
+        let key_id = key_pair.as_ref()
+            .map(|key_pair| hex(&digest::digest(&digest::SHA256, key_pair.public_key().as_ref()).as_ref()[..8]));
+        Self { key_pair, key_id }
+    }
+
+    pub fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+
+    /// Base64 raw Ed25519 public key the backend verifies signatures with
+    pub fn public_key_base64(&self) -> Option<String> {
+        self.key_pair.as_ref().map(|key_pair| general_purpose::STANDARD.encode(key_pair.public_key().as_ref()))
+    }
+
+    /// Headers for a batch body, computed before compression so a re-encoding proxy doesn't invalidate them
+    pub fn headers(&self, body: &[u8]) -> Vec<(&'static str, String)> {
+        let body_digest = digest::digest(&digest::SHA256, body);
+        let mut headers = vec![(DIGEST_HEADER, format!("sha-256={}", general_purpose::STANDARD.encode(body_digest.as_ref())))];
+        if let (Some(key_pair), Some(key_id)) = (&self.key_pair, &self.key_id) {
+            let mut message = SIGNATURE_CONTEXT.to_vec();
+            message.extend_from_slice(body_digest.as_ref());
+            headers.push((SIGNATURE_HEADER, general_purpose::STANDARD.encode(key_pair.sign(&message).as_ref())));
+            headers.push((KEY_ID_HEADER, key_id.clone()));
+        }
+        headers
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{UnparsedPublicKey, ED25519};
+
+    #[test]
+    fn test_batch_headers_verify_with_public_key() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let integrity = BatchIntegrity::new(Some(Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()));
+        let body = br#"{"events":[],"agent_id":"agent-1"}"#;
+        let headers = integrity.headers(body);
+        let header = |name: &str| headers.iter().find(|(n, _)| *n == name).map(|(_, v)| v.clone()).unwrap();
+
+        let body_digest = digest::digest(&digest::SHA256, body);
+        assert_eq!(header(DIGEST_HEADER), format!("sha-256={}", general_purpose::STANDARD.encode(body_digest.as_ref())));
+        assert_eq!(header(KEY_ID_HEADER).len(), 16);
+
+        let public_key = general_purpose::STANDARD.decode(integrity.public_key_base64().unwrap()).unwrap();
+        let signature = general_purpose::STANDARD.decode(header(SIGNATURE_HEADER)).unwrap();
+        let mut message = SIGNATURE_CONTEXT.to_vec();
+        message.extend_from_slice(body_digest.as_ref());
+        assert!(UnparsedPublicKey::new(&ED25519, &public_key).verify(&message, &signature).is_ok());
+        // A different body doesn't verify against the same signature
+        let tampered = digest::digest(&digest::SHA256, b"{}");
+        let mut message = SIGNATURE_CONTEXT.to_vec();
+        message.extend_from_slice(tampered.as_ref());
+        assert!(UnparsedPublicKey::new(&ED25519, &public_key).verify(&message, &signature).is_err());
+
+        assert_eq!(BatchIntegrity::new(None).headers(body).len(), 1);
+    }
+
+    #[test]
+    fn test_event_hash_is_recomputed_unless_forwarded() {
+        let mut event = ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "syslog".to_string(),
+            level: None,
+            message: "login".to_string(),
+            fields: Default::default(),
+            raw_data: "abc".into(),
+            parser_name: "syslog".to_string(),
+        };
+        // A collected record can't supply its own digest
+        event.fields.insert(EVENT_HASH_FIELD.to_string(), Value::String("spoofed".to_string()));
+        stamp_event_hash(&mut event);
+        assert_eq!(event.fields[EVENT_HASH_FIELD], "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        // Forwarded events keep the digest taken where they were collected
+        event.raw_data = "changed".into();
+        stamp_forwarded_event_hash(&mut event);
+        assert_eq!(event.fields[EVENT_HASH_FIELD], "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        stamp_event_hash(&mut event);
+        assert_ne!(event.fields[EVENT_HASH_FIELD], "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+}
@@ -0,0 +1,914 @@
+// KQL queries over the local event buffer
+// Parses the KQL subset used for on-host triage (where, project, extend, summarize, sort, top, take, distinct,
+// count) and lowers it to SQLite, so an investigation can continue when the SIEM is unreachable. Buffered rows are
+// decoded into an in-memory `Events` table first, because message, fields and raw_data may be sealed or compressed
+// at rest; unknown column names become json_extract() over the event's fields. Literals are always bound parameters.
+
+use crate::config::BufferConfig;
+use crate::errors::BufferError;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[cfg(feature = "persistent-storage")]
+use crate::buffer::EventBuffer;
+#[cfg(feature = "persistent-storage")]
+use crate::buffer_encryption::BufferCipher;
+#[cfg(feature = "persistent-storage")]
+use rusqlite::Connection;
+
+/// The buffer's single table
+pub const TABLE: &str = "Events";
+
+/// Columns of `Events`; any other name refers to a key of `fields`
+const EVENT_COLUMNS: [&str; 7] = ["timestamp", "source", "level", "message", "fields", "raw_data", "parser_name"];
+
+const AGGREGATES: [&str; 7] = ["count", "countif", "dcount", "min", "max", "sum", "avg"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub table: String,
+    pub operators: Vec<Operator>,
+}
+
+/// A column produced by project, extend, distinct or summarize: `name = expr`, or a bare column reference
+pub type NamedExpr = (Option<String>, Expr);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operator {
+    Where(Expr),
+    Project(Vec<NamedExpr>),
+    Extend(Vec<NamedExpr>),
+    Take(u64),
+    /// Keys with `true` for descending, KQL's default
+    Sort(Vec<(Expr, bool)>),
+    Top(u64, Expr, bool),
+    Summarize { aggregates: Vec<NamedExpr>, by: Vec<NamedExpr> },
+    Distinct(Vec<NamedExpr>),
+    Count,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Column(String),
+    Literal(Value),
+    /// Seconds; only valid inside ago() and bin()
+    Timespan(f64),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare { op: String, left: Box<Expr>, right: Box<Expr> },
+    In { negated: bool, left: Box<Expr>, values: Vec<Expr> },
+    Call { name: String, args: Vec<Expr> },
+}
+
+/// A lowered query with `?N` parameters
+#[derive(Debug, Clone, Serialize)]
+pub struct SqlQuery {
+    pub sql: String,
+    pub params: Vec<Value>,
+    pub columns: Vec<String>,
+}
+
+/// Parse a KQL query
+pub fn parse(query: &str) -> Result<Query, String> {
+    let mut parser = Parser { tokens: tokenize(query)?, pos: 0 };
+    let query = parser.query()?;
+    match parser.peek() {
+        Some(token) => Err(format!("Unexpected {} after the query", token)),
+        None => Ok(query),
+    }
+}
+
+/// Parse and lower a KQL query; `now` anchors ago() and now()
+pub fn translate(query: &str, now: DateTime<Utc>) -> Result<SqlQuery, String> {
+    lower(&parse(query)?, now)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(f64),
+    Timespan(f64),
+    Symbol(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Str(value) => write!(f, "string \"{}\"", value),
+            Token::Number(n) => write!(f, "number {}", n),
+            Token::Timespan(seconds) => write!(f, "timespan {}s", seconds),
+            Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 16] = ["==", "!=", "=~", "!~", "<=", ">=", "|", ",", "(", ")", "[", "]", "<", ">", "=", "-"];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '"' || c == '\'' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("Unterminated string literal".to_string()),
+                    Some(&ch) if ch == c => break,
+                    Some('\\') => {
+                        let escaped = chars.get(i + 1).ok_or("Unterminated string literal")?;
+                        value.push(match escaped {
+                            'n' => '\n',
+                            't' => '\t',
+                            other => *other,
+                        });
+                        i += 1;
+                    }
+                    Some(&ch) => value.push(ch),
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token::Str(value));
+        } else if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number: f64 = text.parse().map_err(|_| format!("Invalid number '{}'", text))?;
+            let unit_start = i;
+            while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                i += 1;
+            }
+            let unit: String = chars[unit_start..i].iter().collect();
+            tokens.push(match unit.as_str() {
+                "" => Token::Number(number),
+                "d" => Token::Timespan(number * 86_400.0),
+                "h" => Token::Timespan(number * 3_600.0),
+                "m" => Token::Timespan(number * 60.0),
+                "s" => Token::Timespan(number),
+                "ms" => Token::Timespan(number / 1_000.0),
+                other => return Err(format!("Unknown timespan unit '{}' in '{}{}'", other, text, other)),
+            });
+        } else if c.is_alphabetic() || c == '_' || (c == '!' && chars.get(i + 1).is_some_and(|ch| ch.is_alphabetic())) {
+            // ECS names keep their dots: `user.name`; `!contains` and friends are single operator words
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let symbol = SYMBOLS.iter()
+                .find(|symbol| rest.starts_with(**symbol))
+                .ok_or_else(|| format!("Unexpected character '{}'", c))?;
+            i += symbol.chars().count();
+            tokens.push(Token::Symbol(symbol));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), String> {
+        if self.eat_symbol(symbol) {
+            return Ok(());
+        }
+        Err(match self.peek() {
+            Some(token) => format!("Expected '{}' but found {}", symbol, token),
+            None => format!("Expected '{}' at the end of the query", symbol),
+        })
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(name)) if name.eq_ignore_ascii_case(keyword)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.eat_keyword(keyword) {
+            return Ok(());
+        }
+        Err(format!("Expected '{}'", keyword))
+    }
+
+    /// A name, or `['name']` for names that aren't plain identifiers
+    fn ident(&mut self, what: &str) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Ident(name)) => Ok(name),
+            Some(Token::Symbol("[")) => match self.next() {
+                Some(Token::Str(name)) => {
+                    self.expect_symbol("]")?;
+                    Ok(name)
+                }
+                _ => Err(format!("Expected a quoted {} inside [ ]", what)),
+            },
+            Some(token) => Err(format!("Expected {} but found {}", what, token)),
+            None => Err(format!("Expected {} at the end of the query", what)),
+        }
+    }
+
+    fn count(&mut self) -> Result<u64, String> {
+        match self.next() {
+            Some(Token::Number(n)) if n >= 0.0 && n.fract() == 0.0 => Ok(n as u64),
+            _ => Err("Expected a row count".to_string()),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query, String> {
+        let table = self.ident("a table name")?;
+        let mut operators = Vec::new();
+        while self.eat_symbol("|") {
+            operators.push(self.operator()?);
+        }
+        Ok(Query { table, operators })
+    }
+
+    fn operator(&mut self) -> Result<Operator, String> {
+        let name = self.ident("an operator")?.to_ascii_lowercase();
+        Ok(match name.as_str() {
+            "where" | "filter" => Operator::Where(self.expr()?),
+            "project" => Operator::Project(self.named_list()?),
+            "extend" => Operator::Extend(self.named_list()?),
+            "take" | "limit" => Operator::Take(self.count()?),
+            "sort" | "order" => {
+                self.expect_keyword("by")?;
+                let mut keys = vec![self.sort_key()?];
+                while self.eat_symbol(",") {
+                    keys.push(self.sort_key()?);
+                }
+                Operator::Sort(keys)
+            }
+            "top" => {
+                let n = self.count()?;
+                self.expect_keyword("by")?;
+                let (key, descending) = self.sort_key()?;
+                Operator::Top(n, key, descending)
+            }
+            "summarize" => {
+                let aggregates = if matches!(self.peek(), Some(Token::Ident(name)) if name.eq_ignore_ascii_case("by")) {
+                    Vec::new()
+                } else {
+                    self.named_list()?
+                };
+                let by = if self.eat_keyword("by") { self.named_list()? } else { Vec::new() };
+                Operator::Summarize { aggregates, by }
+            }
+            "distinct" => Operator::Distinct(self.named_list()?),
+            "count" => Operator::Count,
+            other => return Err(format!("Unsupported operator '{}'", other)),
+        })
+    }
+
+    fn sort_key(&mut self) -> Result<(Expr, bool), String> {
+        let key = self.expr()?;
+        let descending = if self.eat_keyword("asc") {
+            false
+        } else {
+            self.eat_keyword("desc");
+            true
+        };
+        Ok((key, descending))
+    }
+
+    fn named_list(&mut self) -> Result<Vec<NamedExpr>, String> {
+        let mut items = Vec::new();
+        loop {
+            let alias = match (self.peek(), self.peek_at(1)) {
+                (Some(Token::Ident(_)), Some(Token::Symbol("="))) => {
+                    let alias = self.ident("a column name")?;
+                    self.pos += 1;
+                    Some(alias)
+                }
+                _ => None,
+            };
+            items.push((alias, self.expr()?));
+            if !self.eat_symbol(",") {
+                return Ok(items);
+            }
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+        while self.eat_keyword("or") {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.comparison()?;
+        while self.eat_keyword("and") {
+            left = Expr::And(Box::new(left), Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.primary()?;
+        let op = match self.peek() {
+            Some(Token::Symbol(symbol)) if matches!(*symbol, "==" | "!=" | "=~" | "!~" | "<" | "<=" | ">" | ">=") => symbol.to_string(),
+            Some(Token::Ident(word)) if is_word_operator(word) => word.to_ascii_lowercase(),
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        if op == "in" || op == "!in" {
+            self.expect_symbol("(")?;
+            let mut values = vec![self.primary()?];
+            while self.eat_symbol(",") {
+                values.push(self.primary()?);
+            }
+            self.expect_symbol(")")?;
+            return Ok(Expr::In { negated: op == "!in", left: Box::new(left), values });
+        }
+        Ok(Expr::Compare { op, left: Box::new(left), right: Box::new(self.primary()?) })
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.peek().cloned() {
+            Some(Token::Str(value)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(Value::String(value)))
+            }
+            Some(Token::Number(n)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(number(n)))
+            }
+            Some(Token::Timespan(seconds)) => {
+                self.pos += 1;
+                Ok(Expr::Timespan(seconds))
+            }
+            Some(Token::Symbol("(")) => {
+                self.pos += 1;
+                let inner = self.expr()?;
+                self.expect_symbol(")")?;
+                Ok(inner)
+            }
+            Some(Token::Symbol("[")) => Ok(Expr::Column(self.ident("a column name")?)),
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                match name.to_ascii_lowercase().as_str() {
+                    "true" => return Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => return Ok(Expr::Literal(Value::Bool(false))),
+                    "fields" if self.eat_symbol("[") => {
+                        // fields['user.name'] is the same column as user.name
+                        let Some(Token::Str(key)) = self.next() else {
+                            return Err("Expected a quoted key inside fields[ ]".to_string());
+                        };
+                        self.expect_symbol("]")?;
+                        return Ok(Expr::Column(key));
+                    }
+                    _ => {}
+                }
+                if !self.eat_symbol("(") {
+                    return Ok(Expr::Column(name));
+                }
+                let mut args = Vec::new();
+                if !self.eat_symbol(")") {
+                    args.push(self.expr()?);
+                    while self.eat_symbol(",") {
+                        args.push(self.expr()?);
+                    }
+                    self.expect_symbol(")")?;
+                }
+                if name.eq_ignore_ascii_case("not") {
+                    return match <[Expr; 1]>::try_from(args) {
+                        Ok([inner]) => Ok(Expr::Not(Box::new(inner))),
+                        Err(_) => Err("not() takes one argument".to_string()),
+                    };
+                }
+                Ok(Expr::Call { name, args })
+            }
+            Some(token) => Err(format!("Unexpected {}", token)),
+            None => Err("Unexpected end of the query".to_string()),
+        }
+    }
+}
+
+fn is_word_operator(word: &str) -> bool {
+    let word = word.to_ascii_lowercase();
+    let word = word.strip_prefix('!').unwrap_or(&word);
+    matches!(word, "contains" | "contains_cs" | "startswith" | "endswith" | "has" | "in")
+}
+
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Value::from(n as i64)
+    } else {
+        Value::from(n)
+    }
+}
+
+/// Timestamps are stored and compared as fixed-width UTC RFC 3339 text
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))
+        .map(|time| time.and_utc())
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+struct Lowerer {
+    params: Vec<Value>,
+    now: DateTime<Utc>,
+    /// Columns of the current result; names outside it resolve into `fields` while that column is present
+    columns: Vec<String>,
+}
+
+/// Lower a parsed query to SQLite over the `events` table
+pub fn lower(query: &Query, now: DateTime<Utc>) -> Result<SqlQuery, String> {
+    if !query.table.eq_ignore_ascii_case(TABLE) {
+        return Err(format!("Unknown table '{}': the buffer holds a single table, {}", query.table, TABLE));
+    }
+    let mut lowerer = Lowerer {
+        params: Vec::new(),
+        now,
+        columns: EVENT_COLUMNS.iter().map(|c| c.to_string()).collect(),
+    };
+    let mut sql = "SELECT * FROM events".to_string();
+    for operator in &query.operators {
+        sql = lowerer.operator(operator, sql)?;
+    }
+    Ok(SqlQuery { sql, params: lowerer.params, columns: lowerer.columns })
+}
+
+impl Lowerer {
+    fn param(&mut self, value: Value) -> String {
+        self.params.push(value);
+        format!("?{}", self.params.len())
+    }
+
+    fn column(&mut self, name: &str) -> Result<String, String> {
+        if self.columns.iter().any(|c| c == name) {
+            return Ok(quote_ident(name));
+        }
+        if name == "TimeGenerated" && self.columns.iter().any(|c| c == "timestamp") {
+            return Ok(quote_ident("timestamp"));
+        }
+        if self.columns.iter().any(|c| c == "fields") {
+            let path = self.param(Value::String(format!("$.\"{}\"", name.replace('"', "\\\""))));
+            return Ok(format!("json_extract(\"fields\", {})", path));
+        }
+        Err(format!("Unknown column '{}' (available: {})", name, self.columns.join(", ")))
+    }
+
+    fn operator(&mut self, operator: &Operator, from: String) -> Result<String, String> {
+        Ok(match operator {
+            Operator::Where(condition) => format!("SELECT * FROM ({}) WHERE {}", from, self.expr(condition)?),
+            Operator::Project(items) => {
+                let (select, names) = self.named(items, "project")?;
+                self.columns = names;
+                format!("SELECT {} FROM ({})", select.join(", "), from)
+            }
+            Operator::Extend(items) => {
+                // A name that already exists is replaced
+                let (select, names) = self.named(items, "extend")?;
+                let kept: Vec<String> = self.columns.iter().filter(|c| !names.contains(c)).cloned().collect();
+                let mut columns: Vec<String> = kept.iter().map(|c| quote_ident(c)).collect();
+                columns.extend(select);
+                self.columns = kept.into_iter().chain(names).collect();
+                format!("SELECT {} FROM ({})", columns.join(", "), from)
+            }
+            Operator::Take(n) => format!("SELECT * FROM ({}) LIMIT {}", from, n),
+            Operator::Sort(keys) => {
+                let mut order = Vec::new();
+                for (key, descending) in keys {
+                    order.push(format!("{} {}", self.expr(key)?, if *descending { "DESC" } else { "ASC" }));
+                }
+                format!("SELECT * FROM ({}) ORDER BY {}", from, order.join(", "))
+            }
+            Operator::Top(n, key, descending) => {
+                let key = self.expr(key)?;
+                format!("SELECT * FROM ({}) ORDER BY {} {} LIMIT {}", from, key, if *descending { "DESC" } else { "ASC" }, n)
+            }
+            Operator::Summarize { aggregates, by } => {
+                let mut select = Vec::new();
+                let mut names = Vec::new();
+                for (alias, expr) in by {
+                    let (sql, name) = self.group_key(expr)?;
+                    let name = alias.clone().or(name).ok_or("summarize by needs a name for computed keys, e.g. name = expr")?;
+                    select.push(format!("{} AS {}", sql, quote_ident(&name)));
+                    names.push(name);
+                }
+                for (alias, expr) in aggregates {
+                    let (sql, name) = self.aggregate(expr)?;
+                    let name = alias.clone().unwrap_or(name);
+                    select.push(format!("{} AS {}", sql, quote_ident(&name)));
+                    names.push(name);
+                }
+                if select.is_empty() {
+                    return Err("summarize needs an aggregate or a by clause".to_string());
+                }
+                self.columns = names;
+                let group_by = match by.len() {
+                    0 => String::new(),
+                    n => format!(" GROUP BY {}", (1..=n).map(|i| i.to_string()).collect::<Vec<_>>().join(", ")),
+                };
+                format!("SELECT {} FROM ({}){}", select.join(", "), from, group_by)
+            }
+            Operator::Distinct(items) => {
+                let (select, names) = self.named(items, "distinct")?;
+                self.columns = names;
+                format!("SELECT DISTINCT {} FROM ({})", select.join(", "), from)
+            }
+            Operator::Count => {
+                self.columns = vec!["Count".to_string()];
+                format!("SELECT COUNT(*) AS \"Count\" FROM ({})", from)
+            }
+        })
+    }
+
+    fn named(&mut self, items: &[NamedExpr], operator: &str) -> Result<(Vec<String>, Vec<String>), String> {
+        let mut select = Vec::new();
+        let mut names = Vec::new();
+        for (alias, expr) in items {
+            let name = match (alias, expr) {
+                (Some(alias), _) => alias.clone(),
+                (None, Expr::Column(name)) => name.clone(),
+                _ => return Err(format!("{} needs a name for computed columns, e.g. name = expr", operator)),
+            };
+            select.push(format!("{} AS {}", self.expr(expr)?, quote_ident(&name)));
+            names.push(name);
+        }
+        Ok((select, names))
+    }
+
+    /// A summarize key with its default name; `bin(timestamp, 1h)` buckets times
+    fn group_key(&mut self, expr: &Expr) -> Result<(String, Option<String>), String> {
+        match expr {
+            Expr::Call { name, args } if name.eq_ignore_ascii_case("bin") => match args.as_slice() {
+                [column, Expr::Timespan(seconds)] if *seconds >= 1.0 => {
+                    let value = self.expr(column)?;
+                    let size = *seconds as i64;
+                    let sql = format!(
+                        "strftime('%Y-%m-%dT%H:%M:%SZ', (CAST(strftime('%s', {}) AS INTEGER) / {}) * {}, 'unixepoch')",
+                        value, size, size
+                    );
+                    let name = match column {
+                        Expr::Column(name) => Some(name.clone()),
+                        _ => None,
+                    };
+                    Ok((sql, name))
+                }
+                _ => Err("bin() takes a time column and a timespan of at least 1s".to_string()),
+            },
+            Expr::Column(name) => Ok((self.expr(expr)?, Some(name.clone()))),
+            other => Ok((self.expr(other)?, None)),
+        }
+    }
+
+    fn aggregate(&mut self, expr: &Expr) -> Result<(String, String), String> {
+        let Expr::Call { name, args } = expr else {
+            return Err("summarize takes aggregates such as count(), dcount(x), min(x), max(x), sum(x), avg(x)".to_string());
+        };
+        let function = name.to_ascii_lowercase();
+        let column_name = |arg: &Expr| match arg {
+            Expr::Column(column) => format!("{}_{}", function, column),
+            _ => format!("{}_", function),
+        };
+        Ok(match (function.as_str(), args.as_slice()) {
+            ("count", []) => ("COUNT(*)".to_string(), "count_".to_string()),
+            ("countif", [condition]) => (format!("SUM(CASE WHEN {} THEN 1 ELSE 0 END)", self.expr(condition)?), "countif_".to_string()),
+            ("dcount", [arg]) => (format!("COUNT(DISTINCT {})", self.expr(arg)?), column_name(arg)),
+            ("min" | "max" | "sum" | "avg", [arg]) => (format!("{}({})", function.to_ascii_uppercase(), self.expr(arg)?), column_name(arg)),
+            _ => return Err(format!("Unsupported aggregate '{}' with {} arguments", name, args.len())),
+        })
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<String, String> {
+        Ok(match expr {
+            Expr::Column(name) => self.column(name)?,
+            Expr::Literal(value) => self.param(value.clone()),
+            Expr::Timespan(_) => return Err("Timespans are only supported inside ago() and bin()".to_string()),
+            Expr::Not(inner) => format!("NOT ({})", self.expr(inner)?),
+            Expr::And(left, right) => format!("({} AND {})", self.expr(left)?, self.expr(right)?),
+            Expr::Or(left, right) => format!("({} OR {})", self.expr(left)?, self.expr(right)?),
+            Expr::In { negated, left, values } => {
+                let left = self.expr(left)?;
+                let mut list = Vec::new();
+                for value in values {
+                    list.push(self.expr(value)?);
+                }
+                format!("{} {}IN ({})", left, if *negated { "NOT " } else { "" }, list.join(", "))
+            }
+            Expr::Compare { op, left, right } => {
+                let (left, right) = (self.expr(left)?, self.expr(right)?);
+                match op.as_str() {
+                    "==" => format!("{} = {}", left, right),
+                    "!=" => format!("{} <> {}", left, right),
+                    "=~" => format!("lower({}) = lower({})", left, right),
+                    "!~" => format!("lower({}) <> lower({})", left, right),
+                    "<" | "<=" | ">" | ">=" => format!("{} {} {}", left, op, right),
+                    word => {
+                        let (negated, word) = match word.strip_prefix('!') {
+                            Some(word) => (true, word),
+                            None => (false, word),
+                        };
+                        // Missing values compare as empty strings, as in KQL
+                        let left = format!("coalesce({}, '')", left);
+                        let test = match word {
+                            // has matches whole terms in KQL; here it is a substring match, so it can also match inside longer terms
+                            "contains" | "has" => format!("instr(lower({}), lower({})) > 0", left, right),
+                            "contains_cs" => format!("instr({}, {}) > 0", left, right),
+                            "startswith" => format!("substr(lower({}), 1, length({})) = lower({})", left, right, right),
+                            "endswith" => format!("substr(lower({}), -length({})) = lower({})", left, right, right),
+                            other => return Err(format!("Unsupported operator '{}'", other)),
+                        };
+                        if negated { format!("NOT ({})", test) } else { test }
+                    }
+                }
+            }
+            Expr::Call { name, args } => self.call(name, args)?,
+        })
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr]) -> Result<String, String> {
+        let function = name.to_ascii_lowercase();
+        Ok(match (function.as_str(), args) {
+            ("ago", [Expr::Timespan(seconds)]) => {
+                let time = self.now - chrono::Duration::milliseconds((seconds * 1_000.0) as i64);
+                self.param(Value::String(format_time(time)))
+            }
+            ("now", []) => self.param(Value::String(format_time(self.now))),
+            ("datetime", [Expr::Literal(Value::String(value))]) => {
+                let time = parse_datetime(value).ok_or_else(|| format!("Invalid datetime '{}'", value))?;
+                self.param(Value::String(format_time(time)))
+            }
+            ("isempty", [arg]) => format!("coalesce({}, '') = ''", self.expr(arg)?),
+            ("isnotempty", [arg]) => format!("coalesce({}, '') <> ''", self.expr(arg)?),
+            ("isnull", [arg]) => format!("{} IS NULL", self.expr(arg)?),
+            ("isnotnull", [arg]) => format!("{} IS NOT NULL", self.expr(arg)?),
+            ("tolower", [arg]) => format!("lower({})", self.expr(arg)?),
+            ("toupper", [arg]) => format!("upper({})", self.expr(arg)?),
+            ("strlen", [arg]) => format!("length({})", self.expr(arg)?),
+            ("bin", _) => return Err("bin() is only supported in summarize ... by".to_string()),
+            (aggregate, _) if AGGREGATES.contains(&aggregate) => return Err(format!("{}() is only supported in summarize", name)),
+            _ => return Err(format!("Unsupported function '{}' with {} arguments", name, args.len())),
+        })
+    }
+}
+
+/// Limits for one buffer query
+#[derive(Debug, Clone)]
+pub struct KqlOptions {
+    /// Rows returned; the result is marked truncated beyond this
+    pub max_rows: usize,
+    /// Most recent buffered events loaded for the query
+    pub max_scan_events: usize,
+}
+
+impl Default for KqlOptions {
+    fn default() -> Self {
+        Self { max_rows: 1_000, max_scan_events: 100_000 }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KqlResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// More rows matched than max_rows
+    pub truncated: bool,
+    /// Buffered events loaded, and those that could not be decoded
+    pub scanned: usize,
+    pub skipped: usize,
+    pub took_ms: u64,
+}
+
+#[cfg(feature = "persistent-storage")]
+const QUERY_TABLE: &str = "CREATE TABLE events (timestamp TEXT, source TEXT, level TEXT, message TEXT, fields TEXT, raw_data TEXT, parser_name TEXT)";
+
+/// Run a KQL query against the events buffered on disk; the buffer is opened read-only
+#[cfg(feature = "persistent-storage")]
+pub fn query_buffer(config: &BufferConfig, query: &str, options: &KqlOptions) -> Result<KqlResult, BufferError> {
+    let started = std::time::Instant::now();
+    let sql = translate(query, Utc::now())
+        .map_err(|reason| BufferError::InvalidSearchQuery { query: query.to_string(), reason })?;
+
+    let (_, buffer) = crate::buffer_export::open_read_only(config, "open_buffer_for_query")?;
+    let cipher = match config.encryption.enabled {
+        true => Some(BufferCipher::open(&config.encryption, &buffer)?),
+        false => None,
+    };
+    let conn = Connection::open_in_memory()?;
+    conn.execute_batch(QUERY_TABLE)?;
+    let (scanned, skipped) = load_events(&buffer, cipher.as_ref(), &conn, options.max_scan_events)?;
+
+    let (columns, rows, truncated) = run(&conn, &sql, options.max_rows)?;
+    Ok(KqlResult { columns, rows, truncated, scanned, skipped, took_ms: started.elapsed().as_millis() as u64 })
+}
+
+/// Memory-only builds have no buffer on disk to query
+#[cfg(not(feature = "persistent-storage"))]
+pub fn query_buffer(config: &BufferConfig, _query: &str, _options: &KqlOptions) -> Result<KqlResult, BufferError> {
+    Err(BufferError::PersistenceError {
+        operation: "open_buffer_for_query".to_string(),
+        database_path: config.persistence_path.clone(),
+        recoverable: false,
+        source: Box::new(std::io::Error::new(std::io::ErrorKind::Unsupported, "built without persistent-storage")),
+    })
+}
+
+/// Decode the newest buffered events into the query table; returns (scanned, skipped)
+#[cfg(feature = "persistent-storage")]
+fn load_events(buffer: &Connection, cipher: Option<&BufferCipher>, conn: &Connection, limit: usize) -> Result<(usize, usize), BufferError> {
+    let mut select = buffer.prepare(
+        "SELECT id, timestamp, source, level, message, fields, raw_data, parser_name, event_version FROM events ORDER BY id DESC LIMIT ?1",
+    )?;
+    let mut rows = select.query([limit as i64])?;
+    let (mut scanned, mut skipped) = (0, 0);
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut insert = tx.prepare("INSERT INTO events VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+        while let Some(row) = rows.next()? {
+            scanned += 1;
+            let Ok(event) = EventBuffer::decode_event(row, cipher) else {
+                skipped += 1;
+                continue;
+            };
+            insert.execute(rusqlite::params![
+                format_time(event.timestamp),
+                event.source,
+                event.level,
+                event.message,
+                serde_json::to_string(&event.fields).unwrap_or_default(),
+                &*event.raw_data,
+                event.parser_name,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok((scanned, skipped))
+}
+
+#[cfg(feature = "persistent-storage")]
+fn run(conn: &Connection, query: &SqlQuery, max_rows: usize) -> rusqlite::Result<(Vec<String>, Vec<Vec<Value>>, bool)> {
+    use rusqlite::types::{Value as SqlValue, ValueRef};
+
+    let mut stmt = conn.prepare(&format!("SELECT * FROM ({}) LIMIT {}", query.sql, max_rows + 1))?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let params = query.params.iter().map(|value| match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => n.as_i64().map(SqlValue::Integer).unwrap_or_else(|| SqlValue::Real(n.as_f64().unwrap_or_default())),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    });
+    let mut rows = stmt.query(rusqlite::params_from_iter(params))?;
+    let mut results = Vec::new();
+    while let Some(row) = rows.next()? {
+        if results.len() == max_rows {
+            return Ok((columns, results, true));
+        }
+        let mut values = Vec::with_capacity(columns.len());
+        for (index, column) in columns.iter().enumerate() {
+            values.push(match row.get_ref(index)? {
+                ValueRef::Null => Value::Null,
+                ValueRef::Integer(n) => Value::from(n),
+                ValueRef::Real(f) => Value::from(f),
+                ValueRef::Text(text) => {
+                    let text = String::from_utf8_lossy(text);
+                    // The fields column comes back as the object it was stored from
+                    match column.as_str() {
+                        "fields" => serde_json::from_str(&text).unwrap_or(Value::String(text.into_owned())),
+                        _ => Value::String(text.into_owned()),
+                    }
+                }
+                ValueRef::Blob(bytes) => Value::String(String::from_utf8_lossy(bytes).into_owned()),
+            });
+        }
+        results.push(values);
+    }
+    Ok((columns, results, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-10-16T12:00:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_translate_pipeline() {
+        let sql = translate(
+            "Events | where source == 'syslog' and user.name !contains \"svc\" and timestamp > ago(1h) | summarize failures = count() by user.name | top 5 by failures",
+            now(),
+        ).unwrap();
+        assert_eq!(
+            sql.sql,
+            "SELECT * FROM (SELECT json_extract(\"fields\", ?5) AS \"user.name\", COUNT(*) AS \"failures\" FROM (SELECT * FROM (SELECT * FROM events) \
+             WHERE ((\"source\" = ?1 AND NOT (instr(lower(coalesce(json_extract(\"fields\", ?2), '')), lower(?3)) > 0)) AND \"timestamp\" > ?4)) GROUP BY 1) \
+             ORDER BY \"failures\" DESC LIMIT 5"
+        );
+        assert_eq!(sql.params, vec![
+            Value::from("syslog"),
+            Value::from("$.\"user.name\""),
+            Value::from("svc"),
+            Value::from("2026-10-16T11:00:00.000000Z"),
+            Value::from("$.\"user.name\""),
+        ]);
+        assert_eq!(sql.columns, vec!["user.name", "failures"]);
+
+        assert!(translate("SecurityEvent | take 10", now()).unwrap_err().contains("single table"));
+        assert!(translate("Events | project source | where user.name == 'x'", now()).unwrap_err().contains("Unknown column 'user.name'"));
+        assert!(translate("Events | where count() > 1", now()).unwrap_err().contains("only supported in summarize"));
+    }
+
+    #[cfg(feature = "persistent-storage")]
+    #[test]
+    fn test_query_runs_against_decoded_events() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(QUERY_TABLE).unwrap();
+        for (minute, user, port) in [(10, "alice", 22), (20, "bob", 22), (30, "alice", 443), (40, "alice", 22)] {
+            conn.execute(
+                "INSERT INTO events VALUES (?1, 'syslog', 'info', 'login', ?2, 'raw', 'syslog')",
+                rusqlite::params![
+                    format!("2026-10-16T11:{}:00.000000Z", minute),
+                    serde_json::json!({ "user.name": user, "destination.port": port }).to_string(),
+                ],
+            ).unwrap();
+        }
+
+        let query = translate(
+            "Events | where ['destination.port'] == 22 | summarize logins = count(), last = max(timestamp) by user.name | sort by logins",
+            now(),
+        ).unwrap();
+        let (columns, rows, truncated) = run(&conn, &query, 10).unwrap();
+        assert_eq!(columns, vec!["user.name", "logins", "last"]);
+        assert_eq!(rows, vec![
+            vec![Value::from("alice"), Value::from(2), Value::from("2026-10-16T11:40:00.000000Z")],
+            vec![Value::from("bob"), Value::from(1), Value::from("2026-10-16T11:20:00.000000Z")],
+        ]);
+        assert!(!truncated);
+
+        let query = translate("Events | summarize count() by bin(timestamp, 30m) | sort by timestamp asc", now()).unwrap();
+        let (_, rows, _) = run(&conn, &query, 10).unwrap();
+        assert_eq!(rows, vec![
+            vec![Value::from("2026-10-16T11:00:00Z"), Value::from(2)],
+            vec![Value::from("2026-10-16T11:30:00Z"), Value::from(2)],
+        ]);
+
+        let (_, rows, truncated) = run(&conn, &translate("Events | project fields", now()).unwrap(), 1).unwrap();
+        assert_eq!(rows[0][0]["user.name"], "alice");
+        assert!(truncated);
+    }
+}
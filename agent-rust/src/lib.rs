@@ -1,25 +1,83 @@
 // SecureWatch Agent Library - Enterprise async implementation using Tokio patterns
 
+// The configuration JSON schema is a single deeply nested json! literal
+#![recursion_limit = "256"]
+
 pub mod config;
+pub mod config_diff;
+pub mod hot_reload;
+pub mod capabilities;
 pub mod errors;
 pub mod agent;
+pub mod agent_status;
 pub mod collectors;
 pub mod transport;
+pub mod compression;
+pub mod client_identity;
+pub mod cert_expiry;
+pub mod enrollment;
+pub mod otlp;
+pub mod payload_format;
+pub mod destinations;
+pub mod syslog_output;
+pub mod output_template;
+pub mod splunk_hec;
+pub mod elasticsearch;
 pub mod circuit_breaker;
 #[cfg(feature = "persistent-storage")]
 pub mod buffer;
 #[cfg(not(feature = "persistent-storage"))]
 #[path = "buffer_minimal.rs"]
 pub mod buffer;
+#[cfg(feature = "persistent-storage")]
+pub mod buffer_compression;
+pub mod buffer_encryption;
+pub mod buffer_export;
+pub mod burst_overflow;
+pub mod dedup;
+pub mod error_events;
+pub mod ingest_pause;
+pub mod load_shedding;
 pub mod parsers;
+pub mod ecs;
+pub mod alert_rules;
+pub mod enrichment;
+pub mod cloud_metadata;
+pub mod threat_intel;
+pub mod sigma;
 pub mod utils;
 pub mod retry;
+pub mod bandwidth;
+pub mod proxy;
+pub mod upload_schedule;
 pub mod resource_monitor;
+pub mod disk_quota;
+pub mod component_usage;
+pub mod pipeline_metrics;
+pub mod diagnostics;
 pub mod throttle;
 pub mod resource_management;
 pub mod emergency_shutdown;
+pub mod shutdown_drain;
+pub mod service;
+pub mod chaos;
 pub mod security;
 pub mod validation;
+pub mod process_lineage;
+pub mod field_filter;
+pub mod relay;
+pub mod aggregator;
+pub mod integrity;
+pub mod delivery;
+pub mod admin_audit;
+pub mod event_index;
+pub mod kql;
+pub mod live_tail;
+pub mod control_socket;
+pub mod self_telemetry;
+pub mod simulator;
+pub mod bench_profile;
+pub mod management_tls;
 #[cfg(feature = "grpc-management")]
 pub mod management;
 #[cfg(not(feature = "grpc-management"))]
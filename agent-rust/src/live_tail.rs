@@ -0,0 +1,419 @@
+// Live tail of parsed events for on-host troubleshooting
+// The parsing engine offers every parsed event to a broadcast channel that watchers subscribe to with a filter.
+// Nothing is copied while nobody is watching, and a watcher that falls behind skips events instead of slowing the
+// pipeline down. Watchers connect to a local Unix socket readable only by the agent's user (`securewatch-agent
+// tail`): they send one JSON line with their filter and get one JSON line per matching event back.
+
+use crate::parsers::ParsedEvent;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+/// Events buffered per watcher before it starts skipping
+const TAIL_CHANNEL_CAPACITY: usize = 1024;
+
+/// Longest filter line a watcher may send
+const MAX_REQUEST_BYTES: u64 = 4096;
+
+/// How long a watcher has to send its filter after connecting
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Local live tail socket (`live_tail`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LiveTailConfig {
+    pub enabled: bool,
+    /// Unix socket watchers connect to; created owner-only
+    pub socket_path: String,
+    /// Watchers connected at once; further connections are closed
+    pub max_watchers: usize,
+}
+
+impl Default for LiveTailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            socket_path: "./run/live-tail.sock".to_string(),
+            max_watchers: 4,
+        }
+    }
+}
+
+impl LiveTailConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.socket_path.trim().is_empty() {
+            errors.push("socket_path must not be empty".to_string());
+        }
+        if self.max_watchers == 0 {
+            errors.push("max_watchers must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// Filter a watcher sends when it connects; empty fields match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TailRequest {
+    pub source: String,
+    /// Comma-separated levels
+    pub levels: String,
+    pub contains: String,
+}
+
+impl TailRequest {
+    pub fn filter(&self) -> TailFilter {
+        TailFilter::new(&self.source, &self.levels, &self.contains)
+    }
+}
+
+/// One line sent to a watcher
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TailLine<E> {
+    Event(E),
+    Lagged(u64),
+}
+
+/// Which events a watcher sees; empty criteria match everything
+#[derive(Debug, Clone, Default)]
+pub struct TailFilter {
+    /// Exact source name
+    pub source: Option<String>,
+    /// Comma-separated levels, case-insensitive (`error,critical`)
+    pub levels: Vec<String>,
+    /// Case-insensitive substring of the message or raw data
+    pub contains: Option<String>,
+}
+
+impl TailFilter {
+    pub fn new(source: &str, levels: &str, contains: &str) -> Self {
+        Self {
+            source: Some(source.trim().to_string()).filter(|s| !s.is_empty()),
+            levels: levels.split(',').map(|l| l.trim().to_lowercase()).filter(|l| !l.is_empty()).collect(),
+            contains: Some(contains.to_lowercase()).filter(|s| !s.is_empty()),
+        }
+    }
+
+    pub fn matches(&self, event: &ParsedEvent) -> bool {
+        if self.source.as_ref().is_some_and(|source| *source != event.source) {
+            return false;
+        }
+        if !self.levels.is_empty() {
+            let level = event.level.as_deref().unwrap_or_default().to_lowercase();
+            if !self.levels.contains(&level) {
+                return false;
+            }
+        }
+        match &self.contains {
+            Some(needle) => event.message.to_lowercase().contains(needle) || event.raw_data.to_lowercase().contains(needle),
+            None => true,
+        }
+    }
+}
+
+/// What a watcher receives next
+#[derive(Debug, Clone)]
+pub enum TailItem {
+    Event(Arc<ParsedEvent>),
+    /// This many events were skipped because the watcher fell behind
+    Lagged(u64),
+}
+
+/// Fan-out point between the parsing engine and live tail watchers
+#[derive(Debug)]
+pub struct LiveTail {
+    sender: broadcast::Sender<Arc<ParsedEvent>>,
+}
+
+impl LiveTail {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(TAIL_CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// Offer a parsed event to current watchers
+    pub fn publish(&self, event: &ParsedEvent) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        let _ = self.sender.send(Arc::new(event.clone()));
+    }
+
+    pub fn watchers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    pub fn subscribe(&self, filter: TailFilter) -> TailSubscription {
+        TailSubscription { receiver: self.sender.subscribe(), filter }
+    }
+}
+
+impl Default for LiveTail {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One watcher's view of the tail
+pub struct TailSubscription {
+    receiver: broadcast::Receiver<Arc<ParsedEvent>>,
+    filter: TailFilter,
+}
+
+impl TailSubscription {
+    /// Next matching event or lag notice; None once the agent shuts the tail down
+    pub async fn next(&mut self) -> Option<TailItem> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if self.filter.matches(&event) => return Some(TailItem::Event(event)),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => return Some(TailItem::Lagged(skipped)),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Serve the live tail on the configured socket until shutdown
+#[cfg(unix)]
+pub async fn serve(tail: Arc<LiveTail>, config: LiveTailConfig, mut shutdown_receiver: broadcast::Receiver<()>) -> std::io::Result<()> {
+    let path = Path::new(&config.socket_path);
+    let listener = bind_owner_only(path)?;
+    let watchers = Arc::new(tokio::sync::Semaphore::new(config.max_watchers));
+    info!("👀 Live tail listening on {}", path.display());
+
+    let result = loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("⚠️ Live tail accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let Ok(permit) = watchers.clone().try_acquire_owned() else {
+                    warn!("⚠️ Live tail watcher limit ({}) reached, closing connection", config.max_watchers);
+                    continue;
+                };
+                let tail = tail.clone();
+                let shutdown_receiver = shutdown_receiver.resubscribe();
+                tokio::spawn(async move {
+                    if let Err(e) = watch(&tail, stream, shutdown_receiver).await {
+                        debug!("Live tail watcher closed: {}", e);
+                    }
+                    drop(permit);
+                });
+            }
+            _ = shutdown_receiver.recv() => {
+                info!("🛑 Live tail shutting down");
+                break Ok(());
+            }
+        }
+    };
+    let _ = std::fs::remove_file(path);
+    result
+}
+
+#[cfg(not(unix))]
+pub async fn serve(_tail: Arc<LiveTail>, _config: LiveTailConfig, _shutdown_receiver: broadcast::Receiver<()>) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "the live tail socket needs a Unix platform"))
+}
+
+/// Bind under a temporary name and rename into place once the socket is owner-only, so nobody else can
+/// connect in between. A socket left by an earlier run is replaced; any other file is left alone.
+#[cfg(unix)]
+pub(crate) fn bind_owner_only(path: &Path) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4().simple()));
+    let listener = tokio::net::UnixListener::bind(&temp_path)?;
+    let placed = std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o600))
+        .and_then(|_| std::fs::rename(&temp_path, path));
+    if let Err(e) = placed {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    Ok(listener)
+}
+
+/// Read the watcher's filter, then stream matching events until it disconnects
+#[cfg(unix)]
+async fn watch(tail: &LiveTail, stream: tokio::net::UnixStream, mut shutdown_receiver: broadcast::Receiver<()>) -> std::io::Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader.take(MAX_REQUEST_BYTES));
+    let mut line = String::new();
+    tokio::time::timeout(REQUEST_TIMEOUT, reader.read_line(&mut line))
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "no filter received"))??;
+    let request: TailRequest = serde_json::from_str(&line)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let mut reader = reader.into_inner().into_inner();
+    let mut subscription = tail.subscribe(request.filter());
+    debug!("👀 Live tail watcher connected ({:?})", request);
+
+    // The watcher sends nothing after its filter, so a read returning means it went away
+    let mut closed = [0u8; 1];
+    loop {
+        let item = tokio::select! {
+            item = subscription.next() => item,
+            _ = reader.read(&mut closed) => return Ok(()),
+            _ = shutdown_receiver.recv() => return Ok(()),
+        };
+        let mut line = match item {
+            Some(TailItem::Event(event)) => serde_json::to_vec(&TailLine::Event(&*event)),
+            Some(TailItem::Lagged(skipped)) => serde_json::to_vec(&TailLine::<&ParsedEvent>::Lagged(skipped)),
+            None => return Ok(()),
+        }
+        .map_err(std::io::Error::other)?;
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+    }
+}
+
+/// A watcher's connection to a running agent's live tail
+#[cfg(unix)]
+pub struct TailClient {
+    lines: tokio::io::Lines<tokio::io::BufReader<tokio::net::unix::OwnedReadHalf>>,
+    // Closing the write half tells the agent the watcher is gone
+    _writer: tokio::net::unix::OwnedWriteHalf,
+}
+
+#[cfg(unix)]
+impl TailClient {
+    pub async fn connect(path: &Path, request: &TailRequest) -> std::io::Result<Self> {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let (reader, mut writer) = tokio::net::UnixStream::connect(path).await?.into_split();
+        let mut line = serde_json::to_vec(request).map_err(std::io::Error::other)?;
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+        Ok(Self { lines: BufReader::new(reader).lines(), _writer: writer })
+    }
+
+    /// Next event or lag notice; None once the agent closes the connection
+    pub async fn next(&mut self) -> std::io::Result<Option<TailLine<ParsedEvent>>> {
+        match self.lines.next_line().await? {
+            Some(line) => serde_json::from_str(&line)
+                .map(Some)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn event(source: &str, level: &str, message: &str) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: source.to_string(),
+            level: Some(level.to_string()),
+            message: message.to_string(),
+            fields: HashMap::new(),
+            raw_data: message.into(),
+            parser_name: "test_parser".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watcher_sees_only_matching_events() {
+        let tail = LiveTail::new();
+        // Published with nobody watching: dropped without error
+        tail.publish(&event("sshd", "error", "before anyone watched"));
+
+        let mut subscription = tail.subscribe(TailFilter::new("sshd", "Error, critical", "PASSWORD"));
+        assert_eq!(tail.watchers(), 1);
+        tail.publish(&event("sshd", "info", "Accepted password for alice"));
+        tail.publish(&event("nginx", "error", "bad password header"));
+        tail.publish(&event("sshd", "ERROR", "Failed password for root"));
+
+        match subscription.next().await {
+            Some(TailItem::Event(event)) => assert_eq!(event.message, "Failed password for root"),
+            other => panic!("unexpected {:?}", other),
+        }
+        drop(subscription);
+        assert_eq!(tail.watchers(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_slow_watcher_is_told_how_much_it_missed() {
+        let tail = LiveTail::new();
+        let mut subscription = tail.subscribe(TailFilter::default());
+        for n in 0..TAIL_CHANNEL_CAPACITY + 10 {
+            tail.publish(&event("app", "info", &format!("event {}", n)));
+        }
+        assert!(matches!(subscription.next().await, Some(TailItem::Lagged(10))));
+        assert!(matches!(subscription.next().await, Some(TailItem::Event(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_socket_watcher_receives_matching_events() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = LiveTailConfig {
+            socket_path: dir.path().join("tail.sock").display().to_string(),
+            ..Default::default()
+        };
+        let tail = Arc::new(LiveTail::new());
+        let (shutdown_sender, shutdown_receiver) = broadcast::channel(1);
+        let server = tokio::spawn(serve(tail.clone(), config.clone(), shutdown_receiver));
+
+        let path = Path::new(&config.socket_path);
+        while !path.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(std::fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        let request = TailRequest { source: "sshd".to_string(), levels: "error".to_string(), ..Default::default() };
+        let mut client = TailClient::connect(path, &request).await.unwrap();
+        while tail.watchers() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tail.publish(&event("nginx", "error", "not this one"));
+        tail.publish(&event("sshd", "error", "Failed password for root"));
+
+        match client.next().await.unwrap() {
+            Some(TailLine::Event(event)) => assert_eq!(event.message, "Failed password for root"),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // A watcher that disconnects stops costing the pipeline anything
+        drop(client);
+        while tail.watchers() > 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        shutdown_sender.send(()).unwrap();
+        server.await.unwrap().unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,313 @@
+// Load shedding when the agent outgrows its resource limits
+// Each resource sample is compared with agent.max_memory_mb and agent.max_cpu_percent. While the agent stays over
+// a limit it steps through progressively harsher stages: smaller transport batches, sampling of non-alert events,
+// then disabling low-priority collectors one at a time. It steps back once usage stays clear of the limits.
+// Emergency shutdown only acts after every stage has been applied.
+
+use crate::alert_rules;
+use crate::parsers::ParsedEvent;
+use crate::resource_monitor::ResourceMetrics;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Stages before collectors are disabled: normal, smaller batches, sampling
+const FIXED_STAGES: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoadSheddingConfig {
+    pub enabled: bool,
+    /// Consecutive samples over a limit before moving to the next stage
+    pub escalate_after_samples: u32,
+    /// Consecutive samples below the recovery mark before stepping back a stage
+    pub recover_after_samples: u32,
+    /// How far below a limit (percent of the limit) usage has to be to step back
+    pub recovery_margin_percent: f32,
+    /// Transport batch size, as a share of the configured one, from the first stage on
+    pub batch_size_factor: f64,
+    /// While sampling, keep one of every `sample_every` non-alert events
+    pub sample_every: u64,
+    /// Sources that are sampled; empty samples every source
+    pub sampled_sources: Vec<String>,
+    /// Collectors disabled in the last stages, one per stage, in this order
+    pub low_priority_collectors: Vec<String>,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            escalate_after_samples: 2,
+            recover_after_samples: 3,
+            recovery_margin_percent: 20.0,
+            batch_size_factor: 0.5,
+            sample_every: 4,
+            sampled_sources: Vec::new(),
+            low_priority_collectors: vec![
+                "packet_metadata".to_string(),
+                "http_pull".to_string(),
+                "container".to_string(),
+                "session".to_string(),
+            ],
+        }
+    }
+}
+
+impl LoadSheddingConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.escalate_after_samples == 0 || self.recover_after_samples == 0 {
+            errors.push("escalate_after_samples and recover_after_samples must be greater than 0".to_string());
+        }
+        if !(0.0..100.0).contains(&self.recovery_margin_percent) {
+            errors.push("recovery_margin_percent must be at least 0 and below 100".to_string());
+        }
+        if !(self.batch_size_factor > 0.0 && self.batch_size_factor <= 1.0) {
+            errors.push("batch_size_factor must be greater than 0 and at most 1".to_string());
+        }
+        if self.sample_every == 0 {
+            errors.push("sample_every must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// A move between stages, for logging
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageChange {
+    pub from: usize,
+    pub to: usize,
+    /// What the new stage sheds, e.g. "sampling 1/4 events"
+    pub description: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LoadSheddingStats {
+    pub stage: usize,
+    pub max_stage: usize,
+    pub description: String,
+    pub disabled_collectors: Vec<String>,
+    pub events_sampled_out: u64,
+    pub escalations: u64,
+    pub recoveries: u64,
+}
+
+/// Current stage, consulted by the transport, buffer, collectors and emergency shutdown
+pub struct LoadShedder {
+    config: LoadSheddingConfig,
+    max_memory_bytes: u64,
+    max_cpu_percent: f32,
+    stage: AtomicUsize,
+    /// Consecutive samples (over the limit, below the recovery mark)
+    streaks: Mutex<(u32, u32)>,
+    sample_counter: AtomicU64,
+    events_sampled_out: AtomicU64,
+    escalations: AtomicU64,
+    recoveries: AtomicU64,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig, max_memory_mb: usize, max_cpu_percent: f32) -> Self {
+        Self {
+            config,
+            max_memory_bytes: max_memory_mb as u64 * 1024 * 1024,
+            max_cpu_percent,
+            stage: AtomicUsize::new(0),
+            streaks: Mutex::new((0, 0)),
+            sample_counter: AtomicU64::new(0),
+            events_sampled_out: AtomicU64::new(0),
+            escalations: AtomicU64::new(0),
+            recoveries: AtomicU64::new(0),
+        }
+    }
+
+    /// Feed a resource sample; samples without the agent's own process are ignored
+    pub fn observe(&self, metrics: &ResourceMetrics) -> Option<StageChange> {
+        let process = metrics.agent_process.as_ref()?;
+        // Process CPU is per core; the limit is a share of the whole machine
+        let cpu_percent = process.cpu_usage / metrics.cpu.core_count.max(1) as f32;
+        self.observe_usage(process.memory_bytes, cpu_percent)
+    }
+
+    /// Feed the agent's memory use and CPU share (percent of all cores)
+    pub fn observe_usage(&self, memory_bytes: u64, cpu_percent: f32) -> Option<StageChange> {
+        if !self.config.enabled {
+            return None;
+        }
+        let memory_ratio = memory_bytes as f64 / self.max_memory_bytes.max(1) as f64;
+        let cpu_ratio = cpu_percent as f64 / self.max_cpu_percent.max(f32::EPSILON) as f64;
+        let recovery_mark = 1.0 - self.config.recovery_margin_percent as f64 / 100.0;
+
+        let mut streaks = self.streaks.lock();
+        let from = self.stage.load(Ordering::Relaxed);
+        let (to, reason) = if memory_ratio > 1.0 || cpu_ratio > 1.0 {
+            *streaks = (streaks.0 + 1, 0);
+            if streaks.0 < self.config.escalate_after_samples || from == self.max_stage() {
+                return None;
+            }
+            let reason = format!("memory {} MB of {} MB, CPU {:.1}% of {:.1}%",
+                                 memory_bytes / (1024 * 1024), self.max_memory_bytes / (1024 * 1024), cpu_percent, self.max_cpu_percent);
+            self.escalations.fetch_add(1, Ordering::Relaxed);
+            (from + 1, reason)
+        } else if memory_ratio < recovery_mark && cpu_ratio < recovery_mark {
+            *streaks = (0, streaks.1 + 1);
+            if streaks.1 < self.config.recover_after_samples || from == 0 {
+                return None;
+            }
+            self.recoveries.fetch_add(1, Ordering::Relaxed);
+            (from - 1, "usage back below the recovery mark".to_string())
+        } else {
+            *streaks = (0, 0);
+            return None;
+        };
+        *streaks = (0, 0);
+        self.stage.store(to, Ordering::Relaxed);
+        Some(StageChange { from, to, description: self.describe(to), reason })
+    }
+
+    pub fn stage(&self) -> usize {
+        self.stage.load(Ordering::Relaxed)
+    }
+
+    /// The last stage: every low-priority collector is disabled
+    pub fn max_stage(&self) -> usize {
+        FIXED_STAGES - 1 + self.config.low_priority_collectors.len()
+    }
+
+    /// Nothing is left to shed, so emergency shutdown may act
+    pub fn is_exhausted(&self) -> bool {
+        !self.config.enabled || self.stage() >= self.max_stage()
+    }
+
+    /// Transport batch size for the current stage
+    pub fn batch_size(&self, configured: usize) -> usize {
+        if self.stage() == 0 {
+            return configured;
+        }
+        ((configured as f64 * self.config.batch_size_factor) as usize).max(1)
+    }
+
+    /// Drop this event to sample the stream; alerts are always kept
+    pub fn should_sample_out(&self, event: &ParsedEvent) -> bool {
+        if self.stage() < 2 || self.config.sample_every <= 1 || alert_rules::is_alert(event) {
+            return false;
+        }
+        if !self.config.sampled_sources.is_empty() && !self.config.sampled_sources.contains(&event.source) {
+            return false;
+        }
+        let drop = self.sample_counter.fetch_add(1, Ordering::Relaxed) % self.config.sample_every != 0;
+        if drop {
+            self.events_sampled_out.fetch_add(1, Ordering::Relaxed);
+        }
+        drop
+    }
+
+    pub fn is_collector_disabled(&self, name: &str) -> bool {
+        self.disabled_collectors(self.stage()).iter().any(|collector| collector == name)
+    }
+
+    fn disabled_collectors(&self, stage: usize) -> &[String] {
+        let count = stage.saturating_sub(FIXED_STAGES - 1).min(self.config.low_priority_collectors.len());
+        &self.config.low_priority_collectors[..count]
+    }
+
+    fn describe(&self, stage: usize) -> String {
+        match stage {
+            0 => "normal".to_string(),
+            1 => format!("transport batches at {:.0}%", self.config.batch_size_factor * 100.0),
+            2 => format!("sampling 1/{} events", self.config.sample_every),
+            _ => format!("sampling 1/{} events, collectors disabled: {}",
+                         self.config.sample_every, self.disabled_collectors(stage).join(", ")),
+        }
+    }
+
+    pub fn stats(&self) -> LoadSheddingStats {
+        let stage = self.stage();
+        LoadSheddingStats {
+            stage,
+            max_stage: self.max_stage(),
+            description: self.describe(stage),
+            disabled_collectors: self.disabled_collectors(stage).to_vec(),
+            events_sampled_out: self.events_sampled_out.load(Ordering::Relaxed),
+            escalations: self.escalations.load(Ordering::Relaxed),
+            recoveries: self.recoveries.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn shedder() -> LoadShedder {
+        let config = LoadSheddingConfig {
+            low_priority_collectors: vec!["packet_metadata".to_string(), "http_pull".to_string()],
+            ..Default::default()
+        };
+        LoadShedder::new(config, 512, 50.0)
+    }
+
+    fn event(source: &str, fields: &[(&str, serde_json::Value)]) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: source.to_string(),
+            level: None,
+            message: "event".to_string(),
+            fields: fields.iter().map(|(k, v)| (k.to_string(), v.clone())).collect::<HashMap<_, _>>(),
+            raw_data: "".into(),
+            parser_name: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_stages_escalate_and_recover_with_hysteresis() {
+        let shedder = shedder();
+        assert_eq!(shedder.max_stage(), 4);
+
+        // Two samples over the limit per stage
+        assert!(shedder.observe_usage(600 * MB, 10.0).is_none());
+        let change = shedder.observe_usage(600 * MB, 10.0).unwrap();
+        assert_eq!((change.from, change.to), (0, 1));
+        assert_eq!(shedder.batch_size(100), 50);
+        for _ in 0..6 {
+            shedder.observe_usage(100 * MB, 80.0);
+        }
+        assert_eq!(shedder.stage(), 4);
+        assert!(shedder.is_exhausted());
+        assert!(shedder.is_collector_disabled("packet_metadata") && shedder.is_collector_disabled("http_pull"));
+        assert!(!shedder.is_collector_disabled("syslog"));
+
+        // Between the recovery mark and the limit nothing changes
+        for _ in 0..5 {
+            assert!(shedder.observe_usage(450 * MB, 10.0).is_none());
+        }
+        for _ in 0..3 {
+            shedder.observe_usage(100 * MB, 10.0);
+        }
+        assert_eq!(shedder.stage(), 3);
+        assert!(shedder.is_collector_disabled("packet_metadata") && !shedder.is_collector_disabled("http_pull"));
+        assert!(!shedder.is_exhausted());
+        let stats = shedder.stats();
+        assert_eq!((stats.escalations, stats.recoveries), (4, 1));
+        assert_eq!(stats.description, "sampling 1/4 events, collectors disabled: packet_metadata");
+    }
+
+    #[test]
+    fn test_sampling_keeps_alerts_and_unlisted_sources() {
+        let shedder = LoadShedder::new(LoadSheddingConfig { sampled_sources: vec!["syslog".to_string()], ..Default::default() }, 512, 50.0);
+        let syslog = event("syslog", &[]);
+        assert!(!shedder.should_sample_out(&syslog));
+
+        shedder.stage.store(2, Ordering::Relaxed);
+        let kept = (0..8).filter(|_| !shedder.should_sample_out(&syslog)).count();
+        assert_eq!(kept, 2);
+        assert!(!shedder.should_sample_out(&event("windows_event", &[])));
+        assert!((0..8).all(|_| !shedder.should_sample_out(&event("syslog", &[("alert.rules", serde_json::json!(["r"]))]))));
+        assert_eq!(shedder.stats().events_sampled_out, 6);
+    }
+}
@@ -1,8 +1,7 @@
 // SecureWatch Rust Agent - Main Entry Point with Tokio async patterns
 
-use clap::Parser;
-use std::path::PathBuf;
-use tokio::signal;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
 use tracing::{error, info, Level, warn};
 use tracing_subscriber::{
     fmt::{self, time::ChronoUtc},
@@ -13,6 +12,30 @@ use tracing_subscriber::{
 use tracing_appender::{non_blocking, rolling};
 
 use securewatch_agent::{AgentConfig, Agent};
+use securewatch_agent::management;
+use securewatch_agent::management_tls::ManagementTlsManager;
+use securewatch_agent::capabilities::CapabilityReport;
+use securewatch_agent::chaos::{self, ChaosConfig};
+use securewatch_agent::bench_profile::{self, BenchProfile};
+use securewatch_agent::component_usage::TrackingAllocator;
+use securewatch_agent::ingest_pause::{parse_pause_until, IngestPause, IngestPauseStatus, IngestPauses};
+use securewatch_agent::buffer_export::ExportFormat;
+use securewatch_agent::parsers::harness::ParserHarness;
+use securewatch_agent::service::{self, ServiceInstall};
+use securewatch_agent::security::KeyringReference;
+use securewatch_agent::admin_audit::{self, AdminAuditLog, AuditAction};
+use securewatch_agent::diagnostics::{self, DiagnosticBundle};
+use securewatch_agent::resource_monitor::ResourceMonitor;
+use securewatch_agent::live_tail::TailRequest;
+use securewatch_agent::control_socket::{self, ControlRequest, ControlResponse};
+use securewatch_agent::collectors::CollectorStatus;
+use securewatch_agent::pipeline_metrics::LatencyHistogram;
+#[cfg(unix)]
+use securewatch_agent::live_tail::{TailClient, TailLine};
+
+/// Charges heap allocations to the pipeline component that made them
+#[global_allocator]
+static GLOBAL: TrackingAllocator = TrackingAllocator;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -40,6 +63,401 @@ struct Cli {
     /// Validate configuration and exit
     #[arg(long)]
     validate_config: bool,
+
+    /// Print the management TLS certificate fingerprint (generating the certificate if needed) and exit
+    #[arg(long)]
+    management_fingerprint: bool,
+
+    /// Inject faults for soak testing (dev builds only), optionally tuned by a TOML profile
+    #[arg(long, hide = true, value_name = "PROFILE", num_args = 0..=1)]
+    chaos: Option<Option<PathBuf>>,
+
+    /// Send synthetic syslog load to a running agent and report the rate sustained, optionally tuned by a TOML profile
+    #[arg(long, value_name = "PROFILE", num_args = 0..=1)]
+    bench_profile: Option<Option<PathBuf>>,
+
+    /// Register the agent with systemd (Linux) or the Windows Service Control Manager, start it and exit
+    #[arg(long)]
+    install_service: bool,
+
+    /// Run under the Windows Service Control Manager (set by the installer's service registration)
+    #[arg(long, hide = true)]
+    service: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect the local event buffer
+    Buffer {
+        #[command(subcommand)]
+        action: BufferCommand,
+    },
+    /// Validate the configuration or export its JSON schema
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Report compiled features, detected platform capabilities and which config sections are active
+    Capabilities {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Report on the running agent through its local control socket instead of this configuration file
+        #[arg(long)]
+        agent: bool,
+    },
+    /// Pause or resume ingest, globally or per source; the running agent picks changes up within seconds
+    Ingest {
+        #[command(subcommand)]
+        action: IngestCommand,
+    },
+    /// Try configured parsers against sample input, or reload them in the running agent
+    Parsers {
+        #[command(subcommand)]
+        action: ParsersCommand,
+    },
+    /// Show whether each of the running agent's collectors is running, through its local control socket
+    Collectors {
+        /// Print the collectors as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the running agent's parse, queue wait and flush latency percentiles, through its local control socket
+    Latency {
+        /// Print the histograms as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Ask the running agent's management API for collector, buffer and transport health
+    Status {
+        /// Print the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Keep config secrets in the OS keyring, referenced as e.g. api_key = "keyring:securewatch/api"
+    Secret {
+        #[command(subcommand)]
+        action: SecretCommand,
+    },
+    /// Read and verify the audit log of configuration changes, administrative calls and agent starts and stops
+    Audit {
+        #[command(subcommand)]
+        action: AuditCommand,
+    },
+    /// Gather logs, configuration and runtime state for support
+    Diagnostics {
+        #[command(subcommand)]
+        action: DiagnosticsCommand,
+    },
+    /// Follow events as the running agent parses them, through its local live tail socket
+    Tail {
+        /// Only events from this source
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Only these levels, comma-separated (e.g. error,critical)
+        #[arg(long)]
+        level: Option<String>,
+
+        /// Only events whose message or raw data contains this text (case-insensitive)
+        #[arg(long)]
+        contains: Option<String>,
+
+        /// Print each event as a JSON line
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DiagnosticsCommand {
+    /// Bundle recent logs, the configuration with secrets redacted, buffer stats, resource metrics, the running
+    /// agent's status and crash reports into a .tar.gz
+    Collect {
+        /// Bundle to write (default: securewatch-diagnostics-<host>-<time>.tar.gz in the current directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Don't ask the running agent's management API for its status
+        #[arg(long)]
+        no_status: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditCommand {
+    /// Print the newest entries
+    Show {
+        /// Only entries at or after this time (RFC 3339, or an age such as 30m, 2h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only this action, e.g. config_changed or ingest_paused
+        #[arg(long)]
+        action: Option<String>,
+
+        /// Maximum entries to print
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+
+        /// Print entries as NDJSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the hash chain; exits with an error when an entry was altered or removed
+    Verify {
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SecretCommand {
+    /// Store the first line of stdin under a reference, replacing any previous value
+    Set {
+        /// keyring:<service>/<account>
+        reference: String,
+    },
+    /// Check that a reference resolves, without printing the secret
+    Check {
+        /// keyring:<service>/<account>
+        reference: String,
+    },
+    /// Remove a stored secret
+    Delete {
+        /// keyring:<service>/<account>
+        reference: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Check the configuration file and report errors and warnings (same as --validate-config)
+    Validate,
+    /// Validate a configuration and have the running agent apply and persist it, through its local control socket
+    Push {
+        /// Configuration to push (.toml, or .json)
+        file: PathBuf,
+
+        /// Only report what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Work with the configuration JSON schema
+    Schema {
+        #[command(subcommand)]
+        action: SchemaCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum SchemaCommand {
+    /// Write the configuration JSON schema for editors and config management tooling
+    Export {
+        /// File to write; stdout carries the agent's own log lines
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ParsersCommand {
+    /// Parse a sample file line by line and report extracted fields, match rates and failures
+    Test {
+        /// Sample log file, one event per line ('-' reads stdin)
+        #[arg(long)]
+        file: PathBuf,
+
+        /// Only this configured parser
+        #[arg(long)]
+        parser: Option<String>,
+
+        /// Only parsers for this source type, tried in configuration order as the agent does
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Matched lines to print with their fields
+        #[arg(long, default_value_t = 10)]
+        show: usize,
+
+        /// Failed lines to print
+        #[arg(long, default_value_t = 20)]
+        max_failures: usize,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Rebuild the running agent's parsers from its active configuration, through its local control socket
+    Reload,
+}
+
+#[derive(Subcommand)]
+enum IngestCommand {
+    /// Discard events from a source (all sources when --source is omitted)
+    Pause {
+        /// Source to pause, e.g. syslog or windows_event
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Resume automatically at this time (RFC 3339, or a duration such as 30m, 2h, 1d); otherwise until resumed
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Why ingest is paused, shown in status output
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Lift a pause (the all-sources pause when --source is omitted)
+    Resume {
+        /// Source to resume
+        #[arg(long, conflicts_with = "all")]
+        source: Option<String>,
+
+        /// Lift every pause
+        #[arg(long)]
+        all: bool,
+    },
+    /// Show active pauses
+    Status {
+        /// Print the pauses as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum BufferCommand {
+    /// Count events waiting on disk per source, with quarantined rows and dead-lettered batches
+    Stats {
+        /// Print the stats as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Search buffered and archived events in the local full-text index
+    Search {
+        /// FTS5 query, e.g. '"failed password" AND root'
+        query: String,
+
+        /// Only events from this source
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Only events at or after this time (RFC 3339, or an age such as 30m, 2h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only events at or before this time (RFC 3339, or an age such as 30m, 2h, 7d)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Maximum matches to print
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+
+        /// Print matches as NDJSON
+        #[arg(long)]
+        json: bool,
+
+        /// Ask the running agent through its local control socket instead of opening the index
+        #[arg(long)]
+        agent: bool,
+    },
+    /// Run a KQL query against events buffered on disk, e.g. "Events | where source == 'syslog' | summarize count() by level"
+    Query {
+        /// KQL over the Events table; field names such as user.name refer to parsed fields
+        query: String,
+
+        /// Maximum rows to print
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+
+        /// Newest buffered events to query
+        #[arg(long, default_value_t = 100_000)]
+        scan: usize,
+
+        /// Print rows as NDJSON objects
+        #[arg(long)]
+        json: bool,
+
+        /// Ask the running agent through its local control socket, with the buffer settings it runs with
+        #[arg(long)]
+        agent: bool,
+    },
+    /// Dump events buffered on disk for offline analysis or manual reingestion; the buffer is left as is
+    Export {
+        /// Output format: ndjson (one event per line, reingestable) or csv
+        #[arg(long, default_value = "ndjson")]
+        format: ExportFormat,
+
+        /// File to write; stdout carries the agent's own log lines, so exports always go to a file
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Only events from this source
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Only events at or after this time (RFC 3339, or an age such as 30m, 2h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only events at or before this time (RFC 3339, or an age such as 30m, 2h, 7d)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Stop after this many events
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// List, inspect, export or re-drive batches the transport gave up on
+    DeadLetters {
+        #[command(subcommand)]
+        action: DeadLetterCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum DeadLetterCommand {
+    /// List dead-lettered batches, oldest first
+    List {
+        /// Maximum batches to print
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+
+        /// Print the batches as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print one batch with its events as JSON
+    Inspect {
+        /// Batch ID from `buffer dead-letters list`
+        id: i64,
+    },
+    /// Queue batches behind the buffered events so the running agent sends them again
+    Redrive {
+        /// Batch IDs from `buffer dead-letters list`
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        ids: Vec<i64>,
+
+        /// Re-drive every batch
+        #[arg(long)]
+        all: bool,
+    },
+    /// Write batches as NDJSON, one batch with its events per line; the batches stay dead-lettered
+    Export {
+        /// File to write; stdout carries the agent's own log lines, so exports always go to a file
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Batch IDs to export, every batch when omitted
+        ids: Vec<i64>,
+    },
 }
 
 #[tokio::main]
@@ -78,59 +496,740 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Validate config if requested
     if cli.validate_config {
-        info!(
-            action = "validate_config",
-            status = "valid",
-            "✅ Configuration is valid"
+        return validate_config(&cli.config, &config).await;
+    }
+
+    // Print the management certificate fingerprint for client pinning
+    if cli.management_fingerprint {
+        if !config.management.tls.enabled {
+            error!("❌ Management TLS is disabled in the configuration");
+            return Err("management TLS is disabled".into());
+        }
+        let management_tls = ManagementTlsManager::new(
+            config.management.tls.clone(),
+            &config.management.bind_address,
         );
+        let certificate = management_tls.ensure_certificate()?;
+        println!("{}", certificate.fingerprint_sha256);
         return Ok(());
     }
 
-    // Create and initialize agent
-    let mut agent = Agent::new(config)?;
-    agent.initialize().await?;
+    // Load the syslog listener of an agent already running with this configuration
+    if let Some(profile) = &cli.bench_profile {
+        let profile = match profile {
+            Some(path) => BenchProfile::load_from_file(&path.to_string_lossy()).await?,
+            None => BenchProfile::default(),
+        };
+        let (target, protocol) = profile.resolve_target(config.collectors.syslog.as_ref()).inspect_err(|e| {
+            error!(error = %e, "❌ No target for the bench profile");
+        })?;
+        let report = bench_profile::run(&profile, &target, &protocol).await?;
+        print!("{}", report.to_text());
+        return Ok(());
+    }
 
-    // Setup graceful shutdown with Ctrl+C handling
-    let shutdown_future = async {
-        signal::ctrl_c().await.expect("Failed to listen for ctrl_c");
+    // Register this binary as a service using the current --config and --log-dir
+    if cli.install_service {
+        let install = ServiceInstall::for_current_exe(&cli.config, &cli.log_dir, config.shutdown_drain.deadline_seconds)?;
+        let location = service::install_service(&install).inspect_err(|e| {
+            error!(error = %e, "❌ Service installation failed");
+        })?;
         info!(
-            signal = "SIGINT",
-            action = "shutdown_requested",
-            "🛑 Received Ctrl+C, shutting down..."
+            action = "install_service",
+            config_file = %install.config_path.display(),
+            "✅ Agent service installed and started ({})", location
         );
-    };
+        return Ok(());
+    }
 
-    // Run agent with graceful shutdown using tokio::select!
-    tokio::select! {
-        result = agent.run() => {
-            match result {
-                Ok(_) => info!(
-                    status = "completed",
-                    exit_code = 0,
-                    "✅ Agent completed successfully"
-                ),
-                Err(e) => error!(
-                    status = "failed",
-                    error = %e,
-                    exit_code = 1,
-                    "❌ Agent failed"
-                ),
+    // One-shot maintenance commands run against the local state and exit
+    match &cli.command {
+        Some(Command::Buffer { action }) => return run_buffer_command(&config, action).await,
+        Some(Command::Ingest { action }) => return run_ingest_command(&config, action).await,
+        Some(Command::Parsers { action }) => return run_parsers_command(&config, action).await,
+        Some(Command::Secret { action }) => return run_secret_command(action).await,
+        Some(Command::Audit { action }) => return run_audit_command(&config, action),
+        Some(Command::Diagnostics { action }) => return run_diagnostics_command(&config, &cli.log_dir, action).await,
+        Some(Command::Tail { source, level, contains, json }) => {
+            let request = TailRequest {
+                source: source.clone().unwrap_or_default(),
+                levels: level.clone().unwrap_or_default(),
+                contains: contains.clone().unwrap_or_default(),
+            };
+            return run_tail_command(&config, &request, *json).await;
+        }
+        Some(Command::Config { action: ConfigCommand::Validate }) => return validate_config(&cli.config, &config).await,
+        Some(Command::Config { action: ConfigCommand::Push { file, dry_run } }) => {
+            let format = match file.extension().and_then(|e| e.to_str()) {
+                Some(extension) if extension.eq_ignore_ascii_case("json") => "json",
+                _ => "toml",
+            };
+            let request = ControlRequest::PushConfig {
+                config: std::fs::read_to_string(file)?,
+                format: format.to_string(),
+                dry_run: *dry_run,
+            };
+            let response = control_request(&config, &request).await?;
+            println!("{}", response.message);
+            return Ok(());
+        }
+        Some(Command::Collectors { json }) => {
+            let response = control_request(&config, &ControlRequest::CollectorStatus).await?;
+            let collectors: Vec<CollectorStatus> = serde_json::from_value(response.data)?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&collectors)?);
+            } else {
+                for collector in &collectors {
+                    println!("{:<24} {:<8} {} dropped", collector.name,
+                             if collector.running { "running" } else { "stopped" }, collector.dropped_events);
+                }
             }
+            return Ok(());
         }
-        _ = shutdown_future => {
-            info!(
-                action = "graceful_shutdown",
-                trigger = "signal",
-                "🛑 Graceful shutdown initiated"
-            );
+        Some(Command::Latency { json }) => {
+            let response = control_request(&config, &ControlRequest::PipelineLatency).await?;
+            let histograms: Vec<LatencyHistogram> = serde_json::from_value(response.data)?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&histograms)?);
+            } else {
+                for histogram in &histograms {
+                    println!("{:<10} {:<24} {:>8} samples  p50 {:>8.1}ms  p95 {:>8.1}ms  p99 {:>8.1}ms  max {:>8.1}ms",
+                             histogram.stage.as_str(), histogram.key, histogram.samples(), histogram.percentile_ms(0.5),
+                             histogram.percentile_ms(0.95), histogram.percentile_ms(0.99), histogram.max_ms());
+                }
+            }
+            return Ok(());
+        }
+        Some(Command::Config { action: ConfigCommand::Schema { action: SchemaCommand::Export { output } } }) => {
+            std::fs::write(output, serde_json::to_string_pretty(&AgentConfig::get_json_schema())?)?;
+            info!(action = "config_schema_export", output = %output.display(), "✅ Configuration schema written");
+            return Ok(());
+        }
+        Some(Command::Status { json }) => {
+            let status = management::query_status(&config.management).await.inspect_err(|e| {
+                error!(error_code = %e.code(), error_name = e.code().name, error = %e, "❌ Could not reach the agent's management API");
+            })?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            } else {
+                print!("{}", status.to_text());
+            }
+            return Ok(());
+        }
+        Some(Command::Capabilities { json, agent: true }) => {
+            let response = control_request(&config, &ControlRequest::Capabilities).await?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&response.data)?);
+            } else {
+                print!("{}", response.message);
+            }
+            return Ok(());
+        }
+        Some(Command::Capabilities { json, agent: false }) => {
+            let report = CapabilityReport::detect(&config);
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report.to_text());
+            }
+            return Ok(());
         }
+        None => {}
     }
 
+    // Install fault injection before any component starts
+    if let Some(profile) = &cli.chaos {
+        let chaos_config = match profile {
+            Some(path) => ChaosConfig::load_from_file(&path.to_string_lossy()).await?,
+            None => ChaosConfig::default(),
+        };
+        chaos::install(chaos_config)?;
+    }
+
+    // Panics leave a crash report and diagnostic bundle under the log directory
+    diagnostics::install_panic_hook(&config, &cli.log_dir);
+
+    // A configuration file in use is watched and reloaded while the agent runs
+    let config_path = cli.config.exists().then(|| cli.config.to_string_lossy().to_string());
+    
+    // Under the SCM the agent body runs on the service thread; the dispatcher blocks this one until it stops
+    #[cfg(windows)]
+    if cli.service {
+        let runtime = tokio::runtime::Handle::current();
+        return tokio::task::block_in_place(|| {
+            service::run_windows_service(move || {
+                runtime.block_on(run_agent(config, config_path)).map_err(|e| e.to_string())
+            })
+        })
+        .map_err(Into::into);
+    }
+    #[cfg(not(windows))]
+    if cli.service {
+        warn!("⚠️ --service only applies on Windows; running in the foreground");
+    }
+
+    if let Err(e) = run_agent(config, config_path).await {
+        return Err(e.to_string().into());
+    }
+    Ok(())
+}
+
+/// Report configuration errors and warnings; a missing file validates the defaults
+async fn validate_config(path: &Path, config: &AgentConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let report = if path.exists() {
+        AgentConfig::validate_file_report(&path.to_string_lossy()).await?
+    } else {
+        config.validate_report()
+    };
+    for warning in &report.warnings {
+        warn!(path = %warning.path, warning_type = %warning.warning_type, "⚠️  {}", warning.message);
+        if let Some(suggestion) = &warning.suggestion {
+            warn!(path = %warning.path, "   💡 {}", suggestion);
+        }
+    }
+    for e in &report.errors {
+        error!(path = %e.path, error_type = %e.error_type, "❌ {}", e.message);
+        if let Some(suggestion) = &e.suggestion {
+            error!(path = %e.path, "   💡 {}", suggestion);
+        }
+    }
+    if !report.is_valid() {
+        error!(
+            action = "validate_config",
+            status = "invalid",
+            errors = report.errors.len(),
+            warnings = report.warnings.len(),
+            "❌ Configuration is invalid"
+        );
+        return Err(format!("configuration has {} errors", report.errors.len()).into());
+    }
+    info!(
+        action = "validate_config",
+        status = if report.warnings.is_empty() { "valid" } else { "valid_with_warnings" },
+        warnings = report.warnings.len(),
+        "✅ Configuration is valid"
+    );
+    Ok(())
+}
+
+async fn run_agent(config: AgentConfig, config_path: Option<String>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Create and initialize agent
+    let mut agent = Agent::new(config).inspect_err(|e| {
+        error!(error_code = %e.code(), error_name = e.code().name, error = %e, "❌ Agent configuration rejected");
+    })?;
+    if let Some(config_path) = config_path {
+        agent.set_config_path(config_path);
+    }
+    agent.initialize().await.inspect_err(|e| {
+        error!(error_code = %e.code(), error_name = e.code().name, error = %e, "❌ Agent initialization failed");
+    })?;
+
+    // The agent handles SIGINT/SIGTERM and service stop requests itself so its shutdown drain runs to completion
+    let result = agent.run().await;
+    match &result {
+        Ok(_) => info!(
+            status = "completed",
+            exit_code = 0,
+            "✅ Agent completed successfully"
+        ),
+        Err(e) => error!(
+            status = "failed",
+            error_code = %e.code(),
+            error_name = e.code().name,
+            error = %e,
+            exit_code = 1,
+            "❌ Agent failed"
+        ),
+    }
+
+    chaos::log_summary();
+
     info!(
         action = "shutdown",
         status = "complete",
         "👋 SecureWatch Agent shutting down"
     );
+    result.map_err(Into::into)
+}
+
+#[cfg(feature = "persistent-storage")]
+async fn run_buffer_command(config: &AgentConfig, command: &BufferCommand) -> Result<(), Box<dyn std::error::Error>> {
+    use securewatch_agent::buffer_export::{buffer_disk_stats, export_buffer, ExportOptions};
+    use securewatch_agent::event_index::{parse_time_bound, EventIndex, SearchRequest, SearchResults};
+    use securewatch_agent::kql::{query_buffer, KqlOptions, KqlResult};
+
+    let time_bound = |value: &Option<String>, flag: &str| {
+        value.as_deref()
+            .map(|v| parse_time_bound(v).ok_or_else(|| format!("invalid {} value '{}'", flag, v)))
+            .transpose()
+    };
+
+    match command {
+        BufferCommand::Stats { json } => {
+            let stats = buffer_disk_stats(&config.buffer).inspect_err(|e| {
+                error!(error_code = %e.code(), error_name = e.code().name, error = %e, "❌ Reading buffer stats failed");
+            })?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+                return Ok(());
+            }
+            println!("Buffer {} ({} bytes on disk)", stats.database_path, stats.file_bytes);
+            println!("  waiting:      {} events, {} bytes", stats.events, stats.event_bytes);
+            if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+                println!("  buffered:     {} .. {}", oldest.to_rfc3339(), newest.to_rfc3339());
+            }
+            for (source, backlog) in &stats.by_source {
+                println!("    {:<24} {:>10} events {:>12} bytes", source, backlog.events, backlog.bytes);
+            }
+            println!("  quarantined:  {}", stats.quarantined);
+            println!("  dead letters: {} batches, {} events", stats.dead_letter_batches, stats.dead_letter_events);
+            Ok(())
+        }
+        BufferCommand::Search { query, source, since, until, limit, json, agent } => {
+            let request = SearchRequest {
+                query: query.clone(),
+                source: source.clone(),
+                since: time_bound(since, "--since")?,
+                until: time_bound(until, "--until")?,
+                limit: *limit,
+            };
+
+            let results: SearchResults = if *agent {
+                serde_json::from_value(control_request(config, &ControlRequest::SearchEvents(request)).await?.data)?
+            } else {
+                if !config.event_index.enabled {
+                    error!("❌ The local event index is disabled in the configuration ([event_index] enabled = false)");
+                    return Err("event index is disabled".into());
+                }
+                let index = EventIndex::open(config.event_index.clone())?;
+                index.search(&request).inspect_err(|e| {
+                    error!(error_code = %e.code(), error_name = e.code().name, error = %e, "❌ Event search failed");
+                })?
+            };
+
+            for hit in &results.hits {
+                if *json {
+                    println!("{}", serde_json::to_string(hit)?);
+                } else {
+                    println!("{} {} [{}] {}", hit.timestamp.to_rfc3339(), hit.source, hit.level.as_deref().unwrap_or("-"), hit.snippet);
+                }
+            }
+            if !*json {
+                println!("{} matches in {}ms{}", results.hits.len(), results.took_ms,
+                         if results.truncated { " (more available, raise --limit)" } else { "" });
+            }
+            Ok(())
+        }
+        BufferCommand::Query { query, limit, scan, json, agent } => {
+            let result: KqlResult = if *agent {
+                let request = ControlRequest::QueryEvents { query: query.clone(), limit: Some(*limit), max_scan_events: Some(*scan) };
+                serde_json::from_value(control_request(config, &request).await?.data)?
+            } else {
+                let options = KqlOptions { max_rows: *limit, max_scan_events: *scan };
+                query_buffer(&config.buffer, query, &options).inspect_err(|e| {
+                    error!(error_code = %e.code(), error_name = e.code().name, error = %e, "❌ Buffer query failed");
+                })?
+            };
+
+            if *json {
+                for row in &result.rows {
+                    let object: serde_json::Map<String, serde_json::Value> = result.columns.iter().cloned().zip(row.iter().cloned()).collect();
+                    println!("{}", serde_json::to_string(&object)?);
+                }
+                return Ok(());
+            }
+            println!("{}", result.columns.join("\t"));
+            for row in &result.rows {
+                let cells: Vec<String> = row.iter()
+                    .map(|value| match value {
+                        serde_json::Value::String(text) => text.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect();
+                println!("{}", cells.join("\t"));
+            }
+            println!("{} rows from {} buffered events in {}ms{}", result.rows.len(), result.scanned, result.took_ms,
+                     if result.truncated { " (more available, raise --limit)" } else { "" });
+            if result.skipped > 0 {
+                warn!("⚠️ Skipped {} buffered events that could not be decoded", result.skipped);
+            }
+            Ok(())
+        }
+        BufferCommand::Export { format, output, source, since, until, limit } => {
+            let options = ExportOptions {
+                format: *format,
+                source: source.clone(),
+                since: time_bound(since, "--since")?,
+                until: time_bound(until, "--until")?,
+                limit: *limit,
+            };
+            let file = std::io::BufWriter::new(std::fs::File::create(output)?);
+            let summary = export_buffer(&config.buffer, &options, file).inspect_err(|e| {
+                error!(error_code = %e.code(), error_name = e.code().name, error = %e, "❌ Buffer export failed");
+            })?;
+            if summary.skipped > 0 {
+                warn!("⚠️ Skipped {} buffered events that could not be decoded", summary.skipped);
+            }
+            info!(action = "buffer_export", exported = summary.exported, output = %output.display(),
+                  "✅ Exported {} buffered events", summary.exported);
+            Ok(())
+        }
+        BufferCommand::DeadLetters { action } => run_dead_letter_command(config, action).await,
+    }
+}
+
+/// Listing, inspecting and exporting read the buffer on disk; re-driving changes it, so the running agent does it
+#[cfg(feature = "persistent-storage")]
+async fn run_dead_letter_command(config: &AgentConfig, command: &DeadLetterCommand) -> Result<(), Box<dyn std::error::Error>> {
+    use securewatch_agent::buffer_export::{list_dead_letters, read_dead_letters};
+    use std::io::Write;
+
+    let log_error = |e: &securewatch_agent::errors::BufferError| {
+        error!(error_code = %e.code(), error_name = e.code().name, error = %e, "❌ Reading dead letters failed");
+    };
+    match command {
+        DeadLetterCommand::List { limit, json } => {
+            let batches = list_dead_letters(&config.buffer, *limit).inspect_err(log_error)?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&batches)?);
+                return Ok(());
+            }
+            for batch in &batches {
+                println!("{:>6}  {}  {:>6} events  {} attempts  {}",
+                         batch.id, batch.failed_at.to_rfc3339(), batch.event_count, batch.attempts, batch.error);
+            }
+            println!("{} dead-lettered batches", batches.len());
+        }
+        DeadLetterCommand::Inspect { id } => {
+            match read_dead_letters(&config.buffer, &[*id]).inspect_err(log_error)?.pop() {
+                Some((batch, None)) => println!("{}", serde_json::to_string_pretty(&batch)?),
+                Some((_, Some(reason))) => return Err(format!("dead-letter batch {} cannot be read: {}", id, reason).into()),
+                None => return Err(format!("no dead-letter batch {}", id).into()),
+            }
+        }
+        DeadLetterCommand::Redrive { ids, .. } => {
+            // An empty list re-drives every batch; clap only allows it with --all
+            let response = control_request(config, &ControlRequest::RedriveDeadLetters { ids: ids.clone() }).await?;
+            println!("{}", response.message);
+        }
+        DeadLetterCommand::Export { output, ids } => {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(output)?);
+            let mut exported = 0;
+            for (batch, failure) in read_dead_letters(&config.buffer, ids).inspect_err(log_error)? {
+                if let Some(reason) = failure {
+                    warn!("⚠️ Skipping dead-letter batch {}: {}", batch.id, reason);
+                    continue;
+                }
+                serde_json::to_writer(&mut file, &batch)?;
+                file.write_all(b"\n")?;
+                exported += 1;
+            }
+            file.flush()?;
+            info!(action = "dead_letter_export", exported, output = %output.display(), "✅ Exported {} dead-lettered batches", exported);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "persistent-storage"))]
+async fn run_buffer_command(_config: &AgentConfig, _command: &BufferCommand) -> Result<(), Box<dyn std::error::Error>> {
+    error!("❌ Buffer commands require the persistent-storage feature");
+    Err("persistent storage is not available in this build".into())
+}
+
+async fn run_ingest_command(config: &AgentConfig, command: &IngestCommand) -> Result<(), Box<dyn std::error::Error>> {
+    // Without a persistent buffer the pauses live only in the running agent, so ask it over the control socket
+    if !config.buffer.persistent || !cfg!(feature = "persistent-storage") {
+        let request = match command {
+            IngestCommand::Pause { source, until, reason } => ControlRequest::PauseIngest {
+                source: source.clone(),
+                until: until.as_deref()
+                    .map(|v| parse_pause_until(v).ok_or_else(|| format!("invalid --until value '{}'", v)))
+                    .transpose()?,
+                reason: reason.clone(),
+            },
+            IngestCommand::Resume { source, all } => ControlRequest::ResumeIngest { source: source.clone(), all: *all },
+            IngestCommand::Status { .. } => ControlRequest::IngestStatus,
+        };
+        let response = control_request(config, &request).await?;
+        match command {
+            IngestCommand::Status { json } => {
+                let status: IngestPauseStatus = serde_json::from_value(response.data)?;
+                print_ingest_pauses(&status.pauses, *json)?;
+            }
+            _ => println!("{}", response.message),
+        }
+        return Ok(());
+    }
+    let pauses = IngestPauses::open(&config.buffer)?;
+    let audit_log = config.admin_audit.enabled.then(|| AdminAuditLog::open(&config.admin_audit)).transpose()?;
+    let audit = |action: AuditAction, details: &str| {
+        if let Some(audit_log) = &audit_log {
+            audit_log.record_or_warn(action, &admin_audit::cli_actor(), details, true);
+        }
+    };
+
+    match command {
+        IngestCommand::Pause { source, until, reason } => {
+            let until = until.as_deref()
+                .map(|v| parse_pause_until(v).ok_or_else(|| format!("invalid --until value '{}'", v)))
+                .transpose()?;
+            let pause = pauses.pause(source.as_deref(), until, reason.clone())?;
+            audit(AuditAction::IngestPaused, &pause.describe());
+            match pause.until {
+                Some(until) => println!("Ingest from {} paused until {}", pause.scope(), until.to_rfc3339()),
+                None => println!("Ingest from {} paused until resumed", pause.scope()),
+            }
+        }
+        IngestCommand::Resume { source, all } => {
+            if *all {
+                let lifted = pauses.resume_all()?;
+                audit(AuditAction::IngestResumed, &format!("{} pauses lifted", lifted));
+                println!("{} pauses lifted", lifted);
+            } else if pauses.resume(source.as_deref())? {
+                audit(AuditAction::IngestResumed, source.as_deref().unwrap_or("all sources"));
+                println!("Ingest from {} resumed", source.as_deref().unwrap_or("all sources"));
+            } else {
+                println!("Ingest from {} was not paused", source.as_deref().unwrap_or("all sources"));
+            }
+        }
+        IngestCommand::Status { json } => {
+            print_ingest_pauses(&pauses.status().pauses, *json)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_ingest_pauses(pauses: &[IngestPause], json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(pauses)?);
+    } else if pauses.is_empty() {
+        println!("Ingest is not paused");
+    } else {
+        for pause in pauses {
+            println!("{} paused since {} {}{}", pause.scope(), pause.paused_at.to_rfc3339(),
+                     pause.until.map(|until| format!("until {}", until.to_rfc3339())).unwrap_or_else(|| "until resumed".to_string()),
+                     pause.reason.as_deref().map(|reason| format!(" ({})", reason)).unwrap_or_default());
+        }
+    }
+    Ok(())
+}
+
+/// Send one request to the running agent's control socket; warnings are logged and a refusal becomes an error
+async fn control_request(config: &AgentConfig, request: &ControlRequest) -> Result<ControlResponse, Box<dyn std::error::Error>> {
+    let socket = Path::new(&config.control_socket.socket_path);
+    let response = control_socket::request(socket, request).await.inspect_err(|e| {
+        error!(error = %e, socket = %socket.display(), "❌ Could not reach the agent's control socket (is the agent running with control_socket enabled?)");
+    })?;
+    for warning in &response.warnings {
+        warn!("⚠️ {}", warning);
+    }
+    if !response.success {
+        for e in &response.errors {
+            error!("❌ {}", e);
+        }
+        return Err(response.message.into());
+    }
+    Ok(response)
+}
+
+#[cfg(unix)]
+async fn run_tail_command(config: &AgentConfig, request: &TailRequest, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let socket = Path::new(&config.live_tail.socket_path);
+    let mut client = TailClient::connect(socket, request).await.inspect_err(|e| {
+        error!(error = %e, socket = %socket.display(), "❌ Could not reach the agent's live tail (is the agent running with live_tail enabled?)");
+    })?;
+    while let Some(line) = client.next().await? {
+        match line {
+            TailLine::Event(event) if json => println!("{}", serde_json::to_string(&event)?),
+            TailLine::Event(event) => println!("{} {} [{}] {}", event.timestamp.to_rfc3339(), event.source,
+                                              event.level.as_deref().unwrap_or("-"), event.message),
+            TailLine::Lagged(skipped) => eprintln!("... {} events skipped while falling behind", skipped),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn run_tail_command(_config: &AgentConfig, _request: &TailRequest, _json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    Err("the live tail socket needs a Unix platform".into())
+}
+
+async fn run_diagnostics_command(config: &AgentConfig, log_dir: &Path, command: &DiagnosticsCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let DiagnosticsCommand::Collect { output, no_status } = command;
+    let mut bundle = DiagnosticBundle::collect(config, log_dir, "collect");
+
+    let resources = match ResourceMonitor::new(config.resource_monitor.clone()) {
+        Ok(monitor) => monitor.get_current_metrics().await,
+        Err(e) => Err(e),
+    };
+    bundle.add_result("resources.json", resources);
+
+    // A stopped or wedged agent must not keep the bundle from being written
+    if config.management.enabled && !no_status {
+        let status = match tokio::time::timeout(std::time::Duration::from_secs(10), management::query_status(&config.management)).await {
+            Ok(result) => result.map_err(|e| e.to_string()),
+            Err(_) => Err("timed out waiting for the management API".to_string()),
+        };
+        if let Err(e) = &status {
+            warn!(error = %e, "⚠️  Running agent's status not included");
+        }
+        bundle.add_result("status.json", status);
+    }
+
+    let output = output.clone().unwrap_or_else(|| PathBuf::from(diagnostics::default_bundle_name()));
+    bundle.save(&output).inspect_err(|e| {
+        error!(error = %e, output = %output.display(), "❌ Could not write the diagnostic bundle");
+    })?;
+    info!(
+        action = "diagnostics_collect",
+        output = %output.display(),
+        files = bundle.file_names().count(),
+        "✅ Diagnostic bundle written"
+    );
+    Ok(())
+}
+
+fn run_audit_command(config: &AgentConfig, command: &AuditCommand) -> Result<(), Box<dyn std::error::Error>> {
+    use securewatch_agent::event_index::parse_time_bound;
+
+    if !config.admin_audit.enabled {
+        warn!("⚠️ The administrative audit log is disabled; showing what {} holds", config.admin_audit.path);
+    }
+    let audit_log = AdminAuditLog::open(&config.admin_audit)?;
+
+    match command {
+        AuditCommand::Show { since, action, limit, json } => {
+            let since = since.as_deref()
+                .map(|v| parse_time_bound(v).ok_or_else(|| format!("invalid --since value '{}'", v)))
+                .transpose()?;
+            let action = action.as_deref()
+                .map(|v| AuditAction::parse(v).ok_or_else(|| format!("unknown audit action '{}'", v)))
+                .transpose()?;
+            for entry in audit_log.query(since, action, *limit)? {
+                if *json {
+                    println!("{}", serde_json::to_string(&entry)?);
+                } else {
+                    println!("{:>6} {} {:<24} {} {}{}", entry.sequence, entry.timestamp.to_rfc3339(), entry.action.as_str(),
+                             entry.actor, if entry.success { "" } else { "FAILED " }, entry.details);
+                }
+            }
+        }
+        AuditCommand::Verify { json } => {
+            let verification = audit_log.verify()?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&verification)?);
+            } else if verification.valid {
+                println!("{}: {} entries, chain intact (head {})", audit_log.path().display(), verification.entries,
+                         verification.head_hash.as_deref().unwrap_or("none"));
+            }
+            if let Some(reason) = verification.reason {
+                return Err(format!("{}: chain broken at line {}: {}", audit_log.path().display(),
+                                   verification.broken_at_line.unwrap_or(0), reason).into());
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_secret_command(command: &SecretCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let (SecretCommand::Set { reference } | SecretCommand::Check { reference } | SecretCommand::Delete { reference }) = command;
+    let reference = KeyringReference::parse(reference)
+        .unwrap_or_else(|| Err(format!("'{}' is not a keyring:<service>/<account> reference", reference)))?;
+
+    match command {
+        SecretCommand::Set { .. } => {
+            // Read from stdin so the secret stays out of shell history and process listings
+            let mut secret = String::new();
+            std::io::stdin().read_line(&mut secret)?;
+            let secret = secret.trim_end_matches(['\r', '\n']);
+            if secret.is_empty() {
+                return Err("no secret on stdin".into());
+            }
+            reference.set(secret).await?;
+            println!("Stored {} in the {}", reference, KeyringReference::store_name());
+        }
+        SecretCommand::Check { .. } => {
+            let secret = reference.get().await?;
+            println!("{} resolves to a {}-character secret in the {}", reference, secret.chars().count(), KeyringReference::store_name());
+        }
+        SecretCommand::Delete { .. } => {
+            if reference.delete().await? {
+                println!("Removed {} from the {}", reference, KeyringReference::store_name());
+            } else {
+                println!("{} was not in the {}", reference, KeyringReference::store_name());
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_parsers_command(config: &AgentConfig, command: &ParsersCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let ParsersCommand::Test { file, parser, source, show, max_failures, json } = command else {
+        let response = control_request(config, &ControlRequest::ReloadParsers).await?;
+        println!("{}", response.message);
+        return Ok(());
+    };
+    if let Some(name) = parser {
+        if !config.parsers.parsers.iter().any(|d| &d.name == name) {
+            let available: Vec<&str> = config.parsers.parsers.iter().map(|d| d.name.as_str()).collect();
+            error!("❌ No configured parser named '{}' (available: {})", name, available.join(", "));
+            return Err(format!("unknown parser '{}'", name).into());
+        }
+    }
+
+    let harness = ParserHarness::new(&config.parsers, parser.as_deref(), source.as_deref()).inspect_err(|e| {
+        error!(error_code = %e.code(), error_name = e.code().name, error = %e, "❌ Cannot build the parsers under test");
+    })?;
+    let report = if file.as_os_str() == "-" {
+        harness.run(std::io::stdin().lock(), *show, *max_failures).await?
+    } else {
+        harness.run(std::io::BufReader::new(std::fs::File::open(file)?), *show, *max_failures).await?
+    };
+
+    if *json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    for warning in &report.warnings {
+        println!("⚠️  {}", warning);
+    }
+    for result in &report.results {
+        match &result.parser {
+            Some(parser) => {
+                let fields: Vec<String> = result.fields.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+                match result.event_time {
+                    Some(event_time) => println!("line {} ✅ {} @ {}: {}", result.line_number, parser, event_time.to_rfc3339(), fields.join(" ")),
+                    None => println!("line {} ✅ {}: {}", result.line_number, parser, fields.join(" ")),
+                }
+            }
+            None => {
+                println!("line {} ❌ {}", result.line_number, result.line);
+                for error in &result.errors {
+                    println!("    {}", error);
+                }
+            }
+        }
+    }
+    println!("Parsed {}/{} lines ({:.1}%)", report.matched, report.lines, report.match_rate());
+    for parser in &report.parsers {
+        println!("  {} ({}): {}", parser.name, parser.source_type, parser.matched);
+    }
+    if !report.field_coverage.is_empty() {
+        println!("Field coverage:");
+        for (field, count) in &report.field_coverage {
+            println!("  {}: {}/{}", field, count, report.matched);
+        }
+    }
+    if !report.never_extracted.is_empty() {
+        println!("Never extracted: {}", report.never_extracted.join(", "));
+    }
+    if !report.schema_violations.is_empty() {
+        println!("Schema violations:");
+        for (field, count) in &report.schema_violations {
+            println!("  {}: {}/{}", field, count, report.matched);
+        }
+    }
     Ok(())
 }
 
@@ -1,17 +1,27 @@
 // Remote management gRPC server for agent control and monitoring
 
+use crate::admin_audit::{AdminAuditLog, AuditAction, AuditEntry};
+use crate::agent_status::{AgentStatus, BufferHealth, CertificateHealth, CollectorHealth, TransportHealth};
 use crate::config::ManagementConfig;
 use crate::errors::ManagementError;
+use crate::bandwidth::{BandwidthLimiter, BandwidthStats};
 use crate::buffer::BufferStats;
 use crate::collectors::CollectorStatus;
+use crate::ingest_pause::{IngestPause, IngestPauses};
+use crate::live_tail::{LiveTail, TailFilter, TailItem};
 use crate::parsers::ParserStats;
+use crate::parsers::samples::UnmatchedSampleStore;
 use crate::transport::TransportStats;
+use crate::utils::AgentStats;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 use tonic::{transport::Server, Request, Response, Status};
 use tracing::{info, warn, error, debug};
 
+/// Whether this build serves the gRPC management API
+pub const GRPC_ENABLED: bool = true;
+
 // Include generated gRPC code
 pub mod agent_management {
     tonic::include_proto!("agent_management");
@@ -32,6 +42,12 @@ pub struct AgentManagementService {
     collector_statuses: Arc<RwLock<Vec<CollectorStatus>>>,
     parser_stats: Arc<RwLock<Vec<ParserStats>>>,
     transport_stats: Arc<RwLock<Option<TransportStats>>>,
+    parser_samples: Option<Arc<UnmatchedSampleStore>>,
+    agent_stats: Option<Arc<RwLock<AgentStats>>>,
+    ingest_pauses: Option<Arc<IngestPauses>>,
+    bandwidth_limiter: Option<Arc<BandwidthLimiter>>,
+    live_tail: Option<Arc<LiveTail>>,
+    audit_log: Option<Arc<AdminAuditLog>>,
     
     // Runtime statistics
     events_processed: Arc<Mutex<u64>>,
@@ -57,6 +73,12 @@ impl AgentManagementService {
             collector_statuses: Arc::new(RwLock::new(Vec::new())),
             parser_stats: Arc::new(RwLock::new(Vec::new())),
             transport_stats: Arc::new(RwLock::new(None)),
+            parser_samples: None,
+            agent_stats: None,
+            ingest_pauses: None,
+            bandwidth_limiter: None,
+            live_tail: None,
+            audit_log: None,
             events_processed: Arc::new(Mutex::new(0)),
             events_sent: Arc::new(Mutex::new(0)),
             events_failed: Arc::new(Mutex::new(0)),
@@ -95,6 +117,41 @@ impl AgentManagementService {
         });
     }
     
+    pub fn set_parser_sample_store(&mut self, store: Arc<UnmatchedSampleStore>) {
+        self.parser_samples = Some(store);
+    }
+    
+    /// Source of error counts and the last error reported by health checks
+    pub fn set_agent_stats(&mut self, stats: Arc<RwLock<AgentStats>>) {
+        self.agent_stats = Some(stats);
+    }
+    
+    /// Registry behind the pauses reported by health checks
+    pub fn set_ingest_pauses(&mut self, pauses: Arc<IngestPauses>) {
+        self.ingest_pauses = Some(pauses);
+    }
+    
+    /// Upload cap behind SetBandwidthLimit; its live stats replace the transport snapshot's
+    pub fn set_bandwidth_limiter(&mut self, limiter: Arc<BandwidthLimiter>) {
+        self.bandwidth_limiter = Some(limiter);
+    }
+    
+    /// Source of parsed events for the TailEvents stream
+    pub fn set_live_tail(&mut self, tail: Arc<LiveTail>) {
+        self.live_tail = Some(tail);
+    }
+    
+    /// Audit log that state-changing calls are recorded in and GetAuditLog reads
+    pub fn set_audit_log(&mut self, audit_log: Arc<AdminAuditLog>) {
+        self.audit_log = Some(audit_log);
+    }
+    
+    fn audit(&self, action: AuditAction, actor: &str, details: &str, success: bool) {
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record_or_warn(action, actor, details, success);
+        }
+    }
+    
     pub fn set_config_reload_callback<F>(&mut self, callback: F)
     where
         F: Fn() -> Result<(), String> + Send + Sync + 'static,
@@ -166,14 +223,39 @@ impl AgentManagement for AgentManagementService {
             .filter(|status| status.running)
             .count() as i32;
         
+        let (error_counts, last_error) = match &self.agent_stats {
+            Some(stats) => {
+                let stats = stats.read().await;
+                let counts = stats.error_counts.iter().map(|(name, count)| (name.to_string(), *count)).collect();
+                let last = stats.last_error.as_ref().map(|report| ErrorInfo {
+                    code: report.code.code as u32,
+                    name: report.code.name.to_string(),
+                    category: format!("{:?}", report.category),
+                    severity: report.severity.to_string(),
+                    retryable: report.retryable,
+                    message: report.message.clone(),
+                    occurred_at: report.occurred_at.timestamp(),
+                });
+                (counts, last)
+            }
+            None => (Default::default(), None),
+        };
+        
+        let ingest_pauses: Vec<IngestPauseInfo> = self.ingest_pauses.as_ref()
+            .map(|p| p.status().pauses.iter().map(ingest_pause_info).collect())
+            .unwrap_or_default();
+        
         let response = HealthResponse {
-            status: "healthy".to_string(),
+            status: if ingest_pauses.is_empty() { "healthy" } else { "paused" }.to_string(),
             agent_id: self.agent_id.clone(),
             version: env!("CARGO_PKG_VERSION").to_string(),
             uptime_seconds: self.start_time.elapsed().as_secs() as i64,
             system_resources: Some(system_resources),
             backpressure_active: buffer_stats.backpressure_active,
             active_collectors,
+            error_counts,
+            last_error,
+            ingest_pauses,
         };
         
         Ok(Response::new(response))
@@ -227,6 +309,7 @@ impl AgentManagement for AgentManagementService {
     async fn set_log_level(&self, request: Request<LogLevelRequest>) -> Result<Response<LogLevelResponse>, Status> {
         self.validate_auth_token(&request)?;
         
+        let actor = audit_actor(&request);
         let req = request.into_inner();
         let new_level = req.level.to_lowercase();
         
@@ -235,6 +318,7 @@ impl AgentManagement for AgentManagementService {
         // Validate log level
         let valid_levels = ["trace", "debug", "info", "warn", "error"];
         if !valid_levels.contains(&new_level.as_str()) {
+            self.audit(AuditAction::LogLevelChanged, &actor, &format!("invalid level {}", new_level), false);
             return Ok(Response::new(LogLevelResponse {
                 success: false,
                 message: format!("Invalid log level '{}'. Valid levels: {:?}", new_level, valid_levels),
@@ -246,6 +330,7 @@ impl AgentManagement for AgentManagementService {
         // In a real implementation, you'd change the tracing subscriber's max level here
         // For now, we'll just acknowledge the request
         warn!("⚠️  Dynamic log level changes not fully implemented");
+        self.audit(AuditAction::LogLevelChanged, &actor, &new_level, true);
         
         let response = LogLevelResponse {
             success: true,
@@ -271,6 +356,7 @@ impl AgentManagement for AgentManagementService {
                 configuration: "{}".to_string(), // Would serialize actual config
                 last_error: "".to_string(),
                 last_activity: chrono::Utc::now().timestamp(),
+                dropped_events: status.dropped_events,
             })
             .collect();
         
@@ -325,7 +411,10 @@ impl AgentManagement for AgentManagementService {
         info!("🔄 Configuration reload requested");
         
         if let Some(callback) = &self.config_reload_callback {
-            match callback() {
+            let result = callback();
+            self.audit(AuditAction::ConfigReloadRequested, &audit_actor(&request),
+                       result.as_ref().err().map_or("", |e| e.as_str()), result.is_ok());
+            match result {
                 Ok(_) => {
                     let response = ReloadConfigResponse {
                         success: true,
@@ -376,12 +465,347 @@ impl AgentManagement for AgentManagementService {
                 average_latency_ms: 0.0, // Would track this
                 last_error: "".to_string(),
                 last_success_timestamp: chrono::Utc::now().timestamp(),
+                certificates: stats.certificates.iter()
+                    .map(|certificate| CertificateExpiry {
+                        role: certificate.role.as_str().to_string(),
+                        path: certificate.path.clone(),
+                        subject: certificate.subject.clone(),
+                        not_after: certificate.not_after.timestamp(),
+                        days_until_expiry: certificate.days_until_expiry,
+                        state: certificate.state.as_str().to_string(),
+                    })
+                    .collect(),
+                bandwidth: self.bandwidth_limiter.as_ref().map(|limiter| limiter.stats())
+                    .or_else(|| stats.bandwidth.clone())
+                    .map(|bandwidth| bandwidth_status(&bandwidth)),
+                uploads_held_reason: stats.upload_schedule.as_ref()
+                    .and_then(|schedule| schedule.reason.clone())
+                    .unwrap_or_default(),
+                next_upload_window: stats.upload_schedule.as_ref()
+                    .and_then(|schedule| schedule.next_window)
+                    .map_or(0, |next| next.timestamp()),
+                proxy: stats.proxy.as_ref().map(|proxy| proxy.url.clone()).unwrap_or_default(),
+                proxy_source: stats.proxy.as_ref().map(|proxy| proxy.source.as_str().to_string()).unwrap_or_default(),
             };
             Ok(Response::new(response))
         } else {
             Err(Status::unavailable("Transport statistics not available"))
         }
     }
+    
+    async fn get_parser_samples(&self, request: Request<ParserSamplesRequest>) -> Result<Response<ParserSamplesResponse>, Status> {
+        self.validate_auth_token(&request)?;
+        
+        let req = request.into_inner();
+        debug!("📡 Parser samples requested (source: '{}')", req.source);
+        
+        let Some(store) = &self.parser_samples else {
+            return Ok(Response::new(ParserSamplesResponse {
+                capture_enabled: false,
+                samples: vec![],
+                sources: vec![],
+            }));
+        };
+        
+        let source = Some(req.source.as_str()).filter(|s| !s.is_empty());
+        let limit = if req.limit == 0 { 100 } else { req.limit as usize };
+        let samples = store.samples(source, limit).into_iter()
+            .map(|sample| ParserSample {
+                captured_at: sample.captured_at.timestamp(),
+                source: sample.source,
+                reason: sample.reason,
+                raw_data: sample.raw_data,
+                metadata: sample.metadata,
+                truncated: sample.truncated,
+            })
+            .collect();
+        let sources = store.summaries().into_iter()
+            .map(|summary| ParserSampleSource {
+                source: summary.source,
+                samples: summary.samples as u64,
+                bytes: summary.bytes as u64,
+                total_captured: summary.total_captured,
+            })
+            .collect();
+        
+        Ok(Response::new(ParserSamplesResponse {
+            capture_enabled: true,
+            samples,
+            sources,
+        }))
+    }
+    
+    type TailEventsStream = std::pin::Pin<Box<dyn futures::Stream<Item = Result<TailedEvent, Status>> + Send>>;
+    
+    async fn tail_events(&self, request: Request<TailEventsRequest>) -> Result<Response<Self::TailEventsStream>, Status> {
+        self.validate_auth_token(&request)?;
+        
+        let req = request.into_inner();
+        let tail = self.live_tail.clone()
+            .ok_or_else(|| Status::unavailable("live tail is not available on this agent"))?;
+        let filter = TailFilter::new(&req.source, &req.level, &req.contains);
+        info!("📡 Live tail started (source: '{}', level: '{}', contains: '{}', {} watching)",
+              req.source, req.level, req.contains, tail.watchers() + 1);
+        
+        let limit = Some(req.max_events as usize).filter(|n| *n > 0);
+        let state = (tail.subscribe(filter), 0usize, 0u64);
+        let stream = futures::stream::unfold(state, move |(mut subscription, sent, mut skipped)| async move {
+            if limit.is_some_and(|limit| sent >= limit) {
+                return None;
+            }
+            loop {
+                match subscription.next().await? {
+                    TailItem::Lagged(count) => skipped += count,
+                    TailItem::Event(event) => {
+                        let tailed = TailedEvent {
+                            timestamp: event.timestamp.timestamp_millis(),
+                            source: event.source.clone(),
+                            level: event.level.clone().unwrap_or_default(),
+                            message: event.message.clone(),
+                            fields_json: serde_json::to_string(&event.fields).unwrap_or_default(),
+                            parser_name: event.parser_name.clone(),
+                            raw_data: event.raw_data.clone(),
+                            skipped,
+                        };
+                        return Some((Ok(tailed), (subscription, sent + 1, 0)));
+                    }
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+    
+    async fn set_bandwidth_limit(&self, request: Request<BandwidthLimitRequest>) -> Result<Response<BandwidthLimitResponse>, Status> {
+        self.validate_auth_token(&request)?;
+        
+        let actor = audit_actor(&request);
+        let req = request.into_inner();
+        let Some(limiter) = self.bandwidth_limiter.clone() else {
+            return Err(Status::unavailable("Bandwidth limiting is not available"));
+        };
+        let max_kb_per_second = Some(req.max_kb_per_second).filter(|kb| *kb > 0);
+        let burst_kb = match req.burst_kb {
+            0 => limiter.stats().burst_kb,
+            burst_kb => burst_kb,
+        };
+        if max_kb_per_second.is_some() && burst_kb == 0 {
+            return Err(Status::invalid_argument("burst_kb must be greater than 0"));
+        }
+        info!("🚦 Bandwidth limit change requested through the management API");
+        
+        limiter.set_limit(max_kb_per_second, burst_kb);
+        let message = match max_kb_per_second {
+            Some(kb) => format!("Uploads capped at {} KB/s with a {} KB burst", kb, burst_kb),
+            None => "Upload cap lifted".to_string(),
+        };
+        self.audit(AuditAction::BandwidthLimitChanged, &actor, &message, true);
+        Ok(Response::new(BandwidthLimitResponse {
+            success: true,
+            message,
+            bandwidth: Some(bandwidth_status(&limiter.stats())),
+        }))
+    }
+    
+    async fn get_audit_log(&self, request: Request<AuditLogRequest>) -> Result<Response<AuditLogResponse>, Status> {
+        self.validate_auth_token(&request)?;
+        
+        let req = request.into_inner();
+        let Some(audit_log) = self.audit_log.clone() else {
+            return Err(Status::unavailable("The administrative audit log is disabled"));
+        };
+        let since = match req.since {
+            0 => None,
+            seconds => Some(chrono::DateTime::from_timestamp(seconds, 0)
+                .ok_or_else(|| Status::invalid_argument("since must be a Unix timestamp"))?),
+        };
+        let action = match req.action.as_str() {
+            "" => None,
+            action => Some(AuditAction::parse(action)
+                .ok_or_else(|| Status::invalid_argument(format!("Unknown audit action '{}'", action)))?),
+        };
+        let limit = match req.limit {
+            0 => 100,
+            limit => limit as usize,
+        };
+        
+        let (entries, verification) = tokio::task::spawn_blocking(move || {
+            Ok::<_, std::io::Error>((audit_log.query(since, action, limit)?, audit_log.verify()?))
+        })
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(|e| Status::internal(e.to_string()))?;
+        if !verification.valid {
+            warn!("🚨 Audit log chain is broken at line {}", verification.broken_at_line.unwrap_or(0));
+        }
+        Ok(Response::new(AuditLogResponse {
+            entries: entries.iter().map(audit_entry_info).collect(),
+            total_entries: verification.entries,
+            chain_valid: verification.valid,
+            broken_at_line: verification.broken_at_line.unwrap_or(0),
+            verification_error: verification.reason.unwrap_or_default(),
+            head_hash: verification.head_hash.unwrap_or_default(),
+        }))
+    }
+}
+
+/// Caller recorded as the actor of audited calls
+fn audit_actor<T>(request: &Request<T>) -> String {
+    match request.remote_addr() {
+        Some(addr) => format!("management_api {}", addr),
+        None => "management_api".to_string(),
+    }
+}
+
+fn audit_entry_info(entry: &AuditEntry) -> AuditEntryInfo {
+    AuditEntryInfo {
+        sequence: entry.sequence,
+        timestamp: entry.timestamp.timestamp(),
+        action: entry.action.as_str().to_string(),
+        actor: entry.actor.clone(),
+        details: entry.details.clone(),
+        success: entry.success,
+        previous_hash: entry.previous_hash.clone(),
+        hash: entry.hash.clone(),
+    }
+}
+
+fn bandwidth_status(stats: &BandwidthStats) -> BandwidthStatus {
+    BandwidthStatus {
+        max_kb_per_second: stats.max_kb_per_second.unwrap_or(0),
+        burst_kb: stats.burst_kb,
+        bytes_sent: stats.bytes_sent,
+        throttled_requests: stats.throttled_requests,
+        throttled_wait_ms: stats.throttled_wait_ms,
+        available_kb: stats.available_kb,
+    }
+}
+
+fn ingest_pause_info(pause: &IngestPause) -> IngestPauseInfo {
+    IngestPauseInfo {
+        source: pause.source.clone().unwrap_or_default(),
+        paused_at: pause.paused_at.timestamp(),
+        until: pause.until.map(|until| until.timestamp()).unwrap_or(0),
+        reason: pause.reason.clone().unwrap_or_default(),
+    }
+}
+
+/// Query a running agent's management API for collector, buffer and transport health
+pub async fn query_status(config: &ManagementConfig) -> Result<AgentStatus, ManagementError> {
+    use agent_management::agent_management_client::AgentManagementClient;
+    use tonic::transport::{Certificate, Channel, ClientTlsConfig};
+
+    // A wildcard bind is reachable on loopback
+    let host = match config.bind_address.as_str() {
+        "" | "0.0.0.0" => "127.0.0.1",
+        "::" => "[::1]",
+        address => address,
+    };
+    let scheme = if config.tls.enabled { "https" } else { "http" };
+    let endpoint = format!("{}://{}:{}", scheme, host, config.port);
+    let unavailable = |reason: String| ManagementError::ServiceUnavailable {
+        service: endpoint.clone(),
+        reason,
+        estimated_recovery: None,
+    };
+
+    let mut channel = Channel::from_shared(endpoint.clone()).map_err(|e| unavailable(e.to_string()))?;
+    if config.tls.enabled {
+        // The agent's own certificate is the trust anchor; it always covers localhost
+        let tls = crate::management_tls::ManagementTlsManager::new(config.tls.clone(), &config.bind_address);
+        let cert_path = tls.cert_path();
+        let pem = std::fs::read(&cert_path).map_err(|e| ManagementError::CertificateError {
+            operation: "read".to_string(),
+            path: cert_path.to_string_lossy().to_string(),
+            reason: e.to_string(),
+        })?;
+        channel = channel
+            .tls_config(ClientTlsConfig::new().ca_certificate(Certificate::from_pem(pem)).domain_name("localhost"))
+            .map_err(|e| unavailable(e.to_string()))?;
+    }
+    let mut client = AgentManagementClient::new(channel.connect().await.map_err(|e| unavailable(e.to_string()))?);
+
+    let request = || {
+        let mut request = Request::new(Empty {});
+        if let Some(token) = &config.auth_token {
+            if let Ok(value) = format!("Bearer {}", token).parse() {
+                request.metadata_mut().insert("authorization", value);
+            }
+        }
+        request
+    };
+    let call_error = |method: &str, status: Status| match status.code() {
+        tonic::Code::Unauthenticated | tonic::Code::PermissionDenied => ManagementError::AuthorizationFailed {
+            operation: method.to_string(),
+            required_permission: "management auth_token".to_string(),
+            user_id: None,
+        },
+        _ => ManagementError::GrpcError {
+            service: "AgentManagement".to_string(),
+            method: method.to_string(),
+            source: Box::new(status),
+        },
+    };
+
+    let health = client.get_health(request()).await.map_err(|e| call_error("GetHealth", e))?.into_inner();
+    let collectors = client.get_collector_status(request()).await.map_err(|e| call_error("GetCollectorStatus", e))?.into_inner();
+    let buffer = client.get_buffer_stats(request()).await.map_err(|e| call_error("GetBufferStats", e))?.into_inner();
+    let transport = client.get_transport_stats(request()).await.map_err(|e| call_error("GetTransportStats", e))?.into_inner();
+
+    let non_empty = |value: String| (!value.is_empty()).then_some(value);
+    let timestamp = |secs: i64| (secs > 0).then(|| chrono::DateTime::from_timestamp(secs, 0)).flatten();
+    Ok(AgentStatus {
+        agent_id: health.agent_id,
+        version: health.version,
+        status: health.status,
+        uptime_seconds: health.uptime_seconds.max(0) as u64,
+        paused_sources: health.ingest_pauses.into_iter()
+            .map(|pause| if pause.source.is_empty() { "all".to_string() } else { pause.source })
+            .collect(),
+        collectors: collectors.collectors.into_iter()
+            .map(|c| CollectorHealth {
+                name: c.name,
+                source_type: c.source_type,
+                running: c.running,
+                last_error: non_empty(c.last_error),
+                last_activity: timestamp(c.last_activity),
+                dropped_events: c.dropped_events,
+            })
+            .collect(),
+        buffer: BufferHealth {
+            memory_events: buffer.memory_events,
+            disk_events: buffer.disk_events,
+            total_bytes: buffer.total_bytes,
+            events_processed: buffer.events_processed,
+            events_dropped: buffer.events_dropped,
+            memory_usage_percent: buffer.memory_usage_percent,
+            disk_usage_percent: buffer.disk_usage_percent,
+            backpressure_active: buffer.backpressure_active,
+        },
+        transport: TransportHealth {
+            server_url: transport.server_url,
+            tls_enabled: transport.tls_enabled,
+            requests_sent: transport.requests_sent,
+            requests_failed: transport.requests_failed,
+            bytes_sent: transport.bytes_sent,
+            average_latency_ms: transport.average_latency_ms,
+            last_error: non_empty(transport.last_error),
+            last_success: timestamp(transport.last_success_timestamp),
+            certificates: transport.certificates.into_iter()
+                .map(|c| CertificateHealth {
+                    role: c.role,
+                    path: c.path,
+                    subject: c.subject,
+                    not_after: timestamp(c.not_after),
+                    days_until_expiry: c.days_until_expiry,
+                    state: c.state,
+                })
+                .collect(),
+            uploads_held: non_empty(transport.uploads_held_reason),
+            next_upload_window: timestamp(transport.next_upload_window),
+            bandwidth_limit_kb_per_second: transport.bandwidth
+                .and_then(|bandwidth| (bandwidth.max_kb_per_second > 0).then_some(bandwidth.max_kb_per_second)),
+        },
+    })
 }
 
 pub struct ManagementServer {
@@ -416,6 +840,10 @@ impl ManagementServer {
     pub fn get_service(&self) -> &AgentManagementService {
         &self.service
     }
+    
+    pub fn get_service_mut(&mut self) -> &mut AgentManagementService {
+        &mut self.service
+    }
 }
 
 #[cfg(test)]
@@ -430,6 +858,7 @@ mod tests {
             bind_address: "127.0.0.1".to_string(),
             port: 9091,
             auth_token: None,
+            tls: crate::management_tls::ManagementTlsConfig::default(),
         };
         
         let buffer_stats = Arc::new(Mutex::new(BufferStats {
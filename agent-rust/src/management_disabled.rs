@@ -1,11 +1,24 @@
 // Minimal management server stub when gRPC is disabled
 
+use crate::agent_status::AgentStatus;
 use crate::config::ManagementConfig;
 use crate::errors::ManagementError;
 use crate::buffer::BufferStats;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::info;
+use tracing::warn;
+
+/// Whether this build serves the gRPC management API
+pub const GRPC_ENABLED: bool = false;
+
+/// Without gRPC there is no management API to query
+pub async fn query_status(config: &ManagementConfig) -> Result<AgentStatus, ManagementError> {
+    Err(ManagementError::ServiceUnavailable {
+        service: format!("{}:{}", config.bind_address, config.port),
+        reason: "this build has no gRPC management support".to_string(),
+        estimated_recovery: None,
+    })
+}
 
 pub struct ManagementServer {
     config: ManagementConfig,
@@ -22,7 +35,8 @@ impl ManagementServer {
     
     pub async fn start(&self) -> Result<(), ManagementError> {
         if self.config.enabled {
-            info!("🚫 Management server requested but gRPC support is disabled");
+            warn!("🚫 Management server requested but this build has no gRPC support; push configuration, reload \
+                   parsers and read collector status through the local control socket instead");
         }
         Ok(())
     }
@@ -0,0 +1,430 @@
+// TLS certificate provisioning for the management listener
+// Generates a self-signed (or enrollment-CA-issued) certificate on first start, rotates it
+// before expiry and exposes its SHA-256 fingerprint so clients can pin it
+
+use crate::errors::ManagementError;
+use base64::Engine;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use rcgen::{CertificateParams, DnType, KeyPair};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+const CERT_FILE: &str = "management.crt";
+const KEY_FILE: &str = "management.key";
+
+/// Management listener TLS configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManagementTlsConfig {
+    /// Serve the management API over TLS
+    pub enabled: bool,
+    /// Directory holding the generated certificate and key
+    pub cert_dir: String,
+    /// Additional DNS names or IP addresses for the certificate (bind address and hostname are always included)
+    pub subject_alt_names: Vec<String>,
+    /// Enrollment CA certificate; when set together with `ca_key_path` the certificate is CA-issued
+    pub ca_cert_path: Option<String>,
+    /// Enrollment CA private key
+    pub ca_key_path: Option<String>,
+    /// Lifetime of generated certificates
+    pub validity_days: u32,
+    /// Regenerate the certificate when it expires within this many days
+    pub renew_before_days: u32,
+    /// How often the rotation task checks the certificate
+    pub check_interval_seconds: u64,
+}
+
+impl Default for ManagementTlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cert_dir: "./certs/management".to_string(),
+            subject_alt_names: Vec::new(),
+            ca_cert_path: None,
+            ca_key_path: None,
+            validity_days: 90,
+            renew_before_days: 14,
+            check_interval_seconds: 3600,
+        }
+    }
+}
+
+impl ManagementTlsConfig {
+    /// Audit the settings, returning a message for every problem found
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.enabled {
+            return errors;
+        }
+
+        if self.cert_dir.trim().is_empty() {
+            errors.push("Management TLS cert_dir cannot be empty".to_string());
+        }
+        if self.validity_days == 0 {
+            errors.push("Management TLS validity_days must be greater than 0".to_string());
+        }
+        if self.renew_before_days >= self.validity_days {
+            errors.push(format!(
+                "Management TLS renew_before_days ({}) must be less than validity_days ({})",
+                self.renew_before_days, self.validity_days
+            ));
+        }
+        if self.check_interval_seconds == 0 {
+            errors.push("Management TLS check_interval_seconds must be greater than 0".to_string());
+        }
+        if self.ca_cert_path.is_some() != self.ca_key_path.is_some() {
+            errors.push("Management TLS ca_cert_path and ca_key_path must be set together".to_string());
+        }
+
+        errors
+    }
+}
+
+/// Who signed the management certificate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateIssuer {
+    SelfSigned,
+    EnrollmentCa,
+}
+
+/// The certificate currently served by the management listener
+#[derive(Debug, Clone, Serialize)]
+pub struct ManagementCertificate {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// Colon-separated SHA-256 fingerprint of the leaf certificate, for client pinning
+    pub fingerprint_sha256: String,
+    pub not_after: DateTime<Utc>,
+    pub issuer: CertificateIssuer,
+    /// Whether the certificate was (re)generated by this call
+    pub generated: bool,
+}
+
+pub struct ManagementTlsManager {
+    config: ManagementTlsConfig,
+    subject_alt_names: Vec<String>,
+    current: parking_lot::RwLock<Option<ManagementCertificate>>,
+}
+
+impl ManagementTlsManager {
+    pub fn new(config: ManagementTlsConfig, bind_address: &str) -> Self {
+        let mut subject_alt_names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+        if let Ok(hostname) = hostname::get() {
+            subject_alt_names.push(hostname.to_string_lossy().to_string());
+        }
+        if !bind_address.is_empty() && bind_address != "0.0.0.0" && bind_address != "::" {
+            subject_alt_names.push(bind_address.to_string());
+        }
+        subject_alt_names.extend(config.subject_alt_names.iter().cloned());
+        // Keep the first occurrence of each name, wherever its duplicates are
+        let mut seen = std::collections::HashSet::new();
+        subject_alt_names.retain(|name| seen.insert(name.clone()));
+
+        Self {
+            config,
+            subject_alt_names,
+            current: parking_lot::RwLock::new(None),
+        }
+    }
+
+    pub fn config(&self) -> &ManagementTlsConfig {
+        &self.config
+    }
+
+    /// Certificate from the most recent `ensure_certificate` call
+    pub fn current_certificate(&self) -> Option<ManagementCertificate> {
+        self.current.read().clone()
+    }
+
+    pub fn cert_path(&self) -> PathBuf {
+        Path::new(&self.config.cert_dir).join(CERT_FILE)
+    }
+
+    pub fn key_path(&self) -> PathBuf {
+        Path::new(&self.config.cert_dir).join(KEY_FILE)
+    }
+
+    fn issuer(&self) -> CertificateIssuer {
+        if self.config.ca_cert_path.is_some() && self.config.ca_key_path.is_some() {
+            CertificateIssuer::EnrollmentCa
+        } else {
+            CertificateIssuer::SelfSigned
+        }
+    }
+
+    /// Load the existing certificate, generating a new one when it is missing, unreadable,
+    /// close to expiry or no longer covers the configured names
+    pub fn ensure_certificate(&self) -> Result<ManagementCertificate, ManagementError> {
+        let certificate = match self.load_existing() {
+            Ok(Some(certificate)) => certificate,
+            Ok(None) => self.generate()?,
+            Err(e) => {
+                warn!("⚠️ Existing management certificate is unusable, regenerating: {}", e);
+                self.generate()?
+            }
+        };
+
+        if certificate.generated {
+            info!("🔏 Generated {:?} management certificate valid until {} (SHA-256 {})",
+                  certificate.issuer, certificate.not_after, certificate.fingerprint_sha256);
+        }
+        *self.current.write() = Some(certificate.clone());
+        Ok(certificate)
+    }
+
+    fn load_existing(&self) -> Result<Option<ManagementCertificate>, ManagementError> {
+        let cert_path = self.cert_path();
+        let key_path = self.key_path();
+        if !cert_path.exists() || !key_path.exists() {
+            return Ok(None);
+        }
+
+        let cert_pem = std::fs::read_to_string(&cert_path).map_err(|e| cert_error("read_certificate", &cert_path, e))?;
+        let leaf_der = first_certificate_der(&cert_pem).ok_or_else(|| cert_error("parse_certificate", &cert_path, "no PEM certificate found"))?;
+        let params = CertificateParams::from_ca_cert_der(&leaf_der.clone().into())
+            .map_err(|e| cert_error("parse_certificate", &cert_path, e))?;
+
+        let not_after = Utc
+            .timestamp_opt(params.not_after.unix_timestamp(), 0)
+            .single()
+            .ok_or_else(|| cert_error("parse_certificate", &cert_path, "invalid expiry"))?;
+        let renew_at = not_after - Duration::days(self.config.renew_before_days as i64);
+        if crate::chaos::now() >= renew_at {
+            info!("🔄 Management certificate expires {}, rotating", not_after);
+            return Ok(None);
+        }
+
+        let chained = cert_pem.matches("-----BEGIN CERTIFICATE-----").count() > 1;
+        if chained != (self.issuer() == CertificateIssuer::EnrollmentCa) {
+            info!("🔄 Management certificate issuer changed to {:?}, rotating", self.issuer());
+            return Ok(None);
+        }
+
+        let wanted = CertificateParams::new(self.subject_alt_names.clone())
+            .map_err(|e| cert_error("build_certificate", &cert_path, e))?;
+        if wanted.subject_alt_names.iter().any(|san| !params.subject_alt_names.contains(san)) {
+            info!("🔄 Management certificate does not cover all configured names, rotating");
+            return Ok(None);
+        }
+
+        Ok(Some(ManagementCertificate {
+            cert_path,
+            key_path,
+            fingerprint_sha256: fingerprint(&leaf_der),
+            not_after,
+            issuer: self.issuer(),
+            generated: false,
+        }))
+    }
+
+    fn generate(&self) -> Result<ManagementCertificate, ManagementError> {
+        let cert_path = self.cert_path();
+        let key_path = self.key_path();
+
+        let mut params = CertificateParams::new(self.subject_alt_names.clone())
+            .map_err(|e| cert_error("build_certificate", &cert_path, e))?;
+        let common_name = hostname::get()
+            .map(|h| h.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "securewatch-agent".to_string());
+        params.distinguished_name.push(DnType::CommonName, format!("SecureWatch Agent Management ({})", common_name));
+        params.distinguished_name.push(DnType::OrganizationName, "SecureWatch");
+
+        // rcgen takes calendar dates; backdate a day to tolerate clock skew
+        let not_before = Utc::now() - Duration::days(1);
+        let not_after = Utc::now() + Duration::days(self.config.validity_days as i64);
+        params.not_before = rcgen::date_time_ymd(not_before.year(), not_before.month() as u8, not_before.day() as u8);
+        params.not_after = rcgen::date_time_ymd(not_after.year(), not_after.month() as u8, not_after.day() as u8);
+
+        let key = KeyPair::generate().map_err(|e| cert_error("generate_key", &key_path, e))?;
+        let (certificate, chain_pem) = match (&self.config.ca_cert_path, &self.config.ca_key_path) {
+            (Some(ca_cert_path), Some(ca_key_path)) => {
+                let ca_cert_pem = std::fs::read_to_string(ca_cert_path).map_err(|e| cert_error("read_ca_certificate", ca_cert_path, e))?;
+                let ca_key_pem = std::fs::read_to_string(ca_key_path).map_err(|e| cert_error("read_ca_key", ca_key_path, e))?;
+                let ca_key = KeyPair::from_pem(&ca_key_pem).map_err(|e| cert_error("parse_ca_key", ca_key_path, e))?;
+                let ca_cert = CertificateParams::from_ca_cert_pem(&ca_cert_pem)
+                    .and_then(|ca_params| ca_params.self_signed(&ca_key))
+                    .map_err(|e| cert_error("parse_ca_certificate", ca_cert_path, e))?;
+                let certificate = params
+                    .signed_by(&key, &ca_cert, &ca_key)
+                    .map_err(|e| cert_error("sign_certificate", &cert_path, e))?;
+                // Serve the chain so clients that trust the enrollment CA can verify the listener
+                (certificate, Some(ca_cert_pem))
+            }
+            _ => {
+                let certificate = params.self_signed(&key).map_err(|e| cert_error("sign_certificate", &cert_path, e))?;
+                (certificate, None)
+            }
+        };
+
+        std::fs::create_dir_all(&self.config.cert_dir)
+            .map_err(|e| cert_error("create_cert_dir", Path::new(&self.config.cert_dir), e))?;
+        let mut cert_pem = certificate.pem();
+        if let Some(chain_pem) = chain_pem {
+            cert_pem.push_str(chain_pem.trim_end());
+            cert_pem.push('\n');
+        }
+        write_atomically(&key_path, key.serialize_pem().as_bytes(), true)?;
+        write_atomically(&cert_path, cert_pem.as_bytes(), false)?;
+
+        let not_after = Utc
+            .with_ymd_and_hms(not_after.year(), not_after.month(), not_after.day(), 0, 0, 0)
+            .single()
+            .unwrap_or(not_after);
+
+        Ok(ManagementCertificate {
+            cert_path,
+            key_path,
+            fingerprint_sha256: fingerprint(certificate.der()),
+            not_after,
+            issuer: self.issuer(),
+            generated: true,
+        })
+    }
+}
+
+/// Colon-separated uppercase SHA-256 fingerprint, matching `openssl x509 -fingerprint -sha256`
+pub fn fingerprint(der: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, der)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn first_certificate_der(pem: &str) -> Option<Vec<u8>> {
+    let start = pem.find("-----BEGIN CERTIFICATE-----")? + "-----BEGIN CERTIFICATE-----".len();
+    let end = start + pem[start..].find("-----END CERTIFICATE-----")?;
+    let body: String = pem[start..end].chars().filter(|c| !c.is_whitespace()).collect();
+    base64::engine::general_purpose::STANDARD.decode(body).ok()
+}
+
+/// Write through a fresh temporary file in the same directory; private files are created owner-only, so the key
+/// is never readable by others even before the rename
+fn write_atomically(path: &Path, contents: &[u8], private: bool) -> Result<(), ManagementError> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4().simple()));
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, if private { 0o600 } else { 0o644 });
+    #[cfg(not(unix))]
+    let _ = private;
+
+    let written = options.open(&temp_path).and_then(|mut file| {
+        std::io::Write::write_all(&mut file, contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(cert_error("write_file", &temp_path, e));
+    }
+
+    std::fs::rename(&temp_path, path).map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        cert_error("rename_file", path, e)
+    })
+}
+
+fn cert_error(operation: &str, path: impl AsRef<Path>, reason: impl std::fmt::Display) -> ManagementError {
+    ManagementError::CertificateError {
+        operation: operation.to_string(),
+        path: path.as_ref().display().to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(dir: &Path) -> ManagementTlsConfig {
+        ManagementTlsConfig {
+            cert_dir: dir.join("management").display().to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_self_signed_certificate_is_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ManagementTlsManager::new(config(dir.path()), "127.0.0.1");
+
+        let first = manager.ensure_certificate().unwrap();
+        assert!(first.generated);
+        assert_eq!(first.issuer, CertificateIssuer::SelfSigned);
+        assert_eq!(first.fingerprint_sha256.split(':').count(), 32);
+
+        let second = manager.ensure_certificate().unwrap();
+        assert!(!second.generated);
+        assert_eq!(first.fingerprint_sha256, second.fingerprint_sha256);
+
+        // New names force a rotation
+        let mut config = config(dir.path());
+        config.subject_alt_names = vec!["agent.internal".to_string()];
+        let rotated = ManagementTlsManager::new(config, "127.0.0.1").ensure_certificate().unwrap();
+        assert!(rotated.generated);
+        assert_ne!(rotated.fingerprint_sha256, first.fingerprint_sha256);
+    }
+
+    #[test]
+    fn test_enrollment_ca_issued_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        ca_params.distinguished_name.push(DnType::CommonName, "Enrollment CA");
+        let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+        let ca_cert_path = dir.path().join("ca.crt");
+        let ca_key_path = dir.path().join("ca.key");
+        std::fs::write(&ca_cert_path, ca_cert.pem()).unwrap();
+        std::fs::write(&ca_key_path, ca_key.serialize_pem()).unwrap();
+
+        let mut config = config(dir.path());
+        config.ca_cert_path = Some(ca_cert_path.display().to_string());
+        config.ca_key_path = Some(ca_key_path.display().to_string());
+        let certificate = ManagementTlsManager::new(config, "127.0.0.1").ensure_certificate().unwrap();
+        assert_eq!(certificate.issuer, CertificateIssuer::EnrollmentCa);
+
+        let chain = std::fs::read_to_string(&certificate.cert_path).unwrap();
+        assert_eq!(chain.matches("BEGIN CERTIFICATE").count(), 2);
+        assert_eq!(fingerprint(&first_certificate_der(&chain).unwrap()), certificate.fingerprint_sha256);
+    }
+
+    #[test]
+    fn test_subject_alt_names_are_unique() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(dir.path());
+        config.subject_alt_names = vec!["agent.internal".to_string(), "localhost".to_string(), "agent.internal".to_string()];
+        let manager = ManagementTlsManager::new(config, "10.0.0.5");
+        let mut names = manager.subject_alt_names.clone();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), manager.subject_alt_names.len());
+        assert_eq!(&manager.subject_alt_names[..2], &["localhost".to_string(), "127.0.0.1".to_string()]);
+        assert!(manager.subject_alt_names.contains(&"10.0.0.5".to_string()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_key_is_written_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let certificate = ManagementTlsManager::new(config(dir.path()), "127.0.0.1").ensure_certificate().unwrap();
+        let mode = std::fs::metadata(&certificate.key_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let key_path = Path::new(&certificate.key_path);
+        write_atomically(key_path, b"replaced", true).unwrap();
+        assert_eq!(std::fs::read(key_path).unwrap(), b"replaced");
+        let leftovers: Vec<_> = std::fs::read_dir(key_path.parent().unwrap())
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+}
@@ -0,0 +1,417 @@
+// OpenTelemetry OTLP log export
+// Encodes ParsedEvents as OTLP LogRecords for OTLP/HTTP (protobuf or JSON) and OTLP/gRPC collectors;
+// the protobuf messages are small and stable, so they are written by hand instead of pulling in codegen
+
+use crate::errors::TransportError;
+use crate::parsers::ParsedEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// gRPC method path of the OTLP logs service
+pub const GRPC_EXPORT_PATH: &str = "/opentelemetry.proto.collector.logs.v1.LogsService/Export";
+
+const SCOPE_NAME: &str = "securewatch-agent";
+
+/// OTLP/HTTP body encoding
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpEncoding {
+    #[default]
+    Protobuf,
+    Json,
+}
+
+/// OTLP exporter settings, used when the transport protocol is `otlp_http` or `otlp_grpc`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OtlpConfig {
+    /// Logs endpoint; defaults to `server_url`. OTLP/HTTP collectors usually serve `/v1/logs`,
+    /// OTLP/gRPC takes the collector address (e.g. `http://collector:4317`)
+    pub endpoint: Option<String>,
+    /// Body encoding for OTLP/HTTP; gRPC always uses protobuf
+    pub encoding: OtlpEncoding,
+    /// Extra request headers, e.g. tenant IDs or vendor auth headers
+    pub headers: HashMap<String, String>,
+    /// Send `transport.api_key` as a bearer token
+    pub send_api_key: bool,
+    pub service_name: String,
+    /// Additional resource attributes attached to every export
+    pub resource_attributes: HashMap<String, String>,
+    /// Attach raw_data as the `log.record.original` attribute
+    pub include_raw_data: bool,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            encoding: OtlpEncoding::Protobuf,
+            headers: HashMap::new(),
+            send_api_key: true,
+            service_name: "securewatch-agent".to_string(),
+            resource_attributes: HashMap::new(),
+            include_raw_data: true,
+        }
+    }
+}
+
+impl OtlpConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(endpoint) = &self.endpoint {
+            match url::Url::parse(endpoint) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(_) => errors.push("endpoint must use HTTP or HTTPS scheme".to_string()),
+                Err(e) => errors.push(format!("invalid endpoint: {}", e)),
+            }
+        }
+        if self.service_name.trim().is_empty() {
+            errors.push("service_name cannot be empty".to_string());
+        }
+        for name in self.headers.keys() {
+            if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                errors.push(format!("invalid header name '{}'", name));
+            }
+        }
+        errors
+    }
+}
+
+/// OTLP severity number for a syslog-style or Windows event level
+pub fn severity_number(level: Option<&str>) -> i32 {
+    match level.map(|l| l.to_ascii_lowercase()).as_deref() {
+        Some("trace" | "verbose") => 1,
+        Some("debug") => 5,
+        Some("info" | "informational" | "information") => 9,
+        Some("notice") => 10,
+        Some("warn" | "warning") => 13,
+        Some("err" | "error") => 17,
+        Some("crit" | "critical") => 18,
+        Some("alert") => 19,
+        Some("emerg" | "emergency" | "fatal") => 21,
+        _ => 0,
+    }
+}
+
+/// Builds ExportLogsServiceRequest bodies for one agent
+pub struct OtlpEncoder {
+    resource: Vec<(String, Value)>,
+    include_raw_data: bool,
+}
+
+impl OtlpEncoder {
+    pub fn new(config: &OtlpConfig, agent_id: &str) -> Self {
+        let mut resource = vec![
+            ("service.name".to_string(), json!(config.service_name)),
+            ("service.version".to_string(), json!(env!("CARGO_PKG_VERSION"))),
+            ("service.instance.id".to_string(), json!(agent_id)),
+            ("os.type".to_string(), json!(std::env::consts::OS)),
+        ];
+        if let Ok(hostname) = hostname::get() {
+            resource.push(("host.name".to_string(), json!(hostname.to_string_lossy())));
+        }
+        let mut extra: Vec<_> = config.resource_attributes.iter().collect();
+        extra.sort();
+        for (key, value) in extra {
+            resource.retain(|(existing, _)| existing != key);
+            resource.push((key.clone(), json!(value)));
+        }
+
+        Self { resource, include_raw_data: config.include_raw_data }
+    }
+
+    /// Binary protobuf body, used by OTLP/gRPC and `application/x-protobuf` OTLP/HTTP
+    pub fn encode_protobuf<'a>(&self, events: impl IntoIterator<Item = &'a ParsedEvent>) -> Vec<u8> {
+        let observed = now_unix_nanos();
+        let mut request = ProtoWriter::default();
+        request.message(1, |resource_logs| {
+            resource_logs.message(1, |resource| {
+                for (key, value) in &self.resource {
+                    resource.message(1, |kv| write_key_value(kv, key, value));
+                }
+            });
+            resource_logs.message(2, |scope_logs| {
+                scope_logs.message(1, |scope| {
+                    scope.string(1, SCOPE_NAME);
+                    scope.string(2, env!("CARGO_PKG_VERSION"));
+                });
+                for event in events {
+                    scope_logs.message(2, |record| {
+                        record.fixed64(1, unix_nanos(event));
+                        record.varint(2, severity_number(event.level.as_deref()) as u64);
+                        record.string(3, event.level.as_deref().unwrap_or_default());
+                        record.message(5, |body| write_any_value(body, &Value::String(event.message.clone())));
+                        for (key, value) in self.attributes(event) {
+                            record.message(6, |kv| write_key_value(kv, &key, &value));
+                        }
+                        record.fixed64(11, observed);
+                    });
+                }
+            });
+        });
+        request.buf
+    }
+
+    /// OTLP/JSON body as defined by the OTLP/HTTP spec (lowerCamelCase, 64-bit integers as strings)
+    pub fn encode_json<'a>(&self, events: impl IntoIterator<Item = &'a ParsedEvent>) -> Value {
+        let observed = now_unix_nanos().to_string();
+        let log_records: Vec<Value> = events
+            .into_iter()
+            .map(|event| {
+                json!({
+                    "timeUnixNano": unix_nanos(event).to_string(),
+                    "observedTimeUnixNano": observed,
+                    "severityNumber": severity_number(event.level.as_deref()),
+                    "severityText": event.level.as_deref().unwrap_or_default(),
+                    "body": json_any_value(&Value::String(event.message.clone())),
+                    "attributes": self.attributes(event).iter().map(|(k, v)| json_key_value(k, v)).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        json!({
+            "resourceLogs": [{
+                "resource": {
+                    "attributes": self.resource.iter().map(|(k, v)| json_key_value(k, v)).collect::<Vec<_>>(),
+                },
+                "scopeLogs": [{
+                    "scope": { "name": SCOPE_NAME, "version": env!("CARGO_PKG_VERSION") },
+                    "logRecords": log_records,
+                }],
+            }]
+        })
+    }
+
+    fn attributes(&self, event: &ParsedEvent) -> Vec<(String, Value)> {
+        let mut attributes: Vec<(String, Value)> = vec![
+            ("securewatch.source".to_string(), json!(event.source)),
+            ("securewatch.parser".to_string(), json!(event.parser_name)),
+        ];
+        if self.include_raw_data && !event.raw_data.is_empty() {
+            attributes.push(("log.record.original".to_string(), json!(event.raw_data)));
+        }
+        let mut fields: Vec<_> = event.fields.iter().collect();
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+        attributes.extend(fields.into_iter().map(|(k, v)| (k.clone(), v.clone())));
+        attributes
+    }
+}
+
+/// Length-prefixed gRPC message frame (uncompressed)
+pub fn grpc_frame(message: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(message.len() + 5);
+    frame.push(0);
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend_from_slice(message);
+    frame
+}
+
+/// Map a non-OK gRPC status to a transport error, using the OTLP spec's retryable codes
+pub fn grpc_status_error(code: u32, message: &str) -> Option<TransportError> {
+    let name = match code {
+        0 => return None,
+        1 => "CANCELLED",
+        3 => "INVALID_ARGUMENT",
+        4 => "DEADLINE_EXCEEDED",
+        5 => "NOT_FOUND",
+        7 => "PERMISSION_DENIED",
+        8 => "RESOURCE_EXHAUSTED",
+        10 => "ABORTED",
+        11 => "OUT_OF_RANGE",
+        12 => "UNIMPLEMENTED",
+        13 => "INTERNAL",
+        14 => "UNAVAILABLE",
+        15 => "DATA_LOSS",
+        16 => {
+            return Some(TransportError::AuthenticationFailed {
+                method: "otlp_grpc".to_string(),
+                reason: format!("UNAUTHENTICATED: {}", message),
+                retry_allowed: false,
+            })
+        }
+        _ => "UNKNOWN",
+    };
+    Some(TransportError::ServerError {
+        status: 200,
+        message: format!("gRPC status {} ({}): {}", code, name, message),
+        headers: vec![("grpc-status".to_string(), code.to_string())],
+        body: None,
+        retryable: matches!(code, 1 | 4 | 8 | 10 | 11 | 14 | 15),
+    })
+}
+
+fn unix_nanos(event: &ParsedEvent) -> u64 {
+    event.timestamp.timestamp_nanos_opt().unwrap_or_default().max(0) as u64
+}
+
+fn now_unix_nanos() -> u64 {
+    chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default().max(0) as u64
+}
+
+fn json_key_value(key: &str, value: &Value) -> Value {
+    json!({ "key": key, "value": json_any_value(value) })
+}
+
+fn json_any_value(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({}),
+        Value::Bool(b) => json!({ "boolValue": b }),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => json!({ "intValue": i.to_string() }),
+            None => json!({ "doubleValue": n.as_f64() }),
+        },
+        Value::String(s) => json!({ "stringValue": s }),
+        Value::Array(items) => json!({ "arrayValue": { "values": items.iter().map(json_any_value).collect::<Vec<_>>() } }),
+        Value::Object(map) => json!({ "kvlistValue": { "values": map.iter().map(|(k, v)| json_key_value(k, v)).collect::<Vec<_>>() } }),
+    }
+}
+
+pub(crate) fn write_key_value(writer: &mut ProtoWriter, key: &str, value: &Value) {
+    writer.string(1, key);
+    writer.message(2, |any| write_any_value(any, value));
+}
+
+fn write_any_value(writer: &mut ProtoWriter, value: &Value) {
+    match value {
+        Value::Null => {}
+        Value::Bool(b) => writer.varint_field(2, *b as u64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => writer.varint_field(3, i as u64),
+            None => writer.double(4, n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => writer.string_always(1, s),
+        Value::Array(items) => writer.message(5, |array| {
+            for item in items {
+                array.message(1, |any| write_any_value(any, item));
+            }
+        }),
+        Value::Object(map) => writer.message(6, |list| {
+            for (key, item) in map.iter() {
+                list.message(1, |kv| write_key_value(kv, key, item));
+            }
+        }),
+    }
+}
+
+/// Minimal protobuf wire-format writer, shared with the protobuf batch format
+#[derive(Default)]
+pub(crate) struct ProtoWriter {
+    buf: Vec<u8>,
+}
+
+impl ProtoWriter {
+    fn raw_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn tag(&mut self, field: u32, wire_type: u8) {
+        self.raw_varint(((field as u64) << 3) | wire_type as u64);
+    }
+
+    /// Scalar varint, skipped when zero like proto3 defaults
+    fn varint(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            self.varint_field(field, value);
+        }
+    }
+
+    /// Varint that must be present, e.g. a oneof member
+    fn varint_field(&mut self, field: u32, value: u64) {
+        self.tag(field, 0);
+        self.raw_varint(value);
+    }
+
+    pub(crate) fn fixed64(&mut self, field: u32, value: u64) {
+        self.tag(field, 1);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn double(&mut self, field: u32, value: f64) {
+        self.fixed64(field, value.to_bits());
+    }
+
+    pub(crate) fn string(&mut self, field: u32, value: &str) {
+        if !value.is_empty() {
+            self.string_always(field, value);
+        }
+    }
+
+    fn string_always(&mut self, field: u32, value: &str) {
+        self.tag(field, 2);
+        self.raw_varint(value.len() as u64);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    pub(crate) fn message(&mut self, field: u32, build: impl FnOnce(&mut ProtoWriter)) {
+        let mut nested = ProtoWriter::default();
+        build(&mut nested);
+        self.tag(field, 2);
+        self.raw_varint(nested.buf.len() as u64);
+        self.buf.extend_from_slice(&nested.buf);
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event() -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::DateTime::from_timestamp(1_700_000_000, 5).unwrap(),
+            source: "syslog".to_string(),
+            level: Some("warning".to_string()),
+            message: "Failed password for root".to_string(),
+            fields: HashMap::from([
+                ("user.name".to_string(), json!("root")),
+                ("source.port".to_string(), json!(2222)),
+            ]),
+            raw_data: "<38>sshd: Failed password for root".into(),
+            parser_name: "sshd".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_json_encoding_follows_otlp_mapping() {
+        let encoder = OtlpEncoder::new(&OtlpConfig::default(), "agent-1");
+        let body = encoder.encode_json([&event()]);
+
+        let resource = &body["resourceLogs"][0]["resource"]["attributes"];
+        assert!(resource.as_array().unwrap().iter().any(|kv| kv["key"] == "service.instance.id" && kv["value"]["stringValue"] == "agent-1"));
+        let record = &body["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(record["timeUnixNano"], "1700000000000000005");
+        assert_eq!(record["severityNumber"], 13);
+        assert_eq!(record["body"]["stringValue"], "Failed password for root");
+        let attributes = record["attributes"].as_array().unwrap();
+        assert!(attributes.iter().any(|kv| kv["key"] == "source.port" && kv["value"]["intValue"] == "2222"));
+        assert!(attributes.iter().any(|kv| kv["key"] == "log.record.original"));
+    }
+
+    #[test]
+    fn test_protobuf_and_grpc_framing() {
+        let encoder = OtlpEncoder::new(&OtlpConfig { include_raw_data: false, ..Default::default() }, "agent-1");
+        let body = encoder.encode_protobuf([&event()]);
+        // ExportLogsServiceRequest.resource_logs (field 1, length-delimited)
+        assert_eq!(body[0], 0x0A);
+        let needle = b"Failed password for root";
+        assert!(body.windows(needle.len()).any(|w| w == needle));
+        assert!(!body.windows(6).any(|w| w == b"<38>ss"));
+
+        let frame = grpc_frame(&body);
+        assert_eq!(frame[0], 0);
+        assert_eq!(u32::from_be_bytes(frame[1..5].try_into().unwrap()) as usize, body.len());
+
+        assert!(grpc_status_error(0, "").is_none());
+        assert!(matches!(grpc_status_error(14, "down"), Some(TransportError::ServerError { retryable: true, .. })));
+        assert!(matches!(grpc_status_error(3, "bad"), Some(TransportError::ServerError { retryable: false, .. })));
+        assert_eq!(severity_number(Some("CRITICAL")), 18);
+    }
+}
@@ -0,0 +1,156 @@
+// Per-event templates for third-party output metadata
+// Index names, sourcetypes and hosts for Splunk HEC and Elasticsearch are written as templates such as
+// "securewatch-{source}-{date:%Y.%m.%d}", parsed once when the transport starts and rendered for every event
+
+use crate::parsers::ParsedEvent;
+use chrono::format::{Item, StrftimeItems};
+use serde_json::Value;
+
+/// Rendered in place of a missing field that has no `|default`
+const MISSING_VALUE: &str = "unknown";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Date(String),
+    Field { name: String, default: Option<String> },
+}
+
+/// Template with `{source}`, `{parser}`, `{level}`, `{message}`, `{<field>}` and `{date:<strftime>}` placeholders;
+/// `{<field>|<default>}` renders the default when the event lacks the field
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventTemplate {
+    segments: Vec<Segment>,
+}
+
+impl EventTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| format!("unclosed '{{' in template '{}'", template))?;
+            push_literal(&mut segments, &rest[..start], template)?;
+            segments.push(parse_placeholder(rest[start + 1..end].trim(), template)?);
+            rest = &rest[end + 1..];
+        }
+        push_literal(&mut segments, rest, template)?;
+        Ok(Self { segments })
+    }
+
+    /// True when the template renders the same text for every event
+    pub fn is_static(&self) -> bool {
+        self.segments.iter().all(|segment| matches!(segment, Segment::Literal(_)))
+    }
+
+    pub fn render(&self, event: &ParsedEvent) -> String {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Date(format) => rendered.push_str(&event.timestamp.format(format).to_string()),
+                Segment::Field { name, default } => match event_value(event, name) {
+                    Some(value) => rendered.push_str(&value),
+                    None => rendered.push_str(default.as_deref().unwrap_or(MISSING_VALUE)),
+                },
+            }
+        }
+        rendered
+    }
+}
+
+fn push_literal(segments: &mut Vec<Segment>, text: &str, template: &str) -> Result<(), String> {
+    if text.contains('}') {
+        return Err(format!("unmatched '}}' in template '{}'", template));
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Literal(text.to_string()));
+    }
+    Ok(())
+}
+
+fn parse_placeholder(placeholder: &str, template: &str) -> Result<Segment, String> {
+    if let Some(format) = placeholder.strip_prefix("date:") {
+        if format.is_empty() || StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+            return Err(format!("invalid date format '{}' in template '{}'", format, template));
+        }
+        return Ok(Segment::Date(format.to_string()));
+    }
+    let (name, default) = match placeholder.split_once('|') {
+        Some((name, default)) => (name.trim(), Some(default.trim().to_string())),
+        None => (placeholder, None),
+    };
+    if name.is_empty() {
+        return Err(format!("empty placeholder in template '{}'", template));
+    }
+    Ok(Segment::Field { name: name.to_string(), default })
+}
+
+fn event_value(event: &ParsedEvent, name: &str) -> Option<String> {
+    match name {
+        "source" => Some(event.source.clone()),
+        "parser" | "parser_name" => Some(event.parser_name.clone()),
+        "level" => event.level.clone(),
+        "message" => Some(event.message.clone()),
+        name => match event.fields.get(name)? {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        },
+    }
+}
+
+/// `url` itself when it names a path, otherwise `url` with the output's default `path` appended
+pub fn endpoint_url(url: &str, path: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(parsed) if parsed.path() == "/" && parsed.query().is_none() => format!("{}{}", url.trim_end_matches('/'), path),
+        _ => url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+
+    fn event() -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc.with_ymd_and_hms(2024, 3, 9, 23, 15, 0).unwrap(),
+            source: "windows_event_log".to_string(),
+            level: None,
+            message: "An account failed to log on".to_string(),
+            fields: HashMap::from([
+                ("event_id".to_string(), serde_json::json!(4625)),
+                ("host.name".to_string(), serde_json::json!("dc01")),
+            ]),
+            raw_data: "".into(),
+            parser_name: "windows_security".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render_fields_defaults_and_dates() {
+        let template = EventTemplate::parse("sw-{source}-{event_id}-{date:%Y.%m.%d}").unwrap();
+        assert!(!template.is_static());
+        assert_eq!(template.render(&event()), "sw-windows_event_log-4625-2024.03.09");
+
+        let template = EventTemplate::parse("{host.name}/{level|info}/{parser}/{user.name}").unwrap();
+        assert_eq!(template.render(&event()), "dc01/info/windows_security/unknown");
+        assert!(EventTemplate::parse("_json").unwrap().is_static());
+    }
+
+    #[test]
+    fn test_parse_errors_and_endpoint_paths() {
+        assert!(EventTemplate::parse("sw-{source").unwrap_err().contains("unclosed"));
+        assert!(EventTemplate::parse("sw-source}").unwrap_err().contains("unmatched"));
+        assert!(EventTemplate::parse("sw-{}").unwrap_err().contains("empty placeholder"));
+        assert!(EventTemplate::parse("sw-{date:%Q}").unwrap_err().contains("invalid date format"));
+
+        assert_eq!(endpoint_url("https://es.example.com:9200", "/_bulk"), "https://es.example.com:9200/_bulk");
+        assert_eq!(endpoint_url("https://es.example.com:9200/", "/_bulk"), "https://es.example.com:9200/_bulk");
+        assert_eq!(endpoint_url("https://proxy.example.com/es/_bulk", "/_bulk"), "https://proxy.example.com/es/_bulk");
+    }
+}
@@ -0,0 +1,646 @@
+// Database audit log parsers for PostgreSQL and MySQL/MariaDB
+// Handles PostgreSQL log_line_prefix formatted logs, the MySQL general query log and
+// MariaDB/Percona audit plugin output, extracting user, database, client, duration and
+// statement class fields
+
+use crate::collectors::RawLogEvent;
+use crate::errors::ParserError;
+use crate::parsers::{ParsedEvent, Parser};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tracing::debug;
+
+/// Source type emitted by the database collector
+pub const DATABASE_SOURCE: &str = "database";
+
+/// PostgreSQL's default `log_line_prefix` is too sparse for auditing, so the preset assumes
+/// the commonly recommended `'%m [%p] %q%u@%d '`
+pub const DEFAULT_POSTGRES_LOG_LINE_PREFIX: &str = "%m [%p] %q%u@%d ";
+
+/// Maximum number of MySQL general log sessions remembered for user/db attribution
+const MAX_TRACKED_SESSIONS: usize = 10000;
+
+/// Supported database log presets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseLogPreset {
+    /// PostgreSQL stderr/csv-less logs formatted with `log_line_prefix`
+    Postgresql,
+    /// MySQL general query log (`general_log = ON`)
+    MysqlGeneral,
+    /// MariaDB server_audit (CSV) or Percona audit_log (JSON) output
+    MysqlAudit,
+}
+
+impl DatabaseLogPreset {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DatabaseLogPreset::Postgresql => "postgresql",
+            DatabaseLogPreset::MysqlGeneral => "mysql_general",
+            DatabaseLogPreset::MysqlAudit => "mysql_audit",
+        }
+    }
+
+    fn db_system(&self) -> &'static str {
+        match self {
+            DatabaseLogPreset::Postgresql => "postgresql",
+            DatabaseLogPreset::MysqlGeneral | DatabaseLogPreset::MysqlAudit => "mysql",
+        }
+    }
+}
+
+/// User and database attributed to a MySQL general log session
+type SessionIdentity = (Option<String>, Option<String>);
+
+/// Compiled record boundary and field extraction rules for one preset
+pub struct DatabaseLogFormat {
+    preset: DatabaseLogPreset,
+    record_start: Regex,
+    record: Regex,
+    /// MySQL general log thread id -> (user, database), learned from Connect/Init DB records
+    sessions: Mutex<HashMap<String, SessionIdentity>>,
+}
+
+/// Result of parsing one database log record
+#[derive(Debug, Clone)]
+pub struct DatabaseRecord {
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    pub level: Option<String>,
+    pub message: String,
+    pub fields: HashMap<String, Value>,
+}
+
+impl DatabaseLogFormat {
+    pub fn new(preset: DatabaseLogPreset, log_line_prefix: Option<&str>) -> Result<Self, ParserError> {
+        let (record_start, record) = match preset {
+            DatabaseLogPreset::Postgresql => {
+                let prefix = postgres_prefix_regex(log_line_prefix.unwrap_or(DEFAULT_POSTGRES_LOG_LINE_PREFIX));
+                (
+                    format!(r"^{}[A-Z]+[0-9]?:\s", prefix),
+                    format!(r"(?s)^{}(?P<severity>[A-Z]+[0-9]?):\s+(?P<message>.*)$", prefix),
+                )
+            }
+            DatabaseLogPreset::MysqlGeneral => {
+                let head = r"^(?:(?P<timestamp>\d{4}-\d{2}-\d{2}T\S+|\d{6}\s+\d{1,2}:\d{2}:\d{2})\s+|\t\t)\s*(?P<thread>\d+)\s(?P<command>[A-Za-z][A-Za-z ]*?)";
+                (
+                    format!(r"{}(?:\t|$)", head),
+                    format!(r"(?s){}(?:\t(?P<argument>.*))?$", head),
+                )
+            }
+            DatabaseLogPreset::MysqlAudit => (
+                r"^(?:\d{8} \d{2}:\d{2}:\d{2},|\{)".to_string(),
+                r"(?s)^.*$".to_string(),
+            ),
+        };
+
+        let compile = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| {
+                ParserError::invalid_regex(&format!("Invalid {} log format '{}': {}", preset.as_str(), pattern, e))
+            })
+        };
+
+        Ok(Self {
+            preset,
+            record_start: compile(&record_start)?,
+            record: compile(&record)?,
+            sessions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn preset(&self) -> DatabaseLogPreset {
+        self.preset
+    }
+
+    /// Whether a line begins a new record; anything else continues the previous record
+    pub fn is_record_start(&self, line: &str) -> bool {
+        self.record_start.is_match(line)
+    }
+
+    /// Parse one complete (possibly multi-line) record
+    pub fn parse_record(&self, record: &str) -> Option<DatabaseRecord> {
+        let mut parsed = match self.preset {
+            DatabaseLogPreset::Postgresql => self.parse_postgres(record),
+            DatabaseLogPreset::MysqlGeneral => self.parse_mysql_general(record),
+            DatabaseLogPreset::MysqlAudit => parse_mysql_audit(record),
+        }?;
+
+        parsed.fields.insert("db.system".to_string(), json!(self.preset.db_system()));
+        if let Some(statement) = parsed.fields.get("db.statement").and_then(Value::as_str) {
+            let (operation, class) = classify_statement(statement);
+            if let Some(operation) = operation {
+                parsed.fields.insert("db.operation".to_string(), json!(operation));
+            }
+            parsed.fields.insert("db.statement_class".to_string(), json!(class));
+        }
+        Some(parsed)
+    }
+
+    fn parse_postgres(&self, record: &str) -> Option<DatabaseRecord> {
+        let captures = self.record.captures(record)?;
+        let mut fields = HashMap::new();
+
+        insert_capture(&mut fields, &captures, "user", "user.name");
+        insert_capture(&mut fields, &captures, "database", "db.name");
+        insert_capture(&mut fields, &captures, "client", "client.address");
+        insert_capture(&mut fields, &captures, "application", "db.application");
+        insert_capture(&mut fields, &captures, "sqlstate", "db.sqlstate");
+        insert_capture(&mut fields, &captures, "session", "db.session_id");
+        if let Some(pid) = captures.name("pid").and_then(|m| m.as_str().parse::<u64>().ok()) {
+            fields.insert("process.pid".to_string(), json!(pid));
+        }
+        if let Some(port) = captures.name("client_port").and_then(|m| m.as_str().parse::<u64>().ok()) {
+            fields.insert("client.port".to_string(), json!(port));
+        }
+
+        let severity = captures.name("severity").map(|m| m.as_str()).unwrap_or("LOG");
+        let message = captures.name("message").map(|m| m.as_str().trim_end()).unwrap_or("");
+
+        if severity == "STATEMENT" {
+            fields.insert("db.statement".to_string(), json!(message));
+        } else if let Some(statement) = postgres_statement_regex()
+            .captures(message)
+            .filter(|c| c.name("duration").is_some() || c.name("statement").is_some())
+        {
+            if let Some(duration) = statement.name("duration").and_then(|m| m.as_str().parse::<f64>().ok()) {
+                fields.insert("db.duration_ms".to_string(), json!(duration));
+            }
+            if let Some(text) = statement.name("statement") {
+                fields.insert("db.statement".to_string(), json!(text.as_str().trim()));
+            }
+        } else if let Some(connection) = postgres_connection_regex().captures(message) {
+            for (key, value) in parse_key_values(&connection["details"]) {
+                match key.as_str() {
+                    "user" => fields.entry("user.name".to_string()).or_insert(json!(value)),
+                    "database" => fields.entry("db.name".to_string()).or_insert(json!(value)),
+                    "host" => fields.entry("client.address".to_string()).or_insert(json!(value)),
+                    "port" => fields.entry("client.port".to_string()).or_insert(json!(value.parse::<u64>().ok())),
+                    "application_name" => fields.entry("db.application".to_string()).or_insert(json!(value)),
+                    _ => continue,
+                };
+            }
+            fields.insert("event.action".to_string(), json!(format!("connection_{}", &connection["kind"])));
+        }
+
+        let timestamp = captures
+            .name("timestamp")
+            .and_then(|m| parse_timestamp(m.as_str()));
+
+        Some(DatabaseRecord {
+            timestamp,
+            level: Some(postgres_level(severity).to_string()),
+            message: message.to_string(),
+            fields,
+        })
+    }
+
+    fn parse_mysql_general(&self, record: &str) -> Option<DatabaseRecord> {
+        let captures = self.record.captures(record)?;
+        let mut fields = HashMap::new();
+
+        let thread = captures["thread"].to_string();
+        let command = captures["command"].trim().to_string();
+        let argument = captures.name("argument").map(|m| m.as_str().trim()).unwrap_or("");
+        fields.insert("db.session_id".to_string(), json!(thread));
+        fields.insert("db.command".to_string(), json!(command));
+
+        let mut sessions = self.sessions.lock();
+        match command.as_str() {
+            "Connect" => {
+                // "user@host on db using TCP/IP"
+                if let Some(connect) = mysql_connect_regex().captures(argument) {
+                    let user = connect.name("user").map(|m| m.as_str().to_string());
+                    let database = connect.name("database").map(|m| m.as_str().to_string());
+                    if let Some(host) = connect.name("host") {
+                        fields.insert("client.address".to_string(), json!(host.as_str()));
+                    }
+                    if sessions.len() >= MAX_TRACKED_SESSIONS {
+                        sessions.clear();
+                    }
+                    sessions.insert(thread.clone(), (user, database));
+                }
+                fields.insert("event.action".to_string(), json!("connection_authorized"));
+            }
+            "Init DB" => {
+                let entry = sessions.entry(thread.clone()).or_insert((None, None));
+                entry.1 = Some(argument.to_string());
+            }
+            "Query" | "Execute" | "Prepare" => {
+                fields.insert("db.statement".to_string(), json!(argument));
+            }
+            "Quit" => {
+                if let Some((user, database)) = sessions.remove(&thread) {
+                    insert_optional(&mut fields, "user.name", user);
+                    insert_optional(&mut fields, "db.name", database);
+                }
+                fields.insert("event.action".to_string(), json!("disconnect"));
+            }
+            _ => {}
+        }
+        if let Some((user, database)) = sessions.get(&thread) {
+            insert_optional(&mut fields, "user.name", user.clone());
+            insert_optional(&mut fields, "db.name", database.clone());
+        }
+        drop(sessions);
+
+        let timestamp = captures
+            .name("timestamp")
+            .and_then(|m| parse_timestamp(m.as_str()));
+        let message = if argument.is_empty() { command } else { argument.to_string() };
+
+        Some(DatabaseRecord {
+            timestamp,
+            level: Some("info".to_string()),
+            message,
+            fields,
+        })
+    }
+}
+
+/// MariaDB server_audit CSV or Percona audit_log JSON records
+fn parse_mysql_audit(record: &str) -> Option<DatabaseRecord> {
+    let record = record.trim();
+    if record.starts_with('{') {
+        return parse_percona_audit(record);
+    }
+
+    // timestamp,serverhost,username,host,connectionid,queryid,operation,database,object,retcode
+    let mut parts = record.splitn(9, ',');
+    let timestamp = parts.next()?;
+    let _server_host = parts.next()?;
+    let user = parts.next()?;
+    let host = parts.next()?;
+    let connection_id = parts.next()?;
+    let query_id = parts.next()?;
+    let operation = parts.next()?;
+    let database = parts.next()?;
+    let (object, retcode) = parts.next()?.rsplit_once(',')?;
+
+    let mut fields = HashMap::new();
+    insert_optional(&mut fields, "user.name", non_empty(user));
+    insert_optional(&mut fields, "client.address", non_empty(host));
+    insert_optional(&mut fields, "db.name", non_empty(database));
+    fields.insert("db.session_id".to_string(), json!(connection_id));
+    fields.insert("db.query_id".to_string(), json!(query_id));
+    fields.insert("event.action".to_string(), json!(operation.to_lowercase()));
+    if let Ok(code) = retcode.trim().parse::<i64>() {
+        fields.insert("db.return_code".to_string(), json!(code));
+        fields.insert("event.outcome".to_string(), json!(if code == 0 { "success" } else { "failure" }));
+    }
+
+    let object = unquote_sql(object);
+    if operation == "QUERY" {
+        fields.insert("db.statement".to_string(), json!(object));
+    } else if !object.is_empty() {
+        fields.insert("db.object".to_string(), json!(object));
+    }
+
+    let message = if object.is_empty() { operation.to_string() } else { object };
+    Some(DatabaseRecord {
+        timestamp: chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%d %H:%M:%S")
+            .ok()
+            .map(|naive| naive.and_utc()),
+        level: Some(if retcode.trim() == "0" { "info" } else { "warning" }.to_string()),
+        message,
+        fields,
+    })
+}
+
+fn parse_percona_audit(record: &str) -> Option<DatabaseRecord> {
+    let value: Value = serde_json::from_str(record).ok()?;
+    let audit = value.get("audit_record").unwrap_or(&value);
+    let text = |key: &str| audit.get(key).and_then(Value::as_str).and_then(non_empty);
+
+    let mut fields = HashMap::new();
+    // Percona reports "user" as "priv_user[user] @ host [ip]"
+    let user = text("user").map(|u| u.split(['[', ' ']).next().unwrap_or_default().to_string());
+    insert_optional(&mut fields, "user.name", user.and_then(|u| non_empty(&u)));
+    insert_optional(&mut fields, "db.name", text("db"));
+    insert_optional(&mut fields, "client.address", text("ip").or_else(|| text("host")));
+    insert_optional(&mut fields, "db.session_id", text("connection_id"));
+    insert_optional(&mut fields, "db.statement", text("sqltext"));
+    insert_optional(&mut fields, "event.action", text("name").map(|n| n.to_lowercase()));
+    if let Some(status) = audit.get("status").and_then(Value::as_i64) {
+        fields.insert("db.return_code".to_string(), json!(status));
+        fields.insert("event.outcome".to_string(), json!(if status == 0 { "success" } else { "failure" }));
+    }
+
+    let message = text("sqltext").or_else(|| text("name")).unwrap_or_default();
+    Some(DatabaseRecord {
+        timestamp: text("timestamp").and_then(|t| parse_timestamp(&t)),
+        level: Some("info".to_string()),
+        message,
+        fields,
+    })
+}
+
+/// Classify a SQL statement into its leading keyword and a coarse statement class
+pub fn classify_statement(statement: &str) -> (Option<String>, &'static str) {
+    let keyword = strip_sql_comments(statement)
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ';')
+        .find(|word| !word.is_empty())
+        .map(|word| word.to_uppercase());
+
+    let class = match keyword.as_deref() {
+        Some("SELECT" | "SHOW" | "EXPLAIN" | "DESCRIBE" | "DESC" | "WITH" | "VALUES" | "TABLE") => "read",
+        Some("INSERT" | "UPDATE" | "DELETE" | "MERGE" | "REPLACE" | "COPY" | "LOAD" | "TRUNCATE") => "write",
+        Some("CREATE" | "ALTER" | "DROP" | "RENAME" | "COMMENT") => "ddl",
+        Some("GRANT" | "REVOKE") => "dcl",
+        Some("BEGIN" | "START" | "COMMIT" | "ROLLBACK" | "SAVEPOINT" | "RELEASE" | "END") => "tcl",
+        Some("SET" | "RESET" | "USE" | "DISCARD") => "session",
+        _ => "other",
+    };
+
+    (keyword, class)
+}
+
+fn strip_sql_comments(statement: &str) -> &str {
+    let mut rest = statement.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("--") {
+            rest = after.split_once('\n').map(|(_, tail)| tail).unwrap_or("").trim_start();
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.split_once("*/").map(|(_, tail)| tail).unwrap_or("").trim_start();
+        } else {
+            return rest;
+        }
+    }
+}
+
+/// Translate a PostgreSQL `log_line_prefix` into a regex with named captures
+fn postgres_prefix_regex(prefix: &str) -> String {
+    let mut regex = String::new();
+    let mut used = std::collections::HashSet::new();
+    let mut optional_open = false;
+    let mut chars = prefix.chars().peekable();
+
+    let named = |name: &'static str, pattern: &str, used: &mut std::collections::HashSet<&'static str>| {
+        if used.insert(name) {
+            format!("(?P<{}>{})", name, pattern)
+        } else {
+            format!("(?:{})", pattern)
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            regex.push_str(&regex::escape(&c.to_string()));
+            continue;
+        }
+
+        // Skip padding such as %-10u
+        while matches!(chars.peek(), Some('-') | Some('0'..='9')) {
+            chars.next();
+        }
+
+        let Some(escape) = chars.next() else {
+            regex.push('%');
+            break;
+        };
+        let part = match escape {
+            'm' => named("timestamp", r"\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d+(?: [A-Za-z0-9+:\-]+)?", &mut used),
+            't' => named("timestamp", r"\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}(?: [A-Za-z0-9+:\-]+)?", &mut used),
+            'n' => named("timestamp", r"\d+\.\d+", &mut used),
+            'p' => named("pid", r"\d+", &mut used),
+            'u' => named("user", r"[^\s@,\]\[]*", &mut used),
+            'd' => named("database", r"[^\s@,\]\[]*", &mut used),
+            'r' => format!("{}(?:\\((?P<client_port>\\d+)\\))?", named("client", r"[^\s(,\]\[]*", &mut used)),
+            'h' => named("client", r"[^\s,\]\[]*", &mut used),
+            'a' => named("application", r"[^,\]\[]*?", &mut used),
+            'e' => named("sqlstate", r"[0-9A-Z]{5}", &mut used),
+            'c' => named("session", r"[0-9a-f]+\.[0-9a-f]+", &mut used),
+            'l' | 'P' => r"\d*".to_string(),
+            'Q' => r"-?\d+".to_string(),
+            'x' | 'v' | 'i' | 'L' => r"\S*".to_string(),
+            's' | 'b' => r"[^\]\[]*?".to_string(),
+            '%' => "%".to_string(),
+            'q' => {
+                // Everything after %q is only printed by session processes
+                optional_open = true;
+                "(?:".to_string()
+            }
+            _ => r".*?".to_string(),
+        };
+        regex.push_str(&part);
+    }
+
+    if optional_open {
+        regex.push_str(")?");
+    }
+    regex
+}
+
+fn postgres_statement_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?s)^(?:duration: (?P<duration>[0-9.]+) ms\s*)?(?:(?:statement|(?:execute|parse|bind) [^:]*): (?P<statement>.*))?$")
+            .expect("valid PostgreSQL statement regex")
+    })
+}
+
+fn postgres_connection_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^connection (?P<kind>authorized|received|authenticated): (?P<details>.*)$")
+            .expect("valid PostgreSQL connection regex")
+    })
+}
+
+fn mysql_connect_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^(?P<user>[^@\s]+)@(?P<host>\S+)(?: on (?P<database>\S+))?")
+            .expect("valid MySQL connect regex")
+    })
+}
+
+fn postgres_level(severity: &str) -> &'static str {
+    match severity {
+        "PANIC" | "FATAL" => "critical",
+        "ERROR" => "error",
+        "WARNING" => "warning",
+        "NOTICE" | "INFO" | "LOG" | "STATEMENT" | "DETAIL" | "HINT" | "CONTEXT" => "info",
+        _ => "debug",
+    }
+}
+
+fn parse_timestamp(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(parsed.with_timezone(&chrono::Utc));
+    }
+    if let Ok(parsed) = chrono::DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f %z") {
+        return Some(parsed.with_timezone(&chrono::Utc));
+    }
+
+    // Zone abbreviations can't be resolved reliably; only UTC/GMT are trusted
+    let (naive, zone) = value.rsplit_once(' ').filter(|(_, z)| z.chars().all(|c| c.is_ascii_alphabetic()))
+        .unwrap_or((value, "UTC"));
+    if zone != "UTC" && zone != "GMT" {
+        return None;
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%y%m%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(naive.trim(), format).ok())
+        .map(|naive| naive.and_utc())
+}
+
+fn parse_key_values(details: &str) -> Vec<(String, String)> {
+    details
+        .split_whitespace()
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.trim_end_matches(',').to_string()))
+        .collect()
+}
+
+fn unquote_sql(object: &str) -> String {
+    let object = object.trim();
+    match object.strip_prefix('\'').and_then(|o| o.strip_suffix('\'')) {
+        Some(inner) => inner.replace("\\'", "'").replace("\\\\", "\\"),
+        None => object.to_string(),
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() { None } else { Some(value.to_string()) }
+}
+
+fn insert_capture(fields: &mut HashMap<String, Value>, captures: &regex::Captures, group: &str, field: &str) {
+    if let Some(value) = captures.name(group).and_then(|m| non_empty(m.as_str())) {
+        fields.insert(field.to_string(), json!(value));
+    }
+}
+
+fn insert_optional(fields: &mut HashMap<String, Value>, field: &str, value: Option<String>) {
+    if let Some(value) = value {
+        fields.entry(field.to_string()).or_insert(json!(value));
+    }
+}
+
+/// Parser for records emitted by the database audit collector
+pub struct DatabaseAuditParser {
+    name: String,
+    format: DatabaseLogFormat,
+}
+
+impl DatabaseAuditParser {
+    pub fn new(preset: DatabaseLogPreset, log_line_prefix: Option<&str>) -> Result<Self, ParserError> {
+        Ok(Self {
+            name: format!("database_{}", preset.as_str()),
+            format: DatabaseLogFormat::new(preset, log_line_prefix)?,
+        })
+    }
+}
+
+#[async_trait]
+impl Parser for DatabaseAuditParser {
+    async fn parse(&self, raw_event: &RawLogEvent) -> Result<ParsedEvent, ParserError> {
+        let record = self.format.parse_record(&raw_event.raw_data).ok_or_else(|| {
+            ParserError::parse_failed(&format!("Unrecognized {} record: {}", self.format.preset().as_str(), raw_event.raw_data))
+        })?;
+        debug!("🗄️  Parsed {} record with {} fields", self.format.preset().as_str(), record.fields.len());
+
+        let mut fields = record.fields;
+        if let Some(path) = raw_event.metadata.get("file_path") {
+            fields.insert("log.file.path".to_string(), json!(path));
+        }
+
+        Ok(ParsedEvent {
+            timestamp: record.timestamp.unwrap_or(raw_event.timestamp),
+            source: raw_event.source.clone(),
+            level: record.level,
+            message: record.message,
+            fields,
+            raw_data: raw_event.raw_data.clone(),
+            parser_name: self.name.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source_type(&self) -> &str {
+        DATABASE_SOURCE
+    }
+
+    fn can_parse(&self, raw_event: &RawLogEvent) -> bool {
+        raw_event.source == DATABASE_SOURCE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_postgres_multiline_statement_with_duration() {
+        let format = DatabaseLogFormat::new(DatabaseLogPreset::Postgresql, None).unwrap();
+        let first = "2024-01-15 10:00:00.123 UTC [4242] alice@sales LOG:  duration: 12.5 ms  statement: SELECT *";
+        assert!(format.is_record_start(first));
+        assert!(!format.is_record_start("\tFROM orders"));
+
+        let record = format.parse_record(&format!("{}\n\tFROM orders\n\tWHERE id = 1", first)).unwrap();
+        assert_eq!(record.fields["user.name"], "alice");
+        assert_eq!(record.fields["db.name"], "sales");
+        assert_eq!(record.fields["process.pid"], 4242);
+        assert_eq!(record.fields["db.duration_ms"], 12.5);
+        assert_eq!(record.fields["db.operation"], "SELECT");
+        assert_eq!(record.fields["db.statement_class"], "read");
+        assert!(record.fields["db.statement"].as_str().unwrap().ends_with("WHERE id = 1"));
+        assert!(record.timestamp.is_some());
+    }
+
+    #[test]
+    fn test_postgres_custom_prefix_with_client() {
+        let format = DatabaseLogFormat::new(DatabaseLogPreset::Postgresql, Some("%t [%p]: user=%u,db=%d,client=%r ")).unwrap();
+        let record = format
+            .parse_record("2024-01-15 10:00:00 UTC [77]: user=bob,db=hr,client=10.0.0.5(51234) ERROR:  permission denied for table salaries")
+            .unwrap();
+        assert_eq!(record.fields["client.address"], "10.0.0.5");
+        assert_eq!(record.fields["client.port"], 51234);
+        assert_eq!(record.level.as_deref(), Some("error"));
+
+        // Background processes omit everything after %q
+        let format = DatabaseLogFormat::new(DatabaseLogPreset::Postgresql, None).unwrap();
+        assert!(format.is_record_start("2024-01-15 10:00:00.123 UTC [12] LOG:  checkpoint starting: time"));
+    }
+
+    #[test]
+    fn test_mysql_general_session_attribution() {
+        let format = DatabaseLogFormat::new(DatabaseLogPreset::MysqlGeneral, None).unwrap();
+        let connect = "2024-01-15T10:00:00.123456Z\t   12 Connect\troot@10.1.1.1 on shop using TCP/IP";
+        assert!(format.is_record_start(connect));
+        format.parse_record(connect).unwrap();
+
+        let record = format
+            .parse_record("2024-01-15T10:00:01.000000Z\t   12 Query\tUPDATE carts\nSET total = 0")
+            .unwrap();
+        assert_eq!(record.fields["user.name"], "root");
+        assert_eq!(record.fields["db.name"], "shop");
+        assert_eq!(record.fields["db.statement_class"], "write");
+        assert!(!format.is_record_start("SET total = 0"));
+    }
+
+    #[test]
+    fn test_mysql_audit_csv_and_json() {
+        let format = DatabaseLogFormat::new(DatabaseLogPreset::MysqlAudit, None).unwrap();
+        let record = format
+            .parse_record("20240115 10:00:00,db01,admin,10.0.0.9,31,402,QUERY,shop,'GRANT ALL ON shop.* TO \\'x\\'@\\'%\\'',0")
+            .unwrap();
+        assert_eq!(record.fields["user.name"], "admin");
+        assert_eq!(record.fields["db.statement_class"], "dcl");
+        assert_eq!(record.fields["event.outcome"], "success");
+
+        let record = format
+            .parse_record(r#"{"audit_record":{"name":"Query","timestamp":"2024-01-15T10:00:00Z","connection_id":"4","status":1064,"sqltext":"DROP TABLE t","user":"app[app] @ localhost []","db":"test"}}"#)
+            .unwrap();
+        assert_eq!(record.fields["user.name"], "app");
+        assert_eq!(record.fields["db.statement_class"], "ddl");
+        assert_eq!(record.fields["event.outcome"], "failure");
+    }
+}
@@ -0,0 +1,55 @@
+// Built-in parser for normalized endpoint events emitted by the eBPF collector
+
+use crate::collectors::ebpf::{EndpointEvent, EBPF_SOURCE};
+use crate::collectors::RawLogEvent;
+use crate::errors::ParserError;
+use crate::parsers::{ParsedEvent, Parser};
+use async_trait::async_trait;
+
+pub struct EndpointEventParser {
+    name: String,
+}
+
+impl EndpointEventParser {
+    pub fn new() -> Self {
+        Self {
+            name: "ebpf".to_string(),
+        }
+    }
+}
+
+impl Default for EndpointEventParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Parser for EndpointEventParser {
+    async fn parse(&self, raw_event: &RawLogEvent) -> Result<ParsedEvent, ParserError> {
+        let event: EndpointEvent = serde_json::from_str(&raw_event.raw_data)
+            .map_err(|e| ParserError::parse_failed(&format!("Invalid eBPF endpoint event: {}", e)))?;
+
+        Ok(ParsedEvent {
+            timestamp: event.timestamp(),
+            source: raw_event.source.clone(),
+            level: Some("info".to_string()),
+            message: event.message(),
+            fields: event.to_fields(),
+            raw_data: raw_event.raw_data.clone(),
+            parser_name: self.name.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source_type(&self) -> &str {
+        EBPF_SOURCE
+    }
+
+    fn can_parse(&self, raw_event: &RawLogEvent) -> bool {
+        raw_event.source == EBPF_SOURCE
+    }
+}
@@ -0,0 +1,55 @@
+// Built-in parser for events delivered by the ETW collector
+
+use crate::collectors::etw::{EtwEvent, ETW_SOURCE};
+use crate::collectors::RawLogEvent;
+use crate::errors::ParserError;
+use crate::parsers::{ParsedEvent, Parser};
+use async_trait::async_trait;
+
+pub struct EtwEventParser {
+    name: String,
+}
+
+impl EtwEventParser {
+    pub fn new() -> Self {
+        Self {
+            name: "etw".to_string(),
+        }
+    }
+}
+
+impl Default for EtwEventParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Parser for EtwEventParser {
+    async fn parse(&self, raw_event: &RawLogEvent) -> Result<ParsedEvent, ParserError> {
+        let event: EtwEvent = serde_json::from_str(&raw_event.raw_data)
+            .map_err(|e| ParserError::parse_failed(&format!("Invalid ETW event: {}", e)))?;
+
+        Ok(ParsedEvent {
+            timestamp: event.timestamp(),
+            source: raw_event.source.clone(),
+            level: Some(event.level_name().to_string()),
+            message: event.message(),
+            fields: event.to_fields(),
+            raw_data: raw_event.raw_data.clone(),
+            parser_name: self.name.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source_type(&self) -> &str {
+        ETW_SOURCE
+    }
+
+    fn can_parse(&self, raw_event: &RawLogEvent) -> bool {
+        raw_event.source == ETW_SOURCE
+    }
+}
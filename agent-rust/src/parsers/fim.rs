@@ -0,0 +1,55 @@
+// Built-in parser for file integrity events emitted by the FIM collector
+
+use crate::collectors::fim::{FimEvent, FIM_SOURCE};
+use crate::collectors::RawLogEvent;
+use crate::errors::ParserError;
+use crate::parsers::{ParsedEvent, Parser};
+use async_trait::async_trait;
+
+pub struct FimEventParser {
+    name: String,
+}
+
+impl FimEventParser {
+    pub fn new() -> Self {
+        Self {
+            name: "fim".to_string(),
+        }
+    }
+}
+
+impl Default for FimEventParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Parser for FimEventParser {
+    async fn parse(&self, raw_event: &RawLogEvent) -> Result<ParsedEvent, ParserError> {
+        let event: FimEvent = serde_json::from_str(&raw_event.raw_data)
+            .map_err(|e| ParserError::parse_failed(&format!("Invalid FIM event: {}", e)))?;
+
+        Ok(ParsedEvent {
+            timestamp: event.timestamp,
+            source: raw_event.source.clone(),
+            level: Some(event.level().to_string()),
+            message: event.message(),
+            fields: event.to_fields(),
+            raw_data: raw_event.raw_data.clone(),
+            parser_name: self.name.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source_type(&self) -> &str {
+        FIM_SOURCE
+    }
+
+    fn can_parse(&self, raw_event: &RawLogEvent) -> bool {
+        raw_event.source == FIM_SOURCE
+    }
+}
@@ -0,0 +1,278 @@
+// Offline parser test harness
+// Runs configured parsers (and their processor chains) over sample lines without starting the agent, reporting
+// which parser took each line, the fields it extracted, match rates and why the rest failed. Enrichment, alert
+// rules and the buffer are not involved, so nothing leaves the host.
+
+use super::processors::ProcessorChains;
+use super::schema::FieldSchemas;
+use super::timestamps::TimestampExtractor;
+use super::{build_parser, ParsedEvent, Parser};
+use crate::collectors::RawLogEvent;
+use crate::config::{ParserDefinition, ParserType, ParsersConfig};
+use crate::errors::ParserError;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::BufRead;
+
+/// Outcome of one sample line
+#[derive(Debug, Clone, Serialize)]
+pub struct LineResult {
+    pub line_number: usize,
+    pub line: String,
+    /// Parser that took the line; None when every candidate failed
+    pub parser: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    /// Event time read from the line; None when the event would carry the receive time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_time: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, serde_json::Value>,
+    /// Why each candidate rejected the line, as "parser: reason"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParserMatches {
+    pub name: String,
+    pub source_type: String,
+    pub matched: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HarnessReport {
+    pub lines: usize,
+    pub matched: usize,
+    pub parsers: Vec<ParserMatches>,
+    /// Matched lines that produced each field
+    pub field_coverage: BTreeMap<String, usize>,
+    /// Regex field mappings no sample line produced
+    pub never_extracted: Vec<String>,
+    /// Matched lines whose value for each field didn't fit the parser's declared schema
+    pub schema_violations: BTreeMap<String, usize>,
+    /// Problems in the definitions themselves, found before any line is parsed
+    pub warnings: Vec<String>,
+    /// The first matched lines and the first failures, up to the limits given to `run`
+    pub results: Vec<LineResult>,
+}
+
+impl HarnessReport {
+    pub fn match_rate(&self) -> f64 {
+        if self.lines == 0 {
+            return 0.0;
+        }
+        self.matched as f64 * 100.0 / self.lines as f64
+    }
+}
+
+pub struct ParserHarness {
+    definitions: Vec<ParserDefinition>,
+    parsers: Vec<Box<dyn Parser>>,
+    processor_chains: ProcessorChains,
+    field_schemas: FieldSchemas,
+    timestamps: TimestampExtractor,
+}
+
+impl ParserHarness {
+    /// Test one named parser, every parser for a source type, or (with neither) every configured parser in order
+    pub fn new(config: &ParsersConfig, parser: Option<&str>, source: Option<&str>) -> Result<Self, ParserError> {
+        let definitions: Vec<ParserDefinition> = config.parsers.iter()
+            .filter(|d| parser.is_none_or(|name| d.name == name))
+            .filter(|d| source.is_none_or(|source| d.source_type == source))
+            .cloned()
+            .collect();
+        if definitions.is_empty() {
+            return Err(ParserError::NoMatchingParser {
+                source_type: source.unwrap_or("any").to_string(),
+                available_parsers: config.parsers.iter().map(|d| d.name.clone()).collect(),
+                suggested_parser: parser.map(str::to_string),
+            });
+        }
+        let parsers = definitions.iter().map(build_parser).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            definitions,
+            parsers,
+            processor_chains: ProcessorChains::new(config)?,
+            field_schemas: FieldSchemas::new(config),
+            timestamps: TimestampExtractor::new(&config.timestamps),
+        })
+    }
+
+    /// Parse every non-empty line; the first parser that accepts a line takes it, as in the running agent.
+    /// At most `keep_matched` matched and `keep_failed` failed lines are kept in `results`.
+    pub async fn run<R: BufRead>(&self, input: R, keep_matched: usize, keep_failed: usize) -> std::io::Result<HarnessReport> {
+        let mut report = HarnessReport {
+            lines: 0,
+            matched: 0,
+            parsers: self.parsers.iter()
+                .map(|p| ParserMatches { name: p.name().to_string(), source_type: p.source_type().to_string(), matched: 0 })
+                .collect(),
+            field_coverage: BTreeMap::new(),
+            never_extracted: Vec::new(),
+            schema_violations: BTreeMap::new(),
+            warnings: self.definitions.iter().flat_map(definition_warnings).collect(),
+            results: Vec::new(),
+        };
+        let (mut kept_matched, mut kept_failed) = (0, 0);
+
+        for (index, line) in input.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            report.lines += 1;
+
+            let mut errors = Vec::new();
+            let mut parsed = None;
+            for (position, parser) in self.parsers.iter().enumerate() {
+                let raw_event = RawLogEvent {
+                    timestamp: chrono::Utc::now(),
+                    source: parser.source_type().to_string(),
+                    raw_data: line.into(),
+                    metadata: HashMap::new(),
+                };
+                match parser.parse(&raw_event).await {
+                    Ok(mut event) => {
+                        self.processor_chains.apply(&mut event);
+                        self.timestamps.apply(&mut event, raw_event.timestamp);
+                        for field in self.field_schemas.apply(&mut event) {
+                            *report.schema_violations.entry(field).or_default() += 1;
+                        }
+                        let event_time = (event.timestamp != raw_event.timestamp).then_some(event.timestamp);
+                        parsed = Some((position, event, event_time));
+                        break;
+                    }
+                    Err(e) => errors.push(format!("{}: {}", parser.name(), failure_reason(&e, line))),
+                }
+            }
+
+            let result = match parsed {
+                Some((position, event, event_time)) => {
+                    report.matched += 1;
+                    report.parsers[position].matched += 1;
+                    for field in event.fields.keys() {
+                        *report.field_coverage.entry(field.clone()).or_default() += 1;
+                    }
+                    kept_matched += 1;
+                    (kept_matched <= keep_matched).then(|| line_result(index + 1, line, Some(event), event_time, Vec::new()))
+                }
+                None => {
+                    kept_failed += 1;
+                    (kept_failed <= keep_failed).then(|| line_result(index + 1, line, None, None, errors))
+                }
+            };
+            report.results.extend(result);
+        }
+
+        report.never_extracted = self.definitions.iter()
+            .filter(|d| d.parser_type == ParserType::Regex)
+            .flat_map(|d| d.field_mappings.values())
+            .filter(|field| !report.field_coverage.contains_key(*field))
+            .cloned()
+            .collect();
+        report.never_extracted.sort();
+        report.never_extracted.dedup();
+
+        Ok(report)
+    }
+}
+
+fn line_result(
+    line_number: usize,
+    line: &str,
+    event: Option<ParsedEvent>,
+    event_time: Option<chrono::DateTime<chrono::Utc>>,
+    errors: Vec<String>,
+) -> LineResult {
+    LineResult {
+        line_number,
+        line: line.to_string(),
+        parser: event.as_ref().map(|e| e.parser_name.clone()),
+        level: event.as_ref().and_then(|e| e.level.clone()),
+        event_time,
+        fields: event.map(|e| e.fields.into_iter().collect()).unwrap_or_default(),
+        errors,
+    }
+}
+
+/// The parser's own explanation, without the sample line it echoes back
+fn failure_reason(error: &ParserError, line: &str) -> String {
+    match error {
+        ParserError::ParseFailed { input_sample, .. } => input_sample
+            .strip_suffix(line)
+            .map(|reason| reason.trim_end_matches([':', ' ']).to_string())
+            .unwrap_or_else(|| input_sample.clone()),
+        other => other.to_string(),
+    }
+}
+
+/// Capture groups that are never mapped, and mappings that name no capture group
+fn definition_warnings(definition: &ParserDefinition) -> Vec<String> {
+    if definition.parser_type != ParserType::Regex {
+        return Vec::new();
+    }
+    let Ok(regex) = Regex::new(&definition.regex_pattern) else {
+        return Vec::new();
+    };
+    let groups: Vec<&str> = regex.capture_names().flatten().collect();
+    let mut warnings: Vec<String> = groups.iter()
+        .filter(|group| !definition.field_mappings.contains_key(**group))
+        .map(|group| format!("{}: capture group '{}' has no field mapping and is discarded", definition.name, group))
+        .collect();
+    let mut missing: Vec<&String> = definition.field_mappings.keys().filter(|key| !groups.contains(&key.as_str())).collect();
+    missing.sort();
+    warnings.extend(missing.into_iter().map(|key| {
+        format!("{}: field mapping '{}' names no capture group in regex_pattern", definition.name, key)
+    }));
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_report_counts_matches_fields_and_failures() {
+        let config = ParsersConfig {
+            parsers: vec![ParserDefinition {
+                name: "sshd_auth".to_string(),
+                source_type: "syslog".to_string(),
+                parser_type: ParserType::Regex,
+                regex_pattern: r"(?P<outcome>Accepted|Failed) password for (?P<user>\S+) from (?P<ip>[\d.]+)(?: port (?P<port>\d+))?".to_string(),
+                field_mappings: HashMap::from([
+                    ("outcome".to_string(), "event.outcome".to_string()),
+                    ("user".to_string(), "user.name".to_string()),
+                    ("ip".to_string(), "source.ip".to_string()),
+                    ("pid".to_string(), "process.pid".to_string()),
+                ]),
+                processors: Vec::new(),
+                json: Default::default(),
+                schema: Default::default(),
+            }],
+            chains: HashMap::new(),
+            source_chains: HashMap::new(),
+            sample_capture: Default::default(),
+            timestamps: Default::default(),
+        };
+        let input = "Failed password for root from 203.0.113.9 port 22\n\nAccepted password for alice from 10.0.0.5\nsshd: session closed\n";
+
+        let harness = ParserHarness::new(&config, Some("sshd_auth"), None).unwrap();
+        let report = harness.run(input.as_bytes(), 10, 10).await.unwrap();
+
+        assert_eq!((report.lines, report.matched), (3, 2));
+        assert!((report.match_rate() - 66.6).abs() < 0.1);
+        assert_eq!(report.field_coverage["user.name"], 2);
+        assert_eq!(report.never_extracted, vec!["process.pid".to_string()]);
+        assert_eq!(report.warnings.len(), 2);
+
+        let failure = report.results.iter().find(|r| r.parser.is_none()).unwrap();
+        assert_eq!(failure.line_number, 4);
+        assert_eq!(failure.errors, vec!["sshd_auth: Regex pattern did not match".to_string()]);
+
+        assert!(ParserHarness::new(&config, Some("nginx_access"), None).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+// Built-in parser for decoy access alerts emitted by the honeytoken collector
+
+use crate::collectors::honeytoken::{HoneytokenEvent, HONEYTOKEN_SOURCE};
+use crate::collectors::RawLogEvent;
+use crate::errors::ParserError;
+use crate::parsers::{ParsedEvent, Parser};
+use async_trait::async_trait;
+
+pub struct HoneytokenEventParser {
+    name: String,
+}
+
+impl HoneytokenEventParser {
+    pub fn new() -> Self {
+        Self {
+            name: "honeytoken".to_string(),
+        }
+    }
+}
+
+impl Default for HoneytokenEventParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Parser for HoneytokenEventParser {
+    async fn parse(&self, raw_event: &RawLogEvent) -> Result<ParsedEvent, ParserError> {
+        let event: HoneytokenEvent = serde_json::from_str(&raw_event.raw_data)
+            .map_err(|e| ParserError::parse_failed(&format!("Invalid honeytoken event: {}", e)))?;
+
+        Ok(ParsedEvent {
+            timestamp: event.timestamp,
+            source: raw_event.source.clone(),
+            level: Some("critical".to_string()),
+            message: event.message(),
+            fields: event.to_fields(),
+            raw_data: raw_event.raw_data.clone(),
+            parser_name: self.name.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source_type(&self) -> &str {
+        HONEYTOKEN_SOURCE
+    }
+
+    fn can_parse(&self, raw_event: &RawLogEvent) -> bool {
+        raw_event.source == HONEYTOKEN_SOURCE
+    }
+}
@@ -0,0 +1,295 @@
+// Structured JSON / NDJSON parser
+// Decodes already-structured log lines directly into event fields, flattening nested objects
+// into dotted keys so they can be mapped like regex captures
+
+use crate::collectors::RawLogEvent;
+use crate::config::ParserDefinition;
+use crate::errors::ParserError;
+use crate::parsers::{ParsedEvent, Parser};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Keys checked for the event time when `timestamp_field` is not set
+const DEFAULT_TIMESTAMP_FIELDS: &[&str] = &["@timestamp", "timestamp", "time", "ts"];
+const DEFAULT_LEVEL_FIELDS: &[&str] = &["level", "severity", "log.level"];
+const DEFAULT_MESSAGE_FIELDS: &[&str] = &["message", "msg"];
+
+/// JSON-specific parser options
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JsonParserOptions {
+    /// Flatten nested objects into `parent<separator>child` keys
+    pub flatten: bool,
+    pub separator: String,
+    /// Objects nested deeper than this are kept as JSON values
+    pub max_depth: usize,
+    /// Keep fields without an entry in `field_mappings` (under their flattened name)
+    pub keep_unmapped: bool,
+    /// Field holding the event time (flattened source name); well-known names are tried when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp_field: Option<String>,
+    /// chrono format for string timestamps that aren't RFC 3339 (interpreted as UTC)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level_field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_field: Option<String>,
+}
+
+impl Default for JsonParserOptions {
+    fn default() -> Self {
+        Self {
+            flatten: true,
+            separator: ".".to_string(),
+            max_depth: 8,
+            keep_unmapped: true,
+            timestamp_field: None,
+            timestamp_format: None,
+            level_field: None,
+            message_field: None,
+        }
+    }
+}
+
+pub struct JsonParser {
+    name: String,
+    source_type: String,
+    field_mappings: HashMap<String, String>,
+    options: JsonParserOptions,
+}
+
+impl JsonParser {
+    pub fn new(definition: &ParserDefinition) -> Result<Self, ParserError> {
+        if definition.json.separator.is_empty() {
+            return Err(ParserError::parse_failed(&format!("JSON parser '{}' needs a non-empty separator", definition.name)));
+        }
+
+        Ok(Self {
+            name: definition.name.clone(),
+            source_type: definition.source_type.clone(),
+            field_mappings: definition.field_mappings.clone(),
+            options: definition.json.clone(),
+        })
+    }
+
+    fn flatten(&self, object: Map<String, Value>) -> HashMap<String, Value> {
+        let mut flattened = HashMap::new();
+        if self.options.flatten {
+            flatten_into(&mut flattened, None, object, &self.options.separator, self.options.max_depth);
+        } else {
+            flattened.extend(object);
+        }
+        flattened
+    }
+
+    fn map_fields(&self, flattened: HashMap<String, Value>) -> HashMap<String, Value> {
+        if self.field_mappings.is_empty() {
+            return flattened;
+        }
+
+        let mut fields = HashMap::with_capacity(flattened.len());
+        for (key, value) in flattened {
+            match self.field_mappings.get(&key) {
+                Some(mapped) => {
+                    fields.insert(mapped.clone(), value);
+                }
+                None if self.options.keep_unmapped => {
+                    fields.entry(key).or_insert(value);
+                }
+                None => {}
+            }
+        }
+        fields
+    }
+
+    fn event_timestamp(&self, fields: &HashMap<String, Value>) -> Option<chrono::DateTime<chrono::Utc>> {
+        let value = match &self.options.timestamp_field {
+            Some(field) => fields.get(field),
+            None => DEFAULT_TIMESTAMP_FIELDS.iter().find_map(|f| fields.get(*f)),
+        }?;
+
+        match value {
+            Value::String(s) => chrono::DateTime::parse_from_rfc3339(s)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .ok()
+                .or_else(|| {
+                    let format = self.options.timestamp_format.as_deref()?;
+                    chrono::NaiveDateTime::parse_from_str(s, format).ok().map(|t| t.and_utc())
+                }),
+            // Epoch seconds, or milliseconds for values too large to be seconds
+            Value::Number(n) => {
+                let epoch = n.as_f64()?;
+                let millis = if epoch.abs() >= 1e11 { epoch } else { epoch * 1000.0 };
+                chrono::DateTime::from_timestamp_millis(millis as i64)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Recursively flatten `object` into `out`, joining keys with `separator`
+fn flatten_into(out: &mut HashMap<String, Value>, prefix: Option<&str>, object: Map<String, Value>, separator: &str, depth: usize) {
+    for (key, value) in object {
+        let key = match prefix {
+            Some(prefix) => format!("{}{}{}", prefix, separator, key),
+            None => key,
+        };
+        match value {
+            Value::Object(nested) if depth > 1 && !nested.is_empty() => {
+                flatten_into(out, Some(&key), nested, separator, depth - 1);
+            }
+            other => {
+                out.insert(key, other);
+            }
+        }
+    }
+}
+
+fn first_string(fields: &HashMap<String, Value>, configured: Option<&str>, defaults: &[&str]) -> Option<String> {
+    match configured {
+        Some(field) => fields.get(field),
+        None => defaults.iter().find_map(|f| fields.get(*f)),
+    }
+    .and_then(|v| v.as_str())
+    .map(|s| s.to_string())
+}
+
+#[async_trait]
+impl Parser for JsonParser {
+    async fn parse(&self, raw_event: &RawLogEvent) -> Result<ParsedEvent, ParserError> {
+        debug!("🔍 Parsing event with '{}' JSON parser", self.name);
+
+        let object = match serde_json::from_str::<Value>(raw_event.raw_data.trim()) {
+            Ok(Value::Object(object)) => object,
+            Ok(_) => return Err(ParserError::parse_failed("JSON log line is not an object")),
+            Err(e) => return Err(ParserError::parse_failed(&format!("Invalid JSON at line {} column {}: {}", e.line(), e.column(), e))),
+        };
+        // Timestamp, level and message are looked up by source name, before mapping
+        let flattened = self.flatten(object);
+        let timestamp = self.event_timestamp(&flattened).unwrap_or(raw_event.timestamp);
+        let level = first_string(&flattened, self.options.level_field.as_deref(), DEFAULT_LEVEL_FIELDS);
+        let message = first_string(&flattened, self.options.message_field.as_deref(), DEFAULT_MESSAGE_FIELDS)
+            .unwrap_or_else(|| raw_event.raw_data.to_string());
+        let fields = self.map_fields(flattened);
+
+        Ok(ParsedEvent {
+            timestamp,
+            source: raw_event.source.clone(),
+            level,
+            message,
+            fields,
+            raw_data: raw_event.raw_data.clone(),
+            parser_name: self.name.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source_type(&self) -> &str {
+        &self.source_type
+    }
+
+    fn can_parse(&self, raw_event: &RawLogEvent) -> bool {
+        let data = raw_event.raw_data.trim();
+        raw_event.source == self.source_type && data.starts_with('{') && data.ends_with('}')
+    }
+}
+
+/// Split a raw event carrying several newline-delimited JSON records into one event per record
+pub fn split_ndjson(raw_event: &RawLogEvent) -> Option<Vec<RawLogEvent>> {
+    let records: Vec<&str> = raw_event.raw_data.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    if records.len() < 2 || !records.iter().all(|l| l.starts_with('{') && l.ends_with('}')) {
+        return None;
+    }
+
+    Some(records
+        .into_iter()
+        .map(|record| RawLogEvent {
+            timestamp: raw_event.timestamp,
+            source: raw_event.source.clone(),
+            raw_data: record.into(),
+            metadata: raw_event.metadata.clone(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(field_mappings: HashMap<String, String>, json: JsonParserOptions) -> ParserDefinition {
+        ParserDefinition {
+            name: "app_json".to_string(),
+            source_type: "file_monitor".to_string(),
+            parser_type: crate::config::ParserType::Json,
+            regex_pattern: String::new(),
+            field_mappings,
+            processors: Vec::new(),
+            json,
+            schema: Default::default(),
+        }
+    }
+
+    fn raw(raw_data: &str) -> RawLogEvent {
+        RawLogEvent {
+            timestamp: chrono::Utc::now(),
+            source: "file_monitor".to_string(),
+            raw_data: raw_data.into(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nested_fields_are_flattened_and_mapped() {
+        let parser = JsonParser::new(&definition(
+            HashMap::from([("http.request.method".to_string(), "http.method".to_string())]),
+            JsonParserOptions::default(),
+        )).unwrap();
+        let event = raw(r#"{"ts":"2024-05-01T12:00:00Z","level":"warn","msg":"slow","http":{"request":{"method":"GET","bytes":512}},"tags":["a"]}"#);
+
+        assert!(parser.can_parse(&event));
+        let parsed = parser.parse(&event).await.unwrap();
+        assert_eq!(parsed.fields["http.method"], "GET");
+        assert_eq!(parsed.fields["http.request.bytes"], 512);
+        assert_eq!(parsed.fields["tags"], serde_json::json!(["a"]));
+        assert_eq!(parsed.level.as_deref(), Some("warn"));
+        assert_eq!(parsed.message, "slow");
+        assert_eq!(parsed.timestamp.to_rfc3339(), "2024-05-01T12:00:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_options_and_unmapped_fields() {
+        let options = JsonParserOptions {
+            keep_unmapped: false,
+            timestamp_field: Some("when".to_string()),
+            timestamp_format: Some("%d/%m/%Y %H:%M:%S".to_string()),
+            ..Default::default()
+        };
+        let parser = JsonParser::new(&definition(
+            HashMap::from([("user.id".to_string(), "user.id".to_string())]),
+            options,
+        )).unwrap();
+
+        let parsed = parser.parse(&raw(r#"{"when":"01/05/2024 08:30:00","user":{"id":7},"noise":1}"#)).await.unwrap();
+        assert_eq!(parsed.timestamp.to_rfc3339(), "2024-05-01T08:30:00+00:00");
+        assert_eq!(parsed.fields.len(), 1);
+
+        assert!(parser.parse(&raw("{\"unterminated\": ")).await.is_err());
+        assert!(!parser.can_parse(&raw("plain text line")));
+    }
+
+    #[test]
+    fn test_split_ndjson() {
+        let records = split_ndjson(&raw("{\"a\":1}\n\n{\"a\":2}\n")).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(&*records[1].raw_data, "{\"a\":2}");
+        assert!(split_ndjson(&raw("{\"a\":1}")).is_none());
+        assert!(split_ndjson(&raw("{\"a\":1}\nnot json")).is_none());
+    }
+}
@@ -1,14 +1,46 @@
-// Pluggable parsing engine with regex-based parsers
+// Pluggable parsing engine with regex-based and structured JSON parsers
 
+use crate::alert_rules::AlertEngine;
 use crate::collectors::RawLogEvent;
-use crate::config::{ParsersConfig, ParserDefinition};
+use crate::component_usage;
+use crate::config::{ParsersConfig, ParserDefinition, ParserType};
+use crate::ecs::EcsNormalizer;
+use crate::enrichment::EnrichmentPipeline;
+use crate::live_tail::LiveTail;
+use crate::pipeline_metrics::{self, Stage};
+use crate::process_lineage::ProcessLineageCache;
+use crate::sigma::SigmaEngine;
 use crate::errors::ParserError;
 use async_trait::async_trait;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
 use tracing::{debug, warn, error};
 
+use json::JsonParser;
+use processors::ProcessorChains;
+use samples::UnmatchedSampleStore;
+use schema::FieldSchemas;
+use timestamps::TimestampExtractor;
+
+pub mod container;
+pub mod database;
+pub mod ebpf;
+pub mod etw;
+pub mod fim;
+pub mod harness;
+pub mod honeytoken;
+pub mod json;
+pub mod packet_metadata;
+pub mod processors;
+pub mod samples;
+pub mod schema;
+pub mod session;
+pub mod syslog;
+pub mod timestamps;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParsedEvent {
     pub timestamp: chrono::DateTime<chrono::Utc>,
@@ -16,7 +48,8 @@ pub struct ParsedEvent {
     pub level: Option<String>,
     pub message: String,
     pub fields: HashMap<String, serde_json::Value>,
-    pub raw_data: String,
+    /// The collector's payload; cloning an event or fanning it out to destinations doesn't copy it
+    pub raw_data: Arc<str>,
     pub parser_name: String,
 }
 
@@ -99,7 +132,7 @@ impl Parser for RegexParser {
             .or_else(|| fields.get("msg"))
             .and_then(|v| v.as_str())
             .map(|s| s.to_string())
-            .unwrap_or_else(|| raw_event.raw_data.clone());
+            .unwrap_or_else(|| raw_event.raw_data.to_string());
         
         let parsed_event = ParsedEvent {
             timestamp: raw_event.timestamp,
@@ -151,7 +184,7 @@ impl Parser for PassthroughParser {
             timestamp: raw_event.timestamp,
             source: raw_event.source.clone(),
             level: None,
-            message: raw_event.raw_data.clone(),
+            message: raw_event.raw_data.to_string(),
             fields: HashMap::new(),
             raw_data: raw_event.raw_data.clone(),
             parser_name: self.name.clone(),
@@ -171,9 +204,28 @@ impl Parser for PassthroughParser {
     }
 }
 
+/// Build the parser described by a configuration entry
+pub fn build_parser(definition: &ParserDefinition) -> Result<Box<dyn Parser>, ParserError> {
+    Ok(match definition.parser_type {
+        ParserType::Regex => Box::new(RegexParser::new(definition)?),
+        ParserType::Json => Box::new(JsonParser::new(definition)?),
+    })
+}
+
 pub struct ParsingEngine {
     parsers: Vec<Box<dyn Parser>>,
+    parser_types: HashMap<String, ParserType>,
     fallback_parsers: HashMap<String, Box<dyn Parser>>,
+    processor_chains: ProcessorChains,
+    field_schemas: FieldSchemas,
+    timestamps: TimestampExtractor,
+    sample_store: Option<Arc<UnmatchedSampleStore>>,
+    ecs: Option<Arc<EcsNormalizer>>,
+    process_lineage: Option<ProcessLineageCache>,
+    alert_engine: Option<Arc<AlertEngine>>,
+    enrichment: Option<Arc<EnrichmentPipeline>>,
+    sigma_engine: Option<Arc<SigmaEngine>>,
+    live_tail: Option<Arc<LiveTail>>,
 }
 
 impl ParsingEngine {
@@ -181,12 +233,12 @@ impl ParsingEngine {
         let mut parsers: Vec<Box<dyn Parser>> = Vec::new();
         let mut fallback_parsers = HashMap::new();
         
-        // Create regex parsers from configuration
+        // Create regex and JSON parsers from configuration
         for parser_def in &config.parsers {
-            match RegexParser::new(parser_def) {
+            match build_parser(parser_def) {
                 Ok(parser) => {
                     debug!("📋 Loaded parser: {} for source type: {}", parser.name(), parser.source_type());
-                    parsers.push(Box::new(parser));
+                    parsers.push(parser);
                 }
                 Err(e) => {
                     error!("❌ Failed to create parser '{}': {}", parser_def.name, e);
@@ -204,23 +256,160 @@ impl ParsingEngine {
             );
         }
         
+        let processor_chains = ProcessorChains::new(config)?;
+        if !processor_chains.is_empty() {
+            debug!("🔗 Loaded {} named processor chains", config.chains.len());
+        }
+        
+        let sample_store = config.sample_capture.enabled
+            .then(|| Arc::new(UnmatchedSampleStore::open(config.sample_capture.clone())));
+        
         Ok(Self {
             parsers,
+            parser_types: parser_types(config),
             fallback_parsers,
+            processor_chains,
+            field_schemas: FieldSchemas::new(config),
+            timestamps: TimestampExtractor::new(&config.timestamps),
+            sample_store,
+            ecs: None,
+            process_lineage: None,
+            alert_engine: None,
+            enrichment: None,
+            sigma_engine: None,
+            live_tail: None,
         })
     }
     
+    /// Register a built-in parser that handles every event from its source type
+    /// when no configured parser matches (replaces the passthrough fallback)
+    pub fn register_source_parser(&mut self, parser: Box<dyn Parser>) {
+        debug!("📋 Registered built-in parser: {} for source type: {}", parser.name(), parser.source_type());
+        self.fallback_parsers.insert(parser.source_type().to_string(), parser);
+    }
+    
+    /// A built-in or passthrough parser already handles `source`
+    pub fn has_source_parser(&self, source: &str) -> bool {
+        self.fallback_parsers.contains_key(source)
+    }
+    
+    /// Rename parsed fields to ECS names before enrichment and detection see them
+    pub fn set_ecs_normalizer(&mut self, normalizer: Arc<EcsNormalizer>) {
+        self.ecs = Some(normalizer);
+    }
+    
+    /// Attribute events from local process sources to their process and its ancestors
+    pub fn set_process_lineage(&mut self, cache: ProcessLineageCache) {
+        self.process_lineage = Some(cache);
+    }
+    
+    /// Enrich every successfully parsed event before alert rules see it
+    pub fn set_enrichment_pipeline(&mut self, pipeline: Arc<EnrichmentPipeline>) {
+        self.enrichment = Some(pipeline);
+    }
+    
+    /// Evaluate alert rules against every successfully parsed event
+    pub fn set_alert_engine(&mut self, engine: Arc<AlertEngine>) {
+        self.alert_engine = Some(engine);
+    }
+    
+    /// Evaluate Sigma rules against every parsed event; their synthetic alerts follow the matching event
+    pub fn set_sigma_engine(&mut self, engine: Arc<SigmaEngine>) {
+        self.sigma_engine = Some(engine);
+    }
+    
+    /// Offer every parsed event, alerts included, to live tail watchers
+    pub fn set_live_tail(&mut self, tail: Arc<LiveTail>) {
+        self.live_tail = Some(tail);
+    }
+    
+    /// Samples of unmatched events, when sample capture is enabled
+    /// Capture unmatched events into an existing store instead of the one built from the configuration
+    pub fn set_sample_store(&mut self, store: Option<Arc<UnmatchedSampleStore>>) {
+        self.sample_store = store;
+    }
+    
+    pub fn sample_store(&self) -> Option<Arc<UnmatchedSampleStore>> {
+        self.sample_store.clone()
+    }
+    
+    fn capture_unmatched(&self, raw_event: &RawLogEvent, reason: &str) {
+        if let Some(store) = &self.sample_store {
+            store.record(raw_event, reason);
+        }
+    }
+    
+    /// Parse a raw event that may carry several newline-delimited JSON records, one event per record
+    pub async fn parse_events(&self, raw_event: &RawLogEvent) -> Result<Vec<ParsedEvent>, ParserError> {
+        let has_json_parser = self.parsers.iter().any(|p| {
+            p.source_type() == raw_event.source && self.parser_types.get(p.name()) == Some(&ParserType::Json)
+        });
+        let records = if has_json_parser { json::split_ndjson(raw_event) } else { None };
+        
+        let mut events = Vec::new();
+        match records {
+            Some(records) => {
+                for record in &records {
+                    self.parse_and_detect(record, &mut events).await?;
+                }
+            }
+            None => self.parse_and_detect(raw_event, &mut events).await?,
+        }
+        Ok(events)
+    }
+    
+    /// Parse one record, followed by any Sigma alerts it raised
+    async fn parse_and_detect(&self, raw_event: &RawLogEvent, events: &mut Vec<ParsedEvent>) -> Result<(), ParserError> {
+        let mut event = self.parse_event(raw_event).await?;
+        let alerts = match &self.sigma_engine {
+            Some(sigma) => sigma.evaluate(&mut event),
+            None => Vec::new(),
+        };
+        if let Some(tail) = &self.live_tail {
+            tail.publish(&event);
+            alerts.iter().for_each(|alert| tail.publish(alert));
+        }
+        events.push(event);
+        events.extend(alerts);
+        Ok(())
+    }
+    
     pub async fn parse_event(&self, raw_event: &RawLogEvent) -> Result<ParsedEvent, ParserError> {
+        let started = Instant::now();
+        let parsed = component_usage::instrument(component_usage::PARSER, self.match_and_parse(raw_event)).await;
+        let parser = parsed.as_ref().map_or(pipeline_metrics::UNMATCHED, |event| event.parser_name.as_str());
+        pipeline_metrics::record(Stage::Parse, parser, started.elapsed(), 1);
+        let mut parsed_event = parsed?;
+        if let Some(ecs) = &self.ecs {
+            ecs.normalize(&mut parsed_event);
+        }
+        if let Some(lineage) = &self.process_lineage {
+            lineage.attribute(&mut parsed_event);
+        }
+        if let Some(enrichment) = &self.enrichment {
+            enrichment.enrich(&mut parsed_event).await;
+        }
+        if let Some(alert_engine) = &self.alert_engine {
+            alert_engine.evaluate(&mut parsed_event);
+        }
+        Ok(parsed_event)
+    }
+    
+    async fn match_and_parse(&self, raw_event: &RawLogEvent) -> Result<ParsedEvent, ParserError> {
+        let mut last_error = None;
+        
         // Try to find a matching parser
         for parser in &self.parsers {
             if parser.can_parse(raw_event) {
                 match parser.parse(raw_event).await {
-                    Ok(parsed_event) => {
+                    Ok(mut parsed_event) => {
                         debug!("✅ Event parsed successfully by '{}'", parser.name());
+                        self.post_process(&mut parsed_event, raw_event);
                         return Ok(parsed_event);
                     }
                     Err(e) => {
                         warn!("⚠️  Parser '{}' failed to parse event: {}", parser.name(), e);
+                        last_error = Some(format!("{}: {}", parser.name(), e));
                         // Continue to try other parsers
                     }
                 }
@@ -230,10 +419,22 @@ impl ParsingEngine {
         // If no specific parser worked, try fallback parser
         if let Some(fallback_parser) = self.fallback_parsers.get(&raw_event.source) {
             debug!("🔄 Using fallback parser for source: {}", raw_event.source);
-            return fallback_parser.parse(raw_event).await;
+            let mut parsed_event = match fallback_parser.parse(raw_event).await {
+                Ok(parsed_event) => parsed_event,
+                Err(e) => {
+                    self.capture_unmatched(raw_event, &format!("{}: {}", fallback_parser.name(), e));
+                    return Err(e);
+                }
+            };
+            if fallback_parser.name().starts_with("passthrough") {
+                self.capture_unmatched(raw_event, last_error.as_deref().unwrap_or(samples::REASON_PASSTHROUGH));
+            }
+            self.post_process(&mut parsed_event, raw_event);
+            return Ok(parsed_event);
         }
         
         // If all else fails, return an error
+        self.capture_unmatched(raw_event, last_error.as_deref().unwrap_or(samples::REASON_NO_MATCHING_PARSER));
         Err(ParserError::NoMatchingParser {
             source_type: raw_event.source.clone(),
             available_parsers: self.parsers.iter().map(|p| p.name().to_string()).collect(),
@@ -241,6 +442,16 @@ impl ParsingEngine {
         })
     }
     
+    /// Processor chains, then event time extraction, then the parser's field schema
+    fn post_process(&self, event: &mut ParsedEvent, raw_event: &RawLogEvent) {
+        self.processor_chains.apply(event);
+        self.timestamps.apply(event, raw_event.timestamp);
+        let violations = self.field_schemas.apply(event);
+        if !violations.is_empty() {
+            debug!("🧾 Parser '{}' produced fields that violate its schema: {}", event.parser_name, violations.join(", "));
+        }
+    }
+    
     pub fn get_parser_stats(&self) -> Vec<ParserStats> {
         let mut stats = Vec::new();
         
@@ -248,7 +459,8 @@ impl ParsingEngine {
             stats.push(ParserStats {
                 name: parser.name().to_string(),
                 source_type: parser.source_type().to_string(),
-                parser_type: "regex".to_string(),
+                parser_type: self.parser_types.get(parser.name()).copied().unwrap_or_default().as_str().to_string(),
+                schema_violations: self.field_schemas.violations(parser.name()),
             });
         }
         
@@ -256,25 +468,25 @@ impl ParsingEngine {
             stats.push(ParserStats {
                 name: parser.name().to_string(),
                 source_type: source.clone(),
-                parser_type: "passthrough".to_string(),
+                parser_type: if parser.name().starts_with("passthrough") { "passthrough" } else { "builtin" }.to_string(),
+                schema_violations: 0,
             });
         }
         
         stats
     }
     
+    /// Replace the configured parsers and processor chains; on error the current ones stay in place
     pub async fn reload_parsers(&mut self, config: &ParsersConfig) -> Result<(), ParserError> {
         debug!("🔄 Reloading parsers from configuration");
         
-        // Clear existing parsers
-        self.parsers.clear();
-        
-        // Reload from configuration
+        // Build everything before swapping so a bad definition leaves the engine untouched
+        let mut parsers = Vec::with_capacity(config.parsers.len());
         for parser_def in &config.parsers {
-            match RegexParser::new(parser_def) {
+            match build_parser(parser_def) {
                 Ok(parser) => {
                     debug!("📋 Reloaded parser: {} for source type: {}", parser.name(), parser.source_type());
-                    self.parsers.push(Box::new(parser));
+                    parsers.push(parser);
                 }
                 Err(e) => {
                     error!("❌ Failed to reload parser '{}': {}", parser_def.name, e);
@@ -282,17 +494,30 @@ impl ParsingEngine {
                 }
             }
         }
+        let processor_chains = ProcessorChains::new(config)?;
+        
+        self.parsers = parsers;
+        self.parser_types = parser_types(config);
+        self.processor_chains = processor_chains;
+        self.field_schemas = FieldSchemas::new(config);
+        self.timestamps = TimestampExtractor::new(&config.timestamps);
         
         debug!("✅ Successfully reloaded {} parsers", self.parsers.len());
         Ok(())
     }
 }
 
+fn parser_types(config: &ParsersConfig) -> HashMap<String, ParserType> {
+    config.parsers.iter().map(|p| (p.name.clone(), p.parser_type)).collect()
+}
+
 #[derive(Debug, Serialize)]
 pub struct ParserStats {
     pub name: String,
     pub source_type: String,
     pub parser_type: String,
+    /// Fields that didn't fit the parser's declared schema since it was loaded
+    pub schema_violations: u64,
 }
 
 #[cfg(test)]
@@ -305,11 +530,15 @@ mod tests {
         let definition = ParserDefinition {
             name: "test_parser".to_string(),
             source_type: "test".to_string(),
+            parser_type: ParserType::Regex,
             regex_pattern: r"^(?P<level>\w+): (?P<message>.*)$".to_string(),
             field_mappings: HashMap::from([
                 ("level".to_string(), "log.level".to_string()),
                 ("message".to_string(), "message".to_string()),
             ]),
+            processors: Vec::new(),
+            json: Default::default(),
+            schema: Default::default(),
         };
         
         let parser = RegexParser::new(&definition).unwrap();
@@ -317,7 +546,7 @@ mod tests {
         let raw_event = RawLogEvent {
             timestamp: Utc::now(),
             source: "test".to_string(),
-            raw_data: "INFO: This is a test message".to_string(),
+            raw_data: "INFO: This is a test message".into(),
             metadata: HashMap::new(),
         };
         
@@ -329,4 +558,46 @@ mod tests {
         assert!(parsed.fields.contains_key("log.level"));
         assert!(parsed.fields.contains_key("message"));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_process_events_are_attributed_with_their_ancestry() {
+        use crate::process_lineage::ProcessLineageConfig;
+
+        let config = ParsersConfig {
+            parsers: vec![ParserDefinition {
+                name: "ebpf_exec".to_string(),
+                source_type: "ebpf".to_string(),
+                parser_type: ParserType::Regex,
+                regex_pattern: r"^exec pid=(?P<pid>\d+) ppid=(?P<ppid>\d+)(?: exe=(?P<exe>\S+))?$".to_string(),
+                field_mappings: HashMap::from([
+                    ("pid".to_string(), "process.pid".to_string()),
+                    ("ppid".to_string(), "process.parent.pid".to_string()),
+                    ("exe".to_string(), "process.executable".to_string()),
+                ]),
+                processors: Vec::new(),
+                json: Default::default(),
+                schema: Default::default(),
+            }],
+            chains: HashMap::new(),
+            source_chains: HashMap::new(),
+            sample_capture: Default::default(),
+            timestamps: Default::default(),
+        };
+        let mut engine = ParsingEngine::new(&config).unwrap();
+        engine.set_process_lineage(ProcessLineageCache::new(ProcessLineageConfig::default()));
+
+        let raw = |line: &str| RawLogEvent {
+            timestamp: Utc::now(),
+            source: "ebpf".to_string(),
+            raw_data: line.into(),
+            metadata: HashMap::new(),
+        };
+        engine.parse_event(&raw("exec pid=4100100 ppid=1 exe=/usr/sbin/sshd")).await.unwrap();
+        engine.parse_event(&raw("exec pid=4100200 ppid=4100100 exe=/bin/bash")).await.unwrap();
+
+        // The child only carries PIDs; its parent and grandparent come from the cache
+        let child = engine.parse_event(&raw("exec pid=4100300 ppid=4100200")).await.unwrap();
+        assert_eq!(child.fields["process.parent.executable"], "/bin/bash");
+        assert_eq!(child.fields["process.parent.parent.name"], "sshd");
+    }
+}
@@ -0,0 +1,55 @@
+// Built-in parser for DNS, TLS and HTTP metadata emitted by the packet metadata collector
+
+use crate::collectors::packet_metadata::{NetworkMetadataEvent, PACKET_METADATA_SOURCE};
+use crate::collectors::RawLogEvent;
+use crate::errors::ParserError;
+use crate::parsers::{ParsedEvent, Parser};
+use async_trait::async_trait;
+
+pub struct NetworkMetadataParser {
+    name: String,
+}
+
+impl NetworkMetadataParser {
+    pub fn new() -> Self {
+        Self {
+            name: "packet_metadata".to_string(),
+        }
+    }
+}
+
+impl Default for NetworkMetadataParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Parser for NetworkMetadataParser {
+    async fn parse(&self, raw_event: &RawLogEvent) -> Result<ParsedEvent, ParserError> {
+        let event: NetworkMetadataEvent = serde_json::from_str(&raw_event.raw_data)
+            .map_err(|e| ParserError::parse_failed(&format!("Invalid packet metadata event: {}", e)))?;
+
+        Ok(ParsedEvent {
+            timestamp: event.timestamp(),
+            source: raw_event.source.clone(),
+            level: Some("info".to_string()),
+            message: event.message(),
+            fields: event.to_fields(),
+            raw_data: raw_event.raw_data.clone(),
+            parser_name: self.name.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source_type(&self) -> &str {
+        PACKET_METADATA_SOURCE
+    }
+
+    fn can_parse(&self, raw_event: &RawLogEvent) -> bool {
+        raw_event.source == PACKET_METADATA_SOURCE
+    }
+}
@@ -0,0 +1,633 @@
+// Named processor chains applied to parsed events
+// Chains are defined once under `parsers.chains` and attached to parsers or sources by name,
+// so common transformations (normalization, PII redaction) aren't repeated per parser.
+// Steps can extract further fields, convert and mask values, run conditionally and tag events for routing.
+
+use crate::config::ParsersConfig;
+use crate::errors::ParserError;
+use crate::parsers::ParsedEvent;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Replacement used by `redact` steps without an explicit replacement
+pub const DEFAULT_REDACTION: &str = "[REDACTED]";
+
+/// Field `route` steps set; destinations pick it up with `field = "event.route"` routes
+pub const ROUTE_FIELD: &str = "event.route";
+
+/// Maximum depth of nested chain references
+const MAX_CHAIN_DEPTH: usize = 16;
+
+/// A single transformation step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProcessorStep {
+    /// Move a field to a new name
+    Rename { from: String, to: String },
+    /// Copy a field, keeping the original
+    Copy { from: String, to: String },
+    /// Set a field to a constant value
+    Set { field: String, value: Value },
+    /// Remove fields
+    Remove { fields: Vec<String> },
+    /// Lowercase string fields
+    Lowercase { fields: Vec<String> },
+    /// Replace field values with a fixed marker
+    Redact {
+        fields: Vec<String>,
+        #[serde(default)]
+        replacement: Option<String>,
+    },
+    /// Replace field values with their SHA-256 hex digest (keeps values joinable)
+    Hash { fields: Vec<String> },
+    /// Replace all but the first `keep_start` and last `keep_end` characters with `mask_char`
+    Mask {
+        fields: Vec<String>,
+        #[serde(default)]
+        keep_start: usize,
+        #[serde(default)]
+        keep_end: usize,
+        #[serde(default = "default_mask_char")]
+        mask_char: char,
+    },
+    /// Convert field values to another type; values that cannot be converted are left as they are
+    Convert { fields: Vec<String>, to: FieldType },
+    /// Match a regex against a field (or the message) and store its named groups as fields,
+    /// under the group name unless `fields` maps it to another name
+    Extract {
+        field: String,
+        pattern: Pattern,
+        #[serde(default)]
+        fields: HashMap<String, String>,
+    },
+    /// Tag the event for a named destination through `event.route`
+    Route { destination: String },
+    /// Run `steps` when the condition holds, `otherwise` when it does not
+    If {
+        when: Condition,
+        #[serde(default)]
+        steps: Vec<ProcessorStep>,
+        #[serde(default)]
+        otherwise: Vec<ProcessorStep>,
+    },
+    /// Run another named chain in place
+    Chain { name: String },
+}
+
+fn default_mask_char() -> char {
+    '*'
+}
+
+/// Target type of a `convert` step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    Integer,
+    Float,
+    Boolean,
+    String,
+}
+
+impl FieldType {
+    pub(crate) fn convert(self, value: &Value) -> Option<Value> {
+        let text = value_text(value);
+        let text = text.trim();
+        match self {
+            FieldType::Integer => match value {
+                Value::Number(n) if n.is_i64() || n.is_u64() => Some(value.clone()),
+                Value::Number(n) => n.as_f64().filter(|f| f.fract() == 0.0).map(|f| Value::from(f as i64)),
+                Value::Bool(b) => Some(Value::from(*b as i64)),
+                _ => text.parse::<i64>().ok().map(Value::from),
+            },
+            FieldType::Float => match value {
+                Value::Number(n) => n.as_f64().map(Value::from),
+                _ => text.parse::<f64>().ok().filter(|f| f.is_finite()).map(Value::from),
+            },
+            FieldType::Boolean => match value {
+                Value::Bool(_) => Some(value.clone()),
+                _ => match text.to_ascii_lowercase().as_str() {
+                    "true" | "yes" | "on" | "1" => Some(Value::Bool(true)),
+                    "false" | "no" | "off" | "0" => Some(Value::Bool(false)),
+                    _ => None,
+                },
+            },
+            FieldType::String => Some(Value::String(text.to_string())),
+        }
+    }
+}
+
+/// A regex compiled when the configuration is loaded, kept as its source text for serialization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern(Regex);
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Regex::new(&pattern).map(Pattern)
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.0.as_str().to_string()
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+/// Test against one field of the event; every test that is set must pass
+///
+/// `field` names an event field; `message`, `level`, `source` and `parser` fall back to the event's own
+/// attributes when no field of that name exists.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Condition {
+    pub field: String,
+    /// Whether the field must be present (true) or absent (false)
+    pub exists: Option<bool>,
+    /// Value the field must equal, compared as text
+    pub equals: Option<String>,
+    /// Values the field may equal, compared as text
+    pub one_of: Vec<String>,
+    /// Regex the field's text must match
+    pub matches: Option<Pattern>,
+}
+
+impl Condition {
+    pub fn holds(&self, event: &ParsedEvent) -> bool {
+        let value = lookup(event, &self.field);
+        if let Some(exists) = self.exists {
+            if value.is_some() != exists {
+                return false;
+            }
+        }
+        let has_value_tests = self.equals.is_some() || !self.one_of.is_empty() || self.matches.is_some();
+        if !has_value_tests {
+            return true;
+        }
+        let Some(text) = value else {
+            return false;
+        };
+        self.equals.as_ref().is_none_or(|expected| *expected == text)
+            && (self.one_of.is_empty() || self.one_of.contains(&text))
+            && self.matches.as_ref().is_none_or(|pattern| pattern.0.is_match(&text))
+    }
+}
+
+/// Text of a field, or of the event attribute with that name when the field is absent
+fn lookup(event: &ParsedEvent, field: &str) -> Option<String> {
+    if let Some(value) = event.fields.get(field) {
+        return Some(value_text(value));
+    }
+    match field {
+        "message" => Some(event.message.clone()),
+        "level" => event.level.clone(),
+        "source" => Some(event.source.clone()),
+        "parser" => Some(event.parser_name.clone()),
+        _ => None,
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn mask(text: &str, keep_start: usize, keep_end: usize, mask_char: char) -> String {
+    let length = text.chars().count();
+    if keep_start + keep_end >= length {
+        return std::iter::repeat_n(mask_char, length).collect();
+    }
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| if i < keep_start || i >= length - keep_end { c } else { mask_char })
+        .collect()
+}
+
+impl ProcessorStep {
+    fn apply(&self, event: &mut ParsedEvent) {
+        match self {
+            ProcessorStep::If { when, steps, otherwise } => {
+                let branch = if when.holds(event) { steps } else { otherwise };
+                for step in branch {
+                    step.apply(event);
+                }
+                return;
+            }
+            ProcessorStep::Extract { field, pattern, fields: mappings } => {
+                let Some(text) = lookup(event, field) else {
+                    return;
+                };
+                let Some(captures) = pattern.0.captures(&text) else {
+                    return;
+                };
+                for group in pattern.0.capture_names().flatten() {
+                    if let Some(value) = captures.name(group) {
+                        let name = mappings.get(group).cloned().unwrap_or_else(|| group.to_string());
+                        event.fields.insert(name, Value::String(value.as_str().to_string()));
+                    }
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let fields = &mut event.fields;
+        match self {
+            ProcessorStep::Rename { from, to } => {
+                if let Some(value) = fields.remove(from) {
+                    fields.insert(to.clone(), value);
+                }
+            }
+            ProcessorStep::Copy { from, to } => {
+                if let Some(value) = fields.get(from).cloned() {
+                    fields.insert(to.clone(), value);
+                }
+            }
+            ProcessorStep::Set { field, value } => {
+                fields.insert(field.clone(), value.clone());
+            }
+            ProcessorStep::Remove { fields: names } => {
+                for name in names {
+                    fields.remove(name);
+                }
+            }
+            ProcessorStep::Lowercase { fields: names } => {
+                for name in names {
+                    if let Some(Value::String(s)) = fields.get_mut(name) {
+                        *s = s.to_lowercase();
+                    }
+                }
+            }
+            ProcessorStep::Redact { fields: names, replacement } => {
+                let replacement = replacement.as_deref().unwrap_or(DEFAULT_REDACTION);
+                for name in names {
+                    if let Some(value) = fields.get_mut(name) {
+                        *value = Value::String(replacement.to_string());
+                    }
+                }
+            }
+            ProcessorStep::Hash { fields: names } => {
+                for name in names {
+                    if let Some(value) = fields.get_mut(name) {
+                        let text = match &*value {
+                            Value::String(s) => s.clone(),
+                            other => other.to_string(),
+                        };
+                        let digest = ring::digest::digest(&ring::digest::SHA256, text.as_bytes());
+                        *value = Value::String(digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect());
+                    }
+                }
+            }
+            ProcessorStep::Mask { fields: names, keep_start, keep_end, mask_char } => {
+                for name in names {
+                    if let Some(value) = fields.get_mut(name) {
+                        *value = Value::String(mask(&value_text(value), *keep_start, *keep_end, *mask_char));
+                    }
+                }
+            }
+            ProcessorStep::Convert { fields: names, to } => {
+                for name in names {
+                    if let Some(value) = fields.get_mut(name) {
+                        match to.convert(value) {
+                            Some(converted) => *value = converted,
+                            None => debug!("🔗 Cannot convert field '{}' value {} to {:?}", name, value, to),
+                        }
+                    }
+                }
+            }
+            ProcessorStep::Route { destination } => {
+                fields.insert(ROUTE_FIELD.to_string(), Value::String(destination.clone()));
+            }
+            // Handled above with access to the whole event
+            ProcessorStep::If { .. } | ProcessorStep::Extract { .. } => {}
+            // Nested chains are flattened when chains are resolved
+            ProcessorStep::Chain { .. } => {}
+        }
+    }
+}
+
+/// Chains resolved for every parser and source that references them
+#[derive(Debug, Clone, Default)]
+pub struct ProcessorChains {
+    by_parser: HashMap<String, Vec<ProcessorStep>>,
+    by_source: HashMap<String, Vec<ProcessorStep>>,
+}
+
+impl ProcessorChains {
+    pub fn new(config: &ParsersConfig) -> Result<Self, ParserError> {
+        let mut chains = Self::default();
+
+        for parser in &config.parsers {
+            if !parser.processors.is_empty() {
+                let steps = resolve_chains(config, &parser.processors)?;
+                debug!("🔗 Parser '{}' uses processor chains {:?} ({} steps)", parser.name, parser.processors, steps.len());
+                chains.by_parser.insert(parser.name.clone(), steps);
+            }
+        }
+        for (source, names) in &config.source_chains {
+            let steps = resolve_chains(config, names)?;
+            debug!("🔗 Source '{}' uses processor chains {:?} ({} steps)", source, names, steps.len());
+            chains.by_source.insert(source.clone(), steps);
+        }
+
+        Ok(chains)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_parser.is_empty() && self.by_source.is_empty()
+    }
+
+    /// Run the parser's chains, then the source's chains
+    pub fn apply(&self, event: &mut ParsedEvent) {
+        let parser_steps = self.by_parser.get(&event.parser_name).map(Vec::as_slice).unwrap_or_default();
+        let source_steps = self.by_source.get(&event.source).map(Vec::as_slice).unwrap_or_default();
+        for step in parser_steps.iter().chain(source_steps) {
+            step.apply(event);
+        }
+    }
+}
+
+/// Flatten the named chains (including nested `chain` steps) into a single step list
+pub fn resolve_chains(config: &ParsersConfig, names: &[String]) -> Result<Vec<ProcessorStep>, ParserError> {
+    let mut steps = Vec::new();
+    let mut stack = Vec::new();
+    for name in names {
+        expand_chain(config, name, &mut stack, &mut steps)?;
+    }
+    Ok(steps)
+}
+
+fn expand_chain(
+    config: &ParsersConfig,
+    name: &str,
+    stack: &mut Vec<String>,
+    steps: &mut Vec<ProcessorStep>,
+) -> Result<(), ParserError> {
+    if stack.iter().any(|n| n == name) || stack.len() >= MAX_CHAIN_DEPTH {
+        let root = stack.first().cloned().unwrap_or_else(|| name.to_string());
+        stack.push(name.to_string());
+        return Err(ParserError::InvalidProcessorChain {
+            chain: root,
+            reason: format!("reference cycle {}", stack.join(" -> ")),
+        });
+    }
+    let chain = config.chains.get(name).ok_or_else(|| ParserError::InvalidProcessorChain {
+        chain: name.to_string(),
+        reason: "no chain with this name is defined".to_string(),
+    })?;
+
+    stack.push(name.to_string());
+    expand_steps(config, chain, stack, steps)?;
+    stack.pop();
+    Ok(())
+}
+
+/// Copy steps into `steps`, replacing `chain` references (also inside `if` branches) with the chains' steps
+fn expand_steps(
+    config: &ParsersConfig,
+    source: &[ProcessorStep],
+    stack: &mut Vec<String>,
+    steps: &mut Vec<ProcessorStep>,
+) -> Result<(), ParserError> {
+    for step in source {
+        match step {
+            ProcessorStep::Chain { name } => expand_chain(config, name, stack, steps)?,
+            ProcessorStep::If { when, steps: then, otherwise } => {
+                let (mut then_steps, mut otherwise_steps) = (Vec::new(), Vec::new());
+                expand_steps(config, then, stack, &mut then_steps)?;
+                expand_steps(config, otherwise, stack, &mut otherwise_steps)?;
+                steps.push(ProcessorStep::If { when: when.clone(), steps: then_steps, otherwise: otherwise_steps });
+            }
+            other => steps.push(other.clone()),
+        }
+    }
+    Ok(())
+}
+
+/// Problems with individual steps that deserialization alone does not catch
+fn validate_steps(chain: &str, steps: &[ProcessorStep], errors: &mut Vec<String>) {
+    for step in steps {
+        match step {
+            ProcessorStep::If { when, steps, otherwise } => {
+                if when.field.trim().is_empty() {
+                    errors.push(format!("Processor chain '{}' has an if step without a condition field", chain));
+                }
+                if steps.is_empty() && otherwise.is_empty() {
+                    errors.push(format!("Processor chain '{}' has an if step with no steps in either branch", chain));
+                }
+                validate_steps(chain, steps, errors);
+                validate_steps(chain, otherwise, errors);
+            }
+            ProcessorStep::Extract { field, pattern, .. } if field.trim().is_empty() || pattern.0.capture_names().flatten().next().is_none() => {
+                errors.push(format!("Processor chain '{}' has an extract step that needs a field and a regex with named groups", chain));
+            }
+            ProcessorStep::Route { destination } if destination.trim().is_empty() => {
+                errors.push(format!("Processor chain '{}' has a route step without a destination", chain));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Audit chain definitions and references, returning a message for every problem found
+pub fn validate_chains(config: &ParsersConfig) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for (name, steps) in &config.chains {
+        if name.trim().is_empty() {
+            errors.push("Processor chain names cannot be empty".to_string());
+        }
+        if steps.is_empty() {
+            errors.push(format!("Processor chain '{}' has no steps", name));
+        }
+        validate_steps(name, steps, &mut errors);
+        if let Err(e) = resolve_chains(config, std::slice::from_ref(name)) {
+            errors.push(e.to_string());
+        }
+    }
+    for parser in &config.parsers {
+        for name in &parser.processors {
+            if !config.chains.contains_key(name) {
+                errors.push(format!("Parser '{}' references unknown processor chain '{}'", parser.name, name));
+            }
+        }
+    }
+    for (source, names) in &config.source_chains {
+        for name in names {
+            if !config.chains.contains_key(name) {
+                errors.push(format!("Source '{}' references unknown processor chain '{}'", source, name));
+            }
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ParserDefinition;
+
+    fn config() -> ParsersConfig {
+        let chains: HashMap<String, Vec<ProcessorStep>> = toml::from_str(r#"
+            [[network-normalize]]
+            type = "rename"
+            from = "src"
+            to = "source.ip"
+
+            [[network-normalize]]
+            type = "lowercase"
+            fields = ["protocol"]
+
+            [[pii-redact]]
+            type = "redact"
+            fields = ["user.email"]
+
+            [[pii-redact]]
+            type = "hash"
+            fields = ["user.name"]
+
+            [[firewall]]
+            type = "chain"
+            name = "network-normalize"
+
+            [[firewall]]
+            type = "set"
+            field = "event.category"
+            value = "network"
+        "#).unwrap();
+
+        ParsersConfig {
+            parsers: vec![ParserDefinition {
+                name: "fw".to_string(),
+                source_type: "syslog".to_string(),
+                parser_type: crate::config::ParserType::Regex,
+                regex_pattern: ".*".to_string(),
+                field_mappings: HashMap::new(),
+                processors: vec!["firewall".to_string()],
+                json: Default::default(),
+                schema: Default::default(),
+            }],
+            chains,
+            source_chains: HashMap::from([("syslog".to_string(), vec!["pii-redact".to_string()])]),
+            sample_capture: Default::default(),
+            timestamps: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_parser_and_source_chains_apply() {
+        let chains = ProcessorChains::new(&config()).unwrap();
+        let mut event = ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "syslog".to_string(),
+            level: None,
+            message: "allowed".to_string(),
+            fields: HashMap::from([
+                ("src".to_string(), serde_json::json!("10.0.0.1")),
+                ("protocol".to_string(), serde_json::json!("TCP")),
+                ("user.email".to_string(), serde_json::json!("alice@example.com")),
+                ("user.name".to_string(), serde_json::json!("alice")),
+            ]),
+            raw_data: "".into(),
+            parser_name: "fw".to_string(),
+        };
+
+        chains.apply(&mut event);
+        assert_eq!(event.fields["source.ip"], "10.0.0.1");
+        assert!(!event.fields.contains_key("src"));
+        assert_eq!(event.fields["protocol"], "tcp");
+        assert_eq!(event.fields["event.category"], "network");
+        assert_eq!(event.fields["user.email"], DEFAULT_REDACTION);
+        assert_eq!(event.fields["user.name"].as_str().unwrap().len(), 64);
+    }
+
+    #[test]
+    fn test_unknown_and_cyclic_references_are_rejected() {
+        let mut config = config();
+        config.parsers[0].processors.push("missing".to_string());
+        config.chains.insert("loop-a".to_string(), vec![ProcessorStep::Chain { name: "loop-b".to_string() }]);
+        config.chains.insert("loop-b".to_string(), vec![ProcessorStep::Chain { name: "loop-a".to_string() }]);
+
+        assert!(ProcessorChains::new(&config).is_err());
+        let errors = validate_chains(&config);
+        assert!(errors.iter().any(|e| e.contains("unknown processor chain 'missing'")));
+        assert!(errors.iter().any(|e| e.contains("reference cycle")));
+    }
+
+    #[test]
+    fn test_conditional_extract_convert_mask_and_route() {
+        let chains: HashMap<String, Vec<ProcessorStep>> = toml::from_str(r#"
+            [[auth]]
+            type = "extract"
+            field = "message"
+            pattern = 'for (?P<user>\S+) from (?P<ip>[\d.]+) port (?P<port>\d+)'
+            fields = { user = "user.name", ip = "source.ip" }
+
+            [[auth]]
+            type = "convert"
+            fields = ["port"]
+            to = "integer"
+
+            [[auth]]
+            type = "if"
+            when = { field = "message", matches = "^Failed" }
+            steps = [
+                { type = "set", field = "event.outcome", value = "failure" },
+                { type = "route", destination = "soc" },
+            ]
+            otherwise = [{ type = "set", field = "event.outcome", value = "success" }]
+
+            [[auth]]
+            type = "mask"
+            fields = ["card"]
+            keep_end = 4
+        "#).unwrap();
+        let mut config = config();
+        config.chains = chains;
+        config.parsers[0].processors = vec!["auth".to_string()];
+        config.source_chains.clear();
+        assert!(validate_chains(&config).is_empty());
+
+        let chains = ProcessorChains::new(&config).unwrap();
+        let event = |message: &str| ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "syslog".to_string(),
+            level: None,
+            message: message.to_string(),
+            fields: HashMap::from([("card".to_string(), serde_json::json!("4111111111111111"))]),
+            raw_data: "".into(),
+            parser_name: "fw".to_string(),
+        };
+
+        let mut failed = event("Failed password for root from 203.0.113.9 port 2222");
+        chains.apply(&mut failed);
+        assert_eq!(failed.fields["user.name"], "root");
+        assert_eq!(failed.fields["source.ip"], "203.0.113.9");
+        assert_eq!(failed.fields["port"], 2222);
+        assert_eq!(failed.fields["event.outcome"], "failure");
+        assert_eq!(failed.fields[ROUTE_FIELD], "soc");
+        assert_eq!(failed.fields["card"], "************1111");
+
+        let mut accepted = event("Accepted password for alice from 10.0.0.5 port 22");
+        chains.apply(&mut accepted);
+        assert_eq!(accepted.fields["event.outcome"], "success");
+        assert!(!accepted.fields.contains_key(ROUTE_FIELD));
+
+        // Bad regexes are rejected when the configuration is loaded
+        let invalid = "[[bad]]\ntype = \"extract\"\nfield = \"message\"\npattern = \"(unclosed\"\n";
+        assert!(toml::from_str::<HashMap<String, Vec<ProcessorStep>>>(invalid).is_err());
+    }
+}
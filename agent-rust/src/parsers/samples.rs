@@ -0,0 +1,341 @@
+// Rolling capture of raw events no configured parser matched
+// Gives parser authors real per-source samples (bounded by count and size) persisted to disk
+// and retrievable through management, instead of grepping hosts for examples
+
+use crate::collectors::RawLogEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Reason recorded when no parser claimed the event at all
+pub const REASON_NO_MATCHING_PARSER: &str = "no_matching_parser";
+/// Reason recorded when the event only made it through the passthrough fallback
+pub const REASON_PASSTHROUGH: &str = "passthrough";
+
+/// Unmatched sample capture configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SampleCaptureConfig {
+    /// Capture unmatched raw events
+    pub enabled: bool,
+    /// Directory holding one NDJSON sample file per source
+    pub directory: String,
+    /// Samples kept per source; the oldest are evicted first
+    pub max_samples_per_source: usize,
+    /// Raw bytes kept per source across all its samples
+    pub max_bytes_per_source: usize,
+    /// Raw events longer than this are truncated before capture
+    pub max_event_bytes: usize,
+    /// How often captured samples are written to disk
+    pub flush_interval_seconds: u64,
+}
+
+impl Default for SampleCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: "./parser-samples".to_string(),
+            max_samples_per_source: 100,
+            max_bytes_per_source: 256 * 1024,
+            max_event_bytes: 16 * 1024,
+            flush_interval_seconds: 30,
+        }
+    }
+}
+
+impl SampleCaptureConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.directory.trim().is_empty() {
+            errors.push("Sample capture directory cannot be empty".to_string());
+        }
+        if self.max_samples_per_source == 0 {
+            errors.push("max_samples_per_source must be greater than 0".to_string());
+        }
+        if self.max_event_bytes == 0 || self.max_event_bytes > self.max_bytes_per_source {
+            errors.push(format!("max_event_bytes must be between 1 and max_bytes_per_source ({})", self.max_bytes_per_source));
+        }
+        if self.flush_interval_seconds == 0 {
+            errors.push("flush_interval_seconds must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// A captured unmatched raw event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedSample {
+    pub captured_at: chrono::DateTime<chrono::Utc>,
+    pub source: String,
+    /// Why no parser produced structured fields
+    pub reason: String,
+    pub raw_data: String,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    /// Raw data was cut to `max_event_bytes`
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// Per-source capture summary
+#[derive(Debug, Clone, Serialize)]
+pub struct SampleSourceSummary {
+    pub source: String,
+    pub samples: usize,
+    pub bytes: usize,
+    /// Unmatched events seen since startup, including those evicted
+    pub total_captured: u64,
+    pub last_captured: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Default)]
+struct SourceSamples {
+    samples: VecDeque<CapturedSample>,
+    bytes: usize,
+    total_captured: u64,
+    dirty: bool,
+}
+
+/// Bounded store of unmatched samples shared by the parsing engine, agent and management API
+pub struct UnmatchedSampleStore {
+    config: SampleCaptureConfig,
+    sources: parking_lot::Mutex<HashMap<String, SourceSamples>>,
+}
+
+impl UnmatchedSampleStore {
+    /// Create the store, reloading samples persisted by a previous run
+    pub fn open(config: SampleCaptureConfig) -> Self {
+        let store = Self {
+            config,
+            sources: parking_lot::Mutex::new(HashMap::new()),
+        };
+
+        match store.load() {
+            Ok(0) => {}
+            Ok(loaded) => info!("🧪 Loaded {} captured parser samples from {}", loaded, store.config.directory),
+            Err(e) => warn!("⚠️ Failed to load captured parser samples from {}: {}", store.config.directory, e),
+        }
+        store
+    }
+
+    pub fn config(&self) -> &SampleCaptureConfig {
+        &self.config
+    }
+
+    fn load(&self) -> std::io::Result<usize> {
+        let entries = match std::fs::read_dir(&self.config.directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+
+        let mut loaded = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("ndjson") {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path)?;
+            for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<CapturedSample>(line) {
+                    Ok(sample) => {
+                        self.insert(sample, false);
+                        loaded += 1;
+                    }
+                    Err(e) => debug!("Skipping unreadable sample in {}: {}", path.display(), e),
+                }
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Capture an event no parser matched
+    pub fn record(&self, raw_event: &RawLogEvent, reason: &str) {
+        let (raw_data, truncated) = truncate_utf8(&raw_event.raw_data, self.config.max_event_bytes);
+        self.insert(CapturedSample {
+            captured_at: chrono::Utc::now(),
+            source: raw_event.source.clone(),
+            reason: reason.to_string(),
+            raw_data: raw_data.to_string(),
+            metadata: raw_event.metadata.clone(),
+            truncated,
+        }, true);
+    }
+
+    fn insert(&self, sample: CapturedSample, count: bool) {
+        let mut sources = self.sources.lock();
+        let entry = sources.entry(sample.source.clone()).or_default();
+
+        entry.bytes += sample.raw_data.len();
+        entry.samples.push_back(sample);
+        while entry.samples.len() > self.config.max_samples_per_source
+            || (entry.bytes > self.config.max_bytes_per_source && entry.samples.len() > 1)
+        {
+            if let Some(evicted) = entry.samples.pop_front() {
+                entry.bytes -= evicted.raw_data.len();
+            }
+        }
+        if count {
+            entry.total_captured += 1;
+            entry.dirty = true;
+        }
+    }
+
+    /// Most recent samples, newest first, optionally for a single source
+    pub fn samples(&self, source: Option<&str>, limit: usize) -> Vec<CapturedSample> {
+        let sources = self.sources.lock();
+        let mut samples: Vec<CapturedSample> = sources
+            .iter()
+            .filter(|(name, _)| source.is_none_or(|s| s == name.as_str()))
+            .flat_map(|(_, entry)| entry.samples.iter().cloned())
+            .collect();
+        samples.sort_by_key(|s| std::cmp::Reverse(s.captured_at));
+        samples.truncate(limit);
+        samples
+    }
+
+    pub fn summaries(&self) -> Vec<SampleSourceSummary> {
+        let sources = self.sources.lock();
+        let mut summaries: Vec<SampleSourceSummary> = sources
+            .iter()
+            .map(|(name, entry)| SampleSourceSummary {
+                source: name.clone(),
+                samples: entry.samples.len(),
+                bytes: entry.bytes,
+                total_captured: entry.total_captured,
+                last_captured: entry.samples.back().map(|s| s.captured_at),
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.source.cmp(&b.source));
+        summaries
+    }
+
+    /// Write sources with new samples to disk, returning how many files were written
+    pub fn flush(&self) -> std::io::Result<usize> {
+        let pending: Vec<(String, Vec<CapturedSample>)> = {
+            let mut sources = self.sources.lock();
+            sources
+                .iter_mut()
+                .filter(|(_, entry)| entry.dirty)
+                .map(|(name, entry)| {
+                    entry.dirty = false;
+                    (name.clone(), entry.samples.iter().cloned().collect())
+                })
+                .collect()
+        };
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        std::fs::create_dir_all(&self.config.directory)?;
+        for (source, samples) in &pending {
+            let path = self.sample_path(source);
+            if let Err(e) = write_samples(&path, samples) {
+                // Retry on the next flush
+                if let Some(entry) = self.sources.lock().get_mut(source) {
+                    entry.dirty = true;
+                }
+                return Err(e);
+            }
+        }
+        debug!("🧪 Flushed parser samples for {} sources", pending.len());
+        Ok(pending.len())
+    }
+
+    fn sample_path(&self, source: &str) -> PathBuf {
+        let name: String = source
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        Path::new(&self.config.directory).join(format!("{}.ndjson", name))
+    }
+}
+
+fn write_samples(path: &Path, samples: &[CapturedSample]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("ndjson.tmp");
+    {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&tmp_path)?);
+        for sample in samples {
+            serde_json::to_writer(&mut file, sample)?;
+            file.write_all(b"\n")?;
+        }
+        file.flush()?;
+    }
+    std::fs::rename(&tmp_path, path)
+}
+
+fn truncate_utf8(text: &str, max_bytes: usize) -> (&str, bool) {
+    if text.len() <= max_bytes {
+        return (text, false);
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&text[..end], true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(dir: &Path) -> SampleCaptureConfig {
+        SampleCaptureConfig {
+            enabled: true,
+            directory: dir.display().to_string(),
+            max_samples_per_source: 3,
+            max_bytes_per_source: 64,
+            max_event_bytes: 16,
+            flush_interval_seconds: 1,
+        }
+    }
+
+    fn raw(source: &str, raw_data: &str) -> RawLogEvent {
+        RawLogEvent {
+            timestamp: chrono::Utc::now(),
+            source: source.to_string(),
+            raw_data: raw_data.into(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_capture_is_bounded_per_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UnmatchedSampleStore::open(config(dir.path()));
+        for i in 0..5 {
+            store.record(&raw("syslog", &format!("line {}", i)), REASON_PASSTHROUGH);
+        }
+        store.record(&raw("file_monitor", "a line well over sixteen bytes"), REASON_NO_MATCHING_PARSER);
+
+        let syslog = store.samples(Some("syslog"), 10);
+        assert_eq!(syslog.len(), 3);
+        assert!(syslog.iter().all(|s| s.raw_data != "line 0" && s.raw_data != "line 1"));
+
+        let file_monitor = store.samples(Some("file_monitor"), 10);
+        assert_eq!(file_monitor[0].raw_data.len(), 16);
+        assert!(file_monitor[0].truncated);
+
+        let summaries = store.summaries();
+        assert_eq!(summaries[1].source, "syslog");
+        assert_eq!(summaries[1].total_captured, 5);
+    }
+
+    #[test]
+    fn test_samples_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UnmatchedSampleStore::open(config(dir.path()));
+        store.record(&raw("windows/event", "<Event/>"), REASON_NO_MATCHING_PARSER);
+        assert_eq!(store.flush().unwrap(), 1);
+        assert_eq!(store.flush().unwrap(), 0);
+        assert!(dir.path().join("windows_event.ndjson").exists());
+
+        let reopened = UnmatchedSampleStore::open(config(dir.path()));
+        let samples = reopened.samples(None, 10);
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].source, "windows/event");
+        assert_eq!(samples[0].reason, REASON_NO_MATCHING_PARSER);
+    }
+}
@@ -0,0 +1,330 @@
+// Declared field types for parser output
+// A parser definition can declare the type each of its fields should have. After the processor chains run,
+// values are coerced to the declared type where the meaning is unchanged (the string "443" becomes 443, an epoch
+// becomes an RFC 3339 timestamp) and anything that can't be is listed in `schema.violations`, so downstream
+// analytics see one type per field and a parser producing the wrong thing shows up instead of breaking a query.
+
+use super::processors::FieldType;
+use super::ParsedEvent;
+use crate::config::ParsersConfig;
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Field listing the type violations found in an event, as "field: expected type"
+pub const VIOLATIONS_FIELD: &str = "schema.violations";
+
+/// Timestamps without an offset are read as UTC
+const NAIVE_TIMESTAMP_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y/%m/%d %H:%M:%S%.f"];
+
+/// Epoch values at or above this are milliseconds (year 2001 in milliseconds, year 33658 in seconds)
+const EPOCH_MILLIS_THRESHOLD: f64 = 1e12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    /// IPv4 or IPv6 address, normalized to its canonical text
+    Ip,
+    /// RFC 3339, `YYYY-MM-DD HH:MM:SS` (UTC) or epoch seconds/milliseconds, normalized to RFC 3339 UTC
+    Timestamp,
+    /// One of the field's `values`, compared case-insensitively and normalized to the declared spelling
+    Enum,
+}
+
+impl SchemaType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SchemaType::String => "string",
+            SchemaType::Integer => "integer",
+            SchemaType::Float => "float",
+            SchemaType::Boolean => "boolean",
+            SchemaType::Ip => "ip",
+            SchemaType::Timestamp => "timestamp",
+            SchemaType::Enum => "enum",
+        }
+    }
+}
+
+/// Expected type of one field: just the type name, or a table with enum values and whether the field is required
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FieldSpec {
+    Type(SchemaType),
+    Detailed {
+        #[serde(rename = "type")]
+        field_type: SchemaType,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        values: Vec<String>,
+        #[serde(default)]
+        required: bool,
+    },
+}
+
+impl FieldSpec {
+    pub fn field_type(&self) -> SchemaType {
+        match self {
+            FieldSpec::Type(field_type) | FieldSpec::Detailed { field_type, .. } => *field_type,
+        }
+    }
+
+    fn values(&self) -> &[String] {
+        match self {
+            FieldSpec::Type(_) => &[],
+            FieldSpec::Detailed { values, .. } => values,
+        }
+    }
+
+    fn required(&self) -> bool {
+        matches!(self, FieldSpec::Detailed { required: true, .. })
+    }
+
+    /// The value as the declared type, or None when it can't be represented as one
+    fn coerce(&self, value: &Value) -> Option<Value> {
+        if matches!(value, Value::Array(_) | Value::Object(_)) {
+            return None;
+        }
+        match self.field_type() {
+            SchemaType::String => match value {
+                Value::String(_) => Some(value.clone()),
+                other => Some(Value::String(other.to_string())),
+            },
+            SchemaType::Integer => FieldType::Integer.convert(value),
+            SchemaType::Float => FieldType::Float.convert(value),
+            SchemaType::Boolean => FieldType::Boolean.convert(value),
+            SchemaType::Ip => value.as_str()?.trim().parse::<IpAddr>().ok().map(|ip| Value::String(ip.to_string())),
+            SchemaType::Timestamp => parse_timestamp(value).map(|ts| Value::String(ts.to_rfc3339_opts(SecondsFormat::Millis, true))),
+            SchemaType::Enum => {
+                let text = match value {
+                    Value::String(s) => s.trim().to_string(),
+                    other => other.to_string(),
+                };
+                self.values().iter().find(|v| v.eq_ignore_ascii_case(&text)).map(|v| Value::String(v.clone()))
+            }
+        }
+    }
+}
+
+fn parse_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    let epoch = match value {
+        Value::Number(n) => n.as_f64()?,
+        Value::String(s) => {
+            let s = s.trim();
+            if let Ok(ts) = DateTime::parse_from_rfc3339(s) {
+                return Some(ts.with_timezone(&Utc));
+            }
+            if let Some(ts) = NAIVE_TIMESTAMP_FORMATS.iter().find_map(|format| NaiveDateTime::parse_from_str(s, format).ok()) {
+                return Some(ts.and_utc());
+            }
+            s.parse::<f64>().ok()?
+        }
+        _ => return None,
+    };
+    if !epoch.is_finite() {
+        return None;
+    }
+    let millis = if epoch.abs() >= EPOCH_MILLIS_THRESHOLD { epoch } else { epoch * 1000.0 };
+    DateTime::from_timestamp_millis(millis.round() as i64)
+}
+
+/// What happens to a value that doesn't fit its declared type; the violation is recorded either way
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationAction {
+    /// Leave the original value in place
+    #[default]
+    Keep,
+    /// Remove the field so it can't pollute typed indexes
+    Remove,
+}
+
+/// Declared field types for one parser
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldSchema {
+    /// Field name to expected type, e.g. `"source.port" = "integer"`
+    pub fields: BTreeMap<String, FieldSpec>,
+    pub on_violation: ViolationAction,
+}
+
+impl FieldSchema {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (name, spec) in &self.fields {
+            match (spec.field_type(), spec.values().is_empty()) {
+                (SchemaType::Enum, true) => errors.push(format!("field '{}' is an enum without values", name)),
+                (SchemaType::Enum, false) | (_, true) => {}
+                (_, false) => errors.push(format!("field '{}' lists values but isn't an enum", name)),
+            }
+        }
+        errors
+    }
+
+    /// Coerce the event's fields and record violations; returns the names of the fields that violated the schema
+    pub fn apply(&self, event: &mut ParsedEvent) -> Vec<String> {
+        let mut violations = Vec::new();
+        for (name, spec) in &self.fields {
+            let Some(value) = event.fields.get(name) else {
+                if spec.required() {
+                    violations.push((name, None));
+                }
+                continue;
+            };
+            match spec.coerce(value) {
+                Some(coerced) => {
+                    event.fields.insert(name.clone(), coerced);
+                }
+                None => {
+                    violations.push((name, Some(spec.field_type())));
+                    if self.on_violation == ViolationAction::Remove {
+                        event.fields.remove(name);
+                    }
+                }
+            }
+        }
+        if violations.is_empty() {
+            return Vec::new();
+        }
+
+        let recorded = event.fields.entry(VIOLATIONS_FIELD.to_string()).or_insert_with(|| Value::Array(Vec::new()));
+        if !recorded.is_array() {
+            *recorded = Value::Array(Vec::new());
+        }
+        if let Value::Array(recorded) = recorded {
+            recorded.extend(violations.iter().map(|(name, expected)| match expected {
+                Some(expected) => Value::String(format!("{}: expected {}", name, expected.as_str())),
+                None => Value::String(format!("{}: missing", name)),
+            }));
+        }
+        violations.into_iter().map(|(name, _)| name.clone()).collect()
+    }
+}
+
+/// Schemas of every configured parser, with a running count of the violations each one produced
+#[derive(Debug, Default)]
+pub struct FieldSchemas {
+    by_parser: HashMap<String, (FieldSchema, AtomicU64)>,
+}
+
+impl FieldSchemas {
+    pub fn new(config: &ParsersConfig) -> Self {
+        let by_parser = config.parsers.iter()
+            .filter(|parser| !parser.schema.is_empty())
+            .map(|parser| (parser.name.clone(), (parser.schema.clone(), AtomicU64::new(0))))
+            .collect();
+        Self { by_parser }
+    }
+
+    pub fn apply(&self, event: &mut ParsedEvent) -> Vec<String> {
+        let Some((schema, count)) = self.by_parser.get(&event.parser_name) else {
+            return Vec::new();
+        };
+        let violations = schema.apply(event);
+        count.fetch_add(violations.len() as u64, Ordering::Relaxed);
+        violations
+    }
+
+    /// Violations the parser's events have had since the schemas were loaded
+    pub fn violations(&self, parser: &str) -> u64 {
+        self.by_parser.get(parser).map(|(_, count)| count.load(Ordering::Relaxed)).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(fields: Value) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: Utc::now(),
+            source: "syslog".to_string(),
+            level: None,
+            message: String::new(),
+            fields: serde_json::from_value(fields).unwrap(),
+            raw_data: "".into(),
+            parser_name: "sshd".to_string(),
+        }
+    }
+
+    fn schema(toml_text: &str) -> FieldSchema {
+        toml::from_str(toml_text).unwrap()
+    }
+
+    #[test]
+    fn test_values_coerced_to_declared_types() {
+        let schema = schema(r#"
+            [fields]
+            "source.ip" = "ip"
+            "source.port" = "integer"
+            "event.duration" = "float"
+            "tls.established" = "boolean"
+            "event.created" = "timestamp"
+            "event.ingested" = "timestamp"
+            "event.outcome" = { type = "enum", values = ["success", "failure"] }
+            "process.pid" = "string"
+        "#);
+        let mut event = event(json!({
+            "source.ip": " 2001:DB8::1 ",
+            "source.port": "22",
+            "event.duration": 12,
+            "tls.established": "yes",
+            "event.created": "2024-03-09 23:00:00",
+            "event.ingested": 1710025200250u64,
+            "event.outcome": "FAILURE",
+            "process.pid": 4242,
+        }));
+        assert!(schema.apply(&mut event).is_empty());
+        assert_eq!(event.fields["source.ip"], "2001:db8::1");
+        assert_eq!(event.fields["source.port"], 22);
+        assert_eq!(event.fields["event.duration"], 12.0);
+        assert_eq!(event.fields["tls.established"], true);
+        assert_eq!(event.fields["event.created"], "2024-03-09T23:00:00.000Z");
+        assert_eq!(event.fields["event.ingested"], "2024-03-09T23:00:00.250Z");
+        assert_eq!(event.fields["event.outcome"], "failure");
+        assert_eq!(event.fields["process.pid"], "4242");
+        assert!(!event.fields.contains_key(VIOLATIONS_FIELD));
+    }
+
+    #[test]
+    fn test_violations_flagged_kept_or_removed() {
+        let mut schema = schema(r#"
+            [fields]
+            "source.ip" = "ip"
+            "source.port" = "integer"
+            "user.name" = { type = "string", required = true }
+            "event.outcome" = { type = "enum", values = ["success", "failure"] }
+        "#);
+        let fields = json!({ "source.ip": "not-an-ip", "source.port": "ssh", "event.outcome": "unknown" });
+
+        let mut kept = event(fields.clone());
+        let violations = schema.apply(&mut kept);
+        assert_eq!(violations, vec!["event.outcome", "source.ip", "source.port", "user.name"]);
+        assert_eq!(kept.fields["source.port"], "ssh");
+        assert_eq!(kept.fields[VIOLATIONS_FIELD], json!([
+            "event.outcome: expected enum", "source.ip: expected ip", "source.port: expected integer", "user.name: missing"
+        ]));
+
+        schema.on_violation = ViolationAction::Remove;
+        let mut removed = event(fields);
+        assert_eq!(schema.apply(&mut removed).len(), 4);
+        assert!(!removed.fields.contains_key("source.ip") && !removed.fields.contains_key("source.port"));
+
+        let invalid = self::schema(r#"
+            [fields]
+            "event.outcome" = "enum"
+            "source.port" = { type = "integer", values = ["22"] }
+        "#);
+        assert_eq!(invalid.validate().len(), 2);
+    }
+}
@@ -0,0 +1,348 @@
+// Built-in syslog parser for RFC 5424 and RFC 3164 messages
+// RFC 5424 messages are parsed completely, including MSGID and structured data; anything else is read as
+// BSD syslog (RFC 3164) as far as it conforms. Fields follow the ECS log.syslog.* names.
+
+use crate::collectors::RawLogEvent;
+use crate::errors::ParserError;
+use crate::parsers::{ParsedEvent, Parser};
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, NaiveDateTime, TimeZone, Utc};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Source type emitted by the syslog collector
+pub const SYSLOG_SOURCE: &str = "syslog";
+
+const SEVERITIES: [&str; 8] = ["emergency", "alert", "critical", "error", "warning", "notice", "info", "debug"];
+const FACILITIES: [&str; 24] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron", "authpriv", "ftp",
+    "ntp", "security", "console", "solaris-cron", "local0", "local1", "local2", "local3", "local4", "local5",
+    "local6", "local7",
+];
+
+/// A decoded syslog message
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyslogMessage {
+    pub facility: Option<u8>,
+    pub severity: Option<u8>,
+    /// 1 for RFC 5424, None for BSD syslog
+    pub version: Option<u32>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub hostname: Option<String>,
+    pub app_name: Option<String>,
+    pub procid: Option<String>,
+    pub msgid: Option<String>,
+    /// SD-ID to its parameters, in message order
+    pub structured_data: Vec<(String, Vec<(String, String)>)>,
+    pub message: String,
+}
+
+impl SyslogMessage {
+    /// Parse an RFC 5424 message, falling back to RFC 3164
+    pub fn parse(raw: &str) -> Self {
+        let raw = raw.trim_end_matches(['\r', '\n', '\0']);
+        let (pri, rest) = parse_pri(raw);
+        if pri.is_some() {
+            if let Some(message) = parse_rfc5424(pri, rest) {
+                return message;
+            }
+        }
+        parse_rfc3164(pri, rest)
+    }
+
+    pub fn to_fields(&self) -> HashMap<String, Value> {
+        let mut fields = HashMap::new();
+        if let Some(facility) = self.facility {
+            fields.insert("log.syslog.facility.code".to_string(), json!(facility));
+            if let Some(name) = FACILITIES.get(facility as usize) {
+                fields.insert("log.syslog.facility.name".to_string(), json!(name));
+            }
+        }
+        if let Some(severity) = self.severity {
+            fields.insert("log.syslog.severity.code".to_string(), json!(severity));
+            fields.insert("log.syslog.severity.name".to_string(), json!(SEVERITIES[severity as usize]));
+        }
+        if let Some(version) = self.version {
+            fields.insert("log.syslog.version".to_string(), json!(version.to_string()));
+        }
+        for (name, value) in [
+            ("log.syslog.hostname", &self.hostname),
+            ("log.syslog.appname", &self.app_name),
+            ("log.syslog.procid", &self.procid),
+            ("log.syslog.msgid", &self.msgid),
+        ] {
+            if let Some(value) = value {
+                fields.insert(name.to_string(), json!(value));
+            }
+        }
+        if !self.structured_data.is_empty() {
+            let elements: Map<String, Value> = self.structured_data.iter()
+                .map(|(id, params)| {
+                    let params: Map<String, Value> = params.iter().map(|(k, v)| (k.clone(), json!(v))).collect();
+                    (id.clone(), Value::Object(params))
+                })
+                .collect();
+            fields.insert("log.syslog.structured_data".to_string(), Value::Object(elements));
+        }
+        fields
+    }
+}
+
+/// `<PRI>` prefix: facility * 8 + severity, at most 191
+fn parse_pri(raw: &str) -> (Option<u8>, &str) {
+    let Some(rest) = raw.strip_prefix('<') else { return (None, raw) };
+    let Some(end) = rest.find('>').filter(|end| (1..=3).contains(end)) else { return (None, raw) };
+    match rest[..end].parse::<u8>() {
+        Ok(pri) if pri <= 191 && rest[..end].bytes().all(|b| b.is_ascii_digit()) => (Some(pri), &rest[end + 1..]),
+        _ => (None, raw),
+    }
+}
+
+fn nil(value: &str) -> Option<String> {
+    (value != "-").then(|| value.to_string())
+}
+
+fn parse_rfc5424(pri: Option<u8>, rest: &str) -> Option<SyslogMessage> {
+    let mut header = rest.splitn(7, ' ');
+    let version: u32 = header.next().filter(|v| !v.is_empty() && v.len() <= 2)?.parse().ok()?;
+    let timestamp = header.next()?;
+    let timestamp = match timestamp {
+        "-" => None,
+        t => Some(DateTime::parse_from_rfc3339(t).ok()?.with_timezone(&Utc)),
+    };
+    let hostname = nil(header.next()?);
+    let app_name = nil(header.next()?);
+    let procid = nil(header.next()?);
+    let msgid = nil(header.next()?);
+    let (structured_data, message) = parse_structured_data(header.next().unwrap_or("-"))?;
+
+    Some(SyslogMessage {
+        facility: pri.map(|p| p / 8),
+        severity: pri.map(|p| p % 8),
+        version: Some(version),
+        timestamp,
+        hostname,
+        app_name,
+        procid,
+        msgid,
+        structured_data,
+        message: message.trim_start_matches('\u{feff}').to_string(),
+    })
+}
+
+type StructuredData = Vec<(String, Vec<(String, String)>)>;
+
+/// STRUCTURED-DATA followed by the optional MSG
+fn parse_structured_data(text: &str) -> Option<(StructuredData, &str)> {
+    if let Some(message) = text.strip_prefix('-') {
+        return match message {
+            "" => Some((Vec::new(), "")),
+            m => m.strip_prefix(' ').map(|m| (Vec::new(), m)),
+        };
+    }
+
+    let mut elements = Vec::new();
+    let mut rest = text;
+    while let Some(element) = rest.strip_prefix('[') {
+        let id_end = element.find([' ', ']'])?;
+        let id = &element[..id_end];
+        if id.is_empty() {
+            return None;
+        }
+        let mut params = Vec::new();
+        let mut cursor = &element[id_end..];
+        loop {
+            if let Some(after) = cursor.strip_prefix(']') {
+                cursor = after;
+                break;
+            }
+            let param = cursor.strip_prefix(' ')?;
+            let eq = param.find('=')?;
+            let name = &param[..eq];
+            let quoted = param[eq + 1..].strip_prefix('"')?;
+
+            // PARAM-VALUE escapes '"', '\' and ']' with a backslash
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                match chars.next()? {
+                    (_, '\\') => match chars.next()? {
+                        (_, c @ ('"' | '\\' | ']')) => value.push(c),
+                        (_, c) => {
+                            value.push('\\');
+                            value.push(c);
+                        }
+                    },
+                    (i, '"') => break i,
+                    (_, c) => value.push(c),
+                }
+            };
+            params.push((name.to_string(), value));
+            cursor = &quoted[end + 1..];
+        }
+        elements.push((id.to_string(), params));
+        rest = cursor;
+    }
+    if elements.is_empty() {
+        return None;
+    }
+
+    match rest {
+        "" => Some((elements, "")),
+        m => m.strip_prefix(' ').map(|m| (elements, m)),
+    }
+}
+
+/// BSD syslog: `Mmm dd hh:mm:ss HOSTNAME TAG[PID]: MSG`, every part optional in practice
+fn parse_rfc3164(pri: Option<u8>, rest: &str) -> SyslogMessage {
+    let mut message = SyslogMessage {
+        facility: pri.map(|p| p / 8),
+        severity: pri.map(|p| p % 8),
+        version: None,
+        timestamp: None,
+        hostname: None,
+        app_name: None,
+        procid: None,
+        msgid: None,
+        structured_data: Vec::new(),
+        message: rest.to_string(),
+    };
+
+    let mut body = rest;
+    if let Some(timestamp) = body.get(..15).and_then(parse_bsd_timestamp) {
+        message.timestamp = Some(timestamp);
+        body = body[15..].trim_start();
+
+        // The hostname follows the timestamp unless the next word is already the tag
+        if let Some((word, after)) = body.split_once(' ') {
+            if !word.ends_with(':') && !word.contains('[') {
+                message.hostname = Some(word.to_string());
+                body = after;
+            }
+        }
+    }
+
+    // TAG is up to 32 alphanumeric characters, optionally followed by [PID], then ':'
+    if let Some((tag, after)) = body.split_once(": ") {
+        let (app_name, procid) = match tag.split_once('[') {
+            Some((app, pid)) => (app, pid.strip_suffix(']')),
+            None => (tag, None),
+        };
+        let valid_tag = !app_name.is_empty() && app_name.len() <= 32
+            && app_name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+        if valid_tag && (procid.is_some() || !tag.contains('[')) {
+            message.app_name = Some(app_name.to_string());
+            message.procid = procid.map(str::to_string);
+            body = after;
+        }
+    }
+    message.message = body.to_string();
+    message
+}
+
+/// `Mmm dd hh:mm:ss` in the current year, or the previous one for dates more than a day ahead
+fn parse_bsd_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    let now = Utc::now();
+    let parse = |year: i32| {
+        NaiveDateTime::parse_from_str(&format!("{} {}", year, text.replace("  ", " 0")), "%Y %b %d %H:%M:%S").ok()
+    };
+    let timestamp = Utc.from_utc_datetime(&parse(now.year())?);
+    if timestamp > now + chrono::Duration::days(1) {
+        return parse(now.year() - 1).map(|t| Utc.from_utc_datetime(&t));
+    }
+    Some(timestamp)
+}
+
+pub struct SyslogParser {
+    name: String,
+}
+
+impl SyslogParser {
+    pub fn new() -> Self {
+        Self {
+            name: "syslog".to_string(),
+        }
+    }
+}
+
+impl Default for SyslogParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Parser for SyslogParser {
+    async fn parse(&self, raw_event: &RawLogEvent) -> Result<ParsedEvent, ParserError> {
+        let message = SyslogMessage::parse(&raw_event.raw_data);
+        Ok(ParsedEvent {
+            timestamp: message.timestamp.unwrap_or(raw_event.timestamp),
+            source: raw_event.source.clone(),
+            level: message.severity.map(|severity| SEVERITIES[severity as usize].to_string()),
+            fields: message.to_fields(),
+            message: message.message,
+            raw_data: raw_event.raw_data.clone(),
+            parser_name: self.name.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source_type(&self) -> &str {
+        SYSLOG_SOURCE
+    }
+
+    fn can_parse(&self, raw_event: &RawLogEvent) -> bool {
+        raw_event.source == SYSLOG_SOURCE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc5424_with_structured_data() {
+        let raw = "<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 \
+                   [exampleSDID@32473 iut=\"3\" eventSource=\"Application\" eventID=\"1011\"]\
+                   [examplePriority@32473 class=\"high\" note=\"a \\\"quoted\\\" \\] value\"] \u{feff}An application event log entry";
+        let message = SyslogMessage::parse(raw);
+        assert_eq!((message.facility, message.severity, message.version), (Some(20), Some(5), Some(1)));
+        assert_eq!(message.timestamp.unwrap().to_rfc3339(), "2003-10-11T22:14:15.003+00:00");
+        assert_eq!(message.hostname.as_deref(), Some("mymachine.example.com"));
+        assert_eq!(message.app_name.as_deref(), Some("evntslog"));
+        assert_eq!(message.procid, None);
+        assert_eq!(message.msgid.as_deref(), Some("ID47"));
+        assert_eq!(message.message, "An application event log entry");
+
+        let fields = message.to_fields();
+        assert_eq!(fields["log.syslog.facility.name"], "local4");
+        assert_eq!(fields["log.syslog.severity.name"], "notice");
+        assert_eq!(fields["log.syslog.structured_data"]["exampleSDID@32473"]["eventID"], "1011");
+        assert_eq!(fields["log.syslog.structured_data"]["examplePriority@32473"]["note"], "a \"quoted\" ] value");
+
+        let bare = SyslogMessage::parse("<34>1 - - su - - -");
+        assert_eq!((bare.timestamp, bare.hostname, bare.app_name.as_deref(), bare.message.as_str()), (None, None, Some("su"), ""));
+    }
+
+    #[test]
+    fn test_rfc3164_and_unstructured_fallback() {
+        let message = SyslogMessage::parse("<38>Oct  9 22:33:20 web01 sshd[4123]: Accepted publickey for deploy");
+        assert_eq!((message.facility, message.severity, message.version), (Some(4), Some(6), None));
+        assert_eq!(message.hostname.as_deref(), Some("web01"));
+        assert_eq!(message.app_name.as_deref(), Some("sshd"));
+        assert_eq!(message.procid.as_deref(), Some("4123"));
+        assert_eq!(message.message, "Accepted publickey for deploy");
+        assert_eq!(message.timestamp.unwrap().format("%m-%d %H:%M:%S").to_string(), "10-09 22:33:20");
+
+        // A malformed 5424 header is not mistaken for one
+        let message = SyslogMessage::parse("<13>1 not-a-timestamp host app - - - hello");
+        assert_eq!(message.version, None);
+        assert_eq!(message.message, "1 not-a-timestamp host app - - - hello");
+
+        let message = SyslogMessage::parse("kernel: eth0 link up");
+        assert_eq!((message.facility, message.app_name.as_deref(), message.message.as_str()), (None, Some("kernel"), "eth0 link up"));
+    }
+}
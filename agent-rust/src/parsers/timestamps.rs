@@ -0,0 +1,400 @@
+// Event time extraction and timezone normalization
+// Parsers leave the collector's receive time on events unless the log format has an unambiguous timestamp.
+// This stage runs after the processor chains: rules matched by source or parser name read a field with
+// strptime-style formats (month names in other languages included), place timestamps that have no offset in the
+// rule's timezone, infer the year for syslog-style times that lack one, and keep the receive time as event.created.
+
+use super::ParsedEvent;
+use chrono::format::{Item, ParseErrorKind, StrftimeItems};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+/// ECS field the receive time is recorded in
+pub const RECEIVE_TIME_FIELD: &str = "event.created";
+
+/// Fields read by the built-in rule and by rules that don't name any
+const DEFAULT_FIELDS: &[&str] = &["@timestamp", "timestamp", "time"];
+
+/// Formats tried by the built-in rule after RFC 3339: BSD syslog, Apache/NCSA and common application log layouts
+const DEFAULT_FORMATS: &[&str] = &[
+    "%b %d %H:%M:%S",
+    "%d/%b/%Y:%H:%M:%S %z",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S",
+    "%a %b %d %H:%M:%S %Y",
+];
+
+/// Epoch numbers at or above this are milliseconds, as in the JSON parser
+const EPOCH_MILLIS_THRESHOLD: f64 = 1e11;
+
+/// Month names per language, January first; alternatives are separated by `|` and matched case-insensitively
+const MONTH_NAMES: &[(&str, [&str; 12])] = &[
+    ("de", ["jan|januar|jän|jänner", "feb|februar", "mär|märz|maerz|mrz", "apr|april", "mai", "jun|juni", "jul|juli",
+            "aug|august", "sep|sept|september", "okt|oktober", "nov|november", "dez|dezember"]),
+    ("fr", ["janv|janvier", "févr|fevr|février|fevrier", "mars", "avr|avril", "mai", "juin", "juil|juillet",
+            "août|aout", "sept|septembre", "oct|octobre", "nov|novembre", "déc|dec|décembre|decembre"]),
+    ("es", ["ene|enero", "feb|febrero", "mar|marzo", "abr|abril", "may|mayo", "jun|junio", "jul|julio", "ago|agosto",
+            "sep|sept|septiembre|set|setiembre", "oct|octubre", "nov|noviembre", "dic|diciembre"]),
+    ("it", ["gen|gennaio", "feb|febbraio", "mar|marzo", "apr|aprile", "mag|maggio", "giu|giugno", "lug|luglio",
+            "ago|agosto", "set|settembre", "ott|ottobre", "nov|novembre", "dic|dicembre"]),
+    ("pt", ["jan|janeiro", "fev|fevereiro", "mar|março|marco", "abr|abril", "mai|maio", "jun|junho", "jul|julho",
+            "ago|agosto", "set|setembro", "out|outubro", "nov|novembro", "dez|dezembro"]),
+    ("nl", ["jan|januari", "feb|februari", "mrt|maart", "apr|april", "mei", "jun|juni", "jul|juli", "aug|augustus",
+            "sep|sept|september", "okt|oktober", "nov|november", "dec|december"]),
+];
+
+const ENGLISH_MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Event time extraction settings (`parsers.timestamps`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimestampConfig {
+    pub enabled: bool,
+    /// Zone for timestamps without an offset when no rule sets one: an IANA name, "UTC", "local" or "+05:30"
+    pub default_timezone: String,
+    /// Keep the collector's receive time in event.created
+    pub record_receive_time: bool,
+    /// Tried in order before the built-in rule
+    pub rules: Vec<TimestampRule>,
+}
+
+impl Default for TimestampConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_timezone: "UTC".to_string(),
+            record_receive_time: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// Where to find the event time for some sources or parsers, and how to read it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimestampRule {
+    /// Source types the rule applies to; empty matches every source
+    pub sources: Vec<String>,
+    /// Parser names the rule applies to; empty matches every parser
+    pub parsers: Vec<String>,
+    /// Fields holding the event time, tried in order; defaults to @timestamp, timestamp and time
+    pub fields: Vec<String>,
+    /// strptime-style formats tried in order after RFC 3339; formats without a year take the most recent one
+    /// that doesn't put the event more than a day in the future
+    pub formats: Vec<String>,
+    /// Zone for this rule's timestamps that carry no offset
+    pub timezone: Option<String>,
+    /// Language of the month names ("de", "fr", "es", "it", "pt" or "nl"); English names are always understood
+    pub locale: Option<String>,
+}
+
+impl TimestampConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Err(e) = Zone::parse(&self.default_timezone) {
+            errors.push(format!("default_timezone: {}", e));
+        }
+        for (i, rule) in self.rules.iter().enumerate() {
+            let rule_name = format!("rule {}", i + 1);
+            if let Some(Err(e)) = rule.timezone.as_deref().map(Zone::parse) {
+                errors.push(format!("{}: timezone: {}", rule_name, e));
+            }
+            if let Some(locale) = &rule.locale {
+                if month_names(locale).is_none() {
+                    errors.push(format!("{}: unsupported locale '{}'", rule_name, locale));
+                }
+            }
+            for format in &rule.formats {
+                if format.is_empty() || StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                    errors.push(format!("{}: invalid format '{}'", rule_name, format));
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// Timezone applied to timestamps that carry no offset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zone {
+    Utc,
+    /// The host's zone
+    Local,
+    Fixed(FixedOffset),
+    Named(chrono_tz::Tz),
+}
+
+impl Zone {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("utc") || name.eq_ignore_ascii_case("z") {
+            return Ok(Zone::Utc);
+        }
+        if name.eq_ignore_ascii_case("local") {
+            return Ok(Zone::Local);
+        }
+        if name.starts_with(['+', '-']) {
+            return name.parse::<FixedOffset>().map(Zone::Fixed).map_err(|_| format!("invalid UTC offset '{}'", name));
+        }
+        name.parse::<chrono_tz::Tz>().map(Zone::Named).map_err(|_| format!("unknown timezone '{}'", name))
+    }
+
+    /// The UTC instant of a wall-clock time; DST gaps move forward an hour and repeated hours take the first
+    fn to_utc(self, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::Utc => Some(naive.and_utc()),
+            Zone::Local => resolve(&Local, naive),
+            Zone::Fixed(offset) => resolve(&offset, naive),
+            Zone::Named(tz) => resolve(&tz, naive),
+        }
+    }
+
+    fn year_at(self, instant: DateTime<Utc>) -> i32 {
+        match self {
+            Zone::Utc => instant.year(),
+            Zone::Local => instant.with_timezone(&Local).year(),
+            Zone::Fixed(offset) => instant.with_timezone(&offset).year(),
+            Zone::Named(tz) => instant.with_timezone(&tz).year(),
+        }
+    }
+}
+
+fn resolve<Z: TimeZone>(zone: &Z, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    zone.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| zone.from_local_datetime(&(naive + chrono::Duration::hours(1))).earliest())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn month_names(locale: &str) -> Option<&'static [&'static str; 12]> {
+    let language = locale.split(['_', '-']).next().unwrap_or_default();
+    MONTH_NAMES.iter().find(|(code, _)| code.eq_ignore_ascii_case(language)).map(|(_, names)| names)
+}
+
+/// `text` with every word naming a month in `names` replaced by the English abbreviation
+fn english_month_names(text: &str, names: &[&str; 12]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(char::is_alphabetic) {
+        out.push_str(&rest[..start]);
+        let word_len = rest[start..].find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len() - start);
+        let word = &rest[start..start + word_len];
+        let lower = word.to_lowercase();
+        match names.iter().position(|alternatives| alternatives.split('|').any(|name| name == lower)) {
+            Some(month) => out.push_str(ENGLISH_MONTHS[month]),
+            None => out.push_str(word),
+        }
+        rest = &rest[start + word_len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+struct CompiledRule {
+    sources: Vec<String>,
+    parsers: Vec<String>,
+    fields: Vec<String>,
+    formats: Vec<String>,
+    zone: Zone,
+    month_names: Option<&'static [&'static str; 12]>,
+}
+
+impl CompiledRule {
+    fn matches(&self, event: &ParsedEvent) -> bool {
+        (self.sources.is_empty() || self.sources.iter().any(|s| s.eq_ignore_ascii_case(&event.source)))
+            && (self.parsers.is_empty() || self.parsers.contains(&event.parser_name))
+    }
+
+    fn event_time(&self, event: &ParsedEvent, received: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.fields.iter().find_map(|field| self.parse(event.fields.get(field)?, received))
+    }
+
+    fn parse(&self, value: &Value, received: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let text = match value {
+            Value::Number(n) => {
+                let epoch = n.as_f64().filter(|epoch| epoch.is_finite())?;
+                let millis = if epoch.abs() >= EPOCH_MILLIS_THRESHOLD { epoch } else { epoch * 1000.0 };
+                return DateTime::from_timestamp_millis(millis.round() as i64);
+            }
+            Value::String(s) => s.trim(),
+            _ => return None,
+        };
+        if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+            return Some(time.with_timezone(&Utc));
+        }
+        let text = match self.month_names {
+            Some(names) => english_month_names(text, names),
+            None => text.to_string(),
+        };
+        self.formats.iter().find_map(|format| self.parse_format(&text, format, received))
+    }
+
+    fn parse_format(&self, text: &str, format: &str, received: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if let Ok(time) = DateTime::parse_from_str(text, format) {
+            return Some(time.with_timezone(&Utc));
+        }
+        match NaiveDateTime::parse_from_str(text, format) {
+            Ok(naive) => self.zone.to_utc(naive),
+            Err(e) if e.kind() == ParseErrorKind::NotEnough => self.without_year(text, format, received),
+            Err(_) => None,
+        }
+    }
+
+    /// Times like syslog's `Mar  9 23:15:00` take the receive year, or the one before when that would put them
+    /// more than a day after the receive time (December events read in January)
+    fn without_year(&self, text: &str, format: &str, received: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let format = format!("%Y {}", format);
+        let in_year = |year: i32| {
+            NaiveDateTime::parse_from_str(&format!("{} {}", year, text), &format).ok().and_then(|naive| self.zone.to_utc(naive))
+        };
+        let year = self.zone.year_at(received);
+        let time = in_year(year)?;
+        if time > received + chrono::Duration::days(1) {
+            return in_year(year - 1);
+        }
+        Some(time)
+    }
+}
+
+/// Compiled `parsers.timestamps` configuration
+pub struct TimestampExtractor {
+    enabled: bool,
+    record_receive_time: bool,
+    rules: Vec<CompiledRule>,
+}
+
+impl TimestampExtractor {
+    /// Invalid zones and locales have been rejected by config validation; any that get here fall back to UTC and English
+    pub fn new(timestamps: &TimestampConfig) -> Self {
+        let zone = |name: &str| {
+            Zone::parse(name).unwrap_or_else(|e| {
+                warn!("⚠️  Timestamps: {}, using UTC", e);
+                Zone::Utc
+            })
+        };
+        let default_zone = zone(&timestamps.default_timezone);
+        let mut rules: Vec<CompiledRule> = timestamps.rules.iter().map(|rule| CompiledRule {
+            sources: rule.sources.clone(),
+            parsers: rule.parsers.clone(),
+            fields: match rule.fields.is_empty() {
+                true => DEFAULT_FIELDS.iter().map(|f| f.to_string()).collect(),
+                false => rule.fields.clone(),
+            },
+            formats: match rule.formats.is_empty() {
+                true => DEFAULT_FORMATS.iter().map(|f| f.to_string()).collect(),
+                false => rule.formats.clone(),
+            },
+            zone: rule.timezone.as_deref().map(zone).unwrap_or(default_zone),
+            month_names: rule.locale.as_deref().and_then(month_names),
+        }).collect();
+        rules.push(CompiledRule {
+            sources: Vec::new(),
+            parsers: Vec::new(),
+            fields: DEFAULT_FIELDS.iter().map(|f| f.to_string()).collect(),
+            formats: DEFAULT_FORMATS.iter().map(|f| f.to_string()).collect(),
+            zone: default_zone,
+            month_names: None,
+        });
+        Self { enabled: timestamps.enabled, record_receive_time: timestamps.record_receive_time, rules }
+    }
+
+    /// Set the event time from the first matching rule that can read it, and record the receive time
+    pub fn apply(&self, event: &mut ParsedEvent, received: DateTime<Utc>) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(time) = self.rules.iter().filter(|rule| rule.matches(event)).find_map(|rule| rule.event_time(event, received)) {
+            event.timestamp = time;
+        }
+        if self.record_receive_time {
+            event.fields.insert(RECEIVE_TIME_FIELD.to_string(), Value::String(received.to_rfc3339_opts(SecondsFormat::Millis, true)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn event(source: &str, fields: Value) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: received(),
+            source: source.to_string(),
+            level: None,
+            message: String::new(),
+            fields: serde_json::from_value::<HashMap<String, Value>>(fields).unwrap(),
+            raw_data: "".into(),
+            parser_name: "app".to_string(),
+        }
+    }
+
+    fn received() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()
+    }
+
+    #[test]
+    fn test_rules_apply_formats_zones_and_locales() {
+        let config: TimestampConfig = toml::from_str(r#"
+            default_timezone = "America/Chicago"
+
+            [[rules]]
+            sources = ["firewall"]
+            fields = ["datum"]
+            formats = ["%d. %b %Y %H:%M:%S"]
+            timezone = "Europe/Berlin"
+            locale = "de_DE"
+        "#).unwrap();
+        assert!(config.validate().is_empty());
+        let extractor = TimestampExtractor::new(&config);
+
+        // Rule zone and German month names; the receive time is kept
+        let mut firewall = event("firewall", json!({ "datum": "14. März 2023 10:00:00" }));
+        extractor.apply(&mut firewall, received());
+        assert_eq!(firewall.timestamp.to_rfc3339(), "2023-03-14T09:00:00+00:00");
+        assert_eq!(firewall.fields[RECEIVE_TIME_FIELD], "2024-01-02T03:04:05.000Z");
+
+        // Built-in rule: syslog time without a year in December, received in January, in the default zone
+        let mut syslog = event("syslog", json!({ "@timestamp": "Dec 31 23:30:00" }));
+        extractor.apply(&mut syslog, received());
+        assert_eq!(syslog.timestamp.to_rfc3339(), "2024-01-01T05:30:00+00:00");
+
+        // Offsets and epochs in the text win over the zone; unreadable times keep the receive time
+        let mut apache = event("file_monitor", json!({ "timestamp": "10/Oct/2023:13:55:36 -0700" }));
+        extractor.apply(&mut apache, received());
+        assert_eq!(apache.timestamp.to_rfc3339(), "2023-10-10T20:55:36+00:00");
+        let mut epoch = event("file_monitor", json!({ "time": 1_700_000_000_500u64 }));
+        extractor.apply(&mut epoch, received());
+        assert_eq!(epoch.timestamp.timestamp_millis(), 1_700_000_000_500);
+        let mut unreadable = event("file_monitor", json!({ "time": "yesterday" }));
+        extractor.apply(&mut unreadable, received());
+        assert_eq!(unreadable.timestamp, received());
+    }
+
+    #[test]
+    fn test_zones_dst_and_validation() {
+        let chicago = Zone::parse("America/Chicago").unwrap();
+        // 02:30 doesn't exist on the spring-forward day and moves to 03:30 CDT
+        let gap = NaiveDateTime::parse_from_str("2024-03-10 02:30:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(chicago.to_utc(gap).unwrap().to_rfc3339(), "2024-03-10T08:30:00+00:00");
+        assert_eq!(Zone::parse("+05:30").unwrap(), Zone::Fixed(FixedOffset::east_opt(19_800).unwrap()));
+        assert_eq!(Zone::parse("utc").unwrap(), Zone::Utc);
+        assert_eq!(english_month_names("3 févr. 2024", month_names("fr").unwrap()), "3 Feb. 2024");
+
+        let config = TimestampConfig {
+            default_timezone: "Mars/Olympus".to_string(),
+            rules: vec![TimestampRule { locale: Some("tlh".to_string()), formats: vec!["%Q".to_string()], ..Default::default() }],
+            ..Default::default()
+        };
+        assert_eq!(config.validate().len(), 3);
+
+        let mut disabled = event("syslog", json!({ "@timestamp": "2023-05-01T00:00:00Z" }));
+        TimestampExtractor::new(&TimestampConfig { enabled: false, ..Default::default() }).apply(&mut disabled, received());
+        assert_eq!(disabled.timestamp, received());
+        assert!(!disabled.fields.contains_key(RECEIVE_TIME_FIELD));
+    }
+}
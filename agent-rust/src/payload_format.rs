@@ -0,0 +1,241 @@
+// Wire formats for native transport batches
+// JSON stays the default; NDJSON, protobuf (proto/event_batch.proto) and MessagePack can be selected per
+// destination. A server that answers 415 with an Accept list moves the transport to the best format it accepts,
+// the same way Accept-Encoding negotiates compression.
+
+use crate::errors::TransportError;
+use crate::otlp::{write_key_value, ProtoWriter};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Batch format version carried in every payload
+pub const BATCH_VERSION: &str = "1.0.0";
+
+/// Header carrying the agent identity for formats without a batch envelope
+pub const AGENT_ID_HEADER: &str = "X-SecureWatch-Agent-Id";
+
+/// Serialization of a batch of events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadFormat {
+    /// `{"events": [...], "agent_id": ..., "timestamp": ..., "version": ...}`
+    #[default]
+    Json,
+    /// One event object per line; the agent ID travels in a header
+    Ndjson,
+    /// `EventBatch` from proto/event_batch.proto
+    Protobuf,
+    /// The JSON batch object encoded as MessagePack
+    Msgpack,
+}
+
+/// Preference order when the server lists several acceptable formats
+const NEGOTIATION_ORDER: [PayloadFormat; 4] =
+    [PayloadFormat::Protobuf, PayloadFormat::Msgpack, PayloadFormat::Ndjson, PayloadFormat::Json];
+
+impl PayloadFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Ndjson => "application/x-ndjson",
+            Self::Protobuf => "application/x-protobuf",
+            Self::Msgpack => "application/msgpack",
+        }
+    }
+
+    pub fn from_content_type(value: &str) -> Option<Self> {
+        let media_type = value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        match media_type.as_str() {
+            "application/json" => Some(Self::Json),
+            "application/x-ndjson" | "application/ndjson" => Some(Self::Ndjson),
+            "application/x-protobuf" | "application/protobuf" => Some(Self::Protobuf),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(Self::Msgpack),
+            _ => None,
+        }
+    }
+
+    /// Pick a format from a server's Accept value, keeping `preferred` when it is acceptable.
+    /// `None` when the server lists nothing this agent can produce.
+    pub fn negotiate(preferred: Self, accept: &str) -> Option<Self> {
+        let accepted: Vec<Self> = accept
+            .split(',')
+            .filter(|entry| !entry.split(';').skip(1).any(|p| p.trim().strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0)))
+            .filter_map(Self::from_content_type)
+            .collect();
+        if accepted.contains(&preferred) {
+            return Some(preferred);
+        }
+        NEGOTIATION_ORDER.into_iter().find(|format| accepted.contains(format))
+    }
+
+    /// Encode events already serialized (and field-filtered) as `ParsedEvent` JSON objects
+    pub fn encode(self, events: Vec<Value>, agent_id: &str) -> Result<Vec<u8>, TransportError> {
+        let failed = |e: serde_json::Error| TransportError::serialization_error(&e.to_string());
+        match self {
+            Self::Json => serde_json::to_vec(&batch_object(events, agent_id)).map_err(failed),
+            Self::Ndjson => {
+                let mut body = Vec::new();
+                for event in &events {
+                    serde_json::to_writer(&mut body, event).map_err(failed)?;
+                    body.push(b'\n');
+                }
+                Ok(body)
+            }
+            Self::Protobuf => Ok(encode_protobuf(&events, agent_id)),
+            Self::Msgpack => {
+                let mut body = Vec::new();
+                write_msgpack(&mut body, &batch_object(events, agent_id));
+                Ok(body)
+            }
+        }
+    }
+}
+
+fn batch_object(events: Vec<Value>, agent_id: &str) -> Value {
+    json!({
+        "events": events,
+        "agent_id": agent_id,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "version": BATCH_VERSION
+    })
+}
+
+fn unix_nanos(timestamp: Option<&str>) -> u64 {
+    timestamp
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .and_then(|t| t.timestamp_nanos_opt())
+        .unwrap_or_default()
+        .max(0) as u64
+}
+
+fn encode_protobuf(events: &[Value], agent_id: &str) -> Vec<u8> {
+    let text = |event: &Value, key: &str| event.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    let mut batch = ProtoWriter::default();
+    batch.string(1, agent_id);
+    batch.fixed64(2, chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default().max(0) as u64);
+    batch.string(3, BATCH_VERSION);
+    for event in events {
+        batch.message(4, |record| {
+            record.fixed64(1, unix_nanos(event.get("timestamp").and_then(Value::as_str)));
+            record.string(2, &text(event, "source"));
+            record.string(3, &text(event, "level"));
+            record.string(4, &text(event, "message"));
+            if let Some(fields) = event.get("fields").and_then(Value::as_object) {
+                for (key, value) in fields {
+                    record.message(5, |kv| write_key_value(kv, key, value));
+                }
+            }
+            record.string(6, &text(event, "raw_data"));
+            record.string(7, &text(event, "parser_name"));
+        });
+    }
+    batch.into_bytes()
+}
+
+fn write_msgpack(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => out.push(0xc0),
+        Value::Bool(b) => out.push(if *b { 0xc3 } else { 0xc2 }),
+        Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                match u {
+                    0..=0x7f => out.push(u as u8),
+                    0x80..=0xff => out.extend_from_slice(&[0xcc, u as u8]),
+                    0x100..=0xffff => { out.push(0xcd); out.extend_from_slice(&(u as u16).to_be_bytes()); }
+                    0x1_0000..=0xffff_ffff => { out.push(0xce); out.extend_from_slice(&(u as u32).to_be_bytes()); }
+                    _ => { out.push(0xcf); out.extend_from_slice(&u.to_be_bytes()); }
+                }
+            } else if let Some(i) = n.as_i64() {
+                match i {
+                    -32..=-1 => out.push(i as i8 as u8),
+                    -128..=-33 => out.extend_from_slice(&[0xd0, i as i8 as u8]),
+                    -32_768..=-129 => { out.push(0xd1); out.extend_from_slice(&(i as i16).to_be_bytes()); }
+                    -2_147_483_648..=-32_769 => { out.push(0xd2); out.extend_from_slice(&(i as i32).to_be_bytes()); }
+                    _ => { out.push(0xd3); out.extend_from_slice(&i.to_be_bytes()); }
+                }
+            } else {
+                out.push(0xcb);
+                out.extend_from_slice(&n.as_f64().unwrap_or_default().to_be_bytes());
+            }
+        }
+        Value::String(s) => {
+            write_msgpack_length(out, s.len(), 0xa0, 32, [0xd9, 0xda, 0xdb]);
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::Array(items) => {
+            write_msgpack_length(out, items.len(), 0x90, 16, [0, 0xdc, 0xdd]);
+            for item in items {
+                write_msgpack(out, item);
+            }
+        }
+        Value::Object(map) => {
+            write_msgpack_length(out, map.len(), 0x80, 16, [0, 0xde, 0xdf]);
+            for (key, item) in map {
+                write_msgpack(out, &Value::String(key.clone()));
+                write_msgpack(out, item);
+            }
+        }
+    }
+}
+
+/// Length prefix: a fix type below `fix_limit`, otherwise the 8/16/32-bit marker (a zero 8-bit marker means none)
+fn write_msgpack_length(out: &mut Vec<u8>, len: usize, fix_marker: u8, fix_limit: usize, markers: [u8; 3]) {
+    if len < fix_limit {
+        out.push(fix_marker | len as u8);
+    } else if len <= 0xff && markers[0] != 0 {
+        out.extend_from_slice(&[markers[0], len as u8]);
+    } else if len <= 0xffff {
+        out.push(markers[1]);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(markers[2]);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event() -> Value {
+        json!({
+            "timestamp": "2026-10-16T12:00:00Z",
+            "source": "syslog",
+            "level": "info",
+            "message": "Accepted publickey for alice",
+            "fields": { "user.name": "alice", "source.port": 52144 },
+            "raw_data": "<38>sshd[812]: Accepted publickey for alice",
+            "parser_name": "syslog",
+        })
+    }
+
+    #[test]
+    fn test_formats_encode_the_batch() {
+        let ndjson = PayloadFormat::Ndjson.encode(vec![event(), event()], "agent-1").unwrap();
+        let lines: Vec<Value> = ndjson.split(|b| *b == b'\n').filter(|l| !l.is_empty()).map(|l| serde_json::from_slice(l).unwrap()).collect();
+        assert_eq!(lines, vec![event(), event()]);
+
+        let protobuf = PayloadFormat::Protobuf.encode(vec![event()], "agent-1").unwrap();
+        // agent_id (field 1, length-delimited) comes first
+        assert_eq!(&protobuf[..9], b"\x0a\x07agent-1");
+        let json = PayloadFormat::Json.encode(vec![event()], "agent-1").unwrap();
+        assert!(protobuf.len() < json.len());
+
+        let mut msgpack = Vec::new();
+        write_msgpack(&mut msgpack, &json!({ "n": [1, -1, 300, -200, 1.5, null, true], "s": "x".repeat(40) }));
+        let mut expected = vec![0x82, 0xa1, b'n', 0x97, 0x01, 0xff, 0xcd, 0x01, 0x2c, 0xd1, 0xff, 0x38, 0xcb];
+        expected.extend_from_slice(&1.5f64.to_be_bytes());
+        expected.extend_from_slice(&[0xc0, 0xc3, 0xa1, b's', 0xd9, 40]);
+        expected.extend_from_slice("x".repeat(40).as_bytes());
+        assert_eq!(msgpack, expected);
+    }
+
+    #[test]
+    fn test_negotiation_follows_accept() {
+        use PayloadFormat::*;
+        assert_eq!(PayloadFormat::negotiate(Protobuf, "application/json, application/x-ndjson"), Some(Ndjson));
+        assert_eq!(PayloadFormat::negotiate(Msgpack, "application/vnd.msgpack;q=0.5, application/json"), Some(Msgpack));
+        assert_eq!(PayloadFormat::negotiate(Protobuf, "application/x-protobuf;q=0, application/json"), Some(Json));
+        assert_eq!(PayloadFormat::negotiate(Json, "text/csv"), None);
+    }
+}
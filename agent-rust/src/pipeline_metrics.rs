@@ -0,0 +1,272 @@
+// Latency histograms for the ingest pipeline
+// Parse time is recorded per parser; queue wait (collection until the transport picks the event up) and flush
+// latency (upload until acknowledged, retries included) per source. Fixed buckets keep recording lock-free once a
+// series exists and give percentiles, so tail latency shows up where the averages in the other stats hide it.
+
+use crate::parsers::timestamps::RECEIVE_TIME_FIELD;
+use crate::parsers::ParsedEvent;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the histogram buckets in microseconds; one more bucket holds everything slower
+pub const BUCKET_BOUNDS_MICROS: [u64; 20] = [
+    50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000,
+    1_000_000, 2_500_000, 5_000_000, 10_000_000, 30_000_000, 60_000_000, 300_000_000,
+];
+const BUCKETS: usize = BUCKET_BOUNDS_MICROS.len() + 1;
+
+/// Series per stage before new keys share the `other` series, so a flood of source names can't grow memory
+const MAX_SERIES_PER_STAGE: usize = 256;
+pub const OVERFLOW_KEY: &str = "other";
+/// Parse time of events no parser matched
+pub const UNMATCHED: &str = "unmatched";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Matching and parsing one event, per parser
+    Parse,
+    /// Collection until the transport picks the event up, per source; needs `parsers.timestamps.record_receive_time`
+    QueueWait,
+    /// Upload of a batch until the server acknowledged it, per source in the batch
+    Flush,
+}
+
+impl Stage {
+    const ALL: [Stage; 3] = [Stage::Parse, Stage::QueueWait, Stage::Flush];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Stage::Parse => "parse",
+            Stage::QueueWait => "queue_wait",
+            Stage::Flush => "flush",
+        }
+    }
+}
+
+struct Series {
+    buckets: [AtomicU64; BUCKETS],
+    events: AtomicU64,
+    sum_micros: AtomicU64,
+    max_micros: AtomicU64,
+    created: Instant,
+}
+
+impl Series {
+    fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            events: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+            max_micros: AtomicU64::new(0),
+            created: Instant::now(),
+        }
+    }
+
+    fn record(&self, micros: u64, events: u64) {
+        let bucket = BUCKET_BOUNDS_MICROS.partition_point(|bound| *bound < micros);
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.events.fetch_add(events, Ordering::Relaxed);
+        self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+}
+
+type StageSeries = RwLock<HashMap<String, Arc<Series>>>;
+
+static REGISTRY: OnceLock<[StageSeries; 3]> = OnceLock::new();
+
+fn stage_series(stage: Stage) -> &'static StageSeries {
+    let registry = REGISTRY.get_or_init(|| std::array::from_fn(|_| RwLock::new(HashMap::new())));
+    &registry[stage as usize]
+}
+
+/// Record one sample covering `events` events (a flushed batch covers all of its events from that source)
+pub fn record(stage: Stage, key: &str, elapsed: Duration, events: u64) {
+    let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+    let series = stage_series(stage);
+    if let Some(existing) = series.read().get(key) {
+        existing.record(micros, events);
+        return;
+    }
+    let mut series = series.write();
+    let key = if series.len() >= MAX_SERIES_PER_STAGE && !series.contains_key(key) { OVERFLOW_KEY } else { key };
+    series.entry(key.to_string()).or_insert_with(|| Arc::new(Series::new())).record(micros, events);
+}
+
+/// Queue wait of every event that carries its receive time
+pub fn record_queue_wait(events: &[ParsedEvent]) {
+    let now = Utc::now();
+    for event in events {
+        let Some(received) = event.fields.get(RECEIVE_TIME_FIELD).and_then(Value::as_str).and_then(|t| DateTime::parse_from_rfc3339(t).ok()) else {
+            continue;
+        };
+        record(Stage::QueueWait, &event.source, (now - received.with_timezone(&Utc)).to_std().unwrap_or_default(), 1);
+    }
+}
+
+/// One flush sample per source in the batch, covering that source's events
+pub fn record_flush(events: &[ParsedEvent], elapsed: Duration) {
+    let mut per_source: HashMap<&str, u64> = HashMap::new();
+    for event in events {
+        *per_source.entry(event.source.as_str()).or_default() += 1;
+    }
+    for (source, count) in per_source {
+        record(Stage::Flush, source, elapsed, count);
+    }
+}
+
+/// Cumulative histograms of every series, ordered by stage and key
+pub fn snapshot() -> Vec<LatencyHistogram> {
+    let mut histograms = Vec::new();
+    for stage in Stage::ALL {
+        let series = stage_series(stage).read();
+        let mut keys: Vec<&String> = series.keys().collect();
+        keys.sort();
+        histograms.extend(keys.into_iter().map(|key| {
+            let s = &series[key];
+            LatencyHistogram {
+                stage,
+                key: key.clone(),
+                buckets: s.buckets.iter().map(|b| b.load(Ordering::Relaxed)).collect(),
+                events: s.events.load(Ordering::Relaxed),
+                sum_micros: s.sum_micros.load(Ordering::Relaxed),
+                max_micros: s.max_micros.load(Ordering::Relaxed),
+                window_seconds: s.created.elapsed().as_secs_f64(),
+            }
+        }));
+    }
+    histograms
+}
+
+/// Point-in-time copy of one series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    pub stage: Stage,
+    /// Parser name for parse time, source name otherwise
+    pub key: String,
+    /// Samples per bucket, bounded by BUCKET_BOUNDS_MICROS with the last bucket unbounded
+    pub buckets: Vec<u64>,
+    pub events: u64,
+    pub sum_micros: u64,
+    pub max_micros: u64,
+    /// Time the counts were collected over
+    pub window_seconds: f64,
+}
+
+impl LatencyHistogram {
+    pub fn samples(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    pub fn mean_ms(&self) -> f64 {
+        match self.samples() {
+            0 => 0.0,
+            samples => self.sum_micros as f64 / samples as f64 / 1000.0,
+        }
+    }
+
+    pub fn max_ms(&self) -> f64 {
+        self.max_micros as f64 / 1000.0
+    }
+
+    /// Upper bound of the bucket holding the quantile, capped at the slowest sample
+    pub fn percentile_ms(&self, quantile: f64) -> f64 {
+        let rank = (quantile.clamp(0.0, 1.0) * self.samples() as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = BUCKET_BOUNDS_MICROS.get(bucket).copied().unwrap_or(u64::MAX);
+                return bound.min(self.max_micros) as f64 / 1000.0;
+            }
+        }
+        0.0
+    }
+
+    pub fn events_per_second(&self) -> f64 {
+        if self.window_seconds > 0.0 { self.events as f64 / self.window_seconds } else { 0.0 }
+    }
+
+    /// What was recorded since `previous`, an earlier snapshot of the same series; the maximum can't be split by
+    /// time, so it is the lower of the overall maximum and the slowest bucket used in between
+    pub fn since(&self, previous: &LatencyHistogram) -> LatencyHistogram {
+        let buckets: Vec<u64> = self.buckets.iter().zip(&previous.buckets).map(|(now, before)| now.saturating_sub(*before)).collect();
+        let slowest_bound = buckets.iter().rposition(|count| *count > 0)
+            .map_or(0, |bucket| BUCKET_BOUNDS_MICROS.get(bucket).copied().unwrap_or(u64::MAX));
+        LatencyHistogram {
+            stage: self.stage,
+            key: self.key.clone(),
+            buckets,
+            events: self.events.saturating_sub(previous.events),
+            sum_micros: self.sum_micros.saturating_sub(previous.sum_micros),
+            max_micros: self.max_micros.min(slowest_bound),
+            window_seconds: (self.window_seconds - previous.window_seconds).max(0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(samples_ms: &[u64]) -> LatencyHistogram {
+        let series = Series::new();
+        for ms in samples_ms {
+            series.record(ms * 1000, 10);
+        }
+        LatencyHistogram {
+            stage: Stage::Flush,
+            key: "syslog".to_string(),
+            buckets: series.buckets.iter().map(|b| b.load(Ordering::Relaxed)).collect(),
+            events: series.events.load(Ordering::Relaxed),
+            sum_micros: series.sum_micros.load(Ordering::Relaxed),
+            max_micros: series.max_micros.load(Ordering::Relaxed),
+            window_seconds: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_percentiles_expose_the_tail() {
+        // 98 fast flushes and two slow ones: the mean looks fine, p99 doesn't
+        let mut samples = vec![2; 98];
+        samples.extend([4_000, 7_000]);
+        let flushes = histogram(&samples);
+        assert_eq!(flushes.samples(), 100);
+        assert!(flushes.mean_ms() < 115.0);
+        assert_eq!(flushes.percentile_ms(0.5), 2.5);
+        assert_eq!(flushes.percentile_ms(0.95), 2.5);
+        assert_eq!(flushes.percentile_ms(0.99), 5_000.0);
+        assert_eq!(flushes.percentile_ms(1.0), 7_000.0);
+        assert_eq!(flushes.max_ms(), 7_000.0);
+        assert_eq!(flushes.events_per_second(), 100.0);
+
+        let earlier = histogram(&samples[..98]);
+        let interval = flushes.since(&LatencyHistogram { window_seconds: 4.0, ..earlier });
+        assert_eq!((interval.samples(), interval.events, interval.window_seconds), (2, 20, 6.0));
+        assert_eq!(interval.percentile_ms(0.5), 5_000.0);
+        assert_eq!(interval.max_ms(), 7_000.0);
+        assert_eq!(histogram(&[]).percentile_ms(0.99), 0.0);
+    }
+
+    #[test]
+    fn test_recorded_series_appear_in_snapshot() {
+        record(Stage::Parse, "test_snapshot_parser", Duration::from_micros(300), 1);
+        record(Stage::Parse, "test_snapshot_parser", Duration::from_millis(40), 1);
+        record(Stage::QueueWait, "test_snapshot_source", Duration::from_secs(2), 1);
+
+        let snapshot = snapshot();
+        let parse = snapshot.iter().find(|h| h.stage == Stage::Parse && h.key == "test_snapshot_parser").unwrap();
+        assert_eq!((parse.samples(), parse.events, parse.max_micros), (2, 2, 40_000));
+        assert_eq!(parse.percentile_ms(0.5), 0.5);
+        let wait = snapshot.iter().find(|h| h.key == "test_snapshot_source").unwrap();
+        assert_eq!((wait.stage, wait.percentile_ms(0.99)), (Stage::QueueWait, 2_000.0));
+        assert!(snapshot.windows(2).all(|pair| (pair[0].stage, &pair[0].key) <= (pair[1].stage, &pair[1].key)));
+    }
+}
@@ -0,0 +1,801 @@
+// Process lineage cache for enriching process events
+// Keeps a short-lived PID -> process record map so Sysmon/ETW/eBPF/auditd events that only
+// carry PIDs can be enriched with their process, parent and full ancestry. Records are learned
+// from events and refreshed from the operating system's process table.
+
+use crate::parsers::ParsedEvent;
+use chrono::{DateTime, TimeZone, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind, Users};
+use tracing::debug;
+
+/// Field aliases used to read process identity from parsed events.
+/// ECS names come first, followed by Sysmon and auditd equivalents.
+const PID_FIELDS: &[&str] = &["process.pid", "ProcessId", "pid"];
+const PPID_FIELDS: &[&str] = &["process.parent.pid", "ParentProcessId", "ppid"];
+const NAME_FIELDS: &[&str] = &["process.name", "comm"];
+const EXECUTABLE_FIELDS: &[&str] = &["process.executable", "Image", "exe"];
+const COMMAND_LINE_FIELDS: &[&str] = &["process.command_line", "CommandLine", "proctitle"];
+const PARENT_EXECUTABLE_FIELDS: &[&str] = &["process.parent.executable", "ParentImage"];
+const PARENT_COMMAND_LINE_FIELDS: &[&str] = &["process.parent.command_line", "ParentCommandLine"];
+const USER_FIELDS: &[&str] = &["process.user.name", "User"];
+const START_FIELDS: &[&str] = &["process.start"];
+
+/// Start times further apart than this belong to different processes; the process table only
+/// reports whole seconds
+const START_TIME_TOLERANCE_SECONDS: i64 = 1;
+
+/// Configuration for the process lineage cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessLineageConfig {
+    /// Enable lineage tracking and enrichment
+    pub enabled: bool,
+    /// Maximum number of processes kept in the cache
+    pub max_entries: usize,
+    /// How long a process record is kept after it was last seen (seconds)
+    pub ttl_seconds: u64,
+    /// How often expired records are purged (seconds)
+    pub purge_interval_seconds: u64,
+    /// How often the cache is refreshed from the operating system's process table (seconds);
+    /// 0 learns from events only
+    pub refresh_interval_seconds: u64,
+    /// Ancestors listed in `process.ancestors`, nearest first
+    pub max_depth: usize,
+    /// Sources whose events are attributed; events from other sources may describe processes
+    /// on other hosts
+    pub sources: Vec<String>,
+}
+
+impl Default for ProcessLineageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: 10000,
+            ttl_seconds: 900, // 15 minutes
+            purge_interval_seconds: 60,
+            refresh_interval_seconds: 30,
+            max_depth: 8,
+            sources: ["ebpf", "etw", "windows_event", "file_monitor"].map(String::from).to_vec(),
+        }
+    }
+}
+
+impl ProcessLineageConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.max_entries == 0 {
+            errors.push("max_entries must be greater than 0".to_string());
+        }
+        if self.max_depth == 0 || self.max_depth > 64 {
+            errors.push("max_depth must be between 1 and 64".to_string());
+        }
+        if self.sources.iter().any(|source| source.trim().is_empty()) {
+            errors.push("sources must not contain empty names".to_string());
+        }
+        errors
+    }
+}
+
+/// A single cached process
+#[derive(Debug, Clone)]
+pub struct ProcessRecord {
+    pub pid: u32,
+    pub ppid: Option<u32>,
+    pub name: Option<String>,
+    pub executable: Option<String>,
+    pub command_line: Option<String>,
+    pub user: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub last_seen: Instant,
+}
+
+impl ProcessRecord {
+    fn new(pid: u32, ppid: Option<u32>, now: Instant) -> Self {
+        Self {
+            pid,
+            ppid,
+            name: None,
+            executable: None,
+            command_line: None,
+            user: None,
+            start_time: None,
+            last_seen: now,
+        }
+    }
+
+    /// A process table entry; the user ID is resolved to a name when the account is known
+    fn from_process(process: &Process, users: &Users, now: Instant) -> Self {
+        let command_line = process.cmd().iter().map(|arg| arg.to_string_lossy()).collect::<Vec<_>>().join(" ");
+        Self {
+            pid: process.pid().as_u32(),
+            ppid: process.parent().map(|ppid| ppid.as_u32()),
+            name: Some(process.name().to_string_lossy().into_owned()).filter(|name| !name.is_empty()),
+            executable: process.exe().map(|exe| exe.display().to_string()),
+            command_line: Some(command_line).filter(|cmdline| !cmdline.is_empty()),
+            user: process.user_id().map(|uid| match users.get_user_by_id(uid) {
+                Some(user) => user.name().to_string(),
+                None => uid.to_string(),
+            }),
+            start_time: Utc.timestamp_opt(process.start_time() as i64, 0).single().filter(|t| t.timestamp() > 0),
+            last_seen: now,
+        }
+    }
+
+    /// `other` describes a different process that reuses this PID
+    fn reused_by(&self, other: &ProcessRecord) -> bool {
+        match (self.start_time, other.start_time) {
+            (Some(a), Some(b)) => (a - b).num_seconds().abs() > START_TIME_TOLERANCE_SECONDS,
+            _ => self.ppid.is_some() && other.ppid.is_some() && self.ppid != other.ppid,
+        }
+    }
+
+    /// Fill in what the process table knows. The first parent seen is kept, since orphans are
+    /// reparented to init or a subreaper and the original parent is the one worth reporting.
+    fn update_from(&mut self, other: ProcessRecord) {
+        if self.ppid.is_none() {
+            self.ppid = other.ppid;
+        }
+        merge(&mut self.name, other.name);
+        merge(&mut self.executable, other.executable);
+        merge(&mut self.command_line, other.command_line);
+        merge(&mut self.user, other.user);
+        if other.start_time.is_some() {
+            self.start_time = other.start_time;
+        }
+        self.last_seen = other.last_seen;
+    }
+
+    /// Best-effort display name: explicit name, else the executable's file name
+    pub fn display_name(&self) -> Option<String> {
+        self.name.clone().or_else(|| {
+            self.executable.as_ref().map(|exe| {
+                exe.rsplit(['/', '\\'])
+                    .next()
+                    .unwrap_or(exe)
+                    .to_string()
+            })
+        })
+    }
+}
+
+/// Lineage cache statistics
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProcessLineageStats {
+    pub cached_processes: usize,
+    pub events_observed: u64,
+    pub events_enriched: u64,
+    pub cache_misses: u64,
+    pub evictions: u64,
+    pub expired: u64,
+    pub system_refreshes: u64,
+    pub system_lookups: u64,
+}
+
+/// The operating system's process table, kept between refreshes so unchanged processes are
+/// cheap to revisit
+struct ProcessTable {
+    system: System,
+    users: Users,
+    /// Set by the first full refresh; lookups of single PIDs only start after it
+    refreshed: bool,
+    /// PIDs looked up and not found since the last full refresh
+    missing: HashMap<u32, Instant>,
+}
+
+impl ProcessTable {
+    fn refresh_kind() -> ProcessRefreshKind {
+        ProcessRefreshKind::new()
+            .with_exe(UpdateKind::OnlyIfNotSet)
+            .with_cmd(UpdateKind::OnlyIfNotSet)
+            .with_user(UpdateKind::OnlyIfNotSet)
+    }
+}
+
+/// Cached records indexed by when they were last seen, so eviction and expiry take the oldest
+/// records without scanning the whole cache
+#[derive(Default)]
+struct ProcessEntries {
+    records: HashMap<u32, ProcessRecord>,
+    by_last_seen: BTreeSet<(Instant, u32)>,
+}
+
+impl ProcessEntries {
+    fn get(&self, pid: u32) -> Option<&ProcessRecord> {
+        self.records.get(&pid)
+    }
+
+    fn insert(&mut self, record: ProcessRecord) {
+        self.remove(record.pid);
+        self.by_last_seen.insert((record.last_seen, record.pid));
+        self.records.insert(record.pid, record);
+    }
+
+    fn remove(&mut self, pid: u32) -> Option<ProcessRecord> {
+        let record = self.records.remove(&pid)?;
+        self.by_last_seen.remove(&(record.last_seen, pid));
+        Some(record)
+    }
+
+    fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Drop the `count` least recently seen records
+    fn evict_oldest(&mut self, count: usize) {
+        for _ in 0..count {
+            let Some((_, pid)) = self.by_last_seen.pop_first() else {
+                break;
+            };
+            self.records.remove(&pid);
+        }
+    }
+
+    /// Drop records not seen within `ttl`, returning how many were dropped
+    fn purge_expired(&mut self, ttl: Duration) -> usize {
+        let mut removed = 0;
+        while let Some(&(last_seen, pid)) = self.by_last_seen.first() {
+            if last_seen.elapsed() < ttl {
+                break;
+            }
+            self.by_last_seen.pop_first();
+            self.records.remove(&pid);
+            removed += 1;
+        }
+        removed
+    }
+}
+
+/// Short-lived process tree cache shared across the event pipeline
+#[derive(Clone)]
+pub struct ProcessLineageCache {
+    config: ProcessLineageConfig,
+    entries: Arc<RwLock<ProcessEntries>>,
+    stats: Arc<RwLock<ProcessLineageStats>>,
+    table: Arc<Mutex<ProcessTable>>,
+}
+
+impl ProcessLineageCache {
+    pub fn new(config: ProcessLineageConfig) -> Self {
+        Self {
+            config,
+            entries: Arc::new(RwLock::new(ProcessEntries::default())),
+            stats: Arc::new(RwLock::new(ProcessLineageStats::default())),
+            table: Arc::new(Mutex::new(ProcessTable {
+                system: System::new(),
+                users: Users::new(),
+                refreshed: false,
+                missing: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Events from this source describe processes on this host
+    pub fn attributes(&self, source: &str) -> bool {
+        self.config.sources.iter().any(|s| s == source)
+    }
+
+    /// Hook the parsing engine runs on every parsed event; only events from `sources` are
+    /// recorded and enriched
+    pub fn attribute(&self, event: &mut ParsedEvent) -> bool {
+        self.attributes(&event.source) && self.process_event(event)
+    }
+
+    /// Record the process described by an event and enrich it with its ancestry
+    pub fn process_event(&self, event: &mut ParsedEvent) -> bool {
+        self.observe(event);
+        if let Some(pid) = field_u32(event, PID_FIELDS) {
+            self.resolve(pid);
+        }
+        self.enrich(event)
+    }
+
+    /// Learn from an event: cache the process and, when present, its parent
+    pub fn observe(&self, event: &ParsedEvent) {
+        let Some(pid) = field_u32(event, PID_FIELDS) else {
+            return;
+        };
+        let ppid = field_u32(event, PPID_FIELDS);
+        let now = Instant::now();
+        let mut observed = ProcessRecord::new(pid, ppid, now);
+        observed.start_time = field_time(event, START_FIELDS);
+
+        let mut entries = self.entries.write();
+
+        // A changed parent or start time means the PID was reused, so drop the stale record first
+        let mut record = match entries.remove(pid) {
+            Some(existing) if !existing.reused_by(&observed) => existing,
+            _ => observed,
+        };
+        record.last_seen = now;
+        if ppid.is_some() {
+            record.ppid = ppid;
+        }
+        merge(&mut record.name, field_string(event, NAME_FIELDS));
+        merge(&mut record.executable, field_string(event, EXECUTABLE_FIELDS));
+        merge(&mut record.command_line, field_string(event, COMMAND_LINE_FIELDS));
+        merge(&mut record.user, field_string(event, USER_FIELDS));
+        if let Some(start_time) = field_time(event, START_FIELDS) {
+            record.start_time = Some(start_time);
+        }
+        entries.insert(record);
+
+        // Sysmon process creation events also describe the parent
+        if let Some(ppid) = ppid {
+            let parent_executable = field_string(event, PARENT_EXECUTABLE_FIELDS);
+            let parent_command_line = field_string(event, PARENT_COMMAND_LINE_FIELDS);
+            if parent_executable.is_some() || parent_command_line.is_some() {
+                let mut parent = entries.remove(ppid).unwrap_or_else(|| ProcessRecord::new(ppid, None, now));
+                parent.last_seen = now;
+                merge(&mut parent.executable, parent_executable);
+                merge(&mut parent.command_line, parent_command_line);
+                entries.insert(parent);
+            }
+        }
+
+        let over_capacity = entries.len().saturating_sub(self.config.max_entries);
+        entries.evict_oldest(over_capacity);
+        let cached = entries.len();
+        drop(entries);
+
+        let mut stats = self.stats.write();
+        stats.events_observed += 1;
+        stats.evictions += over_capacity as u64;
+        stats.cached_processes = cached;
+    }
+
+    /// Add the process's own details, its parent and grandparent, and the ancestry chain to an
+    /// event. Returns true if anything was added.
+    pub fn enrich(&self, event: &mut ParsedEvent) -> bool {
+        let Some(pid) = field_u32(event, PID_FIELDS) else {
+            return false;
+        };
+
+        let entries = self.entries.read();
+        let process = entries.get(pid).cloned();
+        let ppid = field_u32(event, PPID_FIELDS)
+            .or_else(|| process.as_ref().and_then(|record| record.ppid));
+        let ancestors = match ppid {
+            Some(ppid) => Self::chain(&entries, ppid, self.config.max_depth, &[pid]),
+            None => Vec::new(),
+        };
+        drop(entries);
+
+        let mut enriched = false;
+        if let Some(process) = &process {
+            if let Some(name) = process.display_name() {
+                enriched |= insert_missing(event, "process.name", serde_json::json!(name));
+            }
+            if let Some(executable) = &process.executable {
+                enriched |= insert_missing(event, "process.executable", serde_json::json!(executable));
+            }
+            if let Some(command_line) = &process.command_line {
+                enriched |= insert_missing(event, "process.command_line", serde_json::json!(command_line));
+            }
+            if let Some(user) = &process.user {
+                enriched |= insert_missing(event, "process.user.name", serde_json::json!(user));
+            }
+            if let Some(start_time) = process.start_time {
+                enriched |= insert_missing(event, "process.start", serde_json::json!(start_time.to_rfc3339()));
+            }
+        }
+
+        if let Some(parent) = ancestors.first() {
+            enriched |= insert_missing(event, "process.parent.pid", serde_json::json!(parent.pid));
+            if let Some(name) = parent.display_name() {
+                enriched |= insert_missing(event, "process.parent.name", serde_json::json!(name));
+            }
+            if let Some(executable) = &parent.executable {
+                enriched |= insert_missing(event, "process.parent.executable", serde_json::json!(executable));
+            }
+            if let Some(command_line) = &parent.command_line {
+                enriched |= insert_missing(event, "process.parent.command_line", serde_json::json!(command_line));
+            }
+            if let Some(user) = &parent.user {
+                enriched |= insert_missing(event, "process.parent.user.name", serde_json::json!(user));
+            }
+        }
+
+        if let Some(grandparent) = ancestors.get(1) {
+            enriched |= insert_missing(event, "process.parent.parent.pid", serde_json::json!(grandparent.pid));
+            if let Some(name) = grandparent.display_name() {
+                enriched |= insert_missing(event, "process.parent.parent.name", serde_json::json!(name));
+            }
+            if let Some(command_line) = &grandparent.command_line {
+                enriched |= insert_missing(event, "process.parent.parent.command_line", serde_json::json!(command_line));
+            }
+        }
+
+        if !ancestors.is_empty() {
+            let chain: Vec<serde_json::Value> = ancestors
+                .iter()
+                .map(|record| {
+                    serde_json::json!({
+                        "pid": record.pid,
+                        "name": record.display_name(),
+                        "executable": record.executable,
+                    })
+                })
+                .collect();
+            enriched |= insert_missing(event, "process.ancestors", serde_json::Value::Array(chain));
+        }
+
+        let mut stats = self.stats.write();
+        if enriched {
+            stats.events_enriched += 1;
+        } else if ancestors.is_empty() {
+            stats.cache_misses += 1;
+        }
+
+        enriched
+    }
+
+    /// Ancestry of a process, starting with the process itself
+    pub fn lineage(&self, pid: u32, max_depth: usize) -> Vec<ProcessRecord> {
+        Self::chain(&self.entries.read(), pid, max_depth, &[])
+    }
+
+    /// Records from `pid` upwards, stopping at the first unknown PID or at a PID already in the
+    /// chain, since reuse can introduce cycles
+    fn chain(entries: &ProcessEntries, pid: u32, max_depth: usize, seen: &[u32]) -> Vec<ProcessRecord> {
+        let mut chain: Vec<ProcessRecord> = Vec::new();
+        let mut current = Some(pid);
+
+        while let Some(pid) = current {
+            if chain.len() >= max_depth || seen.contains(&pid) || chain.iter().any(|r| r.pid == pid) {
+                break;
+            }
+            let Some(record) = entries.get(pid) else {
+                break;
+            };
+            current = record.ppid;
+            chain.push(record.clone());
+        }
+
+        chain
+    }
+
+    /// Reload every process from the operating system's process table. Runs on the blocking pool,
+    /// since a full refresh reads a few files per process on Linux.
+    pub fn refresh_from_system(&self) -> usize {
+        let mut table = self.table.lock();
+        let table = &mut *table;
+        table.system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessTable::refresh_kind());
+        table.users.refresh_list();
+        table.refreshed = true;
+        table.missing.clear();
+
+        let now = Instant::now();
+        let mut entries = self.entries.write();
+        let mut refreshed = 0;
+        for process in table.system.processes().values() {
+            // Linux lists threads alongside processes
+            if process.thread_kind().is_some() {
+                continue;
+            }
+            Self::upsert(&mut entries, ProcessRecord::from_process(process, &table.users, now));
+            refreshed += 1;
+        }
+        let over_capacity = entries.len().saturating_sub(self.config.max_entries);
+        entries.evict_oldest(over_capacity);
+        let cached = entries.len();
+        drop(entries);
+
+        debug!("🌳 Refreshed {} processes from the process table", refreshed);
+        let mut stats = self.stats.write();
+        stats.system_refreshes += 1;
+        stats.evictions += over_capacity as u64;
+        stats.cached_processes = cached;
+        refreshed
+    }
+
+    /// Look up `pid` and any ancestors the cache doesn't know in the process table, so processes
+    /// started since the last refresh are attributed too
+    fn resolve(&self, pid: u32) {
+        let mut current = Some(pid);
+        for _ in 0..=self.config.max_depth {
+            let Some(pid) = current.filter(|pid| *pid != 0) else {
+                return;
+            };
+            let known_parent = self.entries.read().get(pid).map(|record| record.ppid);
+            current = match known_parent {
+                Some(Some(ppid)) => Some(ppid),
+                _ => self.lookup(pid),
+            };
+        }
+    }
+
+    /// Parent of `pid` after reading it from the process table; None when it has exited, or
+    /// before the first full refresh
+    fn lookup(&self, pid: u32) -> Option<u32> {
+        let mut table = self.table.lock();
+        let table = &mut *table;
+        if !table.refreshed || table.missing.contains_key(&pid) {
+            return None;
+        }
+
+        let sys_pid = Pid::from_u32(pid);
+        table.system.refresh_processes_specifics(ProcessesToUpdate::Some(&[sys_pid]), true, ProcessTable::refresh_kind());
+        self.stats.write().system_lookups += 1;
+        let now = Instant::now();
+        let Some(process) = table.system.process(sys_pid) else {
+            table.missing.insert(pid, now);
+            return None;
+        };
+        let record = ProcessRecord::from_process(process, &table.users, now);
+        let ppid = record.ppid;
+        Self::upsert(&mut self.entries.write(), record);
+        ppid
+    }
+
+    fn upsert(entries: &mut ProcessEntries, record: ProcessRecord) {
+        match entries.remove(record.pid) {
+            Some(mut existing) if !existing.reused_by(&record) => {
+                existing.update_from(record);
+                entries.insert(existing);
+            }
+            _ => entries.insert(record),
+        }
+    }
+
+    /// Drop records that have not been seen within the TTL
+    pub fn purge_expired(&self) -> usize {
+        let ttl = Duration::from_secs(self.config.ttl_seconds);
+        let mut entries = self.entries.write();
+        let removed = entries.purge_expired(ttl);
+        let cached = entries.len();
+        drop(entries);
+
+        if removed > 0 {
+            debug!("🌳 Purged {} expired process lineage records", removed);
+        }
+
+        let mut stats = self.stats.write();
+        stats.expired += removed as u64;
+        stats.cached_processes = cached;
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.read().is_empty()
+    }
+
+    pub fn get_stats(&self) -> ProcessLineageStats {
+        self.stats.read().clone()
+    }
+
+    pub fn config(&self) -> &ProcessLineageConfig {
+        &self.config
+    }
+}
+
+fn field_u32(event: &ParsedEvent, names: &[&str]) -> Option<u32> {
+    names.iter().find_map(|name| match event.fields.get(*name)? {
+        serde_json::Value::Number(n) => n.as_u64().and_then(|v| u32::try_from(v).ok()),
+        serde_json::Value::String(s) => {
+            let s = s.trim();
+            // Sysmon renders some PIDs in hex
+            match s.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => s.parse().ok(),
+            }
+        }
+        _ => None,
+    })
+}
+
+fn field_string(event: &ParsedEvent, names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        event
+            .fields
+            .get(*name)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+    })
+}
+
+fn field_time(event: &ParsedEvent, names: &[&str]) -> Option<DateTime<Utc>> {
+    names.iter().find_map(|name| {
+        let value = event.fields.get(*name)?.as_str()?;
+        DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&Utc))
+    })
+}
+
+fn merge(slot: &mut Option<String>, value: Option<String>) {
+    if value.is_some() {
+        *slot = value;
+    }
+}
+
+fn insert_missing(event: &mut ParsedEvent, key: &str, value: serde_json::Value) -> bool {
+    if event.fields.contains_key(key) {
+        return false;
+    }
+    event.fields.insert(key.to_string(), value);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process_event(fields: &[(&str, serde_json::Value)]) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "sysmon".to_string(),
+            level: None,
+            message: "process event".to_string(),
+            fields: fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+            raw_data: "".into(),
+            parser_name: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_enriches_parent_and_grandparent() {
+        let cache = ProcessLineageCache::new(ProcessLineageConfig::default());
+
+        cache.observe(&process_event(&[
+            ("process.pid", serde_json::json!(100)),
+            ("process.parent.pid", serde_json::json!(1)),
+            ("process.name", serde_json::json!("explorer.exe")),
+        ]));
+        cache.observe(&process_event(&[
+            ("process.pid", serde_json::json!(200)),
+            ("process.parent.pid", serde_json::json!(100)),
+            ("process.executable", serde_json::json!("C:\\Windows\\System32\\cmd.exe")),
+            ("process.command_line", serde_json::json!("cmd.exe /c whoami")),
+        ]));
+
+        // Child event only carries PIDs
+        let mut event = process_event(&[
+            ("process.pid", serde_json::json!(300)),
+            ("process.parent.pid", serde_json::json!(200)),
+        ]);
+        assert!(cache.process_event(&mut event));
+
+        assert_eq!(event.fields["process.parent.name"], "cmd.exe");
+        assert_eq!(event.fields["process.parent.command_line"], "cmd.exe /c whoami");
+        assert_eq!(event.fields["process.parent.parent.name"], "explorer.exe");
+        assert_eq!(cache.lineage(300, 8).len(), 3);
+    }
+
+    #[test]
+    fn test_sysmon_parent_fields_populate_cache() {
+        let cache = ProcessLineageCache::new(ProcessLineageConfig::default());
+
+        cache.observe(&process_event(&[
+            ("ProcessId", serde_json::json!("4242")),
+            ("ParentProcessId", serde_json::json!("0x10")),
+            ("ParentImage", serde_json::json!("C:\\Windows\\explorer.exe")),
+            ("ParentCommandLine", serde_json::json!("explorer.exe")),
+        ]));
+
+        let parent = cache.lineage(16, 1);
+        assert_eq!(parent.len(), 1);
+        assert_eq!(parent[0].display_name().as_deref(), Some("explorer.exe"));
+    }
+
+    #[test]
+    fn test_capacity_and_expiry() {
+        let cache = ProcessLineageCache::new(ProcessLineageConfig {
+            max_entries: 2,
+            ttl_seconds: 0,
+            ..Default::default()
+        });
+
+        for pid in 1..=3u32 {
+            cache.observe(&process_event(&[("process.pid", serde_json::json!(pid))]));
+        }
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get_stats().evictions, 1);
+
+        assert_eq!(cache.purge_expired(), 2);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_eviction_keeps_recently_seen_processes() {
+        let cache = ProcessLineageCache::new(ProcessLineageConfig {
+            max_entries: 2,
+            ..Default::default()
+        });
+
+        cache.observe(&process_event(&[("process.pid", serde_json::json!(1))]));
+        cache.observe(&process_event(&[("process.pid", serde_json::json!(2))]));
+        // Seeing PID 1 again makes PID 2 the oldest
+        cache.observe(&process_event(&[("process.pid", serde_json::json!(1))]));
+        cache.observe(&process_event(&[("process.pid", serde_json::json!(3))]));
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.lineage(1, 1).len(), 1);
+        assert!(cache.lineage(2, 1).is_empty());
+        assert_eq!(cache.lineage(3, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_start_time_reuse_and_ancestor_chain() {
+        let cache = ProcessLineageCache::new(ProcessLineageConfig::default());
+
+        cache.observe(&process_event(&[
+            ("process.pid", serde_json::json!(10)),
+            ("process.executable", serde_json::json!("/usr/sbin/sshd")),
+            ("process.start", serde_json::json!("2024-03-09T10:00:00Z")),
+        ]));
+        // Same PID with a later start time is a different process
+        cache.observe(&process_event(&[
+            ("process.pid", serde_json::json!(10)),
+            ("process.parent.pid", serde_json::json!(1)),
+            ("process.name", serde_json::json!("bash")),
+            ("process.user.name", serde_json::json!("alice")),
+            ("process.start", serde_json::json!("2024-03-09T11:00:00Z")),
+        ]));
+        cache.observe(&process_event(&[("process.pid", serde_json::json!(1)), ("process.name", serde_json::json!("systemd"))]));
+        cache.observe(&process_event(&[("process.pid", serde_json::json!(20)), ("process.parent.pid", serde_json::json!(10))]));
+
+        let reused = &cache.lineage(10, 1)[0];
+        assert_eq!(reused.executable, None);
+        assert_eq!(reused.display_name().as_deref(), Some("bash"));
+
+        let mut event = process_event(&[("process.pid", serde_json::json!(30)), ("process.parent.pid", serde_json::json!(20))]);
+        assert!(cache.enrich(&mut event));
+        let ancestors: Vec<u64> = event.fields["process.ancestors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|ancestor| ancestor["pid"].as_u64().unwrap())
+            .collect();
+        assert_eq!(ancestors, vec![20, 10, 1]);
+        assert_eq!(event.fields["process.parent.parent.name"], "bash");
+
+        let mut event = process_event(&[("process.pid", serde_json::json!(10))]);
+        assert!(cache.enrich(&mut event));
+        assert_eq!(event.fields["process.user.name"], "alice");
+        assert_eq!(event.fields["process.start"], "2024-03-09T11:00:00+00:00");
+        assert_eq!(event.fields["process.parent.name"], "systemd");
+    }
+
+    #[test]
+    fn test_refresh_from_system_attributes_pid_only_events() {
+        let cache = ProcessLineageCache::new(ProcessLineageConfig::default());
+        assert!(cache.refresh_from_system() > 0);
+
+        let own = cache.lineage(std::process::id(), 1);
+        assert_eq!(own.len(), 1);
+        assert!(own[0].start_time.is_some());
+        assert!(own[0].executable.is_some());
+
+        let mut event = process_event(&[("process.pid", serde_json::json!(std::process::id()))]);
+        assert!(cache.process_event(&mut event));
+        assert!(event.fields.contains_key("process.name"));
+        assert!(event.fields.contains_key("process.parent.pid"));
+
+        // Started after the refresh, so found by looking the PID up
+        #[cfg(unix)]
+        {
+            let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+            let mut event = process_event(&[("process.pid", serde_json::json!(child.id()))]);
+            cache.process_event(&mut event);
+            let _ = child.kill();
+            let _ = child.wait();
+            assert_eq!(event.fields["process.parent.pid"], serde_json::json!(std::process::id()));
+            assert_eq!(event.fields["process.name"], "sleep");
+        }
+    }
+}
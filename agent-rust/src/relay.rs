@@ -0,0 +1,1020 @@
+// Agent-to-agent relay for segmented networks
+// A relay agent accepts authenticated, encrypted batches from peer agents that have no direct egress
+// and ships their envelopes upstream byte-for-byte, adding only relay metadata alongside them
+
+use crate::compression::CompressionAlgorithm;
+use crate::errors::TransportError;
+use crate::payload_format::PayloadFormat;
+use crate::transport::SecureTransport;
+use base64::{engine::general_purpose, Engine as _};
+use ring::aead;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, Semaphore};
+use tracing::{debug, error, info, warn};
+
+/// Wire protocol version carried in every frame
+const PROTOCOL_VERSION: u8 = 2;
+/// Largest hello frame read from a peer that has not authenticated yet
+const MAX_HELLO_BYTES: usize = 1024;
+/// Longest peer id that still fits in a hello frame
+const MAX_PEER_ID_BYTES: usize = 256;
+/// Relay-chosen session id every frame on a connection is bound to
+const SESSION_LEN: usize = 16;
+/// Initial buffer for a frame body; it grows only as bytes actually arrive
+const READ_CHUNK_BYTES: usize = 64 * 1024;
+/// Quota accounting window
+const QUOTA_WINDOW: Duration = Duration::from_secs(60);
+/// Largest acknowledgement frame accepted from a relay
+const MAX_ACK_BYTES: usize = 64 * 1024;
+
+/// Relay configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayConfig {
+    /// Accept batches from the peers listed below and forward them upstream
+    pub enabled: bool,
+    pub listen_address: String,
+    /// Identity recorded in the relay path (defaults to the agent name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relay_id: Option<String>,
+    /// Relays an envelope may traverse before it is rejected as a probable loop
+    pub max_hops: usize,
+    /// Largest encrypted frame accepted from a peer once its hello has authenticated
+    pub max_frame_bytes: usize,
+    pub max_connections: usize,
+    /// Allowed difference between a peer's clock and ours when checking frame freshness
+    pub max_clock_skew_seconds: u64,
+    /// Time a new connection has to complete its hello
+    pub handshake_timeout_seconds: u64,
+    /// Time allowed to receive the rest of a frame once its length prefix has arrived
+    pub read_timeout_seconds: u64,
+    /// Connections with no new frame for this long are closed
+    pub idle_timeout_seconds: u64,
+    pub peers: Vec<RelayPeerConfig>,
+    /// Send this agent's batches through a relay instead of directly to the server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<RelayUpstreamConfig>,
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_address: "0.0.0.0:5045".to_string(),
+            relay_id: None,
+            max_hops: 3,
+            max_frame_bytes: 16 * 1024 * 1024,
+            max_connections: 256,
+            max_clock_skew_seconds: 300,
+            handshake_timeout_seconds: 10,
+            read_timeout_seconds: 30,
+            idle_timeout_seconds: 300,
+            peers: Vec::new(),
+            upstream: None,
+        }
+    }
+}
+
+/// A peer allowed to relay through this agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayPeerConfig {
+    pub id: String,
+    /// Base64 encoded 32-byte ChaCha20-Poly1305 key shared with the peer
+    pub key: String,
+    #[serde(default = "default_max_events_per_minute")]
+    pub max_events_per_minute: u64,
+    #[serde(default = "default_max_bytes_per_minute")]
+    pub max_bytes_per_minute: u64,
+}
+
+fn default_max_events_per_minute() -> u64 {
+    60_000
+}
+
+fn default_max_bytes_per_minute() -> u64 {
+    64 * 1024 * 1024
+}
+
+/// Relay this agent ships its own batches through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayUpstreamConfig {
+    /// host:port of the relay agent
+    pub address: String,
+    /// Identity this agent presents to the relay
+    pub peer_id: String,
+    /// Base64 encoded 32-byte key matching the relay's entry for `peer_id`
+    pub key: String,
+    #[serde(default = "default_upstream_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+fn default_upstream_timeout_seconds() -> u64 {
+    30
+}
+
+impl RelayConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.enabled {
+            if self.listen_address.parse::<std::net::SocketAddr>().is_err() {
+                errors.push(format!("Invalid relay listen address: {}", self.listen_address));
+            }
+            if self.max_hops == 0 {
+                errors.push("max_hops must be greater than 0".to_string());
+            }
+            if self.max_frame_bytes == 0 || self.max_connections == 0 {
+                errors.push("max_frame_bytes and max_connections must be greater than 0".to_string());
+            }
+            if self.handshake_timeout_seconds == 0 || self.read_timeout_seconds == 0 || self.idle_timeout_seconds == 0 {
+                errors.push("Relay handshake, read and idle timeouts must be greater than 0".to_string());
+            }
+            if self.peers.is_empty() {
+                errors.push("Relay is enabled but no peers are configured".to_string());
+            }
+            let mut seen = std::collections::HashSet::new();
+            for peer in &self.peers {
+                if peer.id.trim().is_empty() {
+                    errors.push("Relay peer ids cannot be empty".to_string());
+                } else if peer.id.len() > MAX_PEER_ID_BYTES {
+                    errors.push(format!("Relay peer id '{}' is longer than {} bytes", peer.id, MAX_PEER_ID_BYTES));
+                } else if !seen.insert(peer.id.as_str()) {
+                    errors.push(format!("Duplicate relay peer '{}'", peer.id));
+                }
+                if let Err(e) = decode_key(&peer.key) {
+                    errors.push(format!("Relay peer '{}': {}", peer.id, e));
+                }
+                if peer.max_events_per_minute == 0 || peer.max_bytes_per_minute == 0 {
+                    errors.push(format!("Relay peer '{}' quotas must be greater than 0", peer.id));
+                }
+            }
+        }
+
+        if let Some(upstream) = &self.upstream {
+            if upstream.address.trim().is_empty() {
+                errors.push("Relay upstream address cannot be empty".to_string());
+            }
+            if self.enabled && upstream.address == self.listen_address {
+                errors.push("Relay upstream cannot be this agent's own listener".to_string());
+            }
+            if upstream.peer_id.trim().is_empty() {
+                errors.push("Relay upstream peer_id cannot be empty".to_string());
+            } else if upstream.peer_id.len() > MAX_PEER_ID_BYTES {
+                errors.push(format!("Relay upstream peer_id is longer than {} bytes", MAX_PEER_ID_BYTES));
+            }
+            if let Err(e) = decode_key(&upstream.key) {
+                errors.push(format!("Relay upstream: {}", e));
+            }
+        }
+
+        errors
+    }
+}
+
+/// Outcome reported back to the sending peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RelayStatus {
+    Accepted,
+    QuotaExceeded,
+    LoopDetected,
+    Stale,
+    UpstreamFailed,
+    /// The frame names a content encoding the relay cannot pass on
+    Unsupported,
+}
+
+/// First frame on a connection; the only one read before the peer has authenticated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RelayHello {
+    sent_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Relay's answer to a hello, carrying the session later frames must be bound to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RelayWelcome {
+    status: RelayStatus,
+    message: String,
+    session: [u8; SESSION_LEN],
+}
+
+/// Decrypted content of a peer frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RelayFrame {
+    /// Position of the frame within its session, starting at 0
+    sequence: u64,
+    sent_at: chrono::DateTime<chrono::Utc>,
+    /// Relays the envelope has already passed through, nearest to the origin first
+    relay_path: Vec<String>,
+    event_count: u32,
+    envelope: Vec<u8>,
+    format: PayloadFormat,
+    /// Content-Encoding of the envelope, absent when it is not compressed
+    content_encoding: Option<String>,
+    /// Headers the origin agent posts with the envelope, such as the batch digest and signature
+    headers: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RelayAck {
+    status: RelayStatus,
+    message: String,
+    retry_after_seconds: Option<u32>,
+}
+
+/// A peer envelope on its way upstream
+#[derive(Debug, Clone)]
+pub struct RelayedEnvelope {
+    /// Peer the envelope was received from
+    pub peer_id: String,
+    /// Relays traversed so far, including this one
+    pub relay_path: Vec<String>,
+    pub event_count: u32,
+    pub received_at: chrono::DateTime<chrono::Utc>,
+    /// Payload exactly as the origin agent prepared it
+    pub payload: Vec<u8>,
+    pub format: PayloadFormat,
+    pub encoding: CompressionAlgorithm,
+    /// Headers the origin agent would have posted the payload with
+    pub origin_headers: Vec<(String, String)>,
+}
+
+impl RelayedEnvelope {
+    /// Origin headers plus relay metadata sent alongside the untouched payload when posting to the server
+    pub fn headers(&self) -> Vec<(String, String)> {
+        // Peers may only pass on SecureWatch headers, and never ones claiming to describe the relay hop
+        let mut headers: Vec<(String, String)> = self.origin_headers
+            .iter()
+            .filter(|(name, _)| {
+                let name = name.to_ascii_lowercase();
+                name.starts_with("x-securewatch-") && !name.starts_with("x-securewatch-relay-")
+            })
+            .cloned()
+            .collect();
+        headers.push(("X-SecureWatch-Relay-Peer".to_string(), self.peer_id.clone()));
+        headers.push(("X-SecureWatch-Relay-Path".to_string(), self.relay_path.join(",")));
+        headers.push(("X-SecureWatch-Relay-Received-At".to_string(), self.received_at.to_rfc3339()));
+        headers
+    }
+}
+
+fn decode_key(key: &str) -> Result<aead::LessSafeKey, String> {
+    let bytes = general_purpose::STANDARD
+        .decode(key.trim())
+        .map_err(|e| format!("key is not valid base64: {}", e))?;
+    let unbound = aead::UnboundKey::new(&aead::CHACHA20_POLY1305, &bytes)
+        .map_err(|_| format!("key must be {} bytes, got {}", aead::CHACHA20_POLY1305.key_len(), bytes.len()))?;
+    Ok(aead::LessSafeKey::new(unbound))
+}
+
+fn seal(key: &aead::LessSafeKey, aad: &[u8], mut data: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    let mut nonce = [0u8; aead::NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| std::io::Error::other("failed to generate relay nonce"))?;
+    key.seal_in_place_append_tag(aead::Nonce::assume_unique_for_key(nonce), aead::Aad::from(aad), &mut data)
+        .map_err(|_| std::io::Error::other("failed to encrypt relay frame"))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&data);
+    Ok(sealed)
+}
+
+fn open(key: &aead::LessSafeKey, aad: &[u8], sealed: &[u8]) -> std::io::Result<Vec<u8>> {
+    let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "relay frame failed authentication");
+    if sealed.len() < aead::NONCE_LEN {
+        return Err(invalid());
+    }
+    let (nonce, ciphertext) = sealed.split_at(aead::NONCE_LEN);
+    let nonce = aead::Nonce::try_assume_unique_for_key(nonce).map_err(|_| invalid())?;
+    let mut data = ciphertext.to_vec();
+    let plaintext_len = key.open_in_place(nonce, aead::Aad::from(aad), &mut data).map_err(|_| invalid())?.len();
+    data.truncate(plaintext_len);
+    Ok(data)
+}
+
+/// Hellos bind the protocol version and claimed identity
+fn hello_aad(peer_id: &str) -> Vec<u8> {
+    let mut aad = b"hello".to_vec();
+    aad.push(PROTOCOL_VERSION);
+    aad.extend_from_slice(peer_id.as_bytes());
+    aad
+}
+
+fn welcome_aad(peer_id: &str) -> Vec<u8> {
+    let mut aad = b"welcome".to_vec();
+    aad.extend_from_slice(&hello_aad(peer_id));
+    aad
+}
+
+/// Peer frames are also bound to the session, so frames captured on one connection fail on any other
+fn frame_aad(peer_id: &str, session: &[u8; SESSION_LEN]) -> Vec<u8> {
+    let mut aad = vec![PROTOCOL_VERSION];
+    aad.extend_from_slice(peer_id.as_bytes());
+    aad.extend_from_slice(session);
+    aad
+}
+
+fn ack_aad(peer_id: &str, session: &[u8; SESSION_LEN]) -> Vec<u8> {
+    let mut aad = b"ack".to_vec();
+    aad.extend_from_slice(&frame_aad(peer_id, session));
+    aad
+}
+
+/// Hello frames name the peer in clear so the relay knows which key to open them with
+fn hello_body(key: &aead::LessSafeKey, peer_id: &str) -> std::io::Result<Vec<u8>> {
+    let hello = RelayHello { sent_at: chrono::Utc::now() };
+    let plaintext = bincode::serialize(&hello).map_err(std::io::Error::other)?;
+    let mut body = vec![PROTOCOL_VERSION];
+    body.extend_from_slice(&(peer_id.len() as u16).to_be_bytes());
+    body.extend_from_slice(peer_id.as_bytes());
+    body.extend_from_slice(&seal(key, &hello_aad(peer_id), plaintext)?);
+    Ok(body)
+}
+
+/// Split a hello into the claimed peer id and its sealed part
+fn parse_hello(body: &[u8]) -> std::io::Result<(String, &[u8])> {
+    let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string());
+    if body.len() < 3 || body[0] != PROTOCOL_VERSION {
+        return Err(invalid("unsupported relay protocol version"));
+    }
+    let id_len = u16::from_be_bytes([body[1], body[2]]) as usize;
+    let peer_id = body
+        .get(3..3 + id_len)
+        .and_then(|id| std::str::from_utf8(id).ok())
+        .ok_or_else(|| invalid("malformed relay hello"))?
+        .to_string();
+    Ok((peer_id, &body[3 + id_len..]))
+}
+
+fn seal_frame(key: &aead::LessSafeKey, peer_id: &str, session: &[u8; SESSION_LEN], frame: &RelayFrame) -> std::io::Result<Vec<u8>> {
+    let plaintext = bincode::serialize(frame).map_err(std::io::Error::other)?;
+    seal(key, &frame_aad(peer_id, session), plaintext)
+}
+
+/// Authenticate a frame and check it is the next one in its session, rejecting replays and reordering
+fn open_frame(
+    key: &aead::LessSafeKey,
+    peer_id: &str,
+    session: &[u8; SESSION_LEN],
+    expected_sequence: u64,
+    body: &[u8],
+) -> std::io::Result<RelayFrame> {
+    let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+    let plaintext = open(key, &frame_aad(peer_id, session), body)?;
+    let frame: RelayFrame = bincode::deserialize(&plaintext).map_err(|_| invalid("undecodable relay frame".to_string()))?;
+    if frame.sequence != expected_sequence {
+        return Err(invalid(format!("relay frame sequence {} where {} was expected", frame.sequence, expected_sequence)));
+    }
+    Ok(frame)
+}
+
+async fn write_frame(stream: &mut TcpStream, body: &[u8]) -> std::io::Result<()> {
+    stream.write_u32(body.len() as u32).await?;
+    stream.write_all(body).await?;
+    stream.flush().await
+}
+
+fn timed_out(what: &str, after: Duration) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::TimedOut, format!("no relay {} within {}s", what, after.as_secs()))
+}
+
+/// Read one length-prefixed frame; `None` on a clean close between frames
+///
+/// `idle` bounds the wait for the length prefix and `read` the time to receive the body after it.
+async fn read_frame<R: AsyncRead + Unpin>(
+    stream: &mut R,
+    max_bytes: usize,
+    idle: Duration,
+    read: Duration,
+) -> std::io::Result<Option<Vec<u8>>> {
+    let len = match tokio::time::timeout(idle, stream.read_u32()).await {
+        Ok(Ok(len)) => len as usize,
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Ok(Err(e)) => return Err(e),
+        Err(_) => return Err(timed_out("frame", idle)),
+    };
+    if len > max_bytes {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("relay frame of {} bytes exceeds limit of {}", len, max_bytes),
+        ));
+    }
+    let mut body = Vec::with_capacity(len.min(READ_CHUNK_BYTES));
+    tokio::time::timeout(read, (&mut *stream).take(len as u64).read_to_end(&mut body))
+        .await
+        .map_err(|_| timed_out("frame body", read))??;
+    if body.len() < len {
+        return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "relay frame truncated"));
+    }
+    Ok(Some(body))
+}
+
+/// Per-peer relay counters
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RelayPeerStats {
+    pub peer_id: String,
+    pub batches_forwarded: u64,
+    pub events_forwarded: u64,
+    pub bytes_forwarded: u64,
+    pub quota_rejections: u64,
+    pub loop_rejections: u64,
+    pub upstream_failures: u64,
+    pub last_seen: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RelayStats {
+    pub relay_id: String,
+    pub active_connections: usize,
+    /// Frames that failed authentication or named an unknown peer
+    pub rejected_frames: u64,
+    pub peers: Vec<RelayPeerStats>,
+}
+
+struct PeerState {
+    config: RelayPeerConfig,
+    key: aead::LessSafeKey,
+    window_start: Instant,
+    window_events: u64,
+    window_bytes: u64,
+    stats: RelayPeerStats,
+}
+
+impl PeerState {
+    /// Charge a batch against the current window, returning seconds until it resets when over quota
+    fn charge(&mut self, events: u64, bytes: u64) -> Result<(), u32> {
+        let now = Instant::now();
+        if now.duration_since(self.window_start) >= QUOTA_WINDOW {
+            self.window_start = now;
+            self.window_events = 0;
+            self.window_bytes = 0;
+        }
+        if self.window_events + events > self.config.max_events_per_minute
+            || self.window_bytes + bytes > self.config.max_bytes_per_minute
+        {
+            let remaining = QUOTA_WINDOW.saturating_sub(now.duration_since(self.window_start));
+            return Err(remaining.as_secs().max(1) as u32);
+        }
+        self.window_events += events;
+        self.window_bytes += bytes;
+        Ok(())
+    }
+}
+
+/// Listener accepting peer batches and forwarding them upstream
+pub struct RelayServer {
+    config: RelayConfig,
+    relay_id: String,
+    transport: Arc<SecureTransport>,
+    peers: parking_lot::Mutex<HashMap<String, PeerState>>,
+    connections: Arc<Semaphore>,
+    rejected_frames: std::sync::atomic::AtomicU64,
+}
+
+impl RelayServer {
+    pub fn new(config: RelayConfig, default_relay_id: &str, transport: Arc<SecureTransport>) -> Result<Self, TransportError> {
+        let mut peers = HashMap::new();
+        for peer in &config.peers {
+            let key = decode_key(&peer.key)
+                .map_err(|e| TransportError::configuration_invalid(&format!("relay peer '{}': {}", peer.id, e)))?;
+            peers.insert(peer.id.clone(), PeerState {
+                config: peer.clone(),
+                key,
+                window_start: Instant::now(),
+                window_events: 0,
+                window_bytes: 0,
+                stats: RelayPeerStats { peer_id: peer.id.clone(), ..Default::default() },
+            });
+        }
+
+        Ok(Self {
+            relay_id: config.relay_id.clone().unwrap_or_else(|| default_relay_id.to_string()),
+            connections: Arc::new(Semaphore::new(config.max_connections)),
+            config,
+            transport,
+            peers: parking_lot::Mutex::new(peers),
+            rejected_frames: std::sync::atomic::AtomicU64::new(0),
+        })
+    }
+
+    pub fn relay_id(&self) -> &str {
+        &self.relay_id
+    }
+
+    pub fn config(&self) -> &RelayConfig {
+        &self.config
+    }
+
+    /// Accept peer connections until shutdown
+    pub async fn run(self: Arc<Self>, mut shutdown_receiver: broadcast::Receiver<()>) -> std::io::Result<()> {
+        let listener = TcpListener::bind(&self.config.listen_address).await?;
+        info!("🛰️ Relay '{}' listening on {} for {} peers", self.relay_id, self.config.listen_address, self.config.peers.len());
+
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, addr) = match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            warn!("⚠️ Relay accept failed: {}", e);
+                            continue;
+                        }
+                    };
+                    let Ok(permit) = self.connections.clone().try_acquire_owned() else {
+                        warn!("⚠️ Relay connection limit ({}) reached, dropping {}", self.config.max_connections, addr);
+                        continue;
+                    };
+                    let server = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = server.handle_connection(stream).await {
+                            debug!("Relay connection from {} closed: {}", addr, e);
+                        }
+                        drop(permit);
+                    });
+                }
+                _ = shutdown_receiver.recv() => {
+                    info!("🛑 Relay listener shutting down");
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn handle_connection(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let handshake = Duration::from_secs(self.config.handshake_timeout_seconds);
+        let Some(hello) = read_frame(&mut stream, MAX_HELLO_BYTES, handshake, handshake).await? else {
+            return Ok(());
+        };
+        let (peer_id, key, welcome) = match self.accept_hello(&hello) {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                // Unauthenticated peers get no response at all
+                self.rejected_frames.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return Err(e);
+            }
+        };
+        let plaintext = bincode::serialize(&welcome).map_err(std::io::Error::other)?;
+        write_frame(&mut stream, &seal(&key, &welcome_aad(&peer_id), plaintext)?).await?;
+        if welcome.status != RelayStatus::Accepted {
+            return Ok(());
+        }
+
+        let session = welcome.session;
+        let idle = Duration::from_secs(self.config.idle_timeout_seconds);
+        let read = Duration::from_secs(self.config.read_timeout_seconds);
+        let mut sequence = 0u64;
+        while let Some(body) = read_frame(&mut stream, self.config.max_frame_bytes, idle, read).await? {
+            let ack = match open_frame(&key, &peer_id, &session, sequence, &body) {
+                Ok(frame) => self.accept_frame(&peer_id, frame),
+                Err(e) => {
+                    self.rejected_frames.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    return Err(e);
+                }
+            };
+            sequence += 1;
+            let ack = match ack {
+                Ok(envelope) => self.forward(envelope).await,
+                Err(ack) => ack,
+            };
+
+            let plaintext = bincode::serialize(&ack).map_err(std::io::Error::other)?;
+            write_frame(&mut stream, &seal(&key, &ack_aad(&peer_id, &session), plaintext)?).await?;
+        }
+        Ok(())
+    }
+
+    /// Authenticate a hello and open a session for the connection, or say why none was opened
+    fn accept_hello(&self, body: &[u8]) -> std::io::Result<(String, aead::LessSafeKey, RelayWelcome)> {
+        let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string());
+        let (peer_id, sealed) = parse_hello(body)?;
+
+        let mut peers = self.peers.lock();
+        let peer = peers.get_mut(&peer_id).ok_or_else(|| invalid("unknown relay peer"))?;
+        let plaintext = open(&peer.key, &hello_aad(&peer_id), sealed)?;
+        let hello: RelayHello = bincode::deserialize(&plaintext).map_err(|_| invalid("undecodable relay hello"))?;
+
+        let now = chrono::Utc::now();
+        peer.stats.last_seen = Some(now);
+
+        let mut welcome = RelayWelcome { status: RelayStatus::Accepted, message: String::new(), session: [0u8; SESSION_LEN] };
+        let skew = (now - hello.sent_at).num_seconds().unsigned_abs();
+        if skew > self.config.max_clock_skew_seconds {
+            welcome.status = RelayStatus::Stale;
+            welcome.message = format!("hello is {}s away from relay clock", skew);
+        } else {
+            SystemRandom::new()
+                .fill(&mut welcome.session)
+                .map_err(|_| std::io::Error::other("failed to generate relay session"))?;
+        }
+        Ok((peer_id, peer.key.clone(), welcome))
+    }
+
+    /// Check an authenticated frame; the result is the envelope to forward or an immediate rejection
+    fn accept_frame(&self, peer_id: &str, frame: RelayFrame) -> Result<RelayedEnvelope, RelayAck> {
+        let mut peers = self.peers.lock();
+        let Some(peer) = peers.get_mut(peer_id) else {
+            return Err(reject(RelayStatus::UpstreamFailed, "peer is no longer configured".to_string(), None));
+        };
+
+        let now = chrono::Utc::now();
+        peer.stats.last_seen = Some(now);
+
+        let skew = (now - frame.sent_at).num_seconds().unsigned_abs();
+        if skew > self.config.max_clock_skew_seconds {
+            return Err(reject(RelayStatus::Stale, format!("frame is {}s away from relay clock", skew), None));
+        }
+        if peer_id == self.relay_id || frame.relay_path.contains(&self.relay_id) {
+            peer.stats.loop_rejections += 1;
+            warn!("⚠️ Relay loop detected for peer '{}' via {:?}", peer_id, frame.relay_path);
+            return Err(reject(RelayStatus::LoopDetected, format!("'{}' is already on the relay path", self.relay_id), None));
+        }
+        if frame.relay_path.len() >= self.config.max_hops {
+            peer.stats.loop_rejections += 1;
+            return Err(reject(RelayStatus::LoopDetected, format!("relay path exceeds {} hops", self.config.max_hops), None));
+        }
+        let Some(encoding) = CompressionAlgorithm::from_content_encoding(frame.content_encoding.as_deref().unwrap_or("")) else {
+            return Err(reject(RelayStatus::Unsupported, format!("unsupported content encoding {:?}", frame.content_encoding), None));
+        };
+        if let Err(retry_after) = peer.charge(frame.event_count as u64, frame.envelope.len() as u64) {
+            peer.stats.quota_rejections += 1;
+            debug!("Relay peer '{}' over quota, retry in {}s", peer_id, retry_after);
+            return Err(reject(RelayStatus::QuotaExceeded, "peer quota exceeded".to_string(), Some(retry_after)));
+        }
+
+        let mut relay_path = frame.relay_path;
+        relay_path.push(self.relay_id.clone());
+        Ok(RelayedEnvelope {
+            peer_id: peer_id.to_string(),
+            relay_path,
+            event_count: frame.event_count,
+            received_at: now,
+            payload: frame.envelope,
+            format: frame.format,
+            encoding,
+            origin_headers: frame.headers,
+        })
+    }
+
+    async fn forward(&self, envelope: RelayedEnvelope) -> RelayAck {
+        let result = self.transport.forward_relayed(&envelope).await;
+
+        let mut peers = self.peers.lock();
+        let stats = peers.get_mut(&envelope.peer_id).map(|p| &mut p.stats);
+        match result {
+            Ok(()) => {
+                if let Some(stats) = stats {
+                    stats.batches_forwarded += 1;
+                    stats.events_forwarded += envelope.event_count as u64;
+                    stats.bytes_forwarded += envelope.payload.len() as u64;
+                }
+                RelayAck { status: RelayStatus::Accepted, message: String::new(), retry_after_seconds: None }
+            }
+            Err(e) => {
+                if let Some(stats) = stats {
+                    stats.upstream_failures += 1;
+                }
+                error!("❌ Failed to forward relayed batch from '{}': {}", envelope.peer_id, e);
+                reject(RelayStatus::UpstreamFailed, e.to_string(), None)
+            }
+        }
+    }
+
+    pub fn get_stats(&self) -> RelayStats {
+        let mut peers: Vec<RelayPeerStats> = self.peers.lock().values().map(|p| p.stats.clone()).collect();
+        peers.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        RelayStats {
+            relay_id: self.relay_id.clone(),
+            active_connections: self.config.max_connections - self.connections.available_permits(),
+            rejected_frames: self.rejected_frames.load(std::sync::atomic::Ordering::Relaxed),
+            peers,
+        }
+    }
+}
+
+fn reject(status: RelayStatus, message: String, retry_after_seconds: Option<u32>) -> RelayAck {
+    RelayAck { status, message, retry_after_seconds }
+}
+
+/// Open connection to a relay and the position within its session
+struct RelaySession {
+    stream: TcpStream,
+    session: [u8; SESSION_LEN],
+    next_sequence: u64,
+}
+
+/// Sends prepared payloads to a relay agent instead of the server
+pub struct RelayClient {
+    config: RelayUpstreamConfig,
+    key: aead::LessSafeKey,
+    connection: tokio::sync::Mutex<Option<RelaySession>>,
+}
+
+impl RelayClient {
+    pub fn new(config: RelayUpstreamConfig) -> Result<Self, TransportError> {
+        let key = decode_key(&config.key)
+            .map_err(|e| TransportError::configuration_invalid(&format!("relay upstream: {}", e)))?;
+        Ok(Self {
+            config,
+            key,
+            connection: tokio::sync::Mutex::new(None),
+        })
+    }
+
+    pub fn address(&self) -> &str {
+        &self.config.address
+    }
+
+    /// Hand a payload, with the format, encoding and headers it would be posted with, to the relay and wait until it
+    /// has been forwarded upstream
+    pub async fn send(
+        &self,
+        payload: &[u8],
+        format: PayloadFormat,
+        encoding: CompressionAlgorithm,
+        headers: &[(String, String)],
+        event_count: u32,
+        relay_path: &[String],
+    ) -> Result<(), TransportError> {
+        let frame = RelayFrame {
+            sequence: 0,
+            sent_at: chrono::Utc::now(),
+            relay_path: relay_path.to_vec(),
+            event_count,
+            envelope: payload.to_vec(),
+            format,
+            content_encoding: encoding.content_encoding().map(str::to_string),
+            headers: headers.to_vec(),
+        };
+
+        let timeout = Duration::from_secs(self.config.timeout_seconds);
+        let mut connection = self.connection.lock().await;
+        // The relay closes idle connections, so a reused one gets a single fresh retry before the send fails
+        let reused = connection.is_some();
+        let mut result = tokio::time::timeout(timeout, self.exchange(&mut connection, frame.clone())).await;
+        if reused && matches!(result, Ok(Err(_))) {
+            *connection = None;
+            result = tokio::time::timeout(timeout, self.exchange(&mut connection, frame)).await;
+        }
+        let ack = match result {
+            Ok(Ok(ack)) => ack,
+            Ok(Err(e)) => {
+                *connection = None;
+                return Err(TransportError::ConnectionFailed {
+                    endpoint: self.config.address.clone(),
+                    attempts: 1,
+                    last_error: e.to_string(),
+                    retry_after: None,
+                });
+            }
+            Err(_) => {
+                *connection = None;
+                return Err(TransportError::Timeout {
+                    operation: "relay_send".to_string(),
+                    duration_ms: timeout.as_millis() as u64,
+                    retryable: true,
+                });
+            }
+        };
+        match ack.status {
+            RelayStatus::Accepted => Ok(()),
+            RelayStatus::QuotaExceeded => Err(TransportError::RateLimitExceeded {
+                limit: 0,
+                window_seconds: QUOTA_WINDOW.as_secs() as u32,
+                retry_after_seconds: ack.retry_after_seconds,
+            }),
+            RelayStatus::LoopDetected | RelayStatus::Stale => Err(TransportError::ServerError {
+                status: 400,
+                message: format!("relay rejected batch: {}", ack.message),
+                headers: vec![],
+                body: None,
+                retryable: ack.status == RelayStatus::Stale,
+            }),
+            RelayStatus::UpstreamFailed => Err(TransportError::ServerError {
+                status: 502,
+                message: format!("relay could not forward batch: {}", ack.message),
+                headers: vec![],
+                body: None,
+                retryable: true,
+            }),
+            RelayStatus::Unsupported => Err(TransportError::ServerError {
+                status: 415,
+                message: format!("relay rejected batch: {}", ack.message),
+                headers: vec![],
+                body: None,
+                retryable: false,
+            }),
+        }
+    }
+
+    /// Say hello to the relay, returning its refusal if it did not open a session
+    async fn connect(&self) -> std::io::Result<Result<RelaySession, RelayAck>> {
+        let mut stream = TcpStream::connect(&self.config.address).await?;
+        write_frame(&mut stream, &hello_body(&self.key, &self.config.peer_id)?).await?;
+
+        let timeout = Duration::from_secs(self.config.timeout_seconds);
+        let response = read_frame(&mut stream, MAX_ACK_BYTES, timeout, timeout)
+            .await?
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "relay closed the connection"))?;
+        let plaintext = open(&self.key, &welcome_aad(&self.config.peer_id), &response)?;
+        let welcome: RelayWelcome =
+            bincode::deserialize(&plaintext).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        if welcome.status != RelayStatus::Accepted {
+            return Ok(Err(reject(welcome.status, welcome.message, None)));
+        }
+
+        debug!("🛰️ Connected to relay {}", self.config.address);
+        Ok(Ok(RelaySession { stream, session: welcome.session, next_sequence: 0 }))
+    }
+
+    async fn exchange(&self, connection: &mut Option<RelaySession>, mut frame: RelayFrame) -> std::io::Result<RelayAck> {
+        if connection.is_none() {
+            match self.connect().await? {
+                Ok(session) => *connection = Some(session),
+                Err(refusal) => return Ok(refusal),
+            }
+        }
+        let session = connection.as_mut().expect("relay connection was just established");
+
+        frame.sequence = session.next_sequence;
+        let body = seal_frame(&self.key, &self.config.peer_id, &session.session, &frame)?;
+        write_frame(&mut session.stream, &body).await?;
+        session.next_sequence += 1;
+
+        let timeout = Duration::from_secs(self.config.timeout_seconds);
+        let response = read_frame(&mut session.stream, MAX_ACK_BYTES, timeout, timeout)
+            .await?
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "relay closed the connection"))?;
+        let plaintext = open(&self.key, &ack_aad(&self.config.peer_id, &session.session), &response)?;
+        bincode::deserialize(&plaintext).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(id: &str, key: &str) -> RelayPeerConfig {
+        RelayPeerConfig {
+            id: id.to_string(),
+            key: key.to_string(),
+            max_events_per_minute: 10,
+            max_bytes_per_minute: 1024,
+        }
+    }
+
+    fn frame(sequence: u64) -> RelayFrame {
+        RelayFrame {
+            sequence,
+            sent_at: chrono::Utc::now(),
+            relay_path: vec![],
+            event_count: 1,
+            envelope: b"envelope".to_vec(),
+            format: PayloadFormat::Ndjson,
+            content_encoding: Some("zstd".to_string()),
+            headers: vec![],
+        }
+    }
+
+    #[test]
+    fn test_sealed_frames_are_bound_to_peer() {
+        let key = decode_key(&general_purpose::STANDARD.encode([7u8; 32])).unwrap();
+        let session = [3u8; SESSION_LEN];
+        let sealed = seal(&key, &frame_aad("web-01", &session), b"envelope".to_vec()).unwrap();
+
+        assert_eq!(open(&key, &frame_aad("web-01", &session), &sealed).unwrap(), b"envelope");
+        assert!(open(&key, &frame_aad("web-02", &session), &sealed).is_err());
+        assert!(decode_key("c2hvcnQ=").is_err());
+
+        let hello = hello_body(&key, "web-01").unwrap();
+        assert!(hello.len() <= MAX_HELLO_BYTES);
+        let (peer_id, sealed) = parse_hello(&hello).unwrap();
+        assert_eq!(peer_id, "web-01");
+        assert!(open(&key, &hello_aad("web-01"), sealed).is_ok());
+        assert!(open(&key, &hello_aad("web-02"), sealed).is_err());
+    }
+
+    #[test]
+    fn test_replayed_frames_are_rejected() {
+        let key = decode_key(&general_purpose::STANDARD.encode([7u8; 32])).unwrap();
+        let session = [1u8; SESSION_LEN];
+        let first = seal_frame(&key, "web-01", &session, &frame(0)).unwrap();
+        let second = seal_frame(&key, "web-01", &session, &frame(1)).unwrap();
+
+        assert!(open_frame(&key, "web-01", &session, 0, &first).is_ok());
+        // Replaying the first frame, skipping ahead or moving it to another session all fail
+        assert!(open_frame(&key, "web-01", &session, 1, &first).is_err());
+        assert!(open_frame(&key, "web-01", &session, 2, &second).is_err());
+        assert!(open_frame(&key, "web-01", &[2u8; SESSION_LEN], 0, &first).is_err());
+        let opened = open_frame(&key, "web-01", &session, 1, &second).unwrap();
+        assert_eq!(opened.sequence, 1);
+        // Format and encoding travel with the envelope
+        assert_eq!(opened.format, PayloadFormat::Ndjson);
+        assert_eq!(opened.content_encoding.as_deref(), Some("zstd"));
+    }
+
+    #[tokio::test]
+    async fn test_read_frame_limits_and_timeouts() {
+        let timeout = Duration::from_millis(50);
+
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client.write_u32(16 * 1024 * 1024).await.unwrap();
+        let err = read_frame(&mut server, MAX_HELLO_BYTES, timeout, timeout).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // A sender that announces a frame and then stalls is cut off
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client.write_u32(100).await.unwrap();
+        client.write_all(&[0u8; 10]).await.unwrap();
+        let err = read_frame(&mut server, MAX_HELLO_BYTES, timeout, timeout).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+        let (client, mut server) = tokio::io::duplex(1024);
+        let err = read_frame(&mut server, MAX_HELLO_BYTES, timeout, timeout).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        drop(client);
+
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client.write_u32(4).await.unwrap();
+        client.write_all(b"ping").await.unwrap();
+        drop(client);
+        assert_eq!(read_frame(&mut server, MAX_HELLO_BYTES, timeout, timeout).await.unwrap().unwrap(), b"ping");
+        assert!(read_frame(&mut server, MAX_HELLO_BYTES, timeout, timeout).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_peer_quota_window() {
+        let key = general_purpose::STANDARD.encode([1u8; 32]);
+        let config = peer("web-01", &key);
+        let mut state = PeerState {
+            key: decode_key(&key).unwrap(),
+            config,
+            window_start: Instant::now(),
+            window_events: 0,
+            window_bytes: 0,
+            stats: RelayPeerStats::default(),
+        };
+
+        assert!(state.charge(8, 100).is_ok());
+        assert!(state.charge(3, 100).is_err());
+        assert!(state.charge(2, 2000).is_err());
+        assert!(state.charge(2, 100).is_ok());
+
+        state.window_start -= QUOTA_WINDOW;
+        assert!(state.charge(10, 1024).is_ok());
+    }
+
+    #[test]
+    fn test_envelope_headers_keep_origin_integrity_headers() {
+        let envelope = RelayedEnvelope {
+            peer_id: "web-01".to_string(),
+            relay_path: vec!["relay-a".to_string()],
+            event_count: 1,
+            received_at: chrono::Utc::now(),
+            payload: vec![],
+            format: PayloadFormat::Json,
+            encoding: CompressionAlgorithm::None,
+            origin_headers: vec![
+                ("X-SecureWatch-Batch-Digest".to_string(), "sha-256=abc".to_string()),
+                ("X-SecureWatch-Batch-Signature".to_string(), "sig".to_string()),
+                ("Authorization".to_string(), "Bearer stolen".to_string()),
+                ("X-SecureWatch-Relay-Peer".to_string(), "forged".to_string()),
+            ],
+        };
+        let headers = envelope.headers();
+        let names: Vec<&str> = headers.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, [
+            "X-SecureWatch-Batch-Digest",
+            "X-SecureWatch-Batch-Signature",
+            "X-SecureWatch-Relay-Peer",
+            "X-SecureWatch-Relay-Path",
+            "X-SecureWatch-Relay-Received-At",
+        ]);
+        assert_eq!(headers[2].1, "web-01");
+    }
+
+    #[test]
+    fn test_validate_rejects_self_upstream() {
+        let key = general_purpose::STANDARD.encode([2u8; 32]);
+        let config = RelayConfig {
+            enabled: true,
+            peers: vec![peer("web-01", &key), peer("web-01", "bad")],
+            upstream: Some(RelayUpstreamConfig {
+                address: "0.0.0.0:5045".to_string(),
+                peer_id: "relay-a".to_string(),
+                key,
+                timeout_seconds: 30,
+            }),
+            ..Default::default()
+        };
+        let errors = config.validate();
+        assert!(errors.iter().any(|e| e.contains("Duplicate relay peer")));
+        assert!(errors.iter().any(|e| e.contains("own listener")));
+        assert!(errors.iter().any(|e| e.contains("'web-01': key")));
+    }
+}
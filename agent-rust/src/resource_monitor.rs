@@ -1,6 +1,7 @@
 // Resource Management - Comprehensive system resource monitoring
 // Implements CPU, memory, disk, and network monitoring with thresholds and alerting
 
+use crate::component_usage::{ComponentUsage, UsageSampler};
 use crate::errors::{AgentError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -95,6 +96,10 @@ pub struct ResourceMetrics {
     pub disk: Vec<DiskMetrics>,
     pub network: Vec<NetworkMetrics>,
     pub processes: Option<Vec<ProcessMetrics>>,
+    /// The agent's own process, measured against agent.max_memory_mb and agent.max_cpu_percent
+    pub agent_process: Option<ProcessMetrics>,
+    /// CPU and allocation attributed to pipeline components, busiest first
+    pub components: Vec<ComponentUsage>,
     pub system: SystemMetrics,
 }
 
@@ -218,6 +223,7 @@ pub struct ResourceMonitor {
     components: Arc<RwLock<Components>>,
     stats: Arc<RwLock<ResourceMonitorStats>>,
     last_network_stats: Arc<RwLock<HashMap<String, (u64, u64, Instant)>>>,
+    component_sampler: Arc<parking_lot::Mutex<UsageSampler>>,
     alert_sender: broadcast::Sender<ResourceAlert>,
     metrics_sender: broadcast::Sender<ResourceMetrics>,
     start_time: Instant,
@@ -246,6 +252,7 @@ impl ResourceMonitor {
             components: Arc::new(RwLock::new(components)),
             stats: Arc::new(RwLock::new(ResourceMonitorStats::default())),
             last_network_stats: Arc::new(RwLock::new(HashMap::new())),
+            component_sampler: Arc::new(parking_lot::Mutex::new(UsageSampler::default())),
             alert_sender,
             metrics_sender,
             start_time: Instant::now(),
@@ -263,6 +270,7 @@ impl ResourceMonitor {
         let components = self.components.clone();
         let stats = self.stats.clone();
         let last_network_stats = self.last_network_stats.clone();
+        let component_sampler = self.component_sampler.clone();
         let alert_sender = self.alert_sender.clone();
         let metrics_sender = self.metrics_sender.clone();
         let start_time = self.start_time;
@@ -281,6 +289,7 @@ impl ResourceMonitor {
                             &components,
                             &stats,
                             &last_network_stats,
+                            &component_sampler,
                             &alert_sender,
                             &metrics_sender,
                             start_time,
@@ -308,6 +317,7 @@ impl ResourceMonitor {
         components: &Arc<RwLock<Components>>,
         stats: &Arc<RwLock<ResourceMonitorStats>>,
         last_network_stats: &Arc<RwLock<HashMap<String, (u64, u64, Instant)>>>,
+        component_sampler: &parking_lot::Mutex<UsageSampler>,
         alert_sender: &broadcast::Sender<ResourceAlert>,
         metrics_sender: &broadcast::Sender<ResourceMetrics>,
         start_time: Instant,
@@ -330,7 +340,8 @@ impl ResourceMonitor {
             components_guard.refresh();
         }
         
-        let metrics = Self::collect_metrics(config, system, disks, networks, components, last_network_stats).await?;
+        let mut metrics = Self::collect_metrics(config, system, disks, networks, components, last_network_stats).await?;
+        metrics.components = component_sampler.lock().sample();
         
         // Update statistics
         {
@@ -356,6 +367,10 @@ impl ResourceMonitor {
         
         debug!("📊 Resource monitoring cycle completed - CPU: {:.1}%, Memory: {:.1}%", 
                metrics.cpu.usage_percent, metrics.memory.usage_percent);
+        if let Some(busiest) = metrics.components.first() {
+            debug!("📊 Busiest component: {} ({:.1}% CPU, {:.0} B/s allocated)",
+                   busiest.component, busiest.cpu_percent, busiest.allocated_bytes_per_second);
+        }
         
         Ok(())
     }
@@ -521,6 +536,19 @@ impl ResourceMonitor {
             None
         };
         
+        let agent_process = sys.process(sysinfo::Pid::from_u32(std::process::id())).map(|process| ProcessMetrics {
+            pid: std::process::id(),
+            name: process.name().to_string_lossy().to_string(),
+            cpu_usage: process.cpu_usage(),
+            memory_bytes: process.memory(),
+            memory_percent: if total_memory > 0 {
+                (process.memory() as f32 / total_memory as f32) * 100.0
+            } else {
+                0.0
+            },
+            status: format!("{:?}", process.status()),
+        });
+        
         // Temperature metrics (optional)
         let temperature = if config.monitor_temperature {
             let mut temp_metrics = Vec::new();
@@ -559,6 +587,8 @@ impl ResourceMonitor {
             disk: disk_metrics,
             network: network_metrics,
             processes,
+            agent_process,
+            components: crate::component_usage::snapshot(),
             system: system_metrics,
         })
     }
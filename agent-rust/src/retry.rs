@@ -1,12 +1,207 @@
 // Enhanced retry mechanism with exponential backoff for SecureWatch Agent
-// Implements industry-standard retry patterns with circuit breaker integration
+// Implements industry-standard retry patterns with circuit breaker integration: decorrelated jitter so agents that
+// failed together don't retry in lockstep, and retry budgets that cap retries to a share of first attempts
 
 use crate::errors::{AgentError, TransportError};
+use serde::{Deserialize, Serialize};
 use std::future::Future;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::{debug, warn, error, info};
 
+/// How retry delays are randomized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JitterStrategy {
+    /// Plain exponential backoff
+    None,
+    /// Exponential backoff ± `jitter_factor` of each delay
+    Proportional,
+    /// Each delay drawn uniformly from [initial delay, 3 × previous delay]
+    #[default]
+    Decorrelated,
+}
+
+/// Retry delay sequence for one operation
+/// Decorrelated jitter follows the AWS Architecture Blog's "Exponential Backoff And Jitter": delays still grow
+/// exponentially on average, but clients that failed at the same moment spread out instead of retrying in step
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    strategy: JitterStrategy,
+    base: Duration,
+    max: Duration,
+    multiplier: f64,
+    jitter_factor: f64,
+    previous: Option<Duration>,
+    rng: u64,
+}
+
+impl Backoff {
+    pub fn new(strategy: JitterStrategy, base: Duration, max: Duration, multiplier: f64, jitter_factor: f64) -> Self {
+        Self {
+            strategy,
+            base,
+            max: max.max(base),
+            multiplier: multiplier.max(1.0),
+            jitter_factor: jitter_factor.clamp(0.0, 1.0),
+            previous: None,
+            rng: random_seed(),
+        }
+    }
+
+    pub fn from_config(config: &RetryConfig) -> Self {
+        Self::new(config.jitter, config.initial_delay, config.max_delay, config.backoff_multiplier, config.jitter_factor)
+    }
+
+    /// Delay before the next retry
+    pub fn next_delay(&mut self) -> Duration {
+        match self.strategy {
+            JitterStrategy::None | JitterStrategy::Proportional => {
+                let nominal = match self.previous {
+                    Some(previous) => previous.mul_f64(self.multiplier).min(self.max),
+                    None => self.base,
+                };
+                self.previous = Some(nominal);
+                if self.strategy == JitterStrategy::None {
+                    return nominal;
+                }
+                // nominal ± jitter_factor × nominal
+                let jitter = (self.random() * 2.0 - 1.0) * self.jitter_factor;
+                nominal.mul_f64(1.0 + jitter).min(self.max)
+            }
+            JitterStrategy::Decorrelated => {
+                let upper = self.previous.unwrap_or(self.base).mul_f64(3.0).min(self.max);
+                let span = upper.saturating_sub(self.base);
+                let delay = (self.base + span.mul_f64(self.random())).min(self.max);
+                self.previous = Some(delay);
+                delay
+            }
+        }
+    }
+
+    /// Start over from the initial delay, e.g. after a success
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// Uniform in [0, 1) from xorshift64*
+    fn random(&mut self) -> f64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        (self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Seed that differs per process and per call; std's RandomState keys come from the OS
+fn random_seed() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+    hasher.finish() | 1
+}
+
+/// Caps retries to a share of first attempts, so a failing endpoint sees at most (1 + ratio) times its normal
+/// request rate however many batches are queued behind it. A small per-second floor keeps quiet agents able to retry
+#[derive(Debug)]
+pub struct RetryBudget {
+    ratio: f64,
+    min_per_second: u32,
+    max_tokens: f64,
+    state: parking_lot::Mutex<BudgetState>,
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    tokens: f64,
+    window_start: Instant,
+    floor_used: u32,
+    exhausted: u64,
+}
+
+/// Retry tokens never accumulate beyond this many requests' worth
+const BUDGET_WINDOW_REQUESTS: f64 = 1000.0;
+
+impl RetryBudget {
+    pub fn new(ratio: f64, min_per_second: u32) -> Self {
+        let ratio = ratio.max(0.0);
+        Self {
+            ratio,
+            min_per_second,
+            max_tokens: (ratio * BUDGET_WINDOW_REQUESTS).max(1.0),
+            state: parking_lot::Mutex::new(BudgetState { tokens: 0.0, window_start: Instant::now(), floor_used: 0, exhausted: 0 }),
+        }
+    }
+
+    /// A first attempt was made, earning `ratio` retries
+    pub fn record_request(&self) {
+        let mut state = self.state.lock();
+        state.tokens = (state.tokens + self.ratio).min(self.max_tokens);
+    }
+
+    /// Spend one retry; false when the budget is used up
+    pub fn try_retry(&self) -> bool {
+        let mut state = self.state.lock();
+        if state.window_start.elapsed() >= Duration::from_secs(1) {
+            state.window_start = Instant::now();
+            state.floor_used = 0;
+        }
+        if state.floor_used < self.min_per_second {
+            state.floor_used += 1;
+            return true;
+        }
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            return true;
+        }
+        state.exhausted += 1;
+        false
+    }
+
+    /// Retries refused so far
+    pub fn exhausted(&self) -> u64 {
+        self.state.lock().exhausted
+    }
+}
+
+/// Transport retry settings beyond `retry_attempts` and `retry_delay`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicyConfig {
+    pub jitter: JitterStrategy,
+    /// Longest delay between attempts, in seconds
+    pub max_delay_secs: u64,
+    /// Retries allowed per first attempt; 0.2 lets retries add at most 20% to an endpoint's load
+    pub budget_ratio: f64,
+    /// Retries always allowed per second regardless of the budget
+    pub budget_min_per_second: u32,
+}
+
+impl Default for RetryPolicyConfig {
+    fn default() -> Self {
+        Self {
+            jitter: JitterStrategy::Decorrelated,
+            max_delay_secs: 60,
+            budget_ratio: 0.2,
+            budget_min_per_second: 1,
+        }
+    }
+}
+
+impl RetryPolicyConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.max_delay_secs == 0 {
+            errors.push("max_delay_secs must be greater than 0".to_string());
+        }
+        if !(0.0..=10.0).contains(&self.budget_ratio) {
+            errors.push("budget_ratio must be between 0 and 10".to_string());
+        }
+        errors
+    }
+}
+
 /// Retry configuration with exponential backoff parameters
 #[derive(Debug, Clone)]
 pub struct RetryConfig {
@@ -22,7 +217,10 @@ pub struct RetryConfig {
     /// Backoff multiplier for exponential growth (default: 2.0)
     pub backoff_multiplier: f64,
     
-    /// Maximum jitter percentage to add randomness (default: 0.1 = 10%)
+    /// How delays are randomized (default: decorrelated)
+    pub jitter: JitterStrategy,
+    
+    /// Maximum jitter percentage for proportional jitter (default: 0.1 = 10%)
     pub jitter_factor: f64,
     
     /// Overall timeout for all retry attempts (default: 5 minutes)
@@ -42,6 +240,7 @@ impl Default for RetryConfig {
             initial_delay: Duration::from_millis(100),
             max_delay: Duration::from_secs(30),
             backoff_multiplier: 2.0,
+            jitter: JitterStrategy::Decorrelated,
             jitter_factor: 0.1,
             total_timeout: Some(Duration::from_secs(300)), // 5 minutes
             retry_on_rate_limit: true,
@@ -124,12 +323,19 @@ pub struct RetryResult<T> {
 /// Main retry executor with exponential backoff
 pub struct RetryExecutor {
     config: RetryConfig,
+    budget: Option<Arc<RetryBudget>>,
 }
 
 impl RetryExecutor {
     /// Create a new retry executor with the given configuration
     pub fn new(config: RetryConfig) -> Self {
-        Self { config }
+        Self { config, budget: None }
+    }
+    
+    /// Draw retries from a budget shared with other executors calling the same endpoint
+    pub fn with_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.budget = Some(budget);
+        self
     }
     
     /// Create a retry executor with default configuration
@@ -144,8 +350,12 @@ impl RetryExecutor {
         Fut: Future<Output = Result<T, AgentError>>,
     {
         let start_time = Instant::now();
-        let mut current_delay = self.config.initial_delay;
+        let mut backoff = Backoff::from_config(&self.config);
+        let mut previous_delay = None;
         let mut last_error = None;
+        if let Some(budget) = &self.budget {
+            budget.record_request();
+        }
         
         for attempt in 1..=self.config.max_attempts {
             let elapsed = start_time.elapsed();
@@ -161,7 +371,7 @@ impl RetryExecutor {
             let retry_attempt = RetryAttempt {
                 attempt,
                 elapsed,
-                previous_delay: if attempt > 1 { Some(current_delay) } else { None },
+                previous_delay,
                 is_final_attempt: attempt == self.config.max_attempts,
             };
             
@@ -200,17 +410,17 @@ impl RetryExecutor {
                     
                     // Log the error and retry info
                     if attempt < self.config.max_attempts {
+                        if self.budget.as_ref().is_some_and(|budget| !budget.try_retry()) {
+                            warn!("⚠️  Attempt {}/{} failed: {} (retry budget exhausted)", attempt, self.config.max_attempts, error);
+                            break;
+                        }
+                        let delay = backoff.next_delay();
                         warn!(
                             "⚠️  Attempt {}/{} failed: {} (will retry in {:.2}s)",
-                            attempt, self.config.max_attempts, error, current_delay.as_secs_f64()
+                            attempt, self.config.max_attempts, error, delay.as_secs_f64()
                         );
-                        
-                        // Apply delay with jitter before next attempt
-                        let delay_with_jitter = self.apply_jitter(current_delay);
-                        sleep(delay_with_jitter).await;
-                        
-                        // Calculate next delay using exponential backoff
-                        current_delay = self.calculate_next_delay(current_delay);
+                        sleep(delay).await;
+                        previous_delay = Some(delay);
                     } else {
                         error!("❌ Final attempt {}/{} failed: {}", attempt, self.config.max_attempts, error);
                     }
@@ -299,48 +509,7 @@ impl RetryExecutor {
             AgentError::Security(_) => false,
         }
     }
-    
-    /// Calculate next delay using exponential backoff
-    fn calculate_next_delay(&self, current_delay: Duration) -> Duration {
-        let next_delay_ms = (current_delay.as_millis() as f64 * self.config.backoff_multiplier) as u64;
-        let next_delay = Duration::from_millis(next_delay_ms);
-        
-        // Cap at maximum delay
-        if next_delay > self.config.max_delay {
-            self.config.max_delay
-        } else {
-            next_delay
-        }
-    }
-    
-    /// Apply jitter to delay to avoid thundering herd problem
-    fn apply_jitter(&self, delay: Duration) -> Duration {
-        if self.config.jitter_factor <= 0.0 {
-            return delay;
-        }
-        
-        // Use a simple linear congruential generator for deterministic jitter
-        // This avoids needing external random number dependencies
-        let seed = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos() as u64;
-        
-        // Simple LCG: next = (a * seed + c) % m
-        let a = 1664525u64;
-        let c = 1013904223u64;
-        let random = a.wrapping_mul(seed).wrapping_add(c);
-        
-        // Convert to 0.0-1.0 range
-        let normalized = (random % 1000000) as f64 / 1000000.0;
-        
-        // Apply jitter: delay ± (jitter_factor * delay)
-        let jitter_range = delay.as_secs_f64() * self.config.jitter_factor;
-        let jitter = (normalized - 0.5) * 2.0 * jitter_range; // -jitter_range to +jitter_range
-        
-        let final_delay_secs = delay.as_secs_f64() + jitter;
-        Duration::from_secs_f64(final_delay_secs.max(0.0))
-    }
+
 }
 
 /// Convenience functions for common retry scenarios
@@ -652,4 +821,47 @@ mod tests {
         // Operations should be blocked
         assert!(!circuit_breaker.is_operation_allowed().await);
     }
+    
+    #[test]
+    fn test_decorrelated_backoff_spreads_and_caps_delays() {
+        let base = Duration::from_millis(100);
+        let max = Duration::from_secs(2);
+        let mut first_delays = std::collections::HashSet::new();
+        for _ in 0..20 {
+            let mut backoff = Backoff::new(JitterStrategy::Decorrelated, base, max, 2.0, 0.0);
+            let mut previous = base;
+            for _ in 0..30 {
+                let delay = backoff.next_delay();
+                assert!(delay >= base && delay <= max);
+                assert!(delay <= previous * 3);
+                previous = delay;
+            }
+            backoff.reset();
+            first_delays.insert(backoff.next_delay());
+        }
+        // Independent agents don't pick the same delay
+        assert!(first_delays.len() > 1);
+        
+        let mut plain = Backoff::new(JitterStrategy::None, base, max, 2.0, 0.0);
+        let delays: Vec<_> = (0..6).map(|_| plain.next_delay().as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1600, 2000]);
+    }
+    
+    #[test]
+    fn test_retry_budget_limits_retries_to_ratio() {
+        let budget = RetryBudget::new(0.25, 0);
+        assert!(!budget.try_retry());
+        for _ in 0..8 {
+            budget.record_request();
+        }
+        assert!(budget.try_retry());
+        assert!(budget.try_retry());
+        assert!(!budget.try_retry());
+        assert_eq!(budget.exhausted(), 2);
+        
+        // The per-second floor allows retries with no traffic at all
+        let floor = RetryBudget::new(0.1, 1);
+        assert!(floor.try_retry());
+        assert!(!floor.try_retry());
+    }
 }
\ No newline at end of file
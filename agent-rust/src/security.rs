@@ -11,7 +11,7 @@ use tokio::sync::{RwLock, Mutex, broadcast};
 use tokio::time::{interval, sleep};
 use tracing::{debug, info, warn, error, trace};
 use base64::{Engine as _, engine::general_purpose};
-use ring::{aead, pbkdf2, rand::{self, SecureRandom}};
+use ring::{aead, pbkdf2, rand::{self, SecureRandom}, signature};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Configuration for secure credential management
@@ -849,6 +849,320 @@ impl SecureCredentialManager {
         info!("✅ Credential deleted successfully");
         Ok(())
     }
+    
+    /// Ed25519 key pair kept as a PKCS#8 credential; generated on first use and replaced with a new key
+    /// once it is past its rotation date, so the caller should publish the public key after every load
+    pub async fn signing_key_pair(&self, id: &str) -> Result<signature::Ed25519KeyPair> {
+        let next_rotation_at = self.credentials.read().await.get(id).map(|credential| credential.next_rotation_at);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map_err(|_| AgentError::Security(SecurityError::SystemTimeError))?
+            .as_secs();
+        
+        let encoded = match next_rotation_at {
+            Some(next_rotation_at) if now <= next_rotation_at => self.get_credential(id).await?,
+            existing => {
+                let pkcs8 = signature::Ed25519KeyPair::generate_pkcs8(&rand::SystemRandom::new())
+                    .map_err(|_| AgentError::Security(SecurityError::KeyCreationFailed))?;
+                let encoded = general_purpose::STANDARD.encode(pkcs8.as_ref());
+                if existing.is_some() {
+                    info!("🔑 Signing key {} is due for rotation, generating a new one", id);
+                    self.rotate_credential(id, &encoded).await?;
+                } else {
+                    info!("🔑 Generating Ed25519 signing key {}", id);
+                    let metadata = HashMap::from([("algorithm".to_string(), "ed25519".to_string())]);
+                    self.store_credential(id.to_string(), CredentialType::PrivateKey, &encoded, Some(metadata), true).await?;
+                }
+                encoded
+            }
+        };
+        
+        let mut pkcs8 = general_purpose::STANDARD.decode(encoded.trim())
+            .map_err(|_| AgentError::Security(SecurityError::KeyCreationFailed))?;
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(&pkcs8)
+            .map_err(|_| AgentError::Security(SecurityError::KeyCreationFailed));
+        pkcs8.zeroize();
+        key_pair
+    }
+}
+
+// OS keyring references
+// A config secret written as `keyring:<service>/<account>` stays out of the TOML file; the transport reads it from
+// the platform store when it starts: DPAPI on Windows, the Keychain on macOS and the Secret Service (GNOME Keyring,
+// KWallet) that libsecret talks to on Linux. `securewatch-agent secret set` puts values there.
+
+/// Marks a config value as a reference to a secret in the OS keyring
+pub const KEYRING_PREFIX: &str = "keyring:";
+
+/// Names a secret in the OS keyring
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyringReference {
+    pub service: String,
+    pub account: String,
+}
+
+impl KeyringReference {
+    /// The reference `value` holds; None when it is the secret itself
+    pub fn parse(value: &str) -> Option<std::result::Result<Self, String>> {
+        let reference = value.strip_prefix(KEYRING_PREFIX)?;
+        // Both parts become file names in the DPAPI store, so keep them to a portable set
+        let valid = |part: &str| !part.is_empty()
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '@'))
+            && !part.starts_with('.');
+        Some(match reference.split_once('/') {
+            Some((service, account)) if valid(service) && valid(account) => Ok(Self {
+                service: service.to_string(),
+                account: account.to_string(),
+            }),
+            _ => Err(format!("'{}' is not a keyring:<service>/<account> reference", value)),
+        })
+    }
+
+    /// Name of the store secrets go to on this platform
+    pub fn store_name() -> &'static str {
+        os_keyring::STORE
+    }
+
+    pub async fn get(&self) -> Result<String> {
+        match self.with_store("read", os_keyring::get).await? {
+            Some(secret) => Ok(secret),
+            None => Err(AgentError::Security(SecurityError::CredentialNotFound(format!(
+                "{} is not in the {}", self, os_keyring::STORE
+            )))),
+        }
+    }
+
+    pub async fn set(&self, secret: &str) -> Result<()> {
+        let secret = secret.to_string();
+        self.with_store("write", move |service, account| os_keyring::set(service, account, &secret)).await?;
+        info!("🔑 Stored {} in the {}", self, os_keyring::STORE);
+        Ok(())
+    }
+
+    /// Remove the secret; false when there was none
+    pub async fn delete(&self) -> Result<bool> {
+        self.with_store("delete", os_keyring::delete).await
+    }
+
+    async fn with_store<T, F>(&self, operation: &str, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&str, &str) -> std::result::Result<T, String> + Send + 'static,
+    {
+        let (service, account) = (self.service.clone(), self.account.clone());
+        // The Secret Service client blocks on D-Bus, which must not happen on a runtime thread
+        tokio::task::spawn_blocking(move || f(&service, &account)).await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result)
+            .map_err(|reason| AgentError::Security(SecurityError::CredentialError {
+                operation: format!("{} {} in the {}: {}", operation, self, os_keyring::STORE, reason),
+                credential_type: "keyring".to_string(),
+                source: reason.into(),
+            }))
+    }
+}
+
+impl std::fmt::Display for KeyringReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}/{}", KEYRING_PREFIX, self.service, self.account)
+    }
+}
+
+/// The secret behind a config value: the value itself, or what its keyring reference points to
+pub async fn resolve_secret(value: &str) -> Result<String> {
+    match KeyringReference::parse(value) {
+        None => Ok(value.to_string()),
+        Some(Ok(reference)) => reference.get().await,
+        Some(Err(e)) => Err(AgentError::Security(SecurityError::CredentialNotFound(e))),
+    }
+}
+
+#[cfg(windows)]
+mod os_keyring {
+    // Encrypted with the DPAPI machine key, so the service account and administrators running the CLI share secrets
+    // that only decrypt on this host. The ciphertext lives under %ProgramData%\SecureWatch\secrets, restricted to
+    // SYSTEM and Administrators.
+    use std::path::{Path, PathBuf};
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{LocalFree, BOOL, HLOCAL};
+    use windows::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SetNamedSecurityInfoW, SDDL_REVISION_1, SE_FILE_OBJECT,
+    };
+    use windows::Win32::Security::Cryptography::{
+        CryptProtectData, CryptUnprotectData, CRYPTPROTECT_LOCAL_MACHINE, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+    };
+    use windows::Win32::Security::{
+        GetSecurityDescriptorDacl, GetSecurityDescriptorOwner, ACL, DACL_SECURITY_INFORMATION, OWNER_SECURITY_INFORMATION,
+        PROTECTED_DACL_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID,
+    };
+
+    pub const STORE: &str = "DPAPI store";
+
+    fn secrets_dir() -> PathBuf {
+        let program_data = std::env::var("ProgramData").unwrap_or_else(|_| "C:\\ProgramData".to_string());
+        PathBuf::from(program_data).join("SecureWatch").join("secrets")
+    }
+
+    fn path(service: &str, account: &str) -> PathBuf {
+        secrets_dir().join(service).join(format!("{}.dpapi", account))
+    }
+
+    /// Ties each blob to its name, so a copied file doesn't decrypt as another secret
+    fn entropy(service: &str, account: &str) -> Vec<u8> {
+        format!("securewatch:{}/{}", service, account).into_bytes()
+    }
+
+    fn blob(data: &[u8]) -> CRYPT_INTEGER_BLOB {
+        CRYPT_INTEGER_BLOB { cbData: data.len() as u32, pbData: data.as_ptr() as *mut u8 }
+    }
+
+    /// Copy DPAPI output and free its buffer
+    unsafe fn take(output: CRYPT_INTEGER_BLOB) -> Vec<u8> {
+        let data = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+        let _ = LocalFree(HLOCAL(output.pbData as _));
+        data
+    }
+
+    pub fn get(service: &str, account: &str) -> Result<Option<String>, String> {
+        let ciphertext = match std::fs::read(path(service, account)) {
+            Ok(ciphertext) => ciphertext,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+        let (input, entropy) = (blob(&ciphertext), entropy(service, account));
+        let entropy = blob(&entropy);
+        let mut output = CRYPT_INTEGER_BLOB::default();
+        let plaintext = unsafe {
+            CryptUnprotectData(&input, None, Some(&entropy as *const _), None, None, CRYPTPROTECT_UI_FORBIDDEN, &mut output)
+                .map_err(|e| e.to_string())?;
+            take(output)
+        };
+        String::from_utf8(plaintext).map(Some).map_err(|_| "stored secret is not UTF-8".to_string())
+    }
+
+    pub fn set(service: &str, account: &str, secret: &str) -> Result<(), String> {
+        let (input, entropy) = (blob(secret.as_bytes()), entropy(service, account));
+        let entropy = blob(&entropy);
+        let mut output = CRYPT_INTEGER_BLOB::default();
+        let ciphertext = unsafe {
+            CryptProtectData(&input, PCWSTR::null(), Some(&entropy as *const _), None, None,
+                             CRYPTPROTECT_LOCAL_MACHINE | CRYPTPROTECT_UI_FORBIDDEN, &mut output)
+                .map_err(|e| e.to_string())?;
+            take(output)
+        };
+        restrict_secrets_dir()?;
+        let path = path(service, account);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        // An existing file keeps its own ACL when overwritten, so the blob always goes into a new one that inherits
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.to_string()),
+        }
+        let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(&path).map_err(|e| e.to_string())?;
+        std::io::Write::write_all(&mut file, &ciphertext).map_err(|e| e.to_string())
+    }
+
+    pub fn delete(service: &str, account: &str) -> Result<bool, String> {
+        match std::fs::remove_file(path(service, account)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Owned by BUILTIN\Administrators; full control for SYSTEM and Administrators only, inherited by everything
+    /// inside and nothing inherited from ProgramData
+    const SECRETS_DIR_SDDL: &str = "O:BAD:P(A;OICI;FA;;;SY)(A;OICI;FA;;;BA)";
+
+    /// Anyone can decrypt machine-key DPAPI blobs they can read, and ProgramData lets every user read and create
+    /// directories. The directory may have been created by someone else before the agent first stored a secret, so
+    /// its owner and DACL are set on every write, and no secret is written when that fails.
+    fn restrict_secrets_dir() -> Result<(), String> {
+        let dir = secrets_dir();
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        // A link would send the ACL change, and the secrets after it, somewhere else
+        let metadata = std::fs::symlink_metadata(&dir).map_err(|e| e.to_string())?;
+        if metadata.file_type().is_symlink() || !metadata.is_dir() {
+            return Err(format!("{} is not a plain directory; refusing to store secrets in it", dir.display()));
+        }
+
+        let mut descriptor = PSECURITY_DESCRIPTOR::default();
+        unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(&HSTRING::from(SECRETS_DIR_SDDL), SDDL_REVISION_1, &mut descriptor, None)
+                .map_err(|e| e.to_string())?;
+            let applied = apply_security(&dir, descriptor);
+            let _ = LocalFree(HLOCAL(descriptor.0));
+            applied.map_err(|e| format!("could not restrict {} to SYSTEM and Administrators: {}", dir.display(), e))
+        }
+    }
+
+    /// Set the owner and a protected DACL from `descriptor` on `dir`
+    unsafe fn apply_security(dir: &Path, descriptor: PSECURITY_DESCRIPTOR) -> windows::core::Result<()> {
+        let (mut owner, mut present, mut defaulted) = (PSID::default(), BOOL::default(), BOOL::default());
+        let mut dacl: *mut ACL = std::ptr::null_mut();
+        GetSecurityDescriptorOwner(descriptor, &mut owner, &mut defaulted)?;
+        GetSecurityDescriptorDacl(descriptor, &mut present, &mut dacl, &mut defaulted)?;
+        SetNamedSecurityInfoW(
+            &HSTRING::from(dir),
+            SE_FILE_OBJECT,
+            OWNER_SECURITY_INFORMATION | DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+            owner,
+            PSID::default(),
+            Some(dacl as *const ACL),
+            None,
+        )
+        .ok()
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+mod os_keyring {
+    pub const STORE: &str = if cfg!(target_os = "macos") { "Keychain" } else { "Secret Service" };
+
+    fn entry(service: &str, account: &str) -> Result<keyring::Entry, String> {
+        keyring::Entry::new(service, account).map_err(|e| e.to_string())
+    }
+
+    pub fn get(service: &str, account: &str) -> Result<Option<String>, String> {
+        match entry(service, account)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn set(service: &str, account: &str, secret: &str) -> Result<(), String> {
+        entry(service, account)?.set_password(secret).map_err(|e| e.to_string())
+    }
+
+    pub fn delete(service: &str, account: &str) -> Result<bool, String> {
+        match entry(service, account)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+mod os_keyring {
+    pub const STORE: &str = "OS keyring";
+
+    const UNSUPPORTED: &str = "no OS keyring is supported on this platform";
+
+    pub fn get(_service: &str, _account: &str) -> Result<Option<String>, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn set(_service: &str, _account: &str, _secret: &str) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn delete(_service: &str, _account: &str) -> Result<bool, String> {
+        Err(UNSUPPORTED.to_string())
+    }
 }
 
 // Helper module for base64 serialization
@@ -992,4 +1306,20 @@ mod tests {
         let stats = manager.get_stats().await;
         assert_eq!(stats.total_credentials, 3);
     }
+
+    #[tokio::test]
+    async fn test_keyring_references() {
+        let reference = KeyringReference::parse("keyring:securewatch/api").unwrap().unwrap();
+        assert_eq!(reference, KeyringReference { service: "securewatch".to_string(), account: "api".to_string() });
+        assert_eq!(reference.to_string(), "keyring:securewatch/api");
+
+        for invalid in ["keyring:securewatch", "keyring:/api", "keyring:securewatch/../api", "keyring:secure watch/api"] {
+            assert!(KeyringReference::parse(invalid).unwrap().is_err(), "{}", invalid);
+        }
+
+        // Literal secrets pass through without touching the keyring
+        assert!(KeyringReference::parse("sk-live-0123456789").is_none());
+        assert_eq!(resolve_secret("sk-live-0123456789").await.unwrap(), "sk-live-0123456789");
+        assert!(resolve_secret("keyring:no-service").await.is_err());
+    }
 }
\ No newline at end of file
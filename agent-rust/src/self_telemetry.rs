@@ -0,0 +1,244 @@
+// Agent self-telemetry as ordinary events
+// On an interval the agent describes its own health (pipeline counters, buffer state, collectors, host resources)
+// in one event sent through the buffer like any collected event, so fleet health can be searched and alerted on
+// in the SIEM without scraping every agent.
+
+use crate::buffer::BufferStats;
+use crate::collectors::CollectorStatus;
+use crate::parsers::ParsedEvent;
+use crate::pipeline_metrics::{LatencyHistogram, Stage};
+use crate::resource_monitor::ResourceMetrics;
+use crate::utils::AgentStats;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+pub const SELF_TELEMETRY_PARSER: &str = "self_telemetry";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SelfTelemetryConfig {
+    pub enabled: bool,
+    pub interval_seconds: u64,
+    /// Source name the health events carry
+    pub source: String,
+    /// Add CPU, memory and busiest-disk figures from the resource monitor
+    pub include_resources: bool,
+    /// Add parse, queue wait and flush latency percentiles per parser and source for the interval
+    pub include_pipeline_latency: bool,
+}
+
+impl Default for SelfTelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: 60,
+            source: "securewatch_agent".to_string(),
+            include_resources: true,
+            include_pipeline_latency: true,
+        }
+    }
+}
+
+impl SelfTelemetryConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !(10..=86_400).contains(&self.interval_seconds) {
+            errors.push("interval_seconds must be between 10 and 86400".to_string());
+        }
+        if self.source.trim().is_empty() {
+            errors.push("source must not be empty".to_string());
+        }
+        errors
+    }
+}
+
+/// Everything one health event reports
+pub struct HealthSnapshot<'a> {
+    pub agent_id: &'a str,
+    pub agent: &'a AgentStats,
+    pub buffer: &'a BufferStats,
+    pub collectors: &'a [CollectorStatus],
+    pub resources: Option<&'a ResourceMetrics>,
+    /// Cumulative latency histograms; each event reports what was recorded since the previous one
+    pub pipeline: &'a [LatencyHistogram],
+}
+
+/// Builds health events; remembers the previous counters so each event also carries what changed since the last
+#[derive(Debug, Default)]
+pub struct HealthReporter {
+    source: String,
+    previous: Option<(u64, u64, u64)>,
+    previous_latency: HashMap<(Stage, String), LatencyHistogram>,
+}
+
+impl HealthReporter {
+    pub fn new(config: &SelfTelemetryConfig) -> Self {
+        Self { source: config.source.clone(), previous: None, previous_latency: HashMap::new() }
+    }
+
+    pub fn event(&mut self, snapshot: &HealthSnapshot) -> ParsedEvent {
+        let agent = snapshot.agent;
+        let buffer = snapshot.buffer;
+        let dropped = agent.events_dropped + buffer.events_dropped;
+        let counters = (agent.events_processed, agent.events_sent, dropped);
+        let (processed_delta, sent_delta, dropped_delta) = match self.previous.replace(counters) {
+            Some((processed, sent, dropped_before)) => (
+                counters.0.saturating_sub(processed),
+                counters.1.saturating_sub(sent),
+                counters.2.saturating_sub(dropped_before),
+            ),
+            None => counters,
+        };
+
+        let stopped: Vec<&str> = snapshot.collectors.iter().filter(|c| !c.running).map(|c| c.name.as_str()).collect();
+        let running = snapshot.collectors.len() - stopped.len();
+        let collector_dropped: u64 = snapshot.collectors.iter().map(|c| c.dropped_events).sum();
+
+        let mut fields: HashMap<String, Value> = HashMap::from([
+            ("event.kind".to_string(), json!("metric")),
+            ("event.dataset".to_string(), json!("securewatch.agent.health")),
+            ("agent.id".to_string(), json!(snapshot.agent_id)),
+            ("agent.version".to_string(), json!(env!("CARGO_PKG_VERSION"))),
+            ("agent.uptime_seconds".to_string(), json!(agent.uptime_seconds())),
+            ("agent.events.processed".to_string(), json!(agent.events_processed)),
+            ("agent.events.sent".to_string(), json!(agent.events_sent)),
+            ("agent.events.failed".to_string(), json!(agent.events_failed)),
+            ("agent.events.dropped".to_string(), json!(dropped)),
+            ("agent.events.processed_delta".to_string(), json!(processed_delta)),
+            ("agent.events.sent_delta".to_string(), json!(sent_delta)),
+            ("agent.events.dropped_delta".to_string(), json!(dropped_delta)),
+            ("agent.errors".to_string(), json!(agent.errors)),
+            ("buffer.memory_events".to_string(), json!(buffer.memory_events)),
+            ("buffer.disk_events".to_string(), json!(buffer.disk_events)),
+            ("buffer.backpressure".to_string(), json!(buffer.backpressure_active)),
+            ("buffer.dead_letter_batches".to_string(), json!(buffer.dead_letter_batches)),
+            ("collectors.total".to_string(), json!(snapshot.collectors.len())),
+            ("collectors.running".to_string(), json!(running)),
+            ("collectors.stopped".to_string(), json!(stopped)),
+            ("collectors.dropped_events".to_string(), json!(collector_dropped)),
+        ]);
+        if let Some(last_error) = &agent.last_error {
+            fields.insert("agent.last_error.code".to_string(), json!(last_error.code.to_string()));
+        }
+        if let Some(resources) = snapshot.resources {
+            fields.insert("host.cpu.usage_percent".to_string(), json!(resources.cpu.usage_percent));
+            fields.insert("host.memory.usage_percent".to_string(), json!(resources.memory.usage_percent));
+            fields.insert("host.memory.used_bytes".to_string(), json!(resources.memory.used_bytes));
+            if let Some(disk) = resources.disk.iter().max_by(|a, b| a.usage_percent.total_cmp(&b.usage_percent)) {
+                fields.insert("host.disk.mount_point".to_string(), json!(disk.mount_point));
+                fields.insert("host.disk.usage_percent".to_string(), json!(disk.usage_percent));
+            }
+        }
+
+        let latency = self.interval_latency(snapshot.pipeline);
+        if !latency.is_empty() {
+            fields.insert("pipeline.latency".to_string(), Value::Array(latency));
+        }
+
+        // Anything an operator should look at raises the level
+        let degraded = !stopped.is_empty() || buffer.backpressure_active || dropped_delta > 0;
+        let message = format!(
+            "Agent health: {} processed, {} sent, {} dropped since last report; {}/{} collectors running{}",
+            processed_delta, sent_delta, dropped_delta, running, snapshot.collectors.len(),
+            if buffer.backpressure_active { "; buffer backpressure active" } else { "" },
+        );
+        let raw_data = serde_json::to_string(&fields).unwrap_or_default();
+
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: self.source.clone(),
+            level: Some(if degraded { "warning" } else { "info" }.to_string()),
+            message,
+            fields,
+            raw_data: raw_data.into(),
+            parser_name: SELF_TELEMETRY_PARSER.to_string(),
+        }
+    }
+
+    /// Percentiles of the samples recorded since the previous event, for every series that had any
+    fn interval_latency(&mut self, pipeline: &[LatencyHistogram]) -> Vec<Value> {
+        let mut latency = Vec::new();
+        for histogram in pipeline {
+            let key = (histogram.stage, histogram.key.clone());
+            let interval = match self.previous_latency.get(&key) {
+                Some(previous) => histogram.since(previous),
+                None => histogram.clone(),
+            };
+            self.previous_latency.insert(key, histogram.clone());
+            if interval.samples() == 0 {
+                continue;
+            }
+            latency.push(json!({
+                "stage": interval.stage.as_str(),
+                "key": interval.key,
+                "samples": interval.samples(),
+                "events_per_second": interval.events_per_second(),
+                "mean_ms": interval.mean_ms(),
+                "p50_ms": interval.percentile_ms(0.5),
+                "p95_ms": interval.percentile_ms(0.95),
+                "p99_ms": interval.percentile_ms(0.99),
+                "max_ms": interval.max_ms(),
+            }));
+        }
+        latency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_health_events_report_deltas_and_degradation() {
+        let config = crate::config::AgentConfig::default();
+        let buffer = crate::buffer::EventBuffer::new(config.buffer.clone()).await.unwrap();
+        let buffer_stats = buffer.get_stats().await;
+        let mut agent = AgentStats::new();
+        agent.events_processed = 100;
+        agent.events_sent = 90;
+        let mut collectors = vec![
+            CollectorStatus { name: "syslog".to_string(), running: true, dropped_events: 0 },
+            CollectorStatus { name: "file_monitor".to_string(), running: true, dropped_events: 0 },
+        ];
+
+        let mut reporter = HealthReporter::new(&SelfTelemetryConfig::default());
+        let mut report = |agent: &AgentStats, collectors: &[CollectorStatus], pipeline: &[LatencyHistogram]| {
+            let snapshot = HealthSnapshot { agent_id: "agent-1", agent, buffer: &buffer_stats, collectors, resources: None, pipeline };
+            reporter.event(&snapshot)
+        };
+        let mut flushes = LatencyHistogram {
+            stage: Stage::Flush,
+            key: "syslog".to_string(),
+            buckets: vec![0; crate::pipeline_metrics::BUCKET_BOUNDS_MICROS.len() + 1],
+            events: 500,
+            sum_micros: 0,
+            max_micros: 8_000_000,
+            window_seconds: 60.0,
+        };
+        flushes.buckets[4] = 9;
+        flushes.buckets[16] = 1;
+        let first = report(&agent, &collectors, std::slice::from_ref(&flushes));
+        assert_eq!(first.source, "securewatch_agent");
+        assert_eq!(first.level.as_deref(), Some("info"));
+        assert_eq!(first.fields["agent.events.processed_delta"], 100);
+        assert_eq!(first.fields["pipeline.latency"][0]["stage"], "flush");
+        assert_eq!(first.fields["pipeline.latency"][0]["p99_ms"], 8_000.0);
+
+        agent.events_processed = 150;
+        collectors[1].running = false;
+        flushes.buckets[4] = 19;
+        flushes.events = 600;
+        flushes.window_seconds = 120.0;
+        let second = report(&agent, &collectors, std::slice::from_ref(&flushes));
+        assert_eq!(second.level.as_deref(), Some("warning"));
+        assert_eq!(second.fields["agent.events.processed_delta"], 50);
+        assert_eq!(second.fields["collectors.stopped"], json!(["file_monitor"]));
+        assert!(second.message.contains("1/2 collectors running"));
+        // Only the interval's fast flushes count towards the second event's percentiles
+        assert_eq!(second.fields["pipeline.latency"][0]["samples"], 10);
+        assert_eq!(second.fields["pipeline.latency"][0]["p99_ms"], 1.0);
+        assert_eq!(second.fields["pipeline.latency"][0]["events_per_second"], 100.0 / 60.0);
+        assert!(!report(&agent, &collectors, std::slice::from_ref(&flushes)).fields.contains_key("pipeline.latency"));
+    }
+}
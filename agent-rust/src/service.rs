@@ -0,0 +1,379 @@
+// Service manager integration: registration with systemd or the Windows SCM, and control requests
+// Stop, reload and state dump requests reach the agent through `request_stop`, `request_reload` and
+// `request_state_dump`, whether they come from Unix signals or the Windows service control handler; on Windows
+// the agent runs under the Service Control Manager when started with --service, as the installer registers it
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Name the agent is registered under with the Windows Service Control Manager
+pub const SERVICE_NAME: &str = "SecureWatchAgent";
+pub const SERVICE_DISPLAY_NAME: &str = "SecureWatch Agent";
+pub const SERVICE_DESCRIPTION: &str = "Collects security events and forwards them to the SecureWatch SIEM";
+
+/// User-defined service control code that dumps internal state: `sc control SecureWatchAgent 128`
+pub const STATE_DUMP_CONTROL: u32 = 128;
+
+/// systemd unit name, shared with the installer
+pub const SYSTEMD_UNIT: &str = "securewatch-agent.service";
+pub const SYSTEMD_UNIT_DIR: &str = "/etc/systemd/system";
+
+/// What a service registration runs; all paths must be absolute since service managers
+/// don't start the agent in the directory it was installed from
+#[derive(Debug, Clone)]
+pub struct ServiceInstall {
+    pub executable: PathBuf,
+    pub config_path: PathBuf,
+    pub log_dir: PathBuf,
+    /// How long the service manager waits for the shutdown drain before killing the agent
+    pub stop_timeout: Duration,
+}
+
+impl ServiceInstall {
+    /// Registration for the running executable, leaving room for the shutdown drain deadline
+    pub fn for_current_exe(
+        config_path: &std::path::Path,
+        log_dir: &std::path::Path,
+        drain_deadline_seconds: u64,
+    ) -> std::io::Result<Self> {
+        Ok(Self {
+            executable: std::env::current_exe()?,
+            config_path: std::path::absolute(config_path)?,
+            log_dir: std::path::absolute(log_dir)?,
+            stop_timeout: Duration::from_secs(drain_deadline_seconds + 30),
+        })
+    }
+
+    pub fn systemd_unit(&self) -> String {
+        let working_directory = self.config_path.parent().unwrap_or(std::path::Path::new("/"));
+        format!(
+            r#"[Unit]
+Description={description}
+Documentation=https://github.com/itrimble/SecureWatch
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=simple
+ExecStart={executable} --config {config} --log-dir {log_dir}
+ExecReload=/bin/kill -HUP $MAINPID
+WorkingDirectory={working_directory}
+Restart=on-failure
+RestartSec=5
+TimeoutStopSec={stop_timeout}
+LimitNOFILE=65536
+
+[Install]
+WantedBy=multi-user.target
+"#,
+            description = SERVICE_DESCRIPTION,
+            executable = systemd_quote(&self.executable.to_string_lossy()),
+            config = systemd_quote(&self.config_path.to_string_lossy()),
+            log_dir = systemd_quote(&self.log_dir.to_string_lossy()),
+            working_directory = systemd_quote(&working_directory.to_string_lossy()),
+            stop_timeout = self.stop_timeout.as_secs(),
+        )
+    }
+}
+
+/// Quote an ExecStart argument; `%` would otherwise be expanded as a unit specifier
+fn systemd_quote(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('%', "%%");
+    format!("\"{}\"", escaped)
+}
+
+/// Register the agent with the platform service manager and start it; returns where it was registered
+#[cfg(target_os = "linux")]
+pub fn install_service(install: &ServiceInstall) -> Result<String, String> {
+    let unit_path = std::path::Path::new(SYSTEMD_UNIT_DIR).join(SYSTEMD_UNIT);
+    std::fs::write(&unit_path, install.systemd_unit())
+        .map_err(|e| format!("failed to write {}: {}", unit_path.display(), e))?;
+
+    for args in [&["daemon-reload"][..], &["enable", "--now", SYSTEMD_UNIT][..]] {
+        let output = std::process::Command::new("systemctl")
+            .args(args)
+            .output()
+            .map_err(|e| format!("failed to run systemctl: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "systemctl {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+    Ok(unit_path.display().to_string())
+}
+
+#[cfg(windows)]
+pub fn install_service(install: &ServiceInstall) -> Result<String, String> {
+    scm::install(install).map_err(|e| format!("failed to register the service: {}", e))?;
+    Ok(format!("Service Control Manager as {}", SERVICE_NAME))
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn install_service(_install: &ServiceInstall) -> Result<String, String> {
+    Err("service installation is supported on Linux (systemd) and Windows; use the installer on macOS".to_string())
+}
+
+fn stop_notify() -> &'static Notify {
+    static STOP: OnceLock<Notify> = OnceLock::new();
+    STOP.get_or_init(Notify::new)
+}
+
+fn reload_notify() -> &'static Notify {
+    static RELOAD: OnceLock<Notify> = OnceLock::new();
+    RELOAD.get_or_init(Notify::new)
+}
+
+fn state_dump_notify() -> &'static Notify {
+    static STATE_DUMP: OnceLock<Notify> = OnceLock::new();
+    STATE_DUMP.get_or_init(Notify::new)
+}
+
+/// Ask the running agent to shut down; a request made before the agent waits is not lost
+pub fn request_stop() {
+    stop_notify().notify_one();
+}
+
+/// Resolves once a service manager has asked the agent to stop
+pub async fn stop_requested() {
+    stop_notify().notified().await
+}
+
+/// Ask the running agent to re-read its configuration file; requests made while one is pending coalesce
+pub fn request_reload() {
+    reload_notify().notify_one();
+}
+
+pub async fn reload_requested() {
+    reload_notify().notified().await
+}
+
+/// Ask the running agent to log its internal state
+pub fn request_state_dump() {
+    state_dump_notify().notify_one();
+}
+
+pub async fn state_dump_requested() {
+    state_dump_notify().notified().await
+}
+
+/// Turn SIGHUP into reload requests and SIGUSR1 into state dump requests, the behaviour operators expect
+/// from Unix daemons; SIGTERM is handled with the other stop requests. Must be called within the runtime
+#[cfg(unix)]
+pub fn listen_for_signals() {
+    use tokio::signal::unix::{signal, SignalKind};
+    let signals: [(&str, SignalKind, fn()); 2] = [
+        ("SIGHUP", SignalKind::hangup(), request_reload),
+        ("SIGUSR1", SignalKind::user_defined1(), request_state_dump),
+    ];
+    for (name, kind, request) in signals {
+        match signal(kind) {
+            Ok(mut received) => {
+                tokio::spawn(async move {
+                    while received.recv().await.is_some() {
+                        tracing::info!("📶 {} received", name);
+                        request();
+                    }
+                });
+            }
+            Err(e) => tracing::warn!("⚠️ Could not listen for {}: {}", name, e),
+        }
+    }
+}
+
+/// Windows has no such signals; the service control handler makes the same requests
+#[cfg(not(unix))]
+pub fn listen_for_signals() {}
+
+#[cfg(windows)]
+mod scm {
+    use super::{
+        request_reload, request_state_dump, request_stop, ServiceInstall, SERVICE_DESCRIPTION, SERVICE_DISPLAY_NAME,
+        SERVICE_NAME, STATE_DUMP_CONTROL,
+    };
+    use std::ffi::OsString;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use windows_service::service::{
+        ServiceAccess, ServiceAction, ServiceActionType, ServiceControl, ServiceControlAccept,
+        ServiceErrorControl, ServiceExitCode, ServiceFailureActions, ServiceFailureResetPeriod, ServiceInfo,
+        ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+    use windows_service::{define_windows_service, service_dispatcher};
+
+    type ServiceBody = Box<dyn FnOnce() -> Result<(), String> + Send>;
+
+    /// The agent body handed over from `main`; the SCM calls `service_main` on its own thread
+    static BODY: Mutex<Option<ServiceBody>> = Mutex::new(None);
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    pub fn run(body: ServiceBody) -> windows_service::Result<()> {
+        *BODY.lock().unwrap_or_else(|e| e.into_inner()) = Some(body);
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+    }
+
+    /// Create or update the registration: delayed auto-start, restart on failure, started right away
+    pub fn install(install: &ServiceInstall) -> windows_service::Result<()> {
+        let info = ServiceInfo {
+            name: OsString::from(SERVICE_NAME),
+            display_name: OsString::from(SERVICE_DISPLAY_NAME),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: install.executable.clone(),
+            launch_arguments: vec![
+                OsString::from("--service"),
+                OsString::from("--config"),
+                install.config_path.clone().into_os_string(),
+                OsString::from("--log-dir"),
+                install.log_dir.clone().into_os_string(),
+            ],
+            dependencies: vec![],
+            account_name: None,
+            account_password: None,
+        };
+        let manager = ServiceManager::local_computer(
+            None::<&str>,
+            ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+        )?;
+        let access = ServiceAccess::QUERY_CONFIG | ServiceAccess::CHANGE_CONFIG | ServiceAccess::QUERY_STATUS | ServiceAccess::START;
+        let service = match manager.open_service(SERVICE_NAME, access) {
+            Ok(service) => {
+                service.change_config(&info)?;
+                service
+            }
+            Err(_) => manager.create_service(&info, access)?,
+        };
+        service.set_description(SERVICE_DESCRIPTION)?;
+        let restart = |secs| ServiceAction { action_type: ServiceActionType::Restart, delay: Duration::from_secs(secs) };
+        service.update_failure_actions(ServiceFailureActions {
+            reset_period: ServiceFailureResetPeriod::After(Duration::from_secs(24 * 60 * 60)),
+            reboot_msg: None,
+            command: None,
+            actions: Some(vec![restart(5), restart(30), restart(120)]),
+        })?;
+        service.set_failure_actions_on_non_crash_failures(true)?;
+        service.set_delayed_auto_start(true)?;
+        if service.query_status()?.current_state == ServiceState::Stopped {
+            service.start::<&str>(&[])?;
+        }
+        Ok(())
+    }
+
+    fn status(state: ServiceState, exit_code: ServiceExitCode, wait_hint: Duration) -> ServiceStatus {
+        ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: if state == ServiceState::Running {
+                ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN | ServiceControlAccept::PARAM_CHANGE
+            } else {
+                ServiceControlAccept::empty()
+            },
+            exit_code,
+            checkpoint: 0,
+            wait_hint,
+            process_id: None,
+        }
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        let handler = |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                request_stop();
+                ServiceControlHandlerResult::NoError
+            }
+            // `sc control SecureWatchAgent paramchange`, the SCM's equivalent of SIGHUP
+            ServiceControl::ParamChange => {
+                request_reload();
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::UserEvent(code) if code.to_raw() == STATE_DUMP_CONTROL => {
+                request_state_dump();
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        };
+        let status_handle = match service_control_handler::register(SERVICE_NAME, handler) {
+            Ok(handle) => handle,
+            Err(e) => {
+                tracing::error!(error = %e, "❌ Failed to register the service control handler");
+                return;
+            }
+        };
+        let _ = status_handle.set_service_status(status(ServiceState::Running, ServiceExitCode::NO_ERROR, Duration::ZERO));
+
+        let body = BODY.lock().unwrap_or_else(|e| e.into_inner()).take();
+        let result = body.map_or(Ok(()), |body| body());
+
+        // A non-zero exit code lets the configured recovery actions restart the agent
+        let exit_code = match &result {
+            Ok(()) => ServiceExitCode::NO_ERROR,
+            Err(e) => {
+                tracing::error!(error = %e, "❌ Agent service stopped with an error");
+                ServiceExitCode::ServiceSpecific(1)
+            }
+        };
+        let _ = status_handle.set_service_status(status(ServiceState::Stopped, exit_code, Duration::ZERO));
+    }
+}
+
+/// Run `body` as the Windows service's main function; blocks until the service stops
+#[cfg(windows)]
+pub fn run_windows_service<F>(body: F) -> Result<(), String>
+where
+    F: FnOnce() -> Result<(), String> + Send + 'static,
+{
+    scm::run(Box::new(body)).map_err(|e| format!("failed to connect to the service control manager: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_systemd_unit_quotes_paths_and_covers_drain() {
+        let install = ServiceInstall {
+            executable: PathBuf::from("/opt/secure watch/securewatch-agent"),
+            config_path: PathBuf::from("/etc/securewatch/config.toml"),
+            log_dir: PathBuf::from("/var/log/securewatch-100%"),
+            stop_timeout: Duration::from_secs(45),
+        };
+        let unit = install.systemd_unit();
+        assert!(unit.contains(
+            "ExecStart=\"/opt/secure watch/securewatch-agent\" --config \"/etc/securewatch/config.toml\" --log-dir \"/var/log/securewatch-100%%\"\n"
+        ));
+        assert!(unit.contains("WorkingDirectory=\"/etc/securewatch\"\n"));
+        assert!(unit.contains("TimeoutStopSec=45\n"));
+        assert!(unit.contains("ExecReload=/bin/kill -HUP $MAINPID\n"));
+        assert!(unit.contains("WantedBy=multi-user.target"));
+    }
+
+    #[tokio::test]
+    async fn test_stop_request_made_before_waiting_is_kept() {
+        request_stop();
+        tokio::time::timeout(std::time::Duration::from_secs(1), stop_requested())
+            .await
+            .expect("stop request was lost");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_signals_become_reload_and_state_dump_requests() {
+        listen_for_signals();
+        let pid = std::process::id().to_string();
+        let send = |signal: &str| std::process::Command::new("kill").args([signal, pid.as_str()]).status().unwrap();
+
+        send("-HUP");
+        tokio::time::timeout(Duration::from_secs(5), reload_requested()).await.expect("SIGHUP did not request a reload");
+        send("-USR1");
+        tokio::time::timeout(Duration::from_secs(5), state_dump_requested()).await.expect("SIGUSR1 did not request a state dump");
+    }
+}
@@ -0,0 +1,335 @@
+// Shutdown drain phase: ship high-priority pending events before the agent stops
+// Pending events are split by priority; high-priority ones are shipped until a deadline while the
+// circuit breaker allows it, and everything left unsent is persisted for the next start
+
+use crate::buffer::EventBuffer;
+use crate::circuit_breaker::CircuitBreakerState;
+use crate::errors::TransportError;
+use crate::parsers::ParsedEvent;
+use crate::transport::SecureTransport;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use tokio::sync::watch;
+use tokio::time::{timeout, Duration};
+use tracing::{debug, info, warn};
+
+/// Shutdown drain configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShutdownDrainConfig {
+    /// Ship priority events on stop; when disabled pending events are only flushed locally
+    pub enabled: bool,
+    /// Time allowed for shipping before the rest is persisted
+    pub deadline_seconds: u64,
+    /// Event levels (case-insensitive) shipped first
+    pub priority_levels: Vec<String>,
+    /// Event sources whose events are always shipped first
+    pub priority_sources: Vec<String>,
+    /// Also ship high-priority events persisted by earlier spills
+    pub include_persisted: bool,
+    /// Upper bound on persisted events pulled back for shipping
+    pub max_persisted_events: usize,
+}
+
+impl Default for ShutdownDrainConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            deadline_seconds: 15,
+            priority_levels: ["emergency", "alert", "critical", "fatal", "error"]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            priority_sources: Vec::new(),
+            include_persisted: true,
+            max_persisted_events: 5_000,
+        }
+    }
+}
+
+impl ShutdownDrainConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.deadline_seconds == 0 || self.deadline_seconds > 600 {
+            errors.push("deadline_seconds must be between 1 and 600".to_string());
+        }
+        if self.priority_levels.is_empty() && self.priority_sources.is_empty() {
+            errors.push("At least one priority level or source is required".to_string());
+        }
+        errors
+    }
+
+    pub fn is_priority(&self, event: &ParsedEvent) -> bool {
+        crate::alert_rules::is_alert(event)
+            || event.level.as_deref().is_some_and(|level| self.priority_levels.iter().any(|p| p.eq_ignore_ascii_case(level)))
+            || self.priority_sources.contains(&event.source)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrainPhase {
+    Collecting,
+    Shipping,
+    Persisting,
+    Complete,
+}
+
+/// Progress published while draining
+#[derive(Debug, Clone, Serialize)]
+pub struct DrainProgress {
+    pub phase: DrainPhase,
+    pub priority_events: usize,
+    pub shipped: usize,
+    pub persisted: usize,
+    pub dropped: usize,
+    pub elapsed_ms: u64,
+    pub remaining_ms: u64,
+}
+
+/// Outcome of the drain phase
+#[derive(Debug, Clone, Serialize)]
+pub struct DrainReport {
+    pub priority_events: usize,
+    pub other_events: usize,
+    pub shipped: usize,
+    pub persisted: usize,
+    pub dropped: usize,
+    /// Events in batches the server refused on every attempt, kept in the buffer's dead-letter table
+    pub dead_lettered: usize,
+    pub deadline_hit: bool,
+    /// Why shipping stopped before every priority event was sent
+    pub stopped_reason: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+/// Coordinates buffer, transport and circuit breaker during stop
+pub struct ShutdownDrain {
+    config: ShutdownDrainConfig,
+    progress: watch::Sender<DrainProgress>,
+}
+
+impl ShutdownDrain {
+    pub fn new(config: ShutdownDrainConfig) -> Self {
+        let (progress, _) = watch::channel(DrainProgress {
+            phase: DrainPhase::Collecting,
+            priority_events: 0,
+            shipped: 0,
+            persisted: 0,
+            dropped: 0,
+            elapsed_ms: 0,
+            remaining_ms: config.deadline_seconds * 1000,
+        });
+        Self { config, progress }
+    }
+
+    /// Progress updates, e.g. for a service control handler reporting stop-pending checkpoints
+    pub fn subscribe(&self) -> watch::Receiver<DrainProgress> {
+        self.progress.subscribe()
+    }
+
+    pub async fn run(&self, buffer: &EventBuffer, transport: Option<&SecureTransport>) -> DrainReport {
+        let started = Instant::now();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(self.config.deadline_seconds);
+        // Ask the service manager for the drain deadline plus headroom to persist
+        notify_service_manager(&format!(
+            "EXTEND_TIMEOUT_USEC={}\nSTATUS=Draining pending events",
+            (self.config.deadline_seconds + 10) * 1_000_000
+        ));
+
+        let (mut priority, mut other): (Vec<ParsedEvent>, Vec<ParsedEvent>) =
+            buffer.drain_pending().await.into_iter().partition(|e| self.config.is_priority(e));
+        if self.config.include_persisted {
+            // Persisted events are older, so they go out first
+            match buffer.take_persisted_matching(&self.config.priority_levels, &self.config.priority_sources, self.config.max_persisted_events).await {
+                Ok(persisted) => {
+                    priority.splice(0..0, persisted);
+                }
+                Err(e) => warn!("⚠️ Failed to load persisted priority events for shutdown drain: {}", e),
+            }
+        }
+
+        let mut report = DrainReport {
+            priority_events: priority.len(),
+            other_events: other.len(),
+            shipped: 0,
+            persisted: 0,
+            dropped: 0,
+            dead_lettered: 0,
+            deadline_hit: false,
+            stopped_reason: None,
+            elapsed_ms: 0,
+        };
+        info!("🚰 Shutdown drain: {} priority and {} other pending events, deadline {}s",
+              report.priority_events, report.other_events, self.config.deadline_seconds);
+
+        self.publish(DrainPhase::Shipping, &report, started, deadline);
+        match transport {
+            Some(transport) => {
+                report.stopped_reason = self.ship(transport, &mut priority, &mut report, started, deadline).await;
+                // Without persistence anything not shipped is lost, so spend the remaining time on it
+                if report.stopped_reason.is_none() && !buffer.is_persistent() {
+                    report.stopped_reason = self.ship(transport, &mut other, &mut report, started, deadline).await;
+                }
+            }
+            None => report.stopped_reason = Some("transport unavailable".to_string()),
+        }
+
+        let unsent: Vec<ParsedEvent> = priority.into_iter().chain(other).collect();
+        let unsent_count = unsent.len();
+        self.publish(DrainPhase::Persisting, &report, started, deadline);
+        report.persisted = match buffer.persist_events(unsent).await {
+            Ok(persisted) => persisted,
+            Err(e) => {
+                warn!("⚠️ Failed to persist unsent events during shutdown: {}", e);
+                0
+            }
+        };
+        report.dropped = unsent_count.saturating_sub(report.persisted);
+        report.elapsed_ms = started.elapsed().as_millis() as u64;
+        self.publish(DrainPhase::Complete, &report, started, deadline);
+
+        match &report.stopped_reason {
+            Some(reason) => warn!("🚰 Shutdown drain stopped early ({}): shipped {}, persisted {}, dead-lettered {}, dropped {} in {}ms",
+                                  reason, report.shipped, report.persisted, report.dead_lettered, report.dropped, report.elapsed_ms),
+            None => info!("🚰 Shutdown drain complete: shipped {}, persisted {}, dead-lettered {}, dropped {} in {}ms",
+                          report.shipped, report.persisted, report.dead_lettered, report.dropped, report.elapsed_ms),
+        }
+        report
+    }
+
+    /// Ship `events` one transport batch at a time, leaving whatever is unsent in place.
+    /// A batch the server refuses is dead-lettered by the transport so it doesn't block the rest
+    async fn ship(&self, transport: &SecureTransport, events: &mut Vec<ParsedEvent>, report: &mut DrainReport, started: Instant, deadline: tokio::time::Instant) -> Option<String> {
+        let transport_stats = transport.get_stats().await;
+        let batch_size = transport_stats.batch_size.max(1);
+
+        while !events.is_empty() {
+            if transport.get_circuit_breaker_state().await == CircuitBreakerState::Open {
+                return Some("circuit breaker open".to_string());
+            }
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                report.deadline_hit = true;
+                return Some("deadline reached".to_string());
+            }
+
+            let batch: Vec<ParsedEvent> = events.drain(..batch_size.min(events.len())).collect();
+            let count = batch.len();
+            match timeout(deadline - now, transport.send_batch(batch.clone())).await {
+                Ok(Ok(())) => {
+                    report.shipped += count;
+                    debug!("🚰 Shipped {} events during shutdown drain", count);
+                    self.publish(DrainPhase::Shipping, report, started, deadline);
+                }
+                // The transport kept the batches the server refused; anything else it sent was shipped
+                Ok(Err(TransportError::DeadLettered { events: kept, .. })) => {
+                    report.dead_lettered += kept;
+                    report.shipped += count.saturating_sub(kept);
+                }
+                Ok(Err(e)) => {
+                    events.splice(0..0, batch);
+                    return Some(format!("transport error: {}", e));
+                }
+                Err(_) => {
+                    events.splice(0..0, batch);
+                    report.deadline_hit = true;
+                    return Some("deadline reached".to_string());
+                }
+            }
+        }
+        None
+    }
+
+    fn publish(&self, phase: DrainPhase, report: &DrainReport, started: Instant, deadline: tokio::time::Instant) {
+        let progress = DrainProgress {
+            phase,
+            priority_events: report.priority_events,
+            shipped: report.shipped,
+            persisted: report.persisted,
+            dropped: report.dropped,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            remaining_ms: deadline.saturating_duration_since(tokio::time::Instant::now()).as_millis() as u64,
+        };
+        notify_service_manager(&format!(
+            "STATUS=Shutdown drain {:?}: {}/{} priority events shipped, {} persisted",
+            phase, progress.shipped, progress.priority_events, progress.persisted
+        ));
+        self.progress.send_replace(progress);
+    }
+}
+
+/// Send a state update to systemd when running under a `Type=notify` unit
+#[cfg(unix)]
+pub fn notify_service_manager(state: &str) -> bool {
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return false;
+    };
+    let Ok(socket) = std::os::unix::net::UnixDatagram::unbound() else {
+        return false;
+    };
+
+    #[cfg(target_os = "linux")]
+    if let Some(name) = path.to_str().and_then(|p| p.strip_prefix('@')) {
+        use std::os::linux::net::SocketAddrExt;
+        return std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())
+            .and_then(|addr| socket.send_to_addr(state.as_bytes(), &addr))
+            .is_ok();
+    }
+    socket.send_to(state.as_bytes(), path).is_ok()
+}
+
+/// Windows service status is reported by the service control handler from `ShutdownDrain::subscribe`
+#[cfg(not(unix))]
+pub fn notify_service_manager(_state: &str) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn event(source: &str, level: Option<&str>) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: source.to_string(),
+            level: level.map(|l| l.to_string()),
+            message: "test".to_string(),
+            fields: HashMap::new(),
+            raw_data: "test".into(),
+            parser_name: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_priority_classification() {
+        let config = ShutdownDrainConfig {
+            priority_sources: vec!["audit".to_string()],
+            ..Default::default()
+        };
+        assert!(config.is_priority(&event("syslog", Some("ERROR"))));
+        assert!(config.is_priority(&event("audit", Some("info"))));
+        assert!(!config.is_priority(&event("syslog", Some("warning"))));
+        assert!(!config.is_priority(&event("syslog", None)));
+        assert!(config.validate().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_notify_service_manager_sends_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify.sock");
+        let listener = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+        std::env::set_var("NOTIFY_SOCKET", &path);
+        let sent = notify_service_manager("STATUS=Draining");
+        std::env::remove_var("NOTIFY_SOCKET");
+        assert!(sent);
+
+        let mut buf = [0u8; 64];
+        let len = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"STATUS=Draining");
+        assert!(!notify_service_manager("STATUS=unset"));
+    }
+}
@@ -0,0 +1,758 @@
+// Sigma rule evaluation at the edge
+// Loads Sigma detection rules (YAML) and evaluates them against parsed events in-stream. Matching events are
+// tagged with the rule ids and highest level, and each match can also be emitted as a synthetic alert event
+// that rides the alert priority lane. Supports selections, keyword lists, the common value modifiers and
+// `and`/`or`/`not`/`1 of`/`all of` conditions; rules using aggregations or unknown modifiers are skipped
+
+use crate::alert_rules::{ALERT_RULES_FIELD, ALERT_SEVERITY_FIELD};
+use crate::parsers::ParsedEvent;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use serde_yaml::Value as Yaml;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{debug, info, warn};
+
+/// Field holding the ids of the Sigma rules an event matched
+pub const SIGMA_RULE_IDS_FIELD: &str = "sigma.rule_ids";
+/// Field holding the titles of the Sigma rules an event matched
+pub const SIGMA_RULE_TITLES_FIELD: &str = "sigma.rule_titles";
+/// Field holding the highest level among matched Sigma rules
+pub const SIGMA_LEVEL_FIELD: &str = "sigma.level";
+/// Source of synthetic alert events
+pub const SIGMA_ALERT_SOURCE: &str = "sigma";
+
+const LEVELS: [&str; 5] = ["informational", "low", "medium", "high", "critical"];
+
+/// Sigma detection configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SigmaConfig {
+    pub enabled: bool,
+    /// Rule files or directories (searched recursively for .yml/.yaml files)
+    pub rule_paths: Vec<String>,
+    /// Tag matching events, emit a synthetic alert event per match, or both
+    pub output: SigmaOutput,
+    /// Rules below this level are not loaded
+    pub min_level: String,
+    /// Rule logsource (`product:windows`, `service:sshd`, `category:process_creation`) to agent event sources.
+    /// Rules whose logsource has no mapping are evaluated against every source
+    pub logsources: HashMap<String, Vec<String>>,
+    /// Sigma field names to event field names, e.g. `CommandLine = "process.command_line"`
+    pub field_mapping: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SigmaOutput {
+    Tag,
+    AlertEvent,
+    Both,
+}
+
+impl Default for SigmaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rule_paths: Vec::new(),
+            output: SigmaOutput::Both,
+            min_level: "low".to_string(),
+            logsources: HashMap::from([
+                ("product:windows".to_string(), vec!["windows_event".to_string()]),
+                ("product:linux".to_string(), vec!["syslog".to_string(), "file_monitor".to_string()]),
+            ]),
+            field_mapping: HashMap::new(),
+        }
+    }
+}
+
+impl SigmaConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.rule_paths.is_empty() {
+            errors.push("at least one rule path is required".to_string());
+        }
+        if !LEVELS.contains(&self.min_level.to_ascii_lowercase().as_str()) {
+            errors.push(format!("min_level must be one of {}", LEVELS.join(", ")));
+        }
+        for key in self.logsources.keys() {
+            if !matches!(key.split_once(':'), Some(("product" | "service" | "category", value)) if !value.is_empty()) {
+                errors.push(format!("logsource key '{}' must be product:<name>, service:<name> or category:<name>", key));
+            }
+        }
+        errors
+    }
+}
+
+/// A value test from a detection item
+enum ValueMatcher {
+    /// Sigma string with `*`/`?` wildcards (and contains/startswith/endswith), case-insensitive
+    Pattern(Regex),
+    /// `|re`, case-sensitive unless the expression says otherwise
+    Regex(Regex),
+    /// `|lt`, `|lte`, `|gt`, `|gte` against the field parsed as a number
+    Compare(fn(f64, f64) -> bool, f64),
+    Cidr(IpAddr, u8),
+    Exists(bool),
+    /// `field: null` matches a missing or empty field
+    Null,
+}
+
+impl ValueMatcher {
+    fn matches(&self, value: Option<&str>) -> bool {
+        match (self, value) {
+            (ValueMatcher::Exists(expected), value) => value.is_some() == *expected,
+            (ValueMatcher::Null, value) => value.is_none_or(str::is_empty),
+            (_, None) => false,
+            (ValueMatcher::Pattern(regex) | ValueMatcher::Regex(regex), Some(value)) => regex.is_match(value),
+            (ValueMatcher::Compare(op, expected), Some(value)) => value.trim().parse().is_ok_and(|actual| op(actual, *expected)),
+            (ValueMatcher::Cidr(network, prefix), Some(value)) => value.trim().parse().is_ok_and(|ip| in_cidr(ip, *network, *prefix)),
+        }
+    }
+}
+
+/// One `field|modifiers: values` entry; values are alternatives unless `|all` is given
+struct FieldMatcher {
+    field: String,
+    values: Vec<ValueMatcher>,
+    all: bool,
+}
+
+enum Selection {
+    /// Alternatives, each a conjunction of field matchers
+    Fields(Vec<Vec<FieldMatcher>>),
+    /// Values searched for anywhere in the message and raw log line
+    Keywords(Vec<ValueMatcher>),
+}
+
+enum Condition {
+    Selection(String),
+    Not(Box<Condition>),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    AnyOf(Vec<String>),
+    AllOf(Vec<String>),
+}
+
+struct SigmaRule {
+    id: String,
+    title: String,
+    level: usize,
+    tags: Vec<String>,
+    description: Option<String>,
+    /// Event sources the rule is restricted to by its logsource; None evaluates every source
+    sources: Option<Vec<String>>,
+    selections: HashMap<String, Selection>,
+    condition: Condition,
+    matches: AtomicU64,
+}
+
+/// Sigma engine metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct SigmaStats {
+    pub rules_loaded: usize,
+    /// Rule file and reason for every rule that could not be loaded
+    pub rules_skipped: Vec<(String, String)>,
+    pub events_matched: u64,
+    pub alerts_emitted: u64,
+    pub matches_by_rule: HashMap<String, u64>,
+}
+
+/// Evaluates loaded Sigma rules against parsed events
+pub struct SigmaEngine {
+    rules: Vec<SigmaRule>,
+    output: SigmaOutput,
+    field_mapping: HashMap<String, String>,
+    rules_skipped: Vec<(String, String)>,
+    events_matched: AtomicU64,
+    alerts_emitted: AtomicU64,
+}
+
+impl SigmaEngine {
+    /// Load every rule under the configured paths; unreadable or unsupported rules are skipped and reported
+    pub fn load(config: &SigmaConfig) -> Result<Self, String> {
+        if let Some(error) = config.validate().into_iter().next() {
+            return Err(error);
+        }
+        let mut files = Vec::new();
+        for path in &config.rule_paths {
+            collect_rule_files(Path::new(path), &mut files).map_err(|e| format!("cannot read rule path {}: {}", path, e))?;
+        }
+
+        let mut documents = Vec::new();
+        for file in files {
+            match std::fs::read_to_string(&file) {
+                Ok(content) => documents.push((file.display().to_string(), content)),
+                Err(e) => warn!("⚠️ Cannot read Sigma rule {}: {}", file.display(), e),
+            }
+        }
+        let engine = Self::from_documents(config, &documents);
+        info!("🛡️ Sigma rules loaded: {} ({} skipped)", engine.rules.len(), engine.rules_skipped.len());
+        Ok(engine)
+    }
+
+    /// Build an engine from (origin, YAML) pairs
+    pub fn from_documents(config: &SigmaConfig, documents: &[(String, String)]) -> Self {
+        let min_level = level_rank(&config.min_level).unwrap_or(1);
+        let mut rules = Vec::new();
+        let mut rules_skipped = Vec::new();
+        for (origin, content) in documents {
+            for document in serde_yaml::Deserializer::from_str(content) {
+                let compiled = Yaml::deserialize(document)
+                    .map_err(|e| format!("invalid YAML: {}", e))
+                    .and_then(|rule| compile_rule(&rule, config));
+                match compiled {
+                    Ok(Some(rule)) if rule.level >= min_level => rules.push(rule),
+                    Ok(_) => {}
+                    Err(reason) => {
+                        warn!("⚠️ Skipping Sigma rule in {}: {}", origin, reason);
+                        rules_skipped.push((origin.clone(), reason));
+                    }
+                }
+            }
+        }
+        Self {
+            rules,
+            output: config.output,
+            field_mapping: config.field_mapping.clone(),
+            rules_skipped,
+            events_matched: AtomicU64::new(0),
+            alerts_emitted: AtomicU64::new(0),
+        }
+    }
+
+    /// Tag the event with every rule it matches; returns the synthetic alert events to ship alongside it
+    pub fn evaluate(&self, event: &mut ParsedEvent) -> Vec<ParsedEvent> {
+        // Never run detections over our own alerts
+        if event.source == SIGMA_ALERT_SOURCE {
+            return Vec::new();
+        }
+        let matched: Vec<&SigmaRule> = self.rules.iter()
+            .filter(|rule| rule.sources.as_ref().is_none_or(|sources| sources.contains(&event.source)))
+            .filter(|rule| self.condition_matches(rule, &rule.condition, event))
+            .collect();
+        if matched.is_empty() {
+            return Vec::new();
+        }
+        self.events_matched.fetch_add(1, Ordering::Relaxed);
+        for rule in &matched {
+            rule.matches.fetch_add(1, Ordering::Relaxed);
+        }
+        let level = matched.iter().map(|rule| rule.level).max().unwrap_or_default();
+        debug!("🛡️ Sigma rule(s) {} matched {} event from {}",
+               matched.iter().map(|rule| rule.title.as_str()).collect::<Vec<_>>().join(", "), LEVELS[level], event.source);
+
+        let alerts = match self.output {
+            SigmaOutput::Tag => Vec::new(),
+            SigmaOutput::AlertEvent | SigmaOutput::Both => matched.iter().map(|rule| alert_event(rule, event)).collect(),
+        };
+        self.alerts_emitted.fetch_add(alerts.len() as u64, Ordering::Relaxed);
+
+        if self.output != SigmaOutput::AlertEvent {
+            event.fields.insert(SIGMA_RULE_IDS_FIELD.to_string(), json!(matched.iter().map(|rule| &rule.id).collect::<Vec<_>>()));
+            event.fields.insert(SIGMA_RULE_TITLES_FIELD.to_string(), json!(matched.iter().map(|rule| &rule.title).collect::<Vec<_>>()));
+            event.fields.insert(SIGMA_LEVEL_FIELD.to_string(), json!(LEVELS[level]));
+        }
+        alerts
+    }
+
+    pub fn get_stats(&self) -> SigmaStats {
+        SigmaStats {
+            rules_loaded: self.rules.len(),
+            rules_skipped: self.rules_skipped.clone(),
+            events_matched: self.events_matched.load(Ordering::Relaxed),
+            alerts_emitted: self.alerts_emitted.load(Ordering::Relaxed),
+            matches_by_rule: self.rules.iter()
+                .map(|rule| (rule.id.clone(), rule.matches.load(Ordering::Relaxed)))
+                .collect(),
+        }
+    }
+
+    fn condition_matches(&self, rule: &SigmaRule, condition: &Condition, event: &ParsedEvent) -> bool {
+        match condition {
+            Condition::Selection(name) => rule.selections.get(name).is_some_and(|selection| self.selection_matches(selection, event)),
+            Condition::Not(inner) => !self.condition_matches(rule, inner, event),
+            Condition::And(parts) => parts.iter().all(|part| self.condition_matches(rule, part, event)),
+            Condition::Or(parts) => parts.iter().any(|part| self.condition_matches(rule, part, event)),
+            Condition::AnyOf(names) => names.iter().any(|name| self.condition_matches(rule, &Condition::Selection(name.clone()), event)),
+            Condition::AllOf(names) => names.iter().all(|name| self.condition_matches(rule, &Condition::Selection(name.clone()), event)),
+        }
+    }
+
+    fn selection_matches(&self, selection: &Selection, event: &ParsedEvent) -> bool {
+        match selection {
+            Selection::Fields(alternatives) => alternatives.iter().any(|fields| {
+                fields.iter().all(|matcher| {
+                    let values = self.field_values(event, &matcher.field);
+                    let test = |value: &ValueMatcher| match values.is_empty() {
+                        true => value.matches(None),
+                        false => values.iter().any(|v| value.matches(Some(v))),
+                    };
+                    if matcher.all {
+                        matcher.values.iter().all(test)
+                    } else {
+                        matcher.values.iter().any(test)
+                    }
+                })
+            }),
+            Selection::Keywords(keywords) => keywords.iter().any(|keyword| {
+                keyword.matches(Some(&event.message)) || keyword.matches(Some(&event.raw_data))
+            }),
+        }
+    }
+
+    /// String forms of a field; arrays contribute every element
+    fn field_values(&self, event: &ParsedEvent, field: &str) -> Vec<String> {
+        let field = self.field_mapping.get(field).map(String::as_str).unwrap_or(field);
+        let value = match field {
+            "message" => return vec![event.message.clone()],
+            "source" => return vec![event.source.clone()],
+            "level" => return event.level.iter().cloned().collect(),
+            field => event.fields.get(field),
+        };
+        match value {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(items)) => items.iter().map(json_string).collect(),
+            Some(value) => vec![json_string(value)],
+        }
+    }
+}
+
+fn json_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn level_rank(level: &str) -> Option<usize> {
+    LEVELS.iter().position(|l| l.eq_ignore_ascii_case(level))
+}
+
+fn collect_rule_files(path: &Path, files: &mut Vec<std::path::PathBuf>) -> std::io::Result<()> {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries: Vec<_> = std::fs::read_dir(path)?.filter_map(Result::ok).map(|entry| entry.path()).collect();
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            collect_rule_files(&entry, files)?;
+        } else if matches!(entry.extension().and_then(|e| e.to_str()), Some("yml" | "yaml")) {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+/// Compile one YAML document; Ok(None) for documents that are not standalone rules
+fn compile_rule(rule: &Yaml, config: &SigmaConfig) -> Result<Option<SigmaRule>, String> {
+    // Rule collections (action: global/reset/repeat) and correlation rules are not evaluated at the edge
+    if rule.get("action").is_some() || rule.get("correlation").is_some() {
+        return Ok(None);
+    }
+    let title = rule.get("title").and_then(Yaml::as_str).ok_or("rule has no title")?.to_string();
+    let id = rule.get("id").and_then(Yaml::as_str).map(str::to_string).unwrap_or_else(|| title.clone());
+    let level = match rule.get("level").and_then(Yaml::as_str) {
+        Some(level) => level_rank(level).ok_or_else(|| format!("rule '{}': unknown level '{}'", title, level))?,
+        None => 2,
+    };
+    let detection = rule.get("detection").and_then(Yaml::as_mapping).ok_or_else(|| format!("rule '{}' has no detection", title))?;
+
+    let mut selections = HashMap::new();
+    let mut condition_text = Vec::new();
+    for (name, body) in detection {
+        let name = name.as_str().ok_or_else(|| format!("rule '{}': selection names must be strings", title))?;
+        match name {
+            "condition" => match body {
+                Yaml::String(condition) => condition_text.push(condition.clone()),
+                Yaml::Sequence(conditions) => condition_text.extend(conditions.iter().filter_map(Yaml::as_str).map(str::to_string)),
+                _ => return Err(format!("rule '{}': condition must be a string or list", title)),
+            },
+            // Correlation window of legacy aggregation rules
+            "timeframe" => {}
+            name => {
+                let selection = compile_selection(body).map_err(|e| format!("rule '{}', selection '{}': {}", title, name, e))?;
+                selections.insert(name.to_string(), selection);
+            }
+        }
+    }
+    if condition_text.is_empty() {
+        return Err(format!("rule '{}' has no condition", title));
+    }
+    let names: Vec<String> = selections.keys().cloned().collect();
+    let mut conditions = Vec::new();
+    for text in &condition_text {
+        if text.contains('|') {
+            return Err(format!("rule '{}': aggregation conditions are not supported", title));
+        }
+        conditions.push(parse_condition(text, &names).map_err(|e| format!("rule '{}': {}", title, e))?);
+    }
+    let condition = if conditions.len() == 1 { conditions.remove(0) } else { Condition::Or(conditions) };
+
+    Ok(Some(SigmaRule {
+        id,
+        title,
+        level,
+        tags: rule.get("tags").and_then(Yaml::as_sequence)
+            .map(|tags| tags.iter().filter_map(Yaml::as_str).map(str::to_string).collect())
+            .unwrap_or_default(),
+        description: rule.get("description").and_then(Yaml::as_str).map(str::to_string),
+        sources: rule_sources(rule.get("logsource"), &config.logsources),
+        selections,
+        condition,
+        matches: AtomicU64::new(0),
+    }))
+}
+
+/// Agent sources mapped from a rule's logsource; None when nothing in the logsource is mapped
+fn rule_sources(logsource: Option<&Yaml>, mapping: &HashMap<String, Vec<String>>) -> Option<Vec<String>> {
+    let logsource = logsource?;
+    let mut sources: Option<Vec<String>> = None;
+    for key in ["category", "service", "product"] {
+        let Some(value) = logsource.get(key).and_then(Yaml::as_str) else { continue };
+        if let Some(mapped) = mapping.get(&format!("{}:{}", key, value.to_ascii_lowercase())) {
+            // Each mapped attribute narrows the sources further
+            sources = Some(match sources {
+                Some(current) => current.into_iter().filter(|s| mapped.contains(s)).collect(),
+                None => mapped.clone(),
+            });
+        }
+    }
+    sources
+}
+
+fn compile_selection(body: &Yaml) -> Result<Selection, String> {
+    match body {
+        Yaml::Mapping(_) => Ok(Selection::Fields(vec![compile_fields(body)?])),
+        Yaml::Sequence(items) if items.iter().all(Yaml::is_mapping) => {
+            Ok(Selection::Fields(items.iter().map(compile_fields).collect::<Result<_, _>>()?))
+        }
+        Yaml::Sequence(items) => Ok(Selection::Keywords(
+            items.iter().map(|item| compile_value(item, &["contains"])).collect::<Result<_, _>>()?,
+        )),
+        scalar => Ok(Selection::Keywords(vec![compile_value(scalar, &["contains"])?])),
+    }
+}
+
+fn compile_fields(body: &Yaml) -> Result<Vec<FieldMatcher>, String> {
+    let mapping = body.as_mapping().ok_or("expected a mapping")?;
+    let mut fields = Vec::new();
+    for (key, values) in mapping {
+        let key = key.as_str().ok_or("field names must be strings")?;
+        let mut parts = key.split('|');
+        let field = parts.next().unwrap_or_default().to_string();
+        let modifiers: Vec<&str> = parts.collect();
+        let all = modifiers.contains(&"all");
+        let modifiers: Vec<&str> = modifiers.into_iter().filter(|m| *m != "all").collect();
+        let values = match values {
+            Yaml::Sequence(items) => items.iter().map(|item| compile_value(item, &modifiers)).collect::<Result<_, _>>()?,
+            value => vec![compile_value(value, &modifiers)?],
+        };
+        fields.push(FieldMatcher { field, values, all });
+    }
+    Ok(fields)
+}
+
+fn compile_value(value: &Yaml, modifiers: &[&str]) -> Result<ValueMatcher, String> {
+    let text = match value {
+        Yaml::Null => return Ok(ValueMatcher::Null),
+        Yaml::String(s) => s.clone(),
+        Yaml::Number(n) => n.to_string(),
+        Yaml::Bool(b) => b.to_string(),
+        _ => return Err("values must be scalars".to_string()),
+    };
+    let (position, rest): (Option<&str>, Vec<&str>) = match modifiers {
+        [] => (None, Vec::new()),
+        [first, rest @ ..] => (Some(*first), rest.to_vec()),
+    };
+    if let Some(unsupported) = rest.iter().find(|m| **m != "cased") {
+        return Err(format!("unsupported modifier combination ending in '{}'", unsupported));
+    }
+    let cased = rest.contains(&"cased") || position == Some("cased");
+    let pattern = |prefix: &str, suffix: &str| -> Result<ValueMatcher, String> {
+        let flags = if cased { "(?s)" } else { "(?is)" };
+        Regex::new(&format!("{}^{}{}{}$", flags, prefix, wildcard_regex(&text), suffix))
+            .map(ValueMatcher::Pattern)
+            .map_err(|e| e.to_string())
+    };
+    match position {
+        None | Some("cased") => pattern("", ""),
+        Some("contains") => pattern(".*", ".*"),
+        Some("startswith") => pattern("", ".*"),
+        Some("endswith") => pattern(".*", ""),
+        Some("re") => Regex::new(&text).map(ValueMatcher::Regex).map_err(|e| format!("invalid regex '{}': {}", text, e)),
+        Some(op @ ("lt" | "lte" | "gt" | "gte")) => {
+            let op: fn(f64, f64) -> bool = match op {
+                "lt" => |actual, expected| actual < expected,
+                "lte" => |actual, expected| actual <= expected,
+                "gt" => |actual, expected| actual > expected,
+                _ => |actual, expected| actual >= expected,
+            };
+            text.parse().map(|n| ValueMatcher::Compare(op, n)).map_err(|_| format!("'{}' is not a number", text))
+        }
+        Some("cidr") => {
+            let (network, prefix) = text.split_once('/').ok_or_else(|| format!("invalid CIDR '{}'", text))?;
+            let network: IpAddr = network.parse().map_err(|_| format!("invalid CIDR '{}'", text))?;
+            let prefix: u8 = prefix.parse().map_err(|_| format!("invalid CIDR '{}'", text))?;
+            if prefix > if network.is_ipv4() { 32 } else { 128 } {
+                return Err(format!("invalid CIDR '{}'", text));
+            }
+            Ok(ValueMatcher::Cidr(network, prefix))
+        }
+        Some("exists") => Ok(ValueMatcher::Exists(text == "true")),
+        Some(other) => Err(format!("unsupported modifier '{}'", other)),
+    }
+}
+
+/// Sigma wildcards to regex: `*` and `?` match anything, `\*`, `\?` and `\\` are literal
+fn wildcard_regex(value: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '\\' if matches!(chars.peek(), Some('*' | '?' | '\\')) => {
+                regex.push_str(&regex::escape(&chars.next().unwrap().to_string()));
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex
+}
+
+fn in_cidr(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Parse a condition expression over the rule's selection names
+fn parse_condition(text: &str, names: &[String]) -> Result<Condition, String> {
+    let spaced = text.replace('(', " ( ").replace(')', " ) ");
+    let tokens: Vec<&str> = spaced.split_whitespace().collect();
+    let mut parser = ConditionParser { tokens: &tokens, pos: 0, names };
+    let condition = parser.or()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(condition),
+        Some(token) => Err(format!("unexpected '{}' in condition", token)),
+    }
+}
+
+struct ConditionParser<'a> {
+    tokens: &'a [&'a str],
+    pos: usize,
+    names: &'a [String],
+}
+
+impl ConditionParser<'_> {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.tokens.get(self.pos).is_some_and(|token| token.eq_ignore_ascii_case(keyword))
+    }
+
+    fn next(&mut self) -> Result<&str, String> {
+        let token = self.tokens.get(self.pos).ok_or("condition ends unexpectedly")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn or(&mut self) -> Result<Condition, String> {
+        let mut parts = vec![self.and()?];
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            parts.push(self.and()?);
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { Condition::Or(parts) })
+    }
+
+    fn and(&mut self) -> Result<Condition, String> {
+        let mut parts = vec![self.not()?];
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            parts.push(self.not()?);
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { Condition::And(parts) })
+    }
+
+    fn not(&mut self) -> Result<Condition, String> {
+        if self.peek_keyword("not") {
+            self.pos += 1;
+            return Ok(Condition::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Condition, String> {
+        let token = self.next()?.to_string();
+        if token == "(" {
+            let inner = self.or()?;
+            return match self.next()? {
+                ")" => Ok(inner),
+                other => Err(format!("expected ')' but found '{}'", other)),
+            };
+        }
+        if self.peek_keyword("of") {
+            self.pos += 1;
+            let target = self.next()?.to_string();
+            let selected: Vec<String> = if target.eq_ignore_ascii_case("them") {
+                self.names.iter().filter(|name| !name.starts_with('_')).cloned().collect()
+            } else {
+                let pattern = Regex::new(&format!("^{}$", wildcard_regex(&target))).map_err(|e| e.to_string())?;
+                self.names.iter().filter(|name| pattern.is_match(name)).cloned().collect()
+            };
+            if selected.is_empty() {
+                return Err(format!("'{} of {}' matches no selection", token, target));
+            }
+            return match token.as_str() {
+                "1" | "any" => Ok(Condition::AnyOf(selected)),
+                "all" => Ok(Condition::AllOf(selected)),
+                other => Err(format!("unsupported quantifier '{}'", other)),
+            };
+        }
+        if !self.names.contains(&token) {
+            return Err(format!("unknown selection '{}'", token));
+        }
+        Ok(Condition::Selection(token))
+    }
+}
+
+fn alert_event(rule: &SigmaRule, event: &ParsedEvent) -> ParsedEvent {
+    let severity = LEVELS[rule.level.max(1)];
+    let mut fields = HashMap::from([
+        ("rule.id".to_string(), json!(rule.id)),
+        ("rule.name".to_string(), json!(rule.title)),
+        ("rule.level".to_string(), json!(LEVELS[rule.level])),
+        ("rule.tags".to_string(), json!(rule.tags)),
+        ("event.source".to_string(), json!(event.source)),
+        ("event.parser".to_string(), json!(event.parser_name)),
+        ("event.message".to_string(), json!(event.message)),
+        ("event.fields".to_string(), json!(event.fields)),
+        // Synthetic alerts take the alert priority lane
+        (ALERT_RULES_FIELD.to_string(), json!([rule.title])),
+        (ALERT_SEVERITY_FIELD.to_string(), json!(severity)),
+    ]);
+    if let Some(description) = &rule.description {
+        fields.insert("rule.description".to_string(), json!(description));
+    }
+    ParsedEvent {
+        timestamp: event.timestamp,
+        source: SIGMA_ALERT_SOURCE.to_string(),
+        level: Some(LEVELS[rule.level].to_string()),
+        message: format!("Sigma rule '{}' matched {} event", rule.title, event.source),
+        fields,
+        raw_data: event.raw_data.clone(),
+        parser_name: SIGMA_ALERT_SOURCE.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUDOERS_RULE: &str = r#"
+title: Sudoers File Modification
+id: 0f79c4d2-5b1e-4a5e-9d69-3c8e7f1a2b10
+level: high
+tags: [attack.privilege_escalation, attack.t1548.003]
+logsource:
+  product: linux
+detection:
+  selection_file:
+    file.path|startswith: /etc/sudoers
+  selection_tool:
+    - process.name: [vi, vim, nano]
+    - process.command_line|contains|all: [tee, sudoers]
+  filter_visudo:
+    process.name: visudo
+  condition: all of selection_* and not filter_visudo
+---
+title: Logon From Internal Network
+level: informational
+logsource:
+  product: windows
+detection:
+  selection:
+    EventID: 4624
+    source.ip|cidr: 10.0.0.0/8
+  condition: selection
+"#;
+
+    fn event(source: &str, fields: &[(&str, Value)]) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: source.to_string(),
+            level: None,
+            message: "event".to_string(),
+            fields: fields.iter().map(|(k, v)| (k.to_string(), v.clone())).collect(),
+            raw_data: "event".into(),
+            parser_name: "test".to_string(),
+        }
+    }
+
+    fn engine(config: &SigmaConfig, yaml: &str) -> SigmaEngine {
+        SigmaEngine::from_documents(config, &[("test.yml".to_string(), yaml.to_string())])
+    }
+
+    #[test]
+    fn test_matching_event_is_tagged_and_alert_emitted() {
+        let engine = engine(&SigmaConfig::default(), SUDOERS_RULE);
+        // The informational rule is below the default min_level
+        assert_eq!(engine.get_stats().rules_loaded, 1);
+
+        let mut edit = event("syslog", &[("file.path", json!("/etc/sudoers.d/backdoor")), ("process.name", json!("VIM"))]);
+        let alerts = engine.evaluate(&mut edit);
+        assert_eq!(edit.fields[SIGMA_LEVEL_FIELD], json!("high"));
+        assert_eq!(edit.fields[SIGMA_RULE_IDS_FIELD], json!(["0f79c4d2-5b1e-4a5e-9d69-3c8e7f1a2b10"]));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].source, SIGMA_ALERT_SOURCE);
+        assert!(crate::alert_rules::is_alert(&alerts[0]));
+        assert_eq!(alerts[0].fields["rule.tags"], json!(["attack.privilege_escalation", "attack.t1548.003"]));
+
+        let mut visudo = event("syslog", &[("file.path", json!("/etc/sudoers")), ("process.name", json!("visudo"))]);
+        assert!(engine.evaluate(&mut visudo).is_empty());
+        let mut tee = event("syslog", &[("process.command_line", json!("echo x | tee -a /etc/sudoers"))]);
+        assert!(engine.evaluate(&mut tee).is_empty(), "selection_file is required alongside the tool");
+
+        // The linux logsource maps to syslog and file_monitor only
+        let mut windows = event("windows_event", &[("file.path", json!("/etc/sudoers")), ("process.name", json!("vi"))]);
+        assert!(engine.evaluate(&mut windows).is_empty());
+        assert_eq!(engine.get_stats().events_matched, 1);
+    }
+
+    #[test]
+    fn test_modifiers_and_unsupported_rules() {
+        let config = SigmaConfig { min_level: "informational".to_string(), output: SigmaOutput::Tag, ..Default::default() };
+        let engine = engine(&config, SUDOERS_RULE);
+        let mut logon = event("windows_event", &[("EventID", json!(4624)), ("source.ip", json!("10.20.30.40"))]);
+        assert!(engine.evaluate(&mut logon).is_empty());
+        assert_eq!(logon.fields[SIGMA_LEVEL_FIELD], json!("informational"));
+        let mut external = event("windows_event", &[("EventID", json!(4624)), ("source.ip", json!("192.0.2.1"))]);
+        engine.evaluate(&mut external);
+        assert!(!external.fields.contains_key(SIGMA_LEVEL_FIELD));
+
+        let engine = SigmaEngine::from_documents(&config, &[
+            ("count.yml".to_string(), "title: Brute force\ndetection:\n  selection:\n    EventID: 4625\n  condition: selection | count() > 5\n".to_string()),
+            ("b64.yml".to_string(), "title: Encoded\ndetection:\n  selection:\n    CommandLine|base64: whoami\n  condition: selection\n".to_string()),
+            ("keywords.yml".to_string(), "title: Keywords\ndetection:\n  keywords:\n    - 'Failed password*root'\n  condition: keywords\n".to_string()),
+        ]);
+        let stats = engine.get_stats();
+        assert_eq!((stats.rules_loaded, stats.rules_skipped.len()), (1, 2));
+        let mut failed = event("syslog", &[]);
+        failed.message = "sshd: failed password for ROOT from 192.0.2.7".to_string();
+        engine.evaluate(&mut failed);
+        assert_eq!(failed.fields[SIGMA_RULE_TITLES_FIELD], json!(["Keywords"]));
+    }
+}
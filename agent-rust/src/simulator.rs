@@ -0,0 +1,834 @@
+// Virtual agent simulator for backend load testing
+// Drives many virtual agents through the agent's own transport with a weighted event mix,
+// periodic heartbeats and simulated network outages followed by reconnect bursts
+
+use crate::config::TransportConfig;
+use crate::errors::TransportError;
+use crate::parsers::ParsedEvent;
+use crate::transport::SecureTransport;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::time::{interval, sleep, Duration, Instant};
+use tracing::{debug, info, warn};
+
+const USERS: &[&str] = &["alice", "bob", "carol", "dave", "svc_backup", "root", "admin", "jenkins"];
+const LINUX_PROCESSES: &[(&str, &str)] = &[
+    ("/usr/bin/curl", "curl -s https://updates.example.com/manifest.json"),
+    ("/usr/bin/python3", "python3 /opt/app/worker.py --queue default"),
+    ("/usr/bin/ssh", "ssh deploy@10.0.4.12"),
+    ("/usr/bin/sudo", "sudo systemctl restart nginx"),
+    ("/usr/bin/bash", "bash -c 'tar czf /tmp/backup.tgz /var/www'"),
+];
+const WINDOWS_PROCESSES: &[(&str, &str)] = &[
+    ("C:\\Windows\\System32\\cmd.exe", "cmd.exe /c whoami /all"),
+    ("C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe", "powershell.exe -NoProfile -Command Get-Service"),
+    ("C:\\Windows\\System32\\svchost.exe", "svchost.exe -k netsvcs -p"),
+    ("C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe", "chrome.exe --type=renderer"),
+];
+const SYSTEM_MESSAGES: &[(&str, &str, &str)] = &[
+    ("CRON", "info", "(root) CMD (/usr/lib/sysstat/sa1 1 1)"),
+    ("systemd", "info", "Started Daily apt upgrade and clean activities."),
+    ("kernel", "warning", "TCP: request_sock_TCP: Possible SYN flooding on port 443. Sending cookies."),
+    ("systemd", "error", "nginx.service: Main process exited, code=exited, status=1/FAILURE"),
+    ("rsyslogd", "info", "[origin software=\"rsyslogd\"] rsyslogd was HUPed"),
+];
+const WEB_PATHS: &[&str] = &["/", "/login", "/api/v1/orders", "/static/app.js", "/admin", "/wp-login.php", "/health"];
+const PORTS: &[u16] = &[443, 80, 22, 53, 3306, 5432, 8080];
+
+/// Kinds of events a virtual agent emits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimulatedEventKind {
+    SyslogAuth,
+    SyslogSystem,
+    WindowsSecurity,
+    ProcessExec,
+    NetworkConnect,
+    WebAccess,
+}
+
+impl SimulatedEventKind {
+    pub const ALL: [SimulatedEventKind; 6] = [
+        SimulatedEventKind::SyslogAuth,
+        SimulatedEventKind::SyslogSystem,
+        SimulatedEventKind::WindowsSecurity,
+        SimulatedEventKind::ProcessExec,
+        SimulatedEventKind::NetworkConnect,
+        SimulatedEventKind::WebAccess,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SimulatedEventKind::SyslogAuth => "syslog_auth",
+            SimulatedEventKind::SyslogSystem => "syslog_system",
+            SimulatedEventKind::WindowsSecurity => "windows_security",
+            SimulatedEventKind::ProcessExec => "process_exec",
+            SimulatedEventKind::NetworkConnect => "network_connect",
+            SimulatedEventKind::WebAccess => "web_access",
+        }
+    }
+
+    fn runs_on(&self, platform: Platform) -> bool {
+        match self {
+            SimulatedEventKind::SyslogAuth | SimulatedEventKind::SyslogSystem => platform == Platform::Linux,
+            SimulatedEventKind::WindowsSecurity => platform == Platform::Windows,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Linux,
+    Windows,
+}
+
+impl Platform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Linux => "linux",
+            Platform::Windows => "windows",
+        }
+    }
+}
+
+/// Relative weights of each event kind
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventMix {
+    weights: Vec<(SimulatedEventKind, u32)>,
+}
+
+impl Default for EventMix {
+    fn default() -> Self {
+        Self {
+            weights: vec![
+                (SimulatedEventKind::SyslogAuth, 20),
+                (SimulatedEventKind::SyslogSystem, 25),
+                (SimulatedEventKind::WindowsSecurity, 20),
+                (SimulatedEventKind::ProcessExec, 15),
+                (SimulatedEventKind::NetworkConnect, 10),
+                (SimulatedEventKind::WebAccess, 10),
+            ],
+        }
+    }
+}
+
+/// Parses `kind=weight,...`; kinds that aren't listed get no events
+impl FromStr for EventMix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = Vec::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, weight) = entry.split_once('=').ok_or_else(|| format!("expected kind=weight, got '{}'", entry))?;
+            let kind = SimulatedEventKind::ALL.iter()
+                .find(|k| k.as_str() == name.trim())
+                .ok_or_else(|| format!("unknown event kind '{}'", name.trim()))?;
+            let weight = weight.trim().parse::<u32>().map_err(|_| format!("invalid weight for '{}'", name.trim()))?;
+            weights.push((*kind, weight));
+        }
+        if weights.iter().all(|(_, weight)| *weight == 0) {
+            return Err("event mix needs at least one non-zero weight".to_string());
+        }
+        Ok(Self { weights })
+    }
+}
+
+impl EventMix {
+    /// Weighted pick among kinds that make sense on `platform`, falling back to every kind
+    fn pick(&self, rng: &mut SimRng, platform: Platform) -> SimulatedEventKind {
+        let compatible: Vec<(SimulatedEventKind, u32)> = self.weights.iter()
+            .filter(|(kind, weight)| *weight > 0 && kind.runs_on(platform))
+            .copied()
+            .collect();
+        let candidates = if compatible.is_empty() { &self.weights } else { &compatible };
+
+        let total: u64 = candidates.iter().map(|(_, weight)| *weight as u64).sum();
+        let mut roll = rng.next_u64() % total.max(1);
+        for (kind, weight) in candidates {
+            if roll < *weight as u64 {
+                return *kind;
+            }
+            roll -= *weight as u64;
+        }
+        candidates[0].0
+    }
+}
+
+/// Simulation parameters
+#[derive(Debug, Clone)]
+pub struct SimulatorConfig {
+    /// Number of virtual agents
+    pub agents: usize,
+    /// Events generated per second by each agent
+    pub events_per_second: f64,
+    pub batch_size: usize,
+    pub flush_interval_ms: u64,
+    pub heartbeat_interval_secs: u64,
+    /// Stop after this long; runs until interrupted when unset
+    pub duration_secs: Option<u64>,
+    /// Agent start times are spread over this period
+    pub ramp_up_secs: u64,
+    /// Simulated network outages per agent per hour
+    pub disconnects_per_hour: f64,
+    pub max_offline_secs: u64,
+    /// Events kept per agent while offline; the oldest are dropped beyond this
+    pub max_buffered_events: usize,
+    /// Upper bound on the reconnect backoff after a failed send
+    pub max_backoff_secs: u64,
+    /// Share of agents that behave like Windows hosts
+    pub windows_ratio: f64,
+    pub mix: EventMix,
+    pub seed: u64,
+    pub agent_prefix: String,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            agents: 10,
+            events_per_second: 5.0,
+            batch_size: 100,
+            flush_interval_ms: 1000,
+            heartbeat_interval_secs: 30,
+            duration_secs: None,
+            ramp_up_secs: 10,
+            disconnects_per_hour: 2.0,
+            max_offline_secs: 60,
+            max_buffered_events: 10_000,
+            max_backoff_secs: 60,
+            windows_ratio: 0.3,
+            mix: EventMix::default(),
+            seed: 1,
+            agent_prefix: "sim-agent".to_string(),
+        }
+    }
+}
+
+impl SimulatorConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.agents == 0 {
+            errors.push("agents must be greater than 0".to_string());
+        }
+        if self.events_per_second.is_nan() || self.events_per_second <= 0.0 {
+            errors.push("events_per_second must be greater than 0".to_string());
+        }
+        if self.batch_size == 0 || self.max_buffered_events == 0 {
+            errors.push("batch_size and max_buffered_events must be greater than 0".to_string());
+        }
+        if self.flush_interval_ms < 10 {
+            errors.push("flush_interval_ms must be at least 10".to_string());
+        }
+        if self.heartbeat_interval_secs == 0 {
+            errors.push("heartbeat_interval_secs must be greater than 0".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.windows_ratio) {
+            errors.push("windows_ratio must be between 0 and 1".to_string());
+        }
+        if self.disconnects_per_hour < 0.0 {
+            errors.push("disconnects_per_hour cannot be negative".to_string());
+        }
+        errors
+    }
+}
+
+/// splitmix64; deterministic so a load test can be replayed from its seed
+#[derive(Debug, Clone)]
+pub struct SimRng(u64);
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound.max(1)
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    fn ip(&mut self) -> String {
+        if self.chance(0.7) {
+            format!("10.{}.{}.{}", self.below(16), self.below(256), 1 + self.below(254))
+        } else {
+            format!("198.51.100.{}", 1 + self.below(254))
+        }
+    }
+}
+
+/// Builds realistic events for one virtual agent
+pub struct EventGenerator {
+    agent_id: String,
+    hostname: String,
+    platform: Platform,
+    mix: EventMix,
+    rng: SimRng,
+    sequence: u64,
+}
+
+impl EventGenerator {
+    pub fn new(agent_id: &str, platform: Platform, mix: EventMix, seed: u64) -> Self {
+        Self {
+            agent_id: agent_id.to_string(),
+            hostname: format!("{}.sim.local", agent_id),
+            platform,
+            mix,
+            rng: SimRng::new(seed),
+            sequence: 0,
+        }
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    pub fn generate(&mut self) -> ParsedEvent {
+        self.sequence += 1;
+        let kind = self.mix.pick(&mut self.rng, self.platform);
+        let (source, level, message, raw_data, mut fields) = match kind {
+            SimulatedEventKind::SyslogAuth => self.syslog_auth(),
+            SimulatedEventKind::SyslogSystem => self.syslog_system(),
+            SimulatedEventKind::WindowsSecurity => self.windows_security(),
+            SimulatedEventKind::ProcessExec => self.process_exec(),
+            SimulatedEventKind::NetworkConnect => self.network_connect(),
+            SimulatedEventKind::WebAccess => self.web_access(),
+        };
+        fields.extend(self.common_fields());
+        fields.insert("event.sequence".to_string(), json!(self.sequence));
+
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: source.to_string(),
+            level: Some(level.to_string()),
+            message,
+            fields,
+            raw_data: raw_data.into(),
+            parser_name: format!("simulator_{}", kind.as_str()),
+        }
+    }
+
+    pub fn heartbeat(&self, uptime: Duration, buffered_events: usize) -> ParsedEvent {
+        let mut fields = self.common_fields();
+        fields.insert("event.category".to_string(), json!("agent"));
+        fields.insert("event.action".to_string(), json!("heartbeat"));
+        fields.insert("agent.uptime_seconds".to_string(), json!(uptime.as_secs()));
+        fields.insert("agent.buffered_events".to_string(), json!(buffered_events));
+        fields.insert("agent.version".to_string(), json!(env!("CARGO_PKG_VERSION")));
+        let message = format!("Heartbeat from agent: {}", self.agent_id);
+
+        ParsedEvent {
+            timestamp: chrono::Utc::now(),
+            source: "agent_heartbeat".to_string(),
+            level: Some("info".to_string()),
+            raw_data: message.as_str().into(),
+            message,
+            fields,
+            parser_name: "simulator_heartbeat".to_string(),
+        }
+    }
+
+    fn common_fields(&self) -> HashMap<String, Value> {
+        HashMap::from([
+            ("agent.id".to_string(), json!(self.agent_id)),
+            ("host.name".to_string(), json!(self.hostname)),
+            ("host.os.type".to_string(), json!(self.platform.as_str())),
+        ])
+    }
+
+    fn syslog_auth(&mut self) -> (&'static str, &'static str, String, String, HashMap<String, Value>) {
+        let user = *self.rng.pick(USERS);
+        let ip = self.rng.ip();
+        let pid = 1000 + self.rng.below(60000);
+        let failed = self.rng.chance(0.2);
+        let message = format!("{} password for {} from {} port {} ssh2",
+                              if failed { "Failed" } else { "Accepted" }, user, ip, 1024 + self.rng.below(64000));
+        let raw = format!("<38>{} {} sshd[{}]: {}", chrono::Utc::now().format("%b %e %H:%M:%S"), self.hostname, pid, message);
+        let fields = HashMap::from([
+            ("event.category".to_string(), json!("authentication")),
+            ("event.outcome".to_string(), json!(if failed { "failure" } else { "success" })),
+            ("user.name".to_string(), json!(user)),
+            ("source.ip".to_string(), json!(ip)),
+            ("process.name".to_string(), json!("sshd")),
+            ("process.pid".to_string(), json!(pid)),
+        ]);
+        ("syslog", if failed { "warning" } else { "info" }, message, raw, fields)
+    }
+
+    fn syslog_system(&mut self) -> (&'static str, &'static str, String, String, HashMap<String, Value>) {
+        let (program, level, message) = *self.rng.pick(SYSTEM_MESSAGES);
+        let pid = 1 + self.rng.below(30000);
+        let raw = format!("<30>{} {} {}[{}]: {}", chrono::Utc::now().format("%b %e %H:%M:%S"), self.hostname, program, pid, message);
+        let fields = HashMap::from([
+            ("process.name".to_string(), json!(program)),
+            ("process.pid".to_string(), json!(pid)),
+        ]);
+        ("syslog", level, message.to_string(), raw, fields)
+    }
+
+    fn windows_security(&mut self) -> (&'static str, &'static str, String, String, HashMap<String, Value>) {
+        let user = *self.rng.pick(USERS);
+        let ip = self.rng.ip();
+        let (event_id, level, message) = match self.rng.below(10) {
+            0..=4 => (4624, "info", format!("An account was successfully logged on: CORP\\{}", user)),
+            5..=6 => (4625, "warning", format!("An account failed to log on: CORP\\{}", user)),
+            7..=8 => (4688, "info", format!("A new process has been created by CORP\\{}", user)),
+            _ => (4672, "info", format!("Special privileges assigned to new logon: CORP\\{}", user)),
+        };
+        let logon_type = *self.rng.pick(&[2, 3, 10]);
+        let raw = json!({ "EventID": event_id, "Channel": "Security", "Computer": self.hostname, "TargetUserName": user, "IpAddress": ip, "LogonType": logon_type }).to_string();
+        let fields = HashMap::from([
+            ("event.code".to_string(), json!(event_id.to_string())),
+            ("winlog.channel".to_string(), json!("Security")),
+            ("user.name".to_string(), json!(user)),
+            ("user.domain".to_string(), json!("CORP")),
+            ("source.ip".to_string(), json!(ip)),
+            ("winlog.logon.type".to_string(), json!(logon_type)),
+        ]);
+        ("windows_event", level, message, raw, fields)
+    }
+
+    fn process_exec(&mut self) -> (&'static str, &'static str, String, String, HashMap<String, Value>) {
+        let processes = if self.platform == Platform::Windows { WINDOWS_PROCESSES } else { LINUX_PROCESSES };
+        let (executable, command_line) = *self.rng.pick(processes);
+        let user = *self.rng.pick(USERS);
+        let pid = 1000 + self.rng.below(60000);
+        let ppid = 1 + self.rng.below(pid);
+        let message = format!("Process executed: {}", command_line);
+        let fields = HashMap::from([
+            ("event.category".to_string(), json!("process")),
+            ("event.action".to_string(), json!("process_exec")),
+            ("process.pid".to_string(), json!(pid)),
+            ("process.parent.pid".to_string(), json!(ppid)),
+            ("process.executable".to_string(), json!(executable)),
+            ("process.command_line".to_string(), json!(command_line)),
+            ("user.name".to_string(), json!(user)),
+        ]);
+        let raw = serde_json::to_string(&fields).unwrap_or_default();
+        (if self.platform == Platform::Windows { "sysmon" } else { "ebpf" }, "info", message, raw, fields)
+    }
+
+    fn network_connect(&mut self) -> (&'static str, &'static str, String, String, HashMap<String, Value>) {
+        let destination = self.rng.ip();
+        let port = *self.rng.pick(PORTS);
+        let processes = if self.platform == Platform::Windows { WINDOWS_PROCESSES } else { LINUX_PROCESSES };
+        let (executable, _) = *self.rng.pick(processes);
+        let message = format!("Network connection to {}:{} from {}", destination, port, executable);
+        let fields = HashMap::from([
+            ("event.category".to_string(), json!("network")),
+            ("event.action".to_string(), json!("network_connect")),
+            ("destination.ip".to_string(), json!(destination)),
+            ("destination.port".to_string(), json!(port)),
+            ("process.executable".to_string(), json!(executable)),
+        ]);
+        let raw = serde_json::to_string(&fields).unwrap_or_default();
+        (if self.platform == Platform::Windows { "sysmon" } else { "ebpf" }, "info", message, raw, fields)
+    }
+
+    fn web_access(&mut self) -> (&'static str, &'static str, String, String, HashMap<String, Value>) {
+        let ip = self.rng.ip();
+        let path = *self.rng.pick(WEB_PATHS);
+        let method = if self.rng.chance(0.8) { "GET" } else { "POST" };
+        let status = *self.rng.pick(&[200, 200, 200, 200, 301, 304, 401, 403, 404, 500]);
+        let bytes = self.rng.below(50_000);
+        let raw = format!("{} - - [{}] \"{} {} HTTP/1.1\" {} {} \"-\" \"Mozilla/5.0\"",
+                          ip, chrono::Utc::now().format("%d/%b/%Y:%H:%M:%S %z"), method, path, status, bytes);
+        let fields = HashMap::from([
+            ("source.ip".to_string(), json!(ip)),
+            ("http.request.method".to_string(), json!(method)),
+            ("url.path".to_string(), json!(path)),
+            ("http.response.status_code".to_string(), json!(status)),
+            ("http.response.body.bytes".to_string(), json!(bytes)),
+        ]);
+        let level = match status {
+            500.. => "error",
+            400.. => "warning",
+            _ => "info",
+        };
+        ("file_monitor", level, raw.clone(), raw, fields)
+    }
+}
+
+/// Events waiting to be sent by one agent, bounded like the real agent's memory buffer
+#[derive(Debug)]
+pub struct PendingEvents {
+    events: VecDeque<ParsedEvent>,
+    max_events: usize,
+}
+
+impl PendingEvents {
+    pub fn new(max_events: usize) -> Self {
+        Self { events: VecDeque::new(), max_events }
+    }
+
+    /// Queue an event, returning how many old events were dropped to make room
+    pub fn push(&mut self, event: ParsedEvent) -> usize {
+        self.events.push_back(event);
+        let overflow = self.events.len().saturating_sub(self.max_events);
+        self.events.drain(..overflow);
+        overflow
+    }
+
+    pub fn take_batch(&mut self, batch_size: usize) -> Vec<ParsedEvent> {
+        let count = batch_size.min(self.events.len());
+        self.events.drain(..count).collect()
+    }
+
+    /// Put an unsent batch back in front, keeping order
+    pub fn requeue(&mut self, batch: Vec<ParsedEvent>) {
+        for event in batch.into_iter().rev() {
+            self.events.push_front(event);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+/// Counters shared by all virtual agents
+#[derive(Debug, Default)]
+pub struct SimulatorStats {
+    events_generated: AtomicU64,
+    events_sent: AtomicU64,
+    events_dropped: AtomicU64,
+    batches_sent: AtomicU64,
+    send_failures: AtomicU64,
+    heartbeats_sent: AtomicU64,
+    disconnects: AtomicU64,
+    reconnects: AtomicU64,
+    agents_online: AtomicU64,
+    send_latency_ms_total: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatorSnapshot {
+    pub elapsed_secs: f64,
+    pub agents_online: u64,
+    pub events_generated: u64,
+    pub events_sent: u64,
+    pub events_dropped: u64,
+    pub batches_sent: u64,
+    pub send_failures: u64,
+    pub heartbeats_sent: u64,
+    pub disconnects: u64,
+    pub reconnects: u64,
+    pub events_per_second: f64,
+    pub average_batch_latency_ms: f64,
+}
+
+impl SimulatorStats {
+    pub fn snapshot(&self, elapsed: Duration) -> SimulatorSnapshot {
+        let events_sent = self.events_sent.load(Ordering::Relaxed);
+        let batches_sent = self.batches_sent.load(Ordering::Relaxed);
+        let elapsed_secs = elapsed.as_secs_f64();
+
+        SimulatorSnapshot {
+            elapsed_secs,
+            agents_online: self.agents_online.load(Ordering::Relaxed),
+            events_generated: self.events_generated.load(Ordering::Relaxed),
+            events_sent,
+            events_dropped: self.events_dropped.load(Ordering::Relaxed),
+            batches_sent,
+            send_failures: self.send_failures.load(Ordering::Relaxed),
+            heartbeats_sent: self.heartbeats_sent.load(Ordering::Relaxed),
+            disconnects: self.disconnects.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            events_per_second: if elapsed_secs > 0.0 { events_sent as f64 / elapsed_secs } else { 0.0 },
+            average_batch_latency_ms: if batches_sent > 0 {
+                self.send_latency_ms_total.load(Ordering::Relaxed) as f64 / batches_sent as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// One simulated agent with its own transport, buffer and connection state
+struct VirtualAgent {
+    agent_id: String,
+    config: SimulatorConfig,
+    transport: SecureTransport,
+    generator: EventGenerator,
+    rng: SimRng,
+    stats: Arc<SimulatorStats>,
+    start_delay: Duration,
+}
+
+impl VirtualAgent {
+    async fn run(mut self, mut shutdown: broadcast::Receiver<()>) {
+        tokio::select! {
+            _ = sleep(self.start_delay) => {}
+            _ = shutdown.recv() => return,
+        }
+        let started = Instant::now();
+        let mut pending = PendingEvents::new(self.config.max_buffered_events);
+        let mut online = true;
+        let mut reconnect_at = Instant::now();
+        let mut backoff = Duration::from_secs(1);
+        let mut carry = 0.0;
+        let flush_interval = Duration::from_millis(self.config.flush_interval_ms);
+        let outage_probability = self.config.disconnects_per_hour * flush_interval.as_secs_f64() / 3600.0;
+        let mut flush_timer = interval(flush_interval);
+        let mut heartbeat_timer = interval(Duration::from_secs(self.config.heartbeat_interval_secs));
+        self.stats.agents_online.fetch_add(1, Ordering::Relaxed);
+        debug!("🤖 Virtual agent {} started ({})", self.agent_id, self.generator.platform().as_str());
+
+        loop {
+            tokio::select! {
+                _ = flush_timer.tick() => {
+                    carry += self.config.events_per_second * flush_interval.as_secs_f64();
+                    let count = carry as u64;
+                    carry -= count as f64;
+                    for _ in 0..count {
+                        let dropped = pending.push(self.generator.generate());
+                        self.stats.events_dropped.fetch_add(dropped as u64, Ordering::Relaxed);
+                    }
+                    self.stats.events_generated.fetch_add(count, Ordering::Relaxed);
+
+                    if online && self.rng.chance(outage_probability) {
+                        let outage = Duration::from_secs(1 + self.rng.below(self.config.max_offline_secs.max(1)));
+                        debug!("📴 Virtual agent {} going offline for {:?}", self.agent_id, outage);
+                        self.disconnect(&mut online);
+                        reconnect_at = Instant::now() + outage;
+                        continue;
+                    }
+                    if !online && Instant::now() < reconnect_at {
+                        continue;
+                    }
+
+                    while !pending.is_empty() {
+                        let batch = pending.take_batch(self.config.batch_size);
+                        if let Err(e) = self.send(batch.clone()).await {
+                            pending.requeue(batch);
+                            debug!("Virtual agent {} send failed: {}", self.agent_id, e);
+                            if online {
+                                self.disconnect(&mut online);
+                            }
+                            reconnect_at = Instant::now() + self.jittered(backoff);
+                            backoff = (backoff * 2).min(Duration::from_secs(self.config.max_backoff_secs.max(1)));
+                            break;
+                        }
+                        if !online {
+                            online = true;
+                            backoff = Duration::from_secs(1);
+                            self.stats.reconnects.fetch_add(1, Ordering::Relaxed);
+                            self.stats.agents_online.fetch_add(1, Ordering::Relaxed);
+                            debug!("📶 Virtual agent {} reconnected, flushing {} buffered events", self.agent_id, pending.len());
+                        }
+                    }
+                }
+                _ = heartbeat_timer.tick() => {
+                    if online {
+                        let heartbeat = self.generator.heartbeat(started.elapsed(), pending.len());
+                        if self.transport.send_batch(vec![heartbeat]).await.is_ok() {
+                            self.stats.heartbeats_sent.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+                _ = shutdown.recv() => break,
+            }
+        }
+
+        if online {
+            self.stats.agents_online.fetch_sub(1, Ordering::Relaxed);
+        }
+        debug!("🤖 Virtual agent {} stopped with {} unsent events", self.agent_id, pending.len());
+    }
+
+    async fn send(&self, batch: Vec<ParsedEvent>) -> Result<(), TransportError> {
+        let count = batch.len() as u64;
+        let started = Instant::now();
+        match self.transport.send_batch(batch).await {
+            Ok(()) => {
+                self.stats.events_sent.fetch_add(count, Ordering::Relaxed);
+                self.stats.batches_sent.fetch_add(1, Ordering::Relaxed);
+                self.stats.send_latency_ms_total.fetch_add(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(e) => {
+                self.stats.send_failures.fetch_add(1, Ordering::Relaxed);
+                Err(e)
+            }
+        }
+    }
+
+    fn disconnect(&self, online: &mut bool) {
+        *online = false;
+        self.stats.disconnects.fetch_add(1, Ordering::Relaxed);
+        self.stats.agents_online.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Full jitter so reconnecting agents don't stampede the backend together
+    fn jittered(&mut self, backoff: Duration) -> Duration {
+        backoff.mul_f64(0.5 + self.rng.next_f64() * 0.5)
+    }
+}
+
+/// Runs the virtual agent fleet
+pub struct Simulator {
+    config: SimulatorConfig,
+    transport_config: TransportConfig,
+    stats: Arc<SimulatorStats>,
+}
+
+impl Simulator {
+    pub fn new(config: SimulatorConfig, mut transport_config: TransportConfig) -> Self {
+        // One send_batch call per simulated batch
+        transport_config.batch_size = config.batch_size;
+        Self {
+            config,
+            transport_config,
+            stats: Arc::new(SimulatorStats::default()),
+        }
+    }
+
+    pub fn stats(&self) -> Arc<SimulatorStats> {
+        self.stats.clone()
+    }
+
+    /// Run until the configured duration elapses or `shutdown` fires, returning the final counters
+    pub async fn run(&self, shutdown: broadcast::Sender<()>) -> Result<SimulatorSnapshot, TransportError> {
+        let started = Instant::now();
+        let mut rng = SimRng::new(self.config.seed);
+        let mut handles = Vec::with_capacity(self.config.agents);
+        info!("🤖 Starting {} virtual agents at {} events/s each against {}",
+              self.config.agents, self.config.events_per_second, self.transport_config.server_url);
+
+        for index in 0..self.config.agents {
+            let agent_id = format!("{}-{:05}", self.config.agent_prefix, index);
+            let mut transport = SecureTransport::new(self.transport_config.clone()).await?;
+            transport.set_agent_id(&agent_id);
+            let platform = if rng.chance(self.config.windows_ratio) { Platform::Windows } else { Platform::Linux };
+            let agent = VirtualAgent {
+                generator: EventGenerator::new(&agent_id, platform, self.config.mix.clone(), rng.next_u64()),
+                rng: SimRng::new(rng.next_u64()),
+                agent_id,
+                config: self.config.clone(),
+                transport,
+                stats: self.stats.clone(),
+                start_delay: Duration::from_secs(self.config.ramp_up_secs).mul_f64(index as f64 / self.config.agents as f64),
+            };
+            handles.push(tokio::spawn(agent.run(shutdown.subscribe())));
+        }
+
+        let stats = self.stats.clone();
+        let mut report_shutdown = shutdown.subscribe();
+        let reporter = tokio::spawn(async move {
+            let mut report_timer = interval(Duration::from_secs(10));
+            report_timer.tick().await;
+            loop {
+                tokio::select! {
+                    _ = report_timer.tick() => {
+                        let s = stats.snapshot(started.elapsed());
+                        info!("📊 {} agents online, {} sent ({:.0}/s), {} failures, {} dropped, {} reconnects, {:.1}ms avg batch",
+                              s.agents_online, s.events_sent, s.events_per_second, s.send_failures,
+                              s.events_dropped, s.reconnects, s.average_batch_latency_ms);
+                    }
+                    _ = report_shutdown.recv() => break,
+                }
+            }
+        });
+
+        let mut stop = shutdown.subscribe();
+        match self.config.duration_secs {
+            Some(duration) => tokio::select! {
+                _ = sleep(Duration::from_secs(duration)) => {}
+                _ = stop.recv() => {}
+            },
+            None => {
+                let _ = stop.recv().await;
+            }
+        }
+        let _ = shutdown.send(());
+
+        for handle in handles {
+            if let Err(e) = handle.await {
+                warn!("⚠️ Virtual agent task failed: {}", e);
+            }
+        }
+        let _ = reporter.await;
+
+        let snapshot = self.stats.snapshot(started.elapsed());
+        info!("🏁 Simulation finished: {} events sent by {} agents in {:.0}s", snapshot.events_sent, self.config.agents, snapshot.elapsed_secs);
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_mix_parsing() {
+        let mix: EventMix = "syslog_auth=3, web_access=1".parse().unwrap();
+        let mut rng = SimRng::new(7);
+        for _ in 0..50 {
+            let kind = mix.pick(&mut rng, Platform::Linux);
+            assert!(matches!(kind, SimulatedEventKind::SyslogAuth | SimulatedEventKind::WebAccess));
+        }
+        // No Windows-compatible kind with weight left besides web access
+        assert_eq!(mix.pick(&mut rng, Platform::Windows), SimulatedEventKind::WebAccess);
+
+        assert!("bogus=1".parse::<EventMix>().is_err());
+        assert!("syslog_auth=0".parse::<EventMix>().is_err());
+        assert!("syslog_auth".parse::<EventMix>().is_err());
+    }
+
+    #[test]
+    fn test_generator_is_deterministic_per_seed() {
+        let mut first = EventGenerator::new("sim-agent-00001", Platform::Windows, EventMix::default(), 42);
+        let mut second = EventGenerator::new("sim-agent-00001", Platform::Windows, EventMix::default(), 42);
+        for _ in 0..100 {
+            let (a, b) = (first.generate(), second.generate());
+            assert_eq!(a.message, b.message);
+            assert_ne!(a.source, "syslog");
+            assert_eq!(a.fields["agent.id"], "sim-agent-00001");
+        }
+        let heartbeat = first.heartbeat(Duration::from_secs(90), 3);
+        assert_eq!(heartbeat.fields["agent.uptime_seconds"], 90);
+    }
+
+    #[test]
+    fn test_pending_events_drop_oldest_and_requeue() {
+        let mut generator = EventGenerator::new("sim", Platform::Linux, EventMix::default(), 1);
+        let mut pending = PendingEvents::new(3);
+        let events: Vec<ParsedEvent> = (0..5).map(|_| generator.generate()).collect();
+        let dropped: usize = events.iter().cloned().map(|e| pending.push(e)).sum();
+        assert_eq!(dropped, 2);
+
+        let batch = pending.take_batch(2);
+        assert_eq!(batch[0].fields["event.sequence"], 3);
+        pending.requeue(batch);
+        assert_eq!(pending.len(), 3);
+        assert_eq!(pending.take_batch(1)[0].fields["event.sequence"], 3);
+    }
+}
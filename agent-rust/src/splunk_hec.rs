@@ -0,0 +1,254 @@
+// Splunk HTTP Event Collector output
+// Batches are posted to the HEC event endpoint as concatenated JSON objects, one per event, each carrying the event
+// body plus Splunk metadata (time, host, source, sourcetype, index) rendered from per-event templates. Selected
+// fields can also be sent as indexed fields so they are searchable without search-time extraction.
+
+use crate::config::TransportConfig;
+use crate::output_template::{self, EventTemplate};
+use crate::parsers::ParsedEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// Appended to `server_url` when it names only the HEC host
+pub const EVENT_PATH: &str = "/services/collector/event";
+
+/// Splunk HEC settings, used when the transport protocol is `splunk_hec`; the HEC token is `transport.api_key`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SplunkHecConfig {
+    /// Event endpoint; defaults to `server_url`, with `/services/collector/event` appended when it has no path
+    pub endpoint: Option<String>,
+    /// Index template; empty leaves the choice to the token's default index
+    pub index: String,
+    pub source: String,
+    pub sourcetype: String,
+    /// Host template; defaults to the event's host.name or hostname field, then this machine's hostname
+    pub host: Option<String>,
+    /// Event fields also sent as indexed fields
+    pub indexed_fields: Vec<String>,
+    /// Attach raw_data to the event body as `raw`
+    pub include_raw_data: bool,
+}
+
+impl Default for SplunkHecConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            index: String::new(),
+            source: "securewatch:{source}".to_string(),
+            sourcetype: "_json".to_string(),
+            host: None,
+            indexed_fields: Vec::new(),
+            include_raw_data: false,
+        }
+    }
+}
+
+impl SplunkHecConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(endpoint) = &self.endpoint {
+            match url::Url::parse(endpoint) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(_) => errors.push("endpoint must use HTTP or HTTPS scheme".to_string()),
+                Err(e) => errors.push(format!("invalid endpoint: {}", e)),
+            }
+        }
+        if self.sourcetype.trim().is_empty() {
+            errors.push("sourcetype cannot be empty".to_string());
+        }
+        let templates = [("index", Some(&self.index)), ("source", Some(&self.source)), ("sourcetype", Some(&self.sourcetype)), ("host", self.host.as_ref())];
+        for (name, template) in templates {
+            if let Some(Err(e)) = template.map(|t| EventTemplate::parse(t)) {
+                errors.push(format!("{}: {}", name, e));
+            }
+        }
+        errors
+    }
+}
+
+/// Builds HEC request bodies with the metadata templates parsed once
+pub struct HecEncoder {
+    url: String,
+    index: Option<EventTemplate>,
+    source: EventTemplate,
+    sourcetype: EventTemplate,
+    host: Option<EventTemplate>,
+    local_hostname: String,
+    indexed_fields: Vec<String>,
+    include_raw_data: bool,
+}
+
+impl HecEncoder {
+    pub fn new(config: &TransportConfig) -> Result<Self, String> {
+        let hec = &config.splunk_hec;
+        let index = match hec.index.trim() {
+            "" => None,
+            index => Some(EventTemplate::parse(index)?),
+        };
+        Ok(Self {
+            url: output_template::endpoint_url(hec.endpoint.as_deref().unwrap_or(&config.server_url), EVENT_PATH),
+            index,
+            source: EventTemplate::parse(&hec.source)?,
+            sourcetype: EventTemplate::parse(&hec.sourcetype)?,
+            host: hec.host.as_deref().map(EventTemplate::parse).transpose()?,
+            local_hostname: hostname::get().map(|h| h.to_string_lossy().into_owned()).unwrap_or_default(),
+            indexed_fields: hec.indexed_fields.clone(),
+            include_raw_data: hec.include_raw_data,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Request body: the events' JSON objects back to back
+    pub fn encode<'a>(&self, events: impl IntoIterator<Item = &'a ParsedEvent>) -> serde_json::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        for event in events {
+            serde_json::to_writer(&mut body, &self.event(event))?;
+            body.push(b'\n');
+        }
+        Ok(body)
+    }
+
+    fn event(&self, event: &ParsedEvent) -> Value {
+        let mut body: Map<String, Value> = event.fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        body.insert("message".to_string(), json!(event.message));
+        body.insert("source".to_string(), json!(event.source));
+        body.insert("parser".to_string(), json!(event.parser_name));
+        if let Some(level) = &event.level {
+            body.insert("level".to_string(), json!(level));
+        }
+        if self.include_raw_data {
+            body.insert("raw".to_string(), json!(event.raw_data.as_ref()));
+        }
+
+        let mut hec = json!({
+            "time": event.timestamp.timestamp_millis() as f64 / 1000.0,
+            "host": self.host(event),
+            "source": self.source.render(event),
+            "sourcetype": self.sourcetype.render(event),
+            "event": body,
+        });
+        if let Some(index) = &self.index {
+            hec["index"] = json!(index_name(&index.render(event)));
+        }
+        let indexed: Map<String, Value> = self
+            .indexed_fields
+            .iter()
+            .filter_map(|name| Some((name.clone(), indexed_value(event.fields.get(name)?)?)))
+            .collect();
+        if !indexed.is_empty() {
+            hec["fields"] = Value::Object(indexed);
+        }
+        hec
+    }
+
+    fn host(&self, event: &ParsedEvent) -> String {
+        if let Some(host) = &self.host {
+            return host.render(event);
+        }
+        ["host.name", "hostname"]
+            .iter()
+            .find_map(|name| event.fields.get(*name).and_then(Value::as_str))
+            .unwrap_or(&self.local_hostname)
+            .to_string()
+    }
+}
+
+/// Splunk index names are lowercase letters, digits, underscores and hyphens
+fn index_name(rendered: &str) -> String {
+    rendered
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Indexed field values must be strings or arrays of strings
+fn indexed_value(value: &Value) -> Option<Value> {
+    match value {
+        Value::Null | Value::Object(_) => None,
+        Value::String(_) => Some(value.clone()),
+        Value::Array(items) => Some(Value::Array(
+            items.iter().filter_map(|item| indexed_value(item).filter(Value::is_string)).collect(),
+        )),
+        other => Some(json!(other.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn event() -> ParsedEvent {
+        ParsedEvent {
+            timestamp: chrono::DateTime::from_timestamp_millis(1_710_025_200_250).unwrap(),
+            source: "windows_event_log".to_string(),
+            level: Some("warning".to_string()),
+            message: "An account failed to log on".to_string(),
+            fields: HashMap::from([
+                ("event_id".to_string(), serde_json::json!(4625)),
+                ("user.name".to_string(), serde_json::json!("alice")),
+                ("hostname".to_string(), serde_json::json!("dc01")),
+            ]),
+            raw_data: "<Event/>".into(),
+            parser_name: "windows_security".to_string(),
+        }
+    }
+
+    fn transport(hec: SplunkHecConfig) -> TransportConfig {
+        let mut config = crate::config::AgentConfig::default().transport;
+        config.server_url = "https://splunk.example.com:8088".to_string();
+        config.splunk_hec = hec;
+        config
+    }
+
+    #[test]
+    fn test_event_metadata_from_templates() {
+        let encoder = HecEncoder::new(&transport(SplunkHecConfig {
+            index: "Security_{source}".to_string(),
+            sourcetype: "securewatch:{parser}".to_string(),
+            indexed_fields: vec!["event_id".to_string(), "user.name".to_string(), "missing".to_string()],
+            include_raw_data: true,
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(encoder.url(), "https://splunk.example.com:8088/services/collector/event");
+
+        let body = encoder.encode([&event(), &event()]).unwrap();
+        let lines: Vec<Value> = body.split(|b| *b == b'\n').filter(|l| !l.is_empty()).map(|l| serde_json::from_slice(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        let hec = &lines[0];
+        assert_eq!(hec["time"], json!(1_710_025_200.25));
+        assert_eq!(hec["host"], "dc01");
+        assert_eq!(hec["index"], "security_windows_event_log");
+        assert_eq!(hec["source"], "securewatch:windows_event_log");
+        assert_eq!(hec["sourcetype"], "securewatch:windows_security");
+        assert_eq!(hec["fields"], json!({ "event_id": "4625", "user.name": "alice" }));
+        assert_eq!(hec["event"]["message"], "An account failed to log on");
+        assert_eq!(hec["event"]["level"], "warning");
+        assert_eq!(hec["event"]["raw"], "<Event/>");
+    }
+
+    #[test]
+    fn test_defaults_and_validation() {
+        let encoder = HecEncoder::new(&transport(SplunkHecConfig {
+            endpoint: Some("https://hec.example.com/custom/collector".to_string()),
+            host: Some("{host.name|edge}".to_string()),
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(encoder.url(), "https://hec.example.com/custom/collector");
+        let hec = encoder.event(&event());
+        assert_eq!(hec["host"], "edge");
+        assert_eq!(hec["sourcetype"], "_json");
+        assert!(hec.get("index").is_none() && hec.get("fields").is_none());
+
+        assert!(SplunkHecConfig::default().validate().is_empty());
+        let invalid = SplunkHecConfig { sourcetype: " ".to_string(), index: "{source".to_string(), ..Default::default() };
+        assert_eq!(invalid.validate().len(), 2);
+    }
+}
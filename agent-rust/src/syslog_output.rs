@@ -0,0 +1,443 @@
+// Syslog forwarder output for legacy SIEMs
+// A destination with protocol = "syslog" formats events as RFC 5424 messages and writes them over TLS (RFC 5425)
+// or plain TCP, so the old SIEM keeps receiving events while the native transport ships to SecureWatch. Event
+// fields travel as structured data. Receivers are connected to directly, not through the transport proxy. Syslog
+// over TCP has no acknowledgements: a batch counts as delivered once it is written, and a connection the receiver
+// dropped is only noticed on the next write.
+
+use crate::config::TransportConfig;
+use crate::errors::TransportError;
+use crate::parsers::ParsedEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tracing::{debug, info};
+
+const FACILITIES: [&str; 24] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron", "authpriv", "ftp",
+    "ntp", "security", "console", "solaris-cron", "local0", "local1", "local2", "local3", "local4", "local5",
+    "local6", "local7",
+];
+
+/// Syslog output settings, used when a destination's protocol is `syslog`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyslogOutputConfig {
+    /// APP-NAME of every message
+    pub app_name: String,
+    /// Facility name, e.g. local4 or security
+    pub facility: String,
+    /// HOSTNAME of every message; defaults to the event's host.name field, then this host's name
+    pub hostname: Option<String>,
+    /// SD-ID event fields are sent under; empty sends no structured data
+    pub structured_data_id: String,
+    /// Messages are cut to this many bytes; RFC 5425 receivers must accept 2048, most accept 8192 or more
+    pub max_message_bytes: usize,
+    /// RFC 5425 octet counting; false ends each message with a newline for receivers that expect it
+    pub octet_counting: bool,
+    pub timeout_seconds: u64,
+}
+
+impl Default for SyslogOutputConfig {
+    fn default() -> Self {
+        Self {
+            app_name: "securewatch".to_string(),
+            facility: "local0".to_string(),
+            hostname: None,
+            structured_data_id: "securewatch@32473".to_string(),
+            max_message_bytes: 8192,
+            octet_counting: true,
+            timeout_seconds: 10,
+        }
+    }
+}
+
+impl SyslogOutputConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if facility_code(&self.facility).is_none() {
+            errors.push(format!("unknown facility '{}'", self.facility));
+        }
+        if header_field(&self.app_name, 48) == "-" {
+            errors.push("app_name must contain printable ASCII characters".to_string());
+        }
+        if !self.structured_data_id.is_empty() && sd_name(&self.structured_data_id) != self.structured_data_id {
+            errors.push(format!("structured_data_id '{}' must be at most 32 printable ASCII characters without '=', ']', '\"' or spaces",
+                                self.structured_data_id));
+        }
+        if self.max_message_bytes < 480 {
+            errors.push("max_message_bytes must be at least 480".to_string());
+        }
+        if self.timeout_seconds == 0 {
+            errors.push("timeout_seconds must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// Host and port of a `tls://` or `tcp://` syslog URL, and whether it uses TLS
+pub fn parse_endpoint(server_url: &str) -> Result<(String, u16, bool), String> {
+    let url = url::Url::parse(server_url).map_err(|e| format!("invalid server_url: {}", e))?;
+    let (tls, default_port) = match url.scheme() {
+        "tls" => (true, 6514),
+        "tcp" => (false, 514),
+        _ => return Err("syslog server_url must use the tls:// or tcp:// scheme".to_string()),
+    };
+    let host = url.host_str().filter(|host| !host.is_empty())
+        .ok_or_else(|| "syslog server_url has no host".to_string())?;
+    Ok((host.trim_start_matches('[').trim_end_matches(']').to_string(), url.port().unwrap_or(default_port), tls))
+}
+
+fn facility_code(name: &str) -> Option<u8> {
+    FACILITIES.iter().position(|facility| facility.eq_ignore_ascii_case(name)).map(|code| code as u8)
+}
+
+/// Syslog severity for a syslog-style or Windows event level; informational when unknown
+fn severity_code(level: Option<&str>) -> u8 {
+    match level.map(|l| l.to_ascii_lowercase()).as_deref() {
+        Some("emerg" | "emergency" | "fatal" | "panic") => 0,
+        Some("alert") => 1,
+        Some("crit" | "critical") => 2,
+        Some("err" | "error") => 3,
+        Some("warn" | "warning") => 4,
+        Some("notice") => 5,
+        Some("debug" | "trace" | "verbose") => 7,
+        _ => 6,
+    }
+}
+
+/// Header fields are printable ASCII without spaces; "-" when nothing is left
+fn header_field(value: &str, max_len: usize) -> String {
+    let field: String = value.chars().filter(|c| c.is_ascii_graphic()).take(max_len).collect();
+    if field.is_empty() { "-".to_string() } else { field }
+}
+
+/// SD-IDs and PARAM-NAMEs additionally exclude '=', ']' and '"'
+fn sd_name(value: &str) -> String {
+    value.chars().filter(|c| c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"')).take(32).collect()
+}
+
+fn sd_value(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn field_text<'a>(event: &'a ParsedEvent, names: &[&str]) -> Option<&'a str> {
+    names.iter().find_map(|name| event.fields.get(*name).and_then(|value| value.as_str()))
+}
+
+/// Formats events as RFC 5424 messages
+pub struct SyslogFormatter {
+    config: SyslogOutputConfig,
+    facility: u8,
+    local_hostname: String,
+}
+
+impl SyslogFormatter {
+    pub fn new(config: &SyslogOutputConfig) -> Self {
+        let local_hostname = hostname::get().map(|h| h.to_string_lossy().into_owned()).unwrap_or_default();
+        Self {
+            config: config.clone(),
+            facility: facility_code(&config.facility).unwrap_or(16),
+            local_hostname,
+        }
+    }
+
+    pub fn format(&self, event: &ParsedEvent) -> String {
+        let pri = self.facility as u16 * 8 + severity_code(event.level.as_deref()) as u16;
+        let hostname = self.config.hostname.as_deref()
+            .or_else(|| field_text(event, &["host.name", "hostname"]))
+            .unwrap_or(&self.local_hostname);
+        let proc_id = match event.fields.get("process.pid").or_else(|| event.fields.get("pid")) {
+            Some(Value::String(pid)) => pid.clone(),
+            Some(Value::Number(pid)) => pid.to_string(),
+            _ => String::new(),
+        };
+        let mut message = format!(
+            "<{}>1 {} {} {} {} {} {}",
+            pri,
+            event.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            header_field(hostname, 255),
+            header_field(&self.config.app_name, 48),
+            header_field(&proc_id, 128),
+            header_field(&event.source, 32),
+            self.structured_data(event),
+        );
+        if !event.message.is_empty() {
+            message.push(' ');
+            if self.config.octet_counting {
+                message.push_str(&event.message);
+            } else {
+                // Without octet counting a newline would end the message early
+                message.push_str(&event.message.replace(['\r', '\n'], " "));
+            }
+        }
+        if message.len() > self.config.max_message_bytes {
+            let cut = (0..=self.config.max_message_bytes).rev().find(|i| message.is_char_boundary(*i)).unwrap_or(0);
+            message.truncate(cut);
+        }
+        message
+    }
+
+    fn structured_data(&self, event: &ParsedEvent) -> String {
+        if self.config.structured_data_id.is_empty() {
+            return "-".to_string();
+        }
+        let mut element = format!("[{} parser=\"{}\"", self.config.structured_data_id, sd_value(&Value::String(event.parser_name.clone())));
+        let mut fields: Vec<_> = event.fields.iter().collect();
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in fields {
+            let name = sd_name(name);
+            if !name.is_empty() && !value.is_null() {
+                element.push_str(&format!(" {}=\"{}\"", name, sd_value(value)));
+            }
+        }
+        element.push(']');
+        element
+    }
+
+    /// One message framed for the wire
+    pub fn frame(&self, event: &ParsedEvent) -> Vec<u8> {
+        let message = self.format(event);
+        if self.config.octet_counting {
+            format!("{} {}", message.len(), message).into_bytes()
+        } else {
+            format!("{}\n", message).into_bytes()
+        }
+    }
+}
+
+enum Connection {
+    Tcp(TcpStream),
+    #[cfg(feature = "native-tls-backend")]
+    Tls(Box<tokio_native_tls::TlsStream<TcpStream>>),
+}
+
+impl Connection {
+    async fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => {
+                stream.write_all(bytes).await?;
+                stream.flush().await
+            }
+            #[cfg(feature = "native-tls-backend")]
+            Connection::Tls(stream) => {
+                stream.write_all(bytes).await?;
+                stream.flush().await
+            }
+        }
+    }
+}
+
+/// Persistent connection to a syslog receiver, reopened after a failed write
+pub struct SyslogForwarder {
+    formatter: SyslogFormatter,
+    // Name the receiver's certificate is checked against
+    #[cfg(feature = "native-tls-backend")]
+    host: String,
+    address: String,
+    timeout: Duration,
+    #[cfg(feature = "native-tls-backend")]
+    tls: Option<tokio_native_tls::TlsConnector>,
+    connection: tokio::sync::Mutex<Option<Connection>>,
+}
+
+impl SyslogForwarder {
+    /// Forwarder for a destination's resolved transport settings; TLS uses its CA, client certificate and tls_verify
+    pub fn new(config: &TransportConfig) -> Result<Self, TransportError> {
+        let syslog = &config.syslog;
+        if let Some(e) = syslog.validate().into_iter().next() {
+            return Err(TransportError::configuration_invalid(&format!("syslog output: {}", e)));
+        }
+        let (host, port, tls) = parse_endpoint(&config.server_url)
+            .map_err(|e| TransportError::configuration_invalid(&e))?;
+        let address = if host.contains(':') { format!("[{}]:{}", host, port) } else { format!("{}:{}", host, port) };
+        info!("📜 Forwarding events as RFC 5424 syslog to {} over {}", address, if tls { "TLS" } else { "plain TCP" });
+
+        #[cfg(feature = "native-tls-backend")]
+        let tls = match tls {
+            true => Some(Self::tls_connector(config)?),
+            false => None,
+        };
+        #[cfg(not(feature = "native-tls-backend"))]
+        if tls {
+            return Err(TransportError::configuration_invalid("syslog over TLS requires the native-tls-backend feature"));
+        }
+
+        Ok(Self {
+            formatter: SyslogFormatter::new(syslog),
+            #[cfg(feature = "native-tls-backend")]
+            host,
+            address,
+            timeout: Duration::from_secs(syslog.timeout_seconds),
+            #[cfg(feature = "native-tls-backend")]
+            tls,
+            connection: tokio::sync::Mutex::new(None),
+        })
+    }
+
+    #[cfg(feature = "native-tls-backend")]
+    fn tls_connector(config: &TransportConfig) -> Result<tokio_native_tls::TlsConnector, TransportError> {
+        let tls_error = |operation: &str, reason: String| TransportError::TlsError {
+            operation: operation.to_string(),
+            reason: reason.clone(),
+            certificate_issue: true,
+            source: Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, reason)),
+        };
+        let read = |path: &str| std::fs::read(path)
+            .map_err(|e| tls_error("read_certificate", format!("Failed to read {}: {}", path, e)));
+
+        let mut builder = native_tls::TlsConnector::builder();
+        if !config.tls_verify {
+            builder.danger_accept_invalid_certs(true);
+        }
+        if let Some(ca_path) = &config.ca_cert_path {
+            let ca = native_tls::Certificate::from_pem(&read(ca_path)?)
+                .map_err(|e| tls_error("parse_ca_certificate", format!("Invalid CA certificate {}: {}", ca_path, e)))?;
+            builder.add_root_certificate(ca);
+        }
+        if let (Some(cert_path), Some(key_path)) = (&config.client_cert_path, &config.client_key_path) {
+            if config.client_key_password.is_some() {
+                return Err(TransportError::configuration_invalid("syslog output needs an unencrypted PKCS#8 client key"));
+            }
+            let identity = native_tls::Identity::from_pkcs8(&read(cert_path)?, &read(key_path)?)
+                .map_err(|e| tls_error("load_client_certificate", format!("Invalid client certificate or key: {}", e)))?;
+            builder.identity(identity);
+        }
+        let connector = builder.build()
+            .map_err(|e| tls_error("create_tls_connector", format!("Failed to create TLS connector: {}", e)))?;
+        Ok(tokio_native_tls::TlsConnector::from(connector))
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Framed messages for a batch, written in one go by `send`
+    pub fn encode<'a>(&self, events: impl IntoIterator<Item = &'a ParsedEvent>) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for event in events {
+            payload.extend_from_slice(&self.formatter.frame(event));
+        }
+        payload
+    }
+
+    /// Write encoded messages on the open connection, connecting first when there is none
+    pub async fn send(&self, payload: &[u8]) -> Result<(), TransportError> {
+        let mut connection = self.connection.lock().await;
+        match tokio::time::timeout(self.timeout, self.write(&mut connection, payload)).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => {
+                *connection = None;
+                Err(TransportError::ConnectionFailed {
+                    endpoint: self.address.clone(),
+                    attempts: 1,
+                    last_error: e.to_string(),
+                    retry_after: None,
+                })
+            }
+            Err(_) => {
+                *connection = None;
+                Err(TransportError::Timeout {
+                    operation: "syslog_send".to_string(),
+                    duration_ms: self.timeout.as_millis() as u64,
+                    retryable: true,
+                })
+            }
+        }
+    }
+
+    async fn write(&self, connection: &mut Option<Connection>, payload: &[u8]) -> std::io::Result<()> {
+        if connection.is_none() {
+            *connection = Some(self.connect().await?);
+            debug!("📜 Connected to syslog receiver {}", self.address);
+        }
+        connection.as_mut().expect("syslog connection was just established").write(payload).await
+    }
+
+    async fn connect(&self) -> std::io::Result<Connection> {
+        let stream = TcpStream::connect(&self.address).await?;
+        #[cfg(feature = "native-tls-backend")]
+        if let Some(tls) = &self.tls {
+            let stream = tls.connect(&self.host, stream).await
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::ConnectionRefused, format!("TLS handshake failed: {}", e)))?;
+            return Ok(Connection::Tls(Box::new(stream)));
+        }
+        Ok(Connection::Tcp(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn event() -> ParsedEvent {
+        let mut fields = HashMap::new();
+        fields.insert("host.name".to_string(), serde_json::json!("web 01"));
+        fields.insert("process.pid".to_string(), serde_json::json!(4242));
+        fields.insert("user.name".to_string(), serde_json::json!("ro\"ot]"));
+        fields.insert("event_id".to_string(), serde_json::json!(4625));
+        ParsedEvent {
+            timestamp: chrono::DateTime::parse_from_rfc3339("2026-03-01T12:00:00.5Z").unwrap().with_timezone(&chrono::Utc),
+            source: "windows_event_log".to_string(),
+            level: Some("warning".to_string()),
+            message: "An account failed to log on.\nLogon Type: 3".to_string(),
+            fields,
+            raw_data: "".into(),
+            parser_name: "windows".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rfc5424_format_and_framing() {
+        let config = SyslogOutputConfig { facility: "auth".to_string(), ..Default::default() };
+        let formatter = SyslogFormatter::new(&config);
+        let message = formatter.format(&event());
+        // auth (4) * 8 + warning (4)
+        assert_eq!(message,
+                   "<36>1 2026-03-01T12:00:00.500000Z web01 securewatch 4242 windows_event_log \
+                    [securewatch@32473 parser=\"windows\" event_id=\"4625\" host.name=\"web 01\" process.pid=\"4242\" user.name=\"ro\\\"ot\\]\"] \
+                    An account failed to log on.\nLogon Type: 3");
+        let frame = String::from_utf8(formatter.frame(&event())).unwrap();
+        assert_eq!(frame, format!("{} {}", message.len(), message));
+
+        let newline = SyslogFormatter::new(&SyslogOutputConfig { octet_counting: false, structured_data_id: String::new(), ..config });
+        let frame = String::from_utf8(newline.frame(&event())).unwrap();
+        assert!(frame.ends_with(" - An account failed to log on. Logon Type: 3\n"));
+        assert_eq!(frame.matches('\n').count(), 1);
+    }
+
+    #[test]
+    fn test_endpoint_and_validation() {
+        assert_eq!(parse_endpoint("tls://siem.example.com").unwrap(), ("siem.example.com".to_string(), 6514, true));
+        assert_eq!(parse_endpoint("tcp://10.0.0.5:1514").unwrap(), ("10.0.0.5".to_string(), 1514, false));
+        assert!(parse_endpoint("https://siem.example.com").is_err());
+
+        assert!(SyslogOutputConfig::default().validate().is_empty());
+        let invalid = SyslogOutputConfig {
+            facility: "local9".to_string(),
+            structured_data_id: "bad id".to_string(),
+            max_message_bytes: 100,
+            ..Default::default()
+        };
+        assert_eq!(invalid.validate().len(), 3);
+
+        let mut event = event();
+        event.message = "x".repeat(10_000);
+        let formatter = SyslogFormatter::new(&SyslogOutputConfig::default());
+        assert_eq!(formatter.format(&event).len(), 8192);
+    }
+}
@@ -0,0 +1,855 @@
+// Threat intelligence indicator matching at the edge
+// Indicator feeds (STIX 2.1 bundles, TAXII 2.1 collections or CSV lists) are downloaded on a schedule into an
+// in-memory store of IP addresses and networks, domains and file hashes. The threat_intel enricher matches event
+// fields against it and records hits in ECS `threat.enrichments`, so the server only has to correlate the hits.
+// The last good copy of every feed is kept on disk, so a restart without network access still matches
+
+use crate::parsers::ParsedEvent;
+use crate::proxy::ProxyConfig;
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// TAXII pages fetched per refresh at most
+const MAX_TAXII_PAGES: usize = 1000;
+const TAXII_MEDIA_TYPE: &str = "application/taxii+json;version=2.1";
+
+fn default_max_feed_bytes() -> u64 {
+    256 * 1024 * 1024
+}
+
+/// Threat intel enricher configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThreatIntelConfig {
+    pub feeds: Vec<ThreatIntelFeedConfig>,
+    /// How often every feed is downloaded again (seconds)
+    pub refresh_interval_seconds: u64,
+    pub timeout_seconds: u64,
+    /// Largest feed download (or TAXII page) accepted; larger bodies fail the refresh of that feed
+    #[serde(default = "default_max_feed_bytes")]
+    pub max_feed_bytes: u64,
+    /// Last good copy of every feed, loaded at startup before the first download finishes
+    pub cache_directory: String,
+    /// Fields holding IP addresses, domains and file hashes
+    pub ip_fields: Vec<String>,
+    pub domain_fields: Vec<String>,
+    /// Sysmon-style `SHA256=...,MD5=...` values are split into their hashes
+    pub hash_fields: Vec<String>,
+}
+
+impl Default for ThreatIntelConfig {
+    fn default() -> Self {
+        Self {
+            feeds: Vec::new(),
+            refresh_interval_seconds: 3600,
+            timeout_seconds: 60,
+            max_feed_bytes: default_max_feed_bytes(),
+            cache_directory: "./threat_intel".to_string(),
+            ip_fields: ["source.ip", "destination.ip", "client.ip", "server.ip"].map(String::from).to_vec(),
+            domain_fields: ["dns.question.name", "url.domain", "destination.domain", "server.domain"].map(String::from).to_vec(),
+            hash_fields: [
+                "file.hash.md5", "file.hash.sha1", "file.hash.sha256",
+                "process.hash.md5", "process.hash.sha1", "process.hash.sha256", "Hashes",
+            ].map(String::from).to_vec(),
+        }
+    }
+}
+
+/// An indicator feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreatIntelFeedConfig {
+    /// Reported in `threat.enrichments` and used for the cached copy's file name
+    pub name: String,
+    /// http(s) URL, or a path to a local file; for TAXII, the collection's objects endpoint
+    pub url: String,
+    pub format: FeedFormat,
+    #[serde(default)]
+    pub auth: FeedAuth,
+    /// CSV: header of the column holding indicators; without it, the first column of every row
+    #[serde(default)]
+    pub column: Option<String>,
+    /// CSV: header of a column describing the indicator, such as the malware family
+    #[serde(default)]
+    pub description_column: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedFormat {
+    /// A STIX 2.1 bundle of indicator objects
+    Stix,
+    /// A TAXII 2.1 collection's objects endpoint, followed page by page
+    Taxii,
+    /// One indicator per row; the type is taken from the value
+    Csv,
+}
+
+/// How a feed download authenticates; secrets are read from environment variables
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeedAuth {
+    #[default]
+    None,
+    /// `Authorization: Bearer <token>`
+    Bearer { token_env: String },
+    /// A custom header, e.g. `X-OTX-API-KEY`
+    Header {
+        name: String,
+        value_env: String,
+        #[serde(default)]
+        prefix: String,
+    },
+    Basic { username: String, password_env: String },
+}
+
+impl ThreatIntelConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.feeds.is_empty() {
+            errors.push("at least one feed is required".to_string());
+        }
+        let mut names = std::collections::HashSet::new();
+        for feed in &self.feeds {
+            if !is_valid_feed_name(&feed.name) {
+                errors.push(format!("feed name '{}' must be letters, digits, '_' or '-'", feed.name));
+            } else if !names.insert(feed.name.as_str()) {
+                errors.push(format!("duplicate feed name '{}'", feed.name));
+            }
+            if feed.url.is_empty() {
+                errors.push(format!("feed '{}': url is required", feed.name));
+            }
+            if feed.format == FeedFormat::Taxii && !is_remote(&feed.url) {
+                errors.push(format!("feed '{}': a TAXII feed needs an http(s) url", feed.name));
+            }
+            if feed.format != FeedFormat::Csv && (feed.column.is_some() || feed.description_column.is_some()) {
+                errors.push(format!("feed '{}': column and description_column only apply to CSV feeds", feed.name));
+            }
+        }
+        if self.refresh_interval_seconds < 60 {
+            errors.push("refresh_interval_seconds must be at least 60".to_string());
+        }
+        if self.timeout_seconds == 0 {
+            errors.push("timeout_seconds must be greater than 0".to_string());
+        }
+        if self.max_feed_bytes == 0 {
+            errors.push("max_feed_bytes must be greater than 0".to_string());
+        }
+        if self.cache_directory.is_empty() {
+            errors.push("cache_directory is required".to_string());
+        }
+        if self.ip_fields.is_empty() && self.domain_fields.is_empty() && self.hash_fields.is_empty() {
+            errors.push("at least one IP, domain or hash field is required".to_string());
+        }
+        errors
+    }
+}
+
+/// Feed names become cache file names, so they may not contain path separators or dots
+fn is_valid_feed_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn is_remote(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndicatorKind {
+    /// An address or a network in CIDR notation
+    Ip,
+    /// A domain; its subdomains match too
+    Domain,
+    /// An MD5, SHA-1 or SHA-256 file hash
+    Hash,
+}
+
+/// One indicator from a feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Indicator {
+    pub kind: IndicatorKind,
+    pub value: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl Indicator {
+    /// The indicator a bare value stands for, if it looks like one
+    pub fn infer(value: &str, description: Option<String>) -> Option<Self> {
+        let value = value.trim().trim_matches('"');
+        let kind = if parse_network(value).is_some() {
+            IndicatorKind::Ip
+        } else if is_hash(value) {
+            IndicatorKind::Hash
+        } else if is_domain(value) {
+            IndicatorKind::Domain
+        } else {
+            return None;
+        };
+        Some(Self { kind, value: normalize(kind, value), description })
+    }
+}
+
+fn normalize(kind: IndicatorKind, value: &str) -> String {
+    match kind {
+        IndicatorKind::Ip => value.to_string(),
+        IndicatorKind::Domain => value.trim_start_matches("*.").trim_end_matches('.').to_ascii_lowercase(),
+        IndicatorKind::Hash => value.to_ascii_lowercase(),
+    }
+}
+
+fn is_hash(value: &str) -> bool {
+    matches!(value.len(), 32 | 40 | 64) && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_domain(value: &str) -> bool {
+    let value = value.trim_start_matches("*.").trim_end_matches('.');
+    value.contains('.')
+        && value.split('.').all(|label| !label.is_empty() && label.len() <= 63)
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_')
+        && !value.rsplit('.').next().is_some_and(|tld| tld.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// An address, or a network and its prefix length
+fn parse_network(value: &str) -> Option<(IpAddr, u8)> {
+    let (address, prefix) = match value.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix)),
+        None => (value, None),
+    };
+    let address: IpAddr = address.parse().ok()?;
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix {
+        Some(prefix) => prefix.parse().ok().filter(|p| *p <= max)?,
+        None => max,
+    };
+    Some((address, prefix))
+}
+
+fn in_network(ip: &IpAddr, network: &IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(*ip) & mask == u32::from(*network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(*ip) & mask == u128::from(*network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Equality comparisons in a STIX pattern, e.g. `[ipv4-addr:value = '198.51.100.7']` or
+/// `[file:hashes.'SHA-256' = '...']`; comparisons joined by OR each yield an indicator
+fn stix_comparison_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(ipv4-addr|ipv6-addr|domain-name|file):(value|hashes\.(?:'[^']+'|[A-Za-z0-9-]+))\s*=\s*'((?:[^'\\]|\\.)*)'")
+            .expect("valid STIX comparison regex")
+    })
+}
+
+/// Indicators from the `indicator` objects of a STIX bundle or TAXII envelope; revoked and expired ones are left out
+pub fn parse_stix(document: &Value) -> Vec<Indicator> {
+    let now = Utc::now();
+    let objects = document.get("objects").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    let mut indicators = Vec::new();
+    for object in objects {
+        if object.get("type").and_then(Value::as_str) != Some("indicator")
+            || object.get("revoked").and_then(Value::as_bool) == Some(true)
+            || object.get("pattern_type").and_then(Value::as_str).is_some_and(|t| t != "stix")
+        {
+            continue;
+        }
+        let expired = object.get("valid_until")
+            .and_then(Value::as_str)
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .is_some_and(|until| until < now);
+        if expired {
+            continue;
+        }
+        let Some(pattern) = object.get("pattern").and_then(Value::as_str) else {
+            continue;
+        };
+        let description = object.get("name").or_else(|| object.get("description"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        for comparison in stix_comparison_regex().captures_iter(pattern) {
+            let value = comparison[3].replace("\\'", "'").replace("\\\\", "\\");
+            let kind = match &comparison[1] {
+                "ipv4-addr" | "ipv6-addr" if parse_network(&value).is_some() => IndicatorKind::Ip,
+                "domain-name" => IndicatorKind::Domain,
+                "file" if comparison[2].starts_with("hashes") && is_hash(&value) => IndicatorKind::Hash,
+                _ => continue,
+            };
+            indicators.push(Indicator { kind, value: normalize(kind, &value), description: description.clone() });
+        }
+    }
+    indicators
+}
+
+/// Indicators from a CSV feed. Lines starting with '#' are comments, except that with `column` set, the first line
+/// naming that column is the header even when commented out, as in the abuse.ch exports.
+pub fn parse_csv(text: &str, column: Option<&str>, description_column: Option<&str>) -> Vec<Indicator> {
+    let mut columns: Option<(usize, Option<usize>)> = None;
+    let mut indicators = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let cells = split_csv_line(line.trim_start_matches('#').trim_start());
+        let Some(column) = column else {
+            if !line.starts_with('#') {
+                indicators.extend(Indicator::infer(&cells[0], None));
+            }
+            continue;
+        };
+        match columns {
+            None => {
+                let position = |name: &str| cells.iter().position(|cell| cell.eq_ignore_ascii_case(name));
+                if let Some(value) = position(column) {
+                    columns = Some((value, description_column.and_then(position)));
+                }
+            }
+            Some(_) if line.starts_with('#') => {}
+            Some((value, description)) => {
+                let Some(cell) = cells.get(value) else {
+                    continue;
+                };
+                let description = description.and_then(|i| cells.get(i)).filter(|d| !d.is_empty()).cloned();
+                indicators.extend(Indicator::infer(cell, description));
+            }
+        }
+    }
+    indicators
+}
+
+/// Cells of one CSV line; double quotes group cells containing commas, and a doubled quote is a literal one
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut cells = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cells.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells.iter_mut().for_each(|cell| *cell = cell.trim().to_string());
+    cells
+}
+
+/// Where a matched indicator came from
+#[derive(Debug, Clone)]
+pub struct IndicatorSource {
+    pub feed: Arc<str>,
+    pub description: Option<Arc<str>>,
+}
+
+/// Indicators of every feed, indexed for lookups
+#[derive(Debug, Default)]
+pub struct IndicatorStore {
+    ips: HashMap<IpAddr, IndicatorSource>,
+    networks: Vec<(IpAddr, u8, IndicatorSource)>,
+    domains: HashMap<String, IndicatorSource>,
+    hashes: HashMap<String, IndicatorSource>,
+}
+
+impl IndicatorStore {
+    /// When several feeds list the same indicator, the first feed is reported
+    pub fn build<'a>(feeds: impl IntoIterator<Item = (&'a str, &'a [Indicator])>) -> Self {
+        let mut store = Self::default();
+        for (feed, indicators) in feeds {
+            let feed: Arc<str> = Arc::from(feed);
+            for indicator in indicators {
+                let source = IndicatorSource { feed: feed.clone(), description: indicator.description.as_deref().map(Arc::from) };
+                match indicator.kind {
+                    IndicatorKind::Ip => match parse_network(&indicator.value) {
+                        Some((ip, prefix)) if prefix == if ip.is_ipv4() { 32 } else { 128 } => {
+                            store.ips.entry(ip).or_insert(source);
+                        }
+                        Some((network, prefix)) => store.networks.push((network, prefix, source)),
+                        None => {}
+                    },
+                    IndicatorKind::Domain => {
+                        store.domains.entry(indicator.value.clone()).or_insert(source);
+                    }
+                    IndicatorKind::Hash => {
+                        store.hashes.entry(indicator.value.clone()).or_insert(source);
+                    }
+                }
+            }
+        }
+        store
+    }
+
+    pub fn len(&self) -> usize {
+        self.ips.len() + self.networks.len() + self.domains.len() + self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn match_ip(&self, ip: &IpAddr) -> Option<&IndicatorSource> {
+        self.ips.get(ip).or_else(|| {
+            self.networks.iter().find(|(network, prefix, _)| in_network(ip, network, *prefix)).map(|(_, _, source)| source)
+        })
+    }
+
+    /// The domain or its nearest listed parent
+    pub fn match_domain(&self, domain: &str) -> Option<&IndicatorSource> {
+        let domain = normalize(IndicatorKind::Domain, domain);
+        let mut candidate = domain.as_str();
+        loop {
+            if let Some(source) = self.domains.get(candidate) {
+                return Some(source);
+            }
+            candidate = candidate.split_once('.')?.1;
+            if !candidate.contains('.') {
+                return None;
+            }
+        }
+    }
+
+    pub fn match_hash(&self, hash: &str) -> Option<&IndicatorSource> {
+        self.hashes.get(&hash.to_ascii_lowercase())
+    }
+}
+
+/// Feeds, their last good indicators and the store built from them; shared by the enricher and its refresh task
+pub struct ThreatIntel {
+    config: ThreatIntelConfig,
+    /// `transport.proxy`, so feeds download from hosts that only reach the internet through it
+    proxy: ProxyConfig,
+    feeds: Mutex<HashMap<String, Vec<Indicator>>>,
+    store: RwLock<Arc<IndicatorStore>>,
+}
+
+impl ThreatIntel {
+    /// Loads the cached copies of the feeds; nothing is downloaded until `refresh`
+    pub fn new(config: ThreatIntelConfig, proxy: ProxyConfig) -> Self {
+        let intel = Self { config, proxy, feeds: Mutex::new(HashMap::new()), store: RwLock::new(Arc::default()) };
+        for feed in &intel.config.feeds {
+            let Some(path) = intel.cache_path(&feed.name) else {
+                warn!("⚠️ Threat intel feed name '{}' is not usable as a file name, skipping its cached copy", feed.name);
+                continue;
+            };
+            match std::fs::read(&path).map_err(|e| e.to_string()).and_then(|bytes| {
+                serde_json::from_slice::<Vec<Indicator>>(&bytes).map_err(|e| e.to_string())
+            }) {
+                Ok(indicators) => {
+                    debug!("🛡️ Loaded {} cached indicators for feed '{}'", indicators.len(), feed.name);
+                    intel.feeds.lock().insert(feed.name.clone(), indicators);
+                }
+                Err(e) if path.exists() => warn!("⚠️ Cached threat intel feed {} unreadable: {}", path.display(), e),
+                Err(_) => {}
+            }
+        }
+        intel.rebuild();
+        intel
+    }
+
+    pub fn config(&self) -> &ThreatIntelConfig {
+        &self.config
+    }
+
+    pub fn store(&self) -> Arc<IndicatorStore> {
+        self.store.read().clone()
+    }
+
+    /// Refresh every feed now, and then every `refresh_interval_seconds` for as long as `intel` is alive
+    pub fn start_refresh(intel: &Arc<Self>) {
+        if tokio::runtime::Handle::try_current().is_err() {
+            return;
+        }
+        let weak: Weak<Self> = Arc::downgrade(intel);
+        let period = Duration::from_secs(intel.config.refresh_interval_seconds.max(1));
+        crate::component_usage::spawn_inherited(async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                // The pipeline that owned the enricher was replaced or shut down
+                let Some(intel) = weak.upgrade() else {
+                    break;
+                };
+                intel.refresh().await;
+            }
+        });
+    }
+
+    /// Download every feed; a feed that fails keeps its previous indicators. Returns the indicators in the store.
+    pub async fn refresh(&self) -> usize {
+        for feed in &self.config.feeds {
+            let fetched = match self.client(&feed.url) {
+                Ok(client) => self.fetch(&client, feed).await,
+                Err(e) => Err(format!("client could not be built: {}", e)),
+            };
+            match fetched {
+                Ok(indicators) => {
+                    info!("🛡️ Threat intel feed '{}' refreshed: {} indicators", feed.name, indicators.len());
+                    if let Err(e) = self.save(&feed.name, &indicators) {
+                        warn!("⚠️ Threat intel feed '{}' could not be cached: {}", feed.name, e);
+                    }
+                    self.feeds.lock().insert(feed.name.clone(), indicators);
+                }
+                Err(e) => warn!("⚠️ Threat intel feed '{}' refresh failed, keeping the previous indicators: {}", feed.name, e),
+            }
+        }
+        self.rebuild()
+    }
+
+    /// Client for one feed, through the proxy `transport.proxy` resolves for its URL
+    fn client(&self, url: &str) -> Result<reqwest::Client, String> {
+        let builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.config.timeout_seconds))
+            .user_agent(concat!("securewatch-agent/", env!("CARGO_PKG_VERSION")));
+        let builder = if is_remote(url) { self.proxy.apply(builder, url)? } else { builder };
+        builder.build().map_err(|e| e.to_string())
+    }
+
+    async fn fetch(&self, client: &reqwest::Client, feed: &ThreatIntelFeedConfig) -> Result<Vec<Indicator>, String> {
+        match feed.format {
+            FeedFormat::Taxii => {
+                let mut indicators = Vec::new();
+                let mut next: Option<String> = None;
+                for _ in 0..MAX_TAXII_PAGES {
+                    let mut request = client.get(&feed.url).header(reqwest::header::ACCEPT, TAXII_MEDIA_TYPE);
+                    if let Some(next) = &next {
+                        request = request.query(&[("next", next)]);
+                    }
+                    let envelope: Value = serde_json::from_slice(&send(authorize(request, &feed.auth)?, self.config.max_feed_bytes).await?)
+                        .map_err(|e| format!("invalid TAXII envelope: {}", e))?;
+                    indicators.extend(parse_stix(&envelope));
+                    next = envelope.get("next").and_then(Value::as_str).map(str::to_string);
+                    if envelope.get("more").and_then(Value::as_bool) != Some(true) || next.is_none() {
+                        return Ok(indicators);
+                    }
+                }
+                warn!("⚠️ Threat intel feed '{}' stopped after {} TAXII pages", feed.name, MAX_TAXII_PAGES);
+                Ok(indicators)
+            }
+            FeedFormat::Stix => {
+                let bundle: Value = serde_json::from_slice(&self.download(client, feed).await?)
+                    .map_err(|e| format!("invalid STIX bundle: {}", e))?;
+                Ok(parse_stix(&bundle))
+            }
+            FeedFormat::Csv => {
+                let body = self.download(client, feed).await?;
+                Ok(parse_csv(&String::from_utf8_lossy(&body), feed.column.as_deref(), feed.description_column.as_deref()))
+            }
+        }
+    }
+
+    async fn download(&self, client: &reqwest::Client, feed: &ThreatIntelFeedConfig) -> Result<Vec<u8>, String> {
+        if !is_remote(&feed.url) {
+            let length = tokio::fs::metadata(&feed.url).await.map_err(|e| format!("{}: {}", feed.url, e))?.len();
+            if length > self.config.max_feed_bytes {
+                return Err(format!("{} is {} bytes, over max_feed_bytes ({})", feed.url, length, self.config.max_feed_bytes));
+            }
+            return tokio::fs::read(&feed.url).await.map_err(|e| format!("{}: {}", feed.url, e));
+        }
+        send(authorize(client.get(&feed.url), &feed.auth)?, self.config.max_feed_bytes).await
+    }
+
+    /// None for names that could escape the cache directory
+    fn cache_path(&self, feed: &str) -> Option<PathBuf> {
+        is_valid_feed_name(feed).then(|| Path::new(&self.config.cache_directory).join(format!("{}.json", feed)))
+    }
+
+    /// Write a feed's indicators through a temporary file, so a partial copy is never loaded
+    fn save(&self, feed: &str, indicators: &[Indicator]) -> std::io::Result<()> {
+        let path = self.cache_path(feed)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "feed name is not usable as a file name"))?;
+        std::fs::create_dir_all(&self.config.cache_directory)?;
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_vec(indicators)?)?;
+        std::fs::rename(&partial, &path)
+    }
+
+    fn rebuild(&self) -> usize {
+        let feeds = self.feeds.lock();
+        // Configuration order decides which feed is reported for an indicator several feeds list
+        let store = IndicatorStore::build(self.config.feeds.iter().filter_map(|feed| {
+            feeds.get(&feed.name).map(|indicators| (feed.name.as_str(), indicators.as_slice()))
+        }));
+        let count = store.len();
+        *self.store.write() = Arc::new(store);
+        count
+    }
+
+    /// ECS `threat.enrichments` entries for the event's fields that match an indicator
+    pub fn enrichments(&self, event: &ParsedEvent) -> Vec<Value> {
+        let store = self.store();
+        if store.is_empty() {
+            return Vec::new();
+        }
+        let mut enrichments = Vec::new();
+        for field in &self.config.ip_fields {
+            for value in field_values(event, field) {
+                let Ok(ip) = value.trim().parse::<IpAddr>() else {
+                    continue;
+                };
+                if let Some(source) = store.match_ip(&ip) {
+                    let kind = if ip.is_ipv4() { "ipv4-addr" } else { "ipv6-addr" };
+                    enrichments.push(enrichment(kind, field, value, source));
+                }
+            }
+        }
+        for field in &self.config.domain_fields {
+            for value in field_values(event, field) {
+                if let Some(source) = store.match_domain(value) {
+                    enrichments.push(enrichment("domain-name", field, value, source));
+                }
+            }
+        }
+        for field in &self.config.hash_fields {
+            for value in field_values(event, field) {
+                // Sysmon lists several hashes in one field: "SHA256=...,MD5=..."
+                for hash in value.split([',', '=']).map(str::trim).filter(|token| is_hash(token)) {
+                    if let Some(source) = store.match_hash(hash) {
+                        enrichments.push(enrichment("file", field, hash, source));
+                    }
+                }
+            }
+        }
+        enrichments
+    }
+}
+
+/// String values of a field, including the strings in an array field
+fn field_values<'a>(event: &'a ParsedEvent, field: &str) -> Vec<&'a str> {
+    match event.fields.get(field) {
+        Some(Value::String(value)) => vec![value.as_str()],
+        Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn enrichment(kind: &str, field: &str, atomic: &str, source: &IndicatorSource) -> Value {
+    let mut indicator = json!({ "type": kind });
+    if let Some(description) = &source.description {
+        indicator["description"] = json!(description.as_ref());
+    }
+    json!({
+        "indicator": indicator,
+        "matched": { "atomic": atomic, "field": field, "type": "indicator_match_rule" },
+        "feed": { "name": source.feed.as_ref() },
+    })
+}
+
+fn authorize(request: reqwest::RequestBuilder, auth: &FeedAuth) -> Result<reqwest::RequestBuilder, String> {
+    let secret = |variable: &str| std::env::var(variable).map_err(|_| format!("environment variable {} is not set", variable));
+    Ok(match auth {
+        FeedAuth::None => request,
+        FeedAuth::Bearer { token_env } => request.bearer_auth(secret(token_env)?),
+        FeedAuth::Header { name, value_env, prefix } => request.header(name, format!("{}{}", prefix, secret(value_env)?)),
+        FeedAuth::Basic { username, password_env } => request.basic_auth(username, Some(secret(password_env)?)),
+    })
+}
+
+/// Response body of a successful request, read up to `max_bytes`
+async fn send(request: reqwest::RequestBuilder, max_bytes: u64) -> Result<Vec<u8>, String> {
+    let mut response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {}", status));
+    }
+    let too_large = || format!("response is larger than max_feed_bytes ({})", max_bytes);
+    if response.content_length().is_some_and(|length| length > max_bytes) {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if (body.len() + chunk.len()) as u64 > max_bytes {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(fields: &[(&str, &str)]) -> ParsedEvent {
+        ParsedEvent {
+            timestamp: Utc::now(),
+            source: "syslog".to_string(),
+            level: None,
+            message: "test".to_string(),
+            fields: fields.iter().map(|(k, v)| (k.to_string(), json!(v))).collect(),
+            raw_data: "test".into(),
+            parser_name: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parses_stix_and_csv_feeds() {
+        let bundle = json!({
+            "type": "bundle",
+            "objects": [
+                { "type": "indicator", "name": "Emotet C2", "pattern_type": "stix",
+                  "pattern": "[ipv4-addr:value = '198.51.100.7'] OR [domain-name:value = 'Evil.Example.']" },
+                { "type": "indicator", "pattern": "[file:hashes.'SHA-256' = 'AABBCCDDEEFF00112233445566778899AABBCCDDEEFF00112233445566778899']" },
+                { "type": "indicator", "revoked": true, "pattern": "[ipv4-addr:value = '192.0.2.1']" },
+                { "type": "indicator", "valid_until": "2001-01-01T00:00:00Z", "pattern": "[ipv4-addr:value = '192.0.2.2']" },
+                { "type": "malware", "name": "Emotet" },
+            ]
+        });
+        let indicators = parse_stix(&bundle);
+        assert_eq!(indicators.iter().map(|i| (i.kind, i.value.as_str())).collect::<Vec<_>>(), vec![
+            (IndicatorKind::Ip, "198.51.100.7"),
+            (IndicatorKind::Domain, "evil.example"),
+            (IndicatorKind::Hash, "aabbccddeeff00112233445566778899aabbccddeeff00112233445566778899"),
+        ]);
+        assert_eq!(indicators[0].description.as_deref(), Some("Emotet C2"));
+
+        let csv = "# abuse.ch export\n# \"first_seen_utc\",\"dst_ip\",\"malware\"\n\"2024-03-09 10:00:00\",\"203.0.113.9\",\"QakBot\"\n";
+        let indicators = parse_csv(csv, Some("dst_ip"), Some("malware"));
+        assert_eq!(indicators, vec![Indicator {
+            kind: IndicatorKind::Ip,
+            value: "203.0.113.9".to_string(),
+            description: Some("QakBot".to_string()),
+        }]);
+
+        let plain = parse_csv("# comment\n10.20.0.0/16\nbad.example.net\n44d88612fea8a8f36de82e1278abb02f\nnot an indicator\n", None, None);
+        assert_eq!(plain.iter().map(|i| i.kind).collect::<Vec<_>>(), vec![IndicatorKind::Ip, IndicatorKind::Domain, IndicatorKind::Hash]);
+    }
+
+    #[tokio::test]
+    async fn test_matches_event_fields_and_keeps_cached_feeds() {
+        let dir = tempfile::tempdir().unwrap();
+        let feed_path = dir.path().join("blocklist.csv");
+        std::fs::write(&feed_path, "10.20.0.0/16\nbad.example.net\n44d88612fea8a8f36de82e1278abb02f\n").unwrap();
+        let config = ThreatIntelConfig {
+            feeds: vec![ThreatIntelFeedConfig {
+                name: "blocklist".to_string(),
+                url: feed_path.display().to_string(),
+                format: FeedFormat::Csv,
+                auth: FeedAuth::None,
+                column: None,
+                description_column: None,
+            }],
+            cache_directory: dir.path().join("cache").display().to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_empty());
+
+        let intel = ThreatIntel::new(config.clone(), ProxyConfig::default());
+        assert!(intel.enrichments(&event(&[("source.ip", "10.20.3.4")])).is_empty());
+        assert_eq!(intel.refresh().await, 3);
+
+        let matched = intel.enrichments(&event(&[
+            ("source.ip", "10.20.3.4"),
+            ("destination.ip", "10.21.0.1"),
+            ("dns.question.name", "cdn.BAD.example.net."),
+            ("Hashes", "SHA1=0000000000000000000000000000000000000000,MD5=44D88612FEA8A8F36DE82E1278ABB02F"),
+        ]));
+        let fields: Vec<&str> = matched.iter().map(|m| m["matched"]["field"].as_str().unwrap()).collect();
+        assert_eq!(fields, vec!["source.ip", "dns.question.name", "Hashes"]);
+        assert_eq!(matched[0]["indicator"]["type"], "ipv4-addr");
+        assert_eq!(matched[0]["feed"]["name"], "blocklist");
+        assert_eq!(matched[2]["matched"]["atomic"], "44D88612FEA8A8F36DE82E1278ABB02F");
+
+        // A failed download keeps the indicators, and a restart loads the cached copy before any download
+        std::fs::remove_file(&feed_path).unwrap();
+        assert_eq!(intel.refresh().await, 3);
+        let restarted = ThreatIntel::new(config, ProxyConfig::default());
+        assert_eq!(restarted.store().len(), 3);
+        assert!(restarted.store().match_domain("example.net").is_none());
+    }
+
+    /// Serves one canned response on a local port and returns the request it received
+    async fn serve_once(body: &'static str) -> (std::net::SocketAddr, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let response = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", body.len(), body);
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+        (address, server)
+    }
+
+    fn csv_feed(name: &str, url: &str) -> ThreatIntelFeedConfig {
+        ThreatIntelFeedConfig {
+            name: name.to_string(),
+            url: url.to_string(),
+            format: FeedFormat::Csv,
+            auth: FeedAuth::None,
+            column: None,
+            description_column: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_feeds_download_through_the_proxy_within_the_size_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let (address, proxy) = serve_once("203.0.113.9\nbad.example.net\n").await;
+        let proxy_config = ProxyConfig {
+            mode: crate::proxy::ProxyMode::Manual,
+            url: Some(format!("http://{}", address)),
+            ..Default::default()
+        };
+        let config = ThreatIntelConfig {
+            feeds: vec![csv_feed("blocklist", "http://intel.example.invalid/blocklist.csv")],
+            cache_directory: dir.path().join("cache").display().to_string(),
+            ..Default::default()
+        };
+        let intel = ThreatIntel::new(config.clone(), proxy_config.clone());
+        assert_eq!(intel.refresh().await, 2);
+        assert!(proxy.await.unwrap().starts_with("GET http://intel.example.invalid/blocklist.csv HTTP/1.1"));
+
+        // A body over the cap fails the refresh and keeps the previous indicators
+        let (address, proxy) = serve_once("198.51.100.1\n198.51.100.2\n198.51.100.3\n").await;
+        let proxy_config = ProxyConfig { url: Some(format!("http://{}", address)), ..proxy_config };
+        let capped = ThreatIntel::new(ThreatIntelConfig { max_feed_bytes: 16, ..config }, proxy_config);
+        assert_eq!(capped.refresh().await, 2);
+        proxy.await.unwrap();
+        assert!(capped.store().match_ip(&"198.51.100.1".parse().unwrap()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_feed_names_never_leave_the_cache_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let feed_path = dir.path().join("blocklist.csv");
+        std::fs::write(&feed_path, "10.20.0.0/16\n").unwrap();
+        let config = ThreatIntelConfig {
+            feeds: vec![csv_feed("../escape", &feed_path.display().to_string())],
+            cache_directory: dir.path().join("cache").display().to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().iter().any(|e| e.contains("../escape")));
+
+        let intel = ThreatIntel::new(config, ProxyConfig::default());
+        assert_eq!(intel.refresh().await, 1);
+        assert!(!dir.path().join("escape.json").exists());
+        assert!(!dir.path().join("cache").exists());
+    }
+}
@@ -1,20 +1,45 @@
 // Secure transport layer with HTTPS, TLS, mTLS, WebSocket, compression, retry logic, and circuit breaker
 
-use crate::config::TransportConfig;
+use crate::cert_expiry::{read_certificates, CertificateRole};
+pub use crate::cert_expiry::CertificateStatus;
+use crate::client_identity::IdentityMaterial;
+use crate::compression::CompressionAlgorithm;
+use crate::config::{TransportConfig, TransportProtocol};
 use crate::errors::TransportError;
 use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerRegistry};
+use crate::retry::{Backoff, RetryBudget};
 
 #[cfg(test)]
 mod tests;
 #[cfg(test)]
 mod circuit_breaker_tests;
 use crate::parsers::ParsedEvent;
+use crate::field_filter::{FieldFilter, FieldFilterConfig};
+use crate::chaos::TransportFault;
+use crate::component_usage;
+use crate::pipeline_metrics;
+use crate::destinations::{DestinationConfig, PrimaryRoute};
+use crate::otlp::{self, OtlpEncoder, OtlpEncoding};
+use crate::syslog_output::SyslogForwarder;
+use crate::splunk_hec::HecEncoder;
+use crate::elasticsearch::{BulkEncoder, BulkOutcome};
+use crate::payload_format::{PayloadFormat, AGENT_ID_HEADER};
+use crate::integrity::BatchIntegrity;
+use crate::delivery::{Acknowledgement, DeliveryLedger, DELIVERY_HEADER, DELIVERY_PROTOCOL};
+use crate::error_events::ErrorEvents;
+use crate::dedup::DuplicateFilter;
+use crate::bandwidth::{BandwidthLimiter, BandwidthStats};
+use crate::buffer::EventBuffer;
+use crate::proxy::ProxyStatus;
+use crate::upload_schedule::{UploadSchedule, UploadScheduleStatus};
+use crate::load_shedding::LoadShedder;
+use crate::relay::{RelayClient, RelayUpstreamConfig, RelayedEnvelope};
 use crate::validation::{InputValidator, ValidationConfig, ValidationRiskLevel};
 use reqwest::{Client, ClientBuilder};
 use serde_json::Value;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, UNIX_EPOCH};
 use std::path::Path;
 use tokio::time::sleep;
 use tokio::sync::mpsc;
@@ -30,18 +55,70 @@ use async_compression::tokio::bufread::ZstdEncoder;
 use tokio::io::{AsyncRead, AsyncBufRead, AsyncWrite};
 
 pub struct SecureTransport {
-    client: Client,
+    // Rebuilt when the client certificate rotates
+    client: parking_lot::RwLock<Client>,
     config: TransportConfig,
     cert_expiry_warning_sent: std::sync::Arc<std::sync::Mutex<bool>>,
     input_validator: std::sync::Arc<tokio::sync::Mutex<InputValidator>>,
     circuit_breaker: CircuitBreaker,
     circuit_breaker_registry: Arc<CircuitBreakerRegistry>,
+    // Caps retries against this endpoint to a share of first attempts
+    retry_budget: Arc<RetryBudget>,
     // WebSocket components
     websocket_sender: Option<Arc<tokio::sync::Mutex<mpsc::UnboundedSender<Message>>>>,
     websocket_connected: Arc<AtomicBool>,
     // Connection pooling and keep-alive management
     connection_pool_stats: Arc<tokio::sync::RwLock<ConnectionPoolStats>>,
     keep_alive_monitor: Option<tokio::task::JoinHandle<()>>,
+    // Per-destination field allow/deny rules applied at serialization time
+    field_filter: FieldFilter,
+    // Relay agent used instead of the server when this host has no direct egress
+    relay_client: Option<Arc<RelayClient>>,
+    // Syslog receiver written to instead of posting when the protocol is syslog
+    syslog: Option<SyslogForwarder>,
+    // Request body builders for the Splunk HEC and Elasticsearch protocols
+    splunk_hec: Option<HecEncoder>,
+    elasticsearch: Option<BulkEncoder>,
+    // Agent identity reported in every batch payload
+    agent_id: String,
+    // Additional named destinations events are routed to alongside server_url
+    destinations: Vec<RoutedDestination>,
+    // Payload encoding in use; starts as the configured algorithm and changes when the server rejects it
+    content_encoding: parking_lot::RwLock<CompressionAlgorithm>,
+    // Batch serialization in use; starts as the configured format and changes when the server rejects it
+    payload_format: parking_lot::RwLock<PayloadFormat>,
+    // Digest and signature headers attached to every native batch
+    integrity: Option<Arc<BatchIntegrity>>,
+    // Event IDs the server acknowledged, so retries leave them out
+    delivery: Option<DeliveryLedger>,
+    // Shrinks batches while the agent is over its resource limits
+    load_shedder: Option<Arc<LoadShedder>>,
+    // Failed batches are reported to the server as error events
+    error_events: Option<Arc<ErrorEvents>>,
+    // Keeps batches the server still refuses after every retry
+    dead_letters: Option<EventBuffer>,
+    // Upload cap shared with the destinations
+    bandwidth: Option<Arc<BandwidthLimiter>>,
+    // Remembers acknowledged events so re-read copies are dropped at ingress
+    duplicate_filter: Option<DuplicateFilter>,
+    // Time windows and networks uploads are restricted to
+    upload_schedule: Option<Arc<UploadSchedule>>,
+    // Proxy the client was built with, configured or discovered
+    proxy: Option<ProxyStatus>,
+}
+
+// Serialized batch ready to post
+struct PreparedPayload {
+    body: Vec<u8>,
+    format: PayloadFormat,
+    encoding: CompressionAlgorithm,
+    headers: Vec<(&'static str, String)>,
+}
+
+// Named destination with its own client, retry policy and circuit breaker
+struct RoutedDestination {
+    config: DestinationConfig,
+    transport: SecureTransport,
 }
 
 // WebSocket connection handle for bidirectional communication
@@ -233,6 +310,38 @@ impl SecureTransport {
         Ok(client_builder)
     }
     
+    /// Configure a client identity fetched at runtime, with its trust bundle
+    fn configure_identity_material(
+        mut client_builder: ClientBuilder,
+        identity: &IdentityMaterial,
+    ) -> Result<ClientBuilder, TransportError> {
+        #[cfg(any(feature = "native-tls-backend", feature = "rustls-backend"))]
+        {
+            let client_identity = reqwest::Identity::from_pkcs8_pem(&identity.cert_pem, &identity.key_pem)
+                .map_err(|e| TransportError::TlsError {
+                    operation: "create_identity".to_string(),
+                    reason: format!("Failed to create client identity for {}: {}",
+                                    identity.spiffe_id.as_deref().unwrap_or("client certificate"), e),
+                    certificate_issue: true,
+                    source: Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
+                })?;
+            client_builder = client_builder.identity(client_identity);
+        }
+        
+        for ca_pem in &identity.ca_pem {
+            let ca_cert = reqwest::Certificate::from_pem(ca_pem)
+                .map_err(|e| TransportError::TlsError {
+                    operation: "parse_trust_bundle".to_string(),
+                    reason: format!("Failed to parse trust bundle certificate: {}", e),
+                    certificate_issue: true,
+                    source: Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
+                })?;
+            client_builder = client_builder.add_root_certificate(ca_cert);
+        }
+        
+        Ok(client_builder)
+    }
+    
     /// Configure custom CA certificate
     fn configure_custom_ca(mut client_builder: ClientBuilder, ca_path: &str) -> Result<ClientBuilder, TransportError> {
         let ca_cert = std::fs::read(ca_path)
@@ -303,7 +412,8 @@ impl SecureTransport {
         // 4. Potentially trigger certificate renewal workflows
     }
 
-    pub async fn new(config: TransportConfig) -> Result<Self, TransportError> {
+    /// HTTP client for `config`, presenting `identity` instead of the configured certificate files when given
+    fn build_client(config: &TransportConfig, identity: Option<&IdentityMaterial>) -> Result<Client, TransportError> {
         let mut client_builder = ClientBuilder::new()
             .timeout(Duration::from_secs(30))
             .connect_timeout(Duration::from_secs(10))
@@ -330,20 +440,30 @@ impl SecureTransport {
             warn!("⚠️  TLS certificate verification is disabled");
         }
 
-        // Configure compression
-        if config.compression {
+        // Accept compressed responses
+        if config.compression.is_enabled() {
             client_builder = client_builder
                 .gzip(true)
                 .brotli(true);
             debug!("🗜️  Compression enabled (gzip, brotli)");
         }
 
+        // OTLP/gRPC needs HTTP/2; TLS collectors negotiate it via ALPN, plaintext ones must be spoken to in HTTP/2 directly
+        if config.protocol == TransportProtocol::OtlpGrpc && Self::otlp_endpoint(config).starts_with("http://") {
+            client_builder = client_builder.http2_prior_knowledge();
+        }
+
+        // Route through the configured or discovered proxy
+        client_builder = config.proxy.apply(client_builder, &config.server_url).map_err(|e| TransportError::connection_failed(&e))?;
+
         // Configure connection pooling and keep-alive management
-        client_builder = Self::configure_connection_pooling(client_builder, &config)?;
+        client_builder = Self::configure_connection_pooling(client_builder, config)?;
 
-        // Configure mTLS client certificates if provided
-        if let (Some(cert_path), Some(key_path)) = (&config.client_cert_path, &config.client_key_path) {
-            client_builder = Self::configure_mtls_certificates(client_builder, cert_path, key_path, &config)?;
+        // Configure mTLS client certificates: a SPIFFE SVID when one was fetched, otherwise the configured files
+        if let Some(identity) = identity {
+            client_builder = Self::configure_identity_material(client_builder, identity)?;
+        } else if let (Some(cert_path), Some(key_path)) = (&config.client_cert_path, &config.client_key_path) {
+            client_builder = Self::configure_mtls_certificates(client_builder, cert_path, key_path, config)?;
         }
         
         // Configure custom CA certificate if provided
@@ -351,12 +471,27 @@ impl SecureTransport {
             client_builder = Self::configure_custom_ca(client_builder, ca_path)?;
         }
 
-        let client = client_builder
+        client_builder
             .build()
-            .map_err(|e| TransportError::connection_failed(&format!("Failed to create HTTP client: {}", e)))?;
+            .map_err(|e| TransportError::connection_failed(&format!("Failed to create HTTP client: {}", e)))
+    }
+
+    pub async fn new(mut config: TransportConfig) -> Result<Self, TransportError> {
+        // Secrets kept in the OS keyring are read once here and never written back to the config file
+        config.resolve_secrets().await
+            .map_err(|e| TransportError::configuration_invalid(&e.to_string()))?;
+        let client = Self::build_client(&config, None)?;
+
+        // Syslog receivers are connected to directly
+        let proxy = config.proxy.resolve(&config.server_url)
+            .filter(|_| config.protocol != TransportProtocol::Syslog)
+            .map(|proxy| proxy.status());
+        if let Some(proxy) = &proxy {
+            info!("🌐 Connecting to {} through {} proxy {}", config.server_url, proxy.source.as_str(), proxy.url);
+        }
 
         let mtls_status = if config.client_cert_path.is_some() { "enabled" } else { "disabled" };
-        info!("🔐 Secure transport initialized with TLS: {}, mTLS: {}, Compression: {}", 
+        info!("🔐 Secure transport initialized with TLS: {}, mTLS: {}, Compression: {:?}", 
               config.tls_verify, mtls_status, config.compression);
         
         // Initialize input validator for transport security
@@ -382,9 +517,23 @@ impl SecureTransport {
             minimum_requests: config.circuit_breaker_minimum_requests.unwrap_or(10),
         };
         
+        let syslog = match config.protocol {
+            TransportProtocol::Syslog => Some(SyslogForwarder::new(&config)?),
+            _ => None,
+        };
+        let splunk_hec = (config.protocol == TransportProtocol::SplunkHec)
+            .then(|| HecEncoder::new(&config))
+            .transpose()
+            .map_err(|e| TransportError::configuration_invalid(&format!("Splunk HEC {}", e)))?;
+        let elasticsearch = (config.protocol == TransportProtocol::Elasticsearch)
+            .then(|| BulkEncoder::new(&config))
+            .transpose()
+            .map_err(|e| TransportError::configuration_invalid(&format!("Elasticsearch {}", e)))?;
+        
         let circuit_breaker_name = format!("transport-{}", config.server_url);
-        let circuit_breaker = CircuitBreaker::new(circuit_breaker_name.clone(), circuit_breaker_config);
         let circuit_breaker_registry = Arc::new(CircuitBreakerRegistry::new());
+        let circuit_breaker = circuit_breaker_registry.get_or_create(circuit_breaker_name.clone(), circuit_breaker_config).await;
+        let retry_budget = Arc::new(RetryBudget::new(config.retry_policy.budget_ratio, config.retry_policy.budget_min_per_second));
         
         info!("🔄 Circuit breaker '{}' initialized for transport resilience", circuit_breaker_name);
         
@@ -394,18 +543,37 @@ impl SecureTransport {
         initial_stats.last_activity = Some(std::time::SystemTime::now());
         
         let transport = Self { 
-            client, 
+            client: parking_lot::RwLock::new(client), 
             config: config.clone(), 
             cert_expiry_warning_sent: std::sync::Arc::new(std::sync::Mutex::new(false)),
             input_validator: std::sync::Arc::new(tokio::sync::Mutex::new(input_validator)),
             circuit_breaker,
             circuit_breaker_registry,
+            retry_budget,
             // Initialize WebSocket components
             websocket_sender: None,
             websocket_connected: Arc::new(AtomicBool::new(false)),
             // Initialize connection pooling components
             connection_pool_stats: Arc::new(tokio::sync::RwLock::new(initial_stats)),
             keep_alive_monitor: None,
+            field_filter: FieldFilter::new(&FieldFilterConfig::default(), &config.server_url),
+            relay_client: None,
+            syslog,
+            splunk_hec,
+            elasticsearch,
+            agent_id: "rust-agent".to_string(),
+            destinations: Vec::new(),
+            content_encoding: parking_lot::RwLock::new(config.compression),
+            payload_format: parking_lot::RwLock::new(config.format),
+            integrity: None,
+            delivery: config.delivery.enabled.then(|| DeliveryLedger::new(&config.delivery)),
+            load_shedder: None,
+            error_events: None,
+            dead_letters: None,
+            bandwidth: None,
+            duplicate_filter: None,
+            upload_schedule: None,
+            proxy,
         };
         
         // Note: Certificate expiry check is performed during operations
@@ -414,12 +582,192 @@ impl SecureTransport {
         Ok(transport)
     }
 
+    /// Apply field allow/deny rules for this destination to all outbound events
+    pub fn set_field_filter(&mut self, config: &FieldFilterConfig) {
+        self.field_filter = FieldFilter::new(config, &self.config.server_url);
+    }
+
+    /// Agent ID reported to the server with each batch
+    pub fn set_agent_id(&mut self, agent_id: &str) {
+        self.agent_id = agent_id.to_string();
+    }
+
+    /// Ship batches through a relay agent instead of posting them to the server
+    pub fn set_relay_upstream(&mut self, config: &RelayUpstreamConfig) -> Result<(), TransportError> {
+        let client = RelayClient::new(config.clone())?;
+        info!("🛰️ Batches will be relayed through {} as '{}'", client.address(), config.peer_id);
+        self.relay_client = Some(Arc::new(client));
+        Ok(())
+    }
+
+    fn client(&self) -> Client {
+        self.client.read().clone()
+    }
+
+    /// Rebuild the HTTP clients with a new client identity, or from the configured certificate files when None.
+    /// Requests already in flight finish on the previous client.
+    pub fn reload_identity(&self, identity: Option<&IdentityMaterial>) -> Result<(), TransportError> {
+        *self.client.write() = Self::build_client(&self.config, identity)?;
+        for destination in &self.destinations {
+            // Destinations configured with their own certificate keep presenting it
+            let inherited = identity.filter(|_| destination.config.client_cert_path.is_none());
+            destination.transport.reload_identity(inherited)?;
+        }
+        Ok(())
+    }
+
+    /// Attach batch digests, and signatures when a key is loaded, to every native batch
+    pub fn set_batch_integrity(&mut self, integrity: Arc<BatchIntegrity>) {
+        self.integrity = Some(integrity);
+    }
+
+    /// Send smaller batches while load shedding asks for it; destinations set afterwards share it
+    pub fn set_load_shedder(&mut self, shedder: Arc<LoadShedder>) {
+        self.load_shedder = Some(shedder);
+    }
+
+    pub fn set_error_events(&mut self, error_events: Arc<ErrorEvents>) {
+        self.error_events = Some(error_events);
+    }
+
+    /// Move batches the server still refuses after the last retry to the buffer's dead-letter table, so they
+    /// neither block the batches behind them nor are lost; the send then fails with DeadLettered
+    pub fn set_dead_letters(&mut self, buffer: EventBuffer) {
+        self.dead_letters = Some(buffer);
+    }
+
+    /// Record each fully acknowledged batch in the duplicate filter
+    pub fn set_duplicate_filter(&mut self, filter: DuplicateFilter) {
+        self.duplicate_filter = Some(filter);
+    }
+
+    /// Hold every payload until the upload cap allows it; destinations set afterwards share the same bucket
+    pub fn set_bandwidth_limiter(&mut self, limiter: Arc<BandwidthLimiter>) {
+        self.bandwidth = Some(limiter);
+    }
+
+    /// Refuse batches with TransmissionDeferred while the schedule is closed; destinations set afterwards share it
+    pub fn set_upload_schedule(&mut self, schedule: Arc<UploadSchedule>) {
+        self.upload_schedule = Some(schedule);
+    }
+
+    /// Build the named destinations from `transport.destinations`, each with its own client and circuit breaker
+    pub async fn set_destinations(&mut self, field_filter: &FieldFilterConfig) -> Result<(), TransportError> {
+        let mut destinations = Vec::with_capacity(self.config.destinations.len());
+        for destination in &self.config.destinations {
+            let mut transport = SecureTransport::new(destination.resolve(&self.config)).await?;
+            transport.set_agent_id(&self.agent_id);
+            transport.set_field_filter(field_filter);
+            if let Some(integrity) = &self.integrity {
+                transport.set_batch_integrity(integrity.clone());
+            }
+            if let Some(shedder) = &self.load_shedder {
+                transport.set_load_shedder(shedder.clone());
+            }
+            if let Some(error_events) = &self.error_events {
+                transport.set_error_events(error_events.clone());
+            }
+            if let Some(limiter) = &self.bandwidth {
+                transport.set_bandwidth_limiter(limiter.clone());
+            }
+            if let Some(schedule) = &self.upload_schedule {
+                transport.set_upload_schedule(schedule.clone());
+            }
+            // One registry holds every endpoint's breaker, so health scores can be compared across destinations
+            self.circuit_breaker_registry.register(transport.circuit_breaker.clone()).await;
+            info!("🔀 Destination '{}' -> {} ({} routes)", destination.name, destination.server_url, destination.routes.len());
+            destinations.push(RoutedDestination { config: destination.clone(), transport });
+        }
+        self.destinations = destinations;
+        Ok(())
+    }
+
+    /// Forward a peer's envelope received by the relay listener, unchanged
+    pub async fn forward_relayed(&self, envelope: &RelayedEnvelope) -> Result<(), TransportError> {
+        component_usage::instrument(component_usage::TRANSPORT, self.circuit_breaker.call(|| async {
+            self.throttle(envelope.payload.len()).await;
+            match &self.relay_client {
+                Some(relay) => relay.send(
+                    &envelope.payload,
+                    envelope.format,
+                    envelope.encoding,
+                    &envelope.origin_headers,
+                    envelope.event_count,
+                    &envelope.relay_path,
+                ).await,
+                None => self.post_payload(envelope.payload.clone(), envelope.format, envelope.encoding, &envelope.headers()).await.map(|_| ()),
+            }
+        })).await
+    }
+
     pub async fn send_batch(&self, events: Vec<ParsedEvent>) -> Result<(), TransportError> {
+        pipeline_metrics::record_queue_wait(&events);
+        // Taken before the batch is routed or trimmed by earlier acknowledgements
+        let dedup_keys = self.duplicate_filter.as_ref().map(|filter| filter.keys(&events));
+        let result = if self.destinations.is_empty() {
+            component_usage::instrument(component_usage::TRANSPORT, self.send_batches(events)).await
+        } else {
+            component_usage::instrument(component_usage::TRANSPORT, self.send_routed(events)).await
+        };
+        if let (Ok(()), Some(filter), Some(keys)) = (&result, &self.duplicate_filter, dedup_keys) {
+            filter.record_delivered(keys).await;
+        }
+        result
+    }
+
+    /// Fan events out to the primary server and every matching destination concurrently.
+    /// Any failure fails the whole call, so destinations that already succeeded may see the batch again on retry.
+    async fn send_routed(&self, events: Vec<ParsedEvent>) -> Result<(), TransportError> {
+        let mut primary = Vec::new();
+        let mut routed: Vec<Vec<ParsedEvent>> = vec![Vec::new(); self.destinations.len()];
+        for event in events {
+            let mut matched = false;
+            for (destination, batch) in self.destinations.iter().zip(routed.iter_mut()) {
+                if destination.config.matches(&event) {
+                    batch.push(event.clone());
+                    matched = true;
+                }
+            }
+            match self.config.primary_route {
+                PrimaryRoute::All => primary.push(event),
+                PrimaryRoute::Unmatched if !matched => primary.push(event),
+                PrimaryRoute::Unmatched | PrimaryRoute::Disabled => {}
+            }
+        }
+
+        let primary_send = self.send_batches(primary);
+        let routed_sends = futures::future::join_all(
+            self.destinations
+                .iter()
+                .zip(routed)
+                .map(|(destination, batch)| async move { (destination, destination.transport.send_batches(batch).await) }),
+        );
+        let (primary_result, routed_results) = futures::future::join(primary_send, routed_sends).await;
+
+        let mut first_error = primary_result.err();
+        for (destination, result) in routed_results {
+            if let Err(e) = result {
+                if !matches!(e, TransportError::TransmissionDeferred { .. }) {
+                    error!("❌ Destination '{}' failed: {}", destination.config.name, e);
+                }
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    async fn send_batches(&self, events: Vec<ParsedEvent>) -> Result<(), TransportError> {
         if events.is_empty() {
             return Ok(());
         }
+        // Held batches are not failures: they skip the retries, the circuit breaker and error reporting
+        if let Some(schedule) = &self.upload_schedule {
+            schedule.check()?;
+        }
 
-        let batch_size = self.config.batch_size.min(events.len());
+        let configured = self.load_shedder.as_ref()
+            .map_or(self.config.batch_size, |shedder| shedder.batch_size(self.config.batch_size));
+        let batch_size = configured.min(events.len());
         let batches: Vec<Vec<ParsedEvent>> = events
             .chunks(batch_size)
             .map(|chunk| chunk.to_vec())
@@ -427,17 +775,39 @@ impl SecureTransport {
 
         info!("📤 Sending {} events in {} batches", events.len(), batches.len());
         let total_batches = batches.len();
+        let mut dead_lettered = 0;
+        let mut last_refusal = None;
 
         for (i, batch) in batches.into_iter().enumerate() {
             debug!("📦 Sending batch {}/{} with {} events", i + 1, events.len() / batch_size + 1, batch.len());
             
+            let kept = self.dead_letters.as_ref().map(|_| batch.clone());
             match self.send_single_batch(batch).await {
                 Ok(_) => {
                     debug!("✅ Batch {} sent successfully", i + 1);
                 }
                 Err(e) => {
                     error!("❌ Failed to send batch {}: {}", i + 1, e);
-                    return Err(e);
+                    if let Some(error_events) = &self.error_events {
+                        error_events.record("transport", e.report());
+                    }
+                    // The server answered and refused this batch on every attempt; an unreachable server is not
+                    // the batch's fault, so those failures stay with the caller to retry later
+                    let (Some(buffer), Some(batch), TransportError::ServerError { .. }) = (&self.dead_letters, kept, &e) else {
+                        return Err(e);
+                    };
+                    let refused = batch.len();
+                    match buffer.dead_letter_batch(batch, &e.to_string(), self.config.retry_attempts as u32).await {
+                        Ok(_) => {
+                            warn!("🪦 Kept batch {} ({} events) in the dead-letter table: {}", i + 1, refused, e);
+                            dead_lettered += refused;
+                            last_refusal = Some(e.to_string());
+                        }
+                        Err(dead_letter_error) => {
+                            warn!("⚠️ Failed to dead-letter batch {}: {}", i + 1, dead_letter_error);
+                            return Err(e);
+                        }
+                    }
                 }
             }
 
@@ -447,7 +817,10 @@ impl SecureTransport {
             }
         }
 
-        Ok(())
+        match last_refusal {
+            Some(reason) => Err(TransportError::DeadLettered { events: dead_lettered, reason }),
+            None => Ok(()),
+        }
     }
 
     /// Validate events before transmission for security
@@ -540,17 +913,41 @@ impl SecureTransport {
         Ok(())
     }
 
-    async fn send_single_batch(&self, events: Vec<ParsedEvent>) -> Result<(), TransportError> {
+    async fn send_single_batch(&self, mut events: Vec<ParsedEvent>) -> Result<(), TransportError> {
         // Validate events for security before transmission
         self.validate_events(&events).await?;
+        let batch_size = events.len();
         
+        let started = std::time::Instant::now();
         let mut attempt = 0;
         let mut last_error = None;
+        let policy = &self.config.retry_policy;
+        let max_delay = Duration::from_secs(policy.max_delay_secs);
+        let mut backoff = Backoff::new(policy.jitter, Duration::from_secs(self.config.retry_delay), max_delay, 2.0, 0.1);
+        self.retry_budget.record_request();
 
         while attempt < self.config.retry_attempts {
+            // Events acknowledged by an earlier attempt, or an earlier send of the same buffered events, stay out
+            if let Some(ledger) = &self.delivery {
+                events = ledger.undelivered(events);
+                if events.is_empty() {
+                    debug!("♻️ All {} events were already acknowledged by {}", batch_size, self.config.server_url);
+                    return Ok(());
+                }
+                if events.len() < batch_size {
+                    info!("♻️ Sending {} of {} events; the rest were already acknowledged", events.len(), batch_size);
+                }
+            }
             if attempt > 0 {
-                let delay = Duration::from_secs(self.config.retry_delay * 2_u64.pow(attempt as u32 - 1));
-                debug!("⏳ Retrying in {:?} (attempt {}/{})", delay, attempt + 1, self.config.retry_attempts);
+                if !self.retry_budget.try_retry() {
+                    warn!("⏸️ Retry budget for {} exhausted ({} retries refused); giving up on this batch",
+                          self.config.server_url, self.retry_budget.exhausted());
+                    break;
+                }
+                // An unhealthy endpoint is retried up to twice as slowly, so a flapping one is not hammered
+                let health = self.circuit_breaker.health_score().await;
+                let delay = backoff.next_delay().mul_f64(2.0 - health).min(max_delay);
+                debug!("⏳ Retrying in {:?} (attempt {}/{}, endpoint health {:.2})", delay, attempt + 1, self.config.retry_attempts, health);
                 sleep(delay).await;
             }
 
@@ -563,11 +960,26 @@ impl SecureTransport {
             }).await;
             
             match request_result {
-                Ok(_) => {
+                Ok(acknowledgement) => {
+                    let missing = self.delivery.as_ref().map_or(0, |ledger| ledger.record(&events, &acknowledgement));
+                    if missing > 0 {
+                        // Partly stored: the next attempt sends only what is missing
+                        last_error = Some(TransportError::ServerError {
+                            status: 200,
+                            message: format!("{} acknowledged {} of {} events", self.config.server_url, events.len() - missing, events.len()),
+                            headers: vec![],
+                            body: None,
+                            retryable: true,
+                        });
+                        attempt += 1;
+                        warn!("⚠️  Request partly acknowledged on attempt {}: {} events missing", attempt, missing);
+                        continue;
+                    }
                     if attempt > 0 {
                         info!("✅ Request succeeded on attempt {} (circuit breaker: {})", 
                               attempt + 1, self.circuit_breaker.state().await);
                     }
+                    pipeline_metrics::record_flush(&events, started.elapsed());
                     return Ok(());
                 }
                 Err(e) => {
@@ -583,49 +995,84 @@ impl SecureTransport {
         Err(last_error.unwrap_or_else(|| TransportError::connection_failed("Unknown error")))
     }
 
-    async fn perform_request(&self, events: &[ParsedEvent]) -> Result<(), TransportError> {
+    async fn perform_request(&self, events: &[ParsedEvent]) -> Result<Acknowledgement, TransportError> {
+        if let Some(fault) = crate::chaos::transport_fault() {
+            return Err(match fault {
+                TransportFault::ServerError { status } => TransportError::ServerError {
+                    status,
+                    message: "chaos: injected server error".to_string(),
+                    headers: vec![],
+                    body: None,
+                    retryable: true,
+                },
+                TransportFault::Timeout { after } => {
+                    sleep(after).await;
+                    TransportError::Timeout {
+                        operation: "http_request".to_string(),
+                        duration_ms: after.as_millis() as u64,
+                        retryable: true,
+                    }
+                }
+            });
+        }
+
+        // Only the native protocol reports acknowledged ranges; a successful export stored the whole batch
+        if let Some(syslog) = &self.syslog {
+            return self.forward_syslog(syslog, events).await.map(|_| Acknowledgement::All);
+        }
+        if let Some(encoder) = &self.splunk_hec {
+            return self.export_hec(encoder, events).await.map(|_| Acknowledgement::All);
+        }
+        if let Some(encoder) = &self.elasticsearch {
+            return self.export_bulk(encoder, events).await.map(|_| Acknowledgement::All);
+        }
+        if self.config.protocol.is_otlp() {
+            return self.export_otlp(events).await.map(|_| Acknowledgement::All);
+        }
+
         let payload = self.prepare_payload(events)?;
+        self.throttle(payload.body.len()).await;
         
+        if let Some(relay) = &self.relay_client {
+            debug!("🛰️ Relaying {} bytes through {}", payload.body.len(), relay.address());
+            let headers: Vec<(String, String)> = payload.headers.iter().map(|(name, value)| (name.to_string(), value.clone())).collect();
+            return relay.send(&payload.body, payload.format, payload.encoding, &headers, events.len() as u32, &[])
+                .await
+                .map(|_| Acknowledgement::All);
+        }
+        let response = self.post_payload(payload.body, payload.format, payload.encoding, &payload.headers).await?;
+        Ok(if self.delivery.is_some() { Acknowledgement::parse(&response) } else { Acknowledgement::All })
+    }
+
+    /// Post a native batch and return the response body of a successful one
+    async fn post_payload<N: AsRef<str>>(
+        &self,
+        payload: Vec<u8>,
+        format: PayloadFormat,
+        encoding: CompressionAlgorithm,
+        headers: &[(N, String)],
+    ) -> Result<String, TransportError> {
         debug!("🌐 Sending {} bytes to {}", payload.len(), self.config.server_url);
 
         // Measure connection time for statistics
         let start_time = std::time::Instant::now();
         
-        let response = self
-            .client
+        let mut request = self
+            .client()
             .post(&self.config.server_url)
             .bearer_auth(&self.config.api_key)
-            .header("Content-Type", "application/json")
+            .header("Content-Type", format.content_type());
+        if let Some(content_encoding) = encoding.content_encoding() {
+            request = request.header(reqwest::header::CONTENT_ENCODING, content_encoding);
+        }
+        for (name, value) in headers {
+            request = request.header(name.as_ref(), value);
+        }
+        let response = request
             .body(payload)
             .send()
             .await
-            .map_err(|e| {
-                // Track connection error
-                tokio::spawn({
-                    let stats_ref = self.connection_pool_stats.clone();
-                    async move {
-                        let mut stats = stats_ref.write().await;
-                        stats.connection_errors += 1;
-                    }
-                });
-                
-                if e.is_timeout() {
-                    TransportError::Timeout {
-                        operation: "http_request".to_string(),
-                        duration_ms: 30000,
-                        retryable: true,
-                    }
-                } else if e.is_connect() {
-                    TransportError::connection_failed(&e.to_string())
-                } else {
-                    TransportError::RequestFailed {
-                        method: "POST".to_string(),
-                        url: self.config.server_url.clone(),
-                        status_code: None,
-                        source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
-                    }
-                }
-            })?;
+            .map_err(|e| self.request_error(e, &self.config.server_url))?;
 
         let status = response.status();
         let connection_time_ms = start_time.elapsed().as_millis() as f64;
@@ -638,7 +1085,44 @@ impl SecureTransport {
         
         if status.is_success() {
             debug!("✅ Server responded with status: {} ({}ms)", status, connection_time_ms);
-            Ok(())
+            Ok(response.text().await.unwrap_or_default())
+        } else if let Some(negotiated) = self.negotiate_format(status, response.headers(), format) {
+            warn!("📦 {} rejected {:?} batches, switching to {:?}", self.config.server_url, format, negotiated);
+            *self.payload_format.write() = negotiated;
+            Err(TransportError::ServerError {
+                status: status.as_u16(),
+                message: response.text().await.unwrap_or_default(),
+                headers: vec![],
+                body: None,
+                retryable: true,
+            })
+        } else if status == reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE && encoding.is_enabled() {
+            // RFC 7694: the server lists the encodings it accepts; retrying re-encodes the batch
+            let accepted = response.headers().get(reqwest::header::ACCEPT_ENCODING)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            let negotiated = CompressionAlgorithm::negotiate(self.config.compression, &accepted);
+            warn!("🗜️ {} rejected {:?} payloads (accepts '{}'), switching to {:?}",
+                  self.config.server_url, encoding, accepted, negotiated);
+            *self.content_encoding.write() = negotiated;
+            Err(TransportError::ServerError {
+                status: status.as_u16(),
+                message: response.text().await.unwrap_or_default(),
+                headers: vec![],
+                body: None,
+                retryable: negotiated != encoding,
+            })
+        } else if status == reqwest::StatusCode::PROXY_AUTHENTICATION_REQUIRED {
+            let challenges = response.headers().get_all(reqwest::header::PROXY_AUTHENTICATE).iter()
+                .filter_map(|value| value.to_str().ok())
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            Err(TransportError::AuthenticationFailed {
+                method: "proxy".to_string(),
+                reason: crate::proxy::auth_rejection(challenges.iter().map(String::as_str)),
+                retry_allowed: false,
+            })
         } else if status.is_client_error() {
             let error_body = response.text().await.unwrap_or_default();
             
@@ -669,35 +1153,313 @@ impl SecureTransport {
         }
     }
 
-    fn prepare_payload(&self, events: &[ParsedEvent]) -> Result<Vec<u8>, TransportError> {
+    /// Wait for the upload cap, if one is set, to allow `bytes` more
+    async fn throttle(&self, bytes: usize) {
+        if let Some(limiter) = &self.bandwidth {
+            limiter.acquire(bytes).await;
+        }
+    }
+
+    /// Map a failed request to a transport error, counting it against the connection pool
+    fn request_error(&self, e: reqwest::Error, url: &str) -> TransportError {
+        // Track connection error
+        tokio::spawn({
+            let stats_ref = self.connection_pool_stats.clone();
+            async move {
+                let mut stats = stats_ref.write().await;
+                stats.connection_errors += 1;
+            }
+        });
+        
+        if e.is_timeout() {
+            TransportError::Timeout {
+                operation: "http_request".to_string(),
+                duration_ms: 30000,
+                retryable: true,
+            }
+        } else if crate::proxy::is_tunnel_auth_rejection(&e) {
+            // A refused HTTPS tunnel; the proxy's challenge isn't visible here
+            TransportError::connection_failed(&format!("{}: {}", e, crate::proxy::auth_rejection([])))
+        } else if e.is_connect() {
+            TransportError::connection_failed(&e.to_string())
+        } else {
+            TransportError::RequestFailed {
+                method: "POST".to_string(),
+                url: url.to_string(),
+                status_code: None,
+                source: Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
+            }
+        }
+    }
+
+    fn otlp_endpoint(config: &TransportConfig) -> &str {
+        config.otlp.endpoint.as_deref().unwrap_or(&config.server_url)
+    }
+
+    /// Events with this transport's field filter applied
+    fn filter_events<'a>(&self, events: &'a [ParsedEvent]) -> Result<Vec<std::borrow::Cow<'a, ParsedEvent>>, TransportError> {
+        events
+            .iter()
+            .map(|event| self.field_filter.apply(event))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TransportError::serialization_error(&e.to_string()))
+    }
+
+    async fn forward_syslog(&self, syslog: &SyslogForwarder, events: &[ParsedEvent]) -> Result<(), TransportError> {
+        let filtered = self.filter_events(events)?;
+        let payload = syslog.encode(filtered.iter().map(|event| event.as_ref()));
+        debug!("📜 Forwarding {} syslog messages ({} bytes) to {}", events.len(), payload.len(), syslog.address());
+        self.throttle(payload.len()).await;
+        syslog.send(&payload).await
+    }
+
+    /// Ship events as OTLP LogRecords to an OpenTelemetry collector
+    async fn export_otlp(&self, events: &[ParsedEvent]) -> Result<(), TransportError> {
+        let filtered = self.filter_events(events)?;
+        let records = filtered.iter().map(|event| event.as_ref());
+        let encoder = OtlpEncoder::new(&self.config.otlp, &self.agent_id);
+        let endpoint = Self::otlp_endpoint(&self.config);
+        let grpc = self.config.protocol == TransportProtocol::OtlpGrpc;
+
+        let (url, content_type, body) = if grpc {
+            let url = format!("{}{}", endpoint.trim_end_matches('/'), otlp::GRPC_EXPORT_PATH);
+            (url, "application/grpc", otlp::grpc_frame(&encoder.encode_protobuf(records)))
+        } else if self.config.otlp.encoding == OtlpEncoding::Json {
+            let body = serde_json::to_vec(&encoder.encode_json(records))
+                .map_err(|e| TransportError::serialization_error(&e.to_string()))?;
+            (endpoint.to_string(), "application/json", body)
+        } else {
+            (endpoint.to_string(), "application/x-protobuf", encoder.encode_protobuf(records))
+        };
+        debug!("🔭 Exporting {} OTLP log records ({} bytes) to {}", events.len(), body.len(), url);
+        self.throttle(body.len()).await;
+
+        let start_time = std::time::Instant::now();
+        let mut request = self.client().post(&url).header("Content-Type", content_type);
+        if grpc {
+            request = request.header("TE", "trailers");
+        }
+        if self.config.otlp.send_api_key {
+            request = request.bearer_auth(&self.config.api_key);
+        }
+        for (name, value) in &self.config.otlp.headers {
+            request = request.header(name.as_str(), value);
+        }
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| self.request_error(e, &url))?;
+        let status = response.status();
+        let elapsed_ms = start_time.elapsed().as_millis() as f64;
+        self.update_connection_stats(elapsed_ms < 100.0, elapsed_ms).await;
+
+        if !status.is_success() {
+            let error_body = response.text().await.unwrap_or_default();
+            if status == 401 || status == 403 {
+                return Err(TransportError::AuthenticationFailed {
+                    method: "otlp".to_string(),
+                    reason: format!("Collector rejected credentials: {}", error_body),
+                    retry_allowed: false,
+                });
+            }
+            // Per the OTLP spec only throttling and gateway errors are worth retrying
+            return Err(TransportError::ServerError {
+                status: status.as_u16(),
+                message: error_body,
+                headers: vec![],
+                body: None,
+                retryable: matches!(status.as_u16(), 429 | 502 | 503 | 504),
+            });
+        }
+        if !grpc {
+            return Ok(());
+        }
+
+        // Errors are usually trailers-only responses; otherwise the status follows the body as a trailer
+        let mut grpc_headers = response.headers().clone();
+        if !grpc_headers.contains_key("grpc-status") {
+            let collected = http_body_util::BodyExt::collect(http::Response::from(response).into_body())
+                .await
+                .map_err(|e| TransportError::connection_failed(&format!("Failed to read gRPC response: {}", e)))?;
+            grpc_headers = collected.trailers().cloned().unwrap_or_default();
+        }
+        let code = grpc_headers
+            .get("grpc-status")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or_else(|| TransportError::ServerError {
+                status: status.as_u16(),
+                message: "gRPC response carried no grpc-status".to_string(),
+                headers: vec![],
+                body: None,
+                retryable: true,
+            })?;
+        let message = grpc_headers.get("grpc-message").and_then(|v| v.to_str().ok()).unwrap_or_default();
+        match otlp::grpc_status_error(code, message) {
+            Some(e) => Err(e),
+            None => {
+                debug!("✅ OTLP collector accepted {} log records ({}ms)", events.len(), elapsed_ms);
+                Ok(())
+            }
+        }
+    }
+
+    /// Post events to a Splunk HTTP Event Collector
+    async fn export_hec(&self, encoder: &HecEncoder, events: &[ParsedEvent]) -> Result<(), TransportError> {
+        let filtered = self.filter_events(events)?;
+        let body = encoder
+            .encode(filtered.iter().map(|event| event.as_ref()))
+            .map_err(|e| TransportError::serialization_error(&e.to_string()))?;
+        debug!("📮 Sending {} HEC events ({} bytes) to {}", events.len(), body.len(), encoder.url());
+        let request = self
+            .client()
+            .post(encoder.url())
+            .header(reqwest::header::AUTHORIZATION, format!("Splunk {}", self.config.api_key))
+            .header("Content-Type", "application/json");
+        self.post_export("splunk_hec", request, encoder.url(), body).await?;
+        Ok(())
+    }
+
+    /// Index events through the Elasticsearch/OpenSearch bulk API
+    async fn export_bulk(&self, encoder: &BulkEncoder, events: &[ParsedEvent]) -> Result<(), TransportError> {
+        let filtered = self.filter_events(events)?;
+        let body = encoder
+            .encode(filtered.iter().map(|event| event.as_ref()), &self.agent_id)
+            .map_err(|e| TransportError::serialization_error(&e.to_string()))?;
+        debug!("🔎 Sending {} bulk documents ({} bytes) to {}", events.len(), body.len(), encoder.url());
+        let mut request = self.client().post(encoder.url()).header("Content-Type", "application/x-ndjson");
+        match &self.config.elasticsearch.username {
+            Some(username) => request = request.basic_auth(username, Some(&self.config.api_key)),
+            None if !self.config.api_key.is_empty() => {
+                request = request.header(reqwest::header::AUTHORIZATION, format!("ApiKey {}", self.config.api_key));
+            }
+            None => {}
+        }
+        let response = self.post_export("elasticsearch", request, encoder.url(), body).await?;
+
+        // The bulk API answers 200 even when individual documents failed
+        let outcome = BulkOutcome::parse(&response).map_err(|e| TransportError::ServerError {
+            status: 200,
+            message: e,
+            headers: vec![],
+            body: None,
+            retryable: true,
+        })?;
+        if outcome.retryable > 0 {
+            // Resending the whole batch is safe with content IDs: documents that made it are conflicts or overwrites
+            if !self.config.elasticsearch.content_ids {
+                debug!("🔎 Retrying the bulk batch may index {} documents again", outcome.items - outcome.retryable);
+            }
+            return Err(TransportError::ServerError {
+                status: 429,
+                message: format!("{} of {} documents were not indexed: {}", outcome.retryable, outcome.items,
+                                 outcome.first_error.unwrap_or_default()),
+                headers: vec![],
+                body: None,
+                retryable: true,
+            });
+        }
+        if outcome.rejected > 0 {
+            warn!("🔎 Elasticsearch rejected {} of {} documents: {}", outcome.rejected, outcome.items,
+                  outcome.first_error.unwrap_or_default());
+        }
+        if outcome.duplicates > 0 {
+            debug!("🔎 {} documents were already indexed by an earlier attempt", outcome.duplicates);
+        }
+        Ok(())
+    }
+
+    /// Send a third-party export request and return the response body of a successful one
+    async fn post_export(&self, service: &str, request: reqwest::RequestBuilder, url: &str, body: Vec<u8>) -> Result<String, TransportError> {
+        self.throttle(body.len()).await;
+        let start_time = std::time::Instant::now();
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| self.request_error(e, url))?;
+        let status = response.status();
+        let elapsed_ms = start_time.elapsed().as_millis() as f64;
+        self.update_connection_stats(elapsed_ms < 100.0, elapsed_ms).await;
+
+        let response_body = response.text().await.unwrap_or_default();
+        if status.is_success() {
+            debug!("✅ {} accepted the batch ({}ms)", service, elapsed_ms);
+            return Ok(response_body);
+        }
+        if status == 401 || status == 403 {
+            return Err(TransportError::AuthenticationFailed {
+                method: service.to_string(),
+                reason: format!("{} rejected credentials: {}", url, response_body),
+                retry_allowed: false,
+            });
+        }
+        Err(TransportError::ServerError {
+            status: status.as_u16(),
+            message: response_body,
+            headers: vec![],
+            body: None,
+            retryable: status == 429 || status.is_server_error(),
+        })
+    }
+
+    /// Batch payload in the current format, with the compression applied and the headers describing it
+    fn prepare_payload(&self, events: &[ParsedEvent]) -> Result<PreparedPayload, TransportError> {
+        let format = *self.payload_format.read();
+        let raw_data = self.encode_payload(events, format)?;
+        
+        let mut headers = Vec::new();
+        // NDJSON has no batch envelope to carry the agent identity
+        if format == PayloadFormat::Ndjson {
+            headers.push((AGENT_ID_HEADER, self.agent_id.clone()));
+        }
+        if let Some(integrity) = &self.integrity {
+            headers.extend(integrity.headers(&raw_data));
+        }
+        if self.delivery.is_some() {
+            headers.push((DELIVERY_HEADER, DELIVERY_PROTOCOL.to_string()));
+        }
+
+        // Apply intelligent compression based on size threshold
+        let (body, encoding) = self.apply_intelligent_compression(raw_data)?;
+        Ok(PreparedPayload { body, format, encoding, headers })
+    }
+
+    /// Batch payload as uncompressed JSON
+    fn serialize_payload(&self, events: &[ParsedEvent]) -> Result<Vec<u8>, TransportError> {
+        self.encode_payload(events, PayloadFormat::Json)
+    }
+
+    fn encode_payload(&self, events: &[ParsedEvent], format: PayloadFormat) -> Result<Vec<u8>, TransportError> {
         let json_events: Vec<Value> = events
             .iter()
             .map(|event| {
-                serde_json::to_value(event)
+                self.field_filter.to_value(event)
                     .map_err(|e| TransportError::serialization_error(&e.to_string()))
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        let payload = serde_json::json!({
-            "events": json_events,
-            "agent_id": "rust-agent", // This could be configurable
-            "timestamp": chrono::Utc::now().to_rfc3339(),
-            "version": "1.0.0"
-        });
-
-        let raw_data = serde_json::to_vec(&payload)
-            .map_err(|e| TransportError::serialization_error(&e.to_string()))?;
+        format.encode(json_events, &self.agent_id)
+    }
 
-        // Apply intelligent compression based on size threshold
-        self.apply_intelligent_compression(raw_data)
+    /// Format to retry with when the server answers 415 with an Accept list that excludes `format`
+    fn negotiate_format(&self, status: reqwest::StatusCode, headers: &reqwest::header::HeaderMap, format: PayloadFormat) -> Option<PayloadFormat> {
+        if status != reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE {
+            return None;
+        }
+        let accept = headers.get(reqwest::header::ACCEPT)?.to_str().ok()?;
+        PayloadFormat::negotiate(self.config.format, accept).filter(|negotiated| *negotiated != format)
     }
 
-    /// Apply intelligent compression based on size thresholds and configuration
-    fn apply_intelligent_compression(&self, data: Vec<u8>) -> Result<Vec<u8>, TransportError> {
+    /// Apply intelligent compression based on size thresholds and configuration;
+    /// returns the payload and the algorithm it ended up encoded with
+    fn apply_intelligent_compression(&self, data: Vec<u8>) -> Result<(Vec<u8>, CompressionAlgorithm), TransportError> {
         // Check if compression is enabled and data meets threshold criteria
-        if !self.config.compression {
+        let algorithm = *self.content_encoding.read();
+        if !algorithm.is_enabled() {
             debug!("🗜️ Compression disabled, sending raw data ({} bytes)", data.len());
-            return Ok(data);
+            return Ok((data, CompressionAlgorithm::None));
         }
 
         let threshold = self.config.compression_threshold.unwrap_or(1024); // Default 1KB
@@ -705,17 +1467,19 @@ impl SecureTransport {
         if data.len() < threshold {
             debug!("🗜️ Data size ({} bytes) below threshold ({} bytes), sending uncompressed", 
                    data.len(), threshold);
-            return Ok(data);
+            return Ok((data, CompressionAlgorithm::None));
         }
 
-        // Perform zstd compression using tokio-compatible async compression
-        let compression_level = self.config.compression_level.unwrap_or(3); // Default level 3
+        // The configured level only applies to the configured algorithm, not a negotiated fallback
+        let compression_level = self.config.compression_level
+            .filter(|_| algorithm == self.config.compression)
+            .unwrap_or_else(|| algorithm.default_level());
         
-        debug!("🗜️ Compressing {} bytes with zstd level {}", data.len(), compression_level);
+        debug!("🗜️ Compressing {} bytes with {:?} level {}", data.len(), algorithm, compression_level);
         
-        // Use spawn_blocking to handle the compression without blocking the async executor
+        // Use block_in_place to handle the compression without blocking the async executor
         let compressed_data = tokio::task::block_in_place(|| {
-            self.compress_with_zstd(&data, compression_level)
+            algorithm.compress(&data, compression_level)
         })?;
 
         let compression_ratio = compressed_data.len() as f64 / data.len() as f64;
@@ -723,28 +1487,13 @@ impl SecureTransport {
         if compression_ratio < 0.9 { // Only use compression if we get >10% reduction
             info!("✅ Compression successful: {} → {} bytes (ratio: {:.2})", 
                   data.len(), compressed_data.len(), compression_ratio);
-            Ok(compressed_data)
+            Ok((compressed_data, algorithm))
         } else {
             debug!("⚠️ Compression not beneficial (ratio: {:.2}), sending uncompressed", compression_ratio);
-            Ok(data)
+            Ok((data, CompressionAlgorithm::None))
         }
     }
 
-    /// Synchronous zstd compression for use within spawn_blocking
-    fn compress_with_zstd(&self, data: &[u8], level: i32) -> Result<Vec<u8>, TransportError> {
-        use std::io::Cursor;
-        
-        let cursor = Cursor::new(data);
-        let mut encoder = zstd::stream::Encoder::new(Vec::new(), level)
-            .map_err(|e| TransportError::compression_error(&format!("Failed to create zstd encoder: {}", e)))?;
-        
-        std::io::copy(&mut cursor.clone(), &mut encoder)
-            .map_err(|e| TransportError::compression_error(&format!("Failed to compress data: {}", e)))?;
-            
-        encoder.finish()
-            .map_err(|e| TransportError::compression_error(&format!("Failed to finalize compression: {}", e)))
-    }
-
     pub async fn test_connection(&self) -> Result<(), TransportError> {
         info!("🔍 Testing connection to {}", self.config.server_url);
 
@@ -754,7 +1503,7 @@ impl SecureTransport {
         });
 
         let response = self
-            .client
+            .client()
             .post(format!("{}/health", self.config.server_url))
             .bearer_auth(&self.config.api_key)
             .header("Content-Type", "application/json")
@@ -794,46 +1543,44 @@ impl SecureTransport {
         }
     }
 
-    /// Get mTLS certificate status and expiry information
+    /// Expiry of the client certificate's leaf, when mTLS is configured with certificate files
     pub async fn get_certificate_status(&self) -> Option<CertificateStatus> {
-        if let Some(cert_path) = &self.config.client_cert_path {
-            match self.get_certificate_info(cert_path).await {
-                Ok(info) => Some(info),
-                Err(e) => {
-                    warn!("Failed to get certificate status: {}", e);
-                    None
-                }
+        let cert_path = self.config.client_cert_path.as_ref()?;
+        match read_certificates(cert_path, CertificateRole::Client, self.config.cert_expiry_warning_days, chrono::Utc::now()) {
+            Ok(certificates) => certificates.into_iter().next(),
+            Err(e) => {
+                warn!("Failed to get certificate status: {}", e);
+                None
             }
-        } else {
-            None
         }
     }
-    
-    /// Get detailed certificate information
-    async fn get_certificate_info(&self, cert_path: &str) -> Result<CertificateStatus, TransportError> {
-        // Read certificate file
-        let cert_pem = tokio::fs::read(cert_path).await
-            .map_err(|e| TransportError::TlsError {
-                operation: "read_certificate_info".to_string(),
-                reason: format!("Failed to read certificate file: {}", e),
-                certificate_issue: true,
-                source: Box::new(e),
-            })?;
-        
-        // Basic certificate information extraction (placeholder implementation)
-        // In production, you'd use a proper X.509 parsing library like x509-parser
-        let cert_str = String::from_utf8_lossy(&cert_pem);
-        let has_certificate = cert_str.contains("-----BEGIN CERTIFICATE-----");
-        
-        Ok(CertificateStatus {
-            path: cert_path.to_string(),
-            exists: true,
-            valid: has_certificate,
-            expires_at: None, // Would be populated with actual X.509 parsing
-            days_until_expiry: None,
-            subject: None,
-            issuer: None,
-        })
+
+    /// Expiry of every client and CA certificate this transport and its destinations load from files
+    pub fn get_certificate_expiry(&self) -> Vec<CertificateStatus> {
+        let now = chrono::Utc::now();
+        let mut files: Vec<(&str, CertificateRole, u32)> = Vec::new();
+        for config in std::iter::once(&self.config).chain(self.destinations.iter().map(|d| &d.transport.config)) {
+            // A SPIFFE SVID replaces the client certificate files and rotates on its own
+            let client_cert = config.client_cert_path.as_deref().filter(|_| config.identity.spiffe.is_none());
+            let candidates = [(client_cert, CertificateRole::Client), (config.ca_cert_path.as_deref(), CertificateRole::Ca)];
+            for (path, role) in candidates {
+                let Some(path) = path else { continue };
+                if !files.iter().any(|(seen, seen_role, _)| *seen == path && *seen_role == role) {
+                    files.push((path, role, config.cert_expiry_warning_days));
+                }
+            }
+        }
+
+        files.into_iter()
+            .filter_map(|(path, role, warning_days)| match read_certificates(path, role, warning_days, now) {
+                Ok(certificates) => Some(certificates),
+                Err(e) => {
+                    debug!("Failed to check certificate expiry: {}", e);
+                    None
+                }
+            })
+            .flatten()
+            .collect()
     }
 
     /// Get circuit breaker statistics for monitoring transport resilience
@@ -887,7 +1634,9 @@ impl SecureTransport {
             server_url: self.config.server_url.clone(),
             tls_enabled: self.config.tls_verify,
             mtls_enabled: self.config.client_cert_path.is_some(),
-            compression_enabled: self.config.compression,
+            compression_enabled: self.config.compression.is_enabled(),
+            compression: *self.content_encoding.read(),
+            payload_format: *self.payload_format.read(),
             batch_size: self.config.batch_size,
             retry_attempts: self.config.retry_attempts,
             // Connection pooling stats
@@ -896,7 +1645,29 @@ impl SecureTransport {
             keep_alive_timeout_sec: self.config.keep_alive_timeout.unwrap_or(std::time::Duration::from_secs(90)).as_secs(),
             connection_reuse_rate: reuse_rate,
             average_connection_time_ms: pool_stats.average_connection_time_ms,
+            certificates: self.get_certificate_expiry(),
+            bandwidth: self.bandwidth.as_ref().map(|limiter| limiter.stats()),
+            upload_schedule: self.upload_schedule.as_ref().map(|schedule| schedule.status()),
+            proxy: self.proxy.clone(),
+        }
+    }
+
+    /// Per-destination state of the named destinations
+    pub async fn get_destination_stats(&self) -> Vec<DestinationStats> {
+        let mut stats = Vec::with_capacity(self.destinations.len());
+        for destination in &self.destinations {
+            let breaker = destination.transport.get_circuit_breaker_stats().await;
+            stats.push(DestinationStats {
+                name: destination.config.name.clone(),
+                server_url: destination.config.server_url.clone(),
+                routes: destination.config.routes.len(),
+                circuit_breaker_state: breaker.state.to_string(),
+                total_requests: breaker.total_requests,
+                failure_rate: breaker.failure_rate,
+                health_score: breaker.health_score,
+            });
         }
+        stats
     }
 
     /// Initialize WebSocket connection for bidirectional communication
@@ -921,7 +1692,7 @@ impl SecureTransport {
 
         // Spawn WebSocket writer task
         let connected_write = self.websocket_connected.clone();
-        tokio::spawn(async move {
+        component_usage::spawn(component_usage::TRANSPORT, async move {
             let mut write = ws_write;
             let mut cmd_receiver = cmd_rx;
             
@@ -937,7 +1708,7 @@ impl SecureTransport {
         // Spawn WebSocket reader task  
         let connected_read = self.websocket_connected.clone();
         let reader_tx = tx.clone();
-        tokio::spawn(async move {
+        component_usage::spawn(component_usage::TRANSPORT, async move {
             let mut read = ws_read;
             
             while let Some(msg_result) = read.next().await {
@@ -977,7 +1748,7 @@ impl SecureTransport {
         }
 
         if let Some(sender_ref) = &self.websocket_sender {
-            let payload = self.prepare_payload(events)?;
+            let payload = self.serialize_payload(events)?;
             let message = Message::text(payload);
             
             let sender = sender_ref.lock().await;
@@ -1027,7 +1798,7 @@ impl SecureTransport {
         let stats_ref = self.connection_pool_stats.clone();
         let config = self.config.clone();
         
-        let monitor_handle = tokio::spawn(async move {
+        let monitor_handle = component_usage::spawn(component_usage::TRANSPORT, async move {
             let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
             
             loop {
@@ -1180,6 +1951,10 @@ pub struct TransportStats {
     pub tls_enabled: bool,
     pub mtls_enabled: bool,
     pub compression_enabled: bool,
+    /// Encoding currently in use, after any negotiation with the server
+    pub compression: CompressionAlgorithm,
+    /// Batch format currently in use, after any negotiation with the server
+    pub payload_format: PayloadFormat,
     pub batch_size: usize,
     pub retry_attempts: usize,
     // Connection pooling stats
@@ -1188,17 +1963,25 @@ pub struct TransportStats {
     pub keep_alive_timeout_sec: u64,
     pub connection_reuse_rate: f64,
     pub average_connection_time_ms: f64,
+    /// Client and CA certificates read from files, with days until expiry
+    pub certificates: Vec<CertificateStatus>,
+    /// Upload cap and the time batches spent waiting for it
+    pub bandwidth: Option<BandwidthStats>,
+    /// Whether uploads are currently allowed, when restricted to windows or networks
+    pub upload_schedule: Option<UploadScheduleStatus>,
+    /// Proxy requests go through and where it came from; None for direct connections
+    pub proxy: Option<ProxyStatus>,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct CertificateStatus {
-    pub path: String,
-    pub exists: bool,
-    pub valid: bool,
-    pub expires_at: Option<SystemTime>,
-    pub days_until_expiry: Option<i64>,
-    pub subject: Option<String>,
-    pub issuer: Option<String>,
+#[derive(Debug, serde::Serialize)]
+pub struct DestinationStats {
+    pub name: String,
+    pub server_url: String,
+    pub routes: usize,
+    pub circuit_breaker_state: String,
+    pub total_requests: u64,
+    pub failure_rate: f64,
+    pub health_score: f64,
 }
 
 #[cfg(test)]
@@ -1212,18 +1995,24 @@ mod tests {
             server_url: "https://api.example.com".to_string(),
             api_key: "test-key".to_string(),
             tls_verify: true,
-            compression: true,
+            compression: crate::compression::CompressionAlgorithm::Zstd,
             compression_threshold: Some(1024),
             compression_level: Some(3),
             batch_size: 100,
             batch_timeout: 5,
             retry_attempts: 3,
             retry_delay: 2,
+            retry_policy: Default::default(),
+            bandwidth: Default::default(),
+            schedule: Default::default(),
+            proxy: Default::default(),
             client_cert_path: None,
             client_key_path: None,
             client_key_password: None,
             ca_cert_path: None,
             cert_expiry_warning_days: 30,
+            identity: Default::default(),
+            enrollment: Default::default(),
             circuit_breaker_failure_threshold: Some(5),
             circuit_breaker_recovery_timeout: Some(std::time::Duration::from_secs(30)),
             circuit_breaker_success_threshold: Some(3),
@@ -1240,6 +2029,15 @@ mod tests {
             http2_keep_alive_interval: Some(std::time::Duration::from_secs(30)),
             http2_keep_alive_timeout: Some(std::time::Duration::from_secs(10)),
             http2_keep_alive_while_idle: Some(true),
+            protocol: Default::default(),
+            otlp: Default::default(),
+            syslog: Default::default(),
+            splunk_hec: Default::default(),
+            elasticsearch: Default::default(),
+            format: Default::default(),
+            delivery: Default::default(),
+            destinations: Vec::new(),
+            primary_route: Default::default(),
         };
 
         let transport = SecureTransport::new(config);
@@ -1252,18 +2050,24 @@ mod tests {
             server_url: "https://api.example.com".to_string(),
             api_key: "test-key".to_string(),
             tls_verify: true,
-            compression: true,
+            compression: crate::compression::CompressionAlgorithm::Zstd,
             compression_threshold: Some(1024),
             compression_level: Some(3),
             batch_size: 100,
             batch_timeout: 5,
             retry_attempts: 3,
             retry_delay: 2,
+            retry_policy: Default::default(),
+            bandwidth: Default::default(),
+            schedule: Default::default(),
+            proxy: Default::default(),
             client_cert_path: None,
             client_key_path: None,
             client_key_password: None,
             ca_cert_path: None,
             cert_expiry_warning_days: 30,
+            identity: Default::default(),
+            enrollment: Default::default(),
             circuit_breaker_failure_threshold: Some(5),
             circuit_breaker_recovery_timeout: Some(std::time::Duration::from_secs(30)),
             circuit_breaker_success_threshold: Some(3),
@@ -1280,6 +2084,15 @@ mod tests {
             http2_keep_alive_interval: Some(std::time::Duration::from_secs(30)),
             http2_keep_alive_timeout: Some(std::time::Duration::from_secs(10)),
             http2_keep_alive_while_idle: Some(true),
+            protocol: Default::default(),
+            otlp: Default::default(),
+            syslog: Default::default(),
+            splunk_hec: Default::default(),
+            elasticsearch: Default::default(),
+            format: Default::default(),
+            delivery: Default::default(),
+            destinations: Vec::new(),
+            primary_route: Default::default(),
         };
 
         let transport = SecureTransport::new(config).await.unwrap();
@@ -43,7 +43,7 @@ fn create_test_event() -> ParsedEvent {
         event_type: "test".to_string(),
         message: "Test message".to_string(),
         fields: std::collections::HashMap::new(),
-        raw_data: "raw test data".to_string(),
+        raw_data: "raw test data".into(),
     }
 }
 
@@ -0,0 +1,269 @@
+// Store-and-forward upload windows
+// Sites on satellite or metered links upload only inside configured time windows, and optionally only while a VPN
+// interface is up or the default route avoids a metered interface. Outside them the transport refuses batches with
+// TransmissionDeferred, so events stay in the buffer until the next window opens.
+
+use crate::errors::TransportError;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDateTime, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::info;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadScheduleConfig {
+    pub enabled: bool,
+    /// Read window times as UTC instead of the host's local time
+    pub utc: bool,
+    /// Uploads happen only inside one of these; empty for any time
+    pub windows: Vec<UploadWindow>,
+    /// At least one of these interfaces must be up, e.g. a VPN tunnel; empty for any network
+    pub required_interfaces: Vec<String>,
+    /// No uploads while the default route goes through one of these, e.g. a cellular modem
+    pub metered_interfaces: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadWindow {
+    /// Days the window starts on, e.g. ["mon", "tue"]; empty for every day
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// HH:MM; a window whose end is not after its start runs past midnight
+    pub start: String,
+    pub end: String,
+}
+
+impl UploadScheduleConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (i, window) in self.windows.iter().enumerate() {
+            if let Err(e) = ParsedWindow::parse(window) {
+                errors.push(format!("windows[{}]: {}", i, e));
+            }
+        }
+        errors
+    }
+}
+
+struct ParsedWindow {
+    /// Empty for every day
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl ParsedWindow {
+    fn parse(window: &UploadWindow) -> Result<Self, String> {
+        let time = |value: &str| NaiveTime::parse_from_str(value, "%H:%M")
+            .map_err(|_| format!("'{}' is not a HH:MM time", value));
+        let days = window.days.iter()
+            .map(|day| day.parse::<Weekday>().map_err(|_| format!("'{}' is not a day of the week", day)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (start, end) = (time(&window.start)?, time(&window.end)?);
+        if start == end {
+            return Err("start and end must differ".to_string());
+        }
+        Ok(Self { days, start, end })
+    }
+
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn contains(&self, at: NaiveDateTime) -> bool {
+        let (day, time) = (at.weekday(), at.time());
+        if self.start < self.end {
+            self.starts_on(day) && self.start <= time && time < self.end
+        } else {
+            (self.starts_on(day) && time >= self.start) || (self.starts_on(day.pred()) && time < self.end)
+        }
+    }
+}
+
+/// Interfaces that are up and the one carrying the default route
+#[derive(Debug, Clone, Default)]
+pub struct NetworkState {
+    pub up_interfaces: Vec<String>,
+    pub default_route: Option<String>,
+}
+
+impl NetworkState {
+    #[cfg(target_os = "linux")]
+    pub fn detect() -> Self {
+        let up_interfaces = std::fs::read_dir("/sys/class/net")
+            .map(|entries| entries.flatten()
+                .filter(|entry| {
+                    // Tunnels without carrier detection report "unknown" while usable
+                    let state = std::fs::read_to_string(entry.path().join("operstate")).unwrap_or_default();
+                    matches!(state.trim(), "up" | "unknown")
+                })
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect())
+            .unwrap_or_default();
+        // Columns: Iface Destination Gateway ...; the default route has destination 00000000
+        let default_route = std::fs::read_to_string("/proc/net/route").ok().and_then(|routes| {
+            routes.lines().skip(1)
+                .map(|line| line.split_whitespace().collect::<Vec<_>>())
+                .find(|columns| columns.get(1) == Some(&"00000000"))
+                .and_then(|columns| columns.first().map(|iface| iface.to_string()))
+        });
+        Self { up_interfaces, default_route }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn detect() -> Self {
+        let networks = sysinfo::Networks::new_with_refreshed_list();
+        Self {
+            up_interfaces: networks.iter().map(|(name, _)| name.to_string()).collect(),
+            default_route: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadScheduleStatus {
+    pub open: bool,
+    /// Why uploads are held, while closed
+    pub reason: Option<String>,
+    /// Start of the next window, when closed by the time windows
+    pub next_window: Option<DateTime<Utc>>,
+}
+
+/// Decides whether the transport may upload right now
+pub struct UploadSchedule {
+    config: UploadScheduleConfig,
+    windows: Vec<ParsedWindow>,
+    open: AtomicBool,
+}
+
+impl UploadSchedule {
+    pub fn new(config: &UploadScheduleConfig) -> Result<Self, String> {
+        let windows = config.windows.iter()
+            .map(ParsedWindow::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { config: config.clone(), windows, open: AtomicBool::new(true) })
+    }
+
+    /// Ok while uploads are allowed; logs when the schedule opens or closes
+    pub fn check(&self) -> Result<(), TransportError> {
+        let status = self.status();
+        if self.open.swap(status.open, Ordering::Relaxed) != status.open {
+            match &status.reason {
+                None => info!("📡 Upload window open, sending buffered events"),
+                Some(reason) => info!("📴 Uploads held until the schedule allows them: {}", reason),
+            }
+        }
+        match status.reason {
+            None => Ok(()),
+            Some(reason) => Err(TransportError::TransmissionDeferred { reason, next_window: status.next_window }),
+        }
+    }
+
+    pub fn status(&self) -> UploadScheduleStatus {
+        let now = match self.config.utc {
+            true => Utc::now().naive_utc(),
+            false => Local::now().naive_local(),
+        };
+        self.status_at(now, &NetworkState::detect())
+    }
+
+    /// A time on the schedule's clock in UTC
+    fn to_utc(&self, at: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self.config.utc {
+            true => Some(at.and_utc()),
+            false => at.and_local_timezone(Local).earliest().map(|local| local.with_timezone(&Utc)),
+        }
+    }
+
+    fn status_at(&self, now: NaiveDateTime, network: &NetworkState) -> UploadScheduleStatus {
+        let closed = |reason: String, next_window: Option<NaiveDateTime>| UploadScheduleStatus {
+            open: false,
+            reason: Some(reason),
+            next_window: next_window.and_then(|next| self.to_utc(next)),
+        };
+        if !self.windows.is_empty() && !self.windows.iter().any(|window| window.contains(now)) {
+            return closed("outside the upload windows".to_string(), self.next_window_start(now));
+        }
+        if !self.config.required_interfaces.is_empty()
+            && !self.config.required_interfaces.iter().any(|name| network.up_interfaces.contains(name)) {
+            return closed(format!("none of {} is up", self.config.required_interfaces.join(", ")), None);
+        }
+        if let Some(route) = network.default_route.as_ref().filter(|route| self.config.metered_interfaces.contains(route)) {
+            return closed(format!("default route is on metered interface {}", route), None);
+        }
+        UploadScheduleStatus { open: true, reason: None, next_window: None }
+    }
+
+    /// Earliest window start after `now`, within the coming week
+    fn next_window_start(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        (0..=7)
+            .map(|offset| now.date() + ChronoDuration::days(offset))
+            .flat_map(|date| self.windows.iter()
+                .filter(move |window| window.starts_on(date.weekday()))
+                .map(move |window| date.and_time(window.start)))
+            .filter(|start| *start > now)
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2026-10-12 is a Monday
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn window(days: &[&str], start: &str, end: &str) -> UploadWindow {
+        UploadWindow { days: days.iter().map(|d| d.to_string()).collect(), start: start.to_string(), end: end.to_string() }
+    }
+
+    #[test]
+    fn test_windows_including_overnight_ones() {
+        let config = UploadScheduleConfig {
+            enabled: true,
+            utc: true,
+            windows: vec![window(&["mon", "wed"], "22:00", "02:00"), window(&["sat"], "09:00", "12:00")],
+            ..Default::default()
+        };
+        let schedule = UploadSchedule::new(&config).unwrap();
+        let network = NetworkState::default();
+
+        assert!(schedule.status_at(at(12, 23, 0), &network).open);
+        // Past midnight the Monday window still runs into Tuesday
+        assert!(schedule.status_at(at(13, 1, 59), &network).open);
+        assert!(!schedule.status_at(at(13, 2, 0), &network).open);
+        assert!(schedule.status_at(at(17, 9, 0), &network).open);
+
+        let closed = schedule.status_at(at(13, 12, 0), &network);
+        assert_eq!(closed.reason.as_deref(), Some("outside the upload windows"));
+        assert_eq!(closed.next_window, Some(at(14, 22, 0).and_utc()));
+
+        let invalid = UploadScheduleConfig { windows: vec![window(&["someday"], "25:00", "01:00"), window(&[], "01:00", "01:00")], ..Default::default() };
+        assert_eq!(invalid.validate().len(), 2);
+    }
+
+    #[test]
+    fn test_network_conditions() {
+        let config = UploadScheduleConfig {
+            enabled: true,
+            required_interfaces: vec!["wg0".to_string()],
+            metered_interfaces: vec!["wwan0".to_string()],
+            ..Default::default()
+        };
+        let schedule = UploadSchedule::new(&config).unwrap();
+        let network = |up: &[&str], route: &str| NetworkState {
+            up_interfaces: up.iter().map(|name| name.to_string()).collect(),
+            default_route: Some(route.to_string()),
+        };
+
+        assert!(schedule.status_at(at(12, 12, 0), &network(&["eth0", "wg0"], "eth0")).open);
+        let vpn_down = schedule.status_at(at(12, 12, 0), &network(&["eth0"], "eth0"));
+        assert_eq!(vpn_down.reason.as_deref(), Some("none of wg0 is up"));
+        let metered = schedule.status_at(at(12, 12, 0), &network(&["wwan0", "wg0"], "wwan0"));
+        assert_eq!(metered.reason.as_deref(), Some("default route is on metered interface wwan0"));
+        assert!(metered.next_window.is_none());
+    }
+}
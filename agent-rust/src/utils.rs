@@ -1,6 +1,9 @@
 // Utility functions and statistics for the SecureWatch Agent
 
+use crate::config::ConfigValidationWarning;
+use crate::errors::{AgentError, ErrorReport};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Instant;
 
 #[derive(Debug, Clone, Serialize)]
@@ -12,6 +15,11 @@ pub struct AgentStats {
     pub bytes_processed: u64,
     pub bytes_sent: u64,
     pub errors: u64,
+    /// Occurrences per stable error code name
+    pub error_counts: HashMap<&'static str, u64>,
+    pub last_error: Option<ErrorReport>,
+    /// Non-blocking findings from validating the active configuration
+    pub config_warnings: Vec<ConfigValidationWarning>,
     #[serde(skip)]
     pub start_time: Instant,
     #[serde(skip)]
@@ -28,11 +36,21 @@ impl AgentStats {
             bytes_processed: 0,
             bytes_sent: 0,
             errors: 0,
+            error_counts: HashMap::new(),
+            last_error: None,
+            config_warnings: Vec::new(),
             start_time: Instant::now(),
             last_activity: None,
         }
     }
     
+    /// Count an error under its stable code and remember it for heartbeats
+    pub fn record_error(&mut self, error: &AgentError) {
+        self.errors += 1;
+        *self.error_counts.entry(error.code().name).or_insert(0) += 1;
+        self.last_error = Some(error.report());
+    }
+    
     pub fn uptime_seconds(&self) -> u64 {
         self.start_time.elapsed().as_secs()
     }
@@ -72,7 +72,7 @@ async fn test_end_to_end_log_processing() {
             event_type: "application".to_string(),
             message: "Test application log".to_string(),
             fields: HashMap::new(),
-            raw_data: "raw log data".to_string(),
+            raw_data: "raw log data".into(),
         },
         ParsedEvent {
             timestamp: chrono::Utc::now(),
@@ -80,7 +80,7 @@ async fn test_end_to_end_log_processing() {
             event_type: "system".to_string(),
             message: "System event occurred".to_string(),
             fields: HashMap::new(),
-            raw_data: "system log data".to_string(),
+            raw_data: "system log data".into(),
         },
     ];
     
@@ -177,7 +177,7 @@ async fn test_error_recovery_integration() {
         event_type: "test".to_string(),
         message: "Test recovery".to_string(),
         fields: HashMap::new(),
-        raw_data: "raw data".to_string(),
+        raw_data: "raw data".into(),
     };
     
     agent.process_event(event).await.expect("Should process event");
@@ -208,7 +208,7 @@ async fn test_resource_monitoring_integration() {
             event_type: "test".to_string(),
             message: format!("Load test event {}", i),
             fields: HashMap::new(),
-            raw_data: format!("raw data {}", i),
+            raw_data: format!("raw data {}", i).into(),
         };
         
         agent.process_event(event).await.expect("Should process event");
@@ -254,7 +254,7 @@ async fn test_security_validation_integration() {
                 serde_json::Value::String("<script>alert('xss')</script>".to_string()));
             fields
         },
-        raw_data: "malicious data".to_string(),
+        raw_data: "malicious data".into(),
     };
     
     // Should be blocked by validation
@@ -294,7 +294,7 @@ async fn test_buffering_persistence_integration() {
                 event_type: "persistent_test".to_string(),
                 message: format!("Persistent event {}", i),
                 fields: HashMap::new(),
-                raw_data: format!("persistent data {}", i),
+                raw_data: format!("persistent data {}", i).into(),
             };
             
             let _ = agent.process_event(event).await; // May fail due to unreachable server
@@ -389,7 +389,7 @@ async fn test_concurrent_processing_integration() {
                 event_type: "test".to_string(),
                 message: format!("Concurrent event {}", i),
                 fields: HashMap::new(),
-                raw_data: format!("concurrent data {}", i),
+                raw_data: format!("concurrent data {}", i).into(),
             };
             
             let mut agent_lock = agent_clone.lock().await;
@@ -472,7 +472,7 @@ async fn test_metrics_collection_integration() {
             event_type: "test".to_string(),
             message: format!("Metrics test event {}", i),
             fields: HashMap::new(),
-            raw_data: format!("metrics data {}", i),
+            raw_data: format!("metrics data {}", i).into(),
         };
         
         agent.process_event(event).await.expect("Should process event");
@@ -9,15 +9,27 @@ use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Manager, State, Emitter};
 use directories::ProjectDirs;
 
+mod preflight;
+mod silent;
+mod template;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 struct InstallationConfig {
     install_path: String,
     server_endpoint: String,
+    /// Bearer token the agent authenticates to the server with
+    api_key: String,
     agent_name: String,
     install_as_service: bool,
     start_automatically: bool,
     create_desktop_shortcut: bool,
     architecture: String,
+    /// Outbound proxy the agent reaches the server through
+    proxy: ProxySettings,
+    /// Merged over the generated config.toml; set by fleet templates
+    #[serde(skip_serializing_if = "toml::Table::is_empty")]
+    agent_config: toml::Table,
 }
 
 impl Default for InstallationConfig {
@@ -31,15 +43,64 @@ impl Default for InstallationConfig {
         Self {
             install_path: default_path,
             server_endpoint: "https://your-securewatch-server.com".to_string(),
+            api_key: String::new(),
             agent_name: "SecureWatch Agent".to_string(),
             install_as_service: true,
             start_automatically: true,
             create_desktop_shortcut: false,
             architecture: std::env::consts::ARCH.to_string(),
+            agent_config: toml::Table::new(),
+            proxy: ProxySettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+struct ProxySettings {
+    /// http://, https://, socks5:// or socks5h://; empty for a direct connection
+    url: String,
+    /// Basic credentials; NTLM-only proxies need a local relay such as Px or Cntlm
+    username: String,
+    password: String,
+    /// Hosts reached directly: names, ".example.com" suffixes, IPs or CIDR ranges
+    no_proxy: Vec<String>,
+}
+
+impl ProxySettings {
+    /// Whether `host` is reached directly; CIDR ranges are only matched by the agent
+    fn bypasses(&self, host: &str) -> bool {
+        self.no_proxy.iter().map(|entry| entry.trim()).any(|entry| {
+            let domain = entry.trim_start_matches('.');
+            entry == "*"
+                || host.eq_ignore_ascii_case(domain)
+                || (host.len() > domain.len()
+                    && host.to_ascii_lowercase().ends_with(&format!(".{}", domain.to_ascii_lowercase())))
+        })
+    }
+
+    /// [transport.proxy] for the generated config.toml; empty without a proxy
+    fn config_section(&self) -> String {
+        if self.url.trim().is_empty() {
+            return String::new();
         }
+        let mut section = format!("\n[transport.proxy]\nurl = {}\n", toml_string(self.url.trim()));
+        if !self.username.is_empty() {
+            section.push_str(&format!("username = {}\npassword = {}\n", toml_string(&self.username), toml_string(&self.password)));
+        }
+        if !self.no_proxy.is_empty() {
+            let hosts: Vec<String> = self.no_proxy.iter().map(|host| toml_string(host.trim())).collect();
+            section.push_str(&format!("no_proxy = [{}]\n", hosts.join(", ")));
+        }
+        section
     }
 }
 
+/// A quoted, escaped TOML string
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct InstallationProgress {
     step: String,
@@ -49,6 +110,27 @@ struct InstallationProgress {
     error: Option<String>,
 }
 
+/// Receives installation_progress updates; the window in the GUI, stdout in silent mode
+type ProgressReporter<'a> = &'a (dyn Fn(InstallationProgress) + Send + Sync);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct UninstallOptions {
+    install_path: String,
+    /// Keep config.toml (and the management TLS material next to it)
+    preserve_config: bool,
+    /// Keep the event buffer, agent state and logs
+    preserve_data: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct LoadedTemplate {
+    path: String,
+    config: InstallationConfig,
+}
+
+/// Settings exported next to config.toml after an install
+const EXPORTED_TEMPLATE: &str = "install-settings.toml";
+
 #[derive(Debug, Serialize, Deserialize)]
 struct SystemInfo {
     os: String,
@@ -101,15 +183,51 @@ async fn validate_install_path(path: String) -> Result<bool, String> {
     }
 }
 
+/// TLS handshake and authenticated health request against the server the agent will ship to
+#[tauri::command]
+async fn test_server_connection(
+    endpoint: String,
+    api_key: String,
+    proxy: Option<ProxySettings>,
+) -> Result<preflight::ConnectionTestResult, String> {
+    Ok(preflight::test_server_connection(&endpoint, &api_key, &proxy.unwrap_or_default()).await)
+}
+
+/// The given template, or the managed one MDM pushed to this host; None when there is none
+#[tauri::command]
+async fn load_install_template(path: Option<String>) -> Result<Option<LoadedTemplate>, String> {
+    let Some(path) = path.map(PathBuf::from).or_else(template::discover) else {
+        return Ok(None);
+    };
+    let config = template::load(&path)?;
+    Ok(Some(LoadedTemplate { path: path.display().to_string(), config }))
+}
+
+/// Save the settings an installation used as a template; returns the file written
+#[tauri::command]
+async fn export_install_template(config: InstallationConfig, path: Option<String>) -> Result<String, String> {
+    let path = path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config_dir(&config.install_path).join(EXPORTED_TEMPLATE));
+    template::export(&config, &path)?;
+    Ok(path.display().to_string())
+}
+
 #[tauri::command]
 async fn perform_installation(
     config: InstallationConfig,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     let window = app_handle.get_webview_window("main").unwrap();
-    
+    run_installation(&config, &|progress| {
+        let _ = window.emit("installation_progress", progress);
+    })
+    .await
+}
+
+async fn run_installation(config: &InstallationConfig, report: ProgressReporter<'_>) -> Result<(), String> {
     // Step 1: Prepare installation
-    let _ = window.emit("installation_progress", InstallationProgress {
+    report(InstallationProgress {
         step: "prepare".to_string(),
         progress: 10,
         message: "Preparing installation...".to_string(),
@@ -120,7 +238,7 @@ async fn perform_installation(
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
     // Step 2: Copy files
-    let _ = window.emit("installation_progress", InstallationProgress {
+    report(InstallationProgress {
         step: "copy_files".to_string(),
         progress: 30,
         message: "Copying SecureWatch Agent files...".to_string(),
@@ -128,8 +246,8 @@ async fn perform_installation(
         error: None,
     });
 
-    if let Err(e) = copy_agent_files(&config).await {
-        let _ = window.emit("installation_progress", InstallationProgress {
+    if let Err(e) = copy_agent_files(config).await {
+        report(InstallationProgress {
             step: "copy_files".to_string(),
             progress: 30,
             message: "Failed to copy files".to_string(),
@@ -142,7 +260,7 @@ async fn perform_installation(
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
     // Step 3: Create configuration
-    let _ = window.emit("installation_progress", InstallationProgress {
+    report(InstallationProgress {
         step: "configure".to_string(),
         progress: 50,
         message: "Creating configuration files...".to_string(),
@@ -150,8 +268,8 @@ async fn perform_installation(
         error: None,
     });
 
-    if let Err(e) = create_configuration(&config).await {
-        let _ = window.emit("installation_progress", InstallationProgress {
+    if let Err(e) = create_configuration(config).await {
+        report(InstallationProgress {
             step: "configure".to_string(),
             progress: 50,
             message: "Failed to create configuration".to_string(),
@@ -165,7 +283,7 @@ async fn perform_installation(
 
     // Step 4: Install service
     if config.install_as_service {
-        let _ = window.emit("installation_progress", InstallationProgress {
+        report(InstallationProgress {
             step: "service".to_string(),
             progress: 70,
             message: "Installing system service...".to_string(),
@@ -173,8 +291,8 @@ async fn perform_installation(
             error: None,
         });
 
-        if let Err(e) = install_service(&config).await {
-            let _ = window.emit("installation_progress", InstallationProgress {
+        if let Err(e) = install_service(config).await {
+            report(InstallationProgress {
                 step: "service".to_string(),
                 progress: 70,
                 message: "Failed to install service".to_string(),
@@ -188,7 +306,7 @@ async fn perform_installation(
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
     // Step 5: Final setup
-    let _ = window.emit("installation_progress", InstallationProgress {
+    report(InstallationProgress {
         step: "finalize".to_string(),
         progress: 90,
         message: "Finalizing installation...".to_string(),
@@ -197,13 +315,13 @@ async fn perform_installation(
     });
 
     if config.create_desktop_shortcut {
-        let _ = create_desktop_shortcut(&config).await;
+        let _ = create_desktop_shortcut(config).await;
     }
 
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
     // Step 6: Complete
-    let _ = window.emit("installation_progress", InstallationProgress {
+    report(InstallationProgress {
         step: "complete".to_string(),
         progress: 100,
         message: "Installation completed successfully!".to_string(),
@@ -218,7 +336,7 @@ async fn perform_installation(
 async fn start_agent_service() -> Result<String, String> {
     if cfg!(target_os = "macos") {
         let output = Command::new("launchctl")
-            .args(&["load", "-w", "/Library/LaunchDaemons/com.securewatch.agent.plist"])
+            .args(&["load", "-w", LAUNCHD_PLIST])
             .output()
             .map_err(|e| format!("Failed to start service: {}", e))?;
 
@@ -229,7 +347,7 @@ async fn start_agent_service() -> Result<String, String> {
         }
     } else if cfg!(target_os = "windows") {
         let output = Command::new("sc")
-            .args(&["start", "SecureWatchAgent"])
+            .args(&["start", WINDOWS_SERVICE_NAME])
             .output()
             .map_err(|e| format!("Failed to start service: {}", e))?;
 
@@ -238,12 +356,332 @@ async fn start_agent_service() -> Result<String, String> {
         } else {
             Err(String::from_utf8_lossy(&output.stderr).to_string())
         }
+    } else if cfg!(target_os = "linux") {
+        run_systemctl(&["start", SYSTEMD_UNIT])?;
+        Ok("Service started successfully".to_string())
     } else {
         Err("Unsupported platform".to_string())
     }
 }
 
+/// Service name registered with the Windows Service Control Manager
+const WINDOWS_SERVICE_NAME: &str = "SecureWatchAgent";
+
+/// systemd unit installed on Linux; matches `securewatch-agent --install-service`
+const SYSTEMD_UNIT: &str = "securewatch-agent.service";
+
+/// LaunchDaemon registered on macOS
+const LAUNCHD_PLIST: &str = "/Library/LaunchDaemons/com.securewatch.agent.plist";
+
+#[tauri::command]
+async fn perform_uninstall(
+    options: UninstallOptions,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let window = app_handle.get_webview_window("main").unwrap();
+    run_uninstall(&options, &|progress| {
+        let _ = window.emit("installation_progress", progress);
+    })
+    .await
+}
+
+async fn run_uninstall(options: &UninstallOptions, report: ProgressReporter<'_>) -> Result<(), String> {
+    let fail = |step: &str, progress: u32, message: &str, e: String| {
+        report(InstallationProgress {
+            step: step.to_string(),
+            progress,
+            message: message.to_string(),
+            completed: false,
+            error: Some(e.clone()),
+        });
+        e
+    };
+
+    // Step 1: Stop and unregister the service
+    report(InstallationProgress {
+        step: "service".to_string(),
+        progress: 20,
+        message: "Stopping and removing the SecureWatch Agent service...".to_string(),
+        completed: false,
+        error: None,
+    });
+
+    remove_service()
+        .await
+        .map_err(|e| fail("service", 20, "Failed to remove service", e))?;
+
+    // Step 2: Remove binaries
+    report(InstallationProgress {
+        step: "remove_files".to_string(),
+        progress: 50,
+        message: "Removing SecureWatch Agent files...".to_string(),
+        completed: false,
+        error: None,
+    });
+
+    remove_agent_files(&options.install_path)
+        .map_err(|e| fail("remove_files", 50, "Failed to remove files", e))?;
+
+    // Step 3: Remove configuration and data unless asked to keep them
+    report(InstallationProgress {
+        step: "cleanup".to_string(),
+        progress: 80,
+        message: "Cleaning up configuration and data...".to_string(),
+        completed: false,
+        error: None,
+    });
+
+    if !options.preserve_data {
+        remove_dir_if_exists(&data_dir(&options.install_path))
+            .and_then(|_| remove_dir_if_exists(&log_dir(&options.install_path)))
+            .map_err(|e| fail("cleanup", 80, "Failed to remove agent data", e))?;
+    }
+    if !options.preserve_config {
+        let config_dir = config_dir(&options.install_path);
+        remove_file_if_exists(&config_dir.join("config.toml"))
+            .and_then(|_| remove_file_if_exists(&config_dir.join(EXPORTED_TEMPLATE)))
+            .map_err(|e| fail("cleanup", 80, "Failed to remove configuration", e))?;
+        // On Windows the config lives in the install directory, which may still hold kept data
+        remove_dir_if_empty(&config_dir);
+    }
+    remove_dir_if_empty(Path::new(&options.install_path));
+
+    // Step 4: Complete
+    report(InstallationProgress {
+        step: "complete".to_string(),
+        progress: 100,
+        message: "SecureWatch Agent was uninstalled".to_string(),
+        completed: true,
+        error: None,
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn perform_upgrade(
+    config: InstallationConfig,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let window = app_handle.get_webview_window("main").unwrap();
+    run_upgrade(&config, &|progress| {
+        let _ = window.emit("installation_progress", progress);
+    })
+    .await
+}
+
+async fn run_upgrade(config: &InstallationConfig, report: ProgressReporter<'_>) -> Result<(), String> {
+    let fail = |step: &str, progress: u32, message: &str, e: String| {
+        report(InstallationProgress {
+            step: step.to_string(),
+            progress,
+            message: message.to_string(),
+            completed: false,
+            error: Some(e.clone()),
+        });
+        e
+    };
+
+    // Step 1: Stop the running agent so its binary can be replaced
+    report(InstallationProgress {
+        step: "service".to_string(),
+        progress: 15,
+        message: "Stopping SecureWatch Agent service...".to_string(),
+        completed: false,
+        error: None,
+    });
+
+    let was_installed = stop_service()
+        .await
+        .map_err(|e| fail("service", 15, "Failed to stop service", e))?;
+
+    // Step 2: Keep the files the upgrade replaces, so a failed upgrade can put the old version back
+    report(InstallationProgress {
+        step: "backup".to_string(),
+        progress: 25,
+        message: "Backing up the installed version...".to_string(),
+        completed: false,
+        error: None,
+    });
+
+    let backup = UpgradeBackup::create(config)
+        .map_err(|e| fail("backup", 25, "Failed to back up the installed version", e))?;
+
+    if let Err((step, progress, message, e)) = replace_installation(config, was_installed, report).await {
+        report(InstallationProgress {
+            step: "rollback".to_string(),
+            progress,
+            message: "Upgrade failed, restoring the previous version...".to_string(),
+            completed: false,
+            error: Some(e.clone()),
+        });
+        // The new version may have been started before the failure; its files cannot be replaced while it runs
+        let _ = stop_service().await;
+        let rollback = backup.restore();
+        let restarted = match (&rollback, was_installed) {
+            (Ok(()), true) => start_agent_service().await.map(|_| ()),
+            _ => Ok(()),
+        };
+        let e = match rollback.and(restarted) {
+            Ok(()) => format!("{} (the previous version was restored)", e),
+            Err(rollback_error) => format!("{}; restoring the previous version also failed: {}", e, rollback_error),
+        };
+        return Err(fail(step, progress, message, e));
+    }
+    backup.discard();
+
+    // Step 6: Complete
+    report(InstallationProgress {
+        step: "complete".to_string(),
+        progress: 100,
+        message: "Upgrade completed successfully!".to_string(),
+        completed: true,
+        error: None,
+    });
+
+    Ok(())
+}
+
+/// Replace the files and service registration of a stopped agent; a failure names its step for the progress report
+async fn replace_installation(
+    config: &InstallationConfig,
+    was_installed: bool,
+    report: ProgressReporter<'_>,
+) -> Result<(), (&'static str, u32, &'static str, String)> {
+    // Step 3: Replace binaries
+    report(InstallationProgress {
+        step: "copy_files".to_string(),
+        progress: 40,
+        message: "Replacing SecureWatch Agent files...".to_string(),
+        completed: false,
+        error: None,
+    });
+
+    copy_agent_files(config)
+        .await
+        .map_err(|e| ("copy_files", 40, "Failed to replace files", e))?;
+
+    // Step 4: Keep the existing configuration; only write one if it went missing
+    report(InstallationProgress {
+        step: "configure".to_string(),
+        progress: 60,
+        message: "Checking configuration...".to_string(),
+        completed: false,
+        error: None,
+    });
+
+    if !config_dir(&config.install_path).join("config.toml").exists() {
+        create_configuration(config)
+            .await
+            .map_err(|e| ("configure", 60, "Failed to create configuration", e))?;
+    }
+
+    // Step 5: Refresh the service registration and start the new version
+    if config.install_as_service || was_installed {
+        report(InstallationProgress {
+            step: "service".to_string(),
+            progress: 80,
+            message: "Updating and restarting system service...".to_string(),
+            completed: false,
+            error: None,
+        });
+
+        install_service(config)
+            .await
+            .map_err(|e| ("service", 80, "Failed to update service", e))?;
+        start_agent_service()
+            .await
+            .map_err(|e| ("service", 80, "Failed to restart service", e))?;
+    }
+
+    Ok(())
+}
+
+/// Copies of the agent binary, its configuration and service definition taken before an upgrade touches them
+struct UpgradeBackup {
+    dir: PathBuf,
+    /// (installed path, copy in `dir`); files that did not exist are not listed and are not restored
+    files: Vec<(PathBuf, PathBuf)>,
+}
+
+impl UpgradeBackup {
+    fn create(config: &InstallationConfig) -> Result<Self, String> {
+        let dir = data_dir(&config.install_path).join("upgrade-backup");
+        remove_dir_if_exists(&dir)?;
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+        let mut installed = vec![
+            Path::new(&config.install_path).join(agent_binary_name()),
+            config_dir(&config.install_path).join("config.toml"),
+        ];
+        if cfg!(target_os = "macos") {
+            installed.push(PathBuf::from(LAUNCHD_PLIST));
+        } else if cfg!(target_os = "linux") {
+            installed.push(PathBuf::from(systemd_unit_path()));
+        }
+
+        let mut files = Vec::new();
+        for (i, path) in installed.into_iter().filter(|path| path.exists()).enumerate() {
+            let copy = dir.join(format!("{}-{}", i, path.file_name().unwrap_or_default().to_string_lossy()));
+            std::fs::copy(&path, &copy)
+                .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+            files.push((path, copy));
+        }
+        Ok(Self { dir, files })
+    }
+
+    /// Put every backed-up file back; copying keeps the permissions the backup was taken with
+    fn restore(&self) -> Result<(), String> {
+        for (path, copy) in &self.files {
+            std::fs::copy(copy, path)
+                .map_err(|e| format!("Failed to restore {}: {}", path.display(), e))?;
+        }
+        if cfg!(target_os = "linux") && Path::new("/run/systemd/system").exists() {
+            run_systemctl(&["daemon-reload"])?;
+        }
+        Ok(())
+    }
+
+    fn discard(self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
 // Helper functions
+fn config_dir(install_path: &str) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        PathBuf::from(install_path)
+    } else {
+        PathBuf::from("/etc/securewatch")
+    }
+}
+
+/// Event buffer and agent state, kept apart from the binaries so upgrades leave them alone
+fn data_dir(install_path: &str) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        Path::new(install_path).join("data")
+    } else {
+        PathBuf::from("/var/lib/securewatch")
+    }
+}
+
+fn log_dir(install_path: &str) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        Path::new(install_path).join("logs")
+    } else {
+        PathBuf::from("/var/log/securewatch")
+    }
+}
+
+fn agent_binary_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "securewatch-agent.exe"
+    } else {
+        "securewatch-agent"
+    }
+}
+
 fn check_admin_privileges() -> bool {
     if cfg!(target_os = "windows") {
         // Check if running as administrator on Windows
@@ -269,6 +707,12 @@ async fn copy_agent_files(config: &InstallationConfig) -> Result<(), String> {
     // Get the path to bundled agent binary
     let binary_name = if cfg!(target_os = "windows") {
         "securewatch-agent.exe"
+    } else if cfg!(target_os = "linux") {
+        if config.architecture == "aarch64" {
+            "securewatch-agent-arm64-linux"
+        } else {
+            "securewatch-agent-x86_64-linux"
+        }
     } else if config.architecture == "aarch64" {
         "securewatch-agent-arm64-macos"
     } else {
@@ -282,7 +726,7 @@ async fn copy_agent_files(config: &InstallationConfig) -> Result<(), String> {
         .ok_or("Failed to get parent directory")?
         .join(binary_name);
 
-    let dest_path = install_path.join("securewatch-agent");
+    let dest_path = install_path.join(agent_binary_name());
 
     if src_path.exists() {
         std::fs::copy(&src_path, &dest_path)
@@ -307,11 +751,7 @@ async fn copy_agent_files(config: &InstallationConfig) -> Result<(), String> {
 }
 
 async fn create_configuration(config: &InstallationConfig) -> Result<(), String> {
-    let config_dir = if cfg!(target_os = "windows") {
-        PathBuf::from(&config.install_path)
-    } else {
-        PathBuf::from("/etc/securewatch")
-    };
+    let config_dir = config_dir(&config.install_path);
 
     std::fs::create_dir_all(&config_dir)
         .map_err(|e| format!("Failed to create config directory: {}", e))?;
@@ -339,6 +779,7 @@ paths = [
 
 [transport]
 endpoint = "{}/api/events"
+api_key = {}
 compression = "gzip"
 retry_attempts = 3
 retry_delay_ms = 1000
@@ -346,9 +787,10 @@ retry_delay_ms = 1000
 [transport.tls]
 verify_certificates = true
 ca_cert_path = ""
-
+{}
 [buffer]
 type = "persistent"
+persistence_path = '{}'
 disk_buffer_size = 100000
 high_water_mark = 0.8
 low_water_mark = 0.3
@@ -363,9 +805,21 @@ bind_address = "127.0.0.1:9090"
 "#, 
         uuid::Uuid::new_v4().to_string().replace("-", "")[..8].to_string(),
         config.agent_name,
-        config.server_endpoint
+        config.server_endpoint,
+        toml_string(&config.api_key),
+        config.proxy.config_section(),
+        data_dir(&config.install_path).join("buffer").display()
     );
 
+    let config_content = if config.agent_config.is_empty() {
+        config_content
+    } else {
+        format!(
+            "# SecureWatch Agent Configuration\n# Generated by SecureWatch Agent Installer from a template\n\n{}",
+            template::apply_overrides(&config_content, &config.agent_config)?
+        )
+    };
+
     let config_file = config_dir.join("config.toml");
     std::fs::write(config_file, config_content)
         .map_err(|e| format!("Failed to write config file: {}", e))?;
@@ -373,11 +827,86 @@ bind_address = "127.0.0.1:9090"
     Ok(())
 }
 
+/// Stops the agent service and waits for it to exit; Ok(false) when no service is registered
+async fn stop_service() -> Result<bool, String> {
+    if cfg!(target_os = "macos") {
+        if !Path::new(LAUNCHD_PLIST).exists() {
+            return Ok(false);
+        }
+        // Unloading without -w stops the daemon but keeps it enabled for the next load
+        let _ = Command::new("launchctl").args(&["unload", LAUNCHD_PLIST]).output();
+        Ok(true)
+    } else if cfg!(target_os = "windows") {
+        stop_windows_service().await
+    } else if cfg!(target_os = "linux") {
+        if !Path::new(&systemd_unit_path()).exists() {
+            return Ok(false);
+        }
+        run_systemctl(&["stop", SYSTEMD_UNIT])?;
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Stops the agent service and removes its launchd/systemd/SCM registration
+async fn remove_service() -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        if Path::new(LAUNCHD_PLIST).exists() {
+            let _ = Command::new("launchctl").args(&["unload", "-w", LAUNCHD_PLIST]).output();
+            remove_file_if_exists(Path::new(LAUNCHD_PLIST))?;
+        }
+        Ok(())
+    } else if cfg!(target_os = "windows") {
+        remove_windows_service().await
+    } else if cfg!(target_os = "linux") {
+        let unit_path = systemd_unit_path();
+        if Path::new(&unit_path).exists() {
+            // Fails harmlessly when the unit was never enabled or started
+            let _ = run_systemctl(&["disable", "--now", SYSTEMD_UNIT]);
+            remove_file_if_exists(Path::new(&unit_path))?;
+            run_systemctl(&["daemon-reload"])?;
+        }
+        Ok(())
+    } else {
+        Ok(())
+    }
+}
+
+fn remove_agent_files(install_path: &str) -> Result<(), String> {
+    remove_file_if_exists(&Path::new(install_path).join(agent_binary_name()))
+}
+
+fn remove_file_if_exists(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn remove_dir_if_exists(path: &Path) -> Result<(), String> {
+    match std::fs::remove_dir_all(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Best effort; shared directories such as /usr/local/bin are never empty and stay put
+fn remove_dir_if_empty(path: &Path) {
+    let _ = std::fs::remove_dir(path);
+}
+
 async fn install_service(config: &InstallationConfig) -> Result<(), String> {
     if cfg!(target_os = "macos") {
         install_macos_service(config).await
     } else if cfg!(target_os = "windows") {
         install_windows_service(config).await
+    } else if cfg!(target_os = "linux") {
+        install_linux_service(config).await
     } else {
         Err("Service installation not supported on this platform".to_string())
     }
@@ -395,6 +924,8 @@ async fn install_macos_service(config: &InstallationConfig) -> Result<(), String
         <string>{}/securewatch-agent</string>
         <string>--config</string>
         <string>/etc/securewatch/config.toml</string>
+        <string>--log-dir</string>
+        <string>/var/log/securewatch</string>
     </array>
     <key>RunAtLoad</key>
     <true/>
@@ -409,16 +940,231 @@ async fn install_macos_service(config: &InstallationConfig) -> Result<(), String
 </dict>
 </plist>"#, config.install_path);
 
-    let plist_path = "/Library/LaunchDaemons/com.securewatch.agent.plist";
-    std::fs::write(plist_path, plist_content)
+    std::fs::write(LAUNCHD_PLIST, plist_content)
         .map_err(|e| format!("Failed to write LaunchDaemon plist: {}", e))?;
 
     Ok(())
 }
 
+async fn install_linux_service(config: &InstallationConfig) -> Result<(), String> {
+    if !Path::new("/run/systemd/system").exists() {
+        return Err("systemd is not running on this host".to_string());
+    }
+
+    // TimeoutStopSec leaves room for the agent's shutdown drain (15s by default)
+    let unit_content = format!(r#"[Unit]
+Description=Collects security events and forwards them to the SecureWatch SIEM
+Documentation=https://github.com/itrimble/SecureWatch
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=simple
+ExecStart="{}/securewatch-agent" --config /etc/securewatch/config.toml --log-dir /var/log/securewatch
+ExecReload=/bin/kill -HUP $MAINPID
+WorkingDirectory=/etc/securewatch
+Restart=on-failure
+RestartSec=5
+TimeoutStopSec=60
+LimitNOFILE=65536
+
+[Install]
+WantedBy=multi-user.target
+"#, config.install_path.trim_end_matches('/').replace('%', "%%"));
+
+    let unit_path = systemd_unit_path();
+    std::fs::write(&unit_path, unit_content)
+        .map_err(|e| format!("Failed to write systemd unit: {}", e))?;
+
+    run_systemctl(&["daemon-reload"])?;
+    if config.start_automatically {
+        run_systemctl(&["enable", SYSTEMD_UNIT])?;
+    }
+
+    Ok(())
+}
+
+fn systemd_unit_path() -> String {
+    format!("/etc/systemd/system/{}", SYSTEMD_UNIT)
+}
+
+fn run_systemctl(args: &[&str]) -> Result<(), String> {
+    let output = Command::new("systemctl")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run systemctl: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("systemctl {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+#[cfg(windows)]
+async fn install_windows_service(config: &InstallationConfig) -> Result<(), String> {
+    use std::ffi::OsString;
+    use std::time::Duration;
+    use windows_service::service::{
+        ServiceAccess, ServiceAction, ServiceActionType, ServiceErrorControl, ServiceFailureActions,
+        ServiceFailureResetPeriod, ServiceInfo, ServiceStartType, ServiceType,
+    };
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+    let install_path = PathBuf::from(&config.install_path);
+    let service_info = ServiceInfo {
+        name: OsString::from(WINDOWS_SERVICE_NAME),
+        display_name: OsString::from("SecureWatch Agent"),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: if config.start_automatically {
+            ServiceStartType::AutoStart
+        } else {
+            ServiceStartType::OnDemand
+        },
+        error_control: ServiceErrorControl::Normal,
+        executable_path: install_path.join(agent_binary_name()),
+        // The service starts in System32, so every path the agent uses must be absolute
+        launch_arguments: vec![
+            OsString::from("--service"),
+            OsString::from("--config"),
+            install_path.join("config.toml").into_os_string(),
+            OsString::from("--log-dir"),
+            log_dir(&config.install_path).into_os_string(),
+        ],
+        dependencies: vec![],
+        account_name: None, // LocalSystem, needed to read the Security event log
+        account_password: None,
+    };
+
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )
+    .map_err(|e| format!("Failed to connect to the Service Control Manager: {}", e))?;
+
+    // Re-running the installer updates the existing registration instead of failing
+    let access = ServiceAccess::QUERY_CONFIG | ServiceAccess::CHANGE_CONFIG | ServiceAccess::START;
+    let service = match manager.open_service(WINDOWS_SERVICE_NAME, access) {
+        Ok(service) => {
+            service
+                .change_config(&service_info)
+                .map_err(|e| format!("Failed to update service configuration: {}", e))?;
+            service
+        }
+        Err(_) => manager
+            .create_service(&service_info, access)
+            .map_err(|e| format!("Failed to create service: {}", e))?,
+    };
+
+    service
+        .set_description("Collects security events and forwards them to the SecureWatch SIEM")
+        .map_err(|e| format!("Failed to set service description: {}", e))?;
+
+    // Restart after 5s, then 30s, then every 2 minutes; the failure count resets after a clean day
+    let restart = |secs| ServiceAction {
+        action_type: ServiceActionType::Restart,
+        delay: Duration::from_secs(secs),
+    };
+    service
+        .update_failure_actions(ServiceFailureActions {
+            reset_period: ServiceFailureResetPeriod::After(Duration::from_secs(24 * 60 * 60)),
+            reboot_msg: None,
+            command: None,
+            actions: Some(vec![restart(5), restart(30), restart(120)]),
+        })
+        .map_err(|e| format!("Failed to configure service recovery actions: {}", e))?;
+    // The agent exits non-zero on fatal errors without crashing; recover from those too
+    service
+        .set_failure_actions_on_non_crash_failures(true)
+        .map_err(|e| format!("Failed to configure service recovery actions: {}", e))?;
+
+    if config.start_automatically {
+        // Wait for networking and the event log to come up before collecting
+        service
+            .set_delayed_auto_start(true)
+            .map_err(|e| format!("Failed to enable delayed auto-start: {}", e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
 async fn install_windows_service(_config: &InstallationConfig) -> Result<(), String> {
-    // Windows service installation would use sc.exe or NSSM
-    // For now, return success - full implementation would require service wrapper
+    Err("Windows service installation requires a Windows host".to_string())
+}
+
+#[cfg(windows)]
+fn open_windows_service(
+    access: windows_service::service::ServiceAccess,
+) -> Result<Option<windows_service::service::Service>, String> {
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+    const ERROR_SERVICE_DOES_NOT_EXIST: i32 = 1060;
+
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .map_err(|e| format!("Failed to connect to the Service Control Manager: {}", e))?;
+    match manager.open_service(WINDOWS_SERVICE_NAME, access) {
+        Ok(service) => Ok(Some(service)),
+        Err(windows_service::Error::Winapi(e)) if e.raw_os_error() == Some(ERROR_SERVICE_DOES_NOT_EXIST) => Ok(None),
+        Err(e) => Err(format!("Failed to open service: {}", e)),
+    }
+}
+
+/// Asks the service to stop and waits for the agent's shutdown drain to finish
+#[cfg(windows)]
+async fn wait_for_windows_service_stop(service: &windows_service::service::Service) -> Result<(), String> {
+    use windows_service::service::ServiceState;
+
+    let status = service
+        .query_status()
+        .map_err(|e| format!("Failed to query service status: {}", e))?;
+    if status.current_state == ServiceState::Stopped {
+        return Ok(());
+    }
+    if status.current_state != ServiceState::StopPending {
+        service.stop().map_err(|e| format!("Failed to stop service: {}", e))?;
+    }
+
+    for _ in 0..120 {
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        let status = service
+            .query_status()
+            .map_err(|e| format!("Failed to query service status: {}", e))?;
+        if status.current_state == ServiceState::Stopped {
+            return Ok(());
+        }
+    }
+    Err("Timed out waiting for the service to stop".to_string())
+}
+
+#[cfg(windows)]
+async fn stop_windows_service() -> Result<bool, String> {
+    use windows_service::service::ServiceAccess;
+
+    match open_windows_service(ServiceAccess::QUERY_STATUS | ServiceAccess::STOP)? {
+        Some(service) => wait_for_windows_service_stop(&service).await.map(|_| true),
+        None => Ok(false),
+    }
+}
+
+#[cfg(windows)]
+async fn remove_windows_service() -> Result<(), String> {
+    use windows_service::service::ServiceAccess;
+
+    let Some(service) = open_windows_service(ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE)? else {
+        return Ok(());
+    };
+    wait_for_windows_service_stop(&service).await?;
+    service.delete().map_err(|e| format!("Failed to delete service: {}", e))
+}
+
+#[cfg(not(windows))]
+async fn stop_windows_service() -> Result<bool, String> {
+    Ok(false)
+}
+
+#[cfg(not(windows))]
+async fn remove_windows_service() -> Result<(), String> {
     Ok(())
 }
 
@@ -430,6 +1176,11 @@ async fn create_desktop_shortcut(_config: &InstallationConfig) -> Result<(), Str
 fn main() {
     tracing_subscriber::fmt::init();
 
+    // Fleet deployments run the same installation steps without the wizard
+    if let Some(cli) = silent::parse_args() {
+        std::process::exit(silent::run(cli));
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
@@ -438,7 +1189,12 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             get_system_info,
             validate_install_path,
+            test_server_connection,
+            load_install_template,
+            export_install_template,
             perform_installation,
+            perform_uninstall,
+            perform_upgrade,
             start_agent_service
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,411 @@
+// Server connectivity pre-flight check
+// Before installing, the wizard and silent mode connect to the server the agent will ship to: a TLS handshake that
+// reports the server's certificates and whether they are trusted, then the authenticated health request the agent
+// itself makes on startup. Without it an install with a wrong endpoint, an untrusted certificate or a bad API key
+// succeeds and the agent fails to connect afterwards. With a proxy configured, both go through it the way the agent's
+// requests will.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use serde::Serialize;
+use base64::Engine;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+use super::ProxySettings;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CertificateDetails {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: String,
+    pub not_after: String,
+    /// Negative once expired
+    pub days_until_expiry: i64,
+    pub dns_names: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TlsDetails {
+    pub protocol_version: String,
+    /// Chains to a bundled or system root and matches the host name
+    pub trusted: bool,
+    pub verification_error: Option<String>,
+    /// Leaf first, as the server sent them
+    pub certificates: Vec<CertificateDetails>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ConnectionTestResult {
+    pub endpoint: String,
+    /// A TCP connection was established, to the proxy when one is used
+    pub reachable: bool,
+    /// Proxy the test went through, without credentials
+    pub proxy: Option<String>,
+    /// None for http endpoints or when the connection failed
+    pub tls: Option<TlsDetails>,
+    /// None when the health request was not made
+    pub authenticated: Option<bool>,
+    pub status_code: Option<u16>,
+    pub latency_ms: u64,
+    pub success: bool,
+    pub errors: Vec<String>,
+}
+
+impl ConnectionTestResult {
+    /// One line for progress output and error dialogs
+    pub fn summary(&self) -> String {
+        match self.success {
+            true => format!("Connected to {} in {}ms", self.endpoint, self.latency_ms),
+            false => format!("Cannot use {}: {}", self.endpoint, self.errors.join("; ")),
+        }
+    }
+}
+
+/// Check that an agent configured with `endpoint` and `api_key` will be able to ship events
+pub async fn test_server_connection(endpoint: &str, api_key: &str, proxy: &ProxySettings) -> ConnectionTestResult {
+    let started = Instant::now();
+    let mut result = ConnectionTestResult {
+        endpoint: endpoint.trim().to_string(),
+        reachable: false,
+        proxy: None,
+        tls: None,
+        authenticated: None,
+        status_code: None,
+        latency_ms: 0,
+        success: false,
+        errors: Vec::new(),
+    };
+    check(&mut result, api_key, proxy).await;
+    result.latency_ms = started.elapsed().as_millis() as u64;
+    result.success = result.errors.is_empty() && result.authenticated == Some(true);
+    result
+}
+
+async fn check(result: &mut ConnectionTestResult, api_key: &str, proxy: &ProxySettings) {
+    let url = match reqwest::Url::parse(&result.endpoint) {
+        Ok(url) if matches!(url.scheme(), "https" | "http") && url.host_str().is_some() => url,
+        _ => {
+            result.errors.push(format!("'{}' is not an http:// or https:// URL", result.endpoint));
+            return;
+        }
+    };
+    // IPv6 hosts come bracketed
+    let host = url.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']').to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let proxy_url = match proxy.url.trim() {
+        "" => None,
+        _ if proxy.bypasses(&host) => None,
+        proxy_url => match reqwest::Url::parse(proxy_url) {
+            Ok(parsed) if parsed.host_str().is_some() => Some(parsed),
+            _ => {
+                result.errors.push(format!("'{}' is not a valid proxy URL", proxy_url));
+                return;
+            }
+        },
+    };
+    if let Some(proxy_url) = &proxy_url {
+        let mut display = proxy_url.clone();
+        let _ = display.set_username("");
+        let _ = display.set_password(None);
+        result.proxy = Some(display.to_string().trim_end_matches('/').to_string());
+    }
+
+    // Through a proxy the connection is made to the proxy; only HTTP proxies can tunnel to the server for the TLS
+    // inspection, with SOCKS and HTTPS proxies the health request below still shows whether TLS works
+    let (connect_host, connect_port) = match &proxy_url {
+        Some(proxy_url) => (
+            proxy_url.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']').to_string(),
+            proxy_url.port_or_known_default().unwrap_or(1080),
+        ),
+        None => (host.clone(), port),
+    };
+    let stream = match tokio::time::timeout(TIMEOUT, TcpStream::connect((connect_host.as_str(), connect_port))).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => {
+            result.errors.push(format!("Cannot connect to {}:{}: {}", connect_host, connect_port, e));
+            return;
+        }
+        Err(_) => {
+            result.errors.push(format!("Connecting to {}:{} timed out after {}s", connect_host, connect_port, TIMEOUT.as_secs()));
+            return;
+        }
+    };
+    result.reachable = true;
+
+    let tunnels = match &proxy_url {
+        Some(proxy_url) => proxy_url.scheme() == "http",
+        None => true,
+    };
+    if url.scheme() == "https" && tunnels {
+        let stream = match &proxy_url {
+            Some(_) => match connect_tunnel(stream, url.host_str().unwrap_or_default(), port, proxy).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    result.errors.push(e);
+                    return;
+                }
+            },
+            None => stream,
+        };
+        match tls_handshake(stream, &host).await {
+            Ok(tls) => {
+                let trusted = tls.trusted;
+                if let Some(e) = &tls.verification_error {
+                    result.errors.push(format!("The server certificate is not trusted: {}", e));
+                }
+                result.tls = Some(tls);
+                // The agent would refuse the connection, so the API key can't be checked either
+                if !trusted {
+                    return;
+                }
+            }
+            Err(e) => {
+                result.errors.push(format!("TLS handshake with {} failed: {}", host, e));
+                return;
+            }
+        }
+    } else {
+        drop(stream);
+    }
+
+    if api_key.trim().is_empty() {
+        result.errors.push("No API key given".to_string());
+        return;
+    }
+    // The same request the agent's connection test makes
+    let health_url = format!("{}/health", result.endpoint.trim_end_matches('/'));
+    let mut builder = reqwest::Client::builder().timeout(TIMEOUT);
+    if let Some(proxy_url) = &proxy_url {
+        match reqwest::Proxy::all(proxy_url.as_str()) {
+            Ok(mut client_proxy) => {
+                if !proxy.username.is_empty() {
+                    client_proxy = client_proxy.basic_auth(&proxy.username, &proxy.password);
+                }
+                builder = builder.proxy(client_proxy);
+            }
+            Err(e) => {
+                result.errors.push(format!("Invalid proxy URL: {}", e));
+                return;
+            }
+        }
+    } else {
+        builder = builder.no_proxy();
+    }
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            result.errors.push(format!("Failed to create HTTP client: {}", e));
+            return;
+        }
+    };
+    let response = client
+        .post(&health_url)
+        .bearer_auth(api_key.trim())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(r#"{"test":true}"#)
+        .send()
+        .await;
+    match response {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            result.status_code = Some(status);
+            match status {
+                200..=299 => result.authenticated = Some(true),
+                401 | 403 => {
+                    result.authenticated = Some(false);
+                    result.errors.push(format!("The server rejected the API key (HTTP {})", status));
+                }
+                407 => {
+                    let challenges = response.headers().get_all(reqwest::header::PROXY_AUTHENTICATE).iter()
+                        .filter_map(|value| value.to_str().ok())
+                        .map(str::to_string)
+                        .collect::<Vec<_>>();
+                    result.errors.push(proxy_auth_error(&challenges));
+                }
+                _ => result.errors.push(format!("{} returned HTTP {}", health_url, status)),
+            }
+        }
+        Err(e) => result.errors.push(format!("Health request to {} failed: {}", health_url, e)),
+    }
+}
+
+/// Ask an HTTP proxy for a tunnel to `host:port`, as the agent does for HTTPS endpoints
+async fn connect_tunnel(mut stream: TcpStream, host: &str, port: u16, proxy: &ProxySettings) -> Result<TcpStream, String> {
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if !proxy.username.is_empty() {
+        let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", proxy.username, proxy.password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+
+    let exchange = async {
+        stream.write_all(request.as_bytes()).await?;
+        let mut response = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !response.windows(4).any(|w| w == b"\r\n\r\n") && response.len() < 16 * 1024 {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            response.extend_from_slice(&buf[..n]);
+        }
+        Ok::<_, std::io::Error>(response)
+    };
+    let response = tokio::time::timeout(TIMEOUT, exchange)
+        .await
+        .map_err(|_| format!("The proxy did not answer within {}s", TIMEOUT.as_secs()))?
+        .map_err(|e| format!("Proxy tunnel to {}:{} failed: {}", host, port, e))?;
+    let response = String::from_utf8_lossy(&response).to_string();
+    let status_line = response.lines().next().unwrap_or_default().to_string();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(stream),
+        Some("407") => {
+            let challenges = response.lines()
+                .filter_map(|line| line.split_once(':'))
+                .filter(|(name, _)| name.trim().eq_ignore_ascii_case("proxy-authenticate"))
+                .map(|(_, value)| value.trim().to_string())
+                .collect::<Vec<_>>();
+            Err(proxy_auth_error(&challenges))
+        }
+        _ => Err(format!("The proxy refused a tunnel to {}:{}: {}", host, port, status_line)),
+    }
+}
+
+/// A 407 explained from the proxy's challenges; the agent only answers Basic, so NTLM and Negotiate (Kerberos)
+/// proxies need a local relay
+fn proxy_auth_error(challenges: &[String]) -> String {
+    let schemes: Vec<String> = challenges
+        .iter()
+        .flat_map(|challenge| challenge.split(','))
+        .filter_map(|part| part.split_whitespace().next().filter(|scheme| !scheme.contains('=')))
+        .map(|scheme| scheme.to_ascii_lowercase())
+        .collect();
+    if !schemes.is_empty() && !schemes.iter().any(|scheme| scheme == "basic") {
+        format!(
+            "The proxy requires {} authentication, which the agent does not support; use a local authenticating relay \
+             such as Px or Cntlm as the proxy (HTTP 407)",
+            schemes.join("/"),
+        )
+    } else {
+        "The proxy rejected the proxy credentials (HTTP 407)".to_string()
+    }
+}
+
+/// Accepts any certificate so its details can be shown, remembering what the real verifier said about it
+#[derive(Debug)]
+struct RecordingVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    outcome: Mutex<Option<Result<(), rustls::Error>>>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let outcome = self.inner
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+            .map(|_| ());
+        *self.outcome.lock().unwrap_or_else(|e| e.into_inner()) = Some(outcome);
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+async fn tls_handshake(stream: TcpStream, host: &str) -> Result<TlsDetails, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+    // Internal CAs are usually installed in the system store, which the agent trusts as well
+    roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+    let inner = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| e.to_string())?;
+    let verifier = Arc::new(RecordingVerifier { inner, outcome: Mutex::new(None) });
+    let config = ClientConfig::builder_with_provider(provider as Arc<CryptoProvider>)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier.clone())
+        .with_no_client_auth();
+
+    let server_name = ServerName::try_from(host.to_string()).map_err(|e| format!("invalid server name: {}", e))?;
+    let tls = tokio::time::timeout(TIMEOUT, TlsConnector::from(Arc::new(config)).connect(server_name, stream))
+        .await
+        .map_err(|_| format!("timed out after {}s", TIMEOUT.as_secs()))?
+        .map_err(|e| e.to_string())?;
+
+    let (_, connection) = tls.get_ref();
+    let certificates = connection.peer_certificates()
+        .map(|chain| chain.iter().filter_map(certificate_details).collect())
+        .unwrap_or_default();
+    let verification_error = match verifier.outcome.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        Some(Ok(())) => None,
+        Some(Err(e)) => Some(e.to_string()),
+        None => Some("the server presented no certificate".to_string()),
+    };
+    Ok(TlsDetails {
+        protocol_version: connection.protocol_version().map(|version| format!("{:?}", version)).unwrap_or_default(),
+        trusted: verification_error.is_none(),
+        verification_error,
+        certificates,
+    })
+}
+
+fn certificate_details(der: &CertificateDer<'_>) -> Option<CertificateDetails> {
+    let (_, certificate) = X509Certificate::from_der(der.as_ref()).ok()?;
+    let validity = certificate.validity();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default();
+    let dns_names = certificate.subject_alternative_name().ok().flatten()
+        .map(|san| san.value.general_names.iter()
+            .filter_map(|name| match name {
+                GeneralName::DNSName(dns) => Some(dns.to_string()),
+                _ => None,
+            })
+            .collect())
+        .unwrap_or_default();
+    Some(CertificateDetails {
+        subject: certificate.subject().to_string(),
+        issuer: certificate.issuer().to_string(),
+        not_before: validity.not_before.to_rfc2822().unwrap_or_default(),
+        not_after: validity.not_after.to_rfc2822().unwrap_or_default(),
+        days_until_expiry: (validity.not_after.timestamp() - now).div_euclid(86_400),
+        dns_names,
+    })
+}
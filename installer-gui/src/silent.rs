@@ -0,0 +1,314 @@
+// Headless installer mode for fleet deployment (Ansible, Intune, SCCM)
+// Runs the wizard's installation, upgrade and uninstall steps from the command line; progress goes to
+// stdout and optionally a log file, since release Windows builds have no console attached
+
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use clap::Parser;
+
+use super::{
+    check_admin_privileges, preflight, template, run_installation, run_uninstall, run_upgrade, start_agent_service,
+    InstallationConfig, InstallationProgress, UninstallOptions,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "securewatch-installer", version, about = "SecureWatch Agent installer; opens the installation wizard unless --silent is given")]
+pub struct Cli {
+    /// Install without the GUI
+    #[arg(long)]
+    silent: bool,
+
+    /// Remove the agent instead of installing it
+    #[arg(long, requires = "silent", conflicts_with = "upgrade")]
+    uninstall: bool,
+
+    /// Replace the binaries of an existing installation, keeping its configuration
+    #[arg(long, requires = "silent")]
+    upgrade: bool,
+
+    /// Installation settings as JSON, the same fields the wizard submits; "-" reads stdin
+    #[arg(long, value_name = "FILE")]
+    config_json: Option<PathBuf>,
+
+    /// Fleet template with installation settings and agent config overrides; defaults to the managed template
+    /// if one was pushed to this host
+    #[arg(long, value_name = "FILE", conflicts_with = "config_json")]
+    template: Option<PathBuf>,
+
+    /// After installing, save the settings used as a template for other hosts
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["uninstall", "upgrade"])]
+    export_template: Option<PathBuf>,
+
+    /// Overrides install_path
+    #[arg(long)]
+    install_path: Option<String>,
+
+    /// Overrides server_endpoint
+    #[arg(long)]
+    server_endpoint: Option<String>,
+
+    /// Overrides api_key
+    #[arg(long)]
+    api_key: Option<String>,
+
+    /// Proxy the agent reaches the server through: http://, https://, socks5:// or socks5h://
+    #[arg(long, value_name = "URL")]
+    proxy_url: Option<String>,
+
+    /// Basic credentials for the proxy
+    #[arg(long, requires = "proxy_url")]
+    proxy_username: Option<String>,
+
+    #[arg(long, requires = "proxy_username")]
+    proxy_password: Option<String>,
+
+    /// Hosts that bypass the proxy, comma-separated
+    #[arg(long, value_delimiter = ',', value_name = "HOSTS")]
+    no_proxy: Option<Vec<String>>,
+
+    /// Install even if the server can't be reached or rejects the API key
+    #[arg(long)]
+    skip_connection_check: bool,
+
+    /// Overrides agent_name
+    #[arg(long)]
+    agent_name: Option<String>,
+
+    /// Don't register the agent as a system service
+    #[arg(long)]
+    no_service: bool,
+
+    /// Register the service without starting it now or at boot
+    #[arg(long)]
+    no_autostart: bool,
+
+    /// Keep config.toml when uninstalling
+    #[arg(long, requires = "uninstall")]
+    keep_config: bool,
+
+    /// Keep the event buffer and logs when uninstalling
+    #[arg(long, requires = "uninstall")]
+    keep_data: bool,
+
+    /// Also append progress to this file
+    #[arg(long, value_name = "FILE")]
+    log_file: Option<PathBuf>,
+
+    /// Print progress as JSON lines, one installation_progress update per line
+    #[arg(long)]
+    json: bool,
+}
+
+/// Command line for silent mode; None starts the wizard
+pub fn parse_args() -> Option<Cli> {
+    match Cli::try_parse() {
+        Ok(cli) if cli.silent => Some(cli),
+        Ok(_) => None,
+        Err(e) => {
+            // Finder and some launchers pass their own arguments; only --silent runs are ours to reject
+            let silent = std::env::args().any(|arg| arg == "--silent");
+            if silent || matches!(e.kind(), clap::error::ErrorKind::DisplayHelp | clap::error::ErrorKind::DisplayVersion) {
+                e.exit();
+            }
+            None
+        }
+    }
+}
+
+impl Cli {
+    /// Template to install from; only installs look for the managed one
+    fn template_path(&self) -> Option<PathBuf> {
+        match &self.template {
+            Some(path) => Some(path.clone()),
+            None if self.config_json.is_none() && !self.uninstall && !self.upgrade => template::discover(),
+            None => None,
+        }
+    }
+
+    fn installation_config(&self) -> Result<InstallationConfig, String> {
+        if let Some(path) = self.template_path() {
+            return Ok(self.with_overrides(template::load(&path)?));
+        }
+        let config = match &self.config_json {
+            Some(path) => {
+                let mut json = String::new();
+                if path.as_os_str() == "-" {
+                    std::io::stdin()
+                        .read_to_string(&mut json)
+                        .map_err(|e| format!("Failed to read settings from stdin: {}", e))?;
+                } else {
+                    json = std::fs::read_to_string(path)
+                        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                }
+                serde_json::from_str(&json).map_err(|e| format!("Invalid installation settings: {}", e))?
+            }
+            None => InstallationConfig::default(),
+        };
+        Ok(self.with_overrides(config))
+    }
+
+    /// Flags win over the settings file or template
+    fn with_overrides(&self, mut config: InstallationConfig) -> InstallationConfig {
+        if let Some(install_path) = &self.install_path {
+            config.install_path = install_path.clone();
+        }
+        if let Some(server_endpoint) = &self.server_endpoint {
+            config.server_endpoint = server_endpoint.clone();
+        }
+        if let Some(api_key) = &self.api_key {
+            config.api_key = api_key.clone();
+        }
+        if let Some(proxy_url) = &self.proxy_url {
+            config.proxy.url = proxy_url.clone();
+        }
+        if let Some(username) = &self.proxy_username {
+            config.proxy.username = username.clone();
+            config.proxy.password = self.proxy_password.clone().unwrap_or_default();
+        }
+        if let Some(no_proxy) = &self.no_proxy {
+            config.proxy.no_proxy = no_proxy.clone();
+        }
+        if let Some(agent_name) = &self.agent_name {
+            config.agent_name = agent_name.clone();
+        }
+        if self.no_service {
+            config.install_as_service = false;
+        }
+        if self.no_autostart {
+            config.start_automatically = false;
+        }
+        // The wizard never offers a shortcut on headless hosts
+        config.create_desktop_shortcut = false;
+        config
+    }
+}
+
+/// Runs the requested operation and returns the process exit code
+pub fn run(cli: Cli) -> i32 {
+    let log_file = match &cli.log_file {
+        Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                eprintln!("Failed to open log file {}: {}", path.display(), e);
+                return 1;
+            }
+        },
+        None => None,
+    };
+    let log_file = Mutex::new(log_file);
+    let json = cli.json;
+    let report = move |progress: InstallationProgress| {
+        let line = if json {
+            serde_json::to_string(&progress).unwrap_or_default()
+        } else {
+            match &progress.error {
+                Some(error) => format!("[{:>3}%] {}: {}", progress.progress, progress.message, error),
+                None => format!("[{:>3}%] {}", progress.progress, progress.message),
+            }
+        };
+        println!("{}", line);
+        if let Some(file) = log_file.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            let _ = writeln!(file, "{}", line);
+        }
+    };
+
+    if let Some(path) = cli.template_path() {
+        report(InstallationProgress {
+            step: "template".to_string(),
+            progress: 0,
+            message: format!("Using installation template {}", path.display()),
+            completed: false,
+            error: None,
+        });
+    }
+    let result = match cli.installation_config() {
+        Ok(config) => execute(&cli, &config, &report),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            report(InstallationProgress {
+                step: "failed".to_string(),
+                progress: 0,
+                message: "Installation failed".to_string(),
+                completed: false,
+                error: Some(e),
+            });
+            1
+        }
+    }
+}
+
+fn execute(
+    cli: &Cli,
+    config: &InstallationConfig,
+    report: &(dyn Fn(InstallationProgress) + Send + Sync),
+) -> Result<(), String> {
+    if !check_admin_privileges() {
+        return Err("The installer must run as root or Administrator".to_string());
+    }
+
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to start runtime: {}", e))?;
+    runtime.block_on(async {
+        if cli.uninstall {
+            let options = UninstallOptions {
+                install_path: config.install_path.clone(),
+                preserve_config: cli.keep_config,
+                preserve_data: cli.keep_data,
+            };
+            run_uninstall(&options, report).await
+        } else if cli.upgrade {
+            run_upgrade(config, report).await
+        } else {
+            if !cli.skip_connection_check {
+                check_server_connection(config, report).await?;
+            }
+            run_installation(config, report).await?;
+            // The wizard starts the agent from its final page
+            if config.install_as_service && config.start_automatically {
+                start_agent_service().await?;
+            }
+            if let Some(path) = &cli.export_template {
+                template::export(config, path)?;
+                report(InstallationProgress {
+                    step: "export".to_string(),
+                    progress: 100,
+                    message: format!("Installation settings exported to {}", path.display()),
+                    completed: true,
+                    error: None,
+                });
+            }
+            Ok(())
+        }
+    })
+}
+
+/// The wizard's Test Connection step; a failure stops the installation before anything is written
+async fn check_server_connection(
+    config: &InstallationConfig,
+    report: &(dyn Fn(InstallationProgress) + Send + Sync),
+) -> Result<(), String> {
+    report(InstallationProgress {
+        step: "preflight".to_string(),
+        progress: 0,
+        message: format!("Checking connection to {}", config.server_endpoint),
+        completed: false,
+        error: None,
+    });
+    let result = preflight::test_server_connection(&config.server_endpoint, &config.api_key, &config.proxy).await;
+    if !result.success {
+        return Err(format!("{} (use --skip-connection-check to install anyway)", result.summary()));
+    }
+    report(InstallationProgress {
+        step: "preflight".to_string(),
+        progress: 0,
+        message: result.summary(),
+        completed: false,
+        error: None,
+    });
+    Ok(())
+}
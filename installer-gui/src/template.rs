@@ -0,0 +1,97 @@
+// Fleet installation templates
+// A template holds the wizard's settings plus an [agent_config] table merged over the generated config.toml, so
+// hundreds of endpoints get the same settings without anyone clicking through the wizard. MDM tools push one to a
+// well-known path that both the wizard and silent mode pick up, and the settings an install used can be exported
+// as a template for the next ones.
+
+use std::path::{Path, PathBuf};
+
+use super::InstallationConfig;
+
+/// Overrides the managed template path
+pub const TEMPLATE_ENV: &str = "SECUREWATCH_INSTALL_TEMPLATE";
+
+/// Where MDM profiles (Intune, Jamf, Ansible) drop the template for this host
+pub fn managed_template_path() -> PathBuf {
+    if cfg!(target_os = "windows") {
+        let program_data = std::env::var("ProgramData").unwrap_or_else(|_| "C:\\ProgramData".to_string());
+        Path::new(&program_data).join("SecureWatch").join("install-template.toml")
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/SecureWatch/install-template.toml")
+    } else {
+        PathBuf::from("/etc/securewatch/install-template.toml")
+    }
+}
+
+/// The template named by SECUREWATCH_INSTALL_TEMPLATE, else the managed one if it exists
+pub fn discover() -> Option<PathBuf> {
+    match std::env::var_os(TEMPLATE_ENV) {
+        Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => Some(managed_template_path()).filter(|path| path.is_file()),
+    }
+}
+
+/// Installation settings from a TOML template, or JSON when the file ends in .json
+pub fn load(path: &Path) -> Result<InstallationConfig, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read template {}: {}", path.display(), e))?;
+    let mut config: InstallationConfig = if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        serde_json::from_str(&content).map_err(|e| format!("Invalid template {}: {}", path.display(), e))?
+    } else {
+        toml::from_str(&content).map_err(|e| format!("Invalid template {}: {}", path.display(), e))?
+    };
+    validate_overrides(&config.agent_config).map_err(|e| format!("Invalid template {}: {}", path.display(), e))?;
+    // Templates are shared across hosts; the binary to install depends on this one
+    config.architecture = std::env::consts::ARCH.to_string();
+    Ok(config)
+}
+
+/// Write the settings an installation used as a template that `load` accepts
+pub fn export(config: &InstallationConfig, path: &Path) -> Result<(), String> {
+    let mut table = toml::Table::try_from(config).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    table.remove("architecture");
+    let content = format!(
+        "# SecureWatch Agent installation template\n# Exported by the installer; contains the API key, keep it private\n\n{}",
+        toml::to_string_pretty(&table).map_err(|e| format!("Failed to serialize settings: {}", e))?
+    );
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, content).map_err(|e| format!("Failed to write template {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict permissions on {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Settings that must stay unique per host can't come from a shared template
+fn validate_overrides(overrides: &toml::Table) -> Result<(), String> {
+    let agent_id = overrides.get("agent").and_then(|agent| agent.as_table()).and_then(|agent| agent.get("id"));
+    match agent_id {
+        Some(_) => Err("agent_config must not set agent.id; every installation generates its own".to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Merge `overrides` into the generated config: tables merge key by key, anything else replaces the generated value
+pub fn apply_overrides(config_toml: &str, overrides: &toml::Table) -> Result<String, String> {
+    let mut config: toml::Table = toml::from_str(config_toml)
+        .map_err(|e| format!("Failed to parse generated config: {}", e))?;
+    merge(&mut config, overrides);
+    toml::to_string_pretty(&config).map_err(|e| format!("Failed to serialize config: {}", e))
+}
+
+fn merge(base: &mut toml::Table, overrides: &toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => merge(base, overrides),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
@@ -23,11 +23,52 @@ interface SystemInfo {
 interface InstallConfig {
   install_path: string
   server_endpoint: string
+  api_key: string
   agent_name: string
   install_as_service: boolean
   start_automatically: boolean
   create_desktop_shortcut: boolean
   architecture: string
+  proxy: ProxySettings
+  agent_config?: Record<string, unknown>
+}
+
+interface ProxySettings {
+  url: string
+  username: string
+  password: string
+  no_proxy: string[]
+}
+
+interface LoadedTemplate {
+  path: string
+  config: InstallConfig
+}
+
+interface CertificateDetails {
+  subject: string
+  issuer: string
+  not_before: string
+  not_after: string
+  days_until_expiry: number
+  dns_names: string[]
+}
+
+interface ConnectionTestResult {
+  endpoint: string
+  reachable: boolean
+  proxy?: string
+  tls?: {
+    protocol_version: string
+    trusted: boolean
+    verification_error?: string
+    certificates: CertificateDetails[]
+  }
+  authenticated?: boolean
+  status_code?: number
+  latency_ms: number
+  success: boolean
+  errors: string[]
 }
 
 interface InstallProgress {
@@ -54,17 +95,24 @@ function App() {
   const [config, setConfig] = useState<InstallConfig>({
     install_path: '',
     server_endpoint: 'https://your-securewatch-server.com',
+    api_key: '',
     agent_name: 'SecureWatch Agent',
     install_as_service: true,
     start_automatically: true,
     create_desktop_shortcut: false,
     architecture: '',
+    proxy: { url: '', username: '', password: '', no_proxy: [] },
   })
   const [licenseAccepted, setLicenseAccepted] = useState(false)
   const [installProgress, setInstallProgress] = useState<InstallProgress | null>(null)
   const [installing, setInstalling] = useState(false)
   const [installComplete, setInstallComplete] = useState(false)
   const [installError, setInstallError] = useState<string | null>(null)
+  const [connectionTest, setConnectionTest] = useState<ConnectionTestResult | null>(null)
+  const [testingConnection, setTestingConnection] = useState(false)
+  const [templatePath, setTemplatePath] = useState('')
+  const [templateMessage, setTemplateMessage] = useState<string | null>(null)
+  const [exportMessage, setExportMessage] = useState<string | null>(null)
 
   useEffect(() => {
     // Get system information
@@ -97,6 +145,35 @@ function App() {
     }
   }, [systemInfo?.os, systemInfo?.arch])
 
+  // A template pushed by MDM pre-fills the wizard once the platform defaults are in
+  useEffect(() => {
+    if (systemInfo) {
+      loadTemplate(null)
+    }
+  }, [systemInfo?.os])
+
+  const loadTemplate = async (path: string | null) => {
+    try {
+      const template = await invoke<LoadedTemplate | null>('load_install_template', { path })
+      if (template) {
+        setConfig(prev => ({ ...prev, ...template.config }))
+        setConnectionTest(null)
+        setTemplateMessage(`Settings loaded from ${template.path}`)
+      }
+    } catch (error) {
+      setTemplateMessage(error as string)
+    }
+  }
+
+  const handleExport = async () => {
+    try {
+      const path = await invoke<string>('export_install_template', { config, path: null })
+      setExportMessage(`Settings exported to ${path}`)
+    } catch (error) {
+      setExportMessage(error as string)
+    }
+  }
+
   const handleNext = () => {
     const stepIndex = steps.findIndex(s => s.id === currentStep)
     if (stepIndex < steps.length - 1) {
@@ -111,9 +188,44 @@ function App() {
     }
   }
 
-  const handleInstall = async () => {
-    setInstalling(true)
+  const testConnection = async (): Promise<ConnectionTestResult | null> => {
+    setTestingConnection(true)
+    try {
+      const result = await invoke<ConnectionTestResult>('test_server_connection', {
+        endpoint: config.server_endpoint,
+        apiKey: config.api_key,
+        proxy: config.proxy,
+      })
+      setConnectionTest(result)
+      return result
+    } catch (error) {
+      console.error('Connection test failed:', error)
+      return null
+    } finally {
+      setTestingConnection(false)
+    }
+  }
+
+  const [showProxy, setShowProxy] = useState(false)
+
+  // A changed endpoint, key or proxy invalidates the last test
+  const updateConnectionSettings = (changes: Partial<InstallConfig>) => {
+    setConfig({ ...config, ...changes })
+    setConnectionTest(null)
+  }
+
+  const handleInstall = async (skipConnectionCheck = false) => {
     setInstallError(null)
+    if (!skipConnectionCheck) {
+      const result = connectionTest?.success ? connectionTest : await testConnection()
+      if (!result?.success) {
+        setInstallError(result
+          ? `Cannot use ${result.endpoint}: ${result.errors.join('; ')}`
+          : 'The connection test could not be run')
+        return
+      }
+    }
+    setInstalling(true)
     try {
       await invoke('perform_installation', { config })
       setCurrentStep('complete')
@@ -131,6 +243,39 @@ function App() {
     }
   }
 
+  const renderConnectionTest = (result: ConnectionTestResult) => (
+    <div className="version-info" style={{ textAlign: 'left', marginTop: '15px', fontSize: '14px' }}>
+      <div style={{ display: 'flex', alignItems: 'center', gap: '8px', color: result.success ? '#28a745' : '#dc3545' }}>
+        {result.success ? <CheckCircle size={16} /> : <AlertCircle size={16} />}
+        <strong>{result.success ? `Connected in ${result.latency_ms}ms` : 'Connection test failed'}</strong>
+      </div>
+      {result.proxy && <div>Through proxy {result.proxy}</div>}
+      {result.errors.map((error, i) => (
+        <div key={i} style={{ color: '#dc3545' }}>• {error}</div>
+      ))}
+      {result.tls && (
+        <div style={{ marginTop: '10px' }}>
+          <div><strong>TLS:</strong> {result.tls.protocol_version}, {result.tls.trusted ? 'trusted certificate' : 'untrusted certificate'}</div>
+          {result.tls.certificates.map((certificate, i) => (
+            <div key={i} style={{ marginTop: '6px', color: '#495057' }}>
+              <div><strong>{i === 0 ? 'Server' : 'Issuer'}:</strong> {certificate.subject}</div>
+              <div>Issued by {certificate.issuer}</div>
+              <div style={{ color: certificate.days_until_expiry < 30 ? '#dc3545' : undefined }}>
+                Valid until {certificate.not_after} ({certificate.days_until_expiry} days)
+              </div>
+              {certificate.dns_names.length > 0 && <div>Names: {certificate.dns_names.join(', ')}</div>}
+            </div>
+          ))}
+        </div>
+      )}
+      {result.authenticated != null && (
+        <div style={{ marginTop: '10px' }}>
+          <strong>API key:</strong> {result.authenticated ? 'accepted' : `rejected (HTTP ${result.status_code})`}
+        </div>
+      )}
+    </div>
+  )
+
   const isStepCompleted = (stepId: string) => {
     const stepIndex = steps.findIndex(s => s.id === stepId)
     const currentIndex = steps.findIndex(s => s.id === currentStep)
@@ -144,7 +289,7 @@ function App() {
       case 'license':
         return licenseAccepted
       case 'config':
-        return config.install_path && config.server_endpoint
+        return config.install_path && config.server_endpoint && config.api_key
       case 'install':
         return !installing
       default:
@@ -297,6 +442,29 @@ function App() {
               Configure the installation settings for SecureWatch Agent.
             </p>
 
+            <div className="form-group">
+              <label className="form-label">Fleet Template (optional)</label>
+              <div style={{ display: 'flex', gap: '10px' }}>
+                <input
+                  type="text"
+                  className="form-input"
+                  value={templatePath}
+                  onChange={(e) => setTemplatePath(e.target.value)}
+                  placeholder="Path to an installation template (.toml or .json)"
+                />
+                <button
+                  className="nav-button"
+                  onClick={() => loadTemplate(templatePath)}
+                  disabled={!templatePath}
+                >
+                  Load
+                </button>
+              </div>
+              {templateMessage && (
+                <div style={{ marginTop: '8px', fontSize: '14px', color: '#6c757d' }}>{templateMessage}</div>
+              )}
+            </div>
+
             <div className="form-group">
               <label className="form-label">Installation Directory</label>
               <input
@@ -314,11 +482,91 @@ function App() {
                 type="text"
                 className="form-input"
                 value={config.server_endpoint}
-                onChange={(e) => setConfig({ ...config, server_endpoint: e.target.value })}
+                onChange={(e) => updateConnectionSettings({ server_endpoint: e.target.value })}
                 placeholder="https://your-securewatch-server.com"
               />
             </div>
 
+            <div className="form-group">
+              <label className="form-label">API Key</label>
+              <input
+                type="password"
+                className="form-input"
+                value={config.api_key}
+                onChange={(e) => updateConnectionSettings({ api_key: e.target.value })}
+                placeholder="Enter the agent API key"
+              />
+            </div>
+
+            <div className="form-checkbox">
+              <input
+                type="checkbox"
+                id="use-proxy"
+                checked={showProxy || !!config.proxy.url}
+                onChange={(e) => {
+                  setShowProxy(e.target.checked)
+                  if (!e.target.checked) {
+                    updateConnectionSettings({ proxy: { url: '', username: '', password: '', no_proxy: [] } })
+                  }
+                }}
+              />
+              <label htmlFor="use-proxy">Connect through a proxy</label>
+            </div>
+
+            {(showProxy || !!config.proxy.url) && (
+              <div className="form-group">
+                <label className="form-label">Proxy URL</label>
+                <input
+                  type="text"
+                  className="form-input"
+                  value={config.proxy.url}
+                  onChange={(e) => updateConnectionSettings({ proxy: { ...config.proxy, url: e.target.value } })}
+                  placeholder="http://proxy.example.com:3128 or socks5://proxy.example.com:1080"
+                />
+                <div style={{ display: 'flex', gap: '10px', marginTop: '10px' }}>
+                  <input
+                    type="text"
+                    className="form-input"
+                    value={config.proxy.username}
+                    onChange={(e) => updateConnectionSettings({ proxy: { ...config.proxy, username: e.target.value } })}
+                    placeholder="Username (optional)"
+                  />
+                  <input
+                    type="password"
+                    className="form-input"
+                    value={config.proxy.password}
+                    onChange={(e) => updateConnectionSettings({ proxy: { ...config.proxy, password: e.target.value } })}
+                    placeholder="Password"
+                  />
+                </div>
+                <input
+                  type="text"
+                  className="form-input"
+                  style={{ marginTop: '10px' }}
+                  value={config.proxy.no_proxy.join(', ')}
+                  onChange={(e) => updateConnectionSettings({
+                    proxy: { ...config.proxy, no_proxy: e.target.value.split(',').map(host => host.trim()).filter(Boolean) },
+                  })}
+                  placeholder="Bypass for hosts, e.g. localhost, .corp.example.com"
+                />
+                <div style={{ marginTop: '8px', fontSize: '13px', color: '#6c757d' }}>
+                  Basic authentication only; for NTLM proxies use a local relay such as Px or Cntlm.
+                </div>
+              </div>
+            )}
+
+            <div className="form-group">
+              <button
+                className="nav-button"
+                onClick={() => testConnection()}
+                disabled={!config.server_endpoint || testingConnection}
+              >
+                {testingConnection && <Loader2 className="animate-spin" style={{ width: '16px', height: '16px', marginRight: '8px' }} />}
+                Test Connection
+              </button>
+              {connectionTest && renderConnectionTest(connectionTest)}
+            </div>
+
             <div className="form-group">
               <label className="form-label">Agent Name</label>
               <input
@@ -381,12 +629,14 @@ function App() {
                 <div className="version-info" style={{ textAlign: 'left', maxWidth: '400px', margin: '0 auto' }}>
                   <div><strong>Install Path:</strong> {config.install_path}</div>
                   <div><strong>Server Endpoint:</strong> {config.server_endpoint}</div>
+                  {config.proxy.url && <div><strong>Proxy:</strong> {config.proxy.url}</div>}
                   <div><strong>Agent Name:</strong> {config.agent_name}</div>
                   <div><strong>Install as Service:</strong> {config.install_as_service ? 'Yes' : 'No'}</div>
                 </div>
                 <button 
                   className="nav-button primary"
-                  onClick={handleInstall}
+                  onClick={() => handleInstall()}
+                  disabled={testingConnection}
                   style={{ marginTop: '30px', padding: '15px 30px', fontSize: '16px' }}
                 >
                   <Download style={{ width: '18px', height: '18px', marginRight: '8px' }} />
@@ -422,16 +672,23 @@ function App() {
                 </div>
                 <div className="install-message">Installation Failed</div>
                 <div className="install-details">{installError}</div>
-                <button 
-                  className="nav-button"
-                  onClick={() => {
-                    setInstallError(null)
-                    setInstalling(false)
-                  }}
-                  style={{ marginTop: '20px' }}
-                >
-                  Try Again
-                </button>
+                {connectionTest && !connectionTest.success && renderConnectionTest(connectionTest)}
+                <div style={{ display: 'flex', gap: '10px', justifyContent: 'center', marginTop: '20px' }}>
+                  <button 
+                    className="nav-button"
+                    onClick={() => {
+                      setInstallError(null)
+                      setInstalling(false)
+                    }}
+                  >
+                    Try Again
+                  </button>
+                  {connectionTest && !connectionTest.success && (
+                    <button className="nav-button" onClick={() => handleInstall(true)}>
+                      Install Anyway
+                    </button>
+                  )}
+                </div>
               </div>
             )}
           </div>
@@ -474,6 +731,18 @@ function App() {
                 Start Service Now
               </button>
             )}
+
+            <button
+              className="nav-button"
+              onClick={handleExport}
+              style={{ marginTop: '20px', marginLeft: '10px' }}
+            >
+              <Download style={{ width: '16px', height: '16px', marginRight: '8px' }} />
+              Export Settings as Template
+            </button>
+            {exportMessage && (
+              <div style={{ marginTop: '10px', fontSize: '14px', color: '#6c757d' }}>{exportMessage}</div>
+            )}
           </div>
         )
 
@@ -545,8 +814,8 @@ function App() {
             {currentStep !== 'complete' && (
               <button
                 className="nav-button primary"
-                onClick={currentStep === 'install' ? handleInstall : handleNext}
-                disabled={!canProceed() || installing}
+                onClick={currentStep === 'install' ? () => handleInstall() : handleNext}
+                disabled={!canProceed() || installing || testingConnection}
               >
                 {installing && <Loader2 className="animate-spin" style={{ width: '16px', height: '16px', marginRight: '8px' }} />}
                 {currentStep === 'install' ? 'Install' : 'Continue'}
@@ -0,0 +1,62 @@
+// Helpers for walking the serde JSON form of the KQL AST.
+// Shared by the SQL lowering, the formatter and the schema extractor, which all read the same externally tagged
+// enums and operand lists.
+
+use serde_json::Value;
+
+/// Split an externally tagged serde enum into its variant name and payload
+pub(crate) fn variant(value: &Value) -> Result<(&str, &Value), String> {
+    match value {
+        Value::String(name) => Ok((name, &Value::Null)),
+        Value::Object(map) if map.len() == 1 => {
+            let (name, body) = map.iter().next().expect("map has one entry");
+            Ok((name, body))
+        }
+        other => Err(format!("Unexpected AST node: {}", other)),
+    }
+}
+
+/// The two operands of a binary node
+pub(crate) fn pair<'a>(body: &'a Value, name: &str) -> Result<(&'a Value, &'a Value), String> {
+    match body.as_array().map(Vec::as_slice) {
+        Some([left, right]) => Ok((left, right)),
+        _ => Err(format!("'{}' needs two operands", name)),
+    }
+}
+
+/// A list node's entries; a single node is a list of one and null an empty list
+pub(crate) fn items(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Null => Vec::new(),
+        other => vec![other],
+    }
+}
+
+/// KQL spelling and binding strength of a binary operator variant
+pub(crate) fn binary_operator(name: &str) -> Option<(&'static str, u8)> {
+    Some(match name {
+        "Or" => ("or", 1),
+        "And" => ("and", 2),
+        "Equals" => ("==", 3),
+        "NotEquals" => ("!=", 3),
+        "EqualsCi" => ("=~", 3),
+        "NotEqualsCi" => ("!~", 3),
+        "Less" => ("<", 3),
+        "Greater" => (">", 3),
+        "LessOrEqual" => ("<=", 3),
+        "GreaterOrEqual" => (">=", 3),
+        "Contains" => ("contains", 3),
+        "NotContains" => ("!contains", 3),
+        "Has" => ("has", 3),
+        "NotHas" => ("!has", 3),
+        "StartsWith" => ("startswith", 3),
+        "EndsWith" => ("endswith", 3),
+        "Add" => ("+", 4),
+        "Substract" | "Subtract" => ("-", 4),
+        "Multiply" => ("*", 5),
+        "Divide" => ("/", 5),
+        "Modulo" => ("%", 5),
+        _ => return None,
+    })
+}
@@ -0,0 +1,372 @@
+// Tolerant parsing for editor autocomplete.
+// While a query is being typed it rarely parses: there is a trailing pipe, a half-typed operator or an open string.
+// The best-effort AST comes from the longest prefix of whole pipeline stages that parses, and what may come next at
+// the cursor is worked out from the tokens of the stage being typed, since the parser's own error only names the
+// last alternative it tried. Offsets are in UTF-16 code units, like the diagnostics, so editors can use them directly.
+
+use crate::diagnostics::utf16_len;
+use crate::schema::{self, QuerySchema};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Tabular operators offered after a pipe
+pub const OPERATORS: [&str; 24] = [
+    "where", "project", "project-away", "project-rename", "extend", "summarize", "sort by", "order by", "top", "take",
+    "limit", "count", "distinct", "join", "union", "mv-expand", "parse", "evaluate", "render", "as", "getschema",
+    "sample", "search", "serialize",
+];
+
+/// Scalar functions offered where an expression starts
+pub const FUNCTIONS: [&str; 18] = [
+    "ago", "now", "datetime", "bin", "isempty", "isnotempty", "isnull", "isnotnull", "tolower", "toupper", "strlen",
+    "strcat", "substring", "extract", "parse_json", "tostring", "toint", "iff",
+];
+
+/// Aggregations offered in summarize
+pub const AGGREGATES: [&str; 10] = ["count", "countif", "dcount", "dcountif", "sum", "avg", "min", "max", "make_set", "make_list"];
+
+const COMPARISONS: [&str; 18] = [
+    "==", "!=", "=~", "!~", "<", "<=", ">", ">=", "contains", "!contains", "contains_cs", "has", "!has", "startswith",
+    "endswith", "in", "!in", "between",
+];
+
+/// What kind of token the cursor is at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionContext {
+    /// The source table
+    Table,
+    /// A tabular operator after `|`
+    Operator,
+    /// The start of an expression: a column, a function or a literal
+    Column,
+    /// The right-hand side of a comparison
+    Value,
+    /// An aggregation in summarize
+    Aggregate,
+    /// A comparison or logical operator after an operand
+    Comparison,
+    /// Keywords, separators or a pipe after a complete clause
+    Keyword,
+    /// Inside a string literal; nothing to complete
+    String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Completion {
+    pub context: CompletionContext,
+    /// Operator of the pipeline stage at the cursor, in KQL spelling
+    pub operator: Option<String>,
+    /// Tokens that may come next, narrowed to those starting with `prefix`; columns come from the schema
+    pub expected: Vec<String>,
+    /// The word being typed at the cursor, which a completion replaces
+    pub prefix: String,
+    pub replace_offset: usize,
+    pub replace_length: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PartialParse {
+    /// The whole query parsed as is
+    pub complete: bool,
+    /// AST of the longest prefix of whole pipeline stages that parses
+    pub ast: Option<Value>,
+    /// UTF-16 length of the query text the AST covers
+    pub parsed_length: usize,
+    /// Tables and columns of the best-effort AST, for column completions
+    pub schema: Option<QuerySchema>,
+    pub completion: Completion,
+}
+
+/// Parse `query` as far as possible and describe what may be typed at `cursor` (UTF-16 offset).
+/// `parse` returns the serialized AST of text that parses, and `None` otherwise.
+pub fn partial_parse(query: &str, cursor: usize, parse: impl Fn(&str) -> Option<Value>) -> PartialParse {
+    let mut parsed = parse(query).map(|ast| (ast, query.len()));
+    let complete = parsed.is_some();
+    if parsed.is_none() {
+        // Drop pipeline stages from the end until what is left parses
+        for pipe in top_level_pipes(query).into_iter().rev() {
+            let prefix = query[..pipe].trim_end();
+            if let Some(ast) = parse(prefix) {
+                parsed = Some((ast, prefix.len()));
+                break;
+            }
+        }
+    }
+
+    let schema = parsed.as_ref().map(|(ast, _)| schema::introspect(ast));
+    let (ast, parsed_bytes) = parsed.unzip();
+    PartialParse {
+        complete,
+        ast,
+        parsed_length: utf16_len(&query[..parsed_bytes.unwrap_or(0)]),
+        schema,
+        completion: complete_at(query, byte_offset(query, cursor)),
+    }
+}
+
+/// Byte offsets of `|` outside strings, brackets and comments
+fn top_level_pipes(query: &str) -> Vec<usize> {
+    tokenize(query).iter().filter(|t| t.text == "|" && t.depth == 0).map(|t| t.start).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+    /// Bracket nesting at the token
+    depth: usize,
+    kind: TokenKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Word,
+    Literal,
+    /// A string literal that runs to the end of the input
+    OpenString,
+    Symbol,
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    const SYMBOLS: [&str; 18] = ["==", "!=", "=~", "!~", "<=", ">=", "<", ">", "=", "(", ")", "[", "]", "{", "}", ",", "|", ";"];
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut pos = 0;
+    while let Some(c) = text[pos..].chars().next() {
+        let rest = &text[pos..];
+        let start = pos;
+        let (len, kind) = if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        } else if rest.starts_with("//") {
+            pos += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        } else if c == '\'' || c == '"' || (c == '@' && rest[1..].starts_with(['\'', '"'])) {
+            let verbatim = c == '@';
+            let quote = if verbatim { rest[1..].chars().next().unwrap_or('"') } else { c };
+            let body = if verbatim { 2 } else { 1 };
+            let mut end = None;
+            let mut chars = rest[body..].char_indices();
+            while let Some((i, s)) = chars.next() {
+                if s == '\\' && !verbatim {
+                    chars.next();
+                } else if s == quote {
+                    end = Some(body + i + 1);
+                    break;
+                }
+            }
+            match end {
+                Some(end) => (end, TokenKind::Literal),
+                None => (rest.len(), TokenKind::OpenString),
+            }
+        } else if c.is_ascii_digit() {
+            (rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '.')).unwrap_or(rest.len()), TokenKind::Literal)
+        } else if c.is_alphabetic() || c == '_' || (c == '!' && rest[1..].starts_with(|c: char| c.is_alphabetic())) {
+            // mv-expand and !contains are single words
+            (1 + rest[1..].find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || c == '-')).unwrap_or(rest.len() - 1), TokenKind::Word)
+        } else {
+            let symbol = SYMBOLS.iter().find(|s| rest.starts_with(**s)).copied().unwrap_or(&rest[..c.len_utf8()]);
+            (symbol.len(), TokenKind::Symbol)
+        };
+        let token_text = &text[start..start + len];
+        if matches!(token_text, ")" | "]" | "}") {
+            depth = depth.saturating_sub(1);
+        }
+        tokens.push(Token { text: token_text, start, depth, kind });
+        if matches!(token_text, "(" | "[" | "{") {
+            depth += 1;
+        }
+        pos = start + len;
+    }
+    tokens
+}
+
+/// What may be typed at a byte offset, from the text before it
+fn complete_at(query: &str, cursor: usize) -> Completion {
+    let before = &query[..cursor];
+    let mut tokens = tokenize(before);
+
+    // A word touching the cursor is the prefix being typed
+    let prefix_token = tokens.last()
+        .filter(|t| t.start + t.text.len() == cursor && matches!(t.kind, TokenKind::Word | TokenKind::OpenString))
+        .cloned();
+    if prefix_token.is_some() {
+        tokens.pop();
+    }
+    let (prefix, replace_at) = match &prefix_token {
+        Some(token) if token.kind == TokenKind::Word => (token.text, token.start),
+        _ => ("", cursor),
+    };
+
+    // The stage being typed starts after the last pipe at the cursor's nesting level
+    let stage_start = tokens.iter().rposition(|t| t.text == "|" && t.depth == 0);
+    let stage: Vec<&Token> = tokens[stage_start.map_or(0, |i| i + 1)..].iter().collect();
+    let operator = stage_start.and(stage.first()).map(|t| t.text.to_ascii_lowercase());
+
+    let (context, expected): (CompletionContext, Vec<&str>) = if prefix_token.is_some_and(|t| t.kind == TokenKind::OpenString) {
+        (CompletionContext::String, Vec::new())
+    } else if stage_start.is_none() {
+        match stage.is_empty() {
+            true => (CompletionContext::Table, Vec::new()),
+            false => (CompletionContext::Keyword, vec!["|"]),
+        }
+    } else if stage.is_empty() {
+        (CompletionContext::Operator, OPERATORS.to_vec())
+    } else {
+        stage_completion(operator.as_deref().unwrap_or_default(), &stage[1..])
+    };
+
+    let lower_prefix = prefix.to_ascii_lowercase();
+    Completion {
+        context,
+        operator,
+        expected: expected.into_iter()
+            .filter(|token| token.to_ascii_lowercase().starts_with(&lower_prefix))
+            .map(str::to_string)
+            .collect(),
+        prefix: prefix.to_string(),
+        replace_offset: utf16_len(&query[..replace_at]),
+        replace_length: utf16_len(prefix),
+    }
+}
+
+/// Completion within a pipeline stage, from the tokens after its operator
+fn stage_completion(operator: &str, args: &[&Token]) -> (CompletionContext, Vec<&'static str>) {
+    let last = args.last().map(|t| t.text.to_ascii_lowercase());
+    match operator {
+        "take" | "limit" | "top" if args.is_empty() => (CompletionContext::Value, Vec::new()),
+        "take" | "limit" | "count" | "getschema" => (CompletionContext::Keyword, vec!["|"]),
+        "sort" | "order" if args.is_empty() => (CompletionContext::Keyword, vec!["by"]),
+        "top" if args.len() == 1 => (CompletionContext::Keyword, vec!["by"]),
+        "sort" | "order" | "top" => match last.as_deref() {
+            Some("by" | ",") => (CompletionContext::Column, FUNCTIONS.to_vec()),
+            Some("asc" | "desc") => (CompletionContext::Keyword, vec!["nulls first", "nulls last", ",", "|"]),
+            _ => (CompletionContext::Keyword, vec!["asc", "desc", ",", "|"]),
+        },
+        "summarize" => {
+            let by = args.iter().position(|t| t.text.eq_ignore_ascii_case("by"));
+            match (by, last.as_deref()) {
+                // Aggregations, optionally named: `Failures = count()`
+                (None, None | Some("," | "=")) => (CompletionContext::Aggregate, AGGREGATES.to_vec()),
+                (None, _) if args.last().is_some_and(|t| t.depth > 0) => expression_completion(args),
+                (None, _) => (CompletionContext::Keyword, vec!["by", ",", "|"]),
+                (Some(by), _) => expression_completion(&args[by + 1..]),
+            }
+        }
+        "where" | "filter" | "project" | "extend" | "distinct" | "project-away" | "mv-expand" => expression_completion(args),
+        "join" if args.is_empty() => (CompletionContext::Keyword, vec!["kind=", "("]),
+        _ => (CompletionContext::Keyword, Vec::new()),
+    }
+}
+
+/// Completion inside an expression list: operands, comparisons, logical operators and separators
+fn expression_completion(args: &[&Token]) -> (CompletionContext, Vec<&'static str>) {
+    let mut expect_operand = true;
+    let mut compared = false;
+    let mut after_comparison = false;
+    for token in args {
+        let text = token.text.to_ascii_lowercase();
+        after_comparison = false;
+        if matches!(text.as_str(), "(" | "," | "=" | "and" | "or" | "not") {
+            expect_operand = true;
+            compared = false;
+        } else if COMPARISONS.contains(&text.as_str()) {
+            expect_operand = true;
+            compared = true;
+            after_comparison = true;
+        } else {
+            // Words, literals and closing brackets complete an operand
+            expect_operand = false;
+        }
+    }
+    let in_brackets = args.last().is_some_and(|t| t.depth > 0 && t.text != "(");
+    match (expect_operand, compared) {
+        (true, _) if after_comparison => (CompletionContext::Value, FUNCTIONS.to_vec()),
+        (true, _) => (CompletionContext::Column, FUNCTIONS.to_vec()),
+        (false, true) => (CompletionContext::Keyword, if in_brackets { vec!["and", "or", ")", ","] } else { vec!["and", "or", ",", "|"] }),
+        (false, false) => {
+            let mut expected = COMPARISONS.to_vec();
+            expected.extend(if in_brackets { ["and", "or", ")", ","] } else { ["and", "or", ",", "|"] });
+            (CompletionContext::Comparison, expected)
+        }
+    }
+}
+
+/// Byte offset of a UTF-16 offset, clamped to the query and to character boundaries
+fn byte_offset(query: &str, utf16_offset: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in query.char_indices() {
+        if units >= utf16_offset {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    query.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Accepts a table followed by `| where ...` / `| take n` stages, like a very small KQL parser
+    fn parse(text: &str) -> Option<Value> {
+        let mut stages = text.split('|').map(str::trim);
+        let table = stages.next().filter(|t| !t.is_empty() && t.chars().all(char::is_alphanumeric))?;
+        let operators: Option<Vec<Value>> = stages
+            .map(|stage| match stage.split_once(' ') {
+                Some(("where", column)) if column.chars().all(char::is_alphanumeric) => Some(json!({ "Where": { "Ident": column } })),
+                Some(("take", n)) => n.parse::<u64>().ok().map(|n| json!({ "Take": n })),
+                _ => None,
+            })
+            .collect();
+        Some(json!({ "source": { "Reference": table }, "operators": operators? }))
+    }
+
+    fn completion(query: &str) -> Completion {
+        partial_parse(query, query.encode_utf16().count(), parse).completion
+    }
+
+    #[test]
+    fn test_best_effort_ast_drops_unfinished_stages() {
+        let result = partial_parse("SecurityEvent | where Enabled | take 10 | whe", 20, parse);
+        assert!(!result.complete);
+        assert_eq!(result.parsed_length, "SecurityEvent | where Enabled | take 10".len());
+        assert_eq!(result.ast.unwrap()["operators"].as_array().unwrap().len(), 2);
+        assert_eq!(result.schema.unwrap().columns, vec!["Enabled"]);
+
+        let result = partial_parse("SecurityEvent | where (", 0, parse);
+        assert_eq!(result.ast.unwrap(), json!({ "source": { "Reference": "SecurityEvent" }, "operators": [] }));
+        assert!(partial_parse("SecurityEvent | take 5", 0, parse).complete);
+    }
+
+    #[test]
+    fn test_expected_tokens_at_cursor() {
+        let c = completion("SecurityEvent | whe");
+        assert_eq!((c.context, c.expected.clone()), (CompletionContext::Operator, vec!["where".to_string()]));
+        assert_eq!((c.prefix.as_str(), c.replace_offset, c.replace_length), ("whe", 16, 3));
+
+        assert_eq!(completion("SecurityEvent |").context, CompletionContext::Operator);
+        assert_eq!(completion("Secur").context, CompletionContext::Table);
+        assert_eq!(completion("T | where Account ").expected[..3], ["==", "!=", "=~"]);
+        assert_eq!(completion("T | where Account !con").expected, vec!["!contains"]);
+        assert_eq!(completion("T | where Account == ").context, CompletionContext::Value);
+        assert_eq!(completion("T | where Account == 'x' ").expected, vec!["and", "or", ",", "|"]);
+        assert_eq!(completion("T | where Account == 'x' and Ho").context, CompletionContext::Column);
+        assert_eq!(completion("T | where Account == 'it").context, CompletionContext::String);
+        assert_eq!(completion("T | where isempty(Acc").context, CompletionContext::Column);
+        assert_eq!(completion("T | summarize Failures = dc").expected, vec!["dcount", "dcountif"]);
+        assert_eq!(completion("T | summarize count() ").expected, vec!["by", ",", "|"]);
+        assert_eq!(completion("T | summarize count() by bin(TimeGenerated, 1h) ").context, CompletionContext::Comparison);
+        assert_eq!(completion("T | sort by Failures ").expected, vec!["asc", "desc", ",", "|"]);
+        assert_eq!(completion("T | top 10 ").expected, vec!["by"]);
+
+        // Completions are positioned in UTF-16 units and only look at the text before the cursor
+        let query = "T | where User == \"ä😀\" | pro | take 5";
+        let cursor = query[..query.find(" | take").unwrap()].encode_utf16().count();
+        let c = partial_parse(query, cursor, parse).completion;
+        assert_eq!((c.expected.clone(), c.replace_offset), (vec!["project".to_string(), "project-away".to_string(), "project-rename".to_string()], cursor - 3));
+        assert_eq!(c.operator, None);
+    }
+}
@@ -0,0 +1,348 @@
+// Structured validation diagnostics for KQL queries.
+// Turns the parser's nom error into a position (offset, line, column) and the tokens that were expected there,
+// and catches unterminated strings and unbalanced brackets up front, where nom would only report the last
+// alternative it tried. Offsets and columns are in UTF-16 code units so editors can use them as JS string indices.
+
+use serde::Serialize;
+
+/// A single problem in a query
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub message: String,
+    /// Start of the offending token, in UTF-16 code units from the start of the query
+    pub offset: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, in UTF-16 code units
+    pub column: usize,
+    /// Length of the offending token in UTF-16 code units (0 at end of input)
+    pub length: usize,
+    /// What the parser would have accepted at this position, when known
+    pub expected: Vec<String>,
+}
+
+/// Result of validating a query
+#[derive(Debug, Clone, Serialize)]
+pub struct Validation {
+    pub valid: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Build the validation result for a query from the outcome of parsing it.
+/// `parse_result` carries the `{:?}` rendering of the parser error on failure.
+pub fn validate(query: &str, parse_result: Result<(), String>) -> Validation {
+    let error = match parse_result {
+        Ok(()) => return Validation { valid: true, diagnostics: Vec::new() },
+        Err(error) => error,
+    };
+
+    // Lexical problems explain a parse failure better than the parser's last attempted alternative
+    let mut diagnostics = structural_diagnostics(query);
+    if diagnostics.is_empty() {
+        diagnostics.push(from_parse_error(query, &error));
+    }
+    Validation { valid: false, diagnostics }
+}
+
+/// Unterminated string literals and unbalanced brackets
+pub fn structural_diagnostics(query: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut open: Vec<(usize, char)> = Vec::new();
+    let mut chars = query.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                // Comment to end of line
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '@' if matches!(chars.peek(), Some((_, '\'' | '"'))) => {
+                // Verbatim string: no escapes, the quote is doubled to include it
+                let (_, quote) = chars.next().unwrap();
+                let mut closed = false;
+                while let Some((_, c)) = chars.next() {
+                    if c == quote {
+                        if matches!(chars.peek(), Some((_, next)) if *next == quote) {
+                            chars.next();
+                        } else {
+                            closed = true;
+                            break;
+                        }
+                    }
+                }
+                if !closed {
+                    diagnostics.push(diagnostic_at(query, pos, 2, "Unterminated string literal".to_string(), vec![quote.to_string()]));
+                }
+            }
+            '\'' | '"' => {
+                let mut closed = false;
+                while let Some((_, s)) = chars.next() {
+                    match s {
+                        '\\' => {
+                            chars.next();
+                        }
+                        s if s == c => {
+                            closed = true;
+                            break;
+                        }
+                        '\n' => break,
+                        _ => {}
+                    }
+                }
+                if !closed {
+                    diagnostics.push(diagnostic_at(query, pos, 1, "Unterminated string literal".to_string(), vec![c.to_string()]));
+                }
+            }
+            '(' | '[' | '{' => open.push((pos, c)),
+            ')' | ']' | '}' => {
+                let opener = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                match open.pop() {
+                    Some((_, o)) if o == opener => {}
+                    Some((open_pos, o)) => {
+                        diagnostics.push(diagnostic_at(
+                            query,
+                            pos,
+                            1,
+                            format!("Mismatched '{}': '{}' opened at offset {} is still open", c, o, utf16_offset(query, open_pos)),
+                            vec![closer(o).to_string()],
+                        ));
+                    }
+                    None => {
+                        diagnostics.push(diagnostic_at(query, pos, 1, format!("Unmatched '{}'", c), Vec::new()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (pos, c) in open {
+        diagnostics.push(diagnostic_at(query, pos, 1, format!("Unclosed '{}'", c), vec![closer(c).to_string()]));
+    }
+    diagnostics.sort_by_key(|d| d.offset);
+    diagnostics
+}
+
+/// Position a parser error from its `{:?}` rendering.
+/// nom errors carry the unparsed remainder of the input (`Error { input: "...", code: Tag }`, or
+/// `("...", Nom(Tag))` / `("...", Char(')'))` / `("...", Context("..."))` entries for verbose errors);
+/// the failure is where the remainder with the least input left starts.
+pub fn from_parse_error(query: &str, error: &str) -> Diagnostic {
+    if error.contains("Incomplete") {
+        return diagnostic_at(query, query.len(), 0, "Unexpected end of query".to_string(), Vec::new());
+    }
+
+    let mut farthest: Option<usize> = None;
+    let mut expected: Vec<String> = Vec::new();
+    for (remaining, token) in nom_entries(error) {
+        let Some(pos) = remainder_offset(query, &remaining) else { continue };
+        match farthest {
+            Some(current) if pos < current => continue,
+            Some(current) if pos > current => expected.clear(),
+            _ => {}
+        }
+        farthest = Some(pos);
+        if let Some(token) = token {
+            if !expected.contains(&token) {
+                expected.push(token);
+            }
+        }
+    }
+
+    match farthest {
+        Some(pos) if pos >= query.trim_end().len() => {
+            diagnostic_at(query, pos, 0, "Unexpected end of query".to_string(), expected)
+        }
+        Some(pos) => {
+            let token = token_at(&query[pos..]);
+            diagnostic_at(query, pos, utf16_len(token), format!("Unexpected '{}'", token), expected)
+        }
+        // Nothing to position the error with; report it against the whole query
+        None => diagnostic_at(query, 0, utf16_len(query), format!("Invalid query: {}", error), Vec::new()),
+    }
+}
+
+fn diagnostic_at(query: &str, byte_pos: usize, length: usize, message: String, expected: Vec<String>) -> Diagnostic {
+    let before = &query[..byte_pos];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    Diagnostic {
+        message,
+        offset: utf16_len(before),
+        line: before.matches('\n').count() + 1,
+        column: utf16_len(&before[line_start..]) + 1,
+        length,
+        expected,
+    }
+}
+
+/// Length in UTF-16 code units, the unit every offset handed to the editor is counted in
+pub(crate) fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+fn utf16_offset(query: &str, byte_pos: usize) -> usize {
+    utf16_len(&query[..byte_pos])
+}
+
+fn closer(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+/// Byte offset of an unparsed remainder within the query
+fn remainder_offset(query: &str, remaining: &str) -> Option<usize> {
+    if query.ends_with(remaining) {
+        Some(query.len() - remaining.len())
+    } else {
+        query.rfind(remaining)
+    }
+}
+
+/// The token starting a remainder: a word, a quoted string or a single symbol
+fn token_at(rest: &str) -> &str {
+    let mut chars = rest.char_indices();
+    let Some((_, first)) = chars.next() else { return rest };
+    let end = if first.is_alphanumeric() || first == '_' {
+        rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.')).unwrap_or(rest.len())
+    } else if first == '\'' || first == '"' {
+        rest[1..].find(first).map(|i| i + 2).unwrap_or(rest.len())
+    } else {
+        first.len_utf8()
+    };
+    &rest[..end]
+}
+
+/// Readable expectation for a nom error kind, where the kind says what was wanted
+fn expected_for_kind(kind: &str) -> Option<String> {
+    let expected = match kind {
+        "Digit" | "HexDigit" | "Float" => "number",
+        "Alpha" | "AlphaNumeric" => "identifier",
+        "Eof" => "end of query",
+        "MultiSpace" | "Space" => "whitespace",
+        "CrLf" => "line break",
+        _ => return None,
+    };
+    Some(expected.to_string())
+}
+
+/// (remaining input, expected token) pairs from a Debug-rendered nom error
+fn nom_entries(error: &str) -> Vec<(String, Option<String>)> {
+    let mut entries = Vec::new();
+    let mut i = 0;
+    while let Some(found) = error[i..].find('"') {
+        let quote = i + found;
+        let Some((text, consumed)) = debug_string(&error[quote..]) else { break };
+        i = quote + consumed;
+
+        // Remainders follow `input:` (nom::error::Error) or open a VerboseError entry tuple
+        let prefix = error[..quote].trim_end();
+        if !(prefix.ends_with("input:") || prefix.ends_with('(')) || prefix.ends_with("Context(") {
+            continue;
+        }
+
+        // Kind follows as `, code: Tag` or `, Nom(Tag)` / `, Char('x')` / `, Context("...")`
+        let after = error[i..].trim_start_matches([',', ' ']);
+        let after = after.strip_prefix("code:").map(str::trim_start).unwrap_or(after);
+        let expected = if let Some(context) = after.strip_prefix("Context(") {
+            debug_string(context).map(|(context, _)| context)
+        } else if let Some(c) = after.strip_prefix("Char('") {
+            after.find("')").map(|end| c[..end - 6].replace("\\'", "'"))
+        } else {
+            let kind = after.strip_prefix("Nom(").unwrap_or(after);
+            let end = kind.find(|c: char| !c.is_alphanumeric()).unwrap_or(kind.len());
+            expected_for_kind(&kind[..end])
+        };
+        entries.push((text, expected));
+    }
+    entries
+}
+
+/// Unescape a Rust Debug string literal at the start of `text`; returns the value and bytes consumed
+fn debug_string(text: &str) -> Option<(String, usize)> {
+    let mut chars = text.char_indices();
+    if chars.next()?.1 != '"' {
+        return None;
+    }
+    let mut value = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, i + 1)),
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                '0' => value.push('\0'),
+                'u' => {
+                    // \u{XXXX}
+                    let mut hex = String::new();
+                    for (_, h) in chars.by_ref() {
+                        match h {
+                            '{' => {}
+                            '}' => break,
+                            h => hex.push(h),
+                        }
+                    }
+                    value.push(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)?);
+                }
+                other => value.push(other),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_errors_are_positioned() {
+        let query = "SecurityEvent\n| where EventID == 4625\n| summarize count() by ";
+        let error = "Error(Error { input: \"\", code: Tag })";
+        let diagnostic = from_parse_error(query, error);
+        assert_eq!(diagnostic.message, "Unexpected end of query");
+        assert_eq!((diagnostic.offset, diagnostic.line, diagnostic.column), (query.len(), 3, 24));
+
+        // Verbose errors: the deepest entry wins and its expectations are reported
+        let query = "events | where user == \"ä\" | take x";
+        let error = "Error(VerboseError { errors: [(\"x\", Char('(')), (\"x\", Nom(Digit)), (\"take x\", Context(\"operator\"))] })";
+        let diagnostic = from_parse_error(query, error);
+        assert_eq!(diagnostic.message, "Unexpected 'x'");
+        assert_eq!(diagnostic.offset, query.encode_utf16().count() - 1);
+        assert_eq!(diagnostic.length, 1);
+        assert_eq!(diagnostic.expected, vec!["(".to_string(), "number".to_string()]);
+
+        let unknown = from_parse_error("events | bogus", "something went wrong");
+        assert_eq!((unknown.offset, unknown.length), (0, 14));
+        assert!(validate("events", Ok(())).valid);
+    }
+
+    #[test]
+    fn test_structural_problems_are_reported_first() {
+        let query = "events | where msg == 'it\\'s | where (a == 1 | project x]";
+        let validation = validate(query, Err("Error(Error { input: \"\", code: Tag })".to_string()));
+        assert!(!validation.valid);
+        assert_eq!(validation.diagnostics.len(), 1);
+        assert_eq!(validation.diagnostics[0].message, "Unterminated string literal");
+        assert_eq!(validation.diagnostics[0].offset, 22);
+
+        let diagnostics = structural_diagnostics("events | where (a == 1 | project x] // (ignored\n| take 1)");
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Mismatched ']': '(' opened at offset 15 is still open", "Unmatched ')'"]);
+        assert_eq!(diagnostics[0].expected, vec![")".to_string()]);
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (2, 9));
+        assert!(structural_diagnostics("T | where s == @'C:\\path''s' and t == \"a\\\"b\"").is_empty());
+    }
+}
@@ -0,0 +1,348 @@
+// Canonical KQL formatting from the AST.
+// Re-emits a query from the serde JSON form of the AST with one pipe per line, normalized spacing and keyword case,
+// and parentheses only where precedence needs them, so the query editor can format on save
+// and the UI can turn an AST it rewrote back into a query.
+// Nodes the formatter does not know are an error rather than being dropped, so formatting never changes a query.
+
+use crate::ast::{binary_operator, items, pair, variant};
+use serde_json::Value;
+
+/// Indentation of nested queries (join and union arguments)
+const INDENT: &str = "    ";
+
+/// Format a serialized KQL query AST
+pub fn format_query(ast: &Value) -> Result<String, String> {
+    let mut out = String::new();
+    if let Some(statements) = ast.get("statements").and_then(Value::as_array) {
+        for statement in statements {
+            out.push_str(&statement_text(statement)?);
+            out.push_str(";\n");
+        }
+    }
+    let query = match ast.get("query") {
+        Some(query) => query,
+        None if ast.get("source").is_some() => ast,
+        None if !out.is_empty() => return Ok(out.trim_end().to_string()),
+        None => return Err("Query does not contain a tabular expression".to_string()),
+    };
+    let query = match variant(query) {
+        Ok(("TabularExpression", body)) => body,
+        _ => query,
+    };
+    out.push_str(&tabular(query, 0)?);
+    Ok(out)
+}
+
+/// Render a serialized AST received as JSON text, e.g. one the UI rewrote, back to KQL
+pub fn render_ast_json(ast_json: &str) -> Result<String, String> {
+    let ast: Value = serde_json::from_str(ast_json).map_err(|e| format!("Invalid AST JSON: {}", e))?;
+    format_query(&ast)
+}
+
+fn statement_text(statement: &Value) -> Result<String, String> {
+    match variant(statement)? {
+        ("Let", body) => match body.as_array().map(Vec::as_slice) {
+            Some([Value::String(name), value]) => {
+                let value = match value.get("source") {
+                    Some(_) => format!("(\n{}{}\n)", INDENT, tabular(value, 1)?),
+                    None => expr(value, 0)?,
+                };
+                Ok(format!("let {} = {}", ident(name), value))
+            }
+            _ => Err("let needs a name and a value".to_string()),
+        },
+        ("TabularExpression", body) => tabular(body, 0),
+        (name, _) => Err(format!("Statement '{}' cannot be formatted", name)),
+    }
+}
+
+/// `source` followed by one `| operator` line per operator, indented `depth` levels
+fn tabular(tabular: &Value, depth: usize) -> Result<String, String> {
+    let mut out = match tabular.get("source").map(variant).transpose()? {
+        Some(("Reference", Value::String(table))) => ident(table),
+        Some((name, _)) => return Err(format!("Source '{}' cannot be formatted", name)),
+        None => return Err("Tabular expression has no source".to_string()),
+    };
+    let newline = format!("\n{}", INDENT.repeat(depth));
+    for operator in tabular.get("operators").and_then(Value::as_array).into_iter().flatten() {
+        out.push_str(&newline);
+        out.push_str("| ");
+        out.push_str(&operator_text(operator, depth)?);
+    }
+    Ok(out)
+}
+
+fn operator_text(operator: &Value, depth: usize) -> Result<String, String> {
+    let (name, body) = variant(operator)?;
+    Ok(match name {
+        "Where" | "Filter" => format!("where {}", expr(body, 0)?),
+        "Take" | "Limit" => format!("take {}", body.as_u64().ok_or("take needs a row count")?),
+        "Count" => "count".to_string(),
+        "Project" => format!("project {}", named_list(body)?),
+        "Extend" => format!("extend {}", named_list(body)?),
+        "Distinct" => format!("distinct {}", list(body)?),
+        "Summarize" => {
+            let parts = body.as_array().ok_or("summarize needs aggregates and group-by columns")?;
+            let aggregates = parts.first().map(named_list).transpose()?.unwrap_or_default();
+            let by = parts.get(1).map(named_list).transpose()?.unwrap_or_default();
+            match (aggregates.is_empty(), by.is_empty()) {
+                (_, true) => format!("summarize {}", aggregates),
+                (true, false) => format!("summarize by {}", by),
+                (false, false) => format!("summarize {} by {}", aggregates, by),
+            }
+        }
+        "Sort" | "Order" => {
+            let keys: Vec<String> = items(body).into_iter().map(sort_key).collect::<Result<_, _>>()?;
+            format!("sort by {}", keys.join(", "))
+        }
+        "Top" => {
+            let parts = body.as_array().ok_or("top needs a row count and a sort expression")?;
+            let n = parts.first().and_then(Value::as_u64).ok_or("top needs a row count")?;
+            let keys: Vec<String> = parts[1..].iter().map(sort_key).collect::<Result<_, _>>()?;
+            format!("top {} by {}", n, keys.join(", "))
+        }
+        "MvExpand" => format!("mv-expand {}", list(body)?),
+        "Join" => {
+            let parts = body.as_array().ok_or("join needs a right-hand query")?;
+            let kind = parts.first().and_then(Value::as_str).map(|kind| format!(" kind={}", kind.to_ascii_lowercase()));
+            let right = parts.get(1).ok_or("join needs a right-hand query")?;
+            let on = parts.get(2).map(list).transpose()?.ok_or("join needs on columns")?;
+            format!(
+                "join{} (\n{}{}\n{}) on {}",
+                kind.unwrap_or_default(),
+                INDENT.repeat(depth + 1),
+                tabular(right, depth + 1)?,
+                INDENT.repeat(depth),
+                on
+            )
+        }
+        other => return Err(format!("Operator '{}' cannot be formatted", other)),
+    })
+}
+
+/// `name = expr` items (alias may be null) or bare expressions, comma separated
+fn named_list(value: &Value) -> Result<String, String> {
+    let mut formatted = Vec::new();
+    for item in items(value) {
+        formatted.push(match item.as_array().map(Vec::as_slice) {
+            Some([Value::String(alias), expression]) => format!("{} = {}", ident(alias), expr(expression, 0)?),
+            Some([Value::Null, expression]) => expr(expression, 0)?,
+            _ => expr(item, 0)?,
+        });
+    }
+    Ok(formatted.join(", "))
+}
+
+fn list(value: &Value) -> Result<String, String> {
+    let formatted: Vec<String> = items(value).into_iter().map(|item| expr(item, 0)).collect::<Result<_, _>>()?;
+    Ok(formatted.join(", "))
+}
+
+/// Sort keys are a column name, an expression, or `[expr, "Asc"|"Desc", nulls?]`
+fn sort_key(item: &Value) -> Result<String, String> {
+    let Some(parts) = item.as_array().filter(|parts| !parts.is_empty()) else {
+        return expr(item, 0);
+    };
+    let mut key = expr(&parts[0], 0)?;
+    for modifier in parts[1..].iter().filter_map(Value::as_str) {
+        key.push(' ');
+        key.push_str(&match modifier.to_ascii_lowercase().as_str() {
+            "asc" | "ascending" => "asc".to_string(),
+            "desc" | "descending" => "desc".to_string(),
+            "nullsfirst" => "nulls first".to_string(),
+            "nullslast" => "nulls last".to_string(),
+            other => return Err(format!("Unknown sort modifier '{}'", other)),
+        });
+    }
+    Ok(key)
+}
+
+/// Format an expression that appears where operators binding at `min_precedence` or tighter need no parentheses
+fn expr(value: &Value, min_precedence: u8) -> Result<String, String> {
+    if let Value::String(column) = value {
+        return Ok(ident(column));
+    }
+    if value.get("source").is_some() {
+        return Ok(format!("({})", tabular(value, 0)?));
+    }
+    let (name, body) = variant(value)?;
+    if let Some((op, precedence)) = binary_operator(name) {
+        let (left, right) = pair(body, name)?;
+        // Left-associative: an equal-precedence right operand keeps its parentheses
+        let text = format!("{} {} {}", expr(left, precedence)?, op, expr(right, precedence + 1)?);
+        return Ok(if precedence < min_precedence { format!("({})", text) } else { text });
+    }
+    match name {
+        "Ident" | "Column" => body.as_str().map(ident).ok_or_else(|| "Identifier must be a string".to_string()),
+        "Value" | "Literal" => literal(body),
+        "Not" => Ok(format!("not({})", expr(body, 0)?)),
+        "Paren" | "Group" => expr(body, min_precedence),
+        "In" | "NotIn" => {
+            let (left, right) = pair(body, name)?;
+            let op = if name == "NotIn" { "!in" } else { "in" };
+            let text = format!("{} {} ({})", expr(left, 4)?, op, list(right)?);
+            Ok(if min_precedence > 3 { format!("({})", text) } else { text })
+        }
+        "Func" | "Call" => {
+            let parts = body.as_array().ok_or("Function call needs a name and arguments")?;
+            let function = parts.first().and_then(Value::as_str).ok_or("Function call needs a name")?;
+            let args = parts.get(1).map(list).transpose()?.unwrap_or_default();
+            Ok(format!("{}({})", function, args))
+        }
+        other => Err(format!("Expression '{}' cannot be formatted", other)),
+    }
+}
+
+fn literal(value: &Value) -> Result<String, String> {
+    let (kind, inner) = match value {
+        Value::Object(_) => variant(value)?,
+        // Untagged literals
+        other => ("", other),
+    };
+    Ok(match (kind, inner) {
+        (_, Value::Null) => "null".to_string(),
+        ("Timespan", span) => timespan(span)?,
+        ("DateTime" | "Datetime", Value::String(time)) => format!("datetime({})", time),
+        ("Dynamic", dynamic) => format!("dynamic({})", dynamic),
+        (_, Value::String(text)) => string(text),
+        (_, other) => other.to_string(),
+    })
+}
+
+/// Double-quoted string literal
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A serialized timespan (`{"secs", "nanos"}`, seconds, or a KQL span like "1h") in the largest whole unit
+fn timespan(value: &Value) -> Result<String, String> {
+    let nanos: i128 = match value {
+        Value::String(span) => return Ok(span.clone()),
+        Value::Object(map) => {
+            let secs = map.get("secs").and_then(Value::as_i64).unwrap_or(0);
+            let nanos = map.get("nanos").and_then(Value::as_i64).unwrap_or(0);
+            secs as i128 * 1_000_000_000 + nanos as i128
+        }
+        Value::Number(seconds) => (seconds.as_f64().unwrap_or_default() * 1e9) as i128,
+        other => return Err(format!("Invalid timespan {}", other)),
+    };
+    const UNITS: [(&str, i128); 6] = [
+        ("d", 86_400_000_000_000),
+        ("h", 3_600_000_000_000),
+        ("m", 60_000_000_000),
+        ("s", 1_000_000_000),
+        ("ms", 1_000_000),
+        ("microsecond", 1_000),
+    ];
+    Ok(UNITS
+        .iter()
+        .find(|(_, size)| nanos != 0 && nanos % size == 0)
+        .map(|(unit, size)| format!("{}{}", nanos / size, unit))
+        .unwrap_or_else(|| format!("{}tick", nanos / 100)))
+}
+
+/// Identifiers that aren't plain (dotted) names are written as `['name']`
+fn ident(name: &str) -> String {
+    let plain = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if plain {
+        name.to_string()
+    } else {
+        format!("['{}']", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ident(name: &str) -> Value {
+        json!({ "Ident": name })
+    }
+
+    #[test]
+    fn test_pipe_per_line_with_minimal_parentheses() {
+        let ast = json!({
+            "statements": [{ "Let": ["threshold", { "Value": { "Long": 5 } }] }],
+            "query": { "TabularExpression": {
+                "source": { "Reference": "SecurityEvent" },
+                "operators": [
+                    { "Where": { "And": [
+                        { "Or": [
+                            { "Equals": [ident("EventID"), { "Value": { "Long": 4625 } }] },
+                            { "In": [ident("EventID"), [{ "Value": { "Long": 4771 } }, { "Value": { "Long": 4776 } }]] }
+                        ] },
+                        { "Greater": [ident("TimeGenerated"), { "Func": ["ago", [{ "Value": { "Timespan": { "secs": 3600, "nanos": 0 } } }]] }] }
+                    ] } },
+                    { "Where": { "NotContains": [ident("Account"), { "Value": { "String": "svc \"x\"" } }] } },
+                    { "Extend": [["Host", { "Func": ["tolower", [ident("Computer")]] }], ["user.name", ident("Account")]] },
+                    { "Summarize": [[["Failures", { "Func": ["count", []] }]], [[null, ident("Host")], [null, ident("Event Source")]]] },
+                    { "Where": { "Greater": [ident("Failures"), { "Multiply": [ident("threshold"), { "Add": [{ "Value": { "Long": 1 } }, { "Value": { "Long": 2 } }] }] }] } },
+                    { "Sort": [[ident("Failures"), "Desc"], "Host"] },
+                    { "Take": 10 }
+                ]
+            } }
+        });
+
+        assert_eq!(format_query(&ast).unwrap(), "\
+let threshold = 5;
+SecurityEvent
+| where (EventID == 4625 or EventID in (4771, 4776)) and TimeGenerated > ago(1h)
+| where Account !contains \"svc \\\"x\\\"\"
+| extend Host = tolower(Computer), user.name = Account
+| summarize Failures = count() by Host, ['Event Source']
+| where Failures > threshold * (1 + 2)
+| sort by Failures desc, Host
+| take 10");
+    }
+
+    #[test]
+    fn test_nested_queries_are_indented_and_unknown_nodes_rejected() {
+        let ast = json!({
+            "source": { "Reference": "SigninLogs" },
+            "operators": [
+                { "Join": ["Inner", { "source": { "Reference": "AuditLogs" }, "operators": [{ "Project": ["UserId", "Operation"] }] }, ["UserId"]] },
+                { "Summarize": [null, [[null, { "Func": ["bin", [ident("TimeGenerated"), { "Value": { "Timespan": { "secs": 0, "nanos": 1500000000 } } }]] }]]] }
+            ]
+        });
+        assert_eq!(format_query(&ast).unwrap(), "\
+SigninLogs
+| join kind=inner (
+    AuditLogs
+    | project UserId, Operation
+) on UserId
+| summarize by bin(TimeGenerated, 1500ms)");
+
+        let ast = json!({ "source": { "Reference": "T" }, "operators": [{ "Evaluate": ["autocluster", []] }] });
+        assert_eq!(format_query(&ast).unwrap_err(), "Operator 'Evaluate' cannot be formatted");
+    }
+
+    #[test]
+    fn test_rewritten_ast_json_renders_back_to_kql() {
+        // The UI injects its time range as the first operator of a parsed query
+        let mut ast = json!({ "source": { "Reference": "SigninLogs" }, "operators": [{ "Take": 5 }] });
+        let range = json!({ "Where": { "And": [
+            { "GreaterOrEqual": [ident("TimeGenerated"), { "Value": { "DateTime": "2026-10-01T00:00:00Z" } }] },
+            { "Less": [ident("TimeGenerated"), { "Value": { "DateTime": "2026-10-02T00:00:00Z" } }] }
+        ] } });
+        ast["operators"].as_array_mut().unwrap().insert(0, range);
+
+        assert_eq!(render_ast_json(&ast.to_string()).unwrap(), "\
+SigninLogs
+| where TimeGenerated >= datetime(2026-10-01T00:00:00Z) and TimeGenerated < datetime(2026-10-02T00:00:00Z)
+| take 5");
+        assert!(render_ast_json("{\"source\": ").unwrap_err().starts_with("Invalid AST JSON: "));
+    }
+}
@@ -5,6 +5,13 @@ use kqlparser::parser::parse_query;
 use kqlparser::ast::Query as KqlRustAst;
 use serde_json;
 
+mod ast;
+pub mod completion;
+pub mod diagnostics;
+pub mod format;
+pub mod schema;
+pub mod sql;
+
 // Optional: wee_alloc for smaller Wasm size if the "optimize_size" feature is enabled in Cargo.toml
 // #[cfg(feature = "optimize_size")]
 // #[global_allocator]
@@ -69,6 +76,105 @@ pub fn parse_kql_to_json_ast_string(kql_query: &str) -> Result<String, JsValue>
     }
 }
 
+/// Parses a KQL query and lowers it to parameterized SQL for Postgres/TimescaleDB.
+/// `dialect` is "postgres" or "timescaledb". Returns a JSON string:
+/// `{ "sql": "SELECT ... WHERE ... LIMIT $3", "where": "...", "params": [...] }`
+/// where every literal from the query is a `$n` parameter, ready to hand to the database driver.
+/// Operators that cannot be expressed as a single SELECT (joins, unions, filters after summarize, ...)
+/// return an error instead of SQL with different semantics.
+#[wasm_bindgen]
+pub fn kql_to_sql(kql_query: &str, dialect: &str) -> Result<String, JsValue> {
+    let dialect: sql::SqlDialect = dialect.parse().map_err(|e: String| JsValue::from_str(&format!("[Rust Wasm] {}", e)))?;
+
+    let parsed_query_ast: KqlRustAst = parse_query(kql_query)
+        .map_err(|nom_error| JsValue::from_str(&format!("[Rust Wasm] KQL Parsing Error: {}", nom_error.to_string())))?;
+
+    // Lower from the serialized AST, the same shape parse_kql_to_json_ast_string hands to JavaScript
+    let ast_value = serde_json::to_value(&parsed_query_ast)
+        .map_err(|e| JsValue::from_str(&format!("[Rust Wasm] AST Serialization Error: {}", e)))?;
+
+    let lowered = sql::lower_query(&ast_value, dialect)
+        .map_err(|e| JsValue::from_str(&format!("[Rust Wasm] SQL Translation Error: {}", e)))?;
+
+    serde_json::to_string(&lowered)
+        .map_err(|e| JsValue::from_str(&format!("[Rust Wasm] SQL Serialization Error: {}", e)))
+}
+
+/// Parses a KQL query and returns what it references as a JSON string:
+/// `{ "tables": [...], "columns": [...], "defined_columns": [...], "operators": [...], "functions": [...] }`
+/// `columns` are read from the source tables; `defined_columns` are created by the query itself.
+#[wasm_bindgen]
+pub fn extract_kql_schema(kql_query: &str) -> Result<String, JsValue> {
+    let parsed_query_ast: KqlRustAst = parse_query(kql_query)
+        .map_err(|nom_error| JsValue::from_str(&format!("[Rust Wasm] KQL Parsing Error: {}", nom_error.to_string())))?;
+
+    let ast_value = serde_json::to_value(&parsed_query_ast)
+        .map_err(|e| JsValue::from_str(&format!("[Rust Wasm] AST Serialization Error: {}", e)))?;
+
+    serde_json::to_string(&schema::introspect(&ast_value))
+        .map_err(|e| JsValue::from_str(&format!("[Rust Wasm] Schema Serialization Error: {}", e)))
+}
+
+/// Parses a KQL query and re-emits it canonically formatted: statements end with `;` on their own line, the
+/// source table comes first and every operator follows on its own `| ` line, with nested queries indented.
+/// Returns an error, rather than a partially formatted query, if the query uses syntax the formatter doesn't know.
+#[wasm_bindgen]
+pub fn format_kql(kql_query: &str) -> Result<String, JsValue> {
+    let parsed_query_ast: KqlRustAst = parse_query(kql_query)
+        .map_err(|nom_error| JsValue::from_str(&format!("[Rust Wasm] KQL Parsing Error: {}", nom_error.to_string())))?;
+
+    let ast_value = serde_json::to_value(&parsed_query_ast)
+        .map_err(|e| JsValue::from_str(&format!("[Rust Wasm] AST Serialization Error: {}", e)))?;
+
+    format::format_query(&ast_value)
+        .map_err(|e| JsValue::from_str(&format!("[Rust Wasm] Formatting Error: {}", e)))
+}
+
+/// Renders a serialized AST (the JSON from `parse_kql_to_json_ast_string`, possibly rewritten by the caller) back to
+/// a KQL string, formatted like `format_kql`. The rendered query is parsed again so a malformed rewrite is reported
+/// here instead of when the query runs.
+#[wasm_bindgen]
+pub fn json_ast_string_to_kql(ast_json: &str) -> Result<String, JsValue> {
+    let kql_query = format::render_ast_json(ast_json)
+        .map_err(|e| JsValue::from_str(&format!("[Rust Wasm] AST Rendering Error: {}", e)))?;
+
+    parse_query(&kql_query)
+        .map_err(|nom_error| JsValue::from_str(&format!("[Rust Wasm] Rendered KQL Parsing Error: {}", nom_error.to_string())))?;
+    Ok(kql_query)
+}
+
+/// Validates a KQL query and returns structured diagnostics as a JSON string:
+/// `{ "valid": false, "diagnostics": [{ "message": "Unexpected 'x'", "offset": 34, "line": 1, "column": 35,
+///    "length": 1, "expected": ["number"] }] }`
+/// Offsets and columns are UTF-16 code units, i.e. JavaScript string indices, so editors can underline the
+/// offending token directly. An invalid query is not an error; only serialization failures are.
+#[wasm_bindgen]
+pub fn validate_kql(kql_query: &str) -> Result<String, JsValue> {
+    // The Debug form keeps the unparsed remainder the diagnostics are positioned from
+    let parsed = parse_query(kql_query)
+        .map(|_| ())
+        .map_err(|nom_error| format!("{:?}", nom_error));
+
+    let validation = diagnostics::validate(kql_query, parsed);
+    serde_json::to_string(&validation)
+        .map_err(|e| JsValue::from_str(&format!("[Rust Wasm] Diagnostics Serialization Error: {}", e)))
+}
+
+/// Parses a possibly unfinished KQL query for autocomplete and returns JSON:
+/// `{ "complete": false, "ast": {...}, "parsed_length": 28, "schema": {...},
+///    "completion": { "context": "operator", "operator": null, "expected": ["where"], "prefix": "whe",
+///    "replace_offset": 31, "replace_length": 3 } }`
+/// `ast` covers the longest prefix of whole pipeline stages that parses (null if not even the source does),
+/// and `completion` describes what may be typed at `cursor_offset`. Offsets are UTF-16 code units.
+#[wasm_bindgen]
+pub fn parse_kql_partial(kql_query: &str, cursor_offset: usize) -> Result<String, JsValue> {
+    let partial = completion::partial_parse(kql_query, cursor_offset, |text| {
+        parse_query(text).ok().and_then(|ast| serde_json::to_value(ast).ok())
+    });
+    serde_json::to_string(&partial)
+        .map_err(|e| JsValue::from_str(&format!("[Rust Wasm] Completion Serialization Error: {}", e)))
+}
+
 /// A simple health check function for the Wasm module.
 #[wasm_bindgen]
 pub fn health_check() -> String {
@@ -0,0 +1,212 @@
+// Schema introspection over the KQL AST.
+// Collects the tables, columns, operators and functions a query references, from the serde JSON form of the AST,
+// so the UI can drive autocomplete and column pruning without walking the AST in JavaScript.
+// Columns a query defines itself (extend/project/summarize aliases) are reported separately from source columns.
+
+use crate::ast::{items, variant};
+use serde::Serialize;
+use serde_json::Value;
+
+/// What a query references, each list in order of first appearance
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct QuerySchema {
+    pub tables: Vec<String>,
+    /// Columns read from the source tables
+    pub columns: Vec<String>,
+    /// Columns the query creates (aliases from extend, project, summarize, ...)
+    pub defined_columns: Vec<String>,
+    /// Tabular operators in KQL spelling, e.g. "where", "summarize", "mv-expand"
+    pub operators: Vec<String>,
+    pub functions: Vec<String>,
+}
+
+/// Introspect a serialized KQL query AST
+pub fn introspect(ast: &Value) -> QuerySchema {
+    let mut schema = QuerySchema::default();
+    schema.walk_any(ast);
+    schema
+}
+
+impl QuerySchema {
+    /// Visit every tabular expression (`source` + `operators`) in a node; also used for unknown payloads
+    fn walk_any(&mut self, value: &Value) {
+        match value {
+            Value::Object(map) if map.contains_key("source") => self.tabular(value),
+            Value::Object(map) => {
+                for (name, body) in map {
+                    match name.as_str() {
+                        "Ident" | "Column" => self.column(body),
+                        _ => self.walk_any(body),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| self.walk_any(item)),
+            _ => {}
+        }
+    }
+
+    fn tabular(&mut self, tabular: &Value) {
+        match tabular.get("source").map(node) {
+            Some(("Reference", Value::String(table))) => push_unique(&mut self.tables, table),
+            Some((_, source)) => self.walk_any(source),
+            None => {}
+        }
+        for operator in tabular.get("operators").and_then(Value::as_array).into_iter().flatten() {
+            self.operator(operator);
+        }
+    }
+
+    fn operator(&mut self, operator: &Value) {
+        let (name, body) = node(operator);
+        push_unique(&mut self.operators, &kql_operator_name(name));
+        match name {
+            "Where" | "Filter" => self.expr(body),
+            "Take" | "Limit" | "Count" => {}
+            "Project" | "Extend" => self.named_exprs(body),
+            "Summarize" => {
+                if let Some(parts) = body.as_array() {
+                    parts.iter().for_each(|part| self.named_exprs(part));
+                }
+            }
+            "Distinct" => items(body).into_iter().for_each(|item| self.expr(item)),
+            "Sort" | "Order" => items(body).into_iter().for_each(|item| self.sort_key(item)),
+            "Top" => {
+                if let Some(parts) = body.as_array() {
+                    parts.iter().skip(1).for_each(|item| self.sort_key(item));
+                }
+            }
+            // Joins, unions, renames, ...: identifiers and nested queries wherever they appear
+            _ => self.walk_any(body),
+        }
+    }
+
+    /// `[alias, expr]` pairs (alias may be null) or bare expressions
+    fn named_exprs(&mut self, list: &Value) {
+        for item in items(list) {
+            match item.as_array().map(Vec::as_slice) {
+                Some([alias, expr]) if alias.is_null() || alias.is_string() => {
+                    self.expr(expr);
+                    if let Some(alias) = alias.as_str() {
+                        push_unique(&mut self.defined_columns, alias);
+                    }
+                }
+                _ => self.expr(item),
+            }
+        }
+    }
+
+    /// Sort keys are a column name, an expression, or `[expr, "Asc"|"Desc", nulls?]`
+    fn sort_key(&mut self, item: &Value) {
+        match item.as_array().and_then(|parts| parts.first()) {
+            Some(expr) => self.expr(expr),
+            None => self.expr(item),
+        }
+    }
+
+    fn expr(&mut self, expr: &Value) {
+        if let Value::String(_) = expr {
+            return self.column(expr);
+        }
+        if expr.get("source").is_some() {
+            return self.tabular(expr);
+        }
+        let (name, body) = node(expr);
+        match name {
+            "Ident" | "Column" => self.column(body),
+            "Value" | "Literal" => {}
+            "Func" | "Call" => {
+                let parts = body.as_array().map(Vec::as_slice).unwrap_or_default();
+                if let Some(function) = parts.first().and_then(Value::as_str) {
+                    push_unique(&mut self.functions, function);
+                }
+                if let Some(args) = parts.get(1) {
+                    items(args).into_iter().for_each(|arg| self.expr(arg));
+                }
+            }
+            _ => items(body).into_iter().for_each(|operand| self.expr(operand)),
+        }
+    }
+
+    fn column(&mut self, name: &Value) {
+        if let Some(name) = name.as_str() {
+            // A column defined earlier in the pipeline is not read from the source
+            if !self.defined_columns.iter().any(|c| c == name) {
+                push_unique(&mut self.columns, name);
+            }
+        }
+    }
+}
+
+/// Variant name and payload; other nodes get an empty name and are walked for what they contain
+fn node(value: &Value) -> (&str, &Value) {
+    variant(value).unwrap_or(("", value))
+}
+
+fn push_unique(list: &mut Vec<String>, item: &str) {
+    if !list.iter().any(|existing| existing == item) {
+        list.push(item.to_string());
+    }
+}
+
+/// AST variant name to KQL spelling: `MvExpand` -> "mv-expand"
+fn kql_operator_name(variant: &str) -> String {
+    let mut name = String::new();
+    for (i, c) in variant.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            name.push('-');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_collects_tables_columns_operators_and_functions() {
+        let ast = json!({
+            "statements": [],
+            "query": {
+                "source": { "Reference": "SecurityEvent" },
+                "operators": [
+                    { "Where": { "And": [
+                        { "Equals": [{ "Ident": "EventID" }, { "Value": { "Long": 4625 } }] },
+                        { "Greater": [{ "Ident": "TimeGenerated" }, { "Func": ["ago", [{ "Value": { "Timespan": "1h" } }]] }] }
+                    ] } },
+                    { "Extend": [["Host", { "Func": ["tolower", [{ "Ident": "Computer" }]] }]] },
+                    { "Summarize": [[["Failures", { "Func": ["count", []] }]], [[null, { "Ident": "Host" }], [null, { "Ident": "Account" }]]] },
+                    { "Sort": [[{ "Ident": "Failures" }, "Desc"]] },
+                    { "MvExpand": [{ "Ident": "Tags" }] },
+                    { "Take": 10 }
+                ]
+            }
+        });
+
+        let schema = introspect(&ast);
+        assert_eq!(schema.tables, vec!["SecurityEvent"]);
+        assert_eq!(schema.columns, vec!["EventID", "TimeGenerated", "Computer", "Account", "Tags"]);
+        assert_eq!(schema.defined_columns, vec!["Host", "Failures"]);
+        assert_eq!(schema.operators, vec!["where", "extend", "summarize", "sort", "mv-expand", "take"]);
+        assert_eq!(schema.functions, vec!["ago", "tolower", "count"]);
+    }
+
+    #[test]
+    fn test_nested_queries_contribute_tables() {
+        let ast = json!({
+            "source": { "Reference": "SigninLogs" },
+            "operators": [
+                { "Join": [null, { "source": { "Reference": "AuditLogs" }, "operators": [{ "Project": ["UserId", "Operation"] }] }, ["UserId"]] },
+                { "Project": [[null, { "Ident": "Operation" }], ["When", { "Ident": "TimeGenerated" }]] }
+            ]
+        });
+
+        let schema = introspect(&ast);
+        assert_eq!(schema.tables, vec!["SigninLogs", "AuditLogs"]);
+        assert_eq!(schema.columns, vec!["UserId", "Operation", "TimeGenerated"]);
+        assert_eq!(schema.operators, vec!["join", "project"]);
+        assert_eq!(schema.defined_columns, vec!["When"]);
+    }
+}
@@ -0,0 +1,532 @@
+// Lowering of the KQL AST to parameterized SQL for Postgres/TimescaleDB.
+// Works on the serde JSON form of the AST (the same shape `parse_kql_to_json_ast_string` returns),
+// so the search-api can run the result directly instead of re-implementing the lowering in TypeScript.
+// Literals always become `$n` parameters; only identifiers are spliced in, and they are quoted.
+
+use crate::ast::{binary_operator, items, pair, variant};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// SQL flavour to generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    Postgres,
+    /// Postgres plus TimescaleDB functions (`time_bucket` for `bin()` over timestamps)
+    TimescaleDb,
+}
+
+impl std::str::FromStr for SqlDialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "postgres" | "postgresql" => Ok(SqlDialect::Postgres),
+            "timescale" | "timescaledb" => Ok(SqlDialect::TimescaleDb),
+            other => Err(format!("Unsupported SQL dialect '{}' (expected postgres or timescaledb)", other)),
+        }
+    }
+}
+
+/// A lowered query: the full statement, its WHERE clause on its own, and the positional parameters
+#[derive(Debug, Clone, Serialize)]
+pub struct SqlQuery {
+    pub sql: String,
+    /// WHERE condition without the keyword, for callers that build their own SELECT
+    #[serde(rename = "where")]
+    pub where_clause: Option<String>,
+    pub params: Vec<Value>,
+}
+
+/// Lower a serialized KQL query AST to SQL
+pub fn lower_query(ast: &Value, dialect: SqlDialect) -> Result<SqlQuery, String> {
+    let tabular = tabular_expression(ast).ok_or("Query does not contain a tabular expression")?;
+    let mut lowerer = Lowerer { dialect, params: Vec::new() };
+    let mut select = SelectParts {
+        from: lowerer.source(tabular.get("source").ok_or("Tabular expression has no source")?)?,
+        ..Default::default()
+    };
+    if let Some(operators) = tabular.get("operators").and_then(Value::as_array) {
+        for operator in operators {
+            lowerer.operator(operator, &mut select)?;
+        }
+    }
+
+    let where_clause = (!select.wheres.is_empty()).then(|| select.wheres.join(" AND "));
+    let mut sql = format!(
+        "SELECT {}{} FROM {}",
+        if select.distinct { "DISTINCT " } else { "" },
+        if select.columns.is_empty() { "*".to_string() } else { select.columns.join(", ") },
+        select.from
+    );
+    if let Some(condition) = &where_clause {
+        sql.push_str(&format!(" WHERE {}", condition));
+    }
+    if !select.group_by.is_empty() {
+        sql.push_str(&format!(" GROUP BY {}", select.group_by.join(", ")));
+    }
+    if !select.order_by.is_empty() {
+        sql.push_str(&format!(" ORDER BY {}", select.order_by.join(", ")));
+    }
+    if let Some(limit) = select.limit {
+        let placeholder = lowerer.param(json!(limit));
+        sql.push_str(&format!(" LIMIT {}", placeholder));
+    }
+
+    Ok(SqlQuery { sql, where_clause, params: lowerer.params })
+}
+
+#[derive(Default)]
+struct SelectParts {
+    from: String,
+    columns: Vec<String>,
+    distinct: bool,
+    wheres: Vec<String>,
+    group_by: Vec<String>,
+    order_by: Vec<String>,
+    limit: Option<u64>,
+    /// Operator that shaped the result set; later filters or sorts would need a subquery
+    shaped_by: Option<&'static str>,
+}
+
+struct Lowerer {
+    dialect: SqlDialect,
+    params: Vec<Value>,
+}
+
+impl Lowerer {
+    fn param(&mut self, value: Value) -> String {
+        self.params.push(value);
+        format!("${}", self.params.len())
+    }
+
+    fn source(&mut self, source: &Value) -> Result<String, String> {
+        match variant(source)? {
+            ("Reference", Value::String(table)) => Ok(quote_ident(table)),
+            (name, _) => Err(format!("Unsupported source '{}': only table references can be translated", name)),
+        }
+    }
+
+    fn operator(&mut self, operator: &Value, select: &mut SelectParts) -> Result<(), String> {
+        let (name, body) = variant(operator)?;
+        match name {
+            "Where" | "Filter" => {
+                if let Some(shaped_by) = select.shaped_by {
+                    return Err(format!("'where' after '{}' is not supported", shaped_by));
+                }
+                let condition = self.expr(body)?;
+                select.wheres.push(condition);
+            }
+            "Take" | "Limit" => {
+                let n = body.as_u64().ok_or("take/limit needs a row count")?;
+                select.limit = Some(select.limit.map_or(n, |limit| limit.min(n)));
+                select.shaped_by.get_or_insert("take");
+            }
+            "Project" => {
+                self.ensure_unshaped(select, "project")?;
+                select.columns = self.named_exprs(body)?;
+                select.shaped_by = Some("project");
+            }
+            "Extend" => {
+                self.ensure_unshaped(select, "extend")?;
+                if select.columns.is_empty() {
+                    select.columns.push("*".to_string());
+                }
+                let columns = self.named_exprs(body)?;
+                select.columns.extend(columns);
+            }
+            "Distinct" => {
+                self.ensure_unshaped(select, "distinct")?;
+                select.columns = items(body).iter().map(|column| self.expr(column)).collect::<Result<_, _>>()?;
+                select.distinct = true;
+                select.shaped_by = Some("distinct");
+            }
+            "Count" => {
+                self.ensure_unshaped(select, "count")?;
+                select.columns = vec![format!("COUNT(*) AS {}", quote_ident("Count"))];
+                select.shaped_by = Some("count");
+            }
+            "Summarize" => {
+                self.ensure_unshaped(select, "summarize")?;
+                let parts = body.as_array().ok_or("summarize needs aggregates and group-by columns")?;
+                let aggregates = parts.first().map(|a| self.named_exprs(a)).transpose()?.unwrap_or_default();
+                let mut group_by = Vec::new();
+                let mut columns = Vec::new();
+                if let Some(by) = parts.get(1) {
+                    for (alias, expr) in named_items(by)? {
+                        let lowered = self.expr(expr)?;
+                        columns.push(match &alias {
+                            Some(alias) => format!("{} AS {}", lowered, quote_ident(alias)),
+                            None => lowered.clone(),
+                        });
+                        group_by.push(lowered);
+                    }
+                }
+                columns.extend(aggregates);
+                select.columns = columns;
+                select.group_by = group_by;
+                select.shaped_by = Some("summarize");
+            }
+            "Sort" | "Order" => {
+                if select.limit.is_some() {
+                    return Err("'sort' after 'take' is not supported".to_string());
+                }
+                for item in items(body) {
+                    let key = self.sort_key(item)?;
+                    select.order_by.push(key);
+                }
+            }
+            "Top" => {
+                if select.limit.is_some() {
+                    return Err("'top' after 'take' is not supported".to_string());
+                }
+                let parts = body.as_array().ok_or("top needs a row count and a sort expression")?;
+                let n = parts.first().and_then(Value::as_u64).ok_or("top needs a row count")?;
+                for item in &parts[1..] {
+                    let key = self.sort_key(item)?;
+                    select.order_by.push(key);
+                }
+                select.limit = Some(n);
+                select.shaped_by = Some("top");
+            }
+            other => return Err(format!("Operator '{}' cannot be translated to SQL", other.to_ascii_lowercase())),
+        }
+        Ok(())
+    }
+
+    fn ensure_unshaped(&self, select: &SelectParts, operator: &str) -> Result<(), String> {
+        match select.shaped_by {
+            Some(previous) => Err(format!("'{}' after '{}' is not supported", operator, previous)),
+            None => Ok(()),
+        }
+    }
+
+    /// `(alias, expr)` pairs as SELECT items
+    fn named_exprs(&mut self, list: &Value) -> Result<Vec<String>, String> {
+        named_items(list)?
+            .into_iter()
+            .map(|(alias, expr)| {
+                let lowered = self.expr(expr)?;
+                Ok(match alias {
+                    Some(alias) => format!("{} AS {}", lowered, quote_ident(&alias)),
+                    None => lowered,
+                })
+            })
+            .collect()
+    }
+
+    /// Sort keys are a column name, an expression, or `[expr, "Asc"|"Desc", nulls?]`; KQL sorts descending by default
+    fn sort_key(&mut self, item: &Value) -> Result<String, String> {
+        let (expr, direction) = match item {
+            Value::String(column) => (quote_ident(column), "DESC"),
+            Value::Array(parts) if !parts.is_empty() => {
+                let direction = match parts.get(1).and_then(Value::as_str).map(str::to_ascii_lowercase).as_deref() {
+                    Some("asc" | "ascending") => "ASC",
+                    _ => "DESC",
+                };
+                (self.expr(&parts[0])?, direction)
+            }
+            other => (self.expr(other)?, "DESC"),
+        };
+        Ok(format!("{} {}", expr, direction))
+    }
+
+    fn expr(&mut self, expr: &Value) -> Result<String, String> {
+        if let Value::String(column) = expr {
+            return Ok(quote_ident(column));
+        }
+        let (name, body) = variant(expr)?;
+        if let Some(op) = sql_operator(name) {
+            let (left, right) = pair(body, name)?;
+            return Ok(format!("({} {} {})", self.expr(left)?, op, self.expr(right)?));
+        }
+        match name {
+            "Ident" | "Column" => body.as_str().map(quote_ident).ok_or_else(|| "Identifier must be a string".to_string()),
+            "Value" | "Literal" => self.value(body),
+            "Not" => Ok(format!("(NOT {})", self.expr(body)?)),
+            "Paren" | "Group" => self.expr(body),
+            "Contains" | "Has" => self.like(body, name, true, true, false),
+            "NotContains" | "NotHas" => self.like(body, name, true, true, true),
+            "StartsWith" => self.like(body, name, false, true, false),
+            "EndsWith" => self.like(body, name, true, false, false),
+            "EqualsCi" => {
+                let (left, right) = pair(body, name)?;
+                Ok(format!("(lower({}) = lower({}))", self.expr(left)?, self.expr(right)?))
+            }
+            "NotEqualsCi" => {
+                let (left, right) = pair(body, name)?;
+                Ok(format!("(lower({}) <> lower({}))", self.expr(left)?, self.expr(right)?))
+            }
+            "In" | "NotIn" => {
+                let (left, right) = pair(body, name)?;
+                let left = self.expr(left)?;
+                let values: Vec<String> = items(right).iter().map(|v| self.expr(v)).collect::<Result<_, _>>()?;
+                if values.is_empty() {
+                    return Err(format!("'{}' needs at least one value", name.to_ascii_lowercase()));
+                }
+                let negate = if name == "NotIn" { "NOT " } else { "" };
+                Ok(format!("({} {}IN ({}))", left, negate, values.join(", ")))
+            }
+            "Func" | "Call" => {
+                let parts = body.as_array().ok_or("Function call needs a name and arguments")?;
+                let function = parts.first().and_then(Value::as_str).ok_or("Function call needs a name")?;
+                let args = parts.get(1).map(items).unwrap_or_default();
+                self.function(function, &args)
+            }
+            other => Err(format!("Expression '{}' cannot be translated to SQL", other)),
+        }
+    }
+
+    fn value(&mut self, value: &Value) -> Result<String, String> {
+        let (kind, inner) = match value {
+            Value::Object(_) => variant(value)?,
+            // Untagged literals
+            other => ("", other),
+        };
+        if inner.is_null() {
+            return Ok("NULL".to_string());
+        }
+        match kind {
+            "Timespan" => Ok(format!("{}::interval", self.param(json!(interval_text(inner)?)))),
+            "DateTime" | "Datetime" => Ok(format!("{}::timestamptz", self.param(inner.clone()))),
+            "Dynamic" => Ok(format!("{}::jsonb", self.param(json!(inner.to_string())))),
+            _ => Ok(self.param(inner.clone())),
+        }
+    }
+
+    /// ILIKE with the literal escaped, so `%` and `_` in the search text match themselves
+    fn like(&mut self, body: &Value, name: &str, leading: bool, trailing: bool, negate: bool) -> Result<String, String> {
+        let (left, right) = pair(body, name)?;
+        let left = self.expr(left)?;
+        let pattern = match literal_string(right) {
+            Some(text) => {
+                let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+                let wrap = |on: bool| if on { "%" } else { "" };
+                self.param(json!(format!("{}{}{}", wrap(leading), escaped, wrap(trailing))))
+            }
+            None => {
+                let right = self.expr(right)?;
+                let wrap = |on: bool| if on { "'%' || " } else { "" };
+                format!("{}{}{}", wrap(leading), right, if trailing { " || '%'" } else { "" })
+            }
+        };
+        let not = if negate { "NOT " } else { "" };
+        Ok(format!("({} {}ILIKE {})", left, not, pattern))
+    }
+
+    fn function(&mut self, name: &str, args: &[&Value]) -> Result<String, String> {
+        let lowered: Vec<String> = args.iter().map(|arg| self.expr(arg)).collect::<Result<_, _>>()?;
+        let arity = |n: usize| -> Result<(), String> {
+            if lowered.len() == n {
+                Ok(())
+            } else {
+                Err(format!("{}() takes {} arguments, got {}", name, n, lowered.len()))
+            }
+        };
+        let sql = match name.to_ascii_lowercase().as_str() {
+            "now" => {
+                arity(0)?;
+                "now()".to_string()
+            }
+            "ago" => {
+                arity(1)?;
+                format!("(now() - {})", lowered[0])
+            }
+            "isnull" => {
+                arity(1)?;
+                format!("({} IS NULL)", lowered[0])
+            }
+            "isnotnull" => {
+                arity(1)?;
+                format!("({} IS NOT NULL)", lowered[0])
+            }
+            "isempty" => {
+                arity(1)?;
+                format!("({} IS NULL OR {} = '')", lowered[0], lowered[0])
+            }
+            "isnotempty" => {
+                arity(1)?;
+                format!("({} IS NOT NULL AND {} <> '')", lowered[0], lowered[0])
+            }
+            "tolower" => {
+                arity(1)?;
+                format!("lower({})", lowered[0])
+            }
+            "toupper" => {
+                arity(1)?;
+                format!("upper({})", lowered[0])
+            }
+            "strlen" => {
+                arity(1)?;
+                format!("length({})", lowered[0])
+            }
+            "bin" | "floor" => {
+                arity(2)?;
+                match self.dialect {
+                    SqlDialect::TimescaleDb => format!("time_bucket({}, {})", lowered[1], lowered[0]),
+                    SqlDialect::Postgres => format!("date_bin({}, {}, TIMESTAMPTZ 'epoch')", lowered[1], lowered[0]),
+                }
+            }
+            "count" if lowered.is_empty() => "COUNT(*)".to_string(),
+            "count" | "sum" | "avg" | "min" | "max" => {
+                arity(1)?;
+                format!("{}({})", name.to_ascii_uppercase(), lowered[0])
+            }
+            "dcount" => {
+                arity(1)?;
+                format!("COUNT(DISTINCT {})", lowered[0])
+            }
+            "countif" => {
+                arity(1)?;
+                format!("COUNT(*) FILTER (WHERE {})", lowered[0])
+            }
+            other => return Err(format!("Function '{}' cannot be translated to SQL", other)),
+        };
+        Ok(sql)
+    }
+}
+
+/// Locate the tabular expression (`source` + `operators`) in a serialized query
+fn tabular_expression(ast: &Value) -> Option<&Value> {
+    match ast {
+        Value::Object(map) if map.contains_key("source") => Some(ast),
+        Value::Object(map) => map.values().rev().find_map(tabular_expression),
+        Value::Array(items) => items.iter().rev().find_map(tabular_expression),
+        _ => None,
+    }
+}
+
+/// `[alias, expr]` pairs (alias may be null) or bare expressions
+fn named_items(list: &Value) -> Result<Vec<(Option<String>, &Value)>, String> {
+    items(list)
+        .into_iter()
+        .map(|item| match item.as_array().map(Vec::as_slice) {
+            Some([alias, expr]) if alias.is_null() || alias.is_string() => Ok((alias.as_str().map(str::to_string), expr)),
+            _ => Ok((None, item)),
+        })
+        .collect()
+}
+
+/// SQL spelling of a binary operator; string matching and case-insensitive comparisons are lowered separately
+fn sql_operator(name: &str) -> Option<&'static str> {
+    let (kql, _) = binary_operator(name)?;
+    Some(match kql {
+        "==" => "=",
+        "!=" => "<>",
+        "and" => "AND",
+        "or" => "OR",
+        "<" | ">" | "<=" | ">=" | "+" | "-" | "*" | "/" | "%" => kql,
+        _ => return None,
+    })
+}
+
+fn literal_string(expr: &Value) -> Option<&str> {
+    let (name, body) = variant(expr).ok()?;
+    if !matches!(name, "Value" | "Literal") {
+        return None;
+    }
+    match body {
+        Value::String(text) => Some(text),
+        Value::Object(_) => match variant(body).ok()? {
+            ("String", Value::String(text)) => Some(text),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Postgres interval text for a serialized timespan (`{"secs", "nanos"}`, seconds, or a KQL span like "1h")
+fn interval_text(value: &Value) -> Result<String, String> {
+    match value {
+        Value::Object(map) => {
+            let secs = map.get("secs").and_then(Value::as_i64).unwrap_or(0);
+            let nanos = map.get("nanos").and_then(Value::as_i64).unwrap_or(0);
+            Ok(format!("{} seconds", secs as f64 + nanos as f64 / 1e9))
+        }
+        Value::Number(seconds) => Ok(format!("{} seconds", seconds)),
+        Value::String(span) => {
+            let split = span.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(span.len());
+            let (amount, unit) = span.split_at(split);
+            let amount: f64 = amount.trim().parse().map_err(|_| format!("Invalid timespan '{}'", span))?;
+            let unit = match unit {
+                "d" => "days",
+                "h" => "hours",
+                "m" => "minutes",
+                "s" | "" => "seconds",
+                "ms" => "milliseconds",
+                "microsecond" | "us" => "microseconds",
+                other => return Err(format!("Unsupported timespan unit '{}'", other)),
+            };
+            Ok(format!("{} {}", amount, unit))
+        }
+        other => Err(format!("Invalid timespan {}", other)),
+    }
+}
+
+/// Double-quoted SQL identifier; dotted KQL names stay a single column name
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ident(name: &str) -> Value {
+        json!({ "Ident": name })
+    }
+
+    fn string(text: &str) -> Value {
+        json!({ "Value": { "String": text } })
+    }
+
+    #[test]
+    fn test_where_project_take_is_parameterized() {
+        let ast = json!({
+            "source": { "Reference": "events" },
+            "operators": [
+                { "Where": { "And": [
+                    { "Equals": [ident("event_id"), { "Value": { "Long": 4625 } }] },
+                    { "Greater": [ident("timestamp"), { "Func": ["ago", [{ "Value": { "Timespan": { "secs": 3600, "nanos": 0 } } }]] }] }
+                ] } },
+                { "Where": { "Contains": [ident("message"), string("50%_off")] } },
+                { "Project": [[null, ident("timestamp")], ["user", ident("user.name")]] },
+                { "Take": 10 }
+            ]
+        });
+
+        let query = lower_query(&ast, SqlDialect::Postgres).unwrap();
+        assert_eq!(
+            query.sql,
+            "SELECT \"timestamp\", \"user.name\" AS \"user\" FROM \"events\" \
+             WHERE ((\"event_id\" = $1) AND (\"timestamp\" > (now() - $2::interval))) AND (\"message\" ILIKE $3) LIMIT $4"
+        );
+        assert_eq!(query.params, vec![json!(4625), json!("3600 seconds"), json!("%50\\%\\_off%"), json!(10)]);
+        assert!(query.where_clause.unwrap().starts_with("((\"event_id\" = $1)"));
+    }
+
+    #[test]
+    fn test_summarize_uses_dialect_buckets_and_rejects_unsupported_shapes() {
+        let ast = json!({ "statements": [{ "TabularExpression": {
+            "source": { "Reference": "events" },
+            "operators": [
+                { "Summarize": [
+                    [["failures", { "Func": ["count", []] }]],
+                    [["hour", { "Func": ["bin", [ident("timestamp"), { "Value": { "Timespan": "1h" } }]] }]]
+                ] }
+            ]
+        } }] });
+        let query = lower_query(&ast, "timescaledb".parse().unwrap()).unwrap();
+        assert_eq!(
+            query.sql,
+            "SELECT time_bucket($1::interval, \"timestamp\") AS \"hour\", COUNT(*) AS \"failures\" FROM \"events\" \
+             GROUP BY time_bucket($1::interval, \"timestamp\")"
+        );
+
+        let ast = json!({
+            "source": { "Reference": "events" },
+            "operators": [{ "Take": 5 }, { "Where": { "Equals": [ident("a"), string("b")] } }]
+        });
+        assert!(lower_query(&ast, SqlDialect::Postgres).unwrap_err().contains("'where' after 'take'"));
+        assert!("mysql".parse::<SqlDialect>().is_err());
+    }
+}